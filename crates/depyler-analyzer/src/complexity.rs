@@ -1,54 +1,9 @@
 use depyler_core::hir::{BinOp, HirExpr, HirStmt};
 
+/// Cyclomatic complexity, counted as attribute synthesis counts it for the
+/// complexity notes it adds to generated functions
 pub fn calculate_cyclomatic(body: &[HirStmt]) -> u32 {
-    // Start with 1 for the function entry point
-    let mut complexity = 1;
-
-    for stmt in body {
-        complexity += cyclomatic_stmt(stmt);
-    }
-
-    complexity
-}
-
-fn cyclomatic_stmt(stmt: &HirStmt) -> u32 {
-    match stmt {
-        HirStmt::If {
-            then_body,
-            else_body,
-            ..
-        } => {
-            let mut complexity = 1; // +1 for the if condition
-            complexity += cyclomatic_body(then_body);
-            if let Some(else_stmts) = else_body {
-                complexity += cyclomatic_body(else_stmts);
-            }
-            complexity
-        }
-        HirStmt::While { body, .. } => {
-            1 + cyclomatic_body(body) // +1 for the loop condition
-        }
-        HirStmt::For { body, .. } => {
-            1 + cyclomatic_body(body) // +1 for the loop
-        }
-        HirStmt::Expr(expr) => cyclomatic_expr(expr),
-        _ => 0,
-    }
-}
-
-fn cyclomatic_body(body: &[HirStmt]) -> u32 {
-    body.iter().map(cyclomatic_stmt).sum()
-}
-
-fn cyclomatic_expr(expr: &HirExpr) -> u32 {
-    match expr {
-        HirExpr::Binary {
-            op: BinOp::And | BinOp::Or,
-            left,
-            right,
-        } => 1 + cyclomatic_expr(left) + cyclomatic_expr(right),
-        _ => 0,
-    }
+    depyler_core::attribute_synthesis::cyclomatic_complexity(body)
 }
 
 pub fn calculate_cognitive(body: &[HirStmt]) -> u32 {
@@ -0,0 +1,630 @@
+//! Coverage-guided migration ordering
+//!
+//! Reads a coverage.py report (`coverage json` or `coverage xml`), maps the
+//! measured lines onto the functions of a Python module and ranks those
+//! functions for transpilation. Hot, well-tested functions come first: their
+//! Python tests exercise the code that matters and can verify the Rust port.
+//!
+//! Hit counts come from the report. Cobertura XML carries them per line;
+//! coverage.py JSON only records executed lines, unless it was written with
+//! `--show-contexts`, in which case the number of contexts (typically tests)
+//! that ran a line is used.
+//!
+//! LCOV tracefiles, such as `cargo llvm-cov --lcov` output for a transpiled
+//! crate, are read into the same per-line model.
+
+use crate::FunctionMetrics;
+use anyhow::{anyhow, bail, Context, Result};
+use depyler_core::hir_spans::HirSpans;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Line coverage at or above which a function counts as well tested
+pub const READY_LINE_COVERAGE: f64 = 0.8;
+
+/// Measured coverage of every file in a report, keyed by the report's path
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FileCoverage {
+    /// Hit count of each executable line; missed lines are 0
+    pub lines: BTreeMap<u32, u64>,
+    /// `(covered, total)` branch destinations of each branching line
+    pub branches: BTreeMap<u32, (usize, usize)>,
+}
+
+/// Line span of a function or method in the source
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSpan {
+    /// `name` for module functions, `Class.name` for methods
+    pub name: String,
+    pub def_line: u32,
+    pub body_start: u32,
+    pub end_line: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionCoverage {
+    pub name: String,
+    pub def_line: u32,
+    /// Executable lines in the body
+    pub lines: usize,
+    pub covered_lines: usize,
+    pub covered_branches: usize,
+    pub branches: usize,
+    /// Highest hit count of any body line
+    pub hits: u64,
+}
+
+/// Module-wide ratios in `0.0..=1.0`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageSummary {
+    pub line_coverage: f64,
+    /// Falls back to line coverage when the report has no branch data
+    pub branch_coverage: f64,
+    /// Share of functions with at least one covered line
+    pub function_coverage: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleCoverage {
+    pub functions: Vec<FunctionCoverage>,
+    pub summary: CoverageSummary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MigrationReadiness {
+    /// Covered well enough for the Python tests to verify the port
+    Ready,
+    /// Partly covered; add tests for the missed lines first
+    PartiallyTested,
+    /// Never executed by the tests
+    Untested,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MigrationPriority {
+    /// 1-based position in the suggested migration order
+    pub rank: usize,
+    pub name: String,
+    pub line_coverage: f64,
+    pub hits: u64,
+    /// Known for module functions only
+    pub cyclomatic_complexity: Option<u32>,
+    pub score: f64,
+    pub readiness: MigrationReadiness,
+}
+
+impl CoverageReport {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read coverage report {}", path.display()))?;
+        Self::parse(&content)
+            .with_context(|| format!("Failed to parse coverage report {}", path.display()))
+    }
+
+    /// Parses any supported report format, detected from its start
+    pub fn parse(content: &str) -> Result<Self> {
+        let content = content.trim_start();
+        if content.starts_with("TN:") || content.starts_with("SF:") {
+            return Self::from_lcov(content);
+        }
+        match content.chars().next() {
+            Some('<') => Self::from_xml(content),
+            Some('{') => Self::from_json(content),
+            _ => bail!("expected a coverage.py JSON or XML report, or an LCOV tracefile"),
+        }
+    }
+
+    /// Parses an LCOV tracefile; only `SF`, `DA` and `BRDA` records are used
+    pub fn from_lcov(content: &str) -> Result<Self> {
+        let mut report = Self::default();
+        let mut current: Option<String> = None;
+        for line in content.lines().map(str::trim) {
+            let (record, value) = match line.split_once(':') {
+                Some(parts) => parts,
+                None => {
+                    if line == "end_of_record" {
+                        current = None;
+                    }
+                    continue;
+                }
+            };
+            if record == "SF" {
+                let path = normalize_path(value);
+                report.files.entry(path.clone()).or_default();
+                current = Some(path);
+                continue;
+            }
+            let file = match (record, &current) {
+                ("DA" | "BRDA", Some(path)) => report.files.entry(path.clone()).or_default(),
+                _ => continue,
+            };
+            let fields: Vec<&str> = value.split(',').collect();
+            let number: u32 = fields[0]
+                .parse()
+                .with_context(|| format!("invalid LCOV line number in `{}`", line))?;
+            if record == "DA" {
+                let hits: u64 = fields.get(1).and_then(|h| h.parse().ok()).unwrap_or(0);
+                *file.lines.entry(number).or_insert(0) += hits;
+            } else {
+                let taken = fields
+                    .get(3)
+                    .and_then(|t| t.parse::<u64>().ok())
+                    .unwrap_or(0);
+                let entry = file.branches.entry(number).or_insert((0, 0));
+                entry.1 += 1;
+                if taken > 0 {
+                    entry.0 += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Parses `coverage json` output
+    ///
+    /// # Complexity
+    /// 8 (per-file line, context and branch passes)
+    pub fn from_json(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+        let files = value
+            .get("files")
+            .and_then(Value::as_object)
+            .ok_or_else(|| anyhow!("coverage JSON has no `files` object"))?;
+
+        let mut report = Self::default();
+        for (name, data) in files {
+            let mut file = FileCoverage::default();
+            for line in line_numbers(data, "missing_lines") {
+                file.lines.insert(line, 0);
+            }
+            for line in line_numbers(data, "executed_lines") {
+                file.lines.insert(line, 1);
+            }
+            if let Some(contexts) = data.get("contexts").and_then(Value::as_object) {
+                for (line, names) in contexts {
+                    let (line, count) = match (line.parse::<u32>(), names.as_array()) {
+                        (Ok(line), Some(names)) => (line, names.len() as u64),
+                        _ => continue,
+                    };
+                    if let Some(hits) = file.lines.get_mut(&line) {
+                        if *hits > 0 {
+                            *hits = count.max(1);
+                        }
+                    }
+                }
+            }
+            for (key, covered) in [("executed_branches", true), ("missing_branches", false)] {
+                for from in branch_sources(data, key) {
+                    let entry = file.branches.entry(from).or_insert((0, 0));
+                    entry.1 += 1;
+                    if covered {
+                        entry.0 += 1;
+                    }
+                }
+            }
+            report.files.insert(normalize_path(name), file);
+        }
+        Ok(report)
+    }
+
+    /// Parses Cobertura XML, as written by `coverage xml`
+    ///
+    /// # Complexity
+    /// 7 (element dispatch + attribute parsing)
+    pub fn from_xml(content: &str) -> Result<Self> {
+        let mut report = Self::default();
+        let mut current: Option<String> = None;
+        for (name, attrs) in xml_elements(content) {
+            match name {
+                "class" => current = attribute(&attrs, "filename").map(normalize_path),
+                "line" => {
+                    let file = match &current {
+                        Some(filename) => report.files.entry(filename.clone()).or_default(),
+                        None => continue,
+                    };
+                    let number: u32 = attribute(&attrs, "number")
+                        .ok_or_else(|| anyhow!("<line> without a `number` attribute"))?
+                        .parse()
+                        .context("invalid <line> number")?;
+                    let hits: u64 = attribute(&attrs, "hits")
+                        .and_then(|hits| hits.parse().ok())
+                        .unwrap_or(0);
+                    // Cobertura repeats method lines under <methods>; keep one count
+                    let entry = file.lines.entry(number).or_insert(0);
+                    *entry = (*entry).max(hits);
+                    if let Some(branches) =
+                        attribute(&attrs, "condition-coverage").and_then(parse_condition_coverage)
+                    {
+                        file.branches.insert(number, branches);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if report.files.is_empty() {
+            bail!("coverage XML has no <class> entries");
+        }
+        Ok(report)
+    }
+
+    /// Coverage of `path`, matched exactly or by trailing path components
+    /// since reports store paths relative to where the tests ran
+    pub fn file(&self, path: &Path) -> Option<&FileCoverage> {
+        let path = path.strip_prefix(".").unwrap_or(path);
+        if let Some(file) = self.files.get(&normalize_path(&path.to_string_lossy())) {
+            return Some(file);
+        }
+        let mut matches = self.files.iter().filter(|(name, _)| {
+            let name = Path::new(name.as_str());
+            path.ends_with(name) || name.ends_with(path)
+        });
+        match (matches.next(), matches.next()) {
+            (Some((_, file)), None) => Some(file),
+            _ => None,
+        }
+    }
+
+    /// Per-function coverage of the module at `path` with the given source
+    pub fn module_coverage(&self, path: &Path, source: &str) -> Result<ModuleCoverage> {
+        let file = self
+            .file(path)
+            .ok_or_else(|| anyhow!("coverage report has no data for {}", path.display()))?;
+        let functions = function_coverage(&function_spans(source)?, file);
+        let summary = file.summary(&functions);
+        Ok(ModuleCoverage { functions, summary })
+    }
+}
+
+impl FileCoverage {
+    pub fn summary(&self, functions: &[FunctionCoverage]) -> CoverageSummary {
+        let covered = self.lines.values().filter(|hits| **hits > 0).count();
+        let line_coverage = ratio(covered, self.lines.len());
+        let (covered_branches, branches) = self
+            .branches
+            .values()
+            .fold((0, 0), |(c, t), (covered, total)| (c + covered, t + total));
+        let branch_coverage = if branches == 0 {
+            line_coverage
+        } else {
+            ratio(covered_branches, branches)
+        };
+        let function_coverage = ratio(
+            functions.iter().filter(|f| f.covered_lines > 0).count(),
+            functions.len(),
+        );
+        CoverageSummary {
+            line_coverage,
+            branch_coverage,
+            function_coverage,
+        }
+    }
+}
+
+impl FunctionCoverage {
+    pub fn line_coverage(&self) -> f64 {
+        ratio(self.covered_lines, self.lines)
+    }
+}
+
+/// Spans of the module's functions and (nested) class methods
+pub fn function_spans(source: &str) -> Result<Vec<FunctionSpan>> {
+    let spans = HirSpans::parse(source)?;
+    Ok(spans
+        .functions()
+        .filter_map(|(name, function)| {
+            // The docstring is part of the measured body
+            let first = function
+                .docstring
+                .or_else(|| function.body.first().map(|s| s.span))?;
+            let lines = spans.lines(function.def);
+            Some(FunctionSpan {
+                name: name.to_string(),
+                def_line: *lines.start() as u32,
+                body_start: spans.position(first.start).line as u32,
+                end_line: *lines.end() as u32,
+            })
+        })
+        .collect())
+}
+
+/// Lines and branches of each span's body measured in `file`
+pub fn function_coverage(spans: &[FunctionSpan], file: &FileCoverage) -> Vec<FunctionCoverage> {
+    spans
+        .iter()
+        .map(|span| {
+            let body = span.body_start..=span.end_line;
+            let lines: Vec<u64> = file
+                .lines
+                .range(body.clone())
+                .map(|(_, hits)| *hits)
+                .collect();
+            let (covered_branches, branches) = file
+                .branches
+                .range(body)
+                .fold((0, 0), |(c, t), (_, (covered, total))| {
+                    (c + covered, t + total)
+                });
+            FunctionCoverage {
+                name: span.name.clone(),
+                def_line: span.def_line,
+                lines: lines.len(),
+                covered_lines: lines.iter().filter(|hits| **hits > 0).count(),
+                covered_branches,
+                branches,
+                hits: lines.iter().copied().max().unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// Orders functions for migration: coverage weighted by how often they run,
+/// simpler functions first on ties
+pub fn rank_for_migration(
+    functions: &[FunctionCoverage],
+    metrics: &[FunctionMetrics],
+) -> Vec<MigrationPriority> {
+    let mut priorities: Vec<MigrationPriority> = functions
+        .iter()
+        .map(|function| {
+            let line_coverage = function.line_coverage();
+            let readiness = if function.covered_lines == 0 {
+                MigrationReadiness::Untested
+            } else if line_coverage >= READY_LINE_COVERAGE {
+                MigrationReadiness::Ready
+            } else {
+                MigrationReadiness::PartiallyTested
+            };
+            MigrationPriority {
+                rank: 0,
+                name: function.name.clone(),
+                line_coverage,
+                hits: function.hits,
+                cyclomatic_complexity: metrics
+                    .iter()
+                    .find(|m| m.name == function.name)
+                    .map(|m| m.cyclomatic_complexity),
+                score: line_coverage * (1.0 + (1.0 + function.hits as f64).log2()),
+                readiness,
+            }
+        })
+        .collect();
+
+    priorities.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| {
+                a.cyclomatic_complexity
+                    .unwrap_or(u32::MAX)
+                    .cmp(&b.cyclomatic_complexity.unwrap_or(u32::MAX))
+            })
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    for (i, priority) in priorities.iter_mut().enumerate() {
+        priority.rank = i + 1;
+    }
+    priorities
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.replace('\\', "/")
+}
+
+fn line_numbers<'a>(data: &'a Value, key: &str) -> impl Iterator<Item = u32> + 'a {
+    data.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_u64)
+        .map(|line| line as u32)
+}
+
+/// Source lines of `[from, to]` branch arcs
+fn branch_sources<'a>(data: &'a Value, key: &str) -> impl Iterator<Item = u32> + 'a {
+    data.get(key)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|arc| arc.get(0)?.as_u64())
+        .map(|line| line as u32)
+}
+
+/// `50% (1/2)` -> `(1, 2)`
+fn parse_condition_coverage(text: &str) -> Option<(usize, usize)> {
+    let counts = text.split('(').nth(1)?.trim_end_matches(')');
+    let (covered, total) = counts.split_once('/')?;
+    Some((covered.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+/// Start tags with their attributes; closing tags, comments and the
+/// declaration are skipped
+fn xml_elements(content: &str) -> Vec<(&str, Vec<(&str, String)>)> {
+    let mut elements = Vec::new();
+    let mut rest = content;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = rest[..end].trim_end_matches('/');
+        rest = &rest[end + 1..];
+        if tag.starts_with(['?', '!', '/']) {
+            continue;
+        }
+        let (name, attrs) = match tag.find(char::is_whitespace) {
+            Some(split) => (&tag[..split], parse_attributes(&tag[split..])),
+            None => (tag, Vec::new()),
+        };
+        elements.push((name, attrs));
+    }
+    elements
+}
+
+fn parse_attributes(text: &str) -> Vec<(&str, String)> {
+    let mut attrs = Vec::new();
+    let mut rest = text;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let quote = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => break,
+        };
+        let close = match value[1..].find(quote) {
+            Some(close) => close + 1,
+            None => break,
+        };
+        attrs.push((key, unescape_xml(&value[1..close])));
+        rest = &value[close + 1..];
+    }
+    attrs
+}
+
+fn attribute<'a>(attrs: &'a [(&str, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+def hot(x):
+    if x > 0:
+        return x
+    return -x
+
+def cold(x):
+    return x * 2
+
+class Shape:
+    def area(self):
+        return 1
+";
+
+    const JSON: &str = r#"{
+        "files": {
+            "pkg/shapes.py": {
+                "executed_lines": [1, 2, 3, 4, 6, 9, 10, 11],
+                "missing_lines": [7],
+                "contexts": {"2": ["test_a", "test_b", "test_c"], "11": ["test_a"]},
+                "executed_branches": [[2, 3], [2, 4]],
+                "missing_branches": []
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_function_spans() {
+        let spans = function_spans(SOURCE).unwrap();
+        let names: Vec<_> = spans
+            .iter()
+            .map(|s| (s.name.as_str(), s.body_start, s.end_line))
+            .collect();
+        assert_eq!(
+            names,
+            [("hot", 2, 4), ("cold", 7, 7), ("Shape.area", 11, 11)]
+        );
+    }
+
+    #[test]
+    fn test_json_report_with_contexts() {
+        let report = CoverageReport::parse(JSON).unwrap();
+        let coverage = report
+            .module_coverage(Path::new("/src/pkg/shapes.py"), SOURCE)
+            .unwrap();
+
+        let hot = &coverage.functions[0];
+        assert_eq!((hot.lines, hot.covered_lines, hot.hits), (3, 3, 3));
+        assert_eq!((hot.covered_branches, hot.branches), (2, 2));
+        assert_eq!(coverage.functions[1].covered_lines, 0);
+        assert_eq!(coverage.summary.function_coverage, 2.0 / 3.0);
+        assert_eq!(coverage.summary.branch_coverage, 1.0);
+    }
+
+    #[test]
+    fn test_cobertura_xml_report() {
+        let xml = r#"<?xml version="1.0" ?>
+<coverage line-rate="0.8">
+  <packages><package name="pkg"><classes>
+    <class name="shapes.py" filename="pkg/shapes.py" line-rate="0.8">
+      <methods/>
+      <lines>
+        <line number="2" hits="5" branch="true" condition-coverage="50% (1/2)" missing-branches="4"/>
+        <line number="3" hits="5"/>
+        <line number="4" hits="0"/>
+      </lines>
+    </class>
+  </classes></package></packages>
+</coverage>"#;
+        let report = CoverageReport::parse(xml).unwrap();
+        let file = report.file(Path::new("shapes.py")).unwrap();
+        assert_eq!(file.lines.get(&2), Some(&5));
+        assert_eq!(file.lines.get(&4), Some(&0));
+        assert_eq!(file.branches.get(&2), Some(&(1, 2)));
+    }
+
+    #[test]
+    fn test_rank_prefers_hot_tested_functions() {
+        let report = CoverageReport::parse(JSON).unwrap();
+        let coverage = report
+            .module_coverage(Path::new("pkg/shapes.py"), SOURCE)
+            .unwrap();
+        let ranked = rank_for_migration(&coverage.functions, &[]);
+
+        let order: Vec<_> = ranked
+            .iter()
+            .map(|p| (p.rank, p.name.as_str(), p.readiness))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                (1, "hot", MigrationReadiness::Ready),
+                (2, "Shape.area", MigrationReadiness::Ready),
+                (3, "cold", MigrationReadiness::Untested),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lcov_tracefile() {
+        let lcov = "TN:\nSF:/tmp/calc/src/lib.rs\nFN:1,_RNvCs1_4calc3add\nDA:1,2\nDA:2,2\nDA:5,0\nBRDA:2,0,0,2\nBRDA:2,0,1,-\nend_of_record\n";
+        let report = CoverageReport::parse(lcov).unwrap();
+        let file = report.file(Path::new("calc/src/lib.rs")).unwrap();
+        assert_eq!(file.lines.get(&2), Some(&2));
+        assert_eq!(file.lines.get(&5), Some(&0));
+        assert_eq!(file.branches.get(&2), Some(&(1, 2)));
+    }
+
+    #[test]
+    fn test_unknown_report_format() {
+        assert!(CoverageReport::parse("line,hits").is_err());
+    }
+}
@@ -1,4 +1,6 @@
 pub mod complexity;
+pub mod coverage;
+pub mod lints;
 pub mod metrics;
 pub mod type_flow;
 
@@ -19,6 +21,9 @@ pub struct AnalysisResult {
     pub module_metrics: ModuleMetrics,
     pub function_metrics: Vec<FunctionMetrics>,
     pub type_coverage: TypeCoverage,
+    /// Functions with dead stores or unreachable statements
+    #[serde(default)]
+    pub dead_code: Vec<metrics::DeadCodeMetrics>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +83,7 @@ impl Analyzer {
             module_metrics,
             function_metrics,
             type_coverage,
+            dead_code: metrics::DeadCodeMetrics::for_module(module),
         })
     }
 
@@ -206,12 +212,14 @@ mod tests {
                 HirParam {
                     name: Symbol::from("x"),
                     ty: Type::Int,
-                    default: None
+                    default: None,
+                    kind: ParamKind::PositionalOrKeyword,
                 },
                 HirParam {
                     name: Symbol::from("y"),
                     ty: Type::String,
-                    default: None
+                    default: None,
+                    kind: ParamKind::PositionalOrKeyword,
                 }
             ],
             ret_type: Type::Int,
@@ -281,7 +289,8 @@ mod tests {
             params: smallvec![HirParam {
                 name: Symbol::from("x"),
                 ty: Type::Int,
-                default: None
+                default: None,
+                kind: ParamKind::PositionalOrKeyword,
             }],
             ret_type: Type::String,
             body: vec![],
@@ -295,7 +304,8 @@ mod tests {
             params: smallvec![HirParam {
                 name: Symbol::from("y"),
                 ty: Type::Unknown,
-                default: None
+                default: None,
+                kind: ParamKind::PositionalOrKeyword,
             }],
             ret_type: Type::Unknown,
             body: vec![],
@@ -0,0 +1,676 @@
+//! Lints over generated Rust
+//!
+//! Complexity metrics say nothing about idiom: a transpiled function can be
+//! simple and still clone values it only reads or convert a `String` to a
+//! `String`. Each [`LintRule`] inspects the functions of the generated code
+//! and reports [`LintDiagnostic`]s carrying the offending snippet and its
+//! suggested replacement, so tooling can gate on them or apply the fixes.
+
+use anyhow::Result;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use syn::visit::{self, Visit};
+use syn::{
+    BinOp, Block, Expr, ExprMethodCall, FnArg, ImplItem, Item, Local, Macro, Pat, Signature, Type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Applicability {
+    /// The replacement preserves behaviour and can be applied as is
+    MachineApplicable,
+    /// The replacement is probably right but may not compile, e.g. when
+    /// it extends a borrow
+    MaybeIncorrect,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub original: String,
+    pub replacement: String,
+    pub applicability: Applicability,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintDiagnostic {
+    pub lint: String,
+    /// `name` for free functions, `Type::name` for methods
+    pub function: String,
+    pub message: String,
+    pub suggestion: Suggestion,
+}
+
+/// A check run over the body of every generated function
+pub trait LintRule {
+    fn name(&self) -> &'static str;
+
+    fn check(&self, function: &FunctionContext<'_>, diagnostics: &mut Vec<LintDiagnostic>);
+}
+
+/// The function a rule is checking
+pub struct FunctionContext<'a> {
+    pub name: String,
+    pub sig: &'a Signature,
+    pub block: &'a Block,
+}
+
+impl FunctionContext<'_> {
+    fn diagnostic(&self, lint: &str, message: String, suggestion: Suggestion) -> LintDiagnostic {
+        LintDiagnostic {
+            lint: lint.to_string(),
+            function: self.name.clone(),
+            message,
+            suggestion,
+        }
+    }
+}
+
+pub struct Linter {
+    rules: Vec<Box<dyn LintRule>>,
+}
+
+impl Default for Linter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Linter {
+    /// A linter with every built-in rule
+    pub fn new() -> Self {
+        Self {
+            rules: vec![
+                Box::new(UnnecessaryClone),
+                Box::new(RedundantToString),
+                Box::new(NeedlessMut),
+            ],
+        }
+    }
+
+    pub fn with_rules(rules: Vec<Box<dyn LintRule>>) -> Self {
+        Self { rules }
+    }
+
+    pub fn rule_names(&self) -> Vec<&'static str> {
+        self.rules.iter().map(|rule| rule.name()).collect()
+    }
+
+    /// Lints every function and method in `code`
+    pub fn lint(&self, code: &str) -> Result<Vec<LintDiagnostic>> {
+        let file = syn::parse_file(code)?;
+        let mut functions = Vec::new();
+        collect_functions(&file.items, &mut functions);
+
+        let mut diagnostics = Vec::new();
+        for function in &functions {
+            for rule in &self.rules {
+                rule.check(function, &mut diagnostics);
+            }
+        }
+        Ok(diagnostics)
+    }
+}
+
+fn collect_functions<'a>(items: &'a [Item], functions: &mut Vec<FunctionContext<'a>>) {
+    for item in items {
+        match item {
+            Item::Fn(f) => functions.push(FunctionContext {
+                name: f.sig.ident.to_string(),
+                sig: &f.sig,
+                block: &f.block,
+            }),
+            Item::Impl(imp) => {
+                let ty = match &*imp.self_ty {
+                    Type::Path(path) => match path.path.segments.last() {
+                        Some(segment) => segment.ident.to_string(),
+                        None => continue,
+                    },
+                    other => render(other.to_token_stream()),
+                };
+                for impl_item in &imp.items {
+                    if let ImplItem::Fn(method) = impl_item {
+                        functions.push(FunctionContext {
+                            name: format!("{}::{}", ty, method.sig.ident),
+                            sig: &method.sig,
+                            block: &method.block,
+                        });
+                    }
+                }
+            }
+            Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    collect_functions(items, functions);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Methods that only read their receiver; the flag says whether the result
+/// borrows from it, in which case dropping the clone may extend a borrow
+const READ_ONLY_METHODS: &[(&str, bool)] = &[
+    ("len", false),
+    ("is_empty", false),
+    ("contains", false),
+    ("contains_key", false),
+    ("starts_with", false),
+    ("ends_with", false),
+    ("to_string", false),
+    ("to_vec", false),
+    ("iter", true),
+    ("get", true),
+    ("first", true),
+    ("last", true),
+    ("keys", true),
+    ("values", true),
+    ("as_str", true),
+];
+
+/// `.clone()` whose result is only read, cloned again, or taken of a literal
+pub struct UnnecessaryClone;
+
+impl LintRule for UnnecessaryClone {
+    fn name(&self) -> &'static str {
+        "unnecessary_clone"
+    }
+
+    fn check(&self, function: &FunctionContext<'_>, diagnostics: &mut Vec<LintDiagnostic>) {
+        let mut visitor = UnnecessaryCloneVisitor {
+            function,
+            diagnostics,
+        };
+        visitor.visit_block(function.block);
+    }
+}
+
+struct UnnecessaryCloneVisitor<'a, 'f> {
+    function: &'a FunctionContext<'f>,
+    diagnostics: &'a mut Vec<LintDiagnostic>,
+}
+
+impl UnnecessaryCloneVisitor<'_, '_> {
+    fn report(
+        &mut self,
+        message: String,
+        original: &ExprMethodCall,
+        replacement: TokenStream,
+        applicability: Applicability,
+    ) {
+        let suggestion = Suggestion {
+            original: render(original.to_token_stream()),
+            replacement: render(replacement),
+            applicability,
+        };
+        self.diagnostics.push(
+            self.function
+                .diagnostic("unnecessary_clone", message, suggestion),
+        );
+    }
+}
+
+impl<'ast> Visit<'ast> for UnnecessaryCloneVisitor<'_, '_> {
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        if let Some(inner) = clone_call(&call.receiver) {
+            let method = call.method.to_string();
+            if method == "clone" && call.args.is_empty() {
+                self.report(
+                    "the value is cloned twice".to_string(),
+                    call,
+                    inner.to_token_stream(),
+                    Applicability::MachineApplicable,
+                );
+            } else if let Some((_, borrows)) =
+                READ_ONLY_METHODS.iter().find(|(name, _)| *name == method)
+            {
+                let mut fixed = call.clone();
+                fixed.receiver = inner.receiver.clone();
+                let applicability = if *borrows {
+                    Applicability::MaybeIncorrect
+                } else {
+                    Applicability::MachineApplicable
+                };
+                self.report(
+                    format!("`{}` only reads the value; call it without cloning", method),
+                    call,
+                    fixed.to_token_stream(),
+                    applicability,
+                );
+            }
+        } else if call.method == "clone" && call.args.is_empty() {
+            if let Expr::Lit(lit) = &*call.receiver {
+                self.report(
+                    "cloning a literal".to_string(),
+                    call,
+                    lit.to_token_stream(),
+                    Applicability::MachineApplicable,
+                );
+            }
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+/// `receiver.clone()`
+fn clone_call(expr: &Expr) -> Option<&ExprMethodCall> {
+    match expr {
+        Expr::MethodCall(call) if call.method == "clone" && call.args.is_empty() => Some(call),
+        _ => None,
+    }
+}
+
+/// `.to_string()` on an expression that already is a `String`
+pub struct RedundantToString;
+
+impl LintRule for RedundantToString {
+    fn name(&self) -> &'static str {
+        "redundant_to_string"
+    }
+
+    fn check(&self, function: &FunctionContext<'_>, diagnostics: &mut Vec<LintDiagnostic>) {
+        let mut visitor = RedundantToStringVisitor {
+            function,
+            diagnostics,
+        };
+        visitor.visit_block(function.block);
+    }
+}
+
+struct RedundantToStringVisitor<'a, 'f> {
+    function: &'a FunctionContext<'f>,
+    diagnostics: &'a mut Vec<LintDiagnostic>,
+}
+
+impl<'ast> Visit<'ast> for RedundantToStringVisitor<'_, '_> {
+    fn visit_expr_method_call(&mut self, call: &'ast ExprMethodCall) {
+        if call.method == "to_string" && call.args.is_empty() && is_string(&call.receiver) {
+            let suggestion = Suggestion {
+                original: render(call.to_token_stream()),
+                replacement: render(call.receiver.to_token_stream()),
+                applicability: Applicability::MachineApplicable,
+            };
+            self.diagnostics.push(self.function.diagnostic(
+                "redundant_to_string",
+                "the value is already a `String`".to_string(),
+                suggestion,
+            ));
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+}
+
+/// Expressions whose type is `String` whatever their operands
+fn is_string(expr: &Expr) -> bool {
+    match expr {
+        Expr::MethodCall(call) => call.method == "to_string" && call.args.is_empty(),
+        Expr::Macro(mac) => mac.mac.path.is_ident("format"),
+        Expr::Call(call) => match &*call.func {
+            Expr::Path(path) => {
+                let segments: Vec<String> = path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|s| s.ident.to_string())
+                    .collect();
+                segments == ["String", "from"] || segments == ["String", "new"]
+            }
+            _ => false,
+        },
+        Expr::Paren(paren) => is_string(&paren.expr),
+        _ => false,
+    }
+}
+
+/// `let mut` bindings and `mut` parameters that are never mutated
+///
+/// A binding counts as mutated when it is assigned to (directly or through
+/// a field or index), borrowed `&mut`, used as a method receiver, or named
+/// inside a macro, so the rule misses some cases rather than suggest a
+/// change that does not compile.
+pub struct NeedlessMut;
+
+impl LintRule for NeedlessMut {
+    fn name(&self) -> &'static str {
+        "needless_mut"
+    }
+
+    fn check(&self, function: &FunctionContext<'_>, diagnostics: &mut Vec<LintDiagnostic>) {
+        let mut bindings = MutBindings::default();
+        for input in &function.sig.inputs {
+            if let FnArg::Typed(param) = input {
+                bindings.add(&param.pat, false);
+            }
+        }
+        bindings.visit_block(function.block);
+
+        let mut mutated = MutatedNames::default();
+        mutated.visit_block(function.block);
+
+        for (name, is_let) in bindings.names {
+            if mutated.names.contains(&name) {
+                continue;
+            }
+            let (original, replacement) = if is_let {
+                (format!("let mut {}", name), format!("let {}", name))
+            } else {
+                (format!("mut {}", name), name.clone())
+            };
+            diagnostics.push(function.diagnostic(
+                "needless_mut",
+                format!("`{}` is never mutated", name),
+                Suggestion {
+                    original,
+                    replacement,
+                    applicability: Applicability::MachineApplicable,
+                },
+            ));
+        }
+    }
+}
+
+/// Names bound `mut`, in order, with whether they came from a `let`
+#[derive(Default)]
+struct MutBindings {
+    names: Vec<(String, bool)>,
+}
+
+impl MutBindings {
+    fn add(&mut self, pat: &Pat, is_let: bool) {
+        match pat {
+            Pat::Ident(ident) if ident.mutability.is_some() && ident.by_ref.is_none() => {
+                let name = ident.ident.to_string();
+                if !self.names.iter().any(|(n, _)| *n == name) {
+                    self.names.push((name, is_let));
+                }
+            }
+            Pat::Type(typed) => self.add(&typed.pat, is_let),
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for MutBindings {
+    fn visit_local(&mut self, local: &'ast Local) {
+        self.add(&local.pat, true);
+        visit::visit_local(self, local);
+    }
+}
+
+#[derive(Default)]
+struct MutatedNames {
+    names: HashSet<String>,
+}
+
+impl MutatedNames {
+    fn mark(&mut self, expr: &Expr) {
+        if let Some(name) = place_root(expr) {
+            self.names.insert(name);
+        }
+    }
+
+    fn mark_tokens(&mut self, tokens: TokenStream) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => {
+                    self.names.insert(ident.to_string());
+                }
+                TokenTree::Group(group) => self.mark_tokens(group.stream()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for MutatedNames {
+    fn visit_expr(&mut self, expr: &'ast Expr) {
+        match expr {
+            Expr::Assign(assign) => self.mark(&assign.left),
+            Expr::Binary(binary) if is_compound_assign(&binary.op) => self.mark(&binary.left),
+            Expr::Reference(reference) if reference.mutability.is_some() => {
+                self.mark(&reference.expr)
+            }
+            Expr::MethodCall(call) => self.mark(&call.receiver),
+            _ => {}
+        }
+        visit::visit_expr(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        self.mark_tokens(mac.tokens.clone());
+    }
+}
+
+/// The variable a place expression such as `x.items[0]` is rooted at
+fn place_root(expr: &Expr) -> Option<String> {
+    match expr {
+        Expr::Path(path) => path.path.get_ident().map(|ident| ident.to_string()),
+        Expr::Field(field) => place_root(&field.base),
+        Expr::Index(index) => place_root(&index.expr),
+        Expr::Paren(paren) => place_root(&paren.expr),
+        _ => None,
+    }
+}
+
+fn is_compound_assign(op: &BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::AddAssign(_)
+            | BinOp::SubAssign(_)
+            | BinOp::MulAssign(_)
+            | BinOp::DivAssign(_)
+            | BinOp::RemAssign(_)
+            | BinOp::BitXorAssign(_)
+            | BinOp::BitAndAssign(_)
+            | BinOp::BitOrAssign(_)
+            | BinOp::ShlAssign(_)
+            | BinOp::ShrAssign(_)
+    )
+}
+
+/// Token text spaced the way rustfmt would for the expressions the rules
+/// suggest; literals are copied verbatim
+fn render(tokens: TokenStream) -> String {
+    let mut text = String::new();
+    write_tokens(tokens, &mut text);
+    text
+}
+
+fn write_tokens(tokens: TokenStream, text: &mut String) {
+    let mut before_prev: Option<TokenTree> = None;
+    let mut prev: Option<TokenTree> = None;
+    for token in tokens {
+        if let Some(prev) = &prev {
+            if space_between(before_prev.as_ref(), prev, &token) {
+                text.push(' ');
+            }
+        }
+        match &token {
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => ("{ ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                text.push_str(open);
+                write_tokens(group.stream(), text);
+                text.push_str(close);
+            }
+            other => text.push_str(&other.to_string()),
+        }
+        before_prev = prev.replace(token);
+    }
+}
+
+fn space_between(before_prev: Option<&TokenTree>, prev: &TokenTree, token: &TokenTree) -> bool {
+    if let TokenTree::Punct(p) = prev {
+        let after_operator = match before_prev {
+            None => true,
+            Some(TokenTree::Punct(b)) => b.spacing() == Spacing::Alone,
+            Some(_) => false,
+        };
+        let path_separator = matches!(
+            before_prev,
+            Some(TokenTree::Punct(b)) if b.as_char() == ':' && b.spacing() == Spacing::Joint
+        );
+        // `::`, `->` and the like, field access, and prefix `&x`, `!x`, `-x`, `*x`
+        if p.spacing() == Spacing::Joint
+            || p.as_char() == '.'
+            || (p.as_char() == ':' && path_separator)
+            || (matches!(p.as_char(), '&' | '!' | '-' | '*') && after_operator)
+        {
+            return false;
+        }
+    }
+    match token {
+        TokenTree::Punct(p) => match p.as_char() {
+            '.' | ',' | ';' | ':' | '?' => false,
+            // a macro's `!`, not `!=`
+            '!' => !(p.spacing() == Spacing::Alone && matches!(prev, TokenTree::Ident(_))),
+            _ => true,
+        },
+        TokenTree::Group(group) if group.delimiter() != Delimiter::Brace => match prev {
+            TokenTree::Ident(_) | TokenTree::Group(_) => false,
+            TokenTree::Punct(p) => p.as_char() != '!',
+            TokenTree::Literal(_) => true,
+        },
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(code: &str) -> Vec<LintDiagnostic> {
+        Linter::new().lint(code).unwrap()
+    }
+
+    #[test]
+    fn test_unnecessary_clone() {
+        let diagnostics = lint(
+            r#"
+pub fn count(items: &Vec<String>, name: &String) -> usize {
+    let n = items.clone().len();
+    let first = items.clone().first().cloned();
+    let s = name.clone().clone();
+    let k = 1.clone();
+    n + s.len() + k
+}
+"#,
+        );
+        let fixes: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                assert_eq!(d.lint, "unnecessary_clone");
+                assert_eq!(d.function, "count");
+                (
+                    d.suggestion.original.as_str(),
+                    d.suggestion.replacement.as_str(),
+                    d.suggestion.applicability,
+                )
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [
+                (
+                    "items.clone().len()",
+                    "items.len()",
+                    Applicability::MachineApplicable
+                ),
+                (
+                    "items.clone().first()",
+                    "items.first()",
+                    Applicability::MaybeIncorrect
+                ),
+                (
+                    "name.clone().clone()",
+                    "name.clone()",
+                    Applicability::MachineApplicable
+                ),
+                ("1.clone()", "1", Applicability::MachineApplicable),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_redundant_to_string() {
+        let diagnostics = lint(
+            r#"
+impl Greeter {
+    pub fn greet(&self, name: &str) -> String {
+        let a = "hi".to_string().to_string();
+        let b = format!("{} {}", a, name).to_string();
+        let c = String::from(name).to_string();
+        let d = name.to_string();
+        a + &b + &c + &d
+    }
+}
+"#,
+        );
+        let originals: Vec<_> = diagnostics
+            .iter()
+            .map(|d| (d.function.as_str(), d.suggestion.replacement.as_str()))
+            .collect();
+        assert_eq!(
+            originals,
+            [
+                ("Greeter::greet", "\"hi\".to_string()"),
+                ("Greeter::greet", "format!(\"{} {}\", a, name)"),
+                ("Greeter::greet", "String::from(name)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_needless_mut() {
+        let diagnostics = lint(
+            r#"
+pub fn process(mut limit: i32, mut items: Vec<i32>) -> i32 {
+    let mut total = 0;
+    let mut count: i32 = 0;
+    let mut unused = 5;
+    let mut point = Point { x: 0 };
+    let mut log = Vec::new();
+    for item in items.iter() {
+        total += item;
+    }
+    point.x = 1;
+    count = total;
+    println!("{:?}", log);
+    limit + count + unused + point.x
+}
+"#,
+        );
+        let fixes: Vec<_> = diagnostics
+            .iter()
+            .map(|d| {
+                assert_eq!(d.lint, "needless_mut");
+                (
+                    d.suggestion.original.as_str(),
+                    d.suggestion.replacement.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            fixes,
+            [("mut limit", "limit"), ("let mut unused", "let unused")]
+        );
+    }
+
+    #[test]
+    fn test_clean_code_has_no_diagnostics() {
+        let diagnostics = lint(
+            r#"
+pub fn add(a: i32, b: i32) -> i32 {
+    let mut result = a;
+    result += b;
+    result
+}
+"#,
+        );
+        assert!(diagnostics.is_empty());
+    }
+}
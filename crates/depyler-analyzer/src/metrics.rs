@@ -1,3 +1,5 @@
+use depyler_core::dataflow::{analyze_dead_code, DeadStore, StmtPath};
+use depyler_core::hir::{HirFunction, HirModule};
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
@@ -12,6 +14,9 @@ pub struct TranspilationMetrics {
     pub functions_transpiled: usize,
     pub direct_transpilation_rate: f64,
     pub mcp_fallback_count: usize,
+    /// HIR nodes rewritten by constant folding
+    #[serde(default)]
+    pub nodes_simplified: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -106,9 +111,41 @@ impl PerformanceProfile {
     }
 }
 
+/// Dead stores and unreachable statements of one function, from a backward
+/// liveness analysis over its control-flow graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadCodeMetrics {
+    pub function: String,
+    pub dead_stores: Vec<DeadStore>,
+    pub unreachable: Vec<StmtPath>,
+}
+
+impl DeadCodeMetrics {
+    pub fn for_function(func: &HirFunction) -> Self {
+        let report = analyze_dead_code(&func.body);
+        Self {
+            function: func.name.clone(),
+            dead_stores: report.dead_stores,
+            unreachable: report.unreachable,
+        }
+    }
+
+    /// Functions of the module with at least one finding
+    pub fn for_module(module: &HirModule) -> Vec<Self> {
+        module
+            .functions
+            .iter()
+            .map(Self::for_function)
+            .filter(|metrics| !metrics.dead_stores.is_empty() || !metrics.unreachable.is_empty())
+            .collect()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use depyler_annotations::TranspilationAnnotations;
+    use depyler_core::hir::{AssignTarget, FunctionProperties, HirExpr, HirStmt, Literal, Type};
     use std::time::Duration;
 
     #[test]
@@ -199,6 +236,7 @@ mod tests {
             functions_transpiled: 10,
             direct_transpilation_rate: 0.8,
             mcp_fallback_count: 2,
+            nodes_simplified: 0,
         };
 
         let memory_peak_bytes = 2 * 1024 * 1024; // 2 MB
@@ -230,6 +268,7 @@ mod tests {
             functions_transpiled: 1,
             direct_transpilation_rate: 1.0,
             mcp_fallback_count: 0,
+            nodes_simplified: 0,
         };
 
         let profile = PerformanceProfile::calculate(&metrics, 1024);
@@ -253,6 +292,7 @@ mod tests {
             functions_transpiled: 5,
             direct_transpilation_rate: 0.6,
             mcp_fallback_count: 2,
+            nodes_simplified: 0,
         };
 
         assert_eq!(metrics.parse_time, Duration::from_millis(50));
@@ -344,4 +384,31 @@ mod tests {
         // Expected: (2*3 + 3*8 + 1*15 + 1*25) / 7 = (6 + 24 + 15 + 25) / 7 = 70/7 = 10
         assert!((dist.average() - 10.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_dead_code_metrics_for_function() {
+        let func = HirFunction {
+            name: "compute".to_string(),
+            params: smallvec::smallvec![],
+            ret_type: Type::Int,
+            body: vec![
+                HirStmt::Assign {
+                    target: AssignTarget::Symbol("unused".to_string()),
+                    value: HirExpr::Literal(Literal::Int(1)),
+                    type_annotation: None,
+                },
+                HirStmt::Return(Some(HirExpr::Literal(Literal::Int(2)))),
+                HirStmt::Pass,
+            ],
+            properties: FunctionProperties::default(),
+            annotations: TranspilationAnnotations::default(),
+            docstring: None,
+        };
+
+        let metrics = DeadCodeMetrics::for_function(&func);
+        assert_eq!(metrics.function, "compute");
+        assert_eq!(metrics.dead_stores.len(), 1);
+        assert_eq!(metrics.dead_stores[0].variable, "unused");
+        assert_eq!(metrics.unreachable, vec![StmtPath(vec![2])]);
+    }
 }
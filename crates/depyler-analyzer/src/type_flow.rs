@@ -166,6 +166,15 @@ impl TypeInferencer {
             HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {
                 // Break, continue, and pass don't affect type inference
             }
+            HirStmt::Match { subject, cases } => {
+                self.infer_expr(subject)?;
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.infer_expr(guard)?;
+                    }
+                    self.infer_body(&case.body)?;
+                }
+            }
             HirStmt::Assert { test, msg } => {
                 // Infer types of test expression and optional message
                 self.infer_expr(test)?;
@@ -388,7 +397,7 @@ impl TypeInferencer {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use depyler_core::hir::{BinOp, HirParam, Literal, UnaryOp};
+    use depyler_core::hir::{BinOp, HirParam, Literal, ParamKind, UnaryOp};
 
     #[test]
     fn test_type_environment_new() {
@@ -1291,6 +1300,7 @@ mod tests {
                 name: "x".to_string(),
                 ty: Type::Int,
                 default: None,
+                kind: ParamKind::PositionalOrKeyword,
             }]),
             ret_type: Type::Int,
             body: vec![HirStmt::Assign {
@@ -28,9 +28,15 @@ pub struct TranspilationAnnotations {
     pub thread_safety: ThreadSafety,
     pub interior_mutability: InteriorMutability,
     pub string_strategy: StringStrategy,
+    pub string_indexing: StringIndexing,
     pub hash_strategy: HashStrategy,
     pub panic_behavior: PanicBehavior,
     pub error_strategy: ErrorStrategy,
+    /// `None` leaves the choice to the module's default
+    pub integer_semantics: Option<IntegerSemantics>,
+    /// `None` leaves the choice to the module's default
+    pub print_handling: Option<PrintHandling>,
+    pub container_strategy: ContainerStrategy,
     pub global_strategy: GlobalStrategy,
     pub termination: Termination,
     pub invariants: Vec<String>,
@@ -39,6 +45,8 @@ pub struct TranspilationAnnotations {
     pub migration_strategy: Option<MigrationStrategy>,
     pub compatibility_layer: Option<CompatibilityLayer>,
     pub pattern: Option<String>,
+    /// Overrides the visibility inferred for the generated item
+    pub visibility: Option<Visibility>,
     // Lambda-specific annotations
     pub lambda_annotations: Option<LambdaAnnotations>,
     pub custom_attributes: Vec<String>,
@@ -57,9 +65,13 @@ impl Default for TranspilationAnnotations {
             thread_safety: ThreadSafety::NotRequired,
             interior_mutability: InteriorMutability::None,
             string_strategy: StringStrategy::Conservative,
+            string_indexing: StringIndexing::Chars,
             hash_strategy: HashStrategy::Standard,
             panic_behavior: PanicBehavior::Propagate,
             error_strategy: ErrorStrategy::Panic,
+            integer_semantics: None,
+            print_handling: None,
+            container_strategy: ContainerStrategy::Adaptive,
             global_strategy: GlobalStrategy::None,
             termination: Termination::Unknown,
             invariants: Vec::new(),
@@ -68,6 +80,7 @@ impl Default for TranspilationAnnotations {
             migration_strategy: None,
             compatibility_layer: None,
             pattern: None,
+            visibility: None,
             lambda_annotations: None,
             custom_attributes: Vec::new(),
         }
@@ -149,6 +162,9 @@ pub enum OwnershipModel {
     Owned,
     Borrowed,
     Shared,
+    /// Instances of an annotated class live in one arena and refer to each
+    /// other by index
+    Arena,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -206,6 +222,20 @@ pub enum StringStrategy {
     Conservative,
     AlwaysOwned,
     ZeroCopy,
+    /// Return `Cow<str>` from functions that return a parameter unchanged
+    /// on some paths and a new string on others
+    CopyOnWrite,
+}
+
+/// What `s[i]` and `s[a:b]` count in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StringIndexing {
+    /// Unicode scalar values, as Python does
+    #[default]
+    Chars,
+    /// Bytes, for strings known to be ASCII; a slice that splits a
+    /// multi-byte character panics
+    Ascii,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -229,6 +259,45 @@ pub enum ErrorStrategy {
     OptionType,
 }
 
+/// What `int` arithmetic does past the range of the machine integer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntegerSemantics {
+    /// Plain operators: panics in debug builds, wraps in release builds
+    #[default]
+    Panic,
+    /// `wrapping_*` operations, wrapping around in every build
+    Wrapping,
+    /// `saturating_*` operations, clamping to the integer's bounds
+    Saturating,
+    /// `checked_*` operations raising `OverflowError`
+    Checked,
+    /// `num_bigint::BigInt`, as unbounded as Python's `int`
+    BigInt,
+}
+
+/// What becomes of `print()` statements
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrintHandling {
+    /// `println!`, writing to stdout as Python does
+    #[default]
+    Keep,
+    /// Dropped, arguments included
+    Strip,
+    /// `log::debug!`, left to the application's logger
+    Log,
+}
+
+/// Whether local lists may become other containers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContainerStrategy {
+    /// A list used only as a queue becomes a `VecDeque`, one used only for
+    /// membership tests a `HashSet`
+    #[default]
+    Adaptive,
+    /// Every list stays a `Vec`
+    AsWritten,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum GlobalStrategy {
     None,
@@ -236,6 +305,16 @@ pub enum GlobalStrategy {
     OnceCell,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// No visibility qualifier
+    Private,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Termination {
     Unknown,
@@ -430,7 +509,11 @@ impl AnnotationExtractor {
                     let mut annotations = Vec::new();
                     let mut j = i.saturating_sub(1);
 
-                    while j < i && (lines[j].trim().starts_with('#') || lines[j].trim().is_empty())
+                    // Comments may sit above the decorators too
+                    while j < i
+                        && (lines[j].trim().starts_with('#')
+                            || lines[j].trim().starts_with('@')
+                            || lines[j].trim().is_empty())
                     {
                         if lines[j].contains("@depyler:") {
                             annotations.push(lines[j]);
@@ -545,8 +628,8 @@ impl AnnotationParser {
                     self.apply_thread_safety_annotation(annotations, &key, &value)?;
                 }
 
-                // String/Hash strategy (2)
-                "string_strategy" | "hash_strategy" => {
+                // String/Hash strategy (3, `hash` is short for `hash_strategy`)
+                "string_strategy" | "string_indexing" | "hash_strategy" | "hash" => {
                     self.apply_string_hash_annotation(annotations, &key, &value)?;
                 }
 
@@ -555,11 +638,31 @@ impl AnnotationParser {
                     self.apply_error_handling_annotation(annotations, &key, &value)?;
                 }
 
+                // Integer overflow (1)
+                "integer_semantics" => {
+                    annotations.integer_semantics = Some(self.parse_integer_semantics(&value)?);
+                }
+
+                // print() statements (1)
+                "print" => {
+                    annotations.print_handling = Some(self.parse_print_handling(&value)?);
+                }
+
+                // Container substitution (1)
+                "containers" => {
+                    annotations.container_strategy = self.parse_container_strategy(&value)?;
+                }
+
                 // Global strategy (1)
                 "global_strategy" => {
                     self.apply_global_strategy_annotation(annotations, &value)?;
                 }
 
+                // Item visibility (1)
+                "visibility" => {
+                    annotations.visibility = Some(self.parse_visibility(&value)?);
+                }
+
                 // Verification (3)
                 "termination" | "invariant" | "verify_bounds" => {
                     self.apply_verification_annotation(annotations, &key, &value)?;
@@ -722,7 +825,7 @@ impl AnnotationParser {
         Ok(())
     }
 
-    /// Apply string/hash strategy annotation (string_strategy, hash_strategy)
+    /// Apply string/hash strategy annotation (string_strategy, string_indexing, hash_strategy)
     #[inline]
     fn apply_string_hash_annotation(
         &self,
@@ -734,7 +837,10 @@ impl AnnotationParser {
             "string_strategy" => {
                 annotations.string_strategy = self.parse_string_strategy(value)?;
             }
-            "hash_strategy" => {
+            "string_indexing" => {
+                annotations.string_indexing = self.parse_string_indexing(value)?;
+            }
+            "hash_strategy" | "hash" => {
                 annotations.hash_strategy = self.parse_hash_strategy(value)?;
             }
             _ => unreachable!("apply_string_hash_annotation called with non-string/hash key"),
@@ -935,6 +1041,7 @@ impl AnnotationParser {
             "owned" => Ok(OwnershipModel::Owned),
             "borrowed" => Ok(OwnershipModel::Borrowed),
             "shared" => Ok(OwnershipModel::Shared),
+            "arena" => Ok(OwnershipModel::Arena),
             _ => Err(AnnotationError::InvalidValue {
                 key: "ownership".to_string(),
                 value: value.to_string(),
@@ -1021,6 +1128,7 @@ impl AnnotationParser {
             "conservative" => Ok(StringStrategy::Conservative),
             "always_owned" => Ok(StringStrategy::AlwaysOwned),
             "zero_copy" => Ok(StringStrategy::ZeroCopy),
+            "cow" => Ok(StringStrategy::CopyOnWrite),
             _ => Err(AnnotationError::InvalidValue {
                 key: "string_strategy".to_string(),
                 value: value.to_string(),
@@ -1028,9 +1136,20 @@ impl AnnotationParser {
         }
     }
 
+    fn parse_string_indexing(&self, value: &str) -> Result<StringIndexing, AnnotationError> {
+        match value {
+            "chars" => Ok(StringIndexing::Chars),
+            "ascii" => Ok(StringIndexing::Ascii),
+            _ => Err(AnnotationError::InvalidValue {
+                key: "string_indexing".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
     fn parse_hash_strategy(&self, value: &str) -> Result<HashStrategy, AnnotationError> {
         match value {
-            "standard" => Ok(HashStrategy::Standard),
+            "standard" | "std" => Ok(HashStrategy::Standard),
             "fnv" => Ok(HashStrategy::Fnv),
             "ahash" => Ok(HashStrategy::AHash),
             _ => Err(AnnotationError::InvalidValue {
@@ -1064,6 +1183,43 @@ impl AnnotationParser {
         }
     }
 
+    fn parse_integer_semantics(&self, value: &str) -> Result<IntegerSemantics, AnnotationError> {
+        match value {
+            "panic" => Ok(IntegerSemantics::Panic),
+            "wrap" | "wrapping" => Ok(IntegerSemantics::Wrapping),
+            "saturating" => Ok(IntegerSemantics::Saturating),
+            "checked" => Ok(IntegerSemantics::Checked),
+            "bigint" => Ok(IntegerSemantics::BigInt),
+            _ => Err(AnnotationError::InvalidValue {
+                key: "integer_semantics".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    fn parse_print_handling(&self, value: &str) -> Result<PrintHandling, AnnotationError> {
+        match value {
+            "keep" => Ok(PrintHandling::Keep),
+            "strip" => Ok(PrintHandling::Strip),
+            "log" => Ok(PrintHandling::Log),
+            _ => Err(AnnotationError::InvalidValue {
+                key: "print".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
+    fn parse_container_strategy(&self, value: &str) -> Result<ContainerStrategy, AnnotationError> {
+        match value {
+            "adaptive" => Ok(ContainerStrategy::Adaptive),
+            "as_written" => Ok(ContainerStrategy::AsWritten),
+            _ => Err(AnnotationError::InvalidValue {
+                key: "containers".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
     fn parse_global_strategy(&self, value: &str) -> Result<GlobalStrategy, AnnotationError> {
         match value {
             "none" => Ok(GlobalStrategy::None),
@@ -1076,6 +1232,18 @@ impl AnnotationParser {
         }
     }
 
+    fn parse_visibility(&self, value: &str) -> Result<Visibility, AnnotationError> {
+        match value {
+            "pub" | "public" => Ok(Visibility::Public),
+            "crate" | "pub(crate)" => Ok(Visibility::Crate),
+            "private" => Ok(Visibility::Private),
+            _ => Err(AnnotationError::InvalidValue {
+                key: "visibility".to_string(),
+                value: value.to_string(),
+            }),
+        }
+    }
+
     fn parse_termination(&self, value: &str) -> Result<Termination, AnnotationError> {
         match value {
             "unknown" => Ok(Termination::Unknown),
@@ -1362,6 +1530,38 @@ def string_function():
         let annotations = parser.parse_annotations(source).unwrap();
         assert_eq!(annotations.string_strategy, StringStrategy::ZeroCopy);
         assert_eq!(annotations.hash_strategy, HashStrategy::Fnv);
+
+        let annotations = parser
+            .parse_annotations("# @depyler: string_strategy = \"cow\"\n")
+            .unwrap();
+        assert_eq!(annotations.string_strategy, StringStrategy::CopyOnWrite);
+    }
+
+    #[test]
+    fn test_string_indexing() {
+        let parser = AnnotationParser::new();
+        let annotations = parser.parse_annotations("def f():\n    pass\n").unwrap();
+        assert_eq!(annotations.string_indexing, StringIndexing::Chars);
+        let annotations = parser
+            .parse_annotations("# @depyler: string_indexing = \"ascii\"\n")
+            .unwrap();
+        assert_eq!(annotations.string_indexing, StringIndexing::Ascii);
+        assert!(parser
+            .parse_annotations("# @depyler: string_indexing = \"bytes\"\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_short_hash_key() {
+        let parser = AnnotationParser::new();
+        let annotations = parser
+            .parse_annotations("# @depyler: hash = \"ahash\"\n")
+            .unwrap();
+        assert_eq!(annotations.hash_strategy, HashStrategy::AHash);
+        let annotations = parser
+            .parse_annotations("# @depyler: hash = \"std\"\n")
+            .unwrap();
+        assert_eq!(annotations.hash_strategy, HashStrategy::Standard);
     }
 
     #[test]
@@ -1379,6 +1579,70 @@ def error_function():
         assert_eq!(annotations.error_strategy, ErrorStrategy::ResultType);
     }
 
+    #[test]
+    fn test_integer_semantics_annotation() {
+        let parser = AnnotationParser::new();
+        let annotations = parser
+            .parse_annotations("# @depyler: integer_semantics = \"bigint\"\n")
+            .unwrap();
+        assert_eq!(
+            annotations.integer_semantics,
+            Some(IntegerSemantics::BigInt)
+        );
+        let annotations = parser
+            .parse_annotations("# @depyler: integer_semantics = \"saturating\"\n")
+            .unwrap();
+        assert_eq!(
+            annotations.integer_semantics,
+            Some(IntegerSemantics::Saturating)
+        );
+        assert_eq!(TranspilationAnnotations::default().integer_semantics, None);
+        assert_eq!(IntegerSemantics::default(), IntegerSemantics::Panic);
+        assert!(parser
+            .parse_annotations("# @depyler: integer_semantics = \"clamp\"\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_print_annotation() {
+        let parser = AnnotationParser::new();
+        let annotations = parser
+            .parse_annotations("# @depyler: print = \"log\"\n")
+            .unwrap();
+        assert_eq!(annotations.print_handling, Some(PrintHandling::Log));
+        assert_eq!(TranspilationAnnotations::default().print_handling, None);
+        assert!(parser
+            .parse_annotations("# @depyler: print = \"stderr\"\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_containers_annotation() {
+        let parser = AnnotationParser::new();
+        let annotations = parser
+            .parse_annotations("# @depyler: containers = \"as_written\"\n")
+            .unwrap();
+        assert_eq!(annotations.container_strategy, ContainerStrategy::AsWritten);
+        assert_eq!(
+            TranspilationAnnotations::default().container_strategy,
+            ContainerStrategy::Adaptive
+        );
+        assert!(parser
+            .parse_annotations("# @depyler: containers = \"vec\"\n")
+            .is_err());
+    }
+
+    #[test]
+    fn test_arena_ownership_on_a_class() {
+        let extractor = AnnotationExtractor::new();
+        let source = "# @depyler: ownership = \"arena\"\n@dataclass\nclass Node:\n    value: int\n";
+        let comments = extractor.extract_class_annotations(source, "Node").unwrap();
+        let annotations = AnnotationParser::new()
+            .parse_annotations(&comments)
+            .unwrap();
+        assert_eq!(annotations.ownership_model, OwnershipModel::Arena);
+    }
+
     #[test]
     fn test_service_and_migration_annotations() {
         let parser = AnnotationParser::new();
@@ -1434,6 +1698,22 @@ def global_function():
         assert_eq!(annotations.global_strategy, GlobalStrategy::LazyStatic);
     }
 
+    #[test]
+    fn test_visibility() {
+        let parser = AnnotationParser::new();
+        let source = r#"
+# @depyler: visibility = "crate"
+def helper():
+    pass
+        "#;
+
+        let annotations = parser.parse_annotations(source).unwrap();
+        assert_eq!(annotations.visibility, Some(Visibility::Crate));
+
+        let invalid = "# @depyler: visibility = \"protected\"";
+        assert!(parser.parse_annotations(invalid).is_err());
+    }
+
     #[test]
     fn test_lambda_annotations_basic() {
         let parser = AnnotationParser::new();
@@ -236,6 +236,8 @@ fn create_calculator_class() -> HirClass {
             },
         ],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: Some("A simple calculator class".to_string()),
     }
 }
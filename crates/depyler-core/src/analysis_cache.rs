@@ -0,0 +1,253 @@
+//! Per-function analysis results kept between runs
+//!
+//! Re-transpiling a large module analyzes every function again, though
+//! most are unchanged since the last run. An [`AnalysisCache`] is a
+//! directory holding the [`FunctionAnalysis`] of each function analyzed,
+//! one JSON file per function, named by the [`structural_hash`] of its HIR.
+//! A function whose HIR hashes the same is not analyzed again.
+//!
+//! The hash covers everything the HIR records of the function, its
+//! annotations included, but not its source positions, so moving a function
+//! or editing another one keeps its entry. It also covers the depyler
+//! version, as analyses change between releases. Entries are never evicted;
+//! deleting the directory clears the cache.
+//!
+//! Attach a cache with
+//! [`DepylerPipeline::with_analysis_cache`](crate::DepylerPipeline::with_analysis_cache).
+
+use crate::attribute_synthesis::cyclomatic_complexity;
+use crate::borrowing::BorrowingContext;
+use crate::hir::{HirFunction, Type};
+use crate::type_hints::{Confidence, HintTarget, TypeHint, TypeHintProvider};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// What the analyses found out about one function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionAnalysis {
+    /// Confident types for the unannotated parameters, by name
+    pub param_types: Vec<(String, Type)>,
+    /// A confident return type, when the return is unannotated
+    pub ret_type: Option<Type>,
+    /// Every hint found, confident or not, to show the user
+    pub hints: Vec<TypeHint>,
+    /// Whether the body mutates each parameter, in parameter order
+    pub mutated_params: Vec<bool>,
+    /// [`cyclomatic_complexity`] of the body
+    pub complexity: u32,
+}
+
+impl FunctionAnalysis {
+    /// Analyzes `func` from scratch
+    pub fn analyze(func: &HirFunction) -> Self {
+        let mut type_hint_provider = TypeHintProvider::new();
+        let hints = type_hint_provider
+            .analyze_function(func)
+            .unwrap_or_default();
+        let confident = hints
+            .iter()
+            .filter(|hint| matches!(hint.confidence, Confidence::High | Confidence::Certain));
+        let unannotated = |name: &str| {
+            func.params
+                .iter()
+                .any(|param| param.name == name && param.ty == Type::Unknown)
+        };
+        let mut param_types: Vec<(String, Type)> = Vec::new();
+        let mut ret_type = None;
+        for hint in confident {
+            match &hint.target {
+                HintTarget::Parameter(name)
+                    if unannotated(name) && !param_types.iter().any(|(known, _)| known == name) =>
+                {
+                    param_types.push((name.clone(), hint.suggested_type.clone()));
+                }
+                HintTarget::Return if ret_type.is_none() && func.ret_type == Type::Unknown => {
+                    ret_type = Some(hint.suggested_type.clone());
+                }
+                _ => {}
+            }
+        }
+
+        let mut borrowing = BorrowingContext::new();
+        borrowing.analyze_function(func);
+        let mutated_params = func
+            .params
+            .iter()
+            .map(|param| borrowing.is_mutated(&param.name))
+            .collect();
+
+        FunctionAnalysis {
+            param_types,
+            ret_type,
+            hints,
+            mutated_params,
+            complexity: cyclomatic_complexity(&func.body),
+        }
+    }
+}
+
+/// How often a cache had the analysis asked for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+/// [`FunctionAnalysis`]es stored in a directory, by function hash
+///
+/// Shared between the modules of a project transpiled in parallel.
+#[derive(Debug)]
+pub struct AnalysisCache {
+    dir: PathBuf,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl AnalysisCache {
+    /// A cache in `dir`, created if missing
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("cannot create analysis cache {}", dir.display()))?;
+        Ok(AnalysisCache {
+            dir,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The analysis of `func`, stored by an earlier run or computed and
+    /// stored now
+    ///
+    /// A cache that cannot be read or written is only slower: unreadable
+    /// entries are analyzed again and failed writes are dropped.
+    pub fn analysis(&self, func: &HirFunction) -> FunctionAnalysis {
+        let path = self.entry_path(structural_hash(func));
+        let stored = fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+        if let Some(analysis) = stored {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return analysis;
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let analysis = FunctionAnalysis::analyze(func);
+        if let Ok(json) = serde_json::to_vec(&analysis) {
+            // Written aside and renamed, so a reader never sees half an entry
+            let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+            if fs::write(&partial, json).is_ok() && fs::rename(&partial, &path).is_err() {
+                let _ = fs::remove_file(&partial);
+            }
+        }
+        analysis
+    }
+
+    /// Hits and misses since the cache was opened
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn entry_path(&self, hash: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", hash))
+    }
+}
+
+/// FNV-1a of the function's HIR and the depyler version
+///
+/// Stable across runs and platforms, unlike `std`'s hashers.
+pub fn structural_hash(func: &HirFunction) -> u64 {
+    let hir = serde_json::to_vec(func).unwrap_or_default();
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in env!("CARGO_PKG_VERSION").bytes().chain([0]).chain(hir) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    fn function(source: &str) -> HirFunction {
+        DepylerPipeline::new()
+            .parse_to_hir(source)
+            .unwrap()
+            .functions
+            .remove(0)
+    }
+
+    #[test]
+    fn test_hash_follows_structure() {
+        let original = function("def add(xs: list[int], x: int) -> None:\n    xs.append(x)\n");
+        let moved = function(
+            "\n\n# moved down\ndef add(xs: list[int], x: int) -> None:\n    xs.append(x)\n",
+        );
+        let edited = function("def add(xs: list[int], x: int) -> None:\n    xs.append(x + 1)\n");
+
+        assert_eq!(structural_hash(&original), structural_hash(&moved));
+        assert_ne!(structural_hash(&original), structural_hash(&edited));
+    }
+
+    #[test]
+    fn test_analysis_is_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let func = function(
+            "def add(xs: list[int], x: int) -> int:\n    if x > 0:\n        xs.append(x)\n    return len(xs)\n",
+        );
+
+        let cache = AnalysisCache::open(dir.path()).unwrap();
+        let analysis = cache.analysis(&func);
+        assert_eq!(analysis.mutated_params, vec![true, false]);
+        assert_eq!(analysis.complexity, 2);
+        assert_eq!(cache.stats(), CacheStats { hits: 0, misses: 1 });
+
+        // A later run finds it on disk
+        let cache = AnalysisCache::open(dir.path()).unwrap();
+        assert_eq!(cache.analysis(&func), analysis);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 0 });
+    }
+
+    #[test]
+    fn test_hit_is_not_analyzed_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let func = function("def add(xs: list[int], x: int) -> None:\n    xs.append(x)\n");
+        let cache = AnalysisCache::open(dir.path()).unwrap();
+        let mut analysis = cache.analysis(&func);
+
+        // An entry no analysis of `func` would produce comes back as stored
+        analysis.mutated_params = vec![false, true];
+        analysis.complexity = 99;
+        let entry = cache.entry_path(structural_hash(&func));
+        fs::write(&entry, serde_json::to_vec(&analysis).unwrap()).unwrap();
+
+        let cache = AnalysisCache::open(dir.path()).unwrap();
+        assert_eq!(cache.analysis(&func), analysis);
+        assert_eq!(cache.stats(), CacheStats { hits: 1, misses: 0 });
+    }
+
+    #[test]
+    fn test_cached_pipeline_transpiles_the_same() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = "def double(x):\n    return x * 2\n\ndef name() -> str:\n    return \"x\"\n";
+        let plain = DepylerPipeline::new().transpile(source).unwrap();
+
+        for hits in [0, 2] {
+            let pipeline = DepylerPipeline::new()
+                .with_analysis_cache(AnalysisCache::open(dir.path()).unwrap());
+            assert_eq!(pipeline.transpile(source).unwrap(), plain);
+            assert_eq!(pipeline.analysis_cache().unwrap().stats().hits, hits);
+        }
+    }
+}
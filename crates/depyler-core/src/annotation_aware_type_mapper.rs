@@ -1,8 +1,10 @@
 use crate::hir::Type as PythonType;
 use crate::type_mapper::{RustType, TypeMapper};
 use depyler_annotations::{
-    OwnershipModel, StringStrategy as AnnotationStringStrategy, TranspilationAnnotations,
+    IntegerSemantics, OwnershipModel, StringStrategy as AnnotationStringStrategy,
+    TranspilationAnnotations,
 };
+use std::borrow::Cow;
 
 /// An enhanced type mapper that considers annotations when mapping types
 pub struct AnnotationAwareTypeMapper {
@@ -37,7 +39,16 @@ impl AnnotationAwareTypeMapper {
             PythonType::List(inner) => self.map_list_type(inner, annotations),
             PythonType::Dict(key, value) => self.map_dict_type(key, value, annotations),
             PythonType::Optional(inner) => self.map_optional_type(inner, annotations),
-            _ => self.base_mapper.map_type(py_type),
+            _ => self.base_mapper_for(annotations).map_type(py_type),
+        }
+    }
+
+    /// The base mapper, with `int` widened to `BigInt` when the annotations
+    /// ask for arbitrary precision
+    fn base_mapper_for(&self, annotations: &TranspilationAnnotations) -> Cow<'_, TypeMapper> {
+        match annotations.integer_semantics {
+            Some(IntegerSemantics::BigInt) => Cow::Owned(self.base_mapper.clone().with_bigint()),
+            _ => Cow::Borrowed(&self.base_mapper),
         }
     }
 
@@ -51,7 +62,7 @@ impl AnnotationAwareTypeMapper {
                 },
                 _ => RustType::String, // Can't do zero-copy without borrowing
             },
-            AnnotationStringStrategy::Conservative => {
+            AnnotationStringStrategy::Conservative | AnnotationStringStrategy::CopyOnWrite => {
                 // Conservative defaults to owned unless explicitly borrowed
                 match annotations.ownership_model {
                     OwnershipModel::Borrowed => RustType::Str {
@@ -85,7 +96,7 @@ impl AnnotationAwareTypeMapper {
                     RustType::Custom(format!("Rc<Vec<{}>>", inner_rust.to_rust_string()))
                 }
             }
-            OwnershipModel::Owned => RustType::Vec(Box::new(inner_rust)),
+            OwnershipModel::Owned | OwnershipModel::Arena => RustType::Vec(Box::new(inner_rust)),
         }
     }
 
@@ -99,20 +110,11 @@ impl AnnotationAwareTypeMapper {
         let key_rust = self.map_type_with_annotations(key, annotations);
         let value_rust = self.map_type_with_annotations(value, annotations);
 
-        // DEPYLER-0278: Always use std::HashMap for standalone file transpilation
-        // FnvHashMap and AHashMap require external crate dependencies that may not be available
-        // For standalone files, we prioritize compilation success over optimization
-        // TODO: In the future, detect Cargo project context and use hash_strategy only within projects
+        // DEPYLER-0278: Always name the map `HashMap`. hash_strategy picks what
+        // that name is bound to for the whole file (see rust_gen::hash_gen), so
+        // maps passed between differently annotated functions keep one type.
         let hash_map_type = "HashMap";
 
-        // Note: hash_strategy annotation is currently ignored for standalone transpilation
-        // Original logic (disabled):
-        // match annotations.hash_strategy {
-        //     depyler_annotations::HashStrategy::Standard => "HashMap",
-        //     depyler_annotations::HashStrategy::Fnv => "FnvHashMap",
-        //     depyler_annotations::HashStrategy::AHash => "AHashMap",
-        // }
-
         let base_type = RustType::Custom(format!(
             "{}<{}, {}>",
             hash_map_type,
@@ -133,7 +135,7 @@ impl AnnotationAwareTypeMapper {
                     RustType::Custom(format!("Rc<{}>", base_type.to_rust_string()))
                 }
             }
-            OwnershipModel::Owned => base_type,
+            OwnershipModel::Owned | OwnershipModel::Arena => base_type,
         }
     }
 
@@ -163,7 +165,7 @@ impl AnnotationAwareTypeMapper {
     ) -> bool {
         match annotations.ownership_model {
             OwnershipModel::Borrowed => !self.base_mapper.can_copy(rust_type),
-            OwnershipModel::Owned => false,
+            OwnershipModel::Owned | OwnershipModel::Arena => false,
             OwnershipModel::Shared => false, // Shared types are already wrapped
         }
     }
@@ -279,10 +281,10 @@ mod tests {
             RustType::Custom("HashMap<String, i32>".to_string())
         );
 
-        // DEPYLER-0278: hash_strategy annotation is ignored for standalone transpilation
-        // All hash strategies now map to HashMap (compilation success over optimization)
+        // DEPYLER-0278: hash_strategy is applied by binding `HashMap` per file,
+        // so the type name stays the same
 
-        // Test Fnv strategy (ignored, uses HashMap)
+        // Test Fnv strategy (uses the HashMap alias)
         annotations.hash_strategy = HashStrategy::Fnv;
         let rust_type = mapper.map_type_with_annotations(&dict_type, &annotations);
         assert_eq!(
@@ -290,7 +292,7 @@ mod tests {
             RustType::Custom("HashMap<String, i32>".to_string())
         );
 
-        // Test AHash strategy (ignored, uses HashMap)
+        // Test AHash strategy (uses the HashMap alias)
         annotations.hash_strategy = HashStrategy::AHash;
         let rust_type = mapper.map_type_with_annotations(&dict_type, &annotations);
         assert_eq!(
@@ -321,6 +323,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_int_mapping_with_bigint_semantics() {
+        let mapper = AnnotationAwareTypeMapper::new();
+        let mut annotations = create_test_annotations();
+        annotations.integer_semantics = Some(IntegerSemantics::Checked);
+        assert_eq!(
+            mapper.map_type_with_annotations(&PythonType::Int, &annotations),
+            RustType::Primitive(PrimitiveType::I32)
+        );
+
+        annotations.integer_semantics = Some(IntegerSemantics::BigInt);
+        let optional_int = PythonType::Optional(Box::new(PythonType::Int));
+        assert_eq!(
+            mapper.map_type_with_annotations(&optional_int, &annotations),
+            RustType::Option(Box::new(RustType::Custom("num_bigint::BigInt".to_string())))
+        );
+    }
+
     #[test]
     fn test_return_type_mapping() {
         let mapper = AnnotationAwareTypeMapper::new();
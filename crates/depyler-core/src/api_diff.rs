@@ -0,0 +1,519 @@
+//! Public API of generated code and semver-aware diffs between versions
+//!
+//! Regenerating a crate with a newer depyler can change its public surface
+//! even when the Python did not change. [`PublicApi::extract`] lists the
+//! public items of a generated file, and [`ApiDiff::between`] classifies
+//! every difference by the version bump it requires:
+//!
+//! - removals and signature changes are breaking
+//! - additions are additive, except a field added to a struct users can
+//!   build with a literal, a variant added to an exhaustive enum, or a
+//!   required method added to an existing trait, which break downstream code
+
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use syn::punctuated::Punctuated;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ApiItemKind {
+    Function,
+    Method,
+    /// `exhaustive` when every field is public and it is not
+    /// `#[non_exhaustive]`, so users can build it with a literal
+    Struct {
+        exhaustive: bool,
+    },
+    Field,
+    Enum {
+        exhaustive: bool,
+    },
+    Variant,
+    Trait,
+    /// `provided` when the method has a default body
+    TraitMethod {
+        provided: bool,
+    },
+    /// Includes derived traits
+    TraitImpl,
+    Const,
+    Static,
+    TypeAlias,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiItem {
+    pub kind: ApiItemKind,
+    /// Normalized declaration without bodies
+    pub signature: String,
+}
+
+/// Public items keyed by path: `add`, `Account`, `Account.balance`,
+/// `Account::deposit`, `Shape::Circle`, `impl Clone for Account`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicApi {
+    pub items: BTreeMap<String, ApiItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SemverImpact {
+    Patch,
+    Minor,
+    Major,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    pub impact: SemverImpact,
+    pub old: Option<String>,
+    pub new: Option<String>,
+    /// Why an addition is breaking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApiDiff {
+    pub changes: Vec<ApiChange>,
+}
+
+impl PublicApi {
+    /// Public items of a generated Rust file
+    pub fn extract(code: &str) -> Result<Self> {
+        let file = syn::parse_file(code)?;
+        let mut api = Self::default();
+        api.items_in(&file.items, "");
+        Ok(api)
+    }
+
+    fn insert(&mut self, path: String, kind: ApiItemKind, signature: String) {
+        self.items.insert(path, ApiItem { kind, signature });
+    }
+
+    /// # Complexity
+    /// 10 (one arm per public item kind)
+    fn items_in(&mut self, items: &[syn::Item], prefix: &str) {
+        for item in items {
+            match item {
+                syn::Item::Fn(f) if is_pub(&f.vis) => {
+                    let sig = &f.sig;
+                    self.insert(
+                        format!("{}{}", prefix, sig.ident),
+                        ApiItemKind::Function,
+                        render(quote!(#sig)),
+                    );
+                }
+                syn::Item::Struct(s) if is_pub(&s.vis) => self.add_struct(s, prefix),
+                syn::Item::Enum(e) if is_pub(&e.vis) => self.add_enum(e, prefix),
+                syn::Item::Trait(t) if is_pub(&t.vis) => self.add_trait(t, prefix),
+                syn::Item::Impl(i) => self.add_impl(i, prefix),
+                syn::Item::Const(c) if is_pub(&c.vis) => {
+                    let (ident, ty) = (&c.ident, &c.ty);
+                    self.insert(
+                        format!("{}{}", prefix, ident),
+                        ApiItemKind::Const,
+                        render(quote!(const #ident: #ty)),
+                    );
+                }
+                syn::Item::Static(s) if is_pub(&s.vis) => {
+                    let (ident, ty, mutability) = (&s.ident, &s.ty, &s.mutability);
+                    self.insert(
+                        format!("{}{}", prefix, ident),
+                        ApiItemKind::Static,
+                        render(quote!(static #mutability #ident: #ty)),
+                    );
+                }
+                syn::Item::Type(t) if is_pub(&t.vis) => {
+                    let (ident, generics, ty) = (&t.ident, &t.generics, &t.ty);
+                    self.insert(
+                        format!("{}{}", prefix, ident),
+                        ApiItemKind::TypeAlias,
+                        render(quote!(type #ident #generics = #ty)),
+                    );
+                }
+                syn::Item::Mod(m) if is_pub(&m.vis) => {
+                    if let Some((_, items)) = &m.content {
+                        self.items_in(items, &format!("{}{}::", prefix, m.ident));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn add_struct(&mut self, s: &syn::ItemStruct, prefix: &str) {
+        let name = format!("{}{}", prefix, s.ident);
+        let (ident, generics) = (&s.ident, &s.generics);
+        let all_pub = s.fields.iter().all(|field| is_pub(&field.vis));
+        self.insert(
+            name.clone(),
+            ApiItemKind::Struct {
+                exhaustive: all_pub && !is_non_exhaustive(&s.attrs),
+            },
+            render(quote!(struct #ident #generics)),
+        );
+        for (i, field) in s.fields.iter().enumerate() {
+            if !is_pub(&field.vis) {
+                continue;
+            }
+            let field_name = match &field.ident {
+                Some(ident) => ident.to_string(),
+                None => i.to_string(),
+            };
+            let ty = &field.ty;
+            self.insert(
+                format!("{}.{}", name, field_name),
+                ApiItemKind::Field,
+                format!("{}: {}", field_name, render(quote!(#ty))),
+            );
+        }
+        self.add_derives(&s.attrs, &name);
+    }
+
+    fn add_enum(&mut self, e: &syn::ItemEnum, prefix: &str) {
+        let name = format!("{}{}", prefix, e.ident);
+        let (ident, generics) = (&e.ident, &e.generics);
+        self.insert(
+            name.clone(),
+            ApiItemKind::Enum {
+                exhaustive: !is_non_exhaustive(&e.attrs),
+            },
+            render(quote!(enum #ident #generics)),
+        );
+        for variant in &e.variants {
+            let (ident, fields) = (&variant.ident, &variant.fields);
+            self.insert(
+                format!("{}::{}", name, ident),
+                ApiItemKind::Variant,
+                render(quote!(#ident #fields)),
+            );
+        }
+        self.add_derives(&e.attrs, &name);
+    }
+
+    fn add_trait(&mut self, t: &syn::ItemTrait, prefix: &str) {
+        let name = format!("{}{}", prefix, t.ident);
+        let (ident, generics, supertraits) = (&t.ident, &t.generics, &t.supertraits);
+        let bounds = if supertraits.is_empty() {
+            TokenStream::new()
+        } else {
+            quote!(: #supertraits)
+        };
+        self.insert(
+            name.clone(),
+            ApiItemKind::Trait,
+            render(quote!(trait #ident #generics #bounds)),
+        );
+        for item in &t.items {
+            if let syn::TraitItem::Fn(method) = item {
+                let sig = &method.sig;
+                self.insert(
+                    format!("{}::{}", name, sig.ident),
+                    ApiItemKind::TraitMethod {
+                        provided: method.default.is_some(),
+                    },
+                    render(quote!(#sig)),
+                );
+            }
+        }
+    }
+
+    fn add_impl(&mut self, i: &syn::ItemImpl, prefix: &str) {
+        let self_ty = &i.self_ty;
+        let generics = &i.generics;
+        if let Some((bang, trait_path, _)) = &i.trait_ {
+            let signature = render(quote!(impl #generics #bang #trait_path for #self_ty));
+            self.insert(
+                format!("{}{}", prefix, signature),
+                ApiItemKind::TraitImpl,
+                signature,
+            );
+            return;
+        }
+        let type_name = format!("{}{}", prefix, type_name(self_ty));
+        for item in &i.items {
+            match item {
+                syn::ImplItem::Fn(method) if is_pub(&method.vis) => {
+                    let sig = &method.sig;
+                    self.insert(
+                        format!("{}::{}", type_name, sig.ident),
+                        ApiItemKind::Method,
+                        render(quote!(#sig)),
+                    );
+                }
+                syn::ImplItem::Const(c) if is_pub(&c.vis) => {
+                    let (ident, ty) = (&c.ident, &c.ty);
+                    self.insert(
+                        format!("{}::{}", type_name, ident),
+                        ApiItemKind::Const,
+                        render(quote!(const #ident: #ty)),
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `#[derive(Clone)]` on `name` -> `impl Clone for name`
+    fn add_derives(&mut self, attrs: &[syn::Attribute], name: &str) {
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("derive")) {
+            let traits = match attr
+                .parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+            {
+                Ok(traits) => traits,
+                Err(_) => continue,
+            };
+            for trait_path in traits {
+                let signature =
+                    format!("impl {} for {}", render(trait_path.to_token_stream()), name);
+                self.insert(signature.clone(), ApiItemKind::TraitImpl, signature);
+            }
+        }
+    }
+}
+
+impl ApiDiff {
+    /// Differences from `old` to `new`, in path order
+    pub fn between(old: &PublicApi, new: &PublicApi) -> Self {
+        let mut changes = Vec::new();
+        for (path, before) in &old.items {
+            match new.items.get(path) {
+                None => changes.push(ApiChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Removed,
+                    impact: SemverImpact::Major,
+                    old: Some(before.signature.clone()),
+                    new: None,
+                    note: None,
+                }),
+                Some(after) if after != before => changes.push(ApiChange {
+                    path: path.clone(),
+                    kind: ChangeKind::Changed,
+                    impact: SemverImpact::Major,
+                    old: Some(before.signature.clone()),
+                    new: Some(after.signature.clone()),
+                    note: None,
+                }),
+                Some(_) => {}
+            }
+        }
+        for (path, after) in &new.items {
+            if old.items.contains_key(path) {
+                continue;
+            }
+            let note = breaking_addition(path, after, old);
+            changes.push(ApiChange {
+                path: path.clone(),
+                kind: ChangeKind::Added,
+                impact: if note.is_some() {
+                    SemverImpact::Major
+                } else {
+                    SemverImpact::Minor
+                },
+                old: None,
+                new: Some(after.signature.clone()),
+                note,
+            });
+        }
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        Self { changes }
+    }
+
+    /// The version bump the changes require; `Patch` when the API is unchanged
+    pub fn impact(&self) -> SemverImpact {
+        self.changes
+            .iter()
+            .map(|change| change.impact)
+            .max()
+            .unwrap_or(SemverImpact::Patch)
+    }
+
+    pub fn is_breaking(&self) -> bool {
+        self.impact() == SemverImpact::Major
+    }
+}
+
+/// Why adding `path` breaks users of `old`, if it does
+fn breaking_addition(path: &str, item: &ApiItem, old: &PublicApi) -> Option<String> {
+    let parent = |separator: &str| {
+        path.rsplit_once(separator)
+            .and_then(|(parent, _)| old.items.get(parent).map(|item| (parent, item.kind)))
+    };
+    match item.kind {
+        ApiItemKind::Field => match parent(".") {
+            Some((name, ApiItemKind::Struct { exhaustive: true })) => {
+                Some(format!("`{}` literals must now set the new field", name))
+            }
+            _ => None,
+        },
+        ApiItemKind::Variant => match parent("::") {
+            Some((name, ApiItemKind::Enum { exhaustive: true })) => Some(format!(
+                "exhaustive matches on `{}` miss the new variant",
+                name
+            )),
+            _ => None,
+        },
+        ApiItemKind::TraitMethod { provided: false } => match parent("::") {
+            Some((name, ApiItemKind::Trait)) => {
+                Some(format!("implementors of `{}` must add the method", name))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_pub(vis: &syn::Visibility) -> bool {
+    matches!(vis, syn::Visibility::Public(_))
+}
+
+fn is_non_exhaustive(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("non_exhaustive"))
+}
+
+/// `Stack<T>` -> `Stack`
+fn type_name(ty: &syn::Type) -> String {
+    match ty {
+        syn::Type::Path(path) => match path.path.segments.last() {
+            Some(segment) => segment.ident.to_string(),
+            None => render(quote!(#ty)),
+        },
+        _ => render(quote!(#ty)),
+    }
+}
+
+/// Token text with the spacing `quote` adds around punctuation removed
+fn render(tokens: TokenStream) -> String {
+    let mut text = tokens.to_string();
+    for (from, to) in [
+        (" :: ", "::"),
+        (" ,", ","),
+        (" (", "("),
+        ("( ", "("),
+        (" )", ")"),
+        (" :", ":"),
+        ("& ", "&"),
+        (" <", "<"),
+        ("< ", "<"),
+        (" >", ">"),
+        ("[ ", "["),
+        (" ]", "]"),
+        (" ;", ";"),
+    ] {
+        text = text.replace(from, to);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = r#"
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub owner: String,
+    pub balance: f64,
+}
+
+impl Account {
+    pub fn new(owner: String) -> Self {
+        Self { owner, balance: 0.0 }
+    }
+
+    fn audit(&self) {}
+}
+
+pub enum Shape {
+    Circle(f64),
+}
+
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn helper() {}
+"#;
+
+    #[test]
+    fn test_extract_public_items() {
+        let api = PublicApi::extract(V1).unwrap();
+        let paths: Vec<&str> = api.items.keys().map(String::as_str).collect();
+        assert_eq!(
+            paths,
+            [
+                "Account",
+                "Account.balance",
+                "Account.owner",
+                "Account::new",
+                "Shape",
+                "Shape::Circle",
+                "add",
+                "impl Clone for Account",
+                "impl Debug for Account",
+            ]
+        );
+        assert_eq!(api.items["add"].signature, "fn add(a: i32, b: i32) -> i32");
+        assert_eq!(
+            api.items["Account"].kind,
+            ApiItemKind::Struct { exhaustive: true }
+        );
+    }
+
+    #[test]
+    fn test_identical_output_is_a_patch() {
+        let api = PublicApi::extract(V1).unwrap();
+        let diff = ApiDiff::between(&api, &api);
+        assert!(diff.changes.is_empty());
+        assert_eq!(diff.impact(), SemverImpact::Patch);
+    }
+
+    #[test]
+    fn test_classifies_changes() {
+        let v2 = V1
+            .replace(
+                "pub fn add(a: i32, b: i32) -> i32",
+                "pub fn add(a: i64, b: i64) -> i64",
+            )
+            .replace("    Circle(f64),\n", "    Circle(f64),\n    Square(f64),\n")
+            .replace("fn helper() {}", "pub fn helper() {}")
+            .replace("#[derive(Debug, Clone)]", "#[derive(Debug)]");
+        let diff = ApiDiff::between(
+            &PublicApi::extract(V1).unwrap(),
+            &PublicApi::extract(&v2).unwrap(),
+        );
+
+        let changes: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|c| (c.path.as_str(), c.kind, c.impact))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("Shape::Square", ChangeKind::Added, SemverImpact::Major),
+                ("add", ChangeKind::Changed, SemverImpact::Major),
+                ("helper", ChangeKind::Added, SemverImpact::Minor),
+                (
+                    "impl Clone for Account",
+                    ChangeKind::Removed,
+                    SemverImpact::Major
+                ),
+            ]
+        );
+        assert!(diff.is_breaking());
+    }
+}
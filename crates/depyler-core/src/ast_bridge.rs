@@ -1,16 +1,26 @@
+use crate::decorators::DecoratorRegistry;
 use crate::hir::*;
+use crate::hir_spans::HirSpans;
 use anyhow::{bail, Result};
 use depyler_annotations::{AnnotationExtractor, AnnotationParser, TranspilationAnnotations};
 use rustpython_ast::{self as ast};
 
 mod converters;
+mod deferred_annotations;
+mod model_classes;
+mod normalize;
 mod properties;
+mod script_main;
 mod type_extraction;
 
 pub use converters::{ExprConverter, StmtConverter};
+pub use deferred_annotations::DeferredAnnotationResolver;
 pub use properties::FunctionAnalyzer;
 pub use type_extraction::TypeExtractor;
 
+use model_classes::ModelClassBuilder;
+use script_main::Script;
+
 /// Bridge between Python AST and Depyler HIR
 ///
 /// The `AstBridge` converts Python AST nodes to Depyler's High-level Intermediate Representation (HIR).
@@ -64,6 +74,8 @@ pub struct AstBridge {
     source_code: Option<String>,
     annotation_extractor: AnnotationExtractor,
     annotation_parser: AnnotationParser,
+    decorators: DecoratorRegistry,
+    script_main: bool,
 }
 
 impl Default for AstBridge {
@@ -88,6 +100,8 @@ impl AstBridge {
             source_code: None,
             annotation_extractor: AnnotationExtractor::new(),
             annotation_parser: AnnotationParser::new(),
+            decorators: DecoratorRegistry::standard(),
+            script_main: false,
         }
     }
 
@@ -111,6 +125,20 @@ impl AstBridge {
         self
     }
 
+    /// Replaces the [standard](DecoratorRegistry::standard) decorator handlers
+    pub fn with_decorators(mut self, decorators: DecoratorRegistry) -> Self {
+        self.decorators = decorators;
+        self
+    }
+
+    /// Keeps the statements a script runs at module level instead of
+    /// skipping them, as a generated `main()`, and an `init()` for those
+    /// outside of `if __name__ == "__main__":`
+    pub fn with_script_main(mut self) -> Self {
+        self.script_main = true;
+        self
+    }
+
     /// Converts a Python AST module to Depyler HIR
     ///
     /// This is the main entry point for AST to HIR conversion. It handles semantic analysis,
@@ -155,11 +183,29 @@ impl AstBridge {
     /// - Function signatures are invalid
     pub fn python_to_hir(&self, module: ast::Mod) -> Result<HirModule> {
         match module {
-            ast::Mod::Module(m) => self.convert_module(m),
+            ast::Mod::Module(m) => self.convert_module(normalize_module(m)),
             _ => bail!("Only module-level code is supported"),
         }
     }
 
+    /// Like [`python_to_hir`](Self::python_to_hir), also returning where in
+    /// the source each definition and statement is
+    ///
+    /// Positions are only meaningful when the bridge was given the source
+    /// with [`with_source`](Self::with_source).
+    pub fn python_to_hir_with_spans(&self, module: ast::Mod) -> Result<(HirModule, HirSpans)> {
+        let module = match module {
+            ast::Mod::Module(m) => normalize_module(m),
+            _ => bail!("Only module-level code is supported"),
+        };
+        // Spans follow the normalized statements, as the HIR does
+        let spans = HirSpans::collect(
+            &module.body,
+            self.source_code.as_deref().unwrap_or_default(),
+        );
+        Ok((self.convert_module(module)?, spans))
+    }
+
     fn convert_module(&self, module: ast::ModModule) -> Result<HirModule> {
         let mut functions = Vec::new();
         let mut imports = Vec::new();
@@ -167,8 +213,14 @@ impl AstBridge {
         let mut protocols = Vec::new();
         let mut classes = Vec::new();
         let mut constants = Vec::new();
+        let mut async_entry = None;
 
-        for stmt in module.body {
+        let (body, script) = if self.script_main {
+            Script::split(module.body)?
+        } else {
+            (module.body, Script::default())
+        };
+        for stmt in body {
             match stmt {
                 ast::Stmt::FunctionDef(f) => {
                     functions.push(self.convert_function(f, false)?);
@@ -177,7 +229,11 @@ impl AstBridge {
                     imports.extend(convert_import(i)?);
                 }
                 ast::Stmt::ImportFrom(i) => {
-                    imports.extend(convert_import_from(i)?);
+                    // `from __future__ import annotations` only defers evaluation at
+                    // runtime; forward references are resolved below either way
+                    if i.module.as_ref().map(|m| m.as_str()) != Some("__future__") {
+                        imports.extend(convert_import_from(i)?);
+                    }
                 }
                 ast::Stmt::AsyncFunctionDef(f) => {
                     functions.push(self.convert_async_function(f)?);
@@ -215,24 +271,45 @@ impl AstBridge {
                         }
                     }
                 }
-                _ => {
-                    // Skip other statements for now
+                other => {
+                    // Other statements are skipped, apart from `asyncio.run(main())`
+                    // which selects the async entry point
+                    if let Some(entry) = asyncio_run_entry(&other) {
+                        async_entry = Some(entry);
+                    }
                 }
             }
         }
 
+        if let Some(entry) = async_entry {
+            for func in functions.iter_mut() {
+                if func.name == entry && func.properties.is_async {
+                    func.properties.is_async_entry = true;
+                }
+            }
+        }
+
+        if self.script_main {
+            script.into_functions(&mut functions, &constants)?;
+        }
+
         // DEPYLER-0359: Propagate can_fail through function calls
         // If a function calls another function that can fail, mark it as can_fail too
-        propagate_can_fail_through_calls(&mut functions);
+        propagate_can_fail_through_calls(&mut functions, &classes);
 
-        Ok(HirModule {
+        let mut hir = HirModule {
             functions,
             imports,
             type_aliases,
             protocols,
             classes,
             constants,
-        })
+        };
+
+        // Forward references and `Self` can only be settled once every class is known
+        DeferredAnnotationResolver::from_module(&hir).resolve_module(&mut hir);
+
+        Ok(hir)
     }
 
     fn convert_function(&self, func: ast::StmtFunctionDef, is_async: bool) -> Result<HirFunction> {
@@ -248,7 +325,7 @@ impl AstBridge {
         let mut properties = FunctionAnalyzer::analyze(&filtered_body);
         properties.is_async = is_async;
 
-        Ok(HirFunction {
+        let mut function = HirFunction {
             name,
             params: params.into(),
             ret_type,
@@ -256,7 +333,10 @@ impl AstBridge {
             properties,
             annotations,
             docstring,
-        })
+        };
+        self.decorators
+            .apply_to_function(&func.decorator_list, &mut function)?;
+        Ok(function)
     }
 
     fn convert_async_function(&self, func: ast::StmtAsyncFunctionDef) -> Result<HirFunction> {
@@ -272,7 +352,7 @@ impl AstBridge {
         let mut properties = FunctionAnalyzer::analyze(&filtered_body);
         properties.is_async = true;
 
-        Ok(HirFunction {
+        let mut function = HirFunction {
             name,
             params: params.into(),
             ret_type,
@@ -280,7 +360,10 @@ impl AstBridge {
             properties,
             annotations,
             docstring,
-        })
+        };
+        self.decorators
+            .apply_to_function(&func.decorator_list, &mut function)?;
+        Ok(function)
     }
 
     fn extract_function_annotations(
@@ -314,6 +397,18 @@ impl AstBridge {
         TranspilationAnnotations::default()
     }
 
+    /// The `# @depyler:` comments above `class`
+    fn extract_class_annotations(&self, class: &ast::StmtClassDef) -> TranspilationAnnotations {
+        self.source_code
+            .as_deref()
+            .and_then(|source| {
+                self.annotation_extractor
+                    .extract_class_annotations(source, &class.name)
+            })
+            .and_then(|text| self.annotation_parser.parse_annotations(&text).ok())
+            .unwrap_or_default()
+    }
+
     fn extract_async_function_annotations(
         &self,
         func: &ast::StmtAsyncFunctionDef,
@@ -568,11 +663,14 @@ impl AstBridge {
         // Extract docstring if present
         let docstring = self.extract_class_docstring(&class.body);
 
-        // Check if it's a dataclass
-        let is_dataclass = class.decorator_list.iter().any(|d| {
-            matches!(d, ast::Expr::Name(n) if n.id.as_str() == "dataclass")
-                || matches!(d, ast::Expr::Attribute(a) if a.attr.as_str() == "dataclass")
-        });
+        // Check if it's a dataclass; attrs and pydantic models are lowered the same way
+        let model_kind = model_classes::detect_model_kind(class)?;
+        let is_dataclass = model_kind.is_some()
+            || class.decorator_list.iter().any(|d| {
+                matches!(d, ast::Expr::Name(n) if n.id.as_str() == "dataclass")
+                    || matches!(d, ast::Expr::Attribute(a) if a.attr.as_str() == "dataclass")
+            });
+        let mut model = model_kind.map(|kind| ModelClassBuilder::new(kind, class.name.as_str()));
 
         // Extract base classes (for now, just store the names)
         let base_classes = class
@@ -593,6 +691,11 @@ impl AstBridge {
         let mut init_method = None;
 
         for stmt in &class.body {
+            if let Some(model) = model.as_mut() {
+                if model.handle_stmt(stmt)? {
+                    continue;
+                }
+            }
             match stmt {
                 ast::Stmt::FunctionDef(method) => {
                     if method.name.as_str() == "__init__" {
@@ -639,6 +742,12 @@ impl AstBridge {
             }
         }
 
+        if let Some(model) = model {
+            let (model_fields, post_init) = model.finish();
+            fields.extend(model_fields);
+            methods.extend(post_init);
+        }
+
         // Infer instance fields from __init__ if no explicit instance fields are defined
         // Check if we have any instance fields (non-class-var fields)
         let has_instance_fields = fields.iter().any(|f| !f.is_class_var);
@@ -658,6 +767,8 @@ impl AstBridge {
             fields,
             is_dataclass,
             docstring,
+            model_kind,
+            annotations: self.extract_class_annotations(class),
         }))
     }
 
@@ -670,12 +781,12 @@ impl AstBridge {
 
         let name = method.name.to_string();
 
-        // Skip dunder methods except __init__, __iter__, __next__, __enter__, __exit__
+        // Skip dunder methods except __init__, __post_init__, __iter__, __next__, __enter__, __exit__
         if name.starts_with("__")
             && name.ends_with("__")
             && !matches!(
                 name.as_str(),
-                "__init__" | "__iter__" | "__next__" | "__enter__" | "__exit__"
+                "__init__" | "__post_init__" | "__iter__" | "__next__" | "__enter__" | "__exit__"
             )
         {
             return Ok(None);
@@ -684,62 +795,6 @@ impl AstBridge {
         // Extract docstring
         let docstring = self.extract_class_docstring(&method.body);
 
-        // Check decorators
-        let is_static = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "staticmethod"));
-        let is_classmethod = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "classmethod"));
-        let is_property = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "property"));
-
-        // Convert parameters (skip 'self' for regular methods, 'cls' for classmethods)
-        let mut params = smallvec![];
-        let skip_first = if is_static {
-            false
-        } else if is_classmethod {
-            // Skip 'cls' parameter for classmethods
-            method
-                .args
-                .args
-                .first()
-                .map(|arg| arg.def.arg.as_str() == "cls")
-                .unwrap_or(false)
-        } else {
-            // Skip 'self' parameter for instance methods
-            method
-                .args
-                .args
-                .first()
-                .map(|arg| arg.def.arg.as_str() == "self")
-                .unwrap_or(false)
-        };
-
-        let args_to_process = if skip_first {
-            &method.args.args[1..]
-        } else {
-            &method.args.args[..]
-        };
-
-        for arg in args_to_process {
-            let param_name = arg.def.arg.to_string();
-            let param_type = if let Some(ann) = &arg.def.annotation {
-                TypeExtractor::extract_type(ann)?
-            } else {
-                Type::Unknown
-            };
-            params.push(HirParam {
-                name: param_name,
-                ty: param_type,
-                default: None, // Note: Method defaults extraction requires AST alignment with convert_parameters()
-            });
-        }
-
         // Convert return type
         let ret_type = if let Some(ret) = &method.returns {
             TypeExtractor::extract_type(ret)?
@@ -762,17 +817,25 @@ impl AstBridge {
         };
         let body = convert_body(filtered_body)?;
 
-        Ok(Some(HirMethod {
+        let mut hir_method = HirMethod {
             name,
-            params,
+            params: smallvec![],
             ret_type,
             body,
-            is_static,
-            is_classmethod,
-            is_property,
+            is_static: false,
+            is_classmethod: false,
+            is_property: false,
             is_async,
             docstring,
-        }))
+        };
+        self.decorators
+            .apply_to_method(&method.decorator_list, &mut hir_method)?;
+        hir_method.params = convert_method_params(
+            &method.args,
+            hir_method.is_static,
+            hir_method.is_classmethod,
+        )?;
+        Ok(Some(hir_method))
     }
 
     fn convert_async_method(
@@ -805,62 +868,6 @@ impl AstBridge {
         // Extract docstring
         let docstring = self.extract_class_docstring(&method.body);
 
-        // Check decorators
-        let is_static = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "staticmethod"));
-        let is_classmethod = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "classmethod"));
-        let is_property = method
-            .decorator_list
-            .iter()
-            .any(|d| matches!(d, ast::Expr::Name(n) if n.id.as_str() == "property"));
-
-        // Convert parameters
-        let mut params = smallvec![];
-        let skip_first = if is_static {
-            false
-        } else if is_classmethod {
-            // Skip 'cls' parameter for classmethods
-            method
-                .args
-                .args
-                .first()
-                .map(|arg| arg.def.arg.as_str() == "cls")
-                .unwrap_or(false)
-        } else {
-            // Skip 'self' parameter for instance methods
-            method
-                .args
-                .args
-                .first()
-                .map(|arg| arg.def.arg.as_str() == "self")
-                .unwrap_or(false)
-        };
-
-        let args_to_process = if skip_first {
-            &method.args.args[1..]
-        } else {
-            &method.args.args[..]
-        };
-
-        for arg in args_to_process {
-            let param_name = arg.def.arg.to_string();
-            let param_type = if let Some(ann) = &arg.def.annotation {
-                TypeExtractor::extract_type(ann)?
-            } else {
-                Type::Unknown
-            };
-            params.push(HirParam {
-                name: param_name,
-                ty: param_type,
-                default: None, // Note: Method defaults extraction requires AST alignment with convert_parameters()
-            });
-        }
-
         // Convert return type
         let ret_type = if let Some(ret) = &method.returns {
             TypeExtractor::extract_type(ret)?
@@ -883,17 +890,25 @@ impl AstBridge {
         };
         let body = convert_body(filtered_body)?;
 
-        Ok(Some(HirMethod {
+        let mut hir_method = HirMethod {
             name,
-            params,
+            params: smallvec![],
             ret_type,
             body,
-            is_static,
-            is_classmethod,
-            is_property,
+            is_static: false,
+            is_classmethod: false,
+            is_property: false,
             is_async: true,
             docstring,
-        }))
+        };
+        self.decorators
+            .apply_to_method(&method.decorator_list, &mut hir_method)?;
+        hir_method.params = convert_method_params(
+            &method.args,
+            hir_method.is_static,
+            hir_method.is_classmethod,
+        )?;
+        Ok(Some(hir_method))
     }
 
     fn extract_class_docstring(&self, body: &[ast::Stmt]) -> Option<String> {
@@ -1108,6 +1123,14 @@ pub fn python_to_hir(module: ast::Mod) -> Result<HirModule> {
     AstBridge::new().python_to_hir(module)
 }
 
+/// `module` with its sugar rewritten into core forms; see [`normalize`]
+fn normalize_module(module: ast::ModModule) -> ast::ModModule {
+    ast::ModModule {
+        body: normalize::normalize_body(module.body),
+        ..module
+    }
+}
+
 /// DEPYLER-0359: Propagate can_fail property through function call chains
 ///
 /// This function performs a fixed-point iteration to propagate the `can_fail` property
@@ -1117,13 +1140,22 @@ pub fn python_to_hir(module: ast::Mod) -> Result<HirModule> {
 /// This is essential for correct Result type propagation in recursive functions.
 ///
 /// Complexity: O(n * m) where n = number of functions, m = max call depth
-fn propagate_can_fail_through_calls(functions: &mut [HirFunction]) {
+fn propagate_can_fail_through_calls(functions: &mut [HirFunction], classes: &[HirClass]) {
     // Build a map of function names to can_fail status for quick lookup
     let mut can_fail_map: std::collections::HashMap<String, bool> = functions
         .iter()
         .map(|f| (f.name.clone(), f.properties.can_fail))
         .collect();
 
+    // Constructors whose `__post_init__` raises return Result as well
+    for class in classes {
+        if let Some(post_init) = class.post_init() {
+            if !FunctionAnalyzer::raised_exception_types(&post_init.body).is_empty() {
+                can_fail_map.insert(class.name.clone(), true);
+            }
+        }
+    }
+
     // Fixed-point iteration: keep propagating until no changes occur
     let mut changed = true;
     let mut iterations = 0;
@@ -1162,6 +1194,12 @@ fn calls_failing_function(
     false
 }
 
+/// Check if statements call `name` directly
+pub(crate) fn calls_function(stmts: &[HirStmt], name: &str) -> bool {
+    let target = std::collections::HashMap::from([(name.to_string(), true)]);
+    calls_failing_function(stmts, &target)
+}
+
 /// Check if a statement calls a function that can fail
 fn stmt_calls_failing_function(
     stmt: &HirStmt,
@@ -1248,37 +1286,108 @@ fn expr_calls_failing_function(
     }
 }
 
-fn convert_parameters(args: &ast::Arguments) -> Result<Vec<HirParam>> {
-    use crate::ast_bridge::converters::ExprConverter;
-    let mut params = Vec::new();
-
-    // Calculate number of args without defaults
-    let num_args = args.args.len();
-    let defaults_vec: Vec<_> = args.defaults().collect();
-    let num_defaults = defaults_vec.len();
-    let first_default_idx = num_args.saturating_sub(num_defaults);
+/// Parameters of a method, without the `self` or `cls` its kind receives
+fn convert_method_params(
+    args: &ast::Arguments,
+    is_static: bool,
+    is_classmethod: bool,
+) -> Result<smallvec::SmallVec<[HirParam; 4]>> {
+    let receiver = if is_static {
+        None
+    } else if is_classmethod {
+        Some("cls")
+    } else {
+        Some("self")
+    };
+    let skip_first = match (receiver, args.args.first()) {
+        (Some(receiver), Some(arg)) => arg.def.arg.as_str() == receiver,
+        _ => false,
+    };
+    let args_to_process = if skip_first {
+        &args.args[1..]
+    } else {
+        &args.args[..]
+    };
 
-    for (i, arg) in args.args.iter().enumerate() {
-        let name = arg.def.arg.to_string();
-        let ty = if let Some(annotation) = &arg.def.annotation {
-            TypeExtractor::extract_type(annotation)?
+    let mut params = smallvec::SmallVec::new();
+    for arg in args_to_process {
+        let param_type = if let Some(ann) = &arg.def.annotation {
+            TypeExtractor::extract_type(ann)?
         } else {
             Type::Unknown
         };
+        params.push(HirParam {
+            name: arg.def.arg.to_string(),
+            ty: param_type,
+            default: None, // Note: Method defaults extraction requires AST alignment with convert_parameters()
+            kind: ParamKind::PositionalOrKeyword,
+        });
+    }
+    Ok(params)
+}
 
-        // Check if this parameter has a default value
-        let default = if i >= first_default_idx {
-            let default_idx = i - first_default_idx;
-            if let Some(default_expr) = defaults_vec.get(default_idx) {
-                Some(ExprConverter::convert((*default_expr).clone())?)
-            } else {
-                None
+/// Name of the coroutine function started by a module-level `asyncio.run(f())`,
+/// either directly or under `if __name__ == "__main__":`
+fn asyncio_run_entry(stmt: &ast::Stmt) -> Option<String> {
+    match stmt {
+        ast::Stmt::Expr(e) => {
+            let call = match e.value.as_ref() {
+                ast::Expr::Call(call) => call,
+                _ => return None,
+            };
+            let is_asyncio_run = matches!(call.func.as_ref(), ast::Expr::Attribute(attr)
+                if attr.attr.as_str() == "run"
+                    && matches!(attr.value.as_ref(), ast::Expr::Name(n) if n.id.as_str() == "asyncio"));
+            match call.args.as_slice() {
+                [ast::Expr::Call(inner)] if is_asyncio_run && inner.args.is_empty() => {
+                    match inner.func.as_ref() {
+                        ast::Expr::Name(n) => Some(n.id.to_string()),
+                        _ => None,
+                    }
+                }
+                _ => None,
             }
-        } else {
-            None
-        };
+        }
+        ast::Stmt::If(i) => {
+            let is_main_guard = matches!(i.test.as_ref(), ast::Expr::Compare(cmp)
+                if matches!(cmp.left.as_ref(), ast::Expr::Name(n) if n.id.as_str() == "__name__"));
+            if !is_main_guard {
+                return None;
+            }
+            i.body.iter().find_map(asyncio_run_entry)
+        }
+        _ => None,
+    }
+}
 
-        params.push(HirParam { name, ty, default });
+/// Parameters of a function, in signature order, each with the kind its
+/// position around `/` and `*` gives it
+fn convert_parameters(args: &ast::Arguments) -> Result<Vec<HirParam>> {
+    use crate::ast_bridge::converters::ExprConverter;
+    let kinded = [
+        (&args.posonlyargs, ParamKind::PositionalOnly),
+        (&args.args, ParamKind::PositionalOrKeyword),
+        (&args.kwonlyargs, ParamKind::KeywordOnly),
+    ]
+    .into_iter()
+    .flat_map(|(args, kind)| args.iter().map(move |arg| (arg, kind)));
+
+    let mut params = Vec::new();
+    for (arg, kind) in kinded {
+        let ty = match &arg.def.annotation {
+            Some(annotation) => TypeExtractor::extract_type(annotation)?,
+            None => Type::Unknown,
+        };
+        let default = match &arg.default {
+            Some(default) => Some(ExprConverter::convert((**default).clone())?),
+            None => None,
+        };
+        params.push(HirParam {
+            name: arg.def.arg.to_string(),
+            ty,
+            default,
+            kind,
+        });
     }
 
     Ok(params)
@@ -1298,8 +1407,29 @@ pub(crate) fn extract_assign_target(expr: &ast::Expr) -> Result<AssignTarget> {
         ast::Expr::Name(n) => Ok(AssignTarget::Symbol(n.id.to_string())),
         ast::Expr::Subscript(s) => {
             let base = Box::new(ExprConverter::convert(s.value.as_ref().clone())?);
-            let index = Box::new(ExprConverter::convert(s.slice.as_ref().clone())?);
-            Ok(AssignTarget::Index { base, index })
+            let index = match s.slice.as_ref() {
+                // `xs[a:b:c] = v` is `xs.__setitem__(slice(a, b, c), v)`
+                ast::Expr::Slice(slice) => {
+                    let bound = |bound: &Option<Box<ast::Expr>>| match bound {
+                        Some(e) => ExprConverter::convert(e.as_ref().clone()),
+                        None => Ok(HirExpr::Literal(Literal::None)),
+                    };
+                    HirExpr::Call {
+                        func: "slice".to_string(),
+                        args: vec![
+                            bound(&slice.lower)?,
+                            bound(&slice.upper)?,
+                            bound(&slice.step)?,
+                        ],
+                        kwargs: Vec::new(),
+                    }
+                }
+                _ => ExprConverter::convert(s.slice.as_ref().clone())?,
+            };
+            Ok(AssignTarget::Index {
+                base,
+                index: Box::new(index),
+            })
         }
         ast::Expr::Attribute(a) => {
             let value = Box::new(ExprConverter::convert(a.value.as_ref().clone())?);
@@ -1342,11 +1472,6 @@ pub(crate) fn convert_binop(op: &ast::Operator) -> Result<BinOp> {
     })
 }
 
-pub(crate) fn convert_aug_op(op: &ast::Operator) -> Result<BinOp> {
-    // Augmented assignment operators map to the same binary operators
-    convert_binop(op)
-}
-
 pub(crate) fn convert_unaryop(op: &ast::UnaryOp) -> Result<UnaryOp> {
     Ok(match op {
         ast::UnaryOp::Not => UnaryOp::Not,
@@ -1472,6 +1597,19 @@ mod tests {
         assert_eq!(func.ret_type, Type::Int);
     }
 
+    #[test]
+    fn test_asyncio_run_marks_async_entry() {
+        let source = "import asyncio\n\nasync def main():\n    pass\n\nasync def helper():\n    pass\n\nif __name__ == \"__main__\":\n    asyncio.run(main())\n";
+        let hir = parse_python_to_hir(source);
+
+        assert!(hir.functions[0].properties.is_async_entry);
+        assert!(!hir.functions[1].properties.is_async_entry);
+
+        let source = "def main():\n    pass\n\nasyncio.run(main())\n";
+        let hir = parse_python_to_hir(source);
+        assert!(!hir.functions[0].properties.is_async_entry);
+    }
+
     #[test]
     fn test_type_annotation_conversion() {
         let source = "def process(items: List[str]) -> Optional[int]:\n    return None";
@@ -1,6 +1,5 @@
 use super::{
-    convert_aug_op, convert_binop, convert_body, convert_cmpop, convert_unaryop,
-    extract_assign_target,
+    convert_binop, convert_body, convert_cmpop, convert_unaryop, extract_assign_target, normalize,
 };
 use crate::hir::*;
 use anyhow::{bail, Result};
@@ -29,19 +28,22 @@ impl StmtConverter {
         match stmt {
             ast::Stmt::Assign(a) => Self::convert_assign(a),
             ast::Stmt::AnnAssign(a) => Self::convert_ann_assign(a),
-            ast::Stmt::AugAssign(a) => Self::convert_aug_assign(a),
+            ast::Stmt::AugAssign(a) => Self::convert_assign(normalize::aug_assign(a)),
             ast::Stmt::Return(r) => Self::convert_return(r),
             ast::Stmt::If(i) => Self::convert_if(i),
             ast::Stmt::While(w) => Self::convert_while(w),
             ast::Stmt::For(f) => Self::convert_for(f),
+            ast::Stmt::AsyncFor(f) => Self::convert_async_for(f),
             ast::Stmt::Expr(e) => Self::convert_expr_stmt(e),
             ast::Stmt::Raise(r) => Self::convert_raise(r),
             ast::Stmt::Break(b) => Self::convert_break(b),
             ast::Stmt::Continue(c) => Self::convert_continue(c),
             ast::Stmt::With(w) => Self::convert_with(w),
+            ast::Stmt::AsyncWith(w) => Self::convert_async_with(w),
             ast::Stmt::Try(t) => Self::convert_try(t),
             ast::Stmt::Assert(a) => Self::convert_assert(a),
             ast::Stmt::Pass(_) => Self::convert_pass(),
+            ast::Stmt::Match(m) => Self::convert_match(m),
             _ => bail!("Statement type not yet supported"),
         }
     }
@@ -112,36 +114,34 @@ impl StmtConverter {
         Ok(HirStmt::For { target, iter, body })
     }
 
-    fn convert_expr_stmt(e: ast::StmtExpr) -> Result<HirStmt> {
-        let expr = super::convert_expr(*e.value)?;
-        Ok(HirStmt::Expr(expr))
-    }
-
-    fn convert_aug_assign(a: ast::StmtAugAssign) -> Result<HirStmt> {
-        let target = extract_assign_target(&a.target)?;
-        let op = convert_aug_op(&a.op)?;
-
-        // Convert the target to an expression for the left side of the binary op
-        let left = match &target {
-            AssignTarget::Symbol(s) => Box::new(HirExpr::Var(s.clone())),
-            AssignTarget::Attribute { value, attr } => Box::new(HirExpr::Attribute {
-                value: value.clone(),
-                attr: attr.clone(),
-            }),
-            AssignTarget::Index { base, index } => Box::new(HirExpr::Index {
-                base: base.clone(),
-                index: index.clone(),
-            }),
-            _ => bail!("Augmented assignment not supported for this target type"),
+    /// `async for x in s` iterates `aiter(s)`, which codegen drains as a stream
+    fn convert_async_for(f: ast::StmtAsyncFor) -> Result<HirStmt> {
+        let target = extract_assign_target(&f.target)?;
+        let iter = HirExpr::Call {
+            func: "aiter".to_string(),
+            args: vec![super::convert_expr(*f.iter)?],
+            kwargs: vec![],
         };
+        let body = convert_body(f.body)?;
+        Ok(HirStmt::For { target, iter, body })
+    }
 
-        let right = Box::new(super::convert_expr(*a.value)?);
-        let value = HirExpr::Binary { op, left, right };
-        Ok(HirStmt::Assign {
-            target,
-            value,
-            type_annotation: None,
-        })
+    fn convert_expr_stmt(e: ast::StmtExpr) -> Result<HirStmt> {
+        match *e.value {
+            // `yield from it` as a statement is `for item in it: yield item`;
+            // its value (the sub-generator's return value) is never used here
+            ast::Expr::YieldFrom(y) => {
+                let item = "__yield_from_item".to_string();
+                Ok(HirStmt::For {
+                    target: AssignTarget::Symbol(item.clone()),
+                    iter: super::convert_expr(*y.value)?,
+                    body: vec![HirStmt::Expr(HirExpr::Yield {
+                        value: Some(Box::new(HirExpr::Var(item))),
+                    })],
+                })
+            }
+            value => Ok(HirStmt::Expr(super::convert_expr(value)?)),
+        }
     }
 
     fn convert_raise(r: ast::StmtRaise) -> Result<HirStmt> {
@@ -163,12 +163,39 @@ impl StmtConverter {
     }
 
     fn convert_with(w: ast::StmtWith) -> Result<HirStmt> {
+        let (context, target, body) = Self::convert_with_items(w.items, w.body)?;
+        Ok(HirStmt::With {
+            context,
+            target,
+            body,
+        })
+    }
+
+    /// `async with m as x` enters `m.__aenter__()`, which codegen awaits
+    fn convert_async_with(w: ast::StmtAsyncWith) -> Result<HirStmt> {
+        let (manager, target, body) = Self::convert_with_items(w.items, w.body)?;
+        Ok(HirStmt::With {
+            context: HirExpr::MethodCall {
+                object: Box::new(manager),
+                method: "__aenter__".to_string(),
+                args: vec![],
+                kwargs: vec![],
+            },
+            target,
+            body,
+        })
+    }
+
+    fn convert_with_items(
+        items: Vec<ast::WithItem>,
+        body: Vec<ast::Stmt>,
+    ) -> Result<(HirExpr, Option<Symbol>, Vec<HirStmt>)> {
         // For now, only support single context manager
-        if w.items.len() != 1 {
+        if items.len() != 1 {
             bail!("Multiple context managers not yet supported");
         }
 
-        let item = &w.items[0];
+        let item = &items[0];
         let context = super::convert_expr(item.context_expr.clone())?;
 
         // Extract optional target variable
@@ -180,17 +207,12 @@ impl StmtConverter {
         });
 
         // Convert body
-        let body = w
-            .body
+        let body = body
             .into_iter()
             .map(super::convert_stmt)
             .collect::<Result<Vec<_>>>()?;
 
-        Ok(HirStmt::With {
-            context,
-            target,
-            body,
-        })
+        Ok((context, target, body))
     }
 
     fn convert_try(t: ast::StmtTry) -> Result<HirStmt> {
@@ -247,6 +269,94 @@ impl StmtConverter {
     fn convert_pass() -> Result<HirStmt> {
         Ok(HirStmt::Pass)
     }
+
+    fn convert_match(m: ast::StmtMatch) -> Result<HirStmt> {
+        let subject = super::convert_expr(*m.subject)?;
+        let cases = m
+            .cases
+            .into_iter()
+            .map(|case| {
+                Ok(MatchCase {
+                    pattern: Self::convert_pattern(case.pattern)?,
+                    guard: case.guard.map(|g| super::convert_expr(*g)).transpose()?,
+                    body: convert_body(case.body)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(HirStmt::Match { subject, cases })
+    }
+
+    fn convert_pattern(pattern: ast::Pattern) -> Result<HirPattern> {
+        match pattern {
+            ast::Pattern::MatchAs(p) => match (p.name, p.pattern) {
+                (None, None) => Ok(HirPattern::Wildcard),
+                (None, Some(_)) => bail!("Invalid as-pattern without a name"),
+                (Some(name), inner) => Ok(HirPattern::Capture {
+                    name: name.to_string(),
+                    pattern: inner
+                        .map(|inner| Self::convert_pattern(*inner).map(Box::new))
+                        .transpose()?,
+                }),
+            },
+            ast::Pattern::MatchValue(p) => match super::convert_expr(*p.value)? {
+                HirExpr::Literal(literal) => Ok(HirPattern::Literal(literal)),
+                HirExpr::Unary {
+                    op: UnaryOp::Neg,
+                    operand,
+                } => match *operand {
+                    HirExpr::Literal(Literal::Int(n)) => Ok(HirPattern::Literal(Literal::Int(-n))),
+                    HirExpr::Literal(Literal::Float(f)) => {
+                        Ok(HirPattern::Literal(Literal::Float(-f)))
+                    }
+                    _ => bail!("Unsupported value pattern"),
+                },
+                value @ HirExpr::Attribute { .. } => Ok(HirPattern::Value(value)),
+                _ => bail!("Unsupported value pattern"),
+            },
+            ast::Pattern::MatchSingleton(p) => match p.value {
+                ast::Constant::None => Ok(HirPattern::Literal(Literal::None)),
+                ast::Constant::Bool(b) => Ok(HirPattern::Literal(Literal::Bool(b))),
+                _ => bail!("Unsupported singleton pattern"),
+            },
+            ast::Pattern::MatchSequence(p) => Ok(HirPattern::Sequence(
+                p.patterns
+                    .into_iter()
+                    .map(Self::convert_pattern)
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            ast::Pattern::MatchClass(p) => {
+                let class = match *p.cls {
+                    ast::Expr::Name(n) => n.id.to_string(),
+                    ast::Expr::Attribute(a) => a.attr.to_string(),
+                    _ => bail!("Unsupported class pattern"),
+                };
+                let args = p
+                    .patterns
+                    .into_iter()
+                    .map(Self::convert_pattern)
+                    .collect::<Result<Vec<_>>>()?;
+                let kwargs = p
+                    .kwd_attrs
+                    .into_iter()
+                    .zip(p.kwd_patterns)
+                    .map(|(attr, pattern)| Ok((attr.to_string(), Self::convert_pattern(pattern)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(HirPattern::Class {
+                    class,
+                    args,
+                    kwargs,
+                })
+            }
+            ast::Pattern::MatchOr(p) => Ok(HirPattern::Or(
+                p.patterns
+                    .into_iter()
+                    .map(Self::convert_pattern)
+                    .collect::<Result<Vec<_>>>()?,
+            )),
+            ast::Pattern::MatchStar(_) => bail!("Star patterns not yet supported"),
+            ast::Pattern::MatchMapping(_) => bail!("Mapping patterns not yet supported"),
+        }
+    }
 }
 
 /// Expression converter to reduce complexity
@@ -263,6 +373,9 @@ impl StmtConverter {
 /// ```
 pub struct ExprConverter;
 
+/// The target, iterable and condition of a single-clause comprehension
+type ComprehensionClause = (Symbol, Box<HirExpr>, Option<Box<HirExpr>>);
+
 impl ExprConverter {
     pub fn convert(expr: ast::Expr) -> Result<HirExpr> {
         match expr {
@@ -326,6 +439,34 @@ impl ExprConverter {
         Ok(HirExpr::Unary { op, operand })
     }
 
+    /// `eval("60 * 60")` of a string holding a constant expression, as that
+    /// expression
+    ///
+    /// The string is parsed and kept only when [`ConstEvaluator`] can
+    /// evaluate it: literals, operators and pure builtins, with no names to
+    /// look up and nothing to run. Any other `eval` stays a call, which
+    /// `dynamic_code::check` rejects.
+    ///
+    /// [`ConstEvaluator`]: crate::const_eval::ConstEvaluator
+    fn convert_constant_eval(c: &ast::ExprCall) -> Option<HirExpr> {
+        use rustpython_parser::Parse;
+
+        let source = match (&*c.func, c.args.as_slice()) {
+            (ast::Expr::Name(n), [ast::Expr::Constant(arg)])
+                if n.id.as_str() == "eval" && c.keywords.is_empty() =>
+            {
+                match &arg.value {
+                    ast::Constant::Str(source) => source,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        let expr = Self::convert(ast::Expr::parse(source.trim(), "<eval>").ok()?).ok()?;
+        crate::const_eval::ConstEvaluator::new().eval(&expr)?;
+        Some(expr)
+    }
+
     fn convert_call(c: ast::ExprCall) -> Result<HirExpr> {
         // Special handling for sorted() with key parameter
         if let ast::Expr::Name(n) = &*c.func {
@@ -409,10 +550,26 @@ impl ExprConverter {
             }
         }
 
+        if let Some(expr) = Self::convert_constant_eval(&c) {
+            return Ok(expr);
+        }
+
+        // `asyncio.gather(*tasks)` awaits every task of an iterable, marked
+        // as `asyncio.gather(iter(tasks))` for the async lowering
+        let is_gather = matches!(&*c.func, ast::Expr::Attribute(attr)
+            if attr.attr.as_str() == "gather"
+                && matches!(&*attr.value, ast::Expr::Name(n) if n.id.as_str() == "asyncio"));
         let args = c
             .args
             .into_iter()
-            .map(Self::convert)
+            .map(|arg| match arg {
+                ast::Expr::Starred(starred) if is_gather => Ok(HirExpr::Call {
+                    func: "iter".to_string(),
+                    args: vec![Self::convert(*starred.value)?],
+                    kwargs: vec![],
+                }),
+                arg => Self::convert(arg),
+            })
             .collect::<Result<Vec<_>>>()?;
 
         // DEPYLER-0364: Extract keyword arguments from Python AST
@@ -551,13 +708,15 @@ impl ExprConverter {
     }
 
     fn convert_compare(c: ast::ExprCompare) -> Result<HirExpr> {
-        // Handle chained comparisons by desugaring them
-        // Example: 0 <= x <= 100 becomes (0 <= x) and (x <= 100)
-
         if c.ops.is_empty() || c.comparators.is_empty() {
             bail!("Compare expression must have at least one operator and comparator");
         }
 
+        // 0 <= x <= 100 is (0 <= x) and (x <= 100)
+        if let Some(conjunction) = normalize::split_comparison(&c) {
+            return Self::convert(conjunction);
+        }
+
         // Special handling for 'is None', 'is True', 'is False' patterns (single comparison only)
         if c.ops.len() == 1
             && c.comparators.len() == 1
@@ -606,163 +765,113 @@ impl ExprConverter {
             }
         }
 
-        // Build chain: a op1 b op2 c becomes (a op1 b) and (b op2 c)
-        let mut left_expr = *c.left;
-        let mut comparisons = Vec::new();
-
-        for (op, comparator) in c.ops.iter().zip(c.comparators.iter()) {
-            let op_hir = convert_cmpop(op)?;
-            let left_hir = Box::new(Self::convert(left_expr.clone())?);
-            let right_hir = Box::new(Self::convert(comparator.clone())?);
-
-            comparisons.push(HirExpr::Binary {
-                op: op_hir,
-                left: left_hir,
-                right: right_hir,
-            });
-
-            // For next iteration, the right side becomes the left side
-            left_expr = comparator.clone();
-        }
-
-        // If only one comparison, return it directly
-        if comparisons.len() == 1 {
-            return Ok(comparisons.into_iter().next().unwrap());
-        }
-
-        // Chain multiple comparisons with AND
-        let mut result = comparisons[0].clone();
-        for comparison in comparisons.iter().skip(1) {
-            result = HirExpr::Binary {
-                op: BinOp::And,
-                left: Box::new(result),
-                right: Box::new(comparison.clone()),
-            };
-        }
-
-        Ok(result)
+        Ok(HirExpr::Binary {
+            op: convert_cmpop(&c.ops[0])?,
+            left: Box::new(Self::convert(*c.left)?),
+            right: Box::new(Self::convert(c.comparators[0].clone())?),
+        })
     }
 
     fn convert_list_comp(lc: ast::ExprListComp) -> Result<HirExpr> {
-        // Convert only simple list comprehensions for now
-        if lc.generators.len() != 1 {
-            bail!("Nested list comprehensions not yet supported");
-        }
-
-        let generator = &lc.generators[0];
-
-        // Extract the target variable
-        let target = match &generator.target {
-            ast::Expr::Name(n) => n.id.to_string(),
-            _ => bail!("Complex comprehension targets not yet supported"),
-        };
-
-        // Convert the iterator expression
-        let iter = Box::new(Self::convert(generator.iter.clone())?);
-
-        // Convert the element expression
         let element = Box::new(Self::convert(*lc.elt)?);
-
-        // Convert the condition if present
-        let condition = if generator.ifs.is_empty() {
-            None
-        } else if generator.ifs.len() == 1 {
-            Some(Box::new(Self::convert(generator.ifs[0].clone())?))
-        } else {
-            bail!("Multiple conditions in list comprehension not yet supported");
-        };
-
-        Ok(HirExpr::ListComp {
-            element,
-            target,
-            iter,
-            condition,
+        let generators = Self::convert_comprehensions(lc.generators)?;
+        Ok(match Self::single_clause(generators) {
+            Ok((target, iter, condition)) => HirExpr::ListComp {
+                element,
+                target,
+                iter,
+                condition,
+            },
+            Err(generators) => Self::collect_generator("list", element, generators),
         })
     }
 
     fn convert_set_comp(sc: ast::ExprSetComp) -> Result<HirExpr> {
-        // Convert only simple set comprehensions for now
-        if sc.generators.len() != 1 {
-            bail!("Nested set comprehensions not yet supported");
-        }
-
-        let generator = &sc.generators[0];
-
-        // Extract the target variable
-        let target = match &generator.target {
-            ast::Expr::Name(n) => n.id.to_string(),
-            _ => bail!("Complex comprehension targets not yet supported"),
-        };
-
-        // Convert the iterator expression
-        let iter = Box::new(Self::convert(generator.iter.clone())?);
-
-        // Convert the element expression
         let element = Box::new(Self::convert(*sc.elt)?);
-
-        // Convert the condition if present
-        let condition = if generator.ifs.is_empty() {
-            None
-        } else if generator.ifs.len() == 1 {
-            Some(Box::new(Self::convert(generator.ifs[0].clone())?))
-        } else {
-            bail!("Multiple if conditions in set comprehensions not yet supported");
-        };
-
-        Ok(HirExpr::SetComp {
-            element,
-            target,
-            iter,
-            condition,
+        let generators = Self::convert_comprehensions(sc.generators)?;
+        Ok(match Self::single_clause(generators) {
+            Ok((target, iter, condition)) => HirExpr::SetComp {
+                element,
+                target,
+                iter,
+                condition,
+            },
+            Err(generators) => Self::collect_generator("set", element, generators),
         })
     }
 
     fn convert_dict_comp(dc: ast::ExprDictComp) -> Result<HirExpr> {
-        // Convert only simple dict comprehensions for now
-        if dc.generators.len() != 1 {
-            bail!("Nested dict comprehensions not yet supported");
-        }
-
-        let generator = &dc.generators[0];
-
-        // Extract the target variable
-        let target = match &generator.target {
-            ast::Expr::Name(n) => n.id.to_string(),
-            _ => bail!("Complex comprehension targets not yet supported"),
-        };
-
-        // Convert the iterator expression
-        let iter = Box::new(Self::convert(generator.iter.clone())?);
-
-        // Convert the key and value expressions
         let key = Box::new(Self::convert(*dc.key)?);
         let value = Box::new(Self::convert(*dc.value)?);
-
-        // Convert the condition if present
-        let condition = if generator.ifs.is_empty() {
-            None
-        } else if generator.ifs.len() == 1 {
-            Some(Box::new(Self::convert(generator.ifs[0].clone())?))
-        } else {
-            bail!("Multiple if conditions in dict comprehensions not yet supported");
-        };
-
-        Ok(HirExpr::DictComp {
-            key,
-            value,
-            target,
-            iter,
-            condition,
+        let generators = Self::convert_comprehensions(dc.generators)?;
+        Ok(match Self::single_clause(generators) {
+            Ok((target, iter, condition)) => HirExpr::DictComp {
+                key,
+                value,
+                target,
+                iter,
+                condition,
+            },
+            Err(generators) => {
+                let item = Box::new(HirExpr::Tuple(vec![*key, *value]));
+                Self::collect_generator("dict", item, generators)
+            }
         })
     }
 
+    /// The target, iterable and condition of a comprehension with one `for`
+    /// clause over a plain name and at most one `if`, or the clauses back
+    fn single_clause(
+        mut generators: Vec<HirComprehension>,
+    ) -> std::result::Result<ComprehensionClause, Vec<HirComprehension>> {
+        let is_single = matches!(
+            generators.as_slice(),
+            [gen] if !gen.target.starts_with('(') && gen.conditions.len() <= 1
+        );
+        if !is_single {
+            return Err(generators);
+        }
+        let gen = generators.remove(0);
+        let condition = gen.conditions.into_iter().next().map(Box::new);
+        Ok((gen.target, gen.iter, condition))
+    }
+
+    /// Any other comprehension, as the collection built from the equivalent
+    /// generator expression: `[e for x in xs for y in ys]` is
+    /// `list(e for x in xs for y in ys)`
+    fn collect_generator(
+        collection: &str,
+        element: Box<HirExpr>,
+        generators: Vec<HirComprehension>,
+    ) -> HirExpr {
+        HirExpr::Call {
+            func: collection.to_string(),
+            args: vec![HirExpr::GeneratorExp {
+                element,
+                generators,
+            }],
+            kwargs: Vec::new(),
+        }
+    }
+
     fn convert_generator_exp(ge: ast::ExprGeneratorExp) -> Result<HirExpr> {
         // Convert element expression
         let element = Box::new(Self::convert(*ge.elt)?);
+        let generators = Self::convert_comprehensions(ge.generators)?;
 
+        Ok(HirExpr::GeneratorExp {
+            element,
+            generators,
+        })
+    }
+
+    /// The `for ... in ... if ...` clauses of a comprehension, outermost first
+    fn convert_comprehensions(
+        generators: Vec<ast::Comprehension>,
+    ) -> Result<Vec<HirComprehension>> {
         // Convert all generators (support nested)
-        let mut generators = Vec::new();
-        for gen in ge.generators {
+        let mut clauses = Vec::new();
+        for gen in generators {
             // Extract target variable(s)
             let target = match &gen.target {
                 ast::Expr::Name(n) => n.id.to_string(),
@@ -799,17 +908,14 @@ impl ExprConverter {
                 .map(|if_expr| Self::convert(if_expr.clone()))
                 .collect::<Result<Vec<_>>>()?;
 
-            generators.push(crate::hir::HirComprehension {
+            clauses.push(HirComprehension {
                 target,
                 iter,
                 conditions,
             });
         }
 
-        Ok(HirExpr::GeneratorExp {
-            element,
-            generators,
-        })
+        Ok(clauses)
     }
 
     fn convert_lambda(l: ast::ExprLambda) -> Result<HirExpr> {
@@ -322,6 +322,42 @@ fn test_convert_list_comp_with_condition() {
     }
 }
 
+#[test]
+fn test_convert_nested_list_comp_collects_generator() {
+    let expr = parse_expr("[x + y for x in range(3) if x for y in range(x) if y > 1]");
+    let result = ExprConverter::convert(expr).unwrap();
+    match result {
+        HirExpr::Call { func, args, .. } => {
+            assert_eq!(func, "list");
+            match &args[..] {
+                [HirExpr::GeneratorExp { generators, .. }] => {
+                    assert_eq!(generators.len(), 2);
+                    assert_eq!(generators[1].target, "y");
+                    assert_eq!(generators[1].conditions.len(), 1);
+                }
+                _ => panic!("Expected a generator expression argument"),
+            }
+        }
+        _ => panic!("Expected list() call"),
+    }
+}
+
+#[test]
+fn test_convert_dict_comp_with_tuple_target() {
+    let expr = parse_expr("{k: v for k, v in pairs}");
+    let result = ExprConverter::convert(expr).unwrap();
+    match result {
+        HirExpr::Call { func, args, .. } => {
+            assert_eq!(func, "dict");
+            assert!(matches!(
+                &args[..],
+                [HirExpr::GeneratorExp { element, .. }] if matches!(**element, HirExpr::Tuple(_))
+            ));
+        }
+        _ => panic!("Expected dict() call"),
+    }
+}
+
 #[test]
 fn test_convert_lambda() {
     let expr = parse_expr("lambda x, y: x + y");
@@ -620,6 +656,61 @@ fn test_convert_with() {
     }
 }
 
+/// The first statement in the body of an `async def`
+fn parse_async_body_stmt(code: &str) -> ast::Stmt {
+    match parse_stmt(code) {
+        ast::Stmt::AsyncFunctionDef(f) => f.body.into_iter().next().unwrap(),
+        _ => panic!("Expected async function"),
+    }
+}
+
+#[test]
+fn test_convert_async_for_iterates_aiter() {
+    let stmt = parse_async_body_stmt("async def f(s):\n    async for x in s:\n        print(x)");
+    match StmtConverter::convert(stmt).unwrap() {
+        HirStmt::For { target, iter, body } => {
+            assert!(matches!(target, AssignTarget::Symbol(ref s) if s == "x"));
+            assert!(matches!(iter, HirExpr::Call { ref func, ref args, .. }
+                if func == "aiter" && args == &[HirExpr::Var("s".to_string())]));
+            assert_eq!(body.len(), 1);
+        }
+        _ => panic!("Expected for statement"),
+    }
+}
+
+#[test]
+fn test_convert_async_with_enters_aenter() {
+    let stmt =
+        parse_async_body_stmt("async def f(lock):\n    async with lock as held:\n        pass");
+    match StmtConverter::convert(stmt).unwrap() {
+        HirStmt::With {
+            context, target, ..
+        } => {
+            assert!(
+                matches!(context, HirExpr::MethodCall { ref object, ref method, .. }
+                if method == "__aenter__" && **object == HirExpr::Var("lock".to_string()))
+            );
+            assert_eq!(target, Some("held".to_string()));
+        }
+        _ => panic!("Expected with statement"),
+    }
+}
+
+#[test]
+fn test_convert_gather_unpacks_with_iter() {
+    let result = ExprConverter::convert(parse_expr("asyncio.gather(*tasks)")).unwrap();
+    match result {
+        HirExpr::MethodCall { method, args, .. } => {
+            assert_eq!(method, "gather");
+            assert!(matches!(args.as_slice(), [HirExpr::Call { func, args, .. }]
+                if func == "iter" && args == &[HirExpr::Var("tasks".to_string())]));
+        }
+        _ => panic!("Expected method call"),
+    }
+    // Unpacking into any other call is still unsupported
+    assert!(ExprConverter::convert(parse_expr("print(*tasks)")).is_err());
+}
+
 #[test]
 fn test_convert_ann_assign() {
     let stmt = parse_stmt("x: int = 42");
@@ -872,3 +963,43 @@ fn test_tuple_assignment_swap() {
         _ => panic!("Expected Assign statement"),
     }
 }
+
+#[test]
+fn test_match_statement_patterns() {
+    let code = "match point:\n    case (0, y):\n        pass\n    case Point(x=px) if px > 0:\n        pass\n    case -1 | None:\n        pass\n    case _:\n        pass\n";
+    let result = StmtConverter::convert(parse_stmt(code)).unwrap();
+
+    match result {
+        HirStmt::Match { subject, cases } => {
+            assert!(matches!(subject, HirExpr::Var(ref s) if s == "point"));
+            assert_eq!(cases.len(), 4);
+            assert_eq!(
+                cases[0].pattern,
+                HirPattern::Sequence(vec![
+                    HirPattern::Literal(Literal::Int(0)),
+                    HirPattern::Capture {
+                        name: "y".to_string(),
+                        pattern: None
+                    },
+                ])
+            );
+            match &cases[1].pattern {
+                HirPattern::Class { class, kwargs, .. } => {
+                    assert_eq!(class, "Point");
+                    assert_eq!(kwargs[0].0, "x");
+                }
+                _ => panic!("Expected class pattern"),
+            }
+            assert!(cases[1].guard.is_some());
+            assert_eq!(
+                cases[2].pattern,
+                HirPattern::Or(vec![
+                    HirPattern::Literal(Literal::Int(-1)),
+                    HirPattern::Literal(Literal::None),
+                ])
+            );
+            assert_eq!(cases[3].pattern, HirPattern::Wildcard);
+        }
+        _ => panic!("Expected Match statement"),
+    }
+}
@@ -0,0 +1,173 @@
+//! Deferred annotation resolution
+//!
+//! Annotations are extracted one definition at a time, so a forward
+//! reference such as `-> "Node"` or `typing.Self` cannot be checked against
+//! the rest of the module when it is first seen. Once the whole module has
+//! been converted, this pass resolves those names against the module's
+//! symbol table:
+//!
+//! - `Self` inside a class becomes the enclosing class
+//! - qualified references (`"models.Node"`) to a class of this module are
+//!   reduced to the bare class name
+//!
+//! With `from __future__ import annotations` every annotation is deferred
+//! at runtime; the AST still carries the expressions, so the same pass
+//! covers both spellings.
+
+use crate::hir::{HirClass, HirFunction, HirModule, Type};
+use std::collections::HashSet;
+
+/// Resolves forward references once all module-level names are known
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::ast_bridge::DeferredAnnotationResolver;
+/// use depyler_core::hir::Type;
+///
+/// let resolver = DeferredAnnotationResolver::with_symbols(["Node"]);
+/// assert_eq!(
+///     resolver.resolve_type(&Type::Custom("Self".to_string()), Some("Node")),
+///     Type::Custom("Node".to_string())
+/// );
+/// assert_eq!(
+///     resolver.resolve_type(&Type::Custom("tree.Node".to_string()), None),
+///     Type::Custom("Node".to_string())
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeferredAnnotationResolver {
+    symbols: HashSet<String>,
+}
+
+impl DeferredAnnotationResolver {
+    /// Collect the type names a module defines (classes, protocols, aliases)
+    pub fn from_module(module: &HirModule) -> Self {
+        let symbols = module
+            .classes
+            .iter()
+            .map(|c| c.name.clone())
+            .chain(module.protocols.iter().map(|p| p.name.clone()))
+            .chain(module.type_aliases.iter().map(|a| a.name.clone()))
+            .collect();
+        Self { symbols }
+    }
+
+    pub fn with_symbols<I, S>(symbols: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            symbols: symbols.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Resolve every signature and field annotation in `module`
+    ///
+    /// # Complexity
+    /// 3 (two loops + per-class delegation)
+    pub fn resolve_module(&self, module: &mut HirModule) {
+        for func in &mut module.functions {
+            self.resolve_function(func);
+        }
+        for class in &mut module.classes {
+            self.resolve_class(class);
+        }
+    }
+
+    fn resolve_function(&self, func: &mut HirFunction) {
+        for param in func.params.iter_mut() {
+            param.ty = self.resolve_type(&param.ty, None);
+        }
+        func.ret_type = self.resolve_type(&func.ret_type, None);
+    }
+
+    fn resolve_class(&self, class: &mut HirClass) {
+        let owner = Some(class.name.as_str());
+        for field in &mut class.fields {
+            field.field_type = self.resolve_type(&field.field_type, owner);
+        }
+        for method in &mut class.methods {
+            for param in method.params.iter_mut() {
+                param.ty = self.resolve_type(&param.ty, owner);
+            }
+            method.ret_type = self.resolve_type(&method.ret_type, owner);
+        }
+    }
+
+    /// Resolve the names in `ty`; `owner` is the class that `Self` refers to
+    ///
+    /// # Complexity
+    /// 9 (one arm per compound type, recursive)
+    pub fn resolve_type(&self, ty: &Type, owner: Option<&str>) -> Type {
+        let resolve = |inner: &Type| Box::new(self.resolve_type(inner, owner));
+        let resolve_all = |types: &[Type]| -> Vec<Type> {
+            types.iter().map(|t| self.resolve_type(t, owner)).collect()
+        };
+
+        match ty {
+            Type::Custom(name) => Type::Custom(self.resolve_name(name, owner)),
+            Type::List(inner) => Type::List(resolve(inner)),
+            Type::Set(inner) => Type::Set(resolve(inner)),
+            Type::Optional(inner) => Type::Optional(resolve(inner)),
+            Type::Final(inner) => Type::Final(resolve(inner)),
+            Type::Dict(key, value) => Type::Dict(resolve(key), resolve(value)),
+            Type::Tuple(items) => Type::Tuple(resolve_all(items)),
+            Type::Union(items) => Type::Union(resolve_all(items)),
+            Type::Generic { base, params } => Type::Generic {
+                base: self.resolve_name(base, owner),
+                params: resolve_all(params),
+            },
+            Type::Function { params, ret } => Type::Function {
+                params: resolve_all(params),
+                ret: resolve(ret),
+            },
+            Type::Array { element_type, size } => Type::Array {
+                element_type: resolve(element_type),
+                size: size.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    fn resolve_name(&self, name: &str, owner: Option<&str>) -> String {
+        match (name, owner) {
+            ("Self", Some(class)) => class.to_string(),
+            _ => match name.rsplit_once('.') {
+                Some((_, last)) if self.symbols.contains(last) => last.to_string(),
+                _ => name.to_string(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_outside_class_is_left_alone() {
+        let resolver = DeferredAnnotationResolver::default();
+        let ty = Type::Custom("Self".to_string());
+        assert_eq!(resolver.resolve_type(&ty, None), ty);
+    }
+
+    #[test]
+    fn test_nested_self_and_qualified_names() {
+        let resolver = DeferredAnnotationResolver::with_symbols(["Node"]);
+        let ty = Type::Optional(Box::new(Type::List(Box::new(Type::Custom(
+            "Self".to_string(),
+        )))));
+        assert_eq!(
+            resolver.resolve_type(&ty, Some("Tree")),
+            Type::Optional(Box::new(Type::List(Box::new(Type::Custom(
+                "Tree".to_string()
+            )))))
+        );
+
+        // Qualified names only collapse when this module defines the class
+        let unknown = Type::Custom("other.Thing".to_string());
+        assert_eq!(resolver.resolve_type(&unknown, None), unknown);
+    }
+}
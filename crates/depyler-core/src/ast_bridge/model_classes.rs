@@ -0,0 +1,767 @@
+//! attrs and pydantic model classes
+//!
+//! Both libraries generate `__init__` from annotated class attributes the way
+//! `@dataclass` does, so a recognised model class is lowered as a dataclass:
+//! annotated attributes become struct fields, defaults become constructor
+//! defaults, and validators are folded into a synthesized `__post_init__`
+//! that the generated `new()` runs before returning. Model structs also
+//! derive serde's `Serialize`/`Deserialize`.
+//!
+//! Supported subset (see `docs/model-classes.md`):
+//!
+//! - attrs: `@attr.s`, `@attr.attrs`, `@attr.define`, `@attrs.define`,
+//!   `@define`, `@frozen`, `@mutable`; fields declared with annotations,
+//!   optionally `= attr.ib(...)` / `= field(...)` with `default=` or
+//!   `factory=`; `@<field>.validator` methods and `__attrs_post_init__`
+//! - pydantic: `BaseModel` subclasses; `Field(...)` with a default,
+//!   `default_factory=` and the `gt`/`ge`/`lt`/`le`/`min_length`/`max_length`
+//!   constraints; `@validator` / `@field_validator` in "after" mode and
+//!   `@model_validator(mode="after")`
+//!
+//! Anything outside the subset is rejected with an error naming the class,
+//! the field or method, and the unsupported construct, instead of being
+//! silently dropped.
+
+use super::{convert_body, ExprConverter, TypeExtractor};
+use crate::hir::{
+    AssignTarget, BinOp, HirExpr, HirField, HirMethod, HirStmt, Literal, ModelKind, Type, UnaryOp,
+};
+use anyhow::{anyhow, bail, Result};
+use rustpython_ast::{self as ast};
+use smallvec::SmallVec;
+
+/// Class-level options that do not change the generated struct
+const ATTRS_OPTIONS: &[&str] = &[
+    "auto_attribs",
+    "frozen",
+    "slots",
+    "kw_only",
+    "eq",
+    "repr",
+    "weakref_slot",
+];
+const PYDANTIC_OPTIONS: &[&str] = &["frozen"];
+
+/// Field specifier arguments that only affect repr/eq/metadata
+const ATTRS_FIELD_METADATA: &[&str] = &["kw_only", "repr", "eq", "order", "hash", "metadata"];
+const PYDANTIC_FIELD_METADATA: &[&str] = &["description", "title", "examples"];
+
+/// Recognise an attrs / pydantic class and validate its class-level options
+pub(crate) fn detect_model_kind(class: &ast::StmtClassDef) -> Result<Option<ModelKind>> {
+    for decorator in &class.decorator_list {
+        let (target, keywords): (&ast::Expr, &[ast::Keyword]) = match decorator {
+            ast::Expr::Call(call) => (call.func.as_ref(), call.keywords.as_slice()),
+            other => (other, &[]),
+        };
+        if is_attrs_decorator(target) {
+            check_options("attrs", class, keywords, ATTRS_OPTIONS)?;
+            return Ok(Some(ModelKind::Attrs));
+        }
+    }
+
+    if class.bases.iter().any(is_base_model) {
+        check_options("pydantic", class, &class.keywords, PYDANTIC_OPTIONS)?;
+        return Ok(Some(ModelKind::Pydantic));
+    }
+
+    Ok(None)
+}
+
+fn is_attrs_decorator(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Name(n) => matches!(n.id.as_str(), "define" | "frozen" | "mutable"),
+        ast::Expr::Attribute(attr) => {
+            matches!(attr.value.as_ref(), ast::Expr::Name(n) if matches!(n.id.as_str(), "attr" | "attrs"))
+                && matches!(
+                    attr.attr.as_str(),
+                    "s" | "attrs" | "define" | "frozen" | "mutable"
+                )
+        }
+        _ => false,
+    }
+}
+
+fn is_base_model(expr: &ast::Expr) -> bool {
+    match expr {
+        ast::Expr::Name(n) => n.id.as_str() == "BaseModel",
+        ast::Expr::Attribute(attr) => {
+            attr.attr.as_str() == "BaseModel"
+                && matches!(attr.value.as_ref(), ast::Expr::Name(n) if n.id.as_str() == "pydantic")
+        }
+        _ => false,
+    }
+}
+
+fn check_options(
+    library: &str,
+    class: &ast::StmtClassDef,
+    keywords: &[ast::Keyword],
+    allowed: &[&str],
+) -> Result<()> {
+    for keyword in keywords {
+        match &keyword.arg {
+            Some(arg) if allowed.contains(&arg.as_str()) => {}
+            Some(arg) => bail!(
+                "{} class `{}`: option `{}` is not supported",
+                library,
+                class.name,
+                arg
+            ),
+            None => bail!(
+                "{} class `{}`: `**kwargs` options are not supported",
+                library,
+                class.name
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// What a validator decorator applies to
+enum ValidatorTarget {
+    Fields(Vec<String>),
+    Model,
+}
+
+/// Collects the fields and validation logic of one model class
+///
+/// Statements the builder does not consume (plain methods, `ClassVar`
+/// attributes) are left to the regular class conversion.
+pub(crate) struct ModelClassBuilder {
+    kind: ModelKind,
+    class_name: String,
+    fields: Vec<HirField>,
+    /// `Field(ge=...)`-style constraints, checked first
+    constraint_checks: Vec<HirStmt>,
+    /// Per-field validators, in definition order
+    validators: Vec<HirStmt>,
+    /// `__attrs_post_init__` / `@model_validator(mode="after")` bodies, checked last
+    model_checks: Vec<HirStmt>,
+}
+
+impl ModelClassBuilder {
+    pub(crate) fn new(kind: ModelKind, class_name: &str) -> Self {
+        Self {
+            kind,
+            class_name: class_name.to_string(),
+            fields: Vec::new(),
+            constraint_checks: Vec::new(),
+            validators: Vec::new(),
+            model_checks: Vec::new(),
+        }
+    }
+
+    /// Consume `stmt` if it declares a field or a validator
+    pub(crate) fn handle_stmt(&mut self, stmt: &ast::Stmt) -> Result<bool> {
+        match stmt {
+            ast::Stmt::AnnAssign(ann) => self.handle_field(ann),
+            ast::Stmt::Assign(assign) => self.handle_assign(assign),
+            ast::Stmt::FunctionDef(func) => self.handle_method(func),
+            _ => Ok(false),
+        }
+    }
+
+    /// The model's fields and the synthesized `__post_init__`, if any check exists
+    pub(crate) fn finish(self) -> (Vec<HirField>, Option<HirMethod>) {
+        let mut body = self.constraint_checks;
+        body.extend(self.validators);
+        body.extend(self.model_checks);
+
+        let post_init = (!body.is_empty()).then(|| HirMethod {
+            name: "__post_init__".to_string(),
+            params: SmallVec::new(),
+            ret_type: Type::None,
+            body,
+            is_static: false,
+            is_classmethod: false,
+            is_property: false,
+            is_async: false,
+            docstring: None,
+        });
+        (self.fields, post_init)
+    }
+
+    fn library(&self) -> &'static str {
+        match self.kind {
+            ModelKind::Attrs => "attrs",
+            ModelKind::Pydantic => "pydantic",
+        }
+    }
+
+    fn field_label(&self, field: &str) -> String {
+        format!("{} field `{}.{}`", self.library(), self.class_name, field)
+    }
+
+    fn method_label(&self, method: &str) -> String {
+        format!(
+            "{} validator `{}.{}`",
+            self.library(),
+            self.class_name,
+            method
+        )
+    }
+
+    fn handle_field(&mut self, ann: &ast::StmtAnnAssign) -> Result<bool> {
+        let name = match ann.target.as_ref() {
+            ast::Expr::Name(n) => n.id.to_string(),
+            _ => return Ok(false),
+        };
+        if is_class_var_annotation(&ann.annotation) {
+            return Ok(false);
+        }
+        if self.kind == ModelKind::Pydantic && name == "model_config" {
+            return Ok(true);
+        }
+
+        let field_type = TypeExtractor::extract_type(&ann.annotation)?;
+        let default_value = match ann.value.as_deref() {
+            None => None,
+            Some(ast::Expr::Call(call)) if self.is_field_specifier(&call.func) => {
+                self.field_specifier(&name, call)?
+            }
+            Some(value) => default_from(value)?,
+        };
+
+        self.fields.push(HirField {
+            name,
+            field_type,
+            default_value,
+            is_class_var: false,
+        });
+        Ok(true)
+    }
+
+    fn handle_assign(&mut self, assign: &ast::StmtAssign) -> Result<bool> {
+        let name = match assign.targets.as_slice() {
+            [ast::Expr::Name(n)] => n.id.to_string(),
+            _ => return Ok(false),
+        };
+        match (self.kind, assign.value.as_ref()) {
+            (ModelKind::Pydantic, _) if name == "model_config" => Ok(true),
+            (ModelKind::Attrs, ast::Expr::Call(call)) if self.is_field_specifier(&call.func) => {
+                bail!("{} needs a type annotation", self.field_label(&name))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn is_field_specifier(&self, func: &ast::Expr) -> bool {
+        match (self.kind, func) {
+            (ModelKind::Attrs, ast::Expr::Name(n)) => {
+                matches!(n.id.as_str(), "field" | "ib" | "attrib")
+            }
+            (ModelKind::Attrs, ast::Expr::Attribute(attr)) => {
+                matches!(attr.value.as_ref(), ast::Expr::Name(n) if matches!(n.id.as_str(), "attr" | "attrs"))
+                    && matches!(attr.attr.as_str(), "field" | "ib" | "attrib")
+            }
+            (ModelKind::Pydantic, ast::Expr::Name(n)) => n.id.as_str() == "Field",
+            (ModelKind::Pydantic, ast::Expr::Attribute(attr)) => {
+                attr.attr.as_str() == "Field"
+                    && matches!(attr.value.as_ref(), ast::Expr::Name(n) if n.id.as_str() == "pydantic")
+            }
+            _ => false,
+        }
+    }
+
+    /// Default value of `attr.ib(...)` / `Field(...)`; constraints become checks
+    fn field_specifier(&mut self, field: &str, call: &ast::ExprCall) -> Result<Option<HirExpr>> {
+        let mut default = match (self.kind, call.args.as_slice()) {
+            (_, []) => None,
+            (ModelKind::Pydantic, [value]) => default_from(value)?,
+            _ => bail!(
+                "{}: positional arguments to the field specifier are not supported",
+                self.field_label(field)
+            ),
+        };
+
+        for keyword in &call.keywords {
+            let arg = match &keyword.arg {
+                Some(arg) => arg.as_str(),
+                None => bail!(
+                    "{}: `**kwargs` in the field specifier is not supported",
+                    self.field_label(field)
+                ),
+            };
+            match (self.kind, arg) {
+                (_, "default") => default = default_from(&keyword.value)?,
+                (ModelKind::Attrs, "factory") | (ModelKind::Pydantic, "default_factory") => {
+                    default = Some(self.factory_default(field, &keyword.value)?)
+                }
+                (ModelKind::Pydantic, "gt" | "ge" | "lt" | "le") => {
+                    let check = self.bound_check(field, arg, &keyword.value)?;
+                    self.constraint_checks.push(check);
+                }
+                (ModelKind::Pydantic, "min_length" | "max_length") => {
+                    let check = self.length_check(field, arg, &keyword.value)?;
+                    self.constraint_checks.push(check);
+                }
+                (ModelKind::Attrs, "validator") => bail!(
+                    "{}: `validator=` is not supported, use an `@{}.validator` method",
+                    self.field_label(field),
+                    field
+                ),
+                (ModelKind::Attrs, arg) if ATTRS_FIELD_METADATA.contains(&arg) => {}
+                (ModelKind::Pydantic, arg) if PYDANTIC_FIELD_METADATA.contains(&arg) => {}
+                _ => bail!("{}: `{}=` is not supported", self.field_label(field), arg),
+            }
+        }
+        Ok(default)
+    }
+
+    fn factory_default(&self, field: &str, factory: &ast::Expr) -> Result<HirExpr> {
+        match factory {
+            ast::Expr::Name(n) => Ok(match n.id.as_str() {
+                "list" => HirExpr::List(vec![]),
+                "dict" => HirExpr::Dict(vec![]),
+                "set" => HirExpr::Set(vec![]),
+                other => HirExpr::Call {
+                    func: other.to_string(),
+                    args: vec![],
+                    kwargs: vec![],
+                },
+            }),
+            _ => bail!(
+                "{}: the default factory must be a class or function name",
+                self.field_label(field)
+            ),
+        }
+    }
+
+    /// `Field(ge=0)` → `if self.x < 0: raise ValueError(...)`
+    fn bound_check(&self, field: &str, constraint: &str, bound: &ast::Expr) -> Result<HirStmt> {
+        let (bound, text) = numeric_literal(bound).ok_or_else(|| {
+            anyhow!(
+                "{}: `{}=` must be a number literal",
+                self.field_label(field),
+                constraint
+            )
+        })?;
+        let (violation, relation) = match constraint {
+            "gt" => (BinOp::LtEq, "greater than"),
+            "ge" => (BinOp::Lt, "greater than or equal to"),
+            "lt" => (BinOp::GtEq, "less than"),
+            _ => (BinOp::Gt, "less than or equal to"),
+        };
+        let condition = HirExpr::Binary {
+            op: violation,
+            left: Box::new(self_attribute(field)),
+            right: Box::new(bound),
+        };
+        Ok(raise_value_error_if(
+            condition,
+            format!("{}: Input should be {} {}", field, relation, text),
+        ))
+    }
+
+    /// `Field(min_length=1)` → `if len(self.x) < 1: raise ValueError(...)`
+    fn length_check(&self, field: &str, constraint: &str, bound: &ast::Expr) -> Result<HirStmt> {
+        let (bound, text) = match numeric_literal(bound) {
+            Some((bound @ HirExpr::Literal(Literal::Int(_)), text)) => (bound, text),
+            _ => bail!(
+                "{}: `{}=` must be an integer literal",
+                self.field_label(field),
+                constraint
+            ),
+        };
+        let (violation, relation) = match constraint {
+            "min_length" => (BinOp::Lt, "at least"),
+            _ => (BinOp::Gt, "at most"),
+        };
+        let condition = HirExpr::Binary {
+            op: violation,
+            left: Box::new(HirExpr::Call {
+                func: "len".to_string(),
+                args: vec![self_attribute(field)],
+                kwargs: vec![],
+            }),
+            right: Box::new(bound),
+        };
+        Ok(raise_value_error_if(
+            condition,
+            format!("{}: length should be {} {}", field, relation, text),
+        ))
+    }
+
+    fn handle_method(&mut self, func: &ast::StmtFunctionDef) -> Result<bool> {
+        if self.kind == ModelKind::Attrs && func.name.as_str() == "__attrs_post_init__" {
+            let body = self.checked_body(func, 0)?;
+            self.model_checks.extend(body);
+            return Ok(true);
+        }
+
+        for decorator in &func.decorator_list {
+            match self.validator_target(decorator)? {
+                Some(ValidatorTarget::Fields(fields)) => {
+                    self.add_field_validator(func, &fields)?;
+                    return Ok(true);
+                }
+                Some(ValidatorTarget::Model) => {
+                    let mut body = self.method_body(func, 0)?;
+                    if matches!(body.last(), Some(HirStmt::Return(Some(HirExpr::Var(v)))) if v == "self")
+                    {
+                        body.pop();
+                    }
+                    self.reject_returns(func, &body)?;
+                    self.model_checks.extend(body);
+                    return Ok(true);
+                }
+                None => {}
+            }
+        }
+        Ok(false)
+    }
+
+    fn validator_target(&self, decorator: &ast::Expr) -> Result<Option<ValidatorTarget>> {
+        match self.kind {
+            ModelKind::Attrs => self.attrs_validator_target(decorator),
+            ModelKind::Pydantic => self.pydantic_validator_target(decorator),
+        }
+    }
+
+    /// `@x.validator`; `@x.default` is rejected
+    fn attrs_validator_target(&self, decorator: &ast::Expr) -> Result<Option<ValidatorTarget>> {
+        let (field, hook) = match decorator {
+            ast::Expr::Attribute(attr) => match attr.value.as_ref() {
+                ast::Expr::Name(n) if self.fields.iter().any(|f| f.name == n.id.as_str()) => {
+                    (n.id.to_string(), attr.attr.as_str())
+                }
+                _ => return Ok(None),
+            },
+            _ => return Ok(None),
+        };
+        match hook {
+            "validator" => Ok(Some(ValidatorTarget::Fields(vec![field]))),
+            "default" => bail!(
+                "{}: `@{}.default` is not supported, use `default=` or `factory=`",
+                self.field_label(&field),
+                field
+            ),
+            _ => Ok(None),
+        }
+    }
+
+    fn pydantic_validator_target(&self, decorator: &ast::Expr) -> Result<Option<ValidatorTarget>> {
+        let (name, call) = match decorator {
+            ast::Expr::Call(call) => (callable_name(&call.func), Some(call)),
+            other => (callable_name(other), None),
+        };
+        match (name, call) {
+            (Some("validator" | "field_validator"), Some(call)) => {
+                let mut fields = Vec::new();
+                for arg in &call.args {
+                    match arg {
+                        ast::Expr::Constant(c) => match &c.value {
+                            ast::Constant::Str(field) => fields.push(field.to_string()),
+                            _ => bail!("pydantic validator fields must be string literals"),
+                        },
+                        _ => bail!("pydantic validator fields must be string literals"),
+                    }
+                }
+                for keyword in &call.keywords {
+                    match keyword.arg.as_ref().map(|a| a.as_str()) {
+                        Some("mode") => check_after_mode(&keyword.value)?,
+                        Some("pre") if is_true(&keyword.value) => {
+                            bail!("pydantic `pre=True` validators are not supported")
+                        }
+                        Some("pre" | "always" | "allow_reuse" | "check_fields") => {}
+                        Some(other) => {
+                            bail!("pydantic validator option `{}` is not supported", other)
+                        }
+                        None => bail!("pydantic validator `**kwargs` are not supported"),
+                    }
+                }
+                Ok(Some(ValidatorTarget::Fields(fields)))
+            }
+            (Some("model_validator"), Some(call)) => {
+                match call.keywords.iter().find(|k| {
+                    k.arg.as_ref().map(|a| a.as_str()) == Some("mode")
+                }) {
+                    Some(mode) => check_after_mode(&mode.value)?,
+                    None => bail!("pydantic `@model_validator` needs `mode=\"after\"`"),
+                }
+                Ok(Some(ValidatorTarget::Model))
+            }
+            (Some("root_validator"), _) => bail!(
+                "pydantic `@root_validator` is not supported, use `@model_validator(mode=\"after\")`"
+            ),
+            _ => Ok(None),
+        }
+    }
+
+    /// Inline a per-field validator once for each field it applies to
+    ///
+    /// The value parameter is bound to the field, and the validated value
+    /// (pydantic's return value, or the attrs value unchanged) is written back.
+    fn add_field_validator(
+        &mut self,
+        func: &ast::StmtFunctionDef,
+        fields: &[String],
+    ) -> Result<()> {
+        let expected_params = match self.kind {
+            // (cls, value)
+            ModelKind::Pydantic => 1,
+            // (self, attribute, value)
+            ModelKind::Attrs => 2,
+        };
+        let value_param = match self.signature(func, expected_params)?.last() {
+            Some(param) => param.clone(),
+            None => bail!(
+                "{} takes no value argument",
+                self.method_label(func.name.as_str())
+            ),
+        };
+
+        let mut body = self.method_body(func, expected_params)?;
+        let result = match self.kind {
+            ModelKind::Pydantic => match body.pop() {
+                Some(HirStmt::Return(Some(value))) => value,
+                _ => bail!(
+                    "{} must end with `return <value>`",
+                    self.method_label(func.name.as_str())
+                ),
+            },
+            ModelKind::Attrs => HirExpr::Var(value_param.clone()),
+        };
+        self.reject_returns(func, &body)?;
+
+        for field in fields {
+            if !self.fields.iter().any(|f| &f.name == field) {
+                bail!(
+                    "{} refers to unknown field `{}`",
+                    self.method_label(func.name.as_str()),
+                    field
+                );
+            }
+            self.validators.push(HirStmt::Assign {
+                target: AssignTarget::Symbol(value_param.clone()),
+                value: self_attribute(field),
+                type_annotation: None,
+            });
+            self.validators.extend(body.iter().cloned());
+            self.validators.push(HirStmt::Assign {
+                target: AssignTarget::Attribute {
+                    value: Box::new(HirExpr::Var("self".to_string())),
+                    attr: field.clone(),
+                },
+                value: result.clone(),
+                type_annotation: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Names of the parameters after `self`/`cls`; exactly `expected` are allowed
+    fn signature(&self, func: &ast::StmtFunctionDef, expected: usize) -> Result<Vec<String>> {
+        let args = &func.args;
+        let params: Vec<String> = args
+            .args
+            .iter()
+            .skip(1)
+            .map(|a| a.def.arg.to_string())
+            .collect();
+        if params.len() != expected
+            || !args.posonlyargs.is_empty()
+            || !args.kwonlyargs.is_empty()
+            || args.vararg.is_some()
+            || args.kwarg.is_some()
+        {
+            let shape = match (self.kind, expected) {
+                (ModelKind::Pydantic, 1) => "(cls, value)",
+                (ModelKind::Attrs, 2) => "(self, attribute, value)",
+                _ => "(self)",
+            };
+            bail!(
+                "{}: only the `{}` signature is supported",
+                self.method_label(func.name.as_str()),
+                shape
+            );
+        }
+        Ok(params)
+    }
+
+    fn method_body(
+        &self,
+        func: &ast::StmtFunctionDef,
+        expected_params: usize,
+    ) -> Result<Vec<HirStmt>> {
+        self.signature(func, expected_params)?;
+        let body = match func.body.split_first() {
+            Some((ast::Stmt::Expr(expr), rest)) if is_docstring(&expr.value) => rest,
+            _ => &func.body[..],
+        };
+        convert_body(body.to_vec())
+    }
+
+    fn checked_body(
+        &self,
+        func: &ast::StmtFunctionDef,
+        expected_params: usize,
+    ) -> Result<Vec<HirStmt>> {
+        let body = self.method_body(func, expected_params)?;
+        self.reject_returns(func, &body)?;
+        Ok(body)
+    }
+
+    /// Folded bodies run inside `new()`, where an early `return` has no equivalent
+    fn reject_returns(&self, func: &ast::StmtFunctionDef, body: &[HirStmt]) -> Result<()> {
+        if contains_return(body) {
+            bail!(
+                "{}: early `return` is not supported",
+                self.method_label(func.name.as_str())
+            );
+        }
+        Ok(())
+    }
+}
+
+fn is_class_var_annotation(annotation: &ast::Expr) -> bool {
+    let base = match annotation {
+        ast::Expr::Subscript(s) => s.value.as_ref(),
+        other => other,
+    };
+    callable_name(base) == Some("ClassVar")
+}
+
+/// Last segment of `name` / `module.name`
+fn callable_name(expr: &ast::Expr) -> Option<&str> {
+    match expr {
+        ast::Expr::Name(n) => Some(n.id.as_str()),
+        ast::Expr::Attribute(attr) => Some(attr.attr.as_str()),
+        _ => None,
+    }
+}
+
+fn is_docstring(expr: &ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Constant(c) if matches!(c.value, ast::Constant::Str(_)))
+}
+
+fn is_true(expr: &ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Constant(c) if matches!(c.value, ast::Constant::Bool(true)))
+}
+
+fn check_after_mode(mode: &ast::Expr) -> Result<()> {
+    match mode {
+        ast::Expr::Constant(c) => match &c.value {
+            ast::Constant::Str(s) if s == "after" => Ok(()),
+            ast::Constant::Str(s) => bail!(
+                "pydantic validator mode `{}` is not supported, only \"after\"",
+                s
+            ),
+            _ => bail!("pydantic validator mode must be a string literal"),
+        },
+        _ => bail!("pydantic validator mode must be a string literal"),
+    }
+}
+
+/// A field default; pydantic's `...` marks a required field
+fn default_from(value: &ast::Expr) -> Result<Option<HirExpr>> {
+    match value {
+        ast::Expr::Constant(c) if matches!(c.value, ast::Constant::Ellipsis) => Ok(None),
+        other => Ok(Some(ExprConverter::convert(other.clone())?)),
+    }
+}
+
+/// A numeric literal (optionally negated) and its source text
+fn numeric_literal(expr: &ast::Expr) -> Option<(HirExpr, String)> {
+    let converted = ExprConverter::convert(expr.clone()).ok()?;
+    let text = match &converted {
+        HirExpr::Literal(Literal::Int(n)) => n.to_string(),
+        HirExpr::Literal(Literal::Float(f)) => f.to_string(),
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => match operand.as_ref() {
+            HirExpr::Literal(Literal::Int(n)) => format!("-{}", n),
+            HirExpr::Literal(Literal::Float(f)) => format!("-{}", f),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some((converted, text))
+}
+
+fn self_attribute(field: &str) -> HirExpr {
+    HirExpr::Attribute {
+        value: Box::new(HirExpr::Var("self".to_string())),
+        attr: field.to_string(),
+    }
+}
+
+fn raise_value_error_if(condition: HirExpr, message: String) -> HirStmt {
+    HirStmt::If {
+        condition,
+        then_body: vec![HirStmt::Raise {
+            exception: Some(HirExpr::Call {
+                func: "ValueError".to_string(),
+                args: vec![HirExpr::Literal(Literal::String(message))],
+                kwargs: vec![],
+            }),
+            cause: None,
+        }],
+        else_body: None,
+    }
+}
+
+fn contains_return(body: &[HirStmt]) -> bool {
+    body.iter().any(|stmt| match stmt {
+        HirStmt::Return(_) => true,
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => contains_return(then_body) || else_body.as_deref().is_some_and(contains_return),
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } | HirStmt::With { body, .. } => {
+            contains_return(body)
+        }
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => {
+            contains_return(body)
+                || handlers.iter().any(|h| contains_return(&h.body))
+                || orelse.as_deref().is_some_and(contains_return)
+                || finalbody.as_deref().is_some_and(contains_return)
+        }
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpython_parser::{parse, Mode};
+
+    fn class_def(source: &str) -> ast::StmtClassDef {
+        match parse(source, Mode::Module, "<test>").unwrap() {
+            ast::Mod::Module(module) => match module.body.into_iter().next() {
+                Some(ast::Stmt::ClassDef(class)) => class,
+                _ => panic!("expected a class"),
+            },
+            _ => panic!("expected a module"),
+        }
+    }
+
+    #[test]
+    fn test_detect_model_kind() {
+        let attrs = class_def("@attr.s(auto_attribs=True)\nclass A:\n    x: int\n");
+        assert_eq!(detect_model_kind(&attrs).unwrap(), Some(ModelKind::Attrs));
+
+        let pydantic = class_def("class P(BaseModel):\n    x: int\n");
+        assert_eq!(
+            detect_model_kind(&pydantic).unwrap(),
+            Some(ModelKind::Pydantic)
+        );
+
+        let plain = class_def("class C:\n    x: int\n");
+        assert_eq!(detect_model_kind(&plain).unwrap(), None);
+    }
+
+    #[test]
+    fn test_unsupported_option_names_class_and_option() {
+        let class = class_def("@define(on_setattr=None)\nclass A:\n    x: int\n");
+        let err = detect_model_kind(&class).unwrap_err().to_string();
+        assert_eq!(err, "attrs class `A`: option `on_setattr` is not supported");
+    }
+}
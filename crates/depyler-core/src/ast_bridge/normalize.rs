@@ -0,0 +1,267 @@
+//! Canonical forms of Python constructs, rewritten before lowering
+//!
+//! Several surface forms are sugar for a smaller set of constructs. The
+//! bridge rewrites them in the Python AST so that the converters, and every
+//! analysis of the HIR they produce, only meet the core forms:
+//!
+//! | Python                | Lowered as                          |
+//! |-----------------------|-------------------------------------|
+//! | `x += v`              | `x = x + v`                         |
+//! | `a < b <= c`          | `a < b and b <= c`                  |
+//! | `a = b = v`           | `a = v` then `b = copy.copy(a)`     |
+//! | `with x as a, y as b` | `with x as a:` around `with y as b` |
+//! | `setattr(o, "x", v)`  | `o.x = v`                           |
+//!
+//! Statements are rewritten over the whole module by [`normalize_body`] before
+//! conversion. Comparisons can appear in any expression, so the expression
+//! converter splits them with [`split_comparison`] as it meets them.
+
+use crate::dynamic_attributes::is_identifier;
+use rustpython_ast::{self as ast};
+
+/// Rewrites the statements of `body` and of every block nested in it
+pub fn normalize_body(body: Vec<ast::Stmt>) -> Vec<ast::Stmt> {
+    let mut normalized = Vec::with_capacity(body.len());
+    for stmt in body {
+        match stmt {
+            ast::Stmt::AugAssign(a) => normalized.push(ast::Stmt::Assign(aug_assign(a))),
+            ast::Stmt::Assign(a) if a.targets.len() > 1 => {
+                normalized.extend(split_assign(a).into_iter().map(ast::Stmt::Assign));
+            }
+            ast::Stmt::With(w) if w.items.len() > 1 => normalized.push(nest_with(w)),
+            ast::Stmt::Expr(e) => normalized.push(match setattr(&e) {
+                Some(assign) => ast::Stmt::Assign(assign),
+                None => ast::Stmt::Expr(e),
+            }),
+            mut stmt => {
+                for block in nested_blocks_mut(&mut stmt) {
+                    *block = normalize_body(std::mem::take(block));
+                }
+                normalized.push(stmt);
+            }
+        }
+    }
+    normalized
+}
+
+/// `x op= v` as `x = x op v`
+///
+/// The target is read back as it is written, so `a[i()] += 1` calls `i`
+/// twice; indices in augmented assignments are plain names in practice.
+pub fn aug_assign(a: ast::StmtAugAssign) -> ast::StmtAssign {
+    let value = ast::Expr::BinOp(ast::ExprBinOp {
+        range: a.range,
+        left: a.target.clone(),
+        op: a.op,
+        right: a.value,
+    });
+    ast::StmtAssign {
+        range: a.range,
+        targets: vec![*a.target],
+        value: Box::new(value),
+        type_comment: None,
+    }
+}
+
+/// `a = b = v` as `a = v; b = copy.copy(a)`
+///
+/// Later targets copy the first one rather than evaluating `v` again, so
+/// `a` is still there to read after `b` is bound, though a mutable value
+/// is no longer shared between them. Literals are repeated instead.
+fn split_assign(mut assign: ast::StmtAssign) -> Vec<ast::StmtAssign> {
+    let targets = std::mem::take(&mut assign.targets);
+    let copied = match *assign.value {
+        ast::Expr::Constant(_) => (*assign.value).clone(),
+        _ => shallow_copy(&assign, &targets[0]),
+    };
+
+    let mut value = Some(assign.value.clone());
+    targets
+        .into_iter()
+        .map(|target| ast::StmtAssign {
+            targets: vec![target],
+            value: value.take().unwrap_or_else(|| Box::new(copied.clone())),
+            ..assign.clone()
+        })
+        .collect()
+}
+
+/// `copy.copy(target)`, placed at `assign`
+fn shallow_copy(assign: &ast::StmtAssign, target: &ast::Expr) -> ast::Expr {
+    let range = assign.range;
+    ast::Expr::Call(ast::ExprCall {
+        range,
+        func: Box::new(ast::Expr::Attribute(ast::ExprAttribute {
+            range,
+            value: Box::new(ast::Expr::Name(ast::ExprName {
+                range,
+                id: ast::Identifier::new("copy"),
+                ctx: ast::ExprContext::Load,
+            })),
+            attr: ast::Identifier::new("copy"),
+            ctx: ast::ExprContext::Load,
+        })),
+        args: vec![target.clone()],
+        keywords: Vec::new(),
+    })
+}
+
+/// `with a, b:` as `with a:` around `with b:`
+fn nest_with(mut with: ast::StmtWith) -> ast::Stmt {
+    let items = std::mem::take(&mut with.items);
+    let body = normalize_body(std::mem::take(&mut with.body));
+    let mut nested = items.into_iter().rev().fold(body, |body, item| {
+        vec![ast::Stmt::With(ast::StmtWith {
+            items: vec![item],
+            body,
+            ..with.clone()
+        })]
+    });
+    nested.remove(0)
+}
+
+/// `setattr(o, "x", v)` as `o.x = v`, when the name is a literal identifier
+///
+/// Other names stay calls, for `dynamic_attributes` to report.
+fn setattr(e: &ast::StmtExpr) -> Option<ast::StmtAssign> {
+    let call = match &*e.value {
+        ast::Expr::Call(call) if call.keywords.is_empty() => call,
+        _ => return None,
+    };
+    match (&*call.func, call.args.as_slice()) {
+        (ast::Expr::Name(func), [object, ast::Expr::Constant(name), value])
+            if func.id.as_str() == "setattr" =>
+        {
+            let attr = match &name.value {
+                ast::Constant::Str(attr) if is_identifier(attr) => attr,
+                _ => return None,
+            };
+            Some(ast::StmtAssign {
+                range: e.range,
+                targets: vec![ast::Expr::Attribute(ast::ExprAttribute {
+                    range: call.range,
+                    value: Box::new(object.clone()),
+                    attr: ast::Identifier::new(attr.as_str()),
+                    ctx: ast::ExprContext::Store,
+                })],
+                value: Box::new(value.clone()),
+                type_comment: None,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// `a < b <= c` as `a < b and b <= c`, or `None` for a single comparison
+///
+/// Inner operands are evaluated once per comparison they take part in.
+pub fn split_comparison(c: &ast::ExprCompare) -> Option<ast::Expr> {
+    if c.ops.len() < 2 {
+        return None;
+    }
+    let lefts = std::iter::once(&*c.left).chain(&c.comparators);
+    let values = lefts
+        .zip(c.ops.iter().zip(&c.comparators))
+        .map(|(left, (op, right))| {
+            ast::Expr::Compare(ast::ExprCompare {
+                range: c.range,
+                left: Box::new(left.clone()),
+                ops: vec![*op],
+                comparators: vec![right.clone()],
+            })
+        })
+        .collect();
+    Some(ast::Expr::BoolOp(ast::ExprBoolOp {
+        range: c.range,
+        op: ast::BoolOp::And,
+        values,
+    }))
+}
+
+/// Every statement block directly inside `stmt`
+fn nested_blocks_mut(stmt: &mut ast::Stmt) -> Vec<&mut Vec<ast::Stmt>> {
+    match stmt {
+        ast::Stmt::FunctionDef(s) => vec![&mut s.body],
+        ast::Stmt::AsyncFunctionDef(s) => vec![&mut s.body],
+        ast::Stmt::ClassDef(s) => vec![&mut s.body],
+        ast::Stmt::If(s) => vec![&mut s.body, &mut s.orelse],
+        ast::Stmt::While(s) => vec![&mut s.body, &mut s.orelse],
+        ast::Stmt::For(s) => vec![&mut s.body, &mut s.orelse],
+        ast::Stmt::AsyncFor(s) => vec![&mut s.body, &mut s.orelse],
+        ast::Stmt::With(s) => vec![&mut s.body],
+        ast::Stmt::AsyncWith(s) => vec![&mut s.body],
+        ast::Stmt::Try(s) => {
+            let mut blocks = vec![&mut s.body, &mut s.orelse, &mut s.finalbody];
+            for h in &mut s.handlers {
+                let ast::ExceptHandler::ExceptHandler(h) = h;
+                blocks.push(&mut h.body);
+            }
+            blocks
+        }
+        ast::Stmt::Match(s) => s.cases.iter_mut().map(|c| &mut c.body).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustpython_parser::{parse, Mode};
+
+    fn normalize(source: &str) -> Vec<ast::Stmt> {
+        match parse(source, Mode::Module, "<test>").unwrap() {
+            ast::Mod::Module(m) => normalize_body(m.body),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_chained_assignment_copies_the_first_target() {
+        let body = normalize("a = b = f()\n");
+        assert_eq!(body.len(), 2);
+        match &body[1] {
+            ast::Stmt::Assign(assign) => {
+                assert!(matches!(&assign.targets[0], ast::Expr::Name(n) if n.id.as_str() == "b"));
+                match &*assign.value {
+                    ast::Expr::Call(call) => {
+                        assert!(
+                            matches!(&call.args[..], [ast::Expr::Name(n)] if n.id.as_str() == "a")
+                        );
+                    }
+                    other => panic!("unexpected {:?}", other),
+                }
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_setattr_with_a_literal_name_assigns_the_attribute() {
+        let body = normalize("setattr(p, \"x\", 1)\nsetattr(p, name, 1)\n");
+        match &body[0] {
+            ast::Stmt::Assign(assign) => {
+                assert!(
+                    matches!(&assign.targets[0], ast::Expr::Attribute(a) if a.attr.as_str() == "x")
+                );
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(matches!(&body[1], ast::Stmt::Expr(_)));
+    }
+
+    #[test]
+    fn test_with_items_nest_in_order() {
+        let body = normalize("def f():\n    with a() as x, b() as y:\n        pass\n");
+        let outer = match &body[0] {
+            ast::Stmt::FunctionDef(f) => &f.body[0],
+            other => panic!("unexpected {:?}", other),
+        };
+        match outer {
+            ast::Stmt::With(outer) => {
+                assert_eq!(outer.items.len(), 1);
+                assert!(matches!(&outer.body[0], ast::Stmt::With(inner) if inner.items.len() == 1));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
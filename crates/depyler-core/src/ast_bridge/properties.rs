@@ -1,4 +1,8 @@
-use crate::hir::{BinOp, FunctionProperties, HirExpr, HirStmt};
+use crate::hir::{
+    AssignTarget, BinOp, FunctionProperties, HirExpr, HirFunction, HirStmt, Literal, Type, UnaryOp,
+};
+use depyler_annotations::IntegerSemantics;
+use std::collections::HashSet;
 
 pub struct FunctionAnalyzer;
 
@@ -14,9 +18,165 @@ impl FunctionAnalyzer {
             error_types,
             is_async: false, // Set by AST bridge when needed
             is_generator: Self::check_is_generator(body),
+            is_context_manager: false, // Set by AST bridge from decorators
+            is_async_entry: false,     // Set by AST bridge from `asyncio.run(...)`
+            memoize: None,             // Set by AST bridge from decorators
+            mutated_params: None,      // Set by the type-hints pass
+            complexity: None,          // Set by the type-hints pass
         }
     }
 
+    /// Settles the `integer_semantics` of `func`, `module_default` unless it
+    /// is annotated, and marks it as raising `OverflowError` when that is
+    /// `checked` and it does `+`, `-`, `*` or `**` on values that may be ints
+    pub fn apply_integer_semantics(func: &mut HirFunction, module_default: IntegerSemantics) {
+        let semantics = *func
+            .annotations
+            .integer_semantics
+            .get_or_insert(module_default);
+        if semantics != IntegerSemantics::Checked {
+            return;
+        }
+        let non_int: HashSet<&str> = func
+            .params
+            .iter()
+            .filter(|param| !matches!(param.ty, Type::Int | Type::Unknown))
+            .map(|param| param.name.as_str())
+            .collect();
+        let properties = &mut func.properties;
+        if Self::stmts_have_int_arithmetic(&func.body, &non_int) {
+            properties.can_fail = true;
+            if !properties.error_types.iter().any(|e| e == "OverflowError") {
+                properties.error_types.push("OverflowError".to_string());
+            }
+        }
+    }
+
+    fn stmts_have_int_arithmetic(stmts: &[HirStmt], non_int: &HashSet<&str>) -> bool {
+        stmts.iter().any(|stmt| match stmt {
+            HirStmt::Expr(expr)
+            | HirStmt::Assign { value: expr, .. }
+            | HirStmt::Return(Some(expr)) => Self::expr_has_int_arithmetic(expr, non_int),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                Self::expr_has_int_arithmetic(condition, non_int)
+                    || Self::stmts_have_int_arithmetic(then_body, non_int)
+                    || else_body
+                        .as_ref()
+                        .is_some_and(|b| Self::stmts_have_int_arithmetic(b, non_int))
+            }
+            HirStmt::While {
+                condition: expr,
+                body,
+            }
+            | HirStmt::For {
+                iter: expr, body, ..
+            }
+            | HirStmt::With {
+                context: expr,
+                body,
+                ..
+            } => {
+                Self::expr_has_int_arithmetic(expr, non_int)
+                    || Self::stmts_have_int_arithmetic(body, non_int)
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                Self::stmts_have_int_arithmetic(body, non_int)
+                    || handlers
+                        .iter()
+                        .any(|h| Self::stmts_have_int_arithmetic(&h.body, non_int))
+                    || orelse
+                        .iter()
+                        .chain(finalbody)
+                        .any(|b| Self::stmts_have_int_arithmetic(b, non_int))
+            }
+            _ => false,
+        })
+    }
+
+    fn expr_has_int_arithmetic(expr: &HirExpr, non_int: &HashSet<&str>) -> bool {
+        match expr {
+            HirExpr::Binary {
+                op: BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Pow,
+                left,
+                right,
+            } if Self::may_be_int(left, non_int) && Self::may_be_int(right, non_int) => true,
+            HirExpr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } if !matches!(operand.as_ref(), HirExpr::Literal(_))
+                && Self::may_be_int(operand, non_int) =>
+            {
+                true
+            }
+            _ => expr
+                .children()
+                .into_iter()
+                .any(|child| Self::expr_has_int_arithmetic(child, non_int)),
+        }
+    }
+
+    fn may_be_int(expr: &HirExpr, non_int: &HashSet<&str>) -> bool {
+        match expr {
+            HirExpr::Literal(literal) => matches!(literal, Literal::Int(_)),
+            HirExpr::Var(name) => !non_int.contains(name.as_str()),
+            HirExpr::Binary {
+                op: BinOp::Add | BinOp::Sub | BinOp::Mul,
+                left,
+                right,
+            } => Self::may_be_int(left, non_int) && Self::may_be_int(right, non_int),
+            HirExpr::List(_)
+            | HirExpr::Tuple(_)
+            | HirExpr::Dict(_)
+            | HirExpr::Set(_)
+            | HirExpr::FString { .. } => false,
+            _ => true,
+        }
+    }
+
+    /// Exception types raised explicitly by `raise` in `body`, deduplicated
+    ///
+    /// Only statements that propagate to the caller are considered; raises
+    /// inside `try` blocks are assumed to be handled locally.
+    pub fn raised_exception_types(body: &[HirStmt]) -> Vec<String> {
+        let mut types = Vec::new();
+        for stmt in body {
+            match stmt {
+                HirStmt::Raise {
+                    exception: exception @ Some(_),
+                    ..
+                } => types.push(Self::extract_exception_type(exception)),
+                HirStmt::If {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    types.extend(Self::raised_exception_types(then_body));
+                    if let Some(else_body) = else_body {
+                        types.extend(Self::raised_exception_types(else_body));
+                    }
+                }
+                HirStmt::While { body, .. }
+                | HirStmt::For { body, .. }
+                | HirStmt::With { body, .. } => {
+                    types.extend(Self::raised_exception_types(body));
+                }
+                _ => {}
+            }
+        }
+        types.sort();
+        types.dedup();
+        types
+    }
+
     fn check_pure(body: &[HirStmt]) -> bool {
         // V1: Conservative - only if no calls to unknown functions
         for stmt in body {
@@ -156,6 +316,15 @@ impl FunctionAnalyzer {
                 let error_type = Self::extract_exception_type(exception);
                 (true, vec![error_type])
             }
+            HirStmt::Assign {
+                target: AssignTarget::Index { index, .. },
+                value,
+                ..
+            } if Self::is_extended_slice(index) => {
+                let (_, mut errors) = Self::expr_can_fail(value);
+                errors.push("ValueError".to_string());
+                (true, errors)
+            }
             HirStmt::Expr(expr) | HirStmt::Assign { value: expr, .. } => Self::expr_can_fail(expr),
             HirStmt::Return(Some(expr)) => Self::expr_can_fail(expr),
             HirStmt::If {
@@ -239,10 +408,64 @@ impl FunctionAnalyzer {
                 // TODO: Improve this to detect uncaught exceptions
                 (false, all_errors)
             }
+            // `with suppress(...)` fails with the errors it lets through
+            HirStmt::With { context, body, .. } => match Self::suppressed_types(context) {
+                Some(suppressed) => {
+                    let (body_fail, errors) = Self::check_can_fail(body);
+                    let escapes = !suppressed
+                        .iter()
+                        .any(|name| *name == "Exception" || *name == "BaseException")
+                        && errors
+                            .iter()
+                            .any(|error| !suppressed.contains(&error.as_str()));
+                    (body_fail && escapes, errors)
+                }
+                None => (false, Vec::new()),
+            },
             _ => (false, Vec::new()),
         }
     }
 
+    /// The exception names of a `suppress(...)` or `contextlib.suppress(...)`
+    /// context manager
+    pub(crate) fn suppressed_types(context: &HirExpr) -> Option<Vec<&str>> {
+        let args = match context {
+            HirExpr::Call { func, args, .. } if func == "suppress" => args,
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                ..
+            } if method == "suppress"
+                && matches!(object.as_ref(), HirExpr::Var(module) if module == "contextlib") =>
+            {
+                args
+            }
+            _ => return None,
+        };
+        Some(
+            args.iter()
+                .filter_map(|arg| match arg {
+                    HirExpr::Var(name) => Some(name.as_str()),
+                    HirExpr::Attribute { attr, .. } => Some(attr.as_str()),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// A `slice(start, stop, step)` assignment index with a step other than
+    /// 1, which raises `ValueError` unless the lengths match
+    fn is_extended_slice(index: &HirExpr) -> bool {
+        match index {
+            HirExpr::Call { func, args, .. } if func == "slice" && args.len() == 3 => !matches!(
+                args[2],
+                HirExpr::Literal(Literal::None) | HirExpr::Literal(Literal::Int(1))
+            ),
+            _ => false,
+        }
+    }
+
     fn expr_can_fail(expr: &HirExpr) -> (bool, Vec<String>) {
         match expr {
             HirExpr::Index { .. } => (true, vec!["IndexError".to_string()]),
@@ -274,6 +497,8 @@ impl FunctionAnalyzer {
                             Vec::new()
                         }
                     }
+                    // next(it) without a default raises once `it` is exhausted
+                    "next" if args.len() == 1 => vec!["StopIteration".to_string()],
                     _ => Vec::new(),
                 };
 
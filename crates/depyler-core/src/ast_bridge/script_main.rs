@@ -0,0 +1,319 @@
+//! Module-level statements of scripts, gathered into functions
+//!
+//! Python runs the statements of a module as it loads it. With
+//! [`AstBridge::with_script_main`](super::AstBridge::with_script_main) they
+//! are kept, in order, instead of skipped:
+//!
+//! - without an `if __name__ == "__main__":` block they become `fn main()`;
+//! - with one, the statements outside of it become `pub fn init()`, what
+//!   importing the module runs, and the block becomes `fn main()`, which
+//!   calls `init()` first;
+//! - a module defining `main()` itself keeps it as the entry point, started
+//!   by a module-level `main()` or `sys.exit(main())`, and has it call
+//!   `init()` first.
+//!
+//! A name bound once at module level is still a module constant. Constants
+//! computed with calls are named as a statement of their own where Python
+//! assigns them, which initializes them at that point rather than on first
+//! use. Names bound more than once are variables of the function their
+//! statements end up in.
+
+use super::{asyncio_run_entry, convert_body, convert_stmt, FunctionAnalyzer};
+use crate::dataflow;
+use crate::hir::{HirConstant, HirExpr, HirFunction, HirStmt, Type};
+use anyhow::{bail, Result};
+use depyler_annotations::TranspilationAnnotations;
+use rustpython_ast::{self as ast};
+use std::collections::HashMap;
+
+const INIT: &str = "init";
+
+enum ScriptStmt {
+    Run(ast::Stmt),
+    /// Where the module constant of that name is assigned
+    Assigned(String),
+}
+
+/// The statements of a module that run when it loads
+#[derive(Default)]
+pub(super) struct Script {
+    /// Outside of `if __name__ == "__main__":`
+    module: Vec<ScriptStmt>,
+    /// The bodies of `if __name__ == "__main__":`
+    entry: Vec<ast::Stmt>,
+    has_guard: bool,
+}
+
+impl Script {
+    /// Splits `body` into the statements converted as usual, definitions,
+    /// imports and constants, and the script
+    pub(super) fn split(body: Vec<ast::Stmt>) -> Result<(Vec<ast::Stmt>, Script)> {
+        let bindings = count_bindings(&body);
+        let bound_once = |target: &ast::Expr| match target {
+            ast::Expr::Name(name) => bindings.get(name.id.as_str()) == Some(&1),
+            _ => false,
+        };
+
+        let mut declarations = Vec::new();
+        let mut script = Script::default();
+        for (index, stmt) in body.into_iter().enumerate() {
+            let starts_async = asyncio_run_entry(&stmt).is_some();
+            let stmt = match stmt {
+                ast::Stmt::If(guard) if !starts_async && is_main_guard(&guard.test) => {
+                    script.has_guard = true;
+                    script.entry.extend(guard.body);
+                    script
+                        .module
+                        .extend(guard.orelse.into_iter().map(ScriptStmt::Run));
+                    continue;
+                }
+                stmt => stmt,
+            };
+            let assigned = match &stmt {
+                ast::Stmt::Expr(expr) if index == 0 && is_string(&expr.value) => {
+                    declarations.push(stmt);
+                    continue;
+                }
+                ast::Stmt::FunctionDef(_)
+                | ast::Stmt::AsyncFunctionDef(_)
+                | ast::Stmt::ClassDef(_)
+                | ast::Stmt::Import(_)
+                | ast::Stmt::ImportFrom(_) => None,
+                _ if starts_async => None,
+                ast::Stmt::Assign(assign)
+                    if assign.targets.len() == 1 && bound_once(&assign.targets[0]) =>
+                {
+                    Some(name_of(&assign.targets[0]))
+                }
+                ast::Stmt::AnnAssign(assign) if assign.value.is_none() => None,
+                ast::Stmt::AnnAssign(assign) if bound_once(&assign.target) => {
+                    Some(name_of(&assign.target))
+                }
+                _ => {
+                    if script.has_guard {
+                        bail!(
+                            "module-level statements after `if __name__ == \"__main__\":` \
+                             would run before it; move them above it"
+                        );
+                    }
+                    script.module.push(ScriptStmt::Run(stmt));
+                    continue;
+                }
+            };
+            script.module.extend(assigned.map(ScriptStmt::Assigned));
+            declarations.push(stmt);
+        }
+        Ok((declarations, script))
+    }
+
+    /// Adds the functions running the script to `functions`
+    pub(super) fn into_functions(
+        self,
+        functions: &mut Vec<HirFunction>,
+        constants: &[HirConstant],
+    ) -> Result<()> {
+        let effect_free = dataflow::effect_free_functions(functions);
+        let mut module = Vec::new();
+        for stmt in self.module {
+            match stmt {
+                ScriptStmt::Run(stmt) => module.push(convert_stmt(stmt)?),
+                // Constants without side effects can be computed whenever
+                ScriptStmt::Assigned(name) => {
+                    let computed = constants.iter().any(|constant| {
+                        constant.name == name
+                            && !dataflow::is_side_effect_free(&constant.value, &effect_free)
+                    });
+                    if computed {
+                        module.push(HirStmt::Expr(HirExpr::Var(name)));
+                    }
+                }
+            }
+        }
+        let mut entry = convert_body(self.entry)?;
+
+        match functions.iter().position(|f| f.name == "main") {
+            Some(main) => {
+                if let Some(start) = module.iter().position(starts_main) {
+                    if runs_anything(&module[start + 1..]) {
+                        bail!(
+                            "module-level statements after the call to `main()` would run \
+                             before it; move them above it"
+                        );
+                    }
+                    module.truncate(start);
+                }
+                entry.retain(|stmt| !starts_main(stmt));
+                if !entry.is_empty() {
+                    bail!(
+                        "`if __name__ == \"__main__\":` does more than call `main()`, which \
+                         the module defines itself; move its statements into `main()`"
+                    );
+                }
+                if runs_anything(&module) {
+                    functions[main].body.insert(0, call(INIT));
+                    add_init(functions, module)?;
+                }
+            }
+            None if self.has_guard => {
+                let mut body = Vec::new();
+                if runs_anything(&module) {
+                    body.push(call(INIT));
+                    add_init(functions, module)?;
+                }
+                body.extend(entry);
+                functions.push(script_function(
+                    "main",
+                    body,
+                    "The `if __name__ == \"__main__\":` block of the module",
+                ));
+            }
+            None if runs_anything(&module) => functions.push(script_function(
+                "main",
+                module,
+                "The module-level statements of the script",
+            )),
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+/// Whether `stmts` do more than initialize constants
+fn runs_anything(stmts: &[HirStmt]) -> bool {
+    stmts
+        .iter()
+        .any(|stmt| !matches!(stmt, HirStmt::Expr(HirExpr::Var(_))))
+}
+
+fn add_init(functions: &mut Vec<HirFunction>, body: Vec<HirStmt>) -> Result<()> {
+    if functions.iter().any(|f| f.name == INIT) {
+        bail!(
+            "the module defines `{}()`, which its module-level statements would become",
+            INIT
+        );
+    }
+    functions.push(script_function(
+        INIT,
+        body,
+        "The module-level statements, run when the module is imported",
+    ));
+    Ok(())
+}
+
+fn script_function(name: &str, body: Vec<HirStmt>, docstring: &str) -> HirFunction {
+    HirFunction {
+        name: name.to_string(),
+        params: Default::default(),
+        ret_type: Type::None,
+        properties: FunctionAnalyzer::analyze(&body),
+        body,
+        annotations: TranspilationAnnotations::default(),
+        docstring: Some(docstring.to_string()),
+    }
+}
+
+fn call(func: &str) -> HirStmt {
+    HirStmt::Expr(HirExpr::Call {
+        func: func.to_string(),
+        args: vec![],
+        kwargs: vec![],
+    })
+}
+
+/// `main()` or `sys.exit(main())`
+fn starts_main(stmt: &HirStmt) -> bool {
+    let is_main_call = |expr: &HirExpr| matches!(expr, HirExpr::Call { func, args, .. } if func == "main" && args.is_empty());
+    match stmt {
+        HirStmt::Expr(HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        }) => {
+            matches!(object.as_ref(), HirExpr::Var(module) if module == "sys")
+                && method == "exit"
+                && matches!(args.as_slice(), [arg] if is_main_call(arg))
+        }
+        HirStmt::Expr(expr) => is_main_call(expr),
+        _ => false,
+    }
+}
+
+/// How often each name is bound by the module-level statements, outside of
+/// `if __name__ == "__main__":`
+fn count_bindings(body: &[ast::Stmt]) -> HashMap<String, usize> {
+    let mut bindings = HashMap::new();
+    count_block(body, &mut bindings);
+    bindings
+}
+
+fn count_block(block: &[ast::Stmt], bindings: &mut HashMap<String, usize>) {
+    let bind = |bindings: &mut HashMap<String, usize>, target: &ast::Expr, times: usize| {
+        if let ast::Expr::Name(name) = target {
+            *bindings.entry(name.id.to_string()).or_insert(0) += times;
+        }
+    };
+    for stmt in block {
+        match stmt {
+            ast::Stmt::Assign(assign) => {
+                for target in &assign.targets {
+                    bind(bindings, target, 1);
+                }
+            }
+            ast::Stmt::AnnAssign(assign) if assign.value.is_some() => {
+                bind(bindings, &assign.target, 1)
+            }
+            // Rebinding what they read, these count as a second binding
+            ast::Stmt::AugAssign(assign) => bind(bindings, &assign.target, 2),
+            ast::Stmt::For(for_stmt) => {
+                bind(bindings, &for_stmt.target, 2);
+                count_block(&for_stmt.body, bindings);
+                count_block(&for_stmt.orelse, bindings);
+            }
+            ast::Stmt::If(if_stmt) if !is_main_guard(&if_stmt.test) => {
+                count_block(&if_stmt.body, bindings);
+                count_block(&if_stmt.orelse, bindings);
+            }
+            ast::Stmt::While(while_stmt) => {
+                count_block(&while_stmt.body, bindings);
+                count_block(&while_stmt.orelse, bindings);
+            }
+            ast::Stmt::With(with) => count_block(&with.body, bindings),
+            ast::Stmt::Try(try_stmt) => {
+                count_block(&try_stmt.body, bindings);
+                count_block(&try_stmt.orelse, bindings);
+                count_block(&try_stmt.finalbody, bindings);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn name_of(target: &ast::Expr) -> String {
+    match target {
+        ast::Expr::Name(name) => name.id.to_string(),
+        _ => unreachable!("only names are bound once"),
+    }
+}
+
+fn is_string(expr: &ast::Expr) -> bool {
+    matches!(expr, ast::Expr::Constant(constant) if matches!(constant.value, ast::Constant::Str(_)))
+}
+
+/// `__name__ == "__main__"`, either way round
+fn is_main_guard(test: &ast::Expr) -> bool {
+    let ast::Expr::Compare(compare) = test else {
+        return false;
+    };
+    let operands = match (compare.ops.as_slice(), compare.comparators.as_slice()) {
+        ([ast::CmpOp::Eq], [right]) => [compare.left.as_ref(), right],
+        _ => return false,
+    };
+    let is_name =
+        |expr: &ast::Expr| matches!(expr, ast::Expr::Name(name) if name.id.as_str() == "__name__");
+    let is_main = |expr: &ast::Expr| {
+        matches!(expr, ast::Expr::Constant(constant)
+            if matches!(&constant.value, ast::Constant::Str(s) if s == "__main__"))
+    };
+    (is_name(operands[0]) && is_main(operands[1])) || (is_main(operands[0]) && is_name(operands[1]))
+}
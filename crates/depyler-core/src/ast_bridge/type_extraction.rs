@@ -43,6 +43,15 @@ impl TypeExtractor {
             ast::Expr::Subscript(s) => Self::extract_generic_type(s),
             // Handle None constant (used in -> None return annotations)
             ast::Expr::Constant(c) if matches!(c.value, ast::Constant::None) => Ok(Type::None),
+            // Forward references: -> "Node", x: "Optional[Node]"
+            ast::Expr::Constant(c) => match &c.value {
+                ast::Constant::Str(text) => Self::extract_string_annotation(text),
+                _ => bail!("Unsupported type annotation"),
+            },
+            // Qualified typing names: typing.Self, typing_extensions.Self
+            ast::Expr::Attribute(a) if Self::is_typing_module(&a.value) => {
+                Self::extract_simple_type(a.attr.as_str())
+            }
             // DEPYLER-0273: Handle PEP 604 union syntax (int | None)
             ast::Expr::BinOp(b) if matches!(b.op, ast::Operator::BitOr) => {
                 Self::extract_union_from_binop(b)
@@ -96,11 +105,49 @@ impl TypeExtractor {
         })
     }
 
+    /// Parse a string annotation and extract the type it names
+    ///
+    /// The result may still mention classes defined later in the module;
+    /// those are settled by `DeferredAnnotationResolver` once the whole
+    /// module has been converted.
+    pub fn extract_string_annotation(text: &str) -> Result<Type> {
+        use rustpython_parser::Parse;
+
+        let expr = ast::Expr::parse(text.trim(), "<annotation>")
+            .map_err(|e| anyhow::anyhow!("Invalid string annotation {:?}: {}", text, e))?;
+
+        // "module.Class" names a class that may not be imported at runtime
+        if let ast::Expr::Attribute(a) = &expr {
+            if !Self::is_typing_module(&a.value) {
+                if let Some(name) = Self::dotted_name(&expr) {
+                    return Ok(Type::Custom(name));
+                }
+            }
+        }
+        Self::extract_type(&expr)
+    }
+
+    fn dotted_name(expr: &ast::Expr) -> Option<String> {
+        match expr {
+            ast::Expr::Name(n) => Some(n.id.to_string()),
+            ast::Expr::Attribute(a) => {
+                Self::dotted_name(&a.value).map(|base| format!("{}.{}", base, a.attr))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_typing_module(expr: &ast::Expr) -> bool {
+        matches!(expr, ast::Expr::Name(n) if matches!(n.id.as_str(), "typing" | "typing_extensions"))
+    }
+
     fn extract_generic_type(s: &ast::ExprSubscript) -> Result<Type> {
-        if let ast::Expr::Name(n) = s.value.as_ref() {
-            Self::extract_named_generic_type(n.id.as_str(), s)
-        } else {
-            bail!("Complex type annotations not yet supported")
+        match s.value.as_ref() {
+            ast::Expr::Name(n) => Self::extract_named_generic_type(n.id.as_str(), s),
+            ast::Expr::Attribute(a) if Self::is_typing_module(&a.value) => {
+                Self::extract_named_generic_type(a.attr.as_str(), s)
+            }
+            _ => bail!("Complex type annotations not yet supported"),
         }
     }
 
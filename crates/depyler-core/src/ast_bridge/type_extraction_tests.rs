@@ -333,3 +333,50 @@ fn test_pep604_union_two_non_none_types() {
     let ty = TypeExtractor::extract_type(&expr).unwrap();
     assert_eq!(ty, Type::Union(vec![Type::Int, Type::String]));
 }
+
+#[test]
+fn test_extract_string_annotation() {
+    let expr = Expr::parse("\"Node\"", "<test>").unwrap();
+    assert_eq!(
+        TypeExtractor::extract_type(&expr).unwrap(),
+        Type::Custom("Node".to_string())
+    );
+
+    let expr = Expr::parse("Optional[\"Node\"]", "<test>").unwrap();
+    assert_eq!(
+        TypeExtractor::extract_type(&expr).unwrap(),
+        Type::Optional(Box::new(Type::Custom("Node".to_string())))
+    );
+
+    let expr = Expr::parse("\"List[Node]\"", "<test>").unwrap();
+    assert_eq!(
+        TypeExtractor::extract_type(&expr).unwrap(),
+        Type::List(Box::new(Type::Custom("Node".to_string())))
+    );
+}
+
+#[test]
+fn test_extract_typing_qualified_names() {
+    let expr = Expr::parse("typing.Self", "<test>").unwrap();
+    assert_eq!(
+        TypeExtractor::extract_type(&expr).unwrap(),
+        Type::Custom("Self".to_string())
+    );
+
+    let expr = Expr::parse("typing.Optional[int]", "<test>").unwrap();
+    assert_eq!(
+        TypeExtractor::extract_type(&expr).unwrap(),
+        Type::Optional(Box::new(Type::Int))
+    );
+}
+
+#[test]
+fn test_error_on_invalid_string_annotation() {
+    let expr = Expr::parse("\"List[\"", "<test>").unwrap();
+    let result = TypeExtractor::extract_type(&expr);
+    assert!(result.is_err());
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Invalid string annotation"));
+}
@@ -0,0 +1,414 @@
+//! Function attributes synthesized from analysis
+//!
+//! Python has no way to say that a function is worth inlining or that its
+//! result must not be dropped, but the analysis the transpiler already does
+//! can tell. With [`AttributeOptions`] set, module functions get:
+//!
+//! - `#[inline]` when they are pure, loop-free and at most
+//!   `max_inline_nodes` HIR nodes long
+//! - `#[must_use]` when they return a value and have no side effects, so a
+//!   call that drops the value does nothing; functions returning `Result`
+//!   get a message, without which the attribute repeats what the type says
+//! - `#[doc = " complexity: N"]` when their cyclomatic complexity is above
+//!   `max_cyclomatic`, the limit the quality gates hold functions to
+//!
+//! A function the module itself calls as a statement, dropping the result,
+//! does not get `#[must_use]`: the generated call would warn.
+
+use crate::hir::{AssignTarget, BinOp, HirClass, HirExpr, HirFunction, HirStmt, Type};
+use crate::rust_gen::ExitCodeOptions;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Thresholds of attribute synthesis
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeOptions {
+    /// Largest pure function, in HIR statements and expressions, that gets
+    /// `#[inline]`
+    pub max_inline_nodes: usize,
+    /// Cyclomatic complexity above which a function is annotated with it;
+    /// the quality gates' `max_cyclomatic` by default
+    pub max_cyclomatic: u32,
+}
+
+impl Default for AttributeOptions {
+    fn default() -> Self {
+        Self {
+            max_inline_nodes: 16,
+            max_cyclomatic: 20,
+        }
+    }
+}
+
+/// Attributes chosen for one function
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionAttributes {
+    pub inline: bool,
+    pub must_use: bool,
+    /// The function returns `Result`, so `#[must_use]` carries a message
+    pub returns_result: bool,
+    /// Cyclomatic complexity, when above the limit
+    pub complexity: Option<u32>,
+}
+
+/// Attributes chosen for each module function
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::attribute_synthesis::{AttributeOptions, AttributeSynthesis};
+/// use depyler_core::DepylerPipeline;
+///
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir("def double(x: int) -> int:\n    return x * 2\n")
+///     .unwrap();
+/// let synthesis =
+///     AttributeSynthesis::analyze(&module.functions, &module.classes, &AttributeOptions::default());
+/// let attributes = synthesis.attributes("double");
+/// assert!(attributes.inline && attributes.must_use);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AttributeSynthesis {
+    by_name: HashMap<String, FunctionAttributes>,
+}
+
+impl AttributeSynthesis {
+    pub fn analyze(
+        functions: &[HirFunction],
+        classes: &[HirClass],
+        options: &AttributeOptions,
+    ) -> Self {
+        let impure = impure_functions(functions);
+        let dropped = dropped_results(functions, classes);
+        let by_name = functions
+            .iter()
+            .filter(|func| is_candidate(func))
+            .map(|func| {
+                let pure = !impure.contains(func.name.as_str());
+                let returns_result = func.properties.can_fail;
+                let returns_value =
+                    returns_result || !matches!(func.ret_type, Type::None | Type::Unknown);
+                let complexity = func
+                    .properties
+                    .complexity
+                    .unwrap_or_else(|| cyclomatic_complexity(&func.body));
+                let requested = |name: &str| {
+                    func.annotations
+                        .custom_attributes
+                        .iter()
+                        .any(|attr| attr.trim_start().starts_with(name))
+                };
+                let attributes = FunctionAttributes {
+                    inline: pure
+                        && !has_loop(&func.body)
+                        && node_count(&func.body) <= options.max_inline_nodes
+                        && !requested("inline"),
+                    must_use: pure
+                        && returns_value
+                        && !dropped.contains(func.name.as_str())
+                        && !requested("must_use"),
+                    returns_result,
+                    complexity: (complexity > options.max_cyclomatic).then_some(complexity),
+                };
+                (func.name.clone(), attributes)
+            })
+            .collect();
+        AttributeSynthesis { by_name }
+    }
+
+    /// Attributes of a module function; unknown names get none
+    pub fn attributes(&self, name: &str) -> FunctionAttributes {
+        self.by_name.get(name).cloned().unwrap_or_default()
+    }
+
+    /// The attributes to emit before the function
+    pub fn tokens(&self, name: &str) -> Vec<TokenStream> {
+        let attributes = self.attributes(name);
+        let mut tokens = Vec::new();
+        if let Some(complexity) = attributes.complexity {
+            let note = format!(" complexity: {}", complexity);
+            tokens.push(quote! { #[doc = #note] });
+        }
+        if attributes.inline {
+            tokens.push(quote! { #[inline] });
+        }
+        if attributes.must_use && attributes.returns_result {
+            let message = format!("`{}` has no effect beyond its result", name);
+            tokens.push(quote! { #[must_use = #message] });
+        } else if attributes.must_use {
+            tokens.push(quote! { #[must_use] });
+        }
+        tokens
+    }
+}
+
+/// Functions whose code is generated in a shape of its own, which these
+/// attributes do not fit
+fn is_candidate(func: &HirFunction) -> bool {
+    let properties = &func.properties;
+    func.name != "main"
+        && func.name != ExitCodeOptions::INNER_MAIN
+        && !properties.is_async
+        && !properties.is_generator
+        && !properties.is_context_manager
+        && properties.memoize.is_none()
+}
+
+/// Cyclomatic complexity of a function body: one, plus one for each `if`,
+/// loop and `and`/`or` in an expression statement
+///
+/// This is the measure `depyler-analyzer` reports and the quality gates
+/// check.
+pub fn cyclomatic_complexity(body: &[HirStmt]) -> u32 {
+    1 + body.iter().map(cyclomatic_stmt).sum::<u32>()
+}
+
+fn cyclomatic_stmt(stmt: &HirStmt) -> u32 {
+    match stmt {
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            1 + then_body.iter().map(cyclomatic_stmt).sum::<u32>()
+                + else_body.iter().flatten().map(cyclomatic_stmt).sum::<u32>()
+        }
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } => {
+            1 + body.iter().map(cyclomatic_stmt).sum::<u32>()
+        }
+        HirStmt::Expr(expr) => cyclomatic_expr(expr),
+        _ => 0,
+    }
+}
+
+fn cyclomatic_expr(expr: &HirExpr) -> u32 {
+    match expr {
+        HirExpr::Binary {
+            op: BinOp::And | BinOp::Or,
+            left,
+            right,
+        } => 1 + cyclomatic_expr(left) + cyclomatic_expr(right),
+        _ => 0,
+    }
+}
+
+/// Module functions with side effects, directly or through the module
+/// functions they call
+fn impure_functions(functions: &[HirFunction]) -> HashSet<&str> {
+    let mut impure: HashSet<&str> = functions
+        .iter()
+        .filter(|func| !func.properties.is_pure || has_side_effect_stmt(&func.body))
+        .map(|func| func.name.as_str())
+        .collect();
+    loop {
+        let before = impure.len();
+        for func in functions {
+            if !impure.contains(func.name.as_str())
+                && called_functions(&func.body)
+                    .iter()
+                    .any(|callee| impure.contains(callee))
+            {
+                impure.insert(&func.name);
+            }
+        }
+        if impure.len() == before {
+            return impure;
+        }
+    }
+}
+
+/// Method calls made for their effect, stores into subscripts and fields,
+/// and `with` blocks, which `FunctionProperties::is_pure` lets through
+fn has_side_effect_stmt(stmts: &[HirStmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        HirStmt::Expr(HirExpr::MethodCall { .. }) | HirStmt::With { .. } => true,
+        HirStmt::Assign { target, .. } => stores_through(target),
+        _ => stmt.nested_bodies().into_iter().any(has_side_effect_stmt),
+    })
+}
+
+fn stores_through(target: &AssignTarget) -> bool {
+    match target {
+        AssignTarget::Symbol(_) => false,
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => true,
+        AssignTarget::Tuple(targets) => targets.iter().any(stores_through),
+    }
+}
+
+/// Names of the functions `stmts` call
+fn called_functions(stmts: &[HirStmt]) -> Vec<&str> {
+    let mut calls = Vec::new();
+    for_each_stmt(stmts, &mut |stmt| {
+        for expr in stmt_exprs(stmt) {
+            collect_calls(expr, &mut calls);
+        }
+    });
+    calls
+}
+
+fn collect_calls<'a>(expr: &'a HirExpr, calls: &mut Vec<&'a str>) {
+    if let HirExpr::Call { func, .. } = expr {
+        calls.push(func);
+    }
+    for child in expr.children() {
+        collect_calls(child, calls);
+    }
+}
+
+/// Functions called as a statement somewhere in the module
+fn dropped_results<'a>(functions: &'a [HirFunction], classes: &'a [HirClass]) -> HashSet<&'a str> {
+    let mut dropped = HashSet::new();
+    let bodies = functions.iter().map(|func| &func.body).chain(
+        classes
+            .iter()
+            .flat_map(|class| class.methods.iter().map(|method| &method.body)),
+    );
+    for body in bodies {
+        for_each_stmt(body, &mut |stmt| {
+            if let HirStmt::Expr(HirExpr::Call { func, .. }) = stmt {
+                dropped.insert(func.as_str());
+            }
+        });
+    }
+    dropped
+}
+
+fn has_loop(stmts: &[HirStmt]) -> bool {
+    let mut found = false;
+    for_each_stmt(stmts, &mut |stmt| {
+        found |= matches!(stmt, HirStmt::While { .. } | HirStmt::For { .. });
+    });
+    found
+}
+
+/// Statements and expressions in `stmts`, nested ones included
+fn node_count(stmts: &[HirStmt]) -> usize {
+    fn expr_count(expr: &HirExpr) -> usize {
+        1 + expr.children().into_iter().map(expr_count).sum::<usize>()
+    }
+    let mut count = 0;
+    for_each_stmt(stmts, &mut |stmt| {
+        count += 1 + stmt_exprs(stmt).into_iter().map(expr_count).sum::<usize>();
+    });
+    count
+}
+
+fn for_each_stmt<'a>(stmts: &'a [HirStmt], visit: &mut impl FnMut(&'a HirStmt)) {
+    for stmt in stmts {
+        visit(stmt);
+        for body in stmt.nested_bodies() {
+            for_each_stmt(body, visit);
+        }
+    }
+}
+
+/// Expressions of `stmt` itself, not of the statements nested in it
+pub(crate) fn stmt_exprs(stmt: &HirStmt) -> Vec<&HirExpr> {
+    match stmt {
+        HirStmt::Assign { value, .. } => vec![value],
+        HirStmt::Expr(expr) => vec![expr],
+        HirStmt::Return(value) => value.iter().collect(),
+        HirStmt::Raise { exception, cause } => exception.iter().chain(cause).collect(),
+        HirStmt::Assert { test, msg } => std::iter::once(test).chain(msg).collect(),
+        HirStmt::If { condition, .. } | HirStmt::While { condition, .. } => vec![condition],
+        HirStmt::For { iter, .. } => vec![iter],
+        HirStmt::With { context, .. } => vec![context],
+        HirStmt::Match { subject, cases } => std::iter::once(subject)
+            .chain(cases.iter().filter_map(|case| case.guard.as_ref()))
+            .collect(),
+        HirStmt::Try { .. } | HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = "\
+def double(x: int) -> int:
+    return x * 2
+
+def log(x: int) -> int:
+    print(x)
+    return x
+
+def logged_double(x: int) -> int:
+    return log(x) * 2
+
+def push(xs: List[int]) -> int:
+    xs.append(1)
+    return len(xs)
+
+def total(xs: List[int]) -> int:
+    t = 0
+    for x in xs:
+        t += x
+    return t
+
+def check(x: int) -> None:
+    if x < 0:
+        raise ValueError(\"negative\")
+
+def caller() -> None:
+    total([1])
+";
+
+    fn synthesize() -> AttributeSynthesis {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        AttributeSynthesis::analyze(
+            &module.functions,
+            &module.classes,
+            &AttributeOptions::default(),
+        )
+    }
+
+    #[test]
+    fn test_pure_small_functions_are_inlined() {
+        let synthesis = synthesize();
+        assert!(synthesis.attributes("double").inline);
+        assert!(!synthesis.attributes("log").inline);
+        assert!(!synthesis.attributes("logged_double").inline);
+        assert!(!synthesis.attributes("push").inline);
+        assert!(!synthesis.attributes("total").inline);
+    }
+
+    #[test]
+    fn test_must_use_needs_a_value_without_side_effects() {
+        let synthesis = synthesize();
+        assert!(synthesis.attributes("double").must_use);
+        assert!(!synthesis.attributes("logged_double").must_use);
+        assert!(!synthesis.attributes("push").must_use);
+        // Called as a statement by `caller`
+        assert!(!synthesis.attributes("total").must_use);
+        assert!(!synthesis.attributes("caller").must_use);
+
+        let check = synthesis.attributes("check");
+        assert!(check.must_use && check.returns_result);
+        let tokens: Vec<String> = synthesis
+            .tokens("check")
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        assert!(tokens.iter().any(|t| t.contains("must_use =")));
+    }
+
+    #[test]
+    fn test_complexity_above_the_limit_is_noted() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let options = AttributeOptions {
+            max_cyclomatic: 1,
+            ..AttributeOptions::default()
+        };
+        let synthesis = AttributeSynthesis::analyze(&module.functions, &[], &options);
+        assert_eq!(synthesis.attributes("total").complexity, Some(2));
+        assert_eq!(synthesis.attributes("double").complexity, None);
+        let tokens = synthesis.tokens("total")[0].to_string();
+        assert_eq!(tokens, "# [doc = \" complexity: 2\"]");
+    }
+}
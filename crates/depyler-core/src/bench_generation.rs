@@ -0,0 +1,203 @@
+//! Criterion benchmark generation for transpiled functions
+//!
+//! Alongside the quickcheck tests of [`test_generation`](crate::test_generation),
+//! pure functions can get a `criterion` benchmark. The benchmarks live in
+//! their own `benches/` target, so they reach the functions through the
+//! library crate the transpiled module is built as.
+
+use crate::hir::{HirFunction, Type};
+use crate::rust_gen::keywords::safe_ident;
+use anyhow::Result;
+use quote::{format_ident, quote};
+
+/// Configuration for benchmark generation
+#[derive(Debug, Clone)]
+pub struct BenchGenConfig {
+    /// Value passed for `int` parameters
+    pub int_input: i64,
+    /// Number of elements in the lists passed for `list` parameters
+    pub collection_len: usize,
+}
+
+impl Default for BenchGenConfig {
+    fn default() -> Self {
+        Self {
+            int_input: 16,
+            collection_len: 64,
+        }
+    }
+}
+
+/// Benchmark generator for HIR functions
+pub struct BenchGenerator {
+    config: BenchGenConfig,
+}
+
+impl BenchGenerator {
+    pub fn new(config: BenchGenConfig) -> Self {
+        Self { config }
+    }
+
+    /// The benchmark of a single function, or `None` when it is not pure or
+    /// takes arguments no input can be made up for
+    pub fn generate_bench_for_function(
+        &self,
+        func: &HirFunction,
+    ) -> Option<proc_macro2::TokenStream> {
+        if !func.properties.is_pure
+            || func.properties.is_async
+            || func.properties.is_generator
+            || func.name == "main"
+            || matches!(func.ret_type, Type::None)
+        {
+            return None;
+        }
+
+        let mut inputs = Vec::new();
+        let mut args = Vec::new();
+        for param in &func.params {
+            let name = safe_ident(&param.name);
+            let (input, arg) = self.input_for(&name, &param.ty)?;
+            inputs.push(quote! { let #name = #input; });
+            args.push(arg);
+        }
+
+        let func_name = safe_ident(&func.name);
+        let bench_name = format_ident!("bench_{}", func.name);
+        let label = &func.name;
+        Some(quote! {
+            fn #bench_name(c: &mut Criterion) {
+                #(#inputs)*
+                c.bench_function(#label, |b| b.iter(|| #func_name(#(#args),*)));
+            }
+        })
+    }
+
+    /// A `benches/` file for the functions of the library crate `crate_name`,
+    /// or `None` when none of them can be benchmarked
+    pub fn generate_bench_file(
+        &self,
+        functions: &[HirFunction],
+        crate_name: &str,
+    ) -> Result<Option<String>> {
+        let benches: Vec<_> = functions
+            .iter()
+            .filter_map(|func| {
+                self.generate_bench_for_function(func)
+                    .map(|bench| (format_ident!("bench_{}", func.name), bench))
+            })
+            .collect();
+        if benches.is_empty() {
+            return Ok(None);
+        }
+
+        let crate_ident = syn::parse_str::<syn::Ident>(&crate_name.replace('-', "_"))?;
+        let (names, items): (Vec<_>, Vec<_>) = benches.into_iter().unzip();
+        let file = quote! {
+            use criterion::{criterion_group, criterion_main, Criterion};
+            use std::hint::black_box;
+            use #crate_ident::*;
+
+            #(#items)*
+
+            criterion_group!(benches, #(#names),*);
+            criterion_main!(benches);
+        };
+        Ok(Some(crate::rust_gen::format_rust_code(file.to_string())))
+    }
+
+    /// The setup binding an input to `name`, and the argument passing it
+    fn input_for(
+        &self,
+        name: &syn::Ident,
+        ty: &Type,
+    ) -> Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+        let len = self.config.collection_len;
+        let input = match ty {
+            Type::Int => {
+                let value = proc_macro2::Literal::i64_unsuffixed(self.config.int_input);
+                quote! { #value }
+            }
+            Type::Float => quote! { 1.5 },
+            Type::Bool => quote! { true },
+            Type::String => {
+                // `&str`, `String` and `Cow<str>` parameters all convert from `&str`
+                return Some((
+                    quote! { String::from("depyler") },
+                    quote! { black_box(&*#name).into() },
+                ));
+            }
+            Type::List(inner) => {
+                let elements = match **inner {
+                    Type::Int => quote! { (0..#len).map(|i| i as _) },
+                    Type::Float => quote! { (0..#len).map(|i| i as f64) },
+                    _ => return None,
+                };
+                return Some((
+                    quote! { #elements.collect::<Vec<_>>() },
+                    quote! { black_box(&#name) },
+                ));
+            }
+            _ => return None,
+        };
+        Some((input, quote! { black_box(#name) }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::FunctionProperties;
+    use crate::hir_builder::{expr, stmt, HirFunctionBuilder};
+
+    fn pure() -> FunctionProperties {
+        FunctionProperties {
+            is_pure: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_only_pure_functions_with_known_inputs_are_benchmarked() {
+        let generator = BenchGenerator::new(BenchGenConfig::default());
+        let id = HirFunctionBuilder::new("id")
+            .param("x", Type::Int)
+            .returns(Type::Int)
+            .stmt(stmt::ret(expr::var("x")))
+            .properties(pure())
+            .build();
+        assert!(generator.generate_bench_for_function(&id).is_some());
+
+        let log = HirFunctionBuilder::new("log")
+            .param("x", Type::Int)
+            .returns(Type::Int)
+            .stmt(stmt::ret(expr::var("x")))
+            .build();
+        assert!(generator.generate_bench_for_function(&log).is_none());
+
+        let keys = HirFunctionBuilder::new("keys")
+            .param("x", Type::Unknown)
+            .returns(Type::Unknown)
+            .stmt(stmt::ret(expr::var("x")))
+            .properties(pure())
+            .build();
+        assert!(generator.generate_bench_for_function(&keys).is_none());
+    }
+
+    #[test]
+    fn test_string_inputs_are_borrowed_into_the_parameter_type() {
+        let generator = BenchGenerator::new(BenchGenConfig::default());
+        let echo = HirFunctionBuilder::new("echo")
+            .param("x", Type::String)
+            .returns(Type::String)
+            .stmt(stmt::ret(expr::var("x")))
+            .properties(pure())
+            .build();
+        let bench = generator
+            .generate_bench_for_function(&echo)
+            .unwrap()
+            .to_string();
+        assert!(bench.contains("let x = String :: from (\"depyler\") ;"));
+        assert!(bench.contains("echo (black_box (& * x) . into ())"));
+    }
+}
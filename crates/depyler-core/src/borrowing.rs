@@ -1,4 +1,5 @@
 use crate::hir::{AssignTarget, HirExpr, HirFunction, HirStmt, Type};
+use crate::method_mutation::is_in_place_mutation;
 use std::collections::HashSet;
 
 /// Tracks how parameters are used within a function to infer borrowing patterns
@@ -51,6 +52,11 @@ impl BorrowingContext {
         }
     }
 
+    /// Whether the function assigns `param_name` or mutates it in place
+    pub fn is_mutated(&self, param_name: &str) -> bool {
+        self.mutated_params.contains(param_name)
+    }
+
     /// Get the borrowing pattern for a specific parameter
     pub fn get_pattern(&self, param_name: &str, param_type: &Type) -> BorrowingPattern {
         if self.escaping_params.contains(param_name) {
@@ -157,10 +163,27 @@ impl BorrowingContext {
             HirExpr::List(elts) | HirExpr::Tuple(elts) => self.analyze_collection(elts),
             HirExpr::Dict(items) => self.analyze_dict(items),
             HirExpr::Index { base, index } => self.analyze_index(base, index),
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                ..
+            } => self.analyze_method_call(object, method, args),
             _ => {}
         }
     }
 
+    /// `xs.append(x)` mutates `xs` in place
+    fn analyze_method_call(&mut self, object: &HirExpr, method: &str, args: &[HirExpr]) {
+        if let HirExpr::Var(name) = object {
+            if is_in_place_mutation(method) && self.read_only_params.contains(name) {
+                self.mutated_params.insert(name.clone());
+            }
+        }
+        self.analyze_expr(object);
+        self.analyze_call(args);
+    }
+
     fn analyze_binary(&mut self, left: &HirExpr, right: &HirExpr) {
         self.analyze_expr(left);
         self.analyze_expr(right);
@@ -353,6 +376,7 @@ impl BorrowingContext {
 mod tests {
     use super::*;
     use crate::hir::{BinOp, FunctionProperties, HirParam, Literal};
+    use crate::hir_builder::HirFunctionBuilder;
     use depyler_annotations::TranspilationAnnotations;
     use smallvec::smallvec;
 
@@ -410,6 +434,35 @@ mod tests {
         // method calls that mutate the receiver.
     }
 
+    #[test]
+    fn test_in_place_method_mutates_parameter() {
+        let mut ctx = BorrowingContext::new();
+
+        let func = HirFunctionBuilder::new("test")
+            .param("x", Type::List(Box::new(Type::Int)))
+            .param("y", Type::List(Box::new(Type::Int)))
+            .stmt(HirStmt::Expr(HirExpr::MethodCall {
+                object: Box::new(HirExpr::Var("x".to_string())),
+                method: "append".to_string(),
+                args: vec![HirExpr::MethodCall {
+                    object: Box::new(HirExpr::Var("y".to_string())),
+                    method: "count".to_string(),
+                    args: vec![HirExpr::Literal(Literal::Int(42))],
+                    kwargs: vec![],
+                }],
+                kwargs: vec![],
+            }))
+            .build();
+
+        ctx.analyze_function(&func);
+        assert!(ctx.is_mutated("x"));
+        assert!(!ctx.is_mutated("y"));
+        assert_eq!(
+            ctx.get_pattern("x", &Type::List(Box::new(Type::Int))),
+            BorrowingPattern::MutableBorrow
+        );
+    }
+
     #[test]
     fn test_escaping_parameter() {
         let mut ctx = BorrowingContext::new();
@@ -245,6 +245,17 @@ impl BorrowingContext {
             HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {
                 // Break, continue, and pass don't analyze any expressions
             }
+            HirStmt::Match { subject, cases } => {
+                self.analyze_expression(subject, 0);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.analyze_expression(guard, 0);
+                    }
+                    for stmt in &case.body {
+                        self.analyze_statement(stmt);
+                    }
+                }
+            }
             HirStmt::Assert { test, msg } => {
                 // Analyze the test expression and optional message
                 self.analyze_expression(test, 0);
@@ -0,0 +1,310 @@
+//! Cargo.toml generation for transpiled code
+//!
+//! Generated Rust reaches external crates through qualified paths
+//! (`serde_json::to_string`, `tokio::time::sleep`, `#[tokio::main]`), and
+//! many of those come from lowering builtins rather than from a Python
+//! import. The dependencies are therefore read back from the generated code.
+
+use anyhow::Result;
+use proc_macro2::{TokenStream, TokenTree};
+use std::collections::BTreeSet;
+use syn::visit::Visit;
+
+/// A crate dependency of generated code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dependency {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub features: &'static [&'static str],
+    /// SPDX license expression of the crate
+    pub license: &'static str,
+}
+
+impl Dependency {
+    const fn new(name: &'static str, version: &'static str, license: &'static str) -> Self {
+        Self {
+            name,
+            version,
+            features: &[],
+            license,
+        }
+    }
+
+    /// The `[dependencies]` entry for this crate
+    pub fn to_toml(&self) -> String {
+        if self.features.is_empty() {
+            return format!("{} = \"{}\"", self.name, self.version);
+        }
+        let features: Vec<String> = self.features.iter().map(|f| format!("\"{}\"", f)).collect();
+        format!(
+            "{} = {{ version = \"{}\", features = [{}] }}",
+            self.name,
+            self.version,
+            features.join(", ")
+        )
+    }
+}
+
+/// Crates the code generator emits paths into, in name order
+const KNOWN_CRATES: &[Dependency] = &[
+    Dependency::new("ahash", "0.8", "MIT OR Apache-2.0"),
+    Dependency::new("base64", "0.21", "MIT OR Apache-2.0"),
+    Dependency::new("blake2", "0.10", "MIT OR Apache-2.0"),
+    Dependency::new("chrono", "0.4", "MIT OR Apache-2.0"),
+    Dependency {
+        name: "clap",
+        version: "4.5",
+        features: &["derive"],
+        license: "MIT OR Apache-2.0",
+    },
+    Dependency::new("crc32fast", "1.0", "MIT OR Apache-2.0"),
+    Dependency::new("csv", "1.0", "Unlicense OR MIT"),
+    Dependency::new("fnv", "1.0", "Apache-2.0 OR MIT"),
+    Dependency::new("futures", "0.3", "MIT OR Apache-2.0"),
+    Dependency::new("hex", "0.4", "MIT OR Apache-2.0"),
+    Dependency::new("hmac", "0.12", "MIT OR Apache-2.0"),
+    Dependency::new("itertools", "0.11", "MIT OR Apache-2.0"),
+    Dependency::new("log", "0.4", "MIT OR Apache-2.0"),
+    Dependency::new("md5", "0.7", "Apache-2.0 OR MIT"),
+    Dependency::new("num", "0.4", "MIT OR Apache-2.0"),
+    Dependency::new("num_bigint", "0.4", "MIT OR Apache-2.0"),
+    Dependency::new("percent_encoding", "2.3", "MIT OR Apache-2.0"),
+    Dependency::new("rand", "0.8", "MIT OR Apache-2.0"),
+    Dependency::new("regex", "1.0", "MIT OR Apache-2.0"),
+    Dependency {
+        name: "reqwest",
+        version: "0.12",
+        features: &["json"],
+        license: "MIT OR Apache-2.0",
+    },
+    Dependency::new("rust_decimal", "1.0", "MIT"),
+    Dependency {
+        name: "serde",
+        version: "1.0",
+        features: &["derive"],
+        license: "MIT OR Apache-2.0",
+    },
+    Dependency::new("serde_json", "1.0", "MIT OR Apache-2.0"),
+    Dependency::new("sha2", "0.10", "MIT OR Apache-2.0"),
+    Dependency::new("tempfile", "3.0", "MIT OR Apache-2.0"),
+    Dependency {
+        name: "tokio",
+        version: "1",
+        features: &["macros", "rt-multi-thread", "sync", "time"],
+        license: "MIT",
+    },
+    Dependency::new("url", "2.5", "MIT OR Apache-2.0"),
+    Dependency {
+        name: "uuid",
+        version: "1.0",
+        features: &["v4"],
+        license: "Apache-2.0 OR MIT",
+    },
+    Dependency::new("wasm_bindgen", "0.2", "MIT OR Apache-2.0"),
+];
+
+/// Dev-dependency of the benchmarks written by
+/// [`BenchGenerator`](crate::bench_generation::BenchGenerator)
+pub const CRITERION: Dependency = Dependency::new("criterion", "0.5", "Apache-2.0 OR MIT");
+
+/// External crates referenced by generated Rust code
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::cargo_toml_gen::detect_dependencies;
+///
+/// let code = "#[tokio::main] async fn main() { println!(\"{}\", serde_json::json!(1)); }";
+/// let names: Vec<_> = detect_dependencies(code).unwrap().iter().map(|d| d.name).collect();
+/// assert_eq!(names, ["serde_json", "tokio"]);
+/// ```
+pub fn detect_dependencies(rust_code: &str) -> Result<Vec<Dependency>> {
+    let file = syn::parse_file(rust_code)?;
+    let mut roots = PathRoots::default();
+    roots.visit_file(&file);
+    Ok(KNOWN_CRATES
+        .iter()
+        .filter(|dep| roots.names.contains(dep.name))
+        .copied()
+        .collect())
+}
+
+/// Cargo.toml for a crate named `package` with the given dependencies
+pub fn generate_cargo_toml(package: &str, dependencies: &[Dependency]) -> String {
+    let mut toml = format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\nedition = \"2021\"\n\n[dependencies]\n",
+        package
+    );
+    for dep in dependencies {
+        toml.push_str(&dep.to_toml());
+        toml.push('\n');
+    }
+    toml
+}
+
+/// [`generate_cargo_toml`] with a criterion `[[bench]]` target for each of
+/// `benches`, the stems of the files in `benches/`
+pub fn generate_cargo_toml_with_benches(
+    package: &str,
+    dependencies: &[Dependency],
+    benches: &[&str],
+) -> String {
+    let mut toml = generate_cargo_toml(package, dependencies);
+    toml.push('\n');
+    toml.push_str(&bench_targets(benches));
+    toml
+}
+
+/// The `[dev-dependencies]` and `[[bench]]` sections for `benches`
+pub fn bench_targets(benches: &[&str]) -> String {
+    let mut toml = format!("[dev-dependencies]\n{}\n", CRITERION.to_toml());
+    for bench in benches {
+        toml.push_str(&format!(
+            "\n[[bench]]\nname = \"{}\"\nharness = false\n",
+            bench
+        ));
+    }
+    toml
+}
+
+/// The `[lib]` section building a crate both as a C library, for the
+/// wrappers of [`ffi_generation`](crate::ffi_generation), and for Rust
+pub fn cdylib_target() -> String {
+    "[lib]\ncrate-type = [\"cdylib\", \"rlib\"]\n".to_string()
+}
+
+/// First segments of qualified paths and `use` trees
+#[derive(Default)]
+struct PathRoots {
+    names: BTreeSet<String>,
+}
+
+impl PathRoots {
+    /// Macro arguments are unparsed tokens, so `a::b` is found by its `::`
+    fn scan_tokens(&mut self, tokens: TokenStream) {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        for (i, token) in tokens.iter().enumerate() {
+            match token {
+                TokenTree::Group(group) => self.scan_tokens(group.stream()),
+                TokenTree::Ident(ident) => {
+                    let after_path_sep = i >= 2
+                        && matches!(&tokens[i - 1], TokenTree::Punct(p) if p.as_char() == ':')
+                        && matches!(&tokens[i - 2], TokenTree::Punct(p) if p.as_char() == ':');
+                    let before_path_sep = matches!(
+                        (tokens.get(i + 1), tokens.get(i + 2)),
+                        (Some(TokenTree::Punct(a)), Some(TokenTree::Punct(b)))
+                            if a.as_char() == ':' && b.as_char() == ':'
+                    );
+                    if before_path_sep && !after_path_sep {
+                        self.names.insert(ident.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for PathRoots {
+    fn visit_path(&mut self, path: &'ast syn::Path) {
+        if path.leading_colon.is_none() && path.segments.len() > 1 {
+            self.names.insert(path.segments[0].ident.to_string());
+        }
+        syn::visit::visit_path(self, path);
+    }
+
+    fn visit_item_use(&mut self, item: &'ast syn::ItemUse) {
+        let root = match &item.tree {
+            syn::UseTree::Path(p) => &p.ident,
+            syn::UseTree::Name(n) => &n.ident,
+            syn::UseTree::Rename(r) => &r.ident,
+            syn::UseTree::Glob(_) | syn::UseTree::Group(_) => return,
+        };
+        self.names.insert(root.to_string());
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.scan_tokens(mac.tokens.clone());
+        syn::visit::visit_macro(self, mac);
+    }
+
+    /// `#[derive(serde::Serialize)]` lists are unparsed tokens too
+    fn visit_meta_list(&mut self, list: &'ast syn::MetaList) {
+        self.scan_tokens(list.tokens.clone());
+        syn::visit::visit_meta_list(self, list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(code: &str) -> Vec<&'static str> {
+        detect_dependencies(code)
+            .unwrap()
+            .iter()
+            .map(|d| d.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_paths_attributes_and_uses() {
+        let code = r#"
+            use regex::Regex;
+            use std::collections::HashMap;
+            #[tokio::main]
+            async fn main() {
+                tokio::time::sleep(std::time::Duration::from_secs_f64(1.0)).await;
+                let _ = Regex::new("a").unwrap();
+            }
+        "#;
+        assert_eq!(names(code), ["regex", "tokio"]);
+    }
+
+    #[test]
+    fn test_detects_paths_inside_macros() {
+        let code = r#"
+            async fn pair() -> (i32, i32) {
+                println!("{}", serde_json::to_string(&1).unwrap());
+                async { tokio::join!(async { 1 }, async { 2 }) }.await
+            }
+        "#;
+        assert_eq!(names(code), ["serde_json", "tokio"]);
+    }
+
+    #[test]
+    fn test_detects_paths_inside_derives() {
+        let code = r#"
+            #[derive(Debug, serde::Serialize, serde::Deserialize)]
+            pub struct Order { pub id: i32 }
+            fn dump(order: &Order) -> Result<String, serde_json::Error> {
+                serde_json::to_string(order)
+            }
+        "#;
+        assert_eq!(names(code), ["serde", "serde_json"]);
+    }
+
+    #[test]
+    fn test_ignores_std_and_local_paths() {
+        let code = "mod rand { pub fn f() {} }\nfn g() { std::process::exit(0); self::rand::f(); }";
+        assert!(names(code).is_empty());
+    }
+
+    #[test]
+    fn test_generate_cargo_toml() {
+        let deps = detect_dependencies("#[tokio::main] async fn main() {}").unwrap();
+        let toml = generate_cargo_toml("demo", &deps);
+        assert!(toml.starts_with("[package]\nname = \"demo\"\n"));
+        assert!(toml.ends_with(
+            "[dependencies]\ntokio = { version = \"1\", features = [\"macros\", \"rt-multi-thread\", \"sync\", \"time\"] }\n"
+        ));
+    }
+
+    #[test]
+    fn test_benches_add_criterion_and_unharnessed_targets() {
+        let toml = generate_cargo_toml_with_benches("demo", &[], &["demo"]);
+        assert!(toml.ends_with(
+            "[dependencies]\n\n[dev-dependencies]\ncriterion = \"0.5\"\n\n[[bench]]\nname = \"demo\"\nharness = false\n"
+        ));
+    }
+}
@@ -596,6 +596,9 @@ fn stmt_to_rust_tokens_with_scope(
             // Pass statement generates no code
             Ok(quote! {})
         }
+        HirStmt::Match { .. } => {
+            bail!("Match statements require rust_gen.rs (use DepylerPipeline instead of direct codegen)")
+        }
     }
 }
 
@@ -0,0 +1,1233 @@
+//! Compile-time evaluation of module constants
+//!
+//! Module constants such as `SIZES = [2**i for i in range(8)]` or
+//! `NAMES = sorted(RAW.keys())` depend only on literals and earlier
+//! constants, so their values are known when transpiling. [`ConstEvaluator`]
+//! interprets the side-effect-free subset of Python these expressions use,
+//! and rust_gen emits the result as static data instead of code that runs at
+//! startup. Anything outside the subset (user functions, attribute access,
+//! builtins not listed in [`ConstEvaluator::eval`]) evaluates to `None` and
+//! the constant is generated as before.
+
+use crate::hir::{BinOp, FStringPart, HirComprehension, HirExpr, Literal, UnaryOp};
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Longest sequence the evaluator builds; `range(10**9)` stays a runtime value
+const MAX_ITEMS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    None,
+    List(Vec<ConstValue>),
+    Tuple(Vec<ConstValue>),
+    /// Insertion order, which Python does not preserve; only order-insensitive
+    /// operations (`sorted`, `len`, `in`, `sum`, `min`, `max`) iterate a set
+    Set(Vec<ConstValue>),
+    /// Entries in insertion order
+    Dict(Vec<(ConstValue, ConstValue)>),
+}
+
+use ConstValue::{Bool, Dict, Float, Int, List, Set, Str, Tuple};
+
+#[derive(Debug, Default)]
+pub struct ConstEvaluator {
+    env: HashMap<String, ConstValue>,
+}
+
+impl ConstEvaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Makes an evaluated constant visible to later expressions
+    pub fn define(&mut self, name: &str, value: ConstValue) {
+        self.env.insert(name.to_string(), value);
+    }
+
+    /// Evaluates `expr`, or returns `None` when it is outside the supported
+    /// subset or would raise in Python
+    ///
+    /// Supported: literals, previously defined constants, arithmetic,
+    /// comparison, boolean and bitwise operators, container displays,
+    /// comprehensions and generator expressions, conditional expressions,
+    /// indexing and slicing, f-strings, `sorted` (with or without `key` and
+    /// `reverse`), the builtins `range`, `len`, `reversed`, `list`, `tuple`,
+    /// `set`, `frozenset`, `dict`, `sum`, `min`, `max`, `abs`, `str`, `repr`, `int`,
+    /// `float`, `bool`, `enumerate`, `zip`, `any` and `all`, and the
+    /// `dict`, `str` and sequence methods handled by `method_call`.
+    pub fn eval(&mut self, expr: &HirExpr) -> Option<ConstValue> {
+        match expr {
+            HirExpr::Literal(lit) => literal(lit),
+            HirExpr::Var(name) => self.env.get(name).cloned(),
+            HirExpr::Binary { op, left, right } => {
+                let left = self.eval(left)?;
+                match op {
+                    BinOp::And if !left.truthy() => Some(left),
+                    BinOp::Or if left.truthy() => Some(left),
+                    BinOp::And | BinOp::Or => self.eval(right),
+                    _ => binary(*op, left, self.eval(right)?),
+                }
+            }
+            HirExpr::Unary { op, operand } => unary(*op, self.eval(operand)?),
+            HirExpr::List(items) => Some(List(self.eval_all(items)?)),
+            HirExpr::Tuple(items) => Some(Tuple(self.eval_all(items)?)),
+            HirExpr::Set(items) | HirExpr::FrozenSet(items) => set_from(self.eval_all(items)?),
+            HirExpr::Dict(entries) => {
+                let mut dict = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let key = self.eval(key)?;
+                    let value = self.eval(value)?;
+                    dict_insert(&mut dict, key, value)?;
+                }
+                Some(Dict(dict))
+            }
+            HirExpr::IfExpr { test, body, orelse } => {
+                if self.eval(test)?.truthy() {
+                    self.eval(body)
+                } else {
+                    self.eval(orelse)
+                }
+            }
+            HirExpr::Index { base, index } => subscript(self.eval(base)?, self.eval(index)?),
+            HirExpr::Slice {
+                base,
+                start,
+                stop,
+                step,
+            } => {
+                let base = self.eval(base)?;
+                let start = self.eval_bound(start.as_deref())?;
+                let stop = self.eval_bound(stop.as_deref())?;
+                let step = self.eval_bound(step.as_deref())?;
+                slice(base, start, stop, step)
+            }
+            HirExpr::FString { parts } => {
+                let mut text = String::new();
+                for part in parts {
+                    match part {
+                        FStringPart::Literal(s) => text.push_str(s),
+                        FStringPart::Expr(expr) => text.push_str(&self.eval(expr)?.py_str()),
+                    }
+                }
+                Some(Str(text))
+            }
+            HirExpr::Call { func, args, kwargs } => {
+                let args = self.eval_all(args)?;
+                let mut keywords = Vec::with_capacity(kwargs.len());
+                for (name, value) in kwargs {
+                    keywords.push((name.as_str(), self.eval(value)?));
+                }
+                call(func, &args, &keywords)
+            }
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                kwargs,
+            } if kwargs.is_empty() => {
+                let object = self.eval(object)?;
+                let args = self.eval_all(args)?;
+                method_call(&object, method, &args)
+            }
+            HirExpr::ListComp {
+                element,
+                target,
+                iter,
+                condition,
+            } => {
+                let conditions: Vec<&HirExpr> = condition.as_deref().into_iter().collect();
+                let items =
+                    self.comprehend(target, iter, &conditions, false, &mut |ev| ev.eval(element))?;
+                Some(List(items))
+            }
+            HirExpr::SetComp {
+                element,
+                target,
+                iter,
+                condition,
+            } => {
+                let conditions: Vec<&HirExpr> = condition.as_deref().into_iter().collect();
+                let items =
+                    self.comprehend(target, iter, &conditions, true, &mut |ev| ev.eval(element))?;
+                set_from(items)
+            }
+            HirExpr::DictComp {
+                key,
+                value,
+                target,
+                iter,
+                condition,
+            } => {
+                let conditions: Vec<&HirExpr> = condition.as_deref().into_iter().collect();
+                let entries = self.comprehend(target, iter, &conditions, false, &mut |ev| {
+                    Some((ev.eval(key)?, ev.eval(value)?))
+                })?;
+                let mut dict = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    dict_insert(&mut dict, key, value)?;
+                }
+                Some(Dict(dict))
+            }
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => {
+                let mut items = Vec::new();
+                self.generate(generators, element, &mut items)?;
+                Some(List(items))
+            }
+            HirExpr::SortByKey {
+                iterable,
+                key_params,
+                key_body,
+                reverse,
+            } => {
+                let param = match key_params.as_slice() {
+                    [param] => param,
+                    _ => return None,
+                };
+                let items = self.eval(iterable)?.unordered_elements()?;
+                let mut keyed = Vec::with_capacity(items.len());
+                for item in items {
+                    let saved = self.bind(param, item.clone())?;
+                    let key = self.eval(key_body);
+                    self.restore(saved);
+                    keyed.push((SortKey::of(&key?)?, item));
+                }
+                let mut keyed = comparable(keyed)?;
+                keyed.sort_by(|(a, _), (b, _)| order(a.compare(b), *reverse));
+                Some(List(keyed.into_iter().map(|(_, item)| item).collect()))
+            }
+            _ => None,
+        }
+    }
+
+    fn eval_all(&mut self, exprs: &[HirExpr]) -> Option<Vec<ConstValue>> {
+        exprs.iter().map(|expr| self.eval(expr)).collect()
+    }
+
+    /// A slice bound: absent, `None` or an integer
+    fn eval_bound(&mut self, bound: Option<&HirExpr>) -> Option<Option<i64>> {
+        match bound.map(|expr| self.eval(expr)) {
+            None | Some(Some(ConstValue::None)) => Some(None),
+            Some(Some(value)) => value.as_int().map(Some),
+            Some(None) => None,
+        }
+    }
+
+    /// Runs `body` for each item of `iter` bound to `target` that passes
+    /// every condition
+    fn comprehend<T>(
+        &mut self,
+        target: &str,
+        iter: &HirExpr,
+        conditions: &[&HirExpr],
+        unordered: bool,
+        body: &mut dyn FnMut(&mut Self) -> Option<T>,
+    ) -> Option<Vec<T>> {
+        let iter = self.eval(iter)?;
+        let items = if unordered {
+            iter.unordered_elements()?
+        } else {
+            iter.elements()?
+        };
+
+        let mut results = Vec::new();
+        for item in items {
+            let saved = self.bind(target, item)?;
+            let result = self.filtered(conditions, body);
+            self.restore(saved);
+            if let Some(value) = result? {
+                if results.len() == MAX_ITEMS {
+                    return None;
+                }
+                results.push(value);
+            }
+        }
+        Some(results)
+    }
+
+    fn filtered<T>(
+        &mut self,
+        conditions: &[&HirExpr],
+        body: &mut dyn FnMut(&mut Self) -> Option<T>,
+    ) -> Option<Option<T>> {
+        for condition in conditions {
+            if !self.eval(condition)?.truthy() {
+                return Some(None);
+            }
+        }
+        body(self).map(Some)
+    }
+
+    fn generate(
+        &mut self,
+        generators: &[HirComprehension],
+        element: &HirExpr,
+        items: &mut Vec<ConstValue>,
+    ) -> Option<()> {
+        let (first, rest) = match generators.split_first() {
+            Some(split) => split,
+            None => {
+                if items.len() == MAX_ITEMS {
+                    return None;
+                }
+                items.push(self.eval(element)?);
+                return Some(());
+            }
+        };
+        let conditions: Vec<&HirExpr> = first.conditions.iter().collect();
+        self.comprehend(&first.target, &first.iter, &conditions, false, &mut |ev| {
+            ev.generate(rest, element, items)
+        })?;
+        Some(())
+    }
+
+    /// Binds a loop target (`x` or the HIR's `(x, y)` form), returning the
+    /// shadowed bindings
+    fn bind(
+        &mut self,
+        target: &str,
+        value: ConstValue,
+    ) -> Option<Vec<(String, Option<ConstValue>)>> {
+        let names: Vec<&str> = match target.strip_prefix('(').and_then(|t| t.strip_suffix(')')) {
+            Some(inner) => inner.split(',').map(str::trim).collect(),
+            None => vec![target],
+        };
+        let values = match names.as_slice() {
+            [_] if !target.starts_with('(') => vec![value],
+            _ => match value {
+                List(items) | Tuple(items) if items.len() == names.len() => items,
+                _ => return None,
+            },
+        };
+        Some(
+            names
+                .into_iter()
+                .zip(values)
+                .map(|(name, value)| (name.to_string(), self.env.insert(name.to_string(), value)))
+                .collect(),
+        )
+    }
+
+    fn restore(&mut self, saved: Vec<(String, Option<ConstValue>)>) {
+        for (name, previous) in saved.into_iter().rev() {
+            match previous {
+                Some(value) => self.env.insert(name, value),
+                None => self.env.remove(&name),
+            };
+        }
+    }
+}
+
+impl ConstValue {
+    pub fn truthy(&self) -> bool {
+        match self {
+            Int(n) => *n != 0,
+            Float(f) => *f != 0.0,
+            Bool(b) => *b,
+            Str(s) => !s.is_empty(),
+            ConstValue::None => false,
+            List(items) | Tuple(items) | Set(items) => !items.is_empty(),
+            Dict(entries) => !entries.is_empty(),
+        }
+    }
+
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Int(n) => Some(*n),
+            Bool(b) => Some(*b as i64),
+            _ => None,
+        }
+    }
+
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Float(f) => Some(*f),
+            _ => self.as_int().map(|n| n as f64),
+        }
+    }
+
+    fn len(&self) -> Option<usize> {
+        match self {
+            Str(s) => Some(s.chars().count()),
+            List(items) | Tuple(items) | Set(items) => Some(items.len()),
+            Dict(entries) => Some(entries.len()),
+            _ => None,
+        }
+    }
+
+    /// Items in Python's iteration order; sets have none
+    fn elements(&self) -> Option<Vec<ConstValue>> {
+        match self {
+            List(items) | Tuple(items) => Some(items.clone()),
+            Str(s) => Some(s.chars().map(|c| Str(c.to_string())).collect()),
+            Dict(entries) => Some(entries.iter().map(|(key, _)| key.clone()).collect()),
+            _ => None,
+        }
+    }
+
+    /// Items for operations whose result does not depend on their order
+    fn unordered_elements(&self) -> Option<Vec<ConstValue>> {
+        match self {
+            Set(items) => Some(items.clone()),
+            _ => self.elements(),
+        }
+    }
+
+    /// `==`, under which `1 == 1.0 == True`
+    fn py_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Str(a), Str(b)) => a == b,
+            (List(a), List(b)) | (Tuple(a), Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.py_eq(y))
+            }
+            (Set(a), Set(b)) => a.len() == b.len() && a.iter().all(|x| contains_value(b, x)),
+            (Dict(a), Dict(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, value)| lookup(b, key).is_some_and(|other| value.py_eq(other)))
+            }
+            (ConstValue::None, ConstValue::None) => true,
+            _ => match (self.as_int(), other.as_int()) {
+                (Some(a), Some(b)) => a == b,
+                _ => match (self.as_float(), other.as_float()) {
+                    (Some(a), Some(b)) => a == b,
+                    _ => false,
+                },
+            },
+        }
+    }
+
+    /// `<` and friends; `None` where Python raises `TypeError`
+    fn py_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Str(a), Str(b)) => Some(a.cmp(b)),
+            (List(a), List(b)) | (Tuple(a), Tuple(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    if !x.py_eq(y) {
+                        return x.py_cmp(y);
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            }
+            _ => match (self.as_int(), other.as_int()) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                _ => self.as_float()?.partial_cmp(&other.as_float()?),
+            },
+        }
+    }
+
+    /// `str(value)`
+    pub(crate) fn py_str(&self) -> String {
+        match self {
+            Str(s) => s.clone(),
+            _ => self.py_repr(),
+        }
+    }
+
+    /// `repr(value)`
+    pub(crate) fn py_repr(&self) -> String {
+        let join = |items: &[ConstValue]| {
+            items
+                .iter()
+                .map(ConstValue::py_repr)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Int(n) => n.to_string(),
+            Float(f) => float_repr(*f),
+            Bool(true) => "True".to_string(),
+            Bool(false) => "False".to_string(),
+            Str(s) => format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'")),
+            ConstValue::None => "None".to_string(),
+            List(items) => format!("[{}]", join(items)),
+            Tuple(items) if items.len() == 1 => format!("({},)", join(items)),
+            Tuple(items) => format!("({})", join(items)),
+            Set(items) if items.is_empty() => "set()".to_string(),
+            Set(items) => format!("{{{}}}", join(items)),
+            Dict(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.py_repr(), value.py_repr()))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+        }
+    }
+
+    /// The Rust type and initializer of a `const` holding this value, or
+    /// `None` when it has no const form: sets, dicts, `None`, and empty or
+    /// mixed-type sequences
+    ///
+    /// Integers are `i32`, like literal constants, unless some integer in
+    /// the value needs `i64`.
+    pub fn to_const_tokens(&self) -> Option<(TokenStream, TokenStream)> {
+        self.const_tokens(self.needs_i64())
+    }
+
+    fn needs_i64(&self) -> bool {
+        match self {
+            Int(n) => i32::try_from(*n).is_err(),
+            List(items) | Tuple(items) => items.iter().any(ConstValue::needs_i64),
+            _ => false,
+        }
+    }
+
+    fn const_tokens(&self, wide: bool) -> Option<(TokenStream, TokenStream)> {
+        match self {
+            Int(n) => {
+                let ty = if wide { quote!(i64) } else { quote!(i32) };
+                let magnitude = proc_macro2::Literal::u64_unsuffixed(n.unsigned_abs());
+                let value = if *n < 0 {
+                    quote!(-#magnitude)
+                } else {
+                    quote!(#magnitude)
+                };
+                Some((ty, value))
+            }
+            Float(f) if f.is_finite() => {
+                let magnitude = proc_macro2::Literal::f64_unsuffixed(f.abs());
+                let value = if f.is_sign_negative() {
+                    quote!(-#magnitude)
+                } else {
+                    quote!(#magnitude)
+                };
+                Some((quote!(f64), value))
+            }
+            Bool(b) => Some((quote!(bool), quote!(#b))),
+            Str(s) => Some((quote!(&str), quote!(#s))),
+            List(items) => {
+                let (types, values) = Self::const_items(items, wide)?;
+                let first = types.first()?.to_string();
+                if types.iter().any(|ty| ty.to_string() != first) {
+                    return None;
+                }
+                let ty = &types[0];
+                Some((quote!(&[#ty]), quote!(&[#(#values),*])))
+            }
+            Tuple(items) => {
+                let (types, values) = Self::const_items(items, wide)?;
+                if items.len() == 1 {
+                    let (ty, value) = (&types[0], &values[0]);
+                    return Some((quote!((#ty,)), quote!((#value,))));
+                }
+                Some((quote!((#(#types),*)), quote!((#(#values),*))))
+            }
+            _ => None,
+        }
+    }
+
+    fn const_items(
+        items: &[ConstValue],
+        wide: bool,
+    ) -> Option<(Vec<TokenStream>, Vec<TokenStream>)> {
+        items
+            .iter()
+            .map(|item| item.const_tokens(wide))
+            .collect::<Option<Vec<_>>>()
+            .map(|pairs| pairs.into_iter().unzip())
+    }
+}
+
+fn literal(lit: &Literal) -> Option<ConstValue> {
+    match lit {
+        Literal::Int(n) => Some(Int(*n)),
+        Literal::Float(f) => Some(Float(*f)),
+        Literal::String(s) => Some(Str(s.clone())),
+        Literal::Bool(b) => Some(Bool(*b)),
+        Literal::None => Some(ConstValue::None),
+        Literal::Bytes(_) => None,
+    }
+}
+
+fn binary(op: BinOp, left: ConstValue, right: ConstValue) -> Option<ConstValue> {
+    let compare = |wanted: &[Ordering]| Some(Bool(wanted.contains(&left.py_cmp(&right)?)));
+    match op {
+        BinOp::Eq => Some(Bool(left.py_eq(&right))),
+        BinOp::NotEq => Some(Bool(!left.py_eq(&right))),
+        BinOp::Lt => compare(&[Ordering::Less]),
+        BinOp::LtEq => compare(&[Ordering::Less, Ordering::Equal]),
+        BinOp::Gt => compare(&[Ordering::Greater]),
+        BinOp::GtEq => compare(&[Ordering::Greater, Ordering::Equal]),
+        BinOp::In => Some(Bool(contains(&right, &left)?)),
+        BinOp::NotIn => Some(Bool(!contains(&right, &left)?)),
+        BinOp::Add => match (left, right) {
+            (Str(a), Str(b)) => Some(Str(a + &b)),
+            (List(a), List(b)) => Some(List(concat(a, b)?)),
+            (Tuple(a), Tuple(b)) => Some(Tuple(concat(a, b)?)),
+            (left, right) => arithmetic(op, &left, &right),
+        },
+        BinOp::Mul => match (left, right) {
+            (Str(s), n) | (n, Str(s)) => {
+                let count = repeat_count(&n, s.chars().count())?;
+                Some(Str(s.repeat(count)))
+            }
+            (List(items), n) | (n, List(items)) => Some(List(repeat(items, &n)?)),
+            (Tuple(items), n) | (n, Tuple(items)) => Some(Tuple(repeat(items, &n)?)),
+            (left, right) => arithmetic(op, &left, &right),
+        },
+        BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => match (&left, &right) {
+            (Bool(a), Bool(b)) => Some(Bool(match op {
+                BinOp::BitAnd => a & b,
+                BinOp::BitOr => a | b,
+                _ => a ^ b,
+            })),
+            _ => arithmetic(op, &left, &right),
+        },
+        _ => arithmetic(op, &left, &right),
+    }
+}
+
+fn arithmetic(op: BinOp, left: &ConstValue, right: &ConstValue) -> Option<ConstValue> {
+    if let (Some(a), Some(b)) = (left.as_int(), right.as_int()) {
+        return int_arithmetic(op, a, b);
+    }
+    let (a, b) = (left.as_float()?, right.as_float()?);
+    let result = match op {
+        BinOp::Add => a + b,
+        BinOp::Sub => a - b,
+        BinOp::Mul => a * b,
+        BinOp::Div if b != 0.0 => a / b,
+        BinOp::FloorDiv if b != 0.0 => (a / b).floor(),
+        BinOp::Mod if b != 0.0 => {
+            let m = a % b;
+            if m != 0.0 && (m < 0.0) != (b < 0.0) {
+                m + b
+            } else {
+                m
+            }
+        }
+        BinOp::Pow => a.powf(b),
+        _ => return None,
+    };
+    result.is_finite().then_some(Float(result))
+}
+
+/// Integer operators with Python's floor division and modulo; overflow
+/// leaves the constant to runtime
+fn int_arithmetic(op: BinOp, a: i64, b: i64) -> Option<ConstValue> {
+    let floor_adjust = |r: i64| r != 0 && (r < 0) != (b < 0);
+    match op {
+        BinOp::Add => a.checked_add(b).map(Int),
+        BinOp::Sub => a.checked_sub(b).map(Int),
+        BinOp::Mul => a.checked_mul(b).map(Int),
+        BinOp::Div if b != 0 => Some(Float(a as f64 / b as f64)),
+        BinOp::FloorDiv if b != 0 => {
+            let (q, r) = (a.checked_div(b)?, a % b);
+            Some(Int(if floor_adjust(r) { q - 1 } else { q }))
+        }
+        BinOp::Mod if b != 0 => {
+            let r = a.checked_rem(b)?;
+            Some(Int(if floor_adjust(r) { r + b } else { r }))
+        }
+        BinOp::Pow if b >= 0 => a.checked_pow(u32::try_from(b).ok()?).map(Int),
+        BinOp::Pow => {
+            let result = (a as f64).powf(b as f64);
+            result.is_finite().then_some(Float(result))
+        }
+        BinOp::BitAnd => Some(Int(a & b)),
+        BinOp::BitOr => Some(Int(a | b)),
+        BinOp::BitXor => Some(Int(a ^ b)),
+        BinOp::LShift => {
+            let shift = u32::try_from(b).ok().filter(|s| *s < 64)?;
+            let result = a << shift;
+            (result >> shift == a).then_some(Int(result))
+        }
+        BinOp::RShift => Some(Int(a >> u32::try_from(b).ok()?.min(63))),
+        _ => None,
+    }
+}
+
+fn unary(op: UnaryOp, operand: ConstValue) -> Option<ConstValue> {
+    match (op, operand) {
+        (UnaryOp::Not, value) => Some(Bool(!value.truthy())),
+        (UnaryOp::Neg, Float(f)) => Some(Float(-f)),
+        (UnaryOp::Pos, Float(f)) => Some(Float(f)),
+        (UnaryOp::Neg, value) => value.as_int()?.checked_neg().map(Int),
+        (UnaryOp::Pos, value) => value.as_int().map(Int),
+        (UnaryOp::BitNot, value) => value.as_int().map(|n| Int(!n)),
+    }
+}
+
+fn concat(mut a: Vec<ConstValue>, b: Vec<ConstValue>) -> Option<Vec<ConstValue>> {
+    if a.len() + b.len() > MAX_ITEMS {
+        return None;
+    }
+    a.extend(b);
+    Some(a)
+}
+
+fn repeat_count(n: &ConstValue, len: usize) -> Option<usize> {
+    let count = usize::try_from(n.as_int()?.max(0)).ok()?;
+    (count.saturating_mul(len) <= MAX_ITEMS).then_some(count)
+}
+
+fn repeat(items: Vec<ConstValue>, n: &ConstValue) -> Option<Vec<ConstValue>> {
+    let count = repeat_count(n, items.len())?;
+    Some(items.repeat(count))
+}
+
+fn contains(container: &ConstValue, item: &ConstValue) -> Option<bool> {
+    match (container, item) {
+        (Str(haystack), Str(needle)) => Some(haystack.contains(needle.as_str())),
+        (List(items) | Tuple(items) | Set(items), _) => Some(contains_value(items, item)),
+        (Dict(entries), _) => Some(lookup(entries, item).is_some()),
+        _ => None,
+    }
+}
+
+fn contains_value(items: &[ConstValue], item: &ConstValue) -> bool {
+    items.iter().any(|x| x.py_eq(item))
+}
+
+fn lookup<'a>(entries: &'a [(ConstValue, ConstValue)], key: &ConstValue) -> Option<&'a ConstValue> {
+    entries
+        .iter()
+        .find(|(k, _)| k.py_eq(key))
+        .map(|(_, value)| value)
+}
+
+fn is_hashable(value: &ConstValue) -> bool {
+    match value {
+        List(_) | Set(_) | Dict(_) => false,
+        Tuple(items) => items.iter().all(is_hashable),
+        _ => true,
+    }
+}
+
+fn dict_insert(
+    entries: &mut Vec<(ConstValue, ConstValue)>,
+    key: ConstValue,
+    value: ConstValue,
+) -> Option<()> {
+    if !is_hashable(&key) {
+        return None;
+    }
+    match entries.iter_mut().find(|(k, _)| k.py_eq(&key)) {
+        Some(entry) => entry.1 = value,
+        None => entries.push((key, value)),
+    }
+    Some(())
+}
+
+fn set_from(items: Vec<ConstValue>) -> Option<ConstValue> {
+    let mut set: Vec<ConstValue> = Vec::with_capacity(items.len());
+    for item in items {
+        if !is_hashable(&item) {
+            return None;
+        }
+        if !contains_value(&set, &item) {
+            set.push(item);
+        }
+    }
+    Some(Set(set))
+}
+
+fn subscript(base: ConstValue, index: ConstValue) -> Option<ConstValue> {
+    match base {
+        Dict(entries) => lookup(&entries, &index).cloned(),
+        base => {
+            let items = base.elements()?;
+            let len = items.len() as i64;
+            let i = index.as_int()?;
+            let i = if i < 0 { i + len } else { i };
+            if i < 0 {
+                return None;
+            }
+            items.into_iter().nth(i as usize)
+        }
+    }
+}
+
+fn slice(
+    base: ConstValue,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+) -> Option<ConstValue> {
+    let is_str = matches!(base, Str(_));
+    let wrap: fn(Vec<ConstValue>) -> ConstValue = match base {
+        Tuple(_) => Tuple,
+        _ => List,
+    };
+    let items = match base {
+        List(_) | Tuple(_) | Str(_) => base.elements()?,
+        _ => return None,
+    };
+    let picked: Vec<ConstValue> = slice_indices(items.len(), start, stop, step)?
+        .into_iter()
+        .map(|i| items[i].clone())
+        .collect();
+    if is_str {
+        return Some(Str(picked.iter().map(ConstValue::py_str).collect()));
+    }
+    Some(wrap(picked))
+}
+
+/// The indices `seq[start:stop:step]` selects, as `slice.indices` computes
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    stop: Option<i64>,
+    step: Option<i64>,
+) -> Option<Vec<usize>> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    if step == 0 {
+        return None;
+    }
+    let (lower, upper) = if step > 0 { (0, len) } else { (-1, len - 1) };
+    let clamp = |i: i64| (if i < 0 { i + len } else { i }).clamp(lower, upper);
+    let start = start.map_or(if step > 0 { lower } else { upper }, clamp);
+    let stop = stop.map_or(if step > 0 { upper } else { lower }, clamp);
+
+    let mut indices = Vec::new();
+    let mut i = start;
+    while (step > 0 && i < stop) || (step < 0 && i > stop) {
+        indices.push(i as usize);
+        i += step;
+    }
+    Some(indices)
+}
+
+fn call(func: &str, args: &[ConstValue], kwargs: &[(&str, ConstValue)]) -> Option<ConstValue> {
+    // `sorted(xs, reverse=True)` is the only keyword argument accepted
+    let reverse = match kwargs {
+        [] => false,
+        [("reverse", value)] if func == "sorted" => value.truthy(),
+        _ => return None,
+    };
+    match (func, args) {
+        ("range", _) => range(args),
+        ("len", [x]) => Some(Int(x.len()? as i64)),
+        ("sorted", [x]) => {
+            let items = x.unordered_elements()?;
+            let keys = items.iter().map(SortKey::of).collect::<Option<Vec<_>>>()?;
+            let mut keyed = comparable(keys.into_iter().zip(items).collect())?;
+            keyed.sort_by(|(a, _), (b, _)| order(a.compare(b), reverse));
+            Some(List(keyed.into_iter().map(|(_, item)| item).collect()))
+        }
+        ("reversed", [x]) => {
+            let mut items = x.elements()?;
+            items.reverse();
+            Some(List(items))
+        }
+        ("list", []) => Some(List(Vec::new())),
+        ("list", [x]) => Some(List(x.elements()?)),
+        ("tuple", []) => Some(Tuple(Vec::new())),
+        ("tuple", [x]) => Some(Tuple(x.elements()?)),
+        ("set" | "frozenset", []) => Some(Set(Vec::new())),
+        ("set" | "frozenset", [x]) => set_from(x.unordered_elements()?),
+        ("dict", []) => Some(Dict(Vec::new())),
+        ("dict", [Dict(entries)]) => Some(Dict(entries.clone())),
+        ("dict", [x]) => {
+            let mut dict = Vec::new();
+            for pair in x.elements()? {
+                match pair {
+                    List(kv) | Tuple(kv) if kv.len() == 2 => {
+                        let mut kv = kv.into_iter();
+                        dict_insert(&mut dict, kv.next()?, kv.next()?)?;
+                    }
+                    _ => return None,
+                }
+            }
+            Some(Dict(dict))
+        }
+        ("sum", [x]) => x
+            .unordered_elements()?
+            .into_iter()
+            .try_fold(Int(0), |total, item| binary(BinOp::Add, total, item)),
+        ("min" | "max", [x]) => extreme(x.unordered_elements()?, func == "max"),
+        ("min" | "max", [_, _, ..]) => extreme(args.to_vec(), func == "max"),
+        ("abs", [Float(f)]) => Some(Float(f.abs())),
+        ("abs", [x]) => x.as_int()?.checked_abs().map(Int),
+        ("str", [x]) => Some(Str(x.py_str())),
+        ("repr", [x]) => Some(Str(x.py_repr())),
+        ("int", [Float(f)]) if f.is_finite() && f.abs() < 9.2e18 => Some(Int(f.trunc() as i64)),
+        ("int", [Str(s)]) => s.trim().replace('_', "").parse().ok().map(Int),
+        ("int", [x]) => x.as_int().map(Int),
+        ("float", [Str(s)]) => s
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite())
+            .map(Float),
+        ("float", [x]) => x.as_float().map(Float),
+        ("bool", [x]) => Some(Bool(x.truthy())),
+        ("enumerate", [x]) => Some(List(
+            x.elements()?
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| Tuple(vec![Int(i as i64), item]))
+                .collect(),
+        )),
+        ("zip", _) => {
+            let columns = args
+                .iter()
+                .map(ConstValue::elements)
+                .collect::<Option<Vec<_>>>()?;
+            let len = columns.iter().map(Vec::len).min().unwrap_or(0);
+            Some(List(
+                (0..len)
+                    .map(|i| Tuple(columns.iter().map(|column| column[i].clone()).collect()))
+                    .collect(),
+            ))
+        }
+        ("any", [x]) => Some(Bool(x.unordered_elements()?.iter().any(ConstValue::truthy))),
+        ("all", [x]) => Some(Bool(x.unordered_elements()?.iter().all(ConstValue::truthy))),
+        _ => None,
+    }
+}
+
+fn range(args: &[ConstValue]) -> Option<ConstValue> {
+    let bounds = args
+        .iter()
+        .map(ConstValue::as_int)
+        .collect::<Option<Vec<_>>>()?;
+    let (start, stop, step) = match bounds.as_slice() {
+        [stop] => (0, *stop, 1),
+        [start, stop] => (*start, *stop, 1),
+        [start, stop, step] if *step != 0 => (*start, *stop, *step),
+        _ => return None,
+    };
+
+    let mut items = Vec::new();
+    let mut i = start;
+    while (step > 0 && i < stop) || (step < 0 && i > stop) {
+        if items.len() == MAX_ITEMS {
+            return None;
+        }
+        items.push(Int(i));
+        i = i.checked_add(step)?;
+    }
+    Some(List(items))
+}
+
+/// `min`/`max`: the first extreme item, as Python returns
+fn extreme(items: Vec<ConstValue>, max: bool) -> Option<ConstValue> {
+    let keys = items.iter().map(SortKey::of).collect::<Option<Vec<_>>>()?;
+    let mut best: Option<(SortKey, ConstValue)> = None;
+    for (key, item) in comparable(keys.into_iter().zip(items).collect())? {
+        let better = match &best {
+            Some((best_key, _)) => {
+                let ordering = key.compare(best_key);
+                if max {
+                    ordering == Ordering::Greater
+                } else {
+                    ordering == Ordering::Less
+                }
+            }
+            None => true,
+        };
+        if better {
+            best = Some((key, item));
+        }
+    }
+    best.map(|(_, item)| item)
+}
+
+fn method_call(object: &ConstValue, method: &str, args: &[ConstValue]) -> Option<ConstValue> {
+    match (object, method, args) {
+        (Dict(entries), "keys", []) => Some(List(entries.iter().map(|(k, _)| k.clone()).collect())),
+        (Dict(entries), "values", []) => {
+            Some(List(entries.iter().map(|(_, v)| v.clone()).collect()))
+        }
+        (Dict(entries), "items", []) => Some(List(
+            entries
+                .iter()
+                .map(|(k, v)| Tuple(vec![k.clone(), v.clone()]))
+                .collect(),
+        )),
+        (Dict(entries), "get", [key]) => {
+            Some(lookup(entries, key).cloned().unwrap_or(ConstValue::None))
+        }
+        (Dict(entries), "get", [key, default]) => {
+            Some(lookup(entries, key).unwrap_or(default).clone())
+        }
+        (Str(s), "upper", []) => Some(Str(s.to_uppercase())),
+        (Str(s), "lower", []) => Some(Str(s.to_lowercase())),
+        (Str(s), "strip", []) => Some(Str(s.trim().to_string())),
+        (Str(s), "split", []) => Some(List(
+            s.split_whitespace()
+                .map(|part| Str(part.to_string()))
+                .collect(),
+        )),
+        (Str(s), "split", [Str(sep)]) if !sep.is_empty() => Some(List(
+            s.split(sep.as_str())
+                .map(|part| Str(part.to_string()))
+                .collect(),
+        )),
+        (Str(sep), "join", [items]) => {
+            let parts = items
+                .elements()?
+                .into_iter()
+                .map(|item| match item {
+                    Str(part) => Some(part),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(Str(parts.join(sep.as_str())))
+        }
+        (Str(s), "replace", [Str(from), Str(to)]) if !from.is_empty() => {
+            Some(Str(s.replace(from.as_str(), to)))
+        }
+        (Str(s), "startswith", [Str(prefix)]) => Some(Bool(s.starts_with(prefix.as_str()))),
+        (Str(s), "endswith", [Str(suffix)]) => Some(Bool(s.ends_with(suffix.as_str()))),
+        (List(items) | Tuple(items), "count", [x]) => {
+            Some(Int(items.iter().filter(|item| item.py_eq(x)).count() as i64))
+        }
+        (List(items) | Tuple(items), "index", [x]) => items
+            .iter()
+            .position(|item| item.py_eq(x))
+            .map(|i| Int(i as i64)),
+        _ => None,
+    }
+}
+
+/// A total order over the values `sorted`, `min` and `max` accept; mixing
+/// numbers and strings (a `TypeError` in Python) has no key
+#[derive(Debug)]
+enum SortKey {
+    Num(f64),
+    Int(i64),
+    Str(String),
+    Seq(Vec<SortKey>),
+}
+
+impl SortKey {
+    fn of(value: &ConstValue) -> Option<Self> {
+        match value {
+            Int(_) | Bool(_) => value.as_int().map(SortKey::Int),
+            Float(f) if !f.is_nan() => Some(SortKey::Num(*f)),
+            Str(s) => Some(SortKey::Str(s.clone())),
+            List(items) | Tuple(items) => items
+                .iter()
+                .map(SortKey::of)
+                .collect::<Option<Vec<_>>>()
+                .map(SortKey::Seq),
+            _ => None,
+        }
+    }
+
+    fn rank(&self) -> u8 {
+        match self {
+            SortKey::Num(_) | SortKey::Int(_) => 0,
+            SortKey::Str(_) => 1,
+            SortKey::Seq(_) => 2,
+        }
+    }
+
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Int(a), SortKey::Int(b)) => a.cmp(b),
+            (SortKey::Int(a), SortKey::Num(b)) => (*a as f64).total_cmp(b),
+            (SortKey::Num(a), SortKey::Int(b)) => a.total_cmp(&(*b as f64)),
+            (SortKey::Num(a), SortKey::Num(b)) => a.total_cmp(b),
+            (SortKey::Str(a), SortKey::Str(b)) => a.cmp(b),
+            (SortKey::Seq(a), SortKey::Seq(b)) => {
+                for (x, y) in a.iter().zip(b) {
+                    match x.compare(y) {
+                        Ordering::Equal => continue,
+                        ordering => return ordering,
+                    }
+                }
+                a.len().cmp(&b.len())
+            }
+            _ => self.rank().cmp(&other.rank()),
+        }
+    }
+}
+
+/// The keyed items, provided their keys are all numbers, all strings or all
+/// sequences
+fn comparable(keyed: Vec<(SortKey, ConstValue)>) -> Option<Vec<(SortKey, ConstValue)>> {
+    let rank = keyed.first().map(|(key, _)| key.rank());
+    keyed
+        .iter()
+        .all(|(key, _)| Some(key.rank()) == rank)
+        .then_some(keyed)
+}
+
+/// Reversing the comparison keeps equal items in their original order, as
+/// `sorted(..., reverse=True)` does
+fn order(ordering: Ordering, reverse: bool) -> Ordering {
+    if reverse {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// `repr` of a float: `2.0`, `0.1`, `1e+20`, `1e-05`
+fn float_repr(f: f64) -> String {
+    if f.is_nan() {
+        return "nan".to_string();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    let abs = f.abs();
+    if abs != 0.0 && !(1e-4..1e16).contains(&abs) {
+        let text = format!("{:e}", f);
+        let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+        let (sign, digits) = match exponent.strip_prefix('-') {
+            Some(digits) => ('-', digits),
+            None => ('+', exponent),
+        };
+        return format!("{}e{}{:0>2}", mantissa, sign, digits);
+    }
+    if f.fract() == 0.0 {
+        format!("{:.1}", f)
+    } else {
+        f.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    /// Evaluates the module's constants in order, as rust_gen does
+    fn eval_constants(source: &str) -> HashMap<String, Option<ConstValue>> {
+        let hir = DepylerPipeline::new().parse_to_hir(source).unwrap();
+        let mut evaluator = ConstEvaluator::new();
+        let mut values = HashMap::new();
+        for constant in &hir.constants {
+            let value = evaluator.eval(&constant.value);
+            if let Some(value) = &value {
+                evaluator.define(&constant.name, value.clone());
+            }
+            values.insert(constant.name.clone(), value);
+        }
+        values
+    }
+
+    fn ints(values: &[i64]) -> ConstValue {
+        List(values.iter().map(|n| Int(*n)).collect())
+    }
+
+    fn strs(values: &[&str]) -> ConstValue {
+        List(values.iter().map(|s| Str(s.to_string())).collect())
+    }
+
+    #[test]
+    fn test_folds_comprehensions_and_builtins() {
+        let values = eval_constants(
+            r#"
+SIZES = [2**i for i in range(8)]
+RAW = {"beta": 2, "alpha": 1, "gamma": 3}
+NAMES = sorted(RAW.keys())
+TOTAL = sum(SIZES) // len(SIZES)
+EVENS = [n for n in range(10) if n % 2 == 0][::-1]
+LABEL = f"{len(NAMES)} names, max {max(SIZES)}"
+BY_VALUE = sorted(RAW.items(), key=lambda kv: kv[1], reverse=True)
+"#,
+        );
+        assert_eq!(values["SIZES"], Some(ints(&[1, 2, 4, 8, 16, 32, 64, 128])));
+        assert_eq!(values["NAMES"], Some(strs(&["alpha", "beta", "gamma"])));
+        assert_eq!(values["TOTAL"], Some(Int(31)));
+        assert_eq!(values["EVENS"], Some(ints(&[8, 6, 4, 2, 0])));
+        assert_eq!(values["LABEL"], Some(Str("3 names, max 128".to_string())));
+        let by_value = match &values["BY_VALUE"] {
+            Some(List(items)) => items.iter().map(|pair| pair.py_repr()).collect::<Vec<_>>(),
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(by_value, ["('gamma', 3)", "('beta', 2)", "('alpha', 1)"]);
+    }
+
+    #[test]
+    fn test_python_semantics() {
+        let mut evaluator = ConstEvaluator::new();
+        let mut eval = |source: &str| {
+            let hir = DepylerPipeline::new()
+                .parse_to_hir(&format!("X = {}\n", source))
+                .unwrap();
+            evaluator.eval(&hir.constants[0].value)
+        };
+        assert_eq!(eval("-7 // 2"), Some(Int(-4)));
+        assert_eq!(eval("-7 % 3"), Some(Int(2)));
+        assert_eq!(eval("7 / 2"), Some(Float(3.5)));
+        assert_eq!(eval("2 ** -1"), Some(Float(0.5)));
+        assert_eq!(eval("'ab' * 3"), Some(Str("ababab".to_string())));
+        assert_eq!(eval("'x' in 'xyz' and 3"), Some(Int(3)));
+        assert_eq!(eval("1 == 1.0"), Some(Bool(true)));
+        assert_eq!(
+            eval("str(1e20) + str(2.0)"),
+            Some(Str("1e+202.0".to_string()))
+        );
+        assert_eq!(eval("'hello'[-1]"), Some(Str("o".to_string())));
+    }
+
+    #[test]
+    fn test_unsupported_or_raising_expressions_are_not_folded() {
+        let values = eval_constants(
+            r#"
+import os
+HOME = os.getcwd()
+START = compute()
+RATIO = 1 / 0
+HUGE = list(range(10**9))
+OVERFLOW = 2**64
+ORDERED = list({3, 1, 2})
+MIXED = sorted([1, "a"])
+"#,
+        );
+        for name in [
+            "HOME", "START", "RATIO", "HUGE", "OVERFLOW", "ORDERED", "MIXED",
+        ] {
+            assert_eq!(values[name], None, "{} should not fold", name);
+        }
+    }
+
+    #[test]
+    fn test_folded_constants_are_generated_as_static_data() {
+        let code = DepylerPipeline::new()
+            .transpile(
+                r#"
+SIZES = [2**i for i in range(8)]
+RAW = {"beta": 2, "alpha": 1}
+NAMES = sorted(RAW.keys())
+TIMEOUT: float = 2 * 30
+"#,
+            )
+            .unwrap();
+        let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+        assert!(code.contains("pubconstSIZES:&[i32]=&[1,2,4,8,16,32,64,128];"));
+        assert!(code.contains("pubconstNAMES:&[&str]=&[\"alpha\",\"beta\"];"));
+        assert!(code.contains("pubconstTIMEOUT:f64=60.0;"));
+    }
+
+    #[test]
+    fn test_const_tokens() {
+        let tokens = |value: ConstValue| {
+            value
+                .to_const_tokens()
+                .map(|(ty, value)| (ty.to_string(), value.to_string()))
+        };
+        assert_eq!(
+            tokens(ints(&[1, -2])),
+            Some(("& [i32]".to_string(), "& [1 , - 2]".to_string()))
+        );
+        assert_eq!(
+            tokens(List(vec![Int(1), Int(1 << 40)])),
+            Some(("& [i64]".to_string(), "& [1 , 1099511627776]".to_string()))
+        );
+        assert_eq!(
+            tokens(Tuple(vec![Str("a".to_string())])),
+            Some(("(& str ,)".to_string(), "(\"a\" ,)".to_string()))
+        );
+        assert_eq!(tokens(List(vec![Int(1), Str("a".to_string())])), None);
+        assert_eq!(tokens(List(Vec::new())), None);
+        assert_eq!(tokens(Set(vec![Int(1)])), None);
+    }
+}
@@ -0,0 +1,449 @@
+//! Detection of generators used as coroutines
+//!
+//! Generators become structs implementing `Iterator`, which only ever hand
+//! values out. Code that drives a generator as a coroutine, sending values
+//! in with `gen.send(x)`, stopping it with `gen.close()` or raising into it
+//! with `gen.throw(e)`, and generators that receive those values with
+//! `x = yield v`, have no such translation. [`check`] rejects them with one
+//! line per usage site instead of emitting Rust that rustc rejects.
+
+use crate::hir::{HirExpr, HirModule, HirParam, HirStmt, Type};
+use crate::hir_spans::HirSpans;
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// How a coroutine-style usage drives the generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoroutineUseKind {
+    /// `gen.send(value)`
+    Send,
+    /// `gen.close()`
+    Close,
+    /// `gen.throw(exc)`
+    Throw,
+    /// `x = yield v`: the generator reads values sent into it
+    YieldValue,
+}
+
+impl CoroutineUseKind {
+    fn from_method(method: &str) -> Option<Self> {
+        match method {
+            "send" => Some(Self::Send),
+            "close" => Some(Self::Close),
+            "throw" => Some(Self::Throw),
+            _ => None,
+        }
+    }
+}
+
+/// One place a generator is used as a coroutine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoroutineUse {
+    pub kind: CoroutineUseKind,
+    /// Function or `Class.method` the usage is in
+    pub function: String,
+    /// The generator function, when known; for a `yield` used as a value it
+    /// is `function` itself
+    pub generator: Option<String>,
+    /// How the generator object is written at the usage site, `gen` or
+    /// `make_gen(...)`
+    pub receiver: String,
+    /// Path of the statement in `function`, as [`FunctionSpans::statement`]
+    /// takes it
+    ///
+    /// [`FunctionSpans::statement`]: crate::hir_spans::FunctionSpans::statement
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for CoroutineUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let generator = match &self.generator {
+            Some(name) => format!("generator `{}`", name),
+            None => format!("generator `{}`", self.receiver),
+        };
+        match self.kind {
+            CoroutineUseKind::Send => write!(
+                f,
+                "`{}.send(...)` in `{}` sends a value into {}",
+                self.receiver, self.function, generator
+            ),
+            CoroutineUseKind::Close => write!(
+                f,
+                "`{}.close()` in `{}` closes {}",
+                self.receiver, self.function, generator
+            ),
+            CoroutineUseKind::Throw => write!(
+                f,
+                "`{}.throw(...)` in `{}` raises into {}",
+                self.receiver, self.function, generator
+            ),
+            CoroutineUseKind::YieldValue => write!(
+                f,
+                "`yield` used as a value in {} receives values sent into it",
+                generator
+            ),
+        }
+    }
+}
+
+/// Every coroutine-style usage of a generator in `module`, in source order
+pub fn find(module: &HirModule) -> Vec<CoroutineUse> {
+    let generators: HashSet<&str> = module
+        .functions
+        .iter()
+        .filter(|f| f.properties.is_generator && !f.properties.is_context_manager)
+        .map(|f| f.name.as_str())
+        .collect();
+
+    let mut uses = Vec::new();
+    for func in &module.functions {
+        let mut finder = Finder::new(&func.name, &func.params, &func.body, &generators);
+        finder.receives_values = generators.contains(func.name.as_str());
+        finder.stmts(&func.body, &mut Vec::new());
+        uses.append(&mut finder.uses);
+    }
+    for class in &module.classes {
+        for method in &class.methods {
+            let name = format!("{}.{}", class.name, method.name);
+            let mut finder = Finder::new(&name, &method.params, &method.body, &generators);
+            finder.stmts(&method.body, &mut Vec::new());
+            uses.append(&mut finder.uses);
+        }
+    }
+    uses
+}
+
+/// Fails with a report of every coroutine-style usage in `module`
+///
+/// With `spans` each usage is prefixed with the line and column of its
+/// statement.
+pub fn check(module: &HirModule, spans: Option<&HirSpans>) -> Result<()> {
+    let uses = find(module);
+    if uses.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = uses
+        .iter()
+        .map(|usage| match position(usage, spans) {
+            Some((line, column)) => format!("  line {}, column {}: {}", line, column, usage),
+            None => format!("  {}", usage),
+        })
+        .collect();
+    bail!(
+        "Generators used as coroutines are not supported; they become Rust iterators, \
+         which cannot receive sent values, be closed or have exceptions thrown into them:\n{}",
+        lines.join("\n")
+    )
+}
+
+fn position(usage: &CoroutineUse, spans: Option<&HirSpans>) -> Option<(usize, usize)> {
+    let spans = spans?;
+    let stmt = spans.function(&usage.function)?.statement(&usage.path)?;
+    let position = spans.position(stmt.span.start);
+    Some((position.line, position.column))
+}
+
+/// Walks one function body, following the statement paths of `HirSpans`
+struct Finder<'a> {
+    function: &'a str,
+    generators: &'a HashSet<&'a str>,
+    /// Locals and parameters holding a generator object, with the
+    /// generator function when known
+    objects: HashMap<String, Option<String>>,
+    /// Whether the function is itself a generator, whose `yield` values
+    /// could be sent into it
+    receives_values: bool,
+    uses: Vec<CoroutineUse>,
+}
+
+impl<'a> Finder<'a> {
+    fn new(
+        function: &'a str,
+        params: &[HirParam],
+        body: &[HirStmt],
+        generators: &'a HashSet<&'a str>,
+    ) -> Self {
+        let mut objects = HashMap::new();
+        for param in params {
+            if let Type::Generic { base, .. } = &param.ty {
+                if base == "Generator" {
+                    objects.insert(param.name.clone(), None);
+                }
+            }
+        }
+        collect_objects(body, generators, &mut objects);
+        Self {
+            function,
+            generators,
+            objects,
+            receives_values: false,
+            uses: Vec::new(),
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        for (index, stmt) in stmts.iter().enumerate() {
+            path.push(index);
+            self.stmt(stmt, path);
+            path.pop();
+        }
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, path: &mut Vec<usize>) {
+        match stmt {
+            // A bare `yield v` hands a value out and ignores what is sent
+            HirStmt::Expr(HirExpr::Yield { value }) => {
+                if let Some(value) = value {
+                    self.expr(value, path);
+                }
+            }
+            HirStmt::Expr(expr) | HirStmt::Assign { value: expr, .. } => self.expr(expr, path),
+            HirStmt::Return(value) => value.iter().for_each(|v| self.expr(v, path)),
+            HirStmt::Raise { exception, cause } => exception
+                .iter()
+                .chain(cause)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::Assert { test, msg } => std::iter::once(test)
+                .chain(msg)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition, path);
+                self.block(0, then_body, path);
+                if let Some(else_body) = else_body {
+                    self.block(1, else_body, path);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition, path);
+                self.block(0, body, path);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter, path);
+                self.block(0, body, path);
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context, path);
+                self.block(0, body, path);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.block(0, body, path);
+                for (i, handler) in handlers.iter().enumerate() {
+                    self.block(1 + i, &handler.body, path);
+                }
+                if let Some(orelse) = orelse {
+                    self.block(1 + handlers.len(), orelse, path);
+                }
+                if let Some(finalbody) = finalbody {
+                    self.block(2 + handlers.len(), finalbody, path);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject, path);
+                for (i, case) in cases.iter().enumerate() {
+                    self.block(i, &case.body, path);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn block(&mut self, block: usize, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        path.push(block);
+        self.stmts(stmts, path);
+        path.pop();
+    }
+
+    fn expr(&mut self, expr: &HirExpr, path: &[usize]) {
+        match expr {
+            HirExpr::Yield { .. } if self.receives_values => {
+                self.report(
+                    CoroutineUseKind::YieldValue,
+                    Some(self.function.to_string()),
+                    self.function.to_string(),
+                    path,
+                );
+            }
+            HirExpr::MethodCall { object, method, .. } => {
+                if let Some(kind) = CoroutineUseKind::from_method(method) {
+                    if let Some((generator, receiver)) = self.generator_object(object) {
+                        self.report(kind, generator, receiver, path);
+                    }
+                }
+            }
+            _ => {}
+        }
+        for child in expr.children() {
+            self.expr(child, path);
+        }
+    }
+
+    /// The generator `expr` evaluates to, and how it is written, when it is
+    /// a generator object
+    fn generator_object(&self, expr: &HirExpr) -> Option<(Option<String>, String)> {
+        match expr {
+            HirExpr::Var(name) => {
+                let generator = self.objects.get(name)?;
+                Some((generator.clone(), name.clone()))
+            }
+            HirExpr::Call { func, .. } if self.generators.contains(func.as_str()) => {
+                Some((Some(func.clone()), format!("{}(...)", func)))
+            }
+            _ => None,
+        }
+    }
+
+    fn report(
+        &mut self,
+        kind: CoroutineUseKind,
+        generator: Option<String>,
+        receiver: String,
+        path: &[usize],
+    ) {
+        self.uses.push(CoroutineUse {
+            kind,
+            function: self.function.to_string(),
+            generator,
+            receiver,
+            path: path.to_vec(),
+        });
+    }
+}
+
+/// Locals assigned the result of calling a generator function, anywhere in
+/// `stmts`
+fn collect_objects(
+    stmts: &[HirStmt],
+    generators: &HashSet<&str>,
+    objects: &mut HashMap<String, Option<String>>,
+) {
+    use crate::hir::AssignTarget;
+
+    for stmt in stmts {
+        match stmt {
+            HirStmt::Assign {
+                target: AssignTarget::Symbol(name),
+                value: HirExpr::Call { func, .. },
+                ..
+            } if generators.contains(func.as_str()) => {
+                objects.insert(name.clone(), Some(func.clone()));
+            }
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                collect_objects(then_body, generators, objects);
+                if let Some(else_body) = else_body {
+                    collect_objects(else_body, generators, objects);
+                }
+            }
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => collect_objects(body, generators, objects),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                collect_objects(body, generators, objects);
+                for handler in handlers {
+                    collect_objects(&handler.body, generators, objects);
+                }
+                for block in orelse.iter().chain(finalbody) {
+                    collect_objects(block, generators, objects);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                for case in cases {
+                    collect_objects(&case.body, generators, objects);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = "\
+def running_total():
+    total = 0
+    while True:
+        value = yield total
+        total += value
+
+def counter(n: int):
+    i = 0
+    while i < n:
+        yield i
+        i += 1
+
+def drive() -> int:
+    acc = running_total()
+    next(acc)
+    acc.send(5)
+    c = counter(3)
+    if True:
+        c.close()
+    return 0
+";
+
+    fn module() -> HirModule {
+        DepylerPipeline::new().parse_to_hir(SOURCE).unwrap()
+    }
+
+    #[test]
+    fn test_finds_send_close_and_yield_values() {
+        let uses = find(&module());
+        let kinds: Vec<_> = uses.iter().map(|u| (u.kind, u.function.as_str())).collect();
+
+        assert_eq!(
+            kinds,
+            [
+                (CoroutineUseKind::YieldValue, "running_total"),
+                (CoroutineUseKind::Send, "drive"),
+                (CoroutineUseKind::Close, "drive"),
+            ]
+        );
+        assert_eq!(uses[1].generator.as_deref(), Some("running_total"));
+        assert_eq!(uses[2].path, [4, 0, 0]);
+    }
+
+    #[test]
+    fn test_plain_generators_pass() {
+        let module = DepylerPipeline::new()
+            .parse_to_hir("def counter(n: int):\n    i = 0\n    while i < n:\n        yield i\n        i += 1\n\ndef use() -> int:\n    c = counter(3)\n    return next(c)\n")
+            .unwrap();
+
+        assert!(find(&module).is_empty());
+        assert!(check(&module, None).is_ok());
+    }
+
+    #[test]
+    fn test_report_names_usage_sites() {
+        let spans = HirSpans::parse(SOURCE).unwrap();
+        let message = check(&module(), Some(&spans)).unwrap_err().to_string();
+
+        assert!(message
+            .contains("line 4, column 9: `yield` used as a value in generator `running_total`"));
+        assert!(message.contains(
+            "line 16, column 5: `acc.send(...)` in `drive` sends a value into generator `running_total`"
+        ));
+        assert!(message
+            .contains("line 19, column 9: `c.close()` in `drive` closes generator `counter`"));
+    }
+}
@@ -0,0 +1,1004 @@
+//! Control-flow graph and liveness analysis over function bodies
+//!
+//! The graph has one node per simple statement and one per branch header
+//! (the condition of an `if` or `while`, the iterator of a `for`, the
+//! subject of a `match`, ...). Liveness runs backward over it: a variable is
+//! live after a node when some path from there reads it before assigning it
+//! again. An assignment whose variable is dead right after it is a dead
+//! store, and nodes no path from the entry reaches are unreachable code.
+//!
+//! Codegen can drop dead stores whose value has no side effects, see
+//! [`elide_dead_stores`], and rename the variables no statement reads, see
+//! [`mark_unused_bindings`].
+
+pub mod cfg;
+
+use crate::hir::{AssignTarget, BinOp, HirExpr, HirFunction, HirStmt};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+
+/// Position of a statement in a function body
+///
+/// Indices alternate between a statement and one of its blocks, so
+/// `[2, 1, 0]` is the first statement in block 1 of the third top-level
+/// statement. Blocks are numbered per statement: `if` then/else are 0/1,
+/// loop and `with` bodies are 0, `try` has its body at 0, handlers at
+/// 1..=n, `else` at n + 1 and `finally` at n + 2, and `match` case i is i.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct StmtPath(pub Vec<usize>);
+
+impl StmtPath {
+    fn child(&self, block: usize, index: usize) -> StmtPath {
+        let mut path = self.0.clone();
+        if !path.is_empty() {
+            path.push(block);
+        }
+        path.push(index);
+        StmtPath(path)
+    }
+
+    fn is_inside(&self, ancestor: &StmtPath) -> bool {
+        self.0.len() > ancestor.0.len() && self.0.starts_with(&ancestor.0)
+    }
+}
+
+impl fmt::Display for StmtPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts: Vec<String> = self.0.iter().map(|i| i.to_string()).collect();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// An assignment whose value is never read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadStore {
+    pub path: StmtPath,
+    pub variable: String,
+}
+
+/// Dead stores and unreachable statements of one function body
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeadCodeReport {
+    pub dead_stores: Vec<DeadStore>,
+    /// Outermost unreachable statements; statements nested inside them are
+    /// not listed separately
+    pub unreachable: Vec<StmtPath>,
+}
+
+impl DeadCodeReport {
+    pub fn is_empty(&self) -> bool {
+        self.dead_stores.is_empty() && self.unreachable.is_empty()
+    }
+}
+
+/// Find dead stores and unreachable statements in a function body
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::dataflow::analyze_dead_code;
+/// use depyler_core::hir::*;
+///
+/// let assign = |name: &str, value: i64| HirStmt::Assign {
+///     target: AssignTarget::Symbol(name.to_string()),
+///     value: HirExpr::Literal(Literal::Int(value)),
+///     type_annotation: None,
+/// };
+/// let body = vec![
+///     assign("x", 1),
+///     assign("x", 2),
+///     HirStmt::Return(Some(HirExpr::Var("x".to_string()))),
+///     assign("y", 3),
+/// ];
+///
+/// let report = analyze_dead_code(&body);
+/// assert_eq!(report.dead_stores.len(), 1);
+/// assert_eq!(report.dead_stores[0].path.to_string(), "0");
+/// assert_eq!(report.unreachable.len(), 1);
+/// assert_eq!(report.unreachable[0].to_string(), "3");
+/// ```
+pub fn analyze_dead_code(body: &[HirStmt]) -> DeadCodeReport {
+    let cfg = Cfg::build(body, &HashSet::new());
+    let reachable = cfg.reachable();
+    let dead_stores = cfg
+        .dead_store_nodes(&reachable)
+        .map(|node| DeadStore {
+            path: node.path.clone(),
+            variable: node.store.clone().unwrap_or_default(),
+        })
+        .collect();
+
+    let mut unreachable: Vec<StmtPath> = cfg
+        .nodes
+        .iter()
+        .enumerate()
+        .filter(|(id, node)| !reachable[*id] && !node.path.0.is_empty())
+        .map(|(_, node)| node.path.clone())
+        .collect();
+    unreachable.sort();
+    unreachable.dedup();
+    let outermost = unreachable
+        .iter()
+        .filter(|path| !unreachable.iter().any(|other| path.is_inside(other)))
+        .cloned()
+        .collect();
+
+    DeadCodeReport {
+        dead_stores,
+        unreachable: outermost,
+    }
+}
+
+/// Drop dead stores whose value can be evaluated without side effects
+///
+/// Calls count as side-effect free when they call one of `effect_free`, see
+/// [`effect_free_functions`]. A store is kept when the variable is assigned
+/// again in a block nested below it, as the generated code declares the
+/// variable at the store. Removing a store can make the stores feeding it
+/// dead as well, so this repeats until nothing changes.
+pub fn elide_dead_stores(body: &[HirStmt], effect_free: &HashSet<String>) -> Vec<HirStmt> {
+    elide(body, effect_free).0
+}
+
+/// [`elide_dead_stores`], also returning the number of stores dropped
+fn elide(body: &[HirStmt], effect_free: &HashSet<String>) -> (Vec<HirStmt>, usize) {
+    let mut body = body.to_vec();
+    let mut elided = 0;
+    loop {
+        let cfg = Cfg::build(&body, effect_free);
+        let reachable = cfg.reachable();
+        let removable: HashSet<StmtPath> = cfg
+            .dead_store_nodes(&reachable)
+            .filter(|node| node.side_effect_free && !cfg.redefined_below(node))
+            .map(|node| node.path.clone())
+            .collect();
+        if removable.is_empty() {
+            return (body, elided);
+        }
+        elided += removable.len();
+        body = prune_block(&body, &StmtPath(Vec::new()), 0, &removable);
+    }
+}
+
+/// Drop the dead stores of `body` as [`elide_dead_stores`] does, then
+/// rename the variables it never reads with [`mark_unused_bindings`],
+/// returning the number of stores dropped and variables renamed
+pub fn clean_up_unused(body: &mut Vec<HirStmt>, effect_free: &HashSet<String>) -> usize {
+    let (elided_body, elided) = elide(body, effect_free);
+    *body = elided_body;
+    elided + mark_unused_bindings(body)
+}
+
+/// Module functions whose calls can be dropped when their result is unused
+///
+/// A function qualifies when the purity analysis found it pure and unable to
+/// raise, and its body only assigns local variables, loops over literals and
+/// `range()`, and computes values from operators and calls of functions that
+/// qualify themselves. Recursive functions never do, so dropping a call
+/// cannot skip an endless recursion.
+pub fn effect_free_functions(functions: &[HirFunction]) -> HashSet<String> {
+    let mut effect_free = HashSet::new();
+    loop {
+        let found: Vec<String> = functions
+            .iter()
+            .filter(|func| {
+                !effect_free.contains(&func.name)
+                    && func.properties.is_pure
+                    && !func.properties.can_fail
+                    && !func.properties.is_generator
+                    && !func.properties.is_async
+                    && body_is_effect_free(&func.body, &effect_free)
+            })
+            .map(|func| func.name.clone())
+            .collect();
+        if found.is_empty() {
+            return effect_free;
+        }
+        effect_free.extend(found);
+    }
+}
+
+fn body_is_effect_free(body: &[HirStmt], effect_free: &HashSet<String>) -> bool {
+    let free = |expr: &HirExpr| is_side_effect_free(expr, effect_free);
+    body.iter().all(|stmt| match stmt {
+        HirStmt::Assign { target, value, .. } => is_local_target(target) && free(value),
+        HirStmt::Expr(expr) | HirStmt::Return(Some(expr)) => free(expr),
+        HirStmt::Return(None) | HirStmt::Pass | HirStmt::Break { .. } => true,
+        HirStmt::Continue { .. } => true,
+        HirStmt::If {
+            condition,
+            then_body,
+            else_body,
+        } => {
+            free(condition)
+                && body_is_effect_free(then_body, effect_free)
+                && else_body
+                    .as_deref()
+                    .map_or(true, |body| body_is_effect_free(body, effect_free))
+        }
+        HirStmt::For { target, iter, body } => {
+            let finite = match iter {
+                HirExpr::List(_) | HirExpr::Tuple(_) => free(iter),
+                HirExpr::Call { func, args, kwargs } => {
+                    func == "range" && kwargs.is_empty() && args.iter().all(free)
+                }
+                _ => false,
+            };
+            finite && is_local_target(target) && body_is_effect_free(body, effect_free)
+        }
+        _ => false,
+    })
+}
+
+fn is_local_target(target: &AssignTarget) -> bool {
+    match target {
+        AssignTarget::Symbol(_) => true,
+        AssignTarget::Tuple(targets) => targets.iter().all(is_local_target),
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => false,
+    }
+}
+
+/// Rename the variables `body` assigns but never reads to `_name`, the way
+/// Rust spells a deliberately unused binding, returning how many were
+///
+/// Only assignment targets are renamed; loop targets and `match` captures
+/// are marked by codegen. A variable is left alone when `_name` is taken.
+pub fn mark_unused_bindings(body: &mut [HirStmt]) -> usize {
+    let cfg = Cfg::build(body, &HashSet::new());
+    let read: HashSet<String> = cfg
+        .nodes
+        .iter()
+        .flat_map(|node| node.uses.iter().cloned())
+        .collect();
+    let named: HashSet<String> = cfg
+        .nodes
+        .into_iter()
+        .flat_map(|node| node.uses.into_iter().chain(node.defs))
+        .collect();
+
+    let mut assigned = HashSet::new();
+    visit_stmts_mut(body, &mut |stmt| {
+        if let HirStmt::Assign { target, .. } = stmt {
+            target_defs(target, &mut assigned);
+        }
+    });
+    let unused: HashSet<String> = assigned
+        .into_iter()
+        .filter(|name| {
+            !name.starts_with('_') && !read.contains(name) && !named.contains(&format!("_{}", name))
+        })
+        .collect();
+    if !unused.is_empty() {
+        visit_stmts_mut(body, &mut |stmt| {
+            if let HirStmt::Assign { target, .. } = stmt {
+                rename_target(target, &unused);
+            }
+        });
+    }
+    unused.len()
+}
+
+fn rename_target(target: &mut AssignTarget, unused: &HashSet<String>) {
+    match target {
+        AssignTarget::Symbol(name) if unused.contains(name.as_str()) => {
+            *name = format!("_{}", name);
+        }
+        AssignTarget::Tuple(targets) => targets
+            .iter_mut()
+            .for_each(|target| rename_target(target, unused)),
+        _ => {}
+    }
+}
+
+/// Calls `visit` with every statement of `body`, nested ones included
+fn visit_stmts_mut(body: &mut [HirStmt], visit: &mut impl FnMut(&mut HirStmt)) {
+    for stmt in body.iter_mut() {
+        visit(stmt);
+        match stmt {
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                visit_stmts_mut(then_body, visit);
+                if let Some(else_body) = else_body {
+                    visit_stmts_mut(else_body, visit);
+                }
+            }
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => visit_stmts_mut(body, visit),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                visit_stmts_mut(body, visit);
+                for handler in handlers {
+                    visit_stmts_mut(&mut handler.body, visit);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    visit_stmts_mut(block, visit);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                for case in cases {
+                    visit_stmts_mut(&mut case.body, visit);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Node {
+    path: StmtPath,
+    uses: HashSet<String>,
+    defs: HashSet<String>,
+    /// Variable written by a plain `name = value` statement
+    store: Option<String>,
+    side_effect_free: bool,
+    succs: Vec<usize>,
+}
+
+impl Node {
+    fn new(path: StmtPath) -> Self {
+        Node {
+            path,
+            uses: HashSet::new(),
+            defs: HashSet::new(),
+            store: None,
+            side_effect_free: false,
+            succs: Vec::new(),
+        }
+    }
+}
+
+/// Control-flow graph of one function body
+struct Cfg {
+    nodes: Vec<Node>,
+    entry: usize,
+}
+
+impl Cfg {
+    fn build(body: &[HirStmt], effect_free: &HashSet<String>) -> Self {
+        let mut builder = Builder {
+            nodes: vec![
+                Node::new(StmtPath(Vec::new())),
+                Node::new(StmtPath(Vec::new())),
+            ],
+            exit: 1,
+            loops: Vec::new(),
+            effect_free,
+        };
+        let exits = builder.block(body, &StmtPath(Vec::new()), 0, vec![0]);
+        builder.connect(&exits, 1);
+        Cfg {
+            nodes: builder.nodes,
+            entry: 0,
+        }
+    }
+
+    fn reachable(&self) -> Vec<bool> {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut queue = VecDeque::from([self.entry]);
+        seen[self.entry] = true;
+        while let Some(id) = queue.pop_front() {
+            for &succ in &self.nodes[id].succs {
+                if !seen[succ] {
+                    seen[succ] = true;
+                    queue.push_back(succ);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Variables live right after each node, solved backward to a fixed point
+    fn live_out(&self) -> Vec<HashSet<String>> {
+        let mut live_in = vec![HashSet::new(); self.nodes.len()];
+        let mut live_out = vec![HashSet::new(); self.nodes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for id in (0..self.nodes.len()).rev() {
+                let node = &self.nodes[id];
+                let out: HashSet<String> = node
+                    .succs
+                    .iter()
+                    .flat_map(|succ| live_in[*succ].iter().cloned())
+                    .collect();
+                let mut inn: HashSet<String> = out.difference(&node.defs).cloned().collect();
+                inn.extend(node.uses.iter().cloned());
+                if inn != live_in[id] || out != live_out[id] {
+                    live_in[id] = inn;
+                    live_out[id] = out;
+                    changed = true;
+                }
+            }
+        }
+        live_out
+    }
+
+    /// Reachable stores whose variable is dead right after them; names
+    /// starting with `_` are deliberately unused and never reported
+    fn dead_store_nodes<'a>(&'a self, reachable: &'a [bool]) -> impl Iterator<Item = &'a Node> {
+        let live_out = self.live_out();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(move |(id, node)| {
+                reachable[*id]
+                    && node
+                        .store
+                        .as_ref()
+                        .is_some_and(|var| !var.starts_with('_') && !live_out[*id].contains(var))
+            })
+            .map(|(_, node)| node)
+    }
+
+    /// Whether the variable `store` writes is also defined in a block nested
+    /// deeper than `store` under the block holding it
+    fn redefined_below(&self, store: &Node) -> bool {
+        let var = match &store.store {
+            Some(var) => var,
+            None => return false,
+        };
+        let path = &store.path.0;
+        let parent = &path[..path.len().saturating_sub(1)];
+        self.nodes.iter().any(|node| {
+            node.path.0.len() > path.len()
+                && node.path.0.starts_with(parent)
+                && node.defs.contains(var)
+        })
+    }
+}
+
+struct Builder<'a> {
+    nodes: Vec<Node>,
+    exit: usize,
+    /// Header node and pending `break` nodes of each enclosing loop
+    loops: Vec<(usize, Vec<usize>)>,
+    /// Functions whose calls have no side effects
+    effect_free: &'a HashSet<String>,
+}
+
+impl Builder<'_> {
+    fn add(&mut self, node: Node, preds: &[usize]) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(node);
+        self.connect(preds, id);
+        id
+    }
+
+    fn connect(&mut self, from: &[usize], to: usize) {
+        for &id in from {
+            if !self.nodes[id].succs.contains(&to) {
+                self.nodes[id].succs.push(to);
+            }
+        }
+    }
+
+    /// Add the nodes of `stmts`, returning the nodes that fall through to
+    /// whatever follows the block
+    fn block(
+        &mut self,
+        stmts: &[HirStmt],
+        parent: &StmtPath,
+        block: usize,
+        mut preds: Vec<usize>,
+    ) -> Vec<usize> {
+        for (index, stmt) in stmts.iter().enumerate() {
+            preds = self.stmt(stmt, parent.child(block, index), preds);
+        }
+        preds
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, path: StmtPath, preds: Vec<usize>) -> Vec<usize> {
+        let mut node = Node::new(path.clone());
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                collect_vars(value, &mut node.uses);
+                target_uses(target, &mut node.uses);
+                target_defs(target, &mut node.defs);
+                if let AssignTarget::Symbol(name) = target {
+                    node.store = Some(name.clone());
+                    node.side_effect_free = is_side_effect_free(value, self.effect_free);
+                }
+                vec![self.add(node, &preds)]
+            }
+            HirStmt::Expr(expr) => {
+                collect_vars(expr, &mut node.uses);
+                vec![self.add(node, &preds)]
+            }
+            HirStmt::Assert { test, msg } => {
+                collect_vars(test, &mut node.uses);
+                if let Some(msg) = msg {
+                    collect_vars(msg, &mut node.uses);
+                }
+                vec![self.add(node, &preds)]
+            }
+            HirStmt::Pass => vec![self.add(node, &preds)],
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    collect_vars(value, &mut node.uses);
+                }
+                let id = self.add(node, &preds);
+                self.connect(&[id], self.exit);
+                Vec::new()
+            }
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    collect_vars(expr, &mut node.uses);
+                }
+                let id = self.add(node, &preds);
+                self.connect(&[id], self.exit);
+                Vec::new()
+            }
+            HirStmt::Break { .. } => {
+                let id = self.add(node, &preds);
+                if let Some((_, breaks)) = self.loops.last_mut() {
+                    breaks.push(id);
+                }
+                Vec::new()
+            }
+            HirStmt::Continue { .. } => {
+                let id = self.add(node, &preds);
+                if let Some(&(header, _)) = self.loops.last() {
+                    self.connect(&[id], header);
+                }
+                Vec::new()
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                collect_vars(condition, &mut node.uses);
+                let header = self.add(node, &preds);
+                let mut exits = self.block(then_body, &path, 0, vec![header]);
+                match else_body {
+                    Some(else_body) => exits.extend(self.block(else_body, &path, 1, vec![header])),
+                    None => exits.push(header),
+                }
+                exits
+            }
+            HirStmt::While { condition, body } => {
+                collect_vars(condition, &mut node.uses);
+                let header = self.add(node, &preds);
+                let mut exits = self.loop_body(body, &path, header);
+                // `while True` only ends through `break`
+                if !matches!(condition, HirExpr::Literal(crate::hir::Literal::Bool(true))) {
+                    exits.push(header);
+                }
+                exits
+            }
+            HirStmt::For { target, iter, body } => {
+                collect_vars(iter, &mut node.uses);
+                let iter_node = self.add(node, &preds);
+                let mut binding = Node::new(path.clone());
+                target_defs(target, &mut binding.defs);
+                let header = self.add(binding, &[iter_node]);
+                let mut exits = self.loop_body(body, &path, header);
+                exits.push(header);
+                exits
+            }
+            HirStmt::With {
+                context,
+                target,
+                body,
+            } => {
+                collect_vars(context, &mut node.uses);
+                node.defs.extend(target.iter().cloned());
+                let id = self.add(node, &preds);
+                self.block(body, &path, 0, vec![id])
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                let first_body_node = self.nodes.len();
+                let body_exits = self.block(body, &path, 0, preds.clone());
+                // Any statement of the body may raise into a handler
+                let mut handler_preds = preds;
+                handler_preds.extend(first_body_node..self.nodes.len());
+
+                let mut exits = match orelse {
+                    Some(orelse) => self.block(orelse, &path, handlers.len() + 1, body_exits),
+                    None => body_exits,
+                };
+                for (index, handler) in handlers.iter().enumerate() {
+                    let mut binding = Node::new(path.clone());
+                    binding.defs.extend(handler.name.iter().cloned());
+                    let entry = self.add(binding, &handler_preds);
+                    exits.extend(self.block(&handler.body, &path, index + 1, vec![entry]));
+                }
+                match finalbody {
+                    Some(finalbody) => self.block(finalbody, &path, handlers.len() + 2, exits),
+                    None => exits,
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                collect_vars(subject, &mut node.uses);
+                let header = self.add(node, &preds);
+                let mut exits = vec![header];
+                for (index, case) in cases.iter().enumerate() {
+                    let mut binding = Node::new(path.clone());
+                    binding.defs.extend(case.pattern.bindings());
+                    if let Some(guard) = &case.guard {
+                        collect_vars(guard, &mut binding.uses);
+                    }
+                    let entry = self.add(binding, &[header]);
+                    exits.extend(self.block(&case.body, &path, index, vec![entry]));
+                }
+                exits
+            }
+        }
+    }
+
+    /// Add a loop body that returns to `header`, returning its `break` nodes
+    fn loop_body(&mut self, body: &[HirStmt], path: &StmtPath, header: usize) -> Vec<usize> {
+        self.loops.push((header, Vec::new()));
+        let body_exits = self.block(body, path, 0, vec![header]);
+        self.connect(&body_exits, header);
+        self.loops
+            .pop()
+            .map(|(_, breaks)| breaks)
+            .unwrap_or_default()
+    }
+}
+
+fn collect_vars(expr: &HirExpr, vars: &mut HashSet<String>) {
+    match expr {
+        HirExpr::Var(name) => {
+            vars.insert(name.clone());
+        }
+        // `f(x)` reads `f` when it is a local holding a function
+        HirExpr::Call { func, .. } => {
+            vars.insert(func.clone());
+        }
+        _ => {}
+    }
+    for child in expr.children() {
+        collect_vars(child, vars);
+    }
+}
+
+/// Variables read by writing to a target: the containers of `a[i] = ...`
+/// and `obj.attr = ...`
+fn target_uses(target: &AssignTarget, vars: &mut HashSet<String>) {
+    match target {
+        AssignTarget::Symbol(_) => {}
+        AssignTarget::Index { base, index } => {
+            collect_vars(base, vars);
+            collect_vars(index, vars);
+        }
+        AssignTarget::Attribute { value, .. } => collect_vars(value, vars),
+        AssignTarget::Tuple(targets) => targets.iter().for_each(|t| target_uses(t, vars)),
+    }
+}
+
+fn target_defs(target: &AssignTarget, vars: &mut HashSet<String>) {
+    match target {
+        AssignTarget::Symbol(name) => {
+            vars.insert(name.clone());
+        }
+        AssignTarget::Tuple(targets) => targets.iter().for_each(|t| target_defs(t, vars)),
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => {}
+    }
+}
+
+/// Values that can be skipped without changing behavior: no calls but those
+/// of `effect_free` functions, and no operators that may raise
+pub(crate) fn is_side_effect_free(expr: &HirExpr, effect_free: &HashSet<String>) -> bool {
+    let free = |expr: &HirExpr| is_side_effect_free(expr, effect_free);
+    match expr {
+        HirExpr::Literal(_) | HirExpr::Var(_) => true,
+        HirExpr::Binary { op, left, right } => {
+            !matches!(op, BinOp::Div | BinOp::FloorDiv | BinOp::Mod | BinOp::Pow)
+                && free(left)
+                && free(right)
+        }
+        HirExpr::Unary { operand, .. } => free(operand),
+        HirExpr::List(items) | HirExpr::Tuple(items) | HirExpr::Set(items) => {
+            items.iter().all(free)
+        }
+        HirExpr::Dict(pairs) => pairs.iter().all(|(k, v)| free(k) && free(v)),
+        HirExpr::Call { func, args, kwargs } => {
+            effect_free.contains(func)
+                && args.iter().all(free)
+                && kwargs.iter().all(|(_, value)| free(value))
+        }
+        _ => false,
+    }
+}
+
+fn prune_block(
+    stmts: &[HirStmt],
+    parent: &StmtPath,
+    block: usize,
+    removed: &HashSet<StmtPath>,
+) -> Vec<HirStmt> {
+    stmts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, stmt)| {
+            let path = parent.child(block, index);
+            (!removed.contains(&path)).then(|| prune_stmt(stmt, &path, removed))
+        })
+        .collect()
+}
+
+fn prune_stmt(stmt: &HirStmt, path: &StmtPath, removed: &HashSet<StmtPath>) -> HirStmt {
+    let prune = |stmts: &[HirStmt], block: usize| prune_block(stmts, path, block, removed);
+    match stmt {
+        HirStmt::If {
+            condition,
+            then_body,
+            else_body,
+        } => HirStmt::If {
+            condition: condition.clone(),
+            then_body: prune(then_body, 0),
+            else_body: else_body.as_ref().map(|body| prune(body, 1)),
+        },
+        HirStmt::While { condition, body } => HirStmt::While {
+            condition: condition.clone(),
+            body: prune(body, 0),
+        },
+        HirStmt::For { target, iter, body } => HirStmt::For {
+            target: target.clone(),
+            iter: iter.clone(),
+            body: prune(body, 0),
+        },
+        HirStmt::With {
+            context,
+            target,
+            body,
+        } => HirStmt::With {
+            context: context.clone(),
+            target: target.clone(),
+            body: prune(body, 0),
+        },
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => HirStmt::Try {
+            body: prune(body, 0),
+            handlers: handlers
+                .iter()
+                .enumerate()
+                .map(|(index, handler)| crate::hir::ExceptHandler {
+                    body: prune(&handler.body, index + 1),
+                    ..handler.clone()
+                })
+                .collect(),
+            orelse: orelse.as_ref().map(|body| prune(body, handlers.len() + 1)),
+            finalbody: finalbody
+                .as_ref()
+                .map(|body| prune(body, handlers.len() + 2)),
+        },
+        HirStmt::Match { subject, cases } => HirStmt::Match {
+            subject: subject.clone(),
+            cases: cases
+                .iter()
+                .enumerate()
+                .map(|(index, case)| crate::hir::MatchCase {
+                    body: prune(&case.body, index),
+                    ..case.clone()
+                })
+                .collect(),
+        },
+        other => other.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::Literal;
+
+    fn var(name: &str) -> HirExpr {
+        HirExpr::Var(name.to_string())
+    }
+
+    fn assign(name: &str, value: HirExpr) -> HirStmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(name.to_string()),
+            value,
+            type_annotation: None,
+        }
+    }
+
+    fn int(value: i64) -> HirExpr {
+        HirExpr::Literal(Literal::Int(value))
+    }
+
+    #[test]
+    fn test_store_read_in_one_branch_is_live() {
+        let body = vec![
+            assign("x", int(1)),
+            HirStmt::If {
+                condition: var("flag"),
+                then_body: vec![HirStmt::Return(Some(var("x")))],
+                else_body: Some(vec![assign("x", int(2))]),
+            },
+            HirStmt::Return(None),
+        ];
+        let report = analyze_dead_code(&body);
+        assert_eq!(report.dead_stores.len(), 1);
+        assert_eq!(report.dead_stores[0].path, StmtPath(vec![1, 1, 0]));
+    }
+
+    #[test]
+    fn test_loop_carried_store_is_live() {
+        let body = vec![
+            assign("total", int(0)),
+            HirStmt::For {
+                target: AssignTarget::Symbol("i".to_string()),
+                iter: var("items"),
+                body: vec![assign(
+                    "total",
+                    HirExpr::Binary {
+                        op: BinOp::Add,
+                        left: Box::new(var("total")),
+                        right: Box::new(var("i")),
+                    },
+                )],
+            },
+            HirStmt::Return(Some(var("total"))),
+        ];
+        assert!(analyze_dead_code(&body).is_empty());
+    }
+
+    #[test]
+    fn test_code_after_infinite_loop_and_break() {
+        let body = vec![
+            HirStmt::While {
+                condition: HirExpr::Literal(Literal::Bool(true)),
+                body: vec![HirStmt::Return(None), HirStmt::Pass],
+            },
+            HirStmt::Pass,
+        ];
+        let report = analyze_dead_code(&body);
+        assert_eq!(
+            report.unreachable,
+            vec![StmtPath(vec![0, 0, 1]), StmtPath(vec![1])]
+        );
+    }
+
+    #[test]
+    fn test_store_read_by_exception_handler_is_live() {
+        let body = vec![HirStmt::Try {
+            body: vec![
+                assign("step", int(1)),
+                HirStmt::Expr(HirExpr::Call {
+                    func: "work".to_string(),
+                    args: vec![],
+                    kwargs: vec![],
+                }),
+                assign("step", int(2)),
+            ],
+            handlers: vec![crate::hir::ExceptHandler {
+                exception_type: None,
+                name: None,
+                body: vec![HirStmt::Return(Some(var("step")))],
+            }],
+            orelse: None,
+            finalbody: None,
+        }];
+        assert!(analyze_dead_code(&body).is_empty());
+    }
+
+    #[test]
+    fn test_elision_keeps_calls_and_cascades() {
+        let call = HirExpr::Call {
+            func: "fetch".to_string(),
+            args: vec![],
+            kwargs: vec![],
+        };
+        let body = vec![
+            assign("a", int(1)),
+            assign("b", var("a")),
+            assign("c", call),
+            HirStmt::Return(None),
+        ];
+        let elided = elide_dead_stores(&body, &HashSet::new());
+        assert_eq!(elided.len(), 2);
+        assert!(matches!(
+            &elided[0],
+            HirStmt::Assign { target: AssignTarget::Symbol(name), .. } if name == "c"
+        ));
+    }
+
+    #[test]
+    fn test_store_redefined_in_nested_block_is_kept() {
+        // `x` is read after the `if`, so the generated code has to declare
+        // it before the branches assign it
+        let body = vec![
+            assign("x", int(0)),
+            HirStmt::If {
+                condition: var("flag"),
+                then_body: vec![assign("x", int(1))],
+                else_body: Some(vec![assign("x", int(2))]),
+            },
+            HirStmt::Return(Some(var("x"))),
+        ];
+        assert_eq!(analyze_dead_code(&body).dead_stores.len(), 1);
+        assert_eq!(elide_dead_stores(&body, &HashSet::new()), body);
+    }
+
+    #[test]
+    fn test_effect_free_functions() {
+        let source = r#"
+def square(x: int) -> int:
+    return x * x
+
+def sum_squares(n: int) -> int:
+    total = 0
+    for i in range(n):
+        total = total + square(i)
+    return total
+
+def shout(message: str) -> int:
+    print(message)
+    return 1
+
+def countdown(n: int) -> int:
+    if n == 0:
+        return 0
+    return countdown(n - 1)
+"#;
+        let module = crate::DepylerPipeline::new().parse_to_hir(source).unwrap();
+        let effect_free = effect_free_functions(&module.functions);
+        let mut names: Vec<&str> = effect_free.iter().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["square", "sum_squares"]);
+    }
+
+    #[test]
+    fn test_unused_bindings_are_marked() {
+        let mut body = vec![
+            assign("_total", int(0)),
+            assign("total", int(1)),
+            HirStmt::Assign {
+                target: AssignTarget::Tuple(vec![
+                    AssignTarget::Symbol("head".to_string()),
+                    AssignTarget::Symbol("rest".to_string()),
+                ]),
+                value: var("pair"),
+                type_annotation: None,
+            },
+            HirStmt::Return(Some(var("head"))),
+        ];
+        assert_eq!(mark_unused_bindings(&mut body), 1);
+        // `_total` is taken, so `total` keeps its name
+        assert!(matches!(
+            &body[1],
+            HirStmt::Assign { target: AssignTarget::Symbol(name), .. } if name == "total"
+        ));
+        assert!(matches!(
+            &body[2],
+            HirStmt::Assign { target: AssignTarget::Tuple(targets), .. }
+                if targets[1] == AssignTarget::Symbol("_rest".to_string())
+        ));
+    }
+
+    #[test]
+    fn test_local_function_call_reads_variable() {
+        let body = vec![
+            assign("f", var("g")),
+            HirStmt::Return(Some(HirExpr::Call {
+                func: "f".to_string(),
+                args: vec![],
+                kwargs: vec![],
+            })),
+        ];
+        assert!(analyze_dead_code(&body).is_empty());
+    }
+}
@@ -0,0 +1,569 @@
+//! Basic blocks for lowering generators into state machines
+//!
+//! The liveness graph in the parent module keeps one node per statement.
+//! Generators need coarser pieces: straight-line runs of statements that
+//! execute in one go, ended by a terminator that says where control goes
+//! next. A `yield` always ends a block and names the block execution
+//! resumes in, so every block is a resume point and codegen can turn the
+//! graph directly into the arms of `Iterator::next`.
+//!
+//! Statements that neither yield nor leave the generator (`return`, or a
+//! `break` / `continue` of a loop around them) are kept whole inside a
+//! block. Yields inside `try`, `with` and `match`, and values sent into the
+//! generator (`x = yield v`), are not lowered.
+
+use crate::hir::{AssignTarget, HirExpr, HirStmt};
+use thiserror::Error;
+
+/// Index of a block in [`BlockGraph::blocks`]; the entry block is 0
+pub type BlockId = usize;
+
+/// How control leaves a block
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminator {
+    Goto(BlockId),
+    Branch {
+        condition: HirExpr,
+        then_block: BlockId,
+        else_block: BlockId,
+    },
+    /// Start iterating `iter`, keeping the iterator in loop slot `slot`
+    IterStart {
+        slot: usize,
+        iter: HirExpr,
+        next: BlockId,
+    },
+    /// Bind the next item of loop slot `slot` to `target` and run `body`,
+    /// or go to `exhausted` once the iterator is done
+    IterNext {
+        slot: usize,
+        target: AssignTarget,
+        body: BlockId,
+        exhausted: BlockId,
+    },
+    /// Hand `value` to the caller; the next call continues at `resume`
+    Yield {
+        value: Option<HirExpr>,
+        resume: BlockId,
+    },
+    /// Finish the generator, optionally with a return value
+    Return(Option<HirExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    pub stmts: Vec<HirStmt>,
+    pub terminator: Terminator,
+}
+
+/// A `for` loop whose iterator lives across resume points
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoopSlot {
+    pub target: AssignTarget,
+    pub iter: HirExpr,
+    /// The body only yields the loop variable, as `yield from` does
+    pub forwards_items: bool,
+}
+
+/// The basic blocks of one generator body
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub loops: Vec<LoopSlot>,
+}
+
+/// A construct the lowering does not handle
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("cannot lower generator: {0}")]
+pub struct UnsupportedFlow(pub String);
+
+/// Splits a generator body into basic blocks at every yield and at the
+/// branches and loops around them
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::dataflow::cfg::{lower_blocks, Terminator};
+/// use depyler_core::hir::{HirExpr, HirStmt, Literal};
+///
+/// let yield_int = |n| HirStmt::Expr(HirExpr::Yield {
+///     value: Some(Box::new(HirExpr::Literal(Literal::Int(n)))),
+/// });
+/// let graph = lower_blocks(&[yield_int(1), yield_int(2)]).unwrap();
+///
+/// assert!(matches!(graph.blocks[0].terminator, Terminator::Yield { resume: 1, .. }));
+/// assert!(matches!(graph.blocks[1].terminator, Terminator::Yield { resume: 2, .. }));
+/// assert_eq!(graph.blocks[2].terminator, Terminator::Return(None));
+/// ```
+pub fn lower_blocks(body: &[HirStmt]) -> Result<BlockGraph, UnsupportedFlow> {
+    let mut lowering = Lowering {
+        blocks: Vec::new(),
+        loops: Vec::new(),
+        jumps: Vec::new(),
+    };
+    let entry = lowering.new_block();
+    if let Some(open) = lowering.stmts(body, entry)? {
+        lowering.terminate(open, Terminator::Return(None));
+    }
+    Ok(BlockGraph {
+        blocks: lowering.blocks,
+        loops: lowering.loops,
+    })
+}
+
+struct Lowering {
+    blocks: Vec<BasicBlock>,
+    loops: Vec<LoopSlot>,
+    /// `continue` and `break` targets of the enclosing lowered loops
+    jumps: Vec<(BlockId, BlockId)>,
+}
+
+impl Lowering {
+    fn new_block(&mut self) -> BlockId {
+        self.blocks.push(BasicBlock {
+            stmts: Vec::new(),
+            terminator: Terminator::Return(None),
+        });
+        self.blocks.len() - 1
+    }
+
+    fn terminate(&mut self, block: BlockId, terminator: Terminator) {
+        self.blocks[block].terminator = terminator;
+    }
+
+    /// Lowers `stmts` starting in `current`; returns the block control falls
+    /// through to afterwards, or `None` when every path has left
+    fn stmts(
+        &mut self,
+        stmts: &[HirStmt],
+        mut current: BlockId,
+    ) -> Result<Option<BlockId>, UnsupportedFlow> {
+        for stmt in stmts {
+            match self.stmt(stmt, current)? {
+                Some(next) => current = next,
+                // Whatever follows is unreachable
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(current))
+    }
+
+    fn stmt(
+        &mut self,
+        stmt: &HirStmt,
+        current: BlockId,
+    ) -> Result<Option<BlockId>, UnsupportedFlow> {
+        if is_opaque(stmt) {
+            self.blocks[current].stmts.push(stmt.clone());
+            return Ok(Some(current));
+        }
+        match stmt {
+            HirStmt::Expr(HirExpr::Yield { value }) => {
+                let value = value.as_deref().cloned();
+                if value.as_ref().is_some_and(contains_yield) {
+                    return Err(unsupported("nested yield"));
+                }
+                let resume = self.new_block();
+                self.terminate(current, Terminator::Yield { value, resume });
+                Ok(Some(resume))
+            }
+            HirStmt::Return(value) => {
+                if value.as_ref().is_some_and(contains_yield) {
+                    return Err(unsupported("yield in a return value"));
+                }
+                self.terminate(current, Terminator::Return(value.clone()));
+                Ok(None)
+            }
+            HirStmt::Break { label: None } | HirStmt::Continue { label: None } => {
+                let (continue_to, break_to) = *self
+                    .jumps
+                    .last()
+                    .ok_or_else(|| unsupported("break or continue outside a loop"))?;
+                let target = match stmt {
+                    HirStmt::Break { .. } => break_to,
+                    _ => continue_to,
+                };
+                self.terminate(current, Terminator::Goto(target));
+                Ok(None)
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                if contains_yield(condition) {
+                    return Err(unsupported("yield in a condition"));
+                }
+                let join = self.new_block();
+                let then_block = self.new_block();
+                let then_end = self.stmts(then_body, then_block)?;
+                self.goto(then_end, join);
+                let else_block = match else_body {
+                    Some(else_body) => {
+                        let else_block = self.new_block();
+                        let else_end = self.stmts(else_body, else_block)?;
+                        self.goto(else_end, join);
+                        else_block
+                    }
+                    None => join,
+                };
+                self.terminate(
+                    current,
+                    Terminator::Branch {
+                        condition: condition.clone(),
+                        then_block,
+                        else_block,
+                    },
+                );
+                Ok(Some(join))
+            }
+            HirStmt::While { condition, body } => {
+                if contains_yield(condition) {
+                    return Err(unsupported("yield in a loop condition"));
+                }
+                let header = self.new_block();
+                let body_block = self.new_block();
+                let exit = self.new_block();
+                self.terminate(current, Terminator::Goto(header));
+                self.terminate(
+                    header,
+                    Terminator::Branch {
+                        condition: condition.clone(),
+                        then_block: body_block,
+                        else_block: exit,
+                    },
+                );
+                self.loop_body(body, body_block, header, exit)?;
+                Ok(Some(exit))
+            }
+            HirStmt::For { target, iter, body } => {
+                if contains_yield(iter) {
+                    return Err(unsupported("yield in a loop iterable"));
+                }
+                let slot = self.loops.len();
+                self.loops.push(LoopSlot {
+                    target: target.clone(),
+                    iter: iter.clone(),
+                    forwards_items: forwards_items(target, body),
+                });
+                let header = self.new_block();
+                let body_block = self.new_block();
+                let exit = self.new_block();
+                self.terminate(
+                    current,
+                    Terminator::IterStart {
+                        slot,
+                        iter: iter.clone(),
+                        next: header,
+                    },
+                );
+                self.terminate(
+                    header,
+                    Terminator::IterNext {
+                        slot,
+                        target: target.clone(),
+                        body: body_block,
+                        exhausted: exit,
+                    },
+                );
+                self.loop_body(body, body_block, header, exit)?;
+                Ok(Some(exit))
+            }
+            HirStmt::Expr(_) | HirStmt::Assign { .. } => {
+                Err(unsupported("yield inside an expression"))
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } => {
+                Err(unsupported("labeled break or continue"))
+            }
+            HirStmt::Try { .. } => Err(unsupported("yield or return inside try")),
+            HirStmt::With { .. } => Err(unsupported("yield or return inside with")),
+            HirStmt::Match { .. } => Err(unsupported("yield or return inside match")),
+            HirStmt::Raise { .. } | HirStmt::Assert { .. } | HirStmt::Pass => {
+                Err(unsupported("yield in a raise or assert"))
+            }
+        }
+    }
+
+    fn loop_body(
+        &mut self,
+        body: &[HirStmt],
+        body_block: BlockId,
+        header: BlockId,
+        exit: BlockId,
+    ) -> Result<(), UnsupportedFlow> {
+        self.jumps.push((header, exit));
+        let end = self.stmts(body, body_block)?;
+        self.jumps.pop();
+        self.goto(end, header);
+        Ok(())
+    }
+
+    fn goto(&mut self, from: Option<BlockId>, to: BlockId) {
+        if let Some(from) = from {
+            self.terminate(from, Terminator::Goto(to));
+        }
+    }
+}
+
+fn unsupported(what: &str) -> UnsupportedFlow {
+    UnsupportedFlow(what.to_string())
+}
+
+/// `for x in it: yield x`
+fn forwards_items(target: &AssignTarget, body: &[HirStmt]) -> bool {
+    match (target, body) {
+        (AssignTarget::Symbol(name), [HirStmt::Expr(HirExpr::Yield { value: Some(value) })]) => {
+            matches!(&**value, HirExpr::Var(var) if var == name)
+        }
+        _ => false,
+    }
+}
+
+/// Statements that run to completion inside one block: no yield, no return,
+/// and no `break` / `continue` reaching a loop outside them
+pub fn is_opaque(stmt: &HirStmt) -> bool {
+    !stmt_yields(stmt) && !leaves(stmt, false)
+}
+
+fn stmt_yields(stmt: &HirStmt) -> bool {
+    let block_yields = |stmts: &[HirStmt]| stmts.iter().any(stmt_yields);
+    match stmt {
+        HirStmt::Assign { value, .. } => contains_yield(value),
+        HirStmt::Return(value) => value.as_ref().is_some_and(contains_yield),
+        HirStmt::Expr(expr) => contains_yield(expr),
+        HirStmt::If {
+            condition,
+            then_body,
+            else_body,
+        } => {
+            contains_yield(condition)
+                || block_yields(then_body)
+                || else_body.as_deref().is_some_and(block_yields)
+        }
+        HirStmt::While { condition, body } => contains_yield(condition) || block_yields(body),
+        HirStmt::For { iter, body, .. } => contains_yield(iter) || block_yields(body),
+        HirStmt::With { context, body, .. } => contains_yield(context) || block_yields(body),
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => {
+            block_yields(body)
+                || handlers.iter().any(|h| block_yields(&h.body))
+                || [orelse, finalbody]
+                    .into_iter()
+                    .flatten()
+                    .any(|block| block_yields(block))
+        }
+        HirStmt::Match { subject, cases } => {
+            contains_yield(subject)
+                || cases
+                    .iter()
+                    .any(|c| c.guard.as_ref().is_some_and(contains_yield) || block_yields(&c.body))
+        }
+        HirStmt::Raise { exception, cause } => {
+            [exception, cause].into_iter().flatten().any(contains_yield)
+        }
+        HirStmt::Assert { test, msg } => {
+            contains_yield(test) || msg.as_ref().is_some_and(contains_yield)
+        }
+        HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => false,
+    }
+}
+
+/// Whether `stmt` returns, or jumps out of the innermost loop around it
+/// when `in_loop` is false
+fn leaves(stmt: &HirStmt, in_loop: bool) -> bool {
+    let block_leaves = |stmts: &[HirStmt], in_loop: bool| stmts.iter().any(|s| leaves(s, in_loop));
+    match stmt {
+        HirStmt::Return(_) => true,
+        HirStmt::Break { label } | HirStmt::Continue { label } => label.is_some() || !in_loop,
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            block_leaves(then_body, in_loop)
+                || else_body
+                    .as_deref()
+                    .is_some_and(|body| block_leaves(body, in_loop))
+        }
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } => block_leaves(body, true),
+        HirStmt::With { body, .. } => block_leaves(body, in_loop),
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => {
+            block_leaves(body, in_loop)
+                || handlers.iter().any(|h| block_leaves(&h.body, in_loop))
+                || [orelse, finalbody]
+                    .into_iter()
+                    .flatten()
+                    .any(|block| block_leaves(block, in_loop))
+        }
+        HirStmt::Match { cases, .. } => cases.iter().any(|c| block_leaves(&c.body, in_loop)),
+        HirStmt::Assign { .. }
+        | HirStmt::Expr(_)
+        | HirStmt::Raise { .. }
+        | HirStmt::Assert { .. }
+        | HirStmt::Pass => false,
+    }
+}
+
+fn contains_yield(expr: &HirExpr) -> bool {
+    matches!(expr, HirExpr::Yield { .. }) || expr.children().into_iter().any(contains_yield)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::{BinOp, Literal};
+
+    fn var(name: &str) -> HirExpr {
+        HirExpr::Var(name.to_string())
+    }
+
+    fn yield_var(name: &str) -> HirStmt {
+        HirStmt::Expr(HirExpr::Yield {
+            value: Some(Box::new(var(name))),
+        })
+    }
+
+    fn assign(name: &str, value: HirExpr) -> HirStmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(name.to_string()),
+            value,
+            type_annotation: None,
+        }
+    }
+
+    #[test]
+    fn test_while_loop_with_two_yields() {
+        let body = vec![
+            assign("i", HirExpr::Literal(Literal::Int(0))),
+            HirStmt::While {
+                condition: HirExpr::Binary {
+                    op: BinOp::Lt,
+                    left: Box::new(var("i")),
+                    right: Box::new(var("n")),
+                },
+                body: vec![
+                    yield_var("i"),
+                    assign(
+                        "i",
+                        HirExpr::Binary {
+                            op: BinOp::Add,
+                            left: Box::new(var("i")),
+                            right: Box::new(HirExpr::Literal(Literal::Int(1))),
+                        },
+                    ),
+                    yield_var("i"),
+                ],
+            },
+        ];
+        let graph = lower_blocks(&body).unwrap();
+
+        // entry, header, body, exit, then one resume block per yield
+        assert_eq!(graph.blocks.len(), 6);
+        assert_eq!(graph.blocks[0].stmts.len(), 1);
+        assert_eq!(graph.blocks[0].terminator, Terminator::Goto(1));
+        assert!(matches!(
+            graph.blocks[1].terminator,
+            Terminator::Branch {
+                then_block: 2,
+                else_block: 3,
+                ..
+            }
+        ));
+        assert!(matches!(
+            graph.blocks[2].terminator,
+            Terminator::Yield { resume: 4, .. }
+        ));
+        assert_eq!(graph.blocks[4].stmts.len(), 1);
+        assert!(matches!(
+            graph.blocks[4].terminator,
+            Terminator::Yield { resume: 5, .. }
+        ));
+        assert_eq!(graph.blocks[5].terminator, Terminator::Goto(1));
+        assert_eq!(graph.blocks[3].terminator, Terminator::Return(None));
+    }
+
+    #[test]
+    fn test_for_loop_gets_a_slot_and_break_leaves_it() {
+        let body = vec![HirStmt::For {
+            target: AssignTarget::Symbol("x".to_string()),
+            iter: var("items"),
+            body: vec![
+                HirStmt::If {
+                    condition: var("x"),
+                    then_body: vec![HirStmt::Break { label: None }],
+                    else_body: None,
+                },
+                yield_var("x"),
+            ],
+        }];
+        let graph = lower_blocks(&body).unwrap();
+
+        assert_eq!(graph.loops.len(), 1);
+        assert!(!graph.loops[0].forwards_items);
+        assert!(matches!(
+            graph.blocks[0].terminator,
+            Terminator::IterStart {
+                slot: 0,
+                next: 1,
+                ..
+            }
+        ));
+        assert!(matches!(
+            graph.blocks[1].terminator,
+            Terminator::IterNext {
+                slot: 0,
+                body: 2,
+                exhausted: 3,
+                ..
+            }
+        ));
+        let breaks_out = graph
+            .blocks
+            .iter()
+            .any(|block| block.terminator == Terminator::Goto(3));
+        assert!(breaks_out);
+    }
+
+    #[test]
+    fn test_yield_from_loop_forwards_items() {
+        let body = vec![HirStmt::For {
+            target: AssignTarget::Symbol("item".to_string()),
+            iter: var("inner"),
+            body: vec![yield_var("item")],
+        }];
+        let graph = lower_blocks(&body).unwrap();
+        assert!(graph.loops[0].forwards_items);
+    }
+
+    #[test]
+    fn test_loops_without_yields_stay_whole() {
+        let body = vec![
+            HirStmt::While {
+                condition: var("running"),
+                body: vec![HirStmt::Break { label: None }],
+            },
+            yield_var("total"),
+        ];
+        let graph = lower_blocks(&body).unwrap();
+        assert_eq!(graph.blocks[0].stmts.len(), 1);
+        assert!(graph.loops.is_empty());
+    }
+
+    #[test]
+    fn test_sent_values_are_unsupported() {
+        let body = vec![assign("x", HirExpr::Yield { value: None })];
+        assert_eq!(
+            lower_blocks(&body).unwrap_err().to_string(),
+            "cannot lower generator: yield inside an expression"
+        );
+    }
+}
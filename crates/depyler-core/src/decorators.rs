@@ -0,0 +1,366 @@
+//! Decorators and what they do to the definitions they wrap
+//!
+//! The AST bridge looks each decorator of a function or method up by its
+//! dotted name in a [`DecoratorRegistry`] and lets the handler registered
+//! under that name rewrite the HIR definition. Decorators with no handler are
+//! ignored. [`DecoratorRegistry::standard`] handles:
+//!
+//! | Decorator                                   | Effect                                |
+//! |---------------------------------------------|---------------------------------------|
+//! | `@staticmethod`                             | associated function without `self`    |
+//! | `@classmethod`                              | associated function without `cls`     |
+//! | `@property`                                 | getter method                         |
+//! | `@functools.lru_cache`, `@functools.cache`  | results memoized in a `HashMap`       |
+//! | `@functools.wraps(f)`                       | none; only copies metadata            |
+//! | `@contextlib.contextmanager`                | setup function returning a guard      |
+//!
+//! Names imported with `from functools import lru_cache` are registered too.
+
+use crate::ast_bridge::ExprConverter;
+use crate::hir::{HirExpr, HirFunction, HirMethod, Literal, Memoize};
+use anyhow::{bail, Result};
+use rustpython_ast::{self as ast};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// `functools.lru_cache`'s default `maxsize`
+const LRU_CACHE_DEFAULT_SIZE: usize = 128;
+
+/// A decorator as written above a definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decorator {
+    /// Dotted name, such as `functools.lru_cache`
+    pub name: String,
+    /// Positional arguments, when the decorator is called
+    pub args: Vec<HirExpr>,
+    /// Keyword arguments, when the decorator is called
+    pub kwargs: Vec<(String, HirExpr)>,
+}
+
+impl Decorator {
+    /// The keyword argument `name`, or else the positional one at `position`
+    pub fn arg(&self, name: &str, position: usize) -> Option<&HirExpr> {
+        self.kwargs
+            .iter()
+            .find(|(kw, _)| kw == name)
+            .map(|(_, value)| value)
+            .or_else(|| self.args.get(position))
+    }
+}
+
+/// Rewrites the definitions a decorator is applied to
+///
+/// Both methods leave the definition as it is by default, so a handler only
+/// implements the kinds of definition its decorator changes.
+pub trait DecoratorHandler: Send + Sync {
+    fn apply_to_function(&self, _decorator: &Decorator, _function: &mut HirFunction) -> Result<()> {
+        Ok(())
+    }
+
+    fn apply_to_method(&self, _decorator: &Decorator, _method: &mut HirMethod) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Decorator handlers by dotted name
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::decorators::{Decorator, DecoratorHandler, DecoratorRegistry};
+/// use depyler_core::hir::HirFunction;
+/// use depyler_core::DepylerPipeline;
+///
+/// struct Pure;
+///
+/// impl DecoratorHandler for Pure {
+///     fn apply_to_function(&self, _: &Decorator, function: &mut HirFunction) -> anyhow::Result<()> {
+///         function.properties.is_pure = true;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut decorators = DecoratorRegistry::standard();
+/// decorators.register("pure", Pure);
+///
+/// let source = "@pure\ndef double(x: int) -> int:\n    return x * 2\n";
+/// let hir = DepylerPipeline::new()
+///     .with_decorators(decorators)
+///     .parse_to_hir(source)
+///     .unwrap();
+/// assert!(hir.functions[0].properties.is_pure);
+/// ```
+#[derive(Clone, Default)]
+pub struct DecoratorRegistry {
+    handlers: BTreeMap<String, Arc<dyn DecoratorHandler>>,
+}
+
+impl fmt::Debug for DecoratorRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
+
+impl DecoratorRegistry {
+    /// A registry that ignores every decorator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The decorators the transpiler understands out of the box
+    pub fn standard() -> Self {
+        let mut registry = Self::new();
+        registry.register("staticmethod", StaticMethod);
+        registry.register("classmethod", ClassMethod);
+        registry.register("property", Property);
+        for name in [
+            "functools.lru_cache",
+            "lru_cache",
+            "functools.cache",
+            "cache",
+        ] {
+            registry.register(name, LruCache);
+        }
+        for name in ["functools.wraps", "wraps"] {
+            registry.register(name, Wraps);
+        }
+        for name in ["contextlib.contextmanager", "contextmanager"] {
+            registry.register(name, ContextManager);
+        }
+        registry
+    }
+
+    /// Handles decorators named `name` with `handler`, replacing any handler
+    /// registered under that name
+    pub fn register(&mut self, name: impl Into<String>, handler: impl DecoratorHandler + 'static) {
+        self.handlers.insert(name.into(), Arc::new(handler));
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Applies `decorators` to `function`, innermost first as Python does
+    pub fn apply_to_function(
+        &self,
+        decorators: &[ast::Expr],
+        function: &mut HirFunction,
+    ) -> Result<()> {
+        for (handler, decorator) in self.resolve(decorators)? {
+            handler.apply_to_function(&decorator, function)?;
+        }
+        Ok(())
+    }
+
+    /// Applies `decorators` to `method`, innermost first as Python does
+    pub fn apply_to_method(&self, decorators: &[ast::Expr], method: &mut HirMethod) -> Result<()> {
+        for (handler, decorator) in self.resolve(decorators)? {
+            handler.apply_to_method(&decorator, method)?;
+        }
+        Ok(())
+    }
+
+    /// Handlers of the registered `decorators`, innermost first
+    fn resolve(&self, decorators: &[ast::Expr]) -> Result<Vec<(&dyn DecoratorHandler, Decorator)>> {
+        let mut resolved = Vec::new();
+        for expr in decorators.iter().rev() {
+            let (callee, call) = match expr {
+                ast::Expr::Call(call) => (call.func.as_ref(), Some(call)),
+                _ => (expr, None),
+            };
+            let name = match dotted_name(callee) {
+                Some(name) => name,
+                None => continue,
+            };
+            let handler = match self.handlers.get(&name) {
+                Some(handler) => handler.as_ref(),
+                None => continue,
+            };
+
+            let mut decorator = Decorator {
+                name,
+                args: Vec::new(),
+                kwargs: Vec::new(),
+            };
+            if let Some(call) = call {
+                for arg in &call.args {
+                    decorator.args.push(ExprConverter::convert(arg.clone())?);
+                }
+                for keyword in &call.keywords {
+                    if let Some(name) = &keyword.arg {
+                        let value = ExprConverter::convert(keyword.value.clone())?;
+                        decorator.kwargs.push((name.to_string(), value));
+                    }
+                }
+            }
+            resolved.push((handler, decorator));
+        }
+        Ok(resolved)
+    }
+}
+
+/// `a.b.c` for a chain of attributes on a name
+fn dotted_name(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::Name(n) => Some(n.id.to_string()),
+        ast::Expr::Attribute(attr) => {
+            dotted_name(&attr.value).map(|base| format!("{}.{}", base, attr.attr.as_str()))
+        }
+        _ => None,
+    }
+}
+
+struct StaticMethod;
+
+impl DecoratorHandler for StaticMethod {
+    fn apply_to_method(&self, _decorator: &Decorator, method: &mut HirMethod) -> Result<()> {
+        method.is_static = true;
+        Ok(())
+    }
+}
+
+struct ClassMethod;
+
+impl DecoratorHandler for ClassMethod {
+    fn apply_to_method(&self, _decorator: &Decorator, method: &mut HirMethod) -> Result<()> {
+        method.is_classmethod = true;
+        Ok(())
+    }
+}
+
+struct Property;
+
+impl DecoratorHandler for Property {
+    fn apply_to_method(&self, _decorator: &Decorator, method: &mut HirMethod) -> Result<()> {
+        method.is_property = true;
+        Ok(())
+    }
+}
+
+/// `@lru_cache`, `@lru_cache(maxsize=n)` and `@cache`
+///
+/// Memoized methods are generated without a cache, since `self` would have
+/// to be part of the key; leaving the cache out does not change results.
+struct LruCache;
+
+impl DecoratorHandler for LruCache {
+    fn apply_to_function(&self, decorator: &Decorator, function: &mut HirFunction) -> Result<()> {
+        let max_size = if decorator.name.ends_with("lru_cache") {
+            match decorator.arg("maxsize", 0) {
+                None => Some(LRU_CACHE_DEFAULT_SIZE),
+                Some(HirExpr::Literal(Literal::None)) => None,
+                Some(HirExpr::Literal(Literal::Int(n))) if *n >= 0 => Some(*n as usize),
+                Some(_) => bail!(
+                    "`@{}` on `{}` needs maxsize to be a non-negative int literal or None",
+                    decorator.name,
+                    function.name
+                ),
+            }
+        } else {
+            None
+        };
+        function.properties.memoize = Some(Memoize { max_size });
+        Ok(())
+    }
+}
+
+/// `@functools.wraps(f)` copies `f`'s name and docstring onto a wrapper,
+/// which the generated code has no use for
+struct Wraps;
+
+impl DecoratorHandler for Wraps {}
+
+struct ContextManager;
+
+impl DecoratorHandler for ContextManager {
+    fn apply_to_function(&self, _decorator: &Decorator, function: &mut HirFunction) -> Result<()> {
+        function.properties.is_context_manager = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::Type;
+    use crate::hir_builder::HirFunctionBuilder;
+    use rustpython_parser::{parse, Mode};
+
+    fn decorators_of(source: &str) -> Vec<ast::Expr> {
+        match parse(source, Mode::Module, "<test>").unwrap() {
+            ast::Mod::Module(m) => match m.body.into_iter().next() {
+                Some(ast::Stmt::FunctionDef(f)) => f.decorator_list,
+                other => panic!("unexpected {:?}", other),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    fn memoize(source: &str) -> Result<Option<Memoize>> {
+        let mut function = HirFunctionBuilder::new("f").returns(Type::Int).build();
+        DecoratorRegistry::standard().apply_to_function(&decorators_of(source), &mut function)?;
+        Ok(function.properties.memoize)
+    }
+
+    #[test]
+    fn test_lru_cache_sizes() {
+        let default = Some(Memoize {
+            max_size: Some(LRU_CACHE_DEFAULT_SIZE),
+        });
+        let unbounded = Some(Memoize { max_size: None });
+
+        assert_eq!(memoize("@lru_cache\ndef f(): pass").unwrap(), default);
+        assert_eq!(
+            memoize("@functools.lru_cache()\ndef f(): pass").unwrap(),
+            default
+        );
+        assert_eq!(
+            memoize("@lru_cache(maxsize=None)\ndef f(): pass").unwrap(),
+            unbounded
+        );
+        assert_eq!(
+            memoize("@functools.lru_cache(16)\ndef f(): pass").unwrap(),
+            Some(Memoize { max_size: Some(16) })
+        );
+        assert_eq!(
+            memoize("@functools.cache\ndef f(): pass").unwrap(),
+            unbounded
+        );
+        assert!(memoize("@lru_cache(maxsize=size)\ndef f(): pass").is_err());
+    }
+
+    #[test]
+    fn test_decorators_without_effect_are_ignored() {
+        assert_eq!(
+            memoize("@app.route('/')\n@tracing\ndef f(): pass").unwrap(),
+            None
+        );
+        assert_eq!(memoize("@wraps(g)\ndef f(): pass").unwrap(), None);
+    }
+
+    #[test]
+    fn test_custom_handlers_run_innermost_first() {
+        struct Rename(&'static str);
+
+        impl DecoratorHandler for Rename {
+            fn apply_to_function(&self, _: &Decorator, function: &mut HirFunction) -> Result<()> {
+                function.name.push_str(self.0);
+                Ok(())
+            }
+        }
+
+        let mut registry = DecoratorRegistry::new();
+        registry.register("outer", Rename("_outer"));
+        registry.register("inner", Rename("_inner"));
+
+        let mut function = HirFunctionBuilder::new("f").returns(Type::Int).build();
+        registry
+            .apply_to_function(
+                &decorators_of("@outer\n@inner\ndef f(): pass"),
+                &mut function,
+            )
+            .unwrap();
+        assert_eq!(function.name, "f_inner_outer");
+    }
+}
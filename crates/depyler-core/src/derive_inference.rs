@@ -0,0 +1,875 @@
+//! Usage-driven derive inference for generated structs
+//!
+//! Rather than a fixed derive list, each class gets the traits that the
+//! module's use of its instances requires:
+//!
+//! - `Debug`: printed (`print`, `repr`, `str`, f-strings), raised, held in
+//!   a generator's state or in a union enum
+//! - `Clone`: copied (`copy.copy`, `.copy()`), read out of a field or a
+//!   collection, or held in a collection, which the generated code clones
+//!   on iteration and lookup
+//! - `PartialEq`: compared with `==`/`!=`, tested with `in`, searched for
+//!   with `list.index`/`count`/`remove`
+//! - `Eq, Hash`: used as a dict key or set element, or passed to `hash()`
+//! - `Eq, PartialOrd, Ord`: compared with `<` and friends, or sorted,
+//!   `min`ed or `max`ed without a key function
+//! - `serde::Serialize`: passed to `json.dumps`/`json.dump`
+//! - `serde::Deserialize`: the annotated or returned result of
+//!   `json.loads`/`json.load`
+//!
+//! A derive on a struct needs the same trait on its fields, so derives
+//! flow into the classes a struct holds. Floats are neither `Eq`, `Hash`
+//! nor `Ord`, so a class holding one (directly or through another class)
+//! keeps only `PartialEq` and `PartialOrd` of those.
+
+use crate::hir::{
+    AssignTarget, BinOp, FStringPart, HirClass, HirExpr, HirFunction, HirParam, HirStmt, Literal,
+    Type,
+};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// A derivable standard trait
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Derive {
+    Debug,
+    Clone,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+}
+
+impl Derive {
+    /// Trait name as written in `#[derive(...)]`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Derive::Debug => "Debug",
+            Derive::Clone => "Clone",
+            Derive::PartialEq => "PartialEq",
+            Derive::Eq => "Eq",
+            Derive::Hash => "Hash",
+            Derive::PartialOrd => "PartialOrd",
+            Derive::Ord => "Ord",
+            Derive::Serialize => "serde::Serialize",
+            Derive::Deserialize => "serde::Deserialize",
+        }
+    }
+
+    /// Whether a struct with float fields can derive this trait
+    fn allows_float(self) -> bool {
+        !matches!(self, Derive::Eq | Derive::Hash | Derive::Ord)
+    }
+}
+
+const EQUALITY: &[Derive] = &[Derive::PartialEq];
+const HASHING: &[Derive] = &[Derive::PartialEq, Derive::Eq, Derive::Hash];
+const ORDERING: &[Derive] = &[
+    Derive::PartialEq,
+    Derive::Eq,
+    Derive::PartialOrd,
+    Derive::Ord,
+];
+const UNION_MEMBER: &[Derive] = &[Derive::Debug, Derive::Clone, Derive::PartialEq];
+
+/// The derives each class of a module needs
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::derive_inference::{Derive, DeriveInference};
+/// use depyler_core::hir::{HirClass, HirField, Type};
+///
+/// let point = HirClass {
+///     name: "Point".to_string(),
+///     base_classes: vec![],
+///     methods: vec![],
+///     fields: vec![HirField {
+///         name: "x".to_string(),
+///         field_type: Type::Int,
+///         default_value: None,
+///         is_class_var: false,
+///     }],
+///     is_dataclass: true,
+///     model_kind: None,
+///     annotations: Default::default(),
+///     docstring: None,
+/// };
+/// let grid = HirClass {
+///     name: "Grid".to_string(),
+///     fields: vec![HirField {
+///         name: "cells".to_string(),
+///         field_type: Type::Set(Box::new(Type::Custom("Point".to_string()))),
+///         default_value: None,
+///         is_class_var: false,
+///     }],
+///     ..point.clone()
+/// };
+///
+/// let derives = DeriveInference::analyze(&[point, grid], &[]);
+/// assert_eq!(
+///     derives.derives("Point"),
+///     [Derive::Clone, Derive::PartialEq, Derive::Eq, Derive::Hash]
+/// );
+/// assert!(derives.derives("Grid").is_empty());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DeriveInference {
+    derives: HashMap<String, BTreeSet<Derive>>,
+    json_callers: BTreeSet<String>,
+}
+
+impl DeriveInference {
+    /// Infer derives from the classes' own declarations and from the
+    /// module functions and methods that use them
+    pub fn analyze(classes: &[HirClass], functions: &[HirFunction]) -> Self {
+        let mut scan = UsageScan::new(classes, functions);
+        for class in classes {
+            for field in &class.fields {
+                scan.scan_type(&field.field_type);
+            }
+            if class.base_classes.iter().any(|b| is_exception_base(b)) {
+                scan.require_class(&class.name, &[Derive::Debug]);
+            }
+        }
+        for func in functions {
+            scan.function = Some(func.name.as_str());
+            scan.scan_function(&func.params, &func.ret_type, &func.body, None);
+            if func.properties.is_generator {
+                for param in &func.params {
+                    scan.require(&param.ty, &[Derive::Debug]);
+                }
+            }
+        }
+        scan.function = None;
+        for class in classes {
+            for method in &class.methods {
+                let self_class = (!method.is_static).then_some(class.name.as_str());
+                scan.scan_function(&method.params, &method.ret_type, &method.body, self_class);
+            }
+        }
+
+        let mut inference = DeriveInference {
+            derives: scan.needs,
+            json_callers: scan.json_callers,
+        };
+        inference.propagate_to_fields(classes);
+        inference.drop_float_incompatible(classes);
+        inference
+    }
+
+    /// Derives for `class`, in `#[derive(...)]` order
+    pub fn derives(&self, class: &str) -> Vec<Derive> {
+        self.derives
+            .get(class)
+            .map(|d| d.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Module functions that pass classes through `serde_json`, in name order
+    ///
+    /// Their generated bodies propagate `serde_json::Error` with `?`.
+    pub fn json_callers(&self) -> impl Iterator<Item = &str> {
+        self.json_callers.iter().map(String::as_str)
+    }
+
+    /// A struct deriving a trait needs it on the classes in its fields
+    fn propagate_to_fields(&mut self, classes: &[HirClass]) {
+        loop {
+            let mut changed = false;
+            for class in classes {
+                let needed = match self.derives.get(&class.name) {
+                    Some(needed) => needed.clone(),
+                    None => continue,
+                };
+                for field in class.fields.iter().filter(|f| !f.is_class_var) {
+                    for name in class_names_in(&field.field_type) {
+                        let derives = self.derives.entry(name).or_default();
+                        let before = derives.len();
+                        derives.extend(needed.iter().copied());
+                        changed |= derives.len() != before;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    fn drop_float_incompatible(&mut self, classes: &[HirClass]) {
+        let by_name: HashMap<&str, &HirClass> =
+            classes.iter().map(|c| (c.name.as_str(), c)).collect();
+        for (name, derives) in self.derives.iter_mut() {
+            if holds_float(name, &by_name, &mut HashSet::new()) {
+                derives.retain(|d| d.allows_float());
+            }
+        }
+    }
+}
+
+/// `json.loads(s)` or `json.load(f)`
+pub fn is_json_read(expr: &HirExpr) -> bool {
+    matches!(
+        expr,
+        HirExpr::MethodCall { object, method, args, .. }
+            if matches!(&**object, HirExpr::Var(m) if m == "json")
+                && matches!(method.as_str(), "loads" | "load")
+                && args.len() == 1
+    )
+}
+
+fn is_exception_base(base: &str) -> bool {
+    base.ends_with("Exception") || base.ends_with("Error")
+}
+
+/// Whether a class holds a float, directly or through another class
+fn holds_float<'a>(
+    class: &'a str,
+    classes: &HashMap<&'a str, &'a HirClass>,
+    seen: &mut HashSet<&'a str>,
+) -> bool {
+    let class: &'a HirClass = match classes.get(class) {
+        Some(class) => class,
+        None => return false,
+    };
+    if !seen.insert(class.name.as_str()) {
+        return false;
+    }
+    class
+        .fields
+        .iter()
+        .filter(|f| !f.is_class_var)
+        .any(|f| type_holds_float(&f.field_type, classes, seen))
+}
+
+fn type_holds_float<'a>(
+    ty: &'a Type,
+    classes: &HashMap<&'a str, &'a HirClass>,
+    seen: &mut HashSet<&'a str>,
+) -> bool {
+    match ty {
+        Type::Float => true,
+        Type::Custom(name) => holds_float(name, classes, seen),
+        _ => type_children(ty)
+            .into_iter()
+            .any(|t| type_holds_float(t, classes, seen)),
+    }
+}
+
+/// Types nested directly inside `ty`
+fn type_children(ty: &Type) -> Vec<&Type> {
+    match ty {
+        Type::List(t) | Type::Set(t) | Type::Optional(t) | Type::Final(t) => vec![&**t],
+        Type::Array { element_type, .. } => vec![&**element_type],
+        Type::Dict(k, v) => vec![&**k, &**v],
+        Type::Tuple(ts) | Type::Union(ts) => ts.iter().collect(),
+        Type::Generic { params, .. } => params.iter().collect(),
+        Type::Function { params, ret } => params.iter().chain([&**ret]).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Names of the custom types appearing anywhere in `ty`
+fn class_names_in(ty: &Type) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut stack = vec![ty];
+    while let Some(ty) = stack.pop() {
+        if let Type::Custom(name) = ty {
+            names.push(name.clone());
+        }
+        stack.extend(type_children(ty));
+    }
+    names
+}
+
+/// Element type of an iterable
+fn element_type(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::List(t) | Type::Set(t) => Some(t),
+        Type::Array { element_type, .. } => Some(element_type),
+        Type::Dict(k, _) => Some(k),
+        _ => None,
+    }
+}
+
+/// Walks function bodies, typing expressions just far enough to see which
+/// class instances flow into each trait-requiring operation
+struct UsageScan<'a> {
+    classes: HashMap<&'a str, &'a HirClass>,
+    returns: HashMap<&'a str, &'a Type>,
+    locals: HashMap<String, Type>,
+    needs: HashMap<String, BTreeSet<Derive>>,
+    /// Module function being scanned; `None` inside methods
+    function: Option<&'a str>,
+    ret_type: Type,
+    json_callers: BTreeSet<String>,
+}
+
+impl<'a> UsageScan<'a> {
+    fn new(classes: &'a [HirClass], functions: &'a [HirFunction]) -> Self {
+        Self {
+            classes: classes.iter().map(|c| (c.name.as_str(), c)).collect(),
+            returns: functions
+                .iter()
+                .map(|f| (f.name.as_str(), &f.ret_type))
+                .collect(),
+            locals: HashMap::new(),
+            needs: HashMap::new(),
+            function: None,
+            ret_type: Type::Unknown,
+            json_callers: BTreeSet::new(),
+        }
+    }
+
+    fn require_class(&mut self, class: &str, derives: &[Derive]) {
+        if self.classes.contains_key(class) {
+            self.needs
+                .entry(class.to_string())
+                .or_default()
+                .extend(derives.iter().copied());
+        }
+    }
+
+    /// Require `derives` on every class inside `ty`
+    fn require(&mut self, ty: &Type, derives: &[Derive]) {
+        for name in class_names_in(ty) {
+            self.require_class(&name, derives);
+        }
+    }
+
+    fn require_expr(&mut self, expr: &HirExpr, derives: &[Derive]) {
+        if let Some(ty) = self.type_of(expr) {
+            self.require(&ty, derives);
+        }
+    }
+
+    /// Requirements that come from a type itself: collection elements are
+    /// cloned, keys and set elements hashed, union members wrapped in an
+    /// enum deriving `Debug, Clone, PartialEq`
+    fn scan_type(&mut self, ty: &Type) {
+        match ty {
+            Type::List(t) => self.require(t, &[Derive::Clone]),
+            Type::Set(t) => {
+                self.require(t, &[Derive::Clone]);
+                self.require(t, HASHING);
+            }
+            Type::Dict(k, v) => {
+                self.require(k, &[Derive::Clone]);
+                self.require(k, HASHING);
+                self.require(v, &[Derive::Clone]);
+            }
+            Type::Union(members) => {
+                for member in members {
+                    self.require(member, UNION_MEMBER);
+                }
+            }
+            _ => {}
+        }
+        for child in type_children(ty) {
+            self.scan_type(child);
+        }
+    }
+
+    fn scan_function(
+        &mut self,
+        params: &[HirParam],
+        ret_type: &Type,
+        body: &[HirStmt],
+        self_class: Option<&str>,
+    ) {
+        self.locals.clear();
+        if let Some(class) = self_class {
+            self.locals
+                .insert("self".to_string(), Type::Custom(class.to_string()));
+        }
+        for param in params {
+            self.scan_type(&param.ty);
+            self.locals.insert(param.name.clone(), param.ty.clone());
+        }
+        self.scan_type(ret_type);
+        self.ret_type = ret_type.clone();
+        self.stmts(body);
+    }
+
+    /// Require a serde derive on the classes in `ty`, noting the function
+    /// when there are any
+    fn require_serde(&mut self, ty: &Type, derive: Derive) {
+        let classes: Vec<String> = class_names_in(ty)
+            .into_iter()
+            .filter(|name| self.classes.contains_key(name.as_str()))
+            .collect();
+        if classes.is_empty() {
+            return;
+        }
+        for class in &classes {
+            self.require_class(class, &[derive]);
+        }
+        if let Some(function) = self.function {
+            self.json_callers.insert(function.to_string());
+        }
+    }
+
+    fn bind(&mut self, name: &str, ty: Type) {
+        self.scan_type(&ty);
+        self.locals.insert(name.to_string(), ty);
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt]) {
+        stmts.iter().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::Assign {
+                target,
+                value,
+                type_annotation,
+            } => {
+                self.expr(value);
+                // A field or element assigned elsewhere is copied out
+                if matches!(value, HirExpr::Attribute { .. } | HirExpr::Index { .. }) {
+                    self.require_expr(value, &[Derive::Clone]);
+                }
+                if let (Some(ty), true) = (type_annotation, is_json_read(value)) {
+                    self.require_serde(ty, Derive::Deserialize);
+                }
+                let ty = type_annotation.clone().or_else(|| self.type_of(value));
+                match (target, ty) {
+                    (AssignTarget::Symbol(name), Some(ty)) => self.bind(name, ty),
+                    (AssignTarget::Symbol(_), None) => {}
+                    (target, _) => self.target(target),
+                }
+            }
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                    if matches!(value, HirExpr::Attribute { .. } | HirExpr::Index { .. }) {
+                        self.require_expr(value, &[Derive::Clone]);
+                    }
+                    if is_json_read(value) {
+                        let ret_type = self.ret_type.clone();
+                        self.require_serde(&ret_type, Derive::Deserialize);
+                    }
+                }
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            HirStmt::For { target, iter, body } => {
+                self.expr(iter);
+                let element = self.type_of(iter).and_then(|ty| element_type(&ty).cloned());
+                if let Some(element) = &element {
+                    self.require(element, &[Derive::Clone]);
+                }
+                match (target, element) {
+                    (AssignTarget::Symbol(name), Some(element)) => self.bind(name, element),
+                    (target, _) => self.target(target),
+                }
+                self.stmts(body);
+            }
+            HirStmt::Expr(expr) => self.expr(expr),
+            HirStmt::Raise { exception, cause } => {
+                if let Some(HirExpr::Call { func, .. }) = exception {
+                    self.require_class(func, &[Derive::Debug]);
+                }
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr);
+                }
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context);
+                self.stmts(body);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body);
+                handlers.iter().for_each(|h| self.stmts(&h.body));
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.expr(test);
+                if let Some(msg) = msg {
+                    self.expr(msg);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.stmts(&case.body);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn target(&mut self, target: &AssignTarget) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            AssignTarget::Attribute { value, .. } => self.expr(value),
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t)),
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Binary { op, left, right } => match op {
+                BinOp::Eq | BinOp::NotEq => {
+                    self.require_expr(left, EQUALITY);
+                    self.require_expr(right, EQUALITY);
+                }
+                BinOp::Lt | BinOp::LtEq | BinOp::Gt | BinOp::GtEq => {
+                    self.require_expr(left, ORDERING);
+                    self.require_expr(right, ORDERING);
+                }
+                BinOp::In | BinOp::NotIn => {
+                    self.require_expr(left, EQUALITY);
+                    let element = self
+                        .type_of(right)
+                        .and_then(|ty| element_type(&ty).cloned());
+                    if let Some(element) = element {
+                        self.require(&element, EQUALITY);
+                    }
+                }
+                _ => {}
+            },
+            HirExpr::Call { func, args, kwargs } => match func.as_str() {
+                "print" | "repr" | "str" | "format" => {
+                    args.iter()
+                        .for_each(|a| self.require_expr(a, &[Derive::Debug]));
+                }
+                "hash" => args.iter().for_each(|a| self.require_expr(a, HASHING)),
+                "copy" | "deepcopy" => {
+                    args.iter()
+                        .for_each(|a| self.require_expr(a, &[Derive::Clone]));
+                }
+                "sorted" | "min" | "max" if !kwargs.iter().any(|(k, _)| k == "key") => {
+                    self.require_ordered(args);
+                }
+                _ => {}
+            },
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                kwargs,
+            } => {
+                let is_copy_module = matches!(&**object, HirExpr::Var(m) if m == "copy");
+                let is_json_module = matches!(&**object, HirExpr::Var(m) if m == "json");
+                match method.as_str() {
+                    "dumps" | "dump" if is_json_module => {
+                        if let Some(ty) = args.first().and_then(|a| self.type_of(a)) {
+                            self.require_serde(&ty, Derive::Serialize);
+                        }
+                    }
+                    "copy" | "deepcopy" if is_copy_module => {
+                        args.iter()
+                            .for_each(|a| self.require_expr(a, &[Derive::Clone]));
+                    }
+                    "copy" => self.require_expr(object, &[Derive::Clone]),
+                    "sort" if !kwargs.iter().any(|(k, _)| k == "key") => {
+                        self.require_elements(object, ORDERING);
+                    }
+                    "index" | "count" | "remove" => self.require_elements(object, EQUALITY),
+                    _ => {}
+                }
+            }
+            HirExpr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.require_expr(expr, &[Derive::Debug]);
+                    }
+                }
+            }
+            HirExpr::Set(items) | HirExpr::FrozenSet(items) => {
+                items.iter().for_each(|e| self.require_expr(e, HASHING));
+            }
+            HirExpr::Dict(pairs) => {
+                pairs
+                    .iter()
+                    .for_each(|(k, _)| self.require_expr(k, HASHING));
+            }
+            _ => {}
+        }
+        for child in expr.children() {
+            self.expr(child);
+        }
+    }
+
+    /// `sorted(xs)`, `min(xs)` or `min(a, b)`
+    fn require_ordered(&mut self, args: &[HirExpr]) {
+        match args {
+            [iterable] => self.require_elements(iterable, ORDERING),
+            _ => args.iter().for_each(|a| self.require_expr(a, ORDERING)),
+        }
+    }
+
+    fn require_elements(&mut self, collection: &HirExpr, derives: &[Derive]) {
+        let element = self
+            .type_of(collection)
+            .and_then(|ty| element_type(&ty).cloned());
+        if let Some(element) = element {
+            self.require(&element, derives);
+        }
+    }
+
+    /// Best-effort static type of an expression
+    fn type_of(&self, expr: &HirExpr) -> Option<Type> {
+        match expr {
+            HirExpr::Literal(Literal::Int(_)) => Some(Type::Int),
+            HirExpr::Literal(Literal::Float(_)) => Some(Type::Float),
+            HirExpr::Literal(Literal::String(_)) => Some(Type::String),
+            HirExpr::Literal(Literal::Bool(_)) => Some(Type::Bool),
+            HirExpr::Var(name) => self.locals.get(name).cloned(),
+            HirExpr::Call { func, .. } if self.classes.contains_key(func.as_str()) => {
+                Some(Type::Custom(func.clone()))
+            }
+            HirExpr::Call { func, args, .. } => match (func.as_str(), args.as_slice()) {
+                ("sorted" | "list" | "reversed", [arg]) => self.type_of(arg),
+                _ => self.returns.get(func.as_str()).map(|ty| (*ty).clone()),
+            },
+            HirExpr::Attribute { value, attr } => {
+                let class = self.class_of(value)?;
+                class
+                    .fields
+                    .iter()
+                    .find(|f| &f.name == attr)
+                    .map(|f| f.field_type.clone())
+            }
+            HirExpr::MethodCall { object, method, .. } => {
+                if let Some(class) = self.class_of(object) {
+                    return class
+                        .methods
+                        .iter()
+                        .find(|m| &m.name == method)
+                        .map(|m| m.ret_type.clone());
+                }
+                match (self.type_of(object)?, method.as_str()) {
+                    (Type::List(t), "pop") => Some(*t),
+                    (Type::Dict(_, v), "get" | "pop") => Some(*v),
+                    _ => None,
+                }
+            }
+            HirExpr::Index { base, .. } => match self.type_of(base)? {
+                Type::List(t) => Some(*t),
+                Type::Dict(_, v) => Some(*v),
+                _ => None,
+            },
+            HirExpr::List(items) => Some(Type::List(Box::new(self.type_of(items.first()?)?))),
+            HirExpr::Set(items) => Some(Type::Set(Box::new(self.type_of(items.first()?)?))),
+            HirExpr::Tuple(items) => items
+                .iter()
+                .map(|e| self.type_of(e))
+                .collect::<Option<Vec<_>>>()
+                .map(Type::Tuple),
+            HirExpr::Dict(pairs) => {
+                let (key, value) = pairs.first()?;
+                Some(Type::Dict(
+                    Box::new(self.type_of(key)?),
+                    Box::new(self.type_of(value)?),
+                ))
+            }
+            HirExpr::IfExpr { body, .. } => self.type_of(body),
+            HirExpr::Borrow { expr, .. } => self.type_of(expr),
+            _ => None,
+        }
+    }
+
+    fn class_of(&self, expr: &HirExpr) -> Option<&'a HirClass> {
+        match self.type_of(expr)? {
+            Type::Custom(name) => self.classes.get(name.as_str()).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::HirField;
+    use crate::hir_builder::HirFunctionBuilder;
+
+    fn class(name: &str, fields: Vec<(&str, Type)>) -> HirClass {
+        HirClass {
+            name: name.to_string(),
+            base_classes: vec![],
+            methods: vec![],
+            fields: fields
+                .into_iter()
+                .map(|(name, field_type)| HirField {
+                    name: name.to_string(),
+                    field_type,
+                    default_value: None,
+                    is_class_var: false,
+                })
+                .collect(),
+            is_dataclass: true,
+            model_kind: None,
+            annotations: Default::default(),
+            docstring: None,
+        }
+    }
+
+    fn var(name: &str) -> Box<HirExpr> {
+        Box::new(HirExpr::Var(name.to_string()))
+    }
+
+    fn custom(name: &str) -> Type {
+        Type::Custom(name.to_string())
+    }
+
+    #[test]
+    fn test_unused_class_gets_no_derives() {
+        let derives = DeriveInference::analyze(&[class("Point", vec![("x", Type::Int)])], &[]);
+        assert!(derives.derives("Point").is_empty());
+    }
+
+    #[test]
+    fn test_comparison_and_printing() {
+        let compare = HirStmt::Expr(HirExpr::Binary {
+            op: BinOp::Lt,
+            left: var("a"),
+            right: var("b"),
+        });
+        let print = HirStmt::Expr(HirExpr::Call {
+            func: "print".to_string(),
+            args: vec![HirExpr::Var("a".to_string())],
+            kwargs: vec![],
+        });
+        let func = HirFunctionBuilder::new("f")
+            .param("a", custom("Version"))
+            .param("b", custom("Version"))
+            .body(vec![compare, print])
+            .build();
+        let derives = DeriveInference::analyze(&[class("Version", vec![])], &[func]);
+        assert_eq!(
+            derives.derives("Version"),
+            [
+                Derive::Debug,
+                Derive::PartialEq,
+                Derive::Eq,
+                Derive::PartialOrd,
+                Derive::Ord
+            ]
+        );
+    }
+
+    #[test]
+    fn test_derives_propagate_to_field_classes() {
+        let compare = HirStmt::Expr(HirExpr::Binary {
+            op: BinOp::Eq,
+            left: var("a"),
+            right: var("b"),
+        });
+        let func = HirFunctionBuilder::new("f")
+            .param("a", custom("Line"))
+            .param("b", custom("Line"))
+            .body(vec![compare])
+            .build();
+        let classes = [
+            class("Line", vec![("start", custom("Point"))]),
+            class("Point", vec![("x", Type::Int)]),
+        ];
+        let derives = DeriveInference::analyze(&classes, &[func]);
+        assert_eq!(derives.derives("Line"), [Derive::PartialEq]);
+        assert_eq!(derives.derives("Point"), [Derive::PartialEq]);
+    }
+
+    #[test]
+    fn test_float_fields_block_eq_hash_and_ord() {
+        let classes = [
+            class("Reading", vec![("value", Type::Float)]),
+            class(
+                "Log",
+                vec![("seen", Type::Set(Box::new(custom("Reading"))))],
+            ),
+        ];
+        let derives = DeriveInference::analyze(&classes, &[]);
+        assert_eq!(
+            derives.derives("Reading"),
+            [Derive::Clone, Derive::PartialEq]
+        );
+    }
+
+    #[test]
+    fn test_json_round_trip_derives_serde() {
+        let json_call = |method: &str, arg: &str| HirExpr::MethodCall {
+            object: var("json"),
+            method: method.to_string(),
+            args: vec![HirExpr::Var(arg.to_string())],
+            kwargs: vec![],
+        };
+        let dump = HirStmt::Expr(json_call("dumps", "order"));
+        let load = HirStmt::Assign {
+            target: AssignTarget::Symbol("copy".to_string()),
+            value: json_call("loads", "text"),
+            type_annotation: Some(custom("Order")),
+        };
+        let func = HirFunctionBuilder::new("f")
+            .param("order", custom("Order"))
+            .param("text", Type::String)
+            .body(vec![dump, load])
+            .build();
+        let classes = [
+            class(
+                "Order",
+                vec![("lines", Type::List(Box::new(custom("Line"))))],
+            ),
+            class("Line", vec![("qty", Type::Int)]),
+        ];
+        let derives = DeriveInference::analyze(&classes, &[func]);
+        assert_eq!(
+            derives.derives("Order"),
+            [Derive::Serialize, Derive::Deserialize]
+        );
+        assert_eq!(
+            derives.derives("Line"),
+            [Derive::Clone, Derive::Serialize, Derive::Deserialize]
+        );
+        assert_eq!(derives.json_callers().collect::<Vec<_>>(), ["f"]);
+    }
+
+    #[test]
+    fn test_iterated_elements_are_cloned_and_printed() {
+        let loop_stmt = HirStmt::For {
+            target: AssignTarget::Symbol("item".to_string()),
+            iter: HirExpr::Var("items".to_string()),
+            body: vec![HirStmt::Expr(HirExpr::FString {
+                parts: vec![FStringPart::Expr(var("item"))],
+            })],
+        };
+        let func = HirFunctionBuilder::new("f")
+            .param("items", Type::List(Box::new(custom("Item"))))
+            .body(vec![loop_stmt])
+            .build();
+        let derives = DeriveInference::analyze(&[class("Item", vec![])], &[func]);
+        assert_eq!(derives.derives("Item"), [Derive::Debug, Derive::Clone]);
+    }
+}
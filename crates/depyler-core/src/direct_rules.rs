@@ -1,7 +1,16 @@
+use crate::derive_inference::{Derive, DeriveInference};
 use crate::hir::*;
+use crate::method_mutation::MutatingMethods;
+use crate::recursive_types::{
+    box_value, unboxed_type, BoxKind, BoxedFieldRewriter, RecursiveTypes,
+};
+use crate::rust_gen::assert_gen;
+use crate::rust_gen::percent_format_gen::{self, Conversion, FieldArg};
 use crate::type_mapper::{RustType, TypeMapper};
+use crate::visibility::VisibilityInference;
 use anyhow::{bail, Result};
 use quote::quote;
+use syn::visit_mut::{self, VisitMut};
 use syn::{self, parse_quote};
 
 /// Check if a name is a Rust keyword that requires raw identifier syntax
@@ -142,6 +151,7 @@ fn extract_nested_indices(
 /// assert!(rust_file.items.len() > 0); // Should have at least std imports + function
 /// ```
 pub fn apply_rules(module: &HirModule, type_mapper: &TypeMapper) -> Result<syn::File> {
+    let type_mapper = &type_mapper.clone().with_type_aliases(&module.type_aliases);
     let mut items = Vec::new();
 
     // Add standard imports
@@ -162,14 +172,21 @@ pub fn apply_rules(module: &HirModule, type_mapper: &TypeMapper) -> Result<syn::
     }
 
     // Convert classes to structs
-    for class in &module.classes {
-        let struct_items = convert_class_to_struct(class, type_mapper)?;
+    let classes = RecursiveTypes::analyze(&module.classes).box_classes(&module.classes);
+    let derives = DeriveInference::analyze(&module.classes, &module.functions);
+    let mutating = MutatingMethods::analyze(&module.classes);
+    for class in &classes {
+        let struct_items = convert_class_to_struct(class, type_mapper, &derives, &mutating)?;
         items.extend(struct_items);
     }
 
     // Convert functions
+    let visibility =
+        VisibilityInference::analyze(&module.functions, &module.classes, &module.constants);
     for func in &module.functions {
-        let rust_func = convert_function(func, type_mapper)?;
+        let mut rust_func = convert_function(func, type_mapper)?;
+        let vis = visibility.tokens(&func.name);
+        rust_func.vis = parse_quote! { #vis };
         items.push(syn::Item::Fn(rust_func));
     }
 
@@ -268,6 +285,7 @@ fn convert_protocol_to_trait(protocol: &Protocol, type_mapper: &TypeMapper) -> R
 ///
 /// * `class` - The HIR class to convert
 /// * `type_mapper` - Type mapper for resolving Python types to Rust types
+/// * `derives` - Derives inferred from how the module uses its classes
 ///
 /// # Returns
 ///
@@ -276,8 +294,10 @@ fn convert_protocol_to_trait(protocol: &Protocol, type_mapper: &TypeMapper) -> R
 /// # Example
 ///
 /// ```
+/// use depyler_core::derive_inference::DeriveInference;
 /// use depyler_core::hir::*;
 /// use depyler_core::direct_rules::convert_class_to_struct;
+/// use depyler_core::method_mutation::MutatingMethods;
 /// use depyler_core::type_mapper::TypeMapper;
 /// use smallvec::smallvec;
 ///
@@ -300,16 +320,22 @@ fn convert_protocol_to_trait(protocol: &Protocol, type_mapper: &TypeMapper) -> R
 ///     ],
 ///     methods: vec![],
 ///     is_dataclass: true,
+///     model_kind: None,
+///     annotations: Default::default(),
 ///     docstring: Some("A 2D point".to_string()),
 /// };
 ///
 /// let type_mapper = TypeMapper::new();
-/// let items = convert_class_to_struct(&class, &type_mapper).unwrap();
+/// let derives = DeriveInference::analyze(std::slice::from_ref(&class), &[]);
+/// let mutating = MutatingMethods::analyze(std::slice::from_ref(&class));
+/// let items = convert_class_to_struct(&class, &type_mapper, &derives, &mutating).unwrap();
 /// assert!(!items.is_empty()); // Should have at least the struct definition
 /// ```
 pub fn convert_class_to_struct(
     class: &HirClass,
     type_mapper: &TypeMapper,
+    derives: &DeriveInference,
+    mutating: &MutatingMethods,
 ) -> Result<Vec<syn::Item>> {
     let mut items = Vec::new();
     let struct_name = syn::Ident::new(&class.name, proc_macro2::Span::call_site());
@@ -335,13 +361,28 @@ pub fn convert_class_to_struct(
         });
     }
 
+    // Derive what the module's use of the class needs; models also (de)serialize
+    let mut class_derives = derives.derives(&class.name);
+    if class.model_kind.is_some() {
+        for serde_derive in [Derive::Serialize, Derive::Deserialize] {
+            if !class_derives.contains(&serde_derive) {
+                class_derives.push(serde_derive);
+            }
+        }
+    }
+    let derive_paths = class_derives
+        .into_iter()
+        .map(|d| syn::parse_str::<syn::Path>(d.as_str()))
+        .collect::<syn::Result<Vec<_>>>()?;
+    let attrs = if derive_paths.is_empty() {
+        vec![]
+    } else {
+        vec![parse_quote! { #[derive(#(#derive_paths),*)] }]
+    };
+
     // Create the struct
     let struct_item = syn::Item::Struct(syn::ItemStruct {
-        attrs: if class.is_dataclass {
-            vec![parse_quote! { #[derive(Debug, Clone, PartialEq)] }]
-        } else {
-            vec![parse_quote! { #[derive(Debug, Clone)] }]
-        },
+        attrs,
         vis: syn::Visibility::Public(syn::Token![pub](proc_macro2::Span::call_site())),
         struct_token: syn::Token![struct](proc_macro2::Span::call_site()),
         ident: struct_name.clone(),
@@ -374,6 +415,13 @@ pub fn convert_class_to_struct(
 
     // Check if class has explicit __init__
     let has_init = class.methods.iter().any(|m| m.name == "__init__");
+    let boxed_fields = BoxedFieldRewriter::boxed_fields(class);
+    let convert_method = |method: &HirMethod| -> Result<syn::ImplItem> {
+        let mutates_self = mutating.mutates_self(&class.name, &method.name);
+        let mut rust_method = convert_method_to_impl_item(method, mutates_self, type_mapper)?;
+        BoxedFieldRewriter::new(&boxed_fields).rewrite_block(&mut rust_method.block);
+        Ok(syn::ImplItem::Fn(rust_method))
+    };
 
     // Convert __init__ to new() if present, or generate default new() for dataclasses
     if has_init {
@@ -382,8 +430,7 @@ pub fn convert_class_to_struct(
                 let new_method = convert_init_to_new(method, class, &struct_name, type_mapper)?;
                 impl_items.push(syn::ImplItem::Fn(new_method));
             } else {
-                let rust_method = convert_method_to_impl_item(method, type_mapper)?;
-                impl_items.push(syn::ImplItem::Fn(rust_method));
+                impl_items.push(convert_method(method)?);
             }
         }
     } else {
@@ -398,10 +445,13 @@ pub fn convert_class_to_struct(
             impl_items.push(syn::ImplItem::Fn(new_method));
         }
 
-        // Add other methods
+        // Add other methods (a dataclass `__post_init__` is folded into new())
+        let folds_post_init = class.post_init().is_some();
         for method in &class.methods {
-            let rust_method = convert_method_to_impl_item(method, type_mapper)?;
-            impl_items.push(syn::ImplItem::Fn(rust_method));
+            if folds_post_init && method.name == "__post_init__" {
+                continue;
+            }
+            impl_items.push(convert_method(method)?);
         }
     }
 
@@ -439,7 +489,8 @@ fn generate_dataclass_new(
 
     for field in &fields_without_defaults {
         let param_ident = syn::Ident::new(&field.name, proc_macro2::Span::call_site());
-        let rust_type = type_mapper.map_type(&field.field_type);
+        // Recursive fields are boxed in the body, callers pass the plain value
+        let rust_type = type_mapper.map_type(&unboxed_type(&field.field_type));
         let param_syn_type = rust_type_to_syn_type(&rust_type)?;
 
         inputs.push(syn::FnArg::Typed(syn::PatType {
@@ -463,27 +514,18 @@ fn generate_dataclass_new(
         .filter(|f| !f.is_class_var) // Skip class constants
         .map(|field| {
             let field_ident = syn::Ident::new(&field.name, proc_macro2::Span::call_site());
-            if field.default_value.is_some() {
-                // Use default value - for now just use Default::default() or 0 for int
-                if field.field_type == Type::Int {
-                    quote! { #field_ident: 0 }
-                } else {
-                    quote! { #field_ident: Default::default() }
+            match &field.default_value {
+                Some(default) => {
+                    let value = field_default(default, type_mapper)?;
+                    Ok(quote! { #field_ident: #value })
                 }
-            } else {
                 // Use parameter
-                quote! { #field_ident }
+                None => Ok(boxed_field_init(field, &field_ident)),
             }
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>>>()?;
 
-    let body = parse_quote! {
-        {
-            Self {
-                #(#field_inits),*
-            }
-        }
-    };
+    let (body, output_type) = constructor_body(class, &field_inits, type_mapper)?;
 
     Ok(syn::ImplItemFn {
         attrs: vec![],
@@ -502,13 +544,25 @@ fn generate_dataclass_new(
             variadic: None,
             output: syn::ReturnType::Type(
                 syn::Token![->](proc_macro2::Span::call_site()),
-                Box::new(parse_quote! { Self }),
+                Box::new(output_type),
             ),
         },
         block: body,
     })
 }
 
+/// Initializer for a field declared with a default value
+fn field_default(default: &HirExpr, type_mapper: &TypeMapper) -> Result<syn::Expr> {
+    match default {
+        HirExpr::Literal(Literal::None) => Ok(parse_quote! { None }),
+        HirExpr::List(elts) | HirExpr::Set(elts) if elts.is_empty() => {
+            Ok(parse_quote! { Default::default() })
+        }
+        HirExpr::Dict(items) if items.is_empty() => Ok(parse_quote! { Default::default() }),
+        other => convert_expr(other, type_mapper),
+    }
+}
+
 fn convert_init_to_new(
     init_method: &HirMethod,
     class: &HirClass,
@@ -556,7 +610,7 @@ fn convert_init_to_new(
             .any(|param| param.name == field.name)
         {
             // Initialize from parameter
-            field_inits.push(quote! { #field_ident });
+            field_inits.push(boxed_field_init(field, &field_ident));
         } else {
             // Initialize with default value based on type
             let default_value = match &field.field_type {
@@ -573,13 +627,7 @@ fn convert_init_to_new(
         }
     }
 
-    let body = parse_quote! {
-        {
-            Self {
-                #(#field_inits),*
-            }
-        }
-    };
+    let (body, output_type) = constructor_body(class, &field_inits, type_mapper)?;
 
     Ok(syn::ImplItemFn {
         attrs: vec![],
@@ -598,13 +646,173 @@ fn convert_init_to_new(
             variadic: None,
             output: syn::ReturnType::Type(
                 syn::Token![->](proc_macro2::Span::call_site()),
-                Box::new(parse_quote! { Self }),
+                Box::new(output_type),
             ),
         },
         block: body,
     })
 }
 
+/// Body and return type of `new()`
+///
+/// A dataclass `__post_init__` runs right after the fields are set, so its
+/// statements are folded into the constructor with `self` bound to the new
+/// instance. If it raises, `new()` returns `Result<Self, E>` and each
+/// `raise` becomes an early `return Err(..)`.
+fn constructor_body(
+    class: &HirClass,
+    field_inits: &[proc_macro2::TokenStream],
+    type_mapper: &TypeMapper,
+) -> Result<(syn::Block, syn::Type)> {
+    let post_init = match class.post_init() {
+        Some(method) => method,
+        None => {
+            let body = parse_quote! {
+                {
+                    Self {
+                        #(#field_inits),*
+                    }
+                }
+            };
+            return Ok((body, parse_quote! { Self }));
+        }
+    };
+
+    let error_type = post_init_error_type(class);
+    let mut block = convert_block_with_context(&post_init.body, type_mapper, false)?;
+    BoxedFieldRewriter::new(&BoxedFieldRewriter::boxed_fields(class)).rewrite_block(&mut block);
+    PostInitFolder {
+        fallible: error_type.is_some(),
+        boxed_error: error_type
+            .as_deref()
+            .is_some_and(|ty| ty.contains("Box<dyn")),
+    }
+    .visit_block_mut(&mut block);
+
+    let mutating = MutatingMethods::analyze(std::slice::from_ref(class));
+    let binding = if mutating.mutates_self(&class.name, &post_init.name) {
+        quote! { let mut this }
+    } else {
+        quote! { let this }
+    };
+    let stmts = &block.stmts;
+
+    match error_type {
+        Some(error_type) => {
+            let error_type: syn::Type = syn::parse_str(&error_type)?;
+            let body = parse_quote! {
+                {
+                    #binding = Self {
+                        #(#field_inits),*
+                    };
+                    #(#stmts)*
+                    Ok(this)
+                }
+            };
+            Ok((body, parse_quote! { Result<Self, #error_type> }))
+        }
+        None => {
+            let body = parse_quote! {
+                {
+                    #binding = Self {
+                        #(#field_inits),*
+                    };
+                    #(#stmts)*
+                    this
+                }
+            };
+            Ok((body, parse_quote! { Self }))
+        }
+    }
+}
+
+/// Error type `new()` returns when the class's `__post_init__` can raise
+///
+/// A single exception type is returned as is; several are unified as
+/// `Box<dyn std::error::Error>`, matching function return types.
+pub fn post_init_error_type(class: &HirClass) -> Option<String> {
+    let post_init = class.post_init()?;
+    let mut raised = crate::ast_bridge::FunctionAnalyzer::raised_exception_types(&post_init.body);
+    match raised.len() {
+        0 => None,
+        1 => raised.pop(),
+        _ => Some("Box<dyn std::error::Error>".to_string()),
+    }
+}
+
+/// Rewrites a converted `__post_init__` body for inlining into `new()`
+///
+/// - `self` becomes the `this` binding of the instance under construction
+/// - `raise` (lowered to `panic!("Exception: {}", e)`) becomes `return Err(e)`
+/// - a bare `return` returns the instance
+struct PostInitFolder {
+    fallible: bool,
+    boxed_error: bool,
+}
+
+impl VisitMut for PostInitFolder {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if self.fallible {
+            if let Some(mut exception) = raised_exception(expr) {
+                self.visit_expr_mut(&mut exception);
+                *expr = if self.boxed_error {
+                    parse_quote! { return Err(Box::new(#exception)) }
+                } else {
+                    parse_quote! { return Err(#exception) }
+                };
+                return;
+            }
+        }
+        match expr {
+            syn::Expr::Path(path) if path.path.is_ident("self") => {
+                *expr = parse_quote! { this };
+            }
+            syn::Expr::Return(ret) if ret.expr.is_none() => {
+                *expr = if self.fallible {
+                    parse_quote! { return Ok(this) }
+                } else {
+                    parse_quote! { return this }
+                };
+            }
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+}
+
+/// The exception of a lowered `raise` statement
+fn raised_exception(expr: &syn::Expr) -> Option<syn::Expr> {
+    let mac = match expr {
+        syn::Expr::Macro(mac) if mac.mac.path.is_ident("panic") => &mac.mac,
+        _ => return None,
+    };
+    let args = mac
+        .parse_body_with(syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated)
+        .ok()?;
+    let mut args = args.into_iter();
+    match (args.next(), args.next(), args.next()) {
+        (
+            Some(syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(format),
+                ..
+            })),
+            Some(exception),
+            None,
+        ) if format.value() == "Exception: {}" => Some(exception),
+        _ => None,
+    }
+}
+
+/// Field initializer from the parameter of the same name, boxing recursive fields
+fn boxed_field_init(field: &HirField, ident: &syn::Ident) -> proc_macro2::TokenStream {
+    match BoxKind::of(&field.field_type) {
+        Some(kind) => {
+            let value = box_value(kind, &parse_quote! { #ident });
+            quote! { #ident: #value }
+        }
+        None => quote! { #ident },
+    }
+}
+
 /// Check if a method mutates self (requires &mut self)
 /// Scans the method body for assignments to self attributes
 pub fn method_mutates_self(method: &HirMethod) -> bool {
@@ -643,6 +851,7 @@ fn stmt_mutates_self(stmt: &HirStmt) -> bool {
 
 fn convert_method_to_impl_item(
     method: &HirMethod,
+    mutates_self: bool,
     type_mapper: &TypeMapper,
 ) -> Result<syn::ImplItemFn> {
     // DEPYLER-0306 FIX: Use raw identifiers for method names that are Rust keywords
@@ -666,8 +875,9 @@ fn convert_method_to_impl_item(
         // Properties typically use &self
         inputs.push(parse_quote! { &self });
     } else {
-        // Regular instance methods: use &mut self if method mutates self, otherwise &self
-        if method_mutates_self(method) {
+        // Regular instance methods: use &mut self if method mutates self, directly
+        // or through the methods it calls, otherwise &self
+        if mutates_self {
             inputs.push(parse_quote! { &mut self });
         } else {
             inputs.push(parse_quote! { &self });
@@ -1390,11 +1600,17 @@ fn convert_stmt_with_context(
             Ok(syn::Stmt::Expr(if_expr, Some(Default::default())))
         }
         HirStmt::While { condition, body } => {
-            let cond = convert_expr_with_context(condition, type_mapper, is_classmethod)?;
             let body_block = convert_block_with_context(body, type_mapper, is_classmethod)?;
 
-            let while_expr = parse_quote! {
-                while #cond #body_block
+            let while_expr = if condition.is_always_true() {
+                parse_quote! {
+                    loop #body_block
+                }
+            } else {
+                let cond = convert_expr_with_context(condition, type_mapper, is_classmethod)?;
+                parse_quote! {
+                    while #cond #body_block
+                }
             };
 
             Ok(syn::Stmt::Expr(while_expr, Some(Default::default())))
@@ -1563,18 +1779,18 @@ fn convert_stmt_with_context(
         HirStmt::Assert { test, msg } => {
             // Generate assert! macro call
             let test_expr = convert_expr_with_context(test, type_mapper, is_classmethod)?;
-            let assert_macro: syn::Stmt = if let Some(message) = msg {
-                let msg_expr = convert_expr_with_context(message, type_mapper, is_classmethod)?;
-                parse_quote! { assert!(#test_expr, "{}", #msg_expr); }
-            } else {
-                parse_quote! { assert!(#test_expr); }
-            };
-            Ok(assert_macro)
+            let assert_macro = assert_gen::codegen_assert(&test_expr, msg.as_ref(), |value| {
+                convert_expr_with_context(value, type_mapper, is_classmethod)
+            })?;
+            Ok(parse_quote! { #assert_macro })
         }
         HirStmt::Pass => {
             // Pass statement generates empty statement
             Ok(syn::Stmt::Expr(parse_quote! { {} }, None))
         }
+        HirStmt::Match { .. } => {
+            bail!("Match statements are not yet supported in class methods")
+        }
     }
 }
 
@@ -1682,12 +1898,37 @@ impl<'a> ExprConverter<'a> {
         Ok(convert_literal(lit))
     }
 
+    /// `template % args`; only literal arguments have a known type here
+    fn convert_percent_format(&self, template: &str, args: &HirExpr) -> Result<syn::Expr> {
+        percent_format_gen::codegen_percent_format(template, args, |arg, conversion| {
+            let arg_expr = self.convert(arg)?;
+            let ty = match arg {
+                HirExpr::Literal(Literal::Int(_)) => Type::Int,
+                HirExpr::Literal(Literal::Float(_)) => Type::Float,
+                HirExpr::Literal(Literal::Bool(_)) => Type::Bool,
+                _ => Type::Unknown,
+            };
+            Ok(match conversion {
+                Conversion::Str => FieldArg::display(arg_expr),
+                Conversion::Repr => FieldArg {
+                    expr: arg_expr,
+                    debug: true,
+                },
+                _ => FieldArg::display(percent_format_gen::numeric_arg(conversion, arg_expr, &ty)),
+            })
+        })
+    }
+
     fn convert_variable(&self, name: &str) -> Result<syn::Expr> {
         let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
         Ok(parse_quote! { #ident })
     }
 
     fn convert_binary(&self, op: BinOp, left: &HirExpr, right: &HirExpr) -> Result<syn::Expr> {
+        if let (BinOp::Mod, HirExpr::Literal(Literal::String(template))) = (op, left) {
+            return self.convert_percent_format(template, right);
+        }
+
         let left_expr = self.convert(left)?;
         let right_expr = self.convert(right)?;
 
@@ -2875,6 +3116,11 @@ mod tests {
                 error_types: vec![],
                 is_async: false,
                 is_generator: false,
+                is_context_manager: false,
+                is_async_entry: false,
+                memoize: None,
+                mutated_params: None,
+                complexity: None,
             },
             annotations: TranspilationAnnotations::default(),
             docstring: None,
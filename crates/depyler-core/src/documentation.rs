@@ -637,6 +637,8 @@ mod tests {
             }],
             base_classes: vec![],
             is_dataclass: false,
+            model_kind: None,
+            annotations: Default::default(),
             docstring: Some("A test class.".to_string()),
         };
 
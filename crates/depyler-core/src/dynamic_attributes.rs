@@ -0,0 +1,304 @@
+//! Detection of attributes named at runtime
+//!
+//! `getattr(obj, "x")`, `setattr(obj, "x", v)` and `hasattr(obj, "x")`
+//! with a literal name are field accesses in disguise and are lowered as
+//! such. When the name is computed, `getattr(obj, name)`, there is no
+//! field to access: Rust resolves fields at compile time. [`check`] rejects
+//! those calls with one line per call site, as does a literal name that no
+//! Rust field can have, like `"max-size"`.
+
+use crate::hir::{AssignTarget, HirExpr, HirModule, HirStmt, Literal};
+use crate::hir_spans::HirSpans;
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// Builtins taking the attribute name as their second argument
+const ATTRIBUTE_BUILTINS: &[&str] = &["getattr", "setattr", "hasattr"];
+
+/// One call naming an attribute at runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicAttribute {
+    /// `getattr`, `setattr` or `hasattr`
+    pub builtin: String,
+    /// Function or `Class.method` the call is in
+    pub function: String,
+    /// The call, written as Python with its arguments abbreviated
+    pub call: String,
+    /// Path of the statement in `function`, as [`FunctionSpans::statement`]
+    /// takes it
+    ///
+    /// [`FunctionSpans::statement`]: crate::hir_spans::FunctionSpans::statement
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for DynamicAttribute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` in `{}` names the attribute at runtime",
+            self.call, self.function
+        )
+    }
+}
+
+/// Whether `name` can be a Rust field name
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Every call in `module` naming an attribute at runtime, in source order
+pub fn find(module: &HirModule) -> Vec<DynamicAttribute> {
+    let mut calls = Vec::new();
+    for func in &module.functions {
+        let mut finder = Finder::new(&func.name);
+        finder.stmts(&func.body, &mut Vec::new());
+        calls.append(&mut finder.calls);
+    }
+    for class in &module.classes {
+        for method in &class.methods {
+            let name = format!("{}.{}", class.name, method.name);
+            let mut finder = Finder::new(&name);
+            finder.stmts(&method.body, &mut Vec::new());
+            calls.append(&mut finder.calls);
+        }
+    }
+    calls
+}
+
+/// Fails with a report of every call in `module` naming an attribute at
+/// runtime
+///
+/// With `spans` each call is prefixed with the line and column of its
+/// statement.
+pub fn check(module: &HirModule, spans: Option<&HirSpans>) -> Result<()> {
+    let calls = find(module);
+    if calls.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = calls
+        .iter()
+        .map(|call| match position(call, spans) {
+            Some((line, column)) => format!("  line {}, column {}: {}", line, column, call),
+            None => format!("  {}", call),
+        })
+        .collect();
+    bail!(
+        "Attributes named at runtime are not supported; getattr(), setattr() and hasattr() \
+         become field accesses, which need the name as a string literal:\n{}",
+        lines.join("\n")
+    )
+}
+
+fn position(call: &DynamicAttribute, spans: Option<&HirSpans>) -> Option<(usize, usize)> {
+    let spans = spans?;
+    let stmt = spans.function(&call.function)?.statement(&call.path)?;
+    let position = spans.position(stmt.span.start);
+    Some((position.line, position.column))
+}
+
+/// Walks one function body, following the statement paths of `HirSpans`
+struct Finder<'a> {
+    function: &'a str,
+    calls: Vec<DynamicAttribute>,
+}
+
+impl<'a> Finder<'a> {
+    fn new(function: &'a str) -> Self {
+        Self {
+            function,
+            calls: Vec::new(),
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        for (index, stmt) in stmts.iter().enumerate() {
+            path.push(index);
+            self.stmt(stmt, path);
+            path.pop();
+        }
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, path: &mut Vec<usize>) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.target(target, path);
+                self.expr(value, path);
+            }
+            HirStmt::Expr(expr) => self.expr(expr, path),
+            HirStmt::Return(value) => value.iter().for_each(|v| self.expr(v, path)),
+            HirStmt::Raise { exception, cause } => exception
+                .iter()
+                .chain(cause)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::Assert { test, msg } => std::iter::once(test)
+                .chain(msg)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition, path);
+                self.block(0, then_body, path);
+                if let Some(else_body) = else_body {
+                    self.block(1, else_body, path);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition, path);
+                self.block(0, body, path);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter, path);
+                self.block(0, body, path);
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context, path);
+                self.block(0, body, path);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.block(0, body, path);
+                for (i, handler) in handlers.iter().enumerate() {
+                    self.block(1 + i, &handler.body, path);
+                }
+                if let Some(orelse) = orelse {
+                    self.block(1 + handlers.len(), orelse, path);
+                }
+                if let Some(finalbody) = finalbody {
+                    self.block(2 + handlers.len(), finalbody, path);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject, path);
+                for (i, case) in cases.iter().enumerate() {
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard, path);
+                    }
+                    self.block(i, &case.body, path);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn block(&mut self, block: usize, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        path.push(block);
+        self.stmts(stmts, path);
+        path.pop();
+    }
+
+    fn target(&mut self, target: &AssignTarget, path: &[usize]) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Index { base, index } => {
+                self.expr(base, path);
+                self.expr(index, path);
+            }
+            AssignTarget::Attribute { value, .. } => self.expr(value, path),
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t, path)),
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr, path: &[usize]) {
+        if let HirExpr::Call { func, args, .. } = expr {
+            if ATTRIBUTE_BUILTINS.contains(&func.as_str()) {
+                let literal = match args.get(1) {
+                    Some(HirExpr::Literal(Literal::String(name))) => is_identifier(name),
+                    _ => false,
+                };
+                if !literal {
+                    self.report(func, args, path);
+                }
+            }
+        }
+        for child in expr.children() {
+            self.expr(child, path);
+        }
+    }
+
+    fn report(&mut self, builtin: &str, args: &[HirExpr], path: &[usize]) {
+        let args: Vec<String> = args.iter().map(abbreviate).collect();
+        self.calls.push(DynamicAttribute {
+            builtin: builtin.to_string(),
+            function: self.function.to_string(),
+            call: format!("{}({})", builtin, args.join(", ")),
+            path: path.to_vec(),
+        });
+    }
+}
+
+/// Names and literals as they are written, anything else as `...`
+fn abbreviate(expr: &HirExpr) -> String {
+    match expr {
+        HirExpr::Var(name) => name.clone(),
+        HirExpr::Literal(Literal::String(s)) => format!("{:?}", s),
+        HirExpr::Literal(Literal::Int(n)) => n.to_string(),
+        HirExpr::Literal(Literal::None) => "None".to_string(),
+        HirExpr::Attribute { value, attr } => format!("{}.{}", abbreviate(value), attr),
+        _ => "...".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = "\
+class Point:
+    def __init__(self, x: int, y: int):
+        self.x = x
+        self.y = y
+
+def read(p: Point, name: str) -> int:
+    if hasattr(p, \"x\"):
+        return getattr(p, name)
+    setattr(p, \"max-size\", 1)
+    return getattr(p, \"y\", 0)
+";
+
+    #[test]
+    fn test_finds_computed_and_non_identifier_names() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let found = find(&module);
+        let calls: Vec<(&str, &str)> = found
+            .iter()
+            .map(|c| (c.builtin.as_str(), c.call.as_str()))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                ("getattr", "getattr(p, name)"),
+                ("setattr", "setattr(p, \"max-size\", 1)"),
+            ]
+        );
+        assert_eq!(found[0].path, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_check_reports_lines_and_calls() {
+        let pipeline = DepylerPipeline::new();
+        let (module, spans) = pipeline.parse_to_hir_with_spans(SOURCE).unwrap();
+        let err = check(&module, Some(&spans)).unwrap_err().to_string();
+        assert!(err.contains(
+            "line 8, column 9: `getattr(p, name)` in `read` names the attribute at runtime"
+        ));
+        assert!(err.contains("line 9, column 5: `setattr(p, \"max-size\", 1)` in `read`"));
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert!(is_identifier("max_size"));
+        assert!(is_identifier("_x1"));
+        assert!(!is_identifier("max-size"));
+        assert!(!is_identifier("1x"));
+        assert!(!is_identifier(""));
+    }
+}
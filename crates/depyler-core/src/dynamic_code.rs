@@ -0,0 +1,292 @@
+//! Detection of code run from strings
+//!
+//! `eval("60 * 60")` of a constant expression is folded to that expression
+//! while converting to HIR. Every other `eval()` and every `exec()` runs
+//! Python source that only exists at runtime, and the generated program has
+//! no interpreter to run it with. [`check`] rejects those calls with one line
+//! per call site rather than emitting a call to a function that does not
+//! exist.
+
+use crate::hir::{AssignTarget, HirExpr, HirModule, HirStmt, Literal};
+use crate::hir_spans::HirSpans;
+use anyhow::{bail, Result};
+use std::fmt;
+
+/// Builtins running Python source given as a string
+const CODE_BUILTINS: &[&str] = &["eval", "exec"];
+
+/// One call running code built at runtime
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicCode {
+    /// `eval` or `exec`
+    pub builtin: String,
+    /// Function or `Class.method` the call is in
+    pub function: String,
+    /// The call, written as Python with its arguments abbreviated
+    pub call: String,
+    /// Path of the statement in `function`, as [`FunctionSpans::statement`]
+    /// takes it
+    ///
+    /// [`FunctionSpans::statement`]: crate::hir_spans::FunctionSpans::statement
+    pub path: Vec<usize>,
+}
+
+impl fmt::Display for DynamicCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` in `{}` runs Python code at runtime",
+            self.call, self.function
+        )
+    }
+}
+
+/// Every `eval()` and `exec()` call left in `module`, in source order
+///
+/// A module defining its own `eval` or `exec` function calls that instead,
+/// and those calls are not reported.
+pub fn find(module: &HirModule) -> Vec<DynamicCode> {
+    let builtins: Vec<&str> = CODE_BUILTINS
+        .iter()
+        .copied()
+        .filter(|name| !module.functions.iter().any(|f| f.name == *name))
+        .collect();
+    let mut calls = Vec::new();
+    for func in &module.functions {
+        let mut finder = Finder::new(&func.name, &builtins);
+        finder.stmts(&func.body, &mut Vec::new());
+        calls.append(&mut finder.calls);
+    }
+    for class in &module.classes {
+        for method in &class.methods {
+            let name = format!("{}.{}", class.name, method.name);
+            let mut finder = Finder::new(&name, &builtins);
+            finder.stmts(&method.body, &mut Vec::new());
+            calls.append(&mut finder.calls);
+        }
+    }
+    calls
+}
+
+/// Fails with a report of every `eval()` and `exec()` call in `module`
+///
+/// With `spans` each call is prefixed with the line and column of its
+/// statement.
+pub fn check(module: &HirModule, spans: Option<&HirSpans>) -> Result<()> {
+    let calls = find(module);
+    if calls.is_empty() {
+        return Ok(());
+    }
+    let lines: Vec<String> = calls
+        .iter()
+        .map(|call| match position(call, spans) {
+            Some((line, column)) => format!("  line {}, column {}: {}", line, column, call),
+            None => format!("  {}", call),
+        })
+        .collect();
+    bail!(
+        "eval() and exec() are not supported; only eval() of a string literal holding a \
+         constant expression, like eval(\"60 * 60\"), can be transpiled:\n{}",
+        lines.join("\n")
+    )
+}
+
+fn position(call: &DynamicCode, spans: Option<&HirSpans>) -> Option<(usize, usize)> {
+    let spans = spans?;
+    let stmt = spans.function(&call.function)?.statement(&call.path)?;
+    let position = spans.position(stmt.span.start);
+    Some((position.line, position.column))
+}
+
+/// Walks one function body, following the statement paths of `HirSpans`
+struct Finder<'a> {
+    function: &'a str,
+    builtins: &'a [&'a str],
+    calls: Vec<DynamicCode>,
+}
+
+impl<'a> Finder<'a> {
+    fn new(function: &'a str, builtins: &'a [&'a str]) -> Self {
+        Self {
+            function,
+            builtins,
+            calls: Vec::new(),
+        }
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        for (index, stmt) in stmts.iter().enumerate() {
+            path.push(index);
+            self.stmt(stmt, path);
+            path.pop();
+        }
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, path: &mut Vec<usize>) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.target(target, path);
+                self.expr(value, path);
+            }
+            HirStmt::Expr(expr) => self.expr(expr, path),
+            HirStmt::Return(value) => value.iter().for_each(|v| self.expr(v, path)),
+            HirStmt::Raise { exception, cause } => exception
+                .iter()
+                .chain(cause)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::Assert { test, msg } => std::iter::once(test)
+                .chain(msg)
+                .for_each(|e| self.expr(e, path)),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition, path);
+                self.block(0, then_body, path);
+                if let Some(else_body) = else_body {
+                    self.block(1, else_body, path);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition, path);
+                self.block(0, body, path);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter, path);
+                self.block(0, body, path);
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context, path);
+                self.block(0, body, path);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.block(0, body, path);
+                for (i, handler) in handlers.iter().enumerate() {
+                    self.block(1 + i, &handler.body, path);
+                }
+                if let Some(orelse) = orelse {
+                    self.block(1 + handlers.len(), orelse, path);
+                }
+                if let Some(finalbody) = finalbody {
+                    self.block(2 + handlers.len(), finalbody, path);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject, path);
+                for (i, case) in cases.iter().enumerate() {
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard, path);
+                    }
+                    self.block(i, &case.body, path);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn block(&mut self, block: usize, stmts: &[HirStmt], path: &mut Vec<usize>) {
+        path.push(block);
+        self.stmts(stmts, path);
+        path.pop();
+    }
+
+    fn target(&mut self, target: &AssignTarget, path: &[usize]) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Index { base, index } => {
+                self.expr(base, path);
+                self.expr(index, path);
+            }
+            AssignTarget::Attribute { value, .. } => self.expr(value, path),
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t, path)),
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr, path: &[usize]) {
+        if let HirExpr::Call { func, args, .. } = expr {
+            if self.builtins.contains(&func.as_str()) {
+                let args: Vec<String> = args.iter().map(abbreviate).collect();
+                self.calls.push(DynamicCode {
+                    builtin: func.clone(),
+                    function: self.function.to_string(),
+                    call: format!("{}({})", func, args.join(", ")),
+                    path: path.to_vec(),
+                });
+            }
+        }
+        for child in expr.children() {
+            self.expr(child, path);
+        }
+    }
+}
+
+/// Names and short strings as they are written, anything else as `...`
+fn abbreviate(expr: &HirExpr) -> String {
+    match expr {
+        HirExpr::Var(name) => name.clone(),
+        HirExpr::Literal(Literal::String(s)) if s.chars().count() <= 24 => format!("{:?}", s),
+        _ => "...".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = "\
+def settings(text: str) -> int:
+    timeout = eval(\"60 * 60\")
+    if timeout > 0:
+        return eval(text)
+    exec(\"import os\")
+    return timeout
+";
+
+    #[test]
+    fn test_constant_eval_is_folded_and_the_rest_found() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let found = find(&module);
+        let calls: Vec<(&str, &str)> = found
+            .iter()
+            .map(|c| (c.builtin.as_str(), c.call.as_str()))
+            .collect();
+        assert_eq!(
+            calls,
+            [("eval", "eval(text)"), ("exec", "exec(\"import os\")")]
+        );
+        assert_eq!(found[0].path, [1, 0, 0]);
+    }
+
+    #[test]
+    fn test_check_reports_lines_and_calls() {
+        let pipeline = DepylerPipeline::new();
+        let (module, spans) = pipeline.parse_to_hir_with_spans(SOURCE).unwrap();
+        let err = check(&module, Some(&spans)).unwrap_err().to_string();
+        assert!(err
+            .contains("line 4, column 9: `eval(text)` in `settings` runs Python code at runtime"));
+        assert!(err.contains("line 5, column 5: `exec(\"import os\")` in `settings`"));
+    }
+
+    #[test]
+    fn test_user_defined_eval_is_not_reported() {
+        let module = DepylerPipeline::new()
+            .parse_to_hir(
+                "\
+def eval(x: int) -> int:
+    return x
+
+def twice(x: int) -> int:
+    return eval(x) * 2
+",
+            )
+            .unwrap();
+        assert!(find(&module).is_empty());
+    }
+}
@@ -0,0 +1,479 @@
+//! C ABI wrappers for transpiled functions
+//!
+//! During a migration the original Python can call the transpiled code
+//! through `ctypes`. Module functions taking and returning simple types get
+//! a `#[no_mangle] pub extern "C"` wrapper in a `pub mod ffi` appended to
+//! the module, and a C header declaring them:
+//!
+//! | Python  | C parameter    | C result (`out`) |
+//! |---------|----------------|------------------|
+//! | `int`   | `int64_t`      | `int64_t *`      |
+//! | `float` | `double`       | `double *`       |
+//! | `bool`  | `bool`         | `bool *`         |
+//! | `str`   | `const char *` | `char **`        |
+//!
+//! Every wrapper returns a status code and passes the function's result
+//! through a trailing `out` pointer, which functions returning `None` do
+//! not take. A raised exception, a panic and an argument that is null or
+//! not UTF-8 each have their own code, and
+//! `<prefix>_last_error()` has the message of the thread's last failure.
+//! Strings returned through `out` belong to the caller, who frees them with
+//! `<prefix>_free_string()`.
+//!
+//! The wrappers are named `<prefix>_<function>`, so they do not clash with
+//! C functions of the same name, and the crate has to be built as a
+//! `cdylib`; see [`cargo_toml_gen::cdylib_target`](crate::cargo_toml_gen::cdylib_target).
+
+use crate::hir::{HirFunction, Type};
+use crate::rust_gen::keywords::safe_ident;
+use anyhow::Result;
+use quote::{format_ident, quote};
+
+/// Status codes of the wrappers, with their names in the header
+const STATUSES: [(&str, i32, &str); 4] = [
+    ("OK", 0, "the call succeeded"),
+    ("ERROR", 1, "the function raised an exception"),
+    ("PANIC", 2, "the function panicked"),
+    (
+        "INVALID_ARGUMENT",
+        3,
+        "an argument was null, not UTF-8 or out of range",
+    ),
+];
+
+/// The wrappers of a module and the header declaring them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfiBindings {
+    /// The `pub mod ffi` to append to the module
+    pub rust: String,
+    /// A C header for the wrappers
+    pub header: String,
+    /// Functions left without a wrapper, as their types have no C form
+    pub skipped: Vec<String>,
+}
+
+/// A type crossing the boundary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FfiType {
+    Int,
+    Float,
+    Bool,
+    Str,
+}
+
+impl FfiType {
+    fn of(ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Int => Some(FfiType::Int),
+            Type::Float => Some(FfiType::Float),
+            Type::Bool => Some(FfiType::Bool),
+            Type::String => Some(FfiType::Str),
+            _ => None,
+        }
+    }
+
+    fn c_param(self) -> &'static str {
+        match self {
+            FfiType::Int => "int64_t",
+            FfiType::Float => "double",
+            FfiType::Bool => "bool",
+            FfiType::Str => "const char *",
+        }
+    }
+
+    fn c_out(self) -> &'static str {
+        match self {
+            FfiType::Int => "int64_t *",
+            FfiType::Float => "double *",
+            FfiType::Bool => "bool *",
+            FfiType::Str => "char **",
+        }
+    }
+
+    fn rust_param(self) -> proc_macro2::TokenStream {
+        match self {
+            FfiType::Int => quote! { i64 },
+            FfiType::Float => quote! { f64 },
+            FfiType::Bool => quote! { bool },
+            FfiType::Str => quote! { *const c_char },
+        }
+    }
+
+    fn rust_out(self) -> proc_macro2::TokenStream {
+        match self {
+            FfiType::Str => quote! { *mut c_char },
+            ty => ty.rust_param(),
+        }
+    }
+}
+
+/// A wrapper in Rust and its C prototype
+struct Wrapper {
+    rust: proc_macro2::TokenStream,
+    prototype: String,
+    takes_str: bool,
+    returns_str: bool,
+}
+
+/// Generator of the C ABI wrappers of a module
+pub struct FfiGenerator {
+    prefix: String,
+}
+
+impl FfiGenerator {
+    /// Wrappers named `<prefix>_<function>`; a crate name is a good prefix
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.replace('-', "_"),
+        }
+    }
+
+    /// The wrappers of the module functions `functions`, or `None` when
+    /// none of them can be wrapped
+    pub fn generate(&self, functions: &[HirFunction]) -> Result<Option<FfiBindings>> {
+        let mut wrappers = Vec::new();
+        let mut skipped = Vec::new();
+        for func in functions {
+            match self.wrap(func) {
+                Some(wrapper) => wrappers.push(wrapper),
+                None if func.name != "main" => skipped.push(func.name.clone()),
+                None => {}
+            }
+        }
+        if wrappers.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(FfiBindings {
+            rust: self.rust_module(&wrappers),
+            header: self.header(&wrappers),
+            skipped,
+        }))
+    }
+
+    fn wrap(&self, func: &HirFunction) -> Option<Wrapper> {
+        if func.properties.is_async || func.properties.is_generator || func.name == "main" {
+            return None;
+        }
+        let ret = match func.ret_type {
+            Type::None => None,
+            ref ty => Some(FfiType::of(ty)?),
+        };
+
+        let mut params = Vec::new();
+        let mut prelude = Vec::new();
+        let mut args = Vec::new();
+        let mut c_params = Vec::new();
+        let mut takes_str = false;
+        for param in &func.params {
+            let ty = FfiType::of(&param.ty)?;
+            if param.name == "out" {
+                return None;
+            }
+            let name = safe_ident(&param.name);
+            let rust_ty = ty.rust_param();
+            params.push(quote! { #name: #rust_ty });
+            c_params.push(format!("{}{}", c_declarator(ty.c_param()), param.name));
+            let label = &param.name;
+            args.push(match ty {
+                // Whatever width the function takes, if the value fits
+                FfiType::Int => {
+                    let message = format!("`{}` is out of range", param.name);
+                    quote! {
+                        #name.try_into().map_err(|_| {
+                            Failure::InvalidArgument(#message.to_string())
+                        })?
+                    }
+                }
+                FfiType::Float | FfiType::Bool => quote! { #name },
+                // `&str`, `String` and `Cow<str>` parameters all convert from `&str`
+                FfiType::Str => {
+                    takes_str = true;
+                    prelude.push(quote! { let #name = unsafe { str_arg(#name, #label) }?; });
+                    quote! { #name.into() }
+                }
+            });
+        }
+
+        let func_name = safe_ident(&func.name);
+        let mut call = quote! { super::#func_name(#(#args),*) };
+        if func.properties.can_fail {
+            call = quote! { #call.map_err(|e| Failure::Raised(e.to_string()))? };
+        }
+        let body = match ret {
+            None => quote! {
+                #call;
+            },
+            Some(ty) => {
+                let out_ty = ty.rust_out();
+                params.push(quote! { out: *mut #out_ty });
+                c_params.push(format!("{}out", c_declarator(ty.c_out())));
+                prelude.push(quote! { let out = unsafe { out_arg(out) }?; });
+                let value = match ty {
+                    FfiType::Int => quote! { value.into() },
+                    FfiType::Float | FfiType::Bool => quote! { value },
+                    FfiType::Str => quote! { string_out(value.to_string())? },
+                };
+                quote! {
+                    let value = #call;
+                    *out = #value;
+                }
+            }
+        };
+
+        let symbol = format_ident!("{}_{}", self.prefix, func.name);
+        let doc = format!(" `{}` for C; returns a status code", func.name);
+        // Only pointer arguments make a wrapper unsafe to call
+        let mut safety = Vec::new();
+        if takes_str {
+            safety.push("String arguments are null or NUL-terminated.".to_string());
+        }
+        if let Some(ty) = ret {
+            safety.push("`out` is null or valid for writes.".to_string());
+            if ty == FfiType::Str {
+                safety.push(format!(
+                    "The string stored in it is freed with `{}_free_string`.",
+                    self.prefix
+                ));
+            }
+        }
+        let (unsafety, safety_doc) = if safety.is_empty() {
+            (None, None)
+        } else {
+            let safety = format!(" {}", safety.join(" "));
+            (
+                Some(quote! { unsafe }),
+                Some(quote! {
+                    ///
+                    /// # Safety
+                    ///
+                    #[doc = #safety]
+                }),
+            )
+        };
+        let rust = quote! {
+            #[doc = #doc]
+            #safety_doc
+            #[no_mangle]
+            pub #unsafety extern "C" fn #symbol(#(#params),*) -> i32 {
+                status(panic::catch_unwind(AssertUnwindSafe(|| {
+                    #(#prelude)*
+                    #body
+                    Ok(())
+                })))
+            }
+        };
+        if c_params.is_empty() {
+            c_params.push("void".to_string());
+        }
+        Some(Wrapper {
+            rust,
+            prototype: format!("int32_t {}({});", symbol, c_params.join(", ")),
+            takes_str,
+            returns_str: ret == Some(FfiType::Str),
+        })
+    }
+
+    fn rust_module(&self, wrappers: &[Wrapper]) -> String {
+        let statuses = STATUSES.iter().map(|(name, code, meaning)| {
+            let name = format_ident!("{}", name);
+            let doc = format!(" Returned when {}", meaning);
+            quote! {
+                #[doc = #doc]
+                pub const #name: i32 = #code;
+            }
+        });
+        let last_error = format_ident!("{}_last_error", self.prefix);
+        let free_string = format_ident!("{}_free_string", self.prefix);
+        let str_arg = wrappers.iter().any(|w| w.takes_str).then(|| {
+            quote! {
+                /// `ptr` as a `&str`
+                unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, Failure> {
+                    if ptr.is_null() {
+                        return Err(Failure::InvalidArgument(format!("`{}` is null", name)));
+                    }
+                    CStr::from_ptr(ptr)
+                        .to_str()
+                        .map_err(|_| Failure::InvalidArgument(format!("`{}` is not UTF-8", name)))
+                }
+            }
+        });
+        let string_out = wrappers.iter().any(|w| w.returns_str).then(|| {
+            quote! {
+                /// `value` for C, to be freed with `free_string`
+                fn string_out(value: String) -> Result<*mut c_char, Failure> {
+                    CString::new(value)
+                        .map(CString::into_raw)
+                        .map_err(|_| Failure::Raised("the result contains a NUL byte".to_string()))
+                }
+            }
+        });
+        let items = wrappers.iter().map(|w| &w.rust);
+
+        let module = quote! {
+            /// C ABI wrappers of the module's functions, for calling them
+            /// through ctypes
+            // Arguments convert into whatever types the functions take
+            #[allow(clippy::useless_conversion)]
+            pub mod ffi {
+                use std::cell::RefCell;
+                use std::ffi::{c_char, CStr, CString};
+                use std::panic::{self, AssertUnwindSafe};
+
+                #(#statuses)*
+
+                thread_local! {
+                    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+                }
+
+                /// How a call failed
+                enum Failure {
+                    Raised(String),
+                    InvalidArgument(String),
+                }
+
+                /// The status code of a call, recording why it failed
+                fn status(result: std::thread::Result<Result<(), Failure>>) -> i32 {
+                    let (code, message) = match result {
+                        Ok(Ok(())) => return OK,
+                        Ok(Err(Failure::Raised(message))) => (ERROR, message),
+                        Ok(Err(Failure::InvalidArgument(message))) => (INVALID_ARGUMENT, message),
+                        Err(panic) => {
+                            let message = match panic.downcast::<String>() {
+                                Ok(message) => *message,
+                                Err(panic) => panic
+                                    .downcast::<&str>()
+                                    .map(|message| message.to_string())
+                                    .unwrap_or_else(|_| "panic".to_string()),
+                            };
+                            (PANIC, message)
+                        }
+                    };
+                    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+                    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+                    code
+                }
+
+                /// `out` as a reference
+                unsafe fn out_arg<'a, T>(out: *mut T) -> Result<&'a mut T, Failure> {
+                    out.as_mut()
+                        .ok_or_else(|| Failure::InvalidArgument("`out` is null".to_string()))
+                }
+
+                #str_arg
+                #string_out
+
+                /// The message of the last call on this thread that failed, or
+                /// null; valid until the next call fails
+                #[no_mangle]
+                pub extern "C" fn #last_error() -> *const c_char {
+                    LAST_ERROR.with(|last| {
+                        last.borrow()
+                            .as_ref()
+                            .map_or(std::ptr::null(), |message| message.as_ptr())
+                    })
+                }
+
+                /// Frees a string a wrapper returned
+                ///
+                /// # Safety
+                ///
+                /// `s` is null or a string a wrapper stored in its `out`, not
+                /// freed before.
+                #[no_mangle]
+                pub unsafe extern "C" fn #free_string(s: *mut c_char) {
+                    if !s.is_null() {
+                        drop(CString::from_raw(s));
+                    }
+                }
+
+                #(#items)*
+            }
+        };
+        crate::rust_gen::format_rust_code(module.to_string())
+    }
+
+    fn header(&self, wrappers: &[Wrapper]) -> String {
+        let guard = format!("{}_H", self.prefix.to_uppercase());
+        let upper = self.prefix.to_uppercase();
+        let mut header = format!(
+            "/* C declarations of the depyler-generated wrappers of {} */\n\n\
+             #ifndef {guard}\n#define {guard}\n\n\
+             #include <stdbool.h>\n#include <stdint.h>\n\n",
+            self.prefix
+        );
+        for (name, code, meaning) in STATUSES {
+            header.push_str(&format!("#define {upper}_{name} {code} /* {meaning} */\n"));
+        }
+        header.push_str(&format!(
+            "\n/* Message of this thread's last failed call, or NULL */\n\
+             const char *{prefix}_last_error(void);\n\
+             /* Frees a string returned through `out` */\n\
+             void {prefix}_free_string(char *s);\n\n",
+            prefix = self.prefix
+        ));
+        for wrapper in wrappers {
+            header.push_str(&wrapper.prototype);
+            header.push('\n');
+        }
+        header.push_str(&format!("\n#endif /* {guard} */\n"));
+        header
+    }
+}
+
+/// `ty` followed by a parameter name
+fn c_declarator(ty: &str) -> String {
+    if ty.ends_with('*') {
+        ty.to_string()
+    } else {
+        format!("{} ", ty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::{FunctionProperties, HirExpr, HirParam, HirStmt};
+    use depyler_annotations::TranspilationAnnotations;
+    use smallvec::smallvec;
+
+    fn function(name: &str, param: Type, ret_type: Type) -> HirFunction {
+        HirFunction {
+            name: name.to_string(),
+            params: smallvec![HirParam::new("x".to_string(), param)],
+            ret_type,
+            body: vec![HirStmt::Return(Some(HirExpr::Var("x".to_string())))],
+            properties: FunctionProperties::default(),
+            annotations: TranspilationAnnotations::default(),
+            docstring: None,
+        }
+    }
+
+    #[test]
+    fn test_only_simple_types_are_wrapped() {
+        let functions = [
+            function("double", Type::Int, Type::Int),
+            function("total", Type::List(Box::new(Type::Int)), Type::Int),
+            function("log", Type::String, Type::None),
+        ];
+        let bindings = FfiGenerator::new("calc")
+            .generate(&functions)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(bindings.skipped, vec!["total".to_string()]);
+        assert!(bindings
+            .header
+            .contains("int32_t calc_double(int64_t x, int64_t *out);"));
+        assert!(bindings.header.contains("int32_t calc_log(const char *x);"));
+        assert!(!bindings.header.contains("calc_total"));
+    }
+
+    #[test]
+    fn test_nothing_to_wrap() {
+        let functions = [function("keys", Type::Unknown, Type::Unknown)];
+        assert!(FfiGenerator::new("calc")
+            .generate(&functions)
+            .unwrap()
+            .is_none());
+    }
+}
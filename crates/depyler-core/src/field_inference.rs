@@ -0,0 +1,390 @@
+//! Types of class attributes, from what the methods store in them
+//!
+//! Fields of plain classes come from the `self.x = ...` assignments of
+//! `__init__`, and lowering only types those assigning a parameter or a
+//! literal. An empty `[]`, `{}` or `set()` gives a collection of `Unknown`.
+//! [`FieldTypeTable::analyze`] refines such fields by walking every method,
+//! `__init__` first, with the types of its parameters and of the locals
+//! assigned so far:
+//!
+//! - `self.x = value` types an `Unknown` field as `value`;
+//! - `self.items.append(v)`, `self.seen.add(v)` and `self.index[k] = v`
+//!   fill in the element types of a collection of `Unknown`.
+//!
+//! Annotated fields, such as those of dataclasses, are never changed. A
+//! field can have the type of another field, even of another class, so the
+//! walk repeats until nothing changes.
+//!
+//! With the table, [`FieldTypeTable::infer_attribute_type`] gives the type
+//! of `obj.attr` for any `obj` typed as a class of the module.
+
+use crate::hir::{AssignTarget, BinOp, HirClass, HirExpr, HirMethod, HirStmt, Literal, Type};
+use std::collections::HashMap;
+
+/// How many times the methods are walked at most
+const MAX_ROUNDS: usize = 8;
+
+/// Field types of the classes of a module
+#[derive(Debug, Clone, Default)]
+pub struct FieldTypeTable {
+    classes: HashMap<String, HashMap<String, Type>>,
+}
+
+impl FieldTypeTable {
+    /// Field types of `classes`, refined by their methods
+    pub fn analyze(classes: &[HirClass]) -> Self {
+        let mut table = Self {
+            classes: classes
+                .iter()
+                .map(|class| {
+                    let fields = class
+                        .fields
+                        .iter()
+                        .map(|field| (field.name.clone(), field.field_type.clone()))
+                        .collect();
+                    (class.name.clone(), fields)
+                })
+                .collect(),
+        };
+
+        for _ in 0..MAX_ROUNDS {
+            let mut changed = false;
+            for class in classes {
+                let init_first = class
+                    .methods
+                    .iter()
+                    .filter(|m| m.name == "__init__")
+                    .chain(class.methods.iter().filter(|m| m.name != "__init__"));
+                for method in init_first {
+                    changed |= table.refine_from_method(&class.name, method);
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        table
+    }
+
+    /// The type of field `field` of class `class`, if the class has it
+    pub fn field_type(&self, class: &str, field: &str) -> Option<&Type> {
+        self.classes.get(class)?.get(field)
+    }
+
+    /// The type of `value.attr` when `value` has type `value_type`
+    ///
+    /// `Unknown` unless `value_type` is a class of the module with a field
+    /// `attr`.
+    pub fn infer_attribute_type(&self, value_type: &Type, attr: &str) -> Type {
+        let class = match value_type {
+            Type::Custom(name) => name,
+            _ => return Type::Unknown,
+        };
+        self.field_type(class, attr)
+            .cloned()
+            .unwrap_or(Type::Unknown)
+    }
+
+    /// Writes the refined types into the fields of `classes`, returning the
+    /// number of fields changed
+    pub fn apply(&self, classes: &mut [HirClass]) -> usize {
+        let mut changed = 0;
+        for class in classes {
+            for field in &mut class.fields {
+                if let Some(ty) = self.field_type(&class.name, &field.name) {
+                    if *ty != field.field_type {
+                        field.field_type = ty.clone();
+                        changed += 1;
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    fn refine_from_method(&mut self, class: &str, method: &HirMethod) -> bool {
+        let mut locals: HashMap<String, Type> = method
+            .params
+            .iter()
+            .map(|param| (param.name.clone(), param.ty.clone()))
+            .collect();
+        if !method.is_static {
+            locals.insert("self".to_string(), Type::Custom(class.to_string()));
+        }
+        let mut changed = false;
+        self.refine_from_block(class, &method.body, &mut locals, &mut changed);
+        changed
+    }
+
+    fn refine_from_block(
+        &mut self,
+        class: &str,
+        body: &[HirStmt],
+        locals: &mut HashMap<String, Type>,
+        changed: &mut bool,
+    ) {
+        for stmt in body {
+            match stmt {
+                HirStmt::Assign { target, value, .. } => {
+                    let ty = self.expr_type(value, locals);
+                    match target {
+                        AssignTarget::Symbol(name) => {
+                            if !matches!(ty, Type::Unknown) {
+                                locals.insert(name.clone(), ty);
+                            }
+                        }
+                        AssignTarget::Attribute { value, attr } if is_self(value) => {
+                            *changed |= self.refine(class, attr, ty);
+                        }
+                        AssignTarget::Index { base, index } => {
+                            if let HirExpr::Attribute { value, attr } = base.as_ref() {
+                                if is_self(value) {
+                                    let key = self.expr_type(index, locals);
+                                    let dict = Type::Dict(Box::new(key), Box::new(ty));
+                                    *changed |= self.refine(class, attr, dict);
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                HirStmt::Expr(HirExpr::MethodCall {
+                    object,
+                    method,
+                    args,
+                    ..
+                }) if args.len() == 1 => {
+                    if let HirExpr::Attribute { value, attr } = object.as_ref() {
+                        if is_self(value) {
+                            let element = Box::new(self.expr_type(&args[0], locals));
+                            let refined = match method.as_str() {
+                                "append" => Some(Type::List(element)),
+                                "add" => Some(Type::Set(element)),
+                                _ => None,
+                            };
+                            if let Some(refined) = refined {
+                                *changed |= self.refine(class, attr, refined);
+                            }
+                        }
+                    }
+                }
+                HirStmt::If {
+                    then_body,
+                    else_body,
+                    ..
+                } => {
+                    self.refine_from_block(class, then_body, locals, changed);
+                    if let Some(else_body) = else_body {
+                        self.refine_from_block(class, else_body, locals, changed);
+                    }
+                }
+                HirStmt::For { target, iter, body } => {
+                    if let AssignTarget::Symbol(name) = target {
+                        let element = match self.expr_type(iter, locals) {
+                            Type::List(element) | Type::Set(element) => *element,
+                            Type::Dict(key, _) => *key,
+                            _ => Type::Unknown,
+                        };
+                        if !matches!(element, Type::Unknown) {
+                            locals.insert(name.clone(), element);
+                        }
+                    }
+                    self.refine_from_block(class, body, locals, changed);
+                }
+                HirStmt::While { body, .. } | HirStmt::With { body, .. } => {
+                    self.refine_from_block(class, body, locals, changed);
+                }
+                HirStmt::Try {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                } => {
+                    self.refine_from_block(class, body, locals, changed);
+                    for handler in handlers {
+                        self.refine_from_block(class, &handler.body, locals, changed);
+                    }
+                    for block in [orelse, finalbody].into_iter().flatten() {
+                        self.refine_from_block(class, block, locals, changed);
+                    }
+                }
+                HirStmt::Match { cases, .. } => {
+                    for case in cases {
+                        self.refine_from_block(class, &case.body, locals, changed);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Narrows field `attr` of `class` to `ty` where its type is unknown,
+    /// returning whether it changed
+    fn refine(&mut self, class: &str, attr: &str, ty: Type) -> bool {
+        let current = match self.classes.get_mut(class).and_then(|f| f.get_mut(attr)) {
+            Some(current) => current,
+            None => return false,
+        };
+        match fill_unknown(current, &ty) {
+            Some(refined) if refined != *current => {
+                *current = refined;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The type of `expr`, or `Unknown`
+    fn expr_type(&self, expr: &HirExpr, locals: &HashMap<String, Type>) -> Type {
+        match expr {
+            HirExpr::Literal(literal) => match literal {
+                Literal::Int(_) => Type::Int,
+                Literal::Float(_) => Type::Float,
+                Literal::String(_) => Type::String,
+                Literal::Bool(_) => Type::Bool,
+                Literal::Bytes(_) | Literal::None => Type::Unknown,
+            },
+            HirExpr::Var(name) => locals.get(name).cloned().unwrap_or(Type::Unknown),
+            HirExpr::Attribute { value, attr } => {
+                self.infer_attribute_type(&self.expr_type(value, locals), attr)
+            }
+            HirExpr::List(elements) => Type::List(Box::new(self.first_known(elements, locals))),
+            HirExpr::Set(elements) => Type::Set(Box::new(self.first_known(elements, locals))),
+            HirExpr::Dict(items) => {
+                let (keys, values): (Vec<_>, Vec<_>) = items.iter().cloned().unzip();
+                Type::Dict(
+                    Box::new(self.first_known(&keys, locals)),
+                    Box::new(self.first_known(&values, locals)),
+                )
+            }
+            HirExpr::Tuple(elements) => {
+                Type::Tuple(elements.iter().map(|e| self.expr_type(e, locals)).collect())
+            }
+            HirExpr::Call { func, .. } => match func.as_str() {
+                "int" | "len" => Type::Int,
+                "float" => Type::Float,
+                "str" => Type::String,
+                "bool" => Type::Bool,
+                "list" => Type::List(Box::new(Type::Unknown)),
+                "dict" => Type::Dict(Box::new(Type::Unknown), Box::new(Type::Unknown)),
+                "set" => Type::Set(Box::new(Type::Unknown)),
+                _ if self.classes.contains_key(func) => Type::Custom(func.clone()),
+                _ => Type::Unknown,
+            },
+            HirExpr::Index { base, .. } => match self.expr_type(base, locals) {
+                Type::List(element) => *element,
+                Type::Dict(_, value) => *value,
+                _ => Type::Unknown,
+            },
+            HirExpr::Binary { op, left, right } => {
+                let (left, right) = (self.expr_type(left, locals), self.expr_type(right, locals));
+                binary_type(*op, &left, &right)
+            }
+            _ => Type::Unknown,
+        }
+    }
+
+    fn first_known(&self, exprs: &[HirExpr], locals: &HashMap<String, Type>) -> Type {
+        exprs
+            .iter()
+            .map(|e| self.expr_type(e, locals))
+            .find(|ty| !matches!(ty, Type::Unknown))
+            .unwrap_or(Type::Unknown)
+    }
+}
+
+fn is_self(expr: &HirExpr) -> bool {
+    matches!(expr, HirExpr::Var(name) if name == "self")
+}
+
+fn binary_type(op: BinOp, left: &Type, right: &Type) -> Type {
+    match op {
+        BinOp::Eq
+        | BinOp::NotEq
+        | BinOp::Lt
+        | BinOp::LtEq
+        | BinOp::Gt
+        | BinOp::GtEq
+        | BinOp::In
+        | BinOp::NotIn => Type::Bool,
+        BinOp::Div => Type::Float,
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::FloorDiv | BinOp::Mod | BinOp::Pow => {
+            match (left, right) {
+                (Type::Int, Type::Int) => Type::Int,
+                (Type::Float, Type::Int | Type::Float) | (Type::Int, Type::Float) => Type::Float,
+                (Type::String, Type::String) if op == BinOp::Add => Type::String,
+                (Type::List(_), Type::List(_)) if op == BinOp::Add => left.clone(),
+                _ => Type::Unknown,
+            }
+        }
+        _ => Type::Unknown,
+    }
+}
+
+/// `current` with its `Unknown` parts taken from `new`, if they agree
+/// everywhere else
+fn fill_unknown(current: &Type, new: &Type) -> Option<Type> {
+    match (current, new) {
+        (_, Type::Unknown) => None,
+        (Type::Unknown, _) => Some(new.clone()),
+        (Type::List(current), Type::List(new)) => {
+            fill_unknown(current, new).map(|t| Type::List(Box::new(t)))
+        }
+        (Type::Set(current), Type::Set(new)) => {
+            fill_unknown(current, new).map(|t| Type::Set(Box::new(t)))
+        }
+        (Type::Dict(current_key, current_value), Type::Dict(new_key, new_value)) => {
+            let key = fill_unknown(current_key, new_key);
+            let value = fill_unknown(current_value, new_value);
+            if key.is_none() && value.is_none() {
+                return None;
+            }
+            Some(Type::Dict(
+                Box::new(key.unwrap_or_else(|| (**current_key).clone())),
+                Box::new(value.unwrap_or_else(|| (**current_value).clone())),
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_unknown_only_narrows() {
+        let unknown_list = Type::List(Box::new(Type::Unknown));
+        assert_eq!(
+            fill_unknown(&unknown_list, &Type::List(Box::new(Type::Int))),
+            Some(Type::List(Box::new(Type::Int)))
+        );
+        assert_eq!(
+            fill_unknown(&Type::List(Box::new(Type::Int)), &unknown_list),
+            None
+        );
+        assert_eq!(fill_unknown(&Type::Int, &Type::String), None);
+
+        let partial = Type::Dict(Box::new(Type::String), Box::new(Type::Unknown));
+        assert_eq!(
+            fill_unknown(
+                &partial,
+                &Type::Dict(Box::new(Type::Unknown), Box::new(Type::Float))
+            ),
+            Some(Type::Dict(Box::new(Type::String), Box::new(Type::Float)))
+        );
+    }
+
+    #[test]
+    fn test_attributes_of_non_classes_are_unknown() {
+        let table = FieldTypeTable::default();
+        assert_eq!(
+            table.infer_attribute_type(&Type::Int, "real"),
+            Type::Unknown
+        );
+        assert_eq!(
+            table.infer_attribute_type(&Type::Custom("Point".to_string()), "x"),
+            Type::Unknown
+        );
+    }
+}
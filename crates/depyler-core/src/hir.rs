@@ -182,6 +182,34 @@ pub struct HirClass {
     pub fields: Vec<HirField>,
     pub is_dataclass: bool,
     pub docstring: Option<String>,
+    /// Set for attrs / pydantic classes, which are lowered like dataclasses
+    #[serde(default)]
+    pub model_kind: Option<ModelKind>,
+    /// `# @depyler:` annotations above the class
+    #[serde(default)]
+    pub annotations: TranspilationAnnotations,
+}
+
+/// Third-party library a model class is declared with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ModelKind {
+    /// `@attr.s`, `@attr.define`, `@attrs.define`, `@define`, `@frozen`
+    Attrs,
+    /// `pydantic.BaseModel` subclasses
+    Pydantic,
+}
+
+impl HirClass {
+    /// The `__post_init__` hook the generated dataclass `__init__` calls
+    ///
+    /// `None` unless this is a dataclass without a hand-written `__init__`,
+    /// the only case in which Python runs the hook.
+    pub fn post_init(&self) -> Option<&HirMethod> {
+        if !self.is_dataclass || self.methods.iter().any(|m| m.name == "__init__") {
+            return None;
+        }
+        self.methods.iter().find(|m| m.name == "__post_init__")
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -232,6 +260,9 @@ pub struct HirParam {
     pub name: Symbol,
     pub ty: Type,
     pub default: Option<HirExpr>,
+    /// How calls may pass the parameter
+    #[serde(default)]
+    pub kind: ParamKind,
 }
 
 impl HirParam {
@@ -241,6 +272,7 @@ impl HirParam {
             name,
             ty,
             default: None,
+            kind: ParamKind::PositionalOrKeyword,
         }
     }
 
@@ -250,8 +282,30 @@ impl HirParam {
             name,
             ty,
             default: Some(default),
+            kind: ParamKind::PositionalOrKeyword,
         }
     }
+
+    /// The parameter, passed as `kind`
+    pub fn with_kind(mut self, kind: ParamKind) -> Self {
+        self.kind = kind;
+        self
+    }
+}
+
+/// How a call may pass a parameter, as set by the `/` and `*` markers of
+/// the signature
+///
+/// The Rust function takes every parameter positionally, in the order of
+/// the Python signature.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamKind {
+    /// Before `/`: never passed by keyword
+    PositionalOnly,
+    #[default]
+    PositionalOrKeyword,
+    /// After `*` or `*args`: only passed by keyword
+    KeywordOnly,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -275,6 +329,29 @@ pub struct FunctionProperties {
     pub error_types: Vec<String>,
     pub is_async: bool,
     pub is_generator: bool,
+    /// Decorated with `@contextlib.contextmanager`
+    #[serde(default)]
+    pub is_context_manager: bool,
+    /// Started from module level by `asyncio.run(f())`
+    #[serde(default)]
+    pub is_async_entry: bool,
+    /// Decorated with `@functools.lru_cache` or `@functools.cache`
+    #[serde(default)]
+    pub memoize: Option<Memoize>,
+    /// Whether the body mutates each parameter, in parameter order, once the
+    /// type-hints pass has looked
+    #[serde(default)]
+    pub mutated_params: Option<Vec<bool>>,
+    /// Cyclomatic complexity of the body, once the type-hints pass has looked
+    #[serde(default)]
+    pub complexity: Option<u32>,
+}
+
+/// Result cache of a memoized function
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Memoize {
+    /// Entries held before the cache is emptied; `None` grows without bound
+    pub max_size: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -342,6 +419,11 @@ pub enum HirStmt {
         msg: Option<HirExpr>,
     },
     Pass,
+    /// Structural pattern matching: match subject: case pattern: ...
+    Match {
+        subject: HirExpr,
+        cases: Vec<MatchCase>,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -351,6 +433,74 @@ pub struct ExceptHandler {
     pub body: Vec<HirStmt>,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MatchCase {
+    pub pattern: HirPattern,
+    pub guard: Option<HirExpr>,
+    pub body: Vec<HirStmt>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum HirPattern {
+    /// Wildcard: case _
+    Wildcard,
+    /// Capture: case x, or case <pattern> as x
+    Capture {
+        name: Symbol,
+        pattern: Option<Box<HirPattern>>,
+    },
+    /// Literal: case 0, case "quit", case None
+    Literal(Literal),
+    /// Dotted constant: case Color.RED
+    Value(HirExpr),
+    /// Sequence: case (x, y) or case [x, y]
+    Sequence(Vec<HirPattern>),
+    /// Class: case Point(x=0, y=y) or case Point(0, y)
+    Class {
+        class: Symbol,
+        args: Vec<HirPattern>,
+        kwargs: Vec<(Symbol, HirPattern)>,
+    },
+    /// Alternatives: case 1 | 2
+    Or(Vec<HirPattern>),
+}
+
+impl HirPattern {
+    /// Names the pattern binds, in order
+    pub fn bindings(&self) -> Vec<Symbol> {
+        let mut names = Vec::new();
+        self.collect_bindings(&mut names);
+        names
+    }
+
+    fn collect_bindings(&self, names: &mut Vec<Symbol>) {
+        match self {
+            HirPattern::Capture { name, pattern } => {
+                if let Some(pattern) = pattern {
+                    pattern.collect_bindings(names);
+                }
+                if !names.contains(name) {
+                    names.push(name.clone());
+                }
+            }
+            HirPattern::Sequence(patterns) => {
+                patterns.iter().for_each(|p| p.collect_bindings(names));
+            }
+            HirPattern::Class { args, kwargs, .. } => {
+                args.iter().for_each(|p| p.collect_bindings(names));
+                kwargs.iter().for_each(|(_, p)| p.collect_bindings(names));
+            }
+            // Every alternative binds the same names
+            HirPattern::Or(alternatives) => {
+                if let Some(first) = alternatives.first() {
+                    first.collect_bindings(names);
+                }
+            }
+            HirPattern::Wildcard | HirPattern::Literal(_) | HirPattern::Value(_) => {}
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum HirExpr {
     Literal(Literal),
@@ -464,6 +614,257 @@ pub enum HirExpr {
     },
 }
 
+impl HirExpr {
+    /// Expressions nested directly inside this one
+    pub fn children(&self) -> Vec<&HirExpr> {
+        match self {
+            HirExpr::Literal(_) | HirExpr::Var(_) => Vec::new(),
+            HirExpr::Binary { left, right, .. } => vec![&**left, &**right],
+            HirExpr::Unary { operand, .. } => vec![&**operand],
+            HirExpr::Call { args, kwargs, .. } => {
+                args.iter().chain(kwargs.iter().map(|(_, v)| v)).collect()
+            }
+            HirExpr::MethodCall {
+                object,
+                args,
+                kwargs,
+                ..
+            } => std::iter::once(&**object)
+                .chain(args)
+                .chain(kwargs.iter().map(|(_, v)| v))
+                .collect(),
+            HirExpr::Index { base, index } => vec![&**base, &**index],
+            HirExpr::Slice {
+                base,
+                start,
+                stop,
+                step,
+            } => std::iter::once(&**base)
+                .chain([start, stop, step].into_iter().flatten().map(|b| &**b))
+                .collect(),
+            HirExpr::Attribute { value, .. } => vec![&**value],
+            HirExpr::List(items)
+            | HirExpr::Tuple(items)
+            | HirExpr::Set(items)
+            | HirExpr::FrozenSet(items) => items.iter().collect(),
+            HirExpr::Dict(pairs) => pairs.iter().flat_map(|(k, v)| [k, v]).collect(),
+            HirExpr::Borrow { expr, .. } => vec![&**expr],
+            HirExpr::ListComp {
+                element,
+                iter,
+                condition,
+                ..
+            }
+            | HirExpr::SetComp {
+                element,
+                iter,
+                condition,
+                ..
+            } => [element, iter]
+                .into_iter()
+                .chain(condition)
+                .map(|e| &**e)
+                .collect(),
+            HirExpr::DictComp {
+                key,
+                value,
+                iter,
+                condition,
+                ..
+            } => [key, value, iter]
+                .into_iter()
+                .chain(condition)
+                .map(|e| &**e)
+                .collect(),
+            HirExpr::Lambda { body, .. } => vec![&**body],
+            HirExpr::Await { value } => vec![&**value],
+            HirExpr::FString { parts } => parts
+                .iter()
+                .filter_map(|part| match part {
+                    FStringPart::Expr(expr) => Some(&**expr),
+                    FStringPart::Literal(_) => None,
+                })
+                .collect(),
+            HirExpr::Yield { value } => value.iter().map(|v| &**v).collect(),
+            HirExpr::IfExpr { test, body, orelse } => vec![&**test, &**body, &**orelse],
+            HirExpr::SortByKey {
+                iterable, key_body, ..
+            } => vec![&**iterable, &**key_body],
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => std::iter::once(&**element)
+                .chain(
+                    generators
+                        .iter()
+                        .flat_map(|g| std::iter::once(&*g.iter).chain(&g.conditions)),
+                )
+                .collect(),
+        }
+    }
+
+    /// Mutable access to the expressions nested directly inside this one,
+    /// in the order of [`children`](Self::children)
+    pub fn children_mut(&mut self) -> Vec<&mut HirExpr> {
+        match self {
+            HirExpr::Literal(_) | HirExpr::Var(_) => Vec::new(),
+            HirExpr::Binary { left, right, .. } => vec![&mut **left, &mut **right],
+            HirExpr::Unary { operand, .. } => vec![&mut **operand],
+            HirExpr::Call { args, kwargs, .. } => args
+                .iter_mut()
+                .chain(kwargs.iter_mut().map(|(_, v)| v))
+                .collect(),
+            HirExpr::MethodCall {
+                object,
+                args,
+                kwargs,
+                ..
+            } => std::iter::once(&mut **object)
+                .chain(args)
+                .chain(kwargs.iter_mut().map(|(_, v)| v))
+                .collect(),
+            HirExpr::Index { base, index } => vec![&mut **base, &mut **index],
+            HirExpr::Slice {
+                base,
+                start,
+                stop,
+                step,
+            } => std::iter::once(&mut **base)
+                .chain([start, stop, step].into_iter().flatten().map(|b| &mut **b))
+                .collect(),
+            HirExpr::Attribute { value, .. } => vec![&mut **value],
+            HirExpr::List(items)
+            | HirExpr::Tuple(items)
+            | HirExpr::Set(items)
+            | HirExpr::FrozenSet(items) => items.iter_mut().collect(),
+            HirExpr::Dict(pairs) => pairs.iter_mut().flat_map(|(k, v)| [k, v]).collect(),
+            HirExpr::Borrow { expr, .. } => vec![&mut **expr],
+            HirExpr::ListComp {
+                element,
+                iter,
+                condition,
+                ..
+            }
+            | HirExpr::SetComp {
+                element,
+                iter,
+                condition,
+                ..
+            } => [element, iter]
+                .into_iter()
+                .chain(condition)
+                .map(|e| &mut **e)
+                .collect(),
+            HirExpr::DictComp {
+                key,
+                value,
+                iter,
+                condition,
+                ..
+            } => [key, value, iter]
+                .into_iter()
+                .chain(condition)
+                .map(|e| &mut **e)
+                .collect(),
+            HirExpr::Lambda { body, .. } => vec![&mut **body],
+            HirExpr::Await { value } => vec![&mut **value],
+            HirExpr::FString { parts } => parts
+                .iter_mut()
+                .filter_map(|part| match part {
+                    FStringPart::Expr(expr) => Some(&mut **expr),
+                    FStringPart::Literal(_) => None,
+                })
+                .collect(),
+            HirExpr::Yield { value } => value.iter_mut().map(|v| &mut **v).collect(),
+            HirExpr::IfExpr { test, body, orelse } => {
+                vec![&mut **test, &mut **body, &mut **orelse]
+            }
+            HirExpr::SortByKey {
+                iterable, key_body, ..
+            } => vec![&mut **iterable, &mut **key_body],
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => std::iter::once(&mut **element)
+                .chain(
+                    generators
+                        .iter_mut()
+                        .flat_map(|g| std::iter::once(&mut *g.iter).chain(g.conditions.iter_mut())),
+                )
+                .collect(),
+        }
+    }
+
+    /// `True` or a non-zero integer literal, as in `while True:` and `while 1:`
+    pub fn is_always_true(&self) -> bool {
+        matches!(self, HirExpr::Literal(Literal::Bool(true)))
+            || matches!(self, HirExpr::Literal(Literal::Int(n)) if *n != 0)
+    }
+}
+
+impl HirStmt {
+    /// Statement blocks nested directly inside this statement, in source
+    /// order
+    pub fn nested_bodies(&self) -> Vec<&[HirStmt]> {
+        match self {
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => std::iter::once(then_body.as_slice())
+                .chain(else_body.as_deref())
+                .collect(),
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => {
+                vec![body.as_slice()]
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => std::iter::once(body.as_slice())
+                .chain(handlers.iter().map(|handler| handler.body.as_slice()))
+                .chain(orelse.as_deref())
+                .chain(finalbody.as_deref())
+                .collect(),
+            HirStmt::Match { cases, .. } => cases.iter().map(|case| case.body.as_slice()).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// [`nested_bodies`](Self::nested_bodies), mutably
+    pub fn nested_bodies_mut(&mut self) -> Vec<&mut Vec<HirStmt>> {
+        match self {
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => std::iter::once(then_body)
+                .chain(else_body.as_mut())
+                .collect(),
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => {
+                vec![body]
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => std::iter::once(body)
+                .chain(handlers.iter_mut().map(|handler| &mut handler.body))
+                .chain(orelse.as_mut())
+                .chain(finalbody.as_mut())
+                .collect(),
+            HirStmt::Match { cases, .. } => cases.iter_mut().map(|case| &mut case.body).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
 /// Comprehension generator (used in list/set/dict/generator comprehensions)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HirComprehension {
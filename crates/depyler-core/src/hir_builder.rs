@@ -0,0 +1,567 @@
+//! Builders for constructing HIR by hand
+//!
+//! The HIR structs grow fields as the transpiler learns more about Python,
+//! and every struct literal has to follow. Code that builds HIR outside of
+//! the AST bridge, such as tests and external tools, should go through these
+//! builders instead; fields they do not set keep the defaults the AST bridge
+//! would give a plain declaration.
+//!
+//! ```rust
+//! use depyler_core::hir::{BinOp, Type};
+//! use depyler_core::hir_builder::{expr, stmt, HirFunctionBuilder, HirModuleBuilder};
+//!
+//! let add = HirFunctionBuilder::new("add")
+//!     .param("a", Type::Int)
+//!     .param("b", Type::Int)
+//!     .returns(Type::Int)
+//!     .stmt(stmt::ret(expr::binary(BinOp::Add, expr::var("a"), expr::var("b"))))
+//!     .build();
+//!
+//! let module = HirModuleBuilder::new().function(add).build();
+//! assert_eq!(module.functions[0].params.len(), 2);
+//! ```
+
+use crate::hir::{
+    FunctionProperties, HirClass, HirConstant, HirExpr, HirField, HirFunction, HirMethod,
+    HirModule, HirParam, HirStmt, Import, Protocol, Type, TypeAlias,
+};
+use depyler_annotations::TranspilationAnnotations;
+use smallvec::SmallVec;
+
+/// A [`HirModule`], empty until items are added
+#[derive(Debug, Clone)]
+pub struct HirModuleBuilder {
+    module: HirModule,
+}
+
+impl Default for HirModuleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HirModuleBuilder {
+    pub fn new() -> Self {
+        Self {
+            module: HirModule {
+                functions: Vec::new(),
+                imports: Vec::new(),
+                type_aliases: Vec::new(),
+                protocols: Vec::new(),
+                classes: Vec::new(),
+                constants: Vec::new(),
+            },
+        }
+    }
+
+    pub fn function(mut self, function: HirFunction) -> Self {
+        self.module.functions.push(function);
+        self
+    }
+
+    pub fn class(mut self, class: HirClass) -> Self {
+        self.module.classes.push(class);
+        self
+    }
+
+    pub fn import(mut self, import: Import) -> Self {
+        self.module.imports.push(import);
+        self
+    }
+
+    pub fn constant(mut self, constant: HirConstant) -> Self {
+        self.module.constants.push(constant);
+        self
+    }
+
+    pub fn type_alias(mut self, alias: TypeAlias) -> Self {
+        self.module.type_aliases.push(alias);
+        self
+    }
+
+    pub fn protocol(mut self, protocol: Protocol) -> Self {
+        self.module.protocols.push(protocol);
+        self
+    }
+
+    pub fn build(self) -> HirModule {
+        self.module
+    }
+}
+
+/// A [`HirFunction`] without parameters, returning `None`, with an empty
+/// body and default properties and annotations
+#[derive(Debug, Clone)]
+pub struct HirFunctionBuilder {
+    function: HirFunction,
+}
+
+impl HirFunctionBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            function: HirFunction {
+                name: name.into(),
+                params: SmallVec::new(),
+                ret_type: Type::None,
+                body: Vec::new(),
+                properties: FunctionProperties::default(),
+                annotations: TranspilationAnnotations::default(),
+                docstring: None,
+            },
+        }
+    }
+
+    /// Adds a required parameter
+    pub fn param(self, name: impl Into<String>, ty: Type) -> Self {
+        self.add_param(HirParam::new(name.into(), ty))
+    }
+
+    /// Adds a parameter defaulting to `default`
+    pub fn param_with_default(self, name: impl Into<String>, ty: Type, default: HirExpr) -> Self {
+        self.add_param(HirParam::with_default(name.into(), ty, default))
+    }
+
+    /// Adds a parameter built elsewhere, e.g. one with a [`ParamKind`](crate::hir::ParamKind)
+    pub fn add_param(mut self, param: HirParam) -> Self {
+        self.function.params.push(param);
+        self
+    }
+
+    pub fn returns(mut self, ty: Type) -> Self {
+        self.function.ret_type = ty;
+        self
+    }
+
+    /// Appends a statement to the body
+    pub fn stmt(mut self, stmt: HirStmt) -> Self {
+        self.function.body.push(stmt);
+        self
+    }
+
+    /// Replaces the body
+    pub fn body(mut self, body: Vec<HirStmt>) -> Self {
+        self.function.body = body;
+        self
+    }
+
+    pub fn properties(mut self, properties: FunctionProperties) -> Self {
+        self.function.properties = properties;
+        self
+    }
+
+    pub fn annotations(mut self, annotations: TranspilationAnnotations) -> Self {
+        self.function.annotations = annotations;
+        self
+    }
+
+    pub fn docstring(mut self, docstring: impl Into<String>) -> Self {
+        self.function.docstring = Some(docstring.into());
+        self
+    }
+
+    pub fn build(self) -> HirFunction {
+        self.function
+    }
+}
+
+/// An instance [`HirMethod`] without parameters besides `self`, returning
+/// `None`, with an empty body
+#[derive(Debug, Clone)]
+pub struct HirMethodBuilder {
+    method: HirMethod,
+}
+
+impl HirMethodBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            method: HirMethod {
+                name: name.into(),
+                params: SmallVec::new(),
+                ret_type: Type::None,
+                body: Vec::new(),
+                is_static: false,
+                is_classmethod: false,
+                is_property: false,
+                is_async: false,
+                docstring: None,
+            },
+        }
+    }
+
+    /// Adds a required parameter after the receiver
+    pub fn param(self, name: impl Into<String>, ty: Type) -> Self {
+        self.add_param(HirParam::new(name.into(), ty))
+    }
+
+    pub fn add_param(mut self, param: HirParam) -> Self {
+        self.method.params.push(param);
+        self
+    }
+
+    pub fn returns(mut self, ty: Type) -> Self {
+        self.method.ret_type = ty;
+        self
+    }
+
+    /// Appends a statement to the body
+    pub fn stmt(mut self, stmt: HirStmt) -> Self {
+        self.method.body.push(stmt);
+        self
+    }
+
+    /// Replaces the body
+    pub fn body(mut self, body: Vec<HirStmt>) -> Self {
+        self.method.body = body;
+        self
+    }
+
+    /// A `@staticmethod`
+    pub fn static_method(mut self) -> Self {
+        self.method.is_static = true;
+        self
+    }
+
+    /// A `@classmethod`
+    pub fn classmethod(mut self) -> Self {
+        self.method.is_classmethod = true;
+        self
+    }
+
+    /// A `@property` getter
+    pub fn property(mut self) -> Self {
+        self.method.is_property = true;
+        self
+    }
+
+    /// An `async def`
+    pub fn async_method(mut self) -> Self {
+        self.method.is_async = true;
+        self
+    }
+
+    pub fn docstring(mut self, docstring: impl Into<String>) -> Self {
+        self.method.docstring = Some(docstring.into());
+        self
+    }
+
+    pub fn build(self) -> HirMethod {
+        self.method
+    }
+}
+
+/// A plain [`HirClass`] without bases, fields or methods
+#[derive(Debug, Clone)]
+pub struct HirClassBuilder {
+    class: HirClass,
+}
+
+impl HirClassBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            class: HirClass {
+                name: name.into(),
+                base_classes: Vec::new(),
+                methods: Vec::new(),
+                fields: Vec::new(),
+                is_dataclass: false,
+                docstring: None,
+                model_kind: None,
+                annotations: Default::default(),
+            },
+        }
+    }
+
+    pub fn base(mut self, base: impl Into<String>) -> Self {
+        self.class.base_classes.push(base.into());
+        self
+    }
+
+    /// Adds an instance field without a default
+    pub fn field(self, name: impl Into<String>, ty: Type) -> Self {
+        self.add_field(name, ty, None, false)
+    }
+
+    /// Adds an instance field defaulting to `default`
+    pub fn field_with_default(self, name: impl Into<String>, ty: Type, default: HirExpr) -> Self {
+        self.add_field(name, ty, Some(default), false)
+    }
+
+    /// Adds a class variable set to `value`
+    pub fn class_var(self, name: impl Into<String>, ty: Type, value: HirExpr) -> Self {
+        self.add_field(name, ty, Some(value), true)
+    }
+
+    fn add_field(
+        mut self,
+        name: impl Into<String>,
+        field_type: Type,
+        default_value: Option<HirExpr>,
+        is_class_var: bool,
+    ) -> Self {
+        self.class.fields.push(HirField {
+            name: name.into(),
+            field_type,
+            default_value,
+            is_class_var,
+        });
+        self
+    }
+
+    pub fn method(mut self, method: HirMethod) -> Self {
+        self.class.methods.push(method);
+        self
+    }
+
+    /// A `@dataclass`
+    pub fn dataclass(mut self) -> Self {
+        self.class.is_dataclass = true;
+        self
+    }
+
+    pub fn docstring(mut self, docstring: impl Into<String>) -> Self {
+        self.class.docstring = Some(docstring.into());
+        self
+    }
+
+    pub fn build(self) -> HirClass {
+        self.class
+    }
+}
+
+/// Expressions, named after the Python they stand for
+pub mod expr {
+    use crate::hir::{BinOp, HirExpr, Literal, UnaryOp};
+
+    pub fn int(value: i64) -> HirExpr {
+        HirExpr::Literal(Literal::Int(value))
+    }
+
+    pub fn float(value: f64) -> HirExpr {
+        HirExpr::Literal(Literal::Float(value))
+    }
+
+    pub fn string(value: impl Into<String>) -> HirExpr {
+        HirExpr::Literal(Literal::String(value.into()))
+    }
+
+    pub fn bool(value: bool) -> HirExpr {
+        HirExpr::Literal(Literal::Bool(value))
+    }
+
+    pub fn none() -> HirExpr {
+        HirExpr::Literal(Literal::None)
+    }
+
+    pub fn var(name: impl Into<String>) -> HirExpr {
+        HirExpr::Var(name.into())
+    }
+
+    pub fn binary(op: BinOp, left: HirExpr, right: HirExpr) -> HirExpr {
+        HirExpr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    pub fn unary(op: UnaryOp, operand: HirExpr) -> HirExpr {
+        HirExpr::Unary {
+            op,
+            operand: Box::new(operand),
+        }
+    }
+
+    /// `func(args...)`
+    pub fn call(func: impl Into<String>, args: Vec<HirExpr>) -> HirExpr {
+        HirExpr::Call {
+            func: func.into(),
+            args,
+            kwargs: Vec::new(),
+        }
+    }
+
+    /// `func(args..., name=value...)`
+    pub fn call_with_kwargs(
+        func: impl Into<String>,
+        args: Vec<HirExpr>,
+        kwargs: Vec<(&str, HirExpr)>,
+    ) -> HirExpr {
+        HirExpr::Call {
+            func: func.into(),
+            args,
+            kwargs: kwargs
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect(),
+        }
+    }
+
+    /// `object.method(args...)`
+    pub fn method_call(object: HirExpr, method: impl Into<String>, args: Vec<HirExpr>) -> HirExpr {
+        HirExpr::MethodCall {
+            object: Box::new(object),
+            method: method.into(),
+            args,
+            kwargs: Vec::new(),
+        }
+    }
+
+    /// `value.attr`
+    pub fn attr(value: HirExpr, attr: impl Into<String>) -> HirExpr {
+        HirExpr::Attribute {
+            value: Box::new(value),
+            attr: attr.into(),
+        }
+    }
+
+    /// `base[index]`
+    pub fn index(base: HirExpr, index: HirExpr) -> HirExpr {
+        HirExpr::Index {
+            base: Box::new(base),
+            index: Box::new(index),
+        }
+    }
+
+    pub fn list(items: Vec<HirExpr>) -> HirExpr {
+        HirExpr::List(items)
+    }
+
+    pub fn tuple(items: Vec<HirExpr>) -> HirExpr {
+        HirExpr::Tuple(items)
+    }
+
+    pub fn dict(items: Vec<(HirExpr, HirExpr)>) -> HirExpr {
+        HirExpr::Dict(items)
+    }
+}
+
+/// Statements, named after the Python they stand for
+pub mod stmt {
+    use crate::hir::{AssignTarget, HirExpr, HirStmt, Type};
+
+    /// `return value`
+    pub fn ret(value: HirExpr) -> HirStmt {
+        HirStmt::Return(Some(value))
+    }
+
+    /// `name = value`
+    pub fn assign(name: impl Into<String>, value: HirExpr) -> HirStmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(name.into()),
+            value,
+            type_annotation: None,
+        }
+    }
+
+    /// `name: ty = value`
+    pub fn assign_typed(name: impl Into<String>, ty: Type, value: HirExpr) -> HirStmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(name.into()),
+            value,
+            type_annotation: Some(ty),
+        }
+    }
+
+    /// An expression evaluated for its effect
+    pub fn expr(value: HirExpr) -> HirStmt {
+        HirStmt::Expr(value)
+    }
+
+    /// `if condition: then_body else: else_body`
+    pub fn if_else(
+        condition: HirExpr,
+        then_body: Vec<HirStmt>,
+        else_body: Option<Vec<HirStmt>>,
+    ) -> HirStmt {
+        HirStmt::If {
+            condition,
+            then_body,
+            else_body,
+        }
+    }
+
+    /// `while condition: body`
+    pub fn while_loop(condition: HirExpr, body: Vec<HirStmt>) -> HirStmt {
+        HirStmt::While { condition, body }
+    }
+
+    /// `for target in iter: body`
+    pub fn for_loop(target: impl Into<String>, iter: HirExpr, body: Vec<HirStmt>) -> HirStmt {
+        HirStmt::For {
+            target: AssignTarget::Symbol(target.into()),
+            iter,
+            body,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::{BinOp, ParamKind};
+
+    #[test]
+    fn test_function_builder_defaults() {
+        let function = HirFunctionBuilder::new("f").build();
+        assert_eq!(function.name, "f");
+        assert!(function.params.is_empty());
+        assert_eq!(function.ret_type, Type::None);
+        assert_eq!(function.properties, FunctionProperties::default());
+        assert_eq!(function.docstring, None);
+    }
+
+    #[test]
+    fn test_function_builder() {
+        let function = HirFunctionBuilder::new("scale")
+            .param("x", Type::Float)
+            .param_with_default("by", Type::Float, expr::float(2.0))
+            .add_param(HirParam::new("exact".into(), Type::Bool).with_kind(ParamKind::KeywordOnly))
+            .returns(Type::Float)
+            .stmt(stmt::ret(expr::binary(
+                BinOp::Mul,
+                expr::var("x"),
+                expr::var("by"),
+            )))
+            .docstring("Scales x")
+            .build();
+
+        assert_eq!(function.params.len(), 3);
+        assert_eq!(function.params[1].default, Some(expr::float(2.0)));
+        assert_eq!(function.params[2].kind, ParamKind::KeywordOnly);
+        assert_eq!(function.ret_type, Type::Float);
+        assert!(matches!(function.body[0], HirStmt::Return(Some(_))));
+    }
+
+    #[test]
+    fn test_class_builder() {
+        let class = HirClassBuilder::new("Point")
+            .dataclass()
+            .field("x", Type::Int)
+            .field_with_default("y", Type::Int, expr::int(0))
+            .method(
+                HirMethodBuilder::new("norm")
+                    .returns(Type::Int)
+                    .stmt(stmt::ret(expr::attr(expr::var("self"), "x")))
+                    .build(),
+            )
+            .method(HirMethodBuilder::new("origin").static_method().build())
+            .build();
+
+        assert!(class.is_dataclass);
+        assert_eq!(class.fields.len(), 2);
+        assert_eq!(class.fields[1].default_value, Some(expr::int(0)));
+        assert!(!class.methods[0].is_static);
+        assert!(class.methods[1].is_static);
+    }
+
+    #[test]
+    fn test_module_builder() {
+        let module = HirModuleBuilder::new()
+            .function(HirFunctionBuilder::new("f").build())
+            .class(HirClassBuilder::new("C").build())
+            .build();
+        assert_eq!(module.functions.len(), 1);
+        assert_eq!(module.classes.len(), 1);
+        assert!(module.imports.is_empty());
+    }
+}
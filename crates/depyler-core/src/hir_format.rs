@@ -0,0 +1,174 @@
+//! Stable interchange format for HIR
+//!
+//! A [`HirDocument`] wraps a [`HirModule`] with a format tag and a schema
+//! version so HIR can be written out after lowering and read back later:
+//! snapshot tests compare it, external tools analyze it, and
+//! [`DepylerPipeline::transpile_hir`](crate::DepylerPipeline::transpile_hir)
+//! generates code from it without the Python source. Documents are JSON,
+//! or CBOR with the `cbor` feature.
+//!
+//! The layout of the module is the serde form of the HIR types.
+//! [`HIR_SCHEMA_VERSION`] is bumped whenever a change to those types would
+//! stop older documents from loading or change their meaning; documents of
+//! a newer version are refused rather than misread.
+
+use crate::hir::HirModule;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Value of [`HirDocument::format`]
+pub const HIR_FORMAT: &str = "depyler-hir";
+
+/// Version of the HIR layout written by this build
+pub const HIR_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HirEncoding {
+    Json,
+    Cbor,
+}
+
+impl HirEncoding {
+    /// `.json` and `.cbor` files
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(HirEncoding::Json),
+            "cbor" => Some(HirEncoding::Cbor),
+            _ => None,
+        }
+    }
+}
+
+/// A HIR module with the version of its layout
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir_format::{HirDocument, HirEncoding};
+/// use depyler_core::DepylerPipeline;
+///
+/// let pipeline = DepylerPipeline::new();
+/// let hir = pipeline.parse_to_hir("def one() -> int:\n    return 1\n").unwrap();
+/// let bytes = HirDocument::new(hir.clone()).encode(HirEncoding::Json).unwrap();
+///
+/// let reloaded = HirDocument::decode(&bytes, HirEncoding::Json).unwrap();
+/// assert_eq!(reloaded.module, hir);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HirDocument {
+    /// Always [`HIR_FORMAT`]
+    pub format: String,
+    pub schema_version: u32,
+    /// Version of depyler that wrote the document, for reference only
+    pub generator: String,
+    pub module: HirModule,
+}
+
+/// The fields checked before the module is read
+#[derive(Deserialize)]
+struct Header {
+    format: String,
+    schema_version: u32,
+}
+
+impl HirDocument {
+    pub fn new(module: HirModule) -> Self {
+        Self {
+            format: HIR_FORMAT.to_string(),
+            schema_version: HIR_SCHEMA_VERSION,
+            generator: format!("depyler {}", env!("CARGO_PKG_VERSION")),
+            module,
+        }
+    }
+
+    pub fn encode(&self, encoding: HirEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            HirEncoding::Json => Ok(serde_json::to_vec_pretty(self)?),
+            HirEncoding::Cbor => encode_cbor(self),
+        }
+    }
+
+    /// Reads a document, refusing other formats and newer schema versions
+    pub fn decode(bytes: &[u8], encoding: HirEncoding) -> Result<Self> {
+        match encoding {
+            HirEncoding::Json => decode_json(bytes),
+            HirEncoding::Cbor => decode_cbor(bytes),
+        }
+    }
+
+    pub fn write(&self, path: &Path, encoding: HirEncoding) -> Result<()> {
+        std::fs::write(path, self.encode(encoding)?)
+            .with_context(|| format!("cannot write {}", path.display()))
+    }
+
+    /// Reads the document at `path`, picking the encoding from its extension
+    pub fn read(path: &Path) -> Result<Self> {
+        let encoding = match HirEncoding::from_path(path) {
+            Some(encoding) => encoding,
+            None => bail!("{} is not a .json or .cbor HIR document", path.display()),
+        };
+        let bytes =
+            std::fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+        Self::decode(&bytes, encoding)
+    }
+}
+
+fn check_header(header: &Header) -> Result<()> {
+    if header.format != HIR_FORMAT {
+        bail!(
+            "not a HIR document: format is `{}`, expected `{}`",
+            header.format,
+            HIR_FORMAT
+        );
+    }
+    if header.schema_version > HIR_SCHEMA_VERSION {
+        bail!(
+            "HIR schema version {} is newer than the supported version {}",
+            header.schema_version,
+            HIR_SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+fn decode_json(bytes: &[u8]) -> Result<HirDocument> {
+    let header: Header = serde_json::from_slice(bytes).context("not a HIR document")?;
+    check_header(&header)?;
+    serde_json::from_slice(bytes).with_context(|| {
+        format!(
+            "malformed HIR document (schema version {})",
+            header.schema_version
+        )
+    })
+}
+
+#[cfg(feature = "cbor")]
+fn encode_cbor(document: &HirDocument) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(document, &mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(feature = "cbor")]
+fn decode_cbor(bytes: &[u8]) -> Result<HirDocument> {
+    let header: Header = ciborium::from_reader(bytes).context("not a HIR document")?;
+    check_header(&header)?;
+    ciborium::from_reader(bytes).with_context(|| {
+        format!(
+            "malformed HIR document (schema version {})",
+            header.schema_version
+        )
+    })
+}
+
+#[cfg(not(feature = "cbor"))]
+fn encode_cbor(_document: &HirDocument) -> Result<Vec<u8>> {
+    bail!("CBOR HIR documents need depyler-core's `cbor` feature")
+}
+
+#[cfg(not(feature = "cbor"))]
+fn decode_cbor(_bytes: &[u8]) -> Result<HirDocument> {
+    bail!("CBOR HIR documents need depyler-core's `cbor` feature")
+}
@@ -0,0 +1,538 @@
+//! Source locations of HIR definitions, statements and expressions
+//!
+//! HIR nodes carry no positions. [`HirSpans`] records them beside a module,
+//! collected from the same rustpython AST the HIR is converted from: every
+//! function, method, class and parameter, and every statement, nested the
+//! way HIR nests blocks. Conversion maps each Python statement to exactly
+//! one `HirStmt`, so the statement at a given index of a HIR body has its
+//! span at the same index here.
+//!
+//! Expressions are nested the same way, by the order of the `HirExpr`
+//! fields they convert to. The few conversions that reshape an expression,
+//! such as `x is None` becoming `x.is_none()` or an `eval` of a constant
+//! becoming its value, keep the spans of the Python operands.
+
+use anyhow::{anyhow, Result};
+use rustpython_ast::{self as ast, Ranged};
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+/// Byte range in the Python source, like rustpython's `TextRange`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Span {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Span {
+    pub fn of<T: Ranged>(node: &T) -> Self {
+        let range = node.range();
+        Self {
+            start: u32::from(range.start()),
+            end: u32::from(range.end()),
+        }
+    }
+
+    pub fn contains(&self, offset: u32) -> bool {
+        (self.start..self.end).contains(&offset)
+    }
+}
+
+/// 1-based line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A statement and the blocks nested in it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StmtSpan {
+    pub span: Span,
+    /// Nested bodies in the order of the `HirStmt` fields: `if` then and
+    /// else, loop and `with` bodies, `try` body, each handler, else and
+    /// finally, and `match` cases. Absent bodies are empty.
+    pub blocks: Vec<Vec<StmtSpan>>,
+    /// Expressions held by the statement itself, in the order of the
+    /// `HirStmt` fields: assigned and returned values, conditions, the
+    /// loop iterable, the `with` context, the raised exception and its
+    /// cause, the assertion and its message, and the `match` subject
+    pub exprs: Vec<ExprSpan>,
+}
+
+/// An expression and the operands nested in it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExprSpan {
+    pub span: Span,
+    /// Operands in the order of the `HirExpr` fields, absent ones skipped:
+    /// the receiver of a method call before its arguments, keyword values
+    /// after positional ones, dict keys before their values, and a
+    /// comprehension's element before its iterable and conditions
+    pub operands: Vec<ExprSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParamSpan {
+    pub name: String,
+    pub span: Span,
+}
+
+/// A `def` and everything in it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FunctionSpans {
+    /// The whole definition
+    pub def: Span,
+    /// Every parameter, `self` and `cls` included
+    pub params: Vec<ParamSpan>,
+    pub docstring: Option<Span>,
+    /// Statements parallel to `HirFunction::body`, so without the docstring
+    pub body: Vec<StmtSpan>,
+}
+
+impl FunctionSpans {
+    pub fn param(&self, name: &str) -> Option<Span> {
+        self.params.iter().find(|p| p.name == name).map(|p| p.span)
+    }
+
+    /// The statement at `path`: an index into the body, then pairs of a
+    /// block index and a statement index within that block
+    ///
+    /// `[2, 1, 0]` is the first statement of the else branch of the third
+    /// statement.
+    pub fn statement(&self, path: &[usize]) -> Option<&StmtSpan> {
+        let (first, rest) = path.split_first()?;
+        let mut stmt = self.body.get(*first)?;
+        for step in rest.chunks(2) {
+            match step {
+                [block, index] => stmt = stmt.blocks.get(*block)?.get(*index)?,
+                _ => return None,
+            }
+        }
+        Some(stmt)
+    }
+
+    /// The expression at `path` in the statement at `statement`: an index
+    /// into the statement's expressions, then operand indices
+    ///
+    /// In `return f(x + 1)`, `[0, 0, 1]` is `1`.
+    pub fn expression(&self, statement: &[usize], path: &[usize]) -> Option<Span> {
+        let (first, rest) = path.split_first()?;
+        let mut expr = self.statement(statement)?.exprs.get(*first)?;
+        for index in rest {
+            expr = expr.operands.get(*index)?;
+        }
+        Some(expr.span)
+    }
+}
+
+/// Spans of a module's definitions, by qualified name
+///
+/// Module functions are keyed by name, methods by `Class.method` and
+/// nested classes by `Outer.Inner`, in source order.
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir_spans::HirSpans;
+///
+/// let source = "def double(x: int) -> int:\n    y = x * 2\n    return y\n";
+/// let spans = HirSpans::parse(source).unwrap();
+/// let double = spans.function("double").unwrap();
+/// let ret = double.statement(&[1]).unwrap();
+/// assert_eq!(spans.lines(ret.span), 3..=3);
+/// assert_eq!(spans.position(double.param("x").unwrap().start).column, 12);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HirSpans {
+    functions: Vec<(String, FunctionSpans)>,
+    classes: Vec<(String, Span)>,
+    line_starts: Vec<u32>,
+}
+
+impl HirSpans {
+    /// Collects the spans of the statements of a module
+    pub fn collect(body: &[ast::Stmt], source: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            .collect();
+        let mut spans = Self {
+            line_starts,
+            ..Self::default()
+        };
+        spans.collect_definitions(body, "");
+        spans
+    }
+
+    /// Parses `source` and collects its spans
+    pub fn parse(source: &str) -> Result<Self> {
+        use rustpython_parser::Parse;
+
+        let suite = ast::Suite::parse(source, "<input>")
+            .map_err(|e| anyhow!("cannot collect spans of invalid Python: {}", e))?;
+        Ok(Self::collect(&suite, source))
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionSpans> {
+        self.functions
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, f)| f)
+    }
+
+    pub fn functions(&self) -> impl Iterator<Item = (&str, &FunctionSpans)> {
+        self.functions.iter().map(|(n, f)| (n.as_str(), f))
+    }
+
+    pub fn class(&self, name: &str) -> Option<Span> {
+        self.classes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, span)| *span)
+    }
+
+    pub fn classes(&self) -> impl Iterator<Item = (&str, Span)> {
+        self.classes.iter().map(|(n, span)| (n.as_str(), *span))
+    }
+
+    pub fn position(&self, offset: u32) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        Position {
+            line,
+            column: (offset - self.line_starts[line - 1]) as usize + 1,
+        }
+    }
+
+    /// Lines the span covers, ignoring the newline that ends it
+    pub fn lines(&self, span: Span) -> RangeInclusive<usize> {
+        let last = span.end.saturating_sub(1).max(span.start);
+        self.position(span.start).line..=self.position(last).line
+    }
+
+    fn collect_definitions(&mut self, body: &[ast::Stmt], prefix: &str) {
+        for stmt in body {
+            match stmt {
+                ast::Stmt::FunctionDef(f) => {
+                    let name = format!("{}{}", prefix, f.name);
+                    self.functions
+                        .push((name, function_spans(stmt, &f.args, &f.body)));
+                }
+                ast::Stmt::AsyncFunctionDef(f) => {
+                    let name = format!("{}{}", prefix, f.name);
+                    self.functions
+                        .push((name, function_spans(stmt, &f.args, &f.body)));
+                }
+                ast::Stmt::ClassDef(class) => {
+                    let name = format!("{}{}", prefix, class.name);
+                    self.classes.push((name.clone(), Span::of(stmt)));
+                    self.collect_definitions(&class.body, &format!("{}.", name));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn function_spans(def: &ast::Stmt, args: &ast::Arguments, body: &[ast::Stmt]) -> FunctionSpans {
+    let params = args
+        .posonlyargs
+        .iter()
+        .chain(&args.args)
+        .chain(&args.kwonlyargs)
+        .map(|arg| &arg.def)
+        .chain(args.vararg.as_deref())
+        .chain(args.kwarg.as_deref())
+        .map(|arg| ParamSpan {
+            name: arg.arg.to_string(),
+            span: Span::of(arg),
+        })
+        .collect();
+    let (docstring, body) = match body.split_first() {
+        Some((first, rest)) if is_docstring(first) => (Some(Span::of(first)), rest),
+        _ => (None, body),
+    };
+    FunctionSpans {
+        def: Span::of(def),
+        params,
+        docstring,
+        body: statement_spans(body),
+    }
+}
+
+/// Docstrings become doc comments, not statements
+fn is_docstring(stmt: &ast::Stmt) -> bool {
+    matches!(
+        stmt,
+        ast::Stmt::Expr(e) if matches!(
+            &*e.value,
+            ast::Expr::Constant(c) if matches!(c.value, ast::Constant::Str(_))
+        )
+    )
+}
+
+fn statement_spans(body: &[ast::Stmt]) -> Vec<StmtSpan> {
+    body.iter()
+        .map(|stmt| StmtSpan {
+            span: Span::of(stmt),
+            blocks: nested_blocks(stmt)
+                .into_iter()
+                .map(statement_spans)
+                .collect(),
+            exprs: statement_exprs(stmt)
+                .into_iter()
+                .map(expression_span)
+                .collect(),
+        })
+        .collect()
+}
+
+/// The expressions a statement converts with, in `HirStmt` field order
+fn statement_exprs(stmt: &ast::Stmt) -> Vec<&ast::Expr> {
+    match stmt {
+        ast::Stmt::Assign(s) => vec![&*s.value],
+        ast::Stmt::AnnAssign(s) => s.value.as_deref().into_iter().collect(),
+        ast::Stmt::Return(s) => s.value.as_deref().into_iter().collect(),
+        ast::Stmt::If(s) => vec![&*s.test],
+        ast::Stmt::While(s) => vec![&*s.test],
+        ast::Stmt::For(s) => vec![&*s.iter],
+        ast::Stmt::Expr(s) => vec![&*s.value],
+        ast::Stmt::Raise(s) => s
+            .exc
+            .as_deref()
+            .into_iter()
+            .chain(s.cause.as_deref())
+            .collect(),
+        ast::Stmt::With(s) => s.items.iter().map(|item| &item.context_expr).collect(),
+        ast::Stmt::Assert(s) => std::iter::once(&*s.test).chain(s.msg.as_deref()).collect(),
+        ast::Stmt::Match(s) => vec![&*s.subject],
+        _ => Vec::new(),
+    }
+}
+
+fn expression_span(expr: &ast::Expr) -> ExprSpan {
+    let chain = match expr {
+        // `a and b and c` nests as `(a and b) and c`
+        ast::Expr::BoolOp(b) => b.values.iter().map(expression_span).reduce(link),
+        // `a < b <= c` converts as `a < b and b <= c`
+        ast::Expr::Compare(c) if c.ops.len() > 1 => std::iter::once(&*c.left)
+            .chain(&c.comparators)
+            .zip(&c.comparators)
+            .map(|(left, right)| link(expression_span(left), expression_span(right)))
+            .reduce(link),
+        _ => None,
+    };
+    if let Some(chain) = chain {
+        return chain;
+    }
+    let nested = match expr {
+        ast::Expr::Compare(c) if is_none_test(c) => vec![&*c.left],
+        _ => operands(expr),
+    };
+    ExprSpan {
+        span: Span::of(expr),
+        operands: nested.into_iter().map(expression_span).collect(),
+    }
+}
+
+/// A binary node over `left` and `right`, spanning both
+fn link(left: ExprSpan, right: ExprSpan) -> ExprSpan {
+    ExprSpan {
+        span: Span {
+            start: left.span.start,
+            end: right.span.end,
+        },
+        operands: vec![left, right],
+    }
+}
+
+/// `x is None` converts to `x.is_none()`, without the `None`
+fn is_none_test(c: &ast::ExprCompare) -> bool {
+    matches!(c.ops[..], [ast::CmpOp::Is | ast::CmpOp::IsNot])
+        && matches!(
+            &c.comparators[..],
+            [ast::Expr::Constant(ast::ExprConstant {
+                value: ast::Constant::None,
+                ..
+            })]
+        )
+}
+
+/// The operands of an expression in `HirExpr` field order
+fn operands(expr: &ast::Expr) -> Vec<&ast::Expr> {
+    match expr {
+        ast::Expr::BinOp(b) => vec![&*b.left, &*b.right],
+        ast::Expr::UnaryOp(u) => vec![&*u.operand],
+        ast::Expr::Compare(c) => std::iter::once(&*c.left).chain(&c.comparators).collect(),
+        ast::Expr::Call(c) => {
+            let receiver = match &*c.func {
+                ast::Expr::Attribute(a) => Some(&*a.value),
+                _ => None,
+            };
+            receiver
+                .into_iter()
+                .chain(&c.args)
+                .chain(c.keywords.iter().map(|kw| &kw.value))
+                .collect()
+        }
+        ast::Expr::Subscript(s) => match &*s.slice {
+            ast::Expr::Slice(slice) => std::iter::once(&*s.value)
+                .chain(slice.lower.as_deref())
+                .chain(slice.upper.as_deref())
+                .chain(slice.step.as_deref())
+                .collect(),
+            index => vec![&*s.value, index],
+        },
+        ast::Expr::List(l) => l.elts.iter().collect(),
+        ast::Expr::Tuple(t) => t.elts.iter().collect(),
+        ast::Expr::Set(s) => s.elts.iter().collect(),
+        ast::Expr::Dict(d) => d
+            .keys
+            .iter()
+            .zip(&d.values)
+            .flat_map(|(key, value)| key.iter().chain([value]))
+            .collect(),
+        ast::Expr::ListComp(c) => comprehension(&[&*c.elt], &c.generators),
+        ast::Expr::SetComp(c) => comprehension(&[&*c.elt], &c.generators),
+        ast::Expr::GeneratorExp(c) => comprehension(&[&*c.elt], &c.generators),
+        ast::Expr::DictComp(c) => comprehension(&[&*c.key, &*c.value], &c.generators),
+        ast::Expr::Lambda(l) => vec![&*l.body],
+        ast::Expr::Attribute(a) => vec![&*a.value],
+        ast::Expr::Await(a) => vec![&*a.value],
+        ast::Expr::Yield(y) => y.value.as_deref().into_iter().collect(),
+        ast::Expr::Starred(s) => vec![&*s.value],
+        ast::Expr::JoinedStr(js) => js
+            .values
+            .iter()
+            .filter_map(|value| match value {
+                ast::Expr::FormattedValue(fv) => Some(&*fv.value),
+                ast::Expr::Constant(_) => None,
+                other => Some(other),
+            })
+            .collect(),
+        ast::Expr::IfExp(i) => vec![&*i.test, &*i.body, &*i.orelse],
+        _ => Vec::new(),
+    }
+}
+
+/// The element, then each clause's iterable and conditions
+fn comprehension<'a>(
+    element: &[&'a ast::Expr],
+    generators: &'a [ast::Comprehension],
+) -> Vec<&'a ast::Expr> {
+    let clauses = generators
+        .iter()
+        .flat_map(|clause| std::iter::once(&clause.iter).chain(&clause.ifs));
+    element.iter().copied().chain(clauses).collect()
+}
+
+/// The bodies a statement converts with, in `HirStmt` field order
+fn nested_blocks(stmt: &ast::Stmt) -> Vec<&[ast::Stmt]> {
+    match stmt {
+        ast::Stmt::If(s) => vec![s.body.as_slice(), s.orelse.as_slice()],
+        ast::Stmt::While(s) => vec![s.body.as_slice()],
+        ast::Stmt::For(s) => vec![s.body.as_slice()],
+        ast::Stmt::With(s) => vec![s.body.as_slice()],
+        ast::Stmt::Try(s) => {
+            let handlers = s.handlers.iter().map(|h| {
+                let ast::ExceptHandler::ExceptHandler(h) = h;
+                h.body.as_slice()
+            });
+            std::iter::once(s.body.as_slice())
+                .chain(handlers)
+                .chain([s.orelse.as_slice(), s.finalbody.as_slice()])
+                .collect()
+        }
+        ast::Stmt::Match(s) => s.cases.iter().map(|c| c.body.as_slice()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+def check(items: list, limit: int = 3) -> int:
+    \"\"\"Counts.\"\"\"
+    total = 0
+    for item in items:
+        if item > limit:
+            total += 1
+        else:
+            total -= 1
+    return total
+
+class Box:
+    def size(self) -> int:
+        return 1
+";
+
+    #[test]
+    fn test_body_statements_skip_the_docstring() {
+        let spans = HirSpans::parse(SOURCE).unwrap();
+        let check = spans.function("check").unwrap();
+
+        assert_eq!(check.body.len(), 3);
+        assert_eq!(spans.lines(check.docstring.unwrap()), 2..=2);
+        assert_eq!(spans.lines(check.body[0].span), 3..=3);
+        assert_eq!(spans.lines(check.def), 1..=9);
+        assert_eq!(
+            spans.position(check.param("limit").unwrap().start).column,
+            24
+        );
+    }
+
+    #[test]
+    fn test_nested_statements_follow_hir_blocks() {
+        let spans = HirSpans::parse(SOURCE).unwrap();
+        let check = spans.function("check").unwrap();
+
+        let loop_if = check.statement(&[1, 0, 0]).unwrap();
+        assert_eq!(spans.lines(loop_if.span), 5..=8);
+        let else_stmt = check.statement(&[1, 0, 0, 1, 0]).unwrap();
+        assert_eq!(
+            spans.position(else_stmt.span.start),
+            Position {
+                line: 8,
+                column: 13
+            }
+        );
+        assert!(check.statement(&[1, 0, 0, 2, 0]).is_none());
+    }
+
+    #[test]
+    fn test_methods_and_classes_are_qualified() {
+        let spans = HirSpans::parse(SOURCE).unwrap();
+
+        assert_eq!(spans.lines(spans.class("Box").unwrap()), 11..=13);
+        let size = spans.function("Box.size").unwrap();
+        assert_eq!(size.params[0].name, "self");
+        let names: Vec<_> = spans.functions().map(|(name, _)| name).collect();
+        assert_eq!(names, ["check", "Box.size"]);
+    }
+
+    #[test]
+    fn test_nested_expressions_follow_hir_operands() {
+        let source = "\
+def scale(xs: list, n: int) -> int:
+    if 0 < n <= 10 and xs:
+        return len(xs) + max(xs, key=abs) * n
+    return 0
+";
+        let spans = HirSpans::parse(source).unwrap();
+        let scale = spans.function("scale").unwrap();
+        let text = |span: Span| &source[span.start as usize..span.end as usize];
+
+        // `len(xs) + max(xs, key=abs) * n`: the right operand's left operand's
+        // keyword value
+        let ret = [0, 0, 0];
+        assert_eq!(text(scale.expression(&ret, &[0, 1, 0, 1]).unwrap()), "abs");
+        assert_eq!(
+            text(scale.expression(&ret, &[0, 1]).unwrap()),
+            "max(xs, key=abs) * n"
+        );
+        // `(0 < n and n <= 10) and xs`
+        assert_eq!(text(scale.expression(&[0], &[0, 0, 1]).unwrap()), "n <= 10");
+        assert_eq!(text(scale.expression(&[0], &[0, 1]).unwrap()), "xs");
+        assert!(scale.expression(&ret, &[0, 2]).is_none());
+    }
+}
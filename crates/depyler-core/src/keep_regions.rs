@@ -0,0 +1,468 @@
+//! Hand-edited regions kept across re-transpilation
+//!
+//! Code between these markers in a generated file survives the next
+//! transpilation of its Python source:
+//!
+//! ```text
+//! // depyler:keep-start fast path
+//! ...hand-tuned Rust...
+//! // depyler:keep-end
+//! ```
+//!
+//! [`merge_keep_regions`] locates each region in the newly generated code by
+//! the generated lines around it, up to three on each side. Whatever the new
+//! code has between those lines is what the region stands in for, and the
+//! region replaces it. A region placed on generated lines it did not edit
+//! adds code in between them.
+//!
+//! The end marker records a fingerprint of the generated code the region
+//! replaced. When that code comes out differently next time, the region is
+//! still kept but reported as a conflict, and keeps being reported until
+//! the fingerprint is deleted from its end marker. A region whose
+//! surrounding lines are gone is left out and reported, as is a start
+//! marker without an end marker.
+
+use std::fmt;
+
+pub const KEEP_START: &str = "// depyler:keep-start";
+pub const KEEP_END: &str = "// depyler:keep-end";
+
+/// Generated lines on each side that locate a region
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The generated code around the region is gone; the region was left out
+    AnchorMissing,
+    /// The start marker has no end marker; the region was left out
+    Unterminated,
+    /// The generated code the region replaces changed; the region was kept
+    GeneratedChanged,
+}
+
+/// A region the merge could not carry over as it was
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepConflict {
+    /// The name after the start marker, or where the region starts
+    pub region: String,
+    /// 1-based line of the start marker in the previous file
+    pub line: usize,
+    pub kind: ConflictKind,
+    /// The region as the previous file had it
+    pub text: String,
+}
+
+impl KeepConflict {
+    /// Whether the merged code lacks the region
+    pub fn is_dropped(&self) -> bool {
+        self.kind != ConflictKind::GeneratedChanged
+    }
+}
+
+impl fmt::Display for KeepConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.kind {
+            ConflictKind::AnchorMissing => {
+                "left out: the generated code around it is gone".to_string()
+            }
+            ConflictKind::Unterminated => format!("left out: no `{}` marker", KEEP_END),
+            ConflictKind::GeneratedChanged => format!(
+                "kept, but the generated code it replaces changed; delete the \
+                 fingerprint after `{}` once reviewed",
+                KEEP_END
+            ),
+        };
+        write!(f, "{} (line {}): {}", self.region, self.line, problem)
+    }
+}
+
+/// The generated code with the regions of the previous file merged in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeepMerge {
+    pub code: String,
+    /// Regions carried over
+    pub kept: usize,
+    pub conflicts: Vec<KeepConflict>,
+}
+
+impl KeepMerge {
+    /// Whether any region of the previous file is missing from [`Self::code`]
+    pub fn dropped_regions(&self) -> bool {
+        self.conflicts.iter().any(KeepConflict::is_dropped)
+    }
+
+    /// The conflicts as text, with the regions that were left out
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for conflict in &self.conflicts {
+            report.push_str(&conflict.to_string());
+            report.push('\n');
+            if conflict.is_dropped() {
+                for line in conflict.text.lines() {
+                    report.push_str("    ");
+                    report.push_str(line);
+                    report.push('\n');
+                }
+            }
+        }
+        report
+    }
+}
+
+struct Region {
+    label: String,
+    /// 0-based lines of the markers in the previous file
+    start: usize,
+    end: usize,
+    fingerprint: Option<u64>,
+    before: Vec<String>,
+    after: Vec<String>,
+}
+
+/// Where regions go in the generated code
+struct Placement {
+    /// Generated lines replaced, as a range of 0-based lines
+    start: usize,
+    end: usize,
+    lines: Vec<String>,
+}
+
+/// Carries the keep regions of `previous`, an earlier version of the file,
+/// over into `generated`
+pub fn merge_keep_regions(previous: &str, generated: &str) -> KeepMerge {
+    let old: Vec<&str> = previous.lines().collect();
+    let (regions, mut conflicts) = find_regions(&old);
+    if regions.is_empty() && conflicts.is_empty() {
+        return KeepMerge {
+            code: generated.to_string(),
+            kept: 0,
+            conflicts,
+        };
+    }
+
+    let new: Vec<&str> = generated.lines().collect();
+    let code_lines: Vec<usize> = (0..new.len())
+        .filter(|&i| !new[i].trim().is_empty())
+        .collect();
+    let mut placements: Vec<Placement> = Vec::new();
+    let mut cursor = 0;
+    let mut kept = 0;
+    for region in regions {
+        let text = old[region.start..=region.end].join("\n");
+        // Regions keep their order and share only identical spans
+        let located =
+            locate(&new, &code_lines, &region, cursor).filter(|&(start, end, _)| match placements
+                .last()
+            {
+                Some(last) => (start, end) == (last.start, last.end) || start >= last.end,
+                None => true,
+            });
+        let Some((start, end, next_cursor)) = located else {
+            conflicts.push(KeepConflict {
+                region: region.label,
+                line: region.start + 1,
+                kind: ConflictKind::AnchorMissing,
+                text,
+            });
+            continue;
+        };
+        cursor = next_cursor;
+
+        let replaced = fingerprint(&new[start..end]);
+        let recorded = match region.fingerprint {
+            Some(recorded) if recorded != replaced => {
+                conflicts.push(KeepConflict {
+                    region: region.label,
+                    line: region.start + 1,
+                    kind: ConflictKind::GeneratedChanged,
+                    text,
+                });
+                recorded
+            }
+            _ => replaced,
+        };
+        let end_marker = old[region.end];
+        let indent = &end_marker[..end_marker.len() - end_marker.trim_start().len()];
+        let mut lines: Vec<String> = old[region.start..region.end]
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        lines.push(format!("{}{} {:016x}", indent, KEEP_END, recorded));
+        kept += 1;
+
+        match placements.last_mut() {
+            Some(last) if (last.start, last.end) == (start, end) => last.lines.extend(lines),
+            _ => placements.push(Placement { start, end, lines }),
+        }
+    }
+
+    let mut merged: Vec<String> = Vec::new();
+    let mut next = 0;
+    for placement in placements {
+        merged.extend(
+            new[next..placement.start]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        let span = &new[placement.start..placement.end];
+        let leading = span
+            .iter()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        let trailing = span[leading..]
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        merged.extend(span[..leading].iter().map(|line| line.to_string()));
+        merged.extend(placement.lines);
+        merged.extend(
+            span[span.len() - trailing..]
+                .iter()
+                .map(|line| line.to_string()),
+        );
+        next = placement.end;
+    }
+    merged.extend(new[next..].iter().map(|line| line.to_string()));
+
+    let mut code = merged.join("\n");
+    if generated.ends_with('\n') {
+        code.push('\n');
+    }
+    KeepMerge {
+        code,
+        kept,
+        conflicts,
+    }
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.trim_start().starts_with(marker)
+}
+
+/// What follows `marker` on `line`
+fn marker_argument<'a>(line: &'a str, marker: &str) -> &'a str {
+    line.trim_start()[marker.len()..].trim()
+}
+
+fn find_regions(old: &[&str]) -> (Vec<Region>, Vec<KeepConflict>) {
+    let mut bounds = Vec::new();
+    let mut conflicts = Vec::new();
+    let mut in_region = vec![false; old.len()];
+    let mut i = 0;
+    while i < old.len() {
+        if !is_marker(old[i], KEEP_START) {
+            i += 1;
+            continue;
+        }
+        let label = match marker_argument(old[i], KEEP_START) {
+            "" => format!("region at line {}", i + 1),
+            name => format!("region `{}`", name),
+        };
+        let next_marker = (i + 1..old.len())
+            .find(|&j| is_marker(old[j], KEEP_END) || is_marker(old[j], KEEP_START));
+        match next_marker {
+            Some(end) if is_marker(old[end], KEEP_END) => {
+                in_region[i..=end].iter_mut().for_each(|line| *line = true);
+                bounds.push((label, i, end));
+                i = end + 1;
+            }
+            _ => {
+                let end = next_marker.unwrap_or(old.len());
+                in_region[i..end].iter_mut().for_each(|line| *line = true);
+                conflicts.push(KeepConflict {
+                    region: label,
+                    line: i + 1,
+                    kind: ConflictKind::Unterminated,
+                    text: old[i..end].join("\n"),
+                });
+                i = end;
+            }
+        }
+    }
+
+    let regions = bounds
+        .into_iter()
+        .map(|(label, start, end)| {
+            let mut before = anchor_lines(old, &in_region, (0..start).rev());
+            before.reverse();
+            Region {
+                label,
+                start,
+                end,
+                fingerprint: u64::from_str_radix(marker_argument(old[end], KEEP_END), 16).ok(),
+                before,
+                after: anchor_lines(old, &in_region, end + 1..old.len()),
+            }
+        })
+        .collect();
+    (regions, conflicts)
+}
+
+/// The first generated lines of `old` in the order of `lines`, skipping
+/// blank lines and those of other regions
+fn anchor_lines(
+    old: &[&str],
+    in_region: &[bool],
+    lines: impl Iterator<Item = usize>,
+) -> Vec<String> {
+    lines
+        .filter(|&k| !in_region[k] && !old[k].trim().is_empty())
+        .take(CONTEXT_LINES)
+        .map(|k| old[k].trim().to_string())
+        .collect()
+}
+
+/// The generated lines `region` replaces in `new`, and where the search for
+/// the next region starts
+fn locate(
+    new: &[&str],
+    code_lines: &[usize],
+    region: &Region,
+    cursor: usize,
+) -> Option<(usize, usize, usize)> {
+    let (start, after_from, next_cursor) = if region.before.is_empty() {
+        (0, 0, cursor)
+    } else {
+        let found = find_sequence(new, code_lines, &region.before, cursor)?;
+        let last = found + region.before.len() - 1;
+        (code_lines[last] + 1, last + 1, found)
+    };
+    let end = if region.after.is_empty() {
+        new.len()
+    } else {
+        code_lines[find_sequence(new, code_lines, &region.after, after_from)?]
+    };
+    Some((start, end, next_cursor))
+}
+
+/// The first index into `code_lines`, from `from` on, where the lines of
+/// `sequence` follow each other
+fn find_sequence(
+    new: &[&str],
+    code_lines: &[usize],
+    sequence: &[String],
+    from: usize,
+) -> Option<usize> {
+    let last_start = code_lines.len().checked_sub(sequence.len())?;
+    (from..=last_start).find(|&i| {
+        sequence
+            .iter()
+            .zip(&code_lines[i..])
+            .all(|(line, &k)| new[k].trim() == line)
+    })
+}
+
+/// FNV-1a of the non-blank lines, trimmed, so reformatting does not count
+/// as a change
+fn fingerprint(lines: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for line in lines
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+    {
+        for byte in line.bytes().chain(std::iter::once(b'\n')) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: &str = "\
+pub fn area(w: i32, h: i32) -> i32 {
+    let result = w * h;
+    result
+}
+
+pub fn perimeter(w: i32, h: i32) -> i32 {
+    2 * (w + h)
+}
+";
+
+    /// `GENERATED` with the body of `area` hand-tuned
+    const EDITED: &str = "\
+pub fn area(w: i32, h: i32) -> i32 {
+    // depyler:keep-start checked
+    w.checked_mul(h).expect(\"area overflows\")
+    // depyler:keep-end
+}
+
+pub fn perimeter(w: i32, h: i32) -> i32 {
+    2 * (w + h)
+}
+";
+
+    #[test]
+    fn test_files_without_regions_are_replaced() {
+        let merge = merge_keep_regions(GENERATED, "fn other() {}\n");
+        assert_eq!(merge.code, "fn other() {}\n");
+        assert_eq!(merge.kept, 0);
+    }
+
+    #[test]
+    fn test_regions_replace_the_code_they_stand_for() {
+        let merge = merge_keep_regions(EDITED, GENERATED);
+        assert_eq!(merge.kept, 1);
+        assert!(merge.conflicts.is_empty());
+        assert!(merge.code.contains("w.checked_mul(h)"));
+        assert!(!merge.code.contains("let result"));
+        assert!(merge.code.contains("    // depyler:keep-end "));
+        assert!(merge.code.contains("pub fn perimeter"));
+
+        // Merging again changes nothing
+        let again = merge_keep_regions(&merge.code, GENERATED);
+        assert_eq!(again.code, merge.code);
+        assert!(again.conflicts.is_empty());
+    }
+
+    #[test]
+    fn test_changed_generated_code_is_a_conflict() {
+        let first = merge_keep_regions(EDITED, GENERATED).code;
+        let changed = GENERATED.replace("let result = w * h;", "let result = h * w;");
+        let merge = merge_keep_regions(&first, &changed);
+
+        assert!(merge.code.contains("w.checked_mul(h)"));
+        assert_eq!(merge.conflicts.len(), 1);
+        let conflict = &merge.conflicts[0];
+        assert_eq!(conflict.kind, ConflictKind::GeneratedChanged);
+        assert_eq!(conflict.region, "region `checked`");
+        assert_eq!(conflict.line, 2);
+        assert!(!merge.dropped_regions());
+        // The recorded fingerprint stays until the user deletes it
+        assert_eq!(merge_keep_regions(&merge.code, &changed).conflicts.len(), 1);
+    }
+
+    #[test]
+    fn test_regions_adding_code_between_generated_lines() {
+        let edited = GENERATED.replace(
+            "}\n\npub fn perimeter",
+            "}\n\n// depyler:keep-start\nfn helper() {}\n// depyler:keep-end\n\npub fn perimeter",
+        );
+        let merge = merge_keep_regions(&edited, GENERATED);
+        assert_eq!(merge.kept, 1);
+        assert!(merge.code.contains("let result = w * h;"));
+        let helper = merge.code.find("fn helper").unwrap();
+        assert!(merge.code.find("fn area").unwrap() < helper);
+        assert!(helper < merge.code.find("fn perimeter").unwrap());
+    }
+
+    #[test]
+    fn test_lost_anchors_and_unterminated_regions_are_reported() {
+        let merge = merge_keep_regions(EDITED, "pub fn volume() {}\n");
+        assert_eq!(merge.code, "pub fn volume() {}\n");
+        assert_eq!(merge.conflicts[0].kind, ConflictKind::AnchorMissing);
+        assert!(merge.dropped_regions());
+        assert!(merge.report().contains("    w.checked_mul(h)"));
+
+        let unterminated = EDITED.replace("    // depyler:keep-end\n", "");
+        let merge = merge_keep_regions(&unterminated, GENERATED);
+        assert_eq!(merge.conflicts[0].kind, ConflictKind::Unterminated);
+        assert_eq!(merge.code, GENERATED);
+    }
+}
@@ -45,24 +45,42 @@
 //! - [`Hir`] - High-level intermediate representation
 //! - [`TranspilationBackend`] - Backend trait for target languages
 
+pub mod analysis_cache;
 pub mod annotation_aware_type_mapper;
+pub mod api_diff;
 pub mod ast_bridge;
+pub mod attribute_synthesis;
 pub mod backend;
+pub mod bench_generation;
 pub mod borrowing;
 pub mod borrowing_context;
+pub mod cargo_toml_gen;
 pub mod codegen;
+pub mod const_eval;
 pub mod const_generic_inference;
+pub mod coroutine_usage;
+pub mod dataflow;
 pub mod debug;
+pub mod decorators;
+pub mod derive_inference;
 pub mod direct_rules;
 pub mod documentation;
+pub mod dynamic_attributes;
+pub mod dynamic_code;
 pub mod error;
 pub mod error_reporting;
+pub mod ffi_generation;
+pub mod field_inference;
 pub mod generator_state;
 pub mod generator_yield_analysis;
 pub mod generic_inference;
 pub mod hir;
+pub mod hir_builder;
+pub mod hir_format;
+pub mod hir_spans;
 pub mod ide;
 pub mod inlining;
+pub mod keep_regions;
 pub mod lambda_codegen;
 pub mod lambda_errors;
 pub mod lambda_inference;
@@ -70,20 +88,31 @@ pub mod lambda_optimizer;
 pub mod lambda_testing;
 pub mod lambda_types;
 pub mod lifetime_analysis;
+pub mod list_usage;
 pub mod lsp;
+pub mod method_mutation;
 pub mod migration_suggestions;
 pub mod module_mapper;
+pub mod mutation;
 pub mod optimization;
+pub mod optimize;
 pub mod optimizer;
 pub mod performance_warnings;
 pub mod profiling;
+pub mod project;
+pub mod recursive_types;
+pub mod regen_merge;
+pub mod resource_analysis;
 pub mod rust_gen;
 pub mod simplified_hir;
 pub mod string_optimization;
+pub mod symbol_names;
 pub mod test_generation;
+pub mod test_translation;
 pub mod type_hints;
 pub mod type_mapper;
 pub mod union_enum_gen;
+pub mod visibility;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
@@ -190,6 +219,24 @@ pub struct DepylerPipeline {
     mcp_client: LazyMcpClient,
     #[serde(skip_serializing_if = "Option::is_none")]
     debug_config: Option<debug::DebugConfig>,
+    #[serde(default)]
+    codegen_options: rust_gen::CodeGenOptions,
+    #[serde(default)]
+    resource_scoping: bool,
+    #[serde(default)]
+    pass_options: optimize::PassOptions,
+    #[serde(skip, default = "decorators::DecoratorRegistry::standard")]
+    decorators: decorators::DecoratorRegistry,
+    #[serde(default)]
+    emit_benches: bool,
+    #[serde(default)]
+    emit_ffi: bool,
+    #[serde(skip)]
+    rewrite_rules: Vec<optimize::peephole::RewriteRule>,
+    #[serde(default)]
+    script_main: bool,
+    #[serde(skip)]
+    analysis_cache: Option<std::sync::Arc<analysis_cache::AnalysisCache>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -266,6 +313,15 @@ impl DepylerPipeline {
             verifier: None,
             mcp_client: LazyMcpClient::default(),
             debug_config: None,
+            codegen_options: rust_gen::CodeGenOptions::default(),
+            resource_scoping: false,
+            pass_options: optimize::PassOptions::default(),
+            decorators: decorators::DecoratorRegistry::standard(),
+            emit_benches: false,
+            emit_ffi: false,
+            rewrite_rules: Vec::new(),
+            script_main: false,
+            analysis_cache: None,
         }
     }
 
@@ -282,6 +338,294 @@ impl DepylerPipeline {
         self
     }
 
+    /// Rewrite `f = open(...); ...; f.close()` into explicit `with` scopes before codegen
+    pub fn with_resource_scoping(mut self) -> Self {
+        self.resource_scoping = true;
+        self
+    }
+
+    /// Fold constant expressions and branches before codegen; see
+    /// [`optimize::const_fold`]
+    pub fn with_const_folding(mut self) -> Self {
+        self.pass_options
+            .toggles
+            .insert("const-fold".to_string(), true);
+        self
+    }
+
+    /// Switch HIR passes on or off and print the module after them; see
+    /// [`optimize::pass_manager`]
+    ///
+    /// Unknown pass names are reported when transpiling.
+    pub fn with_pass_options(mut self, options: optimize::PassOptions) -> Self {
+        self.pass_options.toggles.extend(options.toggles);
+        self.pass_options
+            .print_hir_after
+            .extend(options.print_hir_after);
+        self
+    }
+
+    /// Generate criterion benchmarks for pure functions; see
+    /// [`generate_benches`](Self::generate_benches)
+    pub fn with_benches(mut self) -> Self {
+        self.emit_benches = true;
+        self
+    }
+
+    /// Generate C ABI wrappers for functions of simple types; see
+    /// [`generate_ffi`](Self::generate_ffi)
+    pub fn with_ffi(mut self) -> Self {
+        self.emit_ffi = true;
+        self
+    }
+
+    /// Handle decorators with `decorators` instead of the
+    /// [standard](decorators::DecoratorRegistry::standard) registry
+    pub fn with_decorators(mut self, decorators: decorators::DecoratorRegistry) -> Self {
+        self.decorators = decorators;
+        self
+    }
+
+    /// Rewrite statements matching `rule` after the
+    /// [standard](optimize::peephole::PeepholeRewriter::standard) rules
+    /// have had their go, in the `peephole` pass
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use depyler_core::optimize::peephole::RewriteRule;
+    /// use depyler_core::DepylerPipeline;
+    ///
+    /// let rule = RewriteRule::parse("sorted-copy", "$x = sorted($x)", "$x.sort()").unwrap();
+    /// let rust_code = DepylerPipeline::new()
+    ///     .with_rewrite_rule(rule)
+    ///     .transpile("def f() -> list:\n    xs = [3, 1, 2]\n    xs = sorted(xs)\n    return xs\n")
+    ///     .unwrap();
+    /// assert!(rust_code.contains("xs.sort()"));
+    /// ```
+    pub fn with_rewrite_rule(mut self, rule: optimize::peephole::RewriteRule) -> Self {
+        self.rewrite_rules.push(rule);
+        self
+    }
+
+    /// Keep the statements scripts run at module level, in a generated
+    /// `main()`
+    ///
+    /// Statements outside of `if __name__ == "__main__":` go to an `init()`
+    /// that `main()` calls first; see [`ast_bridge::AstBridge::with_script_main`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use depyler_core::DepylerPipeline;
+    ///
+    /// let python_code = "total = 0\nfor i in range(3):\n    total += i\nprint(total)\n";
+    /// let rust_code = DepylerPipeline::new()
+    ///     .with_script_main()
+    ///     .transpile(python_code)
+    ///     .unwrap();
+    /// assert!(rust_code.contains("fn main()"));
+    /// ```
+    pub fn with_script_main(mut self) -> Self {
+        self.script_main = true;
+        self
+    }
+
+    /// Reuse the per-function analyses of earlier runs stored in `cache`,
+    /// and store those of functions analyzed now
+    ///
+    /// See [`analysis_cache`] for what is cached and when it is reused.
+    pub fn with_analysis_cache(mut self, cache: analysis_cache::AnalysisCache) -> Self {
+        self.analysis_cache = Some(std::sync::Arc::new(cache));
+        self
+    }
+
+    /// The cache set by [`with_analysis_cache`](Self::with_analysis_cache)
+    pub fn analysis_cache(&self) -> Option<&analysis_cache::AnalysisCache> {
+        self.analysis_cache.as_deref()
+    }
+
+    pub fn with_codegen_options(mut self, options: rust_gen::CodeGenOptions) -> Self {
+        self.codegen_options = options;
+        self
+    }
+
+    /// Report exceptions escaping `main()` like Python (`ValueError: msg` + exit status)
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use depyler_core::rust_gen::ExitCodeOptions;
+    /// use depyler_core::DepylerPipeline;
+    ///
+    /// let pipeline = DepylerPipeline::new()
+    ///     .with_exit_codes(ExitCodeOptions::enabled().with_exit_code("ValueError", 2));
+    /// let python_code = r#"
+    /// def main() -> None:
+    ///     raise ValueError("bad input")
+    /// "#;
+    ///
+    /// let rust_code = pipeline.transpile(python_code).unwrap();
+    /// assert!(rust_code.contains("std::process::exit"));
+    /// ```
+    pub fn with_exit_codes(mut self, exit_codes: rust_gen::ExitCodeOptions) -> Self {
+        self.codegen_options.exit_codes = exit_codes;
+        self
+    }
+
+    /// Put module functions behind trait seams (`FetchRateSeam`, `DefaultFetchRate`)
+    ///
+    /// Direct callers of each function gain a `<name>_with` variant taking the
+    /// seam, which is how translated tests replace `mock.patch` targets.
+    pub fn with_seams(mut self, functions: Vec<String>) -> Self {
+        self.codegen_options.seams = functions;
+        self
+    }
+
+    /// Leave out assignments whose value is never read
+    ///
+    /// Only side-effect-free values are dropped; see [`dataflow::elide_dead_stores`].
+    pub fn with_dead_store_elision(mut self) -> Self {
+        self.codegen_options.elide_dead_stores = true;
+        self
+    }
+
+    /// Give every class a Rust module of its own, mirroring Python's class
+    /// namespaces; the classes stay reachable under their plain names
+    pub fn with_class_modules(mut self) -> Self {
+        self.codegen_options.class_modules = true;
+        self
+    }
+
+    /// Type the stubs generated for the unmapped Python module `module` from
+    /// its `.pyi` stub source instead of from call sites
+    pub fn with_pyi_stub(mut self, module: impl Into<String>, source: impl Into<String>) -> Self {
+        self.codegen_options
+            .pyi_stubs
+            .insert(module.into(), source.into());
+        self
+    }
+
+    /// Resolves imports of `module` to `interface`, another module of the
+    /// project generated into the same crate
+    ///
+    /// [`transpile_project`](Self::transpile_project) does this for every
+    /// module it transpiles.
+    pub fn with_project_module(
+        mut self,
+        module: impl Into<String>,
+        interface: module_mapper::ProjectModule,
+    ) -> Self {
+        self.codegen_options
+            .project_modules
+            .insert(module.into(), interface);
+        self
+    }
+
+    /// Pick implementations, such as the hash map, for `profile`
+    pub fn with_performance_profile(mut self, profile: rust_gen::PerformanceProfile) -> Self {
+        self.codegen_options.profile = profile;
+        self
+    }
+
+    /// Bind `HashMap` to `backend` regardless of `hash` annotations
+    pub fn with_hash_backend(mut self, backend: rust_gen::HashBackend) -> Self {
+        self.codegen_options.hash_backend = Some(backend);
+        self
+    }
+
+    /// Add `#[inline]`, `#[must_use]` and complexity notes where analysis
+    /// finds them fitting
+    pub fn with_attribute_synthesis(
+        mut self,
+        options: attribute_synthesis::AttributeOptions,
+    ) -> Self {
+        self.codegen_options.attribute_synthesis = Some(options);
+        self
+    }
+
+    /// Convert `NewType` structs to and from their base type with `From`,
+    /// and dereference them to it
+    pub fn with_newtype_conversions(mut self) -> Self {
+        self.codegen_options.newtype_conversions = true;
+        self
+    }
+
+    /// Choose types for the `profile` platform: `server`, `embedded` or `wasm`
+    pub fn with_type_profile(mut self, profile: type_mapper::TypeProfile) -> Self {
+        self.codegen_options.type_profile = profile;
+        self
+    }
+
+    /// Choose the `Err` type of fallible functions
+    pub fn with_error_model(mut self, model: rust_gen::ErrorModel) -> Self {
+        self.codegen_options.error_model = model;
+        self
+    }
+
+    /// Give `int` arithmetic the overflow behavior of `semantics` in
+    /// functions without an `integer_semantics` annotation
+    pub fn with_integer_semantics(
+        mut self,
+        semantics: depyler_annotations::IntegerSemantics,
+    ) -> Self {
+        self.codegen_options.integer_semantics = semantics;
+        self
+    }
+
+    /// Declare string literals repeated at least `threshold` times in the
+    /// module once, as `&'static str` constants; 0 keeps every literal
+    /// inline
+    pub fn with_intern_threshold(mut self, threshold: usize) -> Self {
+        self.codegen_options.intern_threshold = Some(threshold);
+        self
+    }
+
+    /// Format `str()` and `repr()` the Rust way, skipping the `PyRepr`
+    /// helpers: `str(3.0)` becomes `"3"` and `str(True)` `"true"`
+    pub fn with_native_strings(mut self) -> Self {
+        self.codegen_options.native_strings = true;
+        self
+    }
+
+    /// Fail with terse messages rather than CPython's: a missing dict key
+    /// panics with `KeyError: key not found` instead of `KeyError: 'name'`,
+    /// and exception types display with a prefix such as `value error: `
+    pub fn with_native_error_messages(mut self) -> Self {
+        self.codegen_options.native_error_messages = true;
+        self
+    }
+
+    /// Keep, strip or log the `print()` statements of functions without a
+    /// `print` annotation
+    pub fn with_print_handling(mut self, handling: depyler_annotations::PrintHandling) -> Self {
+        self.codegen_options.print_handling = handling;
+        self
+    }
+
+    /// Have the module functions doing console I/O take the streams they
+    /// use as `impl BufRead` and `impl Write` parameters, so tests can run
+    /// them on buffers; `main()` passes the std streams
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use depyler_core::DepylerPipeline;
+    ///
+    /// let pipeline = DepylerPipeline::new().with_injectable_io();
+    /// let python_code = r#"
+    /// def greet(name: str) -> None:
+    ///     print("Hello,", name)
+    /// "#;
+    ///
+    /// let rust_code = pipeline.transpile(python_code).unwrap();
+    /// assert!(rust_code.contains("stdout: &mut impl std::io::Write"));
+    /// ```
+    pub fn with_injectable_io(mut self) -> Self {
+        self.codegen_options.injectable_io = true;
+        self
+    }
+
     /// Transpiles Python source code to equivalent Rust code
     ///
     /// This is the main entry point for transpilation. It performs the complete
@@ -341,83 +685,124 @@ impl DepylerPipeline {
     /// - Type inference fails
     /// - Verification fails (if enabled)
     pub fn transpile(&self, python_source: &str) -> Result<String> {
-        // Parse Python source
-        let ast = self.parse_python(python_source)?;
-
-        // Convert to HIR with annotation support
-        let mut hir = ast_bridge::AstBridge::new()
-            .with_source(python_source.to_string())
-            .python_to_hir(ast)?;
-
-        // Apply const generic inference
-        let mut const_inferencer = const_generic_inference::ConstGenericInferencer::new();
-        const_inferencer.analyze_module(&mut hir)?;
-
-        // Apply type inference hints
-        if self.analyzer.type_inference_enabled {
-            let mut type_hint_provider = type_hints::TypeHintProvider::new();
-
-            // Analyze all functions and collect hints
-            let mut function_hints = Vec::new();
-            for (idx, func) in hir.functions.iter().enumerate() {
-                if let Ok(hints) = type_hint_provider.analyze_function(func) {
-                    if !hints.is_empty() {
-                        eprintln!("Type inference hints:");
-                        eprintln!("{}", type_hint_provider.format_hints(&hints));
-                        function_hints.push((idx, hints));
-                    }
-                }
-            }
+        // Parse Python source and convert to HIR with annotation support
+        let (hir, spans) = self.parse_to_hir_with_spans(python_source)?;
+        Ok(self.transpile_hir_with_spans(hir, Some(&spans))?.0)
+    }
 
-            // Apply high-confidence hints to the HIR
-            for (func_idx, hints) in function_hints {
-                let func = &mut hir.functions[func_idx];
-
-                // Apply parameter type hints
-                for param in &mut func.params {
-                    if matches!(param.ty, hir::Type::Unknown) {
-                        // Find hint for this parameter
-                        for hint in &hints {
-                            if let type_hints::HintTarget::Parameter(hint_param) = &hint.target {
-                                if hint_param == &param.name
-                                    && matches!(
-                                        hint.confidence,
-                                        type_hints::Confidence::High
-                                            | type_hints::Confidence::Certain
-                                    )
-                                {
-                                    param.ty = hint.suggested_type.clone();
-                                    eprintln!(
-                                        "Applied type hint: {} -> {:?}",
-                                        param.name, param.ty
-                                    );
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                }
+    /// Transpiles like [`transpile`](Self::transpile), also returning which
+    /// Python definition or statement each generated function, method,
+    /// struct and statement came from
+    ///
+    /// Used to report diagnostics on the generated code against the Python
+    /// source.
+    pub fn transpile_with_source_map(
+        &self,
+        python_source: &str,
+    ) -> Result<(String, rust_gen::source_map::SourceMapTable)> {
+        let rust_code = self.transpile(python_source)?;
+        let source_map = rust_gen::source_map::SourceMapTable::build(python_source, &rust_code)?;
+        Ok((rust_code, source_map))
+    }
 
-                // Apply return type hints
-                if matches!(func.ret_type, hir::Type::Unknown) {
-                    for hint in &hints {
-                        if matches!(hint.target, type_hints::HintTarget::Return)
-                            && matches!(
-                                hint.confidence,
-                                type_hints::Confidence::High | type_hints::Confidence::Certain
-                            )
-                        {
-                            func.ret_type = hint.suggested_type.clone();
-                            eprintln!("Applied return type hint: {:?}", func.ret_type);
-                            break;
-                        }
-                    }
-                }
+    /// Runs the analysis, optimization and code generation stages of
+    /// [`transpile`](Self::transpile) on an already converted module
+    pub fn transpile_hir(&self, hir: hir::HirModule) -> Result<String> {
+        Ok(self.transpile_hir_with_metrics(hir)?.0)
+    }
+
+    /// Transpiles like [`transpile`](Self::transpile), also reporting which
+    /// HIR passes ran, how long each took and what they simplified
+    pub fn transpile_with_metrics(
+        &self,
+        python_source: &str,
+    ) -> Result<(String, optimize::OptimizationMetrics)> {
+        let (hir, spans) = self.parse_to_hir_with_spans(python_source)?;
+        let (rust_code, metrics, _) = self.transpile_hir_with_spans(hir, Some(&spans))?;
+        Ok((rust_code, metrics))
+    }
+
+    /// Transpiles like [`transpile`](Self::transpile), also returning the
+    /// known deviations from Python's semantics of each generated function
+    pub fn transpile_with_ledger(
+        &self,
+        python_source: &str,
+    ) -> Result<(String, rust_gen::ledger::DivergenceLedger)> {
+        let (hir, spans) = self.parse_to_hir_with_spans(python_source)?;
+        let (rust_code, _, ledger) = self.transpile_hir_with_spans(hir, Some(&spans))?;
+        Ok((rust_code, ledger))
+    }
+
+    /// [`transpile_hir`](Self::transpile_hir) with the metrics of
+    /// [`transpile_with_metrics`](Self::transpile_with_metrics)
+    pub fn transpile_hir_with_metrics(
+        &self,
+        hir: hir::HirModule,
+    ) -> Result<(String, optimize::OptimizationMetrics)> {
+        let (rust_code, metrics, _) = self.transpile_hir_with_ledger(hir)?;
+        Ok((rust_code, metrics))
+    }
+
+    /// [`transpile_hir_with_metrics`](Self::transpile_hir_with_metrics) with
+    /// the ledger of [`transpile_with_ledger`](Self::transpile_with_ledger)
+    pub fn transpile_hir_with_ledger(
+        &self,
+        hir: hir::HirModule,
+    ) -> Result<(
+        String,
+        optimize::OptimizationMetrics,
+        rust_gen::ledger::DivergenceLedger,
+    )> {
+        // Without the source, usage sites are named by function only
+        self.transpile_hir_with_spans(hir, None)
+    }
+
+    /// [`transpile_hir_with_ledger`](Self::transpile_hir_with_ledger),
+    /// locating diagnostics with the `spans` of the source when given
+    fn transpile_hir_with_spans(
+        &self,
+        mut hir: hir::HirModule,
+        spans: Option<&hir_spans::HirSpans>,
+    ) -> Result<(
+        String,
+        optimize::OptimizationMetrics,
+        rust_gen::ledger::DivergenceLedger,
+    )> {
+        coroutine_usage::check(&hir, spans)?;
+        dynamic_attributes::check(&hir, spans)?;
+        dynamic_code::check(&hir, spans)?;
+
+        // Run resource-leak analysis before resource-scoping rewrites the
+        // statements the spans locate
+        if self.analyzer.metrics_enabled {
+            let mut leak_analyzer = resource_analysis::ResourceLeakAnalyzer::new(
+                resource_analysis::ResourceLeakConfig::default(),
+            );
+            let warnings = leak_analyzer.analyze_functions(&hir.functions);
+            if !warnings.is_empty() {
+                eprintln!("{}", leak_analyzer.format_warnings(&warnings, spans));
             }
         }
 
-        // Apply optimization passes based on annotations
-        optimization::optimize_module(&mut hir);
+        let mut passes = optimize::HirPassManager::standard();
+        if let Some(cache) = &self.analysis_cache {
+            passes.register(optimize::passes::TypeHints(Some(cache.clone())), true);
+        }
+        passes.set_enabled("type-hints", self.analyzer.type_inference_enabled)?;
+        passes.set_enabled("resource-scoping", self.resource_scoping)?;
+        passes.register(
+            optimize::passes::IntegerSemantics(self.codegen_options.integer_semantics),
+            true,
+        );
+        if !self.rewrite_rules.is_empty() {
+            let mut rewriter = optimize::peephole::PeepholeRewriter::standard();
+            for rule in &self.rewrite_rules {
+                rewriter.add_rule(rule.clone());
+            }
+            passes.register(optimize::passes::Peephole(rewriter), true);
+        }
+        passes.configure(&self.pass_options)?;
+        let metrics = passes.run(&mut hir)?;
 
         // Convert HirModule to HirProgram for the new optimizer
         let hir_program = hir::HirProgram {
@@ -472,16 +857,124 @@ impl DepylerPipeline {
         };
 
         // Generate Rust code using the unified generation system
-        let rust_code = rust_gen::generate_rust_file(&optimized_hir, &self.transpiler.type_mapper)?;
+        let (rust_code, ledger) = rust_gen::generate_rust_file_with_ledger(
+            &optimized_hir,
+            &self.transpiler.type_mapper,
+            &self.codegen_options,
+        )?;
+
+        Ok((rust_code, metrics, ledger))
+    }
+
+    /// Transpiles the Python modules of a project
+    ///
+    /// `inputs` are directories, searched recursively for `.py` files, and
+    /// single files. Modules that do not import each other are transpiled in
+    /// parallel; see [`project`] for how modules are named and ordered.
+    pub fn transpile_project(
+        &self,
+        inputs: &[std::path::PathBuf],
+    ) -> Result<project::ProjectReport> {
+        project::transpile_project(self, inputs)
+    }
 
-        Ok(rust_code)
+    /// Transpile every mutant of the module, for mutation testing of the
+    /// generated code against its tests
+    ///
+    /// A mutant whose code cannot be generated has no `code`.
+    pub fn transpile_mutants(&self, python_source: &str) -> Result<Vec<mutation::Mutant>> {
+        let hir = self.parse_to_hir(python_source)?;
+        Ok(mutation::mutations(&hir)
+            .into_iter()
+            .map(|mutation| {
+                let code = mutation::apply(&hir, &mutation)
+                    .and_then(|mutated| self.transpile_hir(mutated).ok());
+                mutation::Mutant { mutation, code }
+            })
+            .collect())
+    }
+
+    fn ast_bridge(&self, source: &str) -> ast_bridge::AstBridge {
+        let bridge = ast_bridge::AstBridge::new()
+            .with_source(source.to_string())
+            .with_decorators(self.decorators.clone());
+        if self.script_main {
+            bridge.with_script_main()
+        } else {
+            bridge
+        }
     }
 
     pub fn parse_to_hir(&self, source: &str) -> Result<hir::HirModule> {
         let ast = self.parse_python(source)?;
-        ast_bridge::AstBridge::new()
-            .with_source(source.to_string())
-            .python_to_hir(ast)
+        self.ast_bridge(source).python_to_hir(ast)
+    }
+
+    /// HIR together with the source spans of its definitions and statements
+    pub fn parse_to_hir_with_spans(
+        &self,
+        source: &str,
+    ) -> Result<(hir::HirModule, hir_spans::HirSpans)> {
+        let ast = self.parse_python(source)?;
+        self.ast_bridge(source).python_to_hir_with_spans(ast)
+    }
+
+    /// Translate a pytest / unittest module into a Rust integration test
+    ///
+    /// `module_source` is the Python module under test, imported by the tests
+    /// as `module_name`; its transpiled form must be the library crate
+    /// `crate_name`.
+    pub fn translate_tests(
+        &self,
+        module_source: &str,
+        module_name: &str,
+        test_source: &str,
+        crate_name: &str,
+    ) -> Result<test_translation::TranslatedTests> {
+        let module = self.parse_to_hir(module_source)?;
+        let tests = self.parse_python(test_source)?;
+        test_translation::TestTranslator::new(&module, module_name, crate_name).translate(
+            tests,
+            test_source,
+            &self.transpiler.type_mapper,
+            &self.codegen_options,
+        )
+    }
+
+    /// A `benches/` file benchmarking the pure functions of `module` through
+    /// the library crate `crate_name`
+    ///
+    /// `None` unless the pipeline was built [`with_benches`](Self::with_benches),
+    /// or when no function can be benchmarked. The file needs the targets of
+    /// [`cargo_toml_gen::bench_targets`] in the crate's Cargo.toml.
+    pub fn generate_benches(
+        &self,
+        module: &hir::HirModule,
+        crate_name: &str,
+    ) -> Result<Option<String>> {
+        if !self.emit_benches {
+            return Ok(None);
+        }
+        bench_generation::BenchGenerator::new(Default::default())
+            .generate_bench_file(&module.functions, crate_name)
+    }
+
+    /// The C ABI wrappers of the module's functions, named
+    /// `<crate_name>_<function>`, and a C header declaring them
+    ///
+    /// `None` unless the pipeline was built [`with_ffi`](Self::with_ffi), or
+    /// when no function takes and returns only simple types. The wrappers
+    /// are appended to the transpiled module, and the crate needs the
+    /// [`cargo_toml_gen::cdylib_target`] section in its Cargo.toml.
+    pub fn generate_ffi(
+        &self,
+        module: &hir::HirModule,
+        crate_name: &str,
+    ) -> Result<Option<ffi_generation::FfiBindings>> {
+        if !self.emit_ffi {
+            return Ok(None);
+        }
+        ffi_generation::FfiGenerator::new(crate_name).generate(&module.functions)
     }
 
     pub fn analyze_to_typed_hir(&self, source: &str) -> Result<hir::HirModule> {
@@ -4,8 +4,11 @@
 //! idiomatic Rust code with proper borrowing and ownership patterns.
 
 use crate::borrowing_context::{BorrowingContext, BorrowingStrategy};
-use crate::hir::{AssignTarget, HirExpr, HirFunction, HirStmt};
+use crate::hir::{AssignTarget, HirExpr, HirFunction, HirStmt, Literal, Type};
+use crate::method_mutation::is_in_place_mutation;
+use crate::string_optimization::cow_return_source;
 use crate::type_mapper::RustType;
+use depyler_annotations::StringIndexing;
 use indexmap::IndexMap;
 use std::collections::{HashMap, HashSet};
 
@@ -106,6 +109,37 @@ pub struct InferredParam {
     pub rust_type: RustType,
 }
 
+impl LifetimeResult {
+    /// The parameter the return value of `func` borrows from, when the
+    /// analysis tied the return type to that parameter's lifetime
+    pub fn borrowed_return_source<'f>(&self, func: &'f HirFunction) -> Option<&'f str> {
+        borrowed_return_source(func).filter(|source| self.is_tied_to_return(source))
+    }
+
+    /// The parameter the `Cow` returned by `func` borrows from on some
+    /// paths, when the analysis tied the return type to its lifetime
+    pub fn cow_return_source<'f>(&self, func: &'f HirFunction) -> Option<&'f str> {
+        cow_return_source(func).filter(|source| self.is_tied_to_return(source))
+    }
+
+    /// Position of the parameter the return value of `func` borrows from,
+    /// always or as a `Cow`
+    pub fn borrowed_return_param(&self, func: &HirFunction) -> Option<usize> {
+        let source = self
+            .borrowed_return_source(func)
+            .or_else(|| self.cow_return_source(func))?;
+        func.params.iter().position(|param| param.name == source)
+    }
+
+    fn is_tied_to_return(&self, param: &str) -> bool {
+        let lifetime = self
+            .param_lifetimes
+            .get(param)
+            .and_then(|param| param.lifetime.as_ref());
+        lifetime.is_some() && self.return_lifetime.as_ref() == lifetime
+    }
+}
+
 impl LifetimeInference {
     pub fn new() -> Self {
         Self {
@@ -295,6 +329,17 @@ impl LifetimeInference {
             HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {
                 // Break, continue, and pass don't contain expressions to analyze
             }
+            HirStmt::Match { subject, cases } => {
+                self.analyze_expr_for_param(param, subject, usage, in_loop, false);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.analyze_expr_for_param(param, guard, usage, in_loop, false);
+                    }
+                    for stmt in &case.body {
+                        self.analyze_stmt_for_param(param, stmt, usage, in_loop);
+                    }
+                }
+            }
             HirStmt::Assert { test, msg } => {
                 // Analyze the test expression and optional message
                 self.analyze_expr_for_param(param, test, usage, in_loop, false);
@@ -687,6 +732,12 @@ impl LifetimeInference {
         // First, do the full analysis
         let full_result = self.analyze_function(func, type_mapper);
 
+        if let Some(source) = borrowed_return_source(func).or_else(|| cow_return_source(func)) {
+            if let Some(result) = self.borrow_return_from(source, &full_result) {
+                return Some(result);
+            }
+        }
+
         // Count reference parameters
         let ref_params: Vec<_> = full_result
             .param_lifetimes
@@ -738,6 +789,49 @@ impl LifetimeInference {
         Some(full_result)
     }
 
+    /// The analysis with `source` borrowed for the one lifetime of the
+    /// signature, which the return type shares; the other parameters keep
+    /// elided lifetimes. `None` when a parameter takes a `Cow`, whose
+    /// lifetime the signature would also need
+    fn borrow_return_from(
+        &self,
+        source: &str,
+        full_result: &LifetimeResult,
+    ) -> Option<LifetimeResult> {
+        if full_result
+            .borrowing_strategies
+            .values()
+            .any(|strategy| matches!(strategy, BorrowingStrategy::UseCow { .. }))
+        {
+            return None;
+        }
+        let lifetime = "'a".to_string();
+        let mut param_lifetimes = full_result.param_lifetimes.clone();
+        for (name, param) in param_lifetimes.iter_mut() {
+            if name == source {
+                param.should_borrow = true;
+                param.needs_mut = false;
+                param.lifetime = Some(lifetime.clone());
+            } else {
+                param.lifetime = None;
+            }
+        }
+        let mut borrowing_strategies = full_result.borrowing_strategies.clone();
+        borrowing_strategies.insert(
+            source.to_string(),
+            BorrowingStrategy::BorrowImmutable {
+                lifetime: Some(lifetime.clone()),
+            },
+        );
+        Some(LifetimeResult {
+            param_lifetimes,
+            return_lifetime: Some(lifetime.clone()),
+            lifetime_params: vec![lifetime],
+            lifetime_bounds: vec![],
+            borrowing_strategies,
+        })
+    }
+
     /// Check if a type is a reference type
     #[allow(dead_code)]
     fn is_reference_type(&self, rust_type: &RustType) -> bool {
@@ -748,6 +842,190 @@ impl LifetimeInference {
     }
 }
 
+/// The parameter every value `func` returns borrows from, when the return
+/// can be a reference into it instead of an owned copy
+///
+/// Each returned value must be the parameter itself, a slice of it without a
+/// step, `strip()`, `lstrip()` or `rstrip()` of it, a string literal, or a
+/// conditional choosing between these, and at least one must borrow. The
+/// parameter has the function's return type, `str` or a list, and no
+/// default; it is never rebound or mutated, so a reference into it stays
+/// valid for as long as the caller's argument does. Functions that can fail
+/// keep their owned return, which callers unwrap.
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::lifetime_analysis::borrowed_return_source;
+/// use depyler_core::DepylerPipeline;
+///
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir("def head(s: str, n: int) -> str:\n    return s[:n]\n")
+///     .unwrap();
+/// assert_eq!(borrowed_return_source(&module.functions[0]), Some("s"));
+/// ```
+pub fn borrowed_return_source(func: &HirFunction) -> Option<&str> {
+    if !can_return_borrow(func) {
+        return None;
+    }
+
+    let mut returns = Vec::new();
+    collect_returns(&func.body, &mut returns);
+    let mut sources = Vec::new();
+    let mut string_only = false;
+    for value in returns {
+        if !borrowed_parts(value?, &mut sources, &mut string_only) {
+            return None;
+        }
+    }
+    let (first, rest) = sources.split_first()?;
+    if rest.iter().any(|name| name != first) {
+        return None;
+    }
+
+    let param = func.params.iter().find(|param| param.name == *first)?;
+    let type_fits = match (&param.ty, &func.ret_type) {
+        (Type::String, Type::String) => true,
+        (Type::List(param_elem), Type::List(ret_elem)) => param_elem == ret_elem && !string_only,
+        _ => false,
+    };
+    if !type_fits || param.default.is_some() || rebinds(&func.body, &param.name) {
+        return None;
+    }
+    Some(&param.name)
+}
+
+/// Whether `func` is generated in a shape whose returns can hand out a
+/// reference: not fallible, async, a generator, a context manager or
+/// memoized, and without `try` or `with`
+pub(crate) fn can_return_borrow(func: &HirFunction) -> bool {
+    let properties = &func.properties;
+    !(properties.can_fail
+        || properties.is_async
+        || properties.is_generator
+        || properties.is_context_manager
+        || properties.memoize.is_some()
+        || func.annotations.string_indexing != StringIndexing::Chars
+        || has_handler_scope(&func.body))
+}
+
+/// `try` and `with` bodies may be generated inside closures, out of which a
+/// `return` cannot hand a borrow
+fn has_handler_scope(stmts: &[HirStmt]) -> bool {
+    stmts.iter().any(|stmt| {
+        matches!(stmt, HirStmt::Try { .. } | HirStmt::With { .. })
+            || stmt.nested_bodies().into_iter().any(has_handler_scope)
+    })
+}
+
+/// The values of the `return` statements in `stmts`, `None` for a bare one
+pub(crate) fn collect_returns<'a>(stmts: &'a [HirStmt], returns: &mut Vec<Option<&'a HirExpr>>) {
+    for stmt in stmts {
+        if let HirStmt::Return(value) = stmt {
+            returns.push(value.as_ref());
+        }
+        for body in stmt.nested_bodies() {
+            collect_returns(body, returns);
+        }
+    }
+}
+
+/// Pushes the parameters a returned `value` borrows from onto `sources`;
+/// false when it is not a borrow of one. String literals borrow nothing but
+/// restrict the parameter to a string, as do the strip methods.
+pub(crate) fn borrowed_parts<'a>(
+    value: &'a HirExpr,
+    sources: &mut Vec<&'a str>,
+    string_only: &mut bool,
+) -> bool {
+    match value {
+        HirExpr::Var(name) => {
+            sources.push(name);
+            true
+        }
+        HirExpr::Slice {
+            base, step: None, ..
+        } => match base.as_ref() {
+            HirExpr::Var(name) => {
+                sources.push(name);
+                true
+            }
+            _ => false,
+        },
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } if args.is_empty() && matches!(method.as_str(), "strip" | "lstrip" | "rstrip") => {
+            match object.as_ref() {
+                HirExpr::Var(name) => {
+                    sources.push(name);
+                    *string_only = true;
+                    true
+                }
+                _ => false,
+            }
+        }
+        HirExpr::Literal(Literal::String(_)) => {
+            *string_only = true;
+            true
+        }
+        HirExpr::IfExpr { body, orelse, .. } => {
+            borrowed_parts(body, sources, string_only)
+                && borrowed_parts(orelse, sources, string_only)
+        }
+        _ => false,
+    }
+}
+
+/// Whether `stmts` assign `name`, store into it or mutate it in place
+pub(crate) fn rebinds(stmts: &[HirStmt], name: &str) -> bool {
+    stmts.iter().any(|stmt| {
+        let here = match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                target_rebinds(target, name) || mutates(value, name)
+            }
+            HirStmt::For { target, iter, .. } => {
+                target_rebinds(target, name) || mutates(iter, name)
+            }
+            HirStmt::If { condition, .. } | HirStmt::While { condition, .. } => {
+                mutates(condition, name)
+            }
+            HirStmt::Expr(expr) | HirStmt::Return(Some(expr)) => mutates(expr, name),
+            _ => false,
+        };
+        here || stmt
+            .nested_bodies()
+            .into_iter()
+            .any(|body| rebinds(body, name))
+    })
+}
+
+fn target_rebinds(target: &AssignTarget, name: &str) -> bool {
+    let is_name = |expr: &HirExpr| matches!(expr, HirExpr::Var(var) if var == name);
+    match target {
+        AssignTarget::Symbol(symbol) => symbol == name,
+        AssignTarget::Index { base, .. } => is_name(base),
+        AssignTarget::Attribute { value, .. } => is_name(value),
+        AssignTarget::Tuple(targets) => targets.iter().any(|target| target_rebinds(target, name)),
+    }
+}
+
+fn mutates(expr: &HirExpr, name: &str) -> bool {
+    let here = match expr {
+        HirExpr::MethodCall { object, method, .. } => {
+            matches!(object.as_ref(), HirExpr::Var(var) if var == name)
+                && is_in_place_mutation(method)
+        }
+        _ => false,
+    };
+    here || expr
+        .children()
+        .into_iter()
+        .any(|child| mutates(child, name))
+}
+
 impl Default for LifetimeInference {
     fn default() -> Self {
         Self::new()
@@ -847,6 +1125,65 @@ mod tests {
         }
     }
 
+    fn borrowed_source(python: &str) -> Option<String> {
+        let module = crate::DepylerPipeline::new().parse_to_hir(python).unwrap();
+        borrowed_return_source(&module.functions[0]).map(str::to_string)
+    }
+
+    #[test]
+    fn test_borrowed_return_source() {
+        let s = Some("s".to_string());
+        assert_eq!(borrowed_source("def f(s: str) -> str:\n    return s\n"), s);
+        assert_eq!(
+            borrowed_source("def f(s: str) -> str:\n    return s.strip() if s else \"-\"\n"),
+            s
+        );
+        assert_eq!(
+            borrowed_source("def f(xs: List[int], n: int) -> List[int]:\n    return xs[n:]\n"),
+            Some("xs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_owned_returns_are_not_borrowed() {
+        for python in [
+            // Two parameters, or none, to borrow from
+            "def f(a: str, b: str) -> str:\n    return a if len(a) > len(b) else b\n",
+            "def f(s: str) -> str:\n    return \"x\"\n",
+            // A new string, and a stepped slice that copies
+            "def f(s: str) -> str:\n    return s.upper()\n",
+            "def f(s: str) -> str:\n    return s[::2]\n",
+            // The parameter changes, or may be the default
+            "def f(xs: List[int]) -> List[int]:\n    xs.append(1)\n    return xs[1:]\n",
+            "def f(s: str) -> str:\n    s = s + \"!\"\n    return s\n",
+            "def f(s: str = \"x\") -> str:\n    return s\n",
+            // A string literal cannot stand in for a list
+            "def f(xs: List[str]) -> List[str]:\n    return xs if xs else \"\"\n",
+        ] {
+            assert_eq!(borrowed_source(python), None, "{}", python);
+        }
+    }
+
+    #[test]
+    fn test_borrowed_return_ties_lifetimes() {
+        let module = crate::DepylerPipeline::new()
+            .parse_to_hir(
+                "def head(n: int, s: str, t: str) -> str:\n    return s[:n] if t else s\n",
+            )
+            .unwrap();
+        let func = &module.functions[0];
+        let type_mapper = crate::type_mapper::TypeMapper::new();
+        let result = LifetimeInference::new()
+            .apply_elision_rules(func, &type_mapper)
+            .unwrap();
+
+        assert_eq!(result.lifetime_params, ["'a"]);
+        assert_eq!(result.return_lifetime.as_deref(), Some("'a"));
+        assert_eq!(result.param_lifetimes["s"].lifetime.as_deref(), Some("'a"));
+        assert_eq!(result.param_lifetimes["t"].lifetime, None);
+        assert_eq!(result.borrowed_return_param(func), Some(1));
+    }
+
     #[test]
     fn test_mutable_parameter_detection() {
         let mut inference = LifetimeInference::new();
@@ -0,0 +1,421 @@
+//! Local lists generated as other containers
+//!
+//! A Python list is as often a worklist or a bag of seen values as it is a
+//! sequence. [`list_substitutions`] picks the local lists a function only
+//! uses in ways a better-suited container supports:
+//!
+//! - Lists worked on from the front at least as often as they are indexed
+//!   become `VecDeque`s. `xs.pop(0)` and `xs.insert(0, x)` shift every
+//!   element of a `Vec`, so draining one from the front costs O(n²).
+//! - Lists only added to and tested with `in` become `HashSet`s, for
+//!   lookups that don't scan. Their order and duplicates are never
+//!   observed, and their elements must be hashable.
+//!
+//! | Python            | `VecDeque`                              | `HashSet`                           |
+//! |-------------------|-----------------------------------------|-------------------------------------|
+//! | `q = []`          | `let mut q = VecDeque::new();`          | `let mut q = HashSet::new();`       |
+//! | `q = [a, b]`      | `let mut q = VecDeque::from(vec![..]);` | `vec![..].into_iter().collect()`    |
+//! | `q.append(x)`     | `q.push_back(x)`                        | `q.insert(x)`                       |
+//! | `q.pop()`         | `q.pop_back().expect(..)`               |                                     |
+//! | `q.pop(0)`        | `q.pop_front().expect(..)`              |                                     |
+//! | `q.insert(0, x)`  | `q.push_front(x)`                       |                                     |
+//! | `x in q`          | `q.contains(&x)`                        | `q.contains(&x)`                    |
+//!
+//! Only lists that never leave the function qualify: one passed to a call,
+//! returned, sliced or sorted would need a `Vec` there, so it stays a
+//! `Vec`. Code generation records each substitution in the divergence
+//! ledger with its reason, and `# @depyler: containers = "as_written"`
+//! keeps every list of a function a `Vec`.
+
+use crate::hir::{AssignTarget, BinOp, HirExpr, HirFunction, HirStmt, Literal, Type, UnaryOp};
+use depyler_annotations::ContainerStrategy;
+use std::collections::HashMap;
+
+/// The container a local list is generated as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListContainer {
+    VecDeque,
+    HashSet,
+}
+
+/// A local list generated as another container
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListSubstitution {
+    pub name: String,
+    pub container: ListContainer,
+    /// Why the list's uses allow it, for the divergence ledger
+    pub reason: String,
+}
+
+/// Local lists of `func` to generate as another container, by name
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::list_usage::{list_substitutions, ListContainer};
+/// use depyler_core::DepylerPipeline;
+///
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir(
+///         "def drain(n: int) -> int:\n    q = [n]\n    total = 0\n    while q:\n        x = q.pop(0)\n        total += x\n        if x > 0:\n            q.append(x - 1)\n    return total\n",
+///     )
+///     .unwrap();
+/// let substitutions = list_substitutions(&module.functions[0]);
+/// assert_eq!(substitutions[0].name, "q");
+/// assert_eq!(substitutions[0].container, ListContainer::VecDeque);
+///
+/// // Popping from the back is what a `Vec` does best
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir(
+///         "def drain(n: int) -> int:\n    q = [n]\n    total = 0\n    while q:\n        x = q.pop()\n        total += x\n    return total\n",
+///     )
+///     .unwrap();
+/// assert!(list_substitutions(&module.functions[0]).is_empty());
+/// ```
+pub fn list_substitutions(func: &HirFunction) -> Vec<ListSubstitution> {
+    if func.annotations.container_strategy == ContainerStrategy::AsWritten {
+        return Vec::new();
+    }
+    let mut usage = Usage::default();
+    usage.stmts(&func.body);
+    for param in &func.params {
+        usage.list(&param.name).escapes = true;
+    }
+    let mut substitutions: Vec<_> = usage
+        .lists
+        .into_iter()
+        .filter_map(|(name, list)| list.substitution(name))
+        .collect();
+    substitutions.sort_by(|a, b| a.name.cmp(&b.name));
+    substitutions
+}
+
+/// How a function uses one name
+#[derive(Debug, Default)]
+struct ListUse {
+    /// Every value bound to the name is a list display
+    assigned_list: bool,
+    /// A list display bound to the name holds elements of unknown or
+    /// unhashable type
+    unhashable: bool,
+    /// `pop(0)` and `insert(0, x)` calls
+    front: usize,
+    /// Reads, writes, `pop(i)` and `insert(i, x)` at other positions
+    indexed: usize,
+    /// `x in xs` and `x not in xs` tests
+    membership: usize,
+    /// Uses that see the order or the duplicates of the list, such as
+    /// `len`, iteration and popping
+    sequence: bool,
+    /// Used in a way neither container supports, or bound to something
+    /// other than a list display
+    escapes: bool,
+}
+
+impl ListUse {
+    fn substitution(self, name: String) -> Option<ListSubstitution> {
+        if !self.assigned_list || self.escapes {
+            return None;
+        }
+        if self.front > 0 && self.front >= self.indexed {
+            let reason = format!(
+                "`{}` is a `VecDeque`: {} front pops and inserts, {} indexed accesses",
+                name, self.front, self.indexed
+            );
+            return Some(ListSubstitution {
+                name,
+                container: ListContainer::VecDeque,
+                reason,
+            });
+        }
+        if self.membership > 0 && !self.sequence && !self.unhashable {
+            let reason = format!(
+                "`{}` is a `HashSet`: {} `in` tests and otherwise only added to, so its order and duplicates are never observed",
+                name, self.membership
+            );
+            return Some(ListSubstitution {
+                name,
+                container: ListContainer::HashSet,
+                reason,
+            });
+        }
+        None
+    }
+}
+
+/// Element types a `HashSet` can hold
+fn is_hashable(ty: &Type) -> bool {
+    match ty {
+        Type::Int | Type::String | Type::Bool => true,
+        Type::Tuple(elements) => elements.iter().all(is_hashable),
+        _ => false,
+    }
+}
+
+/// A list display whose elements are known to be hashable, from its
+/// annotation or its literal elements
+fn is_hashable_list(value: &HirExpr, annotation: Option<&Type>) -> bool {
+    match (annotation, value) {
+        (Some(Type::List(elem)), _) => is_hashable(elem),
+        (None, HirExpr::List(elements)) => {
+            !elements.is_empty()
+                && elements.iter().all(|e| {
+                    matches!(
+                        e,
+                        HirExpr::Literal(Literal::Int(_) | Literal::String(_) | Literal::Bool(_))
+                    )
+                })
+        }
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Usage {
+    lists: HashMap<String, ListUse>,
+}
+
+impl Usage {
+    fn list(&mut self, name: &str) -> &mut ListUse {
+        self.lists.entry(name.to_string()).or_default()
+    }
+
+    fn stmts(&mut self, body: &[HirStmt]) {
+        for stmt in body {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::Assign {
+                target,
+                value,
+                type_annotation,
+            } => {
+                match target {
+                    AssignTarget::Symbol(name) => {
+                        let hashable = is_hashable_list(value, type_annotation.as_ref());
+                        let list = self.list(name);
+                        if matches!(value, HirExpr::List(_)) {
+                            list.assigned_list = true;
+                            list.unhashable |= !hashable;
+                        } else {
+                            list.escapes = true;
+                        }
+                    }
+                    AssignTarget::Index { base, index } => self.index(base, index),
+                    _ => self.rebinds(target),
+                }
+                self.expr(value);
+            }
+            HirStmt::If { condition, .. } | HirStmt::While { condition, .. } => {
+                self.condition(condition)
+            }
+            HirStmt::For { target, iter, .. } => {
+                self.rebinds(target);
+                // `for x in q` iterates a `VecDeque` the same way
+                match iter {
+                    HirExpr::Var(name) => self.list(name).sequence = true,
+                    _ => self.expr(iter),
+                }
+            }
+            HirStmt::Return(Some(expr)) | HirStmt::Expr(expr) => self.expr(expr),
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr);
+                }
+            }
+            HirStmt::With {
+                context, target, ..
+            } => {
+                self.expr(context);
+                if let Some(name) = target {
+                    self.list(name).escapes = true;
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.condition(test);
+                if let Some(msg) = msg {
+                    self.expr(msg);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for guard in cases.iter().filter_map(|case| case.guard.as_ref()) {
+                    self.expr(guard);
+                }
+            }
+            _ => {}
+        }
+        for body in stmt.nested_bodies() {
+            self.stmts(body);
+        }
+    }
+
+    /// Names bound by a `for` or tuple target
+    fn rebinds(&mut self, target: &AssignTarget) {
+        match target {
+            AssignTarget::Symbol(name) => self.list(name).escapes = true,
+            AssignTarget::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            AssignTarget::Attribute { value, .. } => self.expr(value),
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.rebinds(t)),
+        }
+    }
+
+    /// A condition tests a list for emptiness without using it otherwise
+    fn condition(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Var(_) => {}
+            HirExpr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => self.condition(operand),
+            HirExpr::Binary {
+                op: BinOp::And | BinOp::Or,
+                left,
+                right,
+            } => {
+                self.condition(left);
+                self.condition(right);
+            }
+            _ => self.expr(expr),
+        }
+    }
+
+    fn index(&mut self, base: &HirExpr, index: &HirExpr) {
+        match base {
+            HirExpr::Var(name) => {
+                let list = self.list(name);
+                list.indexed += 1;
+                list.sequence = true;
+            }
+            _ => self.expr(base),
+        }
+        self.expr(index);
+    }
+
+    fn expr(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Var(name) => self.list(name).escapes = true,
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                kwargs,
+            } if matches!(**object, HirExpr::Var(_)) => {
+                if let HirExpr::Var(name) = &**object {
+                    self.method_call(name, method, args);
+                }
+                for arg in args.iter().chain(kwargs.iter().map(|(_, v)| v)) {
+                    self.expr(arg);
+                }
+            }
+            HirExpr::Call { func, args, .. } if func == "len" => match args.as_slice() {
+                [HirExpr::Var(name)] => self.list(name).sequence = true,
+                _ => args.iter().for_each(|arg| self.expr(arg)),
+            },
+            HirExpr::Index { base, index } => self.index(base, index),
+            HirExpr::Binary {
+                op: BinOp::In | BinOp::NotIn,
+                left,
+                right,
+            } if matches!(**right, HirExpr::Var(_)) => {
+                if let HirExpr::Var(name) = &**right {
+                    self.list(name).membership += 1;
+                }
+                self.expr(left);
+            }
+            _ => {
+                for child in expr.children() {
+                    self.expr(child);
+                }
+            }
+        }
+    }
+
+    fn method_call(&mut self, name: &str, method: &str, args: &[HirExpr]) {
+        let list = self.list(name);
+        match (method, args) {
+            ("pop", [HirExpr::Literal(Literal::Int(0))])
+            | ("insert", [HirExpr::Literal(Literal::Int(0)), _]) => {
+                list.front += 1;
+                list.sequence = true;
+            }
+            ("pop", [_]) | ("insert", [_, _]) => {
+                list.indexed += 1;
+                list.sequence = true;
+            }
+            ("append" | "extend" | "clear", _) => {}
+            ("pop" | "count" | "index", _) => list.sequence = true,
+            _ => list.escapes = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    fn substitutions(python: &str) -> Vec<(String, ListContainer)> {
+        let module = DepylerPipeline::new().parse_to_hir(python).unwrap();
+        list_substitutions(&module.functions[0])
+            .into_iter()
+            .map(|s| (s.name, s.container))
+            .collect()
+    }
+
+    #[test]
+    fn test_front_operations_must_dominate_indexing() {
+        let bfs = "def bfs(n: int) -> int:\n    q = [0]\n    steps = 0\n    while q:\n        x = q.pop(0)\n        steps += 1\n        if x < n:\n            q.insert(0, x + 1)\n    return steps\n";
+        assert_eq!(
+            substitutions(bfs),
+            vec![("q".to_string(), ListContainer::VecDeque)]
+        );
+
+        let indexed = "def f() -> int:\n    q = [1, 2, 3]\n    q.pop(0)\n    return q[0] + q[1]\n";
+        assert!(substitutions(indexed).is_empty());
+    }
+
+    #[test]
+    fn test_membership_lists_need_hashable_unobserved_elements() {
+        let dedup = "def f(xs: list[int]) -> int:\n    seen: list[int] = []\n    n = 0\n    for x in xs:\n        if x not in seen:\n            seen.append(x)\n            n += 1\n    return n\n";
+        assert_eq!(
+            substitutions(dedup),
+            vec![("seen".to_string(), ListContainer::HashSet)]
+        );
+
+        // Its length counts duplicates
+        let counted = dedup.replace("return n", "return len(seen)");
+        assert!(substitutions(&counted).is_empty());
+        // Floats are not `Hash`
+        let floats = dedup.replace("list[int] = []", "list[float] = []");
+        assert!(substitutions(&floats).is_empty());
+        // Nothing says what an empty list holds
+        let untyped = dedup.replace(": list[int] = []", " = []");
+        assert!(substitutions(&untyped).is_empty());
+    }
+
+    #[test]
+    fn test_lists_leaving_the_function_stay_vecs() {
+        let returned = "def f() -> list[int]:\n    q = [1, 2]\n    q.pop(0)\n    return q\n";
+        assert!(substitutions(returned).is_empty());
+
+        let passed = "def f() -> int:\n    q = [1, 2]\n    q.pop(0)\n    return sum(q)\n";
+        assert!(substitutions(passed).is_empty());
+
+        let param = "def f(q: list[int]) -> int:\n    return q.pop(0)\n";
+        assert!(substitutions(param).is_empty());
+
+        let opted_out = "# @depyler: containers = \"as_written\"\ndef f(x: int) -> bool:\n    small = [1, 2, 3]\n    return x in small\n";
+        assert!(substitutions(opted_out).is_empty());
+        assert_eq!(
+            substitutions(&opted_out[opted_out.find('\n').unwrap() + 1..]).len(),
+            1
+        );
+    }
+}
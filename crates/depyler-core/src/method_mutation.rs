@@ -0,0 +1,443 @@
+//! Interprocedural `&mut self` inference for class methods
+//!
+//! A method needs `&mut self` when it assigns a field, mutates a field in
+//! place (`self.items.append(x)`), or calls a method that does so, either
+//! on `self` (`self.reset()`) or on a field holding another class
+//! (`self.counter.increment()`). The calls only show up across method
+//! boundaries, so the methods of a module form a call graph keyed
+//! `Class.method`, and mutation flows from callees to their callers until
+//! it reaches a fixed point.
+
+use crate::direct_rules::method_mutates_self;
+use crate::hir::{AssignTarget, HirClass, HirExpr, HirMethod, HirStmt, Type};
+use std::collections::{HashMap, HashSet};
+
+/// Methods that mutate the collection they are called on
+pub(crate) fn is_in_place_mutation(method: &str) -> bool {
+    matches!(
+        method,
+        "append"
+            | "extend"
+            | "insert"
+            | "remove"
+            | "pop"
+            | "clear"
+            | "reverse"
+            | "sort"
+            | "update"
+            | "setdefault"
+            | "popitem"
+            | "add"
+            | "discard"
+            | "difference_update"
+            | "intersection_update"
+    )
+}
+
+/// Methods of each class that need `&mut self`
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir::*;
+/// use depyler_core::method_mutation::MutatingMethods;
+/// use smallvec::smallvec;
+///
+/// let method = |name: &str, body: Vec<HirStmt>| HirMethod {
+///     name: name.to_string(),
+///     params: smallvec![],
+///     ret_type: Type::None,
+///     body,
+///     is_static: false,
+///     is_classmethod: false,
+///     is_property: false,
+///     is_async: false,
+///     docstring: None,
+/// };
+/// let self_call = |name: &str| {
+///     HirStmt::Expr(HirExpr::MethodCall {
+///         object: Box::new(HirExpr::Var("self".to_string())),
+///         method: name.to_string(),
+///         args: vec![],
+///         kwargs: vec![],
+///     })
+/// };
+/// let counter = HirClass {
+///     name: "Counter".to_string(),
+///     base_classes: vec![],
+///     methods: vec![
+///         method("reset", vec![HirStmt::Assign {
+///             target: AssignTarget::Attribute {
+///                 value: Box::new(HirExpr::Var("self".to_string())),
+///                 attr: "count".to_string(),
+///             },
+///             value: HirExpr::Literal(Literal::Int(0)),
+///             type_annotation: None,
+///         }]),
+///         method("restart", vec![self_call("reset")]),
+///         method("show", vec![]),
+///     ],
+///     fields: vec![],
+///     is_dataclass: false,
+///     model_kind: None,
+///     annotations: Default::default(),
+///     docstring: None,
+/// };
+///
+/// let mutating = MutatingMethods::analyze(&[counter]);
+/// assert!(mutating.mutates_self("Counter", "reset"));
+/// assert!(mutating.mutates_self("Counter", "restart"));
+/// assert!(!mutating.mutates_self("Counter", "show"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MutatingMethods {
+    by_class: HashMap<String, HashSet<String>>,
+}
+
+impl MutatingMethods {
+    pub fn analyze(classes: &[HirClass]) -> Self {
+        let mut calls: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut mutating: HashSet<String> = HashSet::new();
+        for class in classes {
+            let fields: HashMap<&str, &Type> = class
+                .fields
+                .iter()
+                .filter(|f| !f.is_class_var)
+                .map(|f| (f.name.as_str(), &f.field_type))
+                .collect();
+            for method in class.methods.iter().filter(|m| takes_self(m)) {
+                let key = method_key(&class.name, &method.name);
+                let mut scan = SelfUse {
+                    class: &class.name,
+                    fields: &fields,
+                    callees: HashSet::new(),
+                    mutates: method_mutates_self(method),
+                };
+                scan.stmts(&method.body);
+                if scan.mutates {
+                    mutating.insert(key.clone());
+                }
+                calls.insert(key, scan.callees);
+            }
+        }
+
+        loop {
+            let newly_mutating: Vec<String> = calls
+                .iter()
+                .filter(|(caller, _)| !mutating.contains(*caller))
+                .filter(|(_, callees)| callees.iter().any(|c| mutating.contains(c)))
+                .map(|(caller, _)| caller.clone())
+                .collect();
+            if newly_mutating.is_empty() {
+                break;
+            }
+            mutating.extend(newly_mutating);
+        }
+
+        let mut by_class: HashMap<String, HashSet<String>> = HashMap::new();
+        for class in classes {
+            let methods = by_class.entry(class.name.clone()).or_default();
+            for method in &class.methods {
+                if mutating.contains(&method_key(&class.name, &method.name)) {
+                    methods.insert(method.name.clone());
+                }
+            }
+        }
+        MutatingMethods { by_class }
+    }
+
+    /// Whether `class.method` needs `&mut self`
+    pub fn mutates_self(&self, class: &str, method: &str) -> bool {
+        self.by_class
+            .get(class)
+            .is_some_and(|methods| methods.contains(method))
+    }
+
+    /// Class name -> its methods that need `&mut self`
+    pub fn into_map(self) -> HashMap<String, HashSet<String>> {
+        self.by_class
+    }
+}
+
+fn takes_self(method: &HirMethod) -> bool {
+    !method.is_static && !method.is_classmethod
+}
+
+fn method_key(class: &str, method: &str) -> String {
+    format!("{}.{}", class, method)
+}
+
+/// The class held by a field, seeing through `Optional` and `Box`
+fn field_class(ty: &Type) -> Option<&str> {
+    match ty {
+        Type::Custom(name) => Some(name),
+        Type::Optional(inner) => field_class(inner),
+        Type::Generic { base, params } if base == "Box" && params.len() == 1 => {
+            field_class(&params[0])
+        }
+        _ => None,
+    }
+}
+
+fn is_self(expr: &HirExpr) -> bool {
+    matches!(expr, HirExpr::Var(name) if name == "self")
+}
+
+/// How one method body uses `self`: direct mutation, and the methods it
+/// calls on `self` and on its fields
+struct SelfUse<'a> {
+    class: &'a str,
+    fields: &'a HashMap<&'a str, &'a Type>,
+    callees: HashSet<String>,
+    mutates: bool,
+}
+
+impl SelfUse<'_> {
+    fn stmts(&mut self, stmts: &[HirStmt]) {
+        stmts.iter().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.target(target);
+                self.expr(value);
+            }
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            HirStmt::For { target, iter, body } => {
+                self.target(target);
+                self.expr(iter);
+                self.stmts(body);
+            }
+            HirStmt::Expr(expr) => self.expr(expr),
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr);
+                }
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context);
+                self.stmts(body);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body);
+                handlers.iter().for_each(|h| self.stmts(&h.body));
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.expr(test);
+                if let Some(msg) = msg {
+                    self.expr(msg);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.stmts(&case.body);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    /// `self.x = ...` and `self.items[k] = ...` write through `self`
+    fn target(&mut self, target: &AssignTarget) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Attribute { value, .. } => {
+                self.mutates |= is_self(value);
+                self.expr(value);
+            }
+            AssignTarget::Index { base, index } => {
+                if let HirExpr::Attribute { value, .. } = &**base {
+                    self.mutates |= is_self(value);
+                }
+                self.expr(base);
+                self.expr(index);
+            }
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t)),
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr) {
+        if let HirExpr::MethodCall { object, method, .. } = expr {
+            match &**object {
+                object if is_self(object) => {
+                    self.callees.insert(method_key(self.class, method));
+                }
+                HirExpr::Attribute { value, attr } if is_self(value) => {
+                    let held_class = self
+                        .fields
+                        .get(attr.as_str())
+                        .and_then(|ty| field_class(ty));
+                    match held_class {
+                        Some(class) => {
+                            self.callees.insert(method_key(class, method));
+                        }
+                        None => self.mutates |= is_in_place_mutation(method),
+                    }
+                }
+                _ => {}
+            }
+        }
+        for child in expr.children() {
+            self.expr(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::HirField;
+    use smallvec::smallvec;
+
+    fn method(name: &str, body: Vec<HirStmt>) -> HirMethod {
+        HirMethod {
+            name: name.to_string(),
+            params: smallvec![],
+            ret_type: Type::None,
+            body,
+            is_static: false,
+            is_classmethod: false,
+            is_property: false,
+            is_async: false,
+            docstring: None,
+        }
+    }
+
+    fn call(object: HirExpr, method: &str) -> HirStmt {
+        HirStmt::Expr(HirExpr::MethodCall {
+            object: Box::new(object),
+            method: method.to_string(),
+            args: vec![],
+            kwargs: vec![],
+        })
+    }
+
+    fn self_field(attr: &str) -> HirExpr {
+        HirExpr::Attribute {
+            value: Box::new(HirExpr::Var("self".to_string())),
+            attr: attr.to_string(),
+        }
+    }
+
+    fn class(name: &str, fields: Vec<(&str, Type)>, methods: Vec<HirMethod>) -> HirClass {
+        HirClass {
+            name: name.to_string(),
+            base_classes: vec![],
+            methods,
+            fields: fields
+                .into_iter()
+                .map(|(name, field_type)| HirField {
+                    name: name.to_string(),
+                    field_type,
+                    default_value: None,
+                    is_class_var: false,
+                })
+                .collect(),
+            is_dataclass: false,
+            model_kind: None,
+            annotations: Default::default(),
+            docstring: None,
+        }
+    }
+
+    #[test]
+    fn test_in_place_field_mutation() {
+        let log = class(
+            "Log",
+            vec![("lines", Type::List(Box::new(Type::String)))],
+            vec![
+                method("add", vec![call(self_field("lines"), "append")]),
+                method("size", vec![]),
+            ],
+        );
+        let mutating = MutatingMethods::analyze(&[log]);
+        assert!(mutating.mutates_self("Log", "add"));
+        assert!(!mutating.mutates_self("Log", "size"));
+    }
+
+    #[test]
+    fn test_mutation_through_field_of_another_class() {
+        let counter = class(
+            "Counter",
+            vec![("count", Type::Int)],
+            vec![
+                method(
+                    "increment",
+                    vec![HirStmt::Assign {
+                        target: AssignTarget::Attribute {
+                            value: Box::new(HirExpr::Var("self".to_string())),
+                            attr: "count".to_string(),
+                        },
+                        value: HirExpr::Literal(crate::hir::Literal::Int(1)),
+                        type_annotation: None,
+                    }],
+                ),
+                method("peek", vec![]),
+            ],
+        );
+        let stats = class(
+            "Stats",
+            vec![("hits", Type::Custom("Counter".to_string()))],
+            vec![
+                method("record", vec![call(self_field("hits"), "increment")]),
+                method("report", vec![call(self_field("hits"), "peek")]),
+            ],
+        );
+        let mutating = MutatingMethods::analyze(&[stats, counter]);
+        assert!(mutating.mutates_self("Stats", "record"));
+        assert!(!mutating.mutates_self("Stats", "report"));
+    }
+
+    #[test]
+    fn test_mutually_recursive_methods() {
+        let machine = class(
+            "Machine",
+            vec![("items", Type::List(Box::new(Type::Int)))],
+            vec![
+                method("ping", vec![call(HirExpr::Var("self".to_string()), "pong")]),
+                method(
+                    "pong",
+                    vec![
+                        call(HirExpr::Var("self".to_string()), "ping"),
+                        call(self_field("items"), "clear"),
+                    ],
+                ),
+            ],
+        );
+        let mutating = MutatingMethods::analyze(&[machine]);
+        assert!(mutating.mutates_self("Machine", "ping"));
+        assert!(mutating.mutates_self("Machine", "pong"));
+    }
+}
@@ -1,7 +1,10 @@
 //! Module mapping from Python to Rust equivalents
 
-use crate::hir::{Import, ImportItem};
-use std::collections::HashMap;
+use crate::hir::{HirModule, Import, ImportItem, Type};
+use crate::lifetime_analysis::LifetimeInference;
+use crate::type_mapper::TypeMapper;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 #[cfg(test)]
 #[path = "module_mapper_tests.rs"]
@@ -11,6 +14,8 @@ mod tests;
 pub struct ModuleMapper {
     /// Mapping from Python module names to Rust crate/module paths
     module_map: HashMap<String, ModuleMapping>,
+    /// Modules of the project being transpiled, generated into the same crate
+    project_modules: HashSet<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -151,6 +156,57 @@ impl ModuleMapper {
             },
         );
 
+        // asyncio calls are lowered to tokio paths at the call site, but the
+        // crate still has to be declared as a dependency
+        module_map.insert(
+            "asyncio".to_string(),
+            ModuleMapping {
+                rust_path: "tokio".to_string(),
+                is_external: true,
+                version: Some("1".to_string()),
+                item_map: HashMap::from([
+                    ("sleep".to_string(), "time::sleep".to_string()),
+                    ("gather".to_string(), "join".to_string()),
+                    ("Lock".to_string(), "sync::Mutex".to_string()),
+                    ("TaskGroup".to_string(), "task::JoinSet".to_string()),
+                    (
+                        "Queue".to_string(),
+                        "sync::mpsc::unbounded_channel".to_string(),
+                    ),
+                ]),
+            },
+        );
+
+        // aiohttp sessions become reqwest's async client, likewise lowered
+        // at the call site
+        module_map.insert(
+            "aiohttp".to_string(),
+            ModuleMapping {
+                rust_path: "reqwest".to_string(),
+                is_external: true,
+                version: Some("0.12".to_string()),
+                item_map: HashMap::from([
+                    ("ClientSession".to_string(), "Client".to_string()),
+                    ("ClientResponse".to_string(), "Response".to_string()),
+                ]),
+            },
+        );
+
+        // Calls into these modules are lowered inline by the code generator
+        // (warnings.warn -> eprintln!, traceback.format_exc -> error Display,
+        // contextlib -> ContextGuard / ExitStack)
+        for module in ["warnings", "traceback", "contextlib"] {
+            module_map.insert(
+                module.to_string(),
+                ModuleMapping {
+                    rust_path: "".to_string(),
+                    is_external: false,
+                    version: None,
+                    item_map: HashMap::new(),
+                },
+            );
+        }
+
         module_map.insert(
             "collections".to_string(),
             ModuleMapping {
@@ -351,7 +407,58 @@ impl ModuleMapper {
             },
         );
 
-        Self { module_map }
+        Self {
+            module_map,
+            project_modules: HashSet::new(),
+        }
+    }
+
+    /// Maps `python_module`, another module of the project, to the Rust
+    /// module generated from it
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use depyler_core::module_mapper::{ModuleMapper, ProjectModule};
+    /// use depyler_core::hir::{Import, ImportItem};
+    ///
+    /// let mut mapper = ModuleMapper::new();
+    /// let utils = ProjectModule {
+    ///     rust_path: "crate::utils".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// mapper.add_project_module("mypackage.utils", &utils);
+    ///
+    /// let import = Import {
+    ///     module: "mypackage.utils".to_string(),
+    ///     items: vec![ImportItem::Named("helper".to_string())],
+    /// };
+    /// assert_eq!(mapper.map_import(&import)[0].path, "crate::utils::helper");
+    /// ```
+    pub fn add_project_module(&mut self, python_module: &str, module: &ProjectModule) {
+        let functions = module
+            .functions
+            .keys()
+            .map(|name| (name.clone(), name.clone()));
+        let classes = module
+            .classes
+            .iter()
+            .map(|name| (name.clone(), format!("{}::new", name)));
+        self.module_map.insert(
+            python_module.to_string(),
+            ModuleMapping {
+                rust_path: module.rust_path.clone(),
+                is_external: false,
+                version: None,
+                item_map: functions.chain(classes).collect(),
+            },
+        );
+        self.project_modules.insert(python_module.to_string());
+    }
+
+    /// Whether `module_name` was added by [`add_project_module`](Self::add_project_module)
+    pub fn is_project_module(&self, module_name: &str) -> bool {
+        self.project_modules.contains(module_name)
     }
 
     /// Map a Python import to Rust use statements
@@ -380,7 +487,11 @@ impl ModuleMapper {
             if import.items.is_empty() {
                 // DEPYLER-0363: For mapped modules, emit the Rust equivalent
                 // For argparse, this means `use clap::Parser;`
-                if !mapping.rust_path.is_empty() {
+                if Self::is_lowered_inline(&import.module) {
+                    // Nothing to import: every call site is rewritten in place
+                } else if self.is_project_module(&import.module) {
+                    // Calls through the module name use the full `crate::` path
+                } else if !mapping.rust_path.is_empty() {
                     // For external crates like argparse->clap, import the main trait/type
                     if import.module == "argparse" {
                         // ArgumentParser needs the Parser derive trait
@@ -409,6 +520,22 @@ impl ModuleMapper {
                 // Handle each imported item
                 for item in &import.items {
                     match item {
+                        ImportItem::Named(name) if self.is_project_module(&import.module) => {
+                            rust_imports.push(RustImport {
+                                path: format!("{}::{}", mapping.rust_path, name),
+                                alias: None,
+                                is_external: false,
+                            });
+                        }
+                        ImportItem::Aliased { name, alias }
+                            if self.is_project_module(&import.module) =>
+                        {
+                            rust_imports.push(RustImport {
+                                path: format!("{}::{}", mapping.rust_path, name),
+                                alias: Some(alias.clone()),
+                                is_external: false,
+                            });
+                        }
                         ImportItem::Named(name) => {
                             if let Some(rust_name) = mapping.item_map.get(name) {
                                 rust_imports.push(RustImport {
@@ -497,6 +624,15 @@ impl ModuleMapper {
         deps
     }
 
+    /// Whether calls into `module_name` are rewritten at the call site,
+    /// so importing the module needs no Rust `use` or placeholder comment
+    pub fn is_lowered_inline(module_name: &str) -> bool {
+        matches!(
+            module_name,
+            "warnings" | "traceback" | "contextlib" | "asyncio" | "aiohttp"
+        )
+    }
+
     /// Get module mapping for a given module name
     ///
     /// # Examples
@@ -517,6 +653,72 @@ impl ModuleMapper {
     }
 }
 
+/// Another module of the project, as the modules importing it see it
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectModule {
+    /// Path of the generated Rust module, such as `crate::shop::cart`
+    pub rust_path: String,
+    pub functions: BTreeMap<String, ProjectFunction>,
+    pub classes: BTreeSet<String>,
+}
+
+/// What callers in other modules need to know about a module function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectFunction {
+    pub ret_type: Type,
+    /// Whether each parameter is taken by reference, so callers borrow
+    /// their arguments as calls within the module do
+    pub param_borrows: Vec<bool>,
+    /// Returns a `Result`
+    pub can_fail: bool,
+    /// Position of the parameter the returned reference points into
+    #[serde(default)]
+    pub borrowed_return_param: Option<usize>,
+    /// The returned reference is a `Cow`, borrowed on some paths only
+    #[serde(default)]
+    pub cow_return: bool,
+}
+
+impl ProjectModule {
+    /// The functions and classes of `module`, generated at `rust_path`
+    pub fn from_hir(module: &HirModule, rust_path: String, type_mapper: &TypeMapper) -> Self {
+        let functions = module
+            .functions
+            .iter()
+            .map(|func| {
+                // The same borrowing decisions as the function's own codegen
+                let mut lifetimes = LifetimeInference::new();
+                let lifetime_result = lifetimes
+                    .apply_elision_rules(func, type_mapper)
+                    .unwrap_or_else(|| lifetimes.analyze_function(func, type_mapper));
+                let param_borrows = func
+                    .params
+                    .iter()
+                    .map(|p| {
+                        lifetime_result
+                            .param_lifetimes
+                            .get(&p.name)
+                            .is_some_and(|inf| inf.should_borrow)
+                    })
+                    .collect();
+                let signature = ProjectFunction {
+                    ret_type: func.ret_type.clone(),
+                    param_borrows,
+                    can_fail: func.properties.can_fail,
+                    borrowed_return_param: lifetime_result.borrowed_return_param(func),
+                    cow_return: lifetime_result.cow_return_source(func).is_some(),
+                };
+                (func.name.clone(), signature)
+            })
+            .collect();
+        Self {
+            rust_path,
+            functions,
+            classes: module.classes.iter().map(|c| c.name.clone()).collect(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct RustImport {
     pub path: String,
@@ -1,5 +1,5 @@
 use crate::hir::{Import, ImportItem};
-use crate::module_mapper::{ModuleMapper, ModuleMapping, RustImport};
+use crate::module_mapper::{ModuleMapper, ModuleMapping, ProjectModule, RustImport};
 use std::collections::HashMap;
 
 #[test]
@@ -76,6 +76,23 @@ fn test_external_crate_mappings() {
     assert_eq!(datetime_mapping.version.as_ref().unwrap(), "0.4");
 }
 
+#[test]
+fn test_async_client_and_queue_mappings() {
+    let mapper = ModuleMapper::new();
+
+    let aiohttp = mapper.get_mapping("aiohttp").unwrap();
+    assert_eq!(aiohttp.rust_path, "reqwest");
+    assert!(aiohttp.is_external);
+    assert_eq!(aiohttp.item_map.get("ClientSession").unwrap(), "Client");
+
+    let asyncio = mapper.get_mapping("asyncio").unwrap();
+    assert_eq!(
+        asyncio.item_map.get("Queue").unwrap(),
+        "sync::mpsc::unbounded_channel"
+    );
+    assert!(ModuleMapper::is_lowered_inline("aiohttp"));
+}
+
 #[test]
 fn test_typing_module_mapping() {
     let mapper = ModuleMapper::new();
@@ -392,3 +409,39 @@ fn test_collections_deque_import_path() {
     assert_eq!(rust_imports[0].path, "std::collections::VecDeque");
     assert!(!rust_imports[0].is_external);
 }
+
+#[test]
+fn test_project_module_imports_use_crate_paths() {
+    let mut mapper = ModuleMapper::new();
+    let models = ProjectModule {
+        rust_path: "crate::shop::models".to_string(),
+        classes: ["User".to_string()].into(),
+        ..Default::default()
+    };
+    mapper.add_project_module("shop.models", &models);
+
+    let from_import = Import {
+        module: "shop.models".to_string(),
+        items: vec![ImportItem::Aliased {
+            name: "User".to_string(),
+            alias: "Customer".to_string(),
+        }],
+    };
+    let rust_imports = mapper.map_import(&from_import);
+    assert_eq!(rust_imports.len(), 1);
+    assert_eq!(rust_imports[0].path, "crate::shop::models::User");
+    assert_eq!(rust_imports[0].alias.as_deref(), Some("Customer"));
+
+    // Calls through the module name carry the whole path
+    let whole_import = Import {
+        module: "shop.models".to_string(),
+        items: vec![],
+    };
+    assert!(mapper.map_import(&whole_import).is_empty());
+    assert_eq!(
+        mapper.get_mapping("shop.models").unwrap().item_map["User"],
+        "User::new"
+    );
+    assert!(mapper.is_project_module("shop.models"));
+    assert!(!mapper.is_project_module("json"));
+}
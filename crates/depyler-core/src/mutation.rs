@@ -0,0 +1,468 @@
+//! HIR-level mutation operators
+//!
+//! Mutation testing checks that the tests of transpiled code actually pin
+//! its behaviour: each mutant changes one operator or constant in one
+//! function, the module is transpiled again, and a good test suite fails.
+//! Mutating the HIR instead of the generated text keeps every mutant a
+//! well-formed module and ties it to the Python function it came from.
+//!
+//! Sites are numbered per function in a fixed traversal order, so a
+//! [`Mutation`] found by [`mutations`] can be re-applied with [`apply`].
+
+use crate::hir::{BinOp, FStringPart, HirExpr, HirModule, HirStmt, Literal};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutationOperator {
+    /// `+` <-> `-`, `*` <-> `/`, `==` <-> `!=`, `and` <-> `or`
+    OperatorSwap,
+    /// `<` <-> `<=`, `>` <-> `>=`, and `n` -> `n + 1` for integers compared
+    /// against
+    BoundaryChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mutation {
+    /// `name` for module functions, `Class.name` for methods
+    pub function: String,
+    /// Index of the mutated site within the function
+    pub site: usize,
+    pub operator: MutationOperator,
+    /// e.g. ``replace `<` with `<=` ``
+    pub description: String,
+}
+
+/// A mutation with the Rust code generated for the mutated module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mutant {
+    pub mutation: Mutation,
+    /// `None` when code generation rejected the mutated module
+    pub code: Option<String>,
+}
+
+/// Every mutation of the module's functions and methods
+pub fn mutations(module: &HirModule) -> Vec<Mutation> {
+    let mut found = Vec::new();
+    for (function, body) in bodies(module) {
+        let mut sites = Sites::collect();
+        sites.stmts(&mut body.clone());
+        found.extend(
+            sites
+                .found
+                .into_iter()
+                .enumerate()
+                .map(|(site, (operator, description))| Mutation {
+                    function: function.clone(),
+                    site,
+                    operator,
+                    description,
+                }),
+        );
+    }
+    found
+}
+
+/// The module with `mutation` applied, if its function and site exist
+pub fn apply(module: &HirModule, mutation: &Mutation) -> Option<HirModule> {
+    let mut mutated = module.clone();
+    let body = bodies_mut(&mut mutated)
+        .into_iter()
+        .find(|(name, _)| *name == mutation.function)
+        .map(|(_, body)| body)?;
+    let mut sites = Sites::apply(mutation.site);
+    sites.stmts(body);
+    sites.applied.then_some(mutated)
+}
+
+fn bodies(module: &HirModule) -> Vec<(String, &Vec<HirStmt>)> {
+    let functions = module.functions.iter().map(|f| (f.name.clone(), &f.body));
+    let methods = module.classes.iter().flat_map(|class| {
+        class
+            .methods
+            .iter()
+            .map(move |m| (format!("{}.{}", class.name, m.name), &m.body))
+    });
+    functions.chain(methods).collect()
+}
+
+fn bodies_mut(module: &mut HirModule) -> Vec<(String, &mut Vec<HirStmt>)> {
+    let functions = module
+        .functions
+        .iter_mut()
+        .map(|f| (f.name.clone(), &mut f.body));
+    let methods = module.classes.iter_mut().flat_map(|class| {
+        let class_name = class.name.clone();
+        class
+            .methods
+            .iter_mut()
+            .map(move |m| (format!("{}.{}", class_name, m.name), &mut m.body))
+    });
+    functions.chain(methods).collect()
+}
+
+/// The replacement for a mutable binary operator
+fn swap(op: BinOp) -> Option<(BinOp, MutationOperator)> {
+    use BinOp::*;
+    use MutationOperator::*;
+    Some(match op {
+        Add => (Sub, OperatorSwap),
+        Sub => (Add, OperatorSwap),
+        Mul => (Div, OperatorSwap),
+        Div => (Mul, OperatorSwap),
+        Eq => (NotEq, OperatorSwap),
+        NotEq => (Eq, OperatorSwap),
+        And => (Or, OperatorSwap),
+        Or => (And, OperatorSwap),
+        Lt => (LtEq, BoundaryChange),
+        LtEq => (Lt, BoundaryChange),
+        Gt => (GtEq, BoundaryChange),
+        GtEq => (Gt, BoundaryChange),
+        _ => return None,
+    })
+}
+
+fn symbol(op: BinOp) -> &'static str {
+    match op {
+        BinOp::Add => "+",
+        BinOp::Sub => "-",
+        BinOp::Mul => "*",
+        BinOp::Div => "/",
+        BinOp::Eq => "==",
+        BinOp::NotEq => "!=",
+        BinOp::And => "and",
+        BinOp::Or => "or",
+        BinOp::Lt => "<",
+        BinOp::LtEq => "<=",
+        BinOp::Gt => ">",
+        BinOp::GtEq => ">=",
+        _ => "?",
+    }
+}
+
+fn is_comparison(op: BinOp) -> bool {
+    matches!(
+        op,
+        BinOp::Eq | BinOp::NotEq | BinOp::Lt | BinOp::LtEq | BinOp::Gt | BinOp::GtEq
+    )
+}
+
+/// Walks a function body visiting mutation sites in order, either recording
+/// them or applying the one at `target`
+struct Sites {
+    next: usize,
+    target: Option<usize>,
+    found: Vec<(MutationOperator, String)>,
+    applied: bool,
+}
+
+impl Sites {
+    fn collect() -> Self {
+        Self {
+            next: 0,
+            target: None,
+            found: Vec::new(),
+            applied: false,
+        }
+    }
+
+    fn apply(target: usize) -> Self {
+        Self {
+            target: Some(target),
+            ..Self::collect()
+        }
+    }
+
+    /// Registers the next site; true when it is the one to mutate
+    fn site(&mut self, operator: MutationOperator, describe: impl FnOnce() -> String) -> bool {
+        let index = self.next;
+        self.next += 1;
+        match self.target {
+            Some(target) if target == index => {
+                self.applied = true;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.found.push((operator, describe()));
+                false
+            }
+        }
+    }
+
+    fn stmts(&mut self, stmts: &mut [HirStmt]) {
+        for stmt in stmts {
+            self.stmt(stmt);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &mut HirStmt) {
+        match stmt {
+            HirStmt::Assign { value, .. } | HirStmt::Expr(value) => self.expr(value),
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter);
+                self.stmts(body);
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context);
+                self.stmts(body);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body);
+                for handler in handlers {
+                    self.stmts(&mut handler.body);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block);
+                }
+            }
+            HirStmt::Assert { test, .. } => self.expr(test),
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for case in cases {
+                    if let Some(guard) = &mut case.guard {
+                        self.expr(guard);
+                    }
+                    self.stmts(&mut case.body);
+                }
+            }
+            HirStmt::Raise { .. }
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+            | HirStmt::Pass => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &mut HirExpr) {
+        if let HirExpr::Binary { op, left, right } = expr {
+            if let Some((replacement, operator)) = swap(*op) {
+                let original = *op;
+                if self.site(operator, || {
+                    format!(
+                        "replace `{}` with `{}`",
+                        symbol(original),
+                        symbol(replacement)
+                    )
+                }) {
+                    *op = replacement;
+                }
+            }
+            if is_comparison(*op) {
+                for side in [left.as_mut(), right.as_mut()] {
+                    if let HirExpr::Literal(Literal::Int(n)) = side {
+                        if let Some(shifted) = n.checked_add(1) {
+                            let original = *n;
+                            if self.site(MutationOperator::BoundaryChange, || {
+                                format!("replace `{}` with `{}`", original, shifted)
+                            }) {
+                                *n = shifted;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        self.children(expr);
+    }
+
+    /// # Complexity
+    /// 10 (one arm per compound expression kind)
+    fn children(&mut self, expr: &mut HirExpr) {
+        match expr {
+            HirExpr::Literal(_) | HirExpr::Var(_) => {}
+            HirExpr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            HirExpr::Unary { operand: value, .. }
+            | HirExpr::Attribute { value, .. }
+            | HirExpr::Borrow { expr: value, .. }
+            | HirExpr::Await { value }
+            | HirExpr::Lambda { body: value, .. } => self.expr(value),
+            HirExpr::Call { args, kwargs, .. } => {
+                args.iter_mut().for_each(|arg| self.expr(arg));
+                kwargs.iter_mut().for_each(|(_, arg)| self.expr(arg));
+            }
+            HirExpr::MethodCall {
+                object,
+                args,
+                kwargs,
+                ..
+            } => {
+                self.expr(object);
+                args.iter_mut().for_each(|arg| self.expr(arg));
+                kwargs.iter_mut().for_each(|(_, arg)| self.expr(arg));
+            }
+            HirExpr::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            HirExpr::Slice {
+                base,
+                start,
+                stop,
+                step,
+            } => {
+                self.expr(base);
+                for part in [start, stop, step].into_iter().flatten() {
+                    self.expr(part);
+                }
+            }
+            HirExpr::List(items)
+            | HirExpr::Tuple(items)
+            | HirExpr::Set(items)
+            | HirExpr::FrozenSet(items) => items.iter_mut().for_each(|item| self.expr(item)),
+            HirExpr::Dict(items) => {
+                for (key, value) in items {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            HirExpr::ListComp {
+                element,
+                iter,
+                condition,
+                ..
+            }
+            | HirExpr::SetComp {
+                element,
+                iter,
+                condition,
+                ..
+            } => {
+                self.expr(iter);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                self.expr(element);
+            }
+            HirExpr::DictComp {
+                key,
+                value,
+                iter,
+                condition,
+                ..
+            } => {
+                self.expr(iter);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+                self.expr(key);
+                self.expr(value);
+            }
+            HirExpr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(value) = part {
+                        self.expr(value);
+                    }
+                }
+            }
+            HirExpr::Yield { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirExpr::IfExpr { test, body, orelse } => {
+                self.expr(test);
+                self.expr(body);
+                self.expr(orelse);
+            }
+            HirExpr::SortByKey {
+                iterable, key_body, ..
+            } => {
+                self.expr(iterable);
+                self.expr(key_body);
+            }
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => {
+                for generator in generators {
+                    self.expr(&mut generator.iter);
+                    generator
+                        .conditions
+                        .iter_mut()
+                        .for_each(|condition| self.expr(condition));
+                }
+                self.expr(element);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = r#"
+def clamp(x: int) -> int:
+    if x < 0:
+        return 0
+    return x + 1
+"#;
+
+    #[test]
+    fn test_mutations_are_numbered_per_function() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let found: Vec<_> = mutations(&module)
+            .into_iter()
+            .map(|m| (m.site, m.operator, m.description))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (
+                    0,
+                    MutationOperator::BoundaryChange,
+                    "replace `<` with `<=`".to_string()
+                ),
+                (
+                    1,
+                    MutationOperator::BoundaryChange,
+                    "replace `0` with `1`".to_string()
+                ),
+                (
+                    2,
+                    MutationOperator::OperatorSwap,
+                    "replace `+` with `-`".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_changes_only_the_selected_site() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let swap_add = mutations(&module).remove(2);
+        let mutated = apply(&module, &swap_add).unwrap();
+
+        let rust = DepylerPipeline::new().transpile_hir(mutated).unwrap();
+        assert!(rust.contains("x - 1"));
+        assert!(rust.contains("x < 0"));
+    }
+}
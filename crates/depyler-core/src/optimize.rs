@@ -0,0 +1,24 @@
+//! Rewrites of the HIR between lowering and code generation
+//!
+//! The pipeline runs them through a [`HirPassManager`]; see
+//! [`DepylerPipeline::with_pass_options`](crate::DepylerPipeline::with_pass_options).
+
+use serde::{Deserialize, Serialize};
+
+pub mod const_fold;
+pub mod idioms;
+pub mod pass_manager;
+pub mod passes;
+pub mod peephole;
+
+pub use pass_manager::{HirPass, HirPassManager, PassOptions, PassReport};
+
+/// What the optimization passes changed in a module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OptimizationMetrics {
+    /// Expressions and statements rewritten or removed
+    pub nodes_simplified: usize,
+    /// The passes that ran, in order
+    #[serde(default)]
+    pub passes: Vec<PassReport>,
+}
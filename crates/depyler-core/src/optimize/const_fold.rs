@@ -0,0 +1,589 @@
+//! Constant folding and propagation
+//!
+//! - Operators whose operands are all literals are evaluated with Python's
+//!   semantics by [`ConstEvaluator`]: `60 * 60` becomes `3600` and
+//!   `"a" + "b"` becomes `"ab"`. Expressions that would raise, such as a
+//!   division by zero, are left alone.
+//! - A local bound exactly once in its function, by a top-level assignment
+//!   of a number or a bool, is replaced by that literal where it is read and
+//!   the assignment is dropped. Folding runs again over the result.
+//! - `if` and conditional expressions with a constant test keep only the
+//!   branch that runs; `while False:` and `assert True` are removed.
+//! - `pass` next to other statements and bare literal statements are
+//!   removed.
+//!
+//! Dropped statements shift the positions of later ones in their block, so
+//! a folded module no longer lines up with
+//! [`HirSpans`](crate::hir_spans::HirSpans).
+
+use crate::const_eval::{ConstEvaluator, ConstValue};
+use crate::hir::{
+    AssignTarget, HirExpr, HirModule, HirParam, HirPattern, HirStmt, Literal, Type, UnaryOp,
+};
+use std::collections::HashMap;
+
+/// Folds every function, method and module constant of `module`
+///
+/// Returns the number of nodes simplified: each folded expression,
+/// propagated read and removed or unwrapped statement counts once.
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir::{HirExpr, HirStmt, Literal};
+/// use depyler_core::optimize::const_fold::fold_module;
+/// use depyler_core::DepylerPipeline;
+///
+/// let source = "def area() -> int:\n    side = 4\n    return side * side\n";
+/// let mut module = DepylerPipeline::new().parse_to_hir(source).unwrap();
+///
+/// assert_eq!(fold_module(&mut module), 4);
+/// assert_eq!(
+///     module.functions[0].body,
+///     vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(16))))]
+/// );
+/// ```
+pub fn fold_module(module: &mut HirModule) -> usize {
+    let mut count = 0;
+    for constant in &mut module.constants {
+        fold_expr(&mut constant.value, &mut count);
+    }
+    for func in &mut module.functions {
+        fold_function(&mut func.body, &func.params, &mut count);
+    }
+    for class in &mut module.classes {
+        for method in &mut class.methods {
+            fold_function(&mut method.body, &method.params, &mut count);
+        }
+    }
+    count
+}
+
+/// Each round of propagation removes an assignment, so this terminates
+fn fold_function(body: &mut Vec<HirStmt>, params: &[HirParam], count: &mut usize) {
+    loop {
+        fold_block(body, count);
+        if !propagate(body, params, count) {
+            break;
+        }
+    }
+}
+
+/// # Complexity
+/// 8 (statement loop + simplified statement forms)
+fn fold_block(body: &mut Vec<HirStmt>, count: &mut usize) {
+    for mut stmt in std::mem::take(body) {
+        for expr in stmt_exprs_mut(&mut stmt) {
+            fold_expr(expr, count);
+        }
+        for block in stmt_blocks_mut(&mut stmt) {
+            fold_block(block, count);
+        }
+
+        match stmt {
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                let mut else_body = else_body.unwrap_or_default();
+                match constant_truth(&condition) {
+                    Some(true) if !contains_yield(&else_body) => {
+                        *count += 1;
+                        body.extend(then_body);
+                    }
+                    Some(false) if !contains_yield(&then_body) => {
+                        *count += 1;
+                        body.append(&mut else_body);
+                    }
+                    _ => body.push(HirStmt::If {
+                        condition,
+                        then_body,
+                        else_body: (!else_body.is_empty()).then_some(else_body),
+                    }),
+                }
+            }
+            HirStmt::While {
+                ref condition,
+                body: ref loop_body,
+            } if constant_truth(condition) == Some(false) && !contains_yield(loop_body) => {
+                *count += 1;
+            }
+            HirStmt::Assert { ref test, .. } if constant_truth(test) == Some(true) => *count += 1,
+            HirStmt::Expr(HirExpr::Literal(_)) => *count += 1,
+            stmt => body.push(stmt),
+        }
+    }
+
+    // `pass` only matters in a block with nothing else in it
+    if body.iter().any(|stmt| !matches!(stmt, HirStmt::Pass)) {
+        let before = body.len();
+        body.retain(|stmt| !matches!(stmt, HirStmt::Pass));
+        *count += before - body.len();
+    }
+}
+
+/// Folds `expr` bottom-up
+fn fold_expr(expr: &mut HirExpr, count: &mut usize) {
+    for child in expr.children_mut() {
+        fold_expr(child, count);
+    }
+
+    let folded = match expr {
+        HirExpr::IfExpr { test, body, orelse } => match constant_truth(test) {
+            Some(true) => Some(std::mem::replace(
+                &mut **body,
+                HirExpr::Literal(Literal::None),
+            )),
+            Some(false) => Some(std::mem::replace(
+                &mut **orelse,
+                HirExpr::Literal(Literal::None),
+            )),
+            None => None,
+        },
+        HirExpr::Binary { .. } | HirExpr::Unary { .. }
+            if !is_negative_number(expr) && expr.children().into_iter().all(is_constant) =>
+        {
+            ConstEvaluator::new().eval(expr).and_then(to_literal)
+        }
+        _ => None,
+    };
+    if let Some(folded) = folded {
+        *expr = folded;
+        *count += 1;
+    }
+}
+
+/// Literals, including the `-n` form negative numbers fold to
+fn is_constant(expr: &HirExpr) -> bool {
+    match expr {
+        HirExpr::Literal(lit) => !matches!(lit, Literal::Bytes(_)),
+        _ => is_negative_number(expr),
+    }
+}
+
+fn is_negative_number(expr: &HirExpr) -> bool {
+    matches!(
+        expr,
+        HirExpr::Unary { op: UnaryOp::Neg, operand }
+            if matches!(**operand, HirExpr::Literal(Literal::Int(_) | Literal::Float(_)))
+    )
+}
+
+fn constant_truth(expr: &HirExpr) -> Option<bool> {
+    if !is_constant(expr) {
+        return None;
+    }
+    ConstEvaluator::new().eval(expr).map(|value| value.truthy())
+}
+
+/// The HIR for a folded scalar; negative numbers are negated literals, as
+/// the parser produces them
+fn to_literal(value: ConstValue) -> Option<HirExpr> {
+    let negate = |literal| HirExpr::Unary {
+        op: UnaryOp::Neg,
+        operand: Box::new(HirExpr::Literal(literal)),
+    };
+    Some(match value {
+        ConstValue::Int(n) if n < 0 => negate(Literal::Int(n.checked_neg()?)),
+        ConstValue::Int(n) => HirExpr::Literal(Literal::Int(n)),
+        ConstValue::Float(f) if !f.is_finite() => return None,
+        ConstValue::Float(f) if f.is_sign_negative() => negate(Literal::Float(-f)),
+        ConstValue::Float(f) => HirExpr::Literal(Literal::Float(f)),
+        ConstValue::Bool(b) => HirExpr::Literal(Literal::Bool(b)),
+        ConstValue::Str(s) => HirExpr::Literal(Literal::String(s)),
+        ConstValue::None => HirExpr::Literal(Literal::None),
+        _ => return None,
+    })
+}
+
+/// Propagates the function's single-assignment numeric and bool locals
+///
+/// Returns whether anything was propagated.
+fn propagate(body: &mut Vec<HirStmt>, params: &[HirParam], count: &mut usize) -> bool {
+    let mut bindings: HashMap<String, usize> = HashMap::new();
+    for param in params {
+        *bindings.entry(param.name.clone()).or_default() += 1;
+    }
+    count_bindings(body, &mut bindings);
+
+    let mut constants = HashMap::new();
+    body.retain(|stmt| match stmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(name),
+            value,
+            type_annotation,
+        } if bindings[name] == 1 && is_propagatable(value, type_annotation.as_ref()) => {
+            constants.insert(name.clone(), value.clone());
+            false
+        }
+        _ => true,
+    });
+    if constants.is_empty() {
+        return false;
+    }
+    *count += constants.len();
+
+    for stmt in body.iter_mut() {
+        substitute_stmt(stmt, &constants, count);
+    }
+    true
+}
+
+/// Numbers and bools whose literal has the annotated type, if any
+///
+/// Strings are not propagated: a `String` local and a string literal are
+/// generated differently at their uses.
+fn is_propagatable(value: &HirExpr, annotation: Option<&Type>) -> bool {
+    let ty = match value {
+        HirExpr::Literal(Literal::Int(_)) => Type::Int,
+        HirExpr::Literal(Literal::Float(_)) => Type::Float,
+        HirExpr::Literal(Literal::Bool(_)) => Type::Bool,
+        HirExpr::Unary { operand, .. } if is_negative_number(value) => match **operand {
+            HirExpr::Literal(Literal::Int(_)) => Type::Int,
+            _ => Type::Float,
+        },
+        _ => return false,
+    };
+    annotation.is_none_or(|annotation| *annotation == ty)
+}
+
+/// Counts the bindings of each name in a function body
+fn count_bindings(body: &mut [HirStmt], bindings: &mut HashMap<String, usize>) {
+    let mut bind = |name: &str| *bindings.entry(name.to_string()).or_default() += 1;
+    for stmt in body.iter_mut() {
+        match stmt {
+            HirStmt::Assign { target, .. } | HirStmt::For { target, .. } => {
+                target_names(target, &mut bind);
+            }
+            HirStmt::With {
+                target: Some(name), ..
+            } => bind(name),
+            HirStmt::Try { handlers, .. } => {
+                for name in handlers.iter().filter_map(|h| h.name.as_deref()) {
+                    bind(name);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                for case in cases.iter() {
+                    case.pattern.bindings().iter().for_each(|name| bind(name));
+                }
+            }
+            _ => {}
+        }
+    }
+    for stmt in body.iter_mut() {
+        for block in stmt_blocks_mut(stmt) {
+            count_bindings(block, bindings);
+        }
+    }
+}
+
+fn target_names(target: &AssignTarget, bind: &mut impl FnMut(&str)) {
+    match target {
+        AssignTarget::Symbol(name) => bind(name),
+        AssignTarget::Tuple(targets) => {
+            for target in targets {
+                target_names(target, bind);
+            }
+        }
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => {}
+    }
+}
+
+fn substitute_stmt(stmt: &mut HirStmt, constants: &HashMap<String, HirExpr>, count: &mut usize) {
+    for expr in stmt_exprs_mut(stmt) {
+        substitute(expr, constants, count);
+    }
+    for block in stmt_blocks_mut(stmt) {
+        for stmt in block.iter_mut() {
+            substitute_stmt(stmt, constants, count);
+        }
+    }
+}
+
+/// Replaces reads of `constants`, except where a lambda or comprehension
+/// binds the same name
+///
+/// # Complexity
+/// 7 (one arm per scope-introducing expression)
+fn substitute(expr: &mut HirExpr, constants: &HashMap<String, HirExpr>, count: &mut usize) {
+    match expr {
+        HirExpr::Var(name) => {
+            if let Some(value) = constants.get(name.as_str()) {
+                *expr = value.clone();
+                *count += 1;
+            }
+        }
+        HirExpr::Lambda { params, body } => substitute(body, &without(constants, params), count),
+        HirExpr::ListComp {
+            element,
+            target,
+            iter,
+            condition,
+        }
+        | HirExpr::SetComp {
+            element,
+            target,
+            iter,
+            condition,
+        } => {
+            // The outermost iterable is evaluated in the enclosing scope
+            substitute(iter, constants, count);
+            let inner = without(constants, std::slice::from_ref(target));
+            substitute(element, &inner, count);
+            if let Some(condition) = condition {
+                substitute(condition, &inner, count);
+            }
+        }
+        HirExpr::DictComp {
+            key,
+            value,
+            target,
+            iter,
+            condition,
+        } => {
+            substitute(iter, constants, count);
+            let inner = without(constants, std::slice::from_ref(target));
+            substitute(key, &inner, count);
+            substitute(value, &inner, count);
+            if let Some(condition) = condition {
+                substitute(condition, &inner, count);
+            }
+        }
+        HirExpr::GeneratorExp {
+            element,
+            generators,
+        } => {
+            let mut bound = Vec::new();
+            for generator in generators.iter_mut() {
+                substitute(&mut generator.iter, &without(constants, &bound), count);
+                bound.push(generator.target.clone());
+                let inner = without(constants, &bound);
+                for condition in &mut generator.conditions {
+                    substitute(condition, &inner, count);
+                }
+            }
+            substitute(element, &without(constants, &bound), count);
+        }
+        HirExpr::SortByKey {
+            iterable,
+            key_params,
+            key_body,
+            ..
+        } => {
+            substitute(iterable, constants, count);
+            substitute(key_body, &without(constants, key_params), count);
+        }
+        _ => {
+            for child in expr.children_mut() {
+                substitute(child, constants, count);
+            }
+        }
+    }
+}
+
+fn without(constants: &HashMap<String, HirExpr>, names: &[String]) -> HashMap<String, HirExpr> {
+    constants
+        .iter()
+        .filter(|(name, _)| !names.contains(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
+
+fn contains_yield(body: &[HirStmt]) -> bool {
+    fn expr_yields(expr: &HirExpr) -> bool {
+        matches!(expr, HirExpr::Yield { .. }) || expr.children().into_iter().any(expr_yields)
+    }
+    let mut body = body.to_vec();
+    body.iter_mut().any(|stmt| {
+        stmt_exprs_mut(stmt).into_iter().any(|e| expr_yields(e))
+            || stmt_blocks_mut(stmt).into_iter().any(|b| contains_yield(b))
+    })
+}
+
+/// Expressions a statement evaluates itself, outside its nested blocks
+///
+/// # Complexity
+/// 9 (one arm per statement kind)
+pub(super) fn stmt_exprs_mut(stmt: &mut HirStmt) -> Vec<&mut HirExpr> {
+    match stmt {
+        HirStmt::Assign { target, value, .. } => {
+            let mut exprs = target_exprs_mut(target);
+            exprs.push(value);
+            exprs
+        }
+        HirStmt::For { target, iter, .. } => {
+            let mut exprs = target_exprs_mut(target);
+            exprs.push(iter);
+            exprs
+        }
+        HirStmt::Return(value) => value.iter_mut().collect(),
+        HirStmt::Expr(expr) => vec![expr],
+        HirStmt::If { condition, .. } | HirStmt::While { condition, .. } => vec![condition],
+        HirStmt::With { context, .. } => vec![context],
+        HirStmt::Raise { exception, cause } => exception.iter_mut().chain(cause).collect(),
+        HirStmt::Assert { test, msg } => std::iter::once(test).chain(msg).collect(),
+        HirStmt::Match { subject, cases } => {
+            let mut exprs = vec![subject];
+            for case in cases {
+                pattern_exprs_mut(&mut case.pattern, &mut exprs);
+                exprs.extend(case.guard.as_mut());
+            }
+            exprs
+        }
+        HirStmt::Try { .. } | HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {
+            Vec::new()
+        }
+    }
+}
+
+/// The subscripts and objects an assignment target reads
+fn target_exprs_mut(target: &mut AssignTarget) -> Vec<&mut HirExpr> {
+    match target {
+        AssignTarget::Symbol(_) => Vec::new(),
+        AssignTarget::Index { base, index } => vec![&mut **base, &mut **index],
+        AssignTarget::Attribute { value, .. } => vec![&mut **value],
+        AssignTarget::Tuple(targets) => targets.iter_mut().flat_map(target_exprs_mut).collect(),
+    }
+}
+
+fn pattern_exprs_mut<'a>(pattern: &'a mut HirPattern, exprs: &mut Vec<&'a mut HirExpr>) {
+    match pattern {
+        HirPattern::Value(expr) => exprs.push(expr),
+        HirPattern::Capture {
+            pattern: Some(pattern),
+            ..
+        } => pattern_exprs_mut(pattern, exprs),
+        HirPattern::Sequence(patterns) | HirPattern::Or(patterns) => {
+            for pattern in patterns {
+                pattern_exprs_mut(pattern, exprs);
+            }
+        }
+        HirPattern::Class { args, kwargs, .. } => {
+            for pattern in args.iter_mut().chain(kwargs.iter_mut().map(|(_, p)| p)) {
+                pattern_exprs_mut(pattern, exprs);
+            }
+        }
+        HirPattern::Wildcard | HirPattern::Literal(_) | HirPattern::Capture { .. } => {}
+    }
+}
+
+/// Blocks nested in a statement
+pub(super) fn stmt_blocks_mut(stmt: &mut HirStmt) -> Vec<&mut Vec<HirStmt>> {
+    match stmt {
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => std::iter::once(then_body).chain(else_body).collect(),
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } | HirStmt::With { body, .. } => {
+            vec![body]
+        }
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => std::iter::once(body)
+            .chain(handlers.iter_mut().map(|h| &mut h.body))
+            .chain(orelse)
+            .chain(finalbody)
+            .collect(),
+        HirStmt::Match { cases, .. } => cases.iter_mut().map(|c| &mut c.body).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    fn fold(source: &str) -> (Vec<HirStmt>, usize) {
+        let mut module = DepylerPipeline::new().parse_to_hir(source).unwrap();
+        let count = fold_module(&mut module);
+        (module.functions.remove(0).body, count)
+    }
+
+    fn int(n: i64) -> HirExpr {
+        HirExpr::Literal(Literal::Int(n))
+    }
+
+    #[test]
+    fn test_division_by_zero_is_not_folded() {
+        let (body, count) = fold("def f() -> int:\n    return 1 // 0\n");
+        assert_eq!(count, 0);
+        assert!(matches!(
+            &body[0],
+            HirStmt::Return(Some(HirExpr::Binary { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_negative_results_stay_negated_literals() {
+        let (body, _) = fold("def f() -> int:\n    return 2 - 5\n");
+        assert_eq!(
+            body[0],
+            HirStmt::Return(Some(HirExpr::Unary {
+                op: UnaryOp::Neg,
+                operand: Box::new(int(3)),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_reassigned_locals_are_kept() {
+        let source = "\
+def f(n: int) -> int:
+    total = 0
+    for i in range(n):
+        total += i
+    return total
+";
+        let (body, count) = fold(source);
+        assert_eq!(count, 0);
+        assert_eq!(body.len(), 3);
+    }
+
+    #[test]
+    fn test_comprehension_variable_shadows_constant() {
+        let source = "\
+def f() -> list:
+    x = 3
+    return [x for x in range(x)]
+";
+        let (body, _) = fold(source);
+        match &body[0] {
+            HirStmt::Return(Some(HirExpr::ListComp { element, iter, .. })) => {
+                assert_eq!(**element, HirExpr::Var("x".to_string()));
+                assert!(matches!(
+                    &**iter,
+                    HirExpr::Call { args, .. } if args[0] == int(3)
+                ));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_constant_branches_are_unwrapped() {
+        let source = "\
+def f(x: int) -> int:
+    DEBUG = False
+    if DEBUG:
+        print(x)
+    else:
+        pass
+        x = x + 1
+    while DEBUG:
+        x = 0
+    return x
+";
+        let (body, count) = fold(source);
+        assert_eq!(body.len(), 2);
+        assert!(matches!(body[0], HirStmt::Assign { .. }));
+        // Propagated read x2, assignment, unwrapped `if`, `pass`, `while`
+        assert_eq!(count, 6);
+    }
+}
@@ -0,0 +1,528 @@
+//! Accumulation loops rewritten into the builtins they spell out
+//!
+//! A variable initialized right before a loop that only accumulates into
+//! it becomes one assignment of a builtin over a generator or a list
+//! comprehension, which code generation turns into an iterator chain:
+//!
+//! | Python                                                    | Rewritten to                  |
+//! |-----------------------------------------------------------|-------------------------------|
+//! | `t = 0`, `for x in xs: if c: t += e`                      | `t = sum(e for x in xs if c)` |
+//! | `out = []`, `for x in xs: if c: out.append(e)`            | `out = [e for x in xs if c]`  |
+//! | `found = False`, `for x in xs: if c: found = True; break` | `found = any(c for x in xs)`  |
+//! | `ok = True`, `for x in xs: if c: ok = False; break`       | `ok = all(not c for x in xs)` |
+//! | `m = xs[0]`, `for x in xs: if x > m: m = x`               | `m = max(xs)`                 |
+//!
+//! The `if` filtering the items of a sum or list is optional. The `break`
+//! of `any` and `all` may be missing when `c` calls and indexes nothing,
+//! since the builtins stop at the first deciding item. `min` is recognized
+//! like `max`, from either comparison order.
+//!
+//! A loop is only rewritten when the result means the same:
+//!
+//! - the accumulator appears nowhere else in the loop,
+//! - the loop variable is read nowhere outside loops over it, as it is no
+//!   longer left bound after the rewrite,
+//! - sums, `any` and `all` iterate a `range` or a `list[int]`,
+//!   `list[float]` or `list[bool]` variable, whose items the generated
+//!   chain copies, and sums add floats into a float and integers under
+//!   the default integer semantics into an integer,
+//! - `min` and `max` search a `list[int]`.
+
+use super::const_fold::{stmt_blocks_mut, stmt_exprs_mut};
+use crate::hir::{
+    AssignTarget, BinOp, HirComprehension, HirExpr, HirModule, HirParam, HirStmt, Literal, Type,
+    UnaryOp,
+};
+use depyler_annotations::IntegerSemantics;
+use std::collections::{HashMap, HashSet};
+
+/// Rewrites the accumulation loops of `module`'s functions and methods,
+/// returning how many
+pub fn rewrite_module(module: &mut HirModule) -> usize {
+    let mut count = 0;
+    for func in &mut module.functions {
+        let semantics = func.annotations.integer_semantics.unwrap_or_default();
+        let scope = FunctionScope::new(&func.body, &func.params, semantics);
+        count += rewrite_block(&mut func.body, &scope);
+    }
+    for class in &mut module.classes {
+        for method in &mut class.methods {
+            let scope = FunctionScope::new(&method.body, &method.params, Default::default());
+            count += rewrite_block(&mut method.body, &scope);
+        }
+    }
+    count
+}
+
+/// Facts about the function a loop is in
+struct FunctionScope {
+    /// Types of the parameters and annotated locals
+    types: HashMap<String, Type>,
+    /// Names read outside every loop over them
+    loose: HashSet<String>,
+    semantics: IntegerSemantics,
+}
+
+impl FunctionScope {
+    fn new(body: &[HirStmt], params: &[HirParam], semantics: IntegerSemantics) -> Self {
+        let mut types: HashMap<_, _> = params
+            .iter()
+            .map(|p| (p.name.clone(), p.ty.clone()))
+            .collect();
+        collect_annotations(body, &mut types);
+        let mut loose = HashSet::new();
+        collect_loose(&mut body.to_vec(), &mut Vec::new(), &mut loose);
+        Self {
+            types,
+            loose,
+            semantics,
+        }
+    }
+
+    /// The type of the items a sum, `any` or `all` may copy out of `iter`
+    fn item_type(&self, iter: &HirExpr) -> Option<Type> {
+        match iter {
+            HirExpr::Call { func, .. } if func == "range" => Some(Type::Int),
+            HirExpr::Var(name) => match self.types.get(name)? {
+                Type::List(elem) if matches!(**elem, Type::Int | Type::Float | Type::Bool) => {
+                    Some((**elem).clone())
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+fn collect_annotations(block: &[HirStmt], types: &mut HashMap<String, Type>) {
+    for stmt in block {
+        if let HirStmt::Assign {
+            target: AssignTarget::Symbol(name),
+            type_annotation: Some(ty),
+            ..
+        } = stmt
+        {
+            types.entry(name.clone()).or_insert_with(|| ty.clone());
+        }
+        for nested in stmt.nested_bodies() {
+            collect_annotations(nested, types);
+        }
+    }
+}
+
+/// Adds the names `block` reads outside loops over them, with `bound` the
+/// variables of the loops around it
+fn collect_loose(block: &mut [HirStmt], bound: &mut Vec<String>, loose: &mut HashSet<String>) {
+    for stmt in block {
+        let loop_var = match stmt {
+            HirStmt::For {
+                target: AssignTarget::Symbol(var),
+                ..
+            } => Some(var.clone()),
+            _ => None,
+        };
+        for expr in stmt_exprs_mut(stmt) {
+            collect_reads(expr, bound, loose);
+        }
+        bound.extend(loop_var.clone());
+        for nested in stmt_blocks_mut(stmt) {
+            collect_loose(nested, bound, loose);
+        }
+        if loop_var.is_some() {
+            bound.pop();
+        }
+    }
+}
+
+fn collect_reads(expr: &HirExpr, bound: &[String], loose: &mut HashSet<String>) {
+    if let HirExpr::Var(name) = expr {
+        if !bound.contains(name) {
+            loose.insert(name.clone());
+        }
+    }
+    for child in expr.children() {
+        collect_reads(child, bound, loose);
+    }
+}
+
+/// Rewrites nested blocks first, then each initialization and the loop
+/// after it
+fn rewrite_block(block: &mut Vec<HirStmt>, scope: &FunctionScope) -> usize {
+    let mut count = 0;
+    for stmt in block.iter_mut() {
+        for nested in stmt_blocks_mut(stmt) {
+            count += rewrite_block(nested, scope);
+        }
+    }
+    let mut index = 0;
+    while index + 1 < block.len() {
+        if let Some(rewritten) = rewrite_pair(&block[index], &block[index + 1], scope) {
+            block[index] = rewritten;
+            block.remove(index + 1);
+            count += 1;
+        }
+        index += 1;
+    }
+    count
+}
+
+/// An accumulation loop, split into its parts
+struct Loop<'a> {
+    acc: &'a str,
+    var: &'a str,
+    iter: &'a HirExpr,
+    /// The condition of the `if` the body is wrapped in
+    filter: Option<&'a HirExpr>,
+    /// The statements run for the items passing the filter
+    update: &'a [HirStmt],
+}
+
+impl Loop<'_> {
+    /// `(element for var in iter if filter)`
+    fn generator(&self, element: HirExpr) -> HirExpr {
+        HirExpr::GeneratorExp {
+            element: Box::new(element),
+            generators: vec![HirComprehension {
+                target: self.var.to_string(),
+                iter: Box::new(self.iter.clone()),
+                conditions: self.filter.into_iter().cloned().collect(),
+            }],
+        }
+    }
+
+    fn filter_mentions_acc(&self) -> bool {
+        self.filter.is_some_and(|filter| mentions(filter, self.acc))
+    }
+}
+
+fn rewrite_pair(init: &HirStmt, next: &HirStmt, scope: &FunctionScope) -> Option<HirStmt> {
+    let HirStmt::Assign {
+        target: AssignTarget::Symbol(acc),
+        value: start,
+        type_annotation,
+    } = init
+    else {
+        return None;
+    };
+    let HirStmt::For {
+        target: AssignTarget::Symbol(var),
+        iter,
+        body,
+    } = next
+    else {
+        return None;
+    };
+    if var == acc || scope.loose.contains(var) || mentions(iter, acc) {
+        return None;
+    }
+    let (filter, update) = match body.as_slice() {
+        [HirStmt::If {
+            condition,
+            then_body,
+            else_body: None,
+        }] => (Some(condition), then_body.as_slice()),
+        _ => (None, body.as_slice()),
+    };
+    let lp = Loop {
+        acc,
+        var,
+        iter,
+        filter,
+        update,
+    };
+    let value = sum_of(&lp, start, type_annotation.as_ref(), scope)
+        .or_else(|| collected(&lp, start))
+        .or_else(|| any_or_all(&lp, start, scope))
+        .or_else(|| extreme(&lp, start, scope))?;
+    Some(HirStmt::Assign {
+        target: AssignTarget::Symbol(acc.clone()),
+        value,
+        type_annotation: type_annotation.clone(),
+    })
+}
+
+/// `t = 0` and `t += e` per item as `sum(e for ...)`
+fn sum_of(
+    lp: &Loop,
+    start: &HirExpr,
+    annotation: Option<&Type>,
+    scope: &FunctionScope,
+) -> Option<HirExpr> {
+    let [HirStmt::Assign {
+        target: AssignTarget::Symbol(target),
+        value:
+            HirExpr::Binary {
+                op: BinOp::Add,
+                left,
+                right,
+            },
+        type_annotation: None,
+    }] = lp.update
+    else {
+        return None;
+    };
+    if target != lp.acc || !is_var(left, lp.acc) || mentions(right, lp.acc) {
+        return None;
+    }
+    if lp.filter_mentions_acc() || scope.semantics == IntegerSemantics::BigInt {
+        return None;
+    }
+    let item = scope.item_type(lp.iter)?;
+    let float_sum = match start {
+        HirExpr::Literal(Literal::Float(f)) if *f == 0.0 => true,
+        HirExpr::Literal(Literal::Int(0)) => annotation == Some(&Type::Float),
+        _ => return None,
+    };
+    let float_item = is_float(right, lp.var, &item, scope);
+    if float_sum != float_item || (!float_sum && scope.semantics != IntegerSemantics::Panic) {
+        return None;
+    }
+    Some(HirExpr::Call {
+        func: "sum".to_string(),
+        args: vec![lp.generator((**right).clone())],
+        kwargs: Vec::new(),
+    })
+}
+
+/// `out = []` and `out.append(e)` per item as `[e for ...]`
+fn collected(lp: &Loop, start: &HirExpr) -> Option<HirExpr> {
+    if !matches!(start, HirExpr::List(items) if items.is_empty()) {
+        return None;
+    }
+    let [HirStmt::Expr(HirExpr::MethodCall {
+        object,
+        method,
+        args,
+        kwargs,
+    })] = lp.update
+    else {
+        return None;
+    };
+    let [element] = args.as_slice() else {
+        return None;
+    };
+    if method != "append" || !is_var(object, lp.acc) || !kwargs.is_empty() {
+        return None;
+    }
+    if mentions(element, lp.acc) || lp.filter_mentions_acc() {
+        return None;
+    }
+    Some(HirExpr::ListComp {
+        element: Box::new(element.clone()),
+        target: lp.var.to_string(),
+        iter: Box::new(lp.iter.clone()),
+        condition: lp.filter.cloned().map(Box::new),
+    })
+}
+
+/// `found = False` and `found = True` for an item passing `c` as
+/// `any(c for ...)`, and the reverse as `all(not c for ...)`
+fn any_or_all(lp: &Loop, start: &HirExpr, scope: &FunctionScope) -> Option<HirExpr> {
+    let HirExpr::Literal(Literal::Bool(initial)) = start else {
+        return None;
+    };
+    let condition = lp.filter?;
+    let (assign, breaks) = match lp.update {
+        [assign] => (assign, false),
+        [assign, HirStmt::Break { label: None }] => (assign, true),
+        _ => return None,
+    };
+    let HirStmt::Assign {
+        target: AssignTarget::Symbol(target),
+        value: HirExpr::Literal(Literal::Bool(found)),
+        type_annotation: None,
+    } = assign
+    else {
+        return None;
+    };
+    if target != lp.acc || found == initial || mentions(condition, lp.acc) {
+        return None;
+    }
+    if (!breaks && !is_inert(condition)) || scope.item_type(lp.iter).is_none() {
+        return None;
+    }
+    let (func, element) = if *found {
+        ("any", condition.clone())
+    } else {
+        ("all", negated(condition))
+    };
+    // The condition is what the builtin tests, not a filter of the items
+    let unfiltered = Loop {
+        filter: None,
+        ..*lp
+    };
+    Some(HirExpr::Call {
+        func: func.to_string(),
+        args: vec![unfiltered.generator(element)],
+        kwargs: Vec::new(),
+    })
+}
+
+/// `m = xs[0]` and `m = x` for an `x` beyond `m` as `max(xs)` or `min(xs)`
+fn extreme(lp: &Loop, start: &HirExpr, scope: &FunctionScope) -> Option<HirExpr> {
+    let HirExpr::Index { base, index } = start else {
+        return None;
+    };
+    let HirExpr::Var(list) = &**base else {
+        return None;
+    };
+    if !matches!(**index, HirExpr::Literal(Literal::Int(0))) || !is_var(lp.iter, list) {
+        return None;
+    }
+    let Some(Type::List(elem)) = scope.types.get(list) else {
+        return None;
+    };
+    if **elem != Type::Int {
+        return None;
+    }
+    let (Some(HirExpr::Binary { op, left, right }), [assign]) = (lp.filter, lp.update) else {
+        return None;
+    };
+    if !matches!(
+        assign,
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(target),
+            value: HirExpr::Var(value),
+            type_annotation: None,
+        } if target == lp.acc && value == lp.var
+    ) {
+        return None;
+    }
+    let item_first = if is_var(left, lp.var) && is_var(right, lp.acc) {
+        true
+    } else if is_var(left, lp.acc) && is_var(right, lp.var) {
+        false
+    } else {
+        return None;
+    };
+    let func = match (op, item_first) {
+        (BinOp::Gt | BinOp::GtEq, true) | (BinOp::Lt | BinOp::LtEq, false) => "max",
+        (BinOp::Lt | BinOp::LtEq, true) | (BinOp::Gt | BinOp::GtEq, false) => "min",
+        _ => return None,
+    };
+    Some(HirExpr::Call {
+        func: func.to_string(),
+        args: vec![HirExpr::Var(list.clone())],
+        kwargs: Vec::new(),
+    })
+}
+
+fn is_var(expr: &HirExpr, name: &str) -> bool {
+    matches!(expr, HirExpr::Var(var) if var == name)
+}
+
+fn mentions(expr: &HirExpr, name: &str) -> bool {
+    is_var(expr, name)
+        || expr
+            .children()
+            .into_iter()
+            .any(|child| mentions(child, name))
+}
+
+/// Whether evaluating `expr` for more items than needed cannot raise or
+/// have effects: it calls and indexes nothing
+fn is_inert(expr: &HirExpr) -> bool {
+    !matches!(
+        expr,
+        HirExpr::Call { .. } | HirExpr::MethodCall { .. } | HirExpr::Index { .. }
+    ) && expr.children().into_iter().all(is_inert)
+}
+
+/// Whether `expr` is a float, with `var` an item of type `item`
+fn is_float(expr: &HirExpr, var: &str, item: &Type, scope: &FunctionScope) -> bool {
+    match expr {
+        HirExpr::Literal(Literal::Float(_)) => true,
+        HirExpr::Var(name) if name == var => *item == Type::Float,
+        HirExpr::Var(name) => scope.types.get(name) == Some(&Type::Float),
+        HirExpr::Call { func, .. } => func == "float",
+        HirExpr::Unary {
+            op: UnaryOp::Neg | UnaryOp::Pos,
+            operand,
+        } => is_float(operand, var, item, scope),
+        HirExpr::Binary {
+            op: BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow,
+            left,
+            right,
+        } => is_float(left, var, item, scope) || is_float(right, var, item, scope),
+        _ => false,
+    }
+}
+
+/// `not expr`, without a double negation
+fn negated(expr: &HirExpr) -> HirExpr {
+    match expr {
+        HirExpr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => (**operand).clone(),
+        _ => HirExpr::Unary {
+            op: UnaryOp::Not,
+            operand: Box::new(expr.clone()),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    fn rewritten(source: &str) -> (usize, Vec<HirStmt>) {
+        let mut module = DepylerPipeline::new().parse_to_hir(source).unwrap();
+        let count = rewrite_module(&mut module);
+        (count, module.functions.remove(0).body)
+    }
+
+    fn call_name(stmt: &HirStmt) -> Option<&str> {
+        match stmt {
+            HirStmt::Assign {
+                value: HirExpr::Call { func, .. },
+                ..
+            } => Some(func),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_filtered_sum_becomes_a_generator() {
+        let (count, body) = rewritten(
+            "def f(xs: list[int]) -> int:\n    total = 0\n    for x in xs:\n        if x > 0:\n            total += x * x\n    return total\n",
+        );
+
+        assert_eq!(count, 1);
+        assert_eq!(body.len(), 2);
+        let HirStmt::Assign {
+            value: HirExpr::Call { func, args, .. },
+            ..
+        } = &body[0]
+        else {
+            panic!("expected a sum, got {:?}", body[0]);
+        };
+        assert_eq!(func, "sum");
+        assert!(matches!(
+            &args[0],
+            HirExpr::GeneratorExp { generators, .. } if generators[0].conditions.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_searches_become_builtins() {
+        let (count, body) = rewritten(
+            "def f(xs: list[int]) -> int:\n    found = False\n    for x in xs:\n        if x < 0:\n            found = True\n            break\n    ok = True\n    for x in xs:\n        if not x % 2 == 0:\n            ok = False\n    m = xs[0]\n    for x in xs:\n        if m > x:\n            m = x\n    return m\n",
+        );
+
+        assert_eq!(count, 3);
+        assert_eq!(call_name(&body[0]), Some("any"));
+        assert_eq!(call_name(&body[1]), Some("all"));
+        assert_eq!(call_name(&body[2]), Some("min"));
+    }
+
+    #[test]
+    fn test_loops_that_differ_from_the_builtin_stay() {
+        let (count, _) = rewritten(
+            "def f(xs: list[int], ws: list[str]) -> int:\n    total = 0\n    for x in xs:\n        total += x\n    print(x)\n    n = 0\n    for w in ws:\n        n += len(w)\n    seen = False\n    for y in xs:\n        if check(y):\n            seen = True\n    return total + n\n",
+        );
+
+        assert_eq!(count, 0);
+    }
+}
@@ -0,0 +1,263 @@
+//! Ordered, individually switchable HIR passes
+//!
+//! [`HirPassManager::standard`] registers the passes
+//! [`DepylerPipeline`](crate::DepylerPipeline) runs between lowering and
+//! code generation, in this order:
+//!
+//! | Pass               | Default | Does                                         |
+//! |--------------------|---------|----------------------------------------------|
+//! | `const-fold`       | off     | [`const_fold`](super::const_fold)            |
+//! | `const-generics`   | on      | infers fixed-size array parameters           |
+//! | `type-hints`       | on      | applies confident parameter and return types |
+//! | `class-fields`     | on      | types fields from what methods store in them |
+//! | `annotations`      | on      | optimizations asked for by `# @depyler:`     |
+//! | `peephole`         | on      | [`peephole`](super::peephole) rewrite rules  |
+//! | `idioms`           | off     | [`idioms`](super::idioms): loops to builtins |
+//! | `dead-stores`      | off     | drops dead stores, marks unused variables    |
+//! | `resource-scoping` | off     | scopes handles opened without `with`         |
+
+use super::{passes, OptimizationMetrics};
+use crate::hir::HirModule;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+/// A transformation of a whole module
+pub trait HirPass {
+    /// Name the pass is toggled and printed by, in kebab case
+    fn name(&self) -> &'static str;
+
+    /// Rewrites `module`, returning the number of nodes simplified
+    ///
+    /// Passes that do not count their rewrites return 0.
+    fn run(&self, module: &mut HirModule) -> Result<usize>;
+}
+
+/// How a pipeline's passes differ from the defaults
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassOptions {
+    /// Passes switched on (`true`) or off (`false`), by name
+    #[serde(default)]
+    pub toggles: BTreeMap<String, bool>,
+    /// Passes after which the module is printed to stderr
+    #[serde(default)]
+    pub print_hir_after: BTreeSet<String>,
+}
+
+/// One pass's run, as recorded in [`OptimizationMetrics::passes`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PassReport {
+    pub name: String,
+    pub duration: Duration,
+    pub nodes_simplified: usize,
+}
+
+struct RegisteredPass {
+    pass: Box<dyn HirPass>,
+    enabled: bool,
+}
+
+/// Runs registered passes in registration order
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::optimize::HirPassManager;
+/// use depyler_core::DepylerPipeline;
+///
+/// let source = "def day() -> int:\n    return 24 * 60 * 60\n";
+/// let mut module = DepylerPipeline::new().parse_to_hir(source).unwrap();
+///
+/// let mut passes = HirPassManager::standard();
+/// passes.set_enabled("const-fold", true).unwrap();
+/// passes.set_enabled("type-hints", false).unwrap();
+/// let metrics = passes.run(&mut module).unwrap();
+///
+/// assert_eq!(metrics.nodes_simplified, 2);
+/// assert_eq!(metrics.passes[0].name, "const-fold");
+/// assert!(metrics.passes.iter().all(|pass| pass.name != "type-hints"));
+/// ```
+#[derive(Default)]
+pub struct HirPassManager {
+    passes: Vec<RegisteredPass>,
+    print_hir_after: BTreeSet<String>,
+}
+
+impl HirPassManager {
+    /// A manager with no passes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The passes of the transpilation pipeline, with their default toggles
+    pub fn standard() -> Self {
+        let mut manager = Self::new();
+        manager.register(passes::ConstFold, false);
+        manager.register(passes::ConstGenerics, true);
+        manager.register(passes::TypeHints::default(), true);
+        manager.register(passes::ClassFields, true);
+        manager.register(passes::Annotations, true);
+        manager.register(passes::IntegerSemantics(Default::default()), true);
+        manager.register(
+            passes::Peephole(super::peephole::PeepholeRewriter::standard()),
+            true,
+        );
+        manager.register(passes::Idioms, false);
+        manager.register(passes::DeadStores, false);
+        manager.register(passes::ResourceScoping, false);
+        manager
+    }
+
+    /// Appends `pass`, replacing a registered pass of the same name in place
+    pub fn register(&mut self, pass: impl HirPass + 'static, enabled: bool) {
+        let registered = RegisteredPass {
+            pass: Box::new(pass),
+            enabled,
+        };
+        match self.position(registered.pass.name()) {
+            Some(index) => self.passes[index] = registered,
+            None => self.passes.push(registered),
+        }
+    }
+
+    /// Names of the registered passes, in order
+    pub fn pass_names(&self) -> Vec<&'static str> {
+        self.passes.iter().map(|p| p.pass.name()).collect()
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.position(name)
+            .is_some_and(|index| self.passes[index].enabled)
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<()> {
+        let index = self.checked_position(name)?;
+        self.passes[index].enabled = enabled;
+        Ok(())
+    }
+
+    /// Prints the module to stderr after `name` runs
+    pub fn print_hir_after(&mut self, name: &str) -> Result<()> {
+        self.checked_position(name)?;
+        self.print_hir_after.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Applies `options` over the current toggles
+    pub fn configure(&mut self, options: &PassOptions) -> Result<()> {
+        for (name, enabled) in &options.toggles {
+            self.set_enabled(name, *enabled)?;
+        }
+        for name in &options.print_hir_after {
+            self.print_hir_after(name)?;
+        }
+        Ok(())
+    }
+
+    /// Runs the enabled passes over `module`, timing each
+    pub fn run(&self, module: &mut HirModule) -> Result<OptimizationMetrics> {
+        let mut metrics = OptimizationMetrics::default();
+        for registered in self.passes.iter().filter(|p| p.enabled) {
+            let name = registered.pass.name();
+            let start = Instant::now();
+            let nodes_simplified = registered.pass.run(module)?;
+            metrics.passes.push(PassReport {
+                name: name.to_string(),
+                duration: start.elapsed(),
+                nodes_simplified,
+            });
+            metrics.nodes_simplified += nodes_simplified;
+
+            if self.print_hir_after.contains(name) {
+                eprintln!("// HIR after `{}`\n{:#?}", name, module);
+            }
+        }
+        Ok(metrics)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.passes.iter().position(|p| p.pass.name() == name)
+    }
+
+    fn checked_position(&self, name: &str) -> Result<usize> {
+        match self.position(name) {
+            Some(index) => Ok(index),
+            None => bail!(
+                "unknown HIR pass `{}`; passes are: {}",
+                name,
+                self.pass_names().join(", ")
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Count(&'static str, usize);
+
+    impl HirPass for Count {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn run(&self, _module: &mut HirModule) -> Result<usize> {
+            Ok(self.1)
+        }
+    }
+
+    fn empty_module() -> HirModule {
+        HirModule {
+            functions: vec![],
+            imports: vec![],
+            type_aliases: vec![],
+            protocols: vec![],
+            classes: vec![],
+            constants: vec![],
+        }
+    }
+
+    #[test]
+    fn test_enabled_passes_run_in_order() {
+        let mut manager = HirPassManager::new();
+        manager.register(Count("first", 1), true);
+        manager.register(Count("skipped", 10), false);
+        manager.register(Count("second", 2), true);
+
+        let metrics = manager.run(&mut empty_module()).unwrap();
+        let names: Vec<_> = metrics.passes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(metrics.nodes_simplified, 3);
+    }
+
+    #[test]
+    fn test_registering_a_name_again_replaces_the_pass() {
+        let mut manager = HirPassManager::new();
+        manager.register(Count("a", 1), true);
+        manager.register(Count("b", 1), true);
+        manager.register(Count("a", 5), false);
+
+        assert_eq!(manager.pass_names(), ["a", "b"]);
+        assert!(!manager.is_enabled("a"));
+    }
+
+    #[test]
+    fn test_unknown_pass_names_are_rejected() {
+        let mut manager = HirPassManager::standard();
+        let err = manager.set_enabled("inline", true).unwrap_err();
+        assert!(err.to_string().contains("const-fold, const-generics"));
+
+        let options = PassOptions {
+            print_hir_after: ["fold".to_string()].into(),
+            ..PassOptions::default()
+        };
+        assert!(manager.configure(&options).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! The stages [`HirPassManager::standard`](super::HirPassManager::standard)
+//! registers
+
+use super::{const_fold, idioms, peephole, HirPass};
+use crate::analysis_cache::{AnalysisCache, FunctionAnalysis};
+use crate::ast_bridge::FunctionAnalyzer;
+use crate::hir::HirModule;
+use crate::{
+    const_generic_inference, dataflow, field_inference, optimization, resource_analysis, type_hints,
+};
+use anyhow::Result;
+use std::sync::Arc;
+
+pub struct ConstFold;
+
+impl HirPass for ConstFold {
+    fn name(&self) -> &'static str {
+        "const-fold"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        Ok(const_fold::fold_module(module))
+    }
+}
+
+/// Fixed-size arrays for lists of literal length
+pub struct ConstGenerics;
+
+impl HirPass for ConstGenerics {
+    fn name(&self) -> &'static str {
+        "const-generics"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        const_generic_inference::ConstGenericInferencer::new().analyze_module(module)?;
+        Ok(0)
+    }
+}
+
+/// Types for unannotated parameters and returns, where the hint is
+/// confident, and the mutated parameters and complexity of each function
+/// for the stages after
+///
+/// With a cache, functions unchanged since an earlier run are not analyzed
+/// again.
+#[derive(Default)]
+pub struct TypeHints(pub Option<Arc<AnalysisCache>>);
+
+impl HirPass for TypeHints {
+    fn name(&self) -> &'static str {
+        "type-hints"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        for func in &mut module.functions {
+            let analysis = match &self.0 {
+                Some(cache) => cache.analysis(func),
+                None => FunctionAnalysis::analyze(func),
+            };
+            if !analysis.hints.is_empty() {
+                let type_hint_provider = type_hints::TypeHintProvider::new();
+                eprintln!("Type inference hints:");
+                eprintln!("{}", type_hint_provider.format_hints(&analysis.hints));
+            }
+
+            // Apply parameter type hints
+            for (name, ty) in analysis.param_types {
+                if let Some(param) = func.params.iter_mut().find(|param| param.name == name) {
+                    param.ty = ty;
+                    eprintln!("Applied type hint: {} -> {:?}", param.name, param.ty);
+                }
+            }
+
+            // Apply return type hints
+            if let Some(ret_type) = analysis.ret_type {
+                func.ret_type = ret_type;
+                eprintln!("Applied return type hint: {:?}", func.ret_type);
+            }
+
+            func.properties.mutated_params = Some(analysis.mutated_params);
+            func.properties.complexity = Some(analysis.complexity);
+        }
+        Ok(0)
+    }
+}
+
+/// Element and value types for class fields the methods fill in
+pub struct ClassFields;
+
+impl HirPass for ClassFields {
+    fn name(&self) -> &'static str {
+        "class-fields"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        field_inference::FieldTypeTable::analyze(&module.classes).apply(&mut module.classes);
+        Ok(0)
+    }
+}
+
+/// Optimizations requested by `# @depyler:` annotations
+pub struct Annotations;
+
+impl HirPass for Annotations {
+    fn name(&self) -> &'static str {
+        "annotations"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        optimization::optimize_module(module);
+        Ok(0)
+    }
+}
+
+/// The overflow behavior of each function's `int` arithmetic: its
+/// `integer_semantics` annotation, else the module's default
+pub struct IntegerSemantics(pub depyler_annotations::IntegerSemantics);
+
+impl HirPass for IntegerSemantics {
+    fn name(&self) -> &'static str {
+        "integer-semantics"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        for func in &mut module.functions {
+            FunctionAnalyzer::apply_integer_semantics(func, self.0);
+        }
+        Ok(0)
+    }
+}
+
+/// Idiomatic forms of single statements, by rewrite rule
+pub struct Peephole(pub peephole::PeepholeRewriter);
+
+impl HirPass for Peephole {
+    fn name(&self) -> &'static str {
+        "peephole"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        self.0.rewrite_module(module)
+    }
+}
+
+/// Accumulation loops as `sum`, `any`, `all`, `min`, `max` and list
+/// comprehensions
+pub struct Idioms;
+
+impl HirPass for Idioms {
+    fn name(&self) -> &'static str {
+        "idioms"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        Ok(idioms::rewrite_module(module))
+    }
+}
+
+/// Dead stores of side-effect-free values dropped, and variables nothing
+/// reads renamed with a leading `_`
+pub struct DeadStores;
+
+impl HirPass for DeadStores {
+    fn name(&self) -> &'static str {
+        "dead-stores"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        let effect_free = dataflow::effect_free_functions(&module.functions);
+        let methods = module
+            .classes
+            .iter_mut()
+            .flat_map(|class| &mut class.methods);
+        let mut simplified: usize = methods
+            .map(|method| dataflow::clean_up_unused(&mut method.body, &effect_free))
+            .sum();
+        for func in &mut module.functions {
+            simplified += dataflow::clean_up_unused(&mut func.body, &effect_free);
+        }
+        Ok(simplified)
+    }
+}
+
+/// `f = open(...); ...; f.close()` rewritten into explicit `with` scopes
+pub struct ResourceScoping;
+
+impl HirPass for ResourceScoping {
+    fn name(&self) -> &'static str {
+        "resource-scoping"
+    }
+
+    fn run(&self, module: &mut HirModule) -> Result<usize> {
+        let scoper = resource_analysis::ResourceLeakAnalyzer::new(
+            resource_analysis::ResourceLeakConfig::default(),
+        );
+        for func in &mut module.functions {
+            scoper.insert_explicit_scopes(func);
+        }
+        Ok(0)
+    }
+}
@@ -0,0 +1,586 @@
+//! Pattern-based rewrites of single statements into idiomatic forms
+//!
+//! A [`RewriteRule`] is written as a Python pattern and replacement, in
+//! which `$name` is a metavariable:
+//!
+//! - in an expression or as an assignment target, `$x` matches any
+//!   expression, and every other `$x` of the pattern must match the same one;
+//! - a block made of `$body` alone matches any block.
+//!
+//! The replacement is built from what the pattern bound, once every guard
+//! of the rule holds. [`PeepholeRewriter::standard`] ships these rules:
+//!
+//! | Rule                   | Python                            | Becomes                      |
+//! |------------------------|-----------------------------------|------------------------------|
+//! | `concat-to-append`     | `x = x + [y]`                     | `x.append(y)`, a `push`      |
+//! | `range-len-enumerate`  | `for i in range(len(xs)):`        | `for i, _ in enumerate(xs):` |
+//!
+//! `concat-to-append` leaves parameters alone, since appending would change
+//! the caller's list. `range-len-enumerate` only applies to list parameters
+//! the function never mutates, which the generated code can iterate by
+//! reference while the body still indexes them.
+//!
+//! Rules are matched against the serde form of the HIR, so a pattern matches
+//! exactly the statements that lower to the same HIR as it does.
+
+use super::const_fold::{stmt_blocks_mut, stmt_exprs_mut};
+use crate::ast_bridge::StmtConverter;
+use crate::hir::{AssignTarget, HirExpr, HirModule, HirParam, HirStmt, Type};
+use anyhow::{bail, Context, Result};
+use rustpython_ast::{self as ast};
+use rustpython_parser::{parse, Mode};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+/// What `$` metavariables are renamed to, so that patterns parse as Python
+const METAVARIABLE_PREFIX: &str = "__rewrite_";
+
+/// A condition on the bindings of a match
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Guard {
+    /// `$var` is a name that is not rebound, mutated through a method or
+    /// passed to a call other than `len` in the block bound to `$block`
+    NotMutatedIn { var: String, block: String },
+    /// `$var` is a `List` parameter that the function never mutates
+    ReadOnlyListParam(String),
+    /// `$var` is a name that is not a parameter of the function
+    NotParam(String),
+}
+
+/// A statement pattern and its replacement
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    name: String,
+    kind: std::mem::Discriminant<HirStmt>,
+    pattern: Value,
+    replacement: Value,
+    guards: Vec<Guard>,
+}
+
+impl RewriteRule {
+    /// Parses `pattern` and `replacement`, each a single Python statement
+    ///
+    /// The statements may be indented as a whole, as they are in a raw
+    /// string literal. Every metavariable of the replacement must occur in
+    /// the pattern.
+    pub fn parse(name: &str, pattern: &str, replacement: &str) -> Result<Self> {
+        let pattern_stmt =
+            parse_statement(pattern).with_context(|| format!("rewrite rule `{}`", name))?;
+        let replacement_stmt =
+            parse_statement(replacement).with_context(|| format!("rewrite rule `{}`", name))?;
+        let pattern = serde_json::to_value(&pattern_stmt)?;
+        let replacement = serde_json::to_value(&replacement_stmt)?;
+
+        let bound = metavariables(&pattern);
+        if let Some(unbound) = metavariables(&replacement).difference(&bound).next() {
+            bail!(
+                "rewrite rule `{}`: the replacement uses `${}`, which the pattern does not bind",
+                name,
+                unbound
+            );
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            kind: std::mem::discriminant(&pattern_stmt),
+            pattern,
+            replacement,
+            guards: Vec::new(),
+        })
+    }
+
+    /// Only rewrites matches for which `guard` holds
+    pub fn with_guard(mut self, guard: Guard) -> Self {
+        self.guards.push(guard);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The replacement of `stmt`, if it matches and the guards hold
+    fn rewrite(&self, stmt: &HirStmt, scope: &FunctionScope) -> Result<Option<HirStmt>> {
+        if std::mem::discriminant(stmt) != self.kind {
+            return Ok(None);
+        }
+        let subject = serde_json::to_value(stmt)?;
+        let mut bindings = Bindings::new();
+        if !unify(&self.pattern, &subject, &mut bindings) {
+            return Ok(None);
+        }
+        for guard in &self.guards {
+            if !guard_holds(guard, &bindings, scope)? {
+                return Ok(None);
+            }
+        }
+        let replaced = substitute(&self.replacement, &bindings);
+        Ok(Some(serde_json::from_value(replaced).with_context(
+            || format!("rewrite rule `{}` built an invalid statement", self.name),
+        )?))
+    }
+}
+
+/// Applies rewrite rules to every statement of a module
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir::{HirExpr, HirStmt};
+/// use depyler_core::optimize::peephole::{PeepholeRewriter, RewriteRule};
+/// use depyler_core::DepylerPipeline;
+///
+/// let source = "def tail(xs: list) -> int:\n    return xs[len(xs) - 1]\n";
+/// let mut module = DepylerPipeline::new().parse_to_hir(source).unwrap();
+///
+/// let mut rewriter = PeepholeRewriter::new();
+/// rewriter.add_rule(
+///     RewriteRule::parse("last", "return $x[len($x) - 1]", "return $x[-1]").unwrap(),
+/// );
+/// assert_eq!(rewriter.rewrite_module(&mut module).unwrap(), 1);
+/// assert!(matches!(
+///     &module.functions[0].body[0],
+///     HirStmt::Return(Some(HirExpr::Index { .. }))
+/// ));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PeepholeRewriter {
+    rules: Vec<RewriteRule>,
+}
+
+impl PeepholeRewriter {
+    /// A rewriter with no rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The default idiom rules
+    pub fn standard() -> Self {
+        let mut rewriter = Self::new();
+        rewriter.add_rule(
+            RewriteRule::parse("concat-to-append", "$x = $x + [$y]", "$x.append($y)")
+                .expect("standard rule parses")
+                .with_guard(Guard::NotParam("x".to_string())),
+        );
+        rewriter.add_rule(
+            RewriteRule::parse(
+                "range-len-enumerate",
+                "for $i in range(len($xs)):\n    $body",
+                "for $i, _ in enumerate($xs):\n    $body",
+            )
+            .expect("standard rule parses")
+            .with_guard(Guard::ReadOnlyListParam("xs".to_string())),
+        );
+        rewriter
+    }
+
+    /// Adds `rule` after the existing ones; the first rule matching a
+    /// statement rewrites it
+    pub fn add_rule(&mut self, rule: RewriteRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn rules(&self) -> &[RewriteRule] {
+        &self.rules
+    }
+
+    /// Rewrites the functions and methods of `module`, returning the number
+    /// of statements rewritten
+    pub fn rewrite_module(&self, module: &mut HirModule) -> Result<usize> {
+        let mut count = 0;
+        for func in &mut module.functions {
+            count += self.rewrite_function(&mut func.body, &func.params)?;
+        }
+        for class in &mut module.classes {
+            for method in &mut class.methods {
+                count += self.rewrite_function(&mut method.body, &method.params)?;
+            }
+        }
+        Ok(count)
+    }
+
+    fn rewrite_function(&self, body: &mut [HirStmt], params: &[HirParam]) -> Result<usize> {
+        if self.rules.is_empty() {
+            return Ok(0);
+        }
+        let scope = FunctionScope::new(body, params);
+        let mut count = 0;
+        self.rewrite_block(body, &scope, &mut count)?;
+        Ok(count)
+    }
+
+    /// Rewrites nested blocks first, so a replacement holds rewritten blocks
+    fn rewrite_block(
+        &self,
+        block: &mut [HirStmt],
+        scope: &FunctionScope,
+        count: &mut usize,
+    ) -> Result<()> {
+        for stmt in block {
+            for nested in stmt_blocks_mut(stmt) {
+                self.rewrite_block(nested, scope, count)?;
+            }
+            for rule in &self.rules {
+                if let Some(replacement) = rule.rewrite(stmt, scope)? {
+                    *stmt = replacement;
+                    *count += 1;
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Facts about the function a statement is in, for guards
+struct FunctionScope {
+    params: HashSet<String>,
+    read_only_list_params: HashSet<String>,
+}
+
+impl FunctionScope {
+    fn new(body: &[HirStmt], params: &[HirParam]) -> Self {
+        let mut body = body.to_vec();
+        let read_only_list_params = params
+            .iter()
+            .filter(|p| matches!(p.ty, Type::List(_)) && !mutates(&mut body, &p.name))
+            .map(|p| p.name.clone())
+            .collect();
+        Self {
+            params: params.iter().map(|p| p.name.clone()).collect(),
+            read_only_list_params,
+        }
+    }
+}
+
+/// Metavariable bindings, as serde values of HIR expressions and blocks
+type Bindings = BTreeMap<String, Value>;
+
+/// Matches `subject` against `pattern`, extending `bindings`
+fn unify(pattern: &Value, subject: &Value, bindings: &mut Bindings) -> bool {
+    if let Some((name, form)) = metavariable(pattern) {
+        let bound = match form.to_expr(subject) {
+            Some(bound) => bound,
+            None => return false,
+        };
+        return match bindings.get(name) {
+            Some(previous) => *previous == bound,
+            None => {
+                bindings.insert(name.to_string(), bound);
+                true
+            }
+        };
+    }
+    match (pattern, subject) {
+        (Value::Object(p), Value::Object(s)) => {
+            p.len() == s.len()
+                && p.iter()
+                    .all(|(key, p)| s.get(key).is_some_and(|s| unify(p, s, bindings)))
+        }
+        (Value::Array(p), Value::Array(s)) => {
+            p.len() == s.len() && p.iter().zip(s).all(|(p, s)| unify(p, s, bindings))
+        }
+        _ => pattern == subject,
+    }
+}
+
+/// `replacement` with its metavariables replaced by their bindings
+fn substitute(replacement: &Value, bindings: &Bindings) -> Value {
+    if let Some((name, form)) = metavariable(replacement) {
+        if let Some(bound) = bindings.get(name).and_then(|bound| form.from_expr(bound)) {
+            return bound;
+        }
+    }
+    match replacement {
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), substitute(value, bindings)))
+                .collect(),
+        ),
+        Value::Array(items) => {
+            Value::Array(items.iter().map(|v| substitute(v, bindings)).collect())
+        }
+        _ => replacement.clone(),
+    }
+}
+
+/// Where a metavariable stands in a pattern
+#[derive(Clone, Copy)]
+enum Form {
+    /// `HirExpr::Var`
+    Expr,
+    /// `AssignTarget::Symbol`, bound as the expression the target reads
+    Target,
+    /// A block of the single statement `HirStmt::Expr(HirExpr::Var)`
+    Block,
+}
+
+impl Form {
+    fn to_expr(self, subject: &Value) -> Option<Value> {
+        match self {
+            Form::Expr | Form::Block => Some(subject.clone()),
+            Form::Target => match single_field(subject) {
+                Some(("Symbol", name)) => Some(tagged("Var", name.clone())),
+                Some(("Index" | "Attribute", _)) => Some(subject.clone()),
+                _ => None,
+            },
+        }
+    }
+
+    fn from_expr(self, bound: &Value) -> Option<Value> {
+        match self {
+            Form::Expr | Form::Block => Some(bound.clone()),
+            Form::Target => match single_field(bound) {
+                Some(("Var", name)) => Some(tagged("Symbol", name.clone())),
+                Some(("Index" | "Attribute", _)) => Some(bound.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// The name and form of the metavariable `value` is, if it is one
+fn metavariable(value: &Value) -> Option<(&str, Form)> {
+    let (form, name) = match value {
+        Value::Array(block) if block.len() == 1 => match single_field(&block[0]) {
+            Some(("Expr", expr)) => match single_field(expr) {
+                Some(("Var", name)) => (Form::Block, name),
+                _ => return None,
+            },
+            _ => return None,
+        },
+        _ => match single_field(value) {
+            Some(("Var", name)) => (Form::Expr, name),
+            Some(("Symbol", name)) => (Form::Target, name),
+            _ => return None,
+        },
+    };
+    name.as_str()?
+        .strip_prefix(METAVARIABLE_PREFIX)
+        .map(|name| (name, form))
+}
+
+/// Every metavariable in `value`
+fn metavariables(value: &Value) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    collect_metavariables(value, &mut names);
+    names
+}
+
+fn collect_metavariables(value: &Value, names: &mut BTreeSet<String>) {
+    if let Some((name, _)) = metavariable(value) {
+        names.insert(name.to_string());
+        return;
+    }
+    match value {
+        Value::Object(fields) => fields
+            .values()
+            .for_each(|v| collect_metavariables(v, names)),
+        Value::Array(items) => items.iter().for_each(|v| collect_metavariables(v, names)),
+        _ => {}
+    }
+}
+
+/// The tag and content of an externally tagged enum value
+fn single_field(value: &Value) -> Option<(&str, &Value)> {
+    match value {
+        Value::Object(fields) if fields.len() == 1 => {
+            fields.iter().next().map(|(k, v)| (k.as_str(), v))
+        }
+        _ => None,
+    }
+}
+
+fn tagged(tag: &str, content: Value) -> Value {
+    let mut fields = Map::new();
+    fields.insert(tag.to_string(), content);
+    Value::Object(fields)
+}
+
+fn guard_holds(guard: &Guard, bindings: &Bindings, scope: &FunctionScope) -> Result<bool> {
+    let bound_name = |var: &str| -> Result<Option<String>> {
+        let bound = match bindings.get(var) {
+            Some(bound) => bound,
+            None => bail!("guard on `${}`, which the pattern does not bind", var),
+        };
+        Ok(match single_field(bound) {
+            Some(("Var", Value::String(name))) => Some(name.clone()),
+            _ => None,
+        })
+    };
+
+    match guard {
+        Guard::NotMutatedIn { var, block } => {
+            let name = match bound_name(var)? {
+                Some(name) => name,
+                None => return Ok(false),
+            };
+            let mut block: Vec<HirStmt> = match bindings.get(block) {
+                Some(bound) => serde_json::from_value(bound.clone())?,
+                None => bail!("guard on `${}`, which the pattern does not bind", block),
+            };
+            Ok(!mutates(&mut block, &name))
+        }
+        Guard::ReadOnlyListParam(var) => {
+            Ok(bound_name(var)?.is_some_and(|name| scope.read_only_list_params.contains(&name)))
+        }
+        Guard::NotParam(var) => {
+            Ok(bound_name(var)?.is_some_and(|name| !scope.params.contains(&name)))
+        }
+    }
+}
+
+/// Whether `block` rebinds `name`, calls a method on it or passes it to a
+/// call other than `len`
+fn mutates(block: &mut [HirStmt], name: &str) -> bool {
+    block.iter_mut().any(|stmt| {
+        let rebinds = match stmt {
+            HirStmt::Assign { target, .. } | HirStmt::For { target, .. } => {
+                target_root_is(target, name)
+            }
+            HirStmt::With {
+                target: Some(target),
+                ..
+            } => target == name,
+            _ => false,
+        };
+        rebinds
+            || stmt_exprs_mut(stmt)
+                .into_iter()
+                .any(|expr| expr_mutates(expr, name))
+            || stmt_blocks_mut(stmt)
+                .into_iter()
+                .any(|block| mutates(block, name))
+    })
+}
+
+fn target_root_is(target: &AssignTarget, name: &str) -> bool {
+    match target {
+        AssignTarget::Symbol(symbol) => symbol == name,
+        AssignTarget::Index { base, .. } => root_is(base, name),
+        AssignTarget::Attribute { value, .. } => root_is(value, name),
+        AssignTarget::Tuple(targets) => targets.iter().any(|t| target_root_is(t, name)),
+    }
+}
+
+/// Whether `expr` is `name` or an index or attribute of it
+fn root_is(expr: &HirExpr, name: &str) -> bool {
+    match expr {
+        HirExpr::Var(var) => var == name,
+        HirExpr::Index { base, .. } => root_is(base, name),
+        HirExpr::Attribute { value, .. } => root_is(value, name),
+        _ => false,
+    }
+}
+
+fn expr_mutates(expr: &HirExpr, name: &str) -> bool {
+    let mutates_here = match expr {
+        HirExpr::MethodCall { object, .. } => root_is(object, name),
+        HirExpr::Call { func, args, .. } => {
+            func != "len" && args.iter().any(|arg| root_is(arg, name))
+        }
+        _ => false,
+    };
+    mutates_here
+        || expr
+            .children()
+            .into_iter()
+            .any(|child| expr_mutates(child, name))
+}
+
+/// The HIR of a single Python statement, with `$` metavariables renamed
+fn parse_statement(source: &str) -> Result<HirStmt> {
+    let source = dedent(&source.replace('$', METAVARIABLE_PREFIX));
+    let mut body = match parse(&source, Mode::Module, "<rewrite rule>")? {
+        ast::Mod::Module(module) => module.body,
+        _ => bail!("expected a statement"),
+    };
+    if body.len() != 1 {
+        bail!("expected one statement, found {}", body.len());
+    }
+    StmtConverter::convert(body.remove(0))
+}
+
+/// `source` without the indentation all its lines share
+fn dedent(source: &str) -> String {
+    let lines: Vec<&str> = source
+        .lines()
+        .skip_while(|line| line.trim().is_empty())
+        .collect();
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::Literal;
+
+    fn body_of(source: &str) -> Vec<HirStmt> {
+        let ast = parse(source, Mode::Module, "<test>").unwrap();
+        crate::ast_bridge::python_to_hir(ast).unwrap().functions[0]
+            .body
+            .clone()
+    }
+
+    #[test]
+    fn test_repeated_metavariables_must_match_the_same_expression() {
+        let rule = RewriteRule::parse("concat", "$x = $x + [$y]", "$x.append($y)").unwrap();
+        let scope = FunctionScope::new(&[], &[]);
+
+        let same = body_of("def f(a: list):\n    a = a + [1]\n");
+        let rewritten = rule.rewrite(&same[0], &scope).unwrap().unwrap();
+        assert_eq!(
+            rewritten,
+            HirStmt::Expr(HirExpr::MethodCall {
+                object: Box::new(HirExpr::Var("a".to_string())),
+                method: "append".to_string(),
+                args: vec![HirExpr::Literal(Literal::Int(1))],
+                kwargs: vec![],
+            })
+        );
+
+        let different = body_of("def f(a: list, b: list):\n    a = b + [1]\n");
+        assert!(rule.rewrite(&different[0], &scope).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unbound_replacement_metavariables_are_rejected() {
+        let err = RewriteRule::parse("bad", "$x = $y", "$x = $z").unwrap_err();
+        assert!(err.to_string().contains("`$z`"));
+    }
+
+    #[test]
+    fn test_not_mutated_in_guard() {
+        let rule = RewriteRule::parse("while-loop", "while $x:\n    $body", "while $x:\n    $body")
+            .unwrap()
+            .with_guard(Guard::NotMutatedIn {
+                var: "x".to_string(),
+                block: "body".to_string(),
+            });
+        let scope = FunctionScope::new(&[], &[]);
+
+        let reads = body_of("def f(xs: list):\n    while xs:\n        print(len(xs))\n");
+        assert!(rule.rewrite(&reads[0], &scope).unwrap().is_some());
+        let pops = body_of("def f(xs: list):\n    while xs:\n        xs.pop()\n");
+        assert!(rule.rewrite(&pops[0], &scope).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_dedent_keeps_relative_indentation() {
+        assert_eq!(
+            dedent("\n        for x in y:\n            pass\n"),
+            "for x in y:\n    pass"
+        );
+    }
+}
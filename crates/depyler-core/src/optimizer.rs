@@ -166,6 +166,17 @@ impl Optimizer {
             HirStmt::Return(Some(expr)) => {
                 self.collect_read_vars_expr(expr, read_vars);
             }
+            HirStmt::Match { subject, cases } => {
+                self.collect_read_vars_expr(subject, read_vars);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.collect_read_vars_expr(guard, read_vars);
+                    }
+                    for s in &case.body {
+                        self.collect_read_vars_stmt(s, read_vars);
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -244,6 +255,11 @@ impl Optimizer {
             HirStmt::While { body, .. } | HirStmt::For { body, .. } => {
                 self.count_assignments_stmt(body, assignments);
             }
+            HirStmt::Match { cases, .. } => {
+                for case in cases {
+                    self.count_assignments_stmt(&case.body, assignments);
+                }
+            }
             _ => {}
         }
     }
@@ -590,6 +606,17 @@ impl Optimizer {
             HirStmt::Expr(expr) => {
                 self.collect_used_vars_expr(expr, used);
             }
+            HirStmt::Match { subject, cases } => {
+                self.collect_used_vars_expr(subject, used);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.collect_used_vars_expr(guard, used);
+                    }
+                    for s in &case.body {
+                        self.collect_truly_used_vars_stmt(s, used);
+                    }
+                }
+            }
             _ => {}
         }
     }
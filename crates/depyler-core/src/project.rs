@@ -0,0 +1,486 @@
+//! Transpiling the modules of a Python project together
+//!
+//! [`DepylerPipeline::transpile_project`](crate::DepylerPipeline::transpile_project)
+//! collects the `.py` files of the given directories and files, parses them
+//! in parallel and orders them by their imports of each other. Modules are
+//! then transpiled a layer at a time: every module of a layer only imports
+//! modules of earlier layers, so the modules of a layer are transpiled in
+//! parallel. Modules importing each other in a cycle share the last layer.
+//!
+//! A module is named after its path below the directory it was found in,
+//! so `src/shop/cart.py` found in `src` is `shop.cart` and
+//! `src/shop/__init__.py` is `shop`. Files given directly are named after
+//! their stem.
+//!
+//! Each module is generated as the Rust module of the same path, such as
+//! `crate::shop::cart`. Imports between modules become `use` items of those
+//! paths, and calls into other modules follow the signatures of the callees
+//! as calls within a module do. [`ProjectReport::crate_files`] lays the
+//! modules out as the files of one crate.
+
+use crate::cargo_toml_gen::{self, Dependency};
+use crate::hir::{HirModule, ImportItem};
+use crate::keep_regions::{merge_keep_regions, KeepConflict};
+use crate::module_mapper::ProjectModule;
+use crate::DepylerPipeline;
+use anyhow::{bail, Context, Result};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(not(feature = "wasm"))]
+use std::time::Instant;
+#[cfg(feature = "wasm")]
+use web_time::Instant;
+
+/// Timings and sizes of transpiling one module, or their sums over several
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleMetrics {
+    pub parse_time: Duration,
+    pub transpile_time: Duration,
+    pub source_size_bytes: usize,
+    pub output_size_bytes: usize,
+    /// Module functions and class methods
+    pub functions_transpiled: usize,
+    pub nodes_simplified: usize,
+}
+
+impl ModuleMetrics {
+    /// Adds the figures of `other` to these
+    ///
+    /// Times add up as if the modules were transpiled one after the other.
+    pub fn merge(&mut self, other: &ModuleMetrics) {
+        self.parse_time += other.parse_time;
+        self.transpile_time += other.transpile_time;
+        self.source_size_bytes += other.source_size_bytes;
+        self.output_size_bytes += other.output_size_bytes;
+        self.functions_transpiled += other.functions_transpiled;
+        self.nodes_simplified += other.nodes_simplified;
+    }
+}
+
+/// One module of a transpiled project
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranspiledModule {
+    /// Dotted module name, such as `shop.cart`
+    pub name: String,
+    pub path: PathBuf,
+    pub rust_code: String,
+    /// Project modules this one imports
+    pub dependencies: Vec<String>,
+    pub metrics: ModuleMetrics,
+}
+
+/// Everything [`DepylerPipeline::transpile_project`] produced
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectReport {
+    /// The modules, sorted by name
+    pub modules: Vec<TranspiledModule>,
+    /// Module names, in the layers they were transpiled in
+    pub layers: Vec<Vec<String>>,
+    /// The metrics of all modules, merged
+    pub totals: ModuleMetrics,
+    /// Time the whole project took, which parallelism keeps below the
+    /// merged times
+    pub wall_time: Duration,
+}
+
+impl ProjectReport {
+    pub fn module(&self, name: &str) -> Option<&TranspiledModule> {
+        self.modules.iter().find(|module| module.name == name)
+    }
+
+    /// The files of a library crate named `package` holding the modules,
+    /// keyed by path relative to the crate root
+    ///
+    /// `shop.cart` goes to `src/shop/cart.rs`. A module with submodules goes
+    /// to `mod.rs` in the directory of its submodules and declares them;
+    /// packages without an `__init__.py` get a `mod.rs` declaring only them.
+    /// Top-level `main` and `lib` modules go to `main_module.rs` and
+    /// `lib_module.rs`, which Cargo does not take for crate roots.
+    /// `Cargo.toml` lists the crates any module uses.
+    pub fn crate_files(&self, package: &str) -> Result<BTreeMap<PathBuf, String>> {
+        let mut paths: BTreeSet<&str> = BTreeSet::new();
+        for module in &self.modules {
+            let name = module.name.as_str();
+            paths.insert(name);
+            paths.extend(name.match_indices('.').map(|(dot, _)| &name[..dot]));
+        }
+        let is_package = |path: &str| {
+            paths
+                .iter()
+                .any(|other| other.rsplit_once('.').is_some_and(|(p, _)| p == path))
+        };
+        let is_crate_root = |path: &str| matches!(path, "main" | "lib") && !is_package(path);
+        let declarations = |parent: Option<&str>| -> String {
+            paths
+                .iter()
+                .filter(|path| path.rsplit_once('.').map(|(p, _)| p) == parent)
+                .map(|&path| {
+                    let last = path.rsplit('.').next().unwrap_or(path);
+                    if is_crate_root(path) {
+                        format!("#[path = \"{}_module.rs\"]\npub mod {};\n", last, last)
+                    } else {
+                        format!("pub mod {};\n", last)
+                    }
+                })
+                .collect()
+        };
+
+        let mut files = BTreeMap::new();
+        let mut dependencies: BTreeMap<&str, Dependency> = BTreeMap::new();
+        files.insert(PathBuf::from("src/lib.rs"), declarations(None));
+        for &path in &paths {
+            let mut code = match self.module(path) {
+                Some(module) => {
+                    for dependency in cargo_toml_gen::detect_dependencies(&module.rust_code)
+                        .with_context(|| format!("reading the code of module `{}`", path))?
+                    {
+                        dependencies.insert(dependency.name, dependency);
+                    }
+                    module.rust_code.clone()
+                }
+                None => String::new(),
+            };
+            let mut file = PathBuf::from("src");
+            file.extend(path.split('.'));
+            let file = if is_crate_root(path) {
+                PathBuf::from(format!("src/{}_module.rs", path))
+            } else if is_package(path) {
+                if !code.is_empty() && !code.ends_with('\n') {
+                    code.push('\n');
+                }
+                code.push_str(&declarations(Some(path)));
+                file.join("mod.rs")
+            } else {
+                file.with_extension("rs")
+            };
+            files.insert(file, code);
+        }
+        let dependencies: Vec<Dependency> = dependencies.into_values().collect();
+        files.insert(
+            PathBuf::from("Cargo.toml"),
+            cargo_toml_gen::generate_cargo_toml(package, &dependencies),
+        );
+        Ok(files)
+    }
+
+    /// Writes the [`crate_files`](Self::crate_files) below `dir`
+    ///
+    /// Rust files written before keep their
+    /// [keep regions](crate::keep_regions); the regions that could not be
+    /// carried over as they were come back with the file they were in.
+    pub fn write_crate(&self, dir: &Path, package: &str) -> Result<Vec<(PathBuf, KeepConflict)>> {
+        let mut conflicts = Vec::new();
+        for (path, contents) in self.crate_files(package)? {
+            let path = dir.join(path);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("creating {}", parent.display()))?;
+            }
+            let contents = match fs::read_to_string(&path) {
+                Ok(previous) if path.extension().is_some_and(|ext| ext == "rs") => {
+                    let merge = merge_keep_regions(&previous, &contents);
+                    conflicts.extend(merge.conflicts.into_iter().map(|c| (path.clone(), c)));
+                    merge.code
+                }
+                _ => contents,
+            };
+            fs::write(&path, contents).with_context(|| format!("writing {}", path.display()))?;
+        }
+        Ok(conflicts)
+    }
+}
+
+/// A Python file of the project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceModule {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+/// The Python modules in `inputs`, sorted by name
+///
+/// Directories are searched recursively, skipping hidden directories and
+/// `__pycache__`.
+pub fn discover_modules(inputs: &[PathBuf]) -> Result<Vec<SourceModule>> {
+    let mut modules = BTreeMap::new();
+    for input in inputs {
+        let found = if input.is_dir() {
+            let mut files = Vec::new();
+            collect_python_files(input, &mut files)?;
+            files
+                .into_iter()
+                .map(|path| {
+                    let relative = path.strip_prefix(input).unwrap_or(&path);
+                    SourceModule {
+                        name: module_name(relative),
+                        path,
+                    }
+                })
+                .collect()
+        } else {
+            vec![SourceModule {
+                name: module_name(Path::new(input.file_name().unwrap_or_default())),
+                path: input.clone(),
+            }]
+        };
+        for module in found {
+            if let Some(previous) = modules.insert(module.name.clone(), module.path.clone()) {
+                bail!(
+                    "module `{}` is both {} and {}",
+                    module.name,
+                    previous.display(),
+                    module.path.display()
+                );
+            }
+        }
+    }
+    Ok(modules
+        .into_iter()
+        .map(|(name, path)| SourceModule { name, path })
+        .collect())
+}
+
+fn collect_python_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)
+        .with_context(|| format!("reading {}", dir.display()))?
+        .collect::<std::io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if path.is_dir() {
+            if !name.starts_with('.') && name != "__pycache__" {
+                collect_python_files(&path, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "py") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `shop/cart.py` → `shop.cart`, `shop/__init__.py` → `shop`
+fn module_name(relative: &Path) -> String {
+    let mut parts: Vec<String> = relative
+        .with_extension("")
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    if parts.len() > 1 && parts.last().is_some_and(|last| last == "__init__") {
+        parts.pop();
+    }
+    parts.join(".")
+}
+
+/// The module a module named `importer` means by `module`
+///
+/// The name is looked up in the importing module's package first, as
+/// relative imports are.
+fn resolve_import(index: &BTreeMap<&str, usize>, importer: &str, module: &str) -> Option<usize> {
+    let package = importer.rsplit_once('.').map(|(package, _)| package);
+    let relative = package.map(|package| format!("{}.{}", package, module));
+    relative
+        .as_deref()
+        .and_then(|relative| index.get(relative))
+        .or_else(|| index.get(module))
+        .copied()
+}
+
+fn module_index(names: &[String]) -> BTreeMap<&str, usize> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), i))
+        .collect()
+}
+
+/// For each module, the indices of the project modules it imports
+///
+/// `import a.b`, `from a import b` and `from a.b import c` depend on
+/// `a.b` when the project has it, else on `a`.
+pub fn module_dependencies(names: &[String], hirs: &[&HirModule]) -> Vec<BTreeSet<usize>> {
+    let index = module_index(names);
+    names
+        .iter()
+        .zip(hirs)
+        .enumerate()
+        .map(|(this, (name, hir))| {
+            let mut dependencies = BTreeSet::new();
+            for import in &hir.imports {
+                let submodules = import.items.iter().filter_map(|item| {
+                    let item = match item {
+                        ImportItem::Named(name) => name,
+                        ImportItem::Aliased { name, .. } => name,
+                    };
+                    resolve_import(&index, name, &format!("{}.{}", import.module, item))
+                });
+                let mut found: Vec<usize> = submodules.collect();
+                if found.is_empty() {
+                    found.extend(resolve_import(&index, name, &import.module));
+                }
+                dependencies.extend(found.into_iter().filter(|&dep| dep != this));
+            }
+            dependencies
+        })
+        .collect()
+}
+
+/// Rust path of the module generated from Python module `name`
+pub fn rust_module_path(name: &str) -> String {
+    format!("crate::{}", name.replace('.', "::"))
+}
+
+/// Module indices grouped so that each module only depends on modules of
+/// earlier groups, except those in a cycle, which form the last group
+pub fn dependency_layers(dependencies: &[BTreeSet<usize>]) -> Vec<Vec<usize>> {
+    let mut remaining: BTreeSet<usize> = (0..dependencies.len()).collect();
+    let mut layers = Vec::new();
+    while !remaining.is_empty() {
+        let layer: Vec<usize> = remaining
+            .iter()
+            .copied()
+            .filter(|&module| dependencies[module].iter().all(|d| !remaining.contains(d)))
+            .collect();
+        if layer.is_empty() {
+            layers.push(remaining.into_iter().collect());
+            break;
+        }
+        for module in &layer {
+            remaining.remove(module);
+        }
+        layers.push(layer);
+    }
+    layers
+}
+
+pub(crate) fn transpile_project(
+    pipeline: &DepylerPipeline,
+    inputs: &[PathBuf],
+) -> Result<ProjectReport> {
+    let start = Instant::now();
+    let sources = discover_modules(inputs)?;
+
+    let parsed = sources
+        .par_iter()
+        .map(|module| {
+            let source = fs::read_to_string(&module.path)
+                .with_context(|| format!("reading {}", module.path.display()))?;
+            let parse_start = Instant::now();
+            let hir = pipeline
+                .parse_to_hir(&source)
+                .with_context(|| format!("parsing module `{}`", module.name))?;
+            Ok((hir, source.len(), parse_start.elapsed()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let names: Vec<String> = sources.iter().map(|m| m.name.clone()).collect();
+    let hirs: Vec<&HirModule> = parsed.iter().map(|(hir, ..)| hir).collect();
+    let dependencies = module_dependencies(&names, &hirs);
+    let layers = dependency_layers(&dependencies);
+    let interfaces: Vec<ProjectModule> = hirs
+        .par_iter()
+        .zip(&names)
+        .map(|(hir, name)| {
+            ProjectModule::from_hir(
+                hir,
+                rust_module_path(name),
+                &pipeline.transpiler.type_mapper,
+            )
+        })
+        .collect();
+    let index = module_index(&names);
+
+    let mut parsed: Vec<Option<(HirModule, usize, Duration)>> =
+        parsed.into_iter().map(Some).collect();
+    let mut modules = Vec::with_capacity(sources.len());
+    for layer in &layers {
+        let work: Vec<(usize, (HirModule, usize, Duration))> = layer
+            .iter()
+            .map(|&i| (i, parsed[i].take().expect("each module is in one layer")))
+            .collect();
+        let transpiled = work
+            .into_par_iter()
+            .map(|(i, (hir, source_size_bytes, parse_time))| {
+                let functions_transpiled = hir.functions.len()
+                    + hir.classes.iter().map(|c| c.methods.len()).sum::<usize>();
+                let mut module_pipeline = pipeline.clone();
+                for import in &hir.imports {
+                    match resolve_import(&index, &names[i], &import.module) {
+                        Some(dep) if dep != i => {
+                            module_pipeline = module_pipeline
+                                .with_project_module(&import.module, interfaces[dep].clone());
+                        }
+                        _ => {}
+                    }
+                }
+                let transpile_start = Instant::now();
+                let (rust_code, optimization) = module_pipeline
+                    .transpile_hir_with_metrics(hir)
+                    .with_context(|| format!("transpiling module `{}`", names[i]))?;
+                let metrics = ModuleMetrics {
+                    parse_time,
+                    transpile_time: transpile_start.elapsed(),
+                    source_size_bytes,
+                    output_size_bytes: rust_code.len(),
+                    functions_transpiled,
+                    nodes_simplified: optimization.nodes_simplified,
+                };
+                Ok(TranspiledModule {
+                    name: names[i].clone(),
+                    path: sources[i].path.clone(),
+                    rust_code,
+                    dependencies: dependencies[i].iter().map(|&d| names[d].clone()).collect(),
+                    metrics,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        modules.extend(transpiled);
+    }
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut totals = ModuleMetrics::default();
+    for module in &modules {
+        totals.merge(&module.metrics);
+    }
+    Ok(ProjectReport {
+        modules,
+        layers: layers
+            .iter()
+            .map(|layer| layer.iter().map(|&i| names[i].clone()).collect())
+            .collect(),
+        totals,
+        wall_time: start.elapsed(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_names_follow_package_paths() {
+        assert_eq!(module_name(Path::new("shop/cart.py")), "shop.cart");
+        assert_eq!(module_name(Path::new("shop/__init__.py")), "shop");
+        assert_eq!(module_name(Path::new("main.py")), "main");
+        assert_eq!(module_name(Path::new("__init__.py")), "__init__");
+    }
+
+    #[test]
+    fn test_cycles_share_the_last_layer() {
+        let dependencies = vec![
+            BTreeSet::new(),
+            BTreeSet::from([0]),
+            BTreeSet::from([0, 3]),
+            BTreeSet::from([2]),
+        ];
+        assert_eq!(
+            dependency_layers(&dependencies),
+            vec![vec![0, 1], vec![2, 3]]
+        );
+    }
+}
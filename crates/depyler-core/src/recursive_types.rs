@@ -0,0 +1,434 @@
+//! Recursive type support for self-referential classes
+//!
+//! A Python class can hold an instance of itself (`next: Optional["Node"]`)
+//! because every attribute is a reference. A Rust struct holding itself by
+//! value has infinite size, so fields that close a by-value cycle between
+//! classes are rewritten to `Box<T>` / `Option<Box<T>>` before code
+//! generation. Constructors keep taking the unboxed type and box the value
+//! at the construction site; method bodies get the matching
+//! boxing on assignment and unboxing on reads of those fields.
+//!
+//! Collections (`List["Node"]`, `Dict[str, "Node"]`) already store their
+//! elements on the heap and are never boxed, and neither are classes
+//! annotated `ownership = "arena"`, whose instances are held as indices.
+
+use crate::hir::{HirClass, Type};
+use depyler_annotations::OwnershipModel;
+use std::collections::{HashMap, HashSet};
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_quote, Expr};
+
+/// By-value references between the classes of a module
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::hir::{HirClass, HirField, Type};
+/// use depyler_core::recursive_types::RecursiveTypes;
+///
+/// let node = HirClass {
+///     name: "Node".to_string(),
+///     base_classes: vec![],
+///     methods: vec![],
+///     fields: vec![HirField {
+///         name: "next".to_string(),
+///         field_type: Type::Optional(Box::new(Type::Custom("Node".to_string()))),
+///         default_value: None,
+///         is_class_var: false,
+///     }],
+///     is_dataclass: true,
+///     model_kind: None,
+///     annotations: Default::default(),
+///     docstring: None,
+/// };
+///
+/// let boxed = RecursiveTypes::analyze(&[node.clone()]).box_classes(&[node]);
+/// assert_eq!(
+///     boxed[0].fields[0].field_type,
+///     Type::Optional(Box::new(Type::Generic {
+///         base: "Box".to_string(),
+///         params: vec![Type::Custom("Node".to_string())],
+///     }))
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RecursiveTypes {
+    /// Class name -> classes it contains by value
+    edges: HashMap<String, HashSet<String>>,
+}
+
+/// How a recursive field is stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoxKind {
+    /// `Box<T>`
+    Boxed,
+    /// `Option<Box<T>>`
+    OptionBoxed,
+}
+
+impl BoxKind {
+    /// Storage kind of an already rewritten field type
+    pub fn of(ty: &Type) -> Option<Self> {
+        match ty {
+            Type::Optional(inner) if is_box(inner) => Some(BoxKind::OptionBoxed),
+            _ if is_box(ty) => Some(BoxKind::Boxed),
+            _ => None,
+        }
+    }
+}
+
+impl RecursiveTypes {
+    pub fn analyze(classes: &[HirClass]) -> Self {
+        let by_value: Vec<&HirClass> = classes
+            .iter()
+            .filter(|c| c.annotations.ownership_model != OwnershipModel::Arena)
+            .collect();
+        let names: HashSet<&str> = by_value.iter().map(|c| c.name.as_str()).collect();
+        let mut edges = HashMap::new();
+        for class in by_value {
+            let mut targets = HashSet::new();
+            for field in class.fields.iter().filter(|f| !f.is_class_var) {
+                collect_by_value(&field.field_type, &names, &mut targets);
+            }
+            edges.insert(class.name.clone(), targets);
+        }
+        Self { edges }
+    }
+
+    /// Whether `from` contains `to` by value, directly or through other classes
+    pub fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut stack = vec![from];
+        let mut seen = HashSet::new();
+        while let Some(current) = stack.pop() {
+            if !seen.insert(current) {
+                continue;
+            }
+            if let Some(targets) = self.edges.get(current) {
+                if targets.contains(to) {
+                    return true;
+                }
+                stack.extend(targets.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Whether a by-value field of `owner` holding `target` closes a cycle
+    pub fn needs_box(&self, owner: &str, target: &str) -> bool {
+        self.edges.contains_key(target) && (owner == target || self.reaches(target, owner))
+    }
+
+    /// Whether any class reaches itself by value
+    pub fn has_cycles(&self) -> bool {
+        self.edges.keys().any(|name| self.reaches(name, name))
+    }
+
+    /// Copy of `classes` with every cycle-closing field type boxed
+    pub fn box_classes(&self, classes: &[HirClass]) -> Vec<HirClass> {
+        classes
+            .iter()
+            .map(|class| {
+                let mut class = class.clone();
+                for field in class.fields.iter_mut().filter(|f| !f.is_class_var) {
+                    field.field_type = self.box_type(&field.field_type, &class.name);
+                }
+                class
+            })
+            .collect()
+    }
+
+    fn box_type(&self, ty: &Type, owner: &str) -> Type {
+        match ty {
+            Type::Custom(name) if self.needs_box(owner, name) => Type::Generic {
+                base: "Box".to_string(),
+                params: vec![ty.clone()],
+            },
+            Type::Optional(inner) => Type::Optional(Box::new(self.box_type(inner, owner))),
+            Type::Final(inner) => Type::Final(Box::new(self.box_type(inner, owner))),
+            Type::Tuple(items) => {
+                Type::Tuple(items.iter().map(|t| self.box_type(t, owner)).collect())
+            }
+            Type::Union(items) => {
+                Type::Union(items.iter().map(|t| self.box_type(t, owner)).collect())
+            }
+            Type::Array { element_type, size } => Type::Array {
+                element_type: Box::new(self.box_type(element_type, owner)),
+                size: size.clone(),
+            },
+            other => other.clone(),
+        }
+    }
+}
+
+/// Record the module classes `ty` stores inline
+fn collect_by_value(ty: &Type, names: &HashSet<&str>, out: &mut HashSet<String>) {
+    match ty {
+        Type::Custom(name) if names.contains(name.as_str()) => {
+            out.insert(name.clone());
+        }
+        Type::Optional(inner) | Type::Final(inner) => collect_by_value(inner, names, out),
+        Type::Tuple(items) | Type::Union(items) => {
+            for item in items {
+                collect_by_value(item, names, out);
+            }
+        }
+        Type::Array { element_type, .. } => collect_by_value(element_type, names, out),
+        // Vec, HashMap, HashSet and Box already live on the heap
+        _ => {}
+    }
+}
+
+fn is_box(ty: &Type) -> bool {
+    matches!(ty, Type::Generic { base, params } if base == "Box" && params.len() == 1)
+}
+
+/// The type a constructor parameter for a (possibly boxed) field takes
+pub fn unboxed_type(ty: &Type) -> Type {
+    match (BoxKind::of(ty), ty) {
+        (Some(BoxKind::OptionBoxed), Type::Optional(inner)) => {
+            Type::Optional(Box::new(unboxed_type(inner)))
+        }
+        (Some(BoxKind::Boxed), Type::Generic { params, .. }) => params[0].clone(),
+        _ => ty.clone(),
+    }
+}
+
+/// Wrap a value of the unboxed type for storage in a boxed field
+pub fn box_value(kind: BoxKind, value: &Expr) -> Expr {
+    match kind {
+        BoxKind::Boxed => parse_quote! { Box::new(#value) },
+        BoxKind::OptionBoxed if is_none_literal(value) => value.clone(),
+        BoxKind::OptionBoxed => {
+            let receiver = as_receiver(value);
+            parse_quote! { #receiver.map(Box::new) }
+        }
+    }
+}
+
+/// Parenthesize `expr` unless it can be a method receiver as is
+fn as_receiver(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Path(_) | Expr::Call(_) | Expr::MethodCall(_) | Expr::Field(_) | Expr::Paren(_) => {
+            expr.clone()
+        }
+        _ => parse_quote! { (#expr) },
+    }
+}
+
+/// Read a boxed field as an owned value of the unboxed type
+pub fn unbox_value(kind: BoxKind, field: &Expr) -> Expr {
+    match kind {
+        BoxKind::Boxed => parse_quote! { (*#field).clone() },
+        BoxKind::OptionBoxed => parse_quote! { #field.as_deref().cloned() },
+    }
+}
+
+fn is_none_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Path(p) if p.path.is_ident("None"))
+}
+
+/// Rewrites a method body for the boxed fields of its class
+///
+/// - `self.f = value` stores `Box::new(value)` / `value.map(Box::new)`
+/// - `self.f` / `self.f.clone()` used as a value (returned, bound, passed
+///   as an argument) yields the unboxed type
+///
+/// Field access through a box (`self.left.value`) auto-derefs and is left
+/// untouched.
+pub struct BoxedFieldRewriter<'a> {
+    fields: &'a HashMap<String, BoxKind>,
+}
+
+impl<'a> BoxedFieldRewriter<'a> {
+    pub fn new(fields: &'a HashMap<String, BoxKind>) -> Self {
+        Self { fields }
+    }
+
+    /// Boxed fields of an already rewritten class
+    pub fn boxed_fields(class: &HirClass) -> HashMap<String, BoxKind> {
+        class
+            .fields
+            .iter()
+            .filter(|f| !f.is_class_var)
+            .filter_map(|f| BoxKind::of(&f.field_type).map(|kind| (f.name.clone(), kind)))
+            .collect()
+    }
+
+    pub fn rewrite_block(&mut self, block: &mut syn::Block) {
+        if !self.fields.is_empty() {
+            self.visit_block_mut(block);
+        }
+    }
+
+    /// Kind of `self.<field>` when `<field>` is boxed
+    fn self_field(&self, expr: &Expr) -> Option<BoxKind> {
+        match expr {
+            Expr::Field(field) => match (&*field.base, &field.member) {
+                (Expr::Path(base), syn::Member::Named(name)) if base.path.is_ident("self") => {
+                    self.fields.get(&name.to_string()).copied()
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn unbox_in_place(&self, expr: &mut Expr) {
+        let (kind, field) = match expr {
+            Expr::MethodCall(call) if call.method == "clone" && call.args.is_empty() => {
+                match self.self_field(&call.receiver) {
+                    Some(kind) => (kind, (*call.receiver).clone()),
+                    None => return,
+                }
+            }
+            _ => match self.self_field(expr) {
+                Some(kind) => (kind, expr.clone()),
+                None => return,
+            },
+        };
+        *expr = unbox_value(kind, &field);
+    }
+}
+
+impl VisitMut for BoxedFieldRewriter<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        if let Expr::Assign(assign) = expr {
+            if let Some(kind) = self.self_field(&assign.left) {
+                self.visit_expr_mut(&mut assign.right);
+                *assign.right = box_value(kind, &assign.right);
+                return;
+            }
+        }
+        visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_expr_return_mut(&mut self, ret: &mut syn::ExprReturn) {
+        visit_mut::visit_expr_return_mut(self, ret);
+        if let Some(value) = ret.expr.as_mut() {
+            self.unbox_in_place(value);
+        }
+    }
+
+    fn visit_local_mut(&mut self, local: &mut syn::Local) {
+        visit_mut::visit_local_mut(self, local);
+        if let Some(init) = local.init.as_mut() {
+            self.unbox_in_place(&mut init.expr);
+        }
+    }
+
+    fn visit_expr_call_mut(&mut self, call: &mut syn::ExprCall) {
+        visit_mut::visit_expr_call_mut(self, call);
+        for arg in call.args.iter_mut() {
+            self.unbox_in_place(arg);
+        }
+    }
+
+    fn visit_expr_method_call_mut(&mut self, call: &mut syn::ExprMethodCall) {
+        visit_mut::visit_expr_method_call_mut(self, call);
+        for arg in call.args.iter_mut() {
+            self.unbox_in_place(arg);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::HirField;
+    use quote::ToTokens;
+
+    fn class(name: &str, fields: Vec<(&str, Type)>) -> HirClass {
+        HirClass {
+            name: name.to_string(),
+            base_classes: vec![],
+            methods: vec![],
+            fields: fields
+                .into_iter()
+                .map(|(name, field_type)| HirField {
+                    name: name.to_string(),
+                    field_type,
+                    default_value: None,
+                    is_class_var: false,
+                })
+                .collect(),
+            is_dataclass: true,
+            model_kind: None,
+            annotations: Default::default(),
+            docstring: None,
+        }
+    }
+
+    fn custom(name: &str) -> Type {
+        Type::Custom(name.to_string())
+    }
+
+    #[test]
+    fn test_mutual_recursion_is_boxed_on_both_sides() {
+        let classes = vec![
+            class("A", vec![("b", Type::Optional(Box::new(custom("B"))))]),
+            class("B", vec![("a", custom("A"))]),
+            class("Leaf", vec![("a", custom("A"))]),
+        ];
+        let analysis = RecursiveTypes::analyze(&classes);
+        assert!(analysis.needs_box("A", "B"));
+        assert!(analysis.needs_box("B", "A"));
+        // Leaf holds an A, but nothing holds a Leaf
+        assert!(!analysis.needs_box("Leaf", "A"));
+
+        let boxed = analysis.box_classes(&classes);
+        assert_eq!(
+            BoxKind::of(&boxed[0].fields[0].field_type),
+            Some(BoxKind::OptionBoxed)
+        );
+        assert_eq!(
+            BoxKind::of(&boxed[1].fields[0].field_type),
+            Some(BoxKind::Boxed)
+        );
+        assert_eq!(boxed[2].fields[0].field_type, custom("A"));
+        assert_eq!(
+            unboxed_type(&boxed[0].fields[0].field_type),
+            classes[0].fields[0].field_type
+        );
+    }
+
+    #[test]
+    fn test_collections_are_not_boxed() {
+        let classes = vec![class(
+            "Tree",
+            vec![("children", Type::List(Box::new(custom("Tree"))))],
+        )];
+        let analysis = RecursiveTypes::analyze(&classes);
+        assert!(!analysis.has_cycles());
+        assert_eq!(analysis.box_classes(&classes), classes);
+    }
+
+    #[test]
+    fn test_arena_classes_are_not_boxed() {
+        let mut node = class(
+            "Node",
+            vec![("next", Type::Optional(Box::new(custom("Node"))))],
+        );
+        node.annotations.ownership_model = OwnershipModel::Arena;
+        let classes = vec![node];
+        let analysis = RecursiveTypes::analyze(&classes);
+        assert!(!analysis.has_cycles());
+        assert_eq!(analysis.box_classes(&classes), classes);
+    }
+
+    #[test]
+    fn test_rewriter_boxes_assignments_and_unboxes_reads() {
+        let fields = HashMap::from([("next".to_string(), BoxKind::OptionBoxed)]);
+        let mut block: syn::Block = parse_quote! {
+            {
+                self.next = other;
+                self.next = None;
+                return self.next.clone();
+            }
+        };
+        BoxedFieldRewriter::new(&fields).rewrite_block(&mut block);
+        let code = block.to_token_stream().to_string();
+        assert!(code.contains("self . next = other . map (Box :: new)"));
+        assert!(code.contains("self . next = None"));
+        assert!(code.contains("return self . next . as_deref () . cloned ()"));
+    }
+}
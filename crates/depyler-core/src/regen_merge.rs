@@ -0,0 +1,569 @@
+//! Three-way merge of regenerated code with hand edits
+//!
+//! When a generated file has been edited by hand, [`merge_regenerated`]
+//! combines three versions of it: the code depyler generated last time
+//! (the base), the file as it is now (edited), and the code generated this
+//! time. The files are matched item by item (functions, structs, impl
+//! blocks, and the methods inside impl blocks) rather than line by line:
+//!
+//! - an item only the user changed keeps the user's version;
+//! - an item only the generator changed takes the new generated version;
+//! - items the user added stay after the item they followed, and items the
+//!   user deleted stay deleted, unless the generator changed them too;
+//! - an item both sides changed differently is a conflict, written with
+//!   git-style markers around the two versions.
+//!
+//! Items are compared by their tokens and their comments, so reformatting
+//! an item does not count as editing it.
+
+use proc_macro2::{LineColumn, TokenStream};
+use quote::ToTokens;
+use std::collections::HashMap;
+use std::fmt;
+
+pub const CONFLICT_START: &str = "<<<<<<< edited";
+pub const CONFLICT_SEPARATOR: &str = "=======";
+pub const CONFLICT_END: &str = ">>>>>>> generated";
+
+/// An item both the user and the generator changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// What the item is, such as `fn area` or `impl Shape`
+    pub item: String,
+    /// 1-based line of the conflict start marker in the merged code
+    pub line: usize,
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` (line {}): changed by hand and by the generator",
+            self.item, self.line
+        )
+    }
+}
+
+/// The regenerated code with the hand edits merged in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegenMerge {
+    pub code: String,
+    /// Hand edits carried over: items changed, added or deleted by the user
+    pub integrated: usize,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+impl RegenMerge {
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+}
+
+/// Which of the three files a version comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeSide {
+    Base,
+    Edited,
+    Generated,
+}
+
+/// A file that could not be parsed as Rust
+#[derive(Debug, Clone)]
+pub struct MergeParseError {
+    pub side: MergeSide,
+    pub error: syn::Error,
+}
+
+impl fmt::Display for MergeParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = match self.side {
+            MergeSide::Base => "previously generated",
+            MergeSide::Edited => "edited",
+            MergeSide::Generated => "generated",
+        };
+        let start = self.error.span().start();
+        write!(
+            f,
+            "the {} code does not parse (line {}): {}",
+            side, start.line, self.error
+        )
+    }
+}
+
+impl std::error::Error for MergeParseError {}
+
+/// Merges the user's edits to `base`, found in `edited`, into `generated`
+pub fn merge_regenerated(
+    base: &str,
+    edited: &str,
+    generated: &str,
+) -> Result<RegenMerge, MergeParseError> {
+    let parse = |text: &str, side| {
+        syn::parse_file(text)
+            .map(|file| split_file(text, &file))
+            .map_err(|error| MergeParseError { side, error })
+    };
+    let base = parse(base, MergeSide::Base)?;
+    let edited = parse(edited, MergeSide::Edited)?;
+    let generated = parse(generated, MergeSide::Generated)?;
+
+    let mut merged = Merged::default();
+    merge_text(
+        &mut merged,
+        "file header",
+        &base.head,
+        &edited.head,
+        &generated.head,
+    );
+    merge_units(&mut merged, &base.units, &edited.units, &generated.units);
+    merge_text(
+        &mut merged,
+        "end of file",
+        &base.tail,
+        &edited.tail,
+        &generated.tail,
+    );
+    Ok(RegenMerge {
+        code: merged.code,
+        integrated: merged.integrated,
+        conflicts: merged.conflicts,
+    })
+}
+
+/// Items, or the members of an impl block, with the text around them
+struct Block {
+    head: String,
+    units: Vec<Unit>,
+    tail: String,
+}
+
+struct Unit {
+    /// Identifies the item across the three files
+    key: String,
+    /// The item with the blank lines and comments before it
+    text: String,
+    tokens: String,
+    /// The members of an impl block, which merge one by one
+    members: Option<Block>,
+}
+
+impl Unit {
+    fn same(&self, other: &Unit) -> bool {
+        self.tokens == other.tokens && squeeze(&self.text) == squeeze(&other.text)
+    }
+}
+
+#[derive(Default)]
+struct Merged {
+    code: String,
+    integrated: usize,
+    conflicts: Vec<MergeConflict>,
+}
+
+impl Merged {
+    fn conflict(&mut self, item: &str, edited: Option<&str>, generated: Option<&str>) {
+        let (gap, _) = split_gap(edited.or(generated).unwrap_or_default());
+        self.code.push_str(gap);
+        self.conflicts.push(MergeConflict {
+            item: item.to_string(),
+            line: self.code.matches('\n').count() + 1,
+        });
+        let body = |text: Option<&str>| {
+            text.map_or(String::new(), |text| format!("{}\n", split_gap(text).1))
+        };
+        self.code.push_str(&format!(
+            "{}\n{}{}\n{}{}",
+            CONFLICT_START,
+            body(edited),
+            CONFLICT_SEPARATOR,
+            body(generated),
+            CONFLICT_END
+        ));
+    }
+}
+
+/// Merges text that has no items: the file header and what follows the
+/// last item
+fn merge_text(merged: &mut Merged, what: &str, base: &str, edited: &str, generated: &str) {
+    let (base_key, edited_key, generated_key) =
+        (squeeze(base), squeeze(edited), squeeze(generated));
+    if base_key == edited_key {
+        merged.code.push_str(generated);
+    } else if base_key == generated_key || edited_key == generated_key {
+        merged.integrated += usize::from(edited_key != generated_key);
+        merged.code.push_str(edited);
+    } else {
+        merged.conflict(what, Some(edited), Some(generated));
+        // The text after the markers starts on a new line
+        merged.code.push('\n');
+    }
+}
+
+fn merge_units(merged: &mut Merged, base: &[Unit], edited: &[Unit], generated: &[Unit]) {
+    let by_key = |units: &[Unit]| -> HashMap<String, usize> {
+        units
+            .iter()
+            .enumerate()
+            .map(|(i, unit)| (unit.key.clone(), i))
+            .collect()
+    };
+    let (base_keys, edited_keys, generated_keys) =
+        (by_key(base), by_key(edited), by_key(generated));
+
+    // Items only the edited file has go after the edited item before them
+    // that the generated code still has
+    let mut extra_after: HashMap<Option<&str>, Vec<&Unit>> = HashMap::new();
+    let mut anchor: Option<&str> = None;
+    for unit in edited {
+        if generated_keys.contains_key(&unit.key) {
+            anchor = Some(&unit.key);
+        } else {
+            extra_after.entry(anchor).or_default().push(unit);
+        }
+    }
+
+    let mut emit_extras = |merged: &mut Merged, after: Option<&str>| {
+        for unit in extra_after.remove(&after).unwrap_or_default() {
+            match base_keys.get(&unit.key).map(|&i| &base[i]) {
+                // Added by hand
+                None => {
+                    merged.integrated += 1;
+                    merged.code.push_str(&unit.text);
+                }
+                // Removed by the generator, not edited by hand
+                Some(old) if old.same(unit) => {}
+                Some(_) => merged.conflict(&unit.key, Some(&unit.text), None),
+            }
+        }
+    };
+
+    emit_extras(merged, None);
+    for new in generated {
+        let old = base_keys.get(&new.key).map(|&i| &base[i]);
+        let ours = edited_keys.get(&new.key).map(|&i| &edited[i]);
+        match (old, ours) {
+            (None, None) => merged.code.push_str(&new.text),
+            (None, Some(ours)) if ours.same(new) => merged.code.push_str(&ours.text),
+            (None, Some(ours)) => merged.conflict(&new.key, Some(&ours.text), Some(&new.text)),
+            // Deleted by hand
+            (Some(old), None) if old.same(new) => merged.integrated += 1,
+            (Some(_), None) => merged.conflict(&new.key, None, Some(&new.text)),
+            (Some(old), Some(ours)) => merge_unit(merged, old, ours, new),
+        }
+        emit_extras(merged, Some(&new.key));
+    }
+}
+
+fn merge_unit(merged: &mut Merged, old: &Unit, ours: &Unit, new: &Unit) {
+    if old.same(ours) {
+        merged.code.push_str(&new.text);
+    } else if old.same(new) {
+        merged.integrated += 1;
+        merged.code.push_str(&ours.text);
+    } else if ours.same(new) {
+        merged.code.push_str(&ours.text);
+    } else if let (Some(old_block), Some(our_block), Some(new_block)) =
+        (&old.members, &ours.members, &new.members)
+    {
+        // Both changed the impl block: merge its members unless both
+        // changed its header
+        let (old_head, our_head, new_head) = (
+            squeeze(&old_block.head),
+            squeeze(&our_block.head),
+            squeeze(&new_block.head),
+        );
+        if old_head != our_head && old_head != new_head && our_head != new_head {
+            merged.conflict(&new.key, Some(&ours.text), Some(&new.text));
+            return;
+        }
+        merge_text(
+            merged,
+            &new.key,
+            &old_block.head,
+            &our_block.head,
+            &new_block.head,
+        );
+        merge_units(merged, &old_block.units, &our_block.units, &new_block.units);
+        merge_text(
+            merged,
+            &new.key,
+            &old_block.tail,
+            &our_block.tail,
+            &new_block.tail,
+        );
+    } else {
+        merged.conflict(&new.key, Some(&ours.text), Some(&new.text));
+    }
+}
+
+/// Byte offsets of the lines of a source text, to turn span locations into
+/// positions in it
+struct Lines<'a> {
+    text: &'a str,
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    fn offset(&self, at: LineColumn) -> usize {
+        let Some(&start) = self.starts.get(at.line.saturating_sub(1)) else {
+            return self.text.len();
+        };
+        // Columns count characters, not bytes
+        self.text[start..]
+            .char_indices()
+            .nth(at.column)
+            .map_or(self.text.len(), |(i, _)| start + i)
+    }
+
+    /// Where the tokens start and end in the text
+    fn range(&self, tokens: TokenStream) -> Option<(usize, usize)> {
+        let mut tokens = tokens.into_iter();
+        let first = tokens.next()?;
+        let last = tokens.last().unwrap_or_else(|| first.clone());
+        Some((
+            self.offset(first.span().start()),
+            self.offset(last.span().end()),
+        ))
+    }
+}
+
+fn split_file(text: &str, file: &syn::File) -> Block {
+    let lines = Lines::new(text);
+    let head_end = file
+        .attrs
+        .iter()
+        .filter_map(|attr| lines.range(attr.to_token_stream()))
+        .map(|(_, end)| end)
+        .max()
+        .unwrap_or(0);
+    let items = file.items.iter().map(|item| {
+        let block = match item {
+            syn::Item::Impl(block) => Some(block),
+            _ => None,
+        };
+        (item_key(item), item.to_token_stream(), block)
+    });
+    let (units, end) = split_units(&lines, head_end, items);
+    Block {
+        head: text[..head_end].to_string(),
+        units,
+        tail: text[end..].to_string(),
+    }
+}
+
+/// The members of `block`, whose text starts at `start`
+fn split_impl(lines: &Lines, block: &syn::ItemImpl, start: usize) -> Block {
+    let open = lines.offset(block.brace_token.span.open().end());
+    let close = lines.offset(block.brace_token.span.close().end());
+    let items = block
+        .items
+        .iter()
+        .map(|item| (impl_item_key(item), item.to_token_stream(), None));
+    let (units, end) = split_units(lines, open, items);
+    Block {
+        head: lines.text[start..open].to_string(),
+        units,
+        tail: lines.text[end..close.max(end)].to_string(),
+    }
+}
+
+/// Cuts the text from `from` on into items, returning them and where the
+/// last one ends
+fn split_units<'i>(
+    lines: &Lines,
+    from: usize,
+    items: impl Iterator<Item = (String, TokenStream, Option<&'i syn::ItemImpl>)>,
+) -> (Vec<Unit>, usize) {
+    let mut units = Vec::new();
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    let mut cursor = from;
+    for (key, tokens, block) in items {
+        let Some((_, end)) = lines.range(tokens.clone()) else {
+            continue;
+        };
+        // Items of the same name keep their order
+        let count = seen.entry(key.clone()).or_default();
+        *count += 1;
+        let key = match *count {
+            1 => key,
+            n => format!("{} #{}", key, n),
+        };
+        units.push(Unit {
+            key,
+            text: lines.text[cursor..end].to_string(),
+            tokens: tokens.to_string(),
+            members: block.map(|block| split_impl(lines, block, cursor)),
+        });
+        cursor = end;
+    }
+    (units, cursor)
+}
+
+fn item_key(item: &syn::Item) -> String {
+    match item {
+        syn::Item::Fn(item) => format!("fn {}", item.sig.ident),
+        syn::Item::Struct(item) => format!("struct {}", item.ident),
+        syn::Item::Enum(item) => format!("enum {}", item.ident),
+        syn::Item::Union(item) => format!("union {}", item.ident),
+        syn::Item::Trait(item) => format!("trait {}", item.ident),
+        syn::Item::TraitAlias(item) => format!("trait {}", item.ident),
+        syn::Item::Type(item) => format!("type {}", item.ident),
+        syn::Item::Const(item) => format!("const {}", item.ident),
+        syn::Item::Static(item) => format!("static {}", item.ident),
+        syn::Item::Mod(item) => format!("mod {}", item.ident),
+        syn::Item::Impl(item) => {
+            let self_ty = item.self_ty.to_token_stream();
+            match &item.trait_ {
+                Some((_, path, _)) => {
+                    format!("impl {} for {}", path.to_token_stream(), self_ty)
+                }
+                None => format!("impl {}", self_ty),
+            }
+        }
+        syn::Item::Macro(item) => match &item.ident {
+            Some(ident) => format!("macro {}", ident),
+            None => format!("{}!", item.mac.path.to_token_stream()),
+        },
+        other => other.to_token_stream().to_string(),
+    }
+}
+
+fn impl_item_key(item: &syn::ImplItem) -> String {
+    match item {
+        syn::ImplItem::Fn(item) => format!("fn {}", item.sig.ident),
+        syn::ImplItem::Const(item) => format!("const {}", item.ident),
+        syn::ImplItem::Type(item) => format!("type {}", item.ident),
+        other => other.to_token_stream().to_string(),
+    }
+}
+
+/// The text without whitespace, so formatting does not count as a change
+fn squeeze(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+/// The blank lines at the start of `text`, and the rest
+fn split_gap(text: &str) -> (&str, &str) {
+    let blank = text.len() - text.trim_start().len();
+    let gap = text[..blank].rfind('\n').map_or(0, |i| i + 1);
+    text.split_at(gap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "\
+pub fn area(w: i32, h: i32) -> i32 {
+    w * h
+}
+
+pub fn perimeter(w: i32, h: i32) -> i32 {
+    2 * (w + h)
+}
+
+pub struct Shape {
+    pub sides: i32,
+}
+
+impl Shape {
+    pub fn new(sides: i32) -> Self {
+        Self { sides }
+    }
+}
+";
+
+    #[test]
+    fn test_unedited_files_take_the_generated_code() {
+        let generated = BASE.replace("w * h", "h * w");
+        let merge = merge_regenerated(BASE, BASE, &generated).unwrap();
+        assert_eq!(merge.code, generated);
+        assert_eq!(merge.integrated, 0);
+        assert!(!merge.has_conflicts());
+    }
+
+    #[test]
+    fn test_edits_to_unchanged_items_are_kept() {
+        let edited = BASE.replace(
+            "    w * h\n",
+            "    // checked\n    w.checked_mul(h).unwrap()\n",
+        );
+        let generated = BASE.replace("2 * (w + h)", "(w + h) * 2");
+        let merge = merge_regenerated(BASE, &edited, &generated).unwrap();
+        assert!(merge
+            .code
+            .contains("// checked\n    w.checked_mul(h).unwrap()"));
+        assert!(merge.code.contains("(w + h) * 2"));
+        assert_eq!(merge.integrated, 1);
+        assert!(!merge.has_conflicts());
+    }
+
+    #[test]
+    fn test_reformatting_is_not_an_edit() {
+        let edited = BASE.replace(
+            "pub fn area(w: i32, h: i32) -> i32 {\n    w * h\n}",
+            "pub fn area(w: i32, h: i32) -> i32 { w * h }",
+        );
+        let generated = BASE.replace("w * h", "h * w");
+        let merge = merge_regenerated(BASE, &edited, &generated).unwrap();
+        assert_eq!(merge.code, generated);
+    }
+
+    #[test]
+    fn test_added_and_deleted_items() {
+        let edited = BASE
+            .replace(
+                "}\n\npub fn perimeter",
+                "}\n\nfn helper() {}\n\npub fn perimeter",
+            )
+            .replace(
+                "pub fn perimeter(w: i32, h: i32) -> i32 {\n    2 * (w + h)\n}\n\n",
+                "",
+            );
+        let merge = merge_regenerated(BASE, &edited, BASE).unwrap();
+        assert_eq!(merge.code, edited);
+        assert_eq!(merge.integrated, 2);
+
+        // A method added to a generated impl block survives changes to the
+        // other methods
+        let edited = BASE.replace(
+            "        Self { sides }\n    }\n",
+            "        Self { sides }\n    }\n\n    pub fn is_triangle(&self) -> bool {\n        self.sides == 3\n    }\n",
+        );
+        let generated = BASE.replace("Self { sides }", "Shape { sides }");
+        let merge = merge_regenerated(BASE, &edited, &generated).unwrap();
+        assert!(!merge.has_conflicts());
+        assert!(merge.code.contains("Shape { sides }"));
+        assert!(merge.code.contains("pub fn is_triangle"));
+        assert!(merge.code.ends_with("    }\n}\n"));
+    }
+
+    #[test]
+    fn test_items_changed_on_both_sides_are_conflicts() {
+        let edited = BASE.replace("w * h", "w.checked_mul(h).unwrap()");
+        let generated = BASE.replace("w * h", "h * w");
+        let merge = merge_regenerated(BASE, &edited, &generated).unwrap();
+        assert_eq!(
+            merge.conflicts,
+            vec![MergeConflict {
+                item: "fn area".to_string(),
+                line: 1,
+            }]
+        );
+        assert!(merge.code.starts_with(
+            "<<<<<<< edited\npub fn area(w: i32, h: i32) -> i32 {\n    w.checked_mul(h).unwrap()\n}\n\
+             =======\npub fn area(w: i32, h: i32) -> i32 {\n    h * w\n}\n>>>>>>> generated\n\npub fn perimeter"
+        ));
+
+        // Unresolved markers stop the next merge
+        let error = merge_regenerated(BASE, &merge.code, &generated).unwrap_err();
+        assert_eq!(error.side, MergeSide::Edited);
+    }
+}
@@ -0,0 +1,953 @@
+/// Resource-leak analysis for handles acquired without `with`
+///
+/// CPython closes a leaked file or socket as soon as its refcount drops to
+/// zero, which usually hides the missing `close()`. The generated Rust code
+/// drops the handle at the end of the enclosing block instead, so this
+/// analysis reports acquisitions that are not scoped by `with`, points out
+/// where drop timing becomes observable, and can rewrite the simple
+/// `f = open(...); ...; f.close()` shape into an explicit `with` scope.
+use crate::hir::{AssignTarget, HirExpr, HirFunction, HirProgram, HirStmt, Literal};
+use crate::hir_spans::HirSpans;
+use colored::Colorize;
+use std::collections::HashSet;
+
+/// Analyzer that tracks resource handles bound to local variables
+pub struct ResourceLeakAnalyzer {
+    /// Collected warnings
+    warnings: Vec<ResourceWarning>,
+    /// Configuration
+    config: ResourceLeakConfig,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceLeakConfig {
+    /// Report handles that are returned or stored outside the function
+    pub report_escapes: bool,
+    /// Report read-only handles that are never closed
+    pub report_read_only: bool,
+}
+
+impl Default for ResourceLeakConfig {
+    fn default() -> Self {
+        Self {
+            report_escapes: true,
+            report_read_only: true,
+        }
+    }
+}
+
+/// Kind of resource acquired
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    File,
+    Socket,
+    Connection,
+    TempFile,
+    Archive,
+}
+
+/// Problem found with a resource handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeakIssue {
+    /// Acquired without `with` and never closed
+    NeverClosed,
+    /// Closed, but an early return/raise or a branch can skip the `close()`
+    NotClosedOnAllPaths,
+    /// Used again after `close()`
+    UseAfterClose,
+    /// Returned or stored outside the function
+    EscapesScope,
+    /// The same path is opened again while this handle is still open
+    ReopenedWhileOpen { path: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct ResourceWarning {
+    pub function: String,
+    pub variable: String,
+    pub kind: ResourceKind,
+    pub issue: LeakIssue,
+    /// Statement path of the acquisition, as [`HirSpans`] numbers nested blocks
+    pub path: Vec<usize>,
+    /// Whether Python and Rust would release the handle at observably different times
+    pub drop_timing_observable: bool,
+    pub suggestion: String,
+}
+
+/// A resource handle bound by `name = <acquisition>`
+#[derive(Debug, Clone)]
+struct Acquisition {
+    variable: String,
+    kind: ResourceKind,
+    path: Option<String>,
+    writable: bool,
+    /// Index of the top-level statement containing the acquisition
+    statement: usize,
+    /// Statement path of the acquisition, as [`HirSpans`] numbers nested blocks
+    stmt_path: Vec<usize>,
+}
+
+impl ResourceLeakAnalyzer {
+    pub fn new(config: ResourceLeakConfig) -> Self {
+        Self {
+            warnings: Vec::new(),
+            config,
+        }
+    }
+
+    /// Analyze every function in a program
+    pub fn analyze_program(&mut self, program: &HirProgram) -> Vec<ResourceWarning> {
+        self.analyze_functions(&program.functions)
+    }
+
+    /// Analyze a list of functions
+    pub fn analyze_functions(&mut self, functions: &[HirFunction]) -> Vec<ResourceWarning> {
+        self.warnings.clear();
+        for func in functions {
+            self.analyze_function(func);
+        }
+        self.warnings.clone()
+    }
+
+    fn analyze_function(&mut self, func: &HirFunction) {
+        let mut acquisitions = Vec::new();
+        let mut path = Vec::new();
+        for (index, stmt) in func.body.iter().enumerate() {
+            path.push(index);
+            collect_acquisitions(stmt, index, &mut path, &mut acquisitions);
+            path.pop();
+        }
+
+        for (idx, acq) in acquisitions.iter().enumerate() {
+            let later_acquisitions = &acquisitions[idx + 1..];
+            for issue in self.issues_for(acq, func, later_acquisitions) {
+                let drop_timing_observable = match &issue {
+                    LeakIssue::ReopenedWhileOpen { .. } | LeakIssue::UseAfterClose => true,
+                    LeakIssue::EscapesScope | LeakIssue::NotClosedOnAllPaths => acq.writable,
+                    LeakIssue::NeverClosed => false,
+                };
+                self.warnings.push(ResourceWarning {
+                    function: func.name.clone(),
+                    variable: acq.variable.clone(),
+                    kind: acq.kind,
+                    suggestion: suggestion_for(&issue, &acq.variable),
+                    issue,
+                    path: acq.stmt_path.clone(),
+                    drop_timing_observable,
+                });
+            }
+        }
+    }
+
+    /// Determine the issues for one acquisition
+    ///
+    /// # Complexity
+    /// 8 (close/escape/reopen checks)
+    fn issues_for(
+        &self,
+        acq: &Acquisition,
+        func: &HirFunction,
+        later_acquisitions: &[Acquisition],
+    ) -> Vec<LeakIssue> {
+        let mut issues = Vec::new();
+        let var = acq.variable.as_str();
+        let close_position = top_level_close_position(&func.body, var);
+        let closes_anywhere = func.body.iter().any(|s| stmt_closes(s, var));
+
+        if body_escapes(&func.body, var) {
+            if self.config.report_escapes {
+                issues.push(LeakIssue::EscapesScope);
+            }
+            return issues;
+        }
+
+        match close_position {
+            Some(pos) => {
+                let between = func.body.get(acq.statement + 1..pos).unwrap_or_default();
+                if between.iter().any(stmt_exits_early) {
+                    issues.push(LeakIssue::NotClosedOnAllPaths);
+                }
+                if pos > acq.statement && func.body[pos + 1..].iter().any(|s| stmt_mentions(s, var))
+                {
+                    issues.push(LeakIssue::UseAfterClose);
+                }
+            }
+            None if closes_anywhere => issues.push(LeakIssue::NotClosedOnAllPaths),
+            None if acq.writable || self.config.report_read_only => {
+                issues.push(LeakIssue::NeverClosed)
+            }
+            None => {}
+        }
+
+        if let Some(path) = &acq.path {
+            let closed_at = close_position.unwrap_or(usize::MAX);
+            let reopened = later_acquisitions
+                .iter()
+                .any(|other| other.path.as_ref() == Some(path) && other.statement < closed_at);
+            if reopened {
+                issues.push(LeakIssue::ReopenedWhileOpen { path: path.clone() });
+            }
+        }
+
+        issues
+    }
+
+    /// Rewrite `f = open(...); <uses>; f.close()` into `with open(...) as f: <uses>`
+    ///
+    /// Only blocks where the handle is closed at the same nesting level, is not
+    /// reassigned or escaping, and whose inner bindings are not needed after the
+    /// `close()` are rewritten. Returns the number of scopes inserted.
+    pub fn insert_explicit_scopes(&self, func: &mut HirFunction) -> usize {
+        scope_block(&mut func.body)
+    }
+
+    /// Format warnings for display
+    ///
+    /// Without `spans`, warnings are located by function only.
+    pub fn format_warnings(
+        &self,
+        warnings: &[ResourceWarning],
+        spans: Option<&HirSpans>,
+    ) -> String {
+        if warnings.is_empty() {
+            return "✅ No resource leaks found!\n".green().to_string();
+        }
+
+        let mut output = String::new();
+        output.push_str(&format!("\n{}\n", "Resource Warnings".bold().yellow()));
+        output.push_str(&format!("{}\n\n", "═".repeat(50)));
+
+        for (idx, warning) in warnings.iter().enumerate() {
+            output.push_str(&format!(
+                "{} {:?} '{}': {}\n",
+                format!("[{}]", idx + 1).dimmed(),
+                warning.kind,
+                warning.variable.bold(),
+                describe_issue(&warning.issue)
+            ));
+            let location = match position(warning, spans) {
+                Some((line, column)) => {
+                    format!("{}, line {}, column {}", warning.function, line, column)
+                }
+                None => warning.function.clone(),
+            };
+            output.push_str(&format!("   {} {}\n", "Location:".dimmed(), location));
+            if warning.drop_timing_observable {
+                output.push_str(&format!(
+                    "   {} {}\n",
+                    "Note:".red(),
+                    "Rust releases this handle at a different point than CPython".red()
+                ));
+            }
+            output.push_str(&format!(
+                "   {} {}\n\n",
+                "Fix:".green(),
+                warning.suggestion.green()
+            ));
+        }
+
+        output.push_str(&format!(
+            "{} Found {} resource warnings\n",
+            "Summary:".bold(),
+            warnings.len()
+        ));
+        output
+    }
+}
+
+/// Line and column of the acquiring statement
+fn position(warning: &ResourceWarning, spans: Option<&HirSpans>) -> Option<(usize, usize)> {
+    let spans = spans?;
+    let stmt = spans
+        .function(&warning.function)?
+        .statement(&warning.path)?;
+    let position = spans.position(stmt.span.start);
+    Some((position.line, position.column))
+}
+
+fn describe_issue(issue: &LeakIssue) -> String {
+    match issue {
+        LeakIssue::NeverClosed => "acquired without `with` and never closed".to_string(),
+        LeakIssue::NotClosedOnAllPaths => "close() is skipped on some paths".to_string(),
+        LeakIssue::UseAfterClose => "used after close()".to_string(),
+        LeakIssue::EscapesScope => "handle outlives the function".to_string(),
+        LeakIssue::ReopenedWhileOpen { path } => {
+            format!("'{}' is opened again while this handle is open", path)
+        }
+    }
+}
+
+fn suggestion_for(issue: &LeakIssue, var: &str) -> String {
+    match issue {
+        LeakIssue::NeverClosed | LeakIssue::NotClosedOnAllPaths => format!(
+            "Use `with ... as {}:` so the handle is dropped at a known point",
+            var
+        ),
+        LeakIssue::UseAfterClose => {
+            format!(
+                "Move uses of '{}' before close(); Rust rejects use after drop",
+                var
+            )
+        }
+        LeakIssue::EscapesScope => format!(
+            "Document ownership of '{}'; the caller now decides when it is dropped",
+            var
+        ),
+        LeakIssue::ReopenedWhileOpen { .. } => format!(
+            "Close or flush '{}' before reopening the path; buffered writes may be lost",
+            var
+        ),
+    }
+}
+
+/// Classify an expression as a resource acquisition
+///
+/// # Complexity
+/// 6 (call/method-call match on known constructors)
+fn acquisition_kind(expr: &HirExpr) -> Option<ResourceKind> {
+    match expr {
+        HirExpr::Call { func, .. } => match func.as_str() {
+            "open" => Some(ResourceKind::File),
+            "NamedTemporaryFile" | "TemporaryFile" => Some(ResourceKind::TempFile),
+            _ => None,
+        },
+        HirExpr::MethodCall { object, method, .. } => {
+            let HirExpr::Var(module) = object.as_ref() else {
+                return None;
+            };
+            match (module.as_str(), method.as_str()) {
+                ("io" | "codecs" | "gzip" | "bz2" | "lzma", "open") => Some(ResourceKind::File),
+                ("socket", "socket" | "create_connection") => Some(ResourceKind::Socket),
+                ("sqlite3", "connect") | ("request" | "urllib", "urlopen") => {
+                    Some(ResourceKind::Connection)
+                }
+                ("tempfile", "NamedTemporaryFile" | "TemporaryFile") => {
+                    Some(ResourceKind::TempFile)
+                }
+                ("zipfile", "ZipFile") | ("tarfile", "open") => Some(ResourceKind::Archive),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Literal path and writability of an acquisition call
+fn acquisition_details(expr: &HirExpr) -> (Option<String>, bool) {
+    let (args, kwargs) = match expr {
+        HirExpr::Call { args, kwargs, .. } | HirExpr::MethodCall { args, kwargs, .. } => {
+            (args, kwargs)
+        }
+        _ => return (None, false),
+    };
+    let path = match args.first() {
+        Some(HirExpr::Literal(Literal::String(s))) => Some(s.clone()),
+        _ => None,
+    };
+    let mode = args
+        .get(1)
+        .or_else(|| kwargs.iter().find(|(k, _)| k == "mode").map(|(_, v)| v));
+    let writable = match mode {
+        Some(HirExpr::Literal(Literal::String(m))) => m.contains(['w', 'a', 'x', '+']),
+        _ => false,
+    };
+    (path, writable)
+}
+
+/// Acquisitions in `stmt`, found at `path` inside top-level statement `statement`
+fn collect_acquisitions(
+    stmt: &HirStmt,
+    statement: usize,
+    path: &mut Vec<usize>,
+    out: &mut Vec<Acquisition>,
+) {
+    match stmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(var),
+            value,
+            ..
+        } => {
+            if let Some(kind) = acquisition_kind(value) {
+                let (file_path, writable) = acquisition_details(value);
+                out.push(Acquisition {
+                    variable: var.clone(),
+                    kind,
+                    path: file_path,
+                    writable,
+                    statement,
+                    stmt_path: path.clone(),
+                });
+            }
+        }
+        _ => {
+            for (block, body) in span_blocks(stmt) {
+                path.push(block);
+                for (index, s) in body.iter().enumerate() {
+                    path.push(index);
+                    collect_acquisitions(s, statement, path, out);
+                    path.pop();
+                }
+                path.pop();
+            }
+        }
+    }
+}
+
+/// Nested blocks of a statement with their block index in [`HirSpans`]
+///
+/// Unlike [`child_blocks`], absent `else` and `finally` blocks still take
+/// an index.
+fn span_blocks(stmt: &HirStmt) -> Vec<(usize, &[HirStmt])> {
+    match stmt {
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            let mut blocks = vec![(0, then_body.as_slice())];
+            if let Some(else_body) = else_body {
+                blocks.push((1, else_body.as_slice()));
+            }
+            blocks
+        }
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => {
+            let mut blocks = vec![(0, body.as_slice())];
+            blocks.extend(
+                handlers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, h)| (i + 1, h.body.as_slice())),
+            );
+            let orelse_index = handlers.len() + 1;
+            if let Some(orelse) = orelse {
+                blocks.push((orelse_index, orelse.as_slice()));
+            }
+            if let Some(finalbody) = finalbody {
+                blocks.push((orelse_index + 1, finalbody.as_slice()));
+            }
+            blocks
+        }
+        _ => child_blocks(stmt).into_iter().enumerate().collect(),
+    }
+}
+
+/// Nested statement blocks of a statement
+fn child_blocks(stmt: &HirStmt) -> Vec<&[HirStmt]> {
+    match stmt {
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            let mut blocks = vec![then_body.as_slice()];
+            if let Some(else_body) = else_body {
+                blocks.push(else_body.as_slice());
+            }
+            blocks
+        }
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } | HirStmt::With { body, .. } => {
+            vec![body.as_slice()]
+        }
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => {
+            let mut blocks = vec![body.as_slice()];
+            blocks.extend(handlers.iter().map(|h| h.body.as_slice()));
+            if let Some(orelse) = orelse {
+                blocks.push(orelse.as_slice());
+            }
+            if let Some(finalbody) = finalbody {
+                blocks.push(finalbody.as_slice());
+            }
+            blocks
+        }
+        HirStmt::Match { cases, .. } => cases.iter().map(|c| c.body.as_slice()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn is_close_call(stmt: &HirStmt, var: &str) -> bool {
+    matches!(
+        stmt,
+        HirStmt::Expr(HirExpr::MethodCall { object, method, args, .. })
+            if method == "close" && args.is_empty() && matches!(object.as_ref(), HirExpr::Var(v) if v == var)
+    )
+}
+
+fn stmt_closes(stmt: &HirStmt, var: &str) -> bool {
+    is_close_call(stmt, var)
+        || child_blocks(stmt)
+            .into_iter()
+            .any(|body| body.iter().any(|s| stmt_closes(s, var)))
+}
+
+/// Position of an unconditional `var.close()` in the top-level block
+///
+/// A close inside a `finally:` block counts as unconditional.
+fn top_level_close_position(body: &[HirStmt], var: &str) -> Option<usize> {
+    body.iter().position(|stmt| {
+        is_close_call(stmt, var)
+            || matches!(stmt, HirStmt::Try { finalbody: Some(f), .. } if f.iter().any(|s| is_close_call(s, var)))
+    })
+}
+
+fn stmt_exits_early(stmt: &HirStmt) -> bool {
+    matches!(stmt, HirStmt::Return(_) | HirStmt::Raise { .. })
+        || child_blocks(stmt)
+            .into_iter()
+            .any(|body| body.iter().any(stmt_exits_early))
+}
+
+/// Whether the handle is returned or stored somewhere that outlives the function
+fn body_escapes(body: &[HirStmt], var: &str) -> bool {
+    body.iter().any(|stmt| match stmt {
+        HirStmt::Return(Some(expr)) => expr_mentions(expr, var),
+        HirStmt::Assign {
+            target: AssignTarget::Attribute { .. } | AssignTarget::Index { .. },
+            value,
+            ..
+        } => expr_mentions(value, var),
+        HirStmt::Expr(HirExpr::MethodCall { object, args, .. }) => {
+            !matches!(object.as_ref(), HirExpr::Var(v) if v == var)
+                && args
+                    .iter()
+                    .any(|a| matches!(a, HirExpr::Var(v) if v == var))
+        }
+        _ => child_blocks(stmt)
+            .into_iter()
+            .any(|nested| body_escapes(nested, var)),
+    })
+}
+
+/// Insert `with` scopes in a block and its nested blocks
+fn scope_block(stmts: &mut Vec<HirStmt>) -> usize {
+    let mut inserted = 0;
+    let mut i = 0;
+    while i < stmts.len() {
+        if let Some(close_at) = scopable_range(stmts, i) {
+            let mut tail = stmts.split_off(i);
+            let rest = tail.split_off(close_at - i + 1);
+            tail.pop(); // the close() call
+            let mut scoped = tail.into_iter();
+            if let Some(HirStmt::Assign {
+                target: AssignTarget::Symbol(var),
+                value,
+                ..
+            }) = scoped.next()
+            {
+                stmts.push(HirStmt::With {
+                    context: value,
+                    target: Some(var),
+                    body: scoped.collect(),
+                });
+                inserted += 1;
+            }
+            stmts.extend(rest);
+        }
+        inserted += scope_nested(&mut stmts[i]);
+        i += 1;
+    }
+    inserted
+}
+
+fn scope_nested(stmt: &mut HirStmt) -> usize {
+    match stmt {
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => scope_block(then_body) + else_body.as_mut().map_or(0, scope_block),
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } | HirStmt::With { body, .. } => {
+            scope_block(body)
+        }
+        _ => 0,
+    }
+}
+
+/// Index of the matching `close()` if `stmts[start]` can be turned into a `with`
+///
+/// # Complexity
+/// 7 (acquisition + close lookup + safety checks)
+fn scopable_range(stmts: &[HirStmt], start: usize) -> Option<usize> {
+    let HirStmt::Assign {
+        target: AssignTarget::Symbol(var),
+        value,
+        ..
+    } = &stmts[start]
+    else {
+        return None;
+    };
+    acquisition_kind(value)?;
+
+    let close_at = start
+        + stmts[start..]
+            .iter()
+            .position(|stmt| is_close_call(stmt, var))?;
+    let inner = &stmts[start + 1..close_at];
+    let after = &stmts[close_at + 1..];
+
+    if body_escapes(inner, var)
+        || inner.iter().any(|s| stmt_assigns(s, var))
+        || after.iter().any(|s| stmt_mentions(s, var))
+    {
+        return None;
+    }
+
+    // The `with` body becomes a Rust block, so bindings made inside it must not be needed later
+    let mut bound = HashSet::new();
+    for stmt in inner {
+        collect_bindings(stmt, &mut bound);
+    }
+    if bound
+        .iter()
+        .any(|name| after.iter().any(|s| stmt_mentions(s, name)))
+    {
+        return None;
+    }
+
+    Some(close_at)
+}
+
+fn stmt_assigns(stmt: &HirStmt, var: &str) -> bool {
+    let mut bound = HashSet::new();
+    collect_bindings(stmt, &mut bound);
+    bound.contains(var)
+}
+
+fn collect_bindings(stmt: &HirStmt, bound: &mut HashSet<String>) {
+    match stmt {
+        HirStmt::Assign { target, .. } => collect_target_bindings(target, bound),
+        HirStmt::For { target, .. } => collect_target_bindings(target, bound),
+        HirStmt::With {
+            target: Some(name), ..
+        } => {
+            bound.insert(name.clone());
+        }
+        HirStmt::Match { cases, .. } => {
+            bound.extend(cases.iter().flat_map(|c| c.pattern.bindings()));
+        }
+        _ => {}
+    }
+    for body in child_blocks(stmt) {
+        for s in body {
+            collect_bindings(s, bound);
+        }
+    }
+}
+
+fn collect_target_bindings(target: &AssignTarget, bound: &mut HashSet<String>) {
+    match target {
+        AssignTarget::Symbol(name) => {
+            bound.insert(name.clone());
+        }
+        AssignTarget::Tuple(targets) => {
+            for t in targets {
+                collect_target_bindings(t, bound);
+            }
+        }
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => {}
+    }
+}
+
+fn stmt_mentions(stmt: &HirStmt, var: &str) -> bool {
+    let own = match stmt {
+        HirStmt::Assign { target, value, .. } => {
+            target_mentions(target, var) || expr_mentions(value, var)
+        }
+        HirStmt::Return(Some(expr)) | HirStmt::Expr(expr) => expr_mentions(expr, var),
+        HirStmt::If { condition, .. } | HirStmt::While { condition, .. } => {
+            expr_mentions(condition, var)
+        }
+        HirStmt::For { iter, .. } => expr_mentions(iter, var),
+        HirStmt::With { context, .. } => expr_mentions(context, var),
+        HirStmt::Raise { exception, cause } => {
+            exception.as_ref().is_some_and(|e| expr_mentions(e, var))
+                || cause.as_ref().is_some_and(|e| expr_mentions(e, var))
+        }
+        HirStmt::Assert { test, msg } => {
+            expr_mentions(test, var) || msg.as_ref().is_some_and(|m| expr_mentions(m, var))
+        }
+        HirStmt::Match { subject, cases } => {
+            expr_mentions(subject, var)
+                || cases
+                    .iter()
+                    .any(|c| c.guard.as_ref().is_some_and(|g| expr_mentions(g, var)))
+        }
+        _ => false,
+    };
+    own || child_blocks(stmt)
+        .into_iter()
+        .any(|body| body.iter().any(|s| stmt_mentions(s, var)))
+}
+
+fn target_mentions(target: &AssignTarget, var: &str) -> bool {
+    match target {
+        AssignTarget::Symbol(_) => false,
+        AssignTarget::Index { base, index } => {
+            expr_mentions(base, var) || expr_mentions(index, var)
+        }
+        AssignTarget::Attribute { value, .. } => expr_mentions(value, var),
+        AssignTarget::Tuple(targets) => targets.iter().any(|t| target_mentions(t, var)),
+    }
+}
+
+fn expr_mentions(expr: &HirExpr, var: &str) -> bool {
+    let any = |exprs: &[HirExpr]| exprs.iter().any(|e| expr_mentions(e, var));
+    match expr {
+        HirExpr::Var(name) => name == var,
+        HirExpr::Binary { left, right, .. } => {
+            expr_mentions(left, var) || expr_mentions(right, var)
+        }
+        HirExpr::Unary { operand, .. } => expr_mentions(operand, var),
+        HirExpr::Call { args, kwargs, .. } => {
+            any(args) || kwargs.iter().any(|(_, v)| expr_mentions(v, var))
+        }
+        HirExpr::MethodCall {
+            object,
+            args,
+            kwargs,
+            ..
+        } => {
+            expr_mentions(object, var)
+                || any(args)
+                || kwargs.iter().any(|(_, v)| expr_mentions(v, var))
+        }
+        HirExpr::Index { base, index } => expr_mentions(base, var) || expr_mentions(index, var),
+        HirExpr::Slice {
+            base,
+            start,
+            stop,
+            step,
+        } => {
+            expr_mentions(base, var)
+                || [start, stop, step]
+                    .into_iter()
+                    .flatten()
+                    .any(|e| expr_mentions(e, var))
+        }
+        HirExpr::Attribute { value, .. }
+        | HirExpr::Borrow { expr: value, .. }
+        | HirExpr::Await { value } => expr_mentions(value, var),
+        HirExpr::List(items)
+        | HirExpr::Tuple(items)
+        | HirExpr::Set(items)
+        | HirExpr::FrozenSet(items) => any(items),
+        HirExpr::Dict(pairs) => pairs
+            .iter()
+            .any(|(k, v)| expr_mentions(k, var) || expr_mentions(v, var)),
+        HirExpr::ListComp {
+            element,
+            iter,
+            condition,
+            ..
+        }
+        | HirExpr::SetComp {
+            element,
+            iter,
+            condition,
+            ..
+        } => {
+            expr_mentions(element, var)
+                || expr_mentions(iter, var)
+                || condition.as_ref().is_some_and(|c| expr_mentions(c, var))
+        }
+        HirExpr::DictComp {
+            key,
+            value,
+            iter,
+            condition,
+            ..
+        } => {
+            expr_mentions(key, var)
+                || expr_mentions(value, var)
+                || expr_mentions(iter, var)
+                || condition.as_ref().is_some_and(|c| expr_mentions(c, var))
+        }
+        HirExpr::Lambda { body, .. } => expr_mentions(body, var),
+        HirExpr::FString { parts } => parts.iter().any(|part| match part {
+            crate::hir::FStringPart::Expr(e) => expr_mentions(e, var),
+            crate::hir::FStringPart::Literal(_) => false,
+        }),
+        HirExpr::Yield { value } => value.as_ref().is_some_and(|v| expr_mentions(v, var)),
+        HirExpr::IfExpr { test, body, orelse } => {
+            expr_mentions(test, var) || expr_mentions(body, var) || expr_mentions(orelse, var)
+        }
+        HirExpr::SortByKey {
+            iterable, key_body, ..
+        } => expr_mentions(iterable, var) || expr_mentions(key_body, var),
+        HirExpr::GeneratorExp {
+            element,
+            generators,
+        } => {
+            expr_mentions(element, var)
+                || generators.iter().any(|g| {
+                    expr_mentions(&g.iter, var)
+                        || g.conditions.iter().any(|c| expr_mentions(c, var))
+                })
+        }
+        HirExpr::Literal(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::*;
+    use crate::hir_builder::HirFunctionBuilder;
+
+    fn open_call(path: &str, mode: &str) -> HirExpr {
+        HirExpr::Call {
+            func: "open".to_string(),
+            args: vec![
+                HirExpr::Literal(Literal::String(path.to_string())),
+                HirExpr::Literal(Literal::String(mode.to_string())),
+            ],
+            kwargs: vec![],
+        }
+    }
+
+    fn assign(var: &str, value: HirExpr) -> HirStmt {
+        HirStmt::Assign {
+            target: AssignTarget::Symbol(var.to_string()),
+            value,
+            type_annotation: None,
+        }
+    }
+
+    fn method(var: &str, name: &str, args: Vec<HirExpr>) -> HirExpr {
+        HirExpr::MethodCall {
+            object: Box::new(HirExpr::Var(var.to_string())),
+            method: name.to_string(),
+            args,
+            kwargs: vec![],
+        }
+    }
+
+    fn analyze(body: Vec<HirStmt>) -> Vec<ResourceWarning> {
+        let program = HirProgram {
+            functions: vec![HirFunctionBuilder::new("process").body(body).build()],
+            classes: vec![],
+            imports: vec![],
+        };
+        ResourceLeakAnalyzer::new(ResourceLeakConfig::default()).analyze_program(&program)
+    }
+
+    #[test]
+    fn test_unclosed_open_is_reported() {
+        let warnings = analyze(vec![
+            assign("f", open_call("data.txt", "r")),
+            HirStmt::Expr(method("f", "read", vec![])),
+        ]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].issue, LeakIssue::NeverClosed);
+        assert_eq!(warnings[0].kind, ResourceKind::File);
+        assert!(!warnings[0].drop_timing_observable);
+    }
+
+    #[test]
+    fn test_reopen_before_close_is_observable() {
+        let warnings = analyze(vec![
+            assign("out", open_call("log.txt", "w")),
+            HirStmt::Expr(method(
+                "out",
+                "write",
+                vec![HirExpr::Literal(Literal::String("x".to_string()))],
+            )),
+            assign("inp", open_call("log.txt", "r")),
+            HirStmt::Expr(method("inp", "read", vec![])),
+            HirStmt::Expr(method("inp", "close", vec![])),
+            HirStmt::Expr(method("out", "close", vec![])),
+        ]);
+        let reopened = warnings
+            .iter()
+            .find(|w| matches!(w.issue, LeakIssue::ReopenedWhileOpen { .. }))
+            .expect("reopen should be reported");
+        assert_eq!(reopened.variable, "out");
+        assert!(reopened.drop_timing_observable);
+    }
+
+    #[test]
+    fn test_early_return_skips_close() {
+        let warnings = analyze(vec![
+            assign("f", open_call("data.txt", "r")),
+            HirStmt::If {
+                condition: HirExpr::Var("done".to_string()),
+                then_body: vec![HirStmt::Return(None)],
+                else_body: None,
+            },
+            HirStmt::Expr(method("f", "close", vec![])),
+        ]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].issue, LeakIssue::NotClosedOnAllPaths);
+    }
+
+    #[test]
+    fn test_warning_located_at_nested_acquisition() {
+        let source = "\
+def process(done):
+    if done:
+        pass
+    else:
+        f = open(\"data.txt\", \"r\")
+";
+        let warnings = analyze(vec![HirStmt::If {
+            condition: HirExpr::Var("done".to_string()),
+            then_body: vec![HirStmt::Pass],
+            else_body: Some(vec![assign("f", open_call("data.txt", "r"))]),
+        }]);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].path, vec![0, 1, 0]);
+
+        let analyzer = ResourceLeakAnalyzer::new(ResourceLeakConfig::default());
+        let spans = HirSpans::parse(source).unwrap();
+        let located = analyzer.format_warnings(&warnings, Some(&spans));
+        assert!(located.contains("process, line 5, column 9"), "{}", located);
+        let unlocated = analyzer.format_warnings(&warnings, None);
+        assert!(!unlocated.contains("line"), "{}", unlocated);
+    }
+
+    #[test]
+    fn test_insert_explicit_scope() {
+        let mut func = HirFunctionBuilder::new("process")
+            .body(vec![
+                assign("f", open_call("data.txt", "r")),
+                HirStmt::Expr(method("f", "read", vec![])),
+                HirStmt::Expr(method("f", "close", vec![])),
+                HirStmt::Pass,
+            ])
+            .build();
+        let analyzer = ResourceLeakAnalyzer::new(ResourceLeakConfig::default());
+        assert_eq!(analyzer.insert_explicit_scopes(&mut func), 1);
+        assert_eq!(func.body.len(), 2);
+        match &func.body[0] {
+            HirStmt::With { target, body, .. } => {
+                assert_eq!(target.as_deref(), Some("f"));
+                assert_eq!(body.len(), 1);
+            }
+            other => panic!("expected with statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_scope_not_inserted_when_binding_used_after_close() {
+        let mut func = HirFunctionBuilder::new("process")
+            .body(vec![
+                assign("f", open_call("data.txt", "r")),
+                assign("data", method("f", "read", vec![])),
+                HirStmt::Expr(method("f", "close", vec![])),
+                HirStmt::Return(Some(HirExpr::Var("data".to_string()))),
+            ])
+            .build();
+        let analyzer = ResourceLeakAnalyzer::new(ResourceLeakConfig::default());
+        assert_eq!(analyzer.insert_explicit_scopes(&mut func), 0);
+        assert_eq!(func.body.len(), 4);
+    }
+}
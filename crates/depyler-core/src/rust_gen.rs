@@ -1,28 +1,68 @@
 use crate::annotation_aware_type_mapper::AnnotationAwareTypeMapper;
+use crate::const_eval::{ConstEvaluator, ConstValue};
+use crate::derive_inference::{Derive, DeriveInference};
 use crate::hir::*;
 use crate::string_optimization::StringOptimizer;
+use crate::type_mapper::TypeProfile;
 use anyhow::Result;
+use depyler_annotations::{IntegerSemantics, PrintHandling, StringIndexing};
 use quote::{quote, ToTokens};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use syn::{self, parse_quote};
 
 // Module declarations for rust_gen refactoring (v3.18.0 Phases 2-7)
+mod arena_gen;
+pub(crate) mod assert_gen;
 mod argparse_transform;
+mod async_gen;
+mod borrowed_return_gen;
+mod cast_gen;
+mod class_module_gen;
+mod comparison_gen;
 mod context;
+mod context_manager_gen;
+mod contextlib_gen;
+mod default_param_gen;
+mod dict_lookup_gen;
 mod error_gen;
+mod error_message_gen;
 mod expr_gen;
 mod format;
 mod func_gen;
 mod generator_gen;
+mod hash_gen;
 mod import_gen;
+mod import_stub_gen;
+mod int_semantics_gen;
+mod io_gen;
+mod iter_gen;
 pub mod keywords; // DEPYLER-0023: Centralized keyword escaping
+pub mod ledger;
+mod lazy_global_gen;
+mod memoize_gen;
+mod numeric_parse_gen;
+mod options;
+pub(crate) mod percent_format_gen;
+mod print_gen;
+mod py_str_gen;
+mod slice_gen;
+mod sort_gen;
+pub(crate) mod seam_gen;
+pub mod source_map;
 mod stmt_gen;
+#[cfg(test)]
+mod test_support;
+mod type_alias_gen;
 mod type_gen;
+mod wasm_gen;
 
 // Internal imports
-use error_gen::generate_error_type_definitions;
-use format::format_rust_code;
-use import_gen::process_module_imports;
+use error_gen::{
+    generate_error_type_definitions, generate_exit_code_main, generate_module_error,
+    needs_exit_code_main, ModuleErrorModel,
+};
+pub(crate) use format::format_rust_code;
+use import_gen::{process_module_imports, project_module_mapper, register_project_imports};
 #[cfg(test)]
 use stmt_gen::{
     codegen_assign_attribute, codegen_assign_index, codegen_assign_symbol, codegen_assign_tuple,
@@ -33,6 +73,8 @@ use stmt_gen::{
 
 // Public re-exports for external modules (union_enum_gen, etc.)
 pub use context::{CodeGenContext, RustCodeGen, ToRustExpr};
+pub use options::{CodeGenOptions, ErrorModel, ExitCodeOptions, HashBackend, PerformanceProfile};
+pub use print_gen::{count_prints, PrintCounts};
 pub use type_gen::rust_type_to_syn;
 
 // Internal re-exports for cross-module access
@@ -83,6 +125,11 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
                 let is_mut = if is_mutating_method(method) {
                     // Built-in mutating method
                     true
+                } else if matches!(&**object, HirExpr::Var(var_name)
+                    if var_types.get(var_name).map(String::as_str) == Some(async_gen::QUEUE_TYPE))
+                {
+                    // q.get() receives from the asyncio.Queue channel
+                    method == "get"
                 } else if let HirExpr::Var(var_name) = &**object {
                     // Check if this is a user-defined mutating method
                     if let Some(class_name) = var_types.get(var_name) {
@@ -116,7 +163,13 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
             HirExpr::Unary { operand, .. } => {
                 analyze_expr_for_mutations(operand, mutable, var_types, mutating_methods);
             }
-            HirExpr::Call { args, .. } => {
+            HirExpr::Call { func, args, .. } => {
+                // next(it) advances the iterator held in `it`
+                if func == "next" {
+                    if let Some(HirExpr::Var(var_name)) = args.first() {
+                        mutable.insert(var_name.clone());
+                    }
+                }
                 for arg in args {
                     analyze_expr_for_mutations(arg, mutable, var_types, mutating_methods);
                 }
@@ -144,7 +197,7 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
                 analyze_expr_for_mutations(base, mutable, var_types, mutating_methods);
                 analyze_expr_for_mutations(index, mutable, var_types, mutating_methods);
             }
-            HirExpr::Attribute { value, .. } => {
+            HirExpr::Attribute { value, .. } | HirExpr::Await { value } => {
                 analyze_expr_for_mutations(value, mutable, var_types, mutating_methods);
             }
             _ => {}
@@ -169,6 +222,7 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
         mutable: &mut HashSet<String>,
         var_types: &mut HashMap<String, String>,
         mutating_methods: &HashMap<String, HashSet<String>>,
+        context_managers: &HashMap<String, context_manager_gen::ContextManagerClass>,
     ) {
         match stmt {
             HirStmt::Assign { target, value, .. } => {
@@ -181,6 +235,9 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
                         if let HirExpr::Call { func, .. } = value {
                             // Store the type (class name) for this variable
                             var_types.insert(name.clone(), func.clone());
+                        } else if let Some(Type::Custom(ty)) = async_gen::async_value_type(value) {
+                            // asyncio.Queue() and the other async objects
+                            var_types.insert(name.clone(), ty);
                         }
 
                         if declared.contains(name) {
@@ -236,11 +293,25 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
             } => {
                 analyze_expr_for_mutations(condition, mutable, var_types, mutating_methods);
                 for stmt in then_body {
-                    analyze_stmt(stmt, declared, mutable, var_types, mutating_methods);
+                    analyze_stmt(
+                        stmt,
+                        declared,
+                        mutable,
+                        var_types,
+                        mutating_methods,
+                        context_managers,
+                    );
                 }
                 if let Some(else_stmts) = else_body {
                     for stmt in else_stmts {
-                        analyze_stmt(stmt, declared, mutable, var_types, mutating_methods);
+                        analyze_stmt(
+                            stmt,
+                            declared,
+                            mutable,
+                            var_types,
+                            mutating_methods,
+                            context_managers,
+                        );
                     }
                 }
             }
@@ -249,20 +320,93 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
             } => {
                 analyze_expr_for_mutations(condition, mutable, var_types, mutating_methods);
                 for stmt in body {
-                    analyze_stmt(stmt, declared, mutable, var_types, mutating_methods);
+                    analyze_stmt(
+                        stmt,
+                        declared,
+                        mutable,
+                        var_types,
+                        mutating_methods,
+                        context_managers,
+                    );
                 }
             }
             HirStmt::For { body, .. } => {
                 for stmt in body {
-                    analyze_stmt(stmt, declared, mutable, var_types, mutating_methods);
+                    analyze_stmt(
+                        stmt,
+                        declared,
+                        mutable,
+                        var_types,
+                        mutating_methods,
+                        context_managers,
+                    );
+                }
+            }
+            HirStmt::With {
+                context,
+                target,
+                body,
+            } => {
+                analyze_expr_for_mutations(context, mutable, var_types, mutating_methods);
+                // The `__exit__` guard borrows the manager mutably
+                let class = match context {
+                    HirExpr::Var(name) => var_types.get(name).cloned(),
+                    HirExpr::Call { func, .. } => Some(func.clone()),
+                    _ => None,
+                };
+                if let Some(class) = class.filter(|c| context_managers.contains_key(c)) {
+                    if let HirExpr::Var(name) = context {
+                        mutable.insert(name.clone());
+                    }
+                    // `as` binds the manager itself, whose mutating calls need `let mut`
+                    if let Some(target) = target
+                        .as_ref()
+                        .filter(|_| context_managers[&class].enter_returns_self)
+                    {
+                        var_types.insert(target.clone(), class);
+                    }
+                }
+                for stmt in body {
+                    analyze_stmt(
+                        stmt,
+                        declared,
+                        mutable,
+                        var_types,
+                        mutating_methods,
+                        context_managers,
+                    );
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                analyze_expr_for_mutations(subject, mutable, var_types, mutating_methods);
+                for case in cases {
+                    for stmt in &case.body {
+                        analyze_stmt(
+                            stmt,
+                            declared,
+                            mutable,
+                            var_types,
+                            mutating_methods,
+                            context_managers,
+                        );
+                    }
                 }
             }
             _ => {}
         }
     }
 
-    let mut var_types = HashMap::new();
+    // Parameters annotated with a class count as instances of it, so calling one
+    // of its mutating methods makes the parameter mutable too
+    let mut var_types: HashMap<String, String> = params
+        .iter()
+        .filter_map(|param| match &param.ty {
+            Type::Custom(class) => Some((param.name.clone(), class.clone())),
+            _ => None,
+        })
+        .collect();
     let mutating_methods = &ctx.mutating_methods;
+    let context_managers = &ctx.context_manager_classes;
     for stmt in stmts {
         analyze_stmt(
             stmt,
@@ -270,29 +414,49 @@ fn analyze_mutable_vars(stmts: &[HirStmt], ctx: &mut CodeGenContext, params: &[H
             &mut ctx.mutable_vars,
             &mut var_types,
             mutating_methods,
+            context_managers,
         );
     }
 }
 
 /// Convert Python classes to Rust structs
 ///
-/// Processes all classes and generates token streams.
+/// Processes all classes and generates token streams, grouped by class.
 /// Complexity: 3 (well within ≤10 target)
 fn convert_classes_to_rust(
     classes: &[HirClass],
+    derives: &DeriveInference,
     type_mapper: &crate::type_mapper::TypeMapper,
-) -> Result<Vec<proc_macro2::TokenStream>> {
+) -> Result<Vec<(String, Vec<proc_macro2::TokenStream>)>> {
+    let mutating = crate::method_mutation::MutatingMethods::analyze(classes);
+    // Self-referential fields become Box<T> so the structs have a finite size
+    let classes = crate::recursive_types::RecursiveTypes::analyze(classes).box_classes(classes);
     let mut class_items = Vec::new();
-    for class in classes {
-        let items = crate::direct_rules::convert_class_to_struct(class, type_mapper)?;
-        for item in items {
-            let tokens = item.to_token_stream();
-            class_items.push(tokens);
-        }
+    for class in &classes {
+        let items =
+            crate::direct_rules::convert_class_to_struct(class, type_mapper, derives, &mutating)?;
+        let tokens: Vec<_> = items.iter().map(ToTokens::to_token_stream).collect();
+        class_items.push((class.name.clone(), tokens));
     }
     Ok(class_items)
 }
 
+/// Classes whose structs derive serde's traits: models, and dataclasses
+/// passed through `json`
+fn serde_classes(classes: &[HirClass], derives: &DeriveInference) -> HashSet<String> {
+    classes
+        .iter()
+        .filter(|c| {
+            c.model_kind.is_some()
+                || derives
+                    .derives(&c.name)
+                    .iter()
+                    .any(|d| matches!(d, Derive::Serialize | Derive::Deserialize))
+        })
+        .map(|c| c.name.clone())
+        .collect()
+}
+
 /// Convert HIR functions to Rust token streams
 ///
 /// Processes all functions using the code generation context.
@@ -342,12 +506,43 @@ fn deduplicate_use_statements(
     deduped
 }
 
+/// Generate `#[tokio::main]` for `asyncio.run(entry())` when `entry` is not `main`
+///
+/// An async `main()` entry is annotated directly by func_gen. A module that
+/// already defines some other `main()` keeps it, as Rust allows only one.
+fn generate_async_entry_main(functions: &[HirFunction]) -> Option<proc_macro2::TokenStream> {
+    if functions.iter().any(|f| f.name == "main") {
+        return None;
+    }
+    let entry = functions.iter().find(|f| f.properties.is_async_entry)?;
+    let name = keywords::safe_ident(&entry.name);
+    Some(if entry.properties.can_fail {
+        quote! {
+            #[tokio::main]
+            async fn main() -> Result<(), Box<dyn std::error::Error>> {
+                #name().await?;
+                Ok(())
+            }
+        }
+    } else {
+        quote! {
+            #[tokio::main]
+            async fn main() {
+                #name().await;
+            }
+        }
+    })
+}
+
 fn generate_conditional_imports(ctx: &CodeGenContext) -> Vec<proc_macro2::TokenStream> {
     let mut imports = Vec::new();
 
     // Define all possible conditional imports
     let conditional_imports = [
-        (ctx.needs_hashmap, quote! { use std::collections::HashMap; }),
+        (
+            ctx.needs_hashmap,
+            hash_gen::hash_map_import(ctx.options.hash_backend.unwrap_or_default()),
+        ),
         (ctx.needs_hashset, quote! { use std::collections::HashSet; }),
         (
             ctx.needs_vecdeque,
@@ -358,6 +553,7 @@ fn generate_conditional_imports(ctx: &CodeGenContext) -> Vec<proc_macro2::TokenS
         (ctx.needs_arc, quote! { use std::sync::Arc; }),
         (ctx.needs_rc, quote! { use std::rc::Rc; }),
         (ctx.needs_cow, quote! { use std::borrow::Cow; }),
+        (ctx.needs_stream_ext, quote! { use futures::StreamExt; }),
         (ctx.needs_serde_json, quote! { use serde_json; }),
     ];
 
@@ -378,6 +574,7 @@ fn generate_conditional_imports(ctx: &CodeGenContext) -> Vec<proc_macro2::TokenS
 fn generate_import_tokens(
     imports: &[Import],
     module_mapper: &crate::module_mapper::ModuleMapper,
+    stubbed: &HashSet<&str>,
 ) -> Vec<proc_macro2::TokenStream> {
     let mut items = Vec::new();
     let mut external_imports = Vec::new();
@@ -385,6 +582,10 @@ fn generate_import_tokens(
 
     // Categorize imports
     for import in imports {
+        // Stubbed modules are accounted for by their stub module
+        if stubbed.contains(import.module.as_str()) {
+            continue;
+        }
         let rust_imports = module_mapper.map_import(import);
         for rust_import in rust_imports {
             if rust_import.path.starts_with("//") {
@@ -458,21 +659,63 @@ fn generate_interned_string_tokens(optimizer: &StringOptimizer) -> Vec<proc_macr
         .collect()
 }
 
+/// Fold computed module constants into const tokens
+///
+/// Computed values that [`ConstEvaluator`] can fold, such as
+/// `[2**i for i in range(8)]`, become const scalars, tuples and slices.
+/// Literal constants are left to [`generate_constant_tokens`], so their
+/// entries are `None` like those of values that only exist at runtime.
+fn fold_constants(
+    constants: &[HirConstant],
+) -> Vec<Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)>> {
+    let mut evaluator = ConstEvaluator::new();
+    constants
+        .iter()
+        .map(|constant| {
+            let folded = evaluator.eval(&constant.value);
+            if let Some(value) = &folded {
+                evaluator.define(&constant.name, value.clone());
+            }
+            if matches!(constant.value, HirExpr::Literal(_)) {
+                return None;
+            }
+            let folded = match (&constant.type_annotation, folded) {
+                (Some(Type::Float), Some(ConstValue::Int(n))) => Some(ConstValue::Float(n as f64)),
+                (_, folded) => folded,
+            };
+            folded.as_ref().and_then(ConstValue::to_const_tokens)
+        })
+        .collect()
+}
+
 /// Generate module-level constant tokens
 ///
 /// Generates `pub const` declarations for module-level constants.
 /// For simple literal values (int, float, string, bool), generates const.
-/// For complex expressions, may need to use static or lazy_static.
+/// Folded values from [`fold_constants`] become consts of their folded type.
+/// Values computed at runtime are in `ctx.lazy_globals` and are skipped here;
+/// `lazy_global_gen` emits them as `LazyLock` statics.
 fn generate_constant_tokens(
     constants: &[HirConstant],
+    folded_constants: &[Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)>],
     ctx: &mut CodeGenContext,
 ) -> Result<Vec<proc_macro2::TokenStream>> {
     use crate::rust_gen::context::ToRustExpr;
     
     let mut items = Vec::new();
 
-    for constant in constants {
+    for (constant, folded) in constants.iter().zip(folded_constants) {
         let name_ident = syn::Ident::new(&constant.name, proc_macro2::Span::call_site());
+
+        if let Some((ty, value)) = folded {
+            items.push(quote! {
+                pub const #name_ident: #ty = #value;
+            });
+            continue;
+        }
+        if ctx.lazy_globals.contains_key(&constant.name) {
+            continue;
+        }
         
         // Generate the value expression
         let value_expr = constant.value.to_rust_expr(ctx)?;
@@ -504,16 +747,185 @@ fn generate_constant_tokens(
     Ok(items)
 }
 
+/// Python keeps same-named symbols apart by scope; once flattened into one
+/// Rust module, functions and constants share the value namespace and
+/// classes, protocols, type aliases and generator state structs share the
+/// type namespace, so a name claimed twice is reported here instead of
+/// surfacing as a rustc error in the generated code
+fn check_symbol_collisions(
+    functions: &[HirFunction],
+    constants: &[HirConstant],
+    module: &HirModule,
+) -> Result<()> {
+    use crate::symbol_names::{QualifiedName, SymbolTable};
+
+    let mut values = SymbolTable::default();
+    for func in functions {
+        values.reserve(&func.name, QualifiedName::new([func.name.as_str()]))?;
+    }
+    // Rebinding a module constant is fine; it still may not shadow a function
+    let mut seen_constants = HashSet::new();
+    for constant in constants {
+        if seen_constants.insert(constant.name.as_str()) {
+            values.reserve(&constant.name, QualifiedName::new([constant.name.as_str()]))?;
+        }
+    }
+
+    let mut types = SymbolTable::default();
+    let type_names = module
+        .classes
+        .iter()
+        .map(|c| (c.name.clone(), QualifiedName::new([c.name.as_str()])))
+        .chain(
+            module
+                .protocols
+                .iter()
+                .map(|p| (p.name.clone(), QualifiedName::new([p.name.as_str()]))),
+        )
+        .chain(
+            module
+                .type_aliases
+                .iter()
+                .map(|a| (a.name.clone(), QualifiedName::new([a.name.as_str()]))),
+        )
+        .chain(
+            functions
+                .iter()
+                .filter(|f| f.properties.is_generator && !f.properties.is_context_manager)
+                .map(|f| {
+                    let state = generator_gen::state_struct_name(&f.name);
+                    (state, QualifiedName::new([f.name.as_str(), "<state>"]))
+                }),
+        );
+    for (name, origin) in type_names {
+        types.reserve(&name, origin)?;
+    }
+    Ok(())
+}
+
 /// Generate a complete Rust file from HIR module
 pub fn generate_rust_file(
     module: &HirModule,
     type_mapper: &crate::type_mapper::TypeMapper,
 ) -> Result<String> {
-    let module_mapper = crate::module_mapper::ModuleMapper::new();
+    generate_rust_file_with_options(module, type_mapper, &CodeGenOptions::default())
+}
+
+/// Generate a complete Rust file from HIR module with explicit codegen options
+pub fn generate_rust_file_with_options(
+    module: &HirModule,
+    type_mapper: &crate::type_mapper::TypeMapper,
+    options: &CodeGenOptions,
+) -> Result<String> {
+    generate_rust_file_with_ledger(module, type_mapper, options).map(|(code, _)| code)
+}
+
+/// Generate a complete Rust file from HIR module, along with the deviations
+/// from Python its functions were generated with
+pub fn generate_rust_file_with_ledger(
+    module: &HirModule,
+    type_mapper: &crate::type_mapper::TypeMapper,
+    options: &CodeGenOptions,
+) -> Result<(String, ledger::DivergenceLedger)> {
+    // Type aliases stand for their targets in every annotation
+    let alias_mapper = type_mapper.clone().with_type_aliases(&module.type_aliases);
+    let type_mapper = &alias_mapper;
+    let resolved_module;
+    let module = if type_mapper.type_aliases.is_empty() {
+        module
+    } else {
+        resolved_module = type_alias_gen::resolve_module(module, type_mapper);
+        &resolved_module
+    };
+
+    // Rename a fallible main() so the exit-code wrapper can take its place
+    let wrap_main = needs_exit_code_main(&module.functions, &options.exit_codes);
+    let renamed_functions;
+    let module_functions: &[HirFunction] = if wrap_main {
+        renamed_functions = module
+            .functions
+            .iter()
+            .cloned()
+            .map(|mut f| {
+                if f.name == "main" {
+                    f.name = ExitCodeOptions::INNER_MAIN.to_string();
+                }
+                f
+            })
+            .collect::<Vec<_>>();
+        &renamed_functions
+    } else {
+        &module.functions
+    };
+
+    let elided_functions;
+    let module_functions: &[HirFunction] = if options.elide_dead_stores {
+        let effect_free = crate::dataflow::effect_free_functions(module_functions);
+        elided_functions = module_functions
+            .iter()
+            .cloned()
+            .map(|mut f| {
+                f.body = crate::dataflow::elide_dead_stores(&f.body, &effect_free);
+                f
+            })
+            .collect::<Vec<_>>();
+        &elided_functions
+    } else {
+        module_functions
+    };
+
+    // `__all__` only decides function visibility; it is not emitted as a value
+    let constants: Vec<HirConstant> = module
+        .constants
+        .iter()
+        .filter(|c| c.name != "__all__")
+        .cloned()
+        .collect();
+    check_symbol_collisions(module_functions, &constants, module)?;
+
+    // Functions passing dataclasses through serde_json propagate its errors
+    let derives = DeriveInference::analyze(&module.classes, module_functions);
+    let serde_functions;
+    let module_functions: &[HirFunction] = if derives.json_callers().next().is_some() {
+        let callers: HashSet<&str> = derives.json_callers().collect();
+        serde_functions = module_functions
+            .iter()
+            .cloned()
+            .map(|mut f| {
+                if callers.contains(f.name.as_str()) {
+                    f.properties.can_fail = true;
+                    f.properties
+                        .error_types
+                        .push("serde_json::Error".to_string());
+                }
+                f
+            })
+            .collect::<Vec<_>>();
+        &serde_functions
+    } else {
+        module_functions
+    };
+
+    // The whole file shares one `HashMap`, from annotations or the profile
+    let hash_backend = hash_gen::select_hash_backend(module_functions, options)?;
+    let profiled_mapper = type_mapper
+        .clone()
+        .with_profile(options.type_profile)
+        .with_arena_classes(&module.classes);
+    let type_mapper = &profiled_mapper;
+
+    let module_mapper = project_module_mapper(&options.project_modules);
 
     // Process imports to populate the context
-    let (imported_modules, imported_items) =
+    let (mut imported_modules, mut imported_items) =
         process_module_imports(&module.imports, &module_mapper);
+    let import_stubs = import_stub_gen::collect_stubs(module, &module_mapper, &options.pyi_stubs)?;
+    import_stub_gen::register_stubs(
+        &import_stubs,
+        &module.imports,
+        &mut imported_modules,
+        &mut imported_items,
+    );
 
     // Extract class names from module (DEPYLER-0230: distinguish user classes from builtins)
     let class_names: HashSet<String> = module
@@ -522,23 +934,18 @@ pub fn generate_rust_file(
         .map(|class| class.name.clone())
         .collect();
 
-    // DEPYLER-0231: Build map of mutating methods (class_name -> set of method names)
-    let mut mutating_methods: std::collections::HashMap<String, HashSet<String>> =
-        std::collections::HashMap::new();
-    for class in &module.classes {
-        let mut mut_methods = HashSet::new();
-        for method in &class.methods {
-            if crate::direct_rules::method_mutates_self(method) {
-                mut_methods.insert(method.name.clone());
-            }
-        }
-        mutating_methods.insert(class.name.clone(), mut_methods);
-    }
+    // DEPYLER-0231: Build map of mutating methods (class_name -> set of method names),
+    // following calls between methods so callers of mutating methods mutate too
+    let mutating_methods =
+        crate::method_mutation::MutatingMethods::analyze(&module.classes).into_map();
 
     let mut ctx = CodeGenContext {
         type_mapper,
         annotation_aware_mapper: AnnotationAwareTypeMapper::with_base_mapper(type_mapper.clone()),
-        string_optimizer: StringOptimizer::new(),
+        string_optimizer: match options.intern_threshold {
+            Some(threshold) => StringOptimizer::new().with_intern_threshold(threshold),
+            None => StringOptimizer::new(),
+        },
         union_enum_generator: crate::union_enum_gen::UnionEnumGenerator::new(),
         generated_enums: Vec::new(),
         needs_hashmap: false,
@@ -569,6 +976,14 @@ pub fn generate_rust_file(
         declared_vars: vec![HashSet::new()],
         current_function_can_fail: false,
         current_return_type: None,
+        current_borrowed_return: None,
+        current_return_is_cow: false,
+        deque_vars: HashSet::new(),
+        set_vars: HashSet::new(),
+        integer_semantics: IntegerSemantics::Panic,
+        string_indexing: StringIndexing::Chars,
+        print_handling: options.print_handling,
+        divergences: ledger::DivergenceLedger::default(),
         module_mapper,
         imported_modules,
         imported_items,
@@ -576,6 +991,19 @@ pub fn generate_rust_file(
         needs_zerodivisionerror: false,
         needs_indexerror: false,
         needs_valueerror: false,
+        needs_typeerror: false,
+        needs_stopiteration: false,
+        needs_overflowerror: false,
+        needs_py_int: false,
+        needs_py_float: false,
+        needs_py_repr: false,
+        needs_py_slice: false,
+        needs_py_slice_assign: false,
+        needs_py_slice_assign_step: false,
+        needs_py_str_slice: false,
+        needs_py_ascii_slice: false,
+        needs_py_subslice: false,
+        needs_stream_ext: false,
         in_generator: false,
         is_classmethod: false,
         generator_state_vars: HashSet::new(),
@@ -584,55 +1012,185 @@ pub fn generate_rust_file(
         mutating_methods,
         function_return_types: std::collections::HashMap::new(), // DEPYLER-0269: Track function return types
         function_param_borrows: std::collections::HashMap::new(), // DEPYLER-0270: Track parameter borrowing
+        function_signatures: std::collections::HashMap::new(),
         tuple_iter_vars: HashSet::new(), // DEPYLER-0307 Fix #9: Track tuple iteration variables
         is_final_statement: false, // DEPYLER-0271: Track final statement for expression-based returns
         result_bool_functions: HashSet::new(), // DEPYLER-0308: Track functions returning Result<bool>
         result_returning_functions: HashSet::new(), // DEPYLER-0270: Track ALL Result-returning functions
+        borrowed_return_functions: HashMap::new(),
+        cow_return_functions: HashSet::new(),
         current_error_type: None, // DEPYLER-0310: Track error type for raise statement wrapping
         exception_scopes: Vec::new(), // DEPYLER-0333: Exception scope tracking stack
         argparser_tracker: argparse_transform::ArgParserTracker::new(), // DEPYLER-0363: Track ArgumentParser patterns
+        options: CodeGenOptions {
+            hash_backend: Some(hash_backend),
+            ..options.clone()
+        },
+        current_handler: None,
+        context_manager_functions: HashSet::new(),
+        needs_context_guard: false,
+        needs_exit_stack: false,
+        context_manager_classes: context_manager_gen::context_manager_classes(&module.classes),
+        needs_exit_guards: BTreeSet::new(),
+        lazy_globals: HashMap::new(),
+        serde_classes: serde_classes(&module.classes, &derives),
+        class_fields: crate::field_inference::FieldTypeTable::analyze(&module.classes),
+        arenas: arena_gen::ArenaLayout::analyze(&module.classes, module_functions),
+        // Analyzed before `main` is renamed so the exit-code wrapper keeps it public
+        function_visibility: crate::visibility::VisibilityInference::analyze(
+            &module.functions,
+            &module.classes,
+            &module.constants,
+        ),
+        function_attributes: match &options.attribute_synthesis {
+            Some(attribute_options) => crate::attribute_synthesis::AttributeSynthesis::analyze(
+                module_functions,
+                &module.classes,
+                attribute_options,
+            ),
+            None => Default::default(),
+        },
     };
 
     // Analyze all functions first for string optimization
-    analyze_string_optimization(&mut ctx, &module.functions);
+    analyze_string_optimization(&mut ctx, module_functions);
+
+    // @contextmanager functions return (value, ContextGuard) instead of an iterator
+    for func in module_functions {
+        if func.properties.is_context_manager && func.properties.is_generator {
+            ctx.context_manager_functions.insert(func.name.clone());
+        }
+    }
+
+    // Calls into other project modules follow their signatures
+    register_project_imports(&module.imports, &options.project_modules, &mut ctx);
+
+    // Calls bind to the parameters of functions defined after them too
+    ctx.function_signatures = module_functions
+        .iter()
+        .map(|func| (func.name.clone(), default_param_gen::Signature::of(func)))
+        .collect();
 
     // DEPYLER-0270: Populate Result-returning functions map
     // All functions that can_fail return Result<T, E> and need unwrapping at call sites
-    for func in &module.functions {
-        if func.properties.can_fail {
+    for func in module_functions {
+        if func.properties.can_fail && !ctx.context_manager_functions.contains(&func.name) {
             ctx.result_returning_functions.insert(func.name.clone());
         }
     }
 
+    // Functions returning a reference into a parameter, plain or as a
+    // `Cow`; their callers keep taking an owned value
+    for func in module_functions {
+        let mut lifetimes = crate::lifetime_analysis::LifetimeInference::new();
+        if let Some(result) = lifetimes.apply_elision_rules(func, ctx.type_mapper) {
+            if let Some(position) = result.borrowed_return_param(func) {
+                ctx.borrowed_return_functions
+                    .insert(func.name.clone(), position);
+            }
+            if result.cow_return_source(func).is_some() {
+                ctx.cow_return_functions.insert(func.name.clone());
+            }
+        }
+    }
+
+    // Dataclass constructors whose `__post_init__` raises return Result<Self, E>
+    for class in &module.classes {
+        if let Some(error_type) = crate::direct_rules::post_init_error_type(class) {
+            ctx.result_returning_functions.insert(class.name.clone());
+            ctx.needs_zerodivisionerror |= error_type.contains("ZeroDivisionError");
+            ctx.needs_indexerror |= error_type.contains("IndexError");
+            ctx.needs_valueerror |= error_type.contains("ValueError");
+            ctx.needs_typeerror |= error_type.contains("TypeError");
+            ctx.needs_stopiteration |= error_type.contains("StopIteration");
+            ctx.needs_overflowerror |= error_type.contains("OverflowError");
+        }
+    }
+
+    // One error enum for the whole module, when asked for
+    let module_error = if options.error_model == ErrorModel::ModuleEnum {
+        ModuleErrorModel::collect(module_functions, &ctx.class_names)
+    } else {
+        None
+    };
+    if let Some(model) = &module_error {
+        model.mark_needed(&mut ctx);
+    }
+
     // DEPYLER-0308: Populate Result<bool> functions map
     // Functions that can_fail and return Bool need unwrapping in boolean contexts
-    for func in &module.functions {
+    for func in module_functions {
         if func.properties.can_fail && matches!(func.ret_type, Type::Bool) {
             ctx.result_bool_functions.insert(func.name.clone());
         }
     }
 
+    // Module values neither literal nor foldable are initialized lazily
+    let folded_constants = fold_constants(&constants);
+    ctx.lazy_globals = lazy_global_gen::find_lazy_globals(
+        &constants,
+        &folded_constants,
+        module_functions,
+        &ctx.class_names,
+    );
+
+    let type_aliases = type_alias_gen::generate_type_aliases(&module.type_aliases, &mut ctx)?;
+
     // Convert classes first (they might be used by functions)
-    let classes = convert_classes_to_rust(&module.classes, ctx.type_mapper)?;
+    let classes = convert_classes_to_rust(&module.classes, &derives, ctx.type_mapper)?;
 
     // Convert all functions to detect what imports we need
-    let functions = convert_functions_to_rust(&module.functions, &mut ctx)?;
+    let functions = convert_functions_to_rust(module_functions, &mut ctx)?;
+    let functions = seam_gen::apply_seams(functions, module_functions, &options.seams)?;
+    let (functions, classes) = if options.injectable_io {
+        io_gen::inject_streams(functions, classes)?
+    } else {
+        (functions, classes)
+    };
+    let functions = cast_gen::elide_redundant_casts(functions);
+    let (functions, wasm_exports) = match options.type_profile {
+        TypeProfile::Wasm => wasm_gen::export_functions(functions),
+        _ => (functions, false),
+    };
+    let exit_guards = context_manager_gen::generate_exit_guards(&mut ctx);
 
     // Build items list with all generated code
     let mut items = Vec::new();
 
     // Add module imports (create new mapper for token generation)
-    let import_mapper = crate::module_mapper::ModuleMapper::new();
-    items.extend(generate_import_tokens(&module.imports, &import_mapper));
+    let import_mapper = project_module_mapper(&options.project_modules);
+    let stubbed: HashSet<&str> = import_stubs.iter().map(|s| s.module.as_str()).collect();
+    items.extend(generate_import_tokens(
+        &module.imports,
+        &import_mapper,
+        &stubbed,
+    ));
+    items.extend(import_stub_gen::generate_stub_modules(
+        &import_stubs,
+        &module.imports,
+        &mut ctx,
+    )?);
 
     // Add interned string constants
     items.extend(generate_interned_string_tokens(&ctx.string_optimizer));
 
     // Add module-level constants
-    items.extend(generate_constant_tokens(&module.constants, &mut ctx)?);
+    items.extend(generate_constant_tokens(
+        &constants,
+        &folded_constants,
+        &mut ctx,
+    )?);
+    items.extend(lazy_global_gen::generate_lazy_globals(
+        &constants,
+        module_functions,
+        &mut ctx,
+    )?);
 
     // Add collection imports if needed
     items.extend(generate_conditional_imports(&ctx));
+    if wasm_exports {
+        items.push(quote! { use wasm_bindgen::prelude::*; });
+    }
 
     // DEPYLER-0335 FIX #1: Deduplicate imports across all sources
     // Both generate_import_tokens and generate_conditional_imports can add HashMap
@@ -640,29 +1198,69 @@ pub fn generate_rust_file(
 
     // Add error type definitions if needed
     items.extend(generate_error_type_definitions(&ctx));
+    if let Some(model) = &module_error {
+        items.extend(generate_module_error(model));
+    }
+    items.extend(numeric_parse_gen::generate_parse_helpers(&ctx));
+    items.extend(py_str_gen::generate_py_repr(&ctx));
+    items.extend(slice_gen::generate_slice_helpers(&ctx));
+
+    // Add ContextGuard / ExitStack if contextlib constructs were lowered
+    items.extend(contextlib_gen::generate_contextlib_definitions(&ctx));
+    items.extend(exit_guards);
 
     // Add generated union enums
     items.extend(ctx.generated_enums.clone());
 
-    // Add classes
-    items.extend(classes);
+    // Add type aliases and newtypes
+    items.extend(type_aliases);
+
+    // Add classes, each in a module of its own when asked to
+    if options.class_modules {
+        items.extend(class_module_gen::nest_classes(
+            &module.classes,
+            module_functions,
+            classes,
+        ));
+    } else {
+        items.extend(classes.into_iter().flat_map(|(_, tokens)| tokens));
+    }
+    items.extend(arena_gen::generate_arenas(&module.classes));
 
     // Add all functions
     items.extend(functions);
 
+    // Report exceptions escaping main() the way Python does
+    if wrap_main {
+        let async_main = module
+            .functions
+            .iter()
+            .any(|f| f.name == "main" && f.properties.is_async_entry);
+        items.push(generate_exit_code_main(
+            &ctx,
+            &options.exit_codes,
+            module_error.as_ref(),
+            async_main,
+        ));
+    }
+
+    // Drive the coroutine passed to `asyncio.run()` from a tokio main()
+    items.extend(generate_async_entry_main(&module.functions));
+
     // Generate tests for all functions in a single test module
     // DEPYLER-0280 FIX: Use generate_tests_module() to create a single `mod tests {}` block
     // instead of one per function, which caused "the name `tests` is defined multiple times" errors
     let test_gen = crate::test_generation::TestGenerator::new(Default::default());
-    if let Some(test_module) = test_gen.generate_tests_module(&module.functions)? {
+    if let Some(test_module) = test_gen.generate_tests_module(module_functions)? {
         items.push(test_module);
     }
 
     let file = quote! {
         #(#items)*
     };
+    let file = hash_gen::apply_hash_backend(file, hash_backend);
 
-    Ok(format_rust_code(file.to_string()))
+    Ok((format_rust_code(file.to_string()), ctx.divergences))
 }
 
 #[cfg(test)]
@@ -714,6 +1312,14 @@ mod tests {
             declared_vars: vec![HashSet::new()],
             current_function_can_fail: false,
             current_return_type: None,
+            current_borrowed_return: None,
+            current_return_is_cow: false,
+            deque_vars: HashSet::new(),
+            set_vars: HashSet::new(),
+            integer_semantics: IntegerSemantics::Panic,
+            string_indexing: StringIndexing::Chars,
+            print_handling: PrintHandling::Keep,
+            divergences: ledger::DivergenceLedger::default(),
             module_mapper: crate::module_mapper::ModuleMapper::new(),
             imported_modules: std::collections::HashMap::new(),
             imported_items: std::collections::HashMap::new(),
@@ -721,6 +1327,19 @@ mod tests {
             needs_zerodivisionerror: false,
             needs_indexerror: false,
             needs_valueerror: false,
+            needs_typeerror: false,
+            needs_stopiteration: false,
+            needs_overflowerror: false,
+            needs_py_int: false,
+            needs_py_float: false,
+            needs_py_repr: false,
+            needs_py_slice: false,
+            needs_py_slice_assign: false,
+            needs_py_slice_assign_step: false,
+            needs_py_str_slice: false,
+            needs_py_ascii_slice: false,
+            needs_py_subslice: false,
+            needs_stream_ext: false,
             is_classmethod: false,
             in_generator: false,
             generator_state_vars: HashSet::new(),
@@ -729,13 +1348,29 @@ mod tests {
             mutating_methods: std::collections::HashMap::new(),
             function_return_types: std::collections::HashMap::new(), // DEPYLER-0269: Track function return types
             function_param_borrows: std::collections::HashMap::new(), // DEPYLER-0270: Track parameter borrowing
+            function_signatures: std::collections::HashMap::new(),
             tuple_iter_vars: HashSet::new(), // DEPYLER-0307 Fix #9: Track tuple iteration variables
             is_final_statement: false, // DEPYLER-0271: Track final statement for expression-based returns
             result_bool_functions: HashSet::new(), // DEPYLER-0308: Track functions returning Result<bool>
             result_returning_functions: HashSet::new(), // DEPYLER-0270: Track ALL Result-returning functions
+            borrowed_return_functions: HashMap::new(),
+            cow_return_functions: HashSet::new(),
             current_error_type: None, // DEPYLER-0310: Track error type for raise statement wrapping
             exception_scopes: Vec::new(), // DEPYLER-0333: Exception scope tracking stack
             argparser_tracker: argparse_transform::ArgParserTracker::new(), // DEPYLER-0363: Track ArgumentParser patterns
+            options: CodeGenOptions::default(),
+            current_handler: None,
+            context_manager_functions: HashSet::new(),
+            needs_context_guard: false,
+            needs_exit_stack: false,
+            context_manager_classes: HashMap::new(),
+            needs_exit_guards: BTreeSet::new(),
+            lazy_globals: std::collections::HashMap::new(),
+            serde_classes: HashSet::new(),
+            class_fields: Default::default(),
+            arenas: Default::default(),
+            function_visibility: Default::default(),
+            function_attributes: Default::default(),
         }
     }
 
@@ -969,7 +1604,7 @@ mod tests {
         let result = codegen_raise_stmt(&None, &mut ctx).unwrap();
         assert_eq!(
             result.to_string(),
-            "return Err (\"Exception raised\" . into ()) ;"
+            "return Err (\"No active exception to reraise\" . into ()) ;"
         );
     }
 
@@ -1364,7 +1999,7 @@ mod tests {
             code
         );
 
-        // Test 3: .strip() should also generate String return type
+        // Test 3: .strip() returns a slice of the parameter instead
         let strip_func = HirFunction {
             name: "trim_text".to_string(),
             params: vec![HirParam::new("text".to_string(), Type::String)].into(),
@@ -1385,8 +2020,8 @@ mod tests {
         let code = result.to_string();
 
         assert!(
-            code.contains("-> String"),
-            "Expected '-> String' for .strip() method, got: {}",
+            code.contains("-> & 'a str"),
+            "Expected '-> &'a str' for .strip() method, got: {}",
             code
         );
     }
@@ -1421,10 +2056,10 @@ mod tests {
         let result = divide_func.to_rust_tokens(&mut ctx).unwrap();
         let code = result.to_string();
 
-        // Should generate: (a as f64) / (b as f64)
+        // Should generate: f64::from(a) / f64::from(b)
         // NOT: a / b (which would do integer division)
         assert!(
-            code.contains("as f64") || code.contains("as f32"),
+            code.contains("f64 :: from (a) / f64 :: from (b)"),
             "Expected float cast for int/int division with float return, got: {}",
             code
         );
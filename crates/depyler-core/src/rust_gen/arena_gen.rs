@@ -0,0 +1,731 @@
+//! Arena storage for classes annotated `ownership = "arena"`
+//!
+//! Instances of a Python class refer to each other freely, so linked
+//! lists, trees with parent links and graphs with cycles are ordinary
+//! Python. Rust only shares mutable objects like that through
+//! `Rc<RefCell<T>>`. For a class annotated
+//!
+//! ```python
+//! # @depyler: ownership = "arena"
+//! @dataclass
+//! class Node:
+//!     value: int
+//!     next: Optional["Node"] = None
+//! ```
+//!
+//! the instances live in a `NodeArena` instead, a `Vec<Node>`, and are
+//! named by `NodeId` indices into it. `NodeId` is `Copy`, and fields,
+//! parameters and locals typed `Node` hold one:
+//!
+//! | Python              | Rust                                              |
+//! |---------------------|---------------------------------------------------|
+//! | `n = Node(1)`       | `let n = node_arena.alloc(Node::new(1));`         |
+//! | `n.value`           | `node_arena[n].value`                             |
+//! | `n.next.value`      | `node_arena[node_arena[n].next.unwrap()].value`   |
+//! | `n.next = m`        | `node_arena[n].next = Some(m);`                   |
+//! | `n.items.append(x)` | `node_arena[n].items.push(x)`                     |
+//! | `n.describe()`      | `node_arena[n].describe()`                        |
+//!
+//! Fields that are not `Copy` are cloned out of the arena when read.
+//!
+//! A function with the class in its signature takes the arena as a leading
+//! `node_arena: &mut NodeArena` parameter, which its callers pass along.
+//! Other functions creating instances, or calling functions that take the
+//! arena, start from an empty arena of their own.
+//!
+//! Methods still run on the one instance they are called on: they read and
+//! write its fields and see the handles it holds, but not the arena.
+
+use crate::hir::{AssignTarget, HirClass, HirExpr, HirFunction, HirStmt, Literal, Type};
+use crate::lifetime_analysis::LifetimeResult;
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::keywords::safe_ident;
+use crate::symbol_names::snake_case;
+use anyhow::Result;
+use depyler_annotations::OwnershipModel;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, ToTokens};
+use std::collections::{BTreeSet, HashMap};
+use syn::parse_quote;
+
+/// The arena classes of a module and the functions working on them
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ArenaLayout {
+    /// Arena class -> return types of its methods
+    classes: HashMap<String, HashMap<String, Type>>,
+    /// Function -> arena classes whose arenas it takes
+    params: HashMap<String, BTreeSet<String>>,
+    /// Function -> arena classes it keeps an arena of its own for
+    locals: HashMap<String, BTreeSet<String>>,
+    /// Return types of the functions taking arenas
+    returns: HashMap<String, Type>,
+}
+
+impl ArenaLayout {
+    pub(crate) fn analyze(classes: &[HirClass], functions: &[HirFunction]) -> Self {
+        let mut layout = Self {
+            classes: classes
+                .iter()
+                .filter(|class| class.annotations.ownership_model == OwnershipModel::Arena)
+                .map(|class| {
+                    let methods = class
+                        .methods
+                        .iter()
+                        .map(|method| (method.name.clone(), method.ret_type.clone()))
+                        .collect();
+                    (class.name.clone(), methods)
+                })
+                .collect(),
+            ..Self::default()
+        };
+        if layout.classes.is_empty() {
+            return layout;
+        }
+
+        for func in functions {
+            let mut taken = BTreeSet::new();
+            for ty in func.params.iter().map(|p| &p.ty).chain([&func.ret_type]) {
+                layout.collect_classes(ty, &mut taken);
+            }
+            if !taken.is_empty() {
+                layout.params.insert(func.name.clone(), taken);
+                layout
+                    .returns
+                    .insert(func.name.clone(), func.ret_type.clone());
+            }
+        }
+        for func in functions {
+            let mut used = BTreeSet::new();
+            layout.collect_uses(&func.body, &mut used);
+            if let Some(taken) = layout.params.get(&func.name) {
+                used.retain(|class| !taken.contains(class));
+            }
+            if !used.is_empty() {
+                layout.locals.insert(func.name.clone(), used);
+            }
+        }
+        layout
+    }
+
+    pub(crate) fn is_arena(&self, class: &str) -> bool {
+        self.classes.contains_key(class)
+    }
+
+    /// Adds the arena classes `ty` names, however deeply nested
+    fn collect_classes(&self, ty: &Type, out: &mut BTreeSet<String>) {
+        match ty {
+            Type::Custom(name) if self.is_arena(name) => {
+                out.insert(name.clone());
+            }
+            Type::List(inner) | Type::Set(inner) | Type::Optional(inner) | Type::Final(inner) => {
+                self.collect_classes(inner, out)
+            }
+            Type::Dict(key, value) => {
+                self.collect_classes(key, out);
+                self.collect_classes(value, out);
+            }
+            Type::Tuple(items) | Type::Union(items) => {
+                for item in items {
+                    self.collect_classes(item, out);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Adds the arena classes `stmts` construct, annotate locals with or
+    /// need the arena of for a call
+    fn collect_uses(&self, stmts: &[HirStmt], used: &mut BTreeSet<String>) {
+        for stmt in stmts {
+            if let HirStmt::Assign {
+                type_annotation: Some(ty),
+                ..
+            } = stmt
+            {
+                self.collect_classes(ty, used);
+            }
+            for expr in crate::attribute_synthesis::stmt_exprs(stmt) {
+                self.collect_expr_uses(expr, used);
+            }
+            for body in stmt.nested_bodies() {
+                self.collect_uses(body, used);
+            }
+        }
+    }
+
+    fn collect_expr_uses(&self, expr: &HirExpr, used: &mut BTreeSet<String>) {
+        if let HirExpr::Call { func, .. } = expr {
+            if self.is_arena(func) {
+                used.insert(func.clone());
+            }
+            if let Some(taken) = self.params.get(func) {
+                used.extend(taken.iter().cloned());
+            }
+        }
+        for child in expr.children() {
+            self.collect_expr_uses(child, used);
+        }
+    }
+}
+
+/// `NodeId`
+fn id_type(class: &str) -> syn::Ident {
+    format_ident!("{}Id", class)
+}
+
+/// `NodeArena`
+fn arena_type(class: &str) -> syn::Ident {
+    format_ident!("{}Arena", class)
+}
+
+/// `node_arena`
+fn arena_var(class: &str) -> syn::Ident {
+    format_ident!("{}_arena", snake_case(class))
+}
+
+/// The handle type and arena of each arena class among `classes`
+pub(crate) fn generate_arenas(classes: &[HirClass]) -> Vec<TokenStream> {
+    classes
+        .iter()
+        .filter(|class| class.annotations.ownership_model == OwnershipModel::Arena)
+        .map(|class| {
+            let class_ident = format_ident!("{}", class.name);
+            let id = id_type(&class.name);
+            let arena = arena_type(&class.name);
+            quote! {
+                #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+                pub struct #id(pub usize);
+
+                #[derive(Default)]
+                pub struct #arena {
+                    items: Vec<#class_ident>,
+                }
+
+                impl #arena {
+                    pub fn alloc(&mut self, item: #class_ident) -> #id {
+                        self.items.push(item);
+                        #id(self.items.len() - 1)
+                    }
+
+                    pub fn len(&self) -> usize {
+                        self.items.len()
+                    }
+
+                    pub fn is_empty(&self) -> bool {
+                        self.items.is_empty()
+                    }
+                }
+
+                impl std::ops::Index<#id> for #arena {
+                    type Output = #class_ident;
+                    fn index(&self, id: #id) -> &#class_ident {
+                        &self.items[id.0]
+                    }
+                }
+
+                impl std::ops::IndexMut<#id> for #arena {
+                    fn index_mut(&mut self, id: #id) -> &mut #class_ident {
+                        &mut self.items[id.0]
+                    }
+                }
+            }
+        })
+        .collect()
+}
+
+/// `node_arena: &mut NodeArena` for each arena `func` takes
+pub(crate) fn arena_params(func: &str, ctx: &CodeGenContext) -> Vec<TokenStream> {
+    ctx.arenas
+        .params
+        .get(func)
+        .into_iter()
+        .flatten()
+        .map(|class| {
+            let var = arena_var(class);
+            let arena = arena_type(class);
+            quote! { #var: &mut #arena }
+        })
+        .collect()
+}
+
+/// `let node_arena = &mut NodeArena::default();` for each arena `func`
+/// keeps of its own
+pub(crate) fn local_arenas(func: &str, ctx: &CodeGenContext) -> Vec<TokenStream> {
+    ctx.arenas
+        .locals
+        .get(func)
+        .into_iter()
+        .flatten()
+        .map(|class| {
+            let var = arena_var(class);
+            let arena = arena_type(class);
+            quote! { let #var = &mut #arena::default(); }
+        })
+        .collect()
+}
+
+/// The arguments of a call to `func`, after the arenas it takes
+///
+/// Arguments using an arena are bound to locals first, as the arena is
+/// borrowed from the start of the call.
+pub(crate) fn with_arena_args(
+    func: &str,
+    args: Vec<syn::Expr>,
+    ctx: &CodeGenContext,
+) -> (Vec<syn::Stmt>, Vec<syn::Expr>) {
+    let Some(classes) = ctx.arenas.params.get(func) else {
+        return (Vec::new(), args);
+    };
+    let (hoisted, args) = hoist_arena_args(args, ctx);
+    let mut call_args: Vec<syn::Expr> = classes
+        .iter()
+        .map(|class| {
+            let var = arena_var(class);
+            parse_quote! { #var }
+        })
+        .collect();
+    call_args.extend(args);
+    (hoisted, call_args)
+}
+
+/// Passes the handles `func` takes by value, as they are `Copy`
+pub(crate) fn pass_handles_by_value(
+    func: &HirFunction,
+    lifetime_result: &mut LifetimeResult,
+    ctx: &CodeGenContext,
+) {
+    for param in func
+        .params
+        .iter()
+        .filter(|p| handle_class(&p.ty, ctx).is_some())
+    {
+        lifetime_result.borrowing_strategies.remove(&param.name);
+        if let Some(inferred) = lifetime_result.param_lifetimes.get_mut(&param.name) {
+            inferred.should_borrow = false;
+            inferred.needs_mut = false;
+        }
+    }
+}
+
+/// `node_arena.alloc(Node::new(args))`, constructing an instance of the
+/// arena class `func` in its arena
+pub(crate) fn codegen_alloc(
+    func: &str,
+    args: &[syn::Expr],
+    fallible: bool,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    let (hoisted, args) = hoist_arena_args(args.to_vec(), ctx);
+    let class_ident = format_ident!("{}", func);
+    let item: syn::Expr = if fallible {
+        parse_quote! { #class_ident::new(#(#args),*)? }
+    } else {
+        parse_quote! { #class_ident::new(#(#args),*) }
+    };
+    let var = arena_var(func);
+    with_hoisted(hoisted, parse_quote! { #var.alloc(#item) })
+}
+
+/// The arena class a value of type `ty` is a handle of, and whether the
+/// handle may be `None`
+fn handle_class(ty: &Type, ctx: &CodeGenContext) -> Option<(String, bool)> {
+    match ty {
+        Type::Custom(name) if ctx.arenas.is_arena(name) => Some((name.clone(), false)),
+        Type::Optional(inner) => match &**inner {
+            Type::Custom(name) if ctx.arenas.is_arena(name) => Some((name.clone(), true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The type of `expr`, following fields, methods and calls through handles
+fn value_type(expr: &HirExpr, ctx: &CodeGenContext) -> Type {
+    match expr {
+        HirExpr::Call { func, .. } if ctx.arenas.is_arena(func) => Type::Custom(func.clone()),
+        HirExpr::Call { func, .. } if ctx.arenas.returns.contains_key(func) => {
+            ctx.arenas.returns[func].clone()
+        }
+        HirExpr::Attribute { value, attr } => match handle_of(value, ctx) {
+            Some((class, _)) => ctx
+                .class_fields
+                .field_type(&class, attr)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+            None => ctx.value_type(expr),
+        },
+        HirExpr::MethodCall { object, method, .. } => match handle_of(object, ctx) {
+            Some((class, _)) => ctx.arenas.classes[&class]
+                .get(method)
+                .cloned()
+                .unwrap_or(Type::Unknown),
+            None => ctx.value_type(expr),
+        },
+        HirExpr::List(items) => Type::List(Box::new(
+            items
+                .first()
+                .map(|item| value_type(item, ctx))
+                .unwrap_or(Type::Unknown),
+        )),
+        HirExpr::Index { base, .. } => match value_type(base, ctx) {
+            Type::List(elem) => *elem,
+            _ => ctx.value_type(expr),
+        },
+        _ => ctx.value_type(expr),
+    }
+}
+
+fn handle_of(expr: &HirExpr, ctx: &CodeGenContext) -> Option<(String, bool)> {
+    if ctx.arenas.classes.is_empty() {
+        return None;
+    }
+    handle_class(&value_type(expr, ctx), ctx)
+}
+
+/// Whether `expr` is a handle of an arena class
+pub(crate) fn is_handle(expr: &HirExpr, ctx: &CodeGenContext) -> bool {
+    handle_of(expr, ctx).is_some()
+}
+
+/// Whether `expr` is a handle of an arena class that may be `None`
+pub(crate) fn is_optional_handle(expr: &HirExpr, ctx: &CodeGenContext) -> bool {
+    matches!(handle_of(expr, ctx), Some((_, true)))
+}
+
+/// Whether a value of type `ty` is copied rather than cloned out of an arena
+fn is_copy(ty: &Type, ctx: &CodeGenContext) -> bool {
+    handle_class(ty, ctx).is_some() || ctx.type_mapper.can_copy(&ctx.type_mapper.map_type(ty))
+}
+
+/// `node_arena[id]`, for the instance the handle `object` names
+///
+/// Writing through the place keeps the arena borrowed while `id` is
+/// computed, so with `mutable` an `id` read out of an arena is bound to a
+/// local first.
+fn instance_place(
+    object: &HirExpr,
+    class: &str,
+    optional: bool,
+    mutable: bool,
+    ctx: &mut CodeGenContext,
+) -> Result<(Vec<syn::Stmt>, syn::Expr)> {
+    let handle = object.to_rust_expr(ctx)?;
+    let mut id: syn::Expr = if optional {
+        parse_quote! { #handle.unwrap() }
+    } else {
+        handle
+    };
+    let mut setup = Vec::new();
+    if mutable && mentions_any(&id.to_token_stream(), &arena_vars(ctx)) {
+        setup.push(parse_quote! { let __id = #id; });
+        id = parse_quote! { __id };
+    }
+    let var = arena_var(class);
+    Ok((setup, parse_quote! { #var[#id] }))
+}
+
+/// `node_arena[id].attr` and the type of the field, for `object` a field
+/// of an arena instance
+fn field_parts(
+    object: &HirExpr,
+    mutable: bool,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<(Vec<syn::Stmt>, syn::Expr, Type)>> {
+    let HirExpr::Attribute { value, attr } = object else {
+        return Ok(None);
+    };
+    let Some((class, optional)) = handle_of(value, ctx) else {
+        return Ok(None);
+    };
+    let Some(field_type) = ctx.class_fields.field_type(&class, attr).cloned() else {
+        return Ok(None);
+    };
+    let (setup, instance) = instance_place(value, &class, optional, mutable, ctx)?;
+    let attr_ident = safe_ident(attr);
+    Ok(Some((
+        setup,
+        parse_quote! { #instance.#attr_ident },
+        field_type,
+    )))
+}
+
+/// `node_arena[id].attr`, for reading a field of an arena instance where
+/// it lives rather than from a copy
+pub(crate) fn field_place(object: &HirExpr, ctx: &mut CodeGenContext) -> Result<Option<syn::Expr>> {
+    Ok(field_parts(object, false, ctx)?.map(|(_, place, _)| place))
+}
+
+/// `node_arena[id].attr` for calling a method on a field of an arena
+/// instance, and the statements to run first
+pub(crate) fn field_place_mut(
+    object: &HirExpr,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<(Vec<syn::Stmt>, syn::Expr)>> {
+    Ok(field_parts(object, true, ctx)?.map(|(setup, place, _)| (setup, place)))
+}
+
+/// `value.attr` read out of the arena of `value`
+pub(crate) fn try_codegen_field_read(
+    value: &HirExpr,
+    attr: &str,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let object = HirExpr::Attribute {
+        value: Box::new(value.clone()),
+        attr: attr.to_string(),
+    };
+    let Some((_, place, field_type)) = field_parts(&object, false, ctx)? else {
+        return Ok(None);
+    };
+    Ok(Some(if is_copy(&field_type, ctx) {
+        place
+    } else {
+        parse_quote! { #place.clone() }
+    }))
+}
+
+/// `object.method(args)` on the instance a handle names
+pub(crate) fn try_codegen_method_call(
+    object: &HirExpr,
+    method: &str,
+    args: &[HirExpr],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let Some((class, optional)) = handle_of(object, ctx) else {
+        return Ok(None);
+    };
+    if !ctx.arenas.classes[&class].contains_key(method) {
+        return Ok(None);
+    }
+    let (mut setup, instance) = instance_place(object, &class, optional, true, ctx)?;
+    let arg_exprs = args
+        .iter()
+        .map(|arg| arg.to_rust_expr(ctx))
+        .collect::<Result<Vec<_>>>()?;
+    let (hoisted, arg_exprs) = hoist_arena_args(arg_exprs, ctx);
+    setup.extend(hoisted);
+    let method_ident = safe_ident(method);
+    let call: syn::Expr = parse_quote! { #instance.#method_ident(#(#arg_exprs),*) };
+    Ok(Some(with_hoisted(setup, call)))
+}
+
+/// `object.attr = value` into the arena of `object`, wrapping a handle
+/// stored in an optional field in `Some`
+pub(crate) fn codegen_field_assign(
+    object: &HirExpr,
+    attr: &str,
+    value: &HirExpr,
+    value_expr: syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let target = HirExpr::Attribute {
+        value: Box::new(object.clone()),
+        attr: attr.to_string(),
+    };
+    let Some((setup, place, field_type)) = field_parts(&target, true, ctx)? else {
+        let object_expr = object.to_rust_expr(ctx)?;
+        let attr_ident = safe_ident(attr);
+        return Ok(quote! { #object_expr.#attr_ident = #value_expr; });
+    };
+    let value_expr = stored_value(&field_type, value, value_expr, ctx);
+    Ok(in_block(setup, quote! { #place = #value_expr; }))
+}
+
+/// `object.attr[index] = value` into a list or dict field of an arena
+/// instance
+pub(crate) fn try_codegen_field_index_assign(
+    object: &HirExpr,
+    index_expr: syn::Expr,
+    value_expr: syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let Some((mut setup, place, field_type)) = field_parts(object, true, ctx)? else {
+        return Ok(None);
+    };
+    let (hoisted, args) = hoist_arena_args(vec![index_expr, value_expr], ctx);
+    setup.extend(hoisted);
+    let (index, value) = (&args[0], &args[1]);
+    let assign = match field_type {
+        Type::Dict(..) => quote! { #place.insert(#index, #value); },
+        _ => quote! { #place[(#index) as usize] = #value; },
+    };
+    Ok(Some(in_block(setup, assign)))
+}
+
+/// The type of the handles assigned to `target`: its annotation, the type
+/// the variable already has, or that of `value`
+///
+/// Taken before the assignment records a type for the variable of its own.
+pub(crate) fn assigned_handle_type(
+    target: &AssignTarget,
+    value: &HirExpr,
+    annotation: Option<&Type>,
+    ctx: &CodeGenContext,
+) -> Option<Type> {
+    let AssignTarget::Symbol(var) = target else {
+        return None;
+    };
+    if ctx.arenas.classes.is_empty() {
+        return None;
+    }
+    let ty = match (annotation, ctx.var_types.get(var)) {
+        (Some(ty), _) => ty.clone(),
+        (None, Some(ty)) if ctx.is_declared(var) => ty.clone(),
+        _ => value_type(value, ctx),
+    };
+    let mut classes = BTreeSet::new();
+    ctx.arenas.collect_classes(&ty, &mut classes);
+    (!classes.is_empty()).then_some(ty)
+}
+
+/// `var = value` of handles of type `ty`, recording the type of `var`
+pub(crate) fn codegen_handle_assign(
+    var: &str,
+    ty: Type,
+    value: &HirExpr,
+    value_expr: syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let value_expr = stored_value(&ty, value, value_expr, ctx);
+    ctx.var_types.insert(var.to_string(), ty);
+    value_expr
+}
+
+/// `value_expr` stored into a place of type `ty`, in `Some` when the place
+/// may hold `None` and the value may not
+fn stored_value(
+    ty: &Type,
+    value: &HirExpr,
+    value_expr: syn::Expr,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    let wraps = matches!(ty, Type::Optional(_))
+        && !matches!(value, HirExpr::Literal(Literal::None))
+        && !matches!(value_type(value, ctx), Type::Optional(_) | Type::Unknown);
+    if wraps {
+        parse_quote! { Some(#value_expr) }
+    } else {
+        value_expr
+    }
+}
+
+/// The handle type of the items of `iter`, for a loop over handles
+pub(crate) fn iterated_handle_type(iter: &HirExpr, ctx: &CodeGenContext) -> Option<Type> {
+    if ctx.arenas.classes.is_empty() {
+        return None;
+    }
+    match value_type(iter, ctx) {
+        Type::List(elem) if handle_class(&elem, ctx).is_some() => Some(*elem),
+        _ => None,
+    }
+}
+
+/// Binds the arguments that use an arena to locals first
+///
+/// A call on a place in an arena keeps the arena borrowed while its
+/// arguments are evaluated, so an argument allocating in or reading from
+/// the arena has to be evaluated before.
+pub(crate) fn hoist_arena_args(
+    args: Vec<syn::Expr>,
+    ctx: &CodeGenContext,
+) -> (Vec<syn::Stmt>, Vec<syn::Expr>) {
+    let arenas = arena_vars(ctx);
+    let mut hoisted = Vec::new();
+    let args = args
+        .into_iter()
+        .enumerate()
+        .map(|(index, arg)| {
+            if !mentions_any(&arg.to_token_stream(), &arenas) {
+                return arg;
+            }
+            let local = format_ident!("__arg{}", index);
+            hoisted.push(parse_quote! { let #local = #arg; });
+            parse_quote! { #local }
+        })
+        .collect();
+    (hoisted, args)
+}
+
+/// `{ let __arg0 = ...; call }`, or `call` itself with nothing hoisted
+pub(crate) fn with_hoisted(hoisted: Vec<syn::Stmt>, call: syn::Expr) -> syn::Expr {
+    if hoisted.is_empty() {
+        call
+    } else {
+        parse_quote! { { #(#hoisted)* #call } }
+    }
+}
+
+/// `stmt`, or `{ setup; stmt }`
+fn in_block(setup: Vec<syn::Stmt>, stmt: TokenStream) -> TokenStream {
+    if setup.is_empty() {
+        stmt
+    } else {
+        quote! { { #(#setup)* #stmt } }
+    }
+}
+
+fn arena_vars(ctx: &CodeGenContext) -> Vec<String> {
+    ctx.arenas
+        .classes
+        .keys()
+        .map(|class| arena_var(class).to_string())
+        .collect()
+}
+
+fn mentions_any(tokens: &TokenStream, names: &[String]) -> bool {
+    tokens.clone().into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => names.iter().any(|name| ident == name),
+        proc_macro2::TokenTree::Group(group) => mentions_any(&group.stream(), names),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    const GRAPH: &str = r#"
+from dataclasses import dataclass
+from typing import Optional
+
+# @depyler: ownership = "arena"
+@dataclass
+class Node:
+    value: int
+    next: Optional["Node"] = None
+
+def link(a: Node, b: Node) -> None:
+    a.next = b
+
+def second_value(head: Node) -> int:
+    return head.next.value
+
+def build() -> int:
+    a = Node(1)
+    b = Node(2)
+    link(a, b)
+    return second_value(a)
+"#;
+
+    #[test]
+    fn test_instances_are_named_by_index() {
+        let code = transpile(GRAPH);
+
+        assert!(code.contains("pubstructNodeId(pubusize);"));
+        assert!(code.contains("pubnext:Option<NodeId>"));
+        assert!(!code.contains("Box<Node>"));
+        assert!(code.contains("implstd::ops::Index<NodeId>forNodeArena"));
+    }
+
+    #[test]
+    fn test_functions_pass_the_arena_along() {
+        let code = transpile(GRAPH);
+
+        assert!(code.contains("(node_arena:&mutNodeArena,a:NodeId,b:NodeId)"));
+        assert!(code.contains("node_arena[a].next=Some(b);"));
+        assert!(code.contains("node_arena[node_arena[head].next.unwrap()].value"));
+        assert!(code.contains("letnode_arena=&mutNodeArena::default();"));
+        assert!(code.contains("node_arena.alloc(Node::new(1))"));
+        assert!(code.contains("link(node_arena,a,b)"));
+    }
+}
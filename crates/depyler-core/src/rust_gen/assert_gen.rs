@@ -0,0 +1,102 @@
+//! `assert` statements and their messages
+//!
+//! The message becomes the format arguments of the `assert!`, so it is only
+//! built when the assertion fails, as Python evaluates it:
+//!
+//! | Python                       | Rust                                 |
+//! |------------------------------|--------------------------------------|
+//! | `assert ok`                  | `assert!(ok)`                        |
+//! | `assert ok, "bad {input}"`   | `assert!(ok, "bad {{input}}")`       |
+//! | `assert x > 0, f"bad x: {x}"`| `assert!(x > 0, "bad x: {}", x)`     |
+//! | `assert ok, message`         | `assert!(ok, "{}", message)`         |
+
+use crate::hir::{FStringPart, HirExpr, Literal};
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// `assert test, msg`, with `convert` turning each value the message shows
+/// into an argument that displays as `str()` shows it
+pub(crate) fn codegen_assert(
+    test_expr: &syn::Expr,
+    msg: Option<&HirExpr>,
+    mut convert: impl FnMut(&HirExpr) -> Result<syn::Expr>,
+) -> Result<TokenStream> {
+    let Some(msg) = msg else {
+        return Ok(quote! { assert!(#test_expr); });
+    };
+
+    let mut format_string = String::new();
+    let mut format_args = Vec::new();
+    match msg {
+        HirExpr::Literal(Literal::String(text)) => format_string.push_str(&escape(text)),
+        HirExpr::FString { parts } => {
+            for part in parts {
+                match part {
+                    FStringPart::Literal(text) => format_string.push_str(&escape(text)),
+                    FStringPart::Expr(expr) => {
+                        format_string.push_str("{}");
+                        format_args.push(convert(expr)?);
+                    }
+                }
+            }
+        }
+        msg => {
+            format_string.push_str("{}");
+            format_args.push(convert(msg)?);
+        }
+    }
+    Ok(quote! { assert!(#test_expr, #format_string #(, #format_args)*); })
+}
+
+/// `text` as a format string showing it verbatim
+fn escape(text: &str) -> String {
+    text.replace('{', "{{").replace('}', "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn assert_tokens(msg: Option<&HirExpr>) -> String {
+        let test_expr: syn::Expr = parse_quote! { ok };
+        codegen_assert(&test_expr, msg, |expr| match expr {
+            HirExpr::Var(name) => {
+                let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+                Ok(parse_quote! { #ident })
+            }
+            _ => unreachable!(),
+        })
+        .unwrap()
+        .to_string()
+    }
+
+    #[test]
+    fn test_message_is_inlined_into_the_format_string() {
+        assert_eq!(assert_tokens(None), "assert ! (ok) ;");
+
+        let literal = HirExpr::Literal(Literal::String("bad {input}".to_string()));
+        assert_eq!(
+            assert_tokens(Some(&literal)),
+            "assert ! (ok , \"bad {{input}}\") ;"
+        );
+
+        let fstring = HirExpr::FString {
+            parts: vec![
+                FStringPart::Literal("bad x: ".to_string()),
+                FStringPart::Expr(Box::new(HirExpr::Var("x".to_string()))),
+            ],
+        };
+        assert_eq!(
+            assert_tokens(Some(&fstring)),
+            "assert ! (ok , \"bad x: {}\" , x) ;"
+        );
+
+        let var = HirExpr::Var("message".to_string());
+        assert_eq!(
+            assert_tokens(Some(&var)),
+            "assert ! (ok , \"{}\" , message) ;"
+        );
+    }
+}
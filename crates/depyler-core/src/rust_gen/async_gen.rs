@@ -0,0 +1,807 @@
+//! `async for`, `async with` and the asyncio/aiohttp objects they drive
+//!
+//! | Python                                       | Rust                                              |
+//! |----------------------------------------------|---------------------------------------------------|
+//! | `async for x in stream`                      | `while let Some(x) = _stream.next().await`        |
+//! | `asyncio.Lock()`                             | `tokio::sync::Mutex::new(())`                     |
+//! | `async with lock`                            | `let _guard = lock.lock().await;`                 |
+//! | `asyncio.Queue()`                            | `tokio::sync::mpsc::unbounded_channel()`          |
+//! | `await q.put(x)` / `q.put_nowait(x)`         | `q.0.send(x).expect("queue closed")`              |
+//! | `await q.get()`                              | `q.1.recv().await.expect("queue closed")`         |
+//! | `async with aiohttp.ClientSession() as s`    | `let s = reqwest::Client::new();`                 |
+//! | `async with s.get(url) as resp`              | `let resp = s.get(&*url).send().await?;`          |
+//! | `await resp.text()` / `await resp.json()`    | `resp.text().await?` / `resp.json::<Value>().await?` |
+//! | `resp.status`                                | `resp.status().as_u16() as i32`                   |
+//! | `asyncio.gather(a, b)`                       | `async { tokio::join!(a, b) }`                    |
+//! | `asyncio.gather(*tasks)`                     | `futures::future::join_all(tasks)`                |
+//! | `async with asyncio.TaskGroup() as tg`       | `let mut tg = tokio::task::JoinSet::new();`       |
+//! | `t = tg.create_task(coro)`                   | `let t = tg.spawn(coro).id();`                    |
+//! | `t.result()`, after the group                | `t.clone()`                                       |
+//!
+//! The AST bridge marks the async statements: `async for x in s` iterates
+//! `aiter(s)`, and `async with m` enters `m.__aenter__()`. It also passes
+//! `gather(*tasks)` as `gather(iter(tasks))`. A queue is held as its
+//! `(sender, receiver)` pair, so both ends stay with the local that Python
+//! code puts to and gets from.
+
+use crate::hir::{AssignTarget, HirExpr, HirStmt, Literal, Type};
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::context_manager_gen::handle_io_error;
+use crate::rust_gen::keywords::safe_ident;
+use crate::rust_gen::stmt_gen::codegen_block;
+use anyhow::{bail, Result};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse_quote;
+
+/// Type recorded in `CodeGenContext::var_types` for `asyncio.Lock()`
+pub const ASYNC_LOCK_TYPE: &str = "tokio::sync::Mutex<()>";
+
+/// Type of an `asyncio.Queue()`, a channel's sender and receiver
+pub const QUEUE_TYPE: &str =
+    "(tokio::sync::mpsc::UnboundedSender<_>, tokio::sync::mpsc::UnboundedReceiver<_>)";
+
+/// Type of an `aiohttp.ClientSession()`
+pub const CLIENT_TYPE: &str = "reqwest::Client";
+
+/// Type of the response to a request sent through a session
+pub const RESPONSE_TYPE: &str = "reqwest::Response";
+
+/// Type recorded for the target of `async with asyncio.TaskGroup() as tg`
+pub const TASK_GROUP_TYPE: &str = "tokio::task::JoinSet<_>";
+
+/// Type of a task started by `tg.create_task(coro)`, held as its id in the
+/// group until the group is joined and then as its result
+pub const TASK_TYPE: &str = "tokio::task::Id";
+
+/// Session methods that send a request with that HTTP method
+const REQUEST_METHODS: &[&str] = &["get", "post", "put", "patch", "delete", "head"];
+
+/// Type of `asyncio.Lock()`, `asyncio.Queue()` or `aiohttp.ClientSession()`
+pub(crate) fn async_value_type(value: &HirExpr) -> Option<Type> {
+    let (module, class) = match value {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } if args.is_empty() => match object.as_ref() {
+            HirExpr::Var(module) => (module.as_str(), method.as_str()),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let ty = match (module, class) {
+        ("asyncio", "Lock") => ASYNC_LOCK_TYPE,
+        ("asyncio", "Queue") => QUEUE_TYPE,
+        ("aiohttp", "ClientSession") => CLIENT_TYPE,
+        _ => return None,
+    };
+    Some(Type::Custom(ty.to_string()))
+}
+
+/// `await session.get(url)` and the other requests sent through a session
+pub(crate) fn is_awaited_request(value: &HirExpr, ctx: &CodeGenContext) -> bool {
+    match value {
+        HirExpr::Await { value } => request_parts(value, ctx).is_some(),
+        _ => false,
+    }
+}
+
+/// The `var_types` entry of a variable holding one of the types above
+fn custom_type<'c>(expr: &HirExpr, ctx: &'c CodeGenContext) -> Option<&'c str> {
+    match expr {
+        HirExpr::Var(name) => match ctx.var_types.get(name) {
+            Some(Type::Custom(ty)) => Some(ty.as_str()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The stream of an `async for`, which the AST bridge wraps in `aiter()`
+pub(crate) fn async_for_stream(iter: &HirExpr) -> Option<&HirExpr> {
+    match iter {
+        HirExpr::Call { func, args, .. } if func == "aiter" && args.len() == 1 => args.first(),
+        _ => None,
+    }
+}
+
+/// Drain a `futures::Stream`, pinned on the stack so any stream can be polled
+///
+/// ```text
+/// {
+///     let mut _stream = std::pin::pin!(numbers);
+///     while let Some(n) = _stream.next().await {
+///         total = total + n;
+///     }
+/// }
+/// ```
+pub(crate) fn codegen_async_for(
+    target: &AssignTarget,
+    pattern: &syn::Pat,
+    stream: &HirExpr,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let stream_expr = stream.to_rust_expr(ctx)?;
+    ctx.needs_stream_ext = true;
+
+    // `AsyncIterator[T]` parameters yield `T`
+    let item_type = match stream {
+        HirExpr::Var(name) => match ctx.var_types.get(name) {
+            Some(Type::Generic { base, params }) if is_async_iterable(base) => {
+                params.first().cloned()
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    ctx.enter_scope();
+    match target {
+        AssignTarget::Symbol(name) => {
+            ctx.declare_var(name);
+            if let Some(ty) = item_type {
+                ctx.var_types.insert(name.clone(), ty);
+            }
+        }
+        AssignTarget::Tuple(targets) => {
+            for t in targets {
+                if let AssignTarget::Symbol(name) = t {
+                    ctx.declare_var(name);
+                }
+            }
+        }
+        _ => {}
+    }
+    let body_stmts = codegen_block(body, ctx);
+    ctx.exit_scope();
+    let body_stmts = body_stmts?;
+
+    Ok(quote! {
+        {
+            let mut _stream = std::pin::pin!(#stream_expr);
+            while let Some(#pattern) = _stream.next().await {
+                #(#body_stmts)*
+            }
+        }
+    })
+}
+
+/// Python's async iterable annotations, all consumed as streams
+pub(crate) fn is_async_iterable(base: &str) -> bool {
+    matches!(base, "AsyncIterator" | "AsyncIterable" | "AsyncGenerator")
+}
+
+/// The context manager of an `async with`, which the AST bridge wraps in
+/// `__aenter__()`
+pub(crate) fn async_with_manager(context: &HirExpr) -> Option<&HirExpr> {
+    match context {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } if method == "__aenter__" && args.is_empty() => Some(object.as_ref()),
+        _ => None,
+    }
+}
+
+/// Generate an `async with` block
+///
+/// A lock is held for the block, a session and a request's response are
+/// bound to the target. Any other manager has `__aenter__()` awaited, and
+/// like a plain `with` over an unknown manager, nothing runs on exit.
+///
+/// # Complexity
+/// 6 (manager forms + target handling)
+pub(crate) fn codegen_async_with(
+    manager: &HirExpr,
+    target: &Option<String>,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    if is_task_group(manager) {
+        return codegen_task_group(target, body, ctx);
+    }
+    let is_lock = custom_type(manager, ctx) == Some(ASYNC_LOCK_TYPE)
+        || matches!(async_value_type(manager), Some(Type::Custom(ty)) if ty == ASYNC_LOCK_TYPE);
+    let bound_type = if request_parts(manager, ctx).is_some() {
+        Some(RESPONSE_TYPE)
+    } else {
+        match async_value_type(manager) {
+            Some(Type::Custom(ty)) if ty == CLIENT_TYPE => Some(CLIENT_TYPE),
+            _ => None,
+        }
+    };
+
+    let setup = if is_lock {
+        let lock = manager.to_rust_expr(ctx)?;
+        if matches!(manager, HirExpr::Var(_)) {
+            quote! { let _guard = #lock.lock().await; }
+        } else {
+            quote! {
+                let _lock = #lock;
+                let _guard = _lock.lock().await;
+            }
+        }
+    } else if let Some(ty) = bound_type {
+        let value = match request_parts(manager, ctx) {
+            Some(_) => codegen_request(manager, ctx)?,
+            None => manager.to_rust_expr(ctx)?,
+        };
+        let unbound = if ty == RESPONSE_TYPE {
+            "_response"
+        } else {
+            "_session"
+        };
+        let name = target.clone().unwrap_or_else(|| unbound.to_string());
+        ctx.declare_var(&name);
+        ctx.var_types
+            .insert(name.clone(), Type::Custom(ty.to_string()));
+        let ident = safe_ident(&name);
+        quote! { let #ident = #value; }
+    } else {
+        let value = manager.to_rust_expr(ctx)?;
+        match target {
+            Some(name) => {
+                ctx.declare_var(name);
+                let ident = safe_ident(name);
+                quote! {
+                    let _context = #value;
+                    let #ident = _context.__aenter__().await;
+                }
+            }
+            None => quote! { let _context = #value; },
+        }
+    };
+
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+    let body_stmts = codegen_block(body, ctx);
+    ctx.is_final_statement = saved_is_final;
+    let body_stmts = body_stmts?;
+
+    Ok(quote! {
+        {
+            #setup
+            #(#body_stmts)*
+        }
+    })
+}
+
+/// `asyncio.TaskGroup()`
+fn is_task_group(manager: &HirExpr) -> bool {
+    matches!(manager, HirExpr::MethodCall { object, method, args, .. }
+        if method == "TaskGroup"
+            && args.is_empty()
+            && matches!(object.as_ref(), HirExpr::Var(module) if module == "asyncio"))
+}
+
+/// The coroutine of `tg.create_task(coro)` on a task group
+fn spawned_coroutine<'e>(
+    object: &HirExpr,
+    method: &str,
+    args: &'e [HirExpr],
+    ctx: &CodeGenContext,
+) -> Option<&'e HirExpr> {
+    match args {
+        [coro] if method == "create_task" && custom_type(object, ctx) == Some(TASK_GROUP_TYPE) => {
+            Some(coro)
+        }
+        _ => None,
+    }
+}
+
+/// The coroutine started by `value` when it is a `tg.create_task(coro)`
+fn spawn_of<'e>(value: &'e HirExpr, ctx: &CodeGenContext) -> Option<&'e HirExpr> {
+    match value {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } => spawned_coroutine(object, method, args, ctx),
+        _ => None,
+    }
+}
+
+/// `tg.create_task(coro)`, whose target holds the started task
+pub(crate) fn is_task_spawn(value: &HirExpr, ctx: &CodeGenContext) -> bool {
+    spawn_of(value, ctx).is_some()
+}
+
+/// Whether awaiting `expr` gives a `Result`: a call to a function that can
+/// fail, or a list or comprehension of them
+fn is_fallible_awaitable(expr: &HirExpr, ctx: &CodeGenContext) -> bool {
+    match expr {
+        HirExpr::Call { func, .. } => ctx.result_returning_functions.contains(func),
+        HirExpr::List(elts) => elts.iter().any(|elt| is_fallible_awaitable(elt, ctx)),
+        HirExpr::ListComp { element, .. } | HirExpr::GeneratorExp { element, .. } => {
+            is_fallible_awaitable(element, ctx)
+        }
+        _ => false,
+    }
+}
+
+/// Whether any task `stmts` start in a group can fail, looking into
+/// conditionals and loops
+fn spawns_fallible_task(stmts: &[HirStmt], ctx: &CodeGenContext) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        HirStmt::Assign { value, .. } | HirStmt::Expr(value) => {
+            matches!(spawn_of(value, ctx), Some(coro) if is_fallible_awaitable(coro, ctx))
+        }
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => {
+            spawns_fallible_task(then_body, ctx)
+                || else_body
+                    .as_ref()
+                    .is_some_and(|body| spawns_fallible_task(body, ctx))
+        }
+        HirStmt::For { body, .. } | HirStmt::While { body, .. } => spawns_fallible_task(body, ctx),
+        _ => false,
+    })
+}
+
+/// The first error of fallible tasks propagates with `?` from a function
+/// that can fail, and panics elsewhere
+fn raise_task_error(result: syn::Expr, ctx: &CodeGenContext) -> syn::Expr {
+    if ctx.current_function_can_fail {
+        parse_quote! { #result? }
+    } else {
+        parse_quote! { #result.unwrap_or_else(|e| panic!("{}", e)) }
+    }
+}
+
+/// Generate an `async with asyncio.TaskGroup() as tg` block
+///
+/// The group is a `JoinSet` that every task is spawned on and that is
+/// joined once the body is done. A task bound to a name is spawned as its
+/// id, which is rebound to its result after the join:
+///
+/// ```text
+/// let mut tg = tokio::task::JoinSet::new();
+/// let first = tg.spawn(fetch(1)).id();
+/// tg.spawn(fetch(2)).id();
+/// let mut tg_results = std::collections::HashMap::new();
+/// while let Some(_joined) = tg.join_next_with_id().await {
+///     let (_id, _result) =
+///         _joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+///     tg_results.insert(_id, _result?);
+/// }
+/// let first = tg_results.remove(&first).expect("task group joined every task");
+/// ```
+///
+/// When a task fails, Python cancels its siblings and raises. Here the
+/// first error returned by a task leaves the function, or panics, and
+/// dropping the `JoinSet` on the way out aborts the tasks still running.
+/// Only that first error is raised rather than an `ExceptionGroup`, and a
+/// panicking task resumes its panic. The statements are not wrapped in a
+/// block, so tasks stay bound after it as they do in Python.
+///
+/// # Complexity
+/// 5 (target + named tasks + fallibility)
+fn codegen_task_group(
+    target: &Option<String>,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+    let tokens = codegen_task_group_body(target, body, ctx);
+    ctx.is_final_statement = saved_is_final;
+    tokens
+}
+
+fn codegen_task_group_body(
+    target: &Option<String>,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let name = match target {
+        Some(name) => name,
+        // Nothing can be spawned on a group that is not bound
+        None => {
+            let body_stmts = codegen_block(body, ctx)?;
+            return Ok(quote! { { #(#body_stmts)* } });
+        }
+    };
+    ctx.declare_var(name);
+    ctx.var_types
+        .insert(name.clone(), Type::Custom(TASK_GROUP_TYPE.to_string()));
+    let fallible = spawns_fallible_task(body, ctx);
+    let mut tasks: Vec<&str> = Vec::new();
+    for stmt in body {
+        if let HirStmt::Assign {
+            target: AssignTarget::Symbol(task),
+            value,
+            ..
+        } = stmt
+        {
+            if is_task_spawn(value, ctx) && !tasks.contains(&task.as_str()) {
+                tasks.push(task);
+            }
+        }
+    }
+
+    let body_stmts = codegen_block(body, ctx)?;
+    let group = safe_ident(name);
+    let panicked: syn::Expr =
+        parse_quote! { _joined.unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic())) };
+    if tasks.is_empty() {
+        let joined = if fallible {
+            raise_task_error(panicked, ctx)
+        } else {
+            panicked
+        };
+        return Ok(quote! {
+            let mut #group = tokio::task::JoinSet::new();
+            #(#body_stmts)*
+            while let Some(_joined) = #group.join_next().await {
+                #joined;
+            }
+        });
+    }
+
+    let results = format_ident!("{}_results", name);
+    let result: syn::Expr = if fallible {
+        raise_task_error(parse_quote! { _result }, ctx)
+    } else {
+        parse_quote! { _result }
+    };
+    let task_idents: Vec<syn::Ident> = tasks.iter().map(|task| safe_ident(task)).collect();
+    Ok(quote! {
+        let mut #group = tokio::task::JoinSet::new();
+        #(#body_stmts)*
+        let mut #results = std::collections::HashMap::new();
+        while let Some(_joined) = #group.join_next_with_id().await {
+            let (_id, _result) = #panicked;
+            #results.insert(_id, #result);
+        }
+        #(
+            let #task_idents = #results
+                .remove(&#task_idents)
+                .expect("task group joined every task");
+        )*
+    })
+}
+
+/// `tg.create_task(coro)` on a task group, and `t.result()` on a task once
+/// the group is joined
+pub(crate) fn try_codegen_task_method(
+    object: &HirExpr,
+    method: &str,
+    args: &[HirExpr],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if let Some(coro) = spawned_coroutine(object, method, args, ctx) {
+        let group = object.to_rust_expr(ctx)?;
+        let coro = coro.to_rust_expr(ctx)?;
+        return Ok(Some(parse_quote! { #group.spawn(#coro).id() }));
+    }
+    if method == "result" && args.is_empty() && custom_type(object, ctx) == Some(TASK_TYPE) {
+        let task = object.to_rust_expr(ctx)?;
+        return Ok(Some(parse_quote! { #task.clone() }));
+    }
+    Ok(None)
+}
+
+/// The awaitables of `asyncio.gather(...)`
+enum Awaitables<'e> {
+    /// `gather(a, b)`, gathered into a tuple
+    Listed(&'e [HirExpr]),
+    /// `gather(*tasks)`, gathered into a list
+    Unpacked(&'e HirExpr),
+}
+
+/// The awaitables of an `asyncio.gather(...)` call and whether it returns
+/// exceptions instead of raising them
+fn gather_parts(expr: &HirExpr) -> Option<(Awaitables<'_>, bool)> {
+    let (args, kwargs) = match expr {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            kwargs,
+        } if method == "gather"
+            && matches!(object.as_ref(), HirExpr::Var(module) if module == "asyncio") =>
+        {
+            (args, kwargs)
+        }
+        _ => return None,
+    };
+    let return_exceptions = kwargs.iter().any(|(name, value)| {
+        name == "return_exceptions" && *value == HirExpr::Literal(Literal::Bool(true))
+    });
+    let awaitables = match args.as_slice() {
+        [HirExpr::Call { func, args, .. }] if func == "iter" && args.len() == 1 => {
+            Awaitables::Unpacked(&args[0])
+        }
+        _ => Awaitables::Listed(args),
+    };
+    Some((awaitables, return_exceptions))
+}
+
+/// `asyncio.gather(...)`, awaited or not
+///
+/// The awaitables run concurrently and their results come back in argument
+/// order: listed ones through `tokio::join!`, unpacked ones through
+/// `futures::future::join_all`. When they return a `Result`, `try_join!`
+/// and `try_join_all` end on the first error, which the awaiting code
+/// raises as Python's `gather` does. The other awaitables are dropped,
+/// cancelling them, where Python leaves them running unobserved. With
+/// `return_exceptions=True` every `Result` is kept instead.
+///
+/// # Complexity
+/// 5 (awaitable forms x fallibility + awaited)
+pub(crate) fn try_codegen_gather(
+    expr: &HirExpr,
+    awaited: bool,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let (awaitables, return_exceptions) = match gather_parts(expr) {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+    let gathered: syn::Expr = match awaitables {
+        Awaitables::Listed([]) => bail!("asyncio.gather() requires at least 1 awaitable"),
+        Awaitables::Listed(args) => {
+            let fallible =
+                !return_exceptions && args.iter().any(|arg| is_fallible_awaitable(arg, ctx));
+            let arg_exprs = args
+                .iter()
+                .map(|arg| arg.to_rust_expr(ctx))
+                .collect::<Result<Vec<_>>>()?;
+            if fallible && awaited {
+                // try_join! awaits in place, leaving only the error to raise
+                let joined = parse_quote! { tokio::try_join!(#(#arg_exprs),*) };
+                return Ok(Some(raise_task_error(joined, ctx)));
+            }
+            // join! polls the futures concurrently; wrapping it in an async
+            // block keeps the result awaitable like Python's gather()
+            if fallible {
+                parse_quote! { async { tokio::try_join!(#(#arg_exprs),*) } }
+            } else {
+                parse_quote! { async { tokio::join!(#(#arg_exprs),*) } }
+            }
+        }
+        Awaitables::Unpacked(tasks) => {
+            let fallible = !return_exceptions && is_fallible_awaitable(tasks, ctx);
+            let tasks = tasks.to_rust_expr(ctx)?;
+            if fallible && awaited {
+                let joined = parse_quote! { futures::future::try_join_all(#tasks).await };
+                return Ok(Some(raise_task_error(joined, ctx)));
+            }
+            if fallible {
+                parse_quote! { futures::future::try_join_all(#tasks) }
+            } else {
+                parse_quote! { futures::future::join_all(#tasks) }
+            }
+        }
+    };
+    if awaited {
+        Ok(Some(parse_quote! { #gathered.await }))
+    } else {
+        Ok(Some(gathered))
+    }
+}
+
+/// The session, HTTP method, URL and keyword arguments of
+/// `session.get(url, ...)`
+#[allow(clippy::type_complexity)]
+fn request_parts<'e>(
+    expr: &'e HirExpr,
+    ctx: &CodeGenContext,
+) -> Option<(&'e HirExpr, &'e str, &'e HirExpr, &'e [(String, HirExpr)])> {
+    match expr {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            kwargs,
+        } if REQUEST_METHODS.contains(&method.as_str())
+            && args.len() == 1
+            && custom_type(object, ctx) == Some(CLIENT_TYPE) =>
+        {
+            Some((
+                object.as_ref(),
+                method.as_str(),
+                &args[0],
+                kwargs.as_slice(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Send a request and wait for the response
+///
+/// `json=` serializes a body, `data=` sends one as is. Transport errors
+/// propagate from functions returning a boxed error and panic elsewhere.
+fn codegen_request(request: &HirExpr, ctx: &mut CodeGenContext) -> Result<syn::Expr> {
+    let (session, method, url, kwargs) = match request_parts(request, ctx) {
+        Some(parts) => parts,
+        None => anyhow::bail!("expected a request sent through an aiohttp session"),
+    };
+    let session = session.to_rust_expr(ctx)?;
+    let method = safe_ident(method);
+    let url_expr = url.to_rust_expr(ctx)?;
+    // reqwest takes the URL by value; a `String` or `&str` variable is lent
+    // as a `&str`
+    let url_expr: syn::Expr = match url {
+        HirExpr::Var(_) => parse_quote! { &*#url_expr },
+        _ => url_expr,
+    };
+    let mut builder: syn::Expr = parse_quote! { #session.#method(#url_expr) };
+    for (name, value) in kwargs {
+        let value = value.to_rust_expr(ctx)?;
+        builder = match name.as_str() {
+            "json" => parse_quote! { #builder.json(&#value) },
+            "data" => parse_quote! { #builder.body(#value) },
+            other => anyhow::bail!("aiohttp request argument `{}` is not supported", other),
+        };
+    }
+    Ok(handle_io_error(
+        parse_quote! { #builder.send().await },
+        "send request",
+        ctx,
+    ))
+}
+
+/// `await` of a gather or of a queue, session or response method, which
+/// completes its own await; `None` for any other awaited value
+pub(crate) fn try_codegen_await(
+    value: &HirExpr,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if let Some(gathered) = try_codegen_gather(value, true, ctx)? {
+        return Ok(Some(gathered));
+    }
+    if request_parts(value, ctx).is_some() {
+        return codegen_request(value, ctx).map(Some);
+    }
+    let (object, method, args) = match value {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } => (object.as_ref(), method.as_str(), args),
+        _ => return Ok(None),
+    };
+    if let Some(expr) = try_codegen_queue_method(object, method, args, true, ctx)? {
+        return Ok(Some(expr));
+    }
+    if !args.is_empty() || custom_type(object, ctx) != Some(RESPONSE_TYPE) {
+        return Ok(None);
+    }
+    let response = object.to_rust_expr(ctx)?;
+    let body: syn::Expr = match method {
+        "text" => parse_quote! { #response.text().await },
+        "json" => parse_quote! { #response.json::<serde_json::Value>().await },
+        _ => return Ok(None),
+    };
+    Ok(Some(handle_io_error(body, "read response", ctx)))
+}
+
+/// `asyncio.Queue` methods on the channel pair
+///
+/// `put` never waits on an unbounded channel, so awaiting it and
+/// `put_nowait` are the same send. `get` waits for an item, and is only
+/// lowered where it is awaited.
+pub(crate) fn try_codegen_queue_method(
+    object: &HirExpr,
+    method: &str,
+    args: &[HirExpr],
+    awaited: bool,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if custom_type(object, ctx) != Some(QUEUE_TYPE) {
+        return Ok(None);
+    }
+    let queue = object.to_rust_expr(ctx)?;
+    let expr = match (method, args, awaited) {
+        ("put", [item], true) | ("put_nowait", [item], false) => {
+            let item = item.to_rust_expr(ctx)?;
+            parse_quote! { #queue.0.send(#item).expect("queue closed") }
+        }
+        ("get", [], true) => parse_quote! { #queue.1.recv().await.expect("queue closed") },
+        _ => return Ok(None),
+    };
+    Ok(Some(expr))
+}
+
+/// `resp.status` of a response, as an `int`
+pub(crate) fn try_codegen_response_attr(
+    value: &HirExpr,
+    attr: &str,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if attr != "status" || custom_type(value, ctx) != Some(RESPONSE_TYPE) {
+        return Ok(None);
+    }
+    let response = value.to_rust_expr(ctx)?;
+    Ok(Some(parse_quote! { #response.status().as_u16() as i32 }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn method_call(object: &str, method: &str) -> HirExpr {
+        HirExpr::MethodCall {
+            object: Box::new(HirExpr::Var(object.to_string())),
+            method: method.to_string(),
+            args: vec![],
+            kwargs: vec![],
+        }
+    }
+
+    #[test]
+    fn test_async_value_types() {
+        assert_eq!(
+            async_value_type(&method_call("asyncio", "Queue")),
+            Some(Type::Custom(QUEUE_TYPE.to_string()))
+        );
+        assert_eq!(
+            async_value_type(&method_call("aiohttp", "ClientSession")),
+            Some(Type::Custom(CLIENT_TYPE.to_string()))
+        );
+        assert_eq!(async_value_type(&method_call("threading", "Lock")), None);
+    }
+
+    #[test]
+    fn test_gather_parts() {
+        let gather = |args, kwargs| HirExpr::MethodCall {
+            object: Box::new(HirExpr::Var("asyncio".to_string())),
+            method: "gather".to_string(),
+            args,
+            kwargs,
+        };
+        let tasks = HirExpr::Var("tasks".to_string());
+        let unpacked = gather(
+            vec![HirExpr::Call {
+                func: "iter".to_string(),
+                args: vec![tasks.clone()],
+                kwargs: vec![],
+            }],
+            vec![(
+                "return_exceptions".to_string(),
+                HirExpr::Literal(Literal::Bool(true)),
+            )],
+        );
+        assert!(matches!(
+            gather_parts(&unpacked),
+            Some((Awaitables::Unpacked(t), true)) if *t == tasks
+        ));
+        assert!(matches!(
+            gather_parts(&gather(vec![tasks.clone()], vec![])),
+            Some((Awaitables::Listed([_]), false))
+        ));
+        assert!(gather_parts(&method_call("trio", "gather")).is_none());
+        assert!(is_task_group(&method_call("asyncio", "TaskGroup")));
+    }
+
+    #[test]
+    fn test_async_markers() {
+        let stream = HirExpr::Var("numbers".to_string());
+        let iter = HirExpr::Call {
+            func: "aiter".to_string(),
+            args: vec![stream.clone()],
+            kwargs: vec![],
+        };
+        assert_eq!(async_for_stream(&iter), Some(&stream));
+        assert_eq!(async_for_stream(&stream), None);
+
+        let manager = method_call("lock", "__aenter__");
+        assert_eq!(
+            async_with_manager(&manager),
+            Some(&HirExpr::Var("lock".to_string()))
+        );
+        assert_eq!(async_with_manager(&method_call("lock", "acquire")), None);
+    }
+}
@@ -0,0 +1,149 @@
+//! Return values borrowed from a parameter
+//!
+//! When every value a function returns borrows from one parameter (see
+//! [`borrowed_return_source`](crate::lifetime_analysis::borrowed_return_source)),
+//! the function returns `&'a str` or `&'a [T]` for the parameter's lifetime
+//! and its `return` statements hand out references instead of copies:
+//!
+//! | Python                 | Rust                                   |
+//! |------------------------|----------------------------------------|
+//! | `return s`             | `s`                                    |
+//! | `return xs`            | `xs.as_slice()`                        |
+//! | `return s.strip()`     | `s.trim()`                             |
+//! | `return s[1:n]`        | `py_str_slice(s, Some(1), Some(..))`   |
+//! | `return xs[1:]`        | `&xs[1usize.min(xs.len())..]`          |
+//! | `return xs[i:]`        | `py_subslice(xs, Some(..), None)`      |
+//! | `return "none"`        | `"none"`                               |
+//! | `return a if c else b` | `if c { a } else { b }`                |
+//!
+//! Under `string_strategy = "cow"`, a function returning such a borrow on
+//! some paths and a new string on others (see
+//! [`cow_return_source`](crate::string_optimization::cow_return_source))
+//! returns `Cow<'a, str>`, with `Cow::Borrowed` around the borrows above
+//! and `Cow::Owned` around the rest.
+//!
+//! Callers lend the argument the result points into and call `.to_owned()`
+//! on the result, or `.into_owned()` on a `Cow`, so they still hold the
+//! `String` or `Vec` the Python type maps to.
+
+use crate::hir::{HirExpr, Literal, Type};
+use crate::lifetime_analysis::borrowed_parts;
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::{slice_gen, stmt_gen};
+use anyhow::{bail, Result};
+use syn::parse_quote;
+
+/// A returned `value` as a reference into the parameter `source`
+pub(crate) fn codegen_borrowed_return(
+    value: &HirExpr,
+    source: &str,
+    ctx: &mut CodeGenContext,
+) -> Result<syn::Expr> {
+    let is_list = matches!(ctx.var_types.get(source), Some(Type::List(_)));
+    match value {
+        HirExpr::Var(_) => {
+            let var = value.to_rust_expr(ctx)?;
+            Ok(if is_list {
+                parse_quote! { #var.as_slice() }
+            } else {
+                var
+            })
+        }
+        HirExpr::Literal(Literal::String(s)) => Ok(parse_quote! { #s }),
+        HirExpr::MethodCall { object, method, .. } => {
+            let object_expr = object.to_rust_expr(ctx)?;
+            Ok(match method.as_str() {
+                "lstrip" => parse_quote! { #object_expr.trim_start() },
+                "rstrip" => parse_quote! { #object_expr.trim_end() },
+                _ => parse_quote! { #object_expr.trim() },
+            })
+        }
+        HirExpr::Slice {
+            base, start, stop, ..
+        } => {
+            let base_expr = base.to_rust_expr(ctx)?;
+            let start = start.as_ref().map(|e| e.to_rust_expr(ctx)).transpose()?;
+            let stop = stop.as_ref().map(|e| e.to_rust_expr(ctx)).transpose()?;
+            Ok(slice_gen::codegen_borrowed_range(
+                &base_expr, start, stop, is_list, ctx,
+            ))
+        }
+        HirExpr::IfExpr { test, body, orelse } => {
+            let test_expr = stmt_gen::codegen_condition(test, ctx)?;
+            let body_expr = codegen_borrowed_return(body, source, ctx)?;
+            let orelse_expr = codegen_borrowed_return(orelse, source, ctx)?;
+            Ok(parse_quote! { if #test_expr { #body_expr } else { #orelse_expr } })
+        }
+        _ => bail!("return value does not borrow from `{}`", source),
+    }
+}
+
+/// A returned `value` as a `Cow` borrowing from the parameter `source`
+/// where it can
+pub(crate) fn codegen_cow_return(
+    value: &HirExpr,
+    source: &str,
+    ctx: &mut CodeGenContext,
+) -> Result<syn::Expr> {
+    let mut sources = Vec::new();
+    let mut string_only = false;
+    if borrowed_parts(value, &mut sources, &mut string_only)
+        && sources.iter().all(|name| *name == source)
+    {
+        let borrowed = codegen_borrowed_return(value, source, ctx)?;
+        return Ok(parse_quote! { Cow::Borrowed(#borrowed) });
+    }
+    if let HirExpr::IfExpr { test, body, orelse } = value {
+        let test_expr = stmt_gen::codegen_condition(test, ctx)?;
+        let body_expr = codegen_cow_return(body, source, ctx)?;
+        let orelse_expr = codegen_cow_return(orelse, source, ctx)?;
+        return Ok(parse_quote! { if #test_expr { #body_expr } else { #orelse_expr } });
+    }
+    let owned = value.to_rust_expr(ctx)?;
+    Ok(parse_quote! { Cow::Owned(#owned) })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_slices_and_strips_borrow_from_the_parameter() {
+        let code = transpile(
+            "def head(s: str, n: int) -> str:\n    if n < 0:\n        return s.strip()\n    return s[:n]\n",
+        );
+
+        assert!(code.contains("fnhead<'a>(s:&'astr,n:i32)->&'astr"));
+        assert!(code.contains("returns.trim();"));
+        assert!(code.contains("py_str_slice(s,None,Some((n)asi64))"));
+    }
+
+    #[test]
+    fn test_list_slices_borrow_as_slices() {
+        let code =
+            transpile("def rest(xs: list[int]) -> list[int]:\n    return xs[1:] if xs else xs\n");
+
+        assert!(code.contains("->&'a[i32]"));
+        assert!(code.contains("if!xs.is_empty(){&xs[1usize.min(xs.len())..]}else{xs.as_slice()}"));
+    }
+
+    #[test]
+    fn test_callers_take_an_owned_copy() {
+        let code = transpile(
+            "def first(s: str) -> str:\n    return s[:1]\n\ndef shout(s: str) -> str:\n    return first(s) + \"!\"\n",
+        );
+
+        assert!(code.contains("first(&s).to_owned()"));
+    }
+
+    #[test]
+    fn test_cow_returns_borrow_where_they_can() {
+        let code = transpile(
+            "# @depyler: string_strategy = \"cow\"\ndef squash(s: str) -> str:\n    return s if \" \" not in s else s.replace(\" \", \"\")\n\ndef shout(s: str) -> str:\n    return squash(s) + \"!\"\n",
+        );
+
+        assert!(code.contains("fnsquash<'a>(s:&'astr)->Cow<'a,str>"));
+        assert!(code.contains("{Cow::Borrowed(s)}else{Cow::Owned(s.replace("));
+        assert!(code.contains("squash(&s).into_owned()"));
+    }
+}
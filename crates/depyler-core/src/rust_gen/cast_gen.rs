@@ -0,0 +1,340 @@
+//! Numeric conversions that only cast when the types differ
+//!
+//! `int()`, `float()` and true division convert by the inferred type of the
+//! value rather than casting blindly:
+//!
+//! | Value   | `int(x)`       | `float(x)`                |
+//! |---------|----------------|---------------------------|
+//! | `int`   | `x`            | `f64::from(x)`            |
+//! | `float` | `x as i32`     | `x`                       |
+//! | `bool`  | `i32::from(x)` | `f64::from(u8::from(x))`  |
+//! | unknown | `(x) as i32`   | `(x) as f64`              |
+//!
+//! Widening goes through `From`, which only exists where no value is lost;
+//! an `i64` int has no lossless `f64` and keeps `as`, as does truncating a
+//! float, which has no `TryFrom`.
+//!
+//! Once a function is generated, `elide_redundant_casts` tidies the casts
+//! the other lowerings left:
+//!
+//! | Generated            | Emitted                                   |
+//! |----------------------|-------------------------------------------|
+//! | `(n as i32) as i32`  | `n as i32`                                |
+//! | `2 as f64`           | `2f64`                                    |
+//! | `(n as i32) as i64`  | `i64::from(n as i32)`                     |
+//! | `(n as i64) as i32`  | `i32::try_from(n as i64).expect(..)`      |
+//!
+//! A narrowing cast that would wrap panics instead, as `int` arithmetic does
+//! on overflow by default.
+
+use crate::hir::{HirExpr, Type};
+use crate::rust_gen::context::CodeGenContext;
+use crate::type_mapper::{PrimitiveType, RustType};
+use depyler_annotations::IntegerSemantics;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse_quote;
+use syn::visit_mut::{self, VisitMut};
+
+/// `int(x)` on a number
+pub(crate) fn codegen_int_cast(
+    arg: &HirExpr,
+    arg_expr: &syn::Expr,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    match ctx.value_type(arg) {
+        Type::Int => arg_expr.clone(),
+        Type::Bool => parse_quote! { i32::from(#arg_expr) },
+        _ => parse_quote! { (#arg_expr) as i32 },
+    }
+}
+
+/// `float(x)` on a number, and the operands of `/`
+pub(crate) fn codegen_float_cast(
+    arg: &HirExpr,
+    arg_expr: &syn::Expr,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    match ctx.value_type(arg) {
+        Type::Float => arg_expr.clone(),
+        Type::Int if int_is_i32(ctx) => parse_quote! { f64::from(#arg_expr) },
+        Type::Bool => parse_quote! { f64::from(u8::from(#arg_expr)) },
+        _ => parse_quote! { (#arg_expr) as f64 },
+    }
+}
+
+/// Whether `int` values are `i32`, which `f64` holds exactly
+fn int_is_i32(ctx: &CodeGenContext) -> bool {
+    ctx.integer_semantics != IntegerSemantics::BigInt
+        && ctx.type_mapper.map_type(&Type::Int) == RustType::Primitive(PrimitiveType::I32)
+}
+
+/// Tidies the casts in generated functions; tokens that do not parse are
+/// left as they are
+pub(crate) fn elide_redundant_casts(functions: Vec<TokenStream>) -> Vec<TokenStream> {
+    functions
+        .into_iter()
+        .map(|tokens| match syn::parse2::<syn::File>(tokens.clone()) {
+            Ok(mut file) => {
+                RedundantCasts.visit_file_mut(&mut file);
+                file.into_token_stream()
+            }
+            Err(_) => tokens,
+        })
+        .collect()
+}
+
+struct RedundantCasts;
+
+impl VisitMut for RedundantCasts {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+        let cast = match expr {
+            syn::Expr::Cast(cast) => cast,
+            _ => return,
+        };
+        let target = match Primitive::of(&cast.ty) {
+            Some(target) => target,
+            None => return,
+        };
+        if let Some(literal) = suffixed_literal(&cast.expr, target) {
+            *expr = literal;
+            return;
+        }
+        let inner = match unparenthesized(&cast.expr) {
+            syn::Expr::Cast(inner) => inner.clone(),
+            _ => return,
+        };
+        let source = match Primitive::of(&inner.ty) {
+            Some(source) => source,
+            None => return,
+        };
+        let ty = &cast.ty;
+        *expr = if source == target {
+            syn::Expr::Cast(inner)
+        } else if source.converts_losslessly_to(target) {
+            parse_quote! { #ty::from(#inner) }
+        } else if source.is_int() && target.is_int() {
+            let message = format!("integer out of range for {}", target.name);
+            parse_quote! { #ty::try_from(#inner).expect(#message) }
+        } else {
+            return;
+        };
+    }
+}
+
+fn unparenthesized(expr: &syn::Expr) -> &syn::Expr {
+    match expr {
+        syn::Expr::Paren(paren) => unparenthesized(&paren.expr),
+        _ => expr,
+    }
+}
+
+/// `2 as f64` -> `2f64`, `-1 as i64` -> `-1i64`, for literals the target
+/// holds without wrapping
+fn suffixed_literal(expr: &syn::Expr, target: Primitive) -> Option<syn::Expr> {
+    suffixed(expr, target, false)
+}
+
+fn suffixed(expr: &syn::Expr, target: Primitive, negated: bool) -> Option<syn::Expr> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(n),
+            ..
+        }) if n.suffix().is_empty() && target.holds(n.base10_parse().ok()?, negated) => {
+            let n = syn::LitInt::new(&format!("{}{}", n.base10_digits(), target.name), n.span());
+            Some(parse_quote! { #n })
+        }
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Float(x),
+            ..
+        }) if x.suffix().is_empty() && !target.is_int() => {
+            let x = syn::LitFloat::new(&format!("{}{}", x.base10_digits(), target.name), x.span());
+            Some(parse_quote! { #x })
+        }
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) if target.signed && !negated => {
+            let literal = suffixed(expr, target, true)?;
+            Some(parse_quote! { -#literal })
+        }
+        _ => None,
+    }
+}
+
+/// A numeric primitive a cast targets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Primitive {
+    name: &'static str,
+    /// Width in bits; pointer-sized integers count as 64, though `From`
+    /// only widens bytes and 16-bit integers into them
+    bits: u32,
+    signed: bool,
+    float: bool,
+}
+
+const PRIMITIVES: &[Primitive] = &[
+    Primitive::int("i8", 8, true),
+    Primitive::int("i16", 16, true),
+    Primitive::int("i32", 32, true),
+    Primitive::int("i64", 64, true),
+    Primitive::int("i128", 128, true),
+    Primitive::int("isize", 64, true),
+    Primitive::int("u8", 8, false),
+    Primitive::int("u16", 16, false),
+    Primitive::int("u32", 32, false),
+    Primitive::int("u64", 64, false),
+    Primitive::int("u128", 128, false),
+    Primitive::int("usize", 64, false),
+    Primitive {
+        name: "f32",
+        bits: 32,
+        signed: true,
+        float: true,
+    },
+    Primitive {
+        name: "f64",
+        bits: 64,
+        signed: true,
+        float: true,
+    },
+];
+
+impl Primitive {
+    const fn int(name: &'static str, bits: u32, signed: bool) -> Self {
+        Self {
+            name,
+            bits,
+            signed,
+            float: false,
+        }
+    }
+
+    fn of(ty: &syn::Type) -> Option<Self> {
+        let path = match ty {
+            syn::Type::Path(path) if path.qself.is_none() => path,
+            _ => return None,
+        };
+        let ident = path.path.get_ident()?;
+        PRIMITIVES.iter().copied().find(|p| ident == p.name)
+    }
+
+    fn is_int(self) -> bool {
+        !self.float
+    }
+
+    /// Whether a literal `n`, or `-n`, is in range
+    fn holds(self, n: u128, negated: bool) -> bool {
+        if self.float {
+            return true;
+        }
+        // Pointer-sized integers may be narrower than 64 bits
+        let bits = if self.is_pointer_sized() {
+            32
+        } else {
+            self.bits
+        };
+        match (self.signed, negated) {
+            (true, true) => n <= 1 << (bits - 1),
+            (true, false) => n < 1 << (bits - 1),
+            (false, _) => bits == 128 || n < 1 << bits,
+        }
+    }
+
+    fn is_pointer_sized(self) -> bool {
+        self.name.ends_with("size")
+    }
+
+    /// Whether std has `impl From<self> for target`
+    fn converts_losslessly_to(self, target: Primitive) -> bool {
+        if self.is_pointer_sized() || target.is_pointer_sized() {
+            // Only bytes widen to pointer-sized integers
+            return self.bits <= 8
+                && !target.float
+                && (self.signed == target.signed || !self.signed)
+                || self.name == "i16" && target.name == "isize"
+                || self.name == "u16" && target.name == "usize";
+        }
+        match (self.float, target.float) {
+            (false, false) if self.signed == target.signed => self.bits < target.bits,
+            // Unsigned ints fit in a wider signed int
+            (false, false) => !self.signed && self.bits < target.bits,
+            // An int fits in a float whose mantissa is wider
+            (false, true) => self.bits * 2 <= target.bits,
+            (true, true) => self.bits < target.bits,
+            (true, false) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn elide(tokens: TokenStream) -> String {
+        elide_redundant_casts(vec![tokens])[0].to_string()
+    }
+
+    fn primitive(name: &str) -> Primitive {
+        PRIMITIVES.iter().copied().find(|p| p.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_lossless_conversions_match_std_from() {
+        let widens = |from, to| primitive(from).converts_losslessly_to(primitive(to));
+        assert!(widens("i32", "i64"));
+        assert!(widens("u8", "i32"));
+        assert!(widens("u32", "i64"));
+        assert!(widens("i32", "f64"));
+        assert!(widens("u16", "f32"));
+        assert!(widens("f32", "f64"));
+        assert!(widens("u8", "usize"));
+        assert!(widens("i16", "isize"));
+        assert!(!widens("i64", "f64"));
+        assert!(!widens("i32", "u64"));
+        assert!(!widens("i64", "i32"));
+        assert!(!widens("u32", "usize"));
+        assert!(!widens("usize", "u64"));
+        assert!(widens("u8", "isize"));
+    }
+
+    #[test]
+    fn test_only_literals_in_range_get_a_suffix() {
+        let code = elide(quote! {
+            fn f() {
+                let a = 255 as u8;
+                let b = 256 as u8;
+                let c = -128 as i8;
+                let d = -129 as i8;
+            }
+        });
+
+        assert!(code.contains("let a = 255u8"));
+        assert!(code.contains("let b = 256 as u8"));
+        assert!(code.contains("let c = - 128i8"));
+        assert!(code.contains("let d = - 129 as i8"));
+    }
+
+    #[test]
+    fn test_redundant_casts_are_elided() {
+        let code = elide(quote! {
+            fn f(n: i32, xs: Vec<i32>) -> f64 {
+                let a = (xs.len() as i32) as i32;
+                let b = (n as i32) as i64;
+                let c = (a as i64) as i32;
+                (2 as f64).powf(-1 as f64) + n as f64
+            }
+        });
+
+        assert!(code.contains("let a = xs . len () as i32 ;"));
+        assert!(code.contains("i64 :: from (n as i32)"));
+        assert!(
+            code.contains("i32 :: try_from (a as i64) . expect (\"integer out of range for i32\")")
+        );
+        assert!(code.contains("(2f64) . powf (- 1f64)"));
+        assert!(code.contains("n as f64"));
+    }
+}
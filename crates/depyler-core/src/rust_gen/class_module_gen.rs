@@ -0,0 +1,261 @@
+//! One Rust module per Python class
+//!
+//! With `CodeGenOptions::class_modules`, each class is emitted as
+//! `pub mod <snake_name>` holding its struct and impls, and re-exported with
+//! `pub use`, so everything else in the file keeps naming the class directly.
+//! Exception classes that only one class raises are its error types and move
+//! into that class's module; exceptions shared with other classes or module
+//! functions get a module of their own.
+//!
+//! Class modules start with `use super::*;`, which brings the file's imports,
+//! constants, functions and the other classes into scope.
+
+use crate::hir::{HirClass, HirExpr, HirFunction, HirStmt};
+use crate::rust_gen::keywords::safe_ident;
+use crate::symbol_names::snake_case;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::{HashMap, HashSet};
+
+/// Wraps each class's items in a module named after the class
+///
+/// `class_items` pairs every class name with the items generated for it,
+/// in the order of `classes`.
+pub(crate) fn nest_classes(
+    classes: &[HirClass],
+    functions: &[HirFunction],
+    class_items: Vec<(String, Vec<TokenStream>)>,
+) -> Vec<TokenStream> {
+    let owners = error_type_owners(classes, functions);
+    let mut items_by_class: HashMap<String, Vec<TokenStream>> = class_items.into_iter().collect();
+
+    let mut items = Vec::new();
+    for class in classes {
+        if owners.contains_key(&class.name) {
+            continue;
+        }
+        let mut members = vec![class.name.clone()];
+        members.extend(
+            classes
+                .iter()
+                .filter(|c| owners.get(&c.name) == Some(&class.name))
+                .map(|c| c.name.clone()),
+        );
+        let body: Vec<TokenStream> = members
+            .iter()
+            .flat_map(|name| items_by_class.remove(name).unwrap_or_default())
+            .collect();
+        let module = module_ident(&class.name);
+        let exports = members.iter().map(|name| format_ident!("{}", name));
+        items.push(quote! {
+            pub mod #module {
+                use super::*;
+                #(#body)*
+            }
+            pub use #module::{#(#exports),*};
+        });
+    }
+    items
+}
+
+/// `HttpClient` -> `http_client`
+fn module_ident(class: &str) -> syn::Ident {
+    let name = snake_case(class);
+    match name.as_str() {
+        // Path keywords cannot be raw identifiers
+        "self" | "super" | "crate" => format_ident!("{}_", name),
+        _ => safe_ident(&name),
+    }
+}
+
+/// Exception class -> the one class whose methods raise it, for exceptions
+/// nothing else raises
+fn error_type_owners(classes: &[HirClass], functions: &[HirFunction]) -> HashMap<String, String> {
+    let exceptions = exception_classes(classes);
+
+    let mut raisers: HashMap<String, HashSet<&str>> = HashMap::new();
+    for class in classes {
+        let mut raised = HashSet::new();
+        class
+            .methods
+            .iter()
+            .for_each(|m| raised_names(&m.body, &mut raised));
+        for name in raised {
+            raisers.entry(name).or_default().insert(&class.name);
+        }
+    }
+    let mut raised_by_functions = HashSet::new();
+    functions
+        .iter()
+        .for_each(|f| raised_names(&f.body, &mut raised_by_functions));
+
+    raisers
+        .into_iter()
+        .filter(|(name, _)| exceptions.contains(name) && !raised_by_functions.contains(name))
+        .filter_map(
+            |(name, owners)| match owners.into_iter().collect::<Vec<_>>()[..] {
+                [owner] if owner != name && !exceptions.contains(owner) => {
+                    Some((name, owner.to_string()))
+                }
+                _ => None,
+            },
+        )
+        .collect()
+}
+
+/// Classes deriving from a builtin exception or from another exception class
+fn exception_classes(classes: &[HirClass]) -> HashSet<String> {
+    let mut exceptions = HashSet::new();
+    loop {
+        let found: Vec<String> = classes
+            .iter()
+            .filter(|c| !exceptions.contains(&c.name))
+            .filter(|c| {
+                c.base_classes
+                    .iter()
+                    .any(|base| is_builtin_exception(base) || exceptions.contains(base))
+            })
+            .map(|c| c.name.clone())
+            .collect();
+        if found.is_empty() {
+            return exceptions;
+        }
+        exceptions.extend(found);
+    }
+}
+
+fn is_builtin_exception(name: &str) -> bool {
+    name.ends_with("Error") || name.ends_with("Exception") || name.ends_with("Warning")
+}
+
+/// Names of the exceptions a body raises directly
+fn raised_names(stmts: &[HirStmt], raised: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            HirStmt::Raise {
+                exception: Some(exception),
+                ..
+            } => match exception {
+                HirExpr::Call { func, .. } | HirExpr::Var(func) => {
+                    raised.insert(func.clone());
+                }
+                _ => {}
+            },
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                raised_names(then_body, raised);
+                if let Some(else_body) = else_body {
+                    raised_names(else_body, raised);
+                }
+            }
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => raised_names(body, raised),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                raised_names(body, raised);
+                handlers.iter().for_each(|h| raised_names(&h.body, raised));
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    raised_names(block, raised);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                cases.iter().for_each(|c| raised_names(&c.body, raised));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::{HirMethod, Type};
+    use smallvec::smallvec;
+
+    fn class(name: &str, bases: &[&str], raises: &[&str]) -> HirClass {
+        let body = raises
+            .iter()
+            .map(|exception| HirStmt::Raise {
+                exception: Some(HirExpr::Call {
+                    func: exception.to_string(),
+                    args: vec![],
+                    kwargs: vec![],
+                }),
+                cause: None,
+            })
+            .collect();
+        HirClass {
+            name: name.to_string(),
+            base_classes: bases.iter().map(|b| b.to_string()).collect(),
+            methods: vec![HirMethod {
+                name: "run".to_string(),
+                params: smallvec![],
+                ret_type: Type::None,
+                body,
+                is_static: false,
+                is_classmethod: false,
+                is_property: false,
+                is_async: false,
+                docstring: None,
+            }],
+            fields: vec![],
+            is_dataclass: false,
+            model_kind: None,
+            annotations: Default::default(),
+            docstring: None,
+        }
+    }
+
+    #[test]
+    fn test_exception_raised_by_one_class_moves_into_its_module() {
+        let classes = vec![
+            class("ParseError", &["ValueError"], &[]),
+            class("BadToken", &["ParseError"], &[]),
+            class("SharedError", &["Exception"], &[]),
+            class("Parser", &[], &["ParseError", "BadToken", "SharedError"]),
+            class("Lexer", &[], &["SharedError"]),
+        ];
+        let owners = error_type_owners(&classes, &[]);
+        assert_eq!(owners.get("ParseError").map(String::as_str), Some("Parser"));
+        assert_eq!(owners.get("BadToken").map(String::as_str), Some("Parser"));
+        assert!(!owners.contains_key("SharedError"));
+    }
+
+    #[test]
+    fn test_nested_module_reexports_class_and_its_errors() {
+        let classes = vec![
+            class("StackError", &["Exception"], &[]),
+            class("Stack", &[], &["StackError"]),
+        ];
+        let items = classes
+            .iter()
+            .map(|c| {
+                let ident = format_ident!("{}", c.name);
+                (c.name.clone(), vec![quote! { pub struct #ident; }])
+            })
+            .collect();
+        let code = nest_classes(&classes, &[], items)
+            .into_iter()
+            .map(|item| item.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(code.len(), 1);
+        assert!(code[0].contains("pub mod stack"));
+        assert!(code[0].contains("pub struct StackError"));
+        assert!(code[0].contains("pub use stack :: { Stack , StackError }"));
+    }
+
+    #[test]
+    fn test_module_ident_avoids_keywords() {
+        assert_eq!(module_ident("Match").to_string(), "r#match");
+        assert_eq!(module_ident("Self").to_string(), "self_");
+        assert_eq!(module_ident("HTTPClient").to_string(), "http_client");
+    }
+}
@@ -0,0 +1,206 @@
+//! Comparisons across numeric types and between lists
+//!
+//! Python compares numbers by value whatever their type, so `1 == 1.0` and
+//! `True == 1` hold, and lists compare element by element. Rust compares
+//! values of one type only, so the narrower operand is promoted first:
+//!
+//! | Python                 | Rust                                      |
+//! |------------------------|-------------------------------------------|
+//! | `n == x` (int, float)  | `(n as f64) == x`                         |
+//! | `x < 1` (float)        | `x < 1.0`                                 |
+//! | `flag == 1` (bool)     | `i32::from(flag) == 1`                    |
+//! | `ints == floats`       | equal lengths and every pair promoted     |
+//! | `items == [1, 2]`      | `items[..] == vec![1, 2][..]`             |
+//! | `items == []`          | `items.is_empty()`                        |
+//!
+//! Comparing slices keeps borrowed (`&Vec`) and owned lists comparable.
+//! Operands of one type keep the plain operator.
+
+use crate::hir::{BinOp, HirExpr, Literal, Type};
+use crate::rust_gen::context::CodeGenContext;
+use syn::parse_quote;
+
+/// `left op right` for a comparison that needs promotion, or `None` when
+/// the plain operator already matches Python
+pub(crate) fn codegen_comparison(
+    op: BinOp,
+    left: &HirExpr,
+    right: &HirExpr,
+    left_expr: &syn::Expr,
+    right_expr: &syn::Expr,
+    ctx: &CodeGenContext,
+) -> Option<syn::Expr> {
+    if !matches!(
+        op,
+        BinOp::Eq | BinOp::NotEq | BinOp::Lt | BinOp::LtEq | BinOp::Gt | BinOp::GtEq
+    ) {
+        return None;
+    }
+    let left_type = ctx.value_type(left);
+    let right_type = ctx.value_type(right);
+
+    if let Some(common) = common_numeric(&left_type, &right_type) {
+        let left_expr = promote(Some(left), left_expr, &left_type, &common);
+        let right_expr = promote(Some(right), right_expr, &right_type, &common);
+        return Some(compare(op, &left_expr, &right_expr));
+    }
+
+    let negate = match op {
+        BinOp::Eq => false,
+        BinOp::NotEq => true,
+        _ => return None,
+    };
+    let (left_elem, right_elem) = match (&left_type, &right_type) {
+        (Type::List(left_elem), Type::List(right_elem)) => (left_elem, right_elem),
+        _ => return None,
+    };
+    if let Some(common) = common_numeric(left_elem, right_elem) {
+        return Some(elementwise(
+            left_expr, right_expr, left_elem, right_elem, &common, negate,
+        ));
+    }
+    match (left, right) {
+        (HirExpr::List(items), _) if items.is_empty() => Some(is_empty(right_expr, negate)),
+        (_, HirExpr::List(items)) if items.is_empty() => Some(is_empty(left_expr, negate)),
+        (HirExpr::List(_), _) | (_, HirExpr::List(_)) => {
+            let left_expr = operand(left_expr);
+            let right_expr = operand(right_expr);
+            Some(compare(
+                op,
+                &parse_quote! { #left_expr[..] },
+                &parse_quote! { #right_expr[..] },
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// The type two different numeric types compare as
+fn common_numeric(left: &Type, right: &Type) -> Option<Type> {
+    match (left, right) {
+        (Type::Int, Type::Float)
+        | (Type::Float, Type::Int)
+        | (Type::Bool, Type::Float)
+        | (Type::Float, Type::Bool) => Some(Type::Float),
+        (Type::Bool, Type::Int) | (Type::Int, Type::Bool) => Some(Type::Int),
+        _ => None,
+    }
+}
+
+/// `expr` of type `from` as a value of type `to`; literals are rewritten
+/// rather than cast
+fn promote(hir: Option<&HirExpr>, expr: &syn::Expr, from: &Type, to: &Type) -> syn::Expr {
+    let literal = match hir {
+        Some(HirExpr::Literal(Literal::Int(n))) => Some(*n as f64),
+        Some(HirExpr::Literal(Literal::Bool(b))) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    };
+    match (from, to, literal) {
+        (from, to, _) if from == to => expr.clone(),
+        (_, Type::Float, Some(value)) => {
+            let lit = proc_macro2::Literal::f64_unsuffixed(value);
+            parse_quote! { #lit }
+        }
+        (_, Type::Int, Some(value)) => {
+            let lit = proc_macro2::Literal::i64_unsuffixed(value as i64);
+            parse_quote! { #lit }
+        }
+        (Type::Int, Type::Float, None) => match expr {
+            syn::Expr::Binary(_) => parse_quote! { ((#expr) as f64) },
+            _ => parse_quote! { (#expr as f64) },
+        },
+        (Type::Bool, Type::Float, None) => parse_quote! { f64::from(u8::from(#expr)) },
+        _ => parse_quote! { i32::from(#expr) },
+    }
+}
+
+/// `left` and `right` hold equal numbers pair by pair
+fn elementwise(
+    left_expr: &syn::Expr,
+    right_expr: &syn::Expr,
+    left_elem: &Type,
+    right_elem: &Type,
+    common: &Type,
+    negate: bool,
+) -> syn::Expr {
+    let a = promote(None, &parse_quote! { *a }, left_elem, common);
+    let b = promote(None, &parse_quote! { *b }, right_elem, common);
+    if negate {
+        parse_quote! {
+            {
+                let (__left, __right) = (&#left_expr, &#right_expr);
+                __left.len() != __right.len()
+                    || __left.iter().zip(__right.iter()).any(|(a, b)| #a != #b)
+            }
+        }
+    } else {
+        parse_quote! {
+            {
+                let (__left, __right) = (&#left_expr, &#right_expr);
+                __left.len() == __right.len()
+                    && __left.iter().zip(__right.iter()).all(|(a, b)| #a == #b)
+            }
+        }
+    }
+}
+
+fn is_empty(list: &syn::Expr, negate: bool) -> syn::Expr {
+    let list = operand(list);
+    if negate {
+        parse_quote! { !#list.is_empty() }
+    } else {
+        parse_quote! { #list.is_empty() }
+    }
+}
+
+fn compare(op: BinOp, left: &syn::Expr, right: &syn::Expr) -> syn::Expr {
+    match op {
+        BinOp::Eq => parse_quote! { #left == #right },
+        BinOp::NotEq => parse_quote! { #left != #right },
+        BinOp::Lt => parse_quote! { #left < #right },
+        BinOp::LtEq => parse_quote! { #left <= #right },
+        BinOp::Gt => parse_quote! { #left > #right },
+        _ => parse_quote! { #left >= #right },
+    }
+}
+
+/// Parenthesizes `expr` where a following cast, index or method call would
+/// otherwise bind to part of it
+fn operand(expr: &syn::Expr) -> syn::Expr {
+    match expr {
+        syn::Expr::Binary(_)
+        | syn::Expr::Unary(_)
+        | syn::Expr::Reference(_)
+        | syn::Expr::Cast(_) => {
+            parse_quote! { (#expr) }
+        }
+        _ => expr.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_int_is_promoted_against_float() {
+        let code = transpile("def same(n: int, x: float) -> bool:\n    return n == x\n");
+
+        assert!(code.contains("(nasf64)==x"));
+    }
+
+    #[test]
+    fn test_int_literal_becomes_float_literal() {
+        let code = transpile("def small(x: float) -> bool:\n    return x < 1\n");
+
+        assert!(code.contains("x<1.0"));
+    }
+
+    #[test]
+    fn test_same_types_keep_the_plain_operator() {
+        let code = transpile("def same(a: int, b: int) -> bool:\n    return a == b\n");
+
+        assert!(code.contains("a==b"));
+        assert!(!code.contains("asf64"));
+    }
+}
@@ -5,10 +5,11 @@
 //! the code generation pipeline.
 
 use crate::annotation_aware_type_mapper::AnnotationAwareTypeMapper;
-use crate::hir::{ExceptionScope, Type};
+use crate::hir::{BinOp, ExceptionScope, HirExpr, Literal, Type, UnaryOp};
 use crate::string_optimization::StringOptimizer;
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use depyler_annotations::{IntegerSemantics, PrintHandling, StringIndexing};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 /// Error type classification for Result<T, E> return types
 ///
@@ -26,8 +27,32 @@ pub enum ErrorType {
     /// Box<dyn Error> - mixed or generic error types
     /// Needs wrapping: `return Err(Box::new(ValueError::new(...)))`
     DynBox,
+    /// The module's `ModuleError` enum
+    /// Needs conversion: `return Err(ValueError::new(...).into())`
+    ModuleEnum,
 }
 
+/// The `except` clause whose body is currently being generated
+///
+/// Lets handler-only calls such as `traceback.format_exc()` refer to the
+/// exception being handled.
+///
+/// # Complexity
+/// N/A (data structure)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerBinding {
+    /// Exception type named in the clause (`None` for a bare `except:`)
+    pub exception_type: Option<String>,
+    /// Variable bound with `as` (`None` without a binding)
+    pub name: Option<String>,
+    /// Rust type of the error the generated code binds to `name`, if it
+    /// binds one, so a `raise` in the handler can chain or re-raise it
+    pub bound_error: Option<String>,
+}
+
+/// Type recorded in `CodeGenContext::var_types` for timer variables
+pub const INSTANT_TYPE: &str = "std::time::Instant";
+
 /// Code generation context
 ///
 /// Maintains all state needed during Rust code generation including:
@@ -72,6 +97,23 @@ pub struct CodeGenContext<'a> {
     pub declared_vars: Vec<HashSet<String>>,
     pub current_function_can_fail: bool,
     pub current_return_type: Option<Type>,
+    /// Parameter the current function's return value borrows from
+    pub current_borrowed_return: Option<String>,
+    /// The current function returns a `Cow` borrowing from that parameter
+    /// on some paths
+    pub current_return_is_cow: bool,
+    /// Local lists of the current function generated as `VecDeque`
+    pub deque_vars: HashSet<String>,
+    /// Local lists of the current function generated as `HashSet`
+    pub set_vars: HashSet<String>,
+    /// `integer_semantics` of the function being generated
+    pub integer_semantics: IntegerSemantics,
+    /// `string_indexing` of the function being generated
+    pub string_indexing: StringIndexing,
+    /// What becomes of `print()` statements in the function being generated
+    pub print_handling: PrintHandling,
+    /// Deviations from Python recorded while generating
+    pub divergences: crate::rust_gen::ledger::DivergenceLedger,
     pub module_mapper: crate::module_mapper::ModuleMapper,
     pub imported_modules: std::collections::HashMap<String, crate::module_mapper::ModuleMapping>,
     pub imported_items: std::collections::HashMap<String, String>,
@@ -79,6 +121,26 @@ pub struct CodeGenContext<'a> {
     pub needs_zerodivisionerror: bool,
     pub needs_indexerror: bool,
     pub needs_valueerror: bool,
+    pub needs_typeerror: bool,
+    pub needs_stopiteration: bool,
+    pub needs_overflowerror: bool,
+    /// `int(s)` / `float(s)` on strings call the emitted `py_int` / `py_float`
+    pub needs_py_int: bool,
+    pub needs_py_float: bool,
+    /// `str()` / `repr()` call `py_repr` from the emitted `PyRepr` trait
+    pub needs_py_repr: bool,
+    /// Slices and slice assignments call the emitted `py_slice` helpers
+    pub needs_py_slice: bool,
+    pub needs_py_slice_assign: bool,
+    pub needs_py_slice_assign_step: bool,
+    /// Step-less string slices call `py_str_slice`, or `py_ascii_slice` when
+    /// indexing ASCII strings
+    pub needs_py_str_slice: bool,
+    pub needs_py_ascii_slice: bool,
+    /// Step-less list slices returned by reference call `py_subslice`
+    pub needs_py_subslice: bool,
+    /// `async for` polls streams through `futures::StreamExt`
+    pub needs_stream_ext: bool,
     pub is_classmethod: bool,
     pub in_generator: bool,
     pub generator_state_vars: HashSet<String>,
@@ -93,6 +155,9 @@ pub struct CodeGenContext<'a> {
     /// Maps function name -> Vec of booleans (true if param is borrowed, false if owned)
     /// Used to determine whether to add & when passing List/Dict/Set arguments
     pub function_param_borrows: HashMap<String, Vec<bool>>,
+    /// Parameters of the module's functions, which calls bind their keyword
+    /// and omitted arguments to
+    pub(crate) function_signatures: HashMap<String, crate::rust_gen::default_param_gen::Signature>,
     /// DEPYLER-0307 Fix #9: Track variables that iterate over tuples (from zip())
     /// Used to generate tuple field access syntax (tuple.0, tuple.1) instead of vector indexing
     pub tuple_iter_vars: HashSet<String>,
@@ -105,6 +170,12 @@ pub struct CodeGenContext<'a> {
     /// DEPYLER-0270: Track ALL functions that return Result<T, E>
     /// Used to auto-unwrap at call sites in non-Result functions
     pub result_returning_functions: HashSet<String>,
+    /// Functions returning a reference into a parameter, by the position
+    /// of that parameter; callers lend the argument and take an owned copy
+    /// of the result
+    pub borrowed_return_functions: HashMap<String, usize>,
+    /// Those of `borrowed_return_functions` returning a `Cow`
+    pub cow_return_functions: HashSet<String>,
     /// DEPYLER-0310: Current function's error type (for raise statement wrapping)
     /// None if function doesn't return Result, Some(ErrorType) if it does
     pub current_error_type: Option<ErrorType>,
@@ -116,6 +187,32 @@ pub struct CodeGenContext<'a> {
     /// Accumulates ArgumentParser instances and add_argument calls
     /// to generate #[derive(Parser)] struct definitions
     pub argparser_tracker: crate::rust_gen::argparse_transform::ArgParserTracker,
+    /// User-selected code generation options for this file
+    pub options: crate::rust_gen::options::CodeGenOptions,
+    /// Innermost `except` clause being generated, if any
+    pub current_handler: Option<HandlerBinding>,
+    /// Module functions decorated with `@contextmanager`
+    pub context_manager_functions: HashSet<String>,
+    pub needs_context_guard: bool,
+    pub needs_exit_stack: bool,
+    /// Classes defining `__enter__` and `__exit__`
+    pub context_manager_classes:
+        HashMap<String, crate::rust_gen::context_manager_gen::ContextManagerClass>,
+    /// Classes whose `<Class>Exit` guard a `with` statement uses
+    pub needs_exit_guards: BTreeSet<String>,
+    /// Module values computed at runtime, emitted as `LazyLock` statics
+    pub lazy_globals: HashMap<String, Type>,
+    /// Classes whose structs derive `serde::Serialize`/`Deserialize`
+    pub serde_classes: HashSet<String>,
+    /// Field types of the module's classes, for `obj.attr` on typed values
+    pub class_fields: crate::field_inference::FieldTypeTable,
+    /// Classes kept in arenas and the functions passing their arenas along
+    pub(crate) arenas: crate::rust_gen::arena_gen::ArenaLayout,
+    /// `pub`, `pub(crate)` or private for each module function
+    pub function_visibility: crate::visibility::VisibilityInference,
+    /// `#[inline]`, `#[must_use]` and complexity notes for each module
+    /// function, empty unless `CodeGenOptions::attribute_synthesis` is set
+    pub function_attributes: crate::attribute_synthesis::AttributeSynthesis,
 }
 
 impl<'a> CodeGenContext<'a> {
@@ -170,6 +267,140 @@ impl<'a> CodeGenContext<'a> {
         enum_name
     }
 
+    /// Check if an expression evaluates to a `std::time::Instant`
+    ///
+    /// `time.perf_counter()`, `time.monotonic()` and `timeit.default_timer()`
+    /// lower to `Instant::now()`; variables assigned from them are tracked in
+    /// `var_types` so that `end - start` can become `elapsed()` arithmetic.
+    ///
+    /// # Complexity
+    /// 3 (match + nested match + lookup)
+    pub fn is_instant_expr(&self, expr: &HirExpr) -> bool {
+        match expr {
+            HirExpr::MethodCall { object, method, .. } => match object.as_ref() {
+                HirExpr::Var(module) => matches!(
+                    (module.as_str(), method.as_str()),
+                    ("time", "perf_counter" | "monotonic") | ("timeit", "default_timer")
+                ),
+                _ => false,
+            },
+            HirExpr::Var(name) => matches!(
+                self.var_types.get(name),
+                Some(Type::Custom(ty)) if ty == INSTANT_TYPE
+            ),
+            _ => false,
+        }
+    }
+
+    /// Check if an expression is a timer delta (`perf_counter() - start`)
+    ///
+    /// Such deltas are generated as `f64` seconds, matching Python.
+    ///
+    /// # Complexity
+    /// 2 (match + two lookups)
+    pub fn is_elapsed_expr(&self, expr: &HirExpr) -> bool {
+        matches!(
+            expr,
+            HirExpr::Binary { op: BinOp::Sub, left, right }
+                if self.is_instant_expr(left) && self.is_instant_expr(right)
+        )
+    }
+
+    /// The type of `expr` as far as variable types and simple rules go
+    ///
+    /// `Type::Unknown` when neither says; literals, annotated variables,
+    /// calls of builtins and module functions, indexing and arithmetic on
+    /// those are typed.
+    ///
+    /// # Complexity
+    /// 8 (match over expression kinds + recursion)
+    pub fn value_type(&self, expr: &HirExpr) -> Type {
+        match expr {
+            HirExpr::Literal(Literal::Int(_)) => Type::Int,
+            HirExpr::Literal(Literal::Float(_)) => Type::Float,
+            HirExpr::Literal(Literal::String(_)) => Type::String,
+            HirExpr::Literal(Literal::Bool(_)) => Type::Bool,
+            HirExpr::Literal(Literal::None) => Type::None,
+            HirExpr::Var(name) => self.var_types.get(name).cloned().unwrap_or(Type::Unknown),
+            HirExpr::List(items) => Type::List(Box::new(
+                items
+                    .first()
+                    .map(|item| self.value_type(item))
+                    .unwrap_or(Type::Unknown),
+            )),
+            HirExpr::Call { func, .. } => match func.as_str() {
+                "float" => Type::Float,
+                "int" | "len" => Type::Int,
+                "bool" | "isinstance" => Type::Bool,
+                "str" | "repr" => Type::String,
+                _ => self
+                    .function_return_types
+                    .get(func)
+                    .cloned()
+                    .unwrap_or(Type::Unknown),
+            },
+            HirExpr::Index { base, index } => match (self.value_type(base), &**index) {
+                (Type::List(elem), _) => *elem,
+                (Type::Dict(_, value), _) => *value,
+                (Type::Tuple(items), HirExpr::Literal(Literal::Int(n))) => usize::try_from(*n)
+                    .ok()
+                    .and_then(|n| items.get(n).cloned())
+                    .unwrap_or(Type::Unknown),
+                _ => Type::Unknown,
+            },
+            HirExpr::Unary {
+                op: UnaryOp::Not, ..
+            } => Type::Bool,
+            HirExpr::Unary { operand, .. } => self.value_type(operand),
+            HirExpr::Binary { op, left, right } => match op {
+                BinOp::Mod if matches!(**left, HirExpr::Literal(Literal::String(_))) => {
+                    Type::String
+                }
+                BinOp::Eq
+                | BinOp::NotEq
+                | BinOp::Lt
+                | BinOp::LtEq
+                | BinOp::Gt
+                | BinOp::GtEq
+                | BinOp::In
+                | BinOp::NotIn => Type::Bool,
+                BinOp::Add
+                | BinOp::Sub
+                | BinOp::Mul
+                | BinOp::Div
+                | BinOp::FloorDiv
+                | BinOp::Mod
+                | BinOp::Pow => match (self.value_type(left), self.value_type(right)) {
+                    (Type::Float, right) if right.is_numeric() => Type::Float,
+                    (left, Type::Float) if left.is_numeric() => Type::Float,
+                    (Type::Int, Type::Int) => Type::Int,
+                    _ => Type::Unknown,
+                },
+                _ => Type::Unknown,
+            },
+            _ => Type::Unknown,
+        }
+    }
+
+    /// Check if values of `ty` hold a class deriving serde's traits
+    ///
+    /// `json.dumps`/`json.loads` of such values go through the derived
+    /// impls rather than `serde_json::Value`.
+    ///
+    /// # Complexity
+    /// 3 (match + recursion + lookup)
+    pub fn is_serde_type(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Custom(name) => self.serde_classes.contains(name),
+            Type::List(inner) | Type::Set(inner) | Type::Optional(inner) => {
+                self.is_serde_type(inner)
+            }
+            Type::Dict(_, value) => self.is_serde_type(value),
+            Type::Tuple(items) => items.iter().any(|item| self.is_serde_type(item)),
+            _ => false,
+        }
+    }
+
     // ========================================================================
     // DEPYLER-0333: Exception Scope Tracking
     // ========================================================================
@@ -0,0 +1,380 @@
+//! Context managers lowered to RAII guards
+//!
+//! - `with open(path, mode) as f` opens a `std::fs::File`; leaving the block
+//!   drops it, which closes the file.
+//! - `with lock` over a `threading.Lock()` holds a `std::sync::Mutex<()>`
+//!   guard for the length of the block.
+//! - `with Manager(...) as m`, for a class defining `__enter__` and
+//!   `__exit__`, wraps the manager in a generated `ManagerExit` guard whose
+//!   `Drop` calls `__exit__`, so it runs on every way out of the block,
+//!   `return` and `?` included.
+
+use crate::hir::{HirClass, HirExpr, HirStmt, Literal, Type};
+use crate::rust_gen::context::{CodeGenContext, ErrorType, RustCodeGen, ToRustExpr};
+use crate::rust_gen::keywords::safe_ident;
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::parse_quote;
+
+/// Type recorded in `CodeGenContext::var_types` for `with open(...) as f`
+pub const FILE_TYPE: &str = "std::fs::File";
+
+/// Type of a `threading.Lock()`
+pub const LOCK_TYPE: &str = "std::sync::Mutex<()>";
+
+/// What code generation needs to know about a class defining `__enter__`
+/// and `__exit__`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextManagerClass {
+    /// `__enter__` returns `self`, so `as` binds the manager itself
+    pub enter_returns_self: bool,
+    /// Types of the `__exit__` parameters after `self`
+    pub exit_params: Vec<Type>,
+    pub exit_returns: Type,
+}
+
+/// The classes of a module usable in `with` statements, by name
+pub fn context_manager_classes(classes: &[HirClass]) -> HashMap<String, ContextManagerClass> {
+    classes
+        .iter()
+        .filter_map(|class| {
+            let method = |name: &str| class.methods.iter().find(|m| m.name == name);
+            let enter = method("__enter__")?;
+            let exit = method("__exit__")?;
+            let manager = ContextManagerClass {
+                enter_returns_self: enter.body.iter().any(
+                    |stmt| matches!(stmt, HirStmt::Return(Some(HirExpr::Var(v))) if v == "self"),
+                ),
+                exit_params: exit.params.iter().map(|p| p.ty.clone()).collect(),
+                exit_returns: exit.ret_type.clone(),
+            };
+            Some((class.name.clone(), manager))
+        })
+        .collect()
+}
+
+/// `threading.Lock()`
+pub(crate) fn is_lock_constructor(expr: &HirExpr) -> bool {
+    matches!(
+        expr,
+        HirExpr::MethodCall { object, method, args, .. }
+            if method == "Lock"
+                && args.is_empty()
+                && matches!(object.as_ref(), HirExpr::Var(module) if module == "threading")
+    )
+}
+
+/// A `with` statement whose context manager maps onto a guard
+enum GuardWith<'a> {
+    /// `open(path, mode)` with a literal mode
+    File {
+        path: &'a HirExpr,
+        mode: String,
+    },
+    Lock,
+    /// Instance of a class in `CodeGenContext::context_manager_classes`
+    Class(String),
+}
+
+/// # Complexity
+/// 7 (match + open args + variable type lookups)
+fn classify_with<'e>(context: &'e HirExpr, ctx: &CodeGenContext) -> Option<GuardWith<'e>> {
+    match context {
+        HirExpr::Call { func, args, kwargs } if func == "open" => {
+            let mode = args
+                .get(1)
+                .or_else(|| kwargs.iter().find(|(n, _)| n == "mode").map(|(_, v)| v));
+            let mode = match mode {
+                None => "r".to_string(),
+                Some(HirExpr::Literal(Literal::String(mode))) => mode.clone(),
+                Some(_) => return None,
+            };
+            Some(GuardWith::File {
+                path: args.first()?,
+                mode,
+            })
+        }
+        HirExpr::Call { func, .. } if ctx.context_manager_classes.contains_key(func) => {
+            Some(GuardWith::Class(func.clone()))
+        }
+        HirExpr::Var(name) => match ctx
+            .var_types
+            .get(name)
+            .or_else(|| ctx.lazy_globals.get(name))
+        {
+            Some(Type::Custom(ty)) if ty == LOCK_TYPE => Some(GuardWith::Lock),
+            Some(Type::Custom(class)) if ctx.context_manager_classes.contains_key(class) => {
+                Some(GuardWith::Class(class.clone()))
+            }
+            _ => None,
+        },
+        _ if is_lock_constructor(context) => Some(GuardWith::Lock),
+        _ => None,
+    }
+}
+
+/// Generate a `with` statement over a file, a lock or a class with `__exit__`
+///
+/// Returns `None` for any other context manager, and for `open()` with a
+/// mode that is not a literal.
+///
+/// # Complexity
+/// 7 (classification + 3 forms + target handling)
+pub(crate) fn try_codegen_guard_with(
+    context: &HirExpr,
+    target: &Option<String>,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let kind = match classify_with(context, ctx) {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+    let setup = match kind {
+        GuardWith::File { path, mode } => {
+            let path = path.to_rust_expr(ctx)?;
+            let open = match open_file(&path, &mode) {
+                Some(open) => open,
+                None => return Ok(None),
+            };
+            let open = handle_io_error(open, "open file", ctx);
+            let name = target.clone().unwrap_or_else(|| "_file".to_string());
+            ctx.declare_var(&name);
+            ctx.var_types
+                .insert(name.clone(), Type::Custom(FILE_TYPE.to_string()));
+            let file = safe_ident(&name);
+            quote! { let #file = #open; }
+        }
+        GuardWith::Lock => {
+            let lock = context.to_rust_expr(ctx)?;
+            let acquire = quote! { .lock().unwrap_or_else(std::sync::PoisonError::into_inner) };
+            let mut setup = if matches!(context, HirExpr::Var(_)) {
+                quote! { let _guard = #lock #acquire; }
+            } else {
+                quote! {
+                    let _lock = #lock;
+                    let _guard = _lock #acquire;
+                }
+            };
+            // `Lock.__enter__` returns True
+            if let Some(name) = target {
+                ctx.declare_var(name);
+                let ident = safe_ident(name);
+                setup.extend(quote! { let #ident = true; });
+            }
+            setup
+        }
+        GuardWith::Class(class) => codegen_class_setup(context, &class, target, ctx)?,
+    };
+
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+    let body_stmts = body
+        .iter()
+        .map(|stmt| stmt.to_rust_tokens(ctx))
+        .collect::<Result<Vec<_>>>();
+    ctx.is_final_statement = saved_is_final;
+    let body_stmts = body_stmts?;
+
+    Ok(Some(quote! {
+        {
+            #setup
+            #(#body_stmts)*
+        }
+    }))
+}
+
+/// Open a file the way a Python mode string does
+///
+/// # Complexity
+/// 2 (mode match)
+fn open_file(path: &syn::Expr, mode: &str) -> Option<syn::Expr> {
+    // Binary and text files are both byte streams here
+    let mode: String = mode.chars().filter(|c| !matches!(c, 'b' | 't')).collect();
+    let options: TokenStream = match mode.as_str() {
+        "r" => return Some(parse_quote! { std::fs::File::open(&#path) }),
+        "w" => return Some(parse_quote! { std::fs::File::create(&#path) }),
+        "a" => quote! { .append(true).create(true) },
+        "x" => quote! { .write(true).create_new(true) },
+        "r+" => quote! { .read(true).write(true) },
+        "w+" => quote! { .read(true).write(true).create(true).truncate(true) },
+        "a+" => quote! { .read(true).append(true).create(true) },
+        _ => return None,
+    };
+    Some(parse_quote! { std::fs::OpenOptions::new() #options .open(&#path) })
+}
+
+/// Propagate an `io::Error`, or another error a boxed error converts from,
+/// from functions returning a boxed error; panic with `action` in the
+/// message elsewhere
+pub(crate) fn handle_io_error(expr: syn::Expr, action: &str, ctx: &CodeGenContext) -> syn::Expr {
+    if ctx.current_function_can_fail && ctx.current_error_type == Some(ErrorType::DynBox) {
+        parse_quote! { #expr? }
+    } else {
+        let message = format!("failed to {}", action);
+        parse_quote! { #expr.expect(#message) }
+    }
+}
+
+/// Bind a class-based context manager and put its `__exit__` guard in place
+///
+/// ```text
+/// let mut _manager = Transaction::new(db);
+/// let tx = _manager.__enter__();
+/// let _exit = TransactionExit(&mut _manager);
+/// ```
+///
+/// When `__enter__` returns `self`, the guard itself is bound to the target
+/// and derefs to the manager.
+///
+/// # Complexity
+/// 5 (context form + target forms)
+fn codegen_class_setup(
+    context: &HirExpr,
+    class: &str,
+    target: &Option<String>,
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let enter_returns_self = ctx.context_manager_classes[class].enter_returns_self;
+    ctx.needs_exit_guards.insert(class.to_string());
+    let guard_type = format_ident!("{}Exit", class);
+
+    let mut setup = TokenStream::new();
+    let manager = match context {
+        HirExpr::Var(name) => safe_ident(name),
+        _ => {
+            let value = context.to_rust_expr(ctx)?;
+            setup.extend(quote! { let mut _manager = #value; });
+            format_ident!("_manager")
+        }
+    };
+
+    match target {
+        Some(name) if enter_returns_self => {
+            ctx.declare_var(name);
+            ctx.var_types
+                .insert(name.clone(), Type::Custom(class.to_string()));
+            let ident = safe_ident(name);
+            let binding = if ctx.mutable_vars.contains(name) {
+                quote! { mut #ident }
+            } else {
+                quote! { #ident }
+            };
+            setup.extend(quote! {
+                #manager.__enter__();
+                let #binding = #guard_type(&mut #manager);
+            });
+        }
+        Some(name) => {
+            ctx.declare_var(name);
+            let ident = safe_ident(name);
+            setup.extend(quote! {
+                let #ident = #manager.__enter__();
+                let _exit = #guard_type(&mut #manager);
+            });
+        }
+        None => {
+            setup.extend(quote! {
+                #manager.__enter__();
+                let _exit = #guard_type(&mut #manager);
+            });
+        }
+    }
+    Ok(setup)
+}
+
+/// Method calls on a file bound by `with open(...)`
+///
+/// Returns `None` for methods without a mapping, which are generated as
+/// ordinary method calls.
+///
+/// # Complexity
+/// 4 (method match)
+pub(crate) fn codegen_file_method(
+    file: &str,
+    method: &str,
+    args: &[HirExpr],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let file = safe_ident(file);
+    let read: syn::Expr = parse_quote! { std::io::read_to_string(&#file) };
+    let expr = match (method, args) {
+        ("read", []) => handle_io_error(read, "read file", ctx),
+        ("readlines", []) => {
+            let text = handle_io_error(read, "read file", ctx);
+            parse_quote! {
+                #text.split_inclusive('\n').map(str::to_string).collect::<Vec<String>>()
+            }
+        }
+        ("write", [text]) => {
+            let text = text.to_rust_expr(ctx)?;
+            let write = parse_quote! { std::io::Write::write_all(&mut &#file, #text.as_bytes()) };
+            handle_io_error(write, "write file", ctx)
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(expr))
+}
+
+/// The `<Class>Exit` guards used by the module's `with` statements
+///
+/// # Complexity
+/// 4 (class loop + parameter mapping)
+pub fn generate_exit_guards(ctx: &mut CodeGenContext) -> Vec<TokenStream> {
+    let mut guards = Vec::new();
+    for class in ctx.needs_exit_guards.clone() {
+        let manager = &ctx.context_manager_classes[&class];
+        let exit_args: Vec<TokenStream> = manager
+            .exit_params
+            .iter()
+            .map(|ty| match ty {
+                Type::Optional(_) => quote! { None },
+                // Unannotated parameters are `serde_json::Value`
+                Type::Unknown => quote! { serde_json::Value::Null },
+                _ => quote! { Default::default() },
+            })
+            .collect();
+        let exit_call = if matches!(manager.exit_returns, Type::None | Type::Unknown) {
+            quote! { self.0.__exit__(#(#exit_args),*); }
+        } else {
+            // The flag that suppresses an exception; there is none to suppress here
+            quote! { let _ = self.0.__exit__(#(#exit_args),*); }
+        };
+        if manager.exit_params.contains(&Type::Unknown) {
+            ctx.needs_serde_json = true;
+        }
+
+        let class_type = format_ident!("{}", class);
+        let guard_type = format_ident!("{}Exit", class);
+        let doc = format!(
+            " Calls `{}::__exit__` when dropped, like leaving a `with` block",
+            class
+        );
+        guards.push(quote! {
+            #[doc = #doc]
+            pub struct #guard_type<'a>(&'a mut #class_type);
+
+            impl std::ops::Deref for #guard_type<'_> {
+                type Target = #class_type;
+                fn deref(&self) -> &#class_type {
+                    &*self.0
+                }
+            }
+
+            impl std::ops::DerefMut for #guard_type<'_> {
+                fn deref_mut(&mut self) -> &mut #class_type {
+                    &mut *self.0
+                }
+            }
+
+            impl Drop for #guard_type<'_> {
+                fn drop(&mut self) {
+                    #exit_call
+                }
+            }
+        });
+    }
+    guards
+}
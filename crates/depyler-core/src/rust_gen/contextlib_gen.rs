@@ -0,0 +1,604 @@
+//! contextlib support and code generation
+//!
+//! - `@contextmanager` generators become functions returning the yielded value
+//!   together with a `ContextGuard` that runs the post-yield code on drop.
+//! - `with suppress(...)` runs its block in a closure returning `Result`;
+//!   errors of the suppressed types are dropped and others propagate.
+//!   Exceptions without a Rust error type here, such as `KeyError`, are
+//!   never an `Err` of the generated code, so suppressing them drops
+//!   nothing. `return`, `break` and `continue` would leave only the
+//!   closure, so the block may not contain them.
+//! - `with ExitStack() as stack` becomes an `ExitStack` of boxed cleanup
+//!   closures that run in reverse registration order when it is dropped.
+
+use crate::ast_bridge::FunctionAnalyzer;
+use crate::generator_yield_analysis::YieldAnalysis;
+use crate::hir::{HirExpr, HirFunction, HirStmt, Type};
+use crate::rust_gen::context::{CodeGenContext, ErrorType, RustCodeGen, ToRustExpr};
+use crate::rust_gen::generator_gen::infer_yield_type;
+use crate::rust_gen::keywords::safe_ident;
+use crate::rust_gen::type_gen::rust_type_to_syn;
+use anyhow::{bail, Result};
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::parse_quote;
+
+/// Type recorded in `CodeGenContext::var_types` for `with ExitStack() as stack`
+pub const EXIT_STACK_TYPE: &str = "ExitStack";
+
+/// A `with` statement whose context manager comes from contextlib
+enum ContextlibWith<'a> {
+    /// Call to a module-level `@contextmanager` function
+    Guard(&'a HirExpr),
+    /// `suppress(E1, E2, ...)`, by exception name
+    Suppress(Vec<&'a str>),
+    /// `ExitStack()`
+    ExitStack,
+}
+
+/// The statements around the single `yield` of a `@contextmanager` body
+struct YieldSplit<'a> {
+    enter: Vec<&'a HirStmt>,
+    value: Option<&'a HirExpr>,
+    exit: Vec<&'a HirStmt>,
+}
+
+/// Split a generator body into its `__enter__` and `__exit__` halves
+///
+/// Handles a top-level `yield` and the idiomatic
+/// `try: ... yield ... finally: cleanup` form. `except` clauses around the
+/// yield are dropped: the `with` body's errors are not routed back into the
+/// generator.
+///
+/// # Complexity
+/// 5 (loop + match + recursion + finally merge)
+fn split_at_yield(body: &[HirStmt]) -> Option<YieldSplit<'_>> {
+    for (i, stmt) in body.iter().enumerate() {
+        match stmt {
+            HirStmt::Expr(HirExpr::Yield { value }) => {
+                return Some(YieldSplit {
+                    enter: body[..i].iter().collect(),
+                    value: value.as_deref(),
+                    exit: body[i + 1..].iter().collect(),
+                });
+            }
+            HirStmt::Try {
+                body: try_body,
+                finalbody,
+                ..
+            } => {
+                if let Some(inner) = split_at_yield(try_body) {
+                    let mut enter: Vec<&HirStmt> = body[..i].iter().collect();
+                    enter.extend(inner.enter);
+                    let mut exit = inner.exit;
+                    exit.extend(finalbody.iter().flatten());
+                    exit.extend(body[i + 1..].iter());
+                    return Some(YieldSplit {
+                        enter,
+                        value: inner.value,
+                        exit,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Type of the value bound by `with cm() as x`
+///
+/// Prefers the `Iterator[T]` / `Generator[T, ...]` annotation, then the
+/// yielded expression.
+///
+/// # Complexity
+/// 4 (annotation match + value match)
+fn context_value_type(func: &HirFunction, value: Option<&HirExpr>, ctx: &CodeGenContext) -> Type {
+    if let Type::Generic { base, params } = &func.ret_type {
+        if matches!(
+            base.as_str(),
+            "Iterator" | "Generator" | "ContextManager" | "Iterable"
+        ) {
+            if let Some(item) = params.first() {
+                return item.clone();
+            }
+        }
+    }
+
+    match value {
+        None => Type::None,
+        Some(HirExpr::Var(name)) => ctx.var_types.get(name).cloned().unwrap_or(Type::Unknown),
+        Some(expr) => infer_yield_type(expr),
+    }
+}
+
+/// Whether `name` appears as an identifier anywhere in `tokens`
+fn tokens_mention(tokens: &TokenStream, name: &str) -> bool {
+    tokens.clone().into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == name,
+        TokenTree::Group(group) => tokens_mention(&group.stream(), name),
+        _ => false,
+    })
+}
+
+/// Generate a `@contextmanager` function
+///
+/// ```text
+/// pub fn timer(label: String) -> (f64, ContextGuard<'static>) {
+///     let start = ...;                       // pre-yield code
+///     (start.clone(), ContextGuard::new(move || { ... }))  // post-yield code
+/// }
+/// ```
+///
+/// Returns `None` when the body is not a single-yield generator, in which
+/// case the caller falls back to regular generator generation.
+///
+/// # Complexity
+/// 6 (yield checks + statement conversion + value clone + lifetime choice)
+#[allow(clippy::too_many_arguments)] // Mirrors codegen_generator_function
+pub fn codegen_context_manager_function(
+    func: &HirFunction,
+    name: &syn::Ident,
+    generic_params: &TokenStream,
+    where_clause: &TokenStream,
+    params: &[TokenStream],
+    attrs: &[TokenStream],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    if YieldAnalysis::analyze(func).yield_points.len() != 1 {
+        return Ok(None);
+    }
+    let split = match split_at_yield(&func.body) {
+        Some(split) => split,
+        None => return Ok(None),
+    };
+
+    ctx.enter_scope();
+    for param in &func.params {
+        ctx.declare_var(&param.name);
+    }
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+
+    let enter_stmts = split
+        .enter
+        .iter()
+        .map(|stmt| stmt.to_rust_tokens(ctx))
+        .collect::<Result<Vec<_>>>()?;
+    let value_type = context_value_type(func, split.value, ctx);
+    let value_expr: syn::Expr = match split.value {
+        Some(value) => value.to_rust_expr(ctx)?,
+        None => parse_quote! { () },
+    };
+    let exit_stmts = split
+        .exit
+        .iter()
+        .map(|stmt| stmt.to_rust_tokens(ctx))
+        .collect::<Result<Vec<_>>>()?;
+
+    ctx.is_final_statement = saved_is_final;
+    ctx.exit_scope();
+    ctx.needs_context_guard = true;
+
+    let exit_code = quote! { #(#exit_stmts)* };
+
+    // The caller receives the yielded value; the cleanup closure may still need it
+    let value_expr: syn::Expr = match split.value {
+        Some(HirExpr::Var(var)) if tokens_mention(&exit_code, var) => {
+            parse_quote! { #value_expr.clone() }
+        }
+        _ => value_expr,
+    };
+    let value_ty = rust_type_to_syn(&ctx.type_mapper.map_type(&value_type))?;
+
+    // Borrowed parameters captured by the cleanup closure tie the guard to them
+    let borrows_params = params.iter().any(|p| p.to_string().contains('&'));
+    let guard_ty = if borrows_params {
+        quote! { ContextGuard<'_> }
+    } else {
+        quote! { ContextGuard<'static> }
+    };
+
+    let vis = ctx.function_visibility.tokens(&func.name);
+    Ok(Some(quote! {
+        #(#attrs)*
+        #vis fn #name #generic_params(#(#params),*) -> (#value_ty, #guard_ty) #where_clause {
+            #(#enter_stmts)*
+            (#value_expr, ContextGuard::new(move || { #exit_code }))
+        }
+    }))
+}
+
+/// Recognize contextlib context managers in a `with` statement
+///
+/// # Complexity
+/// 5 (match + name checks)
+fn classify_with<'e>(context: &'e HirExpr, ctx: &CodeGenContext) -> Option<ContextlibWith<'e>> {
+    let name = match context {
+        HirExpr::Call { func, .. } => {
+            if ctx.context_manager_functions.contains(func) {
+                return Some(ContextlibWith::Guard(context));
+            }
+            func.as_str()
+        }
+        HirExpr::MethodCall { object, method, .. } if matches!(object.as_ref(), HirExpr::Var(module) if module == "contextlib") => {
+            method.as_str()
+        }
+        _ => return None,
+    };
+
+    match name {
+        "suppress" => Some(ContextlibWith::Suppress(
+            FunctionAnalyzer::suppressed_types(context).unwrap_or_default(),
+        )),
+        "ExitStack" => Some(ContextlibWith::ExitStack),
+        _ => None,
+    }
+}
+
+/// Generate a `with` statement over a contextlib context manager
+///
+/// Returns `None` for any other context manager.
+///
+/// # Complexity
+/// 6 (classification + 3 forms + target handling)
+pub(crate) fn try_codegen_contextlib_with(
+    context: &HirExpr,
+    target: &Option<String>,
+    body: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let kind = match classify_with(context, ctx) {
+        Some(kind) => kind,
+        None => return Ok(None),
+    };
+
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+
+    let tokens = match kind {
+        ContextlibWith::Guard(call) => {
+            let call_expr = call.to_rust_expr(ctx)?;
+            let binding = match target {
+                Some(var_name) => {
+                    ctx.declare_var(var_name);
+                    let ident = safe_ident(var_name);
+                    quote! { #ident }
+                }
+                None => quote! { _ },
+            };
+            let body_stmts = convert_body(body, ctx)?;
+            quote! {
+                {
+                    let (#binding, _guard) = #call_expr;
+                    #(#body_stmts)*
+                }
+            }
+        }
+        ContextlibWith::Suppress(names) => {
+            if let Some(exit) = block_exit(body, false) {
+                bail!(
+                    "`{}` inside `with suppress(...)` is not supported: the block runs in a closure",
+                    exit
+                );
+            }
+            let matches = names
+                .iter()
+                .filter_map(|name| suppressed_error_check(name, ctx))
+                .collect::<Vec<_>>();
+            let suppressed = if matches.is_empty() {
+                quote! { false }
+            } else {
+                quote! { #(#matches)||* }
+            };
+            let propagate = propagate_error(ctx)?;
+
+            // Raised/propagated errors leave the block instead of the function
+            let saved_can_fail = ctx.current_function_can_fail;
+            let saved_error_type = ctx.current_error_type.replace(ErrorType::DynBox);
+            ctx.current_function_can_fail = true;
+            let body_stmts = convert_body(body, ctx);
+            ctx.current_function_can_fail = saved_can_fail;
+            ctx.current_error_type = saved_error_type;
+            let body_stmts = body_stmts?;
+            quote! {
+                if let Err(err) = (|| -> Result<(), Box<dyn std::error::Error>> {
+                    #(#body_stmts)*
+                    Ok(())
+                })() {
+                    if !(#suppressed) {
+                        #propagate
+                    }
+                }
+            }
+        }
+        ContextlibWith::ExitStack => {
+            ctx.needs_exit_stack = true;
+            ctx.needs_context_guard = true;
+            let stack_name = target.clone().unwrap_or_else(|| "_stack".to_string());
+            ctx.declare_var(&stack_name);
+            ctx.var_types.insert(
+                stack_name.clone(),
+                Type::Custom(EXIT_STACK_TYPE.to_string()),
+            );
+            let stack = safe_ident(&stack_name);
+            let body_stmts = convert_body(body, ctx)?;
+            quote! {
+                {
+                    let mut #stack = ExitStack::new();
+                    #(#body_stmts)*
+                }
+            }
+        }
+    };
+
+    ctx.is_final_statement = saved_is_final;
+    Ok(Some(tokens))
+}
+
+/// Whether the boxed `err` is a `name` exception; `None` when no error of
+/// the generated code can be one
+///
+/// # Complexity
+/// 4 (match over exception families)
+fn suppressed_error_check(name: &str, ctx: &mut CodeGenContext) -> Option<TokenStream> {
+    let io_kind = |kind: &str| {
+        let kind = format_ident!("{}", kind);
+        quote! {
+            err.downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::#kind)
+        }
+    };
+    let needs = match name {
+        "Exception" | "BaseException" => return Some(quote! { true }),
+        "OSError" | "IOError" | "EnvironmentError" => {
+            return Some(quote! { err.is::<std::io::Error>() })
+        }
+        "FileNotFoundError" => return Some(io_kind("NotFound")),
+        "PermissionError" => return Some(io_kind("PermissionDenied")),
+        "FileExistsError" => return Some(io_kind("AlreadyExists")),
+        "ZeroDivisionError" => &mut ctx.needs_zerodivisionerror,
+        "IndexError" => &mut ctx.needs_indexerror,
+        "ValueError" => &mut ctx.needs_valueerror,
+        "TypeError" => &mut ctx.needs_typeerror,
+        "StopIteration" => &mut ctx.needs_stopiteration,
+        "OverflowError" => &mut ctx.needs_overflowerror,
+        _ => return None,
+    };
+    *needs = true;
+    let ty = format_ident!("{}", name);
+    Some(quote! { err.is::<#ty>() })
+}
+
+/// Hands an error `suppress` does not drop to the enclosing function, or
+/// panics with it when the function cannot fail
+fn propagate_error(ctx: &CodeGenContext) -> Result<TokenStream> {
+    if !ctx.current_function_can_fail {
+        return Ok(quote! { panic!("{}", err); });
+    }
+    Ok(match &ctx.current_error_type {
+        Some(ErrorType::DynBox) | None => quote! { return Err(err); },
+        Some(ErrorType::ModuleEnum) => quote! { return Err(err.to_string().into()); },
+        Some(ErrorType::Concrete(ty)) => {
+            let ty: syn::Type = syn::parse_str(ty)?;
+            quote! {
+                match err.downcast::<#ty>() {
+                    Ok(err) => return Err(*err),
+                    Err(err) => panic!("{}", err),
+                }
+            }
+        }
+    })
+}
+
+/// The first `return`, `break` or `continue` leaving `body`; `in_loop`
+/// when `body` is inside a loop of its own, whose exits stay inside
+fn block_exit(body: &[HirStmt], in_loop: bool) -> Option<&'static str> {
+    body.iter().find_map(|stmt| match stmt {
+        HirStmt::Return(_) => Some("return"),
+        HirStmt::Break { label } if !in_loop || label.is_some() => Some("break"),
+        HirStmt::Continue { label } if !in_loop || label.is_some() => Some("continue"),
+        HirStmt::While { body, .. } | HirStmt::For { body, .. } => block_exit(body, true),
+        HirStmt::If {
+            then_body,
+            else_body,
+            ..
+        } => block_exit(then_body, in_loop).or_else(|| {
+            else_body
+                .as_deref()
+                .and_then(|body| block_exit(body, in_loop))
+        }),
+        HirStmt::With { body, .. } => block_exit(body, in_loop),
+        HirStmt::Try {
+            body,
+            handlers,
+            orelse,
+            finalbody,
+        } => block_exit(body, in_loop)
+            .or_else(|| handlers.iter().find_map(|h| block_exit(&h.body, in_loop)))
+            .or_else(|| orelse.as_deref().and_then(|body| block_exit(body, in_loop)))
+            .or_else(|| {
+                finalbody
+                    .as_deref()
+                    .and_then(|body| block_exit(body, in_loop))
+            }),
+        HirStmt::Match { cases, .. } => cases.iter().find_map(|c| block_exit(&c.body, in_loop)),
+        _ => None,
+    })
+}
+
+fn convert_body(body: &[HirStmt], ctx: &mut CodeGenContext) -> Result<Vec<TokenStream>> {
+    body.iter().map(|stmt| stmt.to_rust_tokens(ctx)).collect()
+}
+
+/// Generate `stack.callback(f, *args)` as a boxed cleanup closure
+///
+/// # Complexity
+/// 3 (arg split + callee match)
+pub(crate) fn codegen_exit_stack_callback(
+    stack: &syn::Expr,
+    args: &[HirExpr],
+    ctx: &mut CodeGenContext,
+) -> Result<syn::Expr> {
+    let (callee, rest) = match args.split_first() {
+        Some(split) => split,
+        None => anyhow::bail!("ExitStack.callback() requires a callable"),
+    };
+    let call: syn::Expr = match callee {
+        HirExpr::Var(func) => HirExpr::Call {
+            func: func.clone(),
+            args: rest.to_vec(),
+            kwargs: vec![],
+        }
+        .to_rust_expr(ctx)?,
+        other => {
+            let func = other.to_rust_expr(ctx)?;
+            let rest = rest
+                .iter()
+                .map(|arg| arg.to_rust_expr(ctx))
+                .collect::<Result<Vec<_>>>()?;
+            parse_quote! { (#func)(#(#rest),*) }
+        }
+    };
+    Ok(parse_quote! { #stack.callback(move || { #call; }) })
+}
+
+/// Emit the runtime types used by contextlib lowering
+///
+/// # Complexity
+/// 3 (two flag checks)
+pub fn generate_contextlib_definitions(ctx: &CodeGenContext) -> Vec<TokenStream> {
+    let mut definitions = Vec::new();
+
+    if ctx.needs_context_guard {
+        definitions.push(quote! {
+            /// Cleanup registered by a `@contextmanager` function, run when dropped
+            #[must_use = "the context exits as soon as the guard is dropped"]
+            pub struct ContextGuard<'a> {
+                exit: Option<Box<dyn FnOnce() + 'a>>,
+            }
+
+            impl<'a> ContextGuard<'a> {
+                pub fn new(exit: impl FnOnce() + 'a) -> Self {
+                    Self { exit: Some(Box::new(exit)) }
+                }
+            }
+
+            impl Drop for ContextGuard<'_> {
+                fn drop(&mut self) {
+                    if let Some(exit) = self.exit.take() {
+                        exit();
+                    }
+                }
+            }
+        });
+    }
+
+    if ctx.needs_exit_stack {
+        definitions.push(quote! {
+            /// Cleanup callbacks run in reverse registration order, like `contextlib.ExitStack`
+            #[derive(Default)]
+            pub struct ExitStack<'a> {
+                callbacks: Vec<Box<dyn FnOnce() + 'a>>,
+            }
+
+            impl<'a> ExitStack<'a> {
+                pub fn new() -> Self {
+                    Self::default()
+                }
+
+                pub fn callback(&mut self, callback: impl FnOnce() + 'a) {
+                    self.callbacks.push(Box::new(callback));
+                }
+
+                pub fn enter_context<T>(&mut self, (value, guard): (T, ContextGuard<'a>)) -> T {
+                    self.callbacks.push(Box::new(move || drop(guard)));
+                    value
+                }
+
+                pub fn pop_all(&mut self) -> ExitStack<'a> {
+                    ExitStack {
+                        callbacks: std::mem::take(&mut self.callbacks),
+                    }
+                }
+
+                pub fn close(&mut self) {
+                    while let Some(callback) = self.callbacks.pop() {
+                        callback();
+                    }
+                }
+            }
+
+            impl Drop for ExitStack<'_> {
+                fn drop(&mut self) {
+                    self.close();
+                }
+            }
+        });
+    }
+
+    definitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir::Literal;
+
+    fn yield_stmt(value: Option<HirExpr>) -> HirStmt {
+        HirStmt::Expr(HirExpr::Yield {
+            value: value.map(Box::new),
+        })
+    }
+
+    fn print_stmt(text: &str) -> HirStmt {
+        HirStmt::Expr(HirExpr::Call {
+            func: "print".to_string(),
+            args: vec![HirExpr::Literal(Literal::String(text.to_string()))],
+            kwargs: vec![],
+        })
+    }
+
+    #[test]
+    fn test_split_top_level_yield() {
+        let body = vec![print_stmt("enter"), yield_stmt(None), print_stmt("exit")];
+        let split = split_at_yield(&body).unwrap();
+        assert_eq!(split.enter.len(), 1);
+        assert!(split.value.is_none());
+        assert_eq!(split.exit.len(), 1);
+    }
+
+    #[test]
+    fn test_split_try_finally_yield() {
+        let body = vec![
+            print_stmt("setup"),
+            HirStmt::Try {
+                body: vec![
+                    print_stmt("enter"),
+                    yield_stmt(Some(HirExpr::Literal(Literal::Int(1)))),
+                ],
+                handlers: vec![],
+                orelse: None,
+                finalbody: Some(vec![print_stmt("cleanup")]),
+            },
+            print_stmt("after"),
+        ];
+        let split = split_at_yield(&body).unwrap();
+        assert_eq!(split.enter.len(), 2);
+        assert_eq!(split.enter[1], &print_stmt("enter"));
+        assert!(matches!(
+            split.value,
+            Some(HirExpr::Literal(Literal::Int(1)))
+        ));
+        assert_eq!(split.exit.len(), 2);
+        assert_eq!(split.exit[0], &print_stmt("cleanup"));
+        assert_eq!(split.exit[1], &print_stmt("after"));
+    }
+
+    #[test]
+    fn test_tokens_mention_nested_ident() {
+        let tokens = quote! { println!("{}", (start.elapsed())); };
+        assert!(tokens_mention(&tokens, "start"));
+        assert!(!tokens_mention(&tokens, "stop"));
+    }
+}
@@ -0,0 +1,317 @@
+//! Parameters with default values
+//!
+//! A parameter with a default takes an `Option`, and the function fills in
+//! the default when it gets `None`:
+//!
+//! | Python                        | Parameter              | Prologue                                |
+//! |-------------------------------|------------------------|-----------------------------------------|
+//! | `n: int = 3`                  | `n: Option<i32>`       | `let n = n.unwrap_or(3);`               |
+//! | `sep: str = ","`              | `sep: Option<&str>`    | `let sep = sep.unwrap_or(",");`         |
+//! | `xs: list[int] = []`          | `xs: Option<Vec<i32>>` | `let xs = xs.unwrap_or_default();`      |
+//! | `key: str = make_key()`       | `key: Option<String>`  | `let key = key.unwrap_or_else(|| make_key());` |
+//! | `limit: Optional[int] = None` | `limit: Option<i32>`   | none                                    |
+//!
+//! Calls to the module's functions bind keyword arguments to their
+//! parameters and fill in the ones they omit, so `f(1, sep=";")` calls
+//! `f(1, None, Some(";"))`. An `Optional` parameter is passed its default,
+//! in `Some` like any other value that is not already optional.
+//!
+//! The Rust function takes its parameters in the order of the Python
+//! signature. Positional-only and keyword-only parameters are checked at the
+//! call: passing one the way its kind forbids is a transpile error.
+//!
+//! Unlike Python, a default is evaluated by every call that omits it, so a
+//! mutable default is never shared between calls. Generators keep their
+//! parameters required.
+
+use crate::hir::{HirExpr, HirFunction, HirParam, Literal, ParamKind, Type, UnaryOp};
+use crate::lifetime_analysis::LifetimeResult;
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::int_semantics_gen;
+use crate::rust_gen::keywords::safe_ident;
+use anyhow::{bail, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// The parameters of a module function, as calls bind to them
+#[derive(Debug, Clone)]
+pub(crate) struct Signature {
+    params: Vec<HirParam>,
+    /// Whether the function fills in its own defaults
+    fills_defaults: bool,
+}
+
+impl Signature {
+    pub(crate) fn of(func: &HirFunction) -> Self {
+        Self {
+            params: func.params.to_vec(),
+            fills_defaults: !func.properties.is_generator,
+        }
+    }
+
+    fn takes_option(&self, index: usize) -> bool {
+        self.fills_defaults && self.params.get(index).is_some_and(takes_option)
+    }
+
+    /// Whether the parameter at `index` is passed by value, rather than
+    /// borrowed as lifetime analysis would decide
+    fn is_owned(&self, index: usize) -> bool {
+        self.takes_option(index) && !keeps_borrow(&self.params[index])
+    }
+}
+
+/// Whether `param` of `func` takes an `Option` the function fills in
+pub(crate) fn param_takes_option(param: &HirParam, func: &HirFunction) -> bool {
+    !func.properties.is_generator && takes_option(param)
+}
+
+fn takes_option(param: &HirParam) -> bool {
+    match (&param.default, &param.ty) {
+        (None, _) | (Some(HirExpr::Literal(Literal::None)), _) => false,
+        (_, Type::Optional(_) | Type::Unknown | Type::TypeVar(_)) => false,
+        _ => true,
+    }
+}
+
+/// A string parameter defaulting to a literal may stay borrowed, as the
+/// literal outlives the function
+fn keeps_borrow(param: &HirParam) -> bool {
+    param.ty == Type::String && matches!(param.default, Some(HirExpr::Literal(Literal::String(_))))
+}
+
+/// Passes the parameters of `func` that take an `Option` by value, so a
+/// default computed in the function can stand in for them
+pub(crate) fn own_optional_params(func: &HirFunction, lifetime_result: &mut LifetimeResult) {
+    for param in func.params.iter().filter(|p| param_takes_option(p, func)) {
+        lifetime_result.borrowing_strategies.remove(&param.name);
+        if keeps_borrow(param) {
+            continue;
+        }
+        if let Some(inferred) = lifetime_result.param_lifetimes.get_mut(&param.name) {
+            inferred.should_borrow = false;
+            inferred.needs_mut = false;
+        }
+    }
+}
+
+/// `let n = n.unwrap_or(3);` for each parameter of `func` taking an `Option`
+pub(crate) fn codegen_prologue(
+    func: &HirFunction,
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<TokenStream>> {
+    let borrows = ctx
+        .function_param_borrows
+        .get(&func.name)
+        .cloned()
+        .unwrap_or_default();
+    let mut prologue = Vec::new();
+    for (index, param) in func.params.iter().enumerate() {
+        let default = match &param.default {
+            Some(default) if param_takes_option(param, func) => default,
+            _ => continue,
+        };
+        let name = safe_ident(&param.name);
+        let borrowed = borrows.get(index).copied().unwrap_or(false);
+        let value = fill_default(&name, default, &param.ty, borrowed, ctx)?;
+        let mutability = ctx
+            .mutable_vars
+            .contains(&param.name)
+            .then(|| quote! { mut });
+        prologue.push(quote! { let #mutability #name = #value; });
+    }
+    Ok(prologue)
+}
+
+fn fill_default(
+    name: &syn::Ident,
+    default: &HirExpr,
+    ty: &Type,
+    borrowed: bool,
+    ctx: &mut CodeGenContext,
+) -> Result<syn::Expr> {
+    Ok(match default {
+        HirExpr::Literal(Literal::String(s)) if borrowed => parse_quote! { #name.unwrap_or(#s) },
+        HirExpr::Literal(Literal::String(s)) => {
+            parse_quote! { #name.unwrap_or_else(|| #s.to_string()) }
+        }
+        HirExpr::List(items) | HirExpr::Set(items) if items.is_empty() => {
+            parse_quote! { #name.unwrap_or_default() }
+        }
+        HirExpr::Dict(items) if items.is_empty() => parse_quote! { #name.unwrap_or_default() },
+        _ => {
+            let value = match float_literal(default) {
+                Some(float) if *ty == Type::Float => float.to_rust_expr(ctx)?,
+                _ => int_semantics_gen::codegen_int_value(default, Some(ty), ctx)?,
+            };
+            if is_constant(&value) {
+                parse_quote! { #name.unwrap_or(#value) }
+            } else {
+                parse_quote! { #name.unwrap_or_else(|| #value) }
+            }
+        }
+    })
+}
+
+/// `1` where a float is expected is `1.0`
+fn float_literal(expr: &HirExpr) -> Option<HirExpr> {
+    match expr {
+        HirExpr::Literal(Literal::Int(n)) => Some(HirExpr::Literal(Literal::Float(*n as f64))),
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => Some(HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand: Box::new(float_literal(operand)?),
+        }),
+        _ => None,
+    }
+}
+
+/// Whether evaluating `expr` eagerly costs nothing
+fn is_constant(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Lit(_) => true,
+        syn::Expr::Unary(unary) => is_constant(&unary.expr),
+        syn::Expr::Paren(paren) => is_constant(&paren.expr),
+        _ => false,
+    }
+}
+
+/// The positional arguments of a call to module function `func`, with its
+/// keyword arguments bound to their parameters and omitted parameters filled
+/// in; `None` when the call has to be generated as it is written
+///
+/// A call Python would reject for the kinds of the parameters, such as a
+/// positional-only parameter passed by keyword, is an error.
+pub(crate) fn bind_call_args(
+    func: &str,
+    args: &[HirExpr],
+    kwargs: &[(String, HirExpr)],
+    ctx: &CodeGenContext,
+) -> Result<Option<Vec<HirExpr>>> {
+    let signature = match ctx.function_signatures.get(func) {
+        Some(signature) => signature,
+        None => return Ok(None),
+    };
+    check_call_args(func, &signature.params, args, kwargs)?;
+    if kwargs.is_empty() && args.len() >= signature.params.len() {
+        return Ok(None);
+    }
+    let rest = &signature.params[args.len().min(signature.params.len())..];
+    if !kwargs
+        .iter()
+        .all(|(name, _)| rest.iter().any(|param| param.name == *name))
+    {
+        return Ok(None);
+    }
+    let mut bound = args.to_vec();
+    for (index, param) in signature.params.iter().enumerate().skip(args.len()) {
+        let arg = match kwargs.iter().find(|(name, _)| *name == param.name) {
+            Some((_, value)) => value.clone(),
+            None if signature.takes_option(index) => HirExpr::Literal(Literal::None),
+            None => match &param.default {
+                Some(default) => default.clone(),
+                None => bail!(
+                    "{}() missing required {} argument: '{}'",
+                    func,
+                    match param.kind {
+                        ParamKind::KeywordOnly => "keyword-only",
+                        _ => "positional",
+                    },
+                    param.name
+                ),
+            },
+        };
+        bound.push(arg);
+    }
+    Ok(Some(bound))
+}
+
+/// Rejects the arguments Python would refuse to bind to `params`
+///
+/// Unknown keywords are let through, as they may belong to a `**kwargs`
+/// the parameters do not show.
+fn check_call_args(
+    func: &str,
+    params: &[HirParam],
+    args: &[HirExpr],
+    kwargs: &[(String, HirExpr)],
+) -> Result<()> {
+    let positional = params
+        .iter()
+        .take_while(|param| param.kind != ParamKind::KeywordOnly)
+        .count();
+    if args.len() > positional && positional < params.len() {
+        bail!(
+            "{}() takes {} positional argument{} but {} were given; '{}' is keyword-only",
+            func,
+            positional,
+            if positional == 1 { "" } else { "s" },
+            args.len(),
+            params[positional].name
+        );
+    }
+    for (name, _) in kwargs {
+        let index = match params.iter().position(|param| param.name == *name) {
+            Some(index) => index,
+            None => continue,
+        };
+        if params[index].kind == ParamKind::PositionalOnly {
+            bail!(
+                "{}() got a positional-only argument passed as keyword: '{}'",
+                func,
+                name
+            );
+        }
+        if index < args.len() {
+            bail!("{}() got multiple values for argument '{}'", func, name);
+        }
+    }
+    Ok(())
+}
+
+/// Whether the argument at `index` of a call to `func` is passed by value
+pub(crate) fn passes_owned(func: &str, index: usize, ctx: &CodeGenContext) -> bool {
+    ctx.function_signatures
+        .get(func)
+        .is_some_and(|signature| signature.is_owned(index))
+}
+
+/// Wraps the arguments of a call to module function `func` in `Some` where
+/// its parameters take an `Option`
+pub(crate) fn wrap_optional_args(
+    func: &str,
+    hir_args: &[HirExpr],
+    args: Vec<syn::Expr>,
+    ctx: &CodeGenContext,
+) -> Vec<syn::Expr> {
+    let signature = match ctx.function_signatures.get(func) {
+        Some(signature) => signature,
+        None => return args,
+    };
+    args.into_iter()
+        .zip(hir_args)
+        .enumerate()
+        .map(|(index, (arg, hir_arg))| {
+            let wrap = match signature.params.get(index) {
+                _ if signature.takes_option(index) => {
+                    !matches!(hir_arg, HirExpr::Literal(Literal::None))
+                }
+                Some(HirParam {
+                    ty: Type::Optional(_),
+                    ..
+                }) => !matches!(
+                    ctx.value_type(hir_arg),
+                    Type::Optional(_) | Type::None | Type::Unknown
+                ),
+                _ => false,
+            };
+            if wrap {
+                parse_quote! { Some(#arg) }
+            } else {
+                arg
+            }
+        })
+        .collect()
+}
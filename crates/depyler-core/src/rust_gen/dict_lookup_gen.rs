@@ -0,0 +1,227 @@
+//! Single-lookup forms of the dict double-lookup idioms
+//!
+//! Python reads an optional key either by testing for it first or by
+//! testing what `get` returned. Generated statement by statement, the first
+//! hashes the key twice and the second carries an `Option` through the rest
+//! of the function. On names typed `dict`, they become one lookup:
+//!
+//! | Python                                      | Rust                                                  |
+//! |---------------------------------------------|-------------------------------------------------------|
+//! | `if k in d:` / `v = d[k]` / ...             | `if let Some(v) = d.get(&k).cloned() { ... }`         |
+//! | `v = d.get(k)` / `if v is None:` / `return` | `let v = match d.get(&k).cloned() { Some(v) => v, None => { return ...; } };` |
+//! | `v = d.get(k)` / `if v is None:` / `v = e`  | `let v = match d.get(&k).cloned() { Some(v) => v, None => e };` |
+//!
+//! Dicts with `Optional` values are left alone, as `get` cannot tell a
+//! missing key from a `None` value there.
+
+use crate::hir::{AssignTarget, BinOp, HirExpr, HirStmt, Literal, Type};
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::keywords::safe_ident;
+use crate::rust_gen::stmt_gen::{codegen_block, is_var_used_in_expr, is_var_used_in_stmt};
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// `if k in d:` whose body starts with `v = d[k]`, as an `if let`
+pub(crate) fn codegen_contains_then_index(
+    condition: &HirExpr,
+    then_body: &[HirStmt],
+    else_body: &Option<Vec<HirStmt>>,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let (key, dict) = match condition {
+        HirExpr::Binary {
+            op: BinOp::In,
+            left,
+            right,
+        } => (left.as_ref(), right.as_ref()),
+        _ => return Ok(None),
+    };
+    let (value_var, value_type) = match then_body.first() {
+        Some(HirStmt::Assign {
+            target: AssignTarget::Symbol(var),
+            value: HirExpr::Index { base, index },
+            type_annotation: None,
+        }) if base.as_ref() == dict && index.as_ref() == key => {
+            match lookup_value_type(var, dict, key, ctx) {
+                Some(value_type) => (var, value_type),
+                None => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    let lookup = codegen_lookup(dict, key, ctx)?;
+    let binding = binding_pattern(value_var, ctx);
+
+    ctx.enter_scope();
+    ctx.declare_var(value_var);
+    ctx.var_types.insert(value_var.clone(), value_type);
+    let then_stmts = codegen_block(&then_body[1..], ctx)?;
+    ctx.exit_scope();
+
+    let else_branch = match else_body {
+        Some(else_body) => {
+            ctx.enter_scope();
+            let else_stmts = codegen_block(else_body, ctx)?;
+            ctx.exit_scope();
+            quote! { else { #(#else_stmts)* } }
+        }
+        None => quote! {},
+    };
+    Ok(Some(quote! {
+        if let Some(#binding) = #lookup {
+            #(#then_stmts)*
+        } #else_branch
+    }))
+}
+
+/// `v = d.get(k)` followed by `if v is None:`, at the start of `stmts`, as
+/// a `match` binding `v`
+///
+/// The `if` must have no `else`, and its body must either leave the block
+/// or be a single assignment to `v`. Returns the code of both statements.
+pub(crate) fn codegen_get_then_none_check(
+    stmts: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let (var, dict, key, then_body) = match stmts {
+        [HirStmt::Assign {
+            target: AssignTarget::Symbol(var),
+            value:
+                HirExpr::MethodCall {
+                    object,
+                    method,
+                    args,
+                    kwargs,
+                },
+            type_annotation: None,
+        }, HirStmt::If {
+            condition:
+                HirExpr::MethodCall {
+                    object: checked,
+                    method: check,
+                    args: check_args,
+                    ..
+                },
+            then_body,
+            else_body: None,
+        }, ..]
+            if method == "get"
+                && args.len() == 1
+                && kwargs.is_empty()
+                && check == "is_none"
+                && check_args.is_empty()
+                && matches!(checked.as_ref(), HirExpr::Var(name) if name == var) =>
+        {
+            (var, object.as_ref(), &args[0], then_body)
+        }
+        _ => return Ok(None),
+    };
+    let value_type = match lookup_value_type(var, dict, key, ctx) {
+        Some(value_type) => value_type,
+        None => return Ok(None),
+    };
+
+    let default = match then_body.as_slice() {
+        [HirStmt::Assign {
+            target: AssignTarget::Symbol(target),
+            value,
+            type_annotation: None,
+        }] if target == var && !is_var_used_in_expr(var, value) => {
+            let value = value.to_rust_expr(ctx)?;
+            quote! { #value }
+        }
+        [.., last]
+            if leaves_block(last) && !then_body.iter().any(|s| is_var_used_in_stmt(var, s)) =>
+        {
+            let saved_is_final = ctx.is_final_statement;
+            ctx.is_final_statement = false;
+            ctx.enter_scope();
+            let stmts = codegen_block(then_body, ctx);
+            ctx.exit_scope();
+            ctx.is_final_statement = saved_is_final;
+            let stmts = stmts?;
+            quote! { { #(#stmts)* } }
+        }
+        _ => return Ok(None),
+    };
+
+    let lookup = codegen_lookup(dict, key, ctx)?;
+    let binding = binding_pattern(var, ctx);
+    let var_ident = safe_ident(var);
+    ctx.declare_var(var);
+    ctx.var_types.insert(var.clone(), value_type);
+    Ok(Some(quote! {
+        let #binding = match #lookup {
+            Some(#var_ident) => #var_ident,
+            None => #default,
+        };
+    }))
+}
+
+/// The value type of `dict` when `var = dict[key]` can bind a fresh `var`
+/// from a single lookup
+fn lookup_value_type(
+    var: &str,
+    dict: &HirExpr,
+    key: &HirExpr,
+    ctx: &CodeGenContext,
+) -> Option<Type> {
+    let dict_name = match dict {
+        HirExpr::Var(name) => name,
+        _ => return None,
+    };
+    let value_type = match ctx.var_types.get(dict_name) {
+        Some(Type::Dict(_, value_type)) if !matches!(**value_type, Type::Optional(_)) => {
+            (**value_type).clone()
+        }
+        _ => return None,
+    };
+    let key_is_plain = match key {
+        HirExpr::Var(name) => name != var,
+        HirExpr::Literal(_) => true,
+        _ => false,
+    };
+    if !key_is_plain || dict_name == var || ctx.is_declared(var) {
+        return None;
+    }
+    Some(value_type)
+}
+
+/// `dict.get(key).cloned()`, borrowing `key` as `k in d` does
+fn codegen_lookup(dict: &HirExpr, key: &HirExpr, ctx: &mut CodeGenContext) -> Result<TokenStream> {
+    let dict_expr = dict.to_rust_expr(ctx)?;
+    let key_arg = match key {
+        HirExpr::Literal(Literal::String(s)) => quote! { #s },
+        // `str` parameters are already `&str`
+        HirExpr::Var(name) if matches!(ctx.var_types.get(name), Some(Type::String)) => {
+            let key_expr = key.to_rust_expr(ctx)?;
+            quote! { #key_expr }
+        }
+        _ => {
+            let key_expr = key.to_rust_expr(ctx)?;
+            quote! { &#key_expr }
+        }
+    };
+    Ok(quote! { #dict_expr.get(#key_arg).cloned() })
+}
+
+fn binding_pattern(var: &str, ctx: &CodeGenContext) -> TokenStream {
+    let ident = safe_ident(var);
+    if ctx.mutable_vars.contains(var) {
+        quote! { mut #ident }
+    } else {
+        quote! { #ident }
+    }
+}
+
+fn leaves_block(stmt: &HirStmt) -> bool {
+    matches!(
+        stmt,
+        HirStmt::Return(_)
+            | HirStmt::Raise { .. }
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+    )
+}
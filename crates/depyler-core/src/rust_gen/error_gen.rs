@@ -1,10 +1,31 @@
 //! Error type generation
 //!
 //! This module generates Rust struct definitions for Python error types
-//! like `ZeroDivisionError` and `IndexError`.
+//! like `ZeroDivisionError` and `IndexError`, and under
+//! [`ErrorModel::ModuleEnum`](crate::rust_gen::ErrorModel) the `ModuleError`
+//! enum uniting every exception type of a module.
 
+use crate::hir::{HirFunction, HirStmt};
+use crate::rust_gen::context::ErrorType;
+use crate::rust_gen::error_message_gen;
+use crate::rust_gen::options::ExitCodeOptions;
 use crate::rust_gen::CodeGenContext;
-use quote::quote;
+use quote::{format_ident, quote};
+use std::collections::{BTreeSet, HashSet};
+
+/// Name of the error enum fallible functions return under
+/// `ErrorModel::ModuleEnum`
+pub const MODULE_ERROR: &str = "ModuleError";
+
+/// Exception types with a struct from [`generate_error_type_definitions`]
+const PREDEFINED_ERRORS: [&str; 6] = [
+    "ZeroDivisionError",
+    "IndexError",
+    "ValueError",
+    "TypeError",
+    "StopIteration",
+    "OverflowError",
+];
 
 /// Generate error type definitions if needed
 ///
@@ -21,83 +42,559 @@ use quote::quote;
 /// ```
 /// // If ctx.needs_zerodivisionerror is true, generates:
 /// // #[derive(Debug, Clone)]
-/// // pub struct ZeroDivisionError { message: String }
+/// // pub struct ZeroDivisionError { message: String, cause: Option<Arc<dyn Error + ...>> }
 /// // impl std::fmt::Display for ZeroDivisionError { ... }
-/// // impl std::error::Error for ZeroDivisionError {}
+/// // impl std::error::Error for ZeroDivisionError { fn source(&self) ... }
 /// ```
 pub fn generate_error_type_definitions(ctx: &CodeGenContext) -> Vec<proc_macro2::TokenStream> {
-    let mut definitions = Vec::new();
+    // Like `str(e)` in Python, an exception displays as its message, unless
+    // the terse native messages are asked for
+    let prefixed = |prefix: &str| {
+        if ctx.options.native_error_messages {
+            let format = format!("{}: {{}}", prefix);
+            quote! { write!(f, #format, self.message) }
+        } else {
+            quote! { write!(f, "{}", self.message) }
+        }
+    };
+    let predefined = [
+        (
+            "ZeroDivisionError",
+            ctx.needs_zerodivisionerror,
+            prefixed("division by zero"),
+        ),
+        (
+            "IndexError",
+            ctx.needs_indexerror,
+            prefixed("index out of range"),
+        ),
+        ("ValueError", ctx.needs_valueerror, prefixed("value error")),
+        ("TypeError", ctx.needs_typeerror, prefixed("type error")),
+        (
+            "StopIteration",
+            ctx.needs_stopiteration,
+            if ctx.options.native_error_messages {
+                quote! {
+                    if self.message.is_empty() {
+                        write!(f, "stop iteration")
+                    } else {
+                        write!(f, "stop iteration: {}", self.message)
+                    }
+                }
+            } else {
+                prefixed("stop iteration")
+            },
+        ),
+        (
+            "OverflowError",
+            ctx.needs_overflowerror,
+            prefixed("overflow"),
+        ),
+    ];
+    predefined
+        .into_iter()
+        .filter(|(_, needed, _)| *needed)
+        .map(|(name, _, display)| error_struct(&format_ident!("{}", name), display))
+        .collect()
+}
+
+/// The struct of an exception type: its message, the exception it was
+/// raised from, and `Display` written by `display`
+///
+/// `raise X(...) from cause` becomes `X::new(...).with_cause(cause)`, and
+/// `source()` hands the cause on, so walking `source()` visits the chain
+/// Python prints above a traceback.
+fn error_struct(ty: &syn::Ident, display: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        #[derive(Debug, Clone)]
+        pub struct #ty {
+            message: String,
+            cause: Option<std::sync::Arc<dyn std::error::Error + Send + Sync>>,
+        }
 
-    if ctx.needs_zerodivisionerror {
-        definitions.push(quote! {
-            #[derive(Debug, Clone)]
-            pub struct ZeroDivisionError {
-                message: String,
+        impl std::fmt::Display for #ty {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                #display
             }
+        }
 
-            impl std::fmt::Display for ZeroDivisionError {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    write!(f, "division by zero: {}", self.message)
-                }
+        impl std::error::Error for #ty {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                self.cause
+                    .as_deref()
+                    .map(|cause| cause as &(dyn std::error::Error + 'static))
             }
+        }
+
+        impl #ty {
+            pub fn new(message: impl Into<String>) -> Self {
+                Self { message: message.into(), cause: None }
+            }
+
+            pub fn with_cause(
+                mut self,
+                cause: impl std::error::Error + Send + Sync + 'static,
+            ) -> Self {
+                self.cause = Some(std::sync::Arc::new(cause));
+                self
+            }
+        }
+    }
+}
+
+/// `call`, a `Result` failing with the predefined `exception`, as the value
+/// it yields
+///
+/// The error propagates with `?` from functions returning it, is left to
+/// the enclosing `try` when one catches it, and panics otherwise.
+pub(crate) fn handle_builtin_error(
+    call: syn::Expr,
+    exception: &str,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    let propagates = ctx.current_function_can_fail
+        && match &ctx.current_error_type {
+            Some(ErrorType::DynBox) => true,
+            Some(ErrorType::Concrete(error_type)) => error_type == exception,
+            _ => false,
+        };
+    if ctx.is_exception_handled(exception) || ctx.is_exception_handled("Exception") {
+        // The `try` lowering replaces the default with the handler's value
+        syn::parse_quote! { #call.unwrap_or_default() }
+    } else if propagates {
+        syn::parse_quote! { #call? }
+    } else {
+        let panic = error_message_gen::panic_unhandled(exception, &syn::parse_quote! { e }, ctx);
+        syn::parse_quote! { #call.unwrap_or_else(|e| #panic) }
+    }
+}
+
+/// Whether `raise exception(...)` builds one of the structs generated here,
+/// which take a cause with `with_cause`
+pub(crate) fn has_cause(exception: &str, ctx: &CodeGenContext) -> bool {
+    !ctx.class_names.contains(exception)
+        && (PREDEFINED_ERRORS.contains(&exception)
+            || matches!(ctx.current_error_type, Some(ErrorType::ModuleEnum)))
+}
 
-            impl std::error::Error for ZeroDivisionError {}
+/// The exception types that make up a module's `ModuleError`
+///
+/// # Complexity
+/// N/A (data structure)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModuleErrorModel {
+    /// Every exception raised, caught or implied by a failing operation, in
+    /// name order; each becomes a variant of the same name
+    pub exceptions: BTreeSet<String>,
+    /// Those defined as classes in the module, wrapped as they are instead
+    /// of getting a generated struct
+    pub user_classes: BTreeSet<String>,
+}
+
+impl ModuleErrorModel {
+    /// Scans `functions` for the exception types they raise and catch
+    ///
+    /// Returns `None` for a module with nothing fallible, which needs no
+    /// `ModuleError`.
+    ///
+    /// # Complexity
+    /// 5 (per-function scan + filters)
+    pub fn collect(functions: &[HirFunction], class_names: &HashSet<String>) -> Option<Self> {
+        let mut exceptions = BTreeSet::new();
+        for func in functions {
+            exceptions.extend(func.properties.error_types.iter().cloned());
+            exception_names(&func.body, &mut exceptions);
+        }
+        // Anything but a plain name (`errors.Bad`, a bound instance) has no
+        // type to wrap and ends up in `Other`
+        exceptions.retain(|name| syn::parse_str::<syn::Ident>(name).is_ok());
+
+        if exceptions.is_empty() && !functions.iter().any(|f| f.properties.can_fail) {
+            return None;
+        }
+        let user_classes = exceptions
+            .iter()
+            .filter(|name| class_names.contains(*name))
+            .cloned()
+            .collect();
+        Some(Self {
+            exceptions,
+            user_classes,
+        })
+    }
+
+    /// Whether `name` is a variant with a generated struct, as opposed to a
+    /// user class
+    fn is_generated(&self, name: &str) -> bool {
+        self.exceptions.contains(name) && !self.user_classes.contains(name)
+    }
+
+    /// Marks the predefined error structs the enum wraps as needed
+    pub fn mark_needed(&self, ctx: &mut CodeGenContext) {
+        ctx.needs_zerodivisionerror |= self.is_generated("ZeroDivisionError");
+        ctx.needs_indexerror |= self.is_generated("IndexError");
+        ctx.needs_valueerror |= self.is_generated("ValueError");
+        ctx.needs_typeerror |= self.is_generated("TypeError");
+        ctx.needs_stopiteration |= self.is_generated("StopIteration");
+        ctx.needs_overflowerror |= self.is_generated("OverflowError");
+    }
+}
 
-            impl ZeroDivisionError {
-                pub fn new(message: impl Into<String>) -> Self {
-                    Self { message: message.into() }
+/// Exception types named by `raise` and `except` anywhere in `stmts`
+fn exception_names(stmts: &[HirStmt], names: &mut BTreeSet<String>) {
+    use crate::hir::HirExpr;
+    for stmt in stmts {
+        match stmt {
+            HirStmt::Raise {
+                exception: Some(HirExpr::Call { func: name, .. } | HirExpr::Var(name)),
+                ..
+            } => {
+                names.insert(name.clone());
+            }
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                exception_names(then_body, names);
+                if let Some(else_body) = else_body {
+                    exception_names(else_body, names);
                 }
             }
-        });
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => exception_names(body, names),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                exception_names(body, names);
+                for handler in handlers {
+                    names.extend(handler.exception_type.iter().cloned());
+                    exception_names(&handler.body, names);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    exception_names(block, names);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                cases.iter().for_each(|c| exception_names(&c.body, names));
+            }
+            _ => {}
+        }
     }
+}
 
-    if ctx.needs_indexerror {
-        definitions.push(quote! {
-            #[derive(Debug, Clone)]
-            pub struct IndexError {
-                message: String,
+/// Generate the `ModuleError` enum and the structs its variants wrap
+///
+/// Exceptions other than the predefined ones and the module's own classes
+/// get a struct shaped like `ValueError`. The enum has a variant per
+/// exception with a `From` conversion, so `return Err(KeyError::new(..).into())`
+/// and `?` between the module's functions work, plus an `Other` variant for
+/// string errors such as the one a bare `raise` produces. A variant is
+/// transparent to `source()`, which returns the cause of the exception it
+/// wraps.
+///
+/// # Example
+/// ```text
+/// pub enum ModuleError {
+///     KeyError(KeyError),
+///     ValueError(ValueError),
+///     Other(String),
+/// }
+/// impl From<KeyError> for ModuleError { ... }
+/// impl From<&str> for ModuleError { ... }
+/// ```
+///
+/// # Complexity
+/// 4 (per-variant arms)
+pub fn generate_module_error(model: &ModuleErrorModel) -> Vec<proc_macro2::TokenStream> {
+    let enum_name = format_ident!("{}", MODULE_ERROR);
+    let mut definitions: Vec<proc_macro2::TokenStream> = model
+        .exceptions
+        .iter()
+        .filter(|name| model.is_generated(name) && !PREDEFINED_ERRORS.contains(&name.as_str()))
+        .map(|name| {
+            error_struct(
+                &format_ident!("{}", name),
+                quote! { write!(f, "{}", self.message) },
+            )
+        })
+        .collect();
+
+    let variants: Vec<_> = model
+        .exceptions
+        .iter()
+        .map(|name| format_ident!("{}", name))
+        .collect();
+    // User classes need not implement Debug, Display or Error, so those
+    // variants only report the class name
+    let debug_arms = model.exceptions.iter().map(|name| {
+        let variant = format_ident!("{}", name);
+        if model.user_classes.contains(name) {
+            quote! { #enum_name::#variant(_) => f.write_str(#name), }
+        } else {
+            quote! { #enum_name::#variant(e) => f.debug_tuple(#name).field(e).finish(), }
+        }
+    });
+    let display_arms = model.exceptions.iter().map(|name| {
+        let variant = format_ident!("{}", name);
+        if model.user_classes.contains(name) {
+            quote! { #enum_name::#variant(_) => f.write_str(#name), }
+        } else {
+            quote! { #enum_name::#variant(e) => write!(f, "{}", e), }
+        }
+    });
+    let source_arms = model.exceptions.iter().map(|name| {
+        let variant = format_ident!("{}", name);
+        if model.user_classes.contains(name) {
+            quote! { #enum_name::#variant(_) => None, }
+        } else {
+            quote! { #enum_name::#variant(e) => std::error::Error::source(e), }
+        }
+    });
+
+    definitions.push(quote! {
+        pub enum #enum_name {
+            #(#variants(#variants),)*
+            Other(String),
+        }
+
+        impl std::fmt::Debug for #enum_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    #(#debug_arms)*
+                    #enum_name::Other(message) => f.debug_tuple("Other").field(message).finish(),
+                }
             }
+        }
 
-            impl std::fmt::Display for IndexError {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    write!(f, "index out of range: {}", self.message)
+        impl std::fmt::Display for #enum_name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                match self {
+                    #(#display_arms)*
+                    #enum_name::Other(message) => f.write_str(message),
                 }
             }
+        }
 
-            impl std::error::Error for IndexError {}
+        impl std::error::Error for #enum_name {
+            fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+                match self {
+                    #(#source_arms)*
+                    #enum_name::Other(_) => None,
+                }
+            }
+        }
 
-            impl IndexError {
-                pub fn new(message: impl Into<String>) -> Self {
-                    Self { message: message.into() }
+        #(
+            impl From<#variants> for #enum_name {
+                fn from(e: #variants) -> Self {
+                    #enum_name::#variants(e)
                 }
             }
-        });
-    }
+        )*
 
-    if ctx.needs_valueerror {
-        definitions.push(quote! {
-            #[derive(Debug, Clone)]
-            pub struct ValueError {
-                message: String,
+        impl From<&str> for #enum_name {
+            fn from(message: &str) -> Self {
+                #enum_name::Other(message.to_string())
             }
+        }
 
-            impl std::fmt::Display for ValueError {
-                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                    write!(f, "value error: {}", self.message)
-                }
+        impl From<String> for #enum_name {
+            fn from(message: String) -> Self {
+                #enum_name::Other(message)
             }
+        }
+    });
+    definitions
+}
 
-            impl std::error::Error for ValueError {}
+/// Check whether `main()` should be replaced by an exit-code wrapper
+///
+/// Only a parameterless, fallible `main()` qualifies: infallible mains never
+/// surface an exception, and a `main` taking arguments is not an entry point.
+/// The wrapper is also skipped if the inner name is already taken.
+///
+/// # Complexity
+/// 4 (option check + two scans)
+pub fn needs_exit_code_main(functions: &[HirFunction], options: &ExitCodeOptions) -> bool {
+    if !options.enabled
+        || functions
+            .iter()
+            .any(|f| f.name == ExitCodeOptions::INNER_MAIN)
+    {
+        return false;
+    }
+    functions
+        .iter()
+        .any(|f| f.name == "main" && f.params.is_empty() && f.properties.can_fail)
+}
 
-            impl ValueError {
-                pub fn new(message: impl Into<String>) -> Self {
-                    Self { message: message.into() }
+/// Generate a `fn main()` that reports escaped exceptions like CPython
+///
+/// The user's `main()` has been renamed to [`ExitCodeOptions::INNER_MAIN`].
+/// Its error (concrete or `Box<dyn Error>`) is boxed, downcast against the
+/// Python error types generated for this module, printed as `Name: message`
+/// (just `Name` for an empty message), and turned into an exit status.
+/// The exceptions it was raised from, found through `source()`, are printed
+/// above it the way CPython prints a chained traceback.
+/// Errors that are not one of the generated types are reported as `Exception`.
+/// With a `module_error`, the downcast is to `ModuleError` and its variants
+/// give the exception type.
+/// For an `asyncio.run(main())` program the wrapper is `#[tokio::main]` and
+/// awaits the inner main.
+///
+/// # Example
+/// ```text
+/// pub fn main() {
+///     if let Err(err) = __depyler_main() {
+///         let err: Box<dyn std::error::Error> = err.into();
+///         let describe = |err: &(dyn std::error::Error + 'static)| {
+///             if let Some(e) = err.downcast_ref::<ValueError>() {
+///                 ("ValueError", e.message.clone(), 1)
+///             } else {
+///                 ("Exception", err.to_string(), 1)
+///             }
+///         };
+///         // `describe` each error of the `source()` chain, causes first
+///         ...
+///         std::process::exit(code);
+///     }
+/// }
+/// ```
+///
+/// # Complexity
+/// 5 (per-type downcast arms + header option)
+pub fn generate_exit_code_main(
+    ctx: &CodeGenContext,
+    options: &ExitCodeOptions,
+    module_error: Option<&ModuleErrorModel>,
+    is_async: bool,
+) -> proc_macro2::TokenStream {
+    let inner = format_ident!("{}", ExitCodeOptions::INNER_MAIN);
+
+    let arms = match module_error {
+        Some(model) => module_error_arms(model, options),
+        None => predefined_error_arms(ctx, options),
+    };
+    let fallback_code = proc_macro2::Literal::i32_unsuffixed(options.exit_code_for("Exception"));
+
+    let header = if options.traceback_header {
+        quote! { eprintln!("Traceback (most recent call last):"); }
+    } else {
+        quote! {}
+    };
+
+    // An `asyncio.run(main())` entry point awaits the inner main on tokio
+    let (signature, call) = if is_async {
+        (
+            quote! { #[tokio::main] pub async fn main() },
+            quote! { #inner().await },
+        )
+    } else {
+        (quote! { pub fn main() }, quote! { #inner() })
+    };
+
+    quote! {
+        #signature {
+            if let Err(err) = #call {
+                let err: Box<dyn std::error::Error> = err.into();
+                let describe = |err: &(dyn std::error::Error + 'static)| -> (&'static str, String, i32) {
+                    #(#arms)* { ("Exception", err.to_string(), #fallback_code) }
+                };
+                let mut chain = vec![err.as_ref()];
+                while let Some(cause) = std::error::Error::source(chain[chain.len() - 1]) {
+                    chain.push(cause);
+                }
+                for (depth, err) in chain.iter().rev().enumerate() {
+                    if depth > 0 {
+                        eprintln!("\nThe above exception was the direct cause of the following exception:\n");
+                    }
+                    let (exc_type, message, _) = describe(*err);
+                    #header
+                    if message.is_empty() {
+                        eprintln!("{}", exc_type);
+                    } else {
+                        eprintln!("{}: {}", exc_type, message);
+                    }
                 }
+                let (_, _, code) = describe(err.as_ref());
+                std::process::exit(code);
             }
-        });
+        }
     }
+}
 
-    definitions
+/// Downcast arms for errors that are one of the predefined structs
+fn predefined_error_arms(
+    ctx: &CodeGenContext,
+    options: &ExitCodeOptions,
+) -> Vec<proc_macro2::TokenStream> {
+    let known_types = [
+        ("ZeroDivisionError", ctx.needs_zerodivisionerror),
+        ("IndexError", ctx.needs_indexerror),
+        ("ValueError", ctx.needs_valueerror),
+        ("TypeError", ctx.needs_typeerror),
+        ("StopIteration", ctx.needs_stopiteration),
+        ("OverflowError", ctx.needs_overflowerror),
+    ];
+    known_types
+        .iter()
+        .filter(|(_, needed)| *needed)
+        .map(|(name, _)| {
+            let ty = format_ident!("{}", name);
+            let code = proc_macro2::Literal::i32_unsuffixed(options.exit_code_for(name));
+            quote! {
+                if let Some(e) = err.downcast_ref::<#ty>() {
+                    (#name, e.message.clone(), #code)
+                } else
+            }
+        })
+        .collect()
+}
+
+/// Downcast arms for the variants of `ModuleError`, then for the structs
+/// they wrap, which causes are; `Other` falls through to `Exception`
+fn module_error_arms(
+    model: &ModuleErrorModel,
+    options: &ExitCodeOptions,
+) -> Vec<proc_macro2::TokenStream> {
+    let enum_name = format_ident!("{}", MODULE_ERROR);
+    let structs = model
+        .exceptions
+        .iter()
+        .filter(|name| model.is_generated(name))
+        .map(|name| {
+            let ty = format_ident!("{}", name);
+            let code = proc_macro2::Literal::i32_unsuffixed(options.exit_code_for(name));
+            quote! {
+                if let Some(e) = err.downcast_ref::<#ty>() {
+                    (#name, e.message.clone(), #code)
+                } else
+            }
+        });
+    model
+        .exceptions
+        .iter()
+        .map(|name| {
+            let variant = format_ident!("{}", name);
+            let code = proc_macro2::Literal::i32_unsuffixed(options.exit_code_for(name));
+            let (binding, message) = if model.user_classes.contains(name) {
+                (quote! { _ }, quote! { String::new() })
+            } else {
+                (quote! { e }, quote! { e.message.clone() })
+            };
+            quote! {
+                if let Some(#enum_name::#variant(#binding)) = err.downcast_ref::<#enum_name>() {
+                    (#name, #message, #code)
+                } else
+            }
+        })
+        .chain(structs)
+        .collect()
 }
 
 // Note: Unit tests for this module are covered by integration tests
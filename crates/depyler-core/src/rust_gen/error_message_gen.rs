@@ -0,0 +1,167 @@
+//! Exception messages as CPython words them
+//!
+//! Callers and tests of a Python program often assert on the text of its
+//! exceptions, so the generated code fails with the messages CPython uses:
+//!
+//! | Python                      | Message                                       |
+//! |-----------------------------|-----------------------------------------------|
+//! | `d[k]`, `d.pop(k)`, missing | `KeyError: 'k'` (the key's repr)              |
+//! | `xs[i]`, out of range       | `IndexError: list index out of range`         |
+//! | `xs.pop()`, empty list      | `IndexError: pop from empty list`             |
+//! | `s.remove(x)`, missing      | `KeyError: 3`                                 |
+//! | `xs.index(x)`, missing      | `ValueError: 3 is not in list`                |
+//! | `xs.pop(i)`, out of range   | `IndexError: pop index out of range`          |
+//! | `s.pop()`, empty set        | `KeyError: 'pop from an empty set'`           |
+//! | `d.popitem()`, empty dict   | `KeyError: 'popitem(): dictionary is empty'`  |
+//! | `heappop(h)`, empty heap    | `IndexError: index out of range`              |
+//! | `text.index(sub)`, missing  | `ValueError: substring not found`             |
+//! | `range(a, b, 0)`            | `ValueError: range() arg 3 must not be zero`  |
+//! | `raise` with nothing active | `RuntimeError: No active exception to reraise`|
+//!
+//! An exception that is not handled panics with `Name: message`, the last
+//! line of CPython's traceback, and the generated exception types display
+//! just their message, as `str(e)` does.
+//!
+//! [`CodeGenOptions::native_error_messages`](crate::rust_gen::CodeGenOptions)
+//! keeps depyler's terse messages instead.
+
+use crate::hir::HirExpr;
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::py_str_gen;
+use syn::parse_quote;
+
+/// A builtin operation failing the way Python raises
+pub(crate) enum BuiltinError<'a> {
+    /// `dict[key]` or `dict.pop(key)` without the key
+    MissingKey(&'a HirExpr, &'a syn::Expr),
+    /// `set.remove(element)` without the element
+    MissingElement(&'a HirExpr, &'a syn::Expr),
+    /// `list.index(value)` without the value
+    NotInList(&'a HirExpr, &'a syn::Expr),
+    ListIndexOutOfRange,
+    PopIndexOutOfRange,
+    PopFromEmptyList,
+    PopFromEmptySet,
+    PopitemFromEmptyDict,
+    HeappopFromEmptyHeap,
+    SubstringNotFound,
+    RangeStepZero,
+}
+
+/// A message fixed at transpile time, or one showing a value's repr
+enum Message {
+    Fixed(&'static str),
+    Repr {
+        format: &'static str,
+        repr: syn::Expr,
+    },
+}
+
+impl BuiltinError<'_> {
+    /// depyler's historical message
+    fn native(&self) -> &'static str {
+        match self {
+            BuiltinError::MissingKey(..) => "KeyError: key not found",
+            BuiltinError::MissingElement(..) => "KeyError: element not in set",
+            BuiltinError::NotInList(..) => "ValueError: value is not in list",
+            BuiltinError::ListIndexOutOfRange => "index out of range",
+            BuiltinError::PopIndexOutOfRange => "pop index out of range",
+            BuiltinError::PopFromEmptyList => "pop from empty list",
+            BuiltinError::PopFromEmptySet => "pop from empty set",
+            BuiltinError::PopitemFromEmptyDict => "KeyError: popitem(): dictionary is empty",
+            BuiltinError::HeappopFromEmptyHeap => "heappop from empty heap",
+            BuiltinError::SubstringNotFound => "substring not found",
+            BuiltinError::RangeStepZero => "range() arg 3 must not be zero",
+        }
+    }
+
+    /// The message, for errors that show no value
+    pub(crate) fn text(&self, ctx: &CodeGenContext) -> &'static str {
+        if ctx.options.native_error_messages {
+            return self.native();
+        }
+        match self {
+            // Shown without the value when it cannot be evaluated again
+            BuiltinError::MissingKey(..)
+            | BuiltinError::MissingElement(..)
+            | BuiltinError::NotInList(..) => self.native(),
+            BuiltinError::ListIndexOutOfRange => "IndexError: list index out of range",
+            BuiltinError::PopIndexOutOfRange => "IndexError: pop index out of range",
+            BuiltinError::PopFromEmptyList => "IndexError: pop from empty list",
+            BuiltinError::PopFromEmptySet => "KeyError: 'pop from an empty set'",
+            BuiltinError::PopitemFromEmptyDict => "KeyError: 'popitem(): dictionary is empty'",
+            BuiltinError::HeappopFromEmptyHeap => "IndexError: index out of range",
+            BuiltinError::SubstringNotFound => "ValueError: substring not found",
+            BuiltinError::RangeStepZero => "ValueError: range() arg 3 must not be zero",
+        }
+    }
+
+    fn message(&self, ctx: &mut CodeGenContext) -> Message {
+        let (format, value, value_expr) = match self {
+            BuiltinError::MissingKey(value, value_expr)
+            | BuiltinError::MissingElement(value, value_expr) => {
+                ("KeyError: {}", value, value_expr)
+            }
+            BuiltinError::NotInList(value, value_expr) => {
+                ("ValueError: {} is not in list", value, value_expr)
+            }
+            _ => return Message::Fixed(self.text(ctx)),
+        };
+        // The value is evaluated again for the message, so only when that
+        // is cheap and has no effects
+        if ctx.options.native_error_messages
+            || !matches!(value, HirExpr::Var(_) | HirExpr::Literal(_))
+        {
+            return Message::Fixed(self.text(ctx));
+        }
+        Message::Repr {
+            format,
+            repr: py_str_gen::codegen_repr(value, value_expr, ctx),
+        }
+    }
+}
+
+/// `option` unwrapped, panicking with `error`'s message when it is `None`
+pub(crate) fn expect_some(
+    option: syn::Expr,
+    error: BuiltinError,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    match error.message(ctx) {
+        Message::Fixed(message) => parse_quote! { #option.expect(#message) },
+        Message::Repr { format, repr } => {
+            parse_quote! { #option.unwrap_or_else(|| panic!(#format, #repr)) }
+        }
+    }
+}
+
+/// A `panic!` with `error`'s message
+pub(crate) fn panic_with(error: BuiltinError, ctx: &mut CodeGenContext) -> syn::Expr {
+    match error.message(ctx) {
+        Message::Fixed(message) => parse_quote! { panic!(#message) },
+        Message::Repr { format, repr } => parse_quote! { panic!(#format, #repr) },
+    }
+}
+
+/// A `panic!` for `exception`, an unhandled exception of type `name`
+pub(crate) fn panic_unhandled(
+    name: &str,
+    exception: &syn::Expr,
+    ctx: &CodeGenContext,
+) -> proc_macro2::TokenStream {
+    if ctx.options.native_error_messages || name.is_empty() {
+        quote::quote! { panic!("{}", #exception) }
+    } else {
+        let format = format!("{}: {{}}", name);
+        quote::quote! { panic!(#format, #exception) }
+    }
+}
+
+/// The error of a bare `raise` outside an exception handler
+pub(crate) fn no_active_exception(ctx: &CodeGenContext) -> &'static str {
+    if ctx.options.native_error_messages {
+        "Exception raised"
+    } else {
+        "No active exception to reraise"
+    }
+}
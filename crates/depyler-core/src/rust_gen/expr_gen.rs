@@ -5,11 +5,29 @@
 //! and the ToRustExpr trait implementation for HirExpr.
 
 use crate::hir::*;
-use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::arena_gen;
+use crate::rust_gen::async_gen;
+use crate::rust_gen::cast_gen;
+use crate::rust_gen::comparison_gen;
+use crate::rust_gen::context::{CodeGenContext, HandlerBinding, ToRustExpr};
+use crate::rust_gen::context_manager_gen::{codegen_file_method, FILE_TYPE};
+use crate::rust_gen::contextlib_gen::{codegen_exit_stack_callback, EXIT_STACK_TYPE};
+use crate::rust_gen::default_param_gen;
+use crate::rust_gen::error_message_gen::{self, BuiltinError};
+use crate::rust_gen::int_semantics_gen;
+use crate::rust_gen::io_gen;
+use crate::rust_gen::iter_gen;
+use crate::rust_gen::ledger::{self, DivergenceKind};
+use crate::rust_gen::numeric_parse_gen;
+use crate::rust_gen::percent_format_gen::{self, Conversion, FieldArg};
+use crate::rust_gen::py_str_gen;
 use crate::rust_gen::return_type_expects_float;
+use crate::rust_gen::slice_gen;
+use crate::rust_gen::sort_gen;
 use crate::rust_gen::type_gen::convert_binop;
 use crate::string_optimization::{StringContext, StringOptimizer};
 use anyhow::{bail, Result};
+use depyler_annotations::StringIndexing;
 use quote::quote;
 use syn::{self, parse_quote};
 
@@ -95,6 +113,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             );
         }
 
+        // Module values computed at runtime live behind a LazyLock
+        if self.ctx.lazy_globals.contains_key(name) && !self.ctx.is_declared(name) {
+            let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+            return Ok(parse_quote! { (*#ident) });
+        }
+
         // Inside generators, check if variable is a state variable
         if self.ctx.in_generator && self.ctx.generator_state_vars.contains(name) {
             // Generate self.field for state variables
@@ -116,9 +140,33 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     }
 
     fn convert_binary(&mut self, op: BinOp, left: &HirExpr, right: &HirExpr) -> Result<syn::Expr> {
+        // `"..." % args` formats rather than divides
+        if let (BinOp::Mod, HirExpr::Literal(Literal::String(template))) = (op, left) {
+            return self.convert_percent_format(template, right);
+        }
+
         let left_expr = left.to_rust_expr(self.ctx)?;
         let right_expr = right.to_rust_expr(self.ctx)?;
 
+        // Overflow checks or BigInt operations for `integer_semantics`
+        if let Some(arithmetic) = int_semantics_gen::codegen_int_binary(
+            op,
+            left,
+            right,
+            &left_expr,
+            &right_expr,
+            self.ctx,
+        )? {
+            return Ok(arithmetic);
+        }
+
+        // Python compares int, float and bool by value, and lists deeply
+        if let Some(comparison) =
+            comparison_gen::codegen_comparison(op, left, right, &left_expr, &right_expr, self.ctx)
+        {
+            return Ok(comparison);
+        }
+
         match op {
             BinOp::In => {
                 // Convert "x in container" to appropriate method call
@@ -290,6 +338,23 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 // Set difference operation
                 self.convert_set_operation(op, left_expr, right_expr)
             }
+            BinOp::Sub if self.ctx.is_instant_expr(left) && self.ctx.is_instant_expr(right) => {
+                // time.perf_counter() - start → start.elapsed().as_secs_f64()
+                // start - time.perf_counter() → -start.elapsed().as_secs_f64()
+                // end - start → signed, since `duration_since` saturates at zero
+                match (left, right) {
+                    (HirExpr::Var(_), HirExpr::Var(_)) => Ok(parse_quote! {
+                        match #left_expr.checked_duration_since(#right_expr) {
+                            Some(delta) => delta.as_secs_f64(),
+                            None => -#right_expr.duration_since(#left_expr).as_secs_f64(),
+                        }
+                    }),
+                    (HirExpr::Var(_), _) => {
+                        Ok(parse_quote! { -#left_expr.elapsed().as_secs_f64() })
+                    }
+                    _ => Ok(parse_quote! { #right_expr.elapsed().as_secs_f64() }),
+                }
+            }
             BinOp::Sub => {
                 // Check if we're subtracting from a .len() call to prevent underflow
                 if self.is_len_call(left) {
@@ -359,8 +424,15 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     .unwrap_or(false);
 
                 if needs_float_division {
-                    // Cast both operands to f64 for Python float division semantics
-                    Ok(parse_quote! { (#left_expr as f64) / (#right_expr as f64) })
+                    // Convert both operands to f64 for Python float division semantics
+                    let left_expr = cast_gen::codegen_float_cast(left, &left_expr, self.ctx);
+                    let right_expr = cast_gen::codegen_float_cast(right, &right_expr, self.ctx);
+                    Ok(syn::Expr::Binary(syn::ExprBinary {
+                        attrs: vec![],
+                        left: Box::new(left_expr),
+                        op: parse_quote! { / },
+                        right: Box::new(right_expr),
+                    }))
                 } else {
                     // Regular division (int/int → int, float/float → float)
                     let rust_op = convert_binop(op)?;
@@ -476,7 +548,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     Ok(parse_quote! { !#operand_expr })
                 }
             }
-            UnaryOp::Neg => Ok(parse_quote! { -#operand_expr }),
+            UnaryOp::Neg => {
+                match int_semantics_gen::codegen_int_neg(operand, &operand_expr, self.ctx) {
+                    Some(negated) => Ok(negated),
+                    None => Ok(parse_quote! { -#operand_expr }),
+                }
+            }
             UnaryOp::Pos => Ok(operand_expr), // No +x in Rust
             UnaryOp::BitNot => Ok(parse_quote! { !#operand_expr }),
         }
@@ -532,17 +609,21 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         if func == "sum" && args.len() == 1 && matches!(args[0], HirExpr::GeneratorExp { .. }) {
             let gen_expr = args[0].to_rust_expr(self.ctx)?;
 
-            // Infer the target type from return type context
-            let target_type = self
-                .ctx
-                .current_return_type
-                .as_ref()
-                .and_then(|t| match t {
+            // Infer the target type from the items added up, else from the
+            // return type context
+            let target_type = match self.generator_item_type(&args[0]) {
+                Type::Int => Some(quote! { i32 }),
+                Type::Float => Some(quote! { f64 }),
+                _ => None,
+            }
+            .or_else(|| {
+                self.ctx.current_return_type.as_ref().and_then(|t| match t {
                     Type::Int => Some(quote! { i32 }),
                     Type::Float => Some(quote! { f64 }),
                     _ => None,
                 })
-                .unwrap_or_else(|| quote! { i32 });
+            })
+            .unwrap_or_else(|| quote! { i32 });
 
             return Ok(parse_quote! { #gen_expr.sum::<#target_type>() });
         }
@@ -556,13 +637,9 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         // DEPYLER-0190: Handle sorted(iterable) → { let mut result = iterable.clone(); result.sort(); result }
         if func == "sorted" && args.len() == 1 {
             let iter_expr = args[0].to_rust_expr(self.ctx)?;
-            return Ok(parse_quote! {
-                {
-                    let mut __sorted_result = #iter_expr.clone();
-                    __sorted_result.sort();
-                    __sorted_result
-                }
-            });
+            return Ok(sort_gen::codegen_sorted(
+                &args[0], &iter_expr, None, false, self.ctx,
+            ));
         }
 
         // DEPYLER-0191: Handle reversed(iterable) → iterable.into_iter().rev().collect()
@@ -1002,6 +1079,14 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             return Ok(parse_quote! { true });
         }
 
+        // getattr(obj, "x") → obj.x, hasattr(obj, "x") → true or false
+        if func == "getattr" {
+            return self.convert_getattr_builtin(args);
+        }
+        if func == "hasattr" {
+            return self.convert_hasattr_builtin(args);
+        }
+
         // DEPYLER-0230: Check if func is a user-defined class before treating as builtin
         let is_user_class = self.ctx.class_names.contains(func);
 
@@ -1025,11 +1110,26 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 .collect::<Result<Vec<_>>>()?
         };
 
+        match (func, args) {
+            ("print", _) => {
+                for arg in args {
+                    ledger::record_display(arg, self.ctx);
+                }
+            }
+            ("len", [arg]) if self.ctx.value_type(arg) == Type::String => {
+                self.ctx.divergences.record(
+                    DivergenceKind::UnicodeIndexing,
+                    "`len()` of a str counts UTF-8 bytes, not characters",
+                );
+            }
+            _ => {}
+        }
+
         match func {
             // Python built-in type conversions → Rust casting
             "int" => self.convert_int_cast(args, &arg_exprs),
-            "float" => self.convert_float_cast(&arg_exprs),
-            "str" => self.convert_str_conversion(&arg_exprs),
+            "float" => self.convert_float_cast(args, &arg_exprs),
+            "str" => self.convert_str_conversion(args, &arg_exprs),
             "bool" => self.convert_bool_cast(&arg_exprs),
             // Other built-in functions
             "len" => self.convert_len_call(&arg_exprs),
@@ -1065,12 +1165,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             "chr" => self.convert_chr_builtin(&arg_exprs),
             "ord" => self.convert_ord_builtin(&arg_exprs),
             "hash" => self.convert_hash_builtin(&arg_exprs),
-            "repr" => self.convert_repr_builtin(&arg_exprs),
-            // DEPYLER-STDLIB-50: next(), getattr(), iter(), type()
-            "next" => self.convert_next_builtin(&arg_exprs),
-            "getattr" => self.convert_getattr_builtin(&arg_exprs),
-            "iter" => self.convert_iter_builtin(&arg_exprs),
+            "repr" => self.convert_repr_builtin(args, &arg_exprs),
+            // DEPYLER-STDLIB-50: next(), iter(), type()
+            "next" => self.convert_next_builtin(args, &arg_exprs),
+            "iter" => self.convert_iter_builtin(args, &arg_exprs),
             "type" => self.convert_type_builtin(&arg_exprs),
+            "input" => io_gen::codegen_input(&arg_exprs, self.ctx),
             _ => self.convert_generic_call(func, args, &arg_exprs),
         }
     }
@@ -1163,115 +1263,113 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         Ok(parse_quote! { #arg.len() as i32 })
     }
 
-    fn convert_int_cast(&self, hir_args: &[HirExpr], arg_exprs: &[syn::Expr]) -> Result<syn::Expr> {
+    fn convert_int_cast(
+        &mut self,
+        hir_args: &[HirExpr],
+        arg_exprs: &[syn::Expr],
+    ) -> Result<syn::Expr> {
         if arg_exprs.is_empty() || arg_exprs.len() > 2 {
             bail!("int() requires 1-2 arguments");
         }
         let arg = &arg_exprs[0];
 
         // Python int() serves four purposes:
-        // 1. Parse strings to integers (requires .parse())
+        // 1. Parse strings to integers (Python's rules, via py_int)
         // 2. Convert floats to integers (truncation via as i32)
         // 3. Convert bools to integers (False→0, True→1 via as i32)
         // 4. Ensure integer type for indexing (via as i32)
         //
-        // DEPYLER-0307 Fix #7: Check if variable is String type
-        // String variables need .parse().unwrap_or_default() not 'as i32' cast
-        //
-        // DEPYLER-0327 Fix #1: Improved type inference for method calls
-        // Check if expression is a String-typed method call (e.g., Vec<String>.get())
-        //
         // Strategy:
-        // - For String variables/params → .parse().unwrap_or_default()
-        // - For String literals → .parse().unwrap_or_default()
-        // - For String-typed method calls → .parse().unwrap_or_default()
-        // - For known bool expressions → as i32 cast
-        // - For integer literals → no cast needed
-        // - For other variables → as i32 cast conservatively
-        if !hir_args.is_empty() {
-            match &hir_args[0] {
-                // Integer literals don't need casting
-                HirExpr::Literal(Literal::Int(_)) => return Ok(arg.clone()),
-
-                // DEPYLER-0327 Fix #1: String literals need parsing
-                HirExpr::Literal(Literal::String(_)) => {
-                    return Ok(parse_quote! { #arg.parse::<i32>().unwrap_or_default() });
-                }
+        // - With a base argument, or for String literals, variables and
+        //   String-typed method calls → py_int(), raising ValueError
+        // - For values already int → no cast needed
+        // - For everything else → i32::from or an as i32 cast
+        if arg_exprs.len() == 2 || self.is_string_arg(&hir_args[0]) {
+            let base = hir_args.get(1).zip(arg_exprs.get(1));
+            return Ok(numeric_parse_gen::codegen_int_parse(
+                &hir_args[0],
+                arg,
+                base,
+                self.ctx,
+            ));
+        }
+        Ok(cast_gen::codegen_int_cast(&hir_args[0], arg, self.ctx))
+    }
 
-                // DEPYLER-0307 Fix #7: Check if variable is String type
-                // DEPYLER-0327 Fix #1: Also use heuristic for variable names
-                HirExpr::Var(var_name) => {
-                    // Check if this variable is known to be String type
-                    let is_known_string = if let Some(var_type) = self.ctx.var_types.get(var_name) {
-                        matches!(var_type, Type::String)
-                    } else {
-                        false
-                    };
+    fn convert_float_cast(
+        &mut self,
+        hir_args: &[HirExpr],
+        arg_exprs: &[syn::Expr],
+    ) -> Result<syn::Expr> {
+        if arg_exprs.len() != 1 {
+            bail!("float() requires exactly one argument");
+        }
+        let arg = &arg_exprs[0];
+        if self.is_string_arg(&hir_args[0]) {
+            return Ok(numeric_parse_gen::codegen_float_parse(
+                &hir_args[0],
+                arg,
+                self.ctx,
+            ));
+        }
+        Ok(cast_gen::codegen_float_cast(&hir_args[0], arg, self.ctx))
+    }
 
-                    // Heuristic: variable names ending in _str, _string, or common string names
-                    let name = var_name.as_str();
-                    let looks_like_string = name.ends_with("_str")
-                        || name.ends_with("_string")
-                        || name == "s"
-                        || name == "string"
-                        || name == "text"
-                        || name == "word"
-                        || name == "line"
-                        || name == "value_str"  // Explicit case for this example
-                        || name.starts_with("str_")
-                        || name.starts_with("string_");
-
-                    if is_known_string || looks_like_string {
-                        // String → int requires parsing, not casting
-                        // DEPYLER-0293: Use turbofish syntax to specify target type
-                        return Ok(parse_quote! { #arg.parse::<i32>().unwrap_or_default() });
-                    }
-                    // Default: use as i32 cast for other types
-                    return Ok(parse_quote! { (#arg) as i32 });
-                }
-
-                // DEPYLER-0327 Fix #1: Check if method call returns String type
-                // E.g., Vec<String>.get() or str methods
-                HirExpr::MethodCall { object, method, args: method_args , ..} => {
-                    // Check if this is .get() on a Vec<String> or similar
-                    if self.is_string_method_call(object, method, method_args) {
-                        return Ok(parse_quote! { #arg.parse::<i32>().unwrap_or_default() });
-                    }
-                    // Otherwise, use default cast
-                    return Ok(parse_quote! { (#arg) as i32 });
-                }
-
-                // Check if it's a known bool expression
-                expr => {
-                    if let Some(is_bool) = self.is_bool_expr(expr) {
-                        if is_bool {
-                            return Ok(parse_quote! { (#arg) as i32 });
-                        }
-                    }
-                    // For other complex expressions, apply cast conservatively
-                    return Ok(parse_quote! { (#arg) as i32 });
-                }
+    /// Whether a conversion's argument is a string to parse rather than a
+    /// number to cast
+    fn is_string_arg(&self, arg: &HirExpr) -> bool {
+        match arg {
+            // DEPYLER-0327 Fix #1: String literals need parsing
+            HirExpr::Literal(Literal::String(_)) => true,
+
+            // DEPYLER-0307 Fix #7: Check if variable is String type
+            // DEPYLER-0327 Fix #1: Also use heuristic for variable names
+            HirExpr::Var(var_name) => {
+                let is_known_string =
+                    matches!(self.ctx.var_types.get(var_name), Some(Type::String));
+
+                // Heuristic: variable names ending in _str, _string, or common string names
+                let name = var_name.as_str();
+                let looks_like_string = name.ends_with("_str")
+                    || name.ends_with("_string")
+                    || name == "s"
+                    || name == "string"
+                    || name == "text"
+                    || name == "word"
+                    || name == "line"
+                    || name == "value_str"  // Explicit case for this example
+                    || name.starts_with("str_")
+                    || name.starts_with("string_");
+
+                is_known_string || looks_like_string
             }
-        }
 
-        // Default: cast for safety
-        Ok(parse_quote! { (#arg) as i32 })
-    }
+            // DEPYLER-0327 Fix #1: Check if method call returns String type
+            // E.g., Vec<String>.get() or str methods
+            HirExpr::MethodCall {
+                object,
+                method,
+                args: method_args,
+                ..
+            } => self.is_string_method_call(object, method, method_args),
 
-    fn convert_float_cast(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
-        if args.len() != 1 {
-            bail!("float() requires exactly one argument");
+            _ => false,
         }
-        let arg = &args[0];
-        Ok(parse_quote! { (#arg) as f64 })
     }
 
-    fn convert_str_conversion(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
-        if args.len() != 1 {
+    fn convert_str_conversion(
+        &mut self,
+        hir_args: &[HirExpr],
+        arg_exprs: &[syn::Expr],
+    ) -> Result<syn::Expr> {
+        if arg_exprs.len() != 1 {
             bail!("str() requires exactly one argument");
         }
-        let arg = &args[0];
-        Ok(parse_quote! { #arg.to_string() })
+        Ok(py_str_gen::codegen_str(
+            &hir_args[0],
+            &arg_exprs[0],
+            self.ctx,
+        ))
     }
 
     fn convert_bool_cast(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
@@ -1325,12 +1423,13 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     ) -> Result<syn::Expr> {
         // For negative steps, we need to reverse the range
         // Python: range(10, 0, -1) → Rust: (0..10).rev()
+        let step_zero = BuiltinError::RangeStepZero.text(self.ctx);
         Ok(parse_quote! {
             {
                 // DEPYLER-0313: Cast to i32 before abs() to avoid ambiguous numeric type
                 let step = (#step as i32).abs() as usize;
                 if step == 0 {
-                    panic!("range() arg 3 must not be zero");
+                    panic!(#step_zero);
                 }
                 // DEPYLER-0316: Always use .step_by() for consistent iterator type
                 // This avoids if/else branches returning different types:
@@ -1348,11 +1447,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         step: &syn::Expr,
     ) -> Result<syn::Expr> {
         // Positive step - check for zero
+        let step_zero = BuiltinError::RangeStepZero.text(self.ctx);
         Ok(parse_quote! {
             {
                 let step = #step as usize;
                 if step == 0 {
-                    panic!("range() arg 3 must not be zero");
+                    panic!(#step_zero);
                 }
                 (#start..#end).step_by(step)
             }
@@ -1811,50 +1911,102 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         })
     }
 
-    fn convert_repr_builtin(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
-        if args.len() != 1 {
+    fn convert_repr_builtin(
+        &mut self,
+        hir_args: &[HirExpr],
+        arg_exprs: &[syn::Expr],
+    ) -> Result<syn::Expr> {
+        if arg_exprs.len() != 1 {
             bail!("repr() requires exactly 1 argument");
         }
-        let value = &args[0];
-        Ok(parse_quote! { format!("{:?}", #value) })
+        Ok(py_str_gen::codegen_repr(
+            &hir_args[0],
+            &arg_exprs[0],
+            self.ctx,
+        ))
     }
 
     // DEPYLER-STDLIB-50: next() - get next item from iterator
-    fn convert_next_builtin(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
+    fn convert_next_builtin(
+        &mut self,
+        hir_args: &[HirExpr],
+        args: &[syn::Expr],
+    ) -> Result<syn::Expr> {
         if args.is_empty() || args.len() > 2 {
             bail!("next() requires 1 or 2 arguments (iterator, optional default)");
         }
-        let iterator = &args[0];
-        if args.len() == 2 {
-            let default = &args[1];
-            Ok(parse_quote! {
-                #iterator.next().unwrap_or(#default)
-            })
-        } else {
-            Ok(parse_quote! {
-                #iterator.next().expect("StopIteration: iterator is empty")
-            })
+        let default = hir_args.get(1).zip(args.get(1));
+        Ok(iter_gen::codegen_next(&args[0], default, self.ctx))
+    }
+
+    /// `getattr(obj, "x")` as `obj.x`
+    ///
+    /// With a default, the default stands in for an attribute the class of
+    /// `obj` does not have, so `getattr(obj, "x", None)` is `None` then.
+    /// Names known only at runtime are rejected by `dynamic_attributes`
+    /// before code generation.
+    fn convert_getattr_builtin(&mut self, args: &[HirExpr]) -> Result<syn::Expr> {
+        let (object, attr, default) = match args {
+            [object, HirExpr::Literal(Literal::String(attr))] => (object, attr, None),
+            [object, HirExpr::Literal(Literal::String(attr)), default] => {
+                (object, attr, Some(default))
+            }
+            [_, _] | [_, _, _] => {
+                bail!("getattr() requires the attribute name as a string literal")
+            }
+            _ => bail!("getattr() requires 2 or 3 arguments (object, name, optional default)"),
+        };
+        match (self.class_of(object), default) {
+            (Some(class), default) if !self.has_field(&class, attr) => match default {
+                Some(default) => default.to_rust_expr(self.ctx),
+                None => bail!("getattr(): class `{}` has no attribute `{}`", class, attr),
+            },
+            (None, Some(_)) => bail!(
+                "getattr() with a default requires the object to be typed as a class of the \
+                 module, to know whether it has `{}`",
+                attr
+            ),
+            _ => self.convert_attribute(object, attr),
+        }
+    }
+
+    /// `hasattr(obj, "x")` as `true` or `false`, from the fields of the class
+    /// of `obj`
+    fn convert_hasattr_builtin(&mut self, args: &[HirExpr]) -> Result<syn::Expr> {
+        let (object, attr) = match args {
+            [object, HirExpr::Literal(Literal::String(attr))] => (object, attr),
+            [_, _] => bail!("hasattr() requires the attribute name as a string literal"),
+            _ => bail!("hasattr() requires 2 arguments (object, name)"),
+        };
+        match self.class_of(object) {
+            Some(class) if self.has_field(&class, attr) => Ok(parse_quote! { true }),
+            Some(_) => Ok(parse_quote! { false }),
+            None => bail!(
+                "hasattr() requires the object to be typed as a class of the module, \
+                 to know whether it has `{}`",
+                attr
+            ),
         }
     }
 
-    // DEPYLER-STDLIB-50: getattr() - get attribute by name
-    fn convert_getattr_builtin(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
-        if args.len() < 2 || args.len() > 3 {
-            bail!("getattr() requires 2 or 3 arguments (object, name, optional default)");
+    /// The class of the module `object` is typed as
+    fn class_of(&self, object: &HirExpr) -> Option<String> {
+        match self.ctx.value_type(object) {
+            Type::Custom(class) if self.ctx.class_names.contains(&class) => Some(class),
+            _ => None,
         }
-        // Note: This is a simplified implementation
-        // Full getattr() requires runtime attribute lookup which isn't possible in Rust
-        // For now, we'll bail as it needs special handling
-        bail!("getattr() requires dynamic attribute access not fully supported yet")
+    }
+
+    fn has_field(&self, class: &str, attr: &str) -> bool {
+        self.ctx.class_fields.field_type(class, attr).is_some()
     }
 
     // DEPYLER-STDLIB-50: iter() - create iterator
-    fn convert_iter_builtin(&self, args: &[syn::Expr]) -> Result<syn::Expr> {
+    fn convert_iter_builtin(&self, hir_args: &[HirExpr], args: &[syn::Expr]) -> Result<syn::Expr> {
         if args.len() != 1 {
             bail!("iter() requires exactly 1 argument");
         }
-        let iterable = &args[0];
-        Ok(parse_quote! { #iterable.into_iter() })
+        Ok(iter_gen::codegen_iter(&hir_args[0], &args[0], self.ctx))
     }
 
     // DEPYLER-STDLIB-50: type() - get type name
@@ -1898,6 +2050,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     | "enumerate"
                     | "chain"
                     | "flat_map"
+                    | "filter_map"
                     | "take"
                     | "skip"
                     | "collect"
@@ -1996,9 +2149,15 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             .map(|c| c.is_uppercase())
             .unwrap_or(false)
         {
+            let fallible = self.ctx.current_function_can_fail
+                && self.ctx.result_returning_functions.contains(func);
+            if self.ctx.arenas.is_arena(func) {
+                return Ok(arena_gen::codegen_alloc(func, args, fallible, self.ctx));
+            }
+
             // Treat as constructor call - ClassName::new(args)
             let class_ident = syn::Ident::new(func, proc_macro2::Span::call_site());
-            if args.is_empty() {
+            let call: syn::Expr = if args.is_empty() {
                 // DEPYLER-0233: Only apply default argument heuristics for Python stdlib types
                 // User-defined classes should always generate ClassName::new() with no args
                 let is_user_class = self.ctx.class_names.contains(func);
@@ -2010,9 +2169,16 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 if !is_user_class && func == "Counter" {
                     return Ok(parse_quote! { #class_ident::new(0) });
                 }
-                Ok(parse_quote! { #class_ident::new() })
+                parse_quote! { #class_ident::new() }
             } else {
-                Ok(parse_quote! { #class_ident::new(#(#args),*) })
+                parse_quote! { #class_ident::new(#(#args),*) }
+            };
+
+            // Fallible dataclass constructor (`__post_init__` raises)
+            if fallible {
+                Ok(parse_quote! { #call? })
+            } else {
+                Ok(call)
             }
         } else {
             // Regular function call
@@ -2033,6 +2199,15 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 .zip(args.iter())
                 .enumerate()
                 .map(|(param_idx, (hir_arg, arg_expr))| {
+                    // A parameter taking an `Option` of its default is owned
+                    if default_param_gen::passes_owned(func, param_idx, self.ctx) {
+                        return arg_expr.clone();
+                    }
+                    // The returned reference points into this argument, lent
+                    // whatever its type; `&&str` coerces like `&String`
+                    if self.ctx.borrowed_return_functions.get(func) == Some(&param_idx) {
+                        return parse_quote! { &#arg_expr };
+                    }
                     // Check if this param should be borrowed by looking up function signature
                     let should_borrow = match hir_arg {
                         HirExpr::Var(var_name) => {
@@ -2078,17 +2253,32 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     }
                 })
                 .collect();
+            let borrowed_args =
+                default_param_gen::wrap_optional_args(func, hir_args, borrowed_args, self.ctx);
+            let (hoisted, borrowed_args) =
+                arena_gen::with_arena_args(func, borrowed_args, self.ctx);
 
             // DEPYLER-0287 Fix Part 2: Add `?` operator for recursive calls in Result-returning functions
             // If we're in a function that can fail (returns Result), and we're calling another
             // function (potentially recursive), propagate errors with `?` operator.
             // This is needed for recursive functions that perform operations like list indexing
             // which return Result<T, E>.
-            if self.ctx.current_function_can_fail {
-                Ok(parse_quote! { #func_ident(#(#borrowed_args),*)? })
+            let call: syn::Expr = if self.ctx.current_function_can_fail {
+                parse_quote! { #func_ident(#(#borrowed_args),*)? }
             } else {
-                Ok(parse_quote! { #func_ident(#(#borrowed_args),*) })
-            }
+                parse_quote! { #func_ident(#(#borrowed_args),*) }
+            };
+
+            // A reference into an argument becomes the owned value callers
+            // hold on to
+            let call = if self.ctx.cow_return_functions.contains(func) {
+                parse_quote! { #call.into_owned() }
+            } else if self.ctx.borrowed_return_functions.contains_key(func) {
+                parse_quote! { #call.to_owned() }
+            } else {
+                call
+            };
+            Ok(arena_gen::with_hoisted(hoisted, call))
         }
     }
 
@@ -2253,6 +2443,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         // Mark that we need serde_json crate
         self.ctx.needs_serde_json = true;
 
+        // Dataclasses serialize through their derived impls, and the caller
+        // was made fallible to propagate serde_json's errors
+        let serde_value = self.ctx.current_function_can_fail
+            && args.first().is_some_and(|arg| self.is_serde_value(arg));
+
         let result = match method {
             // String serialization/deserialization
             "dumps" => {
@@ -2264,7 +2459,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 // DEPYLER-0377: Check if indent parameter is provided
                 // json.dumps(result, indent=2) has 2 arguments after HIR conversion
                 // (keyword args become positional args in HIR)
-                if arg_exprs.len() >= 2 {
+                if serde_value && arg_exprs.len() >= 2 {
+                    parse_quote! { serde_json::to_string_pretty(&#obj)? }
+                } else if serde_value {
+                    parse_quote! { serde_json::to_string(&#obj)? }
+                } else if arg_exprs.len() >= 2 {
                     // json.dumps(obj, indent=n) → serde_json::to_string_pretty(&obj).unwrap()
                     parse_quote! { serde_json::to_string_pretty(&#obj).unwrap() }
                 } else {
@@ -2290,8 +2489,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 }
                 let obj = &arg_exprs[0];
                 let file = &arg_exprs[1];
-                // json.dump(obj, file) → serde_json::to_writer(file, &obj).unwrap()
-                parse_quote! { serde_json::to_writer(#file, &#obj).unwrap() }
+                if serde_value {
+                    parse_quote! { serde_json::to_writer(#file, &#obj)? }
+                } else {
+                    // json.dump(obj, file) → serde_json::to_writer(file, &obj).unwrap()
+                    parse_quote! { serde_json::to_writer(#file, &#obj).unwrap() }
+                }
             }
 
             "load" => {
@@ -2311,6 +2514,19 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         Ok(Some(result))
     }
 
+    /// Whether `expr` is a value of a class deriving serde's traits
+    fn is_serde_value(&self, expr: &HirExpr) -> bool {
+        match expr {
+            HirExpr::Var(name) => self
+                .ctx
+                .var_types
+                .get(name)
+                .is_some_and(|ty| self.ctx.is_serde_type(ty)),
+            HirExpr::Call { func, .. } => self.ctx.serde_classes.contains(func),
+            _ => false,
+        }
+    }
+
     /// Try to convert re (regular expressions) module method calls
     /// DEPYLER-STDLIB-RE: Comprehensive regex module support
     ///
@@ -2621,9 +2837,9 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             }
 
             "monotonic" | "perf_counter" => {
-                // time.monotonic() → Instant::now() (returns Instant, need elapsed)
-                // For now, simplified: just generate the call
-                // In real usage, user would call .elapsed() later
+                // time.monotonic() → Instant::now()
+                // Deltas against tracked Instant variables become
+                // start.elapsed().as_secs_f64() in convert_binary
                 parse_quote! { std::time::Instant::now() }
             }
 
@@ -2770,6 +2986,82 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         Ok(Some(result))
     }
 
+    /// Try to convert asyncio module method calls
+    ///
+    /// Maps Python asyncio functions onto tokio:
+    /// - asyncio.sleep(s) → tokio::time::sleep(Duration::from_secs_f64(s))
+    /// - asyncio.gather(...) is lowered in async_gen, which also reads its
+    ///   keyword arguments
+    /// - asyncio.run(coro) → a runtime blocking on `coro` (module-level
+    ///   `asyncio.run(main())` becomes `#[tokio::main]` instead)
+    /// - asyncio.Lock() → tokio::sync::Mutex::new(())
+    /// - asyncio.Queue() → an unbounded tokio mpsc channel (see async_gen)
+    ///
+    /// # Complexity
+    /// 5 (match with 4 branches + fallback)
+    #[inline]
+    fn try_convert_asyncio_method(
+        &mut self,
+        method: &str,
+        args: &[HirExpr],
+    ) -> Result<Option<syn::Expr>> {
+        let arg_exprs: Vec<syn::Expr> = args
+            .iter()
+            .map(|arg| arg.to_rust_expr(self.ctx))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = match method {
+            "sleep" => {
+                if arg_exprs.len() != 1 {
+                    bail!("asyncio.sleep() requires exactly 1 argument (seconds)");
+                }
+                let seconds = &arg_exprs[0];
+                parse_quote! {
+                    tokio::time::sleep(std::time::Duration::from_secs_f64((#seconds) as f64))
+                }
+            }
+
+            "run" => {
+                if arg_exprs.len() != 1 {
+                    bail!("asyncio.run() requires exactly 1 argument (coroutine)");
+                }
+                let coro = &arg_exprs[0];
+                parse_quote! {
+                    tokio::runtime::Runtime::new()
+                        .expect("failed to start the tokio runtime")
+                        .block_on(#coro)
+                }
+            }
+
+            "Lock" => parse_quote! { tokio::sync::Mutex::new(()) },
+
+            "Queue" => {
+                if !arg_exprs.is_empty() {
+                    bail!("asyncio.Queue(maxsize) is not supported; only unbounded queues map to tokio mpsc");
+                }
+                parse_quote! { tokio::sync::mpsc::unbounded_channel() }
+            }
+
+            _ => {
+                bail!("asyncio.{} not implemented yet", method);
+            }
+        };
+
+        Ok(Some(result))
+    }
+
+    /// Try to convert aiohttp module calls
+    ///
+    /// A `ClientSession()` is a `reqwest::Client`; its requests, responses
+    /// and `async with` blocks are lowered in async_gen.
+    #[inline]
+    fn try_convert_aiohttp_method(&mut self, method: &str) -> Result<Option<syn::Expr>> {
+        match method {
+            "ClientSession" => Ok(Some(parse_quote! { reqwest::Client::new() })),
+            _ => bail!("aiohttp.{} not implemented yet", method),
+        }
+    }
+
     /// Try to convert csv module method calls
     /// DEPYLER-STDLIB-CSV: CSV file reading and writing
     ///
@@ -4781,12 +5073,13 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     bail!("heapq.heappop() requires at least 1 argument");
                 }
                 let heap = &arg_exprs[0];
+                let empty = BuiltinError::HeappopFromEmptyHeap.text(self.ctx);
 
                 parse_quote! {
                     {
                         let heap = &mut (#heap);
                         if heap.is_empty() {
-                            panic!("heappop from empty heap");
+                            panic!(#empty);
                         }
 
                         let result = heap[0].clone();
@@ -5219,11 +5512,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     /// Try to convert warnings module method calls
     /// DEPYLER-STDLIB-WARNINGS: Warning control
     ///
-    /// Supports: warn
-    /// Maps to Rust's eprintln! macro for stderr output
+    /// Supports: warn, simplefilter, filterwarnings, resetwarnings
+    /// warn() maps to `eprintln!("UserWarning: {}")`, or to `log::warn!`
+    /// when `CodeGenOptions::warnings_via_log` is set; filters are no-ops
     ///
     /// # Complexity
-    /// Cyclomatic: 2 (match with 1 function + default)
+    /// Cyclomatic: 5 (match with 3 arms + category + log option)
     #[inline]
     fn try_convert_warnings_method(
         &mut self,
@@ -5243,13 +5537,117 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 }
                 let message = &arg_exprs[0];
 
+                // Python prints "<category>: <message>", category defaults to UserWarning
+                let category = match args.get(1) {
+                    Some(HirExpr::Var(name)) => name.clone(),
+                    Some(HirExpr::Attribute { attr, .. }) => attr.clone(),
+                    _ => "UserWarning".to_string(),
+                };
+
+                if self.ctx.options.warnings_via_log {
+                    parse_quote! {
+                        log::warn!("{}: {}", #category, #message)
+                    }
+                } else {
+                    parse_quote! {
+                        eprintln!("{}: {}", #category, #message)
+                    }
+                }
+            }
+
+            // Filter configuration has no runtime equivalent
+            "simplefilter" | "filterwarnings" | "resetwarnings" => parse_quote! { () },
+
+            _ => {
+                bail!(
+                    "warnings.{} not implemented yet (available: warn, simplefilter, filterwarnings, resetwarnings)",
+                    method
+                );
+            }
+        };
+
+        Ok(Some(result))
+    }
+
+    /// Try to convert traceback module method calls
+    /// DEPYLER-STDLIB-TRACEBACK: Exception formatting
+    ///
+    /// Supports: format_exc, print_exc
+    /// The handler must bind the caught error, which only `except E as e:`
+    /// around a lone `return int(s)` does. The text is then
+    /// `<error type>: <message>`, preceded by the messages of its `source()`
+    /// chain the way Python prints a chained exception, root cause first; the
+    /// causes are `dyn Error`, so their type names are not known. In other
+    /// handlers the message is not at hand, so the call is rejected rather
+    /// than printing the type alone.
+    ///
+    /// # Complexity
+    /// Cyclomatic: 4 (match with 2 functions + binding check + default)
+    #[inline]
+    fn try_convert_traceback_method(
+        &mut self,
+        method: &str,
+        _args: &[HirExpr],
+    ) -> Result<Option<syn::Expr>> {
+        let text: syn::Expr = match &self.ctx.current_handler {
+            Some(HandlerBinding {
+                name: Some(name),
+                bound_error: Some(bound_error),
+                ..
+            }) => {
+                let err = crate::rust_gen::keywords::safe_ident(name);
+                // Native messages already carry a terse prefix
+                let last = if self.ctx.options.native_error_messages {
+                    "{}\n".to_string()
+                } else {
+                    format!("{}: {{}}\n", bound_error)
+                };
                 parse_quote! {
-                    eprintln!("Warning: {}", #message)
+                    {
+                        let mut causes = Vec::new();
+                        let mut source = std::error::Error::source(&#err);
+                        while let Some(cause) = source {
+                            causes.push(cause.to_string());
+                            source = cause.source();
+                        }
+                        let mut text: String = causes
+                            .iter()
+                            .rev()
+                            .map(|cause| format!(
+                                "{}\n\nThe above exception was the direct cause of the following exception:\n\n",
+                                cause
+                            ))
+                            .collect();
+                        text.push_str(&format!(#last, #err));
+                        text
+                    }
                 }
             }
+            Some(handler) => {
+                let clause = match (&handler.exception_type, &handler.name) {
+                    (Some(ty), Some(name)) => format!("except {} as {}", ty, name),
+                    (Some(ty), None) => format!("except {}", ty),
+                    (None, _) => "except".to_string(),
+                };
+                bail!(
+                    "traceback.{}() in `{}:` is not supported: the caught error is only bound \
+                     around a lone `return int(...)`",
+                    method,
+                    clause
+                );
+            }
+            // Python returns "NoneType: None" when no exception is being handled
+            _ => parse_quote! { "NoneType: None\n".to_string() },
+        };
 
+        let result = match method {
+            "format_exc" => text,
+            "print_exc" => parse_quote! { eprint!("{}", #text) },
             _ => {
-                bail!("warnings.{} not implemented yet (available: warn)", method);
+                bail!(
+                    "traceback.{} not implemented yet (available: format_exc, print_exc)",
+                    method
+                );
             }
         };
 
@@ -5983,7 +6381,10 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 parse_quote! {
                     {
                         let mut sorted = #data.clone();
-                        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                        sorted.sort_by(|a, b| {
+                            a.partial_cmp(b)
+                                .unwrap_or_else(|| a.partial_cmp(a).is_none().cmp(&b.partial_cmp(b).is_none()))
+                        });
                         let len = sorted.len();
                         if len % 2 == 0 {
                             let mid = len / 2;
@@ -6152,7 +6553,10 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 parse_quote! {
                     {
                         let mut sorted = #data.clone();
-                        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                        sorted.sort_by(|a, b| {
+                            a.partial_cmp(b)
+                                .unwrap_or_else(|| a.partial_cmp(a).is_none().cmp(&b.partial_cmp(b).is_none()))
+                        });
                         let n = #n as usize;
                         let mut result = Vec::new();
                         for i in 1..n {
@@ -6964,6 +7368,24 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 return self.try_convert_time_method(method, args);
             }
 
+            if module_name == "asyncio" {
+                return self.try_convert_asyncio_method(method, args);
+            }
+
+            if module_name == "aiohttp" {
+                return self.try_convert_aiohttp_method(method);
+            }
+
+            // timeit.default_timer() is time.perf_counter()
+            if module_name == "timeit" && method == "default_timer" {
+                return self.try_convert_time_method("perf_counter", args);
+            }
+
+            // threading.Lock() → Mutex<()>, acquired by `with lock:`
+            if module_name == "threading" && method == "Lock" && args.is_empty() {
+                return Ok(Some(parse_quote! { std::sync::Mutex::new(()) }));
+            }
+
             // DEPYLER-STDLIB-CSV: CSV file operations
             if module_name == "csv" {
                 return self.try_convert_csv_method(method, args);
@@ -7055,6 +7477,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 return self.try_convert_warnings_method(method, args);
             }
 
+            // DEPYLER-STDLIB-TRACEBACK: Exception formatting in handlers
+            if module_name == "traceback" {
+                return self.try_convert_traceback_method(method, args);
+            }
+
             // DEPYLER-STDLIB-SYS: System-specific parameters and functions
             if module_name == "sys" {
                 return self.try_convert_sys_method(method, args);
@@ -7142,19 +7569,61 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     // DEPYLER-0142 Phase 2: Category Handlers
     // ========================================================================
 
-    /// Handle list methods (append, extend, pop, insert, remove)
-    #[inline]
-    fn convert_list_method(
+    /// Handle the methods of a list generated as a `VecDeque` that differ
+    /// from `Vec` ones, see [`crate::list_usage`]
+    fn convert_deque_method(
         &mut self,
-        object_expr: &syn::Expr,
         object: &HirExpr,
         method: &str,
-        arg_exprs: &[syn::Expr],
-        hir_args: &[HirExpr],
-    ) -> Result<syn::Expr> {
-        match method {
-            "append" => {
-                if arg_exprs.len() != 1 {
+        args: &[HirExpr],
+    ) -> Result<Option<syn::Expr>> {
+        let is_front = |arg: &HirExpr| matches!(arg, HirExpr::Literal(Literal::Int(0)));
+        let object_expr = object.to_rust_expr(self.ctx)?;
+        Ok(Some(match (method, args) {
+            ("append", [value]) => {
+                let value_expr = value.to_rust_expr(self.ctx)?;
+                parse_quote! { #object_expr.push_back(#value_expr) }
+            }
+            // Only an empty deque has nothing to pop at either end
+            ("pop", []) => self.unwrap_lookup(
+                parse_quote! { #object_expr.pop_back() },
+                "IndexError",
+                BuiltinError::PopFromEmptyList,
+            ),
+            ("pop", [index]) if is_front(index) => self.unwrap_lookup(
+                parse_quote! { #object_expr.pop_front() },
+                "IndexError",
+                BuiltinError::PopFromEmptyList,
+            ),
+            ("pop", [index]) => {
+                let index_expr = index.to_rust_expr(self.ctx)?;
+                error_message_gen::expect_some(
+                    parse_quote! { #object_expr.remove(#index_expr as usize) },
+                    BuiltinError::PopIndexOutOfRange,
+                    self.ctx,
+                )
+            }
+            ("insert", [index, value]) if is_front(index) => {
+                let value_expr = value.to_rust_expr(self.ctx)?;
+                parse_quote! { #object_expr.push_front(#value_expr) }
+            }
+            _ => return Ok(None),
+        }))
+    }
+
+    /// Handle list methods (append, extend, pop, insert, remove)
+    #[inline]
+    fn convert_list_method(
+        &mut self,
+        object_expr: &syn::Expr,
+        object: &HirExpr,
+        method: &str,
+        arg_exprs: &[syn::Expr],
+        hir_args: &[HirExpr],
+    ) -> Result<syn::Expr> {
+        match method {
+            "append" => {
+                if arg_exprs.len() != 1 {
                     bail!("append() requires exactly one argument");
                 }
                 let arg = &arg_exprs[0];
@@ -7204,12 +7673,17 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     if !arg_exprs.is_empty() {
                         bail!("pop() takes no arguments for sets");
                     }
-                    Ok(parse_quote! {
+                    let popped = parse_quote! {
                         #object_expr.iter().next().cloned().map(|x| {
                             #object_expr.remove(&x);
                             x
-                        }).expect("pop from empty set")
-                    })
+                        })
+                    };
+                    Ok(error_message_gen::expect_some(
+                        popped,
+                        BuiltinError::PopFromEmptySet,
+                        self.ctx,
+                    ))
                 } else if self.is_dict_expr(object) {
                     // Dict literal - pop(key) with 1 argument
                     if arg_exprs.len() != 1 {
@@ -7222,18 +7696,23 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                             hir_args[0],
                             HirExpr::Literal(crate::hir::Literal::String(_)) | HirExpr::Var(_)
                         );
-                    if needs_ref {
-                        Ok(
-                            parse_quote! { #object_expr.remove(&#key).expect("KeyError: key not found") },
-                        )
+                    let removed = if needs_ref {
+                        parse_quote! { #object_expr.remove(&#key) }
                     } else {
-                        Ok(
-                            parse_quote! { #object_expr.remove(#key).expect("KeyError: key not found") },
-                        )
-                    }
+                        parse_quote! { #object_expr.remove(#key) }
+                    };
+                    Ok(error_message_gen::expect_some(
+                        removed,
+                        BuiltinError::MissingKey(&hir_args[0], key),
+                        self.ctx,
+                    ))
                 } else if arg_exprs.is_empty() {
                     // List.pop() with no arguments - remove last element
-                    Ok(parse_quote! { #object_expr.pop().unwrap_or_default() })
+                    Ok(self.unwrap_lookup(
+                        parse_quote! { #object_expr.pop() },
+                        "IndexError",
+                        BuiltinError::PopFromEmptyList,
+                    ))
                 } else {
                     // 1 argument: could be list.pop(index) OR dict.pop(key)
                     // Use multiple heuristics to disambiguate:
@@ -7260,15 +7739,16 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                                 hir_args[0],
                                 HirExpr::Literal(crate::hir::Literal::String(_)) | HirExpr::Var(_)
                             );
-                        if needs_ref {
-                            Ok(
-                                parse_quote! { #object_expr.remove(&#arg).expect("KeyError: key not found") },
-                            )
+                        let removed = if needs_ref {
+                            parse_quote! { #object_expr.remove(&#arg) }
                         } else {
-                            Ok(
-                                parse_quote! { #object_expr.remove(#arg).expect("KeyError: key not found") },
-                            )
-                        }
+                            parse_quote! { #object_expr.remove(#arg) }
+                        };
+                        Ok(error_message_gen::expect_some(
+                            removed,
+                            BuiltinError::MissingKey(&hir_args[0], arg),
+                            self.ctx,
+                        ))
                     }
                 }
             }
@@ -7286,9 +7766,13 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 }
                 let value = &arg_exprs[0];
                 if self.is_set_expr(object) {
+                    let missing = error_message_gen::panic_with(
+                        BuiltinError::MissingElement(&hir_args[0], value),
+                        self.ctx,
+                    );
                     Ok(parse_quote! {
                         if !#object_expr.remove(&#value) {
-                            panic!("KeyError: element not in set");
+                            #missing;
                         }
                     })
                 } else {
@@ -7308,12 +7792,16 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     bail!("index() requires exactly one argument");
                 }
                 let value = &arg_exprs[0];
-                Ok(parse_quote! {
+                let position = parse_quote! {
                     #object_expr.iter()
                         .position(|x| x == &#value)
                         .map(|i| i as i32)
-                        .expect("ValueError: value is not in list")
-                })
+                };
+                Ok(error_message_gen::expect_some(
+                    position,
+                    BuiltinError::NotInList(&hir_args[0], value),
+                    self.ctx,
+                ))
             }
             "count" => {
                 // Python: list.count(value) -> counts occurrences
@@ -7360,11 +7848,15 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             }
             "sort" => {
                 // Python: list.sort() -> sorts in place
-                // Rust: list.sort()
+                // Rust: list.sort(), or sort_by for floats and optionals
                 if !arg_exprs.is_empty() {
                     bail!("sort() takes no arguments");
                 }
-                Ok(parse_quote! { #object_expr.sort() })
+                Ok(sort_gen::codegen_sort_in_place(
+                    object,
+                    object_expr,
+                    self.ctx,
+                ))
             }
             _ => bail!("Unknown list method: {}", method),
         }
@@ -7379,6 +7871,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         arg_exprs: &[syn::Expr],
         hir_args: &[HirExpr],
     ) -> Result<syn::Expr> {
+        if matches!(method, "keys" | "values" | "items" | "popitem") {
+            self.ctx.divergences.record(
+                DivergenceKind::DictOrdering,
+                format!("`dict.{}()` follows hash order, not insertion order", method),
+            );
+        }
         match method {
             "get" => {
                 if arg_exprs.len() == 1 {
@@ -7477,10 +7975,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 if !arg_exprs.is_empty() {
                     bail!("popitem() takes no arguments");
                 }
+                let empty = BuiltinError::PopitemFromEmptyDict.text(self.ctx);
                 Ok(parse_quote! {
                     {
                         let key = #object_expr.keys().next().cloned()
-                            .expect("KeyError: popitem(): dictionary is empty");
+                            .expect(#empty);
                         let value = #object_expr.remove(&key)
                             .expect("KeyError: key disappeared");
                         (key, value)
@@ -7501,9 +8000,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                         #object_expr.remove(#key).unwrap_or(#default)
                     })
                 } else {
-                    Ok(parse_quote! {
-                        #object_expr.remove(#key).expect("KeyError: key not found")
-                    })
+                    Ok(error_message_gen::expect_some(
+                        parse_quote! { #object_expr.remove(#key) },
+                        BuiltinError::MissingKey(&hir_args[0], key),
+                        self.ctx,
+                    ))
                 }
             }
             // DEPYLER-STDLIB-50: clear() - remove all items
@@ -7745,10 +8246,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     HirExpr::Literal(Literal::String(s)) => parse_quote! { #s },
                     _ => arg_exprs[0].clone(),
                 };
+                let not_found = BuiltinError::SubstringNotFound.text(self.ctx);
                 Ok(parse_quote! {
                     #object_expr.find(#substring)
                         .map(|i| i as i32)
-                        .expect("substring not found")
+                        .expect(#not_found)
                 })
             }
 
@@ -7777,10 +8279,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     HirExpr::Literal(Literal::String(s)) => parse_quote! { #s },
                     _ => arg_exprs[0].clone(),
                 };
+                let not_found = BuiltinError::SubstringNotFound.text(self.ctx);
                 Ok(parse_quote! {
                     #object_expr.rfind(#substring)
                         .map(|i| i as i32)
-                        .expect("substring not found")
+                        .expect(#not_found)
                 })
             }
 
@@ -7998,6 +8501,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         object_expr: &syn::Expr,
         method: &str,
         arg_exprs: &[syn::Expr],
+        hir_args: &[HirExpr],
     ) -> Result<syn::Expr> {
         match method {
             "add" => {
@@ -8013,9 +8517,13 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                     bail!("remove() requires exactly one argument");
                 }
                 let arg = &arg_exprs[0];
+                let missing = error_message_gen::panic_with(
+                    BuiltinError::MissingElement(&hir_args[0], arg),
+                    self.ctx,
+                );
                 Ok(parse_quote! {
                     if !#object_expr.remove(&#arg) {
-                        panic!("KeyError: element not in set")
+                        #missing
                     }
                 })
             }
@@ -8229,7 +8737,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 | "issubset"
                 | "issuperset"
                 | "isdisjoint" => {
-                    return self.convert_set_method(object_expr, method, arg_exprs);
+                    return self.convert_set_method(object_expr, method, arg_exprs, hir_args);
                 }
                 _ => {}
             }
@@ -8272,7 +8780,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 // Check if argument is a set or dict literal
                 if !hir_args.is_empty() && self.is_set_expr(&hir_args[0]) {
                     // numbers.update({3, 4}) - set update
-                    self.convert_set_method(object_expr, method, arg_exprs)
+                    self.convert_set_method(object_expr, method, arg_exprs, hir_args)
                 } else {
                     // data.update({"b": 2}) - dict update (default for variables)
                     self.convert_dict_method(object_expr, method, arg_exprs, hir_args)
@@ -8308,7 +8816,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             | "symmetric_difference"
             | "issubset"
             | "issuperset"
-            | "isdisjoint" => self.convert_set_method(object_expr, method, arg_exprs),
+            | "isdisjoint" => self.convert_set_method(object_expr, method, arg_exprs, hir_args),
 
             // Regex methods
             "findall" => self.convert_regex_method(object_expr, method, arg_exprs),
@@ -8351,18 +8859,90 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             return Ok(result);
         }
 
-        let object_expr = object.to_rust_expr(self.ctx)?;
-        let arg_exprs: Vec<syn::Expr> = args
+        // sys.stdout.write(text), sys.stdin.readline() and the like
+        if let Some(expr) = io_gen::try_codegen_stream_method(object, method, args, self.ctx)? {
+            return Ok(expr);
+        }
+
+        // contextlib: stack.callback(f, *args) registers a boxed cleanup closure
+        if method == "callback" {
+            if let HirExpr::Var(name) = object {
+                if matches!(self.ctx.var_types.get(name), Some(Type::Custom(ty)) if ty == EXIT_STACK_TYPE)
+                {
+                    let stack = object.to_rust_expr(self.ctx)?;
+                    return codegen_exit_stack_callback(&stack, args, self.ctx);
+                }
+            }
+        }
+
+        // Files bound by `with open(...)` read and write through std::io
+        if let HirExpr::Var(name) = object {
+            if matches!(self.ctx.var_types.get(name), Some(Type::Custom(ty)) if ty == FILE_TYPE) {
+                if let Some(expr) = codegen_file_method(name, method, args, self.ctx)? {
+                    return Ok(expr);
+                }
+            }
+        }
+
+        // asyncio.Queue: put_nowait() outside an await
+        if let Some(expr) =
+            async_gen::try_codegen_queue_method(object, method, args, false, self.ctx)?
+        {
+            return Ok(expr);
+        }
+
+        // asyncio.TaskGroup: tg.create_task(coro) and task.result()
+        if let Some(expr) = async_gen::try_codegen_task_method(object, method, args, self.ctx)? {
+            return Ok(expr);
+        }
+
+        // Methods of arena classes run on the instance in the arena
+        if let Some(expr) = arena_gen::try_codegen_method_call(object, method, args, self.ctx)? {
+            return Ok(expr);
+        }
+
+        // Lists used as queues are `VecDeque`s
+        if matches!(object, HirExpr::Var(name) if self.ctx.deque_vars.contains(name)) {
+            if let Some(expr) = self.convert_deque_method(object, method, args)? {
+                return Ok(expr);
+            }
+        }
+
+        // Lists used for membership tests are `HashSet`s
+        if let (HirExpr::Var(name), "append", [value]) = (object, method, args) {
+            if self.ctx.set_vars.contains(name) {
+                let object_expr = object.to_rust_expr(self.ctx)?;
+                let value_expr = value.to_rust_expr(self.ctx)?;
+                return Ok(parse_quote! { #object_expr.insert(#value_expr) });
+            }
+        }
+
+        // A field of an arena instance is worked on where it lives
+        let Some((mut setup, field)) = arena_gen::field_place_mut(object, self.ctx)? else {
+            let object_expr = object.to_rust_expr(self.ctx)?;
+            let arg_exprs: Vec<syn::Expr> = args
+                .iter()
+                .map(|arg| arg.to_rust_expr(self.ctx))
+                .collect::<Result<Vec<_>>>()?;
+
+            // Dispatch to instance method handler
+            return self.convert_instance_method(object, &object_expr, method, &arg_exprs, args);
+        };
+        let arg_exprs = args
             .iter()
             .map(|arg| arg.to_rust_expr(self.ctx))
             .collect::<Result<Vec<_>>>()?;
-
-        // Dispatch to instance method handler
-        self.convert_instance_method(object, &object_expr, method, &arg_exprs, args)
+        let (hoisted, arg_exprs) = arena_gen::hoist_arena_args(arg_exprs, self.ctx);
+        setup.extend(hoisted);
+        let call = self.convert_instance_method(object, &field, method, &arg_exprs, args)?;
+        Ok(arena_gen::with_hoisted(setup, call))
     }
 
     fn convert_index(&mut self, base: &HirExpr, index: &HirExpr) -> Result<syn::Expr> {
-        let base_expr = base.to_rust_expr(self.ctx)?;
+        let base_expr = match arena_gen::field_place(base, self.ctx)? {
+            Some(field) => field,
+            None => base.to_rust_expr(self.ctx)?,
+        };
 
         // DEPYLER-0307 Fix #9: Handle tuple indexing with integer literals
         // Python: tuple[0], tuple[1] → Rust: tuple.0, tuple.1
@@ -8395,6 +8975,17 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             }
         }
 
+        // A base known to be a `str` is indexed by character, whatever its name
+        if self.ctx.value_type(base) == Type::String {
+            let index_expr = index.to_rust_expr(self.ctx)?;
+            return Ok(slice_gen::codegen_str_index(
+                &base_expr,
+                index,
+                &index_expr,
+                self.ctx,
+            ));
+        }
+
         // DEPYLER-0299 Pattern #3 FIX: Check if base is a String type for character access
         let is_string_base = self.is_string_base(base);
 
@@ -8406,39 +8997,34 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             match index {
                 HirExpr::Literal(Literal::String(s)) => {
                     // String literal - use it directly without .to_string()
-                    Ok(parse_quote! {
-                        #base_expr.get(#s).cloned().unwrap_or_default()
-                    })
+                    let key: syn::Expr = parse_quote! { #s };
+                    Ok(self.unwrap_lookup(
+                        parse_quote! { #base_expr.get(#s).cloned() },
+                        "KeyError",
+                        BuiltinError::MissingKey(index, &key),
+                    ))
                 }
                 _ => {
                     // String variable - needs proper referencing
                     // HashMap.get() expects &K, so we need to borrow the key
                     let index_expr = index.to_rust_expr(self.ctx)?;
-                    Ok(parse_quote! {
-                        #base_expr.get(&#index_expr).cloned().unwrap_or_default()
-                    })
+                    Ok(self.unwrap_lookup(
+                        parse_quote! { #base_expr.get(&#index_expr).cloned() },
+                        "KeyError",
+                        BuiltinError::MissingKey(index, &index_expr),
+                    ))
                 }
             }
         } else if is_string_base {
             // DEPYLER-0299 Pattern #3: String character access with numeric index
             // Strings cannot use .get(usize), must use .chars().nth()
             let index_expr = index.to_rust_expr(self.ctx)?;
-
-            // DEPYLER-0267 FIX: Use .chars().nth() for proper character access
-            // This returns Option<char>, then convert to String
-            Ok(parse_quote! {
-                {
-                    // DEPYLER-0307 Fix #11: Use borrow to avoid moving the base expression
-                    let base = &#base_expr;
-                    let idx: i32 = #index_expr;
-                    let actual_idx = if idx < 0 {
-                        base.chars().count().saturating_sub(idx.abs() as usize)
-                    } else {
-                        idx as usize
-                    };
-                    base.chars().nth(actual_idx).map(|c| c.to_string()).unwrap_or_default()
-                }
-            })
+            Ok(slice_gen::codegen_str_index(
+                &base_expr,
+                index,
+                &index_expr,
+                self.ctx,
+            ))
         } else {
             // Vec/List access with numeric index
             let index_expr = index.to_rust_expr(self.ctx)?;
@@ -8452,12 +9038,19 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 if let HirExpr::Literal(Literal::Int(n)) = **operand {
                     // Negative index literal: arr[-1] → arr.get(arr.len() - 1)
                     let offset = n as usize;
+                    // DEPYLER-0267: Use .cloned() instead of .copied() for non-Copy types (String, Vec, etc.)
+                    let item = self.unwrap_lookup(
+                        parse_quote! {
+                            base.len().checked_sub(#offset).and_then(|i| base.get(i)).cloned()
+                        },
+                        "IndexError",
+                        BuiltinError::ListIndexOutOfRange,
+                    );
                     return Ok(parse_quote! {
                         {
                             // DEPYLER-0307 Fix #11: Use borrow to avoid moving the base expression
                             let base = &#base_expr;
-                            // DEPYLER-0267: Use .cloned() instead of .copied() for non-Copy types (String, Vec, etc.)
-                            base.get(base.len().saturating_sub(#offset)).cloned().unwrap_or_default()
+                            #item
                         }
                     });
                 }
@@ -8468,9 +9061,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             // This avoids unnecessary temporary variables and runtime checks
             if let HirExpr::Literal(Literal::Int(n)) = index {
                 let idx_value = *n as usize;
-                return Ok(parse_quote! {
-                    #base_expr.get(#idx_value).cloned().unwrap_or_default()
-                });
+                return Ok(self.unwrap_lookup(
+                    parse_quote! { #base_expr.get(#idx_value).cloned() },
+                    "IndexError",
+                    BuiltinError::ListIndexOutOfRange,
+                ));
             }
 
             // DEPYLER-0306 FIX: Check if index is a simple variable (not a complex expression)
@@ -8481,12 +9076,20 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             if is_simple_var {
                 // Simple variable index - use inline expression (works in range contexts)
                 // This avoids block expressions that break in `for j in 0..matrix[i].len()`
-                Ok(parse_quote! {
-                    #base_expr.get(#index_expr as usize).cloned().unwrap_or_default()
-                })
+                Ok(self.unwrap_lookup(
+                    parse_quote! { #base_expr.get(#index_expr as usize).cloned() },
+                    "IndexError",
+                    BuiltinError::ListIndexOutOfRange,
+                ))
             } else {
                 // Complex expression - use block with full negative index handling
                 // DEPYLER-0288: Explicitly type idx as i32 to support negation
+                // DEPYLER-0267: Use .cloned() instead of .copied() for non-Copy types (String, Vec, etc.)
+                let item = self.unwrap_lookup(
+                    parse_quote! { actual_idx.and_then(|i| base.get(i)).cloned() },
+                    "IndexError",
+                    BuiltinError::ListIndexOutOfRange,
+                );
                 Ok(parse_quote! {
                     {
                         // DEPYLER-0307 Fix #11: Use borrow to avoid moving the base expression
@@ -8494,18 +9097,33 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                         let idx: i32 = #index_expr;
                         let actual_idx = if idx < 0 {
                             // Use .abs() instead of negation to avoid "Neg not implemented for usize" error
-                            base.len().saturating_sub(idx.abs() as usize)
+                            base.len().checked_sub(idx.abs() as usize)
                         } else {
-                            idx as usize
+                            Some(idx as usize)
                         };
-                        // DEPYLER-0267: Use .cloned() instead of .copied() for non-Copy types (String, Vec, etc.)
-                        base.get(actual_idx).cloned().unwrap_or_default()
+                        #item
                     }
                 })
             }
         }
     }
 
+    /// `lookup` unwrapped, failing with `error` unless a handler catches
+    /// `exception`
+    fn unwrap_lookup(
+        &mut self,
+        lookup: syn::Expr,
+        exception: &str,
+        error: BuiltinError,
+    ) -> syn::Expr {
+        if self.ctx.is_exception_handled(exception) || self.ctx.is_exception_handled("Exception") {
+            // The `try` lowering replaces the default with the handler's value
+            parse_quote! { #lookup.unwrap_or_default() }
+        } else {
+            error_message_gen::expect_some(lookup, error, self.ctx)
+        }
+    }
+
     /// Check if the index expression is a string key (for HashMap access)
     /// Returns true if: index is string literal, OR base is Dict/HashMap type
     fn is_string_index(&self, base: &HirExpr, index: &HirExpr) -> Result<bool> {
@@ -8655,7 +9273,8 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         let base_expr = base.to_rust_expr(self.ctx)?;
 
         // DEPYLER-0302 Phase 3: Check if we're slicing a string
-        let is_string = self.is_string_base(base);
+        let is_string =
+            self.is_string_base(base) || self.ctx.value_type(base) == Type::String;
 
         // Convert slice parameters
         let start_expr = if let Some(s) = start {
@@ -8681,177 +9300,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             return self.convert_string_slice(base_expr, start_expr, stop_expr, step_expr);
         }
 
-        // Generate slice code based on the parameters (for Vec/List)
-        match (start_expr, stop_expr, step_expr) {
-            // Full slice with step: base[::step]
-            (None, None, Some(step)) => {
-                Ok(parse_quote! {
-                    {
-                        let base = #base_expr;
-                        let step = #step;
-                        if step == 1 {
-                            base.clone()
-                        } else if step > 0 {
-                            base.iter().step_by(step as usize).cloned().collect::<Vec<_>>()
-                        } else if step == -1 {
-                            base.iter().rev().cloned().collect::<Vec<_>>()
-                        } else {
-                            // Negative step with abs value
-                            let abs_step = (-step) as usize;
-                            base.iter().rev().step_by(abs_step).cloned().collect::<Vec<_>>()
-                        }
-                    }
-                })
-            }
-
-            // Start and stop: base[start:stop]
-            (Some(start), Some(stop), None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start = (#start).max(0) as usize;
-                    let stop = (#stop).max(0) as usize;
-                    if start < base.len() {
-                        base[start..stop.min(base.len())].to_vec()
-                    } else {
-                        Vec::new()
-                    }
-                }
-            }),
-
-            // Start only: base[start:]
-            (Some(start), None, None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start = (#start).max(0) as usize;
-                    if start < base.len() {
-                        base[start..].to_vec()
-                    } else {
-                        Vec::new()
-                    }
-                }
-            }),
-
-            // Stop only: base[:stop]
-            (None, Some(stop), None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let stop = (#stop).max(0) as usize;
-                    base[..stop.min(base.len())].to_vec()
-                }
-            }),
-
-            // Full slice: base[:]
-            (None, None, None) => Ok(parse_quote! { #base_expr.clone() }),
-
-            // Start, stop, and step: base[start:stop:step]
-            (Some(start), Some(stop), Some(step)) => {
-                Ok(parse_quote! {
-                    {
-                        let base = #base_expr;
-                        let start = (#start).max(0) as usize;
-                        let stop = (#stop).max(0) as usize;
-                        let step = #step;
-
-                        if step == 1 {
-                            if start < base.len() {
-                                base[start..stop.min(base.len())].to_vec()
-                            } else {
-                                Vec::new()
-                            }
-                        } else if step > 0 {
-                            base[start..stop.min(base.len())]
-                                .iter()
-                                .step_by(step as usize)
-                                .cloned()
-                                .collect::<Vec<_>>()
-                        } else {
-                            // Negative step - slice in reverse
-                            let abs_step = (-step) as usize;
-                            if start < base.len() {
-                                base[start..stop.min(base.len())]
-                                    .iter()
-                                    .rev()
-                                    .step_by(abs_step)
-                                    .cloned()
-                                    .collect::<Vec<_>>()
-                            } else {
-                                Vec::new()
-                            }
-                        }
-                    }
-                })
-            }
-
-            // Start and step: base[start::step]
-            (Some(start), None, Some(step)) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start = (#start).max(0) as usize;
-                    let step = #step;
-
-                    if start < base.len() {
-                        if step == 1 {
-                            base[start..].to_vec()
-                        } else if step > 0 {
-                            base[start..]
-                                .iter()
-                                .step_by(step as usize)
-                                .cloned()
-                                .collect::<Vec<_>>()
-                        } else if step == -1 {
-                            base[start..]
-                                .iter()
-                                .rev()
-                                .cloned()
-                                .collect::<Vec<_>>()
-                        } else {
-                            let abs_step = (-step) as usize;
-                            base[start..]
-                                .iter()
-                                .rev()
-                                .step_by(abs_step)
-                                .cloned()
-                                .collect::<Vec<_>>()
-                        }
-                    } else {
-                        Vec::new()
-                    }
-                }
-            }),
-
-            // Stop and step: base[:stop:step]
-            (None, Some(stop), Some(step)) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let stop = (#stop).max(0) as usize;
-                    let step = #step;
-
-                    if step == 1 {
-                        base[..stop.min(base.len())].to_vec()
-                    } else if step > 0 {
-                        base[..stop.min(base.len())]
-                            .iter()
-                            .step_by(step as usize)
-                            .cloned()
-                            .collect::<Vec<_>>()
-                    } else if step == -1 {
-                        base[..stop.min(base.len())]
-                            .iter()
-                            .rev()
-                            .cloned()
-                            .collect::<Vec<_>>()
-                    } else {
-                        let abs_step = (-step) as usize;
-                        base[..stop.min(base.len())]
-                            .iter()
-                            .rev()
-                            .step_by(abs_step)
-                            .cloned()
-                            .collect::<Vec<_>>()
-                    }
-                }
-            }),
-        }
+        // Vec/List: Python's index rules, resolved at runtime unless the
+        // bounds are known to be in order
+        Ok(slice_gen::codegen_list_slice(
+            &base_expr, start_expr, stop_expr, step_expr, self.ctx,
+        ))
     }
 
     /// DEPYLER-0302 Phase 3: String-specific slice code generation
@@ -8865,7 +9318,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     ) -> Result<syn::Expr> {
         match (start_expr, stop_expr, step_expr) {
             // Full slice with step: s[::step]
-            (None, None, Some(step)) => {
+            (None, None, Some(step)) if self.ctx.string_indexing == StringIndexing::Chars => {
                 Ok(parse_quote! {
                     {
                         let base = #base_expr;
@@ -8885,180 +9338,22 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 })
             }
 
-            // Start and stop: s[start:stop]
-            (Some(start), Some(stop), None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start_idx: i32 = #start;
-                    let stop_idx: i32 = #stop;
-                    let len = base.chars().count() as i32;
-
-                    // Handle negative indices
-                    let actual_start = if start_idx < 0 {
-                        (len + start_idx).max(0) as usize
-                    } else {
-                        start_idx.min(len) as usize
-                    };
-
-                    let actual_stop = if stop_idx < 0 {
-                        (len + stop_idx).max(0) as usize
-                    } else {
-                        stop_idx.min(len) as usize
-                    };
-
-                    if actual_start < actual_stop {
-                        base.chars().skip(actual_start).take(actual_stop - actual_start).collect::<String>()
-                    } else {
-                        String::new()
-                    }
-                }
-            }),
-
-            // Start only: s[start:]
-            (Some(start), None, None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start_idx: i32 = #start;
-                    let len = base.chars().count() as i32;
-
-                    // Handle negative index for s[-n:]
-                    let actual_start = if start_idx < 0 {
-                        (len + start_idx).max(0) as usize
-                    } else {
-                        start_idx.min(len) as usize
-                    };
-
-                    base.chars().skip(actual_start).collect::<String>()
-                }
-            }),
-
-            // Stop only: s[:stop]
-            (None, Some(stop), None) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let stop_idx: i32 = #stop;
-                    let len = base.chars().count() as i32;
-
-                    // Handle negative index for s[:-n]
-                    let actual_stop = if stop_idx < 0 {
-                        (len + stop_idx).max(0) as usize
-                    } else {
-                        stop_idx.min(len) as usize
-                    };
-
-                    base.chars().take(actual_stop).collect::<String>()
-                }
-            }),
-
             // Full slice: s[:]
             (None, None, None) => Ok(parse_quote! { #base_expr.to_string() }),
 
-            // Start, stop, and step: s[start:stop:step]
-            (Some(start), Some(stop), Some(step)) => {
-                Ok(parse_quote! {
-                    {
-                        let base = #base_expr;
-                        let start_idx: i32 = #start;
-                        let stop_idx: i32 = #stop;
-                        let step: i32 = #step;
-                        let len = base.chars().count() as i32;
-
-                        // Handle negative indices
-                        let actual_start = if start_idx < 0 {
-                            (len + start_idx).max(0) as usize
-                        } else {
-                            start_idx.min(len) as usize
-                        };
+            // Bounds without a step: s[start:stop], s[start:], s[:stop]
+            (start, stop, None) => Ok(slice_gen::codegen_str_range(
+                &base_expr, start, stop, self.ctx,
+            )),
 
-                        let actual_stop = if stop_idx < 0 {
-                            (len + stop_idx).max(0) as usize
-                        } else {
-                            stop_idx.min(len) as usize
-                        };
-
-                        if step == 1 {
-                            if actual_start < actual_stop {
-                                base.chars().skip(actual_start).take(actual_stop - actual_start).collect::<String>()
-                            } else {
-                                String::new()
-                            }
-                        } else if step > 0 {
-                            base.chars()
-                                .skip(actual_start)
-                                .take(actual_stop.saturating_sub(actual_start))
-                                .step_by(step as usize)
-                                .collect::<String>()
-                        } else {
-                            // Negative step - collect range then reverse
-                            let abs_step = step.abs() as usize;
-                            if actual_start < actual_stop {
-                                base.chars()
-                                    .skip(actual_start)
-                                    .take(actual_stop - actual_start)
-                                    .rev()
-                                    .step_by(abs_step)
-                                    .collect::<String>()
-                            } else {
-                                String::new()
-                            }
-                        }
-                    }
-                })
-            }
-
-            // Start and step: s[start::step]
-            (Some(start), None, Some(step)) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let start_idx: i32 = #start;
-                    let step: i32 = #step;
-                    let len = base.chars().count() as i32;
-
-                    let actual_start = if start_idx < 0 {
-                        (len + start_idx).max(0) as usize
-                    } else {
-                        start_idx.min(len) as usize
-                    };
-
-                    if step == 1 {
-                        base.chars().skip(actual_start).collect::<String>()
-                    } else if step > 0 {
-                        base.chars().skip(actual_start).step_by(step as usize).collect::<String>()
-                    } else if step == -1 {
-                        base.chars().skip(actual_start).rev().collect::<String>()
-                    } else {
-                        let abs_step = step.abs() as usize;
-                        base.chars().skip(actual_start).rev().step_by(abs_step).collect::<String>()
-                    }
-                }
-            }),
-
-            // Stop and step: s[:stop:step]
-            (None, Some(stop), Some(step)) => Ok(parse_quote! {
-                {
-                    let base = #base_expr;
-                    let stop_idx: i32 = #stop;
-                    let step: i32 = #step;
-                    let len = base.chars().count() as i32;
-
-                    let actual_stop = if stop_idx < 0 {
-                        (len + stop_idx).max(0) as usize
-                    } else {
-                        stop_idx.min(len) as usize
-                    };
-
-                    if step == 1 {
-                        base.chars().take(actual_stop).collect::<String>()
-                    } else if step > 0 {
-                        base.chars().take(actual_stop).step_by(step as usize).collect::<String>()
-                    } else if step == -1 {
-                        base.chars().take(actual_stop).rev().collect::<String>()
-                    } else {
-                        let abs_step = step.abs() as usize;
-                        base.chars().take(actual_stop).rev().step_by(abs_step).collect::<String>()
-                    }
-                }
-            }),
+            // Stepped with bounds: s[start:stop:step], s[start::step], s[:stop:step]
+            (start, stop, Some(step)) => Ok(slice_gen::codegen_str_slice(
+                &base_expr,
+                start,
+                stop,
+                Some(step),
+                self.ctx,
+            )),
         }
     }
 
@@ -9235,6 +9530,16 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             }
         }
 
+        // aiohttp responses: resp.status
+        if let Some(expr) = async_gen::try_codegen_response_attr(value, attr, self.ctx)? {
+            return Ok(expr);
+        }
+
+        // Fields of arena instances are read out of the arena
+        if let Some(expr) = arena_gen::try_codegen_field_read(value, attr, self.ctx)? {
+            return Ok(expr);
+        }
+
         // Check if this is a module attribute access
         if let HirExpr::Var(module_name) = value {
             // DEPYLER-STDLIB-MATH: Handle math module constants
@@ -9441,6 +9746,18 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         target: &str,
         iter: &HirExpr,
         condition: &Option<Box<HirExpr>>,
+    ) -> Result<syn::Expr> {
+        let chain = self.convert_comprehension_chain(element, target, iter, condition)?;
+        Ok(parse_quote! { #chain.collect::<Vec<_>>() })
+    }
+
+    /// The iterator a single-clause list, set or dict comprehension collects
+    fn convert_comprehension_chain(
+        &mut self,
+        element: &HirExpr,
+        target: &str,
+        iter: &HirExpr,
+        condition: &Option<Box<HirExpr>>,
     ) -> Result<syn::Expr> {
         let target_ident = syn::Ident::new(target, proc_macro2::Span::call_site());
         let iter_expr = iter.to_rust_expr(self.ctx)?;
@@ -9463,13 +9780,12 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
 
             if is_range {
                 // Ranges are already iterators, don't call .iter()
-                // Range items are owned (i32, etc.), filter receives &i32
-                // Use pattern matching |&x| to dereference for the condition
+                // Range items are owned (i32, etc.), filter receives &i32,
+                // which the condition dereferences
                 Ok(parse_quote! {
                     (#iter_expr)
-                        .filter(|&#target_ident| #cond_with_deref)
+                        .filter(|#target_ident| #cond_with_deref)
                         .map(|#target_ident| #element_expr)
-                        .collect::<Vec<_>>()
                 })
             } else {
                 // DEPYLER-0299 Fix: Clone the collection, then use .into_iter()
@@ -9480,7 +9796,6 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                         .into_iter()
                         .filter(|#target_ident| #cond_with_deref)
                         .map(|#target_ident| #element_expr)
-                        .collect::<Vec<_>>()
                 })
             }
         } else {
@@ -9490,7 +9805,6 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 Ok(parse_quote! {
                     (#iter_expr)
                         .map(|#target_ident| #element_expr)
-                        .collect::<Vec<_>>()
                 })
             } else {
                 // DEPYLER-0299 Fix: Clone the collection, then use .into_iter()
@@ -9501,7 +9815,6 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                         .clone()
                         .into_iter()
                         .map(|#target_ident| #element_expr)
-                        .collect::<Vec<_>>()
                 })
             }
         }
@@ -9772,62 +10085,10 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         iter: &HirExpr,
         condition: &Option<Box<HirExpr>>,
     ) -> Result<syn::Expr> {
-        // DEPYLER-0299 Pattern #5 FIX: Proper iterator handling for set comprehensions
-        // Uses the same strategy as list comprehensions:
-        // - Ranges are already iterators, use them directly
-        // - Collections need .iter() to get an iterator
-        // - Use .cloned() to convert &T to T
-        // - Place .cloned() AFTER .filter() so filter sees &T
-        //
-        // Key insight: .filter(|x| ...) receives &Item where Item is the iterator's item type.
-        // So .iter().filter() means x is &&T, but .iter().filter().cloned() means filter gets &T
-        // and then .cloned() converts to T for the next stage.
-
+        // Same iteration as list comprehensions, collected into a HashSet
         self.ctx.needs_hashset = true;
-        let target_ident = syn::Ident::new(target, proc_macro2::Span::call_site());
-        let iter_expr = iter.to_rust_expr(self.ctx)?;
-        let element_expr = element.to_rust_expr(self.ctx)?;
-
-        let is_range = self.is_range_expr(&iter_expr);
-
-        if let Some(cond) = condition {
-            let cond_expr = cond.to_rust_expr(self.ctx)?;
-            if is_range {
-                // Ranges are already iterators, don't call .iter()
-                // Range items are owned (i32, etc.), so no dereference needed
-                Ok(parse_quote! {
-                    (#iter_expr)
-                        .filter(|#target_ident| #cond_expr)
-                        .map(|#target_ident| #element_expr)
-                        .collect::<HashSet<_>>()
-                })
-            } else {
-                // Collections need .iter().filter().cloned().map()
-                // Use |&target| pattern to automatically dereference in filter closure
-                Ok(parse_quote! {
-                    #iter_expr
-                        .iter()
-                        .filter(|&#target_ident| #cond_expr)
-                        .cloned()
-                        .map(|#target_ident| #element_expr)
-                        .collect::<HashSet<_>>()
-                })
-            }
-        } else if is_range {
-            Ok(parse_quote! {
-                (#iter_expr)
-                    .map(|#target_ident| #element_expr)
-                    .collect::<HashSet<_>>()
-            })
-        } else {
-            Ok(parse_quote! {
-                #iter_expr
-                    .iter()
-                    .cloned()
-                    .map(|#target_ident| #element_expr)
-                    .collect::<HashSet<_>>()
-            })
-        }
+        let chain = self.convert_comprehension_chain(element, target, iter, condition)?;
+        Ok(parse_quote! { #chain.collect::<HashSet<_>>() })
     }
 
     fn convert_dict_comp(
@@ -9838,63 +10099,11 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         iter: &HirExpr,
         condition: &Option<Box<HirExpr>>,
     ) -> Result<syn::Expr> {
-        // DEPYLER-0299 Pattern #5 FIX: Proper iterator handling for dict comprehensions
-        // Uses the same strategy as list comprehensions:
-        // - Ranges are already iterators, use them directly
-        // - Collections need .iter() to get an iterator
-        // - Use .cloned() to convert &T to T
-        // - Place .cloned() AFTER .filter() so filter sees &T
-        //
-        // Key insight: .filter(|x| ...) receives &Item where Item is the iterator's item type.
-        // So .iter().filter() means x is &&T, but .iter().filter().cloned() means filter gets &T
-        // and then .cloned() converts to T for the next stage.
-
+        // Same iteration as list comprehensions over (key, value) pairs
         self.ctx.needs_hashmap = true;
-        let target_ident = syn::Ident::new(target, proc_macro2::Span::call_site());
-        let iter_expr = iter.to_rust_expr(self.ctx)?;
-        let key_expr = key.to_rust_expr(self.ctx)?;
-        let value_expr = value.to_rust_expr(self.ctx)?;
-
-        let is_range = self.is_range_expr(&iter_expr);
-
-        if let Some(cond) = condition {
-            let cond_expr = cond.to_rust_expr(self.ctx)?;
-            if is_range {
-                // Ranges are already iterators, don't call .iter()
-                // Range items are owned (i32, etc.), so no dereference needed
-                Ok(parse_quote! {
-                    (#iter_expr)
-                        .filter(|#target_ident| #cond_expr)
-                        .map(|#target_ident| (#key_expr, #value_expr))
-                        .collect::<HashMap<_, _>>()
-                })
-            } else {
-                // Collections need .iter().filter().cloned().map()
-                // Use |&target| pattern to automatically dereference in filter closure
-                Ok(parse_quote! {
-                    #iter_expr
-                        .iter()
-                        .filter(|&#target_ident| #cond_expr)
-                        .cloned()
-                        .map(|#target_ident| (#key_expr, #value_expr))
-                        .collect::<HashMap<_, _>>()
-                })
-            }
-        } else if is_range {
-            Ok(parse_quote! {
-                (#iter_expr)
-                    .map(|#target_ident| (#key_expr, #value_expr))
-                    .collect::<HashMap<_, _>>()
-            })
-        } else {
-            Ok(parse_quote! {
-                #iter_expr
-                    .iter()
-                    .cloned()
-                    .map(|#target_ident| (#key_expr, #value_expr))
-                    .collect::<HashMap<_, _>>()
-            })
-        }
+        let item = HirExpr::Tuple(vec![key.clone(), value.clone()]);
+        let chain = self.convert_comprehension_chain(&item, target, iter, condition)?;
+        Ok(parse_quote! { #chain.collect::<HashMap<_, _>>() })
     }
 
     fn convert_lambda(&mut self, params: &[String], body: &HirExpr) -> Result<syn::Expr> {
@@ -9930,6 +10139,10 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     }
 
     fn convert_await(&mut self, value: &HirExpr) -> Result<syn::Expr> {
+        // Queue, session and response calls complete their own await
+        if let Some(expr) = async_gen::try_codegen_await(value, self.ctx)? {
+            return Ok(expr);
+        }
         let value_expr = value.to_rust_expr(self.ctx)?;
         Ok(parse_quote! { #value_expr.await })
     }
@@ -9954,6 +10167,26 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         }
     }
 
+    /// `template % args`, each argument converted as Python's `%` converts
+    /// a value of its type
+    fn convert_percent_format(&mut self, template: &str, args: &HirExpr) -> Result<syn::Expr> {
+        percent_format_gen::codegen_percent_format(template, args, |arg, conversion| {
+            let arg_expr = arg.to_rust_expr(self.ctx)?;
+            let ty = self.ctx.value_type(arg);
+            let expr = match conversion {
+                // Ints and strings already display as `str()` shows them
+                Conversion::Str if !matches!(ty, Type::Int | Type::String | Type::Unknown) => {
+                    py_str_gen::codegen_str(arg, &arg_expr, self.ctx)
+                }
+                Conversion::Repr if ty != Type::Int => {
+                    py_str_gen::codegen_repr(arg, &arg_expr, self.ctx)
+                }
+                _ => percent_format_gen::numeric_arg(conversion, arg_expr, &ty),
+            };
+            Ok(FieldArg::display(expr))
+        })
+    }
+
     fn convert_fstring(&mut self, parts: &[FStringPart]) -> Result<syn::Expr> {
         // Handle empty f-strings
         if parts.is_empty() {
@@ -9985,6 +10218,7 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
                 }
                 FStringPart::Expr(expr) => {
                     template.push_str("{}");
+                    ledger::record_display(expr, self.ctx);
                     let arg_expr = expr.to_rust_expr(self.ctx)?;
                     args.push(arg_expr);
                 }
@@ -10085,62 +10319,59 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         let iter_expr = iterable.to_rust_expr(self.ctx)?;
 
         // DEPYLER-0307: Check if this is an identity function (lambda x: x)
-        // If so, use simple .sort() instead of .sort_by_key()
+        // If so, sort the elements themselves instead of by key
         let is_identity =
             key_params.len() == 1 && matches!(key_body, HirExpr::Var(v) if v == &key_params[0]);
-
         if is_identity {
-            // Identity function: just sort() + optional reverse()
-            if reverse {
-                return Ok(parse_quote! {
-                    {
-                        let mut __sorted_result = #iter_expr.clone();
-                        __sorted_result.sort();
-                        __sorted_result.reverse();
-                        __sorted_result
-                    }
-                });
-            } else {
-                return Ok(parse_quote! {
-                    {
-                        let mut __sorted_result = #iter_expr.clone();
-                        __sorted_result.sort();
-                        __sorted_result
-                    }
-                });
-            }
+            return Ok(sort_gen::codegen_sorted(
+                iterable, &iter_expr, None, reverse, self.ctx,
+            ));
         }
 
-        // Non-identity key function: use sort_by_key
-        let body_expr = key_body.to_rust_expr(self.ctx)?;
-
-        // Create the closure parameter pattern
-        let param_pat: syn::Pat = if key_params.len() == 1 {
-            let param = syn::Ident::new(&key_params[0], proc_macro2::Span::call_site());
-            parse_quote! { #param }
-        } else {
+        if key_params.len() != 1 {
             bail!("sorted() key lambda must have exactly one parameter");
-        };
-
-        // Generate: { let mut result = iterable.clone(); result.sort_by_key(|param| body); [result.reverse();] result }
-        if reverse {
-            Ok(parse_quote! {
-                {
-                    let mut __sorted_result = #iter_expr.clone();
-                    __sorted_result.sort_by_key(|#param_pat| #body_expr);
-                    __sorted_result.reverse();
-                    __sorted_result
-                }
-            })
-        } else {
-            Ok(parse_quote! {
-                {
-                    let mut __sorted_result = #iter_expr.clone();
-                    __sorted_result.sort_by_key(|#param_pat| #body_expr);
-                    __sorted_result
-                }
-            })
         }
+        let key = sort_gen::SortKey {
+            param: &key_params[0],
+            hir_body: key_body,
+            body: key_body.to_rust_expr(self.ctx)?,
+        };
+        Ok(sort_gen::codegen_sorted(
+            iterable,
+            &iter_expr,
+            Some(key),
+            reverse,
+            self.ctx,
+        ))
+    }
+
+    /// The type of the items a single-clause generator yields, with its
+    /// target typed as an item of its iterable
+    fn generator_item_type(&mut self, generator: &HirExpr) -> Type {
+        let HirExpr::GeneratorExp {
+            element,
+            generators,
+        } = generator
+        else {
+            return Type::Unknown;
+        };
+        let [clause] = generators.as_slice() else {
+            return Type::Unknown;
+        };
+        let item = match &*clause.iter {
+            HirExpr::Call { func, .. } if func == "range" => Type::Int,
+            iter => match self.ctx.value_type(iter) {
+                Type::List(elem) | Type::Set(elem) => *elem,
+                _ => return Type::Unknown,
+            },
+        };
+        let shadowed = self.ctx.var_types.insert(clause.target.clone(), item);
+        let item_type = self.ctx.value_type(element);
+        match shadowed {
+            Some(ty) => self.ctx.var_types.insert(clause.target.clone(), ty),
+            None => self.ctx.var_types.remove(&clause.target),
+        };
+        item_type
     }
 
     fn convert_generator_expression(
@@ -10165,18 +10396,26 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
             // When the iterator is a variable (likely a borrowed parameter like &Vec<i32>),
             // use .iter().copied() to get owned values instead of references
             // This prevents type mismatches like `&i32` vs `i32` in generator expressions
-            let mut chain: syn::Expr = if matches!(&*gen.iter, HirExpr::Var(_)) {
+            let (mut chain, copied): (syn::Expr, bool) = match &*gen.iter {
                 // Variable iteration - likely borrowed, use .iter().copied()
-                parse_quote! { #iter_expr.iter().copied() }
-            } else {
-                // Direct expression (ranges, lists, etc.) - use .into_iter()
-                parse_quote! { #iter_expr.into_iter() }
+                HirExpr::Var(_) => (parse_quote! { #iter_expr.iter().copied() }, true),
+                // A range already iterates over integers
+                HirExpr::Call { func, .. } if func == "range" => {
+                    (parse_quote! { (#iter_expr) }, true)
+                }
+                // Direct expression (lists, etc.) - use .into_iter()
+                _ => (parse_quote! { #iter_expr.into_iter() }, false),
             };
 
-            // Add filters for each condition
+            // Add filters for each condition; `filter` lends each item, which
+            // the pattern copies back out where the items are copies already
             for cond in &gen.conditions {
                 let cond_expr = cond.to_rust_expr(self.ctx)?;
-                chain = parse_quote! { #chain.filter(|#target_pat| #cond_expr) };
+                chain = if copied {
+                    parse_quote! { #chain.filter(|&#target_pat| #cond_expr) }
+                } else {
+                    parse_quote! { #chain.filter(|#target_pat| #cond_expr) }
+                };
             }
 
             // Add the map transformation
@@ -10192,72 +10431,155 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
         self.convert_nested_generators(element, generators)
     }
 
+    /// Several `for` clauses as a chain of `flat_map`s, one per clause
+    ///
+    /// `(x + y for x in xs if x > 0 for y in range(x))` becomes
+    ///
+    /// ```text
+    /// xs.iter().cloned().flat_map(|x| {
+    ///     if (x > 0) {
+    ///         (0..x).map(|y| { let x = x.clone(); x + y }).collect::<Vec<_>>()
+    ///     } else {
+    ///         Vec::new()
+    ///     }
+    /// })
+    /// ```
+    ///
+    /// The outermost clause stays lazy; each inner clause is collected per
+    /// item of the clause enclosing it, so its closures borrow the enclosing
+    /// targets and locals instead of moving them. Enclosing targets are
+    /// cloned where an inner closure uses them, and each clause's `if`s guard
+    /// everything nested inside it.
     fn convert_nested_generators(
         &mut self,
         element: &HirExpr,
         generators: &[crate::hir::HirComprehension],
     ) -> Result<syn::Expr> {
-        // Start with the outermost generator
-        let first_gen = &generators[0];
-        let first_iter = first_gen.iter.to_rust_expr(self.ctx)?;
-        let first_pat = self.parse_target_pattern(&first_gen.target)?;
-
-        // Build the nested expression recursively
-        let inner_expr = self.build_nested_chain(element, generators, 1)?;
-
-        // Start the chain with the first generator
-        let mut chain: syn::Expr = parse_quote! { #first_iter.into_iter() };
-
-        // Add filters for first generator's conditions
-        for cond in &first_gen.conditions {
-            let cond_expr = cond.to_rust_expr(self.ctx)?;
-            chain = parse_quote! { #chain.filter(|#first_pat| #cond_expr) };
-        }
-
-        // Use flat_map for the first generator
-        chain = parse_quote! { #chain.flat_map(|#first_pat| #inner_expr) };
-
-        Ok(chain)
+        self.convert_comprehension_clause(element, generators, &[])
     }
 
-    fn build_nested_chain(
+    fn convert_comprehension_clause(
         &mut self,
         element: &HirExpr,
         generators: &[crate::hir::HirComprehension],
-        depth: usize,
+        outer_targets: &[String],
     ) -> Result<syn::Expr> {
-        if depth >= generators.len() {
-            // Base case: no more generators, return the element expression
-            let element_expr = element.to_rust_expr(self.ctx)?;
-            return Ok(element_expr);
-        }
-
-        let gen = &generators[depth];
-        let iter_expr = gen.iter.to_rust_expr(self.ctx)?;
+        let (gen, inner) = match generators.split_first() {
+            Some(split) => split,
+            None => bail!("Generator expression must have at least one generator"),
+        };
+        let (source, item_type) = self.comprehension_source(&gen.iter)?;
         let target_pat = self.parse_target_pattern(&gen.target)?;
 
-        // Build the inner expression (recursive)
-        let inner_expr = self.build_nested_chain(element, generators, depth + 1)?;
+        // The target's type lets inner clauses iterate it, e.g. over a row
+        let shadowed = item_type
+            .filter(|_| !gen.target.starts_with('('))
+            .map(|ty| self.ctx.var_types.insert(gen.target.clone(), ty));
+        let mut targets = outer_targets.to_vec();
+        targets.extend(comprehension_target_names(&gen.target));
+        let clause = self.convert_comprehension_body(element, gen, inner, outer_targets, &targets);
+        if let Some(previous) = shadowed {
+            match previous {
+                Some(ty) => self.ctx.var_types.insert(gen.target.clone(), ty),
+                None => self.ctx.var_types.remove(&gen.target),
+            };
+        }
+        let (conditions, closure_body) = clause?;
+
+        let adapter = match (conditions, inner.is_empty()) {
+            (false, true) => quote! { map },
+            (true, true) => quote! { filter_map },
+            (_, false) => quote! { flat_map },
+        };
+        Ok(parse_quote! { #source.#adapter(|#target_pat| #closure_body) })
+    }
 
-        // Build the chain for this level
-        let mut chain: syn::Expr = parse_quote! { #iter_expr.into_iter() };
+    /// The closure body of a comprehension clause, and whether the clause
+    /// has conditions
+    fn convert_comprehension_body(
+        &mut self,
+        element: &HirExpr,
+        gen: &crate::hir::HirComprehension,
+        inner: &[crate::hir::HirComprehension],
+        outer_targets: &[String],
+        targets: &[String],
+    ) -> Result<(bool, syn::Expr)> {
+        let body: syn::Expr = if inner.is_empty() {
+            element.to_rust_expr(self.ctx)?
+        } else {
+            let inner_chain = self.convert_comprehension_clause(element, inner, targets)?;
+            parse_quote! { #inner_chain.collect::<Vec<_>>() }
+        };
 
-        // Add filters for this generator's conditions
+        // Inner closures borrow the enclosing clauses' targets; clone the
+        // ones this clause's conditions and everything nested inside it use
+        let mut used = std::collections::HashSet::new();
         for cond in &gen.conditions {
-            let cond_expr = cond.to_rust_expr(self.ctx)?;
-            chain = parse_quote! { #chain.filter(|#target_pat| #cond_expr) };
+            collect_var_names(cond, &mut used);
         }
+        collect_var_names(element, &mut used);
+        for gen in inner {
+            collect_var_names(&gen.iter, &mut used);
+            for cond in &gen.conditions {
+                collect_var_names(cond, &mut used);
+            }
+        }
+        let own_targets = comprehension_target_names(&gen.target);
+        let rebinds: Vec<syn::Stmt> = outer_targets
+            .iter()
+            .filter(|name| used.contains(name.as_str()) && !own_targets.contains(name))
+            .map(|name| {
+                let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+                parse_quote! { let #ident = #ident.clone(); }
+            })
+            .collect();
 
-        // Use flat_map for intermediate generators, map for the last
-        if depth < generators.len() - 1 {
-            // Intermediate generator: use flat_map
-            chain = parse_quote! { #chain.flat_map(move |#target_pat| #inner_expr) };
+        let conditions = gen
+            .conditions
+            .iter()
+            .map(|cond| cond.to_rust_expr(self.ctx))
+            .collect::<Result<Vec<_>>>()?;
+        let is_last = inner.is_empty();
+        let guarded: syn::Expr = match conditions.as_slice() {
+            [] => body,
+            [condition] if is_last => parse_quote! { if #condition { Some(#body) } else { None } },
+            [condition] => parse_quote! { if #condition { #body } else { Vec::new() } },
+            _ if is_last => parse_quote! {
+                if #((#conditions))&&* { Some(#body) } else { None }
+            },
+            _ => parse_quote! { if #((#conditions))&&* { #body } else { Vec::new() } },
+        };
+        let closure_body: syn::Expr = if rebinds.is_empty() {
+            guarded
         } else {
-            // Last generator: use map
-            chain = parse_quote! { #chain.map(move |#target_pat| #inner_expr) };
-        }
+            parse_quote! { { #(#rebinds)* #guarded } }
+        };
+        Ok((!conditions.is_empty(), closure_body))
+    }
 
-        Ok(chain)
+    /// The iterator a comprehension clause draws its items from, by value,
+    /// and the items' type when known
+    fn comprehension_source(&mut self, iter: &HirExpr) -> Result<(syn::Expr, Option<Type>)> {
+        let iter_expr = iter.to_rust_expr(self.ctx)?;
+        if self.is_range_expr(&iter_expr) {
+            return Ok((parse_quote! { (#iter_expr) }, Some(Type::Int)));
+        }
+        let iter_type = self.ctx.value_type(iter);
+        let item_type = match &iter_type {
+            Type::List(item) | Type::Set(item) => Some(item.as_ref().clone()),
+            Type::Dict(key, _) => Some(key.as_ref().clone()),
+            Type::String => Some(Type::String),
+            _ => None,
+        };
+        let source = match (iter, iter_type) {
+            (HirExpr::Var(_), Type::List(_) | Type::Set(_) | Type::Dict(_, _) | Type::String) => {
+                iter_gen::codegen_iter(iter, &iter_expr, self.ctx)
+            }
+            // Targets of enclosing clauses and untyped locals are re-iterated
+            (HirExpr::Var(_), _) => parse_quote! { #iter_expr.clone().into_iter() },
+            _ => parse_quote! { #iter_expr.into_iter() },
+        };
+        Ok((source, item_type.filter(|ty| !matches!(ty, Type::Unknown))))
     }
 
     fn parse_target_pattern(&self, target: &str) -> Result<syn::Pat> {
@@ -10280,6 +10602,27 @@ impl<'a, 'b> ExpressionConverter<'a, 'b> {
     }
 }
 
+/// The names a comprehension target binds: `x` or `(key, value)`
+fn comprehension_target_names(target: &str) -> Vec<String> {
+    target
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(',')
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Every variable `expr` reads, including inside nested expressions
+fn collect_var_names<'e>(expr: &'e HirExpr, names: &mut std::collections::HashSet<&'e str>) {
+    if let HirExpr::Var(name) = expr {
+        names.insert(name);
+    }
+    for child in expr.children() {
+        collect_var_names(child, names);
+    }
+}
+
 impl ToRustExpr for HirExpr {
     fn to_rust_expr(&self, ctx: &mut CodeGenContext) -> Result<syn::Expr> {
         let mut converter = ExpressionConverter::new(ctx);
@@ -10301,12 +10644,23 @@ impl ToRustExpr for HirExpr {
             HirExpr::Var(name) => converter.convert_variable(name),
             HirExpr::Binary { op, left, right } => converter.convert_binary(*op, left, right),
             HirExpr::Unary { op, operand } => converter.convert_unary(op, operand),
-            HirExpr::Call { func, args , ..} => converter.convert_call(func, args),
+            HirExpr::Call { func, args, kwargs } => {
+                match default_param_gen::bind_call_args(func, args, kwargs, converter.ctx)? {
+                    Some(bound) => converter.convert_call(func, &bound),
+                    None => converter.convert_call(func, args),
+                }
+            }
             HirExpr::MethodCall {
                 object,
                 method,
                 args, ..
-            } => converter.convert_method_call(object, method, args),
+            } => {
+                // asyncio.gather() also reads `return_exceptions=`
+                match async_gen::try_codegen_gather(self, false, converter.ctx)? {
+                    Some(gathered) => Ok(gathered),
+                    None => converter.convert_method_call(object, method, args),
+                }
+            }
             HirExpr::Index { base, index } => converter.convert_index(base, index),
             HirExpr::Slice {
                 base,
@@ -10383,39 +10737,44 @@ fn literal_to_rust_expr(
             parse_quote! { #lit }
         }
         Literal::String(s) => {
-            // Check if this string should be interned
-            if let Some(interned_name) = string_optimizer.get_interned_name(s) {
-                let ident = syn::Ident::new(&interned_name, proc_macro2::Span::call_site());
-                parse_quote! { #ident }
-            } else {
-                let lit = syn::LitStr::new(s, proc_macro2::Span::call_site());
-
-                // Use string optimizer to determine if we need .to_string()
-                let context = StringContext::Literal(s.clone());
-                match string_optimizer.get_optimal_type(&context) {
-                    crate::string_optimization::OptimalStringType::StaticStr => {
-                        // For read-only strings, just use the literal
-                        parse_quote! { #lit }
-                    }
-                    crate::string_optimization::OptimalStringType::BorrowedStr { .. } => {
-                        // Use &'static str for literals that can be borrowed
-                        parse_quote! { #lit }
-                    }
-                    crate::string_optimization::OptimalStringType::CowStr => {
-                        // Check if we're in a context where String is required
-                        if let Some(Type::String) = &ctx.current_return_type {
-                            // Function returns String, so convert to owned
-                            parse_quote! { #lit.to_string() }
-                        } else {
-                            // Use Cow for flexible ownership
-                            parse_quote! { std::borrow::Cow::Borrowed(#lit) }
-                        }
-                    }
-                    crate::string_optimization::OptimalStringType::OwnedString => {
-                        // Only use .to_string() when absolutely necessary
+            // Interned strings are a `&'static str` constant, used like the
+            // literal they stand for
+            let lit: syn::Expr = match string_optimizer.get_interned_name(s) {
+                Some(interned_name) => {
+                    let ident = syn::Ident::new(&interned_name, proc_macro2::Span::call_site());
+                    parse_quote! { #ident }
+                }
+                None => {
+                    let lit = syn::LitStr::new(s, proc_macro2::Span::call_site());
+                    parse_quote! { #lit }
+                }
+            };
+
+            // Use string optimizer to determine if we need .to_string()
+            let context = StringContext::Literal(s.clone());
+            match string_optimizer.get_optimal_type(&context) {
+                crate::string_optimization::OptimalStringType::StaticStr => {
+                    // For read-only strings, just use the literal
+                    lit
+                }
+                crate::string_optimization::OptimalStringType::BorrowedStr { .. } => {
+                    // Use &'static str for literals that can be borrowed
+                    lit
+                }
+                crate::string_optimization::OptimalStringType::CowStr => {
+                    // Check if we're in a context where String is required
+                    if let Some(Type::String) = &ctx.current_return_type {
+                        // Function returns String, so convert to owned
                         parse_quote! { #lit.to_string() }
+                    } else {
+                        // Use Cow for flexible ownership
+                        parse_quote! { std::borrow::Cow::Borrowed(#lit) }
                     }
                 }
+                crate::string_optimization::OptimalStringType::OwnedString => {
+                    // Only use .to_string() when absolutely necessary
+                    parse_quote! { #lit.to_string() }
+                }
             }
         }
         Literal::Bytes(b) => {
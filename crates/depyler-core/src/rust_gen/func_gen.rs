@@ -5,10 +5,20 @@
 
 use crate::hir::*;
 use crate::lifetime_analysis::LifetimeInference;
+use crate::list_usage::{list_substitutions, ListContainer};
+use crate::rust_gen::arena_gen;
 use crate::rust_gen::context::{CodeGenContext, RustCodeGen};
+use crate::rust_gen::default_param_gen;
+use crate::rust_gen::dict_lookup_gen;
+use crate::rust_gen::error_gen;
 use crate::rust_gen::generator_gen::codegen_generator_function;
+use crate::rust_gen::int_semantics_gen;
+use crate::rust_gen::ledger::DivergenceKind;
+use crate::rust_gen::options::ErrorModel;
+use crate::rust_gen::print_gen;
 use crate::rust_gen::type_gen::{rust_type_to_syn, update_import_needs};
 use anyhow::Result;
+use depyler_annotations::{IntegerSemantics, StringIndexing};
 use quote::quote;
 use syn::{self, parse_quote};
 
@@ -195,6 +205,21 @@ pub(crate) fn codegen_function_body(
     ctx.enter_scope();
     ctx.current_function_can_fail = can_fail;
     ctx.current_return_type = Some(func.ret_type.clone());
+    ctx.integer_semantics = int_semantics_gen::function_semantics(func, ctx);
+    ctx.string_indexing = func.annotations.string_indexing;
+    ctx.print_handling = print_gen::function_print_handling(func, ctx);
+    ctx.divergences.enter_function(&func.name);
+    if !func.properties.is_generator {
+        for substitution in list_substitutions(func) {
+            let vars = match substitution.container {
+                ListContainer::VecDeque => &mut ctx.deque_vars,
+                ListContainer::HashSet => &mut ctx.set_vars,
+            };
+            vars.insert(substitution.name);
+            ctx.divergences
+                .record(DivergenceKind::ContainerSubstitution, substitution.reason);
+        }
+    }
     // DEPYLER-0310: Set error type for raise statement wrapping
     ctx.current_error_type = error_type;
 
@@ -207,22 +232,37 @@ pub(crate) fn codegen_function_body(
     // DEPYLER-0312 NOTE: analyze_mutable_vars is now called in impl RustCodeGen BEFORE
     // codegen_function_params, so ctx.mutable_vars is already populated here
 
+    // Parameters taking an `Option` fill in their defaults first, after the
+    // arenas the function keeps of its own are set up
+    let mut body_stmts = arena_gen::local_arenas(&func.name, ctx);
+    body_stmts.extend(default_param_gen::codegen_prologue(func, ctx)?);
+
     // DEPYLER-0271: Convert body, marking final statement for expression-based returns
     let body_len = func.body.len();
-    let body_stmts: Vec<_> = func
-        .body
-        .iter()
-        .enumerate()
-        .map(|(i, stmt)| {
-            // Mark final statement for idiomatic expression-based return
-            ctx.is_final_statement = i == body_len - 1;
-            stmt.to_rust_tokens(ctx)
-        })
-        .collect::<Result<Vec<_>>>()?;
+    body_stmts.reserve(body_len);
+    let mut i = 0;
+    while i < body_len {
+        ctx.is_final_statement = false;
+        if let Some(lookup) = dict_lookup_gen::codegen_get_then_none_check(&func.body[i..], ctx)? {
+            body_stmts.push(lookup);
+            i += 2;
+            continue;
+        }
+        // Mark final statement for idiomatic expression-based return
+        ctx.is_final_statement = i == body_len - 1;
+        body_stmts.push(func.body[i].to_rust_tokens(ctx)?);
+        i += 1;
+    }
 
     ctx.exit_scope();
     ctx.current_function_can_fail = false;
     ctx.current_return_type = None;
+    ctx.integer_semantics = IntegerSemantics::Panic;
+    ctx.string_indexing = StringIndexing::Chars;
+    ctx.print_handling = ctx.options.print_handling;
+    ctx.deque_vars.clear();
+    ctx.set_vars.clear();
+    ctx.divergences.exit_function();
 
     Ok(body_stmts)
 }
@@ -271,7 +311,10 @@ fn codegen_single_param(
         Some(crate::borrowing_context::BorrowingStrategy::TakeOwnership) | None
     );
 
-    let is_param_mutated = is_mutated_in_body && takes_ownership;
+    // A parameter taking an `Option` is shadowed by its value, which is the
+    // one declared `mut`
+    let takes_option = default_param_gen::param_takes_option(param, func);
+    let is_param_mutated = is_mutated_in_body && takes_ownership && !takes_option;
 
     // Get the inferred parameter info
     if let Some(inferred) = lifetime_result.param_lifetimes.get(&param.name) {
@@ -311,6 +354,7 @@ fn codegen_single_param(
             lifetime_result,
             ctx,
         )?;
+        let ty = optional_param_type(ty, takes_option);
 
         Ok(if is_param_mutated {
             quote! { mut #param_ident: #ty }
@@ -323,7 +367,7 @@ fn codegen_single_param(
             .annotation_aware_mapper
             .map_type_with_annotations(&param.ty, &func.annotations);
         update_import_needs(ctx, &rust_type);
-        let ty = rust_type_to_syn(&rust_type)?;
+        let ty = optional_param_type(rust_type_to_syn(&rust_type)?, takes_option);
 
         Ok(if is_param_mutated {
             quote! { mut #param_ident: #ty }
@@ -333,6 +377,15 @@ fn codegen_single_param(
     }
 }
 
+/// `Option<ty>` for a parameter with a default the function fills in
+fn optional_param_type(ty: syn::Type, takes_option: bool) -> syn::Type {
+    if takes_option {
+        parse_quote! { Option<#ty> }
+    } else {
+        ty
+    }
+}
+
 /// Apply borrowing strategy to parameter type
 fn apply_param_borrowing_strategy(
     param_name: &str,
@@ -637,7 +690,10 @@ pub(crate) fn codegen_return_type(
 
     // Check if function can fail and needs Result wrapper
     let can_fail = func.properties.can_fail;
-    let error_type_str = if can_fail && !func.properties.error_types.is_empty() {
+    let module_enum = ctx.options.error_model == ErrorModel::ModuleEnum;
+    let error_type_str = if module_enum {
+        error_gen::MODULE_ERROR.to_string()
+    } else if can_fail && !func.properties.error_types.is_empty() {
         // Use first error type or generic for mixed types
         if func.properties.error_types.len() == 1 {
             func.properties.error_types[0].clone()
@@ -652,7 +708,9 @@ pub(crate) fn codegen_return_type(
     // If Box<dyn Error>, we need to wrap exceptions with Box::new()
     // If concrete type, no wrapping needed
     let error_type = if can_fail {
-        Some(if error_type_str.contains("Box<dyn") {
+        Some(if module_enum {
+            crate::rust_gen::context::ErrorType::ModuleEnum
+        } else if error_type_str.contains("Box<dyn") {
             crate::rust_gen::context::ErrorType::DynBox
         } else {
             crate::rust_gen::context::ErrorType::Concrete(error_type_str.clone())
@@ -673,6 +731,15 @@ pub(crate) fn codegen_return_type(
     if error_type_str.contains("ValueError") {
         ctx.needs_valueerror = true;
     }
+    if error_type_str.contains("TypeError") {
+        ctx.needs_typeerror = true;
+    }
+    if error_type_str.contains("StopIteration") {
+        ctx.needs_stopiteration = true;
+    }
+    if error_type_str.contains("OverflowError") {
+        ctx.needs_overflowerror = true;
+    }
 
     // Also check all error_types from properties (even if can_fail=false)
     // This ensures types used in try/except blocks are generated
@@ -686,6 +753,15 @@ pub(crate) fn codegen_return_type(
         if err_type.contains("ValueError") {
             ctx.needs_valueerror = true;
         }
+        if err_type.contains("TypeError") {
+            ctx.needs_typeerror = true;
+        }
+        if err_type.contains("StopIteration") {
+            ctx.needs_stopiteration = true;
+        }
+        if err_type.contains("OverflowError") {
+            ctx.needs_overflowerror = true;
+        }
     }
 
     let return_type = if matches!(rust_ret_type, crate::type_mapper::RustType::Unit) {
@@ -772,6 +848,26 @@ pub(crate) fn codegen_return_type(
             // If returns_owned_string is true, keep ty as String (already set from rust_type_to_syn)
         }
 
+        // The value borrows from a parameter, for the parameter's lifetime
+        if lifetime_result.cow_return_source(func).is_some() {
+            if let Some(ref return_lt) = lifetime_result.return_lifetime {
+                let lt = syn::Lifetime::new(return_lt.as_str(), proc_macro2::Span::call_site());
+                ctx.needs_cow = true;
+                ty = parse_quote! { Cow<#lt, str> };
+            }
+        } else if lifetime_result.borrowed_return_source(func).is_some() {
+            if let Some(ref return_lt) = lifetime_result.return_lifetime {
+                let lt = syn::Lifetime::new(return_lt.as_str(), proc_macro2::Span::call_site());
+                ty = match &rust_ret_type {
+                    crate::type_mapper::RustType::Vec(elem) => {
+                        let elem = rust_type_to_syn(elem)?;
+                        parse_quote! { &#lt [#elem] }
+                    }
+                    _ => parse_quote! { &#lt str },
+                };
+            }
+        }
+
         if can_fail {
             let error_type: syn::Type = syn::parse_str(&error_type_str)
                 .unwrap_or_else(|_| parse_quote! { Box<dyn std::error::Error> });
@@ -808,9 +904,12 @@ impl RustCodeGen for HirFunction {
 
         // Perform lifetime analysis with automatic elision (DEPYLER-0275)
         let mut lifetime_inference = LifetimeInference::new();
-        let lifetime_result = lifetime_inference
+        let mut lifetime_result = lifetime_inference
             .apply_elision_rules(self, ctx.type_mapper)
             .unwrap_or_else(|| lifetime_inference.analyze_function(self, ctx.type_mapper));
+        int_semantics_gen::widen_param_types(self, &mut lifetime_result, ctx);
+        default_param_gen::own_optional_params(self, &mut lifetime_result);
+        arena_gen::pass_handles_by_value(self, &mut lifetime_result, ctx);
 
         // Generate combined generic parameters (lifetimes + type params)
         let generic_params = codegen_generic_params(&type_params, &lifetime_result.lifetime_params);
@@ -821,9 +920,19 @@ impl RustCodeGen for HirFunction {
         // DEPYLER-0312: Analyze mutability BEFORE generating parameters
         // This populates ctx.mutable_vars which codegen_single_param uses to determine `mut` keyword
         analyze_mutable_vars(&self.body, ctx, &self.params);
+        // Parameters the type-hints pass found mutated, maybe in the analysis cache
+        if let Some(mutated) = &self.properties.mutated_params {
+            for (param, &mutated) in self.params.iter().zip(mutated) {
+                if mutated {
+                    ctx.mutable_vars.insert(param.name.clone());
+                }
+            }
+        }
 
-        // Convert parameters using lifetime analysis results
-        let params = codegen_function_params(self, &lifetime_result, ctx)?;
+        // Convert parameters using lifetime analysis results, after the arenas
+        // the function works in
+        let mut params = arena_gen::arena_params(&self.name, ctx);
+        params.extend(codegen_function_params(self, &lifetime_result, ctx)?);
 
         // DEPYLER-0270: Extract parameter borrowing information for auto-borrow decisions
         // Check which parameters are references (borrowed) vs owned
@@ -846,7 +955,15 @@ impl RustCodeGen for HirFunction {
             codegen_return_type(self, &lifetime_result, ctx)?;
 
         // Process function body with proper scoping
+        let cow_source = lifetime_result.cow_return_source(self);
+        ctx.current_borrowed_return = lifetime_result
+            .borrowed_return_source(self)
+            .or(cow_source)
+            .map(str::to_string);
+        ctx.current_return_is_cow = cow_source.is_some();
         let mut body_stmts = codegen_function_body(self, can_fail, error_type, ctx)?;
+        ctx.current_borrowed_return = None;
+        ctx.current_return_is_cow = false;
 
         // DEPYLER-0363: Check if ArgumentParser was detected and generate Args struct
         if ctx.argparser_tracker.has_parsers() {
@@ -873,12 +990,45 @@ impl RustCodeGen for HirFunction {
             body_stmts.push(parse_quote! { Ok(()) });
         }
 
+        // @lru_cache keeps results in a thread-local map keyed by the arguments
+        if let Some(memoize) = self.properties.memoize {
+            if type_params.is_empty() && lifetime_result.lifetime_params.is_empty() {
+                if let Some(memoized) = crate::rust_gen::memoize_gen::codegen_memoized_body(
+                    self,
+                    memoize,
+                    &return_type,
+                    &body_stmts,
+                    ctx,
+                )? {
+                    body_stmts = memoized;
+                }
+            }
+        }
+
         // Add documentation and custom attributes
-        let attrs = codegen_function_attrs(
+        let mut attrs = codegen_function_attrs(
             &self.docstring,
             &self.properties,
             &self.annotations.custom_attributes,
         );
+        attrs.extend(ctx.function_attributes.tokens(&self.name));
+
+        // @contextmanager generators become a setup function returning a cleanup guard
+        if ctx.context_manager_functions.contains(&self.name) {
+            if let Some(tokens) = crate::rust_gen::contextlib_gen::codegen_context_manager_function(
+                self,
+                &name,
+                &generic_params,
+                &where_clause,
+                &params,
+                &attrs,
+                ctx,
+            )? {
+                return Ok(tokens);
+            }
+        }
+
+        let vis = ctx.function_visibility.tokens(&self.name);
 
         // Check if function is a generator (contains yield)
         let func_tokens = if self.properties.is_generator {
@@ -893,16 +1043,23 @@ impl RustCodeGen for HirFunction {
                 ctx,
             )?
         } else if self.properties.is_async {
+            // `asyncio.run(main())` makes main() itself the runtime entry point
+            let runtime = if self.properties.is_async_entry && self.name == "main" {
+                quote! { #[tokio::main] }
+            } else {
+                quote! {}
+            };
             quote! {
                 #(#attrs)*
-                pub async fn #name #generic_params(#(#params),*) #return_type #where_clause {
+                #runtime
+                #vis async fn #name #generic_params(#(#params),*) #return_type #where_clause {
                     #(#body_stmts)*
                 }
             }
         } else {
             quote! {
                 #(#attrs)*
-                pub fn #name #generic_params(#(#params),*) #return_type #where_clause {
+                #vis fn #name #generic_params(#(#params),*) #return_type #where_clause {
                     #(#body_stmts)*
                 }
             }
@@ -3,13 +3,17 @@
 //! This module handles Python generator functions, converting them to
 //! Rust Iterator implementations with state structs.
 
+use crate::dataflow::cfg::{lower_blocks, BlockGraph, BlockId, LoopSlot, Terminator};
 use crate::generator_state::GeneratorStateInfo;
 use crate::generator_yield_analysis::YieldAnalysis;
-use crate::hir::{HirExpr, HirFunction, HirStmt, Literal, Type};
+use crate::hir::{AssignTarget, BinOp, HirExpr, HirFunction, HirStmt, Literal, Type, UnaryOp};
 use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::keywords::safe_ident;
+use crate::rust_gen::stmt_gen::apply_truthiness_conversion;
 use crate::rust_gen::type_gen::rust_type_to_syn;
 use anyhow::Result;
 use quote::quote;
+use std::collections::{HashMap, HashSet};
 
 /// Generate struct fields for generator state variables
 ///
@@ -71,25 +75,29 @@ fn extract_generator_item_type(
     yield_analysis: &YieldAnalysis,
     ctx: &CodeGenContext,
 ) -> Result<syn::Type> {
-    // DEPYLER-0263: Infer type from first yield expression if func.ret_type is Unknown
-    let yield_type =
-        if matches!(func.ret_type, Type::Unknown) && !yield_analysis.yield_points.is_empty() {
-            // Infer from first yield expression
-            infer_yield_type(&yield_analysis.yield_points[0].yield_expr)
-        } else {
-            // Use func.ret_type if available
-            func.ret_type.clone()
-        };
-
-    let rust_yield_type = ctx.type_mapper.map_type(&yield_type);
+    let rust_yield_type = ctx
+        .type_mapper
+        .map_type(&generator_item_type(func, yield_analysis));
     rust_type_to_syn(&rust_yield_type)
 }
 
+/// The HIR type of the values a generator yields
+fn generator_item_type(func: &HirFunction, yield_analysis: &YieldAnalysis) -> Type {
+    // DEPYLER-0263: Infer type from first yield expression if func.ret_type is Unknown
+    if matches!(func.ret_type, Type::Unknown) && !yield_analysis.yield_points.is_empty() {
+        // Infer from first yield expression
+        infer_yield_type(&yield_analysis.yield_points[0].yield_expr)
+    } else {
+        // Use func.ret_type if available
+        func.ret_type.clone()
+    }
+}
+
 /// Infer type from a yield expression
 ///
 /// # Complexity: 2 (match + return)
 #[inline]
-fn infer_yield_type(expr: &HirExpr) -> Type {
+pub(crate) fn infer_yield_type(expr: &HirExpr) -> Type {
     match expr {
         HirExpr::Literal(lit) => match lit {
             Literal::Int(_) => Type::Int,
@@ -210,10 +218,13 @@ fn get_default_value_for_type(ty: &Type) -> proc_macro2::TokenStream {
 /// # Complexity: 6 (within ≤10 target)
 #[inline]
 fn generate_state_struct_name(name: &syn::Ident) -> syn::Ident {
-    let name_str = name.to_string();
+    syn::Ident::new(&state_struct_name(&name.to_string()), name.span())
+}
 
+/// Name of the state struct generated for generator `name`
+pub(crate) fn state_struct_name(name: &str) -> String {
     // DEPYLER-0259 FIX: Convert snake_case to PascalCase properly
-    let pascal_case = name_str
+    let pascal_case = name
         .split('_')
         .map(|word| {
             let mut chars = word.chars();
@@ -224,8 +235,7 @@ fn generate_state_struct_name(name: &syn::Ident) -> syn::Ident {
         })
         .collect::<String>();
 
-    let state_struct_name = format!("{}State", pascal_case);
-    syn::Ident::new(&state_struct_name, name.span())
+    format!("{}State", pascal_case)
 }
 
 /// Populate generator state variables in context
@@ -414,6 +424,22 @@ fn generate_simple_loop_with_yield(
     })
 }
 
+/// `init; while cond: yield value; updates` with nothing after the loop,
+/// the shape `generate_simple_loop_with_yield` reproduces exactly
+fn is_simple_while_generator(func: &HirFunction) -> bool {
+    let is_yield = |stmt: &HirStmt| matches!(stmt, HirStmt::Expr(HirExpr::Yield { .. }));
+    match func.body.split_last() {
+        Some((HirStmt::While { body, .. }, init)) => {
+            init.iter().all(crate::dataflow::cfg::is_opaque)
+                && body.first().is_some_and(is_yield)
+                && body[1..]
+                    .iter()
+                    .all(|stmt| crate::dataflow::cfg::is_opaque(stmt) && !is_yield(stmt))
+        }
+        _ => false,
+    }
+}
+
 /// Extract loop information from function body
 ///
 /// # Complexity: 5
@@ -486,6 +512,500 @@ fn generate_loop_body_stmts(
     stmts.iter().map(|stmt| stmt.to_rust_tokens(ctx)).collect()
 }
 
+/// Generate a generator as a state machine over `dataflow::cfg` basic blocks
+///
+/// Covers the shapes the simpler paths above do not: yields in branches and
+/// `for` loops, several yields per loop, `yield from` (a `for` loop by the
+/// time it reaches the HIR) and early `return`. Each block becomes one arm of
+/// `loop { match self.state { .. } }`, and parameters, locals and the
+/// iterators of lowered `for` loops become fields of the state struct.
+///
+/// Returns `None` when the body cannot be lowered or a local's type cannot be
+/// inferred, leaving the caller to fall back to a single state.
+#[allow(clippy::too_many_arguments)]
+fn codegen_block_state_machine(
+    func: &HirFunction,
+    name: &syn::Ident,
+    generic_params: &proc_macro2::TokenStream,
+    where_clause: &proc_macro2::TokenStream,
+    params: &[proc_macro2::TokenStream],
+    attrs: &[proc_macro2::TokenStream],
+    item_type: &Type,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<proc_macro2::TokenStream>> {
+    let graph = match lower_blocks(&func.body) {
+        Ok(graph) => graph,
+        Err(_) => return Ok(None),
+    };
+    let locals = match block_locals(func, &graph, item_type) {
+        Some(locals) => locals,
+        None => return Ok(None),
+    };
+    let slot_types: Vec<Type> = {
+        let mut known: HashMap<String, Type> = func
+            .params
+            .iter()
+            .map(|p| (p.name.clone(), p.ty.clone()))
+            .chain(locals.iter().cloned())
+            .collect();
+        let mut slot_types = Vec::new();
+        for slot in &graph.loops {
+            let elem = loop_item_type(slot, item_type, &known);
+            if matches!(elem, Type::Unknown) {
+                return Ok(None);
+            }
+            if let AssignTarget::Symbol(target) = &slot.target {
+                known.insert(target.clone(), elem.clone());
+            }
+            slot_types.push(elem);
+        }
+        slot_types
+    };
+
+    let state_ident = generate_state_struct_name(name);
+    let rust_item_type = rust_type_to_syn(&ctx.type_mapper.map_type(item_type))?;
+
+    let mut fields = Vec::new();
+    let mut inits = Vec::new();
+    for param in &func.params {
+        let field = safe_ident(&param.name);
+        let ty = rust_type_to_syn(&ctx.type_mapper.map_type(&param.ty))?;
+        fields.push(quote! { #field: #ty });
+        // Parameters may arrive borrowed; the state struct owns its copy
+        match param.ty {
+            Type::Int | Type::Float | Type::Bool => inits.push(quote! { #field: #field }),
+            _ => inits.push(quote! { #field: #field.to_owned() }),
+        }
+    }
+    for (local, ty) in &locals {
+        let field = safe_ident(local);
+        let default_value = get_default_value_for_type(ty);
+        let ty = rust_type_to_syn(&ctx.type_mapper.map_type(ty))?;
+        fields.push(quote! { #field: #ty });
+        inits.push(quote! { #field: #default_value });
+    }
+    for (slot, elem) in slot_types.iter().enumerate() {
+        let field = iter_slot_ident(slot);
+        let elem = rust_type_to_syn(&ctx.type_mapper.map_type(elem))?;
+        fields.push(quote! { #field: Option<Box<dyn Iterator<Item = #elem>>> });
+        inits.push(quote! { #field: None });
+    }
+
+    ctx.generator_state_vars.clear();
+    ctx.generator_state_vars
+        .extend(func.params.iter().map(|p| p.name.clone()));
+    ctx.generator_state_vars
+        .extend(locals.iter().map(|(local, _)| local.clone()));
+    for param in &func.params {
+        ctx.var_types
+            .entry(param.name.clone())
+            .or_insert_with(|| param.ty.clone());
+    }
+    for (local, ty) in &locals {
+        ctx.var_types.insert(local.clone(), ty.clone());
+    }
+    ctx.in_generator = true;
+    let arms = block_arms(&graph, ctx);
+    ctx.in_generator = false;
+    ctx.generator_state_vars.clear();
+    let arms = arms?;
+
+    let vis = ctx.function_visibility.tokens(&func.name);
+    Ok(Some(quote! {
+        #(#attrs)*
+        #[doc = " Generator state struct"]
+        struct #state_ident {
+            state: usize,
+            #(#fields),*
+        }
+
+        #[doc = " Generator function - returns Iterator"]
+        #vis fn #name #generic_params(#(#params),*) -> impl Iterator<Item = #rust_item_type> #where_clause {
+            #state_ident {
+                state: 0,
+                #(#inits),*
+            }
+        }
+
+        impl Iterator for #state_ident {
+            type Item = #rust_item_type;
+
+            fn next(&mut self) -> Option<Self::Item> {
+                loop {
+                    match self.state {
+                        #(#arms)*
+                        _ => return None,
+                    }
+                }
+            }
+        }
+    }))
+}
+
+/// One `match` arm per basic block; the state after the last block is the
+/// finished generator
+fn block_arms(
+    graph: &BlockGraph,
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<proc_macro2::TokenStream>> {
+    use crate::rust_gen::RustCodeGen;
+
+    let done = proc_macro2::Literal::usize_unsuffixed(graph.blocks.len());
+    let state = |block: BlockId| proc_macro2::Literal::usize_unsuffixed(block);
+    let mut arms = Vec::new();
+    for (id, block) in graph.blocks.iter().enumerate() {
+        ctx.enter_scope();
+        let stmts = block
+            .stmts
+            .iter()
+            .map(|stmt| stmt.to_rust_tokens(ctx))
+            .collect::<Result<Vec<_>>>()?;
+        let transfer = match &block.terminator {
+            Terminator::Goto(target) => {
+                let target = state(*target);
+                quote! { self.state = #target; }
+            }
+            Terminator::Branch {
+                condition,
+                then_block,
+                else_block,
+            } => {
+                let cond = hir_expr_to_syn(condition, ctx)?;
+                let cond = apply_truthiness_conversion(condition, cond, ctx);
+                let (then_block, else_block) = (state(*then_block), state(*else_block));
+                quote! { self.state = if #cond { #then_block } else { #else_block }; }
+            }
+            Terminator::IterStart { slot, iter, next } => {
+                let field = iter_slot_ident(*slot);
+                let iter_tokens = hir_expr_to_syn(iter, ctx)?;
+                let items = match iter {
+                    HirExpr::Call { func, .. } if func == "range" => quote! { #iter_tokens },
+                    _ if matches!(infer_local_type(iter, &ctx.var_types), Type::Dict(_, _)) => {
+                        quote! { #iter_tokens.clone().into_keys() }
+                    }
+                    _ => quote! { #iter_tokens.clone().into_iter() },
+                };
+                let next = state(*next);
+                quote! {
+                    self.#field = Some(Box::new(#items));
+                    self.state = #next;
+                }
+            }
+            Terminator::IterNext {
+                slot,
+                target,
+                body,
+                exhausted,
+            } => {
+                let field = iter_slot_ident(*slot);
+                let bind = match target {
+                    AssignTarget::Symbol(target) => {
+                        let target = safe_ident(target);
+                        quote! { self.#target = item; }
+                    }
+                    AssignTarget::Tuple(targets) => {
+                        let targets = targets.iter().map(|t| match t {
+                            AssignTarget::Symbol(t) => safe_ident(t),
+                            _ => unreachable!("checked by block_locals"),
+                        });
+                        quote! { (#(self.#targets),*) = item; }
+                    }
+                    _ => unreachable!("checked by block_locals"),
+                };
+                let (body, exhausted) = (state(*body), state(*exhausted));
+                quote! {
+                    match self.#field.as_mut().and_then(|items| items.next()) {
+                        Some(item) => {
+                            #bind
+                            self.state = #body;
+                        }
+                        None => {
+                            self.#field = None;
+                            self.state = #exhausted;
+                        }
+                    }
+                }
+            }
+            Terminator::Yield { value, resume } => {
+                let value = match value {
+                    // Fields stay in the struct; hand out copies
+                    Some(HirExpr::Var(var))
+                        if ctx.generator_state_vars.contains(var)
+                            && !matches!(
+                                ctx.var_types.get(var),
+                                Some(Type::Int | Type::Float | Type::Bool)
+                            ) =>
+                    {
+                        let field = safe_ident(var);
+                        quote! { self.#field.clone() }
+                    }
+                    Some(value) => {
+                        let value = hir_expr_to_syn(value, ctx)?;
+                        quote! { #value }
+                    }
+                    None => quote! { () },
+                };
+                let resume = state(*resume);
+                quote! {
+                    self.state = #resume;
+                    return Some(#value);
+                }
+            }
+            // A returned value only travels in `StopIteration`, which a Rust
+            // iterator has no room for
+            Terminator::Return(_) => quote! {
+                self.state = #done;
+                return None;
+            },
+        };
+        ctx.exit_scope();
+        let id = state(id);
+        arms.push(quote! {
+            #id => {
+                #(#stmts)*
+                #transfer
+            }
+        });
+    }
+    Ok(arms)
+}
+
+fn iter_slot_ident(slot: usize) -> syn::Ident {
+    quote::format_ident!("__iter_{}", slot)
+}
+
+/// Locals of a lowered generator body and their types, in order of first
+/// assignment
+///
+/// Variables of `for` loops that run inside a single block stay ordinary
+/// `let` bindings, so a name used both that way and as a field is rejected,
+/// as are locals whose type cannot be inferred.
+fn block_locals(
+    func: &HirFunction,
+    graph: &BlockGraph,
+    item_type: &Type,
+) -> Option<Vec<(String, Type)>> {
+    let mut scan = LocalScan {
+        graph,
+        item_type,
+        known: func
+            .params
+            .iter()
+            .map(|p| (p.name.clone(), p.ty.clone()))
+            .collect(),
+        params: func.params.iter().map(|p| p.name.clone()).collect(),
+        locals: Vec::new(),
+        inner_loop_vars: HashSet::new(),
+    };
+    scan.stmts(&func.body)?;
+    scan.locals
+        .iter()
+        .map(|local| match scan.known.get(local) {
+            Some(ty) if !contains_unknown(ty) && !scan.inner_loop_vars.contains(local) => {
+                Some((local.clone(), ty.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+struct LocalScan<'a> {
+    graph: &'a BlockGraph,
+    item_type: &'a Type,
+    known: HashMap<String, Type>,
+    params: HashSet<String>,
+    locals: Vec<String>,
+    inner_loop_vars: HashSet<String>,
+}
+
+impl LocalScan<'_> {
+    fn stmts(&mut self, stmts: &[HirStmt]) -> Option<()> {
+        stmts.iter().try_for_each(|stmt| self.stmt(stmt))
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) -> Option<()> {
+        match stmt {
+            HirStmt::Assign {
+                target,
+                value,
+                type_annotation,
+            } => {
+                let ty = type_annotation
+                    .clone()
+                    .unwrap_or_else(|| infer_local_type(value, &self.known));
+                self.bind(target, ty, true)
+            }
+            HirStmt::For { target, iter, body } => {
+                let graph = self.graph;
+                let lowered = graph
+                    .loops
+                    .iter()
+                    .find(|slot| slot.target == *target && slot.iter == *iter);
+                let elem = match lowered {
+                    Some(slot) => loop_item_type(slot, self.item_type, &self.known),
+                    None => element_type(iter, &self.known),
+                };
+                self.bind(target, elem, lowered.is_some())?;
+                self.stmts(body)
+            }
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                self.stmts(then_body)?;
+                else_body
+                    .as_deref()
+                    .map_or(Some(()), |body| self.stmts(body))
+            }
+            HirStmt::While { body, .. } | HirStmt::With { body, .. } => self.stmts(body),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body)?;
+                for handler in handlers {
+                    self.stmts(&handler.body)?;
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block)?;
+                }
+                Some(())
+            }
+            HirStmt::Match { cases, .. } => {
+                cases.iter().try_for_each(|case| self.stmts(&case.body))
+            }
+            _ => Some(()),
+        }
+    }
+
+    /// Records the names `target` binds; `field` is false for the variables
+    /// of loops kept whole in a block
+    fn bind(&mut self, target: &AssignTarget, ty: Type, field: bool) -> Option<()> {
+        match target {
+            AssignTarget::Symbol(name) => {
+                let known = self.known.entry(name.clone()).or_insert(Type::Unknown);
+                if matches!(known, Type::Unknown) {
+                    *known = ty;
+                }
+                if !field {
+                    self.inner_loop_vars.insert(name.clone());
+                } else if !self.params.contains(name) && !self.locals.contains(name) {
+                    self.locals.push(name.clone());
+                }
+                Some(())
+            }
+            AssignTarget::Tuple(targets) => {
+                let types = match ty {
+                    Type::Tuple(types) if types.len() == targets.len() => types,
+                    _ => vec![Type::Unknown; targets.len()],
+                };
+                targets
+                    .iter()
+                    .zip(types)
+                    .try_for_each(|(target, ty)| match target {
+                        AssignTarget::Symbol(_) => self.bind(target, ty, field),
+                        _ => None,
+                    })
+            }
+            // Subscript and attribute stores write through existing values
+            AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => Some(()),
+        }
+    }
+}
+
+fn contains_unknown(ty: &Type) -> bool {
+    match ty {
+        Type::Unknown => true,
+        Type::List(elem) | Type::Set(elem) | Type::Optional(elem) => contains_unknown(elem),
+        Type::Dict(key, value) => contains_unknown(key) || contains_unknown(value),
+        Type::Tuple(items) => items.iter().any(contains_unknown),
+        _ => false,
+    }
+}
+
+/// Type of the items a lowered `for` loop binds
+fn loop_item_type(slot: &LoopSlot, item_type: &Type, known: &HashMap<String, Type>) -> Type {
+    if slot.forwards_items {
+        item_type.clone()
+    } else {
+        element_type(&slot.iter, known)
+    }
+}
+
+fn element_type(iter: &HirExpr, known: &HashMap<String, Type>) -> Type {
+    match iter {
+        HirExpr::Call { func, .. } if func == "range" => Type::Int,
+        _ => match infer_local_type(iter, known) {
+            Type::List(elem) | Type::Set(elem) => *elem,
+            Type::Dict(key, _) => *key,
+            _ => Type::Unknown,
+        },
+    }
+}
+
+/// Best-effort type of a value assigned to a generator local
+fn infer_local_type(expr: &HirExpr, known: &HashMap<String, Type>) -> Type {
+    match expr {
+        HirExpr::Literal(Literal::None) => Type::Unknown,
+        HirExpr::Literal(_) => infer_yield_type(expr),
+        HirExpr::Var(name) => known.get(name).cloned().unwrap_or(Type::Unknown),
+        HirExpr::Binary { op, left, right } => match op {
+            BinOp::Eq
+            | BinOp::NotEq
+            | BinOp::Lt
+            | BinOp::LtEq
+            | BinOp::Gt
+            | BinOp::GtEq
+            | BinOp::In
+            | BinOp::NotIn => Type::Bool,
+            BinOp::Div => Type::Float,
+            _ => match infer_local_type(left, known) {
+                Type::Unknown => infer_local_type(right, known),
+                ty => ty,
+            },
+        },
+        HirExpr::Unary { op, operand } => match op {
+            UnaryOp::Not => Type::Bool,
+            _ => infer_local_type(operand, known),
+        },
+        HirExpr::Call { func, args, .. } => match (func.as_str(), args.as_slice()) {
+            ("len" | "int", _) => Type::Int,
+            ("float", _) => Type::Float,
+            ("str", _) => Type::String,
+            ("bool", _) => Type::Bool,
+            ("abs" | "min" | "max", [first, ..]) => infer_local_type(first, known),
+            _ => Type::Unknown,
+        },
+        HirExpr::List(items) | HirExpr::Set(items) => {
+            let elem = items
+                .first()
+                .map_or(Type::Unknown, |item| infer_local_type(item, known));
+            if matches!(elem, Type::Unknown) {
+                return Type::Unknown;
+            }
+            match expr {
+                HirExpr::Set(_) => Type::Set(Box::new(elem)),
+                _ => Type::List(Box::new(elem)),
+            }
+        }
+        HirExpr::Tuple(items) => Type::Tuple(
+            items
+                .iter()
+                .map(|item| infer_local_type(item, known))
+                .collect(),
+        ),
+        HirExpr::Index { base, .. } => match infer_local_type(base, known) {
+            Type::List(elem) => *elem,
+            Type::Dict(_, value) => *value,
+            _ => Type::Unknown,
+        },
+        _ => Type::Unknown,
+    }
+}
+
 /// Generate complete generator function with state struct and Iterator impl
 ///
 /// This is the main entry point for generator code generation. It:
@@ -528,8 +1048,30 @@ pub fn codegen_generator_function(
     let yield_analysis = YieldAnalysis::analyze(func);
 
     // DEPYLER-0262 Phase 3A: Check if we can use simple multi-state transformation
-    let use_simple_multi_state =
-        yield_analysis.has_yields() && yield_analysis.yield_points.iter().all(|yp| yp.depth == 0);
+    // (it only replays the yields, so nothing else may sit between them)
+    let use_simple_multi_state = yield_analysis.has_yields()
+        && yield_analysis.yield_points.iter().all(|yp| yp.depth == 0)
+        && func
+            .body
+            .iter()
+            .all(|stmt| matches!(stmt, HirStmt::Expr(HirExpr::Yield { .. })));
+    let use_simple_loop = !use_simple_multi_state && is_simple_while_generator(func);
+
+    if !use_simple_multi_state && !use_simple_loop {
+        let item_type = generator_item_type(func, &yield_analysis);
+        if let Some(tokens) = codegen_block_state_machine(
+            func,
+            name,
+            generic_params,
+            where_clause,
+            params,
+            attrs,
+            &item_type,
+            ctx,
+        )? {
+            return Ok(tokens);
+        }
+    }
 
     // Generate state struct name
     let state_ident = generate_state_struct_name(name);
@@ -555,19 +1097,11 @@ pub fn codegen_generator_function(
     // Populate generator state variables for scoping
     populate_generator_state_vars(ctx, &state_info);
 
-    // DEPYLER-0262 Phase 3B: Check if we have simple loop with yield pattern
-    let has_while_loop = func
-        .body
-        .iter()
-        .any(|stmt| matches!(stmt, HirStmt::While { .. }));
-    let has_loop_yields =
-        yield_analysis.has_yields() && yield_analysis.yield_points.iter().any(|yp| yp.depth > 0);
-
     // Generate state machine implementation based on yield analysis
     let state_machine_impl = if use_simple_multi_state {
         // DEPYLER-0262 Phase 3A: Multi-state transformation for sequential yields
         generate_simple_multi_state_match(&yield_analysis, func, ctx)?
-    } else if has_while_loop && has_loop_yields && yield_analysis.yield_points.len() == 1 {
+    } else if use_simple_loop {
         // DEPYLER-0262 Phase 3B: Simple loop with single yield pattern
         generate_simple_loop_with_yield(func, &yield_analysis, ctx)?
     } else {
@@ -587,6 +1121,7 @@ pub fn codegen_generator_function(
     };
 
     // Generate the complete generator implementation
+    let vis = ctx.function_visibility.tokens(&func.name);
     Ok(quote! {
         #(#attrs)*
         #[doc = " Generator state struct"]
@@ -597,7 +1132,7 @@ pub fn codegen_generator_function(
         }
 
         #[doc = " Generator function - returns Iterator"]
-        pub fn #name #generic_params(#(#params),*) -> impl Iterator<Item = #item_type> #where_clause {
+        #vis fn #name #generic_params(#(#params),*) -> impl Iterator<Item = #item_type> #where_clause {
             #state_ident {
                 state: 0,
                 #(#all_inits),*
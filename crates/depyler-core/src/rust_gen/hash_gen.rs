@@ -0,0 +1,316 @@
+//! Hash map backend selection
+//!
+//! Code generation always writes `HashMap`; this module decides which map
+//! that name is bound to for a file and adapts the generated code to it.
+//! `fnv::FnvHashMap` is `std::collections::HashMap` with another hasher, so
+//! the constructors that only exist for the default hasher are rewritten.
+//! The `BTreeMap` backend of the embedded profile binds `HashSet` to
+//! `BTreeSet` as well and drops capacity hints, which B-trees have no use for.
+
+use crate::hir::HirFunction;
+use crate::rust_gen::options::{CodeGenOptions, HashBackend};
+use crate::type_mapper::TypeProfile;
+use anyhow::{bail, Result};
+use depyler_annotations::HashStrategy;
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::quote;
+
+/// The backend for a module: the explicit option, else the functions'
+/// `hash` annotations, else the type profile's, else the performance profile's
+///
+/// Functions asking for different non-standard maps are an error, since a
+/// module binds `HashMap` once.
+pub(crate) fn select_hash_backend(
+    functions: &[HirFunction],
+    options: &CodeGenOptions,
+) -> Result<HashBackend> {
+    if let Some(backend) = options.hash_backend {
+        return Ok(backend);
+    }
+    let mut chosen: Option<(HashBackend, &str)> = None;
+    for func in functions {
+        let backend = match func.annotations.hash_strategy {
+            HashStrategy::Standard => continue,
+            HashStrategy::Fnv => HashBackend::Fnv,
+            HashStrategy::AHash => HashBackend::AHash,
+        };
+        match chosen {
+            Some((other, other_func)) if other != backend => bail!(
+                "conflicting hash annotations: `{}` asks for {} and `{}` for {}; \
+                 every function in a module shares one HashMap",
+                other_func,
+                other.name(),
+                func.name,
+                backend.name()
+            ),
+            Some(_) => {}
+            None => chosen = Some((backend, &func.name)),
+        }
+    }
+    Ok(match (chosen, options.type_profile) {
+        (Some((backend, _)), _) => backend,
+        (None, TypeProfile::Embedded) => HashBackend::BTree,
+        (None, _) => options.profile.hash_backend(),
+    })
+}
+
+/// `use` item binding `HashMap` to `backend`
+pub(crate) fn hash_map_import(backend: HashBackend) -> TokenStream {
+    match backend {
+        HashBackend::Std => quote! { use std::collections::HashMap; },
+        HashBackend::Fnv => quote! { use fnv::FnvHashMap as HashMap; },
+        HashBackend::AHash => quote! { use ahash::AHashMap as HashMap; },
+        HashBackend::BTree => quote! { use std::collections::BTreeMap as HashMap; },
+    }
+}
+
+/// Points fully qualified `std::collections::HashMap` paths, and `HashSet`
+/// ones for [`HashBackend::BTree`], at the alias and replaces constructors
+/// the backend lacks
+pub(crate) fn apply_hash_backend(tokens: TokenStream, backend: HashBackend) -> TokenStream {
+    if backend == HashBackend::Std {
+        return tokens;
+    }
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut out: Vec<TokenTree> = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+    while i < tokens.len() {
+        match &tokens[i] {
+            TokenTree::Group(group) => {
+                let stream = apply_hash_backend(group.stream(), backend);
+                let mut rewritten = Group::new(group.delimiter(), stream);
+                rewritten.set_span(group.span());
+                out.push(TokenTree::Group(rewritten));
+                i += 1;
+            }
+            TokenTree::Ident(ident) if ident == "std" => {
+                match aliased_path(&tokens[i..], backend) {
+                    Some(path) => {
+                        // Continue at the `HashMap` or `HashSet` segment
+                        i += STD_COLLECTION_LEN - 1;
+                        let in_use =
+                            matches!(out.last(), Some(TokenTree::Ident(prev)) if prev == "use");
+                        if in_use {
+                            let alias = &tokens[i];
+                            out.extend(path);
+                            let renamed = matches!(
+                                tokens.get(i + 1),
+                                Some(TokenTree::Ident(next)) if next == "as"
+                            );
+                            if !renamed {
+                                out.extend(quote! { as #alias });
+                            }
+                            i += 1;
+                        }
+                    }
+                    None => {
+                        out.push(tokens[i].clone());
+                        i += 1;
+                    }
+                }
+            }
+            TokenTree::Ident(ident) if ident == "HashMap" && backend == HashBackend::Fnv => {
+                match fnv_constructor(&tokens[i..]) {
+                    Some(replacement) => {
+                        out.extend(replacement);
+                        i += 5;
+                    }
+                    None => {
+                        out.push(tokens[i].clone());
+                        i += 1;
+                    }
+                }
+            }
+            TokenTree::Ident(ident)
+                if (ident == "HashMap" || ident == "HashSet") && backend == HashBackend::BTree =>
+            {
+                match btree_constructor(&tokens[i..]) {
+                    Some(replacement) => {
+                        out.extend(replacement);
+                        i += 5;
+                    }
+                    None => {
+                        out.push(tokens[i].clone());
+                        i += 1;
+                    }
+                }
+            }
+            token => {
+                out.push(token.clone());
+                i += 1;
+            }
+        }
+    }
+    out.into_iter().collect()
+}
+
+/// `std :: collections :: HashMap`
+const STD_COLLECTION_LEN: usize = 7;
+
+fn is_std_collection(tokens: &[TokenTree], name: &str) -> bool {
+    tokens.len() >= STD_COLLECTION_LEN
+        && is_path_sep(&tokens[1..])
+        && matches!(&tokens[3], TokenTree::Ident(i) if i == "collections")
+        && is_path_sep(&tokens[4..])
+        && matches!(&tokens[6], TokenTree::Ident(i) if i == name)
+}
+
+/// The type `backend` puts behind the std path `tokens` start with, if it
+/// replaces that path
+fn aliased_path(tokens: &[TokenTree], backend: HashBackend) -> Option<TokenStream> {
+    if is_std_collection(tokens, "HashMap") {
+        Some(backend_path(backend))
+    } else if backend == HashBackend::BTree && is_std_collection(tokens, "HashSet") {
+        Some(quote! { std::collections::BTreeSet })
+    } else {
+        None
+    }
+}
+
+fn is_path_sep(tokens: &[TokenTree]) -> bool {
+    matches!(
+        tokens,
+        [TokenTree::Punct(a), TokenTree::Punct(b), ..] if a.as_char() == ':' && b.as_char() == ':'
+    )
+}
+
+fn backend_path(backend: HashBackend) -> TokenStream {
+    match backend {
+        HashBackend::Std => quote! { std::collections::HashMap },
+        HashBackend::Fnv => quote! { fnv::FnvHashMap },
+        HashBackend::AHash => quote! { ahash::AHashMap },
+        HashBackend::BTree => quote! { std::collections::BTreeMap },
+    }
+}
+
+/// `HashMap::with_capacity(n)` and `HashSet::with_capacity(n)` as `new()`,
+/// since B-trees allocate per node
+fn btree_constructor(tokens: &[TokenTree]) -> Option<TokenStream> {
+    match tokens {
+        [TokenTree::Ident(ty), _, _, TokenTree::Ident(method), TokenTree::Group(args), ..]
+            if is_path_sep(&tokens[1..])
+                && method == "with_capacity"
+                && args.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+        {
+            Some(quote! { #ty::new() })
+        }
+        _ => None,
+    }
+}
+
+/// `HashMap::new()`, `HashMap::with_capacity(n)` and `HashMap::from(..)`
+/// for a map whose hasher is not `RandomState`
+fn fnv_constructor(tokens: &[TokenTree]) -> Option<TokenStream> {
+    let (method, args) = match tokens {
+        [_, _, _, TokenTree::Ident(method), TokenTree::Group(args), ..]
+            if is_path_sep(&tokens[1..])
+                && args.delimiter() == proc_macro2::Delimiter::Parenthesis =>
+        {
+            (method, args)
+        }
+        _ => return None,
+    };
+    let args = apply_hash_backend(args.stream(), HashBackend::Fnv);
+    let replacement = if method == "new" && args.is_empty() {
+        quote! { HashMap::default() }
+    } else if method == "with_capacity" {
+        quote! { HashMap::with_capacity_and_hasher(#args, Default::default()) }
+    } else if method == "from" {
+        quote! { HashMap::from_iter(#args) }
+    } else {
+        return None;
+    };
+    Some(replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(tokens: TokenStream, backend: HashBackend) -> String {
+        apply_hash_backend(tokens, backend).to_string()
+    }
+
+    #[test]
+    fn test_std_backend_leaves_code_alone() {
+        let code = quote! { let m: HashMap<i32, i32> = HashMap::new(); };
+        assert_eq!(rewrite(code.clone(), HashBackend::Std), code.to_string());
+    }
+
+    #[test]
+    fn test_fnv_constructors_take_the_hasher() {
+        let code = quote! {
+            fn f(n: usize) {
+                let a: HashMap<i32, i32> = HashMap::new();
+                let b: HashMap<i32, i32> = HashMap::with_capacity(n);
+                let c: HashMap<i32, i32> = HashMap::from([(1, 2)]);
+            }
+        };
+        let expected = quote! {
+            fn f(n: usize) {
+                let a: HashMap<i32, i32> = HashMap::default();
+                let b: HashMap<i32, i32> = HashMap::with_capacity_and_hasher(n, Default::default());
+                let c: HashMap<i32, i32> = HashMap::from_iter([(1, 2)]);
+            }
+        };
+        assert_eq!(rewrite(code, HashBackend::Fnv), expected.to_string());
+    }
+
+    #[test]
+    fn test_qualified_paths_and_imports_use_the_alias() {
+        let code = quote! {
+            use std::collections::HashMap;
+            struct S { m: std::collections::HashMap<String, i32> }
+            fn f() -> S { S { m: std::collections::HashMap::new() } }
+        };
+        let expected = quote! {
+            use ahash::AHashMap as HashMap;
+            struct S { m: HashMap<String, i32> }
+            fn f() -> S { S { m: HashMap::new() } }
+        };
+        assert_eq!(rewrite(code, HashBackend::AHash), expected.to_string());
+    }
+
+    #[test]
+    fn test_btree_binds_maps_and_sets() {
+        let code = quote! {
+            use std::collections::HashMap;
+            use std::collections::HashSet;
+            fn f(n: usize) -> std::collections::HashSet<i32> {
+                let m: HashMap<i32, i32> = HashMap::with_capacity(n);
+                let s: HashSet<i32> = HashSet::new();
+                s
+            }
+        };
+        let expected = quote! {
+            use std::collections::BTreeMap as HashMap;
+            use std::collections::BTreeSet as HashSet;
+            fn f(n: usize) -> HashSet<i32> {
+                let m: HashMap<i32, i32> = HashMap::new();
+                let s: HashSet<i32> = HashSet::new();
+                s
+            }
+        };
+        assert_eq!(rewrite(code, HashBackend::BTree), expected.to_string());
+    }
+
+    #[test]
+    fn test_embedded_profile_selects_btree() {
+        let options = CodeGenOptions {
+            type_profile: TypeProfile::Embedded,
+            ..Default::default()
+        };
+        assert_eq!(
+            select_hash_backend(&[], &options).unwrap(),
+            HashBackend::BTree
+        );
+        let explicit = CodeGenOptions {
+            hash_backend: Some(HashBackend::Fnv),
+            ..options
+        };
+        assert_eq!(
+            select_hash_backend(&[], &explicit).unwrap(),
+            HashBackend::Fnv
+        );
+    }
+}
@@ -3,7 +3,10 @@
 //! This module handles Python import statements and maps them to Rust
 //! use statements through the module mapper.
 
-use crate::hir::{Import, ImportItem};
+use crate::hir::{Import, ImportItem, Type};
+use crate::module_mapper::{ModuleMapper, ProjectModule};
+use crate::rust_gen::context::CodeGenContext;
+use std::collections::BTreeMap;
 
 /// Process a whole module import (e.g., `import math`)
 ///
@@ -67,6 +70,11 @@ fn process_specific_items_import(
     module_mapper: &crate::module_mapper::ModuleMapper,
     imported_items: &mut std::collections::HashMap<String, String>,
 ) {
+    // Project modules are brought in by `use`, so calls into them are
+    // generated like calls to the module's own functions
+    if module_mapper.is_project_module(&import.module) {
+        return;
+    }
     if let Some(mapping) = module_mapper.get_mapping(&import.module) {
         for item in &import.items {
             match item {
@@ -117,3 +125,57 @@ pub fn process_module_imports(
 
     (imported_modules, imported_items)
 }
+
+/// The standard module mapper, plus the other modules of the project
+pub fn project_module_mapper(project_modules: &BTreeMap<String, ProjectModule>) -> ModuleMapper {
+    let mut mapper = ModuleMapper::new();
+    for (name, module) in project_modules {
+        mapper.add_project_module(name, module);
+    }
+    mapper
+}
+
+/// Records the signatures of functions imported from other project modules,
+/// so calls to them borrow arguments and unwrap results as local calls do
+pub fn register_project_imports(
+    imports: &[Import],
+    project_modules: &BTreeMap<String, ProjectModule>,
+    ctx: &mut CodeGenContext,
+) {
+    for import in imports {
+        let module = match project_modules.get(&import.module) {
+            Some(module) => module,
+            None => continue,
+        };
+        for item in &import.items {
+            let (name, local) = match item {
+                ImportItem::Named(name) => (name, name),
+                ImportItem::Aliased { name, alias } => (name, alias),
+            };
+            if module.classes.contains(name) {
+                ctx.class_names.insert(local.clone());
+            }
+            let function = match module.functions.get(name) {
+                Some(function) => function,
+                None => continue,
+            };
+            ctx.function_return_types
+                .insert(local.clone(), function.ret_type.clone());
+            ctx.function_param_borrows
+                .insert(local.clone(), function.param_borrows.clone());
+            if let Some(position) = function.borrowed_return_param {
+                ctx.borrowed_return_functions
+                    .insert(local.clone(), position);
+            }
+            if function.cow_return {
+                ctx.cow_return_functions.insert(local.clone());
+            }
+            if function.can_fail {
+                ctx.result_returning_functions.insert(local.clone());
+                if matches!(function.ret_type, Type::Bool) {
+                    ctx.result_bool_functions.insert(local.clone());
+                }
+            }
+        }
+    }
+}
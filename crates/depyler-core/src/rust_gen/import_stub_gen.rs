@@ -0,0 +1,860 @@
+//! `todo!()` stubs for imports without a Rust mapping
+//!
+//! An import the module mapper does not know used to leave only a doc
+//! comment behind, and every call into that module then failed to compile.
+//! Each unmapped module the code calls into now gets a Rust module of stubs
+//! named after it: a function for every function called, and a unit struct
+//! for every class used, with a `new` constructor and the methods called on
+//! its instances. Bodies are `todo!("port <module>.<name>")`, so the crate
+//! builds and searching for `todo!("port` lists what is left to port.
+//!
+//! Signatures come from the module's `.pyi` stub when one was supplied via
+//! [`CodeGenOptions::pyi_stubs`](super::CodeGenOptions::pyi_stubs), and
+//! otherwise from the call sites: argument types from literals and typed
+//! variables, return types from the annotation or function return the
+//! result flows into. Anything still unknown becomes a generic parameter.
+
+use crate::ast_bridge::TypeExtractor;
+use crate::hir::{
+    AssignTarget, BinOp, HirExpr, HirModule, HirParam, HirStmt, Import, ImportItem, Literal, Type,
+};
+use crate::module_mapper::{ModuleMapper, ModuleMapping};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::keywords::is_rust_keyword;
+use crate::rust_gen::type_gen::rust_type_to_syn;
+use anyhow::{anyhow, Result};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use rustpython_ast as ast;
+use std::collections::{BTreeMap, HashMap};
+
+/// Stubs for one unmapped Python module
+#[derive(Debug, Default)]
+pub(crate) struct StubModule {
+    pub(crate) module: String,
+    functions: BTreeMap<String, Signature>,
+    classes: BTreeMap<String, StubClass>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct StubClass {
+    constructor: Option<Signature>,
+    methods: BTreeMap<String, Signature>,
+}
+
+#[derive(Debug, Clone)]
+struct Signature {
+    /// Parameter names, known only from a `.pyi` stub
+    names: Option<Vec<String>>,
+    params: Vec<Type>,
+    ret: Type,
+    /// Some caller uses the result
+    ret_used: bool,
+}
+
+impl Signature {
+    /// Widens a usage-based signature with another call site; `.pyi`
+    /// signatures are kept as declared
+    fn merge(&mut self, call: Signature) {
+        if self.names.is_some() {
+            return;
+        }
+        if call.params.len() > self.params.len() {
+            self.params.resize(call.params.len(), Type::Unknown);
+        }
+        for (ty, seen) in self.params.iter_mut().zip(call.params) {
+            *ty = merge_type(ty, seen);
+        }
+        self.ret = merge_type(&self.ret, call.ret);
+        self.ret_used |= call.ret_used;
+    }
+}
+
+/// Call sites that disagree on a type leave it generic
+fn merge_type(known: &Type, seen: Type) -> Type {
+    match (known, seen) {
+        (Type::Unknown, seen) => seen,
+        (known, Type::Unknown) => known.clone(),
+        (known, seen) if *known == seen => seen,
+        _ => Type::Unknown,
+    }
+}
+
+/// Stubs needed by the unmapped imports of `module`, in module name order
+pub(crate) fn collect_stubs(
+    module: &HirModule,
+    mapper: &ModuleMapper,
+    pyi_stubs: &BTreeMap<String, String>,
+) -> Result<Vec<StubModule>> {
+    let mut collector = Collector::default();
+    for import in module
+        .imports
+        .iter()
+        .filter(|i| is_unmapped(&i.module, mapper))
+    {
+        if !collector.pyi.contains_key(&import.module) {
+            if let Some(source) = pyi_stubs.get(&import.module) {
+                let pyi = parse_pyi(&import.module, source)?;
+                collector.pyi.insert(import.module.clone(), pyi);
+            }
+        }
+        if import.items.is_empty() {
+            collector.bindings.insert(
+                import.module.clone(),
+                Binding::Module(import.module.clone()),
+            );
+        }
+        for item in &import.items {
+            let (name, local) = match item {
+                ImportItem::Named(name) => (name, name),
+                ImportItem::Aliased { name, alias } => (name, alias),
+            };
+            let binding = Binding::Item {
+                module: import.module.clone(),
+                name: name.clone(),
+            };
+            if collector.is_class(&import.module, name) {
+                collector
+                    .instance_types
+                    .insert(local.clone(), (import.module.clone(), name.clone()));
+            }
+            collector.bindings.insert(local.clone(), binding);
+        }
+    }
+    if collector.bindings.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    for constant in &module.constants {
+        let expected = constant.type_annotation.clone().unwrap_or(Type::Unknown);
+        collector.expr(&constant.value, Usage::Value(expected), &HashMap::new());
+    }
+    for function in &module.functions {
+        collector.function(&function.params, &function.ret_type, &function.body);
+    }
+    for method in module.classes.iter().flat_map(|c| &c.methods) {
+        collector.function(&method.params, &method.ret_type, &method.body);
+    }
+    Ok(collector.stubs.into_values().collect())
+}
+
+fn is_unmapped(module: &str, mapper: &ModuleMapper) -> bool {
+    !module.is_empty()
+        && mapper.get_mapping(module).is_none()
+        && !ModuleMapper::is_lowered_inline(module)
+        && !is_rust_keyword(&stub_module_name(module))
+}
+
+/// What a local name refers to
+#[derive(Debug, Clone)]
+enum Binding {
+    /// `import lib`
+    Module(String),
+    /// `from lib import name`
+    Item { module: String, name: String },
+}
+
+/// How a call's result is used
+enum Usage {
+    Discarded,
+    Value(Type),
+}
+
+#[derive(Default)]
+struct Collector {
+    bindings: HashMap<String, Binding>,
+    pyi: HashMap<String, PyiModule>,
+    /// Type names of stub class instances -> (module, class)
+    instance_types: HashMap<String, (String, String)>,
+    stubs: BTreeMap<String, StubModule>,
+}
+
+impl Collector {
+    fn is_class(&self, module: &str, name: &str) -> bool {
+        match self.pyi.get(module) {
+            Some(pyi) if pyi.classes.contains_key(name) => true,
+            Some(pyi) if pyi.functions.contains_key(name) => false,
+            _ => name.starts_with(|c: char| c.is_ascii_uppercase()),
+        }
+    }
+
+    fn stub(&mut self, module: &str) -> &mut StubModule {
+        self.stubs
+            .entry(module.to_string())
+            .or_insert_with(|| StubModule {
+                module: module.to_string(),
+                ..StubModule::default()
+            })
+    }
+
+    fn function(&mut self, params: &[HirParam], ret: &Type, body: &[HirStmt]) {
+        let mut env: HashMap<String, Type> = HashMap::new();
+        for param in params {
+            self.mention(&param.ty);
+            env.insert(param.name.clone(), param.ty.clone());
+        }
+        self.mention(ret);
+        self.stmts(body, ret, &mut env);
+    }
+
+    /// Classes named in annotations get a struct even if never constructed
+    fn mention(&mut self, ty: &Type) {
+        match ty {
+            Type::Custom(name) => {
+                if let Some((module, class)) = self.instance_types.get(name).cloned() {
+                    self.class(&module, &class);
+                }
+            }
+            Type::List(inner) | Type::Set(inner) | Type::Optional(inner) => self.mention(inner),
+            Type::Dict(key, value) => {
+                self.mention(key);
+                self.mention(value);
+            }
+            Type::Tuple(items) => items.iter().for_each(|item| self.mention(item)),
+            _ => {}
+        }
+    }
+
+    fn class(&mut self, module: &str, class: &str) -> &mut StubClass {
+        let declared = self
+            .pyi
+            .get(module)
+            .and_then(|pyi| pyi.classes.get(class))
+            .cloned();
+        self.stub(module)
+            .classes
+            .entry(class.to_string())
+            .or_insert_with(|| declared.unwrap_or_default())
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt], ret: &Type, env: &mut HashMap<String, Type>) {
+        for stmt in stmts {
+            self.stmt(stmt, ret, env);
+        }
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, ret: &Type, env: &mut HashMap<String, Type>) {
+        let value = || Usage::Value(Type::Unknown);
+        match stmt {
+            HirStmt::Assign {
+                target,
+                value: assigned,
+                type_annotation,
+            } => {
+                if let Some(annotation) = type_annotation {
+                    self.mention(annotation);
+                }
+                let expected = type_annotation.clone().unwrap_or(Type::Unknown);
+                let ty = self.expr(assigned, Usage::Value(expected), env);
+                match target {
+                    AssignTarget::Symbol(name) => {
+                        env.insert(name.clone(), type_annotation.clone().unwrap_or(ty));
+                    }
+                    AssignTarget::Index { base, index } => {
+                        self.expr(base, value(), env);
+                        self.expr(index, value(), env);
+                    }
+                    AssignTarget::Attribute { value: object, .. } => {
+                        self.expr(object, value(), env);
+                    }
+                    AssignTarget::Tuple(_) => {}
+                }
+            }
+            HirStmt::Return(Some(returned)) => {
+                self.expr(returned, Usage::Value(ret.clone()), env);
+            }
+            HirStmt::Expr(expr) => {
+                self.expr(expr, Usage::Discarded, env);
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition, value(), env);
+                self.stmts(then_body, ret, env);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body, ret, env);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition, value(), env);
+                self.stmts(body, ret, env);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter, value(), env);
+                self.stmts(body, ret, env);
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context, value(), env);
+                self.stmts(body, ret, env);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body, ret, env);
+                for handler in handlers {
+                    self.stmts(&handler.body, ret, env);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block, ret, env);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject, value(), env);
+                for case in cases {
+                    self.stmts(&case.body, ret, env);
+                }
+            }
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr, value(), env);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.expr(test, value(), env);
+                if let Some(msg) = msg {
+                    self.expr(msg, value(), env);
+                }
+            }
+            HirStmt::Return(None)
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+            | HirStmt::Pass => {}
+        }
+    }
+
+    /// Records the stub calls in `expr` and returns its type, if evident
+    fn expr(&mut self, expr: &HirExpr, usage: Usage, env: &HashMap<String, Type>) -> Type {
+        match expr {
+            HirExpr::Call { func, args, kwargs } => {
+                let args = self.args(args, kwargs, env);
+                match self.bindings.get(func).cloned() {
+                    Some(Binding::Item { module, name }) => {
+                        self.call(&module, &name, func, args, usage)
+                    }
+                    _ => builtin_result_type(func),
+                }
+            }
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                kwargs,
+            } => {
+                let module = match &**object {
+                    HirExpr::Var(local) if !env.contains_key(local) => {
+                        match self.bindings.get(local) {
+                            Some(Binding::Module(module)) => Some(module.clone()),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+                if let Some(module) = module {
+                    let args = self.args(args, kwargs, env);
+                    let local = format!("{}.{}", module, method);
+                    if self.is_class(&module, method) {
+                        self.instance_types
+                            .insert(local.clone(), (module.clone(), method.clone()));
+                    }
+                    return self.call(&module, method, &local, args, usage);
+                }
+                let receiver = self.expr(object, Usage::Value(Type::Unknown), env);
+                let args = self.args(args, kwargs, env);
+                match receiver {
+                    Type::Custom(ty)
+                        if self.instance_types.contains_key(&ty) && !is_rust_keyword(method) =>
+                    {
+                        let (module, class) = self.instance_types[&ty].clone();
+                        let call = call_signature(args, usage);
+                        let ret = call.ret.clone();
+                        let class = self.class(&module, &class);
+                        match class.methods.get_mut(method) {
+                            Some(known) => {
+                                known.merge(call);
+                                known.ret.clone()
+                            }
+                            None => {
+                                class.methods.insert(method.clone(), call);
+                                ret
+                            }
+                        }
+                    }
+                    _ => Type::Unknown,
+                }
+            }
+            _ => {
+                let children: Vec<Type> = expr
+                    .children()
+                    .into_iter()
+                    .map(|child| self.expr(child, Usage::Value(Type::Unknown), env))
+                    .collect();
+                match expr {
+                    HirExpr::Literal(literal) => literal_type(literal),
+                    HirExpr::Var(name) => env.get(name).cloned().unwrap_or(Type::Unknown),
+                    HirExpr::Binary { op, .. } => match op {
+                        BinOp::Eq
+                        | BinOp::NotEq
+                        | BinOp::Lt
+                        | BinOp::LtEq
+                        | BinOp::Gt
+                        | BinOp::GtEq
+                        | BinOp::In
+                        | BinOp::NotIn => Type::Bool,
+                        _ => children
+                            .into_iter()
+                            .find(|ty| !matches!(ty, Type::Unknown))
+                            .unwrap_or(Type::Unknown),
+                    },
+                    HirExpr::List(_) => match children.first() {
+                        Some(Type::Unknown) | None => Type::Unknown,
+                        Some(elem) => Type::List(Box::new(elem.clone())),
+                    },
+                    HirExpr::Tuple(_) => Type::Tuple(children),
+                    _ => Type::Unknown,
+                }
+            }
+        }
+    }
+
+    /// Positional argument types; keyword arguments are only searched for
+    /// nested calls, since calls into a stub pass positional arguments only
+    fn args(
+        &mut self,
+        args: &[HirExpr],
+        kwargs: &[(String, HirExpr)],
+        env: &HashMap<String, Type>,
+    ) -> Vec<Type> {
+        for (_, value) in kwargs {
+            self.expr(value, Usage::Value(Type::Unknown), env);
+        }
+        args.iter()
+            .map(
+                |arg| match self.expr(arg, Usage::Value(Type::Unknown), env) {
+                    // Stub instances are accepted generically
+                    Type::Custom(ty) if self.instance_types.contains_key(&ty) => Type::Unknown,
+                    ty => ty,
+                },
+            )
+            .collect()
+    }
+
+    /// Records a call to `module.name`, written `local` at the call site
+    fn call(
+        &mut self,
+        module: &str,
+        name: &str,
+        local: &str,
+        args: Vec<Type>,
+        usage: Usage,
+    ) -> Type {
+        // Keyword names keep the untranslated call
+        if is_rust_keyword(name) {
+            return Type::Unknown;
+        }
+        let call = call_signature(args, usage);
+        if self.is_class(module, name) {
+            let class = self.class(module, name);
+            match &mut class.constructor {
+                Some(known) => known.merge(call),
+                None => class.constructor = Some(call),
+            }
+            return Type::Custom(local.to_string());
+        }
+        let declared = self
+            .pyi
+            .get(module)
+            .and_then(|pyi| pyi.functions.get(name))
+            .cloned();
+        let functions = &mut self.stub(module).functions;
+        let signature = functions
+            .entry(name.to_string())
+            .or_insert_with(|| declared.unwrap_or_else(|| call.clone()));
+        signature.merge(call);
+        signature.ret.clone()
+    }
+}
+
+fn call_signature(params: Vec<Type>, usage: Usage) -> Signature {
+    let (ret, ret_used) = match usage {
+        Usage::Discarded => (Type::Unknown, false),
+        Usage::Value(ty) => (ty, true),
+    };
+    Signature {
+        names: None,
+        params,
+        ret,
+        ret_used,
+    }
+}
+
+fn literal_type(literal: &Literal) -> Type {
+    match literal {
+        Literal::Int(_) => Type::Int,
+        Literal::Float(_) => Type::Float,
+        Literal::String(_) => Type::String,
+        Literal::Bool(_) => Type::Bool,
+        Literal::Bytes(_) | Literal::None => Type::Unknown,
+    }
+}
+
+fn builtin_result_type(func: &str) -> Type {
+    match func {
+        "len" | "int" => Type::Int,
+        "float" => Type::Float,
+        "str" => Type::String,
+        "bool" => Type::Bool,
+        _ => Type::Unknown,
+    }
+}
+
+/// Signatures declared by a `.pyi` stub
+#[derive(Debug, Default)]
+struct PyiModule {
+    functions: HashMap<String, Signature>,
+    classes: HashMap<String, StubClass>,
+}
+
+fn parse_pyi(module: &str, source: &str) -> Result<PyiModule> {
+    use rustpython_parser::Parse;
+
+    let suite = ast::Suite::parse(source, &format!("{}.pyi", module))
+        .map_err(|e| anyhow!("invalid .pyi stub for `{}`: {}", module, e))?;
+    let mut pyi = PyiModule::default();
+    for stmt in &suite {
+        match stmt {
+            // Later `@overload`s of the same name are ignored
+            ast::Stmt::FunctionDef(f) => {
+                pyi.functions
+                    .entry(f.name.to_string())
+                    .or_insert_with(|| pyi_signature(&f.args, &f.returns, false));
+            }
+            ast::Stmt::ClassDef(c) => {
+                let mut class = StubClass::default();
+                for item in &c.body {
+                    if let ast::Stmt::FunctionDef(m) = item {
+                        let signature = pyi_signature(&m.args, &m.returns, true);
+                        if m.name.as_str() == "__init__" {
+                            class.constructor.get_or_insert(signature);
+                        } else {
+                            class.methods.entry(m.name.to_string()).or_insert(signature);
+                        }
+                    }
+                }
+                pyi.classes.insert(c.name.to_string(), class);
+            }
+            _ => {}
+        }
+    }
+    Ok(pyi)
+}
+
+fn pyi_signature(
+    args: &ast::Arguments,
+    returns: &Option<Box<ast::Expr>>,
+    method: bool,
+) -> Signature {
+    let receiver = args
+        .args
+        .first()
+        .is_some_and(|arg| matches!(arg.def.arg.as_str(), "self" | "cls"));
+    let params = &args.args[usize::from(method && receiver)..];
+    let ret = returns.as_deref().map_or(Type::Unknown, |ret| {
+        TypeExtractor::extract_type(ret).unwrap_or(Type::Unknown)
+    });
+    Signature {
+        names: Some(params.iter().map(|p| p.def.arg.to_string()).collect()),
+        params: params
+            .iter()
+            .map(|p| {
+                p.def.annotation.as_deref().map_or(Type::Unknown, |ann| {
+                    TypeExtractor::extract_type(ann).unwrap_or(Type::Unknown)
+                })
+            })
+            .collect(),
+        ret_used: !matches!(ret, Type::None),
+        ret,
+    }
+}
+
+/// Rust module holding the stubs for Python module `module`
+pub(crate) fn stub_module_name(module: &str) -> String {
+    module.replace('.', "_")
+}
+
+/// Points calls into stubbed modules at the stubs
+pub(crate) fn register_stubs(
+    stubs: &[StubModule],
+    imports: &[Import],
+    imported_modules: &mut HashMap<String, ModuleMapping>,
+    imported_items: &mut HashMap<String, String>,
+) {
+    for stub in stubs {
+        let rust_module = stub_module_name(&stub.module);
+        let path_of = |name: &str| -> Option<String> {
+            if stub.classes.contains_key(name) {
+                Some(format!("{}::new", name))
+            } else if stub.functions.contains_key(name) {
+                Some(name.to_string())
+            } else {
+                None
+            }
+        };
+        for import in imports.iter().filter(|i| i.module == stub.module) {
+            if import.items.is_empty() {
+                let item_map = stub
+                    .functions
+                    .keys()
+                    .chain(stub.classes.keys())
+                    .filter_map(|name| Some((name.clone(), path_of(name)?)))
+                    .collect();
+                imported_modules.insert(
+                    stub.module.clone(),
+                    ModuleMapping {
+                        rust_path: rust_module.clone(),
+                        is_external: false,
+                        version: None,
+                        item_map,
+                    },
+                );
+            }
+            for item in &import.items {
+                let (name, local) = match item {
+                    ImportItem::Named(name) => (name, name),
+                    ImportItem::Aliased { name, alias } => (name, alias),
+                };
+                if let Some(path) = path_of(name) {
+                    imported_items.insert(local.clone(), format!("{}::{}", rust_module, path));
+                }
+            }
+        }
+    }
+}
+
+/// The stub modules, plus `use` items for classes imported by name
+pub(crate) fn generate_stub_modules(
+    stubs: &[StubModule],
+    imports: &[Import],
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<TokenStream>> {
+    let mut items = Vec::new();
+    for stub in stubs {
+        let module_ident = format_ident!("{}", stub_module_name(&stub.module));
+        let mut body = Vec::new();
+        let mut uses_outer = false;
+
+        for (name, signature) in &stub.functions {
+            let todo = format!("port {}.{}", stub.module, name);
+            uses_outer |= mentions_outer_types(signature);
+            body.push(stub_fn(name, signature, None, &todo, ctx)?);
+        }
+        for (class, stub_class) in &stub.classes {
+            let class_ident = format_ident!("{}", class);
+            let mut methods = Vec::new();
+            if let Some(constructor) = &stub_class.constructor {
+                let todo = format!("port {}.{}", stub.module, class);
+                uses_outer |= mentions_outer_types(constructor);
+                methods.push(stub_fn(
+                    "new",
+                    constructor,
+                    Some(Receiver::New),
+                    &todo,
+                    ctx,
+                )?);
+            }
+            for (name, signature) in &stub_class.methods {
+                let todo = format!("port {}.{}.{}", stub.module, class, name);
+                uses_outer |= mentions_outer_types(signature);
+                methods.push(stub_fn(name, signature, Some(Receiver::Ref), &todo, ctx)?);
+            }
+            body.push(quote! {
+                #[derive(Debug, Clone)]
+                pub struct #class_ident;
+            });
+            if !methods.is_empty() {
+                body.push(quote! {
+                    impl #class_ident {
+                        #(#methods)*
+                    }
+                });
+            }
+        }
+
+        let outer = uses_outer.then(|| quote! { use super::*; });
+        let doc = format!(
+            " Stubs for Python module `{}`, which has no Rust mapping yet",
+            stub.module
+        );
+        items.push(quote! {
+            #[doc = #doc]
+            pub mod #module_ident {
+                #outer
+                #(#body)*
+            }
+        });
+
+        for import in imports.iter().filter(|i| i.module == stub.module) {
+            for item in &import.items {
+                let (name, alias) = match item {
+                    ImportItem::Named(name) => (name, None),
+                    ImportItem::Aliased { name, alias } => (name, Some(alias)),
+                };
+                if !stub.classes.contains_key(name) {
+                    continue;
+                }
+                let class_ident = format_ident!("{}", name);
+                items.push(match alias {
+                    Some(alias) => {
+                        let alias = format_ident!("{}", alias);
+                        quote! { use #module_ident::#class_ident as #alias; }
+                    }
+                    None => quote! { use #module_ident::#class_ident; },
+                });
+            }
+        }
+    }
+    Ok(items)
+}
+
+enum Receiver {
+    /// Constructor returning `Self`
+    New,
+    /// Method taking `&self`
+    Ref,
+}
+
+fn stub_fn(
+    name: &str,
+    signature: &Signature,
+    receiver: Option<Receiver>,
+    todo: &str,
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let name = format_ident!("{}", name);
+    let mut generics = Vec::new();
+    let mut params = Vec::new();
+    if let Some(Receiver::Ref) = receiver {
+        params.push(quote! { &self });
+    }
+    for (i, ty) in signature.params.iter().enumerate() {
+        let param = match &signature.names {
+            Some(names) => format_ident!("_{}", names[i]),
+            None => format_ident!("_arg{}", i),
+        };
+        let ty = match ty {
+            Type::Unknown => {
+                let generic = format_ident!("A{}", i);
+                generics.push(generic.clone());
+                quote! { #generic }
+            }
+            // Callers pass both `&str` and `String`
+            Type::String => quote! { impl Into<String> },
+            ty => stub_type(ty, ctx)?,
+        };
+        params.push(quote! { #param: #ty });
+    }
+    let ret = match (&receiver, &signature.ret) {
+        (Some(Receiver::New), _) => Some(quote! { Self }),
+        (_, Type::Unknown) if signature.ret_used => {
+            generics.push(format_ident!("R"));
+            Some(quote! { R })
+        }
+        (_, Type::Unknown | Type::None) => None,
+        (_, ty) => Some(stub_type(ty, ctx)?),
+    };
+    let generics = (!generics.is_empty()).then(|| quote! { <#(#generics),*> });
+    let ret = ret.map(|ret| quote! { -> #ret });
+    Ok(quote! {
+        pub fn #name #generics(#(#params),*) #ret {
+            todo!(#todo)
+        }
+    })
+}
+
+fn stub_type(ty: &Type, ctx: &mut CodeGenContext) -> Result<TokenStream> {
+    mark_collection_imports(ty, ctx);
+    let rust_type = rust_type_to_syn(&ctx.type_mapper.map_type(ty))?;
+    Ok(quote! { #rust_type })
+}
+
+fn mark_collection_imports(ty: &Type, ctx: &mut CodeGenContext) {
+    match ty {
+        Type::Dict(key, value) => {
+            ctx.needs_hashmap = true;
+            mark_collection_imports(key, ctx);
+            mark_collection_imports(value, ctx);
+        }
+        Type::Set(inner) => {
+            ctx.needs_hashset = true;
+            mark_collection_imports(inner, ctx);
+        }
+        Type::List(inner) | Type::Optional(inner) => mark_collection_imports(inner, ctx),
+        Type::Tuple(items) => items
+            .iter()
+            .for_each(|item| mark_collection_imports(item, ctx)),
+        _ => {}
+    }
+}
+
+/// Whether a signature names types defined outside the stub module
+fn mentions_outer_types(signature: &Signature) -> bool {
+    fn outer(ty: &Type) -> bool {
+        match ty {
+            Type::Int | Type::Float | Type::Bool | Type::String | Type::None | Type::Unknown => {
+                false
+            }
+            Type::List(inner) | Type::Optional(inner) => outer(inner),
+            Type::Tuple(items) => items.iter().any(outer),
+            _ => true,
+        }
+    }
+    signature.params.iter().any(outer) || outer(&signature.ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn int_call(args: usize) -> Signature {
+        call_signature(vec![Type::Int; args], Usage::Value(Type::Unknown))
+    }
+
+    #[test]
+    fn test_disagreeing_call_sites_leave_a_generic() {
+        let mut signature = int_call(1);
+        signature.merge(call_signature(
+            vec![Type::String, Type::Bool],
+            Usage::Discarded,
+        ));
+        assert_eq!(signature.params, vec![Type::Unknown, Type::Bool]);
+        assert!(signature.ret_used);
+    }
+
+    #[test]
+    fn test_pyi_signature_is_kept_as_declared() {
+        let pyi = parse_pyi(
+            "geo",
+            "class Point:\n    def __init__(self, x: float, y: float) -> None: ...\n    def norm(self) -> float: ...\n\ndef distance(a: Point, b: Point) -> float: ...\n",
+        )
+        .unwrap();
+        let mut distance = pyi.functions["distance"].clone();
+        distance.merge(int_call(3));
+        assert_eq!(
+            distance.names.as_deref(),
+            Some(&["a".to_string(), "b".to_string()][..])
+        );
+        assert_eq!(distance.params.len(), 2);
+        assert_eq!(distance.ret, Type::Float);
+
+        let point = &pyi.classes["Point"];
+        assert_eq!(
+            point.constructor.as_ref().unwrap().params,
+            vec![Type::Float, Type::Float]
+        );
+        assert_eq!(point.methods["norm"].ret, Type::Float);
+    }
+}
@@ -0,0 +1,362 @@
+//! `int` arithmetic under the `integer_semantics` of a function
+//!
+//! Python's `int` never overflows, while the machine integer it maps to does.
+//! Each function has one behavior for that: its `integer_semantics`
+//! annotation, else the module's default from `CodeGenOptions`, settled by
+//! the `integer-semantics` HIR pass. `panic`, used when neither asks for
+//! anything else, keeps the plain operators, which panic in debug builds and
+//! wrap in release builds. The other modes generate all int arithmetic of
+//! the function here:
+//!
+//! | Python   | `wrapping` (`saturating` alike) | `checked`                                   |
+//! |----------|---------------------------------|---------------------------------------------|
+//! | `a + b`  | `i32::wrapping_add(a, b)`       | `i32::checked_add(a, b)` or `OverflowError` |
+//! | `a * 2`  | `i32::wrapping_mul(a, 2)`       | `i32::checked_mul(a, 2)` or `OverflowError` |
+//! | `a ** 3` | `i32::wrapping_pow(a, 3)`       | `i32::checked_pow(a, 3)` or `OverflowError` |
+//! | `-a`     | `i32::wrapping_neg(a)`          | `i32::checked_neg(a)` or `OverflowError`    |
+//!
+//! `bigint` maps `int` to `num_bigint::BigInt` instead:
+//!
+//! | Python   | `bigint`                            |
+//! |----------|-------------------------------------|
+//! | `a + b`  | `&a + &b`                           |
+//! | `a * 2`  | `&a * &num_bigint::BigInt::from(2)` |
+//! | `a < 2`  | `a < num_bigint::BigInt::from(2)`   |
+//! | `a % b`  | `((a % b) + b) % b` on references   |
+//! | `a ** 3` | `a.pow(3)`                          |
+//! | `-a`     | `-&a`                               |
+//!
+//! The `OverflowError` of `checked` propagates like the other predefined
+//! errors. Under `bigint` operands are borrowed so variables stay usable, and
+//! int literals assigned, returned or combined with a `BigInt` become one;
+//! indices and `range` bounds remain machine integers.
+
+use crate::hir::{BinOp, HirExpr, HirFunction, Literal, Type, UnaryOp};
+use crate::lifetime_analysis::LifetimeResult;
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::error_gen;
+use crate::rust_gen::ledger::DivergenceKind;
+use crate::rust_gen::type_gen::{convert_binop, rust_type_to_syn};
+use crate::type_mapper::RustType;
+use anyhow::{bail, Result};
+use depyler_annotations::IntegerSemantics;
+use quote::format_ident;
+use syn::parse_quote;
+
+/// `left op right` on two ints under the current function's
+/// `integer_semantics`, or `None` when the default lowering applies
+pub(crate) fn codegen_int_binary(
+    op: BinOp,
+    left: &HirExpr,
+    right: &HirExpr,
+    left_expr: &syn::Expr,
+    right_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if ctx.value_type(left) != Type::Int || ctx.value_type(right) != Type::Int {
+        return Ok(None);
+    }
+    record_int_width(op, ctx);
+    if ctx.integer_semantics == IntegerSemantics::Panic {
+        return Ok(None);
+    }
+    if ctx.integer_semantics == IntegerSemantics::BigInt {
+        return codegen_bigint(op, left, right, left_expr, right_expr);
+    }
+    let (operation, description) = match op {
+        BinOp::Add => ("add", "addition"),
+        BinOp::Sub => ("sub", "subtraction"),
+        BinOp::Mul => ("mul", "multiplication"),
+        BinOp::Pow => ("pow", "exponentiation"),
+        _ => return Ok(None),
+    };
+    let right_expr = match op {
+        BinOp::Pow => match exponent(right, right_expr) {
+            Some(exp) => exp,
+            // A negative power is a float, as in Python
+            None => return Ok(None),
+        },
+        _ => right_expr.clone(),
+    };
+    Ok(codegen_machine_op(
+        operation,
+        description,
+        &[left_expr.clone(), right_expr],
+        ctx,
+    ))
+}
+
+/// `-operand` on an int under the current function's `integer_semantics`,
+/// or `None` when the plain operator applies
+pub(crate) fn codegen_int_neg(
+    operand: &HirExpr,
+    operand_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> Option<syn::Expr> {
+    // `-5` is a constant, not an operation that can overflow
+    if ctx.integer_semantics == IntegerSemantics::Panic
+        || matches!(operand, HirExpr::Literal(_))
+        || ctx.value_type(operand) != Type::Int
+    {
+        return None;
+    }
+    if ctx.integer_semantics == IntegerSemantics::BigInt {
+        let operand = bigint_operand(operand, operand_expr);
+        return Some(parse_quote! { -&#operand });
+    }
+    codegen_machine_op("neg", "negation", std::slice::from_ref(operand_expr), ctx)
+}
+
+/// Notes in the ledger that `op` may leave the machine integer's range,
+/// which Python's `int` never does
+fn record_int_width(op: BinOp, ctx: &mut CodeGenContext) {
+    if !matches!(
+        op,
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Pow | BinOp::LShift
+    ) {
+        return;
+    }
+    let int_type = match ctx.type_mapper.map_type(&Type::Int) {
+        RustType::Primitive(primitive) => primitive.to_rust_string(),
+        _ => return,
+    };
+    let on_overflow = match ctx.integer_semantics {
+        IntegerSemantics::Panic => "panics on overflow in debug builds and wraps in release builds",
+        IntegerSemantics::Wrapping => "wraps on overflow",
+        IntegerSemantics::Saturating => "saturates on overflow",
+        IntegerSemantics::Checked => "raises OverflowError on overflow",
+        IntegerSemantics::BigInt => return,
+    };
+    ctx.divergences.record(
+        DivergenceKind::IntWidth,
+        format!("`int` arithmetic is {} and {}", int_type, on_overflow),
+    );
+}
+
+/// `i32::wrapping_add(a, b)` and the other `wrapping_*`, `saturating_*`
+/// and `checked_*` forms of `operation`, the checked ones failing with
+/// `OverflowError`
+///
+/// The associated function form also works for unsuffixed literals.
+fn codegen_machine_op(
+    operation: &str,
+    description: &str,
+    operands: &[syn::Expr],
+    ctx: &mut CodeGenContext,
+) -> Option<syn::Expr> {
+    let prefix = match ctx.integer_semantics {
+        IntegerSemantics::Wrapping => "wrapping",
+        IntegerSemantics::Saturating => "saturating",
+        IntegerSemantics::Checked => "checked",
+        IntegerSemantics::Panic | IntegerSemantics::BigInt => return None,
+    };
+    let int_type = rust_type_to_syn(&ctx.type_mapper.map_type(&Type::Int)).ok()?;
+    let method = format_ident!("{}_{}", prefix, operation);
+    let call: syn::Expr = parse_quote! { #int_type::#method(#(#operands),*) };
+    if ctx.integer_semantics != IntegerSemantics::Checked {
+        return Some(call);
+    }
+    let message = format!("integer {} overflowed", description);
+    ctx.needs_overflowerror = true;
+    let call = parse_quote! { #call.ok_or_else(|| OverflowError::new(#message)) };
+    Some(error_gen::handle_builtin_error(call, "OverflowError", ctx))
+}
+
+/// The `u32` exponent of `**`, or `None` for a negative literal
+fn exponent(right: &HirExpr, right_expr: &syn::Expr) -> Option<syn::Expr> {
+    match right {
+        HirExpr::Literal(Literal::Int(exp)) => {
+            let exp = proc_macro2::Literal::u32_unsuffixed(u32::try_from(*exp).ok()?);
+            Some(parse_quote! { #exp })
+        }
+        HirExpr::Var(_) => Some(parse_quote! { #right_expr as u32 }),
+        _ => Some(parse_quote! { (#right_expr) as u32 }),
+    }
+}
+
+fn codegen_bigint(
+    op: BinOp,
+    left: &HirExpr,
+    right: &HirExpr,
+    left_expr: &syn::Expr,
+    right_expr: &syn::Expr,
+) -> Result<Option<syn::Expr>> {
+    let l = bigint_operand(left, left_expr);
+    let r = bigint_operand(right, right_expr);
+    let expr = match op {
+        BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::BitAnd | BinOp::BitOr | BinOp::BitXor => {
+            let rust_op = convert_binop(op)?;
+            parse_quote! { &#l #rust_op &#r }
+        }
+        BinOp::Eq | BinOp::NotEq | BinOp::Lt | BinOp::LtEq | BinOp::Gt | BinOp::GtEq => {
+            let rust_op = convert_binop(op)?;
+            parse_quote! { #l #rust_op #r }
+        }
+        // Python's `%` and `//` round towards negative infinity, `BigInt`'s
+        // towards zero
+        BinOp::Mod => parse_quote! {
+            {
+                let __lhs = &#l;
+                let __rhs = &#r;
+                ((__lhs % __rhs) + __rhs) % __rhs
+            }
+        },
+        BinOp::FloorDiv => parse_quote! {
+            {
+                let __lhs = &#l;
+                let __rhs = &#r;
+                (__lhs - ((__lhs % __rhs) + __rhs) % __rhs) / __rhs
+            }
+        },
+        BinOp::Pow => match right {
+            HirExpr::Literal(Literal::Int(exp)) if u32::try_from(*exp).is_ok() => {
+                let exp = proc_macro2::Literal::u32_unsuffixed(*exp as u32);
+                parse_quote! { #l.pow(#exp) }
+            }
+            _ => bail!(
+                "`**` on arbitrary-precision ints needs a non-negative literal exponent \
+                 (integer_semantics = \"bigint\")"
+            ),
+        },
+        BinOp::Div => bail!(
+            "`/` on arbitrary-precision ints is not supported (integer_semantics = \"bigint\"); \
+             use `//` for integer division"
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(expr))
+}
+
+/// `expr` as a `BigInt` operand: literals become `BigInt`s and compound
+/// expressions are parenthesized so they can be borrowed
+fn bigint_operand(hir: &HirExpr, expr: &syn::Expr) -> syn::Expr {
+    if let Some(literal) = bigint_literal(hir) {
+        return literal;
+    }
+    match expr {
+        syn::Expr::Path(_)
+        | syn::Expr::Field(_)
+        | syn::Expr::Call(_)
+        | syn::Expr::MethodCall(_)
+        | syn::Expr::Index(_)
+        | syn::Expr::Paren(_) => expr.clone(),
+        _ => parse_quote! { (#expr) },
+    }
+}
+
+/// `num_bigint::BigInt::from(n)` for an int literal `n` or `-n`
+fn bigint_literal(hir: &HirExpr) -> Option<syn::Expr> {
+    let value = match hir {
+        HirExpr::Literal(Literal::Int(n)) => *n,
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => match operand.as_ref() {
+            HirExpr::Literal(Literal::Int(n)) => n.checked_neg()?,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let value = proc_macro2::Literal::i64_unsuffixed(value);
+    Some(parse_quote! { num_bigint::BigInt::from(#value) })
+}
+
+/// `expr` where a value of type `expected` is stored: an assignment without
+/// annotation passes `None`
+///
+/// Under `bigint` an int literal becomes a `BigInt`.
+pub(crate) fn codegen_int_value(
+    expr: &HirExpr,
+    expected: Option<&Type>,
+    ctx: &mut CodeGenContext,
+) -> Result<syn::Expr> {
+    let expects_int = match expected {
+        None | Some(Type::Int) => true,
+        Some(Type::Optional(inner)) => **inner == Type::Int,
+        Some(_) => false,
+    };
+    if ctx.integer_semantics == IntegerSemantics::BigInt && expects_int {
+        if let Some(literal) = bigint_literal(expr) {
+            return Ok(literal);
+        }
+    }
+    expr.to_rust_expr(ctx)
+}
+
+/// Rust type of a local annotated with `ty` in the current function
+pub(crate) fn map_local_type(ty: &Type, ctx: &CodeGenContext) -> RustType {
+    match ctx.integer_semantics {
+        IntegerSemantics::BigInt => ctx.type_mapper.clone().with_bigint().map_type(ty),
+        _ => ctx.type_mapper.map_type(ty),
+    }
+}
+
+/// The `integer_semantics` `func` is generated with: its annotation, else
+/// the module's default
+pub(crate) fn function_semantics(func: &HirFunction, ctx: &CodeGenContext) -> IntegerSemantics {
+    func.annotations
+        .integer_semantics
+        .unwrap_or(ctx.options.integer_semantics)
+}
+
+/// Gives the parameters of a `bigint` function their `BigInt` types
+///
+/// Borrowing was decided for the machine integer types, so `int` parameters
+/// stay passed by value like the `i32` they replace.
+pub(crate) fn widen_param_types(
+    func: &HirFunction,
+    lifetime_result: &mut LifetimeResult,
+    ctx: &CodeGenContext,
+) {
+    if function_semantics(func, ctx) != IntegerSemantics::BigInt {
+        return;
+    }
+    let type_mapper = ctx.type_mapper;
+    let bigint_mapper = type_mapper.clone().with_bigint();
+    for param in &func.params {
+        if let Some(inferred) = lifetime_result.param_lifetimes.get_mut(&param.name) {
+            let widened = bigint_mapper.map_type(&param.ty);
+            if widened != type_mapper.map_type(&param.ty) {
+                inferred.rust_type = widened;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_checked_subtraction_propagates_overflow() {
+        let code = transpile(
+            "# @depyler: integer_semantics = \"checked\"\ndef f(a: int, b: int) -> int:\n    return a - b\n",
+        );
+        assert!(code.contains(
+            "i32::checked_sub(a,b).ok_or_else(||OverflowError::new(\"integersubtractionoverflowed\"))?"
+        ));
+        assert!(code.contains("Result<i32,OverflowError>"));
+    }
+
+    #[test]
+    fn test_bigint_floor_mod_and_pow() {
+        let code = transpile(
+            "# @depyler: integer_semantics = \"bigint\"\ndef f(a: int, b: int) -> int:\n    return a % b + a ** 3\n",
+        );
+        assert!(code.contains("let__lhs=&a;let__rhs=&b;((__lhs%__rhs)+__rhs)%__rhs"));
+        assert!(code.contains("a.pow(3)"));
+        assert!(code.contains("fnf(a:num_bigint::BigInt,b:num_bigint::BigInt)->num_bigint::BigInt"));
+    }
+
+    #[test]
+    fn test_negation_follows_the_semantics() {
+        let code = transpile(
+            "# @depyler: integer_semantics = \"bigint\"\ndef f(a: int) -> int:\n    return -a\n",
+        );
+        assert!(code.contains("-&a"));
+        let code = transpile(
+            "# @depyler: integer_semantics = \"saturating\"\ndef f(a: int) -> int:\n    return -a - -1\n",
+        );
+        assert!(code.contains("i32::saturating_sub(i32::saturating_neg(a),-1)"));
+    }
+}
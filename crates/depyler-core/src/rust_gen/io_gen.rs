@@ -0,0 +1,748 @@
+//! Console I/O, through the std handles or handles passed in
+//!
+//! `print()` becomes `println!`, `sys.stdout.write(text)` and
+//! `sys.stderr.write(text)` write to the locked `std::io::stdout()` and
+//! `std::io::stderr()`, and `input()`, `sys.stdin.readline()` and
+//! `sys.stdin.read()` read from the locked `std::io::stdin()`.
+//!
+//! Code printing straight to the process's streams is hard to test. With
+//! [`CodeGenOptions::injectable_io`](super::CodeGenOptions::injectable_io)
+//! the module functions take the streams they use as parameters instead:
+//!
+//! - a function using a stream, or calling a module function that does,
+//!   takes `stdin: &mut impl std::io::BufRead`,
+//!   `stdout: &mut impl std::io::Write` and
+//!   `stderr: &mut impl std::io::Write`, those it needs, before its own
+//!   parameters, and passes them on
+//! - `main()` takes none: it binds stdout and stderr to the std streams,
+//!   which lock on each write, and locks stdin only around each call
+//!   passing it, so a read elsewhere does not wait on the lock
+//! - class methods and nested functions keep using the std streams, and
+//!   pass them to the module functions they call. Reading stdin there is
+//!   rejected: stdin's lock is not reentrant, and the function calling
+//!   them may hold it
+
+use crate::hir::HirExpr;
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::context_manager_gen::handle_io_error;
+use crate::rust_gen::options::ExitCodeOptions;
+use anyhow::{bail, Result};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use syn::parse_quote;
+use syn::visit::{self, Visit};
+use syn::visit_mut::{self as visit_mut, VisitMut};
+
+/// A standard stream, in file descriptor order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stream {
+    Stdin,
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn name(self) -> &'static str {
+        match self {
+            Stream::Stdin => "stdin",
+            Stream::Stdout => "stdout",
+            Stream::Stderr => "stderr",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "stdin" => Some(Stream::Stdin),
+            "stdout" => Some(Stream::Stdout),
+            "stderr" => Some(Stream::Stderr),
+            _ => None,
+        }
+    }
+
+    /// `std::io::stdout().lock()`, the form the rewrite recognizes
+    fn std_handle(self) -> syn::Expr {
+        let open = format_ident!("{}", self.name());
+        parse_quote! { std::io::#open().lock() }
+    }
+
+    /// `std::io::stdout()`, locked by each write rather than for its whole
+    /// lifetime; stdin has no such handle implementing `BufRead`
+    fn shared_handle(self) -> syn::Expr {
+        let open = format_ident!("{}", self.name());
+        parse_quote! { std::io::#open() }
+    }
+
+    fn param_type(self) -> syn::Type {
+        match self {
+            Stream::Stdin => parse_quote! { &mut impl std::io::BufRead },
+            Stream::Stdout | Stream::Stderr => parse_quote! { &mut impl std::io::Write },
+        }
+    }
+}
+
+/// `sys.stdin`, `sys.stdout` or `sys.stderr`
+fn sys_stream(expr: &HirExpr) -> Option<Stream> {
+    match expr {
+        HirExpr::Attribute { value, attr } => match value.as_ref() {
+            HirExpr::Var(module) if module == "sys" => Stream::from_name(attr),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Reads a line of stdin into `line`
+fn read_line(ctx: &CodeGenContext) -> syn::Expr {
+    let stdin = Stream::Stdin.std_handle();
+    handle_io_error(
+        parse_quote! { std::io::BufRead::read_line(&mut #stdin, &mut line) },
+        "read stdin",
+        ctx,
+    )
+}
+
+/// `input()` and `input(prompt)`: a line of stdin without its line break
+///
+/// The prompt is printed without a newline, and stdout flushed, before
+/// reading.
+pub(crate) fn codegen_input(args: &[syn::Expr], ctx: &CodeGenContext) -> Result<syn::Expr> {
+    let prompt = match args {
+        [] => quote! {},
+        [prompt] => {
+            let stdout = Stream::Stdout.std_handle();
+            let flush = handle_io_error(
+                parse_quote! { std::io::Write::flush(&mut #stdout) },
+                "flush stdout",
+                ctx,
+            );
+            quote! {
+                print!("{}", #prompt);
+                #flush;
+            }
+        }
+        _ => bail!("input() takes at most 1 argument"),
+    };
+    let read = read_line(ctx);
+    Ok(parse_quote! {
+        {
+            #prompt
+            let mut line = String::new();
+            #read;
+            line.trim_end_matches(|c| c == '\n' || c == '\r').to_string()
+        }
+    })
+}
+
+/// Methods of `sys.stdin`, `sys.stdout` and `sys.stderr`
+///
+/// Returns `None` for other receivers and unmapped methods.
+pub(crate) fn try_codegen_stream_method(
+    object: &HirExpr,
+    method: &str,
+    args: &[HirExpr],
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let stream = match sys_stream(object) {
+        Some(stream) => stream,
+        None => return Ok(None),
+    };
+    let handle = stream.std_handle();
+    let expr = match (stream, method, args) {
+        (Stream::Stdin, "readline", []) => {
+            let read = read_line(ctx);
+            parse_quote! {
+                {
+                    let mut line = String::new();
+                    #read;
+                    line
+                }
+            }
+        }
+        (Stream::Stdin, "read", []) => handle_io_error(
+            parse_quote! { std::io::read_to_string(&mut #handle) },
+            "read stdin",
+            ctx,
+        ),
+        (Stream::Stdout | Stream::Stderr, "write", [text]) => {
+            let text = text.to_rust_expr(ctx)?;
+            handle_io_error(
+                parse_quote! { std::io::Write::write_all(&mut #handle, #text.as_bytes()) },
+                &format!("write to {}", stream.name()),
+                ctx,
+            )
+        }
+        (Stream::Stdout | Stream::Stderr, "flush", []) => handle_io_error(
+            parse_quote! { std::io::Write::flush(&mut #handle) },
+            &format!("flush {}", stream.name()),
+            ctx,
+        ),
+        _ => return Ok(None),
+    };
+    Ok(Some(expr))
+}
+
+/// Each class's name and the tokens generated for it
+type ClassTokens = Vec<(String, Vec<TokenStream>)>;
+
+/// Give the generated module functions the streams they use as parameters
+///
+/// `functions` holds the tokens generated for the module functions and
+/// `classes` those of each class.
+///
+/// # Complexity
+/// 9 (call graph fixpoint + stdin check + per-item dispatch)
+pub(crate) fn inject_streams(
+    functions: Vec<TokenStream>,
+    classes: ClassTokens,
+) -> Result<(Vec<TokenStream>, ClassTokens)> {
+    let files = functions
+        .into_iter()
+        .map(syn::parse2::<syn::File>)
+        .collect::<syn::Result<Vec<_>>>()?;
+    let classes = classes
+        .into_iter()
+        .map(|(name, tokens)| {
+            let files = tokens
+                .into_iter()
+                .map(syn::parse2::<syn::File>)
+                .collect::<syn::Result<Vec<_>>>()?;
+            Ok((name, files))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let top_level: Vec<&syn::ItemFn> = files
+        .iter()
+        .flat_map(|file| &file.items)
+        .filter_map(|item| match item {
+            syn::Item::Fn(func) => Some(func),
+            _ => None,
+        })
+        .collect();
+    let names: HashSet<String> = top_level.iter().map(|f| f.sig.ident.to_string()).collect();
+
+    // What each function uses itself, then what the functions it calls use
+    let mut calls = BTreeMap::new();
+    let mut needs: BTreeMap<String, BTreeSet<Stream>> = BTreeMap::new();
+    let mut nested = Vec::new();
+    for func in &top_level {
+        let mut uses = StreamUses::new(&names);
+        uses.visit_block(&func.block);
+        let name = func.sig.ident.to_string();
+        nested.extend(uses.nested.into_iter().map(|item| (name.clone(), item)));
+        needs.insert(name.clone(), uses.streams);
+        calls.insert(name, uses.calls);
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (caller, callees) in &calls {
+            let inherited: BTreeSet<Stream> = callees
+                .iter()
+                .filter(|callee| !is_root(callee))
+                .flat_map(|callee| needs[callee].clone())
+                .collect();
+            let caller_needs = needs.get_mut(caller).expect("every caller has needs");
+            let before = caller_needs.len();
+            caller_needs.extend(inherited);
+            changed |= caller_needs.len() != before;
+        }
+    }
+    // Roots bind the std streams themselves, so their callers pass none
+    let params: BTreeMap<String, Vec<Stream>> = needs
+        .iter()
+        .map(|(name, streams)| {
+            let passed = if is_root(name) {
+                Vec::new()
+            } else {
+                streams.iter().copied().collect()
+            };
+            (name.clone(), passed)
+        })
+        .collect();
+
+    // Code keeping the std streams must not lock stdin
+    let std_users = nested
+        .into_iter()
+        .chain(
+            files
+                .iter()
+                .flat_map(|file| &file.items)
+                .filter_map(|item| match item {
+                    syn::Item::Fn(_) => None,
+                    other => Some((item_name(other), other)),
+                }),
+        )
+        .chain(classes.iter().flat_map(|(name, files)| {
+            files
+                .iter()
+                .flat_map(|file| &file.items)
+                .map(|item| (name.clone(), item))
+        }));
+    for (owner, item) in std_users {
+        if reads_stdin(item, &names, &needs) {
+            bail!(
+                "cannot pass stdin into `{}`: methods and nested functions keep the std streams, \
+                 and stdin may be locked by their caller; read input in module functions",
+                owner
+            );
+        }
+    }
+
+    let mut out = Vec::with_capacity(files.len());
+    for file in files {
+        let mut items = Vec::with_capacity(file.items.len());
+        for mut item in file.items {
+            match &mut item {
+                syn::Item::Fn(func) => {
+                    let needed = &needs[&func.sig.ident.to_string()];
+                    bind_streams(func, needed, &params)?;
+                }
+                other => pass_std_streams(other, &params),
+            }
+            items.push(item);
+        }
+        out.push(quote! { #(#items)* });
+    }
+
+    let mut rewritten = Vec::with_capacity(classes.len());
+    for (name, files) in classes {
+        let mut class_tokens = Vec::with_capacity(files.len());
+        for mut file in files {
+            for item in &mut file.items {
+                pass_std_streams(item, &params);
+            }
+            class_tokens.push(quote! { #file });
+        }
+        rewritten.push((name, class_tokens));
+    }
+    Ok((out, rewritten))
+}
+
+/// Whether `item` reads stdin, itself, in an item nested in it or through
+/// the module functions it calls
+fn reads_stdin(
+    item: &syn::Item,
+    names: &HashSet<String>,
+    needs: &BTreeMap<String, BTreeSet<Stream>>,
+) -> bool {
+    let mut uses = StreamUses::new(names);
+    visit::visit_item(&mut uses, item);
+    uses.streams.contains(&Stream::Stdin)
+        || uses
+            .calls
+            .iter()
+            .any(|callee| needs[callee].contains(&Stream::Stdin))
+        || uses
+            .nested
+            .iter()
+            .any(|item| reads_stdin(item, names, needs))
+}
+
+/// The name an item is reported under
+fn item_name(item: &syn::Item) -> String {
+    match item {
+        syn::Item::Impl(block) => {
+            let ty = &block.self_ty;
+            quote!(#ty).to_string()
+        }
+        syn::Item::Mod(module) => module.ident.to_string(),
+        syn::Item::Struct(strukt) => strukt.ident.to_string(),
+        _ => "module item".to_string(),
+    }
+}
+
+/// `main()`, or the user's main under the exit-code wrapper
+fn is_root(name: &str) -> bool {
+    name == "main" || name == ExitCodeOptions::INNER_MAIN
+}
+
+/// Has `func` reach `needed` streams through parameters, or for a root
+/// through locals bound to the std streams
+///
+/// A root binds stdout and stderr only: holding stdin's lock for its whole
+/// body would block every read made elsewhere.
+fn bind_streams(
+    func: &mut syn::ItemFn,
+    needed: &BTreeSet<Stream>,
+    params: &BTreeMap<String, Vec<Stream>>,
+) -> Result<()> {
+    let name = func.sig.ident.to_string();
+    let bound: BTreeSet<Stream> = if is_root(&name) {
+        needed
+            .iter()
+            .copied()
+            .filter(|s| *s != Stream::Stdin)
+            .collect()
+    } else {
+        needed.clone()
+    };
+    let mut rewrite = StreamRewrite {
+        params,
+        bound: &bound,
+        wrote: false,
+    };
+    rewrite.visit_block_mut(&mut func.block);
+    if needed.is_empty() {
+        return Ok(());
+    }
+
+    if is_root(&name) {
+        let mut bindings: Vec<syn::Stmt> = bound
+            .iter()
+            .map(|stream| {
+                let ident = format_ident!("{}", stream.name());
+                let handle = stream.shared_handle();
+                parse_quote! { let #ident = &mut #handle; }
+            })
+            .collect();
+        // Macros writing to a concrete handle need the trait in scope
+        if rewrite.wrote {
+            bindings.insert(0, parse_quote! { use std::io::Write as _; });
+        }
+        func.block.stmts.splice(0..0, bindings);
+        return Ok(());
+    }
+
+    for (i, stream) in needed.iter().enumerate() {
+        let taken = func.sig.inputs.iter().any(|arg| match arg {
+            syn::FnArg::Typed(pat) => {
+                matches!(pat.pat.as_ref(), syn::Pat::Ident(ident) if ident.ident == stream.name())
+            }
+            syn::FnArg::Receiver(_) => false,
+        });
+        if taken {
+            bail!(
+                "cannot pass `{}` to `{}`: it has a parameter of that name",
+                stream.name(),
+                name
+            );
+        }
+        let ident = format_ident!("{}", stream.name());
+        let ty = stream.param_type();
+        func.sig.inputs.insert(i, parse_quote!(#ident: #ty));
+    }
+    Ok(())
+}
+
+/// Passes the std streams to the module functions called from the methods
+/// and functions inside `item`
+fn pass_std_streams(item: &mut syn::Item, params: &BTreeMap<String, Vec<Stream>>) {
+    let mut rewrite = StreamRewrite {
+        params,
+        bound: &BTreeSet::new(),
+        wrote: false,
+    };
+    match item {
+        syn::Item::Impl(block) => {
+            for impl_item in &mut block.items {
+                if let syn::ImplItem::Fn(method) = impl_item {
+                    rewrite.visit_block_mut(&mut method.block);
+                }
+            }
+        }
+        syn::Item::Fn(func) => rewrite.visit_block_mut(&mut func.block),
+        syn::Item::Mod(module) => {
+            for item in module.content.iter_mut().flat_map(|(_, items)| items) {
+                pass_std_streams(item, params);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The stream an I/O macro writes to: `println!` and `print!` to stdout,
+/// `eprintln!` and `eprint!` to stderr
+fn macro_stream(mac: &syn::Macro) -> Option<(Stream, bool)> {
+    let name = mac.path.get_ident()?.to_string();
+    match name.as_str() {
+        "println" => Some((Stream::Stdout, true)),
+        "print" => Some((Stream::Stdout, false)),
+        "eprintln" => Some((Stream::Stderr, true)),
+        "eprint" => Some((Stream::Stderr, false)),
+        _ => None,
+    }
+}
+
+/// `std::io::stdout().lock()` and the like
+fn std_handle_stream(expr: &syn::Expr) -> Option<Stream> {
+    let call = match expr {
+        syn::Expr::MethodCall(call) if call.method == "lock" && call.args.is_empty() => call,
+        _ => return None,
+    };
+    match call.receiver.as_ref() {
+        syn::Expr::Call(open) if open.args.is_empty() => match open.func.as_ref() {
+            syn::Expr::Path(path) => {
+                let segments: Vec<String> = path
+                    .path
+                    .segments
+                    .iter()
+                    .map(|segment| segment.ident.to_string())
+                    .collect();
+                match segments.as_slice() {
+                    [std, io, stream] if std == "std" && io == "io" => Stream::from_name(stream),
+                    _ => None,
+                }
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The module function a call expression calls, by plain name
+fn called_function(call: &syn::ExprCall) -> Option<&syn::Ident> {
+    match call.func.as_ref() {
+        syn::Expr::Path(path) if path.qself.is_none() => path.path.get_ident(),
+        _ => None,
+    }
+}
+
+/// The streams a function body uses and the module functions it calls,
+/// nested items set aside
+struct StreamUses<'a> {
+    names: &'a HashSet<String>,
+    streams: BTreeSet<Stream>,
+    calls: BTreeSet<String>,
+    nested: Vec<&'a syn::Item>,
+}
+
+impl<'a> StreamUses<'a> {
+    fn new(names: &'a HashSet<String>) -> Self {
+        Self {
+            names,
+            streams: BTreeSet::new(),
+            calls: BTreeSet::new(),
+            nested: Vec::new(),
+        }
+    }
+}
+
+impl<'a> Visit<'a> for StreamUses<'a> {
+    fn visit_item(&mut self, item: &'a syn::Item) {
+        self.nested.push(item);
+    }
+
+    fn visit_macro(&mut self, mac: &'a syn::Macro) {
+        if let Some((stream, _)) = macro_stream(mac) {
+            self.streams.insert(stream);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &'a syn::Expr) {
+        if let Some(stream) = std_handle_stream(expr) {
+            self.streams.insert(stream);
+            return;
+        }
+        if let syn::Expr::Call(call) = expr {
+            if let Some(callee) = called_function(call) {
+                let callee = callee.to_string();
+                if self.names.contains(&callee) {
+                    self.calls.insert(callee);
+                }
+            }
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// Points a body's I/O at the stream variables and passes the streams to
+/// the module functions it calls
+///
+/// Streams outside `bound` have no variable: their I/O stays on the std
+/// streams, and calls get `std::io::stdout()` or, for stdin, a lock held
+/// just for the call.
+struct StreamRewrite<'a> {
+    params: &'a BTreeMap<String, Vec<Stream>>,
+    bound: &'a BTreeSet<Stream>,
+    /// Whether a print macro was turned into a `write!` on a stream variable
+    wrote: bool,
+}
+
+impl StreamRewrite<'_> {
+    /// `println!(...)` -> `writeln!(stdout, ...).expect(...)`
+    fn write_macro(&mut self, mac: &syn::Macro) -> Option<syn::Expr> {
+        let (stream, newline) = macro_stream(mac)?;
+        if !self.bound.contains(&stream) {
+            return None;
+        }
+        self.wrote = true;
+        let ident = format_ident!("{}", stream.name());
+        let message = format!("failed printing to {}", stream.name());
+        let tokens = &mac.tokens;
+        let args = if tokens.is_empty() {
+            quote! { #ident }
+        } else {
+            quote! { #ident, #tokens }
+        };
+        Some(if newline {
+            parse_quote! { writeln!(#args).expect(#message) }
+        } else {
+            parse_quote! { write!(#args).expect(#message) }
+        })
+    }
+
+    /// A bound stream, or stdin locked around the call, is passed by name
+    fn stream_arg(&self, stream: Stream) -> syn::Expr {
+        if self.bound.contains(&stream) || stream == Stream::Stdin {
+            let ident = format_ident!("{}", stream.name());
+            parse_quote! { #ident }
+        } else {
+            let handle = stream.shared_handle();
+            parse_quote! { &mut #handle }
+        }
+    }
+}
+
+impl VisitMut for StreamRewrite<'_> {
+    fn visit_item_mut(&mut self, item: &mut syn::Item) {
+        pass_std_streams(item, self.params);
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut syn::Stmt) {
+        if let syn::Stmt::Macro(statement) = stmt {
+            if let Some(write) = self.write_macro(&statement.mac) {
+                *stmt = syn::Stmt::Expr(write, statement.semi_token);
+                return;
+            }
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if let syn::Expr::Macro(call) = expr {
+            if let Some(write) = self.write_macro(&call.mac) {
+                *expr = write;
+                return;
+            }
+        }
+        if let Some(stream) = std_handle_stream(expr).filter(|s| self.bound.contains(s)) {
+            let ident = format_ident!("{}", stream.name());
+            *expr = parse_quote! { (*#ident) };
+            return;
+        }
+        visit_mut::visit_expr_mut(self, expr);
+        let mut locks_stdin = false;
+        if let syn::Expr::Call(call) = expr {
+            let passed = called_function(call)
+                .and_then(|callee| self.params.get(&callee.to_string()))
+                .filter(|passed| !passed.is_empty());
+            if let Some(passed) = passed {
+                for (i, stream) in passed.iter().enumerate() {
+                    call.args.insert(i, self.stream_arg(*stream));
+                }
+                locks_stdin =
+                    passed.contains(&Stream::Stdin) && !self.bound.contains(&Stream::Stdin);
+            }
+        }
+        // The lock goes when the block ends, not with the whole statement
+        if locks_stdin {
+            let handle = Stream::Stdin.std_handle();
+            *expr = parse_quote! {
+                {
+                    let stdin = &mut #handle;
+                    #expr
+                }
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inject(source: &str) -> String {
+        let (functions, _) = inject_streams(vec![source.parse().unwrap()], Vec::new()).unwrap();
+        functions[0].to_string()
+    }
+
+    #[test]
+    fn test_streams_are_threaded_through_callers() {
+        let code = inject(
+            r#"
+            pub fn greet(name: &str) { println!("Hello, {}", name); }
+            pub fn ask() -> String { std::io::BufRead::read_line(&mut std::io::stdin().lock(), &mut line); greet("x"); line }
+            pub fn pure(x: i32) -> i32 { x * 2 }
+            pub fn main() { let name = ask(); greet(&name); eprintln!("done"); }
+            "#,
+        );
+
+        let expected = quote! {
+            pub fn greet(stdout: &mut impl std::io::Write, name: &str) {
+                writeln!(stdout, "Hello, {}", name).expect("failed printing to stdout");
+            }
+            pub fn ask(stdin: &mut impl std::io::BufRead, stdout: &mut impl std::io::Write) -> String {
+                std::io::BufRead::read_line(&mut (*stdin), &mut line);
+                greet(stdout, "x");
+                line
+            }
+            pub fn pure(x: i32) -> i32 { x * 2 }
+            pub fn main() {
+                use std::io::Write as _;
+                let stdout = &mut std::io::stdout();
+                let stderr = &mut std::io::stderr();
+                let name = {
+                    let stdin = &mut std::io::stdin().lock();
+                    ask(stdin, stdout)
+                };
+                greet(stdout, &name);
+                writeln!(stderr, "done").expect("failed printing to stderr");
+            }
+        };
+        assert_eq!(code, expected.to_string());
+    }
+
+    #[test]
+    fn test_methods_get_the_std_streams() {
+        let class = quote! {
+            impl Greeter {
+                pub fn greet(&self) {
+                    println!("{}", self.name);
+                    log(&self.name);
+                }
+            }
+        };
+        let (_, classes) = inject_streams(
+            vec!["pub fn log(text: &str) { eprintln!(\"{}\", text); }"
+                .parse()
+                .unwrap()],
+            vec![("Greeter".to_string(), vec![class])],
+        )
+        .unwrap();
+
+        let expected = quote! {
+            impl Greeter {
+                pub fn greet(&self) {
+                    println!("{}", self.name);
+                    log(&mut std::io::stderr(), &self.name);
+                }
+            }
+        };
+        assert_eq!(classes[0].1[0].to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_methods_reading_stdin_are_rejected() {
+        let ask = "pub fn ask() -> String { std::io::read_to_string(&mut std::io::stdin().lock()).unwrap() }";
+        let reads = quote! {
+            impl Form { pub fn fill(&mut self) { self.name = ask(); } }
+        };
+        let error = inject_streams(
+            vec![ask.parse().unwrap()],
+            vec![("Form".to_string(), vec![reads])],
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("cannot pass stdin into `Form`"));
+
+        let nested = "pub fn run() { fn line() -> String { ask() } line(); } ".to_string() + ask;
+        let error = inject_streams(vec![nested.parse().unwrap()], Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("cannot pass stdin into `run`"));
+    }
+
+    #[test]
+    fn test_parameter_named_like_a_stream_is_rejected() {
+        let source = "pub fn log(stdout: bool) { println!(\"{}\", stdout); }";
+        let error = inject_streams(vec![source.parse().unwrap()], Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("cannot pass `stdout` to `log`"));
+    }
+}
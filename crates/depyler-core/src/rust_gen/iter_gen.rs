@@ -0,0 +1,281 @@
+//! `iter()` and `next()` with Python's iterator protocol
+//!
+//! An iterator object becomes a Rust iterator held in a `mut` local, so
+//! `it = iter(xs); next(it)` advances it in place:
+//!
+//! | Python                | Rust                                            |
+//! |-----------------------|-------------------------------------------------|
+//! | `iter(xs)`            | `xs.iter().cloned()`                            |
+//! | `iter(d)`             | `d.keys().cloned()`                             |
+//! | `iter(s)`             | `s.chars().map(\|c\| c.to_string())`            |
+//! | `next(it, default)`   | `it.next().unwrap_or(default)`                  |
+//! | `next(it, None)`      | `it.next()`                                     |
+//! | `next(it)`            | `it.next().ok_or_else(\|\| StopIteration::new(""))` |
+//!
+//! The `StopIteration` of an exhausted `next(it)` propagates like the other
+//! predefined errors. A `try` whose body starts with `next(it)` and which
+//! catches `StopIteration` matches on the `Option` directly instead.
+
+use crate::hir::{AssignTarget, ExceptHandler, HirExpr, HirStmt, Literal, Type, UnaryOp};
+use crate::rust_gen::context::{CodeGenContext, HandlerBinding, RustCodeGen, ToRustExpr};
+use crate::rust_gen::error_gen;
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// Local holding the item `next(it)` produced in a lowered `try`
+const NEXT_ITEM: &str = "__next_item";
+
+/// `iter(iterable)` as an iterator over owned items
+pub(crate) fn codegen_iter(
+    iterable: &HirExpr,
+    iter_expr: &syn::Expr,
+    ctx: &CodeGenContext,
+) -> syn::Expr {
+    match ctx.value_type(iterable) {
+        Type::List(_) | Type::Set(_) => parse_quote! { #iter_expr.iter().cloned() },
+        Type::Dict(_, _) => parse_quote! { #iter_expr.keys().cloned() },
+        Type::String => parse_quote! { #iter_expr.chars().map(|c| c.to_string()) },
+        _ => parse_quote! { #iter_expr.into_iter() },
+    }
+}
+
+/// `next(iterator)` or `next(iterator, default)`
+pub(crate) fn codegen_next(
+    iterator: &syn::Expr,
+    default: Option<(&HirExpr, &syn::Expr)>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    match default {
+        Some((HirExpr::Literal(Literal::None), _)) => parse_quote! { #iterator.next() },
+        Some((_, default)) => parse_quote! { #iterator.next().unwrap_or(#default) },
+        None => {
+            ctx.needs_stopiteration = true;
+            let next = parse_quote! { #iterator.next().ok_or_else(|| StopIteration::new("")) };
+            error_gen::handle_builtin_error(next, "StopIteration", ctx)
+        }
+    }
+}
+
+/// A `try` whose first statement calls `next(it)` and whose only handler
+/// catches the `StopIteration`, as a match on `it.next()`
+///
+/// When the handler assigns a plain value to the same target, the pair
+/// becomes `next(it, value)`. When it leaves the function or loop, the item
+/// is bound before the rest of the body runs, keeping its assignments in
+/// scope; otherwise the body runs in the `Some` arm. Returns `None` for any
+/// other `try`.
+pub(crate) fn codegen_next_try(
+    body: &[HirStmt],
+    handlers: &[ExceptHandler],
+    finalbody: &Option<Vec<HirStmt>>,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    let handler = match handlers {
+        [handler] if handler.name.is_none() && finalbody.is_none() => handler,
+        _ => return Ok(None),
+    };
+    let catches = match handler.exception_type.as_deref() {
+        None | Some("StopIteration") | Some("Exception") => true,
+        _ => false,
+    };
+    let mut first = match body.first() {
+        Some(stmt) if catches => stmt.clone(),
+        _ => return Ok(None),
+    };
+    let iterator = match stmt_expr_mut(&mut first).and_then(take_next_call) {
+        Some(iterator) => iterator,
+        None => return Ok(None),
+    };
+
+    if let Some(stmt) = next_with_default(body, handler, &iterator) {
+        return stmt.to_rust_tokens(ctx).map(Some);
+    }
+
+    let iterator = iterator.to_rust_expr(ctx)?;
+    let item = syn::Ident::new(NEXT_ITEM, proc_macro2::Span::call_site());
+    let handler_stmts = codegen_handler(handler, ctx)?;
+    ctx.enter_try_scope(handler.exception_type.iter().cloned().collect());
+    let diverges = matches!(
+        handler.body.last(),
+        Some(
+            HirStmt::Return(_)
+                | HirStmt::Raise { .. }
+                | HirStmt::Break { .. }
+                | HirStmt::Continue { .. }
+        )
+    );
+    let tokens = if diverges {
+        // Assignments in the body stay visible after the `try`
+        let body_stmts = std::iter::once(&first)
+            .chain(&body[1..])
+            .map(|s| s.to_rust_tokens(ctx))
+            .collect::<Result<Vec<_>>>();
+        ctx.exit_exception_scope();
+        let body_stmts = body_stmts?;
+        quote! {
+            let #item = match #iterator.next() {
+                Some(item) => item,
+                None => {
+                    #(#handler_stmts)*
+                }
+            };
+            #(#body_stmts)*
+        }
+    } else {
+        ctx.enter_scope();
+        let saved_is_final = ctx.is_final_statement;
+        ctx.is_final_statement = false;
+        let body_stmts = std::iter::once(&first)
+            .chain(&body[1..])
+            .map(|s| s.to_rust_tokens(ctx))
+            .collect::<Result<Vec<_>>>();
+        ctx.is_final_statement = saved_is_final;
+        ctx.exit_scope();
+        ctx.exit_exception_scope();
+        let body_stmts = body_stmts?;
+        quote! {
+            match #iterator.next() {
+                Some(#item) => {
+                    #(#body_stmts)*
+                }
+                None => {
+                    #(#handler_stmts)*
+                }
+            }
+        }
+    };
+    Ok(Some(tokens))
+}
+
+/// `x = next(it)` caught by `x = value` as `x = next(it, value)`
+fn next_with_default(
+    body: &[HirStmt],
+    handler: &ExceptHandler,
+    iterator: &HirExpr,
+) -> Option<HirStmt> {
+    match (body, handler.body.as_slice()) {
+        (
+            [HirStmt::Assign {
+                target: AssignTarget::Symbol(target),
+                value: HirExpr::Call { func, args, .. },
+                type_annotation,
+            }],
+            [HirStmt::Assign {
+                target: AssignTarget::Symbol(fallback_target),
+                value: fallback,
+                ..
+            }],
+        ) if func == "next"
+            && args.len() == 1
+            && target == fallback_target
+            && is_plain_value(fallback) =>
+        {
+            Some(HirStmt::Assign {
+                target: AssignTarget::Symbol(target.clone()),
+                value: HirExpr::Call {
+                    func: "next".to_string(),
+                    args: vec![iterator.clone(), fallback.clone()],
+                    kwargs: Vec::new(),
+                },
+                type_annotation: type_annotation.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Whether evaluating `expr` eagerly, as `unwrap_or` does, is unobservable
+fn is_plain_value(expr: &HirExpr) -> bool {
+    match expr {
+        HirExpr::Literal(_) | HirExpr::Var(_) => true,
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => matches!(**operand, HirExpr::Literal(_)),
+        _ => false,
+    }
+}
+
+/// The expression a simple statement evaluates
+fn stmt_expr_mut(stmt: &mut HirStmt) -> Option<&mut HirExpr> {
+    match stmt {
+        HirStmt::Assign { value, .. } => Some(value),
+        HirStmt::Return(Some(value)) | HirStmt::Expr(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Replaces the first `next(it)` in `expr` with the item local, returning
+/// the iterator it advanced
+fn take_next_call(expr: &mut HirExpr) -> Option<HirExpr> {
+    if let HirExpr::Call { func, args, .. } = expr {
+        if func == "next" && args.len() == 1 {
+            let iterator = args.remove(0);
+            *expr = HirExpr::Var(NEXT_ITEM.to_string());
+            return Some(iterator);
+        }
+    }
+    if matches!(expr, HirExpr::Lambda { .. }) {
+        return None;
+    }
+    expr.children_mut().into_iter().find_map(take_next_call)
+}
+
+/// The handler's statements, generated as `codegen_try_stmt` does
+fn codegen_handler(handler: &ExceptHandler, ctx: &mut CodeGenContext) -> Result<Vec<TokenStream>> {
+    ctx.enter_handler_scope();
+    ctx.enter_scope();
+    let saved_is_final = ctx.is_final_statement;
+    ctx.is_final_statement = false;
+    let saved_handler = ctx.current_handler.replace(HandlerBinding {
+        exception_type: handler.exception_type.clone(),
+        name: None,
+        bound_error: None,
+    });
+    let stmts = handler
+        .body
+        .iter()
+        .map(|s| s.to_rust_tokens(ctx))
+        .collect::<Result<Vec<_>>>();
+    ctx.is_final_statement = saved_is_final;
+    ctx.current_handler = saved_handler;
+    ctx.exit_scope();
+    ctx.exit_exception_scope();
+    stmts
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_iter_and_next_with_default() {
+        let code = transpile(
+            "def f(xs: list[int]) -> int:\n    it = iter(xs)\n    next(it, 0)\n    return next(it, -1)\n",
+        );
+
+        assert!(code.contains("letmutit=xs.iter().cloned();"));
+        assert!(code.contains("it.next().unwrap_or(-1)"));
+    }
+
+    #[test]
+    fn test_bare_next_raises_stop_iteration() {
+        let code =
+            transpile("def f(xs: list[int]) -> int:\n    it = iter(xs)\n    return next(it)\n");
+
+        assert!(code.contains("structStopIteration"));
+        assert!(code.contains("it.next().ok_or_else(||StopIteration::new(\"\"))?"));
+    }
+
+    #[test]
+    fn test_caught_stop_iteration_matches_on_next() {
+        let code = transpile(
+            "def f(xs: list[int]) -> int:\n    it = iter(xs)\n    try:\n        first = next(it)\n    except StopIteration:\n        return -1\n    return first\n",
+        );
+
+        assert!(code.contains("let__next_item=matchit.next(){Some(item)=>item,None=>{return-1;}};"));
+        assert!(!code.contains("StopIteration::new"));
+    }
+}
@@ -0,0 +1,545 @@
+//! Lazily initialized module-level values
+//!
+//! A module constant computed at runtime, such as `CONFIG = load_config()`
+//! or a dict literal, cannot be a Rust `const`. It becomes a
+//! `std::sync::LazyLock` static that runs its initializer on first access.
+//! Initializing on demand reproduces Python's definition order for any
+//! acyclic set of globals, and `LazyLock` makes that first access thread
+//! safe. Initializers that reach each other would deadlock or panic at
+//! runtime instead, so such cycles are rejected during transpilation.
+
+use crate::hir::{
+    AssignTarget, FStringPart, HirConstant, HirExpr, HirFunction, HirPattern, HirStmt, Literal,
+    Type,
+};
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::context_manager_gen::{is_lock_constructor, LOCK_TYPE};
+use crate::rust_gen::type_gen::rust_type_to_syn;
+use anyhow::{bail, Result};
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use syn::parse_quote;
+
+/// Module constants computed at runtime, with their types
+///
+/// A constant qualifies when it is neither a literal nor folded into a
+/// const, and its type is annotated or follows from its value. Anything
+/// else keeps the plain `const` lowering.
+pub(crate) fn find_lazy_globals(
+    constants: &[HirConstant],
+    folded_constants: &[Option<(TokenStream, TokenStream)>],
+    functions: &[HirFunction],
+    class_names: &HashSet<String>,
+) -> HashMap<String, Type> {
+    let returns: HashMap<&str, &Type> = functions
+        .iter()
+        .map(|f| (f.name.as_str(), &f.ret_type))
+        .collect();
+    let mut globals = HashMap::new();
+    for (constant, folded) in constants.iter().zip(folded_constants) {
+        if folded.is_some() || matches!(constant.value, HirExpr::Literal(_)) {
+            continue;
+        }
+        let ty = match &constant.type_annotation {
+            Some(ty) => Some(ty.clone()),
+            None => infer_value_type(&constant.value, &returns, class_names, &globals),
+        };
+        if let Some(ty) = ty {
+            globals.insert(constant.name.clone(), ty);
+        }
+    }
+    globals
+}
+
+/// `LazyLock` statics for the constants in `ctx.lazy_globals`
+///
+/// A call to a fallible module function is unwrapped, since there is no
+/// caller to propagate the error to.
+pub(crate) fn generate_lazy_globals(
+    constants: &[HirConstant],
+    functions: &[HirFunction],
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<TokenStream>> {
+    let lazy: Vec<&HirConstant> = constants
+        .iter()
+        .filter(|c| ctx.lazy_globals.contains_key(&c.name))
+        .collect();
+    if lazy.is_empty() {
+        return Ok(Vec::new());
+    }
+    if let Some(cycle) = find_initialization_cycle(&lazy, functions, &ctx.lazy_globals) {
+        bail!(
+            "Module-level values depend on each other during initialization: {}",
+            cycle.join(" -> ")
+        );
+    }
+
+    // Initializers run outside of any function body
+    ctx.current_function_can_fail = false;
+    ctx.current_return_type = None;
+
+    let mut items = Vec::new();
+    for constant in lazy {
+        let ty = ctx.lazy_globals[&constant.name].clone();
+        let rust_type = rust_type_to_syn(&ctx.type_mapper.map_type(&ty))?;
+
+        let mut value = constant.value.to_rust_expr(ctx)?;
+        if let HirExpr::Call { func, .. } = &constant.value {
+            if ctx.result_returning_functions.contains(func) {
+                let message = format!("failed to initialize {}", constant.name);
+                value = parse_quote! { #value.expect(#message) };
+            }
+        }
+
+        let name = syn::Ident::new(&constant.name, proc_macro2::Span::call_site());
+        items.push(quote! {
+            pub static #name: std::sync::LazyLock<#rust_type> =
+                std::sync::LazyLock::new(|| #value);
+        });
+    }
+    Ok(items)
+}
+
+/// Type of an unannotated module value, from its literal shape, the
+/// function or class it calls, or an earlier lazy global it copies
+fn infer_value_type(
+    expr: &HirExpr,
+    returns: &HashMap<&str, &Type>,
+    class_names: &HashSet<String>,
+    globals: &HashMap<String, Type>,
+) -> Option<Type> {
+    let infer = |e: &HirExpr| infer_value_type(e, returns, class_names, globals);
+    match expr {
+        HirExpr::Literal(Literal::Int(_)) => Some(Type::Int),
+        HirExpr::Literal(Literal::Float(_)) => Some(Type::Float),
+        HirExpr::Literal(Literal::String(_)) => Some(Type::String),
+        HirExpr::Literal(Literal::Bool(_)) => Some(Type::Bool),
+        HirExpr::Var(name) => globals.get(name).cloned(),
+        HirExpr::Call { func, .. } if class_names.contains(func) => {
+            Some(Type::Custom(func.clone()))
+        }
+        HirExpr::Call { func, .. } => returns
+            .get(func.as_str())
+            .filter(|ty| !matches!(ty, Type::Unknown | Type::None))
+            .map(|ty| (*ty).clone()),
+        HirExpr::List(items) => Some(Type::List(Box::new(infer(items.first()?)?))),
+        HirExpr::Set(items) => Some(Type::Set(Box::new(infer(items.first()?)?))),
+        HirExpr::Tuple(items) => items
+            .iter()
+            .map(infer)
+            .collect::<Option<Vec<_>>>()
+            .map(Type::Tuple),
+        HirExpr::Dict(pairs) => {
+            let (key, value) = pairs.first()?;
+            Some(Type::Dict(Box::new(infer(key)?), Box::new(infer(value)?)))
+        }
+        _ if is_lock_constructor(expr) => Some(Type::Custom(LOCK_TYPE.to_string())),
+        _ => None,
+    }
+}
+
+/// First cycle among lazy globals, as the names along it
+///
+/// A global depends on the globals its initializer reads, directly or
+/// through the module functions it calls.
+fn find_initialization_cycle(
+    lazy: &[&HirConstant],
+    functions: &[HirFunction],
+    globals: &HashMap<String, Type>,
+) -> Option<Vec<String>> {
+    let functions: HashMap<&str, &HirFunction> =
+        functions.iter().map(|f| (f.name.as_str(), f)).collect();
+    let edges: HashMap<&str, BTreeSet<String>> = lazy
+        .iter()
+        .map(|constant| {
+            let mut reads = GlobalReads {
+                functions: &functions,
+                globals,
+                visited: HashSet::new(),
+                found: BTreeSet::new(),
+            };
+            reads.expr(&constant.value);
+            (constant.name.as_str(), reads.found)
+        })
+        .collect();
+
+    let mut done = HashSet::new();
+    let mut path = Vec::new();
+    lazy.iter()
+        .find_map(|constant| visit(&constant.name, &edges, &mut done, &mut path))
+}
+
+/// Depth-first search from `node`, returning the cycle closed by a back edge
+fn visit(
+    node: &str,
+    edges: &HashMap<&str, BTreeSet<String>>,
+    done: &mut HashSet<String>,
+    path: &mut Vec<String>,
+) -> Option<Vec<String>> {
+    if let Some(start) = path.iter().position(|n| n == node) {
+        let mut cycle = path[start..].to_vec();
+        cycle.push(node.to_string());
+        return Some(cycle);
+    }
+    if done.contains(node) {
+        return None;
+    }
+    path.push(node.to_string());
+    for next in edges.get(node).into_iter().flatten() {
+        if let Some(cycle) = visit(next, edges, done, path) {
+            return Some(cycle);
+        }
+    }
+    path.pop();
+    done.insert(node.to_string());
+    None
+}
+
+/// Lazy globals read by an expression, following calls into module functions
+struct GlobalReads<'a> {
+    functions: &'a HashMap<&'a str, &'a HirFunction>,
+    globals: &'a HashMap<String, Type>,
+    visited: HashSet<String>,
+    found: BTreeSet<String>,
+}
+
+impl GlobalReads<'_> {
+    fn call(&mut self, name: &str) {
+        let func = match self.functions.get(name) {
+            Some(func) => *func,
+            None => return,
+        };
+        if !self.visited.insert(name.to_string()) {
+            return;
+        }
+        // Parameters shadow globals of the same name
+        let outer = std::mem::take(&mut self.found);
+        self.stmts(&func.body);
+        let inner = std::mem::replace(&mut self.found, outer);
+        self.found.extend(
+            inner
+                .into_iter()
+                .filter(|global| func.params.iter().all(|p| &p.name != global)),
+        );
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt]) {
+        stmts.iter().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.target(target);
+                self.expr(value);
+            }
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            HirStmt::For { target, iter, body } => {
+                self.target(target);
+                self.expr(iter);
+                self.stmts(body);
+            }
+            HirStmt::Expr(expr) => self.expr(expr),
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr);
+                }
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context);
+                self.stmts(body);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body);
+                handlers.iter().for_each(|h| self.stmts(&h.body));
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.expr(test);
+                if let Some(msg) = msg {
+                    self.expr(msg);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for case in cases {
+                    self.pattern(&case.pattern);
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.stmts(&case.body);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn target(&mut self, target: &AssignTarget) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            AssignTarget::Attribute { value, .. } => self.expr(value),
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t)),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &HirPattern) {
+        match pattern {
+            HirPattern::Value(expr) => self.expr(expr),
+            HirPattern::Capture {
+                pattern: Some(pattern),
+                ..
+            } => self.pattern(pattern),
+            HirPattern::Sequence(patterns) | HirPattern::Or(patterns) => {
+                patterns.iter().for_each(|p| self.pattern(p));
+            }
+            HirPattern::Class { args, kwargs, .. } => {
+                args.iter().for_each(|p| self.pattern(p));
+                kwargs.iter().for_each(|(_, p)| self.pattern(p));
+            }
+            HirPattern::Wildcard | HirPattern::Capture { .. } | HirPattern::Literal(_) => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Literal(_) => {}
+            HirExpr::Var(name) => {
+                if self.globals.contains_key(name) {
+                    self.found.insert(name.clone());
+                }
+            }
+            HirExpr::Binary { left, right, .. } => {
+                self.expr(left);
+                self.expr(right);
+            }
+            HirExpr::Unary { operand, .. } => self.expr(operand),
+            HirExpr::Call { func, args, kwargs } => {
+                self.call(func);
+                args.iter().for_each(|a| self.expr(a));
+                kwargs.iter().for_each(|(_, v)| self.expr(v));
+            }
+            HirExpr::MethodCall {
+                object,
+                args,
+                kwargs,
+                ..
+            } => {
+                self.expr(object);
+                args.iter().for_each(|a| self.expr(a));
+                kwargs.iter().for_each(|(_, v)| self.expr(v));
+            }
+            HirExpr::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            HirExpr::Slice {
+                base,
+                start,
+                stop,
+                step,
+            } => {
+                self.expr(base);
+                for bound in [start, stop, step].into_iter().flatten() {
+                    self.expr(bound);
+                }
+            }
+            HirExpr::Attribute { value, .. } => self.expr(value),
+            HirExpr::List(items)
+            | HirExpr::Tuple(items)
+            | HirExpr::Set(items)
+            | HirExpr::FrozenSet(items) => items.iter().for_each(|e| self.expr(e)),
+            HirExpr::Dict(pairs) => {
+                for (key, value) in pairs {
+                    self.expr(key);
+                    self.expr(value);
+                }
+            }
+            HirExpr::Borrow { expr, .. } => self.expr(expr),
+            HirExpr::ListComp {
+                element,
+                iter,
+                condition,
+                ..
+            }
+            | HirExpr::SetComp {
+                element,
+                iter,
+                condition,
+                ..
+            } => {
+                self.expr(element);
+                self.expr(iter);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+            }
+            HirExpr::DictComp {
+                key,
+                value,
+                iter,
+                condition,
+                ..
+            } => {
+                self.expr(key);
+                self.expr(value);
+                self.expr(iter);
+                if let Some(condition) = condition {
+                    self.expr(condition);
+                }
+            }
+            HirExpr::Lambda { body, .. } => self.expr(body),
+            HirExpr::Await { value } => self.expr(value),
+            HirExpr::FString { parts } => {
+                for part in parts {
+                    if let FStringPart::Expr(expr) = part {
+                        self.expr(expr);
+                    }
+                }
+            }
+            HirExpr::Yield { value } => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirExpr::IfExpr { test, body, orelse } => {
+                self.expr(test);
+                self.expr(body);
+                self.expr(orelse);
+            }
+            HirExpr::SortByKey {
+                iterable, key_body, ..
+            } => {
+                self.expr(iterable);
+                self.expr(key_body);
+            }
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => {
+                self.expr(element);
+                for generator in generators {
+                    self.expr(&generator.iter);
+                    generator.conditions.iter().for_each(|c| self.expr(c));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir_builder::{stmt, HirFunctionBuilder};
+
+    fn constant(name: &str, value: HirExpr) -> HirConstant {
+        HirConstant {
+            name: name.to_string(),
+            value,
+            type_annotation: None,
+        }
+    }
+
+    fn call(func: &str) -> HirExpr {
+        HirExpr::Call {
+            func: func.to_string(),
+            args: vec![],
+            kwargs: vec![],
+        }
+    }
+
+    fn cycle(constants: &[HirConstant], functions: &[HirFunction]) -> Option<Vec<String>> {
+        let lazy: Vec<&HirConstant> = constants.iter().collect();
+        let globals = constants
+            .iter()
+            .map(|c| (c.name.clone(), Type::Int))
+            .collect();
+        find_initialization_cycle(&lazy, functions, &globals)
+    }
+
+    #[test]
+    fn test_independent_globals_have_no_cycle() {
+        let constants = [
+            constant("CONFIG", call("load_config")),
+            constant(
+                "NAMES",
+                HirExpr::List(vec![HirExpr::Var("CONFIG".to_string())]),
+            ),
+        ];
+        let functions = [HirFunctionBuilder::new("load_config")
+            .returns(Type::Unknown)
+            .stmt(stmt::ret(HirExpr::Dict(vec![])))
+            .build()];
+        assert_eq!(cycle(&constants, &functions), None);
+    }
+
+    #[test]
+    fn test_direct_cycle() {
+        let constants = [
+            constant("A", HirExpr::List(vec![HirExpr::Var("B".to_string())])),
+            constant("B", HirExpr::List(vec![HirExpr::Var("A".to_string())])),
+        ];
+        assert_eq!(cycle(&constants, &[]).unwrap(), ["A", "B", "A"]);
+    }
+
+    #[test]
+    fn test_cycle_through_function_calls() {
+        let constants = [constant("A", call("make_a")), constant("B", call("make_b"))];
+        let functions = [
+            HirFunctionBuilder::new("make_a")
+                .returns(Type::Unknown)
+                .stmt(stmt::ret(call("helper")))
+                .build(),
+            HirFunctionBuilder::new("helper")
+                .returns(Type::Unknown)
+                .stmt(stmt::ret(HirExpr::Var("B".to_string())))
+                .build(),
+            HirFunctionBuilder::new("make_b")
+                .returns(Type::Unknown)
+                .stmt(stmt::ret(HirExpr::Var("A".to_string())))
+                .build(),
+        ];
+        assert_eq!(cycle(&constants, &functions).unwrap(), ["A", "B", "A"]);
+    }
+
+    #[test]
+    fn test_parameter_shadows_global() {
+        let constants = [constant("A", call("identity"))];
+        let identity = HirFunctionBuilder::new("identity")
+            .param("A", Type::Int)
+            .returns(Type::Unknown)
+            .stmt(stmt::ret(HirExpr::Var("A".to_string())))
+            .build();
+        assert_eq!(cycle(&constants, &[identity]), None);
+    }
+}
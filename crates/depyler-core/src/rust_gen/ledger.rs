@@ -0,0 +1,174 @@
+//! Known deviations from Python's semantics, recorded per function
+//!
+//! Some lowerings are faithful only up to a point depyler knows about.
+//! Code generation records each one it makes, so the risk can be audited
+//! instead of found in production:
+//!
+//! | Kind                     | Recorded for                                         |
+//! |--------------------------|------------------------------------------------------|
+//! | `int_width`              | `int` arithmetic on fixed-width machine integers     |
+//! | `float_repr`             | floats printed or formatted with Rust's `Display`    |
+//! | `dict_ordering`          | dicts iterated or printed in hash order              |
+//! | `unicode_indexing`       | strings measured or indexed in bytes                 |
+//! | `truthiness`             | conditions of unknown type used as they are          |
+//! | `container_substitution` | lists generated as a `VecDeque` or `HashSet`         |
+//!
+//! Each deviation is recorded once per function, under the Python name of
+//! the function, or `<module>` outside of one.
+
+use crate::hir::{HirExpr, Type};
+use crate::rust_gen::context::CodeGenContext;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// What `<module>` stands for in [`Divergence::function`]
+pub const MODULE_SCOPE: &str = "<module>";
+
+/// The kind of a deviation from Python
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DivergenceKind {
+    /// `int` is a fixed-width integer rather than unbounded
+    IntWidth,
+    /// A float is shown with Rust's formatting rather than `repr`
+    FloatRepr,
+    /// A dict is iterated or shown in hash order, not insertion order
+    DictOrdering,
+    /// A string is indexed or measured in bytes rather than characters
+    UnicodeIndexing,
+    /// A condition is used as it is instead of calling `__bool__`/`__len__`
+    Truthiness,
+    /// A list is another container, which its uses cannot tell apart
+    ContainerSubstitution,
+}
+
+impl DivergenceKind {
+    /// The name used in the serialized ledger
+    pub fn name(self) -> &'static str {
+        match self {
+            DivergenceKind::IntWidth => "int_width",
+            DivergenceKind::FloatRepr => "float_repr",
+            DivergenceKind::DictOrdering => "dict_ordering",
+            DivergenceKind::UnicodeIndexing => "unicode_indexing",
+            DivergenceKind::Truthiness => "truthiness",
+            DivergenceKind::ContainerSubstitution => "container_substitution",
+        }
+    }
+}
+
+/// One deviation in one function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Divergence {
+    /// The Python function, or [`MODULE_SCOPE`]
+    pub function: String,
+    pub kind: DivergenceKind,
+    /// What behaves differently
+    pub detail: String,
+}
+
+/// The deviations of a module, in the order code generation met them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DivergenceLedger {
+    pub entries: Vec<Divergence>,
+    /// The function being generated
+    #[serde(skip)]
+    function: Option<String>,
+}
+
+impl DivergenceLedger {
+    pub(crate) fn enter_function(&mut self, name: &str) {
+        self.function = Some(name.to_string());
+    }
+
+    pub(crate) fn exit_function(&mut self) {
+        self.function = None;
+    }
+
+    /// Records a deviation in the current function, once
+    pub(crate) fn record(&mut self, kind: DivergenceKind, detail: impl Into<String>) {
+        let function = self.function.as_deref().unwrap_or(MODULE_SCOPE);
+        let detail = detail.into();
+        let known = self
+            .entries
+            .iter()
+            .any(|e| e.function == function && e.kind == kind && e.detail == detail);
+        if !known {
+            self.entries.push(Divergence {
+                function: function.to_string(),
+                kind,
+                detail,
+            });
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The deviations recorded in `function`
+    pub fn for_function<'a>(&'a self, function: &'a str) -> impl Iterator<Item = &'a Divergence> {
+        self.entries.iter().filter(move |e| e.function == function)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// The ledger as text, grouped by function
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let mut function = None;
+        for entry in &self.entries {
+            if function != Some(&entry.function) {
+                let _ = writeln!(report, "{}:", entry.function);
+                function = Some(&entry.function);
+            }
+            let _ = writeln!(report, "  [{}] {}", entry.kind.name(), entry.detail);
+        }
+        report
+    }
+}
+
+/// Records how printing or formatting `value` with `{}` strays from `str()`
+pub(crate) fn record_display(value: &HirExpr, ctx: &mut CodeGenContext) {
+    match ctx.value_type(value) {
+        Type::Float => ctx.divergences.record(
+            DivergenceKind::FloatRepr,
+            "floats print with Rust's formatting: `3.0` shows as `3`",
+        ),
+        Type::Dict(_, _) => ctx.divergences.record(
+            DivergenceKind::DictOrdering,
+            "dicts print in hash order and Rust's `Debug` format",
+        ),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deviations_are_recorded_once_per_function() {
+        let mut ledger = DivergenceLedger::default();
+        ledger.record(DivergenceKind::Truthiness, "at module level");
+        ledger.enter_function("f");
+        ledger.record(DivergenceKind::IntWidth, "i32");
+        ledger.record(DivergenceKind::IntWidth, "i32");
+        ledger.exit_function();
+        ledger.enter_function("g");
+        ledger.record(DivergenceKind::IntWidth, "i32");
+
+        assert_eq!(ledger.entries.len(), 3);
+        assert_eq!(ledger.entries[0].function, MODULE_SCOPE);
+        assert_eq!(ledger.for_function("f").count(), 1);
+        assert_eq!(
+            ledger.report(),
+            "<module>:\n  [truthiness] at module level\nf:\n  [int_width] i32\ng:\n  [int_width] i32\n"
+        );
+        assert!(ledger
+            .to_json()
+            .unwrap()
+            .contains("\"kind\": \"int_width\""));
+    }
+}
@@ -0,0 +1,114 @@
+//! `@functools.lru_cache` memoization
+//!
+//! A memoized function keeps its results in a thread-local `HashMap` keyed by
+//! an owned copy of its arguments. The original body runs in a closure, so
+//! its `return`s produce the value that is cached:
+//!
+//! ```rust,ignore
+//! fn fib(n: i64) -> i64 {
+//!     thread_local! {
+//!         static FIB_CACHE: RefCell<HashMap<(i64,), i64>> = RefCell::new(HashMap::new());
+//!     }
+//!     let cache_key = (n,);
+//!     if let Some(cached) = FIB_CACHE.with(|cache| cache.borrow().get(&cache_key).cloned()) {
+//!         return cached;
+//!     }
+//!     let result = (|| -> i64 { /* body */ })();
+//!     FIB_CACHE.with(|cache| { /* insert a clone */ });
+//!     result
+//! }
+//! ```
+//!
+//! A bounded cache is emptied when it fills up instead of evicting its least
+//! recently used entry. Functions that cannot be cached this way (generics,
+//! borrowed, defaulted or unhashable arguments, `Result` returns) are
+//! generated without a cache, which gives the same results.
+
+use crate::hir::{HirFunction, Memoize, Type};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::default_param_gen;
+use crate::rust_gen::keywords::safe_ident;
+use crate::rust_gen::type_gen::rust_type_to_syn;
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+/// `body_stmts` of `func` wrapped in a result cache, or `None` when the
+/// function has to be generated without one
+///
+/// `return_type` is the function's generated `-> T`.
+pub(crate) fn codegen_memoized_body(
+    func: &HirFunction,
+    memoize: Memoize,
+    return_type: &TokenStream,
+    body_stmts: &[TokenStream],
+    ctx: &CodeGenContext,
+) -> Result<Option<Vec<TokenStream>>> {
+    if func.properties.is_generator || func.properties.is_async || func.properties.can_fail {
+        return Ok(None);
+    }
+    let value_type = match syn::parse2::<syn::ReturnType>(return_type.clone()) {
+        Ok(syn::ReturnType::Type(_, ty)) if !quote!(#ty).to_string().contains('&') => ty,
+        _ => return Ok(None),
+    };
+
+    let mut key_types = Vec::new();
+    let mut key_parts = Vec::new();
+    for param in &func.params {
+        // The key would hold the `Option` rather than the value it stands for
+        if default_param_gen::param_takes_option(param, func) {
+            return Ok(None);
+        }
+        let name = safe_ident(&param.name);
+        match owned_key(&name, &param.ty) {
+            Some(part) => key_parts.push(part),
+            None => return Ok(None),
+        }
+        key_types.push(rust_type_to_syn(&ctx.type_mapper.map_type(&param.ty))?);
+    }
+
+    let cache = format_ident!("{}_CACHE", func.name.to_uppercase());
+    let evict = memoize.max_size.map(|max_size| {
+        quote! {
+            if cache.len() >= #max_size {
+                cache.clear();
+            }
+        }
+    });
+    Ok(Some(vec![quote! {
+        thread_local! {
+            static #cache: std::cell::RefCell<
+                std::collections::HashMap<(#(#key_types,)*), #value_type>
+            > = std::cell::RefCell::new(std::collections::HashMap::new());
+        }
+        let cache_key = (#(#key_parts,)*);
+        if let Some(cached) = #cache.with(|cache| cache.borrow().get(&cache_key).cloned()) {
+            return cached;
+        }
+        let result = (|| #return_type { #(#body_stmts)* })();
+        #cache.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            #evict
+            cache.insert(cache_key, result.clone());
+        });
+        result
+    }]))
+}
+
+/// `name` copied into an owned part of a cache key, if values of `ty` hash
+fn owned_key(name: &syn::Ident, ty: &Type) -> Option<TokenStream> {
+    match ty {
+        Type::Int | Type::Bool => Some(quote! { #name }),
+        Type::Optional(inner) if is_copy_key(inner) => Some(quote! { #name }),
+        Type::String => Some(quote! { #name.to_string() }),
+        Type::Tuple(items) if items.iter().all(is_copy_key) => Some(quote! { #name.clone() }),
+        Type::List(inner) if is_copy_key(inner) || matches!(**inner, Type::String) => {
+            Some(quote! { #name.to_vec() })
+        }
+        _ => None,
+    }
+}
+
+fn is_copy_key(ty: &Type) -> bool {
+    matches!(ty, Type::Int | Type::Bool)
+}
@@ -0,0 +1,198 @@
+//! `int(s)` and `float(s)` on strings
+//!
+//! Rust's `str::parse` is stricter than Python: it rejects surrounding
+//! whitespace, `_` digit separators and base prefixes, and the error carries
+//! no `ValueError`. Parsing calls the `py_int` and `py_float` helpers emitted
+//! into the module instead, which follow Python's rules and fail with
+//! `ValueError` and Python's message:
+//!
+//! | Python       | Rust              |
+//! |--------------|-------------------|
+//! | `int(s)`     | `py_int(&s, 10)`  |
+//! | `int(s, 16)` | `py_int(&s, 16)`  |
+//! | `int(s, 0)`  | `py_int(&s, 0)`   |
+//! | `float(s)`   | `py_float(&s)`    |
+//!
+//! With base 0 the base comes from the `0x`/`0o`/`0b` prefix.
+//!
+//! The `ValueError` propagates with `?` from functions returning it, is
+//! left to the enclosing `try` when one catches it, and panics otherwise.
+
+use crate::hir::{HirExpr, Literal};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::error_gen;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// `int(text)` or `int(text, base)` on a string
+pub(crate) fn codegen_int_parse(
+    text: &HirExpr,
+    text_expr: &syn::Expr,
+    base: Option<(&HirExpr, &syn::Expr)>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let call = py_int_call(text, text_expr, base, ctx);
+    handle_value_error(call, ctx)
+}
+
+/// The `py_int` call itself, for callers matching on its `Result`
+pub(crate) fn py_int_call(
+    text: &HirExpr,
+    text_expr: &syn::Expr,
+    base: Option<(&HirExpr, &syn::Expr)>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    ctx.needs_py_int = true;
+    ctx.needs_valueerror = true;
+    let text_arg = text_argument(text, text_expr);
+    let base_arg: syn::Expr = match base {
+        None => parse_quote! { 10 },
+        Some((HirExpr::Literal(Literal::Int(n)), _)) if *n >= 0 => {
+            let n = proc_macro2::Literal::u32_unsuffixed(*n as u32);
+            parse_quote! { #n }
+        }
+        Some((_, base_expr)) => parse_quote! { (#base_expr) as u32 },
+    };
+    parse_quote! { py_int(#text_arg, #base_arg) }
+}
+
+/// `float(text)` on a string
+pub(crate) fn codegen_float_parse(
+    text: &HirExpr,
+    text_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    ctx.needs_py_float = true;
+    ctx.needs_valueerror = true;
+    let text_arg = text_argument(text, text_expr);
+    handle_value_error(parse_quote! { py_float(#text_arg) }, ctx)
+}
+
+fn text_argument(text: &HirExpr, text_expr: &syn::Expr) -> syn::Expr {
+    match text {
+        HirExpr::Literal(Literal::String(s)) => parse_quote! { #s },
+        _ => parse_quote! { &#text_expr },
+    }
+}
+
+/// The error `py_int` and `py_float` fail with
+pub(crate) const PARSE_ERROR: &str = "ValueError";
+
+fn handle_value_error(call: syn::Expr, ctx: &CodeGenContext) -> syn::Expr {
+    error_gen::handle_builtin_error(call, PARSE_ERROR, ctx)
+}
+
+/// The `py_int` and `py_float` helpers the module's parsing calls use
+pub fn generate_parse_helpers(ctx: &CodeGenContext) -> Vec<TokenStream> {
+    let mut helpers = Vec::new();
+
+    if ctx.needs_py_int {
+        helpers.push(quote! {
+            /// Python's `int(text, base)`: surrounding whitespace, a sign and
+            /// `_` between digits are accepted, as is the base's `0x`/`0o`/`0b`
+            /// prefix; base 0 takes the base from the prefix
+            fn py_int(text: &str, base: u32) -> Result<i32, ValueError> {
+                if base == 1 || base > 36 {
+                    return Err(ValueError::new("int() base must be >= 2 and <= 36, or 0"));
+                }
+                let invalid = || {
+                    ValueError::new(format!(
+                        "invalid literal for int() with base {}: '{}'",
+                        base, text
+                    ))
+                };
+                let trimmed = text.trim();
+                let (negative, unsigned) = match trimmed.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+                };
+                let prefix_base = match unsigned.get(..2).map(|p| p.to_ascii_lowercase()).as_deref() {
+                    Some("0x") => 16,
+                    Some("0o") => 8,
+                    Some("0b") => 2,
+                    _ => 0,
+                };
+                let (radix, digits) = if prefix_base != 0 && (base == 0 || base == prefix_base) {
+                    // A single `_` may separate the prefix from the digits
+                    let digits = &unsigned[2..];
+                    (prefix_base, digits.strip_prefix('_').unwrap_or(digits))
+                } else if base == 0 {
+                    // Like Python source, base 0 rejects decimals with leading zeros
+                    if unsigned.starts_with('0') && unsigned.chars().any(|c| c != '0' && c != '_') {
+                        return Err(invalid());
+                    }
+                    (10, unsigned)
+                } else {
+                    (base, unsigned)
+                };
+                if digits.is_empty()
+                    || digits.starts_with('_')
+                    || digits.ends_with('_')
+                    || digits.contains("__")
+                    || !digits.chars().all(|c| c == '_' || c.is_digit(radix))
+                {
+                    return Err(invalid());
+                }
+                let mut signed: String = digits.chars().filter(|&c| c != '_').collect();
+                if negative {
+                    signed.insert(0, '-');
+                }
+                let value = if radix == 10 {
+                    signed.parse::<i32>()
+                } else {
+                    i32::from_str_radix(&signed, radix)
+                };
+                value.map_err(|_| {
+                    ValueError::new(format!("int() result out of range for i32: '{}'", text))
+                })
+            }
+        });
+    }
+
+    if ctx.needs_py_float {
+        helpers.push(quote! {
+            /// Python's `float(text)`: surrounding whitespace and `_` between
+            /// digits are accepted, as are `inf`, `infinity` and `nan` in any case
+            fn py_float(text: &str) -> Result<f64, ValueError> {
+                let invalid =
+                    || ValueError::new(format!("could not convert string to float: '{}'", text));
+                let trimmed = text.trim();
+                let bytes = trimmed.as_bytes();
+                for (i, &b) in bytes.iter().enumerate() {
+                    let between_digits = i > 0
+                        && bytes[i - 1].is_ascii_digit()
+                        && bytes.get(i + 1).is_some_and(|next| next.is_ascii_digit());
+                    if b == b'_' && !between_digits {
+                        return Err(invalid());
+                    }
+                }
+                trimmed.replace('_', "").parse::<f64>().map_err(|_| invalid())
+            }
+        });
+    }
+
+    helpers
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_base_argument_is_passed_through() {
+        let code = transpile("def hex_value(s: str) -> int:\n    return int(s, 16)\n");
+
+        assert!(code.contains("py_int(&s,16)"));
+        assert!(code.contains("fnpy_int(text:&str,base:u32)->Result<i32,ValueError>"));
+        assert!(code.contains("pubstructValueError"));
+    }
+
+    #[test]
+    fn test_numbers_are_still_cast() {
+        let code = transpile("def whole(x: float) -> int:\n    return int(x)\n");
+
+        assert!(code.contains("(x)asi32"));
+        assert!(!code.contains("py_int"));
+    }
+}
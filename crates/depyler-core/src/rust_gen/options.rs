@@ -0,0 +1,266 @@
+//! Code generation options
+//!
+//! User-facing knobs that change the shape of the generated Rust code.
+//! Options are carried by `DepylerPipeline` and copied into the
+//! `CodeGenContext` for the duration of a `generate_rust_file` call.
+
+use crate::type_mapper::TypeProfile;
+use depyler_annotations::{IntegerSemantics, PrintHandling};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Options controlling Rust code generation
+///
+/// The default value reproduces the historical output of
+/// `generate_rust_file`, so every option is opt-in.
+///
+/// # Complexity
+/// N/A (data structure)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeGenOptions {
+    /// Exception-to-exit-code mapping for fallible `main()` functions
+    #[serde(default)]
+    pub exit_codes: ExitCodeOptions,
+    /// Emit `warnings.warn` through `log::warn!` instead of `eprintln!`
+    #[serde(default)]
+    pub warnings_via_log: bool,
+    /// Module functions to put behind trait seams so tests can inject fakes
+    #[serde(default)]
+    pub seams: Vec<String>,
+    /// Drop assignments whose value is never read, when computing it has no
+    /// side effects
+    #[serde(default)]
+    pub elide_dead_stores: bool,
+    /// Emit each class as a module holding its struct, impls and the
+    /// exception classes only it raises, re-exported at the top level
+    #[serde(default)]
+    pub class_modules: bool,
+    /// `.pyi` stub sources for unmapped imports, keyed by Python module name,
+    /// used to type the `todo!()` stubs generated for those modules
+    #[serde(default)]
+    pub pyi_stubs: BTreeMap<String, String>,
+    /// Other modules of the project, keyed by the module name imports of
+    /// them use, generated into the same crate
+    #[serde(default)]
+    pub project_modules: BTreeMap<String, crate::module_mapper::ProjectModule>,
+    /// Implementation choices made when nothing more specific is asked for
+    #[serde(default)]
+    pub profile: PerformanceProfile,
+    /// Platform the types are chosen for: `server`, `embedded` or `wasm`
+    #[serde(default)]
+    pub type_profile: TypeProfile,
+    /// Hash map behind `HashMap`, overriding `hash` annotations and the
+    /// profile
+    #[serde(default)]
+    pub hash_backend: Option<HashBackend>,
+    /// Error type of fallible functions
+    #[serde(default)]
+    pub error_model: ErrorModel,
+    /// Convert `str()` and `repr()` with Rust's `Display` and `Debug`
+    /// instead of Python's formatting of floats, bools, `None` and strings
+    #[serde(default)]
+    pub native_strings: bool,
+    /// Fail with depyler's terse messages instead of the ones CPython
+    /// prints, such as `KeyError: 'name'` for a missing dict key
+    #[serde(default)]
+    pub native_error_messages: bool,
+    /// Overflow behavior of `int` arithmetic in functions without an
+    /// `integer_semantics` annotation
+    #[serde(default)]
+    pub integer_semantics: IntegerSemantics,
+    /// What becomes of `print()` statements in functions without a `print`
+    /// annotation
+    #[serde(default)]
+    pub print_handling: PrintHandling,
+    /// Pass stdin, stdout and stderr to the module functions using them as
+    /// `impl BufRead` and `impl Write` parameters, bound to the std streams
+    /// by `main()`
+    #[serde(default)]
+    pub injectable_io: bool,
+    /// Occurrences of a string literal across the module's functions from
+    /// which it becomes a shared constant, instead of
+    /// `string_optimization::DEFAULT_INTERN_THRESHOLD`; 0 turns interning
+    /// off
+    #[serde(default)]
+    pub intern_threshold: Option<usize>,
+    /// Add `#[inline]`, `#[must_use]` and complexity notes to module
+    /// functions as analysis finds them fitting
+    #[serde(default)]
+    pub attribute_synthesis: Option<crate::attribute_synthesis::AttributeOptions>,
+    /// Give `NewType` structs `From` conversions to and from their base
+    /// type and `Deref` to it
+    #[serde(default)]
+    pub newtype_conversions: bool,
+}
+
+/// What fallible functions put in the `Err` of their `Result`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorModel {
+    /// The exception type when a function raises only one, else
+    /// `Box<dyn std::error::Error>`
+    #[default]
+    PerFunction,
+    /// A `ModuleError` enum with a variant per exception raised or caught
+    /// anywhere in the module, shared by every fallible function so `?`
+    /// works between them
+    ModuleEnum,
+}
+
+/// Hash map implementation the generated code binds to `HashMap`
+///
+/// One module has a single `HashMap`, so the choice is made per file.
+/// [`HashBackend::BTree`] binds `HashSet` too.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashBackend {
+    /// `std::collections::HashMap` with SipHash, resistant to HashDoS
+    #[default]
+    Std,
+    /// `fnv::FnvHashMap`, fastest for small keys such as integers
+    Fnv,
+    /// `ahash::AHashMap`, fast for keys of any size
+    AHash,
+    /// `BTreeMap` and `BTreeSet`, ordered, and available without std
+    BTree,
+}
+
+impl HashBackend {
+    /// Name used by `# @depyler: hash = "..."`
+    pub fn name(self) -> &'static str {
+        match self {
+            HashBackend::Std => "std",
+            HashBackend::Fnv => "fnv",
+            HashBackend::AHash => "ahash",
+            HashBackend::BTree => "btree",
+        }
+    }
+}
+
+/// Performance/portability trade-off for generated code
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerformanceProfile {
+    /// Standard library only
+    #[default]
+    Portable,
+    /// Faster hashing at the cost of an extra dependency
+    Throughput,
+    /// Tuned for maps keyed by integers and short strings
+    SmallKeys,
+}
+
+impl PerformanceProfile {
+    pub fn hash_backend(self) -> HashBackend {
+        match self {
+            PerformanceProfile::Portable => HashBackend::Std,
+            PerformanceProfile::Throughput => HashBackend::AHash,
+            PerformanceProfile::SmallKeys => HashBackend::Fnv,
+        }
+    }
+}
+
+/// Exception-to-exit-code mapping for CLI programs
+///
+/// Python scripts that let an exception escape `main()` print
+/// `ValueError: msg` to stderr and exit with status 1. When enabled, a
+/// fallible `main()` is renamed to [`ExitCodeOptions::INNER_MAIN`] and a
+/// non-fallible `fn main()` wrapper reproduces that behavior instead of
+/// Rust's `Error: ValueError { .. }` debug output.
+///
+/// # Example
+/// ```
+/// use depyler_core::rust_gen::ExitCodeOptions;
+///
+/// let options = ExitCodeOptions::enabled()
+///     .with_exit_code("ValueError", 2)
+///     .with_default_exit_code(3);
+/// assert_eq!(options.exit_code_for("ValueError"), 2);
+/// assert_eq!(options.exit_code_for("IndexError"), 3);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExitCodeOptions {
+    /// Generate the wrapper at all
+    pub enabled: bool,
+    /// Exit status for exceptions without an explicit mapping (Python uses 1)
+    pub default_exit_code: i32,
+    /// Per-exception overrides, matched on the Python exception name
+    pub exception_codes: Vec<(String, i32)>,
+    /// Print Python's `Traceback (most recent call last):` header before the message
+    pub traceback_header: bool,
+}
+
+impl Default for ExitCodeOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_exit_code: 1,
+            exception_codes: Vec::new(),
+            traceback_header: false,
+        }
+    }
+}
+
+impl ExitCodeOptions {
+    /// Name given to the user's `main()` when the wrapper takes its place
+    pub const INNER_MAIN: &'static str = "__depyler_main";
+
+    /// Options with the wrapper turned on and Python's default exit status
+    pub fn enabled() -> Self {
+        Self {
+            enabled: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_exit_code(mut self, exception: impl Into<String>, code: i32) -> Self {
+        let exception = exception.into();
+        self.exception_codes.retain(|(name, _)| name != &exception);
+        self.exception_codes.push((exception, code));
+        self
+    }
+
+    pub fn with_default_exit_code(mut self, code: i32) -> Self {
+        self.default_exit_code = code;
+        self
+    }
+
+    pub fn with_traceback_header(mut self) -> Self {
+        self.traceback_header = true;
+        self
+    }
+
+    /// Exit status used when `exception` escapes `main()`
+    ///
+    /// # Complexity
+    /// 2 (linear lookup + fallback)
+    pub fn exit_code_for(&self, exception: &str) -> i32 {
+        self.exception_codes
+            .iter()
+            .find(|(name, _)| name == exception)
+            .map(|(_, code)| *code)
+            .unwrap_or(self.default_exit_code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_options_are_disabled() {
+        let options = CodeGenOptions::default();
+        assert!(!options.exit_codes.enabled);
+        assert_eq!(options.exit_codes.default_exit_code, 1);
+    }
+
+    #[test]
+    fn test_exit_code_override_replaces_previous_mapping() {
+        let options = ExitCodeOptions::enabled()
+            .with_exit_code("ValueError", 2)
+            .with_exit_code("ValueError", 4);
+        assert_eq!(options.exception_codes.len(), 1);
+        assert_eq!(options.exit_code_for("ValueError"), 4);
+        assert_eq!(options.exit_code_for("KeyError"), 1);
+    }
+}
@@ -0,0 +1,413 @@
+//! printf-style `%` formatting
+//!
+//! `"%s: %d" % (name, count)` becomes a `format!` call with a field for each
+//! conversion of the template, which must be a string literal:
+//!
+//! | Python           | Rust               | Argument                         |
+//! |------------------|--------------------|----------------------------------|
+//! | `%s`             | `{}`               | as `str(x)` formats it           |
+//! | `%r`, `%a`       | `{}`               | as `repr(x)` formats it          |
+//! | `%d`, `%i`, `%u` | `{}`               | floats truncated with `as i64`   |
+//! | `%05d`, `%-4d`   | `{:05}`, `{:<4}`   |                                  |
+//! | `%f`, `%+.2f`    | `{:.6}`, `{:+.2}`  | ints converted with `as f64`     |
+//! | `%x`, `%X`, `%#o`| `{}`               | sign and digits of the magnitude |
+//! | `%c`             | `{}`               | ints converted to a `char`       |
+//! | `%%`             | `%`                |                                  |
+//!
+//! Rust's `{:x}`, `{:X}` and `{:o}` write the two's complement of a
+//! negative number where Python writes `-ff`, so those fields format the
+//! sign and the magnitude into a string, zero padding included.
+//!
+//! Python right-aligns every padded field and Rust left-aligns strings, so
+//! the alignment is always spelled out. `%#X` prefixes `0x` where Python
+//! writes `0X`. `%e` and `%g`, mapping keys
+//! (`%(name)s`), `*` widths and the space flag have no `format!` equivalent
+//! and are rejected.
+
+use crate::hir::{HirExpr, Type};
+use anyhow::{bail, Result};
+use std::iter::Peekable;
+use std::str::Chars;
+use syn::parse_quote;
+
+/// What a field turns its argument into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Conversion {
+    Str,
+    Repr,
+    Int,
+    Float,
+    Hex,
+    UpperHex,
+    Octal,
+    Char,
+}
+
+impl Conversion {
+    fn is_numeric(self) -> bool {
+        !matches!(self, Conversion::Str | Conversion::Repr | Conversion::Char)
+    }
+
+    fn is_integer(self) -> bool {
+        self == Conversion::Int || self.is_radix()
+    }
+
+    fn is_radix(self) -> bool {
+        matches!(
+            self,
+            Conversion::Hex | Conversion::UpperHex | Conversion::Octal
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Field {
+    conversion: Conversion,
+    /// `-`
+    left: bool,
+    /// `0`
+    zero: bool,
+    /// `+`
+    plus: bool,
+    /// `#`
+    alternate: bool,
+    width: Option<usize>,
+    precision: Option<usize>,
+}
+
+impl Field {
+    /// The `format!` placeholder for the field
+    fn placeholder(&self, debug: bool) -> String {
+        if self.conversion.is_radix() {
+            // `radix_digits` already zero-filled the field
+            return match self.width {
+                Some(width) if !self.zero || self.left => {
+                    format!("{{:{}{}}}", if self.left { '<' } else { '>' }, width)
+                }
+                _ => "{}".to_string(),
+            };
+        }
+        let numeric = self.conversion.is_numeric();
+        let zero = self.zero && numeric && !self.left;
+        let mut spec = String::new();
+        if self.width.is_some() && !zero {
+            spec.push(if self.left { '<' } else { '>' });
+        }
+        if self.plus && numeric {
+            spec.push('+');
+        }
+        if zero {
+            spec.push('0');
+        }
+        if let Some(width) = self.width {
+            spec.push_str(&width.to_string());
+        }
+        let precision = match self.conversion {
+            Conversion::Float => Some(self.precision.unwrap_or(6)),
+            Conversion::Str | Conversion::Repr => self.precision,
+            _ => None,
+        };
+        if let Some(precision) = precision {
+            spec.push('.');
+            spec.push_str(&precision.to_string());
+        }
+        if debug {
+            spec.push('?');
+        }
+        if spec.is_empty() {
+            "{}".to_string()
+        } else {
+            format!("{{:{}}}", spec)
+        }
+    }
+
+    /// The integer `value` of a `%x`, `%X` or `%o` field as Python writes
+    /// it: the sign, then the prefix, zeros and digits of the magnitude
+    fn radix_digits(&self, value: syn::Expr) -> syn::Expr {
+        let zero_width = self.width.filter(|_| self.zero && !self.left);
+        let mut spec = String::new();
+        if self.alternate {
+            spec.push('#');
+        }
+        if zero_width.is_some() {
+            spec.push_str("0width$");
+        }
+        spec.push(match self.conversion {
+            Conversion::Hex => 'x',
+            Conversion::UpperHex => 'X',
+            _ => 'o',
+        });
+        let format_string = format!("{{}}{{:{}}}", spec);
+        let positive = if self.plus { "+" } else { "" };
+        let width = zero_width.map(proc_macro2::Literal::usize_unsuffixed);
+        let operand: syn::Expr = match &value {
+            syn::Expr::Binary(_) => parse_quote! { (#value) },
+            _ => value,
+        };
+        match width {
+            Some(width) => parse_quote! {
+                {
+                    let __value = #operand as i128;
+                    let sign = if __value < 0 { "-" } else { #positive };
+                    format!(#format_string, sign, __value.unsigned_abs(), width = #width - sign.len())
+                }
+            },
+            None => parse_quote! {
+                {
+                    let __value = #operand as i128;
+                    let sign = if __value < 0 { "-" } else { #positive };
+                    format!(#format_string, sign, __value.unsigned_abs())
+                }
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece {
+    Literal(String),
+    Field(Field),
+}
+
+/// An argument as the caller converted it for its field
+pub(crate) struct FieldArg {
+    pub(crate) expr: syn::Expr,
+    /// Formatted with `Debug` rather than `Display`
+    pub(crate) debug: bool,
+}
+
+impl FieldArg {
+    pub(crate) fn display(expr: syn::Expr) -> Self {
+        Self { expr, debug: false }
+    }
+}
+
+/// `template % args`, with `convert` turning each argument into what its
+/// field formats
+pub(crate) fn codegen_percent_format(
+    template: &str,
+    args: &HirExpr,
+    mut convert: impl FnMut(&HirExpr, Conversion) -> Result<FieldArg>,
+) -> Result<syn::Expr> {
+    let pieces = parse(template)?;
+    // A tuple holds the arguments, anything else is the only one
+    let args = match args {
+        HirExpr::Tuple(items) => items.as_slice(),
+        arg => std::slice::from_ref(arg),
+    };
+    let fields = pieces
+        .iter()
+        .filter(|piece| matches!(piece, Piece::Field(_)))
+        .count();
+    if fields != args.len() {
+        bail!(
+            "`%` formatting of {:?} needs {} arguments, not {}",
+            template,
+            fields,
+            args.len()
+        );
+    }
+
+    let mut format_string = String::new();
+    let mut format_args = Vec::new();
+    for piece in &pieces {
+        match piece {
+            Piece::Literal(text) => {
+                format_string.push_str(&text.replace('{', "{{").replace('}', "}}"));
+            }
+            Piece::Field(field) => {
+                let arg = convert(&args[format_args.len()], field.conversion)?;
+                format_string.push_str(&field.placeholder(arg.debug));
+                if field.conversion.is_radix() {
+                    format_args.push(field.radix_digits(arg.expr));
+                } else {
+                    format_args.push(arg.expr);
+                }
+            }
+        }
+    }
+    if format_args.is_empty() {
+        return Ok(parse_quote! { #format_string.to_string() });
+    }
+    Ok(parse_quote! { format!(#format_string, #(#format_args),*) })
+}
+
+/// `arg_expr` as Python converts an argument of type `ty` for a numeric
+/// or `%c` field
+pub(crate) fn numeric_arg(conversion: Conversion, arg_expr: syn::Expr, ty: &Type) -> syn::Expr {
+    let operand: syn::Expr = match &arg_expr {
+        syn::Expr::Binary(_) => parse_quote! { (#arg_expr) },
+        _ => arg_expr.clone(),
+    };
+    match (ty, conversion) {
+        (Type::Float, conversion) if conversion.is_integer() => parse_quote! { (#operand as i64) },
+        (Type::Bool, conversion) if conversion.is_integer() => {
+            parse_quote! { i32::from(#arg_expr) }
+        }
+        (Type::Int, Conversion::Float) => parse_quote! { (#operand as f64) },
+        (Type::Bool, Conversion::Float) => parse_quote! { f64::from(u8::from(#arg_expr)) },
+        (Type::Int, Conversion::Char) => parse_quote! {
+            char::from_u32(#operand as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+        },
+        _ => arg_expr,
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Piece>> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            literal.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'(') {
+            bail!(
+                "mapping keys in `%` formatting are not supported: {:?}",
+                template
+            );
+        }
+        let (mut left, mut zero, mut plus, mut alternate) = (false, false, false, false);
+        while let Some(&flag) = chars.peek() {
+            match flag {
+                '-' => left = true,
+                '0' => zero = true,
+                '+' => plus = true,
+                '#' => alternate = true,
+                ' ' => bail!("the ` ` flag of `%` formatting is not supported"),
+                _ => break,
+            }
+            chars.next();
+        }
+        let width = digits(&mut chars)?;
+        let precision = if chars.peek() == Some(&'.') {
+            chars.next();
+            Some(digits(&mut chars)?.unwrap_or(0))
+        } else {
+            None
+        };
+        // Length modifiers mean nothing in Python
+        while matches!(chars.peek(), Some('h' | 'l' | 'L')) {
+            chars.next();
+        }
+        let conversion = match chars.next() {
+            Some('%') => {
+                literal.push('%');
+                continue;
+            }
+            Some('s') => Conversion::Str,
+            Some('r' | 'a') => Conversion::Repr,
+            Some('d' | 'i' | 'u') => Conversion::Int,
+            Some('f' | 'F') => Conversion::Float,
+            Some('x') => Conversion::Hex,
+            Some('X') => Conversion::UpperHex,
+            Some('o') => Conversion::Octal,
+            Some('c') => Conversion::Char,
+            Some(c @ ('e' | 'E' | 'g' | 'G')) => {
+                bail!("`%{}` has no `format!` equivalent", c)
+            }
+            Some(c) => bail!("unsupported format character {:?} in {:?}", c, template),
+            None => bail!("incomplete format in {:?}", template),
+        };
+        if precision.is_some() && conversion.is_integer() {
+            bail!("precision of integer `%` fields is not supported");
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+        }
+        pieces.push(Piece::Field(Field {
+            conversion,
+            left,
+            zero,
+            plus,
+            alternate,
+            width,
+            precision,
+        }));
+    }
+    if !literal.is_empty() {
+        pieces.push(Piece::Literal(literal));
+    }
+    Ok(pieces)
+}
+
+/// The number at the front of `chars`, if any
+fn digits(chars: &mut Peekable<Chars>) -> Result<Option<usize>> {
+    if chars.peek() == Some(&'*') {
+        bail!("`*` widths and precisions of `%` formatting are not supported");
+    }
+    let mut number = None;
+    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+        number = Some(number.unwrap_or(0) * 10 + digit as usize);
+        chars.next();
+    }
+    Ok(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(template: &str) -> Vec<String> {
+        parse(template)
+            .unwrap()
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => text,
+                Piece::Field(field) => field.placeholder(false),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fields_map_onto_format_specs() {
+        assert_eq!(placeholders("%s: %d"), ["{}", ": ", "{}"]);
+        assert_eq!(
+            placeholders("%05d|%-4d|%4s"),
+            ["{:05}", "|", "{:<4}", "|", "{:>4}"]
+        );
+        assert_eq!(
+            placeholders("%f %+.2f %8.3f"),
+            ["{:.6}", " ", "{:+.2}", " ", "{:>8.3}"]
+        );
+        assert_eq!(
+            placeholders("%x %6X %-06o %06x %.3s"),
+            ["{}", " ", "{:>6}", " ", "{:<6}", " ", "{}", " ", "{:.3}"]
+        );
+        assert_eq!(placeholders("100%% of %ld"), ["100% of ", "{}"]);
+    }
+
+    #[test]
+    fn test_radix_fields_format_sign_and_magnitude() {
+        let Piece::Field(field) = parse("%#08x").unwrap().remove(0) else {
+            panic!("expected a field");
+        };
+        let expected: syn::Expr = parse_quote! {
+            {
+                let __value = n as i128;
+                let sign = if __value < 0 { "-" } else { "" };
+                format!("{}{:#0width$x}", sign, __value.unsigned_abs(), width = 8 - sign.len())
+            }
+        };
+        assert_eq!(field.radix_digits(parse_quote! { n }), expected);
+    }
+
+    #[test]
+    fn test_unsupported_fields_are_rejected() {
+        for template in ["%(name)s", "%e", "%*d", "% d", "%.3d", "%", "%y"] {
+            assert!(parse(template).is_err(), "{}", template);
+        }
+    }
+
+    #[test]
+    fn test_argument_count_must_match() {
+        let args = HirExpr::Tuple(vec![HirExpr::Var("a".to_string())]);
+        let convert = |_: &HirExpr, _| Ok(FieldArg::display(parse_quote! { a }));
+        assert!(codegen_percent_format("%s %s", &args, convert).is_err());
+        let single = HirExpr::Var("a".to_string());
+        let expr = codegen_percent_format("{%s}", &single, convert).unwrap();
+        let expected: syn::Expr = parse_quote! { format!("{{{}}}", a) };
+        assert_eq!(expr, expected);
+    }
+}
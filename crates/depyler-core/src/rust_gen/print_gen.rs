@@ -0,0 +1,175 @@
+//! `print()` statements in library code
+//!
+//! A `print()` statement becomes `println!` by default. A module can
+//! instead drop its prints or hand them to the `log` facade, and a function
+//! can choose for itself with `# @depyler: print = "keep" | "strip" | "log"`:
+//!
+//! | Handling | `print("total", n)`              |
+//! |----------|----------------------------------|
+//! | `keep`   | `println!("{} {}", "total", n);` |
+//! | `strip`  | nothing                          |
+//! | `log`    | `log::debug!("{} {}", "total", n);` |
+//!
+//! Stripping a print drops its arguments too, so calls made only to be
+//! printed are not evaluated. Only prints used as statements are affected.
+
+use crate::hir::{HirExpr, HirFunction, HirModule, HirStmt};
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::options::CodeGenOptions;
+use anyhow::Result;
+use depyler_annotations::PrintHandling;
+use proc_macro2::TokenStream;
+use quote::quote;
+use serde::Serialize;
+
+/// How many `print()` statements of a module each handling applies to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PrintCounts {
+    pub kept: usize,
+    pub stripped: usize,
+    pub logged: usize,
+}
+
+impl PrintCounts {
+    pub fn total(&self) -> usize {
+        self.kept + self.stripped + self.logged
+    }
+
+    fn add(&mut self, handling: PrintHandling, count: usize) {
+        match handling {
+            PrintHandling::Keep => self.kept += count,
+            PrintHandling::Strip => self.stripped += count,
+            PrintHandling::Log => self.logged += count,
+        }
+    }
+}
+
+/// The handling of `func`'s prints: its `print` annotation, else the
+/// module's default
+pub(crate) fn function_print_handling(func: &HirFunction, ctx: &CodeGenContext) -> PrintHandling {
+    func.annotations
+        .print_handling
+        .unwrap_or(ctx.options.print_handling)
+}
+
+/// The print statements of `module`, counted by the handling they get
+pub fn count_prints(module: &HirModule, options: &CodeGenOptions) -> PrintCounts {
+    let mut counts = PrintCounts::default();
+    for func in &module.functions {
+        let handling = func
+            .annotations
+            .print_handling
+            .unwrap_or(options.print_handling);
+        counts.add(handling, print_statements(&func.body));
+    }
+    let methods = module.classes.iter().flat_map(|class| &class.methods);
+    for method in methods {
+        counts.add(options.print_handling, print_statements(&method.body));
+    }
+    counts
+}
+
+fn print_statements(body: &[HirStmt]) -> usize {
+    body.iter()
+        .map(|stmt| match stmt {
+            HirStmt::Expr(expr) => usize::from(is_print(expr)),
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => print_statements(then_body) + else_body.as_deref().map_or(0, print_statements),
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => print_statements(body),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                print_statements(body)
+                    + handlers
+                        .iter()
+                        .map(|handler| print_statements(&handler.body))
+                        .sum::<usize>()
+                    + orelse.as_deref().map_or(0, print_statements)
+                    + finalbody.as_deref().map_or(0, print_statements)
+            }
+            HirStmt::Match { cases, .. } => {
+                cases.iter().map(|case| print_statements(&case.body)).sum()
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
+fn is_print(expr: &HirExpr) -> bool {
+    matches!(expr, HirExpr::Call { func, .. } if func == "print")
+}
+
+/// The statement a `print()` statement becomes under the current handling;
+/// `None` for anything else, and for prints that are kept
+pub(crate) fn codegen_print_stmt(
+    expr: &HirExpr,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<TokenStream>> {
+    if !is_print(expr) {
+        return Ok(None);
+    }
+    match ctx.print_handling {
+        PrintHandling::Keep => Ok(None),
+        PrintHandling::Strip => Ok(Some(quote! {})),
+        PrintHandling::Log => {
+            let printed = expr.to_rust_expr(ctx)?;
+            Ok(Some(match printed {
+                syn::Expr::Macro(mut call) if call.mac.path.is_ident("println") => {
+                    call.mac.path = syn::parse_quote! { log::debug };
+                    if call.mac.tokens.is_empty() {
+                        call.mac.tokens = quote! { "" };
+                    }
+                    quote! { #call; }
+                }
+                other => quote! { #other; },
+            }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DepylerPipeline;
+
+    const SOURCE: &str = r#"
+def report(items: list[int]) -> int:
+    total = 0
+    for item in items:
+        print("item", item)
+        total = total + item
+    print()
+    return total
+
+# @depyler: print = "keep"
+def show(total: int) -> None:
+    print(total)
+"#;
+
+    #[test]
+    fn test_count_prints_by_handling() {
+        let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+        let options = CodeGenOptions {
+            print_handling: PrintHandling::Strip,
+            ..Default::default()
+        };
+        let counts = count_prints(&module, &options);
+        assert_eq!(
+            counts,
+            PrintCounts {
+                kept: 1,
+                stripped: 2,
+                logged: 0
+            }
+        );
+        assert_eq!(counts.total(), 3);
+    }
+}
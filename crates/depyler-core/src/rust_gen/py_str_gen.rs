@@ -0,0 +1,284 @@
+//! `str(x)` and `repr(x)` as Python formats them
+//!
+//! Rust's `Display` and `Debug` disagree with Python on the values programs
+//! print most: `str(3.0)` is `"3.0"` but `3.0_f64.to_string()` is `"3"`,
+//! `str(True)` is `"True"`, `str(None)` is `"None"`, and `repr` quotes
+//! strings with `'`. Conversions go by the argument's inferred type:
+//!
+//! | Argument            | `str(x)`            | `repr(x)`            |
+//! |---------------------|---------------------|----------------------|
+//! | constant            | folded to a literal | folded to a literal  |
+//! | `int`, `str`        | `x.to_string()`     | `x.py_repr()` (str)  |
+//! | `float`, `bool`     | `x.py_repr()`       | `x.py_repr()`        |
+//! | `Optional[str]`     | `"None"` or `x`     | `x.py_repr()`        |
+//! | other `Optional`s, lists, dicts, sets and tuples of these | `x.py_repr()` | `x.py_repr()` |
+//! | anything else       | `x.to_string()`     | `format!("{:?}", x)` |
+//!
+//! `py_repr` comes from the `PyRepr` trait emitted into the module.
+//! [`CodeGenOptions::native_strings`](crate::rust_gen::CodeGenOptions)
+//! keeps Rust's formatting throughout.
+
+use crate::const_eval::ConstEvaluator;
+use crate::hir::{HirExpr, Type};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::ledger;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// `str(arg)`
+pub(crate) fn codegen_str(
+    arg: &HirExpr,
+    arg_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    if ctx.options.native_strings {
+        ledger::record_display(arg, ctx);
+        return parse_quote! { #arg_expr.to_string() };
+    }
+    if let Some(value) = ConstEvaluator::new().eval(arg) {
+        let text = value.py_str();
+        return parse_quote! { #text.to_string() };
+    }
+    match ctx.value_type(arg) {
+        Type::Optional(inner) if *inner == Type::String => parse_quote! {
+            match &#arg_expr {
+                Some(text) => text.to_string(),
+                None => "None".to_string(),
+            }
+        },
+        ty @ (Type::Float
+        | Type::Bool
+        | Type::None
+        | Type::Optional(_)
+        | Type::List(_)
+        | Type::Dict(..)
+        | Type::Set(_)
+        | Type::Tuple(_))
+            if has_py_repr(&ty) =>
+        {
+            ctx.needs_py_repr = true;
+            parse_quote! { #arg_expr.py_repr() }
+        }
+        _ => parse_quote! { #arg_expr.to_string() },
+    }
+}
+
+/// `repr(arg)`
+pub(crate) fn codegen_repr(
+    arg: &HirExpr,
+    arg_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    if ctx.options.native_strings {
+        return parse_quote! { format!("{:?}", #arg_expr) };
+    }
+    if let Some(value) = ConstEvaluator::new().eval(arg) {
+        let text = value.py_repr();
+        return parse_quote! { #text.to_string() };
+    }
+    let ty = ctx.value_type(arg);
+    // `Debug` of an integer already is its repr
+    if ty != Type::Int && has_py_repr(&ty) {
+        ctx.needs_py_repr = true;
+        parse_quote! { #arg_expr.py_repr() }
+    } else {
+        parse_quote! { format!("{:?}", #arg_expr) }
+    }
+}
+
+/// Whether the Rust type generated for `ty` implements `PyRepr`
+fn has_py_repr(ty: &Type) -> bool {
+    match ty {
+        Type::Int | Type::Float | Type::String | Type::Bool | Type::None => true,
+        Type::Optional(inner) | Type::List(inner) | Type::Set(inner) => has_py_repr(inner),
+        Type::Dict(key, value) => has_py_repr(key) && has_py_repr(value),
+        Type::Tuple(items) => (2..=3).contains(&items.len()) && items.iter().all(has_py_repr),
+        _ => false,
+    }
+}
+
+/// The `PyRepr` trait the module's `str()` and `repr()` calls use
+pub fn generate_py_repr(ctx: &CodeGenContext) -> Vec<TokenStream> {
+    if !ctx.needs_py_repr {
+        return Vec::new();
+    }
+    vec![quote! {
+        /// Python's `repr()`, which is also `str()` for everything but strings
+        trait PyRepr {
+            fn py_repr(&self) -> String;
+        }
+
+        impl PyRepr for bool {
+            fn py_repr(&self) -> String {
+                let text = if *self { "True" } else { "False" };
+                text.to_string()
+            }
+        }
+
+        impl PyRepr for i32 {
+            fn py_repr(&self) -> String {
+                self.to_string()
+            }
+        }
+
+        impl PyRepr for i64 {
+            fn py_repr(&self) -> String {
+                self.to_string()
+            }
+        }
+
+        impl PyRepr for usize {
+            fn py_repr(&self) -> String {
+                self.to_string()
+            }
+        }
+
+        impl PyRepr for f64 {
+            /// `2.0`, `0.1`, `1e+20`, `1e-05`, `inf`, `nan`
+            fn py_repr(&self) -> String {
+                if self.is_nan() {
+                    return "nan".to_string();
+                }
+                if self.is_infinite() {
+                    return if *self > 0.0 { "inf" } else { "-inf" }.to_string();
+                }
+                let abs = self.abs();
+                if abs != 0.0 && !(1e-4..1e16).contains(&abs) {
+                    let text = format!("{:e}", self);
+                    let (mantissa, exponent) = text.split_once('e').unwrap_or((&text, "0"));
+                    let (sign, digits) = match exponent.strip_prefix('-') {
+                        Some(digits) => ('-', digits),
+                        None => ('+', exponent),
+                    };
+                    return format!("{}e{}{:0>2}", mantissa, sign, digits);
+                }
+                if self.fract() == 0.0 {
+                    format!("{:.1}", self)
+                } else {
+                    self.to_string()
+                }
+            }
+        }
+
+        impl PyRepr for str {
+            /// Quoted with `'`, or with `"` when only `'` occurs inside
+            fn py_repr(&self) -> String {
+                let quote = if self.contains('\'') && !self.contains('"') { '"' } else { '\'' };
+                let mut repr = String::with_capacity(self.len() + 2);
+                repr.push(quote);
+                for c in self.chars() {
+                    match c {
+                        '\\' => repr.push_str("\\\\"),
+                        '\n' => repr.push_str("\\n"),
+                        '\r' => repr.push_str("\\r"),
+                        '\t' => repr.push_str("\\t"),
+                        c if c == quote => {
+                            repr.push('\\');
+                            repr.push(c);
+                        }
+                        c if (c as u32) < 0x20 || c as u32 == 0x7f => {
+                            repr.push_str(&format!("\\x{:02x}", c as u32));
+                        }
+                        c => repr.push(c),
+                    }
+                }
+                repr.push(quote);
+                repr
+            }
+        }
+
+        impl PyRepr for String {
+            fn py_repr(&self) -> String {
+                self.as_str().py_repr()
+            }
+        }
+
+        impl<T: PyRepr + ?Sized> PyRepr for &T {
+            fn py_repr(&self) -> String {
+                (**self).py_repr()
+            }
+        }
+
+        impl<T: PyRepr> PyRepr for Option<T> {
+            fn py_repr(&self) -> String {
+                match self {
+                    Some(value) => value.py_repr(),
+                    None => "None".to_string(),
+                }
+            }
+        }
+
+        impl PyRepr for () {
+            fn py_repr(&self) -> String {
+                "None".to_string()
+            }
+        }
+
+        impl<T: PyRepr> PyRepr for [T] {
+            fn py_repr(&self) -> String {
+                let items: Vec<String> = self.iter().map(PyRepr::py_repr).collect();
+                format!("[{}]", items.join(", "))
+            }
+        }
+
+        impl<T: PyRepr> PyRepr for Vec<T> {
+            fn py_repr(&self) -> String {
+                self.as_slice().py_repr()
+            }
+        }
+
+        impl<K: PyRepr, V: PyRepr, S> PyRepr for std::collections::HashMap<K, V, S> {
+            fn py_repr(&self) -> String {
+                let entries: Vec<String> = self
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key.py_repr(), value.py_repr()))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+        }
+
+        impl<T: PyRepr, S> PyRepr for std::collections::HashSet<T, S> {
+            fn py_repr(&self) -> String {
+                if self.is_empty() {
+                    return "set()".to_string();
+                }
+                let items: Vec<String> = self.iter().map(PyRepr::py_repr).collect();
+                format!("{{{}}}", items.join(", "))
+            }
+        }
+
+        impl<A: PyRepr, B: PyRepr> PyRepr for (A, B) {
+            fn py_repr(&self) -> String {
+                format!("({}, {})", self.0.py_repr(), self.1.py_repr())
+            }
+        }
+
+        impl<A: PyRepr, B: PyRepr, C: PyRepr> PyRepr for (A, B, C) {
+            fn py_repr(&self) -> String {
+                format!("({}, {}, {})", self.0.py_repr(), self.1.py_repr(), self.2.py_repr())
+            }
+        }
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_str_of_float_uses_py_repr() {
+        let code = transpile("def show(x: float) -> str:\n    return str(x)\n");
+
+        assert!(code.contains("x.py_repr()"));
+        assert!(code.contains("traitPyRepr"));
+    }
+
+    #[test]
+    fn test_str_of_int_stays_to_string() {
+        let code = transpile("def show(n: int) -> str:\n    return str(n)\n");
+
+        assert!(code.contains("n.to_string()"));
+        assert!(!code.contains("PyRepr"));
+    }
+}
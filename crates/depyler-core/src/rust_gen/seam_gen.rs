@@ -0,0 +1,288 @@
+//! Trait seams for module functions that tests replace
+//!
+//! Python tests swap a dependency with `unittest.mock.patch("mod.fetch_rate")`.
+//! Rust has no such hook, so a seam makes the dependency explicit instead.
+//! For a seam target `fetch_rate`:
+//!
+//! - `pub trait FetchRateSeam` declares `fetch_rate(&self, ...)` with the
+//!   function's signature, and `DefaultFetchRate` implements it by calling
+//!   the real function
+//! - every function that calls `fetch_rate` directly gets a `<name>_with`
+//!   variant taking one `&impl <Target>Seam` per seam it uses, and the
+//!   original function becomes a wrapper passing the defaults
+//!
+//! Only direct callers get a variant; tests inject fakes through them.
+
+use crate::hir::HirFunction;
+use anyhow::{bail, Result};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::parse_quote;
+use syn::visit_mut::{self, VisitMut};
+
+/// `fetch_rate` -> `FetchRateSeam`
+pub(crate) fn trait_name(target: &str) -> String {
+    format!("{}Seam", pascal_case(target))
+}
+
+/// `fetch_rate` -> `DefaultFetchRate`
+pub(crate) fn default_impl_name(target: &str) -> String {
+    format!("Default{}", pascal_case(target))
+}
+
+/// `convert` -> `convert_with`
+pub(crate) fn variant_name(caller: &str) -> String {
+    format!("{}_with", caller)
+}
+
+pub(crate) fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().collect::<String>() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// Functions calling seam targets directly, with the targets each one uses
+/// in `targets` order
+pub(crate) fn seam_callers(
+    functions: &[HirFunction],
+    targets: &[String],
+) -> Vec<(String, Vec<String>)> {
+    functions
+        .iter()
+        .filter_map(|func| {
+            let used: Vec<String> = targets
+                .iter()
+                .filter(|t| **t != func.name && crate::ast_bridge::calls_function(&func.body, t))
+                .cloned()
+                .collect();
+            (!used.is_empty()).then(|| (func.name.clone(), used))
+        })
+        .collect()
+}
+
+/// Trait method for a seam target: `&self` receiver, plain parameter names
+pub(crate) fn seam_method_signature(sig: &syn::Signature) -> syn::Signature {
+    let mut sig = sig.clone();
+    for arg in sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat) = arg {
+            if let syn::Pat::Ident(ident) = pat.pat.as_mut() {
+                ident.mutability = None;
+                ident.by_ref = None;
+            }
+        }
+    }
+    sig.inputs.insert(0, parse_quote!(&self));
+    sig
+}
+
+/// Rewrite generated functions for the seam targets and append the seam traits
+///
+/// `functions` holds the tokens generated for each of `hir`, in order.
+///
+/// # Complexity
+/// 6 (target checks + per-function dispatch)
+pub(crate) fn apply_seams(
+    functions: Vec<TokenStream>,
+    hir: &[HirFunction],
+    targets: &[String],
+) -> Result<Vec<TokenStream>> {
+    for target in targets {
+        match hir.iter().find(|f| &f.name == target) {
+            Some(func) if func.properties.is_generator => {
+                bail!("seam target `{}` is a generator", target)
+            }
+            Some(_) => {}
+            None => bail!("seam target `{}` is not a module-level function", target),
+        }
+    }
+    let callers = seam_callers(hir, targets);
+
+    let mut seams = Vec::new();
+    for target in targets {
+        let index = hir
+            .iter()
+            .position(|f| &f.name == target)
+            .unwrap_or_default();
+        let item = find_fn(&functions[index], target)?;
+        seams.push(seam_items(&item));
+    }
+
+    let mut out = Vec::with_capacity(functions.len() + seams.len());
+    for (tokens, func) in functions.into_iter().zip(hir) {
+        match callers.iter().find(|(name, _)| name == &func.name) {
+            Some((_, used)) => out.push(split_caller(tokens, &func.name, used)?),
+            None => out.push(tokens),
+        }
+    }
+    out.extend(seams);
+    Ok(out)
+}
+
+fn find_fn(tokens: &TokenStream, name: &str) -> Result<syn::ItemFn> {
+    let file: syn::File = syn::parse2(tokens.clone())?;
+    file.items
+        .into_iter()
+        .find_map(|item| match item {
+            syn::Item::Fn(func) if func.sig.ident == name => Some(func),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow::anyhow!("no generated function `{}`", name))
+}
+
+/// `trait <Target>Seam` and its default implementation
+fn seam_items(target: &syn::ItemFn) -> TokenStream {
+    let name = target.sig.ident.to_string();
+    let trait_ident = format_ident!("{}", trait_name(&name));
+    let default_ident = format_ident!("{}", default_impl_name(&name));
+    let fn_ident = &target.sig.ident;
+    let sig = seam_method_signature(&target.sig);
+    let call = forward_call(fn_ident, &target.sig, &[]);
+    let trait_doc = format!(" Replaceable `{}` dependency", name);
+    let default_doc = format!(" Calls the real [`{}`]", name);
+
+    quote! {
+        #[doc = #trait_doc]
+        pub trait #trait_ident {
+            #sig;
+        }
+
+        #[doc = #default_doc]
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct #default_ident;
+
+        impl #trait_ident for #default_ident {
+            #sig {
+                #call
+            }
+        }
+    }
+}
+
+/// `callee(prefix..., <sig's parameters>)`, awaited for async signatures
+fn forward_call(callee: &syn::Ident, sig: &syn::Signature, prefix: &[TokenStream]) -> TokenStream {
+    let args = sig.inputs.iter().filter_map(|arg| match arg {
+        syn::FnArg::Typed(pat) => match pat.pat.as_ref() {
+            syn::Pat::Ident(ident) => Some(&ident.ident),
+            _ => None,
+        },
+        syn::FnArg::Receiver(_) => None,
+    });
+    let call = quote! { #callee(#(#prefix,)* #(#args),*) };
+    match sig.asyncness {
+        Some(_) => quote! { #call.await },
+        None => call,
+    }
+}
+
+/// `caller` becomes a wrapper around `caller_with`, which takes the seams
+fn split_caller(tokens: TokenStream, caller: &str, used: &[String]) -> Result<TokenStream> {
+    let file: syn::File = syn::parse2(tokens)?;
+    let mut items = Vec::with_capacity(file.items.len() + 1);
+    for item in file.items {
+        let mut func = match item {
+            syn::Item::Fn(func) if func.sig.ident == caller => func,
+            other => {
+                items.push(other);
+                continue;
+            }
+        };
+        if func.sig.inputs.iter().any(|arg| match arg {
+            syn::FnArg::Typed(pat) => !matches!(pat.pat.as_ref(), syn::Pat::Ident(_)),
+            syn::FnArg::Receiver(_) => true,
+        }) {
+            bail!(
+                "cannot add seams to `{}`: unsupported parameter pattern",
+                caller
+            );
+        }
+
+        let mut variant = func.clone();
+        variant.sig.ident = format_ident!("{}", variant_name(caller));
+        for (i, target) in used.iter().enumerate() {
+            let param = format_ident!("{}_seam", target);
+            let trait_ident = format_ident!("{}", trait_name(target));
+            variant
+                .sig
+                .inputs
+                .insert(i, parse_quote!(#param: &impl #trait_ident));
+        }
+        SeamCalls { targets: used }.visit_block_mut(&mut variant.block);
+        let doc = format!(" [`{}`] with its seam dependencies injected", caller);
+        variant.attrs.retain(|attr| !attr.path().is_ident("doc"));
+        variant.attrs.insert(0, parse_quote!(#[doc = #doc]));
+
+        for arg in func.sig.inputs.iter_mut() {
+            if let syn::FnArg::Typed(pat) = arg {
+                if let syn::Pat::Ident(ident) = pat.pat.as_mut() {
+                    ident.mutability = None;
+                }
+            }
+        }
+        let defaults: Vec<TokenStream> = used
+            .iter()
+            .map(|target| {
+                let default_ident = format_ident!("{}", default_impl_name(target));
+                quote! { &#default_ident }
+            })
+            .collect();
+        let call = forward_call(&variant.sig.ident, &func.sig, &defaults);
+        func.block = parse_quote!({ #call });
+
+        items.push(syn::Item::Fn(func));
+        items.push(syn::Item::Fn(variant));
+    }
+    Ok(quote! { #(#items)* })
+}
+
+/// `fetch_rate(x)` -> `fetch_rate_seam.fetch_rate(x)`
+struct SeamCalls<'a> {
+    targets: &'a [String],
+}
+
+impl VisitMut for SeamCalls<'_> {
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+        let call = match expr {
+            syn::Expr::Call(call) => call,
+            _ => return,
+        };
+        let target = match call.func.as_ref() {
+            syn::Expr::Path(p) => match p.path.get_ident() {
+                Some(ident) if self.targets.iter().any(|t| ident == t) => ident.clone(),
+                _ => return,
+            },
+            _ => return,
+        };
+        let param = format_ident!("{}_seam", target);
+        let args = &call.args;
+        *expr = parse_quote!(#param.#target(#args));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seam_names() {
+        assert_eq!(trait_name("fetch_rate"), "FetchRateSeam");
+        assert_eq!(default_impl_name("fetch_rate"), "DefaultFetchRate");
+        assert_eq!(variant_name("convert"), "convert_with");
+    }
+
+    #[test]
+    fn test_seam_method_signature_drops_mut_patterns() {
+        let sig: syn::Signature = parse_quote!(fn fetch_rate(mut currency: String) -> f64);
+        let method = seam_method_signature(&sig);
+        assert_eq!(
+            quote!(#method).to_string(),
+            quote!(fn fetch_rate(&self, currency: String) -> f64).to_string()
+        );
+    }
+}
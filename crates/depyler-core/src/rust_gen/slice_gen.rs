@@ -0,0 +1,551 @@
+//! Slices and slice assignment with Python's index rules
+//!
+//! Slice bounds may be negative, out of range or run backwards, and the step
+//! may be negative, none of which Rust ranges accept. Slicing calls helpers
+//! emitted into the module that resolve the bounds at runtime as CPython's
+//! `slice.indices()` does:
+//!
+//! | Python            | Rust                                                |
+//! |-------------------|-----------------------------------------------------|
+//! | `xs[a:b:c]`       | `py_slice(&xs, Some(a), Some(b), Some(c))`          |
+//! | `xs[-3:]`         | `py_slice(&xs, Some(-3), None, None)`               |
+//! | `xs[2:]`          | `xs[2usize.min(xs.len())..].to_vec()`               |
+//! | `s[1::2]`         | `py_slice(&s.chars().collect::<Vec<char>>(), ..)`   |
+//! | `s[2:5]`          | `py_str_slice(&s, Some(2), Some(5)).to_string()`    |
+//! | `s[-1]`           | `s.chars().rev().nth(0)`                            |
+//! | `xs[a:b] = ys`    | `py_slice_assign(xs.as_mut(), Some(a), Some(b), ys)` |
+//! | `xs[::2] = ys`    | `py_slice_assign_step(xs.as_mut(), .., ys)`         |
+//!
+//! Stepped slices walk the resolved range with `step_by`, reversed for a
+//! negative step. Plain slice assignment splices, so the list may grow or
+//! shrink; assigning to an extended slice needs a sequence of the same
+//! length and raises `ValueError` otherwise.
+//!
+//! Strings are indexed and sliced by character, as in Python, never by byte
+//! offsets that could split a UTF-8 sequence: `py_str_slice` counts chars
+//! and cuts the string at the byte offsets `char_indices` finds for them.
+//! Under `string_indexing = "ascii"` the offsets are bytes, sparing the walk
+//! over the string, and `py_ascii_slice` cuts it directly.
+//!
+//! A function returning a slice of its parameter returns it by reference
+//! (see `borrowed_return_gen`): `py_str_slice` already borrows from the
+//! string it cuts, and `py_subslice` borrows a step-less list slice.
+
+use crate::hir::{HirExpr, Literal, UnaryOp};
+use crate::rust_gen::context::{CodeGenContext, ToRustExpr};
+use crate::rust_gen::error_gen;
+use crate::rust_gen::ledger::DivergenceKind;
+use anyhow::Result;
+use depyler_annotations::StringIndexing;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// `base[start:stop:step]` on a list
+pub(crate) fn codegen_list_slice(
+    base_expr: &syn::Expr,
+    start: Option<syn::Expr>,
+    stop: Option<syn::Expr>,
+    step: Option<syn::Expr>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    if start.is_none() && stop.is_none() && step.is_none() {
+        return parse_quote! { #base_expr.clone() };
+    }
+    if let Some(range) = in_bounds_range(base_expr, start.as_ref(), stop.as_ref(), step.as_ref()) {
+        return parse_quote! { #base_expr[#range].to_vec() };
+    }
+    ctx.needs_py_slice = true;
+    let (start, stop, step) = (bound_arg(start), bound_arg(stop), bound_arg(step));
+    parse_quote! { py_slice(&#base_expr, #start, #stop, #step) }
+}
+
+/// `s[start:stop:step]` on a string, through its chars
+pub(crate) fn codegen_str_slice(
+    base_expr: &syn::Expr,
+    start: Option<syn::Expr>,
+    stop: Option<syn::Expr>,
+    step: Option<syn::Expr>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    ctx.needs_py_slice = true;
+    let (start, stop, step) = (bound_arg(start), bound_arg(stop), bound_arg(step));
+    if ctx.string_indexing == StringIndexing::Ascii {
+        record_ascii_indexing(ctx);
+        return parse_quote! {
+            py_slice(#base_expr.as_bytes(), #start, #stop, #step)
+                .into_iter()
+                .map(char::from)
+                .collect::<String>()
+        };
+    }
+    parse_quote! {
+        py_slice(&#base_expr.chars().collect::<Vec<char>>(), #start, #stop, #step)
+            .into_iter()
+            .collect::<String>()
+    }
+}
+
+/// `s[start:stop]` on a string
+pub(crate) fn codegen_str_range(
+    base_expr: &syn::Expr,
+    start: Option<syn::Expr>,
+    stop: Option<syn::Expr>,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let (start, stop) = (bound_arg(start), bound_arg(stop));
+    if ctx.string_indexing == StringIndexing::Ascii {
+        record_ascii_indexing(ctx);
+        ctx.needs_py_ascii_slice = true;
+        return parse_quote! { py_ascii_slice(&#base_expr, #start, #stop) };
+    }
+    ctx.needs_py_str_slice = true;
+    parse_quote! { py_str_slice(&#base_expr, #start, #stop).to_string() }
+}
+
+/// `base[start:stop]` borrowed from a `&str` or a list parameter
+pub(crate) fn codegen_borrowed_range(
+    base_expr: &syn::Expr,
+    start: Option<syn::Expr>,
+    stop: Option<syn::Expr>,
+    is_list: bool,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    if start.is_none() && stop.is_none() {
+        return if is_list {
+            parse_quote! { #base_expr.as_slice() }
+        } else {
+            base_expr.clone()
+        };
+    }
+    if !is_list {
+        ctx.needs_py_str_slice = true;
+        let (start, stop) = (bound_arg(start), bound_arg(stop));
+        return parse_quote! { py_str_slice(#base_expr, #start, #stop) };
+    }
+    if let Some(range) = in_bounds_range(base_expr, start.as_ref(), stop.as_ref(), None) {
+        return parse_quote! { &#base_expr[#range] };
+    }
+    ctx.needs_py_subslice = true;
+    let (start, stop) = (bound_arg(start), bound_arg(stop));
+    parse_quote! { py_subslice(#base_expr, #start, #stop) }
+}
+
+fn record_ascii_indexing(ctx: &mut CodeGenContext) {
+    ctx.divergences.record(
+        DivergenceKind::UnicodeIndexing,
+        "strings are indexed and sliced by byte (string_indexing = \"ascii\")",
+    );
+}
+
+/// `s[index]` on a string, the empty string when out of range
+pub(crate) fn codegen_str_index(
+    base_expr: &syn::Expr,
+    index: &HirExpr,
+    index_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let ascii = ctx.string_indexing == StringIndexing::Ascii;
+    if ascii {
+        record_ascii_indexing(ctx);
+    }
+    let from_end = match index {
+        HirExpr::Literal(Literal::Int(n)) if *n < 0 => Some(-*n - 1),
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => match operand.as_ref() {
+            HirExpr::Literal(Literal::Int(n)) if *n > 0 => Some(*n - 1),
+            _ => None,
+        },
+        _ => None,
+    };
+    let found: TokenStream = match (index, from_end) {
+        (HirExpr::Literal(Literal::Int(n)), _) if *n >= 0 => {
+            let n = proc_macro2::Literal::usize_unsuffixed(*n as usize);
+            if ascii {
+                quote! { #base_expr.as_bytes().get(#n).map(|&b| char::from(b)) }
+            } else {
+                quote! { #base_expr.chars().nth(#n) }
+            }
+        }
+        // `s[-n]` counts back from the end without measuring the string
+        (_, Some(n)) => {
+            let n = proc_macro2::Literal::usize_unsuffixed(n as usize);
+            if ascii {
+                quote! { #base_expr.as_bytes().iter().rev().nth(#n).map(|&b| char::from(b)) }
+            } else {
+                quote! { #base_expr.chars().rev().nth(#n) }
+            }
+        }
+        _ => {
+            let (len, nth) = if ascii {
+                (
+                    quote! { base.len() },
+                    quote! { base.as_bytes().get(idx).map(|&b| char::from(b)) },
+                )
+            } else {
+                (
+                    quote! { base.chars().count() },
+                    quote! { base.chars().nth(idx) },
+                )
+            };
+            quote! {
+                {
+                    let base = &#base_expr;
+                    let idx = (#index_expr) as i64;
+                    let idx = if idx < 0 { idx + #len as i64 } else { idx };
+                    usize::try_from(idx).ok().and_then(|idx| #nth)
+                }
+            }
+        }
+    };
+    parse_quote! { #found.map(|c| c.to_string()).unwrap_or_default() }
+}
+
+/// The `start`, `stop` and `step` of a `slice(...)` assignment index
+pub(crate) fn slice_index_bounds(index: &HirExpr) -> Option<&[HirExpr]> {
+    match index {
+        HirExpr::Call { func, args, .. } if func == "slice" && args.len() == 3 => Some(args),
+        _ => None,
+    }
+}
+
+/// `base[start:stop:step] = value`
+pub(crate) fn codegen_slice_assign(
+    base: &HirExpr,
+    bounds: &[HirExpr],
+    value: &HirExpr,
+    value_expr: syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> Result<TokenStream> {
+    let base_expr = base.to_rust_expr(ctx)?;
+    let bound_args = bounds
+        .iter()
+        .map(|bound| Ok(bound_arg(Some(bound.to_rust_expr(ctx)?))))
+        .collect::<Result<Vec<_>>>()?;
+    let (start, stop, step) = (&bound_args[0], &bound_args[1], &bound_args[2]);
+    // The assigned list is copied into the slice, leaving the original usable
+    let values: syn::Expr = match value {
+        HirExpr::Var(_) => parse_quote! { #value_expr.clone() },
+        _ => value_expr,
+    };
+
+    let is_plain = matches!(
+        bounds[2],
+        HirExpr::Literal(Literal::None) | HirExpr::Literal(Literal::Int(1))
+    );
+    // `as_mut` reaches the `Vec` behind both owned locals and `&mut` parameters
+    if is_plain {
+        ctx.needs_py_slice_assign = true;
+        return Ok(quote! { py_slice_assign(#base_expr.as_mut(), #start, #stop, #values); });
+    }
+    ctx.needs_py_slice_assign_step = true;
+    ctx.needs_valueerror = true;
+    let call = parse_quote! {
+        py_slice_assign_step(#base_expr.as_mut(), #start, #stop, #step, #values)
+    };
+    let call = error_gen::handle_builtin_error(call, "ValueError", ctx);
+    Ok(quote! { #call; })
+}
+
+/// A bound as the helpers' `Option<i64>` argument
+fn bound_arg(bound: Option<syn::Expr>) -> syn::Expr {
+    let bound = match bound {
+        None => return parse_quote! { None },
+        Some(syn::Expr::Path(path)) if path.path.is_ident("None") => return parse_quote! { None },
+        Some(bound) => bound,
+    };
+    match int_literal(&bound) {
+        Some(n) if n < 0 => {
+            let n = proc_macro2::Literal::i64_unsuffixed(-n);
+            parse_quote! { Some(-#n) }
+        }
+        Some(n) => {
+            let n = proc_macro2::Literal::i64_unsuffixed(n);
+            parse_quote! { Some(#n) }
+        }
+        None => parse_quote! { Some((#bound) as i64) },
+    }
+}
+
+/// The value of an integer literal, possibly negated
+fn int_literal(expr: &syn::Expr) -> Option<i64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(n),
+            ..
+        }) => n.base10_parse().ok(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => int_literal(expr).map(|n: i64| -n),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => int_literal(expr),
+        _ => None,
+    }
+}
+
+/// The range of a step-less slice on a local or field whose bounds are
+/// non-negative literals in order, which only needs clamping to the length
+fn in_bounds_range(
+    base_expr: &syn::Expr,
+    start: Option<&syn::Expr>,
+    stop: Option<&syn::Expr>,
+    step: Option<&syn::Expr>,
+) -> Option<TokenStream> {
+    if step.is_some() || !matches!(base_expr, syn::Expr::Path(_) | syn::Expr::Field(_)) {
+        return None;
+    }
+    let literal = |bound: Option<&syn::Expr>| match bound {
+        None => Some(None),
+        Some(bound) => int_literal(bound).filter(|n| *n >= 0).map(Some),
+    };
+    let clamp = |n: i64| {
+        let n = proc_macro2::Literal::usize_suffixed(n as usize);
+        quote! { #n.min(#base_expr.len()) }
+    };
+    match (literal(start)?, literal(stop)?) {
+        (Some(start), Some(stop)) if start <= stop => {
+            let (start, stop) = (clamp(start), clamp(stop));
+            Some(quote! { #start..#stop })
+        }
+        (Some(start), None) => {
+            let start = clamp(start);
+            Some(quote! { #start.. })
+        }
+        (None, Some(stop)) => {
+            let stop = clamp(stop);
+            Some(quote! { ..#stop })
+        }
+        _ => None,
+    }
+}
+
+/// The slicing helpers the module's slices and slice assignments call
+pub fn generate_slice_helpers(ctx: &CodeGenContext) -> Vec<TokenStream> {
+    let mut helpers = Vec::new();
+    if !(ctx.needs_py_slice
+        || ctx.needs_py_slice_assign
+        || ctx.needs_py_slice_assign_step
+        || ctx.needs_py_str_slice
+        || ctx.needs_py_ascii_slice
+        || ctx.needs_py_subslice)
+    {
+        return helpers;
+    }
+
+    helpers.push(quote! {
+        /// Python's `slice(start, stop, step).indices(len)`: negative bounds
+        /// count from the end and out-of-range bounds are clamped
+        fn py_slice_indices(
+            len: usize,
+            start: Option<i64>,
+            stop: Option<i64>,
+            step: Option<i64>,
+        ) -> (i64, i64, i64) {
+            let len = len as i64;
+            let step = step.unwrap_or(1);
+            if step == 0 {
+                panic!("ValueError: slice step cannot be zero");
+            }
+            let (lower, upper) = if step > 0 { (0, len) } else { (-1, len - 1) };
+            let resolve = |index: i64| {
+                if index < 0 {
+                    (index + len).max(lower)
+                } else {
+                    index.min(upper)
+                }
+            };
+            let start = start.map_or(if step > 0 { lower } else { upper }, resolve);
+            let stop = stop.map_or(if step > 0 { upper } else { lower }, resolve);
+            (start, stop, step)
+        }
+    });
+
+    if ctx.needs_py_slice {
+        helpers.push(quote! {
+            /// Python's `items[start:stop:step]`
+            fn py_slice<T: Clone>(
+                items: &[T],
+                start: Option<i64>,
+                stop: Option<i64>,
+                step: Option<i64>,
+            ) -> Vec<T> {
+                let (start, stop, step) = py_slice_indices(items.len(), start, stop, step);
+                if step > 0 {
+                    if start >= stop {
+                        return Vec::new();
+                    }
+                    items[start as usize..stop as usize]
+                        .iter()
+                        .step_by(step as usize)
+                        .cloned()
+                        .collect()
+                } else {
+                    if start <= stop {
+                        return Vec::new();
+                    }
+                    items[(stop + 1) as usize..=start as usize]
+                        .iter()
+                        .rev()
+                        .step_by(step.unsigned_abs() as usize)
+                        .cloned()
+                        .collect()
+                }
+            }
+        });
+    }
+
+    if ctx.needs_py_str_slice {
+        helpers.push(quote! {
+            /// Python's `s[start:stop]`, cut at the byte offsets of the chars
+            /// the bounds count
+            fn py_str_slice(s: &str, start: Option<i64>, stop: Option<i64>) -> &str {
+                let (start, stop, _) = py_slice_indices(s.chars().count(), start, stop, None);
+                if start >= stop {
+                    return "";
+                }
+                let offset = |index: i64| {
+                    s.char_indices()
+                        .nth(index as usize)
+                        .map_or(s.len(), |(offset, _)| offset)
+                };
+                &s[offset(start)..offset(stop)]
+            }
+        });
+    }
+
+    if ctx.needs_py_ascii_slice {
+        helpers.push(quote! {
+            /// Python's `s[start:stop]` on an ASCII string, whose bytes are its chars
+            fn py_ascii_slice(s: &str, start: Option<i64>, stop: Option<i64>) -> String {
+                let (start, stop, _) = py_slice_indices(s.len(), start, stop, None);
+                s[start as usize..stop.max(start) as usize].to_string()
+            }
+        });
+    }
+
+    if ctx.needs_py_subslice {
+        helpers.push(quote! {
+            /// Python's `items[start:stop]`, borrowed from `items`
+            fn py_subslice<T>(items: &[T], start: Option<i64>, stop: Option<i64>) -> &[T] {
+                let (start, stop, _) = py_slice_indices(items.len(), start, stop, None);
+                &items[start as usize..stop.max(start) as usize]
+            }
+        });
+    }
+
+    if ctx.needs_py_slice_assign {
+        helpers.push(quote! {
+            /// Python's `items[start:stop] = values`, which may change the length
+            fn py_slice_assign<T>(
+                items: &mut Vec<T>,
+                start: Option<i64>,
+                stop: Option<i64>,
+                values: Vec<T>,
+            ) {
+                let (start, stop, _) = py_slice_indices(items.len(), start, stop, None);
+                items.splice(start as usize..stop.max(start) as usize, values);
+            }
+        });
+    }
+
+    if ctx.needs_py_slice_assign_step {
+        helpers.push(quote! {
+            /// Python's `items[start:stop:step] = values`: a step of 1 splices,
+            /// any other step replaces exactly the selected items
+            fn py_slice_assign_step<T>(
+                items: &mut Vec<T>,
+                start: Option<i64>,
+                stop: Option<i64>,
+                step: Option<i64>,
+                values: Vec<T>,
+            ) -> Result<(), ValueError> {
+                let (start, stop, step) = py_slice_indices(items.len(), start, stop, step);
+                if step == 1 {
+                    items.splice(start as usize..stop.max(start) as usize, values);
+                    return Ok(());
+                }
+                let positions: Vec<usize> = if step > 0 {
+                    (start..stop).step_by(step as usize).map(|i| i as usize).collect()
+                } else {
+                    ((stop + 1)..=start)
+                        .rev()
+                        .step_by(step.unsigned_abs() as usize)
+                        .map(|i| i as usize)
+                        .collect()
+                };
+                if positions.len() != values.len() {
+                    return Err(ValueError::new(format!(
+                        "attempt to assign sequence of size {} to extended slice of size {}",
+                        values.len(),
+                        positions.len()
+                    )));
+                }
+                for (position, value) in positions.into_iter().zip(values) {
+                    items[position] = value;
+                }
+                Ok(())
+            }
+        });
+    }
+
+    helpers
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_literal_bounds_in_order_are_clamped() {
+        let code = transpile("def f(xs: list[int]) -> int:\n    return len(xs[1:3])\n");
+
+        assert!(code.contains("xs[1usize.min(xs.len())..3usize.min(xs.len())].to_vec()"));
+        assert!(!code.contains("fnpy_slice"));
+    }
+
+    #[test]
+    fn test_negative_and_stepped_bounds_resolve_at_runtime() {
+        let code = transpile("def f(xs: list[int], n: int) -> list[int]:\n    return xs[-n::-2]\n");
+
+        assert!(code.contains("py_slice(&xs,Some((-n)asi64),None,Some(-2))"));
+        assert!(code.contains("fnpy_slice_indices(len:usize"));
+    }
+
+    #[test]
+    fn test_slice_assignment_splices_or_checks_lengths() {
+        let code = transpile(
+            "def f(xs: list[int], ys: list[int]) -> None:\n    xs[1:3] = ys\n    xs[::2] = [0, 0]\n",
+        );
+
+        assert!(code.contains("py_slice_assign(xs.as_mut(),Some(1),Some(3),ys.clone());"));
+        assert!(code.contains("py_slice_assign_step(xs.as_mut(),None,None,Some(2),vec![0,0])"));
+        assert!(code.contains("pubstructValueError"));
+    }
+
+    #[test]
+    fn test_strings_are_indexed_by_char() {
+        let code =
+            transpile("def f(s: str, i: int) -> str:\n    return s[0] + s[-2] + s[i] + s[1:i]\n");
+
+        assert!(code.contains("s.chars().nth(0).map(|c|c.to_string()).unwrap_or_default()"));
+        assert!(code.contains("s.chars().rev().nth(1)"));
+        assert!(code.contains("usize::try_from(idx).ok().and_then(|idx|base.chars().nth(idx))"));
+        assert!(code.contains("py_str_slice(&s,Some(1),Some((i)asi64))"));
+        assert!(code.contains("s.char_indices()"));
+    }
+
+    #[test]
+    fn test_ascii_strings_are_indexed_by_byte() {
+        let code = transpile(
+            "# @depyler: string_indexing = \"ascii\"\ndef f(s: str) -> str:\n    return s[0] + s[:-1] + s[::2]\n",
+        );
+
+        assert!(code.contains("s.as_bytes().get(0).map(|&b|char::from(b))"));
+        assert!(code.contains("py_ascii_slice(&s,None,Some(-1))"));
+        assert!(code.contains("py_slice(s.as_bytes(),None,None,Some(2))"));
+        assert!(!code.contains("fnpy_str_slice"));
+    }
+}
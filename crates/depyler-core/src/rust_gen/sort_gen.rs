@@ -0,0 +1,305 @@
+//! `sorted()` and `list.sort()` with Python's ordering
+//!
+//! Python's sort is stable and compares with `<`, which raises `TypeError`
+//! for values that do not order, such as `None` against anything. Rust's
+//! `sort` needs `Ord`, which floats lack, and reversing after sorting puts
+//! equal keys in the opposite order. The generated sorts therefore:
+//!
+//! | Python                              | Rust                                         |
+//! |-------------------------------------|----------------------------------------------|
+//! | `sorted(ints)`                      | `.sort()`                                    |
+//! | `sorted(floats)`                    | `.sort_by(..)` with NaN ordered last          |
+//! | `sorted(items, key=f, reverse=True)`| `.sort_by(\|a, b\| f(b).cmp(&f(a)))`        |
+//! | `sorted(maybe_ints)`                | `TypeError` when a `None` meets a value      |
+//!
+//! All of them are stable sorts. Floats order by value, with every NaN after
+//! the numbers and equal to each other, which keeps the order total so Rust's
+//! sort never panics on it. The `TypeError` propagates with `?` from
+//! functions returning it, is left to the enclosing `try` when one catches
+//! it, and panics otherwise.
+
+use crate::hir::{HirExpr, Type};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::error_gen;
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse_quote;
+
+/// A `key=lambda param: body` argument, already converted
+pub(crate) struct SortKey<'a> {
+    pub param: &'a str,
+    pub hir_body: &'a HirExpr,
+    pub body: syn::Expr,
+}
+
+/// `sorted(iterable, key=..., reverse=...)` as a sorted copy
+pub(crate) fn codegen_sorted(
+    iterable: &HirExpr,
+    iter_expr: &syn::Expr,
+    key: Option<SortKey<'_>>,
+    reverse: bool,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let result: syn::Expr = parse_quote! { __sorted_result };
+    let elem = element_type(iterable, ctx);
+    let sort = sort_statements(&result, &elem, key.as_ref(), reverse, ctx);
+    match none_check(&result, &elem, key.as_ref(), ctx) {
+        None => parse_quote! {
+            {
+                let mut __sorted_result = #iter_expr.clone();
+                #sort
+                __sorted_result
+            }
+        },
+        Some((check, message)) => {
+            let checked = parse_quote! {
+                (if #check {
+                    Err(TypeError::new(#message))
+                } else {
+                    #sort
+                    Ok(__sorted_result)
+                })
+            };
+            let sorted = error_gen::handle_builtin_error(checked, "TypeError", ctx);
+            parse_quote! {
+                {
+                    let mut __sorted_result = #iter_expr.clone();
+                    #sorted
+                }
+            }
+        }
+    }
+}
+
+/// `list.sort()` in place
+pub(crate) fn codegen_sort_in_place(
+    list: &HirExpr,
+    list_expr: &syn::Expr,
+    ctx: &mut CodeGenContext,
+) -> syn::Expr {
+    let elem = element_type(list, ctx);
+    let sort: syn::Expr = if needs_comparator(&elem) {
+        let ordering = ordering(&elem, &parse_quote! { (*a) }, &parse_quote! { (*b) });
+        parse_quote! { #list_expr.sort_by(|a, b| #ordering) }
+    } else {
+        parse_quote! { #list_expr.sort() }
+    };
+    match none_check(list_expr, &elem, None, ctx) {
+        None => sort,
+        Some((check, message)) => {
+            let checked = parse_quote! {
+                (if #check {
+                    Err(TypeError::new(#message))
+                } else {
+                    #sort;
+                    Ok(())
+                })
+            };
+            error_gen::handle_builtin_error(checked, "TypeError", ctx)
+        }
+    }
+}
+
+fn element_type(list: &HirExpr, ctx: &CodeGenContext) -> Type {
+    match ctx.value_type(list) {
+        Type::List(elem) => *elem,
+        _ => Type::Unknown,
+    }
+}
+
+/// The type `key` returns for elements of type `elem`
+fn key_type(key: &SortKey<'_>, elem: &Type, ctx: &mut CodeGenContext) -> Type {
+    let shadowed = ctx.var_types.insert(key.param.to_string(), elem.clone());
+    let ty = ctx.value_type(key.hir_body);
+    match shadowed {
+        Some(previous) => ctx.var_types.insert(key.param.to_string(), previous),
+        None => ctx.var_types.remove(key.param),
+    };
+    ty
+}
+
+/// Statements sorting `list` in place, stably
+fn sort_statements(
+    list: &syn::Expr,
+    elem: &Type,
+    key: Option<&SortKey<'_>>,
+    reverse: bool,
+    ctx: &mut CodeGenContext,
+) -> TokenStream {
+    match key {
+        None if !needs_comparator(elem) => {
+            // Equal values are indistinguishable, so reversing keeps stability
+            if reverse {
+                quote! { #list.sort(); #list.reverse(); }
+            } else {
+                quote! { #list.sort(); }
+            }
+        }
+        None => {
+            let (a, b): (syn::Expr, syn::Expr) = if reverse {
+                (parse_quote! { (*b) }, parse_quote! { (*a) })
+            } else {
+                (parse_quote! { (*a) }, parse_quote! { (*b) })
+            };
+            let ordering = ordering(elem, &a, &b);
+            quote! { #list.sort_by(|a, b| #ordering); }
+        }
+        Some(key) => {
+            let ty = key_type(key, elem, ctx);
+            let param = syn::Ident::new(key.param, proc_macro2::Span::call_site());
+            let body = &key.body;
+            if !reverse && !needs_comparator(&ty) {
+                return quote! { #list.sort_by_key(|#param| #body); };
+            }
+            let (first, second) = if reverse {
+                (quote! { __b }, quote! { __a })
+            } else {
+                (quote! { __a }, quote! { __b })
+            };
+            let ordering = ordering(&ty, &parse_quote! { __key_a }, &parse_quote! { __key_b });
+            quote! {
+                #list.sort_by(|__a, __b| {
+                    let __key_a = { let #param = #first; #body };
+                    let __key_b = { let #param = #second; #body };
+                    #ordering
+                });
+            }
+        }
+    }
+}
+
+/// Whether values of `ty` cannot be sorted with `Ord`
+fn needs_comparator(ty: &Type) -> bool {
+    match ty {
+        Type::Float => true,
+        Type::List(inner) | Type::Optional(inner) => needs_comparator(inner),
+        Type::Tuple(items) => items.iter().any(needs_comparator),
+        _ => false,
+    }
+}
+
+/// The `Ordering` of places `a` and `b` of type `ty`
+///
+/// Floats put NaN last; after [`none_check`] an optional value is only
+/// `None` in a list of one, where the order is never asked for.
+fn ordering(ty: &Type, a: &syn::Expr, b: &syn::Expr) -> syn::Expr {
+    match ty {
+        Type::Float => parse_quote! {
+            #a.partial_cmp(&#b).unwrap_or_else(|| #a.is_nan().cmp(&#b.is_nan()))
+        },
+        Type::Optional(inner) if needs_comparator(inner) => {
+            let inner = ordering(inner, &parse_quote! { (*x) }, &parse_quote! { (*y) });
+            parse_quote! {
+                match (&#a, &#b) {
+                    (Some(x), Some(y)) => #inner,
+                    (x, y) => x.is_some().cmp(&y.is_some()),
+                }
+            }
+        }
+        Type::List(inner) if needs_comparator(inner) => {
+            let inner = ordering(inner, &parse_quote! { (*x) }, &parse_quote! { (*y) });
+            parse_quote! {
+                #a.iter()
+                    .zip(#b.iter())
+                    .map(|(x, y)| #inner)
+                    .find(|order| order.is_ne())
+                    .unwrap_or_else(|| #a.len().cmp(&#b.len()))
+            }
+        }
+        Type::Tuple(items) if items.iter().any(needs_comparator) => {
+            let mut orders = items.iter().enumerate().map(|(i, item)| {
+                let i = syn::Index::from(i);
+                ordering(item, &parse_quote! { #a.#i }, &parse_quote! { #b.#i })
+            });
+            let first = orders
+                .next()
+                .unwrap_or_else(|| parse_quote! { std::cmp::Ordering::Equal });
+            orders.fold(first, |acc, next| parse_quote! { #acc.then_with(|| #next) })
+        }
+        _ => parse_quote! { #a.cmp(&#b) },
+    }
+}
+
+/// The condition under which sorting `list` raises `TypeError`, and the
+/// message, when its elements or keys may be `None`
+fn none_check(
+    list: &syn::Expr,
+    elem: &Type,
+    key: Option<&SortKey<'_>>,
+    ctx: &mut CodeGenContext,
+) -> Option<(syn::Expr, String)> {
+    let (ty, is_none): (Type, syn::Expr) = match key {
+        None => (elem.clone(), parse_quote! { |item| item.is_none() }),
+        Some(key) => {
+            let param = syn::Ident::new(key.param, proc_macro2::Span::call_site());
+            let body = &key.body;
+            (
+                key_type(key, elem, ctx),
+                parse_quote! { |#param| (#body).is_none() },
+            )
+        }
+    };
+    let inner = match ty {
+        Type::Optional(inner) => inner,
+        _ => return None,
+    };
+    ctx.needs_typeerror = true;
+    let message = format!(
+        "'<' not supported between instances of 'NoneType' and '{}'",
+        python_type_name(&inner)
+    );
+    Some((
+        parse_quote! { #list.len() > 1 && #list.iter().any(#is_none) },
+        message,
+    ))
+}
+
+/// The name `type(value).__name__` gives for a value of `ty`
+fn python_type_name(ty: &Type) -> &'static str {
+    match ty {
+        Type::Int => "int",
+        Type::Float => "float",
+        Type::String => "str",
+        Type::Bool => "bool",
+        Type::List(_) => "list",
+        Type::Tuple(_) => "tuple",
+        Type::Dict(_, _) => "dict",
+        Type::Set(_) => "set",
+        Type::None => "NoneType",
+        _ => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile;
+
+    #[test]
+    fn test_floats_sort_with_nan_last() {
+        let code = transpile("def f(xs: list[float]) -> list[float]:\n    return sorted(xs)\n");
+
+        assert!(code.contains("sort_by(|a,b|(*a).partial_cmp(&(*b))"));
+        assert!(code.contains("(*a).is_nan().cmp(&(*b).is_nan())"));
+    }
+
+    #[test]
+    fn test_reverse_with_key_compares_swapped() {
+        let code = transpile(
+            "def f(xs: list[int]) -> list[int]:\n    return sorted(xs, key=lambda x: x % 3, reverse=True)\n",
+        );
+
+        assert!(code.contains("let__key_a={letx=__b;"));
+        assert!(!code.contains(".reverse()"));
+    }
+
+    #[test]
+    fn test_none_elements_raise_type_error() {
+        let code = transpile(
+            "from typing import Optional\n\ndef f(xs: list[Optional[int]]) -> list[Optional[int]]:\n    return sorted(xs)\n",
+        );
+
+        assert!(code.contains("structTypeError"));
+        assert!(code.contains("any(|item|item.is_none())"));
+        assert!(code.contains("'NoneType'and'int'"));
+    }
+}
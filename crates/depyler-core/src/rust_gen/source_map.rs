@@ -0,0 +1,353 @@
+//! Generated Rust lines back to the Python they came from
+//!
+//! The table is keyed by symbol: every function, method and struct in the
+//! generated file is paired with the Python `def` or `class` it was
+//! generated from, whose lines come from [`HirSpans`], and both sides record
+//! the lines they span. Inside a function whose Rust body has as many top-level
+//! statements as the Python body, the statements are paired in order too;
+//! elsewhere a Rust line resolves to the whole Python definition.
+
+use crate::hir_spans::{HirSpans, Span};
+use crate::rust_gen::options::ExitCodeOptions;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use syn::spanned::Spanned;
+
+/// Inclusive 1-based line range
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    pub fn contains(&self, line: usize) -> bool {
+        (self.start..=self.end).contains(&line)
+    }
+
+    fn len(&self) -> usize {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceMapKind {
+    /// A function, method or struct
+    Item,
+    /// A top-level statement of a function body
+    Statement,
+}
+
+/// One generated item or statement and the Python code behind it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMapEntry {
+    pub kind: SourceMapKind,
+    /// `area`, `Shape::area`; statements carry their function's symbol
+    pub rust_symbol: String,
+    /// `area`, `Shape.area`
+    pub python_symbol: String,
+    pub rust_lines: LineRange,
+    pub python_lines: LineRange,
+    /// 1-based column where the Python code starts
+    pub python_column: usize,
+}
+
+/// Where each item of a generated Rust file came from in the Python source
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::DepylerPipeline;
+///
+/// let python = "def double(x: int) -> int:\n    return x * 2\n";
+/// let (rust, map) = DepylerPipeline::new().transpile_with_source_map(python).unwrap();
+/// let line = rust.lines().position(|l| l.contains("fn double")).unwrap() + 1;
+/// let entry = map.lookup(line).unwrap();
+/// assert_eq!(entry.python_symbol, "double");
+/// assert_eq!(entry.python_lines.start, 1);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceMapTable {
+    entries: Vec<SourceMapEntry>,
+}
+
+impl SourceMapTable {
+    /// Pairs the items of `rust_code` with the definitions in `python_source`
+    ///
+    /// Generated items without a Python counterpart, such as error types
+    /// and imports, are left out.
+    pub fn build(python_source: &str, rust_code: &str) -> Result<Self> {
+        let definitions = python_definitions(python_source)?;
+        let file = syn::parse_file(rust_code)?;
+        let mut entries = Vec::new();
+        for item in rust_items(&file.items) {
+            let (python_symbol, definition) = match python_symbol_for(&item.symbol, &definitions) {
+                Some(found) => found,
+                None => continue,
+            };
+            entries.push(SourceMapEntry {
+                kind: SourceMapKind::Item,
+                rust_symbol: item.symbol.clone(),
+                python_symbol: python_symbol.clone(),
+                rust_lines: item.lines,
+                python_lines: definition.lines,
+                python_column: definition.column,
+            });
+            if item.statements.len() == definition.statements.len() {
+                for (rust_lines, (python_lines, python_column)) in
+                    item.statements.iter().zip(&definition.statements)
+                {
+                    entries.push(SourceMapEntry {
+                        kind: SourceMapKind::Statement,
+                        rust_symbol: item.symbol.clone(),
+                        python_symbol: python_symbol.clone(),
+                        rust_lines: *rust_lines,
+                        python_lines: *python_lines,
+                        python_column: *python_column,
+                    });
+                }
+            }
+        }
+        Ok(Self { entries })
+    }
+
+    pub fn entries(&self) -> &[SourceMapEntry] {
+        &self.entries
+    }
+
+    /// The innermost generated item or statement containing `rust_line`
+    pub fn lookup(&self, rust_line: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .filter(|e| e.rust_lines.contains(rust_line))
+            .min_by_key(|e| e.rust_lines.len())
+    }
+}
+
+/// A Python `def` or `class` and the statements of its body
+struct PythonDefinition {
+    lines: LineRange,
+    column: usize,
+    statements: Vec<(LineRange, usize)>,
+}
+
+/// Rust symbol -> the Python definition it was generated from
+fn python_symbol_for<'a>(
+    rust_symbol: &str,
+    definitions: &'a HashMap<String, PythonDefinition>,
+) -> Option<(String, &'a PythonDefinition)> {
+    let python_symbol = match rust_symbol.split_once("::") {
+        Some((class, "new")) if !definitions.contains_key(&format!("{}.new", class)) => {
+            // Dataclasses get `new` without an `__init__`
+            let init = format!("{}.__init__", class);
+            if definitions.contains_key(&init) {
+                init
+            } else {
+                class.to_string()
+            }
+        }
+        Some((class, method)) => format!("{}.{}", class, method),
+        None if rust_symbol == ExitCodeOptions::INNER_MAIN => "main".to_string(),
+        None => rust_symbol.to_string(),
+    };
+    let definition = definitions.get(&python_symbol)?;
+    Some((python_symbol, definition))
+}
+
+/// A generated function, method or struct
+struct RustItem {
+    symbol: String,
+    lines: LineRange,
+    /// Top-level statements of a function body
+    statements: Vec<LineRange>,
+}
+
+impl RustItem {
+    fn new(symbol: String, node: &impl Spanned, body: Option<&syn::Block>) -> Self {
+        Self {
+            symbol,
+            lines: lines_of(node),
+            statements: body
+                .map(|block| block.stmts.iter().map(lines_of).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Functions, structs and methods; class modules are searched too
+fn rust_items(items: &[syn::Item]) -> Vec<RustItem> {
+    let mut found = Vec::new();
+    for item in items {
+        match item {
+            syn::Item::Fn(f) => found.push(RustItem::new(
+                ident_name(&f.sig.ident),
+                item,
+                Some(&f.block),
+            )),
+            syn::Item::Struct(s) => found.push(RustItem::new(ident_name(&s.ident), item, None)),
+            syn::Item::Impl(block) => {
+                let ty = match &*block.self_ty {
+                    syn::Type::Path(path) => {
+                        path.path.segments.last().map(|s| ident_name(&s.ident))
+                    }
+                    _ => None,
+                };
+                let ty = match ty {
+                    Some(ty) => ty,
+                    None => continue,
+                };
+                for impl_item in &block.items {
+                    if let syn::ImplItem::Fn(method) = impl_item {
+                        let symbol = format!("{}::{}", ty, ident_name(&method.sig.ident));
+                        found.push(RustItem::new(symbol, method, Some(&method.block)));
+                    }
+                }
+            }
+            syn::Item::Mod(module) => {
+                if let Some((_, items)) = &module.content {
+                    found.extend(rust_items(items));
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// `r#type` -> `type`
+fn ident_name(ident: &syn::Ident) -> String {
+    let name = ident.to_string();
+    name.strip_prefix("r#").map(str::to_string).unwrap_or(name)
+}
+
+fn lines_of<T: Spanned>(node: &T) -> LineRange {
+    let span = node.span();
+    LineRange {
+        start: span.start().line,
+        end: span.end().line,
+    }
+}
+
+/// `name` and `Class.method` -> their definitions
+fn python_definitions(python_source: &str) -> Result<HashMap<String, PythonDefinition>> {
+    let spans = HirSpans::parse(python_source)
+        .map_err(|e| anyhow!("cannot map invalid Python source: {}", e))?;
+    let located = |span: Span| {
+        let lines = spans.lines(span);
+        let range = LineRange {
+            start: *lines.start(),
+            end: *lines.end(),
+        };
+        (range, spans.position(span.start).column)
+    };
+    let mut definitions = HashMap::new();
+    for (name, class) in spans.classes() {
+        let (lines, column) = located(class);
+        definitions.insert(
+            name.to_string(),
+            PythonDefinition {
+                lines,
+                column,
+                statements: Vec::new(),
+            },
+        );
+    }
+    for (name, function) in spans.functions() {
+        let (lines, column) = located(function.def);
+        definitions.insert(
+            name.to_string(),
+            PythonDefinition {
+                lines,
+                column,
+                statements: function.body.iter().map(|s| located(s.span)).collect(),
+            },
+        );
+    }
+    Ok(definitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTHON: &str = "\
+class Counter:
+    def __init__(self, start: int):
+        self.value = start
+
+    def bump(self) -> int:
+        \"\"\"Adds one\"\"\"
+        self.value += 1
+        return self.value
+
+
+def main() -> None:
+    c = Counter(1)
+    print(c.bump())
+";
+
+    const RUST: &str = "\
+pub struct Counter {
+    pub value: i32,
+}
+impl Counter {
+    pub fn new(start: i32) -> Self {
+        Self { value: start }
+    }
+    pub fn bump(&mut self) -> i32 {
+        self.value += 1;
+        self.value
+    }
+}
+pub fn __depyler_main() {
+    let c = Counter::new(1);
+    println!(\"{}\", c.bump());
+}
+";
+
+    #[test]
+    fn test_methods_constructors_and_renamed_main_are_mapped() {
+        let map = SourceMapTable::build(PYTHON, RUST).unwrap();
+        let symbols: Vec<(&str, &str)> = map
+            .entries()
+            .iter()
+            .filter(|e| e.kind == SourceMapKind::Item)
+            .map(|e| (e.rust_symbol.as_str(), e.python_symbol.as_str()))
+            .collect();
+        assert_eq!(
+            symbols,
+            [
+                ("Counter", "Counter"),
+                ("Counter::new", "Counter.__init__"),
+                ("Counter::bump", "Counter.bump"),
+                ("__depyler_main", "main"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lookup_picks_the_innermost_statement() {
+        let map = SourceMapTable::build(PYTHON, RUST).unwrap();
+        // `self.value` in bump, past the docstring
+        let entry = map.lookup(10).unwrap();
+        assert_eq!(entry.kind, SourceMapKind::Statement);
+        assert_eq!(entry.python_symbol, "Counter.bump");
+        assert_eq!(entry.python_lines, LineRange { start: 8, end: 8 });
+        assert_eq!(entry.python_column, 9);
+        let entry = map.lookup(15).unwrap();
+        assert_eq!(entry.python_lines.start, 13);
+        assert_eq!(entry.python_column, 5);
+        assert!(map.lookup(4).is_none());
+    }
+
+    #[test]
+    fn test_differing_statement_counts_fall_back_to_the_definition() {
+        let rust = "pub fn __depyler_main() {\n    let c = Counter::new(1);\n    let b = c.bump();\n    println!(\"{}\", b);\n}\n";
+        let map = SourceMapTable::build(PYTHON, rust).unwrap();
+        let entry = map.lookup(3).unwrap();
+        assert_eq!(entry.kind, SourceMapKind::Item);
+        assert_eq!(entry.python_lines, LineRange { start: 11, end: 13 });
+        assert_eq!(entry.python_column, 1);
+    }
+}
@@ -4,10 +4,26 @@
 //! It includes all statement conversion helpers and the HirStmt RustCodeGen trait implementation.
 
 use crate::hir::*;
-use crate::rust_gen::context::{CodeGenContext, RustCodeGen, ToRustExpr};
+use crate::rust_gen::arena_gen;
+use crate::rust_gen::assert_gen;
+use crate::rust_gen::async_gen;
+use crate::rust_gen::borrowed_return_gen;
+use crate::rust_gen::context::{CodeGenContext, HandlerBinding, RustCodeGen, ToRustExpr};
+use crate::rust_gen::dict_lookup_gen;
+use crate::rust_gen::error_gen;
+use crate::rust_gen::error_message_gen;
+use crate::rust_gen::int_semantics_gen;
+use crate::rust_gen::iter_gen;
 use crate::rust_gen::keywords::safe_ident; // DEPYLER-0023: Keyword escaping
+use crate::rust_gen::ledger::{self, DivergenceKind};
+use crate::rust_gen::numeric_parse_gen;
+use crate::rust_gen::print_gen;
+use crate::rust_gen::py_str_gen;
+use crate::rust_gen::slice_gen;
 use crate::rust_gen::type_gen::rust_type_to_syn;
+use crate::type_mapper::RustType;
 use anyhow::{bail, Result};
+use depyler_annotations::IntegerSemantics;
 use quote::quote;
 use syn::{self, parse_quote};
 
@@ -113,12 +129,17 @@ pub(crate) fn codegen_assert_stmt(
 ) -> Result<proc_macro2::TokenStream> {
     let test_expr = test.to_rust_expr(ctx)?;
 
-    if let Some(message_expr) = msg {
-        let msg_tokens = message_expr.to_rust_expr(ctx)?;
-        Ok(quote! { assert!(#test_expr, "{}", #msg_tokens); })
-    } else {
-        Ok(quote! { assert!(#test_expr); })
-    }
+    assert_gen::codegen_assert(&test_expr, msg.as_ref(), |value| {
+        let value_expr = value.to_rust_expr(ctx)?;
+        // Ints and strings already display as `str()` shows them
+        Ok(match ctx.value_type(value) {
+            Type::Int | Type::String | Type::Unknown => {
+                ledger::record_display(value, ctx);
+                value_expr
+            }
+            _ => py_str_gen::codegen_str(value, &value_expr, ctx),
+        })
+    })
 }
 
 /// Generate code for Break statement with optional label
@@ -266,6 +287,18 @@ pub(crate) fn codegen_expr_stmt(
         }
     }
 
+    if let Some(print) = print_gen::codegen_print_stmt(expr, ctx)? {
+        return Ok(print);
+    }
+
+    // Naming a module value computed at runtime initializes it right there
+    if let HirExpr::Var(name) = expr {
+        if ctx.lazy_globals.contains_key(name) && !ctx.is_declared(name) {
+            let ident = syn::Ident::new(name, proc_macro2::Span::call_site());
+            return Ok(quote! { std::sync::LazyLock::force(&#ident); });
+        }
+    }
+
     let expr_tokens = expr.to_rust_expr(ctx)?;
     Ok(quote! { #expr_tokens; })
 }
@@ -282,7 +315,22 @@ pub(crate) fn codegen_return_stmt(
     ctx: &mut CodeGenContext,
 ) -> Result<proc_macro2::TokenStream> {
     if let Some(e) = expr {
-        let mut expr_tokens = e.to_rust_expr(ctx)?;
+        let json_read = match ctx.current_return_type.clone() {
+            Some(return_type) => codegen_json_read_into(e, &return_type, ctx)?,
+            None => None,
+        };
+        let mut expr_tokens = match (json_read, ctx.current_borrowed_return.clone()) {
+            (Some(read), _) => read,
+            // The function returns a reference into this parameter
+            (None, Some(source)) if ctx.current_return_is_cow => {
+                borrowed_return_gen::codegen_cow_return(e, &source, ctx)?
+            }
+            (None, Some(source)) => borrowed_return_gen::codegen_borrowed_return(e, &source, ctx)?,
+            (None, None) => {
+                let return_type = ctx.current_return_type.clone();
+                int_semantics_gen::codegen_int_value(e, return_type.as_ref(), ctx)?
+            }
+        };
 
         // DEPYLER-0241: Apply type conversion if needed (e.g., usize -> i32 from enumerate())
         if let Some(return_type) = &ctx.current_return_type {
@@ -298,9 +346,11 @@ pub(crate) fn codegen_return_stmt(
             }
         }
 
-        // Check if return type is Optional and wrap value in Some()
+        // Check if return type is Optional and wrap value in Some(), unless
+        // the value is an optional handle into an arena already
         let is_optional_return =
-            matches!(ctx.current_return_type.as_ref(), Some(Type::Optional(_)));
+            matches!(ctx.current_return_type.as_ref(), Some(Type::Optional(_)))
+                && !arena_gen::is_optional_handle(e, ctx);
 
         // DEPYLER-0330: DISABLED - Heuristic too broad, breaks plain int variables named "result"
         // Original logic: Unwrap Option-typed variables when returning from non-Optional function
@@ -410,6 +460,28 @@ pub(crate) fn codegen_return_stmt(
     }
 }
 
+/// Generate code for the statements of a block
+///
+/// A dict lookup followed by a check of its result for `None` becomes a
+/// single statement; see [`dict_lookup_gen`].
+pub(crate) fn codegen_block(
+    stmts: &[HirStmt],
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<proc_macro2::TokenStream>> {
+    let mut tokens = Vec::with_capacity(stmts.len());
+    let mut rest = stmts;
+    while let Some(stmt) = rest.first() {
+        if let Some(lookup) = dict_lookup_gen::codegen_get_then_none_check(rest, ctx)? {
+            tokens.push(lookup);
+            rest = &rest[2..];
+        } else {
+            tokens.push(stmt.to_rust_tokens(ctx)?);
+            rest = &rest[1..];
+        }
+    }
+    Ok(tokens)
+}
+
 /// Generate code for While loop statement
 #[inline]
 pub(crate) fn codegen_while_stmt(
@@ -417,12 +489,32 @@ pub(crate) fn codegen_while_stmt(
     body: &[HirStmt],
     ctx: &mut CodeGenContext,
 ) -> Result<proc_macro2::TokenStream> {
+    if condition.is_always_true() {
+        // `if done: break` at the top of `while True:` is the loop condition
+        if let Some((stop, rest)) = leading_break_condition(body) {
+            let cond = negate_condition(codegen_condition(stop, ctx)?);
+            ctx.enter_scope();
+            let body_stmts = codegen_block(rest, ctx)?;
+            ctx.exit_scope();
+            return Ok(quote! {
+                while #cond {
+                    #(#body_stmts)*
+                }
+            });
+        }
+        ctx.enter_scope();
+        let body_stmts = codegen_block(body, ctx)?;
+        ctx.exit_scope();
+        return Ok(quote! {
+            loop {
+                #(#body_stmts)*
+            }
+        });
+    }
+
     let cond = condition.to_rust_expr(ctx)?;
     ctx.enter_scope();
-    let body_stmts: Vec<_> = body
-        .iter()
-        .map(|s| s.to_rust_tokens(ctx))
-        .collect::<Result<Vec<_>>>()?;
+    let body_stmts = codegen_block(body, ctx)?;
     ctx.exit_scope();
     Ok(quote! {
         while #cond {
@@ -431,6 +523,46 @@ pub(crate) fn codegen_while_stmt(
     })
 }
 
+/// The condition of an `if cond: break` opening `body`, and the statements
+/// after it
+///
+/// Python loops have no `else` here yet. Once they do, only loops without
+/// one can be hoisted: `while` runs the `else` when its condition turns
+/// false, and `break` skips it.
+fn leading_break_condition(body: &[HirStmt]) -> Option<(&HirExpr, &[HirStmt])> {
+    match body {
+        [HirStmt::If {
+            condition,
+            then_body,
+            else_body: None,
+        }, rest @ ..]
+            if matches!(then_body.as_slice(), [HirStmt::Break { label: None }]) =>
+        {
+            Some((condition, rest))
+        }
+        _ => None,
+    }
+}
+
+/// `!cond`, dropping a leading `!` instead of doubling it
+fn negate_condition(cond: syn::Expr) -> syn::Expr {
+    match cond {
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Not(_),
+            expr,
+            ..
+        }) => *expr,
+        syn::Expr::Path(_)
+        | syn::Expr::Call(_)
+        | syn::Expr::MethodCall(_)
+        | syn::Expr::Field(_)
+        | syn::Expr::Index(_)
+        | syn::Expr::Paren(_)
+        | syn::Expr::Lit(_) => parse_quote! { !#cond },
+        _ => parse_quote! { !(#cond) },
+    }
+}
+
 /// Generate code for Raise (exception) statement
 ///
 /// DEPYLER-0310: Wraps exceptions with Box::new() when error type is Box<dyn Error>
@@ -438,40 +570,121 @@ pub(crate) fn codegen_while_stmt(
 #[inline]
 pub(crate) fn codegen_raise_stmt(
     exception: &Option<HirExpr>,
+    cause: &Option<HirExpr>,
     ctx: &mut CodeGenContext,
 ) -> Result<proc_macro2::TokenStream> {
     // For V1, we'll implement basic error handling
     if let Some(exc) = exception {
-        let exc_expr = exc.to_rust_expr(ctx)?;
+        let mut exc_expr = exc.to_rust_expr(ctx)?;
+        if let Some(cause_expr) = raise_cause(exc, cause, ctx)? {
+            exc_expr = parse_quote! { #exc_expr.with_cause(#cause_expr) };
+        }
 
         // DEPYLER-0333: Extract exception type to check if it's handled
         let exception_type = extract_exception_type(exc);
 
+        // A bound instance (`raise err`) has no type name to print
+        let type_name = match exc {
+            HirExpr::Call { .. } | HirExpr::Var(_)
+                if exception_type.starts_with(char::is_uppercase) =>
+            {
+                exception_type.as_str()
+            }
+            _ => "",
+        };
+        let panic = error_message_gen::panic_unhandled(type_name, &exc_expr, ctx);
+
         // DEPYLER-0333: Check if exception is caught by current try block
         if ctx.is_exception_handled(&exception_type) {
             // Exception is caught - for now use panic! (control flow jump is complex)
             // TODO: In future, implement proper control flow to jump to handler
-            Ok(quote! { panic!("{}", #exc_expr); })
+            Ok(quote! { #panic; })
         } else if ctx.current_function_can_fail {
             // Exception propagates to caller - use return Err
-            // DEPYLER-0310: Check if we need to wrap with Box::new()
-            let needs_boxing = matches!(
-                ctx.current_error_type,
-                Some(crate::rust_gen::context::ErrorType::DynBox)
-            );
-
-            if needs_boxing {
-                Ok(quote! { return Err(Box::new(#exc_expr)); })
-            } else {
-                Ok(quote! { return Err(#exc_expr); })
-            }
+            Ok(return_err(&exc_expr, ctx))
         } else {
             // Function doesn't return Result - use panic!
-            Ok(quote! { panic!("{}", #exc_expr); })
+            Ok(quote! { #panic; })
         }
+    } else if let Some(err) = reraised_error(ctx) {
+        // A bare `raise` in a handler hands on the caught error, cause and all
+        Ok(return_err(&err, ctx))
     } else {
-        // Re-raise or bare raise - use generic error
-        Ok(quote! { return Err("Exception raised".into()); })
+        // A bare `raise` with no exception being handled
+        let message = error_message_gen::no_active_exception(ctx);
+        Ok(quote! { return Err(#message.into()); })
+    }
+}
+
+/// `return Err(value)`, converted to the function's error type
+///
+/// DEPYLER-0310: `Box<dyn Error>` needs `Box::new`, and the module's error
+/// enum converts from every exception type.
+fn return_err(value: &syn::Expr, ctx: &CodeGenContext) -> proc_macro2::TokenStream {
+    match ctx.current_error_type {
+        Some(crate::rust_gen::context::ErrorType::DynBox) => {
+            quote! { return Err(Box::new(#value)); }
+        }
+        Some(crate::rust_gen::context::ErrorType::ModuleEnum) => {
+            quote! { return Err(#value.into()); }
+        }
+        _ => quote! { return Err(#value); },
+    }
+}
+
+/// The exception `raise exception` chains to: the `from` clause, or else
+/// the error being handled, as Python records it in `__context__`
+///
+/// `from None` suppresses the chain. Only the generated exception structs
+/// carry a cause, so user classes and other values are raised unchained.
+fn raise_cause(
+    exception: &HirExpr,
+    cause: &Option<HirExpr>,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    let chainable = matches!(
+        exception,
+        HirExpr::Call { func, .. } if error_gen::has_cause(func, ctx)
+    );
+    if !chainable {
+        return Ok(None);
+    }
+    match cause {
+        Some(HirExpr::Literal(Literal::None)) => Ok(None),
+        Some(cause) => Ok(Some(cause.to_rust_expr(ctx)?)),
+        None => Ok(match &ctx.current_handler {
+            Some(HandlerBinding {
+                name: Some(name),
+                bound_error: Some(_),
+                ..
+            }) => {
+                let err = safe_ident(name);
+                Some(parse_quote! { #err })
+            }
+            _ => None,
+        }),
+    }
+}
+
+/// The caught error a bare `raise` re-raises, when the handler binds one
+/// the function can return
+fn reraised_error(ctx: &CodeGenContext) -> Option<syn::Expr> {
+    use crate::rust_gen::context::ErrorType;
+    match &ctx.current_handler {
+        Some(HandlerBinding {
+            name: Some(name),
+            bound_error: Some(bound_error),
+            ..
+        }) if ctx.current_function_can_fail => {
+            let returnable = match &ctx.current_error_type {
+                Some(ErrorType::DynBox) | Some(ErrorType::ModuleEnum) => true,
+                Some(ErrorType::Concrete(ty)) => ty == bound_error,
+                None => false,
+            };
+            let err = safe_ident(name);
+            returnable.then(|| parse_quote! { #err })
+        }
+        _ => None,
     }
 }
 
@@ -495,6 +708,25 @@ pub(crate) fn codegen_with_stmt(
     body: &[HirStmt],
     ctx: &mut CodeGenContext,
 ) -> Result<proc_macro2::TokenStream> {
+    // `async with`: tokio locks, aiohttp sessions and requests
+    if let Some(manager) = async_gen::async_with_manager(context) {
+        return async_gen::codegen_async_with(manager, target, body, ctx);
+    }
+
+    // contextlib: @contextmanager guards, suppress(...), ExitStack()
+    if let Some(tokens) =
+        crate::rust_gen::contextlib_gen::try_codegen_contextlib_with(context, target, body, ctx)?
+    {
+        return Ok(tokens);
+    }
+
+    // open(), threading.Lock() and classes with __exit__: scope-bound guards
+    if let Some(tokens) =
+        crate::rust_gen::context_manager_gen::try_codegen_guard_with(context, target, body, ctx)?
+    {
+        return Ok(tokens);
+    }
+
     // Convert context expression
     let context_expr = context.to_rust_expr(ctx)?;
 
@@ -513,7 +745,7 @@ pub(crate) fn codegen_with_stmt(
     ctx.is_final_statement = saved_is_final;
 
     // Generate code that calls __enter__() and binds the result
-    // Note: __exit__() is not yet called (Drop trait implementation pending)
+    // Note: __exit__() is not called for context managers of unknown type
     if let Some(var_name) = target {
         let var_ident = safe_ident(var_name); // DEPYLER-0023
         ctx.declare_var(var_name);
@@ -552,7 +784,7 @@ pub(crate) fn codegen_with_stmt(
 ///
 /// # DEPYLER-0339
 /// Fixes: `if val` where `val: String` failing to compile
-fn apply_truthiness_conversion(
+pub(crate) fn apply_truthiness_conversion(
     condition: &HirExpr,
     cond_expr: syn::Expr,
     ctx: &CodeGenContext,
@@ -592,14 +824,8 @@ fn apply_truthiness_conversion(
     cond_expr
 }
 
-/// Generate code for If statement with optional else clause
-#[inline]
-pub(crate) fn codegen_if_stmt(
-    condition: &HirExpr,
-    then_body: &[HirStmt],
-    else_body: &Option<Vec<HirStmt>>,
-    ctx: &mut CodeGenContext,
-) -> Result<proc_macro2::TokenStream> {
+/// An `if` condition as a Rust `bool`
+pub(crate) fn codegen_condition(condition: &HirExpr, ctx: &mut CodeGenContext) -> Result<syn::Expr> {
     let mut cond = condition.to_rust_expr(ctx)?;
 
     // DEPYLER-0308: Auto-unwrap Result<bool> in if conditions
@@ -616,21 +842,64 @@ pub(crate) fn codegen_if_stmt(
 
     // DEPYLER-0339: Apply Python truthiness conversion
     // Convert non-boolean expressions to boolean (e.g., `if val` where val: String)
-    cond = apply_truthiness_conversion(condition, cond, ctx);
+    record_truthiness(condition, ctx);
+    Ok(apply_truthiness_conversion(condition, cond, ctx))
+}
+
+/// Notes in the ledger a variable tested without a type the truthiness
+/// conversion knows, which is then used as it is
+fn record_truthiness(condition: &HirExpr, ctx: &mut CodeGenContext) {
+    let name = match condition {
+        HirExpr::Var(name) => name,
+        _ => return,
+    };
+    let converted = matches!(
+        ctx.var_types.get(name),
+        Some(
+            Type::Bool
+                | Type::String
+                | Type::List(_)
+                | Type::Dict(_, _)
+                | Type::Set(_)
+                | Type::Optional(_)
+                | Type::Int
+                | Type::Float
+        )
+    );
+    if !converted {
+        ctx.divergences.record(
+            DivergenceKind::Truthiness,
+            format!(
+                "`{}` is tested as it is, without Python's truthiness rules",
+                name
+            ),
+        );
+    }
+}
+
+/// Generate code for If statement with optional else clause
+#[inline]
+pub(crate) fn codegen_if_stmt(
+    condition: &HirExpr,
+    then_body: &[HirStmt],
+    else_body: &Option<Vec<HirStmt>>,
+    ctx: &mut CodeGenContext,
+) -> Result<proc_macro2::TokenStream> {
+    if let Some(if_let) =
+        dict_lookup_gen::codegen_contains_then_index(condition, then_body, else_body, ctx)?
+    {
+        return Ok(if_let);
+    }
+
+    let cond = codegen_condition(condition, ctx)?;
 
     ctx.enter_scope();
-    let then_stmts: Vec<_> = then_body
-        .iter()
-        .map(|s| s.to_rust_tokens(ctx))
-        .collect::<Result<Vec<_>>>()?;
+    let then_stmts = codegen_block(then_body, ctx)?;
     ctx.exit_scope();
 
     if let Some(else_stmts) = else_body {
         ctx.enter_scope();
-        let else_tokens: Vec<_> = else_stmts
-            .iter()
-            .map(|s| s.to_rust_tokens(ctx))
-            .collect::<Result<Vec<_>>>()?;
+        let else_tokens = codegen_block(else_stmts, ctx)?;
         ctx.exit_scope();
         Ok(quote! {
             if #cond {
@@ -648,8 +917,284 @@ pub(crate) fn codegen_if_stmt(
     }
 }
 
+/// Generate code for a match statement
+///
+/// Case patterns map onto Rust patterns. Dotted constants (`Color.RED`) and
+/// float literals cannot appear in Rust patterns, so they bind a temporary
+/// compared in the arm's guard. A match whose last case can fail gets a
+/// `_ => {}` arm, as Python falls through when no case matches.
+pub(crate) fn codegen_match_stmt(
+    subject: &HirExpr,
+    cases: &[MatchCase],
+    ctx: &mut CodeGenContext,
+) -> Result<proc_macro2::TokenStream> {
+    let mut subject_expr = subject.to_rust_expr(ctx)?;
+
+    let top_level: Vec<&HirPattern> = cases
+        .iter()
+        .flat_map(|case| top_level_alternatives(&case.pattern))
+        .collect();
+    let is_list = matches!(subject, HirExpr::Var(name)
+        if matches!(ctx.var_types.get(name), Some(Type::List(_))));
+    let slices = is_list
+        && top_level
+            .iter()
+            .any(|p| matches!(p, HirPattern::Sequence(_)));
+    if slices {
+        subject_expr = parse_quote! { #subject_expr.as_slice() };
+    } else if top_level
+        .iter()
+        .any(|p| matches!(p, HirPattern::Literal(Literal::String(_))))
+    {
+        // String literal patterns need a &str subject
+        subject_expr = parse_quote! { &*#subject_expr };
+    }
+
+    let mut arms = Vec::with_capacity(cases.len() + 1);
+    for case in cases {
+        ctx.enter_scope();
+        let mut lowering = PatternLowering {
+            case,
+            guards: Vec::new(),
+            temps: 0,
+        };
+        let pattern = codegen_pattern(&case.pattern, slices, &mut lowering, ctx)?;
+        for name in case.pattern.bindings() {
+            ctx.declare_var(&name);
+        }
+        let mut guards = lowering.guards;
+        if let Some(guard) = &case.guard {
+            let guard = guard.to_rust_expr(ctx)?;
+            guards.push(quote! { #guard });
+        }
+        let body: Vec<_> = case
+            .body
+            .iter()
+            .map(|s| s.to_rust_tokens(ctx))
+            .collect::<Result<Vec<_>>>()?;
+        ctx.exit_scope();
+
+        let guard = if guards.is_empty() {
+            quote! {}
+        } else {
+            quote! { if #(#guards)&&* }
+        };
+        arms.push(quote! {
+            #pattern #guard => {
+                #(#body)*
+            }
+        });
+    }
+
+    let exhaustive = cases
+        .last()
+        .is_some_and(|case| case.guard.is_none() && is_irrefutable(&case.pattern));
+    if !exhaustive {
+        arms.push(quote! { _ => {} });
+    }
+
+    Ok(quote! {
+        match #subject_expr {
+            #(#arms)*
+        }
+    })
+}
+
+/// Per-case state while lowering a pattern
+struct PatternLowering<'a> {
+    case: &'a MatchCase,
+    /// Conditions the arm's guard must check
+    guards: Vec<proc_macro2::TokenStream>,
+    temps: usize,
+}
+
+impl PatternLowering<'_> {
+    /// Captures unused by the guard and body get a leading underscore
+    fn binding_ident(&self, name: &str) -> syn::Ident {
+        let used = self
+            .case
+            .guard
+            .as_ref()
+            .is_some_and(|g| is_var_used_in_expr(name, g))
+            || self.case.body.iter().any(|s| is_var_used_in_stmt(name, s));
+        if used {
+            safe_ident(name)
+        } else {
+            safe_ident(&format!("_{}", name))
+        }
+    }
+
+    /// Binds a temporary and requires it to equal one of `values`
+    fn compare(&mut self, values: Vec<syn::Expr>) -> proc_macro2::TokenStream {
+        let temp = quote::format_ident!("__match_{}", self.temps);
+        self.temps += 1;
+        self.guards.push(quote! { (#(#temp == #values)||*) });
+        quote! { #temp }
+    }
+}
+
+fn codegen_pattern(
+    pattern: &HirPattern,
+    slices: bool,
+    lowering: &mut PatternLowering,
+    ctx: &mut CodeGenContext,
+) -> Result<proc_macro2::TokenStream> {
+    match pattern {
+        HirPattern::Wildcard => Ok(quote! { _ }),
+        HirPattern::Capture { name, pattern } => {
+            let ident = lowering.binding_ident(name);
+            match pattern {
+                Some(inner) => {
+                    let inner = codegen_pattern(inner, slices, lowering, ctx)?;
+                    Ok(quote! { #ident @ #inner })
+                }
+                None => Ok(quote! { #ident }),
+            }
+        }
+        HirPattern::Literal(Literal::Float(_)) | HirPattern::Value(_) => {
+            let value = pattern_value(pattern, ctx)?;
+            Ok(lowering.compare(vec![value]))
+        }
+        HirPattern::Literal(literal) => literal_pattern(literal),
+        HirPattern::Sequence(items) => {
+            let items: Vec<_> = items
+                .iter()
+                .map(|item| codegen_pattern(item, false, lowering, ctx))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(if slices {
+                quote! { [#(#items),*] }
+            } else if items.len() == 1 {
+                let item = &items[0];
+                quote! { (#item,) }
+            } else {
+                quote! { (#(#items),*) }
+            })
+        }
+        HirPattern::Class {
+            class,
+            args,
+            kwargs,
+        } => {
+            if ctx.class_names.contains(class) {
+                if !args.is_empty() {
+                    bail!(
+                        "Positional sub-patterns of class {} are not supported; use keyword patterns",
+                        class
+                    );
+                }
+                let ty = safe_ident(class);
+                let fields: Vec<_> = kwargs
+                    .iter()
+                    .map(|(field, p)| {
+                        let field = safe_ident(field);
+                        let p = codegen_pattern(p, false, lowering, ctx)?;
+                        Ok(quote! { #field: #p })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(quote! { #ty { #(#fields,)* .. } });
+            }
+            // Builtin type checks are settled by the static type
+            let is_builtin = matches!(
+                class.as_str(),
+                "int" | "float" | "str" | "bool" | "bytes" | "list" | "tuple" | "dict" | "set"
+            );
+            match (is_builtin, args.as_slice()) {
+                (true, []) if kwargs.is_empty() => Ok(quote! { _ }),
+                (true, [inner]) if kwargs.is_empty() => {
+                    codegen_pattern(inner, slices, lowering, ctx)
+                }
+                _ => bail!("Class pattern {}(...) is not supported", class),
+            }
+        }
+        HirPattern::Or(alternatives) => {
+            if alternatives.iter().any(needs_guard) {
+                if !alternatives
+                    .iter()
+                    .all(|p| matches!(p, HirPattern::Literal(_) | HirPattern::Value(_)))
+                {
+                    bail!(
+                        "Or-patterns mixing constants with captures or sequences are not supported"
+                    );
+                }
+                let values = alternatives
+                    .iter()
+                    .map(|p| pattern_value(p, ctx))
+                    .collect::<Result<Vec<_>>>()?;
+                return Ok(lowering.compare(values));
+            }
+            let alternatives: Vec<_> = alternatives
+                .iter()
+                .map(|p| codegen_pattern(p, slices, lowering, ctx))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(quote! { #(#alternatives)|* })
+        }
+    }
+}
+
+fn literal_pattern(literal: &Literal) -> Result<proc_macro2::TokenStream> {
+    Ok(match literal {
+        Literal::Int(n) => {
+            let magnitude = proc_macro2::Literal::u64_unsuffixed(n.unsigned_abs());
+            if *n < 0 {
+                quote! { -#magnitude }
+            } else {
+                quote! { #magnitude }
+            }
+        }
+        Literal::String(s) => quote! { #s },
+        Literal::Bool(b) => quote! { #b },
+        Literal::None => quote! { None },
+        Literal::Float(_) | Literal::Bytes(_) => bail!("Unsupported literal pattern"),
+    })
+}
+
+/// The value a guard compares against for a constant pattern
+fn pattern_value(pattern: &HirPattern, ctx: &mut CodeGenContext) -> Result<syn::Expr> {
+    match pattern {
+        HirPattern::Literal(Literal::String(s)) => Ok(parse_quote! { #s }),
+        HirPattern::Literal(literal) => HirExpr::Literal(literal.clone()).to_rust_expr(ctx),
+        HirPattern::Value(expr) => expr.to_rust_expr(ctx),
+        _ => bail!("Pattern is not a constant"),
+    }
+}
+
+fn needs_guard(pattern: &HirPattern) -> bool {
+    matches!(
+        pattern,
+        HirPattern::Literal(Literal::Float(_)) | HirPattern::Value(_)
+    )
+}
+
+/// The patterns a subject is compared against directly, looking through
+/// captures and alternatives
+fn top_level_alternatives(pattern: &HirPattern) -> Vec<&HirPattern> {
+    match pattern {
+        HirPattern::Capture {
+            pattern: Some(inner),
+            ..
+        } => top_level_alternatives(inner),
+        HirPattern::Or(alternatives) => alternatives
+            .iter()
+            .flat_map(top_level_alternatives)
+            .collect(),
+        _ => vec![pattern],
+    }
+}
+
+fn is_irrefutable(pattern: &HirPattern) -> bool {
+    match pattern {
+        HirPattern::Wildcard => true,
+        HirPattern::Capture { pattern, .. } => match pattern {
+            Some(inner) => is_irrefutable(inner),
+            None => true,
+        },
+        HirPattern::Or(alternatives) => alternatives.iter().any(is_irrefutable),
+        _ => false,
+    }
+}
+
 /// Check if a variable is used in an expression
-fn is_var_used_in_expr(var_name: &str, expr: &HirExpr) -> bool {
+pub(crate) fn is_var_used_in_expr(var_name: &str, expr: &HirExpr) -> bool {
     match expr {
         HirExpr::Var(name) => name == var_name,
         HirExpr::Binary { left, right, .. } => {
@@ -718,7 +1263,7 @@ fn is_var_used_in_assign_target(var_name: &str, target: &AssignTarget) -> bool {
 
 /// Check if a variable is used in a statement
 /// DEPYLER-0303 Phase 2: Fixed to check assignment targets too (for `d[k] = v`)
-fn is_var_used_in_stmt(var_name: &str, stmt: &HirStmt) -> bool {
+pub(crate) fn is_var_used_in_stmt(var_name: &str, stmt: &HirStmt) -> bool {
     match stmt {
         HirStmt::Assign { target, value, .. } => {
             // Check both target (e.g., d[k]) and value (e.g., v)
@@ -754,6 +1299,15 @@ fn is_var_used_in_stmt(var_name: &str, stmt: &HirStmt) -> bool {
                     .as_ref()
                     .is_some_and(|m| is_var_used_in_expr(var_name, m))
         }
+        HirStmt::Match { subject, cases } => {
+            is_var_used_in_expr(var_name, subject)
+                || cases.iter().any(|case| {
+                    case.guard
+                        .as_ref()
+                        .is_some_and(|g| is_var_used_in_expr(var_name, g))
+                        || case.body.iter().any(|s| is_var_used_in_stmt(var_name, s))
+                })
+        }
         _ => false,
     }
 }
@@ -808,6 +1362,18 @@ pub(crate) fn codegen_for_stmt(
         _ => bail!("Unsupported for loop target type"),
     };
 
+    // `async for` drains a Stream
+    if let Some(stream) = async_gen::async_for_stream(iter) {
+        return async_gen::codegen_async_for(target, &target_pattern, stream, body, ctx);
+    }
+
+    if matches!(ctx.value_type(iter), Type::Dict(_, _)) {
+        ctx.divergences.record(
+            DivergenceKind::DictOrdering,
+            "iterating a dict follows hash order, not insertion order",
+        );
+    }
+
     let mut iter_expr = iter.to_rust_expr(ctx)?;
 
     // Check if we're iterating over a borrowed collection
@@ -862,6 +1428,7 @@ pub(crate) fn codegen_for_stmt(
                 _ => None,
             })
         }
+        HirExpr::Attribute { .. } => arena_gen::iterated_handle_type(iter, ctx),
         HirExpr::Call { func, args , ..} if func == "enumerate" => {
             // enumerate(items) yields (int, elem_type)
             if let Some(HirExpr::Var(var_name)) = args.first() {
@@ -905,10 +1472,7 @@ pub(crate) fn codegen_for_stmt(
         }
         _ => {}
     }
-    let body_stmts: Vec<_> = body
-        .iter()
-        .map(|s| s.to_rust_tokens(ctx))
-        .collect::<Result<Vec<_>>>()?;
+    let body_stmts = codegen_block(body, ctx)?;
     ctx.exit_scope();
 
     // DEPYLER-0307 Fix #8: Handle enumerate() usize index casting
@@ -1027,6 +1591,33 @@ fn is_dict_augassign_pattern(target: &AssignTarget, value: &HirExpr) -> bool {
     false
 }
 
+/// `json.loads(s)`/`json.load(f)` read straight into a serde-deriving target
+/// type instead of a `serde_json::Value`, propagating parse errors
+fn codegen_json_read_into(
+    value: &HirExpr,
+    target_type: &Type,
+    ctx: &mut CodeGenContext,
+) -> Result<Option<syn::Expr>> {
+    if !ctx.current_function_can_fail
+        || !ctx.is_serde_type(target_type)
+        || !crate::derive_inference::is_json_read(value)
+    {
+        return Ok(None);
+    }
+    let (method, source) = match value {
+        HirExpr::MethodCall { method, args, .. } => (method, &args[0]),
+        _ => return Ok(None),
+    };
+    let source = source.to_rust_expr(ctx)?;
+    let target = rust_type_to_syn(&ctx.type_mapper.map_type(target_type))?;
+    ctx.needs_serde_json = true;
+    Ok(Some(if method == "loads" {
+        parse_quote! { serde_json::from_str::<#target>(&#source)? }
+    } else {
+        parse_quote! { serde_json::from_reader::<_, #target>(#source)? }
+    }))
+}
+
 /// Generate code for Assign statement (variable/index/attribute/tuple assignment)
 #[inline]
 pub(crate) fn codegen_assign_stmt(
@@ -1117,6 +1708,9 @@ pub(crate) fn codegen_assign_stmt(
         }
     }
 
+    // Handles into arenas keep the type the variable was given
+    let handle_type = arena_gen::assigned_handle_type(target, value, type_annotation.as_ref(), ctx);
+
     // DEPYLER-0232: Track variable types for class instances
     // This allows proper method dispatch for user-defined classes
     // DEPYLER-0224: Also track types for set/dict/list literals for proper method dispatch
@@ -1131,10 +1725,45 @@ pub(crate) fn codegen_assign_stmt(
                 Type::List(_) | Type::Dict(_, _) | Type::Set(_) => {
                     ctx.var_types.insert(var_name.clone(), annot_type.clone());
                 }
+                Type::Custom(_) if ctx.is_serde_type(annot_type) => {
+                    ctx.var_types.insert(var_name.clone(), annot_type.clone());
+                }
                 _ => {}
             }
         }
 
+        // Track timer Instants and their f64 deltas (start = time.perf_counter(); ...)
+        if ctx.is_instant_expr(value) {
+            ctx.var_types.insert(
+                var_name.clone(),
+                Type::Custom(crate::rust_gen::context::INSTANT_TYPE.to_string()),
+            );
+        } else if ctx.is_elapsed_expr(value) {
+            ctx.var_types.insert(var_name.clone(), Type::Float);
+        } else if crate::rust_gen::context_manager_gen::is_lock_constructor(value) {
+            ctx.var_types.insert(
+                var_name.clone(),
+                Type::Custom(crate::rust_gen::context_manager_gen::LOCK_TYPE.to_string()),
+            );
+        } else if let Some(ty) = async_gen::async_value_type(value) {
+            ctx.var_types.insert(var_name.clone(), ty);
+        } else if async_gen::is_awaited_request(value, ctx) {
+            ctx.var_types.insert(
+                var_name.clone(),
+                Type::Custom(async_gen::RESPONSE_TYPE.to_string()),
+            );
+        } else if async_gen::is_task_spawn(value, ctx) {
+            ctx.var_types.insert(
+                var_name.clone(),
+                Type::Custom(async_gen::TASK_TYPE.to_string()),
+            );
+        } else if ctx.integer_semantics != IntegerSemantics::Panic
+            && ctx.value_type(value) == Type::Int
+        {
+            // Checked and BigInt arithmetic apply to int locals too
+            ctx.var_types.insert(var_name.clone(), Type::Int);
+        }
+
         match value {
             HirExpr::Call { func, .. } => {
                 // Check if this is a user-defined class constructor
@@ -1245,11 +1874,29 @@ pub(crate) fn codegen_assign_stmt(
                     ctx.var_types.insert(var_name.clone(), Type::String);
                 }
             }
+            // Fields of class instances, e.g. items = order.items where order: Order
+            HirExpr::Attribute { value, attr } => {
+                if let HirExpr::Var(obj_var) = value.as_ref() {
+                    if let Some(obj_type) = ctx.var_types.get(obj_var) {
+                        let field_type = ctx.class_fields.infer_attribute_type(obj_type, attr);
+                        if !matches!(field_type, Type::Unknown) {
+                            ctx.var_types.insert(var_name.clone(), field_type);
+                        }
+                    }
+                }
+            }
             _ => {}
         }
     }
 
-    let mut value_expr = value.to_rust_expr(ctx)?;
+    let json_read = match type_annotation {
+        Some(target_type) => codegen_json_read_into(value, target_type, ctx)?,
+        None => None,
+    };
+    let mut value_expr = match json_read {
+        Some(read) => read,
+        None => int_semantics_gen::codegen_int_value(value, type_annotation.as_ref(), ctx)?,
+    };
 
     // DEPYLER-0270: Auto-unwrap Result-returning function calls in assignments
     // When assigning from a function that returns Result<T, E> in a non-Result context,
@@ -1261,6 +1908,32 @@ pub(crate) fn codegen_assign_stmt(
             value_expr = parse_quote! { #value_expr.unwrap() };
         }
     }
+    if let (AssignTarget::Symbol(symbol), Some(ty)) = (target, handle_type) {
+        value_expr = arena_gen::codegen_handle_assign(symbol, ty, value, value_expr, ctx);
+    }
+
+    // Lists used as queues start out as `VecDeque`s, and lists used for
+    // membership tests as `HashSet`s
+    if let (AssignTarget::Symbol(symbol), HirExpr::List(elements)) = (target, value) {
+        if ctx.deque_vars.contains(symbol) {
+            ctx.needs_vecdeque = true;
+            value_expr = if elements.is_empty() {
+                parse_quote! { VecDeque::new() }
+            } else {
+                parse_quote! { VecDeque::from(#value_expr) }
+            };
+        } else if ctx.set_vars.contains(symbol) {
+            ctx.needs_hashset = true;
+            if let Some(Type::List(elem)) = ctx.var_types.get(symbol).cloned() {
+                ctx.var_types.insert(symbol.clone(), Type::Set(elem));
+            }
+            value_expr = if elements.is_empty() {
+                parse_quote! { HashSet::new() }
+            } else {
+                parse_quote! { #value_expr.into_iter().collect::<HashSet<_>>() }
+            };
+        }
+    }
 
     // If there's a type annotation, handle type conversions
     let (type_annotation_tokens, is_final) = if let Some(target_type) = type_annotation {
@@ -1270,7 +1943,22 @@ pub(crate) fn codegen_assign_stmt(
             _ => (target_type, false),
         };
 
-        let target_rust_type = ctx.type_mapper.map_type(actual_type);
+        let target_rust_type = match (target, int_semantics_gen::map_local_type(actual_type, ctx)) {
+            (AssignTarget::Symbol(symbol), RustType::Vec(elem))
+                if ctx.deque_vars.contains(symbol) =>
+            {
+                RustType::Generic {
+                    base: "VecDeque".to_string(),
+                    params: vec![*elem],
+                }
+            }
+            (AssignTarget::Symbol(symbol), RustType::Vec(elem))
+                if ctx.set_vars.contains(symbol) =>
+            {
+                RustType::HashSet(elem)
+            }
+            (_, rust_type) => rust_type,
+        };
         let target_syn_type = rust_type_to_syn(&target_rust_type)?;
 
         // DEPYLER-0272: Check if we need type conversion (e.g., usize to i32)
@@ -1288,7 +1976,16 @@ pub(crate) fn codegen_assign_stmt(
         AssignTarget::Symbol(symbol) => {
             codegen_assign_symbol(symbol, value_expr, type_annotation_tokens, is_final, ctx)
         }
-        AssignTarget::Index { base, index } => codegen_assign_index(base, index, value_expr, ctx),
+        AssignTarget::Index { base, index } => match slice_gen::slice_index_bounds(index) {
+            Some(bounds) => slice_gen::codegen_slice_assign(base, bounds, value, value_expr, ctx),
+            None => codegen_assign_index(base, index, value_expr, ctx),
+        },
+        AssignTarget::Attribute {
+            value: object,
+            attr,
+        } if arena_gen::is_handle(object, ctx) => {
+            arena_gen::codegen_field_assign(object, attr, value, value_expr, ctx)
+        }
         AssignTarget::Attribute { value, attr } => {
             codegen_assign_attribute(value, attr, value_expr, ctx)
         }
@@ -1352,6 +2049,16 @@ pub(crate) fn codegen_assign_index(
 ) -> Result<proc_macro2::TokenStream> {
     let final_index = index.to_rust_expr(ctx)?;
 
+    // Lists and dicts in arena instances are set where they live
+    if let Some(assign) = arena_gen::try_codegen_field_index_assign(
+        base,
+        final_index.clone(),
+        value_expr.clone(),
+        ctx,
+    )? {
+        return Ok(assign);
+    }
+
     // DEPYLER-0304: Type-aware subscript assignment detection
     // Check base variable type to determine if this is Vec or HashMap
     // Vec.insert() requires usize index, HashMap.insert() takes key of any type
@@ -1452,6 +2159,16 @@ pub(crate) fn codegen_assign_tuple(
         .collect();
 
     match all_symbols {
+        // Generator locals live in the state struct: `(self.a, self.b) = ...`
+        Some(symbols)
+            if ctx.in_generator
+                && symbols
+                    .iter()
+                    .all(|s| ctx.generator_state_vars.contains(*s)) =>
+        {
+            let idents = symbols.iter().map(|s| safe_ident(s));
+            Ok(quote! { (#(self.#idents),*) = #value_expr; })
+        }
         Some(symbols) => {
             let all_declared = symbols.iter().all(|s| ctx.is_declared(s));
 
@@ -1493,6 +2210,11 @@ pub(crate) fn codegen_try_stmt(
     finalbody: &Option<Vec<HirStmt>>,
     ctx: &mut CodeGenContext,
 ) -> Result<proc_macro2::TokenStream> {
+    // A caught `next(it)` becomes a match on the iterator's Option
+    if let Some(tokens) = iter_gen::codegen_next_try(body, handlers, finalbody, ctx)? {
+        return Ok(tokens);
+    }
+
     // DEPYLER-0358: Detect simple try-except pattern for optimization
     // Pattern: try { return int(str_var) } except ValueError { return literal }
     // We can optimize this to: s.parse::<i32>().unwrap_or(literal)
@@ -1509,7 +2231,7 @@ pub(crate) fn codegen_try_stmt(
             HirStmt::Return(Some(HirExpr::Literal(lit))) => {
                 Some(((match lit {
                     Literal::Int(n) => n.to_string(),
-                    Literal::Float(f) => f.to_string(),
+                    Literal::Float(f) => format!("{:?}", f),
                     Literal::String(s) => format!("\"{}\"", s),
                     Literal::Bool(b) => b.to_string(),
                     _ => "Default::default()".to_string(),
@@ -1523,7 +2245,7 @@ pub(crate) fn codegen_try_stmt(
                             Some((format!("-{}", n), handlers[0].exception_type.clone()))
                         }
                         (crate::hir::UnaryOp::Neg, Literal::Float(f)) => {
-                            Some((format!("-{}", f), handlers[0].exception_type.clone()))
+                            Some((format!("-{:?}", f), handlers[0].exception_type.clone()))
                         }
                         _ => None,
                     }
@@ -1627,6 +2349,14 @@ pub(crate) fn codegen_try_stmt(
     // DEPYLER-0333: Exit try block scope
     ctx.exit_exception_scope();
 
+    // Only a lone `return int(s)` matches on the error, binding the error
+    // py_int returns as `Err(e)`
+    let binds_error = handlers.len() == 1
+        && handlers[0].name.is_some()
+        && body.len() == 1
+        && matches!(&body[0], HirStmt::Return(Some(HirExpr::Call { func, args, .. }))
+            if func == "int" && args.len() == 1);
+
     // Generate except handler code
     let mut handler_tokens = Vec::new();
     for handler in handlers {
@@ -1645,14 +2375,22 @@ pub(crate) fn codegen_try_stmt(
         let saved_is_final = ctx.is_final_statement;
         ctx.is_final_statement = false;
 
+        // Expose the handled exception to traceback.format_exc() and friends
+        let saved_handler = ctx.current_handler.replace(HandlerBinding {
+            exception_type: handler.exception_type.clone(),
+            name: handler.name.clone(),
+            bound_error: binds_error.then(|| numeric_parse_gen::PARSE_ERROR.to_string()),
+        });
+
         let handler_stmts: Vec<_> = handler
             .body
             .iter()
             .map(|s| s.to_rust_tokens(ctx))
             .collect::<Result<Vec<_>>>()?;
 
-        // Restore is_final_statement flag
+        // Restore is_final_statement flag and the enclosing handler
         ctx.is_final_statement = saved_is_final;
+        ctx.current_handler = saved_handler;
         ctx.exit_scope();
         // DEPYLER-0333: Exit handler scope
         ctx.exit_exception_scope();
@@ -1750,6 +2488,8 @@ pub(crate) fn codegen_try_stmt(
                         if func == "int" && args.len() == 1 {
                             // Single handler with exception binding - use match with Err(e)
                             let arg_expr = args[0].to_rust_expr(ctx)?;
+                            let parse =
+                                numeric_parse_gen::py_int_call(&args[0], &arg_expr, None, ctx);
                             let handler_body = &handler_tokens[0];
                             let err_var = handlers[0].name.as_ref().map(|s| {
                                 safe_ident(s) // DEPYLER-0023
@@ -1762,7 +2502,7 @@ pub(crate) fn codegen_try_stmt(
                                     .collect::<Result<Vec<_>>>()?;
                                 return Ok(quote! {
                                     {
-                                        match #arg_expr.parse::<i32>() {
+                                        match #parse {
                                             Ok(__value) => __value,
                                             Err(#err_var) => {
                                                 #handler_body
@@ -1773,7 +2513,7 @@ pub(crate) fn codegen_try_stmt(
                                 });
                             } else {
                                 return Ok(quote! {
-                                    match #arg_expr.parse::<i32>() {
+                                    match #parse {
                                         Ok(__value) => __value,
                                         Err(#err_var) => {
                                             #handler_body
@@ -1836,6 +2576,8 @@ pub(crate) fn codegen_try_stmt(
                     if let HirStmt::Return(Some(HirExpr::Call { func, args , ..})) = &body[0] {
                         if func == "int" && args.len() == 1 {
                             let arg_expr = args[0].to_rust_expr(ctx)?;
+                            let parse =
+                                numeric_parse_gen::py_int_call(&args[0], &arg_expr, None, ctx);
 
                             // Check if any handler binds the exception variable
                             let has_exception_binding = handlers.iter().any(|h| h.name.is_some());
@@ -1850,7 +2592,7 @@ pub(crate) fn codegen_try_stmt(
                                 if let Some(finally_code) = finally_stmts {
                                     return Ok(quote! {
                                         {
-                                            match #arg_expr.parse::<i32>() {
+                                            match #parse {
                                                 Ok(__value) => __value,
                                                 Err(#err_var) => {
                                                     #handler_body
@@ -1861,7 +2603,7 @@ pub(crate) fn codegen_try_stmt(
                                     });
                                 } else {
                                     return Ok(quote! {
-                                        match #arg_expr.parse::<i32>() {
+                                        match #parse {
                                             Ok(__value) => __value,
                                             Err(#err_var) => {
                                                 #handler_body
@@ -1872,16 +2614,16 @@ pub(crate) fn codegen_try_stmt(
                             } else if handlers.len() >= 2 {
                                 // DEPYLER-0361: Multiple handlers for int() - include ALL handlers
                                 // Convert: try { return int(data) } except ValueError {...} except TypeError {...}
-                                // To: if let Ok(v) = data.parse::<i32>() { v } else { handler1; handler2; }
+                                // To: if let Ok(v) = py_int(&data, 10) { v } else { handler1; handler2; }
 
-                                // NOTE: Rust's parse() returns a single error type, so we can't dispatch
+                                // NOTE: py_int() returns a single error type, so we can't dispatch
                                 // to specific handlers. We execute all handlers sequentially.
                                 // This is semantically incorrect but compiles. TODO: Improve error dispatch.
 
                                 if let Some(finally_code) = finally_stmts {
                                     return Ok(quote! {
                                         {
-                                            if let Ok(__parse_result) = #arg_expr.parse::<i32>() {
+                                            if let Ok(__parse_result) = #parse {
                                                 __parse_result
                                             } else {
                                                 #(#handler_tokens)*
@@ -1892,7 +2634,7 @@ pub(crate) fn codegen_try_stmt(
                                 } else {
                                     return Ok(quote! {
                                         {
-                                            if let Ok(__parse_result) = #arg_expr.parse::<i32>() {
+                                            if let Ok(__parse_result) = #parse {
                                                 __parse_result
                                             } else {
                                                 #(#handler_tokens)*
@@ -2011,10 +2753,7 @@ impl RustCodeGen for HirStmt {
             HirStmt::While { condition, body } => codegen_while_stmt(condition, body, ctx),
             HirStmt::For { target, iter, body } => codegen_for_stmt(target, iter, body, ctx),
             HirStmt::Expr(expr) => codegen_expr_stmt(expr, ctx),
-            HirStmt::Raise {
-                exception,
-                cause: _,
-            } => codegen_raise_stmt(exception, ctx),
+            HirStmt::Raise { exception, cause } => codegen_raise_stmt(exception, cause, ctx),
             HirStmt::Break { label } => codegen_break_stmt(label),
             HirStmt::Continue { label } => codegen_continue_stmt(label),
             HirStmt::With {
@@ -2030,6 +2769,7 @@ impl RustCodeGen for HirStmt {
             } => codegen_try_stmt(body, handlers, finalbody, ctx),
             HirStmt::Assert { test, msg } => codegen_assert_stmt(test, msg, ctx),
             HirStmt::Pass => codegen_pass_stmt(),
+            HirStmt::Match { subject, cases } => codegen_match_stmt(subject, cases, ctx),
         }
     }
 }
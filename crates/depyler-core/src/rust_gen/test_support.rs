@@ -0,0 +1,18 @@
+//! Helpers shared by the code generators' unit tests
+
+use crate::DepylerPipeline;
+
+/// `python` transpiled by `pipeline`, without whitespace so assertions don't
+/// depend on formatting
+pub(crate) fn transpile_with(pipeline: DepylerPipeline, python: &str) -> String {
+    pipeline
+        .transpile(python)
+        .unwrap()
+        .split_whitespace()
+        .collect()
+}
+
+/// `python` transpiled by the default pipeline, without whitespace
+pub(crate) fn transpile(python: &str) -> String {
+    transpile_with(DepylerPipeline::new(), python)
+}
@@ -0,0 +1,197 @@
+//! Module-level type aliases and `NewType`s
+//!
+//! | Python                            | Rust                             |
+//! |-----------------------------------|----------------------------------|
+//! | `Vector = list[float]`            | `pub type Vector = Vec<f64>;`    |
+//! | `Grid: TypeAlias = list[Vector]`  | `pub type Grid = Vec<Vec<f64>>;` |
+//! | `UserId = NewType("UserId", int)` | `pub struct UserId(pub i32);`    |
+//!
+//! An alias is only another name for its target, so every annotation naming
+//! one is resolved to the target before code generation: the analyses that
+//! decide borrowing and method translation see a list where the source says
+//! `Vector`. The `type` item remains for code using the module.
+//!
+//! A `NewType` is a type of its own. Its struct derives what the base type
+//! allows and has a `new` constructor, which `UserId(5)` calls. With
+//! `CodeGenOptions::newtype_conversions` it also converts to and from the
+//! base type with `From` and dereferences to it.
+
+use crate::hir::{HirModule, HirParam, HirStmt, TypeAlias};
+use crate::rust_gen::context::CodeGenContext;
+use crate::rust_gen::type_gen::{rust_type_to_syn, update_import_needs};
+use crate::type_mapper::{PrimitiveType, RustType, TypeMapper};
+use anyhow::Result;
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// `module` with the aliases named in its annotations resolved by `mapper`
+pub(crate) fn resolve_module(module: &HirModule, mapper: &TypeMapper) -> HirModule {
+    let mut module = module.clone();
+    for func in &mut module.functions {
+        resolve_params(&mut func.params, mapper);
+        func.ret_type = mapper.resolve_aliases(&func.ret_type);
+        resolve_body(&mut func.body, mapper);
+    }
+    for class in &mut module.classes {
+        for field in &mut class.fields {
+            field.field_type = mapper.resolve_aliases(&field.field_type);
+        }
+        for method in &mut class.methods {
+            resolve_params(&mut method.params, mapper);
+            method.ret_type = mapper.resolve_aliases(&method.ret_type);
+            resolve_body(&mut method.body, mapper);
+        }
+    }
+    for constant in &mut module.constants {
+        if let Some(ty) = &mut constant.type_annotation {
+            *ty = mapper.resolve_aliases(ty);
+        }
+    }
+    module
+}
+
+fn resolve_params(params: &mut [HirParam], mapper: &TypeMapper) {
+    for param in params {
+        param.ty = mapper.resolve_aliases(&param.ty);
+    }
+}
+
+fn resolve_body(body: &mut [HirStmt], mapper: &TypeMapper) {
+    for stmt in body {
+        if let HirStmt::Assign {
+            type_annotation: Some(ty),
+            ..
+        } = stmt
+        {
+            *ty = mapper.resolve_aliases(ty);
+        }
+        for nested in stmt.nested_bodies_mut() {
+            resolve_body(nested, mapper);
+        }
+    }
+}
+
+/// `type` items and newtype structs for the module's aliases
+pub(crate) fn generate_type_aliases(
+    aliases: &[TypeAlias],
+    ctx: &mut CodeGenContext,
+) -> Result<Vec<TokenStream>> {
+    let mut items = Vec::new();
+    for alias in aliases {
+        let name = syn::Ident::new(&alias.name, proc_macro2::Span::call_site());
+        let rust_type = ctx.type_mapper.map_type(&alias.target_type);
+        update_import_needs(ctx, &rust_type);
+        let target = rust_type_to_syn(&rust_type)?;
+        if !alias.is_newtype {
+            items.push(quote! { pub type #name = #target; });
+            continue;
+        }
+
+        let derives = newtype_derives(&rust_type);
+        items.push(quote! {
+            #[derive(#(#derives),*)]
+            pub struct #name(pub #target);
+
+            impl #name {
+                pub fn new(value: impl Into<#target>) -> Self {
+                    Self(value.into())
+                }
+            }
+        });
+        if ctx.options.newtype_conversions {
+            items.push(quote! {
+                impl From<#target> for #name {
+                    fn from(value: #target) -> Self {
+                        Self(value)
+                    }
+                }
+
+                impl From<#name> for #target {
+                    fn from(value: #name) -> Self {
+                        value.0
+                    }
+                }
+
+                impl std::ops::Deref for #name {
+                    type Target = #target;
+
+                    fn deref(&self) -> &Self::Target {
+                        &self.0
+                    }
+                }
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// The derives the base type supports, of those a Python value of it has
+/// a use for
+fn newtype_derives(base: &RustType) -> Vec<syn::Ident> {
+    let names: &[&str] = match base {
+        RustType::Primitive(PrimitiveType::F32 | PrimitiveType::F64) => {
+            &["Debug", "Clone", "Copy", "PartialEq", "PartialOrd"]
+        }
+        RustType::Primitive(_) => &[
+            "Debug",
+            "Clone",
+            "Copy",
+            "PartialEq",
+            "Eq",
+            "Hash",
+            "PartialOrd",
+            "Ord",
+        ],
+        RustType::String => &[
+            "Debug",
+            "Clone",
+            "PartialEq",
+            "Eq",
+            "Hash",
+            "PartialOrd",
+            "Ord",
+        ],
+        _ => &["Debug", "Clone", "PartialEq"],
+    };
+    names
+        .iter()
+        .map(|name| syn::Ident::new(name, proc_macro2::Span::call_site()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rust_gen::test_support::transpile_with;
+    use crate::DepylerPipeline;
+
+    #[test]
+    fn test_aliases_resolve_in_signatures() {
+        let code = transpile_with(
+            DepylerPipeline::new(),
+            "Vector = list[float]\n\ndef total(v: Vector) -> float:\n    return sum(v)\n",
+        );
+
+        assert!(code.contains("pubtypeVector=Vec<f64>;"));
+        assert!(code.contains("v:&Vec<f64>"));
+    }
+
+    #[test]
+    fn test_newtypes_derive_what_their_base_allows() {
+        let python = "UserId = NewType(\"UserId\", int)\nName = NewType(\"Name\", str)\n";
+        let code = transpile_with(DepylerPipeline::new(), python);
+
+        assert!(code.contains(
+            "#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash,PartialOrd,Ord)]pubstructUserId(pubi32);"
+        ));
+        assert!(code.contains(
+            "#[derive(Debug,Clone,PartialEq,Eq,Hash,PartialOrd,Ord)]pubstructName(pubString);"
+        ));
+        assert!(code.contains("pubfnnew(value:implInto<i32>)->Self"));
+        assert!(!code.contains("implFrom<i32>forUserId"));
+
+        let code = transpile_with(DepylerPipeline::new().with_newtype_conversions(), python);
+        assert!(code.contains("implFrom<i32>forUserId"));
+        assert!(code.contains("implFrom<UserId>fori32"));
+        assert!(code.contains("implstd::ops::DerefforName"));
+    }
+}
@@ -0,0 +1,178 @@
+//! `#[wasm_bindgen]` exports for the wasm type profile
+//!
+//! A public function is exported when wasm-bindgen can pass every value in
+//! its signature across the JavaScript boundary:
+//!
+//! | Position  | Accepted                                                  |
+//! |-----------|-----------------------------------------------------------|
+//! | parameter | numbers, `bool`, `char`, `String`, `&str`, `&[number]`    |
+//! | parameter | `Option` of a number, `bool`, `char` or `String`          |
+//! | parameter | `Vec` of a number or `String`                             |
+//! | return    | the owned parameter types, or nothing                     |
+//!
+//! Other functions stay as they are and remain callable from Rust. Generic,
+//! `async` and fallible functions are never exported: a `Result` needs an
+//! error that converts to `JsValue`, which generated error types are not.
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::parse_quote;
+
+const NUMBERS: &[&str] = &[
+    "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "isize", "usize", "f32", "f64",
+];
+
+/// Marks the functions wasm-bindgen can export, and says whether any were;
+/// tokens that do not parse are left as they are
+pub(crate) fn export_functions(functions: Vec<TokenStream>) -> (Vec<TokenStream>, bool) {
+    let mut exported = false;
+    let functions = functions
+        .into_iter()
+        .map(|tokens| match syn::parse2::<syn::File>(tokens.clone()) {
+            Ok(mut file) => {
+                for item in &mut file.items {
+                    if let syn::Item::Fn(func) = item {
+                        if is_exportable(func) {
+                            func.attrs.push(parse_quote! { #[wasm_bindgen] });
+                            exported = true;
+                        }
+                    }
+                }
+                file.into_token_stream()
+            }
+            Err(_) => tokens,
+        })
+        .collect();
+    (functions, exported)
+}
+
+fn is_exportable(func: &syn::ItemFn) -> bool {
+    let sig = &func.sig;
+    let params_pass = sig.inputs.iter().all(|input| match input {
+        syn::FnArg::Typed(param) => {
+            matches!(*param.pat, syn::Pat::Ident(_)) && is_parameter_type(&param.ty)
+        }
+        syn::FnArg::Receiver(_) => false,
+    });
+    let returns_pass = match &sig.output {
+        syn::ReturnType::Default => true,
+        syn::ReturnType::Type(_, ty) => is_owned_type(ty),
+    };
+    matches!(func.vis, syn::Visibility::Public(_))
+        && sig.ident != "main"
+        && sig.asyncness.is_none()
+        && sig.generics.params.is_empty()
+        && !has_wasm_bindgen(&func.attrs)
+        && params_pass
+        && returns_pass
+}
+
+fn has_wasm_bindgen(attrs: &[syn::Attribute]) -> bool {
+    attrs
+        .iter()
+        .any(|attr| attr.path().is_ident("wasm_bindgen"))
+}
+
+fn is_parameter_type(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(reference) => match &*reference.elem {
+            syn::Type::Path(path) => path.path.is_ident("str") && reference.mutability.is_none(),
+            syn::Type::Slice(slice) => is_number(&slice.elem),
+            _ => false,
+        },
+        _ => is_owned_type(ty),
+    }
+}
+
+fn is_owned_type(ty: &syn::Type) -> bool {
+    if is_scalar(ty) || is_named(ty, "String") {
+        return true;
+    }
+    match single_argument(ty) {
+        Some(("Option", inner)) => is_scalar(inner) || is_named(inner, "String"),
+        Some(("Vec", inner)) => is_number(inner) || is_named(inner, "String"),
+        _ => false,
+    }
+}
+
+fn is_scalar(ty: &syn::Type) -> bool {
+    is_number(ty) || is_named(ty, "bool") || is_named(ty, "char")
+}
+
+fn is_number(ty: &syn::Type) -> bool {
+    NUMBERS.iter().any(|name| is_named(ty, name))
+}
+
+fn is_named(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident(name))
+}
+
+/// `Name<T>` as its name and `T`
+fn single_argument(ty: &syn::Type) -> Option<(&'static str, &syn::Type)> {
+    let path = match ty {
+        syn::Type::Path(path) if path.qself.is_none() && path.path.segments.len() == 1 => path,
+        _ => return None,
+    };
+    let segment = &path.path.segments[0];
+    let name = ["Option", "Vec"]
+        .into_iter()
+        .find(|name| segment.ident == name)?;
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(inner) => Some((name, inner)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+
+    fn exported(tokens: TokenStream) -> bool {
+        let (functions, any) = export_functions(vec![tokens]);
+        let marked = functions[0].to_string().contains("wasm_bindgen");
+        assert_eq!(marked, any);
+        marked
+    }
+
+    #[test]
+    fn test_exports_functions_with_passable_signatures() {
+        assert!(exported(
+            quote! { pub fn add(a: i32, b: i32) -> i32 { a + b } }
+        ));
+        assert!(exported(
+            quote! { pub fn greet(name: &str) -> String { name.to_string() } }
+        ));
+        assert!(exported(
+            quote! { pub fn total(xs: &[f64]) -> f64 { xs.iter().sum() } }
+        ));
+        assert!(exported(
+            quote! { pub fn find(xs: Vec<String>) -> Option<i32> { None } }
+        ));
+        assert!(exported(quote! { pub fn log(n: u8) {} }));
+    }
+
+    #[test]
+    fn test_keeps_other_functions_as_they_are() {
+        assert!(!exported(quote! { fn private(a: i32) -> i32 { a } }));
+        assert!(!exported(quote! { pub fn main() {} }));
+        assert!(!exported(
+            quote! { pub async fn fetch(n: i32) -> i32 { n } }
+        ));
+        assert!(!exported(
+            quote! { pub fn first<'a>(s: &'a str) -> &'a str { s } }
+        ));
+        assert!(!exported(
+            quote! { pub fn words(s: &str) -> Vec<&str> { vec![] } }
+        ));
+        assert!(!exported(
+            quote! { pub fn keys(m: &HashMap<String, i32>) -> Vec<String> { vec![] } }
+        ));
+        assert!(!exported(
+            quote! { pub fn parse(s: &str) -> Result<i32, ValueError> { Ok(0) } }
+        ));
+    }
+}
@@ -1,8 +1,22 @@
 use crate::hir::{AssignTarget, HirExpr, HirFunction, HirStmt, Literal, Type};
+use crate::lifetime_analysis::{borrowed_parts, can_return_borrow, collect_returns, rebinds};
+use depyler_annotations::StringStrategy;
 use std::collections::{HashMap, HashSet};
 
+/// Occurrences of a string literal, across all functions of a module, from
+/// which it is interned by default
+pub const DEFAULT_INTERN_THRESHOLD: usize = 4;
+
+/// Longest name, after `STR_`, derived from an interned string's content
+const MAX_INTERNED_NAME_LEN: usize = 32;
+
 /// Analyzes string usage patterns to determine optimal string types
-#[derive(Debug, Default)]
+///
+/// A literal repeated at least [`StringOptimizer::intern_threshold`] times
+/// in the analyzed functions is interned: it is declared once as a
+/// `&'static str` constant and every use refers to the constant, followed
+/// by `.to_string()` where an owned `String` is needed.
+#[derive(Debug)]
 pub struct StringOptimizer {
     /// String literals that are only read, never mutated
     read_only_strings: HashSet<String>,
@@ -14,8 +28,24 @@ pub struct StringOptimizer {
     mixed_usage_strings: HashSet<String>,
     /// String literal frequency counter for interning decisions
     string_literal_count: HashMap<String, usize>,
-    /// Strings that should be interned due to frequent use
-    interned_strings: HashSet<String>,
+    /// Strings interned due to frequent use, with their constant names
+    interned_names: HashMap<String, String>,
+    /// Occurrences from which a literal is interned; 0 disables interning
+    intern_threshold: usize,
+}
+
+impl Default for StringOptimizer {
+    fn default() -> Self {
+        Self {
+            read_only_strings: HashSet::new(),
+            immutable_params: HashSet::new(),
+            returned_strings: HashSet::new(),
+            mixed_usage_strings: HashSet::new(),
+            string_literal_count: HashMap::new(),
+            interned_names: HashMap::new(),
+            intern_threshold: DEFAULT_INTERN_THRESHOLD,
+        }
+    }
 }
 
 /// Optimal string representation based on usage analysis
@@ -36,6 +66,26 @@ impl StringOptimizer {
         Self::default()
     }
 
+    /// Intern literals repeated at least `threshold` times instead of
+    /// [`DEFAULT_INTERN_THRESHOLD`]; 0 interns nothing
+    ///
+    /// # Example
+    /// ```
+    /// use depyler_core::string_optimization::StringOptimizer;
+    ///
+    /// let optimizer = StringOptimizer::new().with_intern_threshold(2);
+    /// assert_eq!(optimizer.intern_threshold(), 2);
+    /// ```
+    pub fn with_intern_threshold(mut self, threshold: usize) -> Self {
+        self.intern_threshold = threshold;
+        self
+    }
+
+    /// Occurrences from which a literal is interned
+    pub fn intern_threshold(&self) -> usize {
+        self.intern_threshold
+    }
+
     /// Analyze a function to determine optimal string types
     pub fn analyze_function(&mut self, func: &HirFunction) {
         // Track string parameters
@@ -184,10 +234,10 @@ impl StringOptimizer {
     }
 
     fn analyze_string_literal(&mut self, s: &str, is_returned: bool) {
-        *self.string_literal_count.entry(s.to_string()).or_insert(0) += 1;
-
-        if self.string_literal_count.get(s).copied().unwrap_or(0) > 3 {
-            self.interned_strings.insert(s.to_string());
+        let count = self.string_literal_count.entry(s.to_string()).or_insert(0);
+        *count += 1;
+        if self.intern_threshold > 0 && *count == self.intern_threshold {
+            self.intern(s);
         }
 
         if is_returned {
@@ -280,44 +330,137 @@ impl StringOptimizer {
         )
     }
 
+    /// Give `s` a constant name derived from its content
+    ///
+    /// Strings whose names would clash, like `"max-size"` and `"max_size"`,
+    /// are told apart by a numeric suffix in the order they are interned.
+    fn intern(&mut self, s: &str) {
+        let name = s
+            .chars()
+            .take(MAX_INTERNED_NAME_LEN)
+            .map(|c| match c {
+                'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
+                _ => '_',
+            })
+            .collect::<String>();
+        let base = format!("STR_{}", if name.is_empty() { "EMPTY" } else { &name });
+        let taken = |name: &String| self.interned_names.values().any(|n| n == name);
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while taken(&name) {
+            suffix += 1;
+            name = format!("{}_{}", base, suffix);
+        }
+        self.interned_names.insert(s.to_string(), name);
+    }
+
     /// Check if a string literal should be interned
     pub fn should_intern(&self, s: &str) -> bool {
-        self.interned_strings.contains(s)
+        self.interned_names.contains_key(s)
     }
 
     /// Get interned string name for a literal
     pub fn get_interned_name(&self, s: &str) -> Option<String> {
-        if self.should_intern(s) {
-            // Generate a constant name from the string content
-            let name = s
-                .chars()
-                .map(|c| match c {
-                    'a'..='z' | 'A'..='Z' | '0'..='9' => c.to_ascii_uppercase(),
-                    _ => '_',
-                })
-                .collect::<String>();
-            Some(format!(
-                "STR_{}",
-                if name.is_empty() { "EMPTY" } else { &name }
-            ))
-        } else {
-            None
-        }
+        self.interned_names.get(s).cloned()
     }
 
-    /// Generate interned string constants
+    /// Generate interned string constants, ordered by name
     pub fn generate_interned_constants(&self) -> Vec<String> {
-        let mut constants = Vec::new();
-        for s in &self.interned_strings {
-            if let Some(name) = self.get_interned_name(s) {
-                constants.push(format!(
-                    "const {}: &'static str = \"{}\";",
-                    name,
-                    escape_string(s)
-                ));
-            }
-        }
-        constants
+        let mut interned: Vec<(&String, &String)> = self.interned_names.iter().collect();
+        interned.sort_by(|a, b| a.1.cmp(b.1));
+        interned
+            .into_iter()
+            .map(|(s, name)| format!("const {}: &'static str = \"{}\";", name, escape_string(s)))
+            .collect()
+    }
+}
+
+/// The `str` parameter a function under `string_strategy = "cow"` returns
+/// unchanged on some paths, when it returns a new string on the others
+///
+/// Returned values are split into the branches of their conditional
+/// expressions. A branch that is the parameter, a slice or strip of it, or
+/// a string literal borrows; any other branch is a new string. Both kinds
+/// must occur and every borrow must be of the one parameter, which is
+/// neither rebound nor mutated. A function whose returns all borrow returns
+/// a plain reference instead, see
+/// [`borrowed_return_source`](crate::lifetime_analysis::borrowed_return_source).
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::string_optimization::cow_return_source;
+/// use depyler_core::DepylerPipeline;
+///
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir(
+///         "# @depyler: string_strategy = \"cow\"\n\
+///          def tidy(s: str) -> str:\n    return s if s.isalpha() else s.strip()\n",
+///     )
+///     .unwrap();
+/// assert_eq!(cow_return_source(&module.functions[0]), None);
+///
+/// let module = DepylerPipeline::new()
+///     .parse_to_hir(
+///         "# @depyler: string_strategy = \"cow\"\n\
+///          def tidy(s: str) -> str:\n    return s if s.isalpha() else s.lower()\n",
+///     )
+///     .unwrap();
+/// assert_eq!(cow_return_source(&module.functions[0]), Some("s"));
+/// ```
+pub fn cow_return_source(func: &HirFunction) -> Option<&str> {
+    if func.annotations.string_strategy != StringStrategy::CopyOnWrite
+        || func.ret_type != Type::String
+        || !can_return_borrow(func)
+    {
+        return None;
+    }
+
+    let params: Vec<&str> = func
+        .params
+        .iter()
+        .filter(|param| param.ty == Type::String)
+        .map(|param| param.name.as_str())
+        .collect();
+    let mut returns = Vec::new();
+    collect_returns(&func.body, &mut returns);
+    let mut sources = Vec::new();
+    let mut owned = false;
+    for value in returns {
+        cow_parts(value?, &params, &mut sources, &mut owned);
+    }
+    let (first, rest) = sources.split_first()?;
+    if !owned || rest.iter().any(|name| name != first) {
+        return None;
+    }
+
+    let param = func.params.iter().find(|param| param.name == *first)?;
+    if param.default.is_some() || rebinds(&func.body, &param.name) {
+        return None;
+    }
+    Some(&param.name)
+}
+
+/// Pushes the parameters the borrowing branches of a returned `value`
+/// borrow from onto `sources`, and sets `owned` when a branch is a new
+/// string; locals are owned
+fn cow_parts<'a>(
+    value: &'a HirExpr,
+    params: &[&str],
+    sources: &mut Vec<&'a str>,
+    owned: &mut bool,
+) {
+    let mut borrowed = Vec::new();
+    let mut string_only = false;
+    if borrowed_parts(value, &mut borrowed, &mut string_only)
+        && borrowed.iter().all(|name| params.contains(name))
+    {
+        sources.extend(borrowed);
+    } else if let HirExpr::IfExpr { body, orelse, .. } = value {
+        cow_parts(body, params, sources, owned);
+        cow_parts(orelse, params, sources, owned);
+    } else {
+        *owned = true;
     }
 }
 
@@ -499,4 +642,75 @@ mod tests {
             generate_optimized_string(&optimizer, &StringContext::Literal("hello".to_string()));
         assert!(code == "\"hello\".to_string()" || code == "\"hello\"");
     }
+
+    fn literals(strings: &[&str]) -> HirFunction {
+        HirFunction {
+            name: "test".to_string(),
+            params: vec![].into(),
+            ret_type: Type::None,
+            body: strings
+                .iter()
+                .map(|s| HirStmt::Expr(HirExpr::Literal(Literal::String(s.to_string()))))
+                .collect(),
+            properties: FunctionProperties::default(),
+            annotations: Default::default(),
+            docstring: None,
+        }
+    }
+
+    #[test]
+    fn test_intern_threshold_counts_across_functions() {
+        let mut optimizer = StringOptimizer::new().with_intern_threshold(3);
+        optimizer.analyze_function(&literals(&["key", "key"]));
+        assert!(!optimizer.should_intern("key"));
+        optimizer.analyze_function(&literals(&["key"]));
+        assert_eq!(
+            optimizer.get_interned_name("key").as_deref(),
+            Some("STR_KEY")
+        );
+    }
+
+    #[test]
+    fn test_zero_threshold_interns_nothing() {
+        let mut optimizer = StringOptimizer::new().with_intern_threshold(0);
+        optimizer.analyze_function(&literals(&["key"; 10]));
+        assert!(optimizer.generate_interned_constants().is_empty());
+    }
+
+    #[test]
+    fn test_clashing_names_get_a_suffix() {
+        let mut optimizer = StringOptimizer::new().with_intern_threshold(1);
+        optimizer.analyze_function(&literals(&["max-size", "max_size", "max size"]));
+        assert_eq!(
+            optimizer.generate_interned_constants(),
+            [
+                "const STR_MAX_SIZE: &'static str = \"max-size\";",
+                "const STR_MAX_SIZE_2: &'static str = \"max_size\";",
+                "const STR_MAX_SIZE_3: &'static str = \"max size\";",
+            ]
+        );
+    }
+
+    fn cow_source(python: &str) -> Option<String> {
+        let module = crate::DepylerPipeline::new().parse_to_hir(python).unwrap();
+        cow_return_source(&module.functions[0]).map(str::to_string)
+    }
+
+    #[test]
+    fn test_cow_return_needs_the_annotation_and_both_kinds_of_return() {
+        let cow = "# @depyler: string_strategy = \"cow\"\n";
+        let norm = "def norm(s: str) -> str:\n    if s.startswith(\"#\"):\n        return s\n    result = s.lower()\n    return result\n";
+        assert_eq!(
+            cow_source(&format!("{}{}", cow, norm)).as_deref(),
+            Some("s")
+        );
+        assert_eq!(cow_source(norm), None);
+
+        // Every return borrows
+        let head = "def head(s: str) -> str:\n    return s[:3] if s else \"-\"\n";
+        assert_eq!(cow_source(&format!("{}{}", cow, head)), None);
+        // Borrows from either parameter
+        let pick = "def pick(a: str, b: str) -> str:\n    if a:\n        return a\n    if b:\n        return b\n    return a + b\n";
+        assert_eq!(cow_source(&format!("{}{}", cow, pick)), None);
+    }
 }
@@ -0,0 +1,230 @@
+//! Deterministic names for symbols flattened into one Rust namespace
+//!
+//! Python keeps functions apart by module and class: `calc.get`,
+//! `Cache.get` and `tests.get` never clash. Wherever the transpiler
+//! flattens several of those scopes into one Rust module or directory, each
+//! symbol claims its name in a [`SymbolTable`]. A name that is already taken
+//! is qualified with the symbol's enclosing scopes, innermost first, and
+//! only then numbered, so the same input always yields the same names.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Where a symbol comes from: its enclosing scopes, then its own name
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QualifiedName {
+    segments: Vec<String>,
+}
+
+impl QualifiedName {
+    /// `QualifiedName::new(["calc", "Cache", "get"])` is `get` in class
+    /// `Cache` of module `calc`
+    pub fn new<I, S>(segments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            segments: segments.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// The symbol's own name
+    pub fn name(&self) -> &str {
+        self.segments.last().map_or("", String::as_str)
+    }
+
+    /// Enclosing scopes, innermost first
+    fn qualifiers(&self) -> impl Iterator<Item = &str> {
+        let scopes = self.segments.len().saturating_sub(1);
+        self.segments[..scopes].iter().rev().map(String::as_str)
+    }
+}
+
+impl fmt::Display for QualifiedName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.segments.join("."))
+    }
+}
+
+/// Two symbols that would be emitted under the same Rust name
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{}", describe_collision(.name, .first, .second))]
+pub struct SymbolCollision {
+    pub name: String,
+    pub first: QualifiedName,
+    pub second: QualifiedName,
+}
+
+fn describe_collision(name: &str, first: &QualifiedName, second: &QualifiedName) -> String {
+    if first == second {
+        format!("`{}` is defined more than once", first)
+    } else {
+        format!(
+            "`{}` would be generated for both `{}` and `{}`",
+            name, first, second
+        )
+    }
+}
+
+/// Rust names claimed so far in one namespace
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_core::symbol_names::{QualifiedName, SymbolTable};
+///
+/// let mut symbols = SymbolTable::default();
+/// symbols
+///     .reserve("get", QualifiedName::new(["calc", "get"]))
+///     .unwrap();
+///
+/// let cache = symbols.assign("get", QualifiedName::new(["calc", "Cache", "get"]));
+/// let store = symbols.assign("get", QualifiedName::new(["calc", "Store", "get"]));
+/// assert_eq!(cache, "cache_get");
+/// assert_eq!(store, "store_get");
+///
+/// let err = symbols
+///     .reserve("cache_get", QualifiedName::new(["calc", "cache_get"]))
+///     .unwrap_err();
+/// assert_eq!(
+///     err.to_string(),
+///     "`cache_get` would be generated for both `calc.Cache.get` and `calc.cache_get`"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SymbolTable {
+    names: BTreeMap<String, QualifiedName>,
+}
+
+impl SymbolTable {
+    /// Claims `name` exactly, for symbols whose name others already rely on
+    pub fn reserve(&mut self, name: &str, origin: QualifiedName) -> Result<(), SymbolCollision> {
+        if let Some(first) = self.names.get(name) {
+            return Err(SymbolCollision {
+                name: name.to_string(),
+                first: first.clone(),
+                second: origin,
+            });
+        }
+        self.names.insert(name.to_string(), origin);
+        Ok(())
+    }
+
+    /// Claims `preferred` if it is free, otherwise the first free name among
+    /// `preferred` prefixed by the origin's scopes one at a time, then that
+    /// fully qualified name suffixed `_2`, `_3`, ...
+    pub fn assign(&mut self, preferred: &str, origin: QualifiedName) -> String {
+        let mut candidate = preferred.to_string();
+        let mut qualifiers = origin.qualifiers().map(snake_case).collect::<Vec<_>>();
+        qualifiers.retain(|q| !q.is_empty());
+        let mut qualifiers = qualifiers.into_iter();
+        while self.names.contains_key(&candidate) {
+            match qualifiers.next() {
+                // `test_parser_test_get` already carries its class
+                Some(q) if candidate.starts_with(&format!("{}_", q)) => {}
+                Some(q) => candidate = format!("{}_{}", q, candidate),
+                None => break,
+            }
+        }
+        let qualified = candidate.clone();
+        let mut n = 2;
+        while self.names.contains_key(&candidate) {
+            candidate = format!("{}_{}", qualified, n);
+            n += 1;
+        }
+        self.names.insert(candidate.clone(), origin);
+        candidate
+    }
+
+    /// The symbol that claimed `name`
+    pub fn origin(&self, name: &str) -> Option<&QualifiedName> {
+        self.names.get(name)
+    }
+}
+
+/// `TestParser` -> `test_parser`, `HTTPClientTests` -> `http_client_tests`
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::new();
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() {
+            let prev_lower =
+                i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit());
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            if i > 0 && (prev_lower || (next_lower && chars[i - 1].is_uppercase())) {
+                out.push('_');
+            }
+            out.extend(ch.to_lowercase());
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin(segments: &[&str]) -> QualifiedName {
+        QualifiedName::new(segments.iter().copied())
+    }
+
+    #[test]
+    fn test_free_names_are_kept() {
+        let mut symbols = SymbolTable::default();
+        assert_eq!(symbols.assign("get", origin(&["calc", "get"])), "get");
+        assert_eq!(
+            symbols.assign("put", origin(&["calc", "Cache", "put"])),
+            "put"
+        );
+        assert_eq!(
+            symbols.origin("put").map(ToString::to_string).as_deref(),
+            Some("calc.Cache.put")
+        );
+    }
+
+    #[test]
+    fn test_qualifiers_apply_innermost_first_then_numbers() {
+        let mut symbols = SymbolTable::default();
+        let get = origin(&["calc", "Cache", "get"]);
+        assert_eq!(symbols.assign("get", get.clone()), "get");
+        assert_eq!(symbols.assign("get", get.clone()), "cache_get");
+        assert_eq!(symbols.assign("get", get.clone()), "calc_cache_get");
+        assert_eq!(symbols.assign("get", get.clone()), "calc_cache_get_2");
+        assert_eq!(symbols.assign("get", get), "calc_cache_get_3");
+    }
+
+    #[test]
+    fn test_qualifier_already_in_preferred_name_is_skipped() {
+        let mut symbols = SymbolTable::default();
+        let method = origin(&["tests", "TestCache", "test_get"]);
+        symbols
+            .reserve(
+                "test_cache_test_get",
+                origin(&["calc", "test_cache_test_get"]),
+            )
+            .unwrap();
+        assert_eq!(
+            symbols.assign("test_cache_test_get", method),
+            "tests_test_cache_test_get"
+        );
+    }
+
+    #[test]
+    fn test_reserve_reports_both_origins() {
+        let mut symbols = SymbolTable::default();
+        symbols.reserve("get", origin(&["get"])).unwrap();
+        let err = symbols.reserve("get", origin(&["get"])).unwrap_err();
+        assert_eq!(err.to_string(), "`get` is defined more than once");
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("TestParser"), "test_parser");
+        assert_eq!(snake_case("HTTPClientTests"), "http_client_tests");
+        assert_eq!(snake_case("Test2Things"), "test2_things");
+    }
+}
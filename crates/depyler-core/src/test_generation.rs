@@ -50,8 +50,9 @@ impl TestGenerator {
         &self,
         func: &HirFunction,
     ) -> Result<Vec<proc_macro2::TokenStream>> {
-        // Only generate tests for pure functions
-        if !func.properties.is_pure {
+        // Only generate tests for pure functions; a defaulted parameter takes
+        // an `Option` the generated calls would not pass
+        if !func.properties.is_pure || func.params.iter().any(|p| p.default.is_some()) {
             return Ok(Vec::new());
         }
 
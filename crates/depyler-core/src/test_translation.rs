@@ -0,0 +1,1766 @@
+//! pytest / unittest test translation
+//!
+//! Converts a Python test module into a Rust integration test - a file for
+//! the crate's `tests/` directory - that exercises the transpiled module
+//! through its public API:
+//!
+//! - `test_*` functions become `#[test]` functions
+//! - `assert` statements become `assert!`, `assert_eq!` or `assert_ne!`
+//! - `pytest.raises(E)` / `self.assertRaises(E)` check that the call returns
+//!   `Err` (for fallible functions) or panics (for everything else)
+//! - `unittest.TestCase` test methods become one test each, with `setUp` and
+//!   `tearDown` inlined and `self.<attr>` fixtures turned into locals
+//! - `@pytest.fixture` functions are called for every test parameter that
+//!   names them
+//! - `@pytest.mark.parametrize` cases become `rstest` `#[case]`s when every
+//!   parameter's type follows from its literal values, and one `#[test]` per
+//!   case otherwise; case IDs name the cases either way
+//! - `unittest.mock.patch` targets are reported; functions of the module
+//!   under test that were transpiled with a trait seam are replaced by fakes
+//!   returning the mock's `return_value`
+//!
+//! Tests that depend on anything else (yield fixtures, builtin fixtures such
+//! as `tmp_path`, skip markers, helper methods on `self`) are emitted as
+//! `#[ignore]`d stubs that say why, so the gap stays visible in `cargo test`.
+//!
+//! Test bodies are generated together with the module under test so that
+//! call sites see its signatures (borrowed parameters, fallible functions,
+//! constructors); only the test items are kept in the output.
+
+use crate::ast_bridge::AstBridge;
+use crate::hir::{
+    AssignTarget, BinOp, FunctionProperties, HirClass, HirExpr, HirFunction, HirModule, HirParam,
+    HirStmt, Literal, Type, UnaryOp,
+};
+use crate::rust_gen::{self, CodeGenOptions};
+use crate::symbol_names::{snake_case, QualifiedName, SymbolTable};
+use crate::type_mapper::TypeMapper;
+use anyhow::{bail, Result};
+use quote::{quote, ToTokens};
+use rustpython_ast::{self as ast};
+use smallvec::SmallVec;
+use std::collections::{HashMap, HashSet};
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
+use syn::{parse_quote, Token};
+
+mod parametrize;
+mod patching;
+
+use parametrize::{case_ident, case_type, parametrize_cases, ParamCase};
+use patching::{decorator_patches, fake_items, seam_call, with_patches, Fakes, Patch};
+
+/// Call that marks "the wrapped expression must raise" until the Rust pass
+const EXPECT_RAISES: &str = "__depyler_expect_raises__";
+
+/// Statement carrying one `rstest` case (`id, values...`) until the Rust pass
+const RSTEST_CASE: &str = "__depyler_rstest_case__";
+
+/// `rstest` release the generated `#[case]` attributes are written against
+const RSTEST_VERSION: &str = "0.18";
+
+/// Scope that qualifies test-file symbols clashing with the module under test
+const TESTS: &str = "tests";
+
+/// A Rust integration test file translated from a Python test module
+#[derive(Debug, Clone)]
+pub struct TranslatedTests {
+    /// Contents of the `tests/<name>.rs` file
+    pub code: String,
+    /// Names of the translated `#[test]` functions
+    pub tests: Vec<String>,
+    /// Tests emitted as `#[ignore]` stubs
+    pub ignored: Vec<IgnoredTest>,
+    /// `(crate, version)` pairs the test file needs under `[dev-dependencies]`
+    pub dev_dependencies: Vec<(String, String)>,
+    /// Names replaced with `unittest.mock.patch`, translated or not
+    pub patches: Vec<PatchedTarget>,
+}
+
+/// A Python test that could not be translated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IgnoredTest {
+    pub name: String,
+    pub reason: String,
+}
+
+/// A name a Python test replaces with `unittest.mock.patch`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchedTarget {
+    pub test: String,
+    /// The patched name as written, `"calc.fetch_rate"`
+    pub target: String,
+    /// The function of the module under test it replaces; a seam for it
+    /// (`DepylerPipeline::with_seams`) lets the translated test inject a fake
+    pub function: Option<String>,
+}
+
+/// Translates test modules for one transpiled Python module
+pub struct TestTranslator<'a> {
+    module: &'a HirModule,
+    module_name: String,
+    crate_name: String,
+}
+
+impl<'a> TestTranslator<'a> {
+    /// `module` is the HIR of the module under test, imported by the tests as
+    /// `module_name` and compiled into the library crate `crate_name`
+    pub fn new(module: &'a HirModule, module_name: &str, crate_name: &str) -> Self {
+        Self {
+            module,
+            module_name: module_name.to_string(),
+            crate_name: crate_name.replace('-', "_"),
+        }
+    }
+
+    pub fn translate(
+        &self,
+        tests: ast::Mod,
+        source: &str,
+        type_mapper: &TypeMapper,
+        options: &CodeGenOptions,
+    ) -> Result<TranslatedTests> {
+        let scan = match &tests {
+            ast::Mod::Module(m) => TestModuleScan::new(&m.body, &self.module_name),
+            _ => bail!("Test files must be Python modules"),
+        };
+        let mut hir = AstBridge::new()
+            .with_source(source.to_string())
+            .python_to_hir(tests)?;
+
+        // Tests, helpers and the module under test end up in one Rust module
+        let mut symbols = SymbolTable::default();
+        for func in &self.module.functions {
+            let origin = QualifiedName::new([self.module_name.as_str(), func.name.as_str()]);
+            symbols.reserve(&func.name, origin)?;
+        }
+        let original_names = rename_helpers(&mut hir, &scan, &mut symbols);
+
+        let normalizer = BodyNormalizer {
+            module_aliases: &scan.module_aliases,
+            renamed: &scan.renamed,
+            localize_self: false,
+        };
+        let fixtures: HashMap<&str, &HirFunction> = hir
+            .functions
+            .iter()
+            .map(|f| (original_name(&original_names, &f.name), f))
+            .filter(|(name, _)| scan.fixtures.contains(*name))
+            .collect();
+
+        let mut fakes = Fakes::new(
+            &self.module.functions,
+            &options.seams,
+            &self.module_name,
+            &scan.module_aliases,
+        );
+
+        let mut helpers = Vec::new();
+        let mut tests = Vec::new();
+        let mut rstest_tests = HashSet::new();
+        let mut ignored = Vec::new();
+        let mut patched = Vec::new();
+
+        for func in &hir.functions {
+            if original_names.contains_key(&func.name) || !is_test(&func.name, &scan) {
+                let mut helper = func.clone();
+                helper.body = normalizer
+                    .normalize(helper.body)
+                    .unwrap_or_else(|_| func.body.clone());
+                helpers.push(helper);
+                continue;
+            }
+            let name = symbols.assign(&func.name, QualifiedName::new([TESTS, func.name.as_str()]));
+            let cases = scan.parametrized.get(&func.name);
+            let decorators = scan.patches.get(&func.name).map_or(&[][..], Vec::as_slice);
+            let patches = report_patches(&name, decorators, &func.body, &fakes);
+            let translated = match scan.ignore_reasons.get(&func.name) {
+                Some(reason) => Err(reason.clone()),
+                None => {
+                    let case_names: HashSet<&str> = cases
+                        .and_then(|cases| cases.first())
+                        .map(|case| case.values.iter().map(|(n, _)| n.as_str()).collect())
+                        .unwrap_or_default();
+                    // Mocks from `patch` decorators are passed before fixtures
+                    let (mocks, params) = func
+                        .params
+                        .split_at(decorators.len().min(func.params.len()));
+                    let fixture_params: Vec<HirParam> = params
+                        .iter()
+                        .filter(|p| !case_names.contains(p.name.as_str()))
+                        .cloned()
+                        .collect();
+                    fixture_setup(&fixture_params, &fixtures)
+                        .and_then(|mut body| {
+                            body.extend(normalizer.normalize(func.body.clone())?);
+                            Ok(body)
+                        })
+                        .and_then(|body| {
+                            if patches.is_empty() {
+                                return Ok(body);
+                            }
+                            fakes.apply(mock_bindings(decorators, mocks), body)
+                        })
+                }
+            };
+            patched.extend(patches);
+            match (translated, cases) {
+                (Ok(body), None) => tests.push(test_function(&name, body, &func.docstring)),
+                (Ok(body), Some(cases)) => match rstest_function(func, cases, &body) {
+                    Some(test) => {
+                        rstest_tests.insert(test.name.clone());
+                        tests.push(test);
+                    }
+                    None => tests.extend(expanded_cases(func, cases, &body)),
+                },
+                (Err(reason), _) => ignored.push(IgnoredTest { name, reason }),
+            }
+        }
+
+        let mut helper_classes = Vec::new();
+        for class in &hir.classes {
+            if !scan.test_case_classes.contains(&class.name) {
+                helper_classes.push(class.clone());
+                continue;
+            }
+            let (translated, skipped) =
+                self.flatten_test_case(class, &scan, &mut symbols, &mut fakes, &mut patched);
+            tests.extend(translated);
+            ignored.extend(skipped);
+        }
+
+        let module = HirModule {
+            functions: self
+                .module
+                .functions
+                .iter()
+                .chain(&helpers)
+                .chain(&tests)
+                .cloned()
+                .collect(),
+            imports: hir
+                .imports
+                .into_iter()
+                .filter(|import| !self.is_test_only_import(&import.module))
+                .collect(),
+            type_aliases: hir.type_aliases,
+            protocols: hir.protocols,
+            classes: self
+                .module
+                .classes
+                .iter()
+                .chain(&helper_classes)
+                .cloned()
+                .collect(),
+            constants: hir.constants,
+        };
+        // Seams belong to the transpiled crate; here tests would count as callers.
+        // Helper classes are kept by name, so they stay at the top level.
+        let codegen_options = CodeGenOptions {
+            seams: Vec::new(),
+            class_modules: false,
+            ..options.clone()
+        };
+        let rust_code =
+            rust_gen::generate_rust_file_with_options(&module, type_mapper, &codegen_options)?;
+        let generated = syn::parse_file(&rust_code)?;
+
+        let test_names: Vec<String> = tests.iter().map(|t| t.name.clone()).collect();
+        let mut fallible = fallible_callables(self.module);
+        let variants: Vec<String> = fakes
+            .callers()
+            .filter(|caller| fallible.contains(*caller))
+            .map(rust_gen::seam_gen::variant_name)
+            .collect();
+        fallible.extend(variants);
+        let mut rewriter = TestItemRewriter {
+            fallible,
+            seam_signatures: generated
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    syn::Item::Fn(func) if options.seams.iter().any(|s| func.sig.ident == s) => {
+                        Some((func.sig.ident.to_string(), func.sig.clone()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        };
+        let kept = KeptItems {
+            tests: test_names.iter().cloned().collect(),
+            rstest: rstest_tests.clone(),
+            helpers: helpers.iter().map(|h| h.name.clone()).collect(),
+            types: helper_classes.iter().map(|c| c.name.clone()).collect(),
+            constants: module.constants.iter().map(|c| c.name.clone()).collect(),
+        };
+
+        let crate_ident = syn::Ident::new(&self.crate_name, proc_macro2::Span::call_site());
+        let mut items: Vec<syn::Item> = vec![parse_quote! { use #crate_ident::*; }];
+        let mut dev_dependencies = Vec::new();
+        if !rstest_tests.is_empty() {
+            items.push(parse_quote! { use rstest::rstest; });
+            dev_dependencies.push(("rstest".to_string(), RSTEST_VERSION.to_string()));
+        }
+        for item in generated.items {
+            if let Some(item) = kept.select(item, &mut rewriter) {
+                items.push(item);
+            }
+        }
+        items.extend(ignored.iter().map(ignored_stub));
+
+        let file = quote! {
+            #![allow(unused_imports)]
+            #(#items)*
+        };
+        Ok(TranslatedTests {
+            code: rust_gen::format_rust_code(file.to_string()),
+            tests: test_names,
+            ignored,
+            dev_dependencies,
+            patches: patched,
+        })
+    }
+
+    /// One test per `test*` method, with `setUp` / `tearDown` inlined
+    fn flatten_test_case(
+        &self,
+        class: &HirClass,
+        scan: &TestModuleScan,
+        symbols: &mut SymbolTable,
+        fakes: &mut Fakes,
+        patched: &mut Vec<PatchedTarget>,
+    ) -> (Vec<HirFunction>, Vec<IgnoredTest>) {
+        let hook = |name: &str| class.methods.iter().find(|m| m.name == name);
+        let set_up = hook("setUp").map(|m| m.body.clone()).unwrap_or_default();
+        let tear_down = hook("tearDown").map(|m| m.body.clone()).unwrap_or_default();
+        let class_hooks = ["setUpClass", "tearDownClass"]
+            .into_iter()
+            .find(|name| hook(*name).is_some());
+
+        let normalizer = BodyNormalizer {
+            module_aliases: &scan.module_aliases,
+            renamed: &scan.renamed,
+            localize_self: true,
+        };
+        let prefix = snake_case(&class.name);
+
+        let mut tests = Vec::new();
+        let mut ignored = Vec::new();
+        for method in class.methods.iter().filter(|m| m.name.starts_with("test")) {
+            let name = symbols.assign(
+                &format!("{}_{}", prefix, method.name),
+                QualifiedName::new([TESTS, class.name.as_str(), method.name.as_str()]),
+            );
+            let reason = scan
+                .ignore_reasons
+                .get(&format!("{}.{}", class.name, method.name))
+                .or_else(|| scan.ignore_reasons.get(&class.name))
+                .cloned()
+                .or_else(|| class_hooks.map(|h| format!("`{}` is not supported", h)));
+            let decorators = scan
+                .patches
+                .get(&format!("{}.{}", class.name, method.name))
+                .map_or(&[][..], Vec::as_slice);
+            let patches = report_patches(&name, decorators, &method.body, fakes);
+
+            let translated = match reason {
+                Some(reason) => Err(reason),
+                None => {
+                    let mut body = set_up.clone();
+                    body.extend(method.body.iter().cloned());
+                    body.extend(tear_down.iter().cloned());
+                    normalizer.normalize(body).and_then(|body| {
+                        if patches.is_empty() {
+                            return Ok(body);
+                        }
+                        fakes.apply(mock_bindings(decorators, &method.params), body)
+                    })
+                }
+            };
+            patched.extend(patches);
+            match translated {
+                Ok(body) => tests.push(test_function(&name, body, &method.docstring)),
+                Err(reason) => ignored.push(IgnoredTest { name, reason }),
+            }
+        }
+        (tests, ignored)
+    }
+
+    fn is_module_under_test(&self, module: &str) -> bool {
+        module == self.module_name || module.ends_with(&format!(".{}", self.module_name))
+    }
+
+    /// The module under test is reached through the crate; test frameworks have no Rust side
+    fn is_test_only_import(&self, module: &str) -> bool {
+        self.is_module_under_test(module)
+            || module == "pytest"
+            || module == "unittest"
+            || module.starts_with("unittest.")
+    }
+}
+
+/// What the HIR loses about a test module: decorators, bases and import aliases
+#[derive(Default)]
+struct TestModuleScan {
+    fixtures: HashSet<String>,
+    test_case_classes: HashSet<String>,
+    /// Keyed by function name, `Class.method` or class name
+    ignore_reasons: HashMap<String, String>,
+    /// `@pytest.mark.parametrize` cases by test function
+    parametrized: HashMap<String, Vec<ParamCase>>,
+    /// `@patch` decorators by test function or `Class.method`
+    patches: HashMap<String, Vec<Patch>>,
+    /// Names bound by `import <module under test> [as alias]`
+    module_aliases: HashSet<String>,
+    /// `from <module under test> import name as alias`: alias -> name
+    renamed: HashMap<String, String>,
+}
+
+impl TestModuleScan {
+    fn new(body: &[ast::Stmt], module_name: &str) -> Self {
+        let under_test =
+            |module: &str| module == module_name || module.ends_with(&format!(".{}", module_name));
+        let mut scan = Self::default();
+
+        for stmt in body {
+            match stmt {
+                ast::Stmt::FunctionDef(func) => {
+                    let name = func.name.to_string();
+                    if func.decorator_list.iter().any(|d| {
+                        matches!(
+                            dotted_name(d).as_deref(),
+                            Some("pytest.fixture" | "fixture")
+                        )
+                    }) {
+                        scan.fixtures.insert(name.clone());
+                    }
+                    let reason = ignore_reason(&func.decorator_list);
+                    match parametrize_cases(&func.decorator_list) {
+                        Some(Ok(cases)) => {
+                            scan.parametrized.insert(name.clone(), cases);
+                        }
+                        Some(Err(reason)) => {
+                            scan.ignore_reasons.insert(name.clone(), reason);
+                        }
+                        None => {}
+                    }
+                    scan.record_patches(name.clone(), &func.decorator_list);
+                    // Skip markers explain more than a parametrize or patch problem
+                    if let Some(reason) = reason {
+                        scan.ignore_reasons.insert(name, reason);
+                    }
+                }
+                ast::Stmt::ClassDef(class) if class.bases.iter().any(is_test_case_base) => {
+                    let name = class.name.to_string();
+                    let class_patches = decorator_patches(&class.decorator_list);
+                    if let Some(reason) = ignore_reason(&class.decorator_list) {
+                        scan.ignore_reasons.insert(name.clone(), reason);
+                    } else if !matches!(class_patches, Ok(ref p) if p.is_empty()) {
+                        scan.ignore_reasons.insert(
+                            name.clone(),
+                            "class-level `patch` decorators are not supported".to_string(),
+                        );
+                    }
+                    for stmt in &class.body {
+                        if let ast::Stmt::FunctionDef(method) = stmt {
+                            let key = format!("{}.{}", name, method.name);
+                            scan.record_patches(key.clone(), &method.decorator_list);
+                            if let Some(reason) = ignore_reason(&method.decorator_list) {
+                                scan.ignore_reasons.insert(key, reason);
+                            }
+                        }
+                    }
+                    scan.test_case_classes.insert(name);
+                }
+                ast::Stmt::Import(import) => {
+                    for alias in &import.names {
+                        if alias.name.as_str() == module_name {
+                            let bound = alias.asname.as_ref().unwrap_or(&alias.name);
+                            scan.module_aliases.insert(bound.to_string());
+                        }
+                    }
+                }
+                ast::Stmt::ImportFrom(import) => {
+                    if import
+                        .module
+                        .as_ref()
+                        .is_some_and(|m| under_test(m.as_str()))
+                    {
+                        for alias in &import.names {
+                            if let Some(asname) = &alias.asname {
+                                scan.renamed
+                                    .insert(asname.to_string(), alias.name.to_string());
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        scan
+    }
+
+    fn record_patches(&mut self, key: String, decorators: &[ast::Expr]) {
+        match decorator_patches(decorators) {
+            Ok(patches) if patches.is_empty() => {}
+            Ok(patches) => {
+                self.patches.insert(key, patches);
+            }
+            Err(reason) => {
+                self.ignore_reasons.insert(key, reason);
+            }
+        }
+    }
+}
+
+/// `pytest.mark.skip(reason=...)` -> `Some("pytest.mark.skip")`
+fn dotted_name(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::Name(n) => Some(n.id.to_string()),
+        ast::Expr::Attribute(a) => Some(format!("{}.{}", dotted_name(&a.value)?, a.attr)),
+        ast::Expr::Call(c) => dotted_name(&c.func),
+        _ => None,
+    }
+}
+
+fn is_test_case_base(base: &ast::Expr) -> bool {
+    matches!(
+        dotted_name(base).as_deref(),
+        Some("TestCase" | "unittest.TestCase")
+    )
+}
+
+/// Why a decorated test cannot run as a plain `#[test]`
+fn ignore_reason(decorators: &[ast::Expr]) -> Option<String> {
+    decorators.iter().find_map(|decorator| {
+        let reason = match dotted_name(decorator)?.as_str() {
+            "pytest.mark.skip" | "unittest.skip" | "skip" => {
+                explicit_reason(decorator).unwrap_or_else(|| "skipped in Python".to_string())
+            }
+            "pytest.mark.skipif"
+            | "unittest.skipIf"
+            | "unittest.skipUnless"
+            | "skipIf"
+            | "skipUnless" => "conditionally skipped in Python".to_string(),
+            "pytest.mark.xfail" | "unittest.expectedFailure" | "expectedFailure" => {
+                "expected to fail in Python".to_string()
+            }
+            _ => return None,
+        };
+        Some(reason)
+    })
+}
+
+fn explicit_reason(decorator: &ast::Expr) -> Option<String> {
+    let call = match decorator {
+        ast::Expr::Call(call) => call,
+        _ => return None,
+    };
+    let reason = call
+        .keywords
+        .iter()
+        .find(|k| k.arg.as_ref().map(|a| a.as_str()) == Some("reason"))
+        .map(|k| &k.value)
+        .or_else(|| call.args.first())?;
+    match reason {
+        ast::Expr::Constant(c) => match &c.value {
+            ast::Constant::Str(s) => Some(s.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Pytest collects `test*` functions that are not fixtures
+fn is_test(name: &str, scan: &TestModuleScan) -> bool {
+    name.starts_with("test") && !scan.fixtures.contains(name)
+}
+
+/// Gives test-file helpers a name of their own in the flattened module and
+/// points their callers at it; returns new name -> Python name
+fn rename_helpers(
+    hir: &mut HirModule,
+    scan: &TestModuleScan,
+    symbols: &mut SymbolTable,
+) -> HashMap<String, String> {
+    let mut renamed = HashMap::new();
+    for func in hir.functions.iter_mut().filter(|f| !is_test(&f.name, scan)) {
+        let origin = QualifiedName::new([TESTS, func.name.as_str()]);
+        let name = symbols.assign(&func.name, origin);
+        if name != func.name {
+            renamed.insert(func.name.clone(), name.clone());
+            func.name = name;
+        }
+    }
+    if renamed.is_empty() {
+        return renamed;
+    }
+
+    let bodies = hir.functions.iter_mut().map(|f| &mut f.body).chain(
+        hir.classes
+            .iter_mut()
+            .flat_map(|c| c.methods.iter_mut().map(|m| &mut m.body)),
+    );
+    for body in bodies {
+        rewrite_exprs(body, &mut |expr| {
+            if let HirExpr::Call { func, .. } = expr {
+                if let Some(name) = renamed.get(func.as_str()) {
+                    *func = name.clone();
+                }
+            }
+        });
+    }
+    renamed.into_iter().map(|(old, new)| (new, old)).collect()
+}
+
+/// The Python name of a possibly renamed test-file function
+fn original_name<'a>(original_names: &'a HashMap<String, String>, name: &'a str) -> &'a str {
+    original_names.get(name).map_or(name, String::as_str)
+}
+
+/// `name = fixture(...)` for every parameter of a pytest test
+fn fixture_setup(
+    params: &[HirParam],
+    fixtures: &HashMap<&str, &HirFunction>,
+) -> std::result::Result<Vec<HirStmt>, String> {
+    params
+        .iter()
+        .map(|param| {
+            Ok(HirStmt::Assign {
+                target: AssignTarget::Symbol(param.name.clone()),
+                value: fixture_call(&param.name, fixtures, 0)?,
+                type_annotation: None,
+            })
+        })
+        .collect()
+}
+
+/// Fixtures requesting other fixtures get them as arguments
+fn fixture_call(
+    name: &str,
+    fixtures: &HashMap<&str, &HirFunction>,
+    depth: usize,
+) -> std::result::Result<HirExpr, String> {
+    let fixture = match fixtures.get(name) {
+        Some(fixture) if depth < 16 => fixture,
+        Some(_) => return Err(format!("fixture `{}` depends on itself", name)),
+        None => return Err(format!("fixture `{}` is not supported", name)),
+    };
+    if fixture.properties.is_generator {
+        return Err(format!("yield fixture `{}` is not supported", name));
+    }
+    let args = fixture
+        .params
+        .iter()
+        .map(|p| fixture_call(&p.name, fixtures, depth + 1))
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    Ok(HirExpr::Call {
+        func: fixture.name.clone(),
+        args,
+        kwargs: vec![],
+    })
+}
+
+/// Every patch a test applies, decorators first
+fn report_patches(
+    test: &str,
+    decorators: &[Patch],
+    body: &[HirStmt],
+    fakes: &Fakes,
+) -> Vec<PatchedTarget> {
+    decorators
+        .iter()
+        .cloned()
+        .chain(with_patches(body))
+        .map(|patch| PatchedTarget {
+            test: test.to_string(),
+            function: fakes.function(&patch.target),
+            target: patch.target,
+        })
+        .collect()
+}
+
+/// Decorator patches with the parameters receiving their mocks; the
+/// decorator closest to the function passes the first one
+fn mock_bindings(decorators: &[Patch], params: &[HirParam]) -> Vec<(Patch, Option<String>)> {
+    let mut params = params.iter();
+    let mut bindings: Vec<_> = decorators
+        .iter()
+        .rev()
+        .map(|patch| (patch.clone(), params.next().map(|p| p.name.clone())))
+        .collect();
+    bindings.reverse();
+    bindings
+}
+
+/// One `rstest` function whose cases are carried by marker statements,
+/// `None` when a parameter's type cannot be read off the case values
+fn rstest_function(
+    func: &HirFunction,
+    cases: &[ParamCase],
+    body: &[HirStmt],
+) -> Option<HirFunction> {
+    let params = cases
+        .first()?
+        .values
+        .iter()
+        .map(|(name, _)| Some(HirParam::new(name.clone(), case_type(cases, name)?)))
+        .collect::<Option<SmallVec<_>>>()?;
+
+    let mut idents = CaseIdents::default();
+    let mut stmts: Vec<HirStmt> = cases
+        .iter()
+        .map(|case| {
+            let ident = idents.next(&case.id, None);
+            let mut args = vec![HirExpr::Literal(Literal::String(ident))];
+            args.extend(case.values.iter().map(|(_, value)| value.clone()));
+            HirStmt::Expr(HirExpr::Call {
+                func: RSTEST_CASE.to_string(),
+                args,
+                kwargs: vec![],
+            })
+        })
+        .collect();
+    stmts.extend(body.iter().cloned());
+
+    let mut test = test_function(&func.name, stmts, &func.docstring);
+    test.params = params;
+    Some(test)
+}
+
+/// One `#[test]` per case, binding the case values before the body
+fn expanded_cases(func: &HirFunction, cases: &[ParamCase], body: &[HirStmt]) -> Vec<HirFunction> {
+    let mut idents = CaseIdents::default();
+    cases
+        .iter()
+        .enumerate()
+        .map(|(index, case)| {
+            let ident = idents.next(&case.id, Some(index));
+            let mut stmts: Vec<HirStmt> = case
+                .values
+                .iter()
+                .map(|(name, value)| HirStmt::Assign {
+                    target: AssignTarget::Symbol(name.clone()),
+                    value: value.clone(),
+                    type_annotation: None,
+                })
+                .collect();
+            stmts.extend(body.iter().cloned());
+            test_function(&format!("{}_{}", func.name, ident), stmts, &func.docstring)
+        })
+        .collect()
+}
+
+/// Unique identifiers for the cases of one test
+#[derive(Default)]
+struct CaseIdents {
+    seen: HashSet<String>,
+}
+
+impl CaseIdents {
+    /// The case ID as an identifier. IDs without letters or digits become
+    /// `case_<index>` when an index is given and stay empty (unnamed) otherwise
+    fn next(&mut self, id: &str, index: Option<usize>) -> String {
+        let mut ident = case_ident(id);
+        if ident.is_empty() {
+            match index {
+                Some(index) => ident = format!("case_{}", index),
+                None => return ident,
+            }
+        }
+        let base = ident.clone();
+        let mut n = 2;
+        while !self.seen.insert(ident.clone()) {
+            ident = format!("{}_{}", base, n);
+            n += 1;
+        }
+        ident
+    }
+}
+
+fn test_function(name: &str, body: Vec<HirStmt>, docstring: &Option<String>) -> HirFunction {
+    HirFunction {
+        name: name.to_string(),
+        params: SmallVec::new(),
+        ret_type: Type::None,
+        body,
+        properties: FunctionProperties::default(),
+        annotations: Default::default(),
+        docstring: docstring.clone(),
+    }
+}
+
+/// Rewrites test-framework constructs in a test body into plain statements
+struct BodyNormalizer<'s> {
+    module_aliases: &'s HashSet<String>,
+    renamed: &'s HashMap<String, String>,
+    /// unittest: `self.<attr>` set up in `setUp` becomes a local
+    localize_self: bool,
+}
+
+impl BodyNormalizer<'_> {
+    fn normalize(&self, body: Vec<HirStmt>) -> std::result::Result<Vec<HirStmt>, String> {
+        let mut body = self.normalize_stmts(body)?;
+        rewrite_exprs(&mut body, &mut |expr| self.normalize_expr(expr));
+
+        if self.localize_self {
+            localize_self_targets(&mut body);
+            let mut uses_self = false;
+            rewrite_exprs(&mut body, &mut |expr| {
+                uses_self |= matches!(expr, HirExpr::Var(v) if v == "self");
+            });
+            if uses_self {
+                return Err("uses helper methods on `self`, which are not supported".to_string());
+            }
+        }
+        Ok(body)
+    }
+
+    fn normalize_stmts(&self, body: Vec<HirStmt>) -> std::result::Result<Vec<HirStmt>, String> {
+        let mut out = Vec::with_capacity(body.len());
+        for stmt in body {
+            match stmt {
+                HirStmt::Expr(HirExpr::MethodCall {
+                    object,
+                    method,
+                    args,
+                    kwargs,
+                }) if is_var(&object, "self") && method.starts_with("assert") => {
+                    out.push(unittest_assert(&method, args, kwargs)?);
+                }
+                HirStmt::With {
+                    context,
+                    target,
+                    body,
+                } => match expected_exception(&context) {
+                    Some(exception) => {
+                        if let Some(target) = target {
+                            return Err(format!(
+                                "binding the raised exception (`as {}`) is not supported",
+                                target
+                            ));
+                        }
+                        let mut body = self.normalize_stmts(body)?;
+                        let raising = match body.pop() {
+                            Some(HirStmt::Expr(expr)) => expr,
+                            Some(HirStmt::Assign { value, .. }) => value,
+                            _ => {
+                                return Err(format!(
+                                    "the `{}` block must end with the raising call",
+                                    exception
+                                ))
+                            }
+                        };
+                        out.extend(body);
+                        out.push(expect_raises(raising, exception));
+                    }
+                    None => out.push(HirStmt::With {
+                        context,
+                        target,
+                        body: self.normalize_stmts(body)?,
+                    }),
+                },
+                HirStmt::If {
+                    condition,
+                    then_body,
+                    else_body,
+                } => out.push(HirStmt::If {
+                    condition,
+                    then_body: self.normalize_stmts(then_body)?,
+                    else_body: else_body.map(|b| self.normalize_stmts(b)).transpose()?,
+                }),
+                HirStmt::For { target, iter, body } => out.push(HirStmt::For {
+                    target,
+                    iter,
+                    body: self.normalize_stmts(body)?,
+                }),
+                HirStmt::While { condition, body } => out.push(HirStmt::While {
+                    condition,
+                    body: self.normalize_stmts(body)?,
+                }),
+                other => out.push(other),
+            }
+        }
+        Ok(out)
+    }
+
+    fn normalize_expr(&self, expr: &mut HirExpr) {
+        let replacement = match expr {
+            // mymod.f(...) -> f(...)
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                kwargs,
+            } if matches!(object.as_ref(), HirExpr::Var(v) if self.module_aliases.contains(v)) => {
+                Some(HirExpr::Call {
+                    func: method.clone(),
+                    args: std::mem::take(args),
+                    kwargs: std::mem::take(kwargs),
+                })
+            }
+            HirExpr::Attribute { value, attr } => match value.as_ref() {
+                HirExpr::Var(v) if self.module_aliases.contains(v) => {
+                    Some(HirExpr::Var(attr.clone()))
+                }
+                HirExpr::Var(v) if self.localize_self && v == "self" => {
+                    Some(HirExpr::Var(attr.clone()))
+                }
+                _ => None,
+            },
+            HirExpr::Call { func, .. } => {
+                if let Some(name) = self.renamed.get(func.as_str()) {
+                    *func = name.clone();
+                }
+                None
+            }
+            HirExpr::Var(name) => self.renamed.get(name.as_str()).cloned().map(HirExpr::Var),
+            HirExpr::Binary { op, left, right } if matches!(op, BinOp::Eq | BinOp::NotEq) => {
+                approx_comparison(*op, left, right)
+            }
+            _ => None,
+        };
+        if let Some(replacement) = replacement {
+            *expr = replacement;
+        }
+    }
+}
+
+fn is_var(expr: &HirExpr, name: &str) -> bool {
+    matches!(expr, HirExpr::Var(v) if v == name)
+}
+
+/// `pytest.raises(E)` / `self.assertRaises(E)` used as a context manager
+fn expected_exception(context: &HirExpr) -> Option<String> {
+    match context {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } if (is_var(object, "pytest") && method == "raises")
+            || (is_var(object, "self")
+                && matches!(method.as_str(), "assertRaises" | "assertRaisesRegex")) =>
+        {
+            Some(
+                args.first()
+                    .map_or_else(|| "an exception".to_string(), exception_name),
+            )
+        }
+        _ => None,
+    }
+}
+
+fn exception_name(expr: &HirExpr) -> String {
+    match expr {
+        HirExpr::Var(name) => name.clone(),
+        HirExpr::Attribute { attr, .. } => attr.clone(),
+        _ => "an exception".to_string(),
+    }
+}
+
+fn expect_raises(raising: HirExpr, exception: String) -> HirStmt {
+    HirStmt::Expr(HirExpr::Call {
+        func: EXPECT_RAISES.to_string(),
+        args: vec![raising, HirExpr::Literal(Literal::String(exception))],
+        kwargs: vec![],
+    })
+}
+
+/// `self.assertEqual(a, b)` -> `assert a == b`, and so on
+fn unittest_assert(
+    method: &str,
+    mut args: Vec<HirExpr>,
+    kwargs: Vec<(String, HirExpr)>,
+) -> std::result::Result<HirStmt, String> {
+    if matches!(method, "assertRaises" | "assertRaisesRegex") {
+        // Callable form: self.assertRaises(E, func, *args)
+        let exception = args
+            .first()
+            .map_or_else(|| "an exception".to_string(), exception_name);
+        let call = match args.get(1) {
+            Some(HirExpr::Var(func)) => HirExpr::Call {
+                func: func.clone(),
+                args: args.split_off(2),
+                kwargs,
+            },
+            _ => return Err(format!("`self.{}` needs a function to call", method)),
+        };
+        return Ok(expect_raises(call, exception));
+    }
+
+    let arity = match method {
+        "assertTrue" | "assertFalse" | "assertIsNone" | "assertIsNotNone" => 1,
+        _ => 2,
+    };
+    if args.len() < arity {
+        return Err(format!("`self.{}` is missing arguments", method));
+    }
+    let mut msg = args.drain(arity..).next();
+    let mut tolerance = None;
+    for (name, value) in kwargs {
+        match name.as_str() {
+            "msg" => msg = Some(value),
+            "delta" => tolerance = Some(value),
+            "places" => match value {
+                HirExpr::Literal(Literal::Int(places)) => {
+                    tolerance = Some(HirExpr::Literal(Literal::Float(
+                        0.5 * 10f64.powi(-(places as i32)),
+                    )))
+                }
+                _ => return Err(format!("`self.{}`: `places` must be a literal", method)),
+            },
+            other => return Err(format!("`self.{}`: `{}=` is not supported", method, other)),
+        }
+    }
+
+    let mut args = args.into_iter();
+    let mut next = || args.next().expect("arity checked above");
+    let compare = |op, left, right| HirExpr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    };
+    let test = match method {
+        "assertEqual"
+        | "assertEquals"
+        | "assertListEqual"
+        | "assertTupleEqual"
+        | "assertDictEqual"
+        | "assertSetEqual"
+        | "assertSequenceEqual"
+        | "assertMultiLineEqual" => compare(BinOp::Eq, next(), next()),
+        "assertNotEqual" => compare(BinOp::NotEq, next(), next()),
+        "assertTrue" => next(),
+        "assertFalse" => HirExpr::Unary {
+            op: UnaryOp::Not,
+            operand: Box::new(next()),
+        },
+        "assertIsNone" | "assertIsNotNone" => HirExpr::MethodCall {
+            object: Box::new(next()),
+            method: if method == "assertIsNone" {
+                "is_none"
+            } else {
+                "is_some"
+            }
+            .to_string(),
+            args: vec![],
+            kwargs: vec![],
+        },
+        "assertIn" => compare(BinOp::In, next(), next()),
+        "assertNotIn" => compare(BinOp::NotIn, next(), next()),
+        "assertGreater" => compare(BinOp::Gt, next(), next()),
+        "assertGreaterEqual" => compare(BinOp::GtEq, next(), next()),
+        "assertLess" => compare(BinOp::Lt, next(), next()),
+        "assertLessEqual" => compare(BinOp::LtEq, next(), next()),
+        "assertAlmostEqual" | "assertNotAlmostEqual" => {
+            let difference = HirExpr::Call {
+                func: "abs".to_string(),
+                args: vec![compare(BinOp::Sub, next(), next())],
+                kwargs: vec![],
+            };
+            let tolerance = tolerance.unwrap_or(HirExpr::Literal(Literal::Float(0.5e-7)));
+            if method == "assertAlmostEqual" {
+                compare(BinOp::LtEq, difference, tolerance)
+            } else {
+                compare(BinOp::Gt, difference, tolerance)
+            }
+        }
+        other => return Err(format!("`self.{}` is not supported", other)),
+    };
+    Ok(HirStmt::Assert { test, msg })
+}
+
+/// `x == pytest.approx(y, rel=.., abs=..)` -> `abs(x - y) <= rel * abs(y) + abs`
+fn approx_comparison(op: BinOp, left: &HirExpr, right: &HirExpr) -> Option<HirExpr> {
+    let approx = |expr: &HirExpr| match expr {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            kwargs,
+        } if is_var(object, "pytest") && method == "approx" && args.len() == 1 => {
+            Some((args[0].clone(), kwargs.clone()))
+        }
+        _ => None,
+    };
+    let (actual, (expected, kwargs)) = match (approx(left), approx(right)) {
+        (None, Some(found)) => (left.clone(), found),
+        (Some(found), None) => (right.clone(), found),
+        _ => return None,
+    };
+    let option = |name: &str, default: f64| {
+        kwargs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.clone())
+            .unwrap_or(HirExpr::Literal(Literal::Float(default)))
+    };
+    let abs = |expr: HirExpr| HirExpr::Call {
+        func: "abs".to_string(),
+        args: vec![expr],
+        kwargs: vec![],
+    };
+    let binary = |op, left, right| HirExpr::Binary {
+        op,
+        left: Box::new(left),
+        right: Box::new(right),
+    };
+
+    let tolerance = binary(
+        BinOp::Add,
+        binary(BinOp::Mul, option("rel", 1e-6), abs(expected.clone())),
+        option("abs", 1e-12),
+    );
+    let close = binary(
+        BinOp::LtEq,
+        abs(binary(BinOp::Sub, actual, expected)),
+        tolerance,
+    );
+    Some(match op {
+        BinOp::NotEq => HirExpr::Unary {
+            op: UnaryOp::Not,
+            operand: Box::new(close),
+        },
+        _ => close,
+    })
+}
+
+/// `self.x = ...` -> `x = ...`
+fn localize_self_targets(body: &mut [HirStmt]) {
+    for stmt in body {
+        match stmt {
+            HirStmt::Assign { target, .. } | HirStmt::For { target, .. } => {
+                localize_self_target(target)
+            }
+            _ => {}
+        }
+        match stmt {
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                localize_self_targets(then_body);
+                if let Some(else_body) = else_body {
+                    localize_self_targets(else_body);
+                }
+            }
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => localize_self_targets(body),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                localize_self_targets(body);
+                for handler in handlers {
+                    localize_self_targets(&mut handler.body);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    localize_self_targets(block);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn localize_self_target(target: &mut AssignTarget) {
+    match target {
+        AssignTarget::Attribute { value, attr } if is_var(value, "self") => {
+            *target = AssignTarget::Symbol(attr.clone());
+        }
+        AssignTarget::Tuple(targets) => targets.iter_mut().for_each(localize_self_target),
+        _ => {}
+    }
+}
+
+/// Apply `f` to every expression in `body`, innermost first
+fn rewrite_exprs(body: &mut [HirStmt], f: &mut dyn FnMut(&mut HirExpr)) {
+    for stmt in body {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                rewrite_target(target, f);
+                rewrite_expr(value, f);
+            }
+            HirStmt::Return(Some(expr)) | HirStmt::Expr(expr) => rewrite_expr(expr, f),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                rewrite_expr(condition, f);
+                rewrite_exprs(then_body, f);
+                if let Some(else_body) = else_body {
+                    rewrite_exprs(else_body, f);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                rewrite_expr(condition, f);
+                rewrite_exprs(body, f);
+            }
+            HirStmt::For { target, iter, body } => {
+                rewrite_target(target, f);
+                rewrite_expr(iter, f);
+                rewrite_exprs(body, f);
+            }
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    rewrite_expr(expr, f);
+                }
+            }
+            HirStmt::With { context, body, .. } => {
+                rewrite_expr(context, f);
+                rewrite_exprs(body, f);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                rewrite_exprs(body, f);
+                for handler in handlers {
+                    rewrite_exprs(&mut handler.body, f);
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    rewrite_exprs(block, f);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                rewrite_expr(test, f);
+                if let Some(msg) = msg {
+                    rewrite_expr(msg, f);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                rewrite_expr(subject, f);
+                for case in cases {
+                    if let Some(guard) = &mut case.guard {
+                        rewrite_expr(guard, f);
+                    }
+                    rewrite_exprs(&mut case.body, f);
+                }
+            }
+            HirStmt::Return(None)
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+            | HirStmt::Pass => {}
+        }
+    }
+}
+
+fn rewrite_target(target: &mut AssignTarget, f: &mut dyn FnMut(&mut HirExpr)) {
+    match target {
+        AssignTarget::Symbol(_) => {}
+        AssignTarget::Index { base, index } => {
+            rewrite_expr(base, f);
+            rewrite_expr(index, f);
+        }
+        AssignTarget::Attribute { value, .. } => rewrite_expr(value, f),
+        AssignTarget::Tuple(targets) => {
+            for target in targets {
+                rewrite_target(target, f);
+            }
+        }
+    }
+}
+
+fn rewrite_expr(expr: &mut HirExpr, f: &mut dyn FnMut(&mut HirExpr)) {
+    match expr {
+        HirExpr::Literal(_) | HirExpr::Var(_) => {}
+        HirExpr::Binary { left, right, .. } => {
+            rewrite_expr(left, f);
+            rewrite_expr(right, f);
+        }
+        HirExpr::Unary { operand, .. } => rewrite_expr(operand, f),
+        HirExpr::Call { args, kwargs, .. } => {
+            args.iter_mut().for_each(|a| rewrite_expr(a, f));
+            kwargs.iter_mut().for_each(|(_, v)| rewrite_expr(v, f));
+        }
+        HirExpr::MethodCall {
+            object,
+            args,
+            kwargs,
+            ..
+        } => {
+            rewrite_expr(object, f);
+            args.iter_mut().for_each(|a| rewrite_expr(a, f));
+            kwargs.iter_mut().for_each(|(_, v)| rewrite_expr(v, f));
+        }
+        HirExpr::Index { base, index } => {
+            rewrite_expr(base, f);
+            rewrite_expr(index, f);
+        }
+        HirExpr::Slice {
+            base,
+            start,
+            stop,
+            step,
+        } => {
+            rewrite_expr(base, f);
+            for bound in [start, stop, step].into_iter().flatten() {
+                rewrite_expr(bound, f);
+            }
+        }
+        HirExpr::Attribute { value, .. } => rewrite_expr(value, f),
+        HirExpr::List(items)
+        | HirExpr::Tuple(items)
+        | HirExpr::Set(items)
+        | HirExpr::FrozenSet(items) => items.iter_mut().for_each(|e| rewrite_expr(e, f)),
+        HirExpr::Dict(pairs) => {
+            for (key, value) in pairs {
+                rewrite_expr(key, f);
+                rewrite_expr(value, f);
+            }
+        }
+        HirExpr::Borrow { expr, .. } => rewrite_expr(expr, f),
+        HirExpr::ListComp {
+            element,
+            iter,
+            condition,
+            ..
+        }
+        | HirExpr::SetComp {
+            element,
+            iter,
+            condition,
+            ..
+        } => {
+            rewrite_expr(element, f);
+            rewrite_expr(iter, f);
+            if let Some(condition) = condition {
+                rewrite_expr(condition, f);
+            }
+        }
+        HirExpr::DictComp {
+            key,
+            value,
+            iter,
+            condition,
+            ..
+        } => {
+            rewrite_expr(key, f);
+            rewrite_expr(value, f);
+            rewrite_expr(iter, f);
+            if let Some(condition) = condition {
+                rewrite_expr(condition, f);
+            }
+        }
+        HirExpr::Lambda { body, .. } => rewrite_expr(body, f),
+        HirExpr::Await { value } => rewrite_expr(value, f),
+        HirExpr::FString { parts } => {
+            for part in parts {
+                if let crate::hir::FStringPart::Expr(expr) = part {
+                    rewrite_expr(expr, f);
+                }
+            }
+        }
+        HirExpr::Yield { value } => {
+            if let Some(value) = value {
+                rewrite_expr(value, f);
+            }
+        }
+        HirExpr::IfExpr { test, body, orelse } => {
+            rewrite_expr(test, f);
+            rewrite_expr(body, f);
+            rewrite_expr(orelse, f);
+        }
+        HirExpr::SortByKey {
+            iterable, key_body, ..
+        } => {
+            rewrite_expr(iterable, f);
+            rewrite_expr(key_body, f);
+        }
+        HirExpr::GeneratorExp {
+            element,
+            generators,
+        } => {
+            rewrite_expr(element, f);
+            for generator in generators {
+                rewrite_expr(&mut generator.iter, f);
+                generator
+                    .conditions
+                    .iter_mut()
+                    .for_each(|c| rewrite_expr(c, f));
+            }
+        }
+    }
+    f(expr);
+}
+
+/// Functions and constructors of the module under test that return `Result`
+fn fallible_callables(module: &HirModule) -> HashSet<String> {
+    let functions = module
+        .functions
+        .iter()
+        .filter(|f| f.properties.can_fail)
+        .filter(|f| !(f.properties.is_context_manager && f.properties.is_generator))
+        .map(|f| f.name.clone());
+    let constructors = module
+        .classes
+        .iter()
+        .filter(|c| crate::direct_rules::post_init_error_type(c).is_some())
+        .map(|c| c.name.clone());
+    functions.chain(constructors).collect()
+}
+
+/// Items of the generated file that belong to the test file
+struct KeptItems {
+    tests: HashSet<String>,
+    /// Tests carrying `rstest` case markers
+    rstest: HashSet<String>,
+    helpers: HashSet<String>,
+    types: HashSet<String>,
+    constants: HashSet<String>,
+}
+
+impl KeptItems {
+    fn select(&self, item: syn::Item, rewriter: &mut TestItemRewriter) -> Option<syn::Item> {
+        match item {
+            syn::Item::Use(_) => Some(item),
+            syn::Item::Fn(mut func) => {
+                let name = func.sig.ident.to_string();
+                if self.tests.contains(&name) {
+                    if self.rstest.contains(&name) {
+                        rstest_cases(&mut func);
+                    } else {
+                        func.attrs.insert(0, parse_quote! { #[test] });
+                    }
+                    rewriter.visit_block_mut(&mut func.block);
+                    func.vis = syn::Visibility::Inherited;
+                    Some(syn::Item::Fn(func))
+                } else if self.helpers.contains(&name) {
+                    rewriter.visit_block_mut(&mut func.block);
+                    Some(syn::Item::Fn(func))
+                } else {
+                    None
+                }
+            }
+            syn::Item::Struct(ref s) if self.types.contains(&s.ident.to_string()) => Some(item),
+            syn::Item::Enum(ref e) if self.types.contains(&e.ident.to_string()) => Some(item),
+            syn::Item::Impl(ref imp) if self.is_helper_type(&imp.self_ty) => Some(item),
+            syn::Item::Const(ref c) if self.constants.contains(&c.ident.to_string()) => Some(item),
+            syn::Item::Static(ref s) if self.constants.contains(&s.ident.to_string()) => Some(item),
+            _ => None,
+        }
+    }
+}
+
+impl KeptItems {
+    fn is_helper_type(&self, ty: &syn::Type) -> bool {
+        match ty {
+            syn::Type::Path(p) => p
+                .path
+                .get_ident()
+                .is_some_and(|ident| self.types.contains(&ident.to_string())),
+            _ => false,
+        }
+    }
+}
+
+/// Moves `__depyler_rstest_case__(id, values...)` statements into
+/// `#[case::id(values...)]` attributes and marks the parameters `#[case]`
+fn rstest_cases(func: &mut syn::ItemFn) {
+    let mut cases: Vec<syn::Attribute> = Vec::new();
+    let param_types: Vec<syn::Type> = func
+        .sig
+        .inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat) => Some((*pat.ty).clone()),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect();
+
+    func.block.stmts.retain(|stmt| {
+        let call = match stmt {
+            syn::Stmt::Expr(syn::Expr::Call(call), _) => call,
+            _ => return true,
+        };
+        if !matches!(call.func.as_ref(), syn::Expr::Path(p) if p.path.is_ident(RSTEST_CASE)) {
+            return true;
+        }
+        let mut args = call.args.iter();
+        let id = args.next().and_then(string_literal).unwrap_or_default();
+        // Case values are written as owned literals; borrowed parameters take a reference
+        let values = args.zip(&param_types).map(|(value, ty)| match ty {
+            syn::Type::Reference(_) => match string_literal(value) {
+                Some(text) => quote! { #text },
+                None => quote! { &#value },
+            },
+            _ => value.to_token_stream(),
+        });
+        cases.push(if id.is_empty() {
+            parse_quote! { #[case(#(#values),*)] }
+        } else {
+            // Identifiers may start with a digit (`1_2`); rstest needs an identifier
+            let name = if id.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("_{}", id)
+            } else {
+                id
+            };
+            let name = syn::Ident::new(&name, proc_macro2::Span::call_site());
+            parse_quote! { #[case::#name(#(#values),*)] }
+        });
+        false
+    });
+
+    for arg in func.sig.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat) = arg {
+            pat.attrs.push(parse_quote! { #[case] });
+        }
+    }
+    let mut attrs: Vec<syn::Attribute> = vec![parse_quote! { #[rstest] }];
+    attrs.extend(cases);
+    attrs.append(&mut func.attrs);
+    func.attrs = attrs;
+}
+
+fn ignored_stub(test: &IgnoredTest) -> syn::Item {
+    let ident = syn::Ident::new(&test.name, proc_macro2::Span::call_site());
+    let reason = &test.reason;
+    parse_quote! {
+        #[test]
+        #[ignore = #reason]
+        fn #ident() {}
+    }
+}
+
+/// Finishes generated test bodies
+///
+/// - unwraps calls into the module under test that return `Result`
+/// - turns `__depyler_expect_raises__(call, "E")` into an assertion that the
+///   call returns `Err`, or panics when the callee is infallible in Rust
+/// - turns `assert!(a == b)` into `assert_eq!(a, b)` for better failure output
+/// - defines the fakes for patched functions and passes them to seam callers
+struct TestItemRewriter {
+    fallible: HashSet<String>,
+    /// Generated signatures of the seam targets
+    seam_signatures: HashMap<String, syn::Signature>,
+}
+
+impl TestItemRewriter {
+    fn is_fallible_call(&self, expr: &syn::Expr) -> bool {
+        let call = match expr {
+            syn::Expr::Call(call) => call,
+            _ => return false,
+        };
+        let path = match call.func.as_ref() {
+            syn::Expr::Path(p) => &p.path,
+            _ => return false,
+        };
+        let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+        match segments.as_slice() {
+            [name] => self.fallible.contains(name),
+            [class, new] => new == "new" && self.fallible.contains(class),
+            _ => false,
+        }
+    }
+
+    fn expect_raises(&mut self, expr: &syn::Expr) -> Option<syn::Expr> {
+        let call = match expr {
+            syn::Expr::Call(call) => call,
+            _ => return None,
+        };
+        if !matches!(call.func.as_ref(), syn::Expr::Path(p) if p.path.is_ident(EXPECT_RAISES)) {
+            return None;
+        }
+
+        let mut raising = without_unwrap(call.args.first()?.clone());
+        if let Some(call) = seam_call(&raising) {
+            raising = call;
+        }
+        let exception = call
+            .args
+            .iter()
+            .nth(1)
+            .and_then(string_literal)
+            .unwrap_or_else(|| "an exception".to_string());
+        let message = format!("expected {} to be raised", exception);
+
+        if self.is_fallible_call(&raising) {
+            if let syn::Expr::Call(inner) = &mut raising {
+                inner
+                    .args
+                    .iter_mut()
+                    .for_each(|arg| self.visit_expr_mut(arg));
+            }
+            Some(parse_quote! { assert!(#raising.is_err(), #message) })
+        } else {
+            self.visit_expr_mut(&mut raising);
+            Some(parse_quote! {
+                assert!(
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        let _ = #raising;
+                    }))
+                    .is_err(),
+                    #message
+                )
+            })
+        }
+    }
+}
+
+/// The call itself, not the `.unwrap()` / `?` codegen put around it
+fn without_unwrap(expr: syn::Expr) -> syn::Expr {
+    match expr {
+        syn::Expr::MethodCall(m) if m.method == "unwrap" && m.args.is_empty() => {
+            without_unwrap(*m.receiver)
+        }
+        syn::Expr::Try(t) => without_unwrap(*t.expr),
+        syn::Expr::Paren(p) => without_unwrap(*p.expr),
+        other => other,
+    }
+}
+
+/// `"text"` or `"text".to_string()`
+fn string_literal(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        }) => Some(s.value()),
+        syn::Expr::MethodCall(m) if m.method == "to_string" => string_literal(&m.receiver),
+        _ => None,
+    }
+}
+
+fn unparenthesized(expr: &syn::Expr) -> &syn::Expr {
+    match expr {
+        syn::Expr::Paren(p) => unparenthesized(&p.expr),
+        other => other,
+    }
+}
+
+impl VisitMut for TestItemRewriter {
+    fn visit_block_mut(&mut self, block: &mut syn::Block) {
+        let mut stmts = Vec::with_capacity(block.stmts.len());
+        for stmt in std::mem::take(&mut block.stmts) {
+            match fake_items(&stmt, &self.seam_signatures) {
+                Some(items) => stmts.extend(items),
+                None => stmts.push(stmt),
+            }
+        }
+        block.stmts = stmts;
+        visit_mut::visit_block_mut(self, block);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if let Some(assertion) = self.expect_raises(expr) {
+            *expr = assertion;
+            return;
+        }
+        if let Some(call) = seam_call(expr) {
+            *expr = call;
+        }
+        // `let x = f().unwrap();` is already unwrapped by the assignment codegen
+        if let syn::Expr::MethodCall(m) = expr {
+            if m.method == "unwrap" && self.is_fallible_call(&m.receiver) {
+                if let syn::Expr::Call(call) = m.receiver.as_mut() {
+                    call.args
+                        .iter_mut()
+                        .for_each(|arg| self.visit_expr_mut(arg));
+                }
+                return;
+            }
+        }
+        visit_mut::visit_expr_mut(self, expr);
+        if self.is_fallible_call(expr) {
+            *expr = parse_quote! { #expr.unwrap() };
+        }
+    }
+
+    fn visit_macro_mut(&mut self, mac: &mut syn::Macro) {
+        let name = match mac.path.get_ident() {
+            Some(ident) => ident.to_string(),
+            None => return,
+        };
+        if !matches!(name.as_str(), "assert" | "assert_eq" | "assert_ne") {
+            return;
+        }
+        let mut args =
+            match mac.parse_body_with(Punctuated::<syn::Expr, Token![,]>::parse_terminated) {
+                Ok(args) => args,
+                Err(_) => return,
+            };
+        args.iter_mut().for_each(|arg| self.visit_expr_mut(arg));
+
+        if name == "assert" {
+            if let Some(syn::Expr::Binary(binary)) = args.first().map(unparenthesized) {
+                let path: Option<syn::Path> = match binary.op {
+                    syn::BinOp::Eq(_) => Some(parse_quote!(assert_eq)),
+                    syn::BinOp::Ne(_) => Some(parse_quote!(assert_ne)),
+                    _ => None,
+                };
+                if let Some(path) = path {
+                    let (left, right) = (&binary.left, &binary.right);
+                    let rest = args.iter().skip(1);
+                    mac.tokens = quote! { #left, #right #(, #rest)* };
+                    mac.path = path;
+                    return;
+                }
+            }
+        }
+        mac.tokens = args.to_token_stream();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("TestParser"), "test_parser");
+        assert_eq!(snake_case("HTTPClientTests"), "http_client_tests");
+        assert_eq!(snake_case("Test2Things"), "test2_things");
+    }
+
+    #[test]
+    fn test_unittest_assert_mapping() {
+        let x = || HirExpr::Var("x".to_string());
+        let one = || HirExpr::Literal(Literal::Int(1));
+
+        let stmt = unittest_assert("assertEqual", vec![x(), one()], vec![]).unwrap();
+        assert_eq!(
+            stmt,
+            HirStmt::Assert {
+                test: HirExpr::Binary {
+                    op: BinOp::Eq,
+                    left: Box::new(x()),
+                    right: Box::new(one()),
+                },
+                msg: None,
+            }
+        );
+
+        let stmt = unittest_assert("assertIsNone", vec![x()], vec![]).unwrap();
+        assert!(matches!(
+            stmt,
+            HirStmt::Assert { test: HirExpr::MethodCall { ref method, .. }, .. } if method == "is_none"
+        ));
+
+        let err = unittest_assert("assertWarns", vec![x(), one()], vec![]).unwrap_err();
+        assert_eq!(err, "`self.assertWarns` is not supported");
+    }
+
+    #[test]
+    fn test_ignore_reasons_from_markers() {
+        let module = crate::DepylerPipeline::new()
+            .parse_python(
+                "@pytest.mark.skip(reason=\"flaky\")\ndef test_a():\n    pass\n\n@pytest.mark.xfail\ndef test_b():\n    pass\n",
+            )
+            .unwrap();
+        let body = match &module {
+            ast::Mod::Module(m) => &m.body,
+            _ => unreachable!(),
+        };
+        let scan = TestModuleScan::new(body, "mymod");
+        assert_eq!(scan.ignore_reasons["test_a"], "flaky");
+        assert_eq!(scan.ignore_reasons["test_b"], "expected to fail in Python");
+    }
+}
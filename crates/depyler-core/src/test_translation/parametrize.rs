@@ -0,0 +1,302 @@
+//! `@pytest.mark.parametrize` cases
+//!
+//! Stacked decorators are combined the way pytest does it: the decorator
+//! closest to the function varies slowest, and case IDs are joined with `-`
+//! in that order (`test_f[2-0]`). IDs come from `ids=`, `pytest.param(id=)`
+//! or pytest's defaults: the value itself for scalars, `<argname><index>`
+//! for anything else.
+
+use super::dotted_name;
+use crate::hir::{HirExpr, Literal, Type, UnaryOp};
+use rustpython_ast::{self as ast};
+
+/// One set of arguments for a parametrized test
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct ParamCase {
+    pub id: String,
+    pub values: Vec<(String, HirExpr)>,
+}
+
+/// Cases of a parametrized test, `None` if it has no `parametrize` decorator
+pub(super) fn parametrize_cases(
+    decorators: &[ast::Expr],
+) -> Option<Result<Vec<ParamCase>, String>> {
+    let decorators: Vec<&ast::Expr> = decorators
+        .iter()
+        .filter(|d| dotted_name(d).as_deref() == Some("pytest.mark.parametrize"))
+        .collect();
+    if decorators.is_empty() {
+        return None;
+    }
+
+    let mut cases = vec![ParamCase {
+        id: String::new(),
+        values: vec![],
+    }];
+    for decorator in decorators.into_iter().rev() {
+        let call = match decorator {
+            ast::Expr::Call(call) => call,
+            _ => return Some(Err("`pytest.mark.parametrize` needs arguments".to_string())),
+        };
+        let inner = match decorator_cases(call) {
+            Ok(inner) => inner,
+            Err(reason) => return Some(Err(reason)),
+        };
+        cases = cases
+            .iter()
+            .flat_map(|outer| {
+                inner.iter().map(move |case| ParamCase {
+                    id: if outer.id.is_empty() {
+                        case.id.clone()
+                    } else {
+                        format!("{}-{}", outer.id, case.id)
+                    },
+                    values: outer.values.iter().chain(&case.values).cloned().collect(),
+                })
+            })
+            .collect();
+    }
+    Some(Ok(cases))
+}
+
+fn decorator_cases(call: &ast::ExprCall) -> Result<Vec<ParamCase>, String> {
+    let keyword = |name: &str| {
+        call.keywords
+            .iter()
+            .find(|k| k.arg.as_ref().map(|a| a.as_str()) == Some(name))
+            .map(|k| &k.value)
+    };
+    if let Some(other) = call.keywords.iter().find_map(|k| {
+        k.arg
+            .as_ref()
+            .map(|a| a.as_str())
+            .filter(|a| !matches!(*a, "argnames" | "argvalues" | "ids"))
+    }) {
+        return Err(format!(
+            "`pytest.mark.parametrize({}=...)` is not supported",
+            other
+        ));
+    }
+
+    let names = match call.args.first().or_else(|| keyword("argnames")) {
+        Some(names) => argnames(names)?,
+        None => return Err("`pytest.mark.parametrize` is missing argument names".to_string()),
+    };
+    let rows = match call.args.get(1).or_else(|| keyword("argvalues")) {
+        Some(ast::Expr::List(list)) => &list.elts,
+        Some(ast::Expr::Tuple(tuple)) => &tuple.elts,
+        _ => return Err("`pytest.mark.parametrize` values must be a literal list".to_string()),
+    };
+    let ids = match keyword("ids") {
+        None => vec![None; rows.len()],
+        Some(ast::Expr::List(ast::ExprList { elts, .. }))
+        | Some(ast::Expr::Tuple(ast::ExprTuple { elts, .. }))
+            if elts.len() == rows.len() =>
+        {
+            elts.iter().map(string_constant).collect()
+        }
+        Some(_) => return Err("`ids=` must be a literal list with one entry per case".to_string()),
+    };
+
+    rows.iter()
+        .zip(ids)
+        .enumerate()
+        .map(|(index, (row, id))| {
+            let (exprs, explicit_id) = row_values(row, names.len(), index)?;
+            if exprs.len() != names.len() {
+                return Err(format!(
+                    "case {} has {} values for {} names",
+                    index,
+                    exprs.len(),
+                    names.len()
+                ));
+            }
+            let values = names
+                .iter()
+                .zip(exprs)
+                .map(|(name, expr)| {
+                    crate::ast_bridge::convert_expr(expr.clone())
+                        .map(|value| (name.clone(), value))
+                        .map_err(|e| format!("case {}: {}", index, e))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let id = explicit_id
+                .or(id)
+                .unwrap_or_else(|| default_id(&values, index));
+            Ok(ParamCase { id, values })
+        })
+        .collect()
+}
+
+/// `"a, b"` or `["a", "b"]`
+fn argnames(expr: &ast::Expr) -> Result<Vec<String>, String> {
+    let names: Vec<String> = match expr {
+        ast::Expr::List(ast::ExprList { elts, .. })
+        | ast::Expr::Tuple(ast::ExprTuple { elts, .. }) => {
+            elts.iter().filter_map(string_constant).collect()
+        }
+        other => match string_constant(other) {
+            Some(names) => names.split(',').map(|n| n.trim().to_string()).collect(),
+            None => vec![],
+        },
+    };
+    if names.is_empty() || names.iter().any(|n| n.is_empty()) {
+        return Err("`pytest.mark.parametrize` argument names must be literal strings".to_string());
+    }
+    Ok(names)
+}
+
+/// The values of one case and its `pytest.param(..., id=...)` ID
+fn row_values(
+    row: &ast::Expr,
+    arity: usize,
+    index: usize,
+) -> Result<(Vec<&ast::Expr>, Option<String>), String> {
+    if let ast::Expr::Call(call) = row {
+        if dotted_name(&call.func).as_deref() == Some("pytest.param") {
+            let mut id = None;
+            for keyword in &call.keywords {
+                match keyword.arg.as_ref().map(|a| a.as_str()) {
+                    Some("id") => id = string_constant(&keyword.value),
+                    Some("marks") => {
+                        return Err("`pytest.param(marks=...)` is not supported".to_string())
+                    }
+                    _ => return Err("unsupported `pytest.param` argument".to_string()),
+                }
+            }
+            return Ok((call.args.iter().collect(), id));
+        }
+    }
+    let values = match row {
+        _ if arity == 1 => vec![row],
+        ast::Expr::Tuple(ast::ExprTuple { elts, .. })
+        | ast::Expr::List(ast::ExprList { elts, .. }) => elts.iter().collect(),
+        _ => return Err(format!("case {} must be a tuple of values", index)),
+    };
+    Ok((values, None))
+}
+
+fn string_constant(expr: &ast::Expr) -> Option<String> {
+    match expr {
+        ast::Expr::Constant(c) => match &c.value {
+            ast::Constant::Str(s) => Some(s.to_string()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// pytest's ID for a case without an explicit one
+fn default_id(values: &[(String, HirExpr)], index: usize) -> String {
+    values
+        .iter()
+        .map(|(name, value)| match value {
+            HirExpr::Literal(Literal::Int(i)) => i.to_string(),
+            HirExpr::Literal(Literal::Float(f)) => f.to_string(),
+            HirExpr::Literal(Literal::String(s)) => s.clone(),
+            HirExpr::Literal(Literal::Bool(true)) => "True".to_string(),
+            HirExpr::Literal(Literal::Bool(false)) => "False".to_string(),
+            HirExpr::Literal(Literal::None) => "None".to_string(),
+            _ => format!("{}{}", name, index),
+        })
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// Rust identifier for a case ID: `"empty list"` -> `empty_list`, `"1-2"` -> `1_2`
+///
+/// May start with a digit; callers that need a standalone identifier prefix it.
+pub(super) fn case_ident(id: &str) -> String {
+    let mut ident = String::new();
+    for ch in id.chars() {
+        if ch.is_ascii_alphanumeric() {
+            ident.extend(ch.to_lowercase());
+        } else if !ident.is_empty() && !ident.ends_with('_') {
+            ident.push('_');
+        }
+    }
+    ident.trim_end_matches('_').to_string()
+}
+
+/// The parameter type when every case passes a literal of the same type
+pub(super) fn case_type(cases: &[ParamCase], name: &str) -> Option<Type> {
+    let mut types = cases.iter().map(|case| {
+        case.values
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, value)| literal_type(value))
+    });
+    let first = types.next()??;
+    types.all(|ty| ty.as_ref() == Some(&first)).then_some(first)
+}
+
+fn literal_type(expr: &HirExpr) -> Option<Type> {
+    match expr {
+        HirExpr::Literal(Literal::Int(_)) => Some(Type::Int),
+        HirExpr::Literal(Literal::Float(_)) => Some(Type::Float),
+        HirExpr::Literal(Literal::String(_)) => Some(Type::String),
+        HirExpr::Literal(Literal::Bool(_)) => Some(Type::Bool),
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => literal_type(operand).filter(|ty| matches!(ty, Type::Int | Type::Float)),
+        HirExpr::List(items) => {
+            let first = literal_type(items.first()?)?;
+            items
+                .iter()
+                .all(|item| literal_type(item).as_ref() == Some(&first))
+                .then(|| Type::List(Box::new(first)))
+        }
+        HirExpr::Tuple(items) => items
+            .iter()
+            .map(literal_type)
+            .collect::<Option<Vec<_>>>()
+            .map(Type::Tuple),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decorators(source: &str) -> Vec<ast::Expr> {
+        let module = crate::DepylerPipeline::new().parse_python(source).unwrap();
+        match module {
+            ast::Mod::Module(m) => match m.body.into_iter().next() {
+                Some(ast::Stmt::FunctionDef(f)) => f.decorator_list,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_stacked_decorators_follow_pytest_order() {
+        let decorators = decorators(
+            "@pytest.mark.parametrize(\"x\", [0, 1])\n@pytest.mark.parametrize(\"y\", [2, 3])\ndef test_f(x, y):\n    pass\n",
+        );
+        let cases = parametrize_cases(&decorators).unwrap().unwrap();
+        let ids: Vec<_> = cases.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["2-0", "2-1", "3-0", "3-1"]);
+        assert_eq!(case_type(&cases, "x"), Some(Type::Int));
+    }
+
+    #[test]
+    fn test_explicit_ids_and_params() {
+        let decorators = decorators(
+            "@pytest.mark.parametrize(\"a,b\", [(1, \"x\"), pytest.param(2, \"y\", id=\"second\")], ids=[\"first\", None])\ndef test_f(a, b):\n    pass\n",
+        );
+        let cases = parametrize_cases(&decorators).unwrap().unwrap();
+        let ids: Vec<_> = cases.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["first", "second"]);
+        assert_eq!(case_type(&cases, "b"), Some(Type::String));
+    }
+
+    #[test]
+    fn test_case_ident() {
+        assert_eq!(case_ident("Empty list"), "empty_list");
+        assert_eq!(case_ident("1-2"), "1_2");
+        assert_eq!(case_ident("--"), "");
+    }
+}
@@ -0,0 +1,686 @@
+//! `unittest.mock.patch` targets and fakes
+//!
+//! Every patch is reported. A patched function of the module under test
+//! that was transpiled with a seam (`--seam <function>`) is replaced by a
+//! fake: a unit struct implementing the seam trait whose method returns the
+//! mock's `return_value`, or `Default::default()` without one. Calls in the
+//! patched scope to functions that use the seam go through their
+//! `<name>_with` variant with the fake in place of the default.
+//!
+//! Mocks are only configured through `return_value`; assertions on calls,
+//! `side_effect` and replacement objects leave the test ignored.
+
+use super::{is_var, rewrite_expr, rewrite_exprs, string_literal, without_unwrap};
+use crate::ast_bridge::calls_function;
+use crate::hir::{AssignTarget, HirExpr, HirFunction, HirStmt, Literal, UnaryOp};
+use crate::rust_gen::seam_gen::{
+    default_impl_name, pascal_case, seam_callers, seam_method_signature, trait_name, variant_name,
+};
+use quote::{format_ident, quote};
+use rustpython_ast::{self as ast};
+use std::collections::{HashMap, HashSet};
+use syn::parse_quote;
+
+/// Statement defining a fake (`name, function[, return value]`) until the Rust pass
+const FAKE: &str = "__depyler_fake__";
+
+/// Call through a seam (`"Fake,Default..."`, call) until the Rust pass
+const SEAM_CALL: &str = "__depyler_seam_call__";
+
+/// One `patch(...)` in a test
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Patch {
+    /// Patched name as written: `"calc.fetch_rate"`
+    pub target: String,
+    /// `return_value=` given to `patch`
+    pub return_value: Option<HirExpr>,
+    /// Why no fake can stand in for this patch, seam or not
+    pub unsupported: Option<String>,
+}
+
+/// Patches applied by the decorators of a test, outermost first
+pub(super) fn decorator_patches(decorators: &[ast::Expr]) -> Result<Vec<Patch>, String> {
+    decorators
+        .iter()
+        .filter(|d| {
+            matches!(
+                super::dotted_name(d).as_deref(),
+                Some(
+                    "patch"
+                        | "mock.patch"
+                        | "unittest.mock.patch"
+                        | "patch.object"
+                        | "mock.patch.object"
+                        | "unittest.mock.patch.object"
+                )
+            )
+        })
+        .map(|decorator| {
+            crate::ast_bridge::convert_expr(decorator.clone())
+                .ok()
+                .as_ref()
+                .and_then(patch_call)
+                .ok_or_else(|| "unsupported `patch` decorator".to_string())
+        })
+        .collect()
+}
+
+/// `patch(...)`, `mock.patch(...)`, `unittest.mock.patch(...)` or `patch.object(...)`
+pub(super) fn patch_call(expr: &HirExpr) -> Option<Patch> {
+    let (args, kwargs, object_form) = match expr {
+        HirExpr::Call { func, args, kwargs } if func == "patch" => (args, kwargs, false),
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            kwargs,
+        } => match (method.as_str(), dotted(object)?.as_str()) {
+            ("patch", "mock" | "unittest.mock") => (args, kwargs, false),
+            ("object", "patch" | "mock.patch" | "unittest.mock.patch") => (args, kwargs, true),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let keyword = |name: &str| kwargs.iter().find(|(k, _)| k == name).map(|(_, v)| v);
+
+    let (target, positional) = if object_form {
+        let owner = dotted(args.first()?)?;
+        let attribute = string_value(args.get(1).or_else(|| keyword("attribute"))?)?;
+        (format!("{}.{}", owner, attribute), 2)
+    } else {
+        (
+            string_value(args.first().or_else(|| keyword("target"))?)?,
+            1,
+        )
+    };
+    let unsupported = if args.len() > positional || keyword("new").is_some() {
+        Some(format!(
+            "replacing `{}` with a given object is not supported",
+            target
+        ))
+    } else {
+        kwargs
+            .iter()
+            .map(|(k, _)| k.as_str())
+            .find(|k| {
+                !matches!(
+                    *k,
+                    "target" | "attribute" | "return_value" | "autospec" | "spec" | "spec_set"
+                )
+            })
+            .map(|k| format!("`patch({}=...)` is not supported", k))
+    };
+    Some(Patch {
+        target,
+        return_value: keyword("return_value").cloned(),
+        unsupported,
+    })
+}
+
+/// Patches applied by `with patch(...)` blocks anywhere in `body`
+pub(super) fn with_patches(body: &[HirStmt]) -> Vec<Patch> {
+    let mut patches = Vec::new();
+    for stmt in body {
+        match stmt {
+            HirStmt::With { context, body, .. } => {
+                patches.extend(patch_call(context));
+                patches.extend(with_patches(body));
+            }
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                patches.extend(with_patches(then_body));
+                if let Some(else_body) = else_body {
+                    patches.extend(with_patches(else_body));
+                }
+            }
+            HirStmt::While { body, .. } | HirStmt::For { body, .. } => {
+                patches.extend(with_patches(body))
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                patches.extend(with_patches(body));
+                for handler in handlers {
+                    patches.extend(with_patches(&handler.body));
+                }
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    patches.extend(with_patches(block));
+                }
+            }
+            _ => {}
+        }
+    }
+    patches
+}
+
+/// `a.b.c` for a variable or attribute chain
+fn dotted(expr: &HirExpr) -> Option<String> {
+    match expr {
+        HirExpr::Var(name) => Some(name.clone()),
+        HirExpr::Attribute { value, attr } => Some(format!("{}.{}", dotted(value)?, attr)),
+        _ => None,
+    }
+}
+
+fn string_value(expr: &HirExpr) -> Option<String> {
+    match expr {
+        HirExpr::Literal(Literal::String(s)) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+/// Values a fake can return without access to the test's locals
+fn is_constant(expr: &HirExpr) -> bool {
+    match expr {
+        HirExpr::Literal(_) => true,
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => is_constant(operand),
+        HirExpr::List(items) | HirExpr::Tuple(items) | HirExpr::Set(items) => {
+            items.iter().all(is_constant)
+        }
+        HirExpr::Dict(pairs) => pairs.iter().all(|(k, v)| is_constant(k) && is_constant(v)),
+        _ => false,
+    }
+}
+
+/// Replaces patched functions with fakes in test bodies
+pub(super) struct Fakes<'a> {
+    module_name: &'a str,
+    module_aliases: &'a HashSet<String>,
+    functions: &'a [HirFunction],
+    seams: &'a [String],
+    /// Functions using seams directly, with the seams they take
+    callers: Vec<(String, Vec<String>)>,
+    /// `(function, seam)`: the function reaches the seam only through other functions
+    indirect: Vec<(String, String)>,
+    /// Fake struct names taken in the current test
+    names: HashSet<String>,
+}
+
+impl<'a> Fakes<'a> {
+    pub fn new(
+        functions: &'a [HirFunction],
+        seams: &'a [String],
+        module_name: &'a str,
+        module_aliases: &'a HashSet<String>,
+    ) -> Self {
+        let mut indirect = Vec::new();
+        for func in functions {
+            for seam in seams {
+                if func.name != *seam
+                    && !calls_function(&func.body, seam)
+                    && reaches(functions, &func.name, seam, &mut HashSet::new())
+                {
+                    indirect.push((func.name.clone(), seam.clone()));
+                }
+            }
+        }
+        Self {
+            module_name,
+            module_aliases,
+            functions,
+            seams,
+            callers: seam_callers(functions, seams),
+            indirect,
+            names: HashSet::new(),
+        }
+    }
+
+    /// Functions with a `<name>_with` variant
+    pub fn callers(&self) -> impl Iterator<Item = &str> {
+        self.callers.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The module function a patch target names, if any
+    pub fn function(&self, target: &str) -> Option<String> {
+        let (owner, name) = target.rsplit_once('.')?;
+        let under_test = owner == self.module_name
+            || owner.ends_with(&format!(".{}", self.module_name))
+            || self.module_aliases.contains(owner);
+        (under_test && self.functions.iter().any(|f| f.name == name)).then(|| name.to_string())
+    }
+
+    /// Fakes for the decorator patches (each with the parameter receiving
+    /// its mock) over the whole body, and for `with patch(...)` blocks
+    /// within them
+    pub fn apply(
+        &mut self,
+        decorators: Vec<(Patch, Option<String>)>,
+        body: Vec<HirStmt>,
+    ) -> Result<Vec<HirStmt>, String> {
+        self.names.clear();
+        let (mut out, body, active) = self.activate(decorators, body, &HashMap::new())?;
+        out.extend(self.scope(body, &active)?);
+        Ok(out)
+    }
+
+    /// Fake definitions for `patches`, the scope without the mocks'
+    /// `return_value` assignments, and the fakes active in it
+    #[allow(clippy::type_complexity)]
+    fn activate(
+        &mut self,
+        patches: Vec<(Patch, Option<String>)>,
+        mut body: Vec<HirStmt>,
+        active: &HashMap<String, String>,
+    ) -> Result<(Vec<HirStmt>, Vec<HirStmt>, HashMap<String, String>), String> {
+        let mut active = active.clone();
+        let mut fakes = Vec::new();
+        for (patch, mock) in patches {
+            if let Some(reason) = patch.unsupported {
+                return Err(reason);
+            }
+            let function = match self.function(&patch.target) {
+                Some(function) if self.seams.contains(&function) => function,
+                Some(function) => {
+                    return Err(format!(
+                        "patches `{}`; transpile with `--seam {}` to inject a fake",
+                        patch.target, function
+                    ))
+                }
+                None => {
+                    return Err(format!(
+                        "patches `{}`, which is not a function of the module under test",
+                        patch.target
+                    ))
+                }
+            };
+
+            let mut value = patch.return_value;
+            if let Some(mock) = mock {
+                body.retain(|stmt| match stmt {
+                    HirStmt::Assign {
+                        target:
+                            AssignTarget::Attribute {
+                                value: object,
+                                attr,
+                            },
+                        value: assigned,
+                        ..
+                    } if is_var(object, &mock) && attr == "return_value" => {
+                        value = Some(assigned.clone());
+                        false
+                    }
+                    _ => true,
+                });
+                let mut used = false;
+                rewrite_exprs(&mut body, &mut |expr| used |= is_var(expr, &mock));
+                if used {
+                    return Err(format!(
+                        "uses mock `{}` beyond `return_value`, which is not supported",
+                        mock
+                    ));
+                }
+            }
+            if value.as_ref().is_some_and(|v| !is_constant(v)) {
+                return Err(format!(
+                    "the fake for `{}` needs a literal `return_value`",
+                    patch.target
+                ));
+            }
+
+            let base = format!("Fake{}", pascal_case(&function));
+            let mut fake = base.clone();
+            let mut n = 2;
+            while !self.names.insert(fake.clone()) {
+                fake = format!("{}{}", base, n);
+                n += 1;
+            }
+            let mut args = vec![
+                HirExpr::Literal(Literal::String(fake.clone())),
+                HirExpr::Literal(Literal::String(function.clone())),
+            ];
+            args.extend(value);
+            fakes.push(HirStmt::Expr(HirExpr::Call {
+                func: FAKE.to_string(),
+                args,
+                kwargs: vec![],
+            }));
+            active.insert(function, fake);
+        }
+        Ok((fakes, body, active))
+    }
+
+    fn scope(
+        &mut self,
+        body: Vec<HirStmt>,
+        active: &HashMap<String, String>,
+    ) -> Result<Vec<HirStmt>, String> {
+        let mut out = Vec::with_capacity(body.len());
+        for stmt in body {
+            let stmt = match stmt {
+                HirStmt::With {
+                    context,
+                    target,
+                    body,
+                } => match patch_call(&context) {
+                    Some(patch) => {
+                        let (fakes, body, active) =
+                            self.activate(vec![(patch, target)], body, active)?;
+                        out.extend(fakes);
+                        out.extend(self.scope(body, &active)?);
+                        continue;
+                    }
+                    None => HirStmt::With {
+                        context: self.redirected(context, active)?,
+                        target,
+                        body: self.scope(body, active)?,
+                    },
+                },
+                HirStmt::If {
+                    condition,
+                    then_body,
+                    else_body,
+                } => HirStmt::If {
+                    condition: self.redirected(condition, active)?,
+                    then_body: self.scope(then_body, active)?,
+                    else_body: else_body.map(|b| self.scope(b, active)).transpose()?,
+                },
+                HirStmt::While { condition, body } => HirStmt::While {
+                    condition: self.redirected(condition, active)?,
+                    body: self.scope(body, active)?,
+                },
+                HirStmt::For { target, iter, body } => HirStmt::For {
+                    target,
+                    iter: self.redirected(iter, active)?,
+                    body: self.scope(body, active)?,
+                },
+                HirStmt::Try {
+                    body,
+                    handlers,
+                    orelse,
+                    finalbody,
+                } => HirStmt::Try {
+                    body: self.scope(body, active)?,
+                    handlers: handlers
+                        .into_iter()
+                        .map(|mut handler| {
+                            handler.body = self.scope(handler.body, active)?;
+                            Ok(handler)
+                        })
+                        .collect::<Result<_, String>>()?,
+                    orelse: orelse.map(|b| self.scope(b, active)).transpose()?,
+                    finalbody: finalbody.map(|b| self.scope(b, active)).transpose()?,
+                },
+                mut simple => {
+                    let mut error = None;
+                    rewrite_exprs(std::slice::from_mut(&mut simple), &mut |expr| {
+                        if let Err(reason) = self.redirect(expr, active) {
+                            error.get_or_insert(reason);
+                        }
+                    });
+                    if let Some(reason) = error {
+                        return Err(reason);
+                    }
+                    simple
+                }
+            };
+            out.push(stmt);
+        }
+        Ok(out)
+    }
+
+    fn redirected(
+        &self,
+        mut expr: HirExpr,
+        active: &HashMap<String, String>,
+    ) -> Result<HirExpr, String> {
+        let mut error = None;
+        rewrite_expr(&mut expr, &mut |e| {
+            if let Err(reason) = self.redirect(e, active) {
+                error.get_or_insert(reason);
+            }
+        });
+        error.map_or(Ok(expr), Err)
+    }
+
+    /// Route a call through the active fakes when it uses one of them
+    fn redirect(&self, expr: &mut HirExpr, active: &HashMap<String, String>) -> Result<(), String> {
+        let func = match expr {
+            HirExpr::Call { func, .. } => func.clone(),
+            _ => return Ok(()),
+        };
+        if active.contains_key(&func) {
+            return Err(format!("calls the patched `{}` directly", func));
+        }
+        if let Some((_, seam)) = self
+            .indirect
+            .iter()
+            .find(|(f, seam)| *f == func && active.contains_key(seam))
+        {
+            return Err(format!(
+                "calls `{}`, which reaches the patched `{}` only through other functions",
+                func, seam
+            ));
+        }
+        let used = match self.callers.iter().find(|(caller, _)| *caller == func) {
+            Some((_, used)) if used.iter().any(|seam| active.contains_key(seam)) => used,
+            _ => return Ok(()),
+        };
+        let seams: Vec<String> = used
+            .iter()
+            .map(|seam| {
+                active
+                    .get(seam)
+                    .cloned()
+                    .unwrap_or_else(|| default_impl_name(seam))
+            })
+            .collect();
+
+        let call = std::mem::replace(expr, HirExpr::Literal(Literal::None));
+        *expr = HirExpr::Call {
+            func: SEAM_CALL.to_string(),
+            args: vec![HirExpr::Literal(Literal::String(seams.join(","))), call],
+            kwargs: vec![],
+        };
+        Ok(())
+    }
+}
+
+fn reaches(
+    functions: &[HirFunction],
+    from: &str,
+    target: &str,
+    seen: &mut HashSet<String>,
+) -> bool {
+    if !seen.insert(from.to_string()) {
+        return false;
+    }
+    let func = match functions.iter().find(|f| f.name == from) {
+        Some(func) => func,
+        None => return false,
+    };
+    functions.iter().any(|callee| {
+        callee.name != from
+            && calls_function(&func.body, &callee.name)
+            && (callee.name == target || reaches(functions, &callee.name, target, seen))
+    })
+}
+
+fn is_marker(call: &syn::ExprCall, marker: &str) -> bool {
+    matches!(call.func.as_ref(), syn::Expr::Path(p) if p.path.is_ident(marker))
+}
+
+/// `__depyler_fake__("FakeX", "f", value)` -> `struct FakeX;` and its seam impl
+///
+/// `signatures` holds the generated signatures of the seam targets.
+pub(super) fn fake_items(
+    stmt: &syn::Stmt,
+    signatures: &HashMap<String, syn::Signature>,
+) -> Option<Vec<syn::Stmt>> {
+    let call = match stmt {
+        syn::Stmt::Expr(syn::Expr::Call(call), _) if is_marker(call, FAKE) => call,
+        _ => return None,
+    };
+    let mut args = call.args.iter();
+    let fake = format_ident!("{}", string_literal(args.next()?)?);
+    let function = string_literal(args.next()?)?;
+    let value = args.next();
+    let signature = signatures.get(&function)?;
+
+    let mut method = seam_method_signature(signature);
+    for arg in method.inputs.iter_mut() {
+        if let syn::FnArg::Typed(pat) = arg {
+            *pat.pat = parse_quote!(_);
+        }
+    }
+    let body = match &signature.output {
+        syn::ReturnType::Default => quote! {},
+        syn::ReturnType::Type(_, ty) => match value {
+            Some(value) => return_value(value, ty),
+            None => match result_ok_type(ty) {
+                Some(_) => quote! { Ok(Default::default()) },
+                None => quote! { Default::default() },
+            },
+        },
+    };
+    let seam = format_ident!("{}", trait_name(&function));
+    Some(vec![
+        syn::Stmt::Item(parse_quote! { struct #fake; }),
+        syn::Stmt::Item(parse_quote! {
+            impl #seam for #fake {
+                #method {
+                    #body
+                }
+            }
+        }),
+    ])
+}
+
+/// The fake's return expression: `Ok`-wrapped for fallible functions,
+/// integer literals widened for float returns
+fn return_value(value: &syn::Expr, ty: &syn::Type) -> proc_macro2::TokenStream {
+    if let Some(ok) = result_ok_type(ty) {
+        let value = return_value(value, ok);
+        return quote! { Ok(#value) };
+    }
+    let is_float =
+        matches!(ty, syn::Type::Path(p) if p.path.is_ident("f64") || p.path.is_ident("f32"));
+    match value {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) if is_float => {
+            let float = syn::LitFloat::new(&format!("{}.0", int.base10_digits()), int.span());
+            quote! { #float }
+        }
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) if is_float => {
+            let inner = return_value(expr, ty);
+            quote! { -#inner }
+        }
+        other => quote! { #other },
+    }
+}
+
+fn result_ok_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Result" {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+/// `__depyler_seam_call__("FakeX,DefaultY", g(args))` -> `g_with(&FakeX, &DefaultY, args)`
+pub(super) fn seam_call(expr: &syn::Expr) -> Option<syn::Expr> {
+    let call = match expr {
+        syn::Expr::Call(call) if is_marker(call, SEAM_CALL) => call,
+        _ => return None,
+    };
+    let mut args = call.args.iter();
+    let seams: Vec<syn::Ident> = string_literal(args.next()?)?
+        .split(',')
+        .map(|seam| format_ident!("{}", seam))
+        .collect();
+    let inner = match without_unwrap(args.next()?.clone()) {
+        syn::Expr::Call(inner) => inner,
+        _ => return None,
+    };
+    let callee = match inner.func.as_ref() {
+        syn::Expr::Path(p) => p.path.get_ident()?.to_string(),
+        _ => return None,
+    };
+    let variant = format_ident!("{}", variant_name(&callee));
+    let args = inner.args.iter();
+    Some(parse_quote! { #variant(#(&#seams,)* #(#args),*) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(func: &str, args: Vec<HirExpr>, kwargs: Vec<(String, HirExpr)>) -> HirExpr {
+        HirExpr::Call {
+            func: func.to_string(),
+            args,
+            kwargs,
+        }
+    }
+
+    fn string(s: &str) -> HirExpr {
+        HirExpr::Literal(Literal::String(s.to_string()))
+    }
+
+    #[test]
+    fn test_patch_call_forms() {
+        let patch = patch_call(&call(
+            "patch",
+            vec![string("calc.fetch_rate")],
+            vec![(
+                "return_value".to_string(),
+                HirExpr::Literal(Literal::Int(2)),
+            )],
+        ))
+        .unwrap();
+        assert_eq!(patch.target, "calc.fetch_rate");
+        assert_eq!(patch.return_value, Some(HirExpr::Literal(Literal::Int(2))));
+        assert_eq!(patch.unsupported, None);
+
+        let object = HirExpr::MethodCall {
+            object: Box::new(HirExpr::Var("patch".to_string())),
+            method: "object".to_string(),
+            args: vec![HirExpr::Var("calc".to_string()), string("fetch_rate")],
+            kwargs: vec![("side_effect".to_string(), HirExpr::Var("boom".to_string()))],
+        };
+        let patch = patch_call(&object).unwrap();
+        assert_eq!(patch.target, "calc.fetch_rate");
+        assert_eq!(
+            patch.unsupported.as_deref(),
+            Some("`patch(side_effect=...)` is not supported")
+        );
+
+        assert!(patch_call(&call("open", vec![string("f.txt")], vec![])).is_none());
+    }
+
+    #[test]
+    fn test_seam_call_marker() {
+        let expr: syn::Expr = parse_quote!(__depyler_seam_call__(
+            "FakeFetchRate",
+            convert(amount).unwrap()
+        ));
+        let call = seam_call(&expr).unwrap();
+        assert_eq!(
+            quote!(#call).to_string(),
+            quote!(convert_with(&FakeFetchRate, amount)).to_string()
+        );
+    }
+}
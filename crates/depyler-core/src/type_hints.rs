@@ -2,6 +2,7 @@ use crate::error_reporting::EnhancedError;
 use crate::hir::{HirExpr, HirFunction, HirStmt, Type};
 use anyhow::Result;
 use colored::Colorize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 /// Type inference hints provider
@@ -16,7 +17,7 @@ pub struct TypeHintProvider {
     context: InferenceContext,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TypeHint {
     pub suggested_type: Type,
     pub confidence: Confidence,
@@ -25,14 +26,14 @@ pub struct TypeHint {
     pub target: HintTarget,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum HintTarget {
     Parameter(String),
     Return,
     Variable(String),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Confidence {
     Low,
     Medium,
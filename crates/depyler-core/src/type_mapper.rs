@@ -1,13 +1,20 @@
-use crate::hir::{ConstGeneric, Type as PythonType};
+use crate::hir::{ConstGeneric, HirClass, Type as PythonType, TypeAlias};
+use depyler_annotations::OwnershipModel;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum IntWidth {
     I32,
     I64,
     ISize,
+    /// `num_bigint::BigInt`, for `integer_semantics = "bigint"`
+    BigInt,
 }
 
+/// Rust type of `int` under `integer_semantics = "bigint"`
+pub const BIGINT_TYPE: &str = "num_bigint::BigInt";
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StringStrategy {
     AlwaysOwned,    // String everywhere (safe, simple)
@@ -15,10 +22,52 @@ pub enum StringStrategy {
     CowByDefault,   // Cow<'static, str> (V1.2)
 }
 
+/// Platform the generated types are chosen for
+///
+/// Selected with `CodeGenOptions::type_profile`; [`TypeMapper::with_profile`]
+/// applies the integer choice and code generation the rest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TypeProfile {
+    /// Hosted targets with std: the mapper's own choices, `HashMap` and
+    /// `HashSet` for dicts and sets
+    #[default]
+    Server,
+    /// Targets without std: `int` stays `i32`, the native width of most
+    /// microcontrollers, and dicts and sets become the `BTreeMap` and
+    /// `BTreeSet` that `alloc` provides, as `alloc` has no hash maps. Python
+    /// arrays of a fixed size already map to `[T; N]` and need no heap.
+    Embedded,
+    /// WebAssembly through wasm-bindgen: `int` stays `i32`, which JavaScript
+    /// numbers hold exactly where `i64` crosses as a `BigInt`, and public
+    /// functions whose signatures wasm-bindgen can pass are exported with
+    /// `#[wasm_bindgen]`
+    Wasm,
+}
+
+impl TypeProfile {
+    /// Name used in configuration files
+    pub fn name(self) -> &'static str {
+        match self {
+            TypeProfile::Server => "server",
+            TypeProfile::Embedded => "embedded",
+            TypeProfile::Wasm => "wasm",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeMapper {
     pub width_preference: IntWidth,
     pub string_type: StringStrategy,
+    /// Targets of the module's type aliases, which stand for them wherever
+    /// they are named; `NewType`s are distinct types and not among them
+    #[serde(default)]
+    pub type_aliases: HashMap<String, PythonType>,
+    /// Classes annotated `ownership = "arena"`, whose instances are named
+    /// by `<Class>Id` handles into an arena
+    #[serde(default)]
+    pub arena_classes: HashSet<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -98,6 +147,8 @@ impl Default for TypeMapper {
         Self {
             width_preference: IntWidth::I32,
             string_type: StringStrategy::AlwaysOwned,
+            type_aliases: HashMap::new(),
+            arena_classes: HashSet::new(),
         }
     }
 }
@@ -112,21 +163,178 @@ impl TypeMapper {
         self
     }
 
+    pub fn with_bigint(mut self) -> Self {
+        self.width_preference = IntWidth::BigInt;
+        self
+    }
+
     pub fn with_string_strategy(mut self, strategy: StringStrategy) -> Self {
         self.string_type = strategy;
         self
     }
 
+    /// Adjusts the mapping for `profile`
+    ///
+    /// Embedded and wasm targets keep `int` at `i32` whatever width was
+    /// preferred, except an explicit choice of `BigInt`.
+    ///
+    /// # Example
+    /// ```
+    /// use depyler_core::hir::Type;
+    /// use depyler_core::type_mapper::{PrimitiveType, RustType, TypeMapper, TypeProfile};
+    ///
+    /// let mapper = TypeMapper::new().with_i64().with_profile(TypeProfile::Wasm);
+    /// assert_eq!(mapper.map_type(&Type::Int), RustType::Primitive(PrimitiveType::I32));
+    /// ```
+    pub fn with_profile(mut self, profile: TypeProfile) -> Self {
+        match profile {
+            TypeProfile::Server => {}
+            TypeProfile::Embedded | TypeProfile::Wasm => {
+                if self.width_preference != IntWidth::BigInt {
+                    self.width_preference = IntWidth::I32;
+                }
+            }
+        }
+        self
+    }
+
+    /// Resolves the plain aliases among `aliases` to their targets
+    ///
+    /// # Example
+    /// ```
+    /// use depyler_core::hir::{Type, TypeAlias};
+    /// use depyler_core::type_mapper::{PrimitiveType, RustType, TypeMapper};
+    ///
+    /// let vector = TypeAlias {
+    ///     name: "Vector".to_string(),
+    ///     target_type: Type::List(Box::new(Type::Float)),
+    ///     is_newtype: false,
+    /// };
+    /// let mapper = TypeMapper::new().with_type_aliases(&[vector]);
+    /// assert_eq!(
+    ///     mapper.map_type(&Type::Custom("Vector".to_string())),
+    ///     RustType::Vec(Box::new(RustType::Primitive(PrimitiveType::F64)))
+    /// );
+    /// ```
+    pub fn with_type_aliases(mut self, aliases: &[TypeAlias]) -> Self {
+        self.type_aliases.extend(
+            aliases
+                .iter()
+                .filter(|alias| !alias.is_newtype)
+                .map(|alias| (alias.name.clone(), alias.target_type.clone())),
+        );
+        self
+    }
+
+    /// Names the instances of the arena classes among `classes` by handle
+    ///
+    /// # Example
+    /// ```
+    /// use depyler_annotations::OwnershipModel;
+    /// use depyler_core::hir::{HirClass, Type};
+    /// use depyler_core::type_mapper::{RustType, TypeMapper};
+    ///
+    /// let mut node = HirClass {
+    ///     name: "Node".to_string(),
+    ///     base_classes: vec![],
+    ///     methods: vec![],
+    ///     fields: vec![],
+    ///     is_dataclass: true,
+    ///     docstring: None,
+    ///     model_kind: None,
+    ///     annotations: Default::default(),
+    /// };
+    /// node.annotations.ownership_model = OwnershipModel::Arena;
+    /// let mapper = TypeMapper::new().with_arena_classes(&[node]);
+    /// assert_eq!(
+    ///     mapper.map_type(&Type::Optional(Box::new(Type::Custom("Node".to_string())))),
+    ///     RustType::Option(Box::new(RustType::Custom("NodeId".to_string())))
+    /// );
+    /// ```
+    pub fn with_arena_classes(mut self, classes: &[HirClass]) -> Self {
+        self.arena_classes.extend(
+            classes
+                .iter()
+                .filter(|class| class.annotations.ownership_model == OwnershipModel::Arena)
+                .map(|class| class.name.clone()),
+        );
+        self
+    }
+
+    /// `py_type` with the aliases in it replaced by their targets
+    pub fn resolve_aliases(&self, py_type: &PythonType) -> PythonType {
+        self.resolve_aliases_within(py_type, &mut Vec::new())
+    }
+
+    /// `expanding` holds the aliases being resolved, so an alias naming
+    /// itself is left as it is
+    fn resolve_aliases_within<'a>(
+        &'a self,
+        py_type: &PythonType,
+        expanding: &mut Vec<&'a str>,
+    ) -> PythonType {
+        let mut resolve =
+            |inner: &PythonType| Box::new(self.resolve_aliases_within(inner, expanding));
+        match py_type {
+            PythonType::Custom(name) => match self.type_aliases.get_key_value(name) {
+                Some((alias, target)) if !expanding.contains(&alias.as_str()) => {
+                    expanding.push(alias);
+                    let resolved = self.resolve_aliases_within(target, expanding);
+                    expanding.pop();
+                    resolved
+                }
+                _ => py_type.clone(),
+            },
+            PythonType::List(inner) => PythonType::List(resolve(inner)),
+            PythonType::Set(inner) => PythonType::Set(resolve(inner)),
+            PythonType::Optional(inner) => PythonType::Optional(resolve(inner)),
+            PythonType::Final(inner) => PythonType::Final(resolve(inner)),
+            PythonType::Dict(key, value) => {
+                let key = resolve(key);
+                PythonType::Dict(key, resolve(value))
+            }
+            PythonType::Array { element_type, size } => PythonType::Array {
+                element_type: resolve(element_type),
+                size: size.clone(),
+            },
+            PythonType::Function { params, ret } => PythonType::Function {
+                params: params.iter().map(|p| *resolve(p)).collect(),
+                ret: resolve(ret),
+            },
+            PythonType::Tuple(items) => {
+                PythonType::Tuple(items.iter().map(|t| *resolve(t)).collect())
+            }
+            PythonType::Union(items) => {
+                PythonType::Union(items.iter().map(|t| *resolve(t)).collect())
+            }
+            PythonType::Generic { base, params } => PythonType::Generic {
+                base: base.clone(),
+                params: params.iter().map(|p| *resolve(p)).collect(),
+            },
+            _ => py_type.clone(),
+        }
+    }
+
     pub fn map_type(&self, py_type: &PythonType) -> RustType {
         match py_type {
+            PythonType::Custom(name) if self.type_aliases.contains_key(name) => {
+                match self.resolve_aliases(py_type) {
+                    PythonType::Custom(unresolved) => RustType::Custom(unresolved),
+                    resolved => self.map_type(&resolved),
+                }
+            }
+            PythonType::Custom(name) if self.arena_classes.contains(name) => {
+                RustType::Custom(format!("{}Id", name))
+            }
             // DEPYLER-0264: Map Unknown to serde_json::Value instead of undefined DynamicType
             // This matches the pattern used for untyped Dict/List (lines 158-161)
             PythonType::Unknown => RustType::Custom("serde_json::Value".to_string()),
-            PythonType::Int => RustType::Primitive(match self.width_preference {
-                IntWidth::I32 => PrimitiveType::I32,
-                IntWidth::I64 => PrimitiveType::I64,
-                IntWidth::ISize => PrimitiveType::ISize,
-            }),
+            PythonType::Int => match self.width_preference {
+                IntWidth::I32 => RustType::Primitive(PrimitiveType::I32),
+                IntWidth::I64 => RustType::Primitive(PrimitiveType::I64),
+                IntWidth::ISize => RustType::Primitive(PrimitiveType::ISize),
+                IntWidth::BigInt => RustType::Custom(BIGINT_TYPE.to_string()),
+            },
             PythonType::Float => RustType::Primitive(PrimitiveType::F64),
             PythonType::String => match self.string_type {
                 StringStrategy::AlwaysOwned => RustType::String,
@@ -179,6 +387,13 @@ impl TypeMapper {
                         Box::new(self.map_type(&params[0])),
                         Box::new(self.map_type(&params[1])),
                     ),
+                    // `async for` consumes any of these as a futures Stream
+                    "AsyncIterator" | "AsyncIterable" | "AsyncGenerator" if !params.is_empty() => {
+                        RustType::Custom(format!(
+                            "impl futures::Stream<Item = {}>",
+                            self.map_type(&params[0]).to_rust_string()
+                        ))
+                    }
                     _ => RustType::Generic {
                         base: base.clone(),
                         params: params.iter().map(|t| self.map_type(t)).collect(),
@@ -431,6 +646,12 @@ mod tests {
             mapper_isize.map_type(&PythonType::Int),
             RustType::Primitive(PrimitiveType::ISize)
         );
+
+        let mapper_bigint = TypeMapper::new().with_bigint();
+        assert_eq!(
+            mapper_bigint.map_type(&PythonType::List(Box::new(PythonType::Int))),
+            RustType::Vec(Box::new(RustType::Custom(BIGINT_TYPE.to_string())))
+        );
     }
 
     #[test]
@@ -637,4 +858,27 @@ mod tests {
         // Sets need references
         assert!(mapper.needs_reference(&RustType::HashSet(Box::new(RustType::String))));
     }
+
+    #[test]
+    fn test_profiles_pin_int_width() {
+        let int = RustType::Primitive(PrimitiveType::I32);
+        let wide = TypeMapper::new().with_i64();
+        assert_eq!(
+            wide.clone()
+                .with_profile(TypeProfile::Embedded)
+                .map_type(&PythonType::Int),
+            int
+        );
+        assert_eq!(
+            wide.clone()
+                .with_profile(TypeProfile::Wasm)
+                .map_type(&PythonType::Int),
+            int
+        );
+        assert_eq!(
+            wide.with_profile(TypeProfile::Server)
+                .map_type(&PythonType::Int),
+            RustType::Primitive(PrimitiveType::I64)
+        );
+    }
 }
@@ -0,0 +1,318 @@
+//! Visibility inference for generated module functions
+//!
+//! Python has no access modifiers, only conventions: a module's `__all__`
+//! lists its public API, and a leading underscore marks a name as internal.
+//! Emitting every function as `pub fn` makes helpers part of the crate API,
+//! so each function gets the narrowest visibility the conventions allow:
+//!
+//! - a `# @depyler: visibility = "..."` annotation always wins
+//! - `main` and exported functions stay `pub`; without `__all__` every name
+//!   without a leading underscore counts as exported
+//! - internal functions referenced by other module code become private
+//! - internal functions nothing references become `pub(crate)`, so they stay
+//!   reachable from the rest of the crate instead of tripping `dead_code`
+
+use crate::hir::{AssignTarget, HirClass, HirConstant, HirExpr, HirFunction, HirStmt, Literal};
+use depyler_annotations::Visibility;
+use proc_macro2::TokenStream;
+use quote::quote;
+use std::collections::{HashMap, HashSet};
+
+/// Visibility chosen for each module function
+///
+/// # Examples
+///
+/// ```rust
+/// use depyler_annotations::Visibility;
+/// use depyler_core::hir_builder::{expr, stmt, HirFunctionBuilder};
+/// use depyler_core::visibility::VisibilityInference;
+///
+/// let functions = vec![
+///     HirFunctionBuilder::new("_scale")
+///         .stmt(stmt::ret(expr::int(2)))
+///         .build(),
+///     HirFunctionBuilder::new("area")
+///         .stmt(stmt::ret(expr::call("_scale", vec![])))
+///         .build(),
+/// ];
+///
+/// let visibility = VisibilityInference::analyze(&functions, &[], &[]);
+/// assert_eq!(visibility.visibility("area"), Visibility::Public);
+/// assert_eq!(visibility.visibility("_scale"), Visibility::Private);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VisibilityInference {
+    by_name: HashMap<String, Visibility>,
+}
+
+impl VisibilityInference {
+    pub fn analyze(
+        functions: &[HirFunction],
+        classes: &[HirClass],
+        constants: &[HirConstant],
+    ) -> Self {
+        let exported = exported_names(constants);
+        let referenced = referenced_names(functions, classes, constants);
+        let by_name = functions
+            .iter()
+            .map(|func| {
+                let is_exported = match &exported {
+                    Some(names) => names.contains(&func.name),
+                    None => !func.name.starts_with('_'),
+                };
+                let visibility = if let Some(visibility) = func.annotations.visibility {
+                    visibility
+                } else if is_exported || func.name == "main" {
+                    Visibility::Public
+                } else if referenced.contains(&func.name) {
+                    Visibility::Private
+                } else {
+                    Visibility::Crate
+                };
+                (func.name.clone(), visibility)
+            })
+            .collect();
+        VisibilityInference { by_name }
+    }
+
+    /// Visibility of a module function; unknown names stay public
+    pub fn visibility(&self, name: &str) -> Visibility {
+        self.by_name
+            .get(name)
+            .copied()
+            .unwrap_or(Visibility::Public)
+    }
+
+    /// The visibility qualifier to emit before `fn`
+    pub fn tokens(&self, name: &str) -> TokenStream {
+        match self.visibility(name) {
+            Visibility::Public => quote! { pub },
+            Visibility::Crate => quote! { pub(crate) },
+            Visibility::Private => quote! {},
+        }
+    }
+}
+
+/// Names listed in the module's `__all__`, if it declares one
+pub fn exported_names(constants: &[HirConstant]) -> Option<HashSet<String>> {
+    let all = constants.iter().rev().find(|c| c.name == "__all__")?;
+    match &all.value {
+        HirExpr::List(items) | HirExpr::Tuple(items) => Some(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    HirExpr::Literal(Literal::String(name)) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Names each piece of module code refers to, minus a function's references
+/// to itself
+fn referenced_names(
+    functions: &[HirFunction],
+    classes: &[HirClass],
+    constants: &[HirConstant],
+) -> HashSet<String> {
+    let mut referenced = HashSet::new();
+    for func in functions {
+        let mut refs = References::default();
+        refs.stmts(&func.body);
+        func.params
+            .iter()
+            .filter_map(|p| p.default.as_ref())
+            .for_each(|default| refs.expr(default));
+        refs.names.remove(&func.name);
+        referenced.extend(refs.names);
+    }
+
+    let mut refs = References::default();
+    for class in classes {
+        class.methods.iter().for_each(|m| refs.stmts(&m.body));
+        class
+            .fields
+            .iter()
+            .filter_map(|f| f.default_value.as_ref())
+            .for_each(|default| refs.expr(default));
+    }
+    constants.iter().for_each(|c| refs.expr(&c.value));
+    referenced.extend(refs.names);
+    referenced
+}
+
+/// Collects the names called or used as values in a body
+#[derive(Default)]
+struct References {
+    names: HashSet<String>,
+}
+
+impl References {
+    fn stmts(&mut self, stmts: &[HirStmt]) {
+        stmts.iter().for_each(|stmt| self.stmt(stmt));
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.target(target);
+                self.expr(value);
+            }
+            HirStmt::Return(value) => {
+                if let Some(value) = value {
+                    self.expr(value);
+                }
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.expr(condition);
+                self.stmts(then_body);
+                if let Some(else_body) = else_body {
+                    self.stmts(else_body);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                self.expr(condition);
+                self.stmts(body);
+            }
+            HirStmt::For { iter, body, .. } => {
+                self.expr(iter);
+                self.stmts(body);
+            }
+            HirStmt::Expr(expr) => self.expr(expr),
+            HirStmt::Raise { exception, cause } => {
+                for expr in [exception, cause].into_iter().flatten() {
+                    self.expr(expr);
+                }
+            }
+            HirStmt::With { context, body, .. } => {
+                self.expr(context);
+                self.stmts(body);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                self.stmts(body);
+                handlers.iter().for_each(|h| self.stmts(&h.body));
+                for block in [orelse, finalbody].into_iter().flatten() {
+                    self.stmts(block);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.expr(test);
+                if let Some(msg) = msg {
+                    self.expr(msg);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.expr(subject);
+                for case in cases {
+                    if let Some(guard) = &case.guard {
+                        self.expr(guard);
+                    }
+                    self.stmts(&case.body);
+                }
+            }
+            HirStmt::Break { .. } | HirStmt::Continue { .. } | HirStmt::Pass => {}
+        }
+    }
+
+    fn target(&mut self, target: &AssignTarget) {
+        match target {
+            AssignTarget::Symbol(_) => {}
+            AssignTarget::Attribute { value, .. } => self.expr(value),
+            AssignTarget::Index { base, index } => {
+                self.expr(base);
+                self.expr(index);
+            }
+            AssignTarget::Tuple(targets) => targets.iter().for_each(|t| self.target(t)),
+        }
+    }
+
+    fn expr(&mut self, expr: &HirExpr) {
+        match expr {
+            HirExpr::Call { func, .. } => {
+                self.names.insert(func.clone());
+            }
+            HirExpr::Var(name) => {
+                self.names.insert(name.clone());
+            }
+            _ => {}
+        }
+        for child in expr.children() {
+            self.expr(child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hir_builder::HirFunctionBuilder;
+
+    fn call(name: &str) -> HirStmt {
+        HirStmt::Expr(HirExpr::Call {
+            func: name.to_string(),
+            args: vec![],
+            kwargs: vec![],
+        })
+    }
+
+    fn all(names: &[&str]) -> HirConstant {
+        HirConstant {
+            name: "__all__".to_string(),
+            value: HirExpr::List(
+                names
+                    .iter()
+                    .map(|n| HirExpr::Literal(Literal::String(n.to_string())))
+                    .collect(),
+            ),
+            type_annotation: None,
+        }
+    }
+
+    #[test]
+    fn test_all_limits_public_api() {
+        let functions = vec![
+            HirFunctionBuilder::new("run").stmt(call("helper")).build(),
+            HirFunctionBuilder::new("helper").build(),
+            HirFunctionBuilder::new("unused").build(),
+        ];
+        let visibility = VisibilityInference::analyze(&functions, &[], &[all(&["run"])]);
+        assert_eq!(visibility.visibility("run"), Visibility::Public);
+        assert_eq!(visibility.visibility("helper"), Visibility::Private);
+        assert_eq!(visibility.visibility("unused"), Visibility::Crate);
+    }
+
+    #[test]
+    fn test_underscore_names_are_internal_without_all() {
+        let functions = vec![
+            HirFunctionBuilder::new("_recurse")
+                .stmt(call("_recurse"))
+                .build(),
+            HirFunctionBuilder::new("_scale").build(),
+            HirFunctionBuilder::new("main").stmt(call("_scale")).build(),
+        ];
+        let visibility = VisibilityInference::analyze(&functions, &[], &[]);
+        assert_eq!(visibility.visibility("main"), Visibility::Public);
+        assert_eq!(visibility.visibility("_scale"), Visibility::Private);
+        assert_eq!(visibility.visibility("_recurse"), Visibility::Crate);
+    }
+
+    #[test]
+    fn test_annotation_overrides_inference() {
+        let mut helper = HirFunctionBuilder::new("_helper").build();
+        helper.annotations.visibility = Some(Visibility::Public);
+        let visibility = VisibilityInference::analyze(&[helper], &[], &[]);
+        assert_eq!(visibility.visibility("_helper"), Visibility::Public);
+        assert_eq!(visibility.tokens("_helper").to_string(), "pub");
+    }
+}
@@ -0,0 +1,113 @@
+//! Classes annotated `ownership = "arena"` live in an arena and refer to
+//! each other by index, cycles included
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+from dataclasses import dataclass
+from typing import Optional
+
+# @depyler: ownership = "arena"
+@dataclass
+class Node:
+    value: int
+    next: Optional["Node"] = None
+
+    def doubled(self) -> int:
+        return self.value * 2
+
+def push_front(head: Optional[Node], value: int) -> Node:
+    node = Node(value)
+    node.next = head
+    return node
+
+def length(head: Optional[Node]) -> int:
+    count = 0
+    cur = head
+    while cur is not None:
+        count += 1
+        cur = cur.next
+    return count
+
+def build(n: int) -> int:
+    head: Optional[Node] = None
+    for i in range(n):
+        head = push_front(head, i)
+    return length(head)
+
+def ring_sum(n: int, steps: int) -> int:
+    first = Node(0)
+    last = first
+    for i in range(1, n):
+        node = Node(i)
+        last.next = node
+        last = node
+    last.next = first
+    total = 0
+    cur: Optional[Node] = first
+    for _ in range(steps):
+        total += cur.value
+        cur = cur.next
+    return total
+
+def front_doubled(value: int) -> int:
+    head = push_front(None, value)
+    return head.doubled()
+"#;
+
+#[test]
+fn test_instances_are_allocated_in_an_arena() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("pubnext:Option<NodeId>"));
+    assert!(code.contains("pubstructNodeArena{items:Vec<Node>,}"));
+    assert!(!code.contains("Rc<RefCell<"));
+    assert!(code.contains("node_arena:&mutNodeArena,head:Option<NodeId>,value:i32)->NodeId"));
+    assert!(code.contains("node=node_arena.alloc(Node::new(value));"));
+    assert!(code.contains("node_arena[node].next=head;"));
+    assert!(code.contains("cur=node_arena[cur.unwrap()].next;"));
+    assert!(code.contains("head=Some(push_front(node_arena,head,i));"));
+    assert!(code.contains("node_arena[last].next=Some(node);"));
+    assert!(code.contains("node_arena[head].doubled()"));
+}
+
+#[test]
+fn test_arena_graphs_behave_like_python_objects() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(build(0), 0);
+    assert_eq!(build(4), 4);
+    assert_eq!(ring_sum(3, 7), 6);
+    assert_eq!(ring_sum(1, 3), 0);
+    assert_eq!(front_doubled(21), 42);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("arena.rs");
+    let binary = dir.path().join("arena");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,68 @@
+//! `assert` messages, f-strings included, become the `assert!` message
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_fstring_message_is_inlined() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .transpile(
+                r#"
+def check(x: int, ratio: float) -> int:
+    assert x > 0, f"bad x: {x}"
+    assert ratio < 1.0, f"{{ratio}} = {ratio}"
+    return x
+"#,
+            )
+            .unwrap(),
+    );
+
+    assert!(code.contains("assert!(x>0,\"badx:{}\",x);"));
+    // Braces are escaped and floats show as `str()` shows them
+    assert!(code.contains("assert!(ratio<1.0,\"{{ratio}}={}\",ratio.py_repr());"));
+}
+
+#[test]
+fn test_literal_and_other_messages() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .transpile(
+                r#"
+def check(x: int, why: str) -> int:
+    assert x != 1, "not {one}"
+    assert x != 2, why
+    assert x != 3
+    return x
+"#,
+            )
+            .unwrap(),
+    );
+
+    assert!(code.contains("assert!(x!=1,\"not{{one}}\");"));
+    assert!(code.contains("assert!(x!=2,\"{}\",why);"));
+    assert!(code.contains("assert!(x!=3);"));
+}
+
+#[test]
+fn test_fstring_message_in_method() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .transpile(
+                r#"
+class Counter:
+    def __init__(self, count: int):
+        self.count = count
+
+    def bump(self, step: int) -> None:
+        assert step > 0, f"bad step: {step}"
+        self.count += step
+"#,
+            )
+            .unwrap(),
+    );
+
+    assert!(code.contains("assert!(step>0,\"badstep:{}\",step);"));
+}
@@ -0,0 +1,87 @@
+//! `async for` over streams, `async with` over tokio locks and aiohttp
+//! sessions, and asyncio queues as tokio channels
+
+mod common;
+
+use common::compact;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+import asyncio
+import aiohttp
+from typing import AsyncIterator
+
+async def total(numbers: AsyncIterator[int]) -> int:
+    result = 0
+    async for n in numbers:
+        result = result + n
+    return result
+
+async def drain() -> int:
+    queue = asyncio.Queue()
+    queue.put_nowait(1)
+    await queue.put(2)
+    first = await queue.get()
+    second = await queue.get()
+    return first + second
+
+async def guarded(count: int) -> int:
+    lock = asyncio.Lock()
+    async with lock:
+        count = count + 1
+    return count
+
+async def fetch(url: str) -> str:
+    async with aiohttp.ClientSession() as session:
+        async with session.get(url) as resp:
+            if resp.status != 200:
+                return ""
+            return await resp.text()
+"#;
+
+#[test]
+fn test_async_for_drains_a_stream() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("usefutures::StreamExt;"));
+    assert!(code.contains("numbers:implfutures::Stream<Item=i32>"));
+    assert!(code.contains("letmut_stream=std::pin::pin!(numbers);"));
+    assert!(code.contains("whileletSome(n)=_stream.next().await{"));
+}
+
+#[test]
+fn test_asyncio_queue_is_a_tokio_channel() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("letmutqueue=tokio::sync::mpsc::unbounded_channel();"));
+    assert!(code.contains("queue.0.send(1).expect(\"queueclosed\");"));
+    assert!(code.contains("queue.0.send(2).expect(\"queueclosed\");"));
+    assert!(code.contains("letfirst=queue.1.recv().await.expect(\"queueclosed\");"));
+}
+
+#[test]
+fn test_async_with_lock_and_session() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("letlock=tokio::sync::Mutex::new(());"));
+    assert!(code.contains("let_guard=lock.lock().await;"));
+    assert!(code.contains("letsession=reqwest::Client::new();"));
+    assert!(
+        code.contains("letresp=session.get(&*url).send().await.expect(\"failedtosendrequest\");")
+    );
+    assert!(code.contains("resp.status().as_u16()asi32"));
+    assert!(code.contains("resp.text().await.expect(\"failedtoreadresponse\")"));
+}
+
+#[test]
+fn test_async_crates_become_dependencies() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let names: Vec<&str> = detect_dependencies(&rust_code)
+        .unwrap()
+        .iter()
+        .map(|d| d.name)
+        .collect();
+
+    assert_eq!(names, ["futures", "reqwest", "tokio"]);
+}
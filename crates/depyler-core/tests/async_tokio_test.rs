@@ -0,0 +1,172 @@
+//! Tests for async function transpilation onto tokio
+//!
+//! `async def` becomes `async fn`, `await` becomes `.await`, asyncio calls
+//! map to tokio, and `asyncio.run(entry())` produces a `#[tokio::main]`.
+
+mod common;
+
+use common::compact;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_async_main_entry_point() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import asyncio
+
+async def fetch(n: int) -> int:
+    await asyncio.sleep(0.1)
+    return n * 2
+
+async def main() -> None:
+    value = await fetch(21)
+    print(value)
+
+if __name__ == "__main__":
+    asyncio.run(main())
+"#;
+    let rust_code = pipeline.transpile(python_code).unwrap();
+    let compact_code = compact(&rust_code);
+
+    assert!(compact_code.contains("pubasyncfnfetch("));
+    assert!(compact_code.contains("tokio::time::sleep(std::time::Duration::from_secs_f64("));
+    assert!(compact_code.contains("asf64)).await"));
+    assert!(compact_code.contains("fetch(21).await"));
+    assert!(compact_code.contains("#[tokio::main]pubasyncfnmain()"));
+    assert!(!rust_code.contains("use tokio"));
+
+    let deps = detect_dependencies(&rust_code).unwrap();
+    assert!(deps.iter().any(|d| d.name == "tokio"));
+}
+
+#[test]
+fn test_asyncio_run_of_other_coroutine() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import asyncio
+
+async def run_app() -> None:
+    await asyncio.sleep(1)
+
+asyncio.run(run_app())
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[tokio::main]asyncfnmain(){run_app().await;}"));
+}
+
+#[test]
+fn test_asyncio_gather() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import asyncio
+
+async def one() -> int:
+    return 1
+
+async def two() -> int:
+    return 2
+
+async def both() -> int:
+    a, b = await asyncio.gather(one(), two())
+    return a + b
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("async{tokio::join!(one(),two())}.await"));
+    // No asyncio.run(), so there is no runtime entry point
+    assert!(!rust_code.contains("tokio::main"));
+}
+
+const FALLIBLE: &str = r#"
+import asyncio
+
+async def fetch(n: int) -> int:
+    if n < 0:
+        raise ValueError("negative")
+    return n * 2
+"#;
+
+#[test]
+fn test_asyncio_gather_raises_the_first_error() {
+    let python_code = format!(
+        "{}{}",
+        FALLIBLE,
+        r#"
+async def pair() -> int:
+    a, b = await asyncio.gather(fetch(1), fetch(2))
+    return a + b
+
+async def settled() -> None:
+    results = await asyncio.gather(fetch(1), fetch(-1), return_exceptions=True)
+    print(results)
+"#
+    );
+    let rust_code = compact(&DepylerPipeline::new().transpile(&python_code).unwrap());
+
+    assert!(rust_code.contains("tokio::try_join!(fetch(1),fetch(2))"));
+    assert!(!rust_code.contains("async{tokio::try_join!"));
+    // return_exceptions=True keeps every Result
+    assert!(rust_code.contains("async{tokio::join!(fetch(1),fetch(-1))}.await"));
+}
+
+#[test]
+fn test_asyncio_gather_of_unpacked_tasks() {
+    let python_code = r#"
+import asyncio
+
+async def double(n: int) -> int:
+    return n * 2
+
+async def doubled(ns: list[int]) -> list[int]:
+    tasks = [double(n) for n in ns]
+    return await asyncio.gather(*tasks)
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python_code).unwrap();
+
+    assert!(compact(&rust_code).contains("futures::future::join_all(tasks).await"));
+    let deps = detect_dependencies(&rust_code).unwrap();
+    assert!(deps.iter().any(|d| d.name == "futures"));
+}
+
+#[test]
+fn test_task_group_joins_on_a_join_set() {
+    let python_code = format!(
+        "{}{}",
+        FALLIBLE,
+        r#"
+async def total() -> int:
+    async with asyncio.TaskGroup() as tg:
+        first = tg.create_task(fetch(1))
+        second = tg.create_task(fetch(2))
+    return first.result() + second.result()
+"#
+    );
+    let rust_code = compact(&DepylerPipeline::new().transpile(&python_code).unwrap());
+
+    assert!(rust_code.contains("letmuttg=tokio::task::JoinSet::new();"));
+    assert!(rust_code.contains("letfirst=tg.spawn(fetch(1)).id();"));
+    assert!(rust_code.contains("whileletSome(_joined)=tg.join_next_with_id().await"));
+    assert!(rust_code.contains("std::panic::resume_unwind(e.into_panic())"));
+    // The first error ends the join; dropping the JoinSet aborts the rest
+    assert!(rust_code.contains("tg_results.insert(_id,_result"));
+    assert!(rust_code.contains("letfirst=tg_results.remove(&first)"));
+    assert!(rust_code.contains("first.clone()+second.clone()"));
+}
+
+#[test]
+fn test_asyncio_run_requires_async_function() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import asyncio
+
+def main() -> None:
+    print("sync")
+
+asyncio.run(main())
+"#;
+    let rust_code = pipeline.transpile(python_code).unwrap();
+
+    assert!(!rust_code.contains("tokio::main"));
+}
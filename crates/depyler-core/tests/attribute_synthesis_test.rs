@@ -0,0 +1,56 @@
+//! `#[inline]`, `#[must_use]` and complexity notes chosen by analysis
+
+mod common;
+
+use common::compact;
+use depyler_core::attribute_synthesis::AttributeOptions;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def square(x: int) -> int:
+    return x * x
+
+def report(x: int) -> None:
+    print(square(x))
+
+def classify(x: int) -> str:
+    if x < 0:
+        return "negative"
+    if x == 0:
+        return "zero"
+    if x < 10:
+        return "small"
+    return "large"
+"#;
+
+#[test]
+fn test_attributes_are_opt_in() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+    assert!(!code.contains("#[inline]"));
+    assert!(!code.contains("#[must_use]"));
+}
+
+#[test]
+fn test_pure_functions_get_inline_and_must_use() {
+    let rust = DepylerPipeline::new()
+        .with_attribute_synthesis(AttributeOptions::default())
+        .transpile(SOURCE)
+        .unwrap();
+    let code = compact(&rust);
+    assert!(code.contains("#[inline]#[must_use]pubfnsquare("));
+    assert!(code.contains("pubfnreport("));
+    assert!(!code.contains("#[must_use]pubfnreport("));
+    assert!(!code.contains("complexity:"));
+}
+
+#[test]
+fn test_functions_above_the_complexity_limit_are_noted() {
+    let rust = DepylerPipeline::new()
+        .with_attribute_synthesis(AttributeOptions {
+            max_cyclomatic: 3,
+            ..AttributeOptions::default()
+        })
+        .transpile(SOURCE)
+        .unwrap();
+    assert!(rust.contains("/// complexity: 4") || rust.contains("#[doc = \" complexity: 4\"]"));
+}
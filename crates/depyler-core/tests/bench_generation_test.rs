@@ -0,0 +1,49 @@
+//! Criterion benchmarks generated for pure functions
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+const MODULE: &str = r#"
+def total(values: List[int]) -> int:
+    return sum(values)
+
+def greet(name: str) -> str:
+    return "Hello, " + name
+
+def report(x: int) -> None:
+    print(x)
+"#;
+
+fn benches(pipeline: &DepylerPipeline) -> Option<String> {
+    let module = pipeline.parse_to_hir(MODULE).unwrap();
+    let benches = pipeline.generate_benches(&module, "stats-lib").unwrap();
+    if let Some(code) = &benches {
+        println!("Generated benches:\n{}", code);
+    }
+    benches.map(|code| compact(&code))
+}
+
+#[test]
+fn test_benches_are_off_by_default() {
+    assert!(benches(&DepylerPipeline::new()).is_none());
+}
+
+#[test]
+fn test_pure_functions_get_a_criterion_harness() {
+    let code = benches(&DepylerPipeline::new().with_benches()).unwrap();
+
+    assert!(code.contains("usecriterion::{criterion_group,criterion_main,Criterion};"));
+    assert!(code.contains("usestats_lib::*;"));
+    assert!(code.contains("c.bench_function(\"total\",|b|b.iter(||total(black_box(&values))));"));
+    assert!(code.contains("greet(black_box(&*name).into())"));
+    assert!(code.contains("criterion_group!(benches,bench_total,bench_greet);"));
+    assert!(code.contains("criterion_main!(benches);"));
+}
+
+#[test]
+fn test_functions_without_a_result_are_not_benchmarked() {
+    let code = benches(&DepylerPipeline::new().with_benches()).unwrap();
+    assert!(!code.contains("bench_report"));
+}
@@ -0,0 +1,81 @@
+//! Functions returning a slice of a parameter return a reference into it,
+//! for a lifetime the signature ties to that parameter
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def head(s: str, n: int) -> str:
+    return s[:n]
+
+def clean(s: str) -> str:
+    return s.strip() if s else "-"
+
+def tail(xs: List[int], n: int) -> List[int]:
+    return xs[n:]
+
+def middle(xs: List[int]) -> List[int]:
+    return xs[1:3]
+
+def shout(s: str) -> str:
+    return head(s, 3) + "!"
+"#;
+
+#[test]
+fn test_signatures_tie_the_return_to_the_parameter() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("fnhead<'a>(s:&'astr,n:i32)->&'astr"));
+    assert!(code.contains("fnclean<'a>(s:&'astr)->&'astr"));
+    assert!(code.contains("fntail<'a>(xs:&'aVec<i32>,n:i32)->&'a[i32]"));
+    assert!(code.contains("&xs[1usize.min(xs.len())..3usize.min(xs.len())]"));
+    // Callers lend the argument and keep an owned result
+    assert!(code.contains("head(&s,3).to_owned()"));
+    assert!(code.contains("->String"));
+}
+
+#[test]
+fn test_borrowed_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    let text = String::from("héllo wörld");
+    assert_eq!(head(&text, 4), "héll");
+    assert_eq!(head(&text, -2), "héllo wör");
+    assert_eq!(clean("  hi  "), "hi");
+    assert_eq!(clean(""), "-");
+    let xs = vec![1, 2, 3, 4];
+    assert_eq!(tail(&xs, -3), [2, 3, 4]);
+    assert_eq!(tail(&xs, 9), [] as [i32; 0]);
+    assert_eq!(middle(&xs), [2, 3]);
+    assert_eq!(middle(&vec![1]), [] as [i32; 0]);
+    assert_eq!(shout("hello".into()), "hel!");
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("borrowed.rs");
+    let binary = dir.path().join("borrowed");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,83 @@
+//! `int()`, `float()` and `/` only convert values whose type differs from
+//! the target, widening through `From`
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def count(xs: list[int]) -> int:
+    return int(len(xs))
+
+def same(n: int) -> int:
+    return int(n)
+
+def flag(b: bool) -> int:
+    return int(b) + 1
+
+def widen(n: int) -> float:
+    return float(n)
+
+def keep(x: float) -> float:
+    return float(x)
+
+def truncate(x: float) -> int:
+    return int(x)
+
+def ratio(a: int, b: int) -> float:
+    return a / b
+"#;
+
+#[test]
+fn test_casts_follow_value_types() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("xs.len()asi32"));
+    assert!(!code.contains("asi32)asi32"));
+    assert!(!code.contains("(n)asi32"));
+    assert!(code.contains("i32::from(b)+1"));
+    assert!(code.contains("f64::from(n)"));
+    assert!(!code.contains("(x)asf64"));
+    assert!(code.contains("(x)asi32"));
+    assert!(code.contains("f64::from(a)/f64::from(b)"));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(same(-4), -4);
+    assert_eq!(flag(true), 2);
+    assert_eq!(widen(7), 7.0);
+    assert_eq!(keep(2.5), 2.5);
+    assert_eq!(truncate(-2.7), -2);
+    assert_eq!(ratio(7, 2), 3.5);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("casts.rs");
+    let binary = dir.path().join("casts");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
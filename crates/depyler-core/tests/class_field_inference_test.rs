@@ -0,0 +1,105 @@
+//! Class field types inferred from what the methods store in them
+
+use depyler_core::field_inference::FieldTypeTable;
+use depyler_core::hir::Type;
+use depyler_core::DepylerPipeline;
+
+const INVENTORY: &str = r#"
+class Inventory:
+    def __init__(self, owner: str):
+        self.owner = owner
+        self.items = []
+        self.counts = {}
+        self.tags = set()
+        self.total = 0
+
+    def add(self, name: str, qty: int) -> None:
+        self.items.append(name)
+        self.counts[name] = qty
+        self.tags.add(qty)
+"#;
+
+fn table(python: &str) -> FieldTypeTable {
+    let module = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    FieldTypeTable::analyze(&module.classes)
+}
+
+#[test]
+fn test_collection_fields_take_the_types_methods_store() {
+    let table = table(INVENTORY);
+
+    assert_eq!(
+        table.field_type("Inventory", "items"),
+        Some(&Type::List(Box::new(Type::String)))
+    );
+    assert_eq!(
+        table.field_type("Inventory", "counts"),
+        Some(&Type::Dict(Box::new(Type::String), Box::new(Type::Int)))
+    );
+    assert_eq!(
+        table.field_type("Inventory", "tags"),
+        Some(&Type::Set(Box::new(Type::Int)))
+    );
+    assert_eq!(table.field_type("Inventory", "total"), Some(&Type::Int));
+}
+
+#[test]
+fn test_attribute_of_a_typed_value_has_the_field_type() {
+    let table = table(
+        r#"
+class Node:
+    def __init__(self, value: int):
+        self.value = value
+        self.label = str(value)
+
+class Tree:
+    def __init__(self, root: Node):
+        self.root = root
+        self.root_value = root.value
+"#,
+    );
+
+    let tree = Type::Custom("Tree".to_string());
+    assert_eq!(
+        table.infer_attribute_type(&tree, "root"),
+        Type::Custom("Node".to_string())
+    );
+    assert_eq!(table.infer_attribute_type(&tree, "root_value"), Type::Int);
+    assert_eq!(
+        table.infer_attribute_type(&Type::Custom("Node".to_string()), "label"),
+        Type::String
+    );
+    assert_eq!(table.infer_attribute_type(&tree, "missing"), Type::Unknown);
+}
+
+#[test]
+fn test_refined_fields_reach_the_generated_struct() {
+    let rust_code = DepylerPipeline::new().transpile(INVENTORY).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub items: Vec<String>"));
+    assert!(rust_code.contains("pub counts: HashMap<String, i32>"));
+}
+
+#[test]
+fn test_dataclass_fields_of_parameters_are_typed() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+from dataclasses import dataclass
+
+@dataclass
+class Order:
+    id: int
+    items: list[int]
+
+def show(order: Order) -> None:
+    items = order.items
+    print(items)
+"#,
+        )
+        .unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("println!(\"{:?}\", items)"));
+}
@@ -0,0 +1,49 @@
+//! Classes emitted as Rust modules mirroring Python's class namespaces
+
+mod common;
+
+use common::transpile_with;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+class StackEmpty(Exception):
+    pass
+
+class Stack:
+    def __init__(self):
+        self.items: list[int] = []
+
+    def push(self, item: int) -> None:
+        self.items.append(item)
+
+    def pop(self) -> int:
+        if not self.items:
+            raise StackEmpty("empty")
+        return self.items.pop()
+
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+def total(stack: Stack) -> int:
+    return stack.pop()
+"#;
+
+#[test]
+fn test_each_class_gets_a_module() {
+    let code = transpile_with(DepylerPipeline::new().with_class_modules(), SOURCE);
+
+    assert!(code.contains("pubmodstack{usesuper::*;"));
+    assert!(code.contains("pubmodcounter{usesuper::*;"));
+    assert!(code.contains("pubusestack::{Stack,StackEmpty};"));
+    assert!(code.contains("pubusecounter::Counter;") || code.contains("pubusecounter::{Counter};"));
+    // Only Stack raises StackEmpty, so it is Stack's error type
+    assert!(!code.contains("pubmodstack_empty"));
+}
+
+#[test]
+fn test_classes_stay_flat_by_default() {
+    let rust = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    assert!(!rust.contains("pub mod stack"));
+    assert!(rust.contains("pub struct Stack"));
+}
@@ -0,0 +1,30 @@
+//! Helpers shared by the integration tests
+//!
+//! Every test file is a crate of its own and uses only some of these.
+#![allow(dead_code)]
+
+use depyler_core::DepylerPipeline;
+
+/// `rust` without whitespace, so assertions don't depend on formatting
+pub fn compact(rust: &str) -> String {
+    rust.split_whitespace().collect()
+}
+
+/// `python` transpiled by `pipeline` and compacted, printing the code for
+/// failing tests to show
+pub fn transpile_with(pipeline: DepylerPipeline, python: &str) -> String {
+    let rust = pipeline.transpile(python).unwrap();
+    println!("Generated code:\n{}", rust);
+    compact(&rust)
+}
+
+/// `python` transpiled by the default pipeline and compacted
+pub fn transpile(python: &str) -> String {
+    transpile_with(DepylerPipeline::new(), python)
+}
+
+/// The message of the error transpiling `python` fails with
+pub fn transpile_error(python: &str) -> String {
+    let err = DepylerPipeline::new().transpile(python).unwrap_err();
+    format!("{:#}", err)
+}
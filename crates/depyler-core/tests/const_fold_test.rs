@@ -0,0 +1,73 @@
+//! Constant folding through the pipeline
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+fn transpile(python: &str) -> (String, usize) {
+    let (rust_code, metrics) = DepylerPipeline::new()
+        .with_const_folding()
+        .transpile_with_metrics(python)
+        .unwrap();
+    println!("Generated code:\n{}", rust_code);
+    (compact(&rust_code), metrics.nodes_simplified)
+}
+
+#[test]
+fn test_literal_arithmetic_is_folded() {
+    let (code, simplified) = transpile(
+        r#"
+def seconds_per_day() -> int:
+    return 24 * 60 * 60
+"#,
+    );
+
+    assert!(code.contains("86400"));
+    assert!(!code.contains("24*60"));
+    assert_eq!(simplified, 2);
+}
+
+#[test]
+fn test_single_assignment_is_propagated() {
+    let (code, _) = transpile(
+        r#"
+def scale(x: float) -> float:
+    factor = 2.5
+    return x * factor
+"#,
+    );
+
+    assert!(!code.contains("factor"));
+    assert!(code.contains("2.5"));
+}
+
+#[test]
+fn test_debug_branch_is_removed() {
+    let (code, _) = transpile(
+        r#"
+def compute(x: int) -> int:
+    verbose = False
+    if verbose:
+        print(x)
+    return x + 1
+"#,
+    );
+
+    assert!(!code.contains("println!"));
+    assert!(!code.contains("verbose"));
+}
+
+#[test]
+fn test_folding_is_off_by_default() {
+    let python = r#"
+def seconds_per_day() -> int:
+    return 24 * 60 * 60
+"#;
+    let (rust_code, metrics) = DepylerPipeline::new()
+        .transpile_with_metrics(python)
+        .unwrap();
+
+    assert_eq!(metrics.nodes_simplified, 0);
+    assert_eq!(rust_code, DepylerPipeline::new().transpile(python).unwrap());
+}
@@ -0,0 +1,64 @@
+//! `eval()` of a constant expression is transpiled as that expression; any
+//! other `eval()` or `exec()` is reported with its line
+
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_eval_of_constant_arithmetic_is_folded() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def timeout() -> int:
+    return eval("60 * 60") + eval(" 2 ** 4 ")
+"#,
+        )
+        .unwrap();
+    assert!(!rust_code.contains("eval"), "{}", rust_code);
+}
+
+#[test]
+fn test_eval_of_a_constant_default_is_folded() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def retries() -> list[int]:
+    return eval("[1, 2, 4]")
+"#,
+        )
+        .unwrap();
+    assert!(!rust_code.contains("eval"), "{}", rust_code);
+    assert!(rust_code.contains("vec!"), "{}", rust_code);
+}
+
+#[test]
+fn test_eval_of_names_is_rejected() {
+    let err = DepylerPipeline::new()
+        .transpile(
+            r#"
+def scale(x: int) -> int:
+    return eval("x * 2")
+"#,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("eval() and exec() are not supported"),
+        "{}",
+        err
+    );
+    assert!(err.contains("line 3, column 5: `eval(\"x * 2\")` in `scale`"));
+}
+
+#[test]
+fn test_exec_is_rejected() {
+    let err = DepylerPipeline::new()
+        .transpile(
+            r#"
+def run(code: str) -> None:
+    exec(code)
+"#,
+        )
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("line 3, column 5: `exec(code)` in `run` runs Python code at runtime"));
+}
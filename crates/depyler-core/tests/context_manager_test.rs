@@ -0,0 +1,113 @@
+//! `with` statements over files, locks and classes with `__exit__`
+
+mod common;
+
+use common::transpile;
+
+#[test]
+fn test_open_binds_a_file_closed_by_scope() {
+    let code = transpile(
+        r#"
+def read_file(path: str) -> str:
+    with open(path) as f:
+        return f.read()
+"#,
+    );
+
+    assert!(code.contains("letf=std::fs::File::open(&path)"));
+    assert!(code.contains("std::io::read_to_string(&f)"));
+    assert!(!code.contains("__enter__"));
+}
+
+#[test]
+fn test_open_modes() {
+    let code = transpile(
+        r#"
+def save(path: str, text: str) -> None:
+    with open(path, "w") as f:
+        f.write(text)
+
+def log(path: str, line: str) -> None:
+    with open(path, mode="a") as f:
+        f.write(line)
+"#,
+    );
+
+    assert!(code.contains("std::fs::File::create(&path)"));
+    assert!(code.contains("std::fs::OpenOptions::new().append(true).create(true).open(&path)"));
+    assert!(code.contains("std::io::Write::write_all(&mut&f,"));
+}
+
+#[test]
+fn test_lock_is_held_for_the_block() {
+    let code = transpile(
+        r#"
+import threading
+
+def bump(counts: List[int]) -> None:
+    lock = threading.Lock()
+    with lock:
+        counts.append(1)
+"#,
+    );
+
+    assert!(code.contains("letlock=std::sync::Mutex::new(());"));
+    assert!(
+        code.contains("let_guard=lock.lock().unwrap_or_else(std::sync::PoisonError::into_inner);")
+    );
+}
+
+const TRANSACTION: &str = r#"
+class Transaction:
+    def __init__(self, name: str):
+        self.name = name
+        self.open = False
+
+    def __enter__(self):
+        self.open = True
+        return self
+
+    def __exit__(self, exc_type, exc, tb):
+        self.open = False
+
+    def describe(self) -> str:
+        return self.name
+
+def run(name: str) -> str:
+    with Transaction(name) as tx:
+        return tx.describe()
+"#;
+
+#[test]
+fn test_class_exit_runs_from_a_drop_guard() {
+    let code = transpile(TRANSACTION);
+
+    assert!(code.contains("pubstructTransactionExit<'a>(&'amutTransaction);"));
+    assert!(code.contains("implDropforTransactionExit<'_>"));
+    assert!(code.contains(
+        "self.0.__exit__(serde_json::Value::Null,serde_json::Value::Null,serde_json::Value::Null);"
+    ));
+    assert!(code.contains("implstd::ops::DerefforTransactionExit<'_>"));
+}
+
+#[test]
+fn test_enter_returning_self_binds_the_guard() {
+    let code = transpile(TRANSACTION);
+
+    assert!(code.contains("letmut_manager=Transaction::new(name"));
+    assert!(code.contains("_manager.__enter__();lettx=TransactionExit(&mut_manager);"));
+}
+
+#[test]
+fn test_unused_guards_are_not_generated() {
+    let python = r#"
+class Resource:
+    def __enter__(self):
+        return self
+
+    def __exit__(self, exc_type, exc, tb):
+        pass
+"#;
+    let code = transpile(python);
+    assert!(!code.contains("ResourceExit"));
+}
@@ -0,0 +1,163 @@
+//! contextlib support: `@contextmanager`, `suppress`, `ExitStack`
+
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+#[test]
+fn test_contextmanager_becomes_guard() {
+    let python = r#"
+from contextlib import contextmanager
+
+@contextmanager
+def tag(name: str):
+    print("<" + name + ">")
+    yield
+    print("</" + name + ">")
+
+def render() -> None:
+    with tag("p"):
+        print("hello")
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub struct ContextGuard"));
+    assert!(rust_code.contains("impl Drop for ContextGuard"));
+    assert!(rust_code.contains("ContextGuard::new(move ||"));
+    assert!(rust_code.contains("let (_, _guard) = tag("));
+    assert!(!rust_code.contains("impl Iterator"));
+}
+
+#[test]
+fn test_contextmanager_try_finally_binds_value() {
+    let python = r#"
+import contextlib
+from typing import Iterator
+
+@contextlib.contextmanager
+def counter(start: int) -> Iterator[int]:
+    value = start * 2
+    try:
+        yield value
+    finally:
+        print("done")
+
+def use_counter() -> int:
+    with counter(3) as n:
+        return n
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("(i32, ContextGuard<'static>)"));
+    assert!(rust_code.contains("let (n, _guard) = counter("));
+    assert!(!rust_code.contains("TODO: Map Python module 'contextlib'"));
+}
+
+#[test]
+fn test_suppress_drops_only_the_suppressed_errors() {
+    let python = r#"
+from contextlib import suppress
+
+def safe_parse(s: str) -> int:
+    result = 0
+    with suppress(ValueError):
+        result = int(s)
+    return result
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("if let Err(err) = (|| -> Result<(), Box<dyn std::error::Error>>"));
+    assert!(rust_code.contains("err.is::<ValueError>()"));
+}
+
+#[test]
+fn test_suppress_propagates_other_errors() {
+    let python = r#"
+from contextlib import suppress
+
+def check(n: int) -> int:
+    with suppress(ValueError):
+        if n < 0:
+            raise ValueError("negative")
+        if n == 0:
+            raise TypeError("zero")
+    return n
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    let checks = r##"
+fn main() {
+    assert_eq!(check(5).unwrap(), 5);
+    assert_eq!(check(-1).unwrap(), -1);
+    let err = check(0).unwrap_err();
+    assert!(err.is::<TypeError>());
+    assert_eq!(err.to_string(), "zero");
+}
+"##;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("suppress.rs");
+    let binary = dir.path().join("suppress");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
+
+#[test]
+fn test_suppress_rejects_leaving_the_block() {
+    let python = r#"
+from contextlib import suppress
+
+def first_number(items: list[str]) -> int:
+    for item in items:
+        with suppress(ValueError):
+            return int(item)
+    return 0
+"#;
+    let error = DepylerPipeline::new().transpile(python).unwrap_err();
+    let message = format!("{:#}", error);
+    assert!(
+        message.contains("`return` inside `with suppress(...)` is not supported"),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn test_exit_stack_runs_callbacks_in_reverse() {
+    let python = r#"
+from contextlib import ExitStack
+
+def cleanup(name: str) -> None:
+    print(name)
+
+def run() -> None:
+    with ExitStack() as stack:
+        stack.callback(cleanup, "first")
+        stack.callback(cleanup, "second")
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub struct ExitStack"));
+    assert!(rust_code.contains("let mut stack = ExitStack::new()"));
+    assert!(rust_code.contains("stack.callback(move ||"));
+    assert!(rust_code.contains("self.callbacks.pop()"));
+}
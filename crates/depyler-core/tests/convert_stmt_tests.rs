@@ -6,8 +6,8 @@
 
 use depyler_core::direct_rules::apply_rules;
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::type_mapper::TypeMapper;
-use smallvec::smallvec;
 
 /// Helper to create an empty HIR module
 fn create_empty_module() -> HirModule {
@@ -22,15 +22,7 @@ fn create_empty_module() -> HirModule {
 
 /// Helper to create a simple function with given statements
 fn create_function_with_body(name: &str, body: Vec<HirStmt>) -> HirFunction {
-    HirFunction {
-        name: name.to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body,
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    }
+    HirFunctionBuilder::new(name).body(body).build()
 }
 
 // ============================================================================
@@ -230,15 +222,12 @@ fn test_assign_attribute_nested() {
 fn test_return_with_value() {
     let mut module = create_empty_module();
 
-    module.functions.push(HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42))))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    });
+    module.functions.push(
+        HirFunctionBuilder::new("test")
+            .returns(Type::Int)
+            .stmt(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42)))))
+            .build(),
+    );
 
     let type_mapper = TypeMapper::new();
     let result = apply_rules(&module, &type_mapper);
@@ -263,19 +252,16 @@ fn test_return_without_value() {
 fn test_return_complex_expr() {
     let mut module = create_empty_module();
 
-    module.functions.push(HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
-            op: BinOp::Add,
-            left: Box::new(HirExpr::Literal(Literal::Int(1))),
-            right: Box::new(HirExpr::Literal(Literal::Int(2))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    });
+    module.functions.push(
+        HirFunctionBuilder::new("test")
+            .returns(Type::Int)
+            .stmt(HirStmt::Return(Some(HirExpr::Binary {
+                op: BinOp::Add,
+                left: Box::new(HirExpr::Literal(Literal::Int(1))),
+                right: Box::new(HirExpr::Literal(Literal::Int(2))),
+            })))
+            .build(),
+    );
 
     let type_mapper = TypeMapper::new();
     let result = apply_rules(&module, &type_mapper);
@@ -0,0 +1,55 @@
+//! Generators driven as coroutines are rejected with their usage sites
+
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_throw_into_generator_parameter_is_reported() {
+    let python = r#"
+from typing import Generator
+
+def stop(gen: Generator[int, None, None]) -> None:
+    gen.throw(ValueError("stop"))
+"#;
+    let error = DepylerPipeline::new().transpile(python).unwrap_err();
+    let message = error.to_string();
+
+    assert!(message.contains("Generators used as coroutines are not supported"));
+    assert!(message
+        .contains("line 5, column 5: `gen.throw(...)` in `stop` raises into generator `gen`"));
+}
+
+#[test]
+fn test_send_on_fresh_generator_names_the_generator() {
+    let python = r#"
+def numbers():
+    yield 1
+    yield 2
+
+def first() -> int:
+    return numbers().send(None)
+"#;
+    let message = DepylerPipeline::new()
+        .transpile(python)
+        .unwrap_err()
+        .to_string();
+
+    assert!(message.contains(
+        "line 7, column 5: `numbers(...).send(...)` in `first` sends a value into generator `numbers`"
+    ));
+}
+
+#[test]
+fn test_generators_used_as_iterators_still_transpile() {
+    let python = r#"
+def numbers():
+    yield 1
+    yield 2
+
+def total() -> int:
+    result = 0
+    for n in numbers():
+        result = result + n
+    return result
+"#;
+    assert!(DepylerPipeline::new().transpile(python).is_ok());
+}
@@ -0,0 +1,81 @@
+//! `string_strategy = "cow"` returns `Cow<str>` from functions that hand
+//! back their argument on some paths and a new string on others
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r##"
+# @depyler: string_strategy = "cow"
+def normalize(s: str) -> str:
+    if s.startswith("#"):
+        return s
+    result = s.lower()
+    return result
+
+# @depyler: string_strategy = "cow"
+def squash(s: str) -> str:
+    return s if " " not in s else s.replace(" ", "")
+
+def lower(s: str) -> str:
+    if s.startswith("#"):
+        return s
+    return s.lower()
+
+def label(s: str) -> str:
+    return normalize(s) + ":"
+"##;
+
+#[test]
+fn test_annotated_functions_return_cow() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("usestd::borrow::Cow;"));
+    assert!(code.contains("fnnormalize<'a>(s:&'astr)->Cow<'a,str>"));
+    assert!(code.contains("returnCow::Borrowed(s);"));
+    assert!(code.contains("Cow::Owned(result)"));
+    assert!(code.contains("fnsquash<'a>(s:&'astr)->Cow<'a,str>"));
+    // Without the annotation the return stays owned
+    assert!(!code.contains("fnlower<'a>"));
+    assert!(code.contains("normalize(&s).into_owned()"));
+}
+
+#[test]
+fn test_cow_functions_borrow_at_run_time() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r##"
+fn main() {
+    assert!(matches!(normalize("#Tag"), Cow::Borrowed("#Tag")));
+    assert!(matches!(normalize("Tag"), Cow::Owned(_)));
+    assert_eq!(normalize("Tag"), "tag");
+    assert!(matches!(squash("ab"), Cow::Borrowed("ab")));
+    assert_eq!(squash("a b c"), "abc");
+    assert_eq!(label("X".into()), "x:");
+}
+"##;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("cow.rs");
+    let binary = dir.path().join("cow");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,35 @@
+//! Tests for dropping dead stores during code generation
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+const PYTHON: &str = r#"
+def label(count: int) -> str:
+    text = "none"
+    scratch = count * 2
+    text = "some"
+    report = str(count)
+    return text
+"#;
+
+#[test]
+fn test_dead_stores_kept_by_default() {
+    let rust_code = compact(&DepylerPipeline::new().transpile(PYTHON).unwrap());
+
+    assert!(rust_code.contains("\"none\""));
+    assert!(rust_code.contains("scratch"));
+}
+
+#[test]
+fn test_side_effect_free_dead_stores_are_elided() {
+    let pipeline = DepylerPipeline::new().with_dead_store_elision();
+    let rust_code = compact(&pipeline.transpile(PYTHON).unwrap());
+
+    assert!(!rust_code.contains("\"none\""));
+    assert!(!rust_code.contains("scratch"));
+    assert!(rust_code.contains("\"some\""));
+    // Calls may have side effects, so their stores stay
+    assert!(rust_code.contains("report"));
+}
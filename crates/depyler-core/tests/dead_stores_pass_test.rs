@@ -0,0 +1,80 @@
+//! The `dead-stores` pass drops stores nothing reads when computing them has
+//! no effect Python could observe, and marks the variables nothing reads
+//! with a leading `_`
+
+mod common;
+
+use common::transpile_with;
+use depyler_core::optimize::PassOptions;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def square(x: int) -> int:
+    return x * x
+
+def shout(message: str) -> int:
+    print(message)
+    return 1
+
+def total(items: list[int]) -> int:
+    unused = square(3)
+    noisy = shout("total")
+    result = 0
+    for item in items:
+        result = result + item
+    return result
+
+def first(pair: tuple[int, int]) -> int:
+    a, b = pair
+    return a
+"#;
+
+fn pipeline() -> DepylerPipeline {
+    DepylerPipeline::new().with_pass_options(PassOptions {
+        toggles: [("dead-stores".to_string(), true)].into(),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_pass_is_off_by_default() {
+    let code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    assert!(code.contains("let unused"));
+    assert!(code.contains("let noisy"));
+}
+
+#[test]
+fn test_dead_stores_and_unused_bindings() {
+    let code = transpile_with(pipeline(), SOURCE);
+
+    // `square` is pure, so nothing observes the call going away
+    assert!(!code.contains("unused"));
+    // `shout` prints, so its call stays and only the binding is marked
+    assert!(code.contains(r#"let_noisy=shout("total""#));
+    assert!(code.contains("_b"));
+    assert!(code.contains("letmutresult=0;"));
+}
+
+#[test]
+fn test_cleaned_up_code_has_no_unused_variables() {
+    let rust_code = pipeline().transpile(SOURCE).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("dead_stores.rs");
+    std::fs::write(&source, &rust_code).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "--crate-type", "lib"])
+        .args(["-D", "unused_variables", "-D", "unused_assignments"])
+        .arg("--out-dir")
+        .arg(dir.path())
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile without unused variables:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,125 @@
+//! Decorators handled through the decorator registry
+
+mod common;
+
+use common::transpile;
+use depyler_core::decorators::{Decorator, DecoratorHandler, DecoratorRegistry};
+use depyler_core::hir::{HirFunction, Memoize};
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_lru_cache_memoizes_in_a_thread_local_map() {
+    let code = transpile(
+        r#"
+import functools
+
+@functools.lru_cache(maxsize=None)
+def fib(n: int) -> int:
+    if n < 2:
+        return n
+    return fib(n - 1) + fib(n - 2)
+"#,
+    );
+
+    assert!(
+        code.contains("staticFIB_CACHE:std::cell::RefCell<std::collections::HashMap<(i32,),i32>>")
+    );
+    assert!(code.contains("letcache_key=(n,);"));
+    assert!(code.contains("returncached;"));
+    assert!(code.contains("cache.insert(cache_key,result.clone());"));
+    assert!(!code.contains("cache.clear()"));
+}
+
+#[test]
+fn test_bounded_cache_is_emptied_when_full() {
+    let code = transpile(
+        r#"
+from functools import lru_cache
+
+@lru_cache(maxsize=32)
+def shout(word: str, times: int) -> str:
+    return word.upper() + str(times)
+"#,
+    );
+
+    assert!(code.contains("letcache_key=(word.to_string(),times,);"));
+    assert!(code.contains("ifcache.len()>=32usize{cache.clear();}"));
+}
+
+#[test]
+fn test_unhashable_arguments_are_not_cached() {
+    let code = transpile(
+        r#"
+from functools import cache
+
+@cache
+def half(x: float) -> float:
+    return x / 2.0
+"#,
+    );
+
+    assert!(code.contains("fnhalf(x:f64)->f64"));
+    assert!(!code.contains("HALF_CACHE"));
+}
+
+#[test]
+fn test_method_decorators_set_the_method_kind() {
+    let hir = DepylerPipeline::new()
+        .parse_to_hir(
+            r#"
+class Circle:
+    def __init__(self, radius: float):
+        self.radius = radius
+
+    @property
+    def area(self) -> float:
+        return 3.14 * self.radius * self.radius
+
+    @staticmethod
+    def unit() -> float:
+        return 1.0
+
+    @classmethod
+    def of(cls, radius: float) -> float:
+        return radius
+"#,
+        )
+        .unwrap();
+
+    let methods = &hir.classes[0].methods;
+    let area = methods.iter().find(|m| m.name == "area").unwrap();
+    assert!(area.is_property && area.params.is_empty());
+    let unit = methods.iter().find(|m| m.name == "unit").unwrap();
+    assert!(unit.is_static);
+    let of = methods.iter().find(|m| m.name == "of").unwrap();
+    assert!(of.is_classmethod);
+    assert_eq!(of.params.len(), 1);
+}
+
+#[test]
+fn test_custom_registry_replaces_the_standard_handlers() {
+    struct Cached;
+
+    impl DecoratorHandler for Cached {
+        fn apply_to_function(
+            &self,
+            _: &Decorator,
+            function: &mut HirFunction,
+        ) -> anyhow::Result<()> {
+            function.properties.memoize = Some(Memoize { max_size: Some(8) });
+            Ok(())
+        }
+    }
+
+    let mut decorators = DecoratorRegistry::new();
+    decorators.register("app.cached", Cached);
+    let hir = DepylerPipeline::new()
+        .with_decorators(decorators)
+        .parse_to_hir("@app.cached\n@lru_cache\ndef one() -> int:\n    return 1\n")
+        .unwrap();
+
+    assert_eq!(
+        hir.functions[0].properties.memoize,
+        Some(Memoize { max_size: Some(8) })
+    );
+}
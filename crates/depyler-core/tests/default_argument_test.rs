@@ -0,0 +1,79 @@
+//! Parameters with defaults take an `Option` filled in by the function, and
+//! calls to them bind keyword arguments and pass `None` for omitted ones
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def step(n: int, by: int = 1) -> int:
+    return n + by
+
+def scale(x: float, factor: float = 2) -> float:
+    return x * factor
+
+def label(name: str, prefix: str = "item") -> str:
+    return f"{prefix}-{name}"
+
+def collect(x: int, acc: list[int] = []) -> list[int]:
+    acc.append(x)
+    return acc
+
+def use_defaults() -> int:
+    return step(1) + step(1, 5) + step(n=2, by=3) + len(collect(7)) + len(collect(1, [2, 3]))
+"#;
+
+#[test]
+fn test_defaults_lowering() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("fnstep(n:i32,by:Option<i32>)->i32"));
+    assert!(code.contains("letby=by.unwrap_or(1);"));
+    assert!(code.contains("letfactor=factor.unwrap_or(2.0);"));
+    assert!(code.contains("letmutacc=acc.unwrap_or_default();"));
+    assert!(code.contains("step(1,None)"));
+    assert!(code.contains("step(1,Some(5))"));
+    assert!(code.contains("step(2,Some(3))"));
+    assert!(code.contains("collect(1,Some(vec![2,3]))"));
+}
+
+#[test]
+fn test_defaults_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(step(1, None), 2);
+    assert_eq!(step(1, Some(5)), 6);
+    assert_eq!(scale(1.5, None), 3.0);
+    assert_eq!(label("x".into(), None), "item-x");
+    assert_eq!(label("x".into(), Some("tag".into())), "tag-x");
+    assert_eq!(collect(1, None), vec![1]);
+    assert_eq!(use_defaults(), 17);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("defaults.rs");
+    let binary = dir.path().join("defaults");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -1,33 +1,21 @@
 // EXTREME TDD: Tests for default parameter functionality (DEPYLER-0104)
 // These tests define the expected behavior BEFORE implementation
 
-use depyler_annotations::TranspilationAnnotations;
-use depyler_core::hir::{FunctionProperties, HirExpr, HirFunction, HirParam, Literal, Type};
-use smallvec::smallvec;
+use depyler_core::hir::{HirExpr, HirParam, Literal, Type};
+use depyler_core::hir_builder::{expr, HirFunctionBuilder};
 
 /// Test 1: Function with one default parameter (None)
 #[test]
 fn test_function_with_none_default() {
-    let func = HirFunction {
-        name: "greet".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "name".to_string(),
-                ty: Type::String,
-                default: None,
-            },
-            HirParam {
-                name: "greeting".to_string(),
-                ty: Type::Optional(Box::new(Type::String)),
-                default: Some(HirExpr::Literal(Literal::None)),
-            },
-        ],
-        ret_type: Type::String,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("greet")
+        .param("name", Type::String)
+        .param_with_default(
+            "greeting",
+            Type::Optional(Box::new(Type::String)),
+            expr::none(),
+        )
+        .returns(Type::String)
+        .build();
 
     assert_eq!(func.params.len(), 2);
     assert_eq!(func.params[0].name, "name");
@@ -39,26 +27,11 @@ fn test_function_with_none_default() {
 /// Test 2: Function with integer default value
 #[test]
 fn test_function_with_int_default() {
-    let func = HirFunction {
-        name: "increment".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "x".to_string(),
-                ty: Type::Int,
-                default: None,
-            },
-            HirParam {
-                name: "step".to_string(),
-                ty: Type::Int,
-                default: Some(HirExpr::Literal(Literal::Int(1))),
-            },
-        ],
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("increment")
+        .param("x", Type::Int)
+        .param_with_default("step", Type::Int, expr::int(1))
+        .returns(Type::Int)
+        .build();
 
     assert_eq!(func.params.len(), 2);
     assert_eq!(func.params[1].name, "step");
@@ -71,26 +44,11 @@ fn test_function_with_int_default() {
 /// Test 3: Function with string default value
 #[test]
 fn test_function_with_string_default() {
-    let func = HirFunction {
-        name: "log".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "message".to_string(),
-                ty: Type::String,
-                default: None,
-            },
-            HirParam {
-                name: "level".to_string(),
-                ty: Type::String,
-                default: Some(HirExpr::Literal(Literal::String("INFO".to_string()))),
-            },
-        ],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("log")
+        .param("message", Type::String)
+        .param_with_default("level", Type::String, expr::string("INFO"))
+        .returns(Type::None)
+        .build();
 
     match &func.params[1].default {
         Some(HirExpr::Literal(Literal::String(s))) if s == "INFO" => {}
@@ -101,26 +59,11 @@ fn test_function_with_string_default() {
 /// Test 4: Function with boolean default value
 #[test]
 fn test_function_with_bool_default() {
-    let func = HirFunction {
-        name: "process".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "data".to_string(),
-                ty: Type::String,
-                default: None,
-            },
-            HirParam {
-                name: "verbose".to_string(),
-                ty: Type::Bool,
-                default: Some(HirExpr::Literal(Literal::Bool(false))),
-            },
-        ],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("process")
+        .param("data", Type::String)
+        .param_with_default("verbose", Type::Bool, expr::bool(false))
+        .returns(Type::None)
+        .build();
 
     match &func.params[1].default {
         Some(HirExpr::Literal(Literal::Bool(false))) => {}
@@ -131,19 +74,10 @@ fn test_function_with_bool_default() {
 /// Test 5: Function with list default (empty list)
 #[test]
 fn test_function_with_empty_list_default() {
-    let func = HirFunction {
-        name: "extend".to_string(),
-        params: smallvec![HirParam {
-            name: "items".to_string(),
-            ty: Type::List(Box::new(Type::Int)),
-            default: Some(HirExpr::List(vec![])),
-        },],
-        ret_type: Type::List(Box::new(Type::Int)),
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("extend")
+        .param_with_default("items", Type::List(Box::new(Type::Int)), expr::list(vec![]))
+        .returns(Type::List(Box::new(Type::Int)))
+        .build();
 
     match &func.params[0].default {
         Some(HirExpr::List(items)) if items.is_empty() => {}
@@ -154,29 +88,18 @@ fn test_function_with_empty_list_default() {
 /// Test 6: Function with dict/HashMap default (None → empty dict pattern)
 #[test]
 fn test_function_with_dict_none_default() {
-    let func = HirFunction {
-        name: "fibonacci_memo".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "n".to_string(),
-                ty: Type::Int,
-                default: None,
-            },
-            HirParam {
-                name: "memo".to_string(),
-                ty: Type::Optional(Box::new(Type::Dict(
-                    Box::new(Type::Int),
-                    Box::new(Type::Int)
-                ))),
-                default: Some(HirExpr::Literal(Literal::None)),
-            },
-        ],
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("fibonacci_memo")
+        .param("n", Type::Int)
+        .param_with_default(
+            "memo",
+            Type::Optional(Box::new(Type::Dict(
+                Box::new(Type::Int),
+                Box::new(Type::Int),
+            ))),
+            expr::none(),
+        )
+        .returns(Type::Int)
+        .build();
 
     // This is the fibonacci_memo.py pattern: def fib(n, memo: Dict[int, int] = None)
     assert_eq!(func.params[1].name, "memo");
@@ -190,31 +113,12 @@ fn test_function_with_dict_none_default() {
 /// Test 7: Multiple defaults in sequence
 #[test]
 fn test_function_with_multiple_defaults() {
-    let func = HirFunction {
-        name: "configure".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "host".to_string(),
-                ty: Type::String,
-                default: Some(HirExpr::Literal(Literal::String("localhost".to_string()))),
-            },
-            HirParam {
-                name: "port".to_string(),
-                ty: Type::Int,
-                default: Some(HirExpr::Literal(Literal::Int(8080))),
-            },
-            HirParam {
-                name: "debug".to_string(),
-                ty: Type::Bool,
-                default: Some(HirExpr::Literal(Literal::Bool(false))),
-            },
-        ],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("configure")
+        .param_with_default("host", Type::String, expr::string("localhost"))
+        .param_with_default("port", Type::Int, expr::int(8080))
+        .param_with_default("debug", Type::Bool, expr::bool(false))
+        .returns(Type::None)
+        .build();
 
     assert!(func.params.iter().all(|p| p.default.is_some()));
 }
@@ -222,26 +126,11 @@ fn test_function_with_multiple_defaults() {
 /// Test 8: No default parameters (backward compatibility)
 #[test]
 fn test_function_with_no_defaults() {
-    let func = HirFunction {
-        name: "add".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "a".to_string(),
-                ty: Type::Int,
-                default: None,
-            },
-            HirParam {
-                name: "b".to_string(),
-                ty: Type::Int,
-                default: None,
-            },
-        ],
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("add")
+        .param("a", Type::Int)
+        .param("b", Type::Int)
+        .returns(Type::Int)
+        .build();
 
     assert!(func.params.iter().all(|p| p.default.is_none()));
 }
@@ -249,31 +138,12 @@ fn test_function_with_no_defaults() {
 /// Test 9: Mixed parameters (some with defaults, some without)
 #[test]
 fn test_function_with_mixed_defaults() {
-    let func = HirFunction {
-        name: "fetch".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "url".to_string(),
-                ty: Type::String,
-                default: None,
-            },
-            HirParam {
-                name: "timeout".to_string(),
-                ty: Type::Int,
-                default: Some(HirExpr::Literal(Literal::Int(30))),
-            },
-            HirParam {
-                name: "retries".to_string(),
-                ty: Type::Int,
-                default: Some(HirExpr::Literal(Literal::Int(3))),
-            },
-        ],
-        ret_type: Type::String,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("fetch")
+        .param("url", Type::String)
+        .param_with_default("timeout", Type::Int, expr::int(30))
+        .param_with_default("retries", Type::Int, expr::int(3))
+        .returns(Type::String)
+        .build();
 
     assert_eq!(func.params[0].default, None);
     assert!(func.params[1].default.is_some());
@@ -283,26 +153,11 @@ fn test_function_with_mixed_defaults() {
 /// Test 10: Float default value
 #[test]
 fn test_function_with_float_default() {
-    let func = HirFunction {
-        name: "scale".to_string(),
-        params: smallvec![
-            HirParam {
-                name: "value".to_string(),
-                ty: Type::Float,
-                default: None,
-            },
-            HirParam {
-                name: "factor".to_string(),
-                ty: Type::Float,
-                default: Some(HirExpr::Literal(Literal::Float(1.0))),
-            },
-        ],
-        ret_type: Type::Float,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("scale")
+        .param("value", Type::Float)
+        .param_with_default("factor", Type::Float, expr::float(1.0))
+        .returns(Type::Float)
+        .build();
 
     match &func.params[1].default {
         Some(HirExpr::Literal(Literal::Float(f))) if (*f - 1.0).abs() < f64::EPSILON => {}
@@ -330,11 +185,7 @@ fn test_hir_param_constructor() {
 /// Test 12: Serialization/deserialization of HirParam
 #[test]
 fn test_hir_param_serde() {
-    let param = HirParam {
-        name: "count".to_string(),
-        ty: Type::Int,
-        default: Some(HirExpr::Literal(Literal::Int(0))),
-    };
+    let param = HirParam::with_default("count".to_string(), Type::Int, expr::int(0));
 
     let json = serde_json::to_string(&param).unwrap();
     let deserialized: HirParam = serde_json::from_str(&json).unwrap();
@@ -70,11 +70,11 @@ def parse_and_divide(s1: str, s2: str) -> int:
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Should use .parse::<i32>() for both string variables
-    let parse_count = rust_code.matches(".parse::<i32>()").count();
+    // Should parse both string variables with py_int()
+    let parse_count = rust_code.matches("py_int(&").count();
     assert!(
         parse_count >= 2,
-        "Should use .parse::<i32>() for both string variables (found {})",
+        "Should use py_int() for both string variables (found {})",
         parse_count
     );
 }
@@ -202,8 +202,8 @@ fn main() {
     assert!(result.is_err());
     let error = result.unwrap_err();
     let error_msg = format!("{}", error);
-    assert!(error_msg.contains("value error"));
-    assert!(error_msg.contains("negative value"));
+    // Like str(e) in Python, the error displays as its message
+    assert_eq!(error_msg, "negative value");
 }
 "#
     );
@@ -339,14 +339,20 @@ def check(x: int) -> int:
     return x
 "#;
 
-    let pipeline = DepylerPipeline::new();
+    let pipeline = DepylerPipeline::new().with_native_error_messages();
     let rust_code = pipeline
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Display implementation should use "value error:" prefix
+    // With native messages, Display uses the "value error:" prefix
     assert!(
         rust_code.contains(r#"write!(f, "value error: {}", self.message)"#),
         "Display should format with 'value error:' prefix"
     );
+
+    // By default it is just the message, like str(e) in Python
+    let rust_code = DepylerPipeline::new()
+        .transpile(python_code)
+        .expect("Transpilation failed");
+    assert!(rust_code.contains(r#"write!(f, "{}", self.message)"#));
 }
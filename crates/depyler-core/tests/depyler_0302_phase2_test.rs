@@ -25,7 +25,7 @@ def repeat_ab() -> str:
         "Should contain .repeat() method"
     );
     assert!(
-        rust_code.contains("3 as usize"),
+        rust_code.contains("repeat(3usize)"),
         "Should cast count to usize"
     );
 
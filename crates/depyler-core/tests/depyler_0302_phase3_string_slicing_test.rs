@@ -61,10 +61,10 @@ def get_last_n_chars(s: str, n: int) -> str:
         "Should use String::new() not Vec::new()"
     );
 
-    // Should return String type
+    // Should cut the string at char boundaries
     assert!(
-        rust_code.contains("collect::<String>()"),
-        "Should collect into String"
+        rust_code.contains("py_str_slice("),
+        "Should slice through py_str_slice"
     );
 
     println!("✅ Generated Rust code:\n{}", rust_code);
@@ -82,14 +82,14 @@ def get_all_but_last_n(s: str, n: int) -> str:
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Should use .chars() and .take() for string operations
+    // Should count chars for string operations
     assert!(
         rust_code.contains(".chars()"),
         "Should use .chars() for string slicing"
     );
     assert!(
-        rust_code.contains(".take("),
-        "Should use .take() for prefix slicing"
+        rust_code.contains("py_str_slice("),
+        "Should slice through py_str_slice"
     );
 
     // Should NOT use Vec operations
@@ -146,14 +146,14 @@ def substring(s: str, start: int, stop: int) -> str:
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Should use .chars().skip().take() pattern
+    // Should find the byte offsets of the chars the bounds count
     assert!(
         rust_code.contains(".chars()"),
         "Should use .chars() for string slicing"
     );
     assert!(
-        rust_code.contains(".skip(") || rust_code.contains(".take("),
-        "Should use .skip()/.take() for range slicing"
+        rust_code.contains(".char_indices()"),
+        "Should use .char_indices() for range slicing"
     );
 
     println!("✅ Generated Rust code:\n{}", rust_code);
@@ -315,10 +315,13 @@ def full_copy(s: str) -> str:
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Full slice should be simple clone or to_string
+    // Full slice returns the string it was given
     assert!(
-        rust_code.contains(".to_string()") || rust_code.contains(".clone()"),
-        "Full slice should use simple copy"
+        rust_code
+            .split_whitespace()
+            .collect::<String>()
+            .contains("fnfull_copy<'a>(s:&'astr)->&'astr"),
+        "Full slice should borrow the parameter"
     );
 
     println!("✅ Full slice works:\n{}", rust_code);
@@ -72,14 +72,16 @@ def sort_by_abs_descending(numbers: list[int]) -> list[int]:
         .transpile(python_code)
         .expect("Transpilation failed");
 
-    // Should use .sort_by_key() with reverse
+    // Should sort by the key, descending
     assert!(
         rust_code.contains(".sort_by_key(") || rust_code.contains("sort_by"),
         "Should use sort_by_key for custom key"
     );
+    // Comparing swapped keys keeps equal keys in their original order,
+    // where sorting then reversing would flip them
     assert!(
-        rust_code.contains(".reverse()"),
-        "Should use .reverse() when reverse=True with key"
+        !rust_code.contains(".reverse()"),
+        "Should compare swapped keys when reverse=True with key"
     );
 }
 
@@ -11,9 +11,8 @@
 //! - Basic decision-making logic
 
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::inlining::*;
-use depyler_annotations::TranspilationAnnotations;
-use smallvec::smallvec;
 
 // ============================================================================
 // CONFIGURATION TESTS
@@ -98,15 +97,10 @@ fn test_analyze_empty_program() {
 fn test_analyze_single_function_no_calls() {
     let mut analyzer = InliningAnalyzer::new(InliningConfig::default());
 
-    let func = HirFunction {
-        name: "simple".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42))))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("simple")
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42)))))
+        .build();
 
     let program = HirProgram {
         functions: vec![func],
@@ -125,19 +119,15 @@ fn test_analyze_trivial_function() {
     let mut analyzer = InliningAnalyzer::new(InliningConfig::default());
 
     // Trivial function: single return expression
-    let func = HirFunction {
-        name: "add_one".to_string(),
-        params: smallvec![HirParam::new("x".to_string(), Type::Int)],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("add_one")
+        .param("x", Type::Int)
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Add,
             left: Box::new(HirExpr::Var("x".to_string())),
             right: Box::new(HirExpr::Literal(Literal::Int(1))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     let program = HirProgram {
         functions: vec![func],
@@ -168,15 +158,10 @@ fn test_analyze_large_function() {
     }
     body.push(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(0)))));
 
-    let func = HirFunction {
-        name: "large_func".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body,
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("large_func")
+        .returns(Type::Int)
+        .body(body)
+        .build();
 
     let program = HirProgram {
         functions: vec![func],
@@ -196,25 +181,19 @@ fn test_analyze_large_function() {
 fn test_call_graph_simple_call() {
     let mut analyzer = InliningAnalyzer::new(InliningConfig::default());
 
-    let helper = HirFunction {
-        name: "helper".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42))))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let helper = HirFunctionBuilder::new("helper")
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42)))))
+        .build();
 
-    let caller = HirFunction {
-        name: "caller".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Call { func: "helper".to_string(), args: vec![], kwargs: vec![] }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let caller = HirFunctionBuilder::new("caller")
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Call {
+            func: "helper".to_string(),
+            args: vec![],
+            kwargs: vec![],
+        })))
+        .build();
 
     let program = HirProgram {
         functions: vec![helper, caller],
@@ -245,14 +224,10 @@ mod property_tests {
             let mut analyzer = InliningAnalyzer::new(InliningConfig::default());
 
             let functions: Vec<HirFunction> = (0..func_count)
-                .map(|i| HirFunction {
-                    name: format!("func_{}", i),
-                    params: smallvec![],
-                    ret_type: Type::Int,
-                    body: vec![],
-                    properties: FunctionProperties::default(),
-                    annotations: TranspilationAnnotations::default(),
-                    docstring: None,
+                .map(|i| {
+                    HirFunctionBuilder::new(format!("func_{}", i))
+                        .returns(Type::Int)
+                        .build()
                 })
                 .collect();
 
@@ -189,7 +189,6 @@ def test_complex(nums: list) -> list:
 }
 
 #[test]
-#[ignore = "Nested comprehensions with conditions not fully tested"]
 fn test_depyler_0345_nested_comp_with_condition() {
     let pipeline = DepylerPipeline::new();
     let python_code = r#"
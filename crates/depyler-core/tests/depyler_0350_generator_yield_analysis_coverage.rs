@@ -14,8 +14,7 @@
 
 use depyler_core::generator_yield_analysis::*;
 use depyler_core::hir::*;
-use depyler_annotations::TranspilationAnnotations;
-use smallvec::smallvec;
+use depyler_core::hir_builder::HirFunctionBuilder;
 
 // ============================================================================
 // BASIC FUNCTIONALITY TESTS (Complement existing tests)
@@ -24,20 +23,16 @@ use smallvec::smallvec;
 #[test]
 fn test_depyler_0350_no_yields_empty_analysis() {
     // Test: Function with no yields should return empty analysis
-    let func = HirFunction {
-        name: "no_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![
-            HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42)))),
-        ],
-        properties: FunctionProperties {
+    let func = HirFunctionBuilder::new("no_yield")
+        .returns(Type::Int)
+        .body(vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(
+            42,
+        ))))])
+        .properties(FunctionProperties {
             is_generator: false,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -61,20 +56,16 @@ fn test_depyler_0350_default_trait_creates_empty() {
 #[test]
 fn test_depyler_0350_has_yields_true_case() {
     // Test: has_yields() returns true when yields exist
-    let func = HirFunction {
-        name: "with_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Expr(HirExpr::Yield {
+    let func = HirFunctionBuilder::new("with_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::Expr(HirExpr::Yield {
             value: Some(Box::new(HirExpr::Literal(Literal::Int(1)))),
-        })],
-        properties: FunctionProperties {
+        }))
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -89,24 +80,20 @@ fn test_depyler_0350_has_yields_true_case() {
 #[test]
 fn test_depyler_0350_for_loop_with_yield() {
     // Test: Yield inside for loop should be detected with depth tracking
-    let func = HirFunction {
-        name: "for_generator".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::For {
+    let func = HirFunctionBuilder::new("for_generator")
+        .returns(Type::Int)
+        .stmt(HirStmt::For {
             target: AssignTarget::Symbol("i".to_string()),
             iter: HirExpr::Var("items".to_string()),
             body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Var("i".to_string()))),
             })],
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -118,11 +105,9 @@ fn test_depyler_0350_for_loop_with_yield() {
 #[test]
 fn test_depyler_0350_nested_for_loops_with_yields() {
     // Test: Nested for loops with yields should track increasing depth
-    let func = HirFunction {
-        name: "nested_for".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::For {
+    let func = HirFunctionBuilder::new("nested_for")
+        .returns(Type::Int)
+        .stmt(HirStmt::For {
             target: AssignTarget::Symbol("i".to_string()),
             iter: HirExpr::Var("outer".to_string()),
             body: vec![HirStmt::For {
@@ -132,14 +117,12 @@ fn test_depyler_0350_nested_for_loops_with_yields() {
                     value: Some(Box::new(HirExpr::Var("j".to_string()))),
                 })],
             }],
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -154,24 +137,20 @@ fn test_depyler_0350_nested_for_loops_with_yields() {
 #[test]
 fn test_depyler_0350_if_branch_with_yield() {
     // Test: Yield in if branch (no else)
-    let func = HirFunction {
-        name: "if_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::If {
+    let func = HirFunctionBuilder::new("if_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::If {
             condition: HirExpr::Literal(Literal::Bool(true)),
             then_body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(1)))),
             })],
             else_body: None,
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -182,11 +161,9 @@ fn test_depyler_0350_if_branch_with_yield() {
 #[test]
 fn test_depyler_0350_if_else_both_with_yields() {
     // Test: Yields in both if and else branches
-    let func = HirFunction {
-        name: "if_else_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::If {
+    let func = HirFunctionBuilder::new("if_else_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::If {
             condition: HirExpr::Var("x".to_string()),
             then_body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(1)))),
@@ -194,14 +171,12 @@ fn test_depyler_0350_if_else_both_with_yields() {
             else_body: Some(vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(2)))),
             })]),
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -213,11 +188,9 @@ fn test_depyler_0350_if_else_both_with_yields() {
 #[test]
 fn test_depyler_0350_nested_if_with_yields() {
     // Test: Nested if statements with yields
-    let func = HirFunction {
-        name: "nested_if".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::If {
+    let func = HirFunctionBuilder::new("nested_if")
+        .returns(Type::Int)
+        .stmt(HirStmt::If {
             condition: HirExpr::Var("x".to_string()),
             then_body: vec![HirStmt::If {
                 condition: HirExpr::Var("y".to_string()),
@@ -227,14 +200,12 @@ fn test_depyler_0350_nested_if_with_yields() {
                 else_body: None,
             }],
             else_body: None,
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -249,25 +220,21 @@ fn test_depyler_0350_nested_if_with_yields() {
 #[test]
 fn test_depyler_0350_try_block_with_yield() {
     // Test: Yield in try block body
-    let func = HirFunction {
-        name: "try_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Try {
+    let func = HirFunctionBuilder::new("try_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::Try {
             body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(1)))),
             })],
             handlers: vec![],
             orelse: None,
             finalbody: None,
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -277,11 +244,9 @@ fn test_depyler_0350_try_block_with_yield() {
 #[test]
 fn test_depyler_0350_except_handler_with_yield() {
     // Test: Yield in except handler
-    let func = HirFunction {
-        name: "except_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Try {
+    let func = HirFunctionBuilder::new("except_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::Try {
             body: vec![HirStmt::Pass],
             handlers: vec![ExceptHandler {
                 exception_type: Some("Exception".to_string()),
@@ -292,14 +257,12 @@ fn test_depyler_0350_except_handler_with_yield() {
             }],
             orelse: None,
             finalbody: None,
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -309,25 +272,21 @@ fn test_depyler_0350_except_handler_with_yield() {
 #[test]
 fn test_depyler_0350_try_else_with_yield() {
     // Test: Yield in try else clause
-    let func = HirFunction {
-        name: "try_else_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Try {
+    let func = HirFunctionBuilder::new("try_else_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::Try {
             body: vec![HirStmt::Pass],
             handlers: vec![],
             orelse: Some(vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(3)))),
             })]),
             finalbody: None,
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -337,25 +296,21 @@ fn test_depyler_0350_try_else_with_yield() {
 #[test]
 fn test_depyler_0350_finally_with_yield() {
     // Test: Yield in finally block
-    let func = HirFunction {
-        name: "finally_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Try {
+    let func = HirFunctionBuilder::new("finally_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::Try {
             body: vec![HirStmt::Pass],
             handlers: vec![],
             orelse: None,
             finalbody: Some(vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(4)))),
             })]),
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -365,11 +320,9 @@ fn test_depyler_0350_finally_with_yield() {
 #[test]
 fn test_depyler_0350_try_all_sections_with_yields() {
     // Test: Yields in try, except, else, and finally
-    let func = HirFunction {
-        name: "try_complete".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Try {
+    let func = HirFunctionBuilder::new("try_complete")
+        .returns(Type::Int)
+        .stmt(HirStmt::Try {
             body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(1)))),
             })],
@@ -386,14 +339,12 @@ fn test_depyler_0350_try_all_sections_with_yields() {
             finalbody: Some(vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Literal(Literal::Int(4)))),
             })]),
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -412,24 +363,20 @@ fn test_depyler_0350_try_all_sections_with_yields() {
 #[test]
 fn test_depyler_0350_with_statement_yield() {
     // Test: Yield inside with statement
-    let func = HirFunction {
-        name: "with_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::With {
+    let func = HirFunctionBuilder::new("with_yield")
+        .returns(Type::Int)
+        .stmt(HirStmt::With {
             context: HirExpr::Var("ctx".to_string()),
             target: Some("f".to_string()),
             body: vec![HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Var("f".to_string()))),
             })],
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -439,11 +386,9 @@ fn test_depyler_0350_with_statement_yield() {
 #[test]
 fn test_depyler_0350_nested_with_statements() {
     // Test: Nested with statements with yields
-    let func = HirFunction {
-        name: "nested_with".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::With {
+    let func = HirFunctionBuilder::new("nested_with")
+        .returns(Type::Int)
+        .stmt(HirStmt::With {
             context: HirExpr::Var("ctx1".to_string()),
             target: Some("c1".to_string()),
             body: vec![HirStmt::With {
@@ -453,14 +398,12 @@ fn test_depyler_0350_nested_with_statements() {
                     value: Some(Box::new(HirExpr::Var("c2".to_string()))),
                 })],
             }],
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -474,11 +417,9 @@ fn test_depyler_0350_nested_with_statements() {
 #[test]
 fn test_depyler_0350_resume_points_sequential() {
     // Test: Resume points correctly track statement indices
-    let func = HirFunction {
-        name: "resume_test".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![
+    let func = HirFunctionBuilder::new("resume_test")
+        .returns(Type::Int)
+        .body(vec![
             HirStmt::Assign {
                 target: AssignTarget::Symbol("x".to_string()),
                 value: HirExpr::Literal(Literal::Int(1)),
@@ -495,14 +436,12 @@ fn test_depyler_0350_resume_points_sequential() {
             HirStmt::Expr(HirExpr::Yield {
                 value: Some(Box::new(HirExpr::Var("y".to_string()))),
             }),
-        ],
-        properties: FunctionProperties {
+        ])
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -517,25 +456,25 @@ fn test_depyler_0350_resume_points_sequential() {
 #[test]
 fn test_depyler_0350_non_yield_expr_ignored() {
     // Test: Non-yield expressions should not be detected as yields
-    let func = HirFunction {
-        name: "no_yield_expr".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![
-            HirStmt::Expr(HirExpr::Call { func: "print".to_string(), args: vec![HirExpr::Literal(Literal::String("hello".to_string()))], kwargs: vec![] }),
+    let func = HirFunctionBuilder::new("no_yield_expr")
+        .returns(Type::Int)
+        .body(vec![
+            HirStmt::Expr(HirExpr::Call {
+                func: "print".to_string(),
+                args: vec![HirExpr::Literal(Literal::String("hello".to_string()))],
+                kwargs: vec![],
+            }),
             HirStmt::Expr(HirExpr::Binary {
                 op: BinOp::Add,
                 left: Box::new(HirExpr::Literal(Literal::Int(1))),
                 right: Box::new(HirExpr::Literal(Literal::Int(2))),
             }),
-        ],
-        properties: FunctionProperties {
+        ])
+        .properties(FunctionProperties {
             is_generator: false,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -545,18 +484,13 @@ fn test_depyler_0350_non_yield_expr_ignored() {
 #[test]
 fn test_depyler_0350_yield_with_none_value() {
     // Test: Yield with None value (bare yield)
-    let func = HirFunction {
-        name: "bare_yield".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![HirStmt::Expr(HirExpr::Yield { value: None })],
-        properties: FunctionProperties {
+    let func = HirFunctionBuilder::new("bare_yield")
+        .stmt(HirStmt::Expr(HirExpr::Yield { value: None }))
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -571,32 +505,24 @@ fn test_depyler_0350_yield_with_none_value() {
 #[test]
 fn test_depyler_0350_mixed_control_flow() {
     // Test: Complex mix of control flow with multiple yields
-    let func = HirFunction {
-        name: "complex".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![
-            HirStmt::For {
-                target: AssignTarget::Symbol("i".to_string()),
-                iter: HirExpr::Var("items".to_string()),
-                body: vec![
-                    HirStmt::If {
-                        condition: HirExpr::Var("condition".to_string()),
-                        then_body: vec![HirStmt::Expr(HirExpr::Yield {
-                            value: Some(Box::new(HirExpr::Var("i".to_string()))),
-                        })],
-                        else_body: None,
-                    },
-                ],
-            },
-        ],
-        properties: FunctionProperties {
+    let func = HirFunctionBuilder::new("complex")
+        .returns(Type::Int)
+        .body(vec![HirStmt::For {
+            target: AssignTarget::Symbol("i".to_string()),
+            iter: HirExpr::Var("items".to_string()),
+            body: vec![HirStmt::If {
+                condition: HirExpr::Var("condition".to_string()),
+                then_body: vec![HirStmt::Expr(HirExpr::Yield {
+                    value: Some(Box::new(HirExpr::Var("i".to_string()))),
+                })],
+                else_body: None,
+            }],
+        }])
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -607,11 +533,9 @@ fn test_depyler_0350_mixed_control_flow() {
 #[test]
 fn test_depyler_0350_while_loop_multiple_yields() {
     // Test: While loop with multiple yields in body
-    let func = HirFunction {
-        name: "while_multi".to_string(),
-        params: smallvec![],
-        ret_type: Type::Int,
-        body: vec![HirStmt::While {
+    let func = HirFunctionBuilder::new("while_multi")
+        .returns(Type::Int)
+        .stmt(HirStmt::While {
             condition: HirExpr::Literal(Literal::Bool(true)),
             body: vec![
                 HirStmt::Expr(HirExpr::Yield {
@@ -621,14 +545,12 @@ fn test_depyler_0350_while_loop_multiple_yields() {
                     value: Some(Box::new(HirExpr::Literal(Literal::Int(2)))),
                 }),
             ],
-        }],
-        properties: FunctionProperties {
+        })
+        .properties(FunctionProperties {
             is_generator: true,
             ..Default::default()
-        },
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let analysis = YieldAnalysis::analyze(&func);
 
@@ -695,18 +617,14 @@ mod property_tests {
                 }));
             }
 
-            let func = HirFunction {
-                name: "prop_test".to_string(),
-                params: smallvec![],
-                ret_type: Type::Int,
-                body,
-                properties: FunctionProperties {
+            let func = HirFunctionBuilder::new("prop_test")
+                .returns(Type::Int)
+                .body(body)
+                .properties(FunctionProperties {
                     is_generator: true,
                     ..Default::default()
-                },
-                annotations: TranspilationAnnotations::default(),
-                docstring: None,
-            };
+                })
+                .build();
 
             let analysis = YieldAnalysis::analyze(&func);
 
@@ -728,18 +646,14 @@ mod property_tests {
                 }));
             }
 
-            let func = HirFunction {
-                name: "states_test".to_string(),
-                params: smallvec![],
-                ret_type: Type::Int,
-                body,
-                properties: FunctionProperties {
+            let func = HirFunctionBuilder::new("states_test")
+                .returns(Type::Int)
+                .body(body)
+                .properties(FunctionProperties {
                     is_generator: count > 0,
                     ..Default::default()
-                },
-                annotations: TranspilationAnnotations::default(),
-                docstring: None,
-            };
+                })
+                .build();
 
             let analysis = YieldAnalysis::analyze(&func);
 
@@ -764,18 +678,14 @@ mod property_tests {
                 }];
             }
 
-            let func = HirFunction {
-                name: "depth_test".to_string(),
-                params: smallvec![],
-                ret_type: Type::Int,
-                body,
-                properties: FunctionProperties {
+            let func = HirFunctionBuilder::new("depth_test")
+                .returns(Type::Int)
+                .body(body)
+                .properties(FunctionProperties {
                     is_generator: true,
                     ..Default::default()
-                },
-                annotations: TranspilationAnnotations::default(),
-                docstring: None,
-            };
+                })
+                .build();
 
             let analysis = YieldAnalysis::analyze(&func);
 
@@ -16,8 +16,8 @@
 //! NOTE: All tests use PUBLIC APIs only (no direct field access)
 
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::ide::*;
-use depyler_annotations::TranspilationAnnotations;
 use rustpython_parser::text_size::{TextRange, TextSize};
 use smallvec::smallvec;
 
@@ -50,6 +50,8 @@ fn test_depyler_0351_index_class_with_methods() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -91,6 +93,8 @@ fn test_depyler_0351_index_class_with_fields() {
         fields: vec![field],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -155,6 +159,8 @@ fn test_depyler_0351_index_multiple_methods() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -198,15 +204,7 @@ fn test_depyler_0351_index_empty_module() {
 fn test_depyler_0351_index_module_with_functions_and_classes() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "helper".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("helper").build();
 
     let class = HirClass {
         name: "Widget".to_string(),
@@ -214,6 +212,8 @@ fn test_depyler_0351_index_module_with_functions_and_classes() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -236,19 +236,11 @@ fn test_depyler_0351_index_module_with_functions_and_classes() {
 fn test_depyler_0351_index_function_with_multiple_params() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "process".to_string(),
-        params: smallvec![
-            HirParam::new("a".to_string(), Type::Int),
-            HirParam::new("b".to_string(), Type::String),
-            HirParam::new("c".to_string(), Type::Bool),
-        ],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("process")
+        .param("a", Type::Int)
+        .param("b", Type::String)
+        .param("c", Type::Bool)
+        .build();
 
     let module = HirModule {
         functions: vec![func],
@@ -291,6 +283,8 @@ fn test_depyler_0351_index_method_detail_format() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -331,6 +325,8 @@ fn test_depyler_0351_index_field_type_annotation() {
         fields: vec![field],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -360,15 +356,7 @@ fn test_depyler_0351_index_field_type_annotation() {
 fn test_depyler_0351_symbol_at_position_found() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -390,15 +378,7 @@ fn test_depyler_0351_symbol_at_position_found() {
 fn test_depyler_0351_symbol_at_position_not_found() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -419,15 +399,7 @@ fn test_depyler_0351_symbol_at_position_not_found() {
 fn test_depyler_0351_find_references_single() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "func".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("func").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -464,15 +436,7 @@ fn test_depyler_0351_find_references_none() {
 fn test_depyler_0351_completions_empty_prefix() {
     let mut ide = IdeIntegration::new();
 
-    let func1 = HirFunction {
-        name: "func1".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func1 = HirFunctionBuilder::new("func1").build();
 
     let class1 = HirClass {
         name: "class1".to_string(),
@@ -480,6 +444,8 @@ fn test_depyler_0351_completions_empty_prefix() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -501,15 +467,7 @@ fn test_depyler_0351_completions_empty_prefix() {
 fn test_depyler_0351_completions_no_matches() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -529,25 +487,9 @@ fn test_depyler_0351_completions_no_matches() {
 fn test_depyler_0351_completions_prefix_filtering() {
     let mut ide = IdeIntegration::new();
 
-    let func1 = HirFunction {
-        name: "my_func".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func1 = HirFunctionBuilder::new("my_func").build();
 
-    let func2 = HirFunction {
-        name: "other_func".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func2 = HirFunctionBuilder::new("other_func").build();
 
     let module = HirModule {
         functions: vec![func1, func2],
@@ -568,15 +510,7 @@ fn test_depyler_0351_completions_prefix_filtering() {
 fn test_depyler_0351_completions_different_kinds() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "test_func".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test_func").build();
 
     let class = HirClass {
         name: "test_class".to_string(),
@@ -584,6 +518,8 @@ fn test_depyler_0351_completions_different_kinds() {
         fields: vec![],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -768,15 +704,7 @@ fn test_depyler_0351_diagnostic_severity_levels() {
 
 #[test]
 fn test_depyler_0351_create_ide_integration() {
-    let func = HirFunction {
-        name: "main".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("main").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -820,15 +748,7 @@ fn test_depyler_0351_empty_ide_integration() {
 fn test_depyler_0351_symbol_boundary_position() {
     let mut ide = IdeIntegration::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test").build();
 
     let module = HirModule {
         functions: vec![func],
@@ -881,6 +801,8 @@ fn test_depyler_0351_class_with_multiple_fields() {
         fields: vec![field1, field2],
         base_classes: vec![],
         is_dataclass: false,
+        model_kind: None,
+        annotations: Default::default(),
         docstring: None,
     };
 
@@ -953,15 +875,7 @@ mod property_tests {
 
             let mut functions = Vec::new();
             for i in 0..func_count {
-                functions.push(HirFunction {
-                    name: format!("func{}", i),
-                    params: smallvec![],
-                    ret_type: Type::None,
-                    body: vec![],
-                    properties: FunctionProperties::default(),
-                    annotations: TranspilationAnnotations::default(),
-                    docstring: None,
-                });
+                functions.push(HirFunctionBuilder::new(format!("func{}", i)).build());
             }
 
             let module = HirModule {
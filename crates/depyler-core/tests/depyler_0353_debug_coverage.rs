@@ -13,8 +13,8 @@
 //! - Edge cases and property-based tests
 
 use depyler_core::debug::*;
-use depyler_core::hir::{FunctionProperties, HirFunction, Type};
-use smallvec::SmallVec;
+use depyler_core::hir::{FunctionProperties, Type};
+use depyler_core::hir_builder::HirFunctionBuilder;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -192,15 +192,9 @@ fn test_depyler_0353_add_function_mapping_basic() {
         DebugLevel::Full,
     );
 
-    let func = HirFunction {
-        name: "test_function".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test_function")
+        .returns(Type::Int)
+        .build();
 
     generator.add_function_mapping(&func, 10);
 
@@ -217,15 +211,14 @@ fn test_depyler_0353_add_function_mapping_async_function() {
         DebugLevel::Full,
     );
 
-    let func = HirFunction {
-        name: "async_func".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties { is_async: true, ..Default::default() },
-        annotations: Default::default(),
-        docstring: Some("Async function".to_string()),
-    };
+    let func = HirFunctionBuilder::new("async_func")
+        .returns(Type::Int)
+        .properties(FunctionProperties {
+            is_async: true,
+            ..Default::default()
+        })
+        .docstring("Async function")
+        .build();
 
     generator.add_function_mapping(&func, 50);
 
@@ -241,15 +234,10 @@ fn test_depyler_0353_add_function_mapping_with_docstring() {
         DebugLevel::Basic,
     );
 
-    let func = HirFunction {
-        name: "documented_func".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::String,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: Some("This function is well documented".to_string()),
-    };
+    let func = HirFunctionBuilder::new("documented_func")
+        .returns(Type::String)
+        .docstring("This function is well documented")
+        .build();
 
     generator.add_function_mapping(&func, 100);
 
@@ -265,15 +253,9 @@ fn test_depyler_0353_add_function_mapping_zero_rust_line() {
         DebugLevel::Full,
     );
 
-    let func = HirFunction {
-        name: "start_func".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("start_func")
+        .returns(Type::Int)
+        .build();
 
     generator.add_function_mapping(&func, 0);
 
@@ -293,15 +275,9 @@ fn test_depyler_0353_generate_function_debug_basic() {
         DebugLevel::Full,
     );
 
-    let func = HirFunction {
-        name: "my_function".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("my_function")
+        .returns(Type::Int)
+        .build();
 
     let debug_code = generator.generate_function_debug(&func);
 
@@ -318,15 +294,13 @@ fn test_depyler_0353_generate_function_debug_async() {
         DebugLevel::Full,
     );
 
-    let func = HirFunction {
-        name: "async_function".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties { is_async: true, ..Default::default() },
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("async_function")
+        .returns(Type::Int)
+        .properties(FunctionProperties {
+            is_async: true,
+            ..Default::default()
+        })
+        .build();
 
     let debug_code = generator.generate_function_debug(&func);
     assert!(debug_code.len() >= 0);
@@ -340,15 +314,9 @@ fn test_depyler_0353_generate_function_debug_none_level() {
         DebugLevel::None,
     );
 
-    let func = HirFunction {
-        name: "no_debug".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("no_debug")
+        .returns(Type::Int)
+        .build();
 
     let debug_code = generator.generate_function_debug(&func);
 
@@ -697,15 +665,10 @@ fn test_depyler_0353_full_debug_workflow() {
     generator.add_mapping(5, 4, Some("helper".to_string()));
 
     // Create function debug
-    let func = HirFunction {
-        name: "test_func".to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::Int,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: Some("Test function".to_string()),
-    };
+    let func = HirFunctionBuilder::new("test_func")
+        .returns(Type::Int)
+        .docstring("Test function")
+        .build();
 
     generator.add_function_mapping(&func, 10);
     let func_debug = generator.generate_function_debug(&func);
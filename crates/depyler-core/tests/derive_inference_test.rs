@@ -0,0 +1,154 @@
+//! Tests for usage-driven derives on generated structs
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_struct_used_as_dict_key_derives_hash() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Cell:
+    row: int
+    col: int
+
+def count(cells: list[Cell]) -> dict[Cell, int]:
+    counts: dict[Cell, int] = {}
+    for cell in cells:
+        counts[cell] = counts.get(cell, 0) + 1
+    return counts
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(Clone,PartialEq,Eq,Hash)]pubstructCell"));
+}
+
+#[test]
+fn test_sorted_struct_derives_ord() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Version:
+    major: int
+    minor: int
+
+def newer(a: Version, b: Version) -> bool:
+    return a > b
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(PartialEq,Eq,PartialOrd,Ord)]pubstructVersion"));
+}
+
+#[test]
+fn test_float_fields_keep_partial_derives_only() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Reading:
+    value: float
+
+def lower(a: Reading, b: Reading) -> bool:
+    return a < b
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(PartialEq,PartialOrd)]pubstructReading"));
+}
+
+#[test]
+fn test_derives_follow_fields_into_nested_structs() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Point:
+    x: int
+    y: int
+
+@dataclass
+class Segment:
+    start: Point
+    end: Point
+
+def same(a: Segment, b: Segment) -> bool:
+    return a == b
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(PartialEq)]pubstructSegment"));
+    assert!(rust_code.contains("#[derive(PartialEq)]pubstructPoint"));
+}
+
+#[test]
+fn test_unused_struct_has_no_derives() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Config:
+    retries: int
+
+def retries(config: Config) -> int:
+    return config.retries
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubstructConfig{"));
+    assert!(!rust_code.contains("#[derive"));
+}
+
+#[test]
+fn test_json_dumps_derives_serialize_and_propagates_errors() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import json
+from dataclasses import dataclass
+
+@dataclass
+class Order:
+    id: int
+    item: str
+
+def encode(order: Order) -> str:
+    return json.dumps(order)
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(serde::Serialize)]pubstructOrder"));
+    assert!(rust_code.contains("->Result<String,serde_json::Error>"));
+    assert!(rust_code.contains("serde_json::to_string(&order)?"));
+    assert!(!rust_code.contains("serde_json::Value"));
+}
+
+#[test]
+fn test_json_loads_into_dataclass_derives_deserialize() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+import json
+from dataclasses import dataclass
+
+@dataclass
+class Order:
+    id: int
+    item: str
+
+def decode(text: str) -> Order:
+    return json.loads(text)
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("#[derive(serde::Deserialize)]pubstructOrder"));
+    assert!(rust_code.contains("serde_json::from_str::<Order>(&text)?"));
+    assert!(!rust_code.contains("serde_json::Value"));
+}
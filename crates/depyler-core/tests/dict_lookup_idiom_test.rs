@@ -0,0 +1,86 @@
+//! Dict double-lookup idioms generated as a single lookup
+
+mod common;
+
+use common::transpile;
+
+#[test]
+fn test_membership_test_then_index_becomes_if_let() {
+    let code = transpile(
+        r#"
+def price(prices: dict[str, int], item: str) -> int:
+    if item in prices:
+        p = prices[item]
+        return p * 2
+    else:
+        return 0
+"#,
+    );
+
+    assert!(code.contains("ifletSome(p)=prices.get(item).cloned(){"));
+    assert!(!code.contains("contains_key"));
+    assert!(code.contains("}else{"));
+}
+
+#[test]
+fn test_get_then_early_return_becomes_match() {
+    let code = transpile(
+        r#"
+def lookup(ages: dict[str, int], name: str) -> int:
+    age = ages.get(name)
+    if age is None:
+        return -1
+    return age + 1
+"#,
+    );
+
+    assert!(code.contains("letage=matchages.get(name).cloned(){Some(age)=>age,"));
+    assert!(code.contains("None=>{return-1;}"));
+    assert!(!code.contains("is_none()"));
+}
+
+#[test]
+fn test_get_then_default_assignment_becomes_match() {
+    let code = transpile(
+        r#"
+def count_of(counts: dict[str, int], word: str) -> int:
+    n = counts.get(word)
+    if n is None:
+        n = 0
+    return n
+"#,
+    );
+
+    assert!(code.contains("=matchcounts.get(word).cloned(){Some(n)=>n,None=>0,};"));
+}
+
+#[test]
+fn test_none_branch_reading_the_variable_is_kept() {
+    let code = transpile(
+        r#"
+def describe(ages: dict[str, int], name: str) -> str:
+    age = ages.get(name)
+    if age is None:
+        print(age)
+        return "unknown"
+    return "known"
+"#,
+    );
+
+    assert!(code.contains("age.is_none()"));
+}
+
+#[test]
+fn test_optional_values_keep_the_double_lookup() {
+    let code = transpile(
+        r#"
+def maybe(values: dict[str, int | None], key: str) -> int:
+    if key in values:
+        v = values[key]
+        return 1
+    return 0
+"#,
+    );
+
+    assert!(code.contains("contains_key"));
+}
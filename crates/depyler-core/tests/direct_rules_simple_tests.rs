@@ -2,6 +2,7 @@
 mod tests {
     use depyler_core::direct_rules::apply_rules;
     use depyler_core::hir::*;
+    use depyler_core::hir_builder::HirFunctionBuilder;
     use depyler_core::type_mapper::TypeMapper;
     use smallvec::smallvec;
 
@@ -28,15 +29,12 @@ mod tests {
     fn test_simple_function() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "test".to_string(),
-            params: smallvec![],
-            ret_type: Type::Int,
-            body: vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42))))],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("test")
+                .returns(Type::Int)
+                .stmt(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(42)))))
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -47,30 +45,19 @@ mod tests {
     fn test_function_with_params() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "add".to_string(),
-            params: smallvec![
-                HirParam {
-                    name: Symbol::from("a"),
-                    ty: Type::Int,
-                    default: None
-                },
-                HirParam {
-                    name: Symbol::from("b"),
-                    ty: Type::Int,
-                    default: None
-                }
-            ],
-            ret_type: Type::Int,
-            body: vec![HirStmt::Return(Some(HirExpr::Binary {
-                op: BinOp::Add,
-                left: Box::new(HirExpr::Var("a".to_string())),
-                right: Box::new(HirExpr::Var("b".to_string())),
-            }))],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: Some("Add two numbers".to_string()),
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("add")
+                .param("a", Type::Int)
+                .param("b", Type::Int)
+                .returns(Type::Int)
+                .stmt(HirStmt::Return(Some(HirExpr::Binary {
+                    op: BinOp::Add,
+                    left: Box::new(HirExpr::Var("a".to_string())),
+                    right: Box::new(HirExpr::Var("b".to_string())),
+                })))
+                .docstring("Add two numbers")
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -87,6 +74,8 @@ mod tests {
             methods: vec![],
             fields: vec![],
             is_dataclass: false,
+            model_kind: None,
+            annotations: Default::default(),
             docstring: None,
         });
 
@@ -110,6 +99,8 @@ mod tests {
                 is_class_var: false,
             }],
             is_dataclass: true,
+            model_kind: None,
+            annotations: Default::default(),
             docstring: None,
         });
 
@@ -167,29 +158,23 @@ mod tests {
     fn test_control_flow() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "control_flow".to_string(),
-            params: smallvec![HirParam {
-                name: Symbol::from("x"),
-                ty: Type::Int,
-                default: None
-            }],
-            ret_type: Type::Int,
-            body: vec![HirStmt::If {
-                condition: HirExpr::Binary {
-                    op: BinOp::Gt,
-                    left: Box::new(HirExpr::Var("x".to_string())),
-                    right: Box::new(HirExpr::Literal(Literal::Int(0))),
-                },
-                then_body: vec![HirStmt::Return(Some(HirExpr::Var("x".to_string())))],
-                else_body: Some(vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(
-                    0,
-                ))))]),
-            }],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("control_flow")
+                .param("x", Type::Int)
+                .returns(Type::Int)
+                .stmt(HirStmt::If {
+                    condition: HirExpr::Binary {
+                        op: BinOp::Gt,
+                        left: Box::new(HirExpr::Var("x".to_string())),
+                        right: Box::new(HirExpr::Literal(Literal::Int(0))),
+                    },
+                    then_body: vec![HirStmt::Return(Some(HirExpr::Var("x".to_string())))],
+                    else_body: Some(vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(
+                        0,
+                    ))))]),
+                })
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -200,27 +185,27 @@ mod tests {
     fn test_loop_structures() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "loops".to_string(),
-            params: smallvec![],
-            ret_type: Type::None,
-            body: vec![
-                // For loop
-                HirStmt::For {
-                    target: AssignTarget::Symbol("i".to_string()),
-                    iter: HirExpr::Call { func: "range".to_string(), args: vec![HirExpr::Literal(Literal::Int(10))], kwargs: vec![] },
-                    body: vec![HirStmt::Expr(HirExpr::Var("i".to_string()))],
-                },
-                // While loop
-                HirStmt::While {
-                    condition: HirExpr::Literal(Literal::Bool(true)),
-                    body: vec![HirStmt::Break { label: None }],
-                },
-            ],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("loops")
+                .body(vec![
+                    // For loop
+                    HirStmt::For {
+                        target: AssignTarget::Symbol("i".to_string()),
+                        iter: HirExpr::Call {
+                            func: "range".to_string(),
+                            args: vec![HirExpr::Literal(Literal::Int(10))],
+                            kwargs: vec![],
+                        },
+                        body: vec![HirStmt::Expr(HirExpr::Var("i".to_string()))],
+                    },
+                    // While loop
+                    HirStmt::While {
+                        condition: HirExpr::Literal(Literal::Bool(true)),
+                        body: vec![HirStmt::Break { label: None }],
+                    },
+                ])
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -231,19 +216,16 @@ mod tests {
     fn test_collections() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "collections".to_string(),
-            params: smallvec![],
-            ret_type: Type::List(Box::new(Type::Int)),
-            body: vec![HirStmt::Return(Some(HirExpr::List(vec![
-                HirExpr::Literal(Literal::Int(1)),
-                HirExpr::Literal(Literal::Int(2)),
-                HirExpr::Literal(Literal::Int(3)),
-            ])))],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("collections")
+                .returns(Type::List(Box::new(Type::Int)))
+                .stmt(HirStmt::Return(Some(HirExpr::List(vec![
+                    HirExpr::Literal(Literal::Int(1)),
+                    HirExpr::Literal(Literal::Int(2)),
+                    HirExpr::Literal(Literal::Int(3)),
+                ]))))
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -262,7 +244,8 @@ mod tests {
                 params: smallvec![HirParam {
                     name: Symbol::from("self"),
                     ty: Type::Unknown,
-                    default: None
+                    default: None,
+                    kind: ParamKind::PositionalOrKeyword,
                 }],
                 ret_type: Type::None,
                 body: vec![],
@@ -274,6 +257,8 @@ mod tests {
             }],
             fields: vec![],
             is_dataclass: false,
+            model_kind: None,
+            annotations: Default::default(),
             docstring: None,
         });
 
@@ -286,23 +271,20 @@ mod tests {
     fn test_complex_expression() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "complex".to_string(),
-            params: smallvec![],
-            ret_type: Type::Int,
-            body: vec![HirStmt::Return(Some(HirExpr::Binary {
-                op: BinOp::Add,
-                left: Box::new(HirExpr::Binary {
-                    op: BinOp::Mul,
-                    left: Box::new(HirExpr::Literal(Literal::Int(2))),
-                    right: Box::new(HirExpr::Literal(Literal::Int(3))),
-                }),
-                right: Box::new(HirExpr::Literal(Literal::Int(4))),
-            }))],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("complex")
+                .returns(Type::Int)
+                .stmt(HirStmt::Return(Some(HirExpr::Binary {
+                    op: BinOp::Add,
+                    left: Box::new(HirExpr::Binary {
+                        op: BinOp::Mul,
+                        left: Box::new(HirExpr::Literal(Literal::Int(2))),
+                        right: Box::new(HirExpr::Literal(Literal::Int(3))),
+                    }),
+                    right: Box::new(HirExpr::Literal(Literal::Int(4))),
+                })))
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -313,29 +295,30 @@ mod tests {
     fn test_lambda() {
         let mut module = create_empty_module();
 
-        module.functions.push(HirFunction {
-            name: "use_lambda".to_string(),
-            params: smallvec![],
-            ret_type: Type::Int,
-            body: vec![
-                HirStmt::Assign {
-                    target: AssignTarget::Symbol("f".to_string()),
-                    value: HirExpr::Lambda {
-                        params: vec!["x".to_string()],
-                        body: Box::new(HirExpr::Binary {
-                            op: BinOp::Mul,
-                            left: Box::new(HirExpr::Var("x".to_string())),
-                            right: Box::new(HirExpr::Literal(Literal::Int(2))),
-                        }),
+        module.functions.push(
+            HirFunctionBuilder::new("use_lambda")
+                .returns(Type::Int)
+                .body(vec![
+                    HirStmt::Assign {
+                        target: AssignTarget::Symbol("f".to_string()),
+                        value: HirExpr::Lambda {
+                            params: vec!["x".to_string()],
+                            body: Box::new(HirExpr::Binary {
+                                op: BinOp::Mul,
+                                left: Box::new(HirExpr::Var("x".to_string())),
+                                right: Box::new(HirExpr::Literal(Literal::Int(2))),
+                            }),
+                        },
+                        type_annotation: None,
                     },
-                    type_annotation: None,
-                },
-                HirStmt::Return(Some(HirExpr::Call { func: "f".to_string(), args: vec![HirExpr::Literal(Literal::Int(5))], kwargs: vec![] })),
-            ],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+                    HirStmt::Return(Some(HirExpr::Call {
+                        func: "f".to_string(),
+                        args: vec![HirExpr::Literal(Literal::Int(5))],
+                        kwargs: vec![],
+                    })),
+                ])
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -364,19 +347,13 @@ mod tests {
             is_newtype: false,
         });
 
-        module.functions.push(HirFunction {
-            name: "process".to_string(),
-            params: smallvec![HirParam {
-                name: Symbol::from("data"),
-                ty: Type::List(Box::new(Type::Int)),
-                default: None
-            }],
-            ret_type: Type::Int,
-            body: vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(0))))],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        });
+        module.functions.push(
+            HirFunctionBuilder::new("process")
+                .param("data", Type::List(Box::new(Type::Int)))
+                .returns(Type::Int)
+                .stmt(HirStmt::Return(Some(HirExpr::Literal(Literal::Int(0)))))
+                .build(),
+        );
 
         let type_mapper = TypeMapper::new();
         let result = apply_rules(&module, &type_mapper);
@@ -389,9 +366,9 @@ mod tests {
 mod property_tests {
     use depyler_core::direct_rules::apply_rules;
     use depyler_core::hir::*;
+    use depyler_core::hir_builder::HirFunctionBuilder;
     use depyler_core::type_mapper::TypeMapper;
     use proptest::prelude::*;
-    use smallvec::smallvec;
 
     prop_compose! {
         fn arb_simple_module()(
@@ -406,15 +383,11 @@ mod property_tests {
             };
 
             for i in 0..num_functions {
-                module.functions.push(HirFunction {
-                    name: format!("func{}", i),
-                    params: smallvec![],
-                    ret_type: Type::Unknown,
-                    body: vec![],
-                    properties: FunctionProperties::default(),
-                    annotations: Default::default(),
-                    docstring: None,
-                });
+                module.functions.push(
+                    HirFunctionBuilder::new(format!("func{}", i))
+                        .returns(Type::Unknown)
+                        .build(),
+                );
             }
 
             module
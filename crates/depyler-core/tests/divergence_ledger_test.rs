@@ -0,0 +1,76 @@
+//! Code generation records, per function, each place where the generated
+//! code knowingly behaves differently from Python
+
+use depyler_core::rust_gen::ledger::{DivergenceKind, DivergenceLedger};
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def add(a: int, b: int) -> int:
+    return a + b + 1
+
+def show(x: float) -> None:
+    print(x)
+
+def total(scores: dict[str, int]) -> int:
+    result = 0
+    for name in scores:
+        result = result + scores[name]
+    return result
+
+def width(text: str) -> int:
+    return len(text)
+
+def pick(flag, a: int, b: int) -> int:
+    if flag:
+        return a
+    return b
+
+def greet() -> str:
+    return "hello"
+
+# @depyler: integer_semantics = "bigint"
+def big(a: int, b: int) -> int:
+    return a * b
+"#;
+
+fn kinds(ledger: &DivergenceLedger, function: &str) -> Vec<DivergenceKind> {
+    ledger.for_function(function).map(|d| d.kind).collect()
+}
+
+#[test]
+fn test_deviations_are_recorded_per_function() {
+    let (_, ledger) = DepylerPipeline::new()
+        .transpile_with_ledger(SOURCE)
+        .unwrap();
+
+    assert_eq!(kinds(&ledger, "add"), [DivergenceKind::IntWidth]);
+    assert!(kinds(&ledger, "show").contains(&DivergenceKind::FloatRepr));
+    assert!(kinds(&ledger, "total").contains(&DivergenceKind::DictOrdering));
+    assert!(kinds(&ledger, "width").contains(&DivergenceKind::UnicodeIndexing));
+    assert!(kinds(&ledger, "pick").contains(&DivergenceKind::Truthiness));
+    assert!(kinds(&ledger, "greet").is_empty());
+    assert!(kinds(&ledger, "big").is_empty());
+}
+
+#[test]
+fn test_ledger_names_the_overflow_behavior() {
+    let source =
+        "# @depyler: integer_semantics = \"wrapping\"\ndef f(a: int) -> int:\n    return a * 2\n";
+    let (_, ledger) = DepylerPipeline::new()
+        .transpile_with_ledger(source)
+        .unwrap();
+
+    let report = ledger.report();
+    assert_eq!(
+        report,
+        "f:\n  [int_width] `int` arithmetic is i32 and wraps on overflow\n"
+    );
+    assert!(ledger.to_json().unwrap().contains("\"function\": \"f\""));
+}
+
+#[test]
+fn test_transpile_output_is_unchanged() {
+    let pipeline = DepylerPipeline::new();
+    let (code, _) = pipeline.transpile_with_ledger(SOURCE).unwrap();
+    assert_eq!(code, pipeline.transpile(SOURCE).unwrap());
+}
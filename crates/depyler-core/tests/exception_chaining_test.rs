@@ -0,0 +1,101 @@
+//! `raise X from Y` and raising inside an `except` block keep the original
+//! error, reachable through `std::error::Error::source()`
+
+mod common;
+
+use depyler_core::rust_gen::ExitCodeOptions;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+fn transpile(handler: &str) -> String {
+    let source = format!(
+        r#"
+def parse(text: str) -> int:
+    try:
+        return int(text)
+    except ValueError as e:
+        {}
+"#,
+        handler
+    );
+    common::transpile(&source)
+}
+
+#[test]
+fn test_raise_from_chains_the_cause() {
+    let code = transpile(r#"raise ValueError("not a number: " + text) from e"#);
+    assert!(code.contains(".with_cause(e)"));
+    assert!(code.contains("fnsource(&self)"));
+    assert!(code.contains("pubfnwith_cause("));
+}
+
+#[test]
+fn test_raise_in_handler_chains_the_handled_error() {
+    let code = transpile(r#"raise ValueError("not a number")"#);
+    assert!(code.contains(".with_cause(e)"));
+}
+
+#[test]
+fn test_raise_from_none_suppresses_the_chain() {
+    let code = transpile(r#"raise ValueError("not a number") from None"#);
+    assert!(!code.contains(".with_cause("));
+}
+
+#[test]
+fn test_bare_raise_re_raises_the_handled_error() {
+    let code = transpile("raise");
+    assert!(code.contains("returnErr(e);"));
+    assert!(!code.contains("Exceptionraised"));
+}
+
+#[test]
+fn test_exit_code_main_prints_the_cause_chain() {
+    let rust_code = DepylerPipeline::new()
+        .with_exit_codes(ExitCodeOptions::enabled())
+        .transpile(
+            r#"
+def main() -> None:
+    raise ValueError("bad config") from ValueError("missing key")
+"#,
+        )
+        .unwrap();
+    assert!(rust_code.contains("std::error::Error::source("));
+    assert!(rust_code.contains("The above exception was the direct cause"));
+}
+
+#[test]
+fn test_cause_is_reachable_through_source() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def parse(text: str) -> int:
+    try:
+        return int(text)
+    except ValueError as e:
+        raise ValueError("not a number: " + text) from e
+"#,
+        )
+        .unwrap();
+    let program = format!(
+        "{}\nfn main() {{\n    use std::error::Error;\n    let err = parse(\"abc\").unwrap_err();\n    assert!(err.source().is_some());\n}}\n",
+        rust_code
+    );
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("chaining.rs");
+    let binary = dir.path().join("chaining");
+    std::fs::write(&source, &program).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        program,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(Command::new(&binary).status().unwrap().success());
+}
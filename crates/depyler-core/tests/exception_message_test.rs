@@ -0,0 +1,66 @@
+//! Failing builtins and unhandled exceptions use CPython's messages, or
+//! depyler's terse ones when asked
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def take(d: dict[str, int], key: str) -> int:
+    return d.pop(key)
+
+def where_is(xs: list[int], x: int) -> int:
+    return xs.index(x)
+
+def first_space(text: str) -> int:
+    return text.index(" ")
+
+def parse_count(text: str) -> int:
+    return int(text)
+
+def lookup(d: dict[str, int], key: str) -> int:
+    return d[key]
+
+def at(xs: list[int], i: int) -> int:
+    return xs[i]
+
+def last(xs: list[int]) -> int:
+    return xs.pop()
+"#;
+
+#[test]
+fn test_messages_follow_cpython() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    // The missing key and value show as their repr
+    assert!(code.contains("unwrap_or_else(||panic!(\"KeyError:{}\",key.py_repr()))"));
+    assert!(code.contains("panic!(\"ValueError:{}isnotinlist\","));
+    assert!(code.contains("expect(\"ValueError:substringnotfound\")"));
+    assert!(code
+        .contains("d.get(&key).cloned().unwrap_or_else(||panic!(\"KeyError:{}\",key.py_repr()))"));
+    assert!(code.contains("expect(\"IndexError:listindexoutofrange\")"));
+    assert!(code.contains("xs.pop().expect(\"IndexError:popfromemptylist\")"));
+    // Unhandled exceptions panic with the last line of the traceback
+    assert!(code.contains("panic!(\"ValueError:{}\",e)"));
+    assert!(code.contains("write!(f,\"{}\",self.message)"));
+    assert!(!code.contains("keynotfound"));
+}
+
+#[test]
+fn test_native_error_messages_stay_terse() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .with_native_error_messages()
+            .transpile(SOURCE)
+            .unwrap(),
+    );
+
+    assert!(code.contains("expect(\"KeyError:keynotfound\")"));
+    assert!(code.contains("expect(\"ValueError:valueisnotinlist\")"));
+    assert!(code.contains("expect(\"substringnotfound\")"));
+    assert!(code.contains("expect(\"indexoutofrange\")"));
+    assert!(code.contains("expect(\"popfromemptylist\")"));
+    assert!(code.contains("panic!(\"{}\",e)"));
+    assert!(code.contains("write!(f,\"valueerror:{}\",self.message)"));
+}
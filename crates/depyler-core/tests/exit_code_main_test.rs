@@ -0,0 +1,62 @@
+//! Exception-to-exit-code mapping for CLI programs
+//!
+//! A fallible `main()` is wrapped so that an escaping exception prints
+//! `ValueError: msg` to stderr and exits with a configurable status,
+//! instead of Rust's default `Error: ...` debug output.
+
+use depyler_core::rust_gen::ExitCodeOptions;
+use depyler_core::DepylerPipeline;
+
+const FAILING_MAIN: &str = r#"
+def main() -> None:
+    raise ValueError("bad input")
+"#;
+
+#[test]
+fn test_exit_code_wrapper_disabled_by_default() {
+    let rust_code = DepylerPipeline::new().transpile(FAILING_MAIN).unwrap();
+
+    assert!(!rust_code.contains("__depyler_main"));
+    assert!(!rust_code.contains("std::process::exit"));
+}
+
+#[test]
+fn test_exit_code_wrapper_renames_fallible_main() {
+    let pipeline = DepylerPipeline::new().with_exit_codes(ExitCodeOptions::enabled());
+    let rust_code = pipeline.transpile(FAILING_MAIN).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub fn __depyler_main"));
+    assert!(rust_code.contains("pub fn main()"));
+    assert!(rust_code.contains("downcast_ref::<ValueError>"));
+    assert!(rust_code.contains("\"{}: {}\""));
+    assert!(rust_code.contains("std::process::exit(code)"));
+}
+
+#[test]
+fn test_exit_code_wrapper_uses_configured_codes() {
+    let pipeline = DepylerPipeline::new().with_exit_codes(
+        ExitCodeOptions::enabled()
+            .with_exit_code("ValueError", 2)
+            .with_default_exit_code(70)
+            .with_traceback_header(),
+    );
+    let rust_code = pipeline.transpile(FAILING_MAIN).unwrap();
+
+    assert!(rust_code.contains("e.message.clone(), 2)"));
+    assert!(rust_code.contains("err.to_string(), 70)"));
+    assert!(rust_code.contains("Traceback (most recent call last):"));
+}
+
+#[test]
+fn test_exit_code_wrapper_skips_infallible_main() {
+    let pipeline = DepylerPipeline::new().with_exit_codes(ExitCodeOptions::enabled());
+    let python_code = r#"
+def main() -> None:
+    print("hello")
+"#;
+    let rust_code = pipeline.transpile(python_code).unwrap();
+
+    assert!(!rust_code.contains("__depyler_main"));
+    assert!(!rust_code.contains("std::process::exit"));
+}
@@ -0,0 +1,127 @@
+//! C ABI wrappers and header generated with `with_ffi`
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const MODULE: &str = r#"
+def add(a: int, b: int) -> int:
+    return a + b
+
+def ratio(a: float, b: float) -> float:
+    if b == 0.0:
+        raise ValueError("b must not be zero")
+    return a / b
+
+def first_space(text: str) -> int:
+    return text.index(" ")
+
+def greet(name: str) -> str:
+    return "Hello, " + name
+
+def total(values: List[int]) -> int:
+    return sum(values)
+"#;
+
+#[test]
+fn test_ffi_is_off_by_default() {
+    let pipeline = DepylerPipeline::new();
+    let module = pipeline.parse_to_hir(MODULE).unwrap();
+    assert!(pipeline.generate_ffi(&module, "calc").unwrap().is_none());
+}
+
+#[test]
+fn test_simple_functions_get_wrappers_and_a_header() {
+    let pipeline = DepylerPipeline::new().with_ffi();
+    let module = pipeline.parse_to_hir(MODULE).unwrap();
+    let ffi = pipeline.generate_ffi(&module, "calc-lib").unwrap().unwrap();
+    println!("Generated wrappers:\n{}\n{}", ffi.rust, ffi.header);
+    let rust = compact(&ffi.rust);
+
+    assert!(rust
+        .contains("#[no_mangle]pubunsafeextern\"C\"fncalc_lib_add(a:i64,b:i64,out:*muti64)->i32"));
+    assert!(rust.contains("pubunsafeextern\"C\"fncalc_lib_greet("));
+    assert!(ffi.header.contains("#ifndef CALC_LIB_H"));
+    assert!(ffi
+        .header
+        .contains("int32_t calc_lib_add(int64_t a, int64_t b, int64_t *out);"));
+    assert!(ffi
+        .header
+        .contains("int32_t calc_lib_ratio(double a, double b, double *out);"));
+    assert!(ffi
+        .header
+        .contains("int32_t calc_lib_greet(const char *name, char **out);"));
+    assert!(ffi.header.contains("void calc_lib_free_string(char *s);"));
+    assert_eq!(ffi.skipped, vec!["total".to_string()]);
+}
+
+#[test]
+fn test_wrappers_report_failures_through_status() {
+    let pipeline = DepylerPipeline::new().with_ffi();
+    let module = pipeline.parse_to_hir(MODULE).unwrap();
+    let ffi = pipeline.generate_ffi(&module, "calc").unwrap().unwrap();
+    let rust_code = pipeline.transpile(MODULE).unwrap();
+    let checks = r##"
+fn last_error() -> String {
+    let message = unsafe { std::ffi::CStr::from_ptr(ffi::calc_last_error()) };
+    message.to_str().unwrap().to_string()
+}
+
+fn main() {
+    let mut sum = 0i64;
+    assert_eq!(unsafe { ffi::calc_add(2, 3, &mut sum) }, ffi::OK);
+    assert_eq!(sum, 5);
+    assert_eq!(unsafe { ffi::calc_add(i64::MAX, 1, &mut sum) }, ffi::INVALID_ARGUMENT);
+    assert_eq!(last_error(), "`a` is out of range");
+    assert_eq!(unsafe { ffi::calc_add(2, 3, std::ptr::null_mut()) }, ffi::INVALID_ARGUMENT);
+    assert_eq!(last_error(), "`out` is null");
+
+    let mut quotient = 0.0f64;
+    assert_eq!(unsafe { ffi::calc_ratio(1.0, 4.0, &mut quotient) }, ffi::OK);
+    assert_eq!(quotient, 0.25);
+    assert_eq!(unsafe { ffi::calc_ratio(1.0, 0.0, &mut quotient) }, ffi::ERROR);
+    assert!(last_error().contains("b must not be zero"));
+
+    let text = std::ffi::CString::new("nospace").unwrap();
+    let mut index = 0i64;
+    assert_eq!(unsafe { ffi::calc_first_space(text.as_ptr(), &mut index) }, ffi::PANIC);
+    assert!(last_error().contains("substring not found"));
+
+    let name = std::ffi::CString::new("ctypes").unwrap();
+    let mut greeting: *mut std::ffi::c_char = std::ptr::null_mut();
+    assert_eq!(unsafe { ffi::calc_greet(name.as_ptr(), &mut greeting) }, ffi::OK);
+    let text = unsafe { std::ffi::CStr::from_ptr(greeting) }.to_str().unwrap().to_string();
+    assert_eq!(text, "Hello, ctypes");
+    unsafe { ffi::calc_free_string(greeting) };
+    assert_eq!(
+        unsafe { ffi::calc_greet(std::ptr::null(), &mut greeting) },
+        ffi::INVALID_ARGUMENT
+    );
+}
+"##;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("calc.rs");
+    let binary = dir.path().join("calc");
+    std::fs::write(&source, format!("{}\n{}\n{}", rust_code, ffi.rust, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        ffi.rust,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,90 @@
+//! String annotations, `typing.Self` and `from __future__ import annotations`
+
+use depyler_core::hir::Type;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_string_return_annotation_resolves_to_class() {
+    let python = r#"
+class Node:
+    def __init__(self, value: int) -> None:
+        self.value = value
+
+def make_node(value: int) -> "Node":
+    return Node(value)
+"#;
+    let hir = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    let func = hir
+        .functions
+        .iter()
+        .find(|f| f.name == "make_node")
+        .unwrap();
+    assert_eq!(func.ret_type, Type::Custom("Node".to_string()));
+}
+
+#[test]
+fn test_forward_reference_before_class_definition() {
+    let python = r#"
+from typing import Optional
+
+def first_child(tree: "Tree") -> "Optional[Tree]":
+    return None
+
+class Tree:
+    def __init__(self) -> None:
+        self.size = 0
+"#;
+    let hir = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    let func = &hir.functions[0];
+    assert_eq!(func.params[0].ty, Type::Custom("Tree".to_string()));
+    assert_eq!(
+        func.ret_type,
+        Type::Optional(Box::new(Type::Custom("Tree".to_string())))
+    );
+}
+
+#[test]
+fn test_self_return_type_maps_to_enclosing_class() {
+    let python = r#"
+from typing import Self
+
+class Builder:
+    def __init__(self) -> None:
+        self.count = 0
+
+    def clone_builder(self) -> Self:
+        return Builder()
+
+    def merge(self, other: "typing.Self") -> "Builder":
+        return other
+"#;
+    let hir = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    let class = &hir.classes[0];
+    let clone_builder = class
+        .methods
+        .iter()
+        .find(|m| m.name == "clone_builder")
+        .unwrap();
+    assert_eq!(clone_builder.ret_type, Type::Custom("Builder".to_string()));
+
+    let merge = class.methods.iter().find(|m| m.name == "merge").unwrap();
+    assert_eq!(merge.params[0].ty, Type::Custom("Builder".to_string()));
+}
+
+#[test]
+fn test_future_annotations_import_is_dropped() {
+    let python = r#"
+from __future__ import annotations
+
+class Point:
+    def __init__(self, x: int) -> None:
+        self.x = x
+
+def origin() -> Point:
+    return Point(0)
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(!rust_code.contains("__future__"));
+    assert!(rust_code.contains("-> Point"));
+}
@@ -3,23 +3,15 @@
 // EXTREME TDD: These tests are written BEFORE refactoring to ensure
 // behavior preservation during Extract Method pattern application.
 
-use depyler_annotations::TranspilationAnnotations;
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::rust_gen::generate_rust_file;
 use depyler_core::type_mapper::TypeMapper;
 use smallvec::SmallVec;
 
 /// Helper function to create a minimal valid HirFunction
 fn create_simple_function(name: &str) -> HirFunction {
-    HirFunction {
-        name: name.to_string(),
-        params: SmallVec::new(),
-        ret_type: Type::None,
-        body: vec![],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    }
+    HirFunctionBuilder::new(name).build()
 }
 
 /// Helper function to create an empty HirModule
@@ -211,6 +203,7 @@ mod function_with_params_tests {
             name: Symbol::from("x"),
             ty: Type::Int,
             default: None,
+            kind: ParamKind::PositionalOrKeyword,
         }]);
         func.ret_type = Type::Int;
 
@@ -237,11 +230,13 @@ mod function_with_params_tests {
                 name: Symbol::from("a"),
                 ty: Type::Int,
                 default: None,
+                kind: ParamKind::PositionalOrKeyword,
             },
             HirParam {
                 name: Symbol::from("b"),
                 ty: Type::Int,
                 default: None,
+                kind: ParamKind::PositionalOrKeyword,
             },
         ]);
         func.ret_type = Type::Int;
@@ -317,6 +312,7 @@ mod regression_tests {
                     name: Symbol::from("p"),
                     ty: Type::Int,
                     default: None,
+                    kind: ParamKind::PositionalOrKeyword,
                 }]);
                 m.functions = vec![func];
                 m
@@ -0,0 +1,81 @@
+//! Generators lowered to basic-block state machines
+//!
+//! Yields inside loops and branches, `yield from` and early `return` resume
+//! through `match self.state` arms inside a `loop`, with locals and live
+//! iterators kept in the state struct.
+
+mod common;
+
+use common::transpile;
+
+#[test]
+fn test_several_yields_per_loop_iteration() {
+    let code = transpile(
+        r#"
+def up_down(n: int):
+    i = 0
+    while i < n:
+        yield i
+        yield -i
+        i = i + 1
+"#,
+    );
+
+    assert!(code.contains("structUpDownState{state:usize,n:i32,i:i32"));
+    assert!(code.contains("fnup_down(n:i32)->implIterator<Item=i32>"));
+    assert!(code.contains("loop{matchself.state{"));
+    // One resume point per yield
+    assert_eq!(code.matches("returnSome(").count(), 2);
+}
+
+#[test]
+fn test_for_loop_iterator_lives_in_the_state_struct() {
+    let code = transpile(
+        r#"
+def parity(n: int):
+    for i in range(n):
+        if i % 2 == 0:
+            yield "even"
+        else:
+            yield "odd"
+"#,
+    );
+
+    assert!(code.contains("__iter_0:Option<Box<dynIterator<Item=i32>>>"));
+    assert!(code.contains("self.__iter_0=Some(Box::new("));
+    assert!(code.contains("matchself.__iter_0.as_mut().and_then(|items|items.next())"));
+    assert!(code.contains("self.i=item;"));
+}
+
+#[test]
+fn test_yield_from_forwards_each_item() {
+    let code = transpile(
+        r#"
+def chain(first: list[int], second: list[int]):
+    yield from first
+    yield from second
+"#,
+    );
+
+    assert!(code.contains("implIterator<Item=i32>"));
+    assert!(code.contains("self.first.clone().into_iter()"));
+    assert!(code.contains("self.second.clone().into_iter()"));
+    assert!(code.contains("__iter_1:Option<Box<dynIterator<Item=i32>>>"));
+}
+
+#[test]
+fn test_return_finishes_the_generator() {
+    let code = transpile(
+        r#"
+def until_negative(values: list[int]):
+    for v in values:
+        if v < 0:
+            return
+        yield v
+"#,
+    );
+
+    assert!(code.contains("implIterator<Item=i32>"));
+    assert!(code.contains("returnNone;"));
+    assert!(code.contains("_=>returnNone,"));
+}
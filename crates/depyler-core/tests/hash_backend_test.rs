@@ -0,0 +1,86 @@
+//! Choice of the map behind `HashMap`
+//!
+//! `hash` annotations, the performance profile and an explicit backend all
+//! select one map per file, and the import, constructors and Cargo
+//! dependencies follow it.
+
+mod common;
+
+use common::compact;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::rust_gen::{HashBackend, PerformanceProfile};
+use depyler_core::DepylerPipeline;
+
+const COUNTS: &str = r#"
+def counts() -> Dict[str, int]:
+    d = {"a": 1}
+    return d
+"#;
+
+#[test]
+fn test_default_is_std() {
+    let rust = DepylerPipeline::new().transpile(COUNTS).unwrap();
+    let code = compact(&rust);
+    assert!(code.contains("usestd::collections::HashMap;"));
+    assert!(code.contains("HashMap::new()"));
+}
+
+#[test]
+fn test_fnv_annotation_binds_hash_map_and_adapts_constructors() {
+    let python = format!("# @depyler: hash = \"fnv\"{}", COUNTS);
+    let rust = DepylerPipeline::new().transpile(&python).unwrap();
+    let code = compact(&rust);
+
+    assert!(code.contains("usefnv::FnvHashMapasHashMap;"));
+    assert!(!code.contains("usestd::collections::HashMap;"));
+    assert!(code.contains("letmutmap=HashMap::default();"));
+    let deps: Vec<_> = detect_dependencies(&rust)
+        .unwrap()
+        .iter()
+        .map(|d| d.name)
+        .collect();
+    assert_eq!(deps, ["fnv"]);
+}
+
+#[test]
+fn test_throughput_profile_uses_ahash() {
+    let rust = DepylerPipeline::new()
+        .with_performance_profile(PerformanceProfile::Throughput)
+        .transpile(COUNTS)
+        .unwrap();
+    let code = compact(&rust);
+
+    assert!(code.contains("useahash::AHashMapasHashMap;"));
+    assert!(code.contains("HashMap::new()"));
+}
+
+#[test]
+fn test_explicit_backend_overrides_annotations() {
+    let python = format!("# @depyler: hash = \"fnv\"{}", COUNTS);
+    let rust = DepylerPipeline::new()
+        .with_hash_backend(HashBackend::Std)
+        .transpile(&python)
+        .unwrap();
+    assert!(compact(&rust).contains("usestd::collections::HashMap;"));
+}
+
+#[test]
+fn test_conflicting_annotations_in_one_module_are_rejected() {
+    let python = r#"
+# @depyler: hash = "fnv"
+def first() -> Dict[str, int]:
+    return {"a": 1}
+
+# @depyler: hash = "ahash"
+def second() -> Dict[str, int]:
+    return {"b": 2}
+"#;
+    let err = DepylerPipeline::new().transpile(python).unwrap_err();
+    let message = format!("{:#}", err);
+    assert!(
+        message.contains("conflicting hash annotations"),
+        "{}",
+        message
+    );
+    assert!(message.contains("`first` asks for fnv and `second` for ahash"));
+}
@@ -0,0 +1,100 @@
+//! HIR documents survive a round trip and replay code generation
+
+use depyler_core::hir_format::{HirDocument, HirEncoding, HIR_FORMAT, HIR_SCHEMA_VERSION};
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+from dataclasses import dataclass
+from typing import Dict, List, Optional
+
+MAX_ITEMS = 10
+
+@dataclass
+class Item:
+    name: str
+    price: float
+
+def total(items: List[Item], discount: Optional[float] = None) -> float:
+    """Sum the prices."""
+    result = 0.0
+    for item in items:
+        result += item.price
+    if discount is not None:
+        result = result * (1.0 - discount)
+    return result
+
+def index_names(items: List[Item]) -> Dict[str, int]:
+    return {item.name: i for i, item in enumerate(items)}
+
+def parse(text: str) -> int:
+    try:
+        return int(text)
+    except ValueError:
+        raise ValueError("not a number")
+"#;
+
+fn round_trip(encoding: HirEncoding) {
+    let pipeline = DepylerPipeline::new();
+    let hir = pipeline.parse_to_hir(SOURCE).unwrap();
+
+    let bytes = HirDocument::new(hir.clone()).encode(encoding).unwrap();
+    let document = HirDocument::decode(&bytes, encoding).unwrap();
+
+    assert_eq!(document.format, HIR_FORMAT);
+    assert_eq!(document.schema_version, HIR_SCHEMA_VERSION);
+    assert_eq!(document.module, hir);
+    assert_eq!(
+        pipeline.transpile_hir(document.module).unwrap(),
+        pipeline.transpile(SOURCE).unwrap()
+    );
+}
+
+#[test]
+fn test_json_round_trip() {
+    round_trip(HirEncoding::Json);
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn test_cbor_round_trip() {
+    round_trip(HirEncoding::Cbor);
+}
+
+#[test]
+fn test_newer_schema_is_refused() {
+    let hir = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+    let mut document = HirDocument::new(hir);
+    document.schema_version = HIR_SCHEMA_VERSION + 1;
+    let bytes = document.encode(HirEncoding::Json).unwrap();
+
+    let err = HirDocument::decode(&bytes, HirEncoding::Json).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("is newer than the supported version"));
+}
+
+#[test]
+fn test_other_json_is_not_a_document() {
+    let err = HirDocument::decode(
+        br#"{"format": "other", "schema_version": 1}"#,
+        HirEncoding::Json,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("not a HIR document"));
+    assert!(HirDocument::decode(b"[]", HirEncoding::Json).is_err());
+}
+
+#[test]
+fn test_encoding_follows_the_extension() {
+    use std::path::Path;
+
+    assert_eq!(
+        HirEncoding::from_path(Path::new("out.hir.json")),
+        Some(HirEncoding::Json)
+    );
+    assert_eq!(
+        HirEncoding::from_path(Path::new("out.cbor")),
+        Some(HirEncoding::Cbor)
+    );
+    assert_eq!(HirEncoding::from_path(Path::new("module.py")), None);
+}
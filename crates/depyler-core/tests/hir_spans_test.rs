@@ -0,0 +1,49 @@
+//! Spans collected beside the HIR line up with its statements
+
+use depyler_core::hir::HirStmt;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def classify(values: List[int]) -> int:
+    """Count positives."""
+    count = 0
+    for v in values:
+        if v > 0:
+            count += 1
+    try:
+        check(count)
+    except ValueError:
+        count = 0
+    return count
+
+def check(n: int) -> None:
+    if n < 0:
+        raise ValueError("negative")
+"#;
+
+#[test]
+fn test_spans_parallel_hir_bodies() {
+    let (hir, spans) = DepylerPipeline::new()
+        .parse_to_hir_with_spans(SOURCE)
+        .unwrap();
+
+    for func in &hir.functions {
+        let function = spans.function(&func.name).unwrap();
+        assert_eq!(function.body.len(), func.body.len(), "{}", func.name);
+    }
+
+    let classify = spans.function("classify").unwrap();
+    let lines: Vec<_> = classify
+        .body
+        .iter()
+        .map(|s| *spans.lines(s.span).start())
+        .collect();
+    assert_eq!(lines, [4, 5, 8, 12]);
+
+    // The handler body is the second block of the `try`
+    let func = &hir.functions[0];
+    assert!(matches!(func.body[2], HirStmt::Try { .. }));
+    let handler_stmt = classify.statement(&[2, 1, 0]).unwrap();
+    assert_eq!(spans.position(handler_stmt.span.start).line, 11);
+    assert_eq!(spans.position(handler_stmt.span.start).column, 9);
+}
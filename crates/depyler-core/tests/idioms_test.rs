@@ -0,0 +1,129 @@
+//! The `idioms` pass turns accumulation loops into iterator chains
+
+mod common;
+
+use common::compact;
+use depyler_core::optimize::PassOptions;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def sum_of_squares(xs: list[int]) -> int:
+    total = 0
+    for x in xs:
+        if x > 0:
+            total += x * x
+    return total
+
+def total_weight(ws: list[float]) -> float:
+    total = 0.0
+    for w in ws:
+        total += w * 2.0
+    return total
+
+def tens(xs: list[int]) -> list[int]:
+    out: list[int] = []
+    for x in xs:
+        if x % 2 == 0:
+            out.append(x * 10)
+    return out
+
+def has_negative(xs: list[int]) -> bool:
+    found = False
+    for x in xs:
+        if x < 0:
+            found = True
+            break
+    return found
+
+def all_small(xs: list[int]) -> bool:
+    ok = True
+    for x in xs:
+        if x >= 100:
+            ok = False
+    return ok
+
+def largest(xs: list[int]) -> int:
+    best = xs[0]
+    for x in xs:
+        if x > best:
+            best = x
+    return best
+"#;
+
+fn with_idioms() -> DepylerPipeline {
+    DepylerPipeline::new().with_pass_options(PassOptions {
+        toggles: [("idioms".to_string(), true)].into(),
+        ..PassOptions::default()
+    })
+}
+
+#[test]
+fn test_accumulation_loops_become_iterator_chains() {
+    let (rust, metrics) = with_idioms().transpile_with_metrics(SOURCE).unwrap();
+    println!("Generated code:\n{}", rust);
+    let code = compact(&rust);
+
+    let idioms = metrics
+        .passes
+        .iter()
+        .find(|pass| pass.name == "idioms")
+        .unwrap();
+    assert_eq!(idioms.nodes_simplified, 6);
+    assert!(!code.contains("forxinxs"));
+    assert!(code.contains("xs.iter().copied().filter(|&x|"));
+    assert!(code.contains(".sum::<i32>()"));
+    assert!(code.contains(".sum::<f64>()"));
+    assert!(code.contains(".collect::<Vec<_>>()"));
+    assert!(code.contains(".any(|x|x)"));
+    assert!(code.contains(".all(|x|x)"));
+    assert!(code.contains("*xs.iter().max().unwrap()"));
+}
+
+#[test]
+fn test_idioms_are_off_by_default() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("forxin"));
+    assert!(!code.contains(".any(|x|x)"));
+}
+
+#[test]
+fn test_rewritten_loops_compute_the_same() {
+    let rust_code = with_idioms().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(sum_of_squares(&vec![3, -2, 1]), 10);
+    assert_eq!(total_weight(&vec![0.5, 1.5]), 4.0);
+    assert_eq!(tens(&vec![1, 2, 3, 4]), vec![20, 40]);
+    assert!(has_negative(&vec![1, -1]));
+    assert!(!has_negative(&vec![1, 2]));
+    assert!(all_small(&vec![1, 99]));
+    assert!(!all_small(&vec![1, 100]));
+    assert_eq!(largest(&vec![3, 7, 5]), 7);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("idioms.rs");
+    let binary = dir.path().join("idioms");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
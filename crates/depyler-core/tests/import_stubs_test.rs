@@ -0,0 +1,105 @@
+//! `todo!()` stubs for imports without a Rust mapping
+//!
+//! Functions and classes used from an unmapped module get stubs in a Rust
+//! module named after it, typed from call sites or from a `.pyi` stub.
+
+mod common;
+
+use common::{compact, transpile};
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_called_module_function_gets_a_stub() {
+    let code = transpile(
+        r#"
+import requests
+
+def fetch(url: str) -> str:
+    return requests.get(url, 10)
+"#,
+    );
+
+    assert!(code.contains("pubmodrequests{"));
+    assert!(code.contains(
+        "pubfnget(_arg0:implInto<String>,_arg1:i32)->String{todo!(\"portrequests.get\")}"
+    ));
+    assert!(code.contains("requests::get("));
+    assert!(!code.contains("TODO:MapPythonmodule'requests'"));
+}
+
+#[test]
+fn test_unknown_types_become_generics() {
+    let code = transpile(
+        r#"
+from telemetry import emit, snapshot
+
+def report() -> None:
+    emit(None)
+    state = snapshot()
+    print(state)
+"#,
+    );
+
+    assert!(code.contains("pubmodtelemetry{"));
+    assert!(code.contains("pubfnemit<A0>(_arg0:A0){todo!(\"porttelemetry.emit\")}"));
+    assert!(code.contains("pubfnsnapshot<R>()->R{todo!(\"porttelemetry.snapshot\")}"));
+    assert!(code.contains("telemetry::emit("));
+}
+
+#[test]
+fn test_used_class_gets_struct_constructor_and_methods() {
+    let code = transpile(
+        r#"
+from store import Client
+
+def save(key: str) -> bool:
+    client = Client("localhost")
+    client.put(key, 1)
+    return client.exists(key)
+"#,
+    );
+
+    assert!(code.contains("pubstructClient;"));
+    assert!(code.contains("pubfnnew(_arg0:implInto<String>)->Self{todo!(\"portstore.Client\")}"));
+    assert!(code.contains(
+        "pubfnput(&self,_arg0:implInto<String>,_arg1:i32){todo!(\"portstore.Client.put\")}"
+    ));
+    assert!(code.contains("pubfnexists(&self,_arg0:implInto<String>)->bool{"));
+    assert!(code.contains("usestore::Client;"));
+}
+
+#[test]
+fn test_pyi_stub_supplies_the_signature() {
+    let rust = DepylerPipeline::new()
+        .with_pyi_stub(
+            "geometry",
+            "def area(width: float, height: float) -> float: ...\n",
+        )
+        .transpile(
+            r#"
+import geometry
+
+def room(w, h):
+    return geometry.area(w, h)
+"#,
+        )
+        .unwrap();
+    let code = compact(&rust);
+
+    assert!(code.contains("pubfnarea(_width:f64,_height:f64)->f64{todo!(\"portgeometry.area\")}"));
+}
+
+#[test]
+fn test_unused_unmapped_import_keeps_the_todo_comment() {
+    let code = transpile(
+        r#"
+import requests
+
+def noop() -> int:
+    return 1
+"#,
+    );
+
+    assert!(!code.contains("pubmodrequests"));
+    assert!(code.contains("TODO:MapPythonmodule'requests'"));
+}
@@ -0,0 +1,193 @@
+//! `with_injectable_io` passes stdin, stdout and stderr to the functions
+//! doing I/O, so tests can run them on buffers
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+import sys
+
+def greet(name: str) -> None:
+    print("Hello,", name)
+
+def ask() -> str:
+    return input("Name? ")
+
+def warn(message: str) -> None:
+    sys.stderr.write(message)
+
+def run() -> int:
+    name = ask()
+    if name == "":
+        warn("no name\n")
+        return 1
+    greet(name)
+    return 0
+
+def double(x: int) -> int:
+    return x * 2
+"#;
+
+#[test]
+fn test_io_goes_to_the_std_streams_by_default() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("pubfngreet(name:"));
+    assert!(code.contains("std::io::BufRead::read_line(&mutstd::io::stdin().lock(),&mutline)"));
+    assert!(code.contains("std::io::Write::write_all(&mutstd::io::stderr().lock(),"));
+    assert!(!code.contains("implstd::io::Write"));
+}
+
+#[test]
+fn test_streams_are_threaded_to_the_functions_using_them() {
+    let rust = DepylerPipeline::new()
+        .with_injectable_io()
+        .transpile(SOURCE)
+        .unwrap();
+    println!("Generated code:\n{}", rust);
+    let code = compact(&rust);
+
+    assert!(code.contains("pubfngreet(stdout:&mutimplstd::io::Write,name:"));
+    assert!(code.contains("writeln!(stdout,"));
+    assert!(code.contains(
+        "pubfnask(stdin:&mutimplstd::io::BufRead,stdout:&mutimplstd::io::Write)->String"
+    ));
+    assert!(code.contains("pubfnwarn(stderr:&mutimplstd::io::Write,message:"));
+    // run() reads and writes only through the functions it calls
+    assert!(code.contains(
+        "pubfnrun(stdin:&mutimplstd::io::BufRead,stdout:&mutimplstd::io::Write,stderr:&mutimplstd::io::Write"
+    ));
+    assert!(code.contains("ask(stdin,stdout)"));
+    assert!(code.contains("pubfndouble(x:i32)->i32"));
+}
+
+#[test]
+fn test_injected_functions_run_on_buffers() {
+    let rust_code = DepylerPipeline::new()
+        .with_injectable_io()
+        .transpile(SOURCE)
+        .unwrap();
+    let checks = r##"
+fn main() {
+    let mut input = std::io::Cursor::new(b"Ada\n".to_vec());
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    assert_eq!(run(&mut input, &mut out, &mut err), 0);
+    assert_eq!(String::from_utf8(out).unwrap(), "Name? Hello, Ada\n");
+    assert!(err.is_empty());
+
+    let mut input = std::io::Cursor::new(b"\n".to_vec());
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    assert_eq!(run(&mut input, &mut out, &mut err), 1);
+    assert_eq!(String::from_utf8(err).unwrap(), "no name\n");
+}
+"##;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("cli.rs");
+    let binary = dir.path().join("cli");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
+
+#[test]
+fn test_main_locks_stdin_only_around_reads() {
+    let source = r#"
+class Banner:
+    def __init__(self, title: str) -> None:
+        self.title = title
+
+    def show(self) -> None:
+        print(self.title)
+
+def ask(prompt: str) -> str:
+    return input(prompt)
+
+def main() -> None:
+    title = input()
+    banner = Banner(title)
+    banner.show()
+    name = ask("Name? ")
+    print("Hello,", name)
+"#;
+    let rust_code = DepylerPipeline::new()
+        .with_injectable_io()
+        .transpile(source)
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("banner.rs");
+    let binary = dir.path().join("banner");
+    std::fs::write(&path, &rust_code).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&path)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut child = Command::new(&binary)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::io::Write::write_all(child.stdin.as_mut().unwrap(), b"Menu\nAda\n").unwrap();
+    let run = child.wait_with_output().unwrap();
+    assert!(run.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&run.stdout),
+        "Menu\nName? Hello, Ada\n"
+    );
+}
+
+#[test]
+fn test_methods_reading_stdin_are_rejected() {
+    let source = r#"
+class Form:
+    def __init__(self) -> None:
+        self.name = ""
+
+    def fill(self) -> None:
+        self.name = input("Name? ")
+
+def main() -> None:
+    form = Form()
+    form.fill()
+    print(form.name)
+"#;
+    assert!(DepylerPipeline::new().transpile(source).is_ok());
+    let error = DepylerPipeline::new()
+        .with_injectable_io()
+        .transpile(source)
+        .unwrap_err();
+    let message = format!("{:#}", error);
+    assert!(
+        message.contains("cannot pass stdin into `Form`"),
+        "{}",
+        message
+    );
+}
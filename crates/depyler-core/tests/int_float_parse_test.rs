@@ -0,0 +1,126 @@
+//! `int(s)` and `float(s)` parse strings with Python's rules and raise
+//! `ValueError`
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+#[test]
+fn test_int_of_string_raises_by_panicking() {
+    let code = transpile("def parse_count(text: str) -> int:\n    return int(text)\n");
+
+    assert!(code.contains("py_int(&text,10).unwrap_or_else(|e|panic!(\"ValueError:{}\",e))"));
+    assert!(!code.contains("unwrap_or_default"));
+}
+
+#[test]
+fn test_value_error_propagates_from_functions_raising_it() {
+    let code = transpile(
+        r#"
+def checked(s: str) -> int:
+    n = int(s)
+    if n < 0:
+        raise ValueError("negative")
+    return n
+"#,
+    );
+
+    assert!(code.contains("py_int(&s,10)?"));
+    assert!(code.contains("Result<i32,ValueError>"));
+}
+
+#[test]
+fn test_caught_value_error_uses_the_handler_value() {
+    let code = transpile(
+        r#"
+def parse_or(s: str) -> int:
+    try:
+        return int(s, 0)
+    except ValueError:
+        return -1
+"#,
+    );
+
+    assert!(code.contains("py_int(&s,0).unwrap_or(-1)"));
+}
+
+#[test]
+fn test_float_of_string() {
+    let code = transpile("def ratio(text: str) -> float:\n    return float(text) / 2.0\n");
+
+    assert!(code.contains("py_float(&text)"));
+    assert!(code.contains("fnpy_float(text:&str)->Result<f64,ValueError>"));
+}
+
+#[test]
+fn test_parsing_follows_python() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def parse_int(s: str, base: int) -> int:
+    try:
+        return int(s, base)
+    except ValueError:
+        return -1
+
+def parse_float(s: str) -> float:
+    try:
+        return float(s)
+    except ValueError:
+        return -1.0
+"#,
+        )
+        .unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(py_int(" 42\n", 10).unwrap(), 42);
+    assert_eq!(py_int("-1_000", 10).unwrap(), -1000);
+    assert_eq!(py_int("+7", 10).unwrap(), 7);
+    assert_eq!(py_int("0x1F", 0).unwrap(), 31);
+    assert_eq!(py_int("0o17", 0).unwrap(), 15);
+    assert_eq!(py_int("-0b_101", 0).unwrap(), -5);
+    assert_eq!(py_int("ff", 16).unwrap(), 255);
+    assert_eq!(py_int("0xff", 16).unwrap(), 255);
+    assert_eq!(py_int("000", 0).unwrap(), 0);
+    for bad in ["", " ", "1__0", "_1", "1_", "12a", "4.0", "010", "0x"] {
+        assert!(py_int(bad, 0).is_err(), "{:?} should not parse", bad);
+    }
+    assert!(py_int("12", 1).is_err());
+    assert!(py_int("99999999999", 10).is_err());
+    let message = py_int("abc", 10).unwrap_err().to_string();
+    assert!(message.contains("invalid literal for int() with base 10: 'abc'"));
+
+    assert_eq!(py_float(" 2.5 ").unwrap(), 2.5);
+    assert_eq!(py_float("1_000.5").unwrap(), 1000.5);
+    assert!(py_float("-inf").unwrap().is_infinite());
+    assert!(py_float("nan").unwrap().is_nan());
+    for bad in ["", "1__0", "_1", "1._5", "abc"] {
+        assert!(py_float(bad).is_err(), "{:?} should not parse", bad);
+    }
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("parse.rs");
+    let binary = dir.path().join("parse");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,195 @@
+//! `integer_semantics`, per function by annotation or per module by
+//! option: panicking, wrapping, saturating, overflow-checked and
+//! arbitrary-precision ints
+
+mod common;
+
+use common::compact;
+use depyler_annotations::IntegerSemantics;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const CHECKED: &str = r#"
+# @depyler: integer_semantics = "checked"
+def scale(total: int, factor: int) -> int:
+    result = total * factor
+    return result + 1
+
+# @depyler: integer_semantics = "checked"
+def greet(name: str) -> str:
+    return "hi " + name
+
+def plain(a: int, b: int) -> int:
+    return a + b
+"#;
+
+#[test]
+fn test_checked_arithmetic_raises_overflow_error() {
+    let code = compact(&DepylerPipeline::new().transpile(CHECKED).unwrap());
+
+    assert!(code.contains("fnscale(total:i32,factor:i32)->Result<i32,OverflowError>"));
+    assert!(code.contains(
+        "i32::checked_mul(total,factor).ok_or_else(||OverflowError::new(\"integermultiplicationoverflowed\"))?"
+    ));
+    assert!(code.contains("i32::checked_add(result,1)"));
+    assert!(code.contains("pubstructOverflowError"));
+    // String concatenation cannot overflow, and unannotated functions wrap
+    let greet = &code[code.find("fngreet").unwrap()..];
+    assert!(!greet[..greet.find('{').unwrap()].contains("Result"));
+    assert!(code.contains("fnplain(a:i32,b:i32)->i32"));
+    assert!(code.contains("a+b"));
+}
+
+/// Compiles `rust_code` with a `main` running `checks`, and runs it
+fn compile_and_run(rust_code: &str, checks: &str) {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("semantics.rs");
+    let binary = dir.path().join("semantics");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
+
+#[test]
+fn test_checked_overflow_is_an_error_at_runtime() {
+    let rust_code = DepylerPipeline::new().transpile(CHECKED).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(scale(6, 7).unwrap(), 43);
+    assert!(scale(i32::MAX, 2).is_err());
+    assert!(scale(i32::MAX, 1).is_err());
+}
+"#;
+    compile_and_run(&rust_code, checks);
+}
+
+const MODULE: &str = r#"
+def grow(a: int, b: int) -> int:
+    return a * b + 1
+
+def flip(a: int) -> int:
+    return -a
+
+def cube(a: int) -> int:
+    return a ** 3
+
+# @depyler: integer_semantics = "saturating"
+def clamp_sum(a: int, b: int) -> int:
+    return a + b
+"#;
+
+#[test]
+fn test_module_default_applies_to_unannotated_functions() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .with_integer_semantics(IntegerSemantics::Wrapping)
+            .transpile(MODULE)
+            .unwrap(),
+    );
+
+    assert!(code.contains("i32::wrapping_add(i32::wrapping_mul(a,b),1)"));
+    assert!(code.contains("i32::wrapping_neg(a)"));
+    assert!(code.contains("i32::wrapping_pow(a,3)"));
+    // The annotation wins over the module's default
+    assert!(code.contains("i32::saturating_add(a,b)"));
+    assert!(!code.contains("Result"));
+
+    // Without a module default, unannotated functions keep the operators
+    let code = compact(&DepylerPipeline::new().transpile(MODULE).unwrap());
+    assert!(code.contains("a*b+1"));
+    assert!(code.contains("i32::saturating_add(a,b)"));
+}
+
+#[test]
+fn test_checked_module_makes_arithmetic_fallible() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .with_integer_semantics(IntegerSemantics::Checked)
+            .transpile(MODULE)
+            .unwrap(),
+    );
+
+    assert!(code.contains("fnflip(a:i32)->Result<i32,OverflowError>"));
+    assert!(code.contains(
+        "i32::checked_neg(a).ok_or_else(||OverflowError::new(\"integernegationoverflowed\"))?"
+    ));
+    assert!(code.contains("i32::checked_pow(a,3)"));
+    assert!(code.contains("fnclamp_sum(a:i32,b:i32)->i32"));
+}
+
+#[test]
+fn test_wrapping_and_saturating_at_runtime() {
+    let rust_code = DepylerPipeline::new()
+        .with_integer_semantics(IntegerSemantics::Wrapping)
+        .transpile(MODULE)
+        .unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(grow(6, 7), 43);
+    assert_eq!(grow(i32::MAX, 1), i32::MIN);
+    assert_eq!(flip(i32::MIN), i32::MIN);
+    assert_eq!(cube(2000), 2000i32.wrapping_pow(3));
+    assert_eq!(clamp_sum(i32::MAX, 5), i32::MAX);
+    assert_eq!(clamp_sum(i32::MIN, -5), i32::MIN);
+}
+"#;
+    compile_and_run(&rust_code, checks);
+}
+
+#[test]
+fn test_bigint_maps_int_and_adds_the_dependency() {
+    let python = r#"
+# @depyler: integer_semantics = "bigint"
+def power_of_two(n: int) -> int:
+    result = 1
+    while n > 0:
+        result = result * 2
+        n = n - 1
+    return result
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    let code = compact(&rust_code);
+
+    assert!(code.contains("fnpower_of_two(mutn:num_bigint::BigInt)->num_bigint::BigInt"));
+    assert!(code.contains("letmutresult=num_bigint::BigInt::from(1);"));
+    assert!(code.contains("whilen>num_bigint::BigInt::from(0)"));
+    assert!(code.contains("result=&result*&num_bigint::BigInt::from(2);"));
+    assert!(code.contains("n=&n-&num_bigint::BigInt::from(1);"));
+
+    let names: Vec<&str> = detect_dependencies(&rust_code)
+        .unwrap()
+        .iter()
+        .map(|dep| dep.name)
+        .collect();
+    assert!(names.contains(&"num_bigint"));
+}
+
+#[test]
+fn test_bigint_rejects_true_division() {
+    let python = r#"
+# @depyler: integer_semantics = "bigint"
+def half(n: int) -> int:
+    return n / 2
+"#;
+    let error = DepylerPipeline::new().transpile(python).unwrap_err();
+    let message = format!("{:#}", error);
+    assert!(message.contains("`/` on arbitrary-precision ints is not supported"));
+}
@@ -0,0 +1,95 @@
+//! `iter()` and `next()` drive Rust iterators held in locals, with
+//! `StopIteration` raised and caught as in Python
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+from typing import Optional
+
+def second(xs: list[int]) -> int:
+    it = iter(xs)
+    next(it, 0)
+    return next(it, -1)
+
+def first_or_none(xs: list[str]) -> Optional[str]:
+    return next(iter(xs), None)
+
+def first(xs: list[int]) -> int:
+    it = iter(xs)
+    return next(it)
+
+def head(xs: list[int]) -> int:
+    it = iter(xs)
+    try:
+        value = next(it)
+    except StopIteration:
+        value = -1
+    return value
+
+def total(xs: list[int]) -> int:
+    it = iter(xs)
+    result = 0
+    while True:
+        try:
+            item = next(it)
+        except StopIteration:
+            break
+        result = result + item
+    return result
+"#;
+
+#[test]
+fn test_iterators_are_mutable_locals() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("letmutit=xs.iter().cloned();"));
+    assert!(code.contains("it.next().unwrap_or(-1)"));
+    assert!(code.contains("Result<i32,StopIteration>"));
+    assert!(!code.contains("expect(\"StopIteration"));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(second(vec![1, 2, 3]), 2);
+    assert_eq!(second(vec![1]), -1);
+    assert_eq!(first_or_none(vec!["a".to_string()]), Some("a".to_string()));
+    assert_eq!(first_or_none(vec![]), None);
+    assert_eq!(first(vec![7]).unwrap(), 7);
+    assert!(first(vec![]).is_err());
+    assert_eq!(head(vec![4, 5]), 4);
+    assert_eq!(head(vec![]), -1);
+    assert_eq!(total(vec![1, 2, 3]), 6);
+    assert_eq!(total(vec![]), 0);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("iterators.rs");
+    let binary = dir.path().join("iterators");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,66 @@
+//! Tests for module-level values computed at runtime
+//!
+//! Values that are neither literals nor foldable into consts become
+//! `LazyLock` statics, and initializers that depend on each other in a
+//! cycle are rejected.
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_function_call_becomes_lazy_static() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def load_config() -> dict[str, str]:
+    return {"mode": "fast"}
+
+CONFIG = load_config()
+
+def mode() -> str:
+    return CONFIG["mode"]
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains(
+        "pubstaticCONFIG:std::sync::LazyLock<HashMap<String,String>>=std::sync::LazyLock::new(||load_config());"
+    ));
+    assert!(rust_code.contains("(*CONFIG)"));
+    assert!(!rust_code.contains("pubconstCONFIG"));
+}
+
+#[test]
+fn test_lazy_statics_from_literals_and_classes() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+class Registry:
+    def __init__(self):
+        self.count = 0
+
+LIMITS = {"low": 1, "high": 10}
+REGISTRY = Registry()
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubstaticLIMITS:std::sync::LazyLock<HashMap<String,i32>>"));
+    assert!(rust_code.contains("pubstaticREGISTRY:std::sync::LazyLock<Registry>"));
+}
+
+#[test]
+fn test_initialization_cycle_is_rejected() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def make_a() -> list[int]:
+    return B
+
+def make_b() -> list[int]:
+    return A
+
+A = make_a()
+B = make_b()
+"#;
+    let error = pipeline.transpile(python_code).unwrap_err();
+
+    assert!(format!("{:#}", error).contains("A -> B -> A"));
+}
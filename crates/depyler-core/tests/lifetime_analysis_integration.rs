@@ -1,27 +1,21 @@
-use depyler_annotations::TranspilationAnnotations;
-use depyler_core::hir::{
-    BinOp, FunctionProperties, HirExpr, HirFunction, HirModule, HirParam, HirStmt, Literal, Type,
-};
+use depyler_core::hir::{BinOp, HirExpr, HirModule, HirStmt, Literal, Type};
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::lifetime_analysis::LifetimeInference;
 use depyler_core::rust_gen::generate_rust_file;
 use depyler_core::type_mapper::TypeMapper;
-use smallvec::smallvec;
 
 #[test]
 fn test_lifetime_inference_for_string_parameter() {
     // Create a function that takes a string and returns its length
-    let func = HirFunction {
-        name: "get_length".to_string(),
-        params: smallvec![HirParam::new("s".to_string(), Type::String)],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Attribute {
+    let func = HirFunctionBuilder::new("get_length")
+        .param("s", Type::String)
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Attribute {
             value: Box::new(HirExpr::Var("s".to_string())),
             attr: "len".to_string(),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: Some("Get the length of a string".to_string()),
-    };
+        })))
+        .docstring("Get the length of a string")
+        .build();
 
     let module = HirModule {
         functions: vec![func],
@@ -51,11 +45,9 @@ fn test_lifetime_inference_for_string_parameter() {
 #[test]
 fn test_lifetime_inference_for_mutable_parameter() {
     // Create a function that mutates a variable
-    let func = HirFunction {
-        name: "append_bang".to_string(),
-        params: smallvec![HirParam::new("s".to_string(), Type::String)],
-        ret_type: Type::None,
-        body: vec![HirStmt::Assign {
+    let func = HirFunctionBuilder::new("append_bang")
+        .param("s", Type::String)
+        .stmt(HirStmt::Assign {
             target: depyler_core::hir::AssignTarget::Symbol("s".to_string()),
             value: HirExpr::Binary {
                 op: BinOp::Add,
@@ -63,11 +55,8 @@ fn test_lifetime_inference_for_mutable_parameter() {
                 right: Box::new(HirExpr::Literal(Literal::String("!".to_string()))),
             },
             type_annotation: None,
-        }],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     let type_mapper = TypeMapper::new();
     let mut inference = LifetimeInference::new();
@@ -82,22 +71,16 @@ fn test_lifetime_inference_for_mutable_parameter() {
 #[test]
 fn test_lifetime_inference_with_multiple_parameters() {
     // Create a function with multiple string parameters
-    let func = HirFunction {
-        name: "concat_strings".to_string(),
-        params: smallvec![
-            HirParam::new("s1".to_string(), Type::String),
-            HirParam::new("s2".to_string(), Type::String)
-        ],
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("concat_strings")
+        .param("s1", Type::String)
+        .param("s2", Type::String)
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Add,
             left: Box::new(HirExpr::Var("s1".to_string())),
             right: Box::new(HirExpr::Var("s2".to_string())),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     let module = HirModule {
         functions: vec![func],
@@ -125,15 +108,11 @@ fn test_lifetime_inference_with_multiple_parameters() {
 #[test]
 fn test_lifetime_inference_escaping_parameter() {
     // Create a function that returns one of its parameters
-    let func = HirFunction {
-        name: "identity".to_string(),
-        params: smallvec![HirParam::new("x".to_string(), Type::String)],
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Var("x".to_string())))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("identity")
+        .param("x", Type::String)
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Var("x".to_string()))))
+        .build();
 
     let type_mapper = TypeMapper::new();
     let mut inference = LifetimeInference::new();
@@ -150,18 +129,12 @@ fn test_lifetime_inference_escaping_parameter() {
 #[test]
 fn test_lifetime_bounds_generation() {
     // Create a function where one parameter must outlive another
-    let func = HirFunction {
-        name: "select_first".to_string(),
-        params: smallvec![
-            HirParam::new("a".to_string(), Type::String),
-            HirParam::new("b".to_string(), Type::String)
-        ],
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Var("a".to_string())))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("select_first")
+        .param("a", Type::String)
+        .param("b", Type::String)
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Var("a".to_string()))))
+        .build();
 
     let type_mapper = TypeMapper::new();
     let mut inference = LifetimeInference::new();
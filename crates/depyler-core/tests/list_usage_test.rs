@@ -0,0 +1,140 @@
+//! Local lists worked on from the front become `VecDeque`s, and lists only
+//! used for membership tests become `HashSet`s
+
+mod common;
+
+use common::{compact, transpile};
+use depyler_core::rust_gen::ledger::DivergenceKind;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def countdown(n: int) -> list[int]:
+    order: list[int] = []
+    pending = [n]
+    while pending:
+        x = pending.pop(0)
+        order.append(x)
+        if x > 0:
+            pending.append(x - 1)
+        if x > 1:
+            pending.insert(0, x - 2)
+    return order
+
+def rotate(k: int) -> int:
+    window: list[int] = [1, 2, 3]
+    for i in range(k):
+        x = window.pop(0)
+        window.append(x)
+    return window[0]
+
+def count_unique(xs: list[int]) -> int:
+    seen: list[int] = []
+    n = 0
+    for x in xs:
+        if x not in seen:
+            seen.append(x)
+            n += 1
+    return n
+
+def is_vowel(c: str) -> bool:
+    vowels = ["a", "e", "i", "o", "u"]
+    return c in vowels
+"#;
+
+#[test]
+fn test_queues_become_vecdeques() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("usestd::collections::VecDeque;"));
+    assert!(code.contains("letmutpending=VecDeque::from(vec![n]);"));
+    assert!(code.contains("pending.pop_front().expect(\"IndexError:popfromemptylist\")"));
+    assert!(code.contains("pending.push_back("));
+    assert!(code.contains("pending.push_front("));
+    assert!(code.contains("letmutwindow:VecDeque<i32>=VecDeque::from(vec![1,2,3]);"));
+    // The list handed back to the caller stays a Vec
+    assert!(code.contains("letmutorder:Vec<i32>=vec![];"));
+    assert!(code.contains("order.push(x)"));
+}
+
+#[test]
+fn test_membership_lists_become_hashsets() {
+    let rust = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let code = compact(&rust);
+
+    assert!(code.contains("usestd::collections::HashSet;"));
+    assert!(code.contains("letmutseen:HashSet<i32>=HashSet::new();"));
+    assert!(code.contains("!seen.contains(&x)"));
+    assert!(code.contains("seen.insert(x)"));
+    assert!(code.contains(".into_iter().collect::<HashSet<_>>()"));
+}
+
+#[test]
+fn test_substitutions_are_reported_and_can_be_declined() {
+    let (_, ledger) = DepylerPipeline::new()
+        .transpile_with_ledger(SOURCE)
+        .unwrap();
+    let reasons: Vec<_> = ledger
+        .entries
+        .iter()
+        .filter(|e| e.kind == DivergenceKind::ContainerSubstitution)
+        .map(|e| (e.function.as_str(), e.detail.as_str()))
+        .collect();
+    assert_eq!(reasons.len(), 4);
+    assert!(reasons.contains(&(
+        "countdown",
+        "`pending` is a `VecDeque`: 2 front pops and inserts, 0 indexed accesses"
+    )));
+    assert!(reasons
+        .iter()
+        .any(|(f, detail)| *f == "count_unique" && detail.starts_with("`seen` is a `HashSet`")));
+
+    let declined = format!(
+        "# @depyler: containers = \"as_written\"\n{}",
+        SOURCE.trim_start()
+    );
+    let code = compact(&DepylerPipeline::new().transpile(&declined).unwrap());
+    assert!(code.contains("letmutpending=vec![n];"));
+    // The annotation covers the function below it only
+    assert!(code.contains("letmutseen:HashSet<i32>=HashSet::new();"));
+}
+
+#[test]
+fn test_substituted_lists_behave_like_python_lists() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(countdown(3), vec![3, 1, 2, 0, 0, 1, 0]);
+    assert_eq!(countdown(0), vec![0]);
+    assert_eq!(rotate(0), 1);
+    assert_eq!(rotate(1), 2);
+    assert_eq!(rotate(4), 2);
+    assert_eq!(count_unique(&vec![3, 1, 3, 2, 1]), 3);
+    assert!(is_vowel("e"));
+    assert!(!is_vowel("x"));
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("containers.rs");
+    let binary = dir.path().join("containers");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
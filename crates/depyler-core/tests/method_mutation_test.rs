@@ -0,0 +1,73 @@
+//! Tests for `&mut self` inference across method calls
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_method_calling_mutating_method_takes_mut_self() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def increment(self):
+        self.count += 1
+
+    def increment_twice(self):
+        self.increment()
+        self.increment()
+
+    def value(self) -> int:
+        return self.count
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubfnincrement(&mutself)"));
+    assert!(rust_code.contains("pubfnincrement_twice(&mutself)"));
+    assert!(rust_code.contains("pubfnvalue(&self)"));
+}
+
+#[test]
+fn test_mutation_through_field_holding_another_class() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def increment(self):
+        self.count += 1
+
+class Stats:
+    def __init__(self, hits: Counter):
+        self.hits = hits
+
+    def record(self):
+        self.hits.increment()
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubfnrecord(&mutself)"));
+}
+
+#[test]
+fn test_parameter_calling_mutating_method_is_mutable() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def increment(self):
+        self.count += 1
+
+def bump(counter: Counter) -> None:
+    counter.increment()
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("counter:&mutCounter") || rust_code.contains("mutcounter:Counter"));
+}
@@ -1,7 +1,7 @@
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::migration_suggestions::*;
 use proptest::prelude::*;
-use smallvec::smallvec;
 
 // Generate arbitrary migration config
 prop_compose! {
@@ -71,15 +71,10 @@ prop_compose! {
         body in prop::collection::vec(arb_simple_stmt(), stmt_count),
         name in Just(func_name)
     ) -> HirFunction {
-        HirFunction {
-            name,
-            params: smallvec![],
-            ret_type: Type::Unknown,
-            body,
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        }
+        HirFunctionBuilder::new(name)
+            .returns(Type::Unknown)
+            .body(body)
+            .build()
     }
 }
 
@@ -172,20 +167,15 @@ proptest! {
     ) {
         let mut analyzer = MigrationAnalyzer::new(config);
 
-        let func = HirFunction {
-            name: func_name,
-            params: smallvec![],
-            ret_type: Type::Unknown,
-            body: vec![
+        let func = HirFunctionBuilder::new(func_name)
+            .returns(Type::Unknown)
+            .body(vec![
                 HirStmt::While {
                     condition: HirExpr::Literal(Literal::Bool(true)),
                     body: vec![HirStmt::Break { label: None }],
                 }
-            ],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        };
+            ])
+            .build();
 
         let program = HirProgram {
             imports: vec![],
@@ -208,11 +198,9 @@ proptest! {
     ) {
         let mut analyzer = MigrationAnalyzer::new(config);
 
-        let func = HirFunction {
-            name: func_name,
-            params: smallvec![],
-            ret_type: Type::Unknown,
-            body: vec![
+        let func = HirFunctionBuilder::new(func_name)
+            .returns(Type::Unknown)
+            .body(vec![
                 HirStmt::If {
                     condition: HirExpr::Call { func: "isinstance".to_string(), args: vec![
                             HirExpr::Var(var_name),
@@ -221,11 +209,8 @@ proptest! {
                     then_body: vec![HirStmt::Expr(HirExpr::Literal(Literal::None))],
                     else_body: None,
                 }
-            ],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        };
+            ])
+            .build();
 
         let program = HirProgram {
             imports: vec![],
@@ -251,21 +236,16 @@ proptest! {
     ) {
         let mut analyzer = MigrationAnalyzer::new(config);
 
-        let func = HirFunction {
-            name: func_name,
-            params: smallvec![],
-            ret_type: Type::Unknown,
-            body: vec![
+        let func = HirFunctionBuilder::new(func_name)
+            .returns(Type::Unknown)
+            .body(vec![
                 HirStmt::For {
                     target: AssignTarget::Symbol(target),
                     iter: HirExpr::Call { func: "enumerate".to_string(), args: vec![HirExpr::Var(items)], kwargs: vec![] },
                     body: vec![HirStmt::Expr(HirExpr::Literal(Literal::None))],
                 }
-            ],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        };
+            ])
+            .build();
 
         let program = HirProgram {
             imports: vec![],
@@ -0,0 +1,112 @@
+//! Comparisons between int, float and bool, and between lists, as Python
+//! makes them
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Point:
+    x: int
+    y: int
+
+def same_number(n: int, x: float) -> bool:
+    return n == x
+
+def below_one(x: float) -> bool:
+    return x < 1
+
+def differs_from_half(n: int) -> bool:
+    return n != 0.5
+
+def flag_is_one(flag: bool) -> bool:
+    return flag == 1
+
+def same_values() -> bool:
+    ints = [1, 2]
+    floats = [1.0, 2.0]
+    return ints == floats
+
+def different_values() -> bool:
+    ints = [1, 2]
+    floats = [1.0, 2.5]
+    return ints != floats
+
+def is_origin(x: int, y: int) -> bool:
+    point = [x, y]
+    return point == [0, 0]
+
+def nothing_in(x: int) -> bool:
+    items = [x]
+    return items == []
+
+def same_points(a: list[Point], b: list[Point]) -> bool:
+    return a == b
+"#;
+
+#[test]
+fn test_narrower_operand_is_promoted() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("(nasf64)==x"));
+    assert!(code.contains("x<1.0"));
+    assert!(code.contains("i32::from(flag)==1"));
+    assert!(code.contains("all(|(a,b)|(*aasf64)==*b)"));
+    assert!(code.contains("items.is_empty()"));
+}
+
+#[test]
+fn test_compared_classes_derive_partial_eq() {
+    let code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+
+    assert!(code.contains("PartialEq"));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert!(same_number(1, 1.0));
+    assert!(!same_number(1, 1.5));
+    assert!(below_one(0.5));
+    assert!(!below_one(1.0));
+    assert!(differs_from_half(0));
+    assert!(flag_is_one(true));
+    assert!(!flag_is_one(false));
+    assert!(same_values());
+    assert!(different_values());
+    assert!(is_origin(0, 0));
+    assert!(!is_origin(0, 1));
+    assert!(!nothing_in(3));
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("compare.rs");
+    let binary = dir.path().join("compare");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,126 @@
+//! attrs and pydantic model classes lowered like dataclasses
+
+use depyler_core::hir::ModelKind;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_attrs_define_fields_defaults_and_validator() {
+    let python = r#"
+from typing import List
+from attrs import define, field
+
+@define
+class Inventory:
+    name: str
+    count: int = 0
+    tags: List[str] = field(factory=list)
+
+    @count.validator
+    def _check_count(self, attribute, value) -> None:
+        if value < 0:
+            raise ValueError("count must be non-negative")
+"#;
+    let hir = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    let class = &hir.classes[0];
+    assert_eq!(class.model_kind, Some(ModelKind::Attrs));
+    assert!(class.is_dataclass);
+    let names: Vec<_> = class.fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["name", "count", "tags"]);
+    assert!(class.fields.iter().all(|f| !f.is_class_var));
+
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("serde::Serialize"));
+    assert!(rust_code.contains("serde::Deserialize"));
+    assert!(rust_code.contains("pub fn new(name: String) -> Result<Self, ValueError>"));
+    assert!(rust_code.contains("count: 0"));
+    assert!(rust_code.contains("tags: Default::default()"));
+    assert!(rust_code.contains("return Err(ValueError::new("));
+    assert!(!rust_code.contains("_check_count"));
+}
+
+#[test]
+fn test_pydantic_field_constraints_and_validator() {
+    let python = r#"
+from pydantic import BaseModel, Field, field_validator
+
+class User(BaseModel):
+    name: str
+    age: int = Field(ge=0)
+
+    @field_validator("name")
+    @classmethod
+    def strip_name(cls, v: str) -> str:
+        if v == "":
+            raise ValueError("name must not be empty")
+        return v
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("serde::Deserialize"));
+    assert!(rust_code.contains("pub fn new(name: String, age: i32) -> Result<Self, ValueError>"));
+    assert!(rust_code.contains("this.age < 0"));
+    assert!(rust_code.contains("age: Input should be greater than or equal to 0"));
+    assert!(rust_code.contains("this.name = v"));
+    assert!(!rust_code.contains("strip_name"));
+}
+
+#[test]
+fn test_plain_pydantic_model_keeps_infallible_new() {
+    let python = r#"
+from pydantic import BaseModel
+
+class Point(BaseModel):
+    x: float
+    y: float = 1.5
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub fn new(x: f64) -> Self"));
+    assert!(!rust_code.contains("Result<Self"));
+}
+
+#[test]
+fn test_unsupported_constructs_are_rejected_precisely() {
+    let root_validator = r#"
+from pydantic import BaseModel, root_validator
+
+class Window(BaseModel):
+    low: int
+    high: int
+
+    @root_validator
+    def check(cls, values):
+        return values
+"#;
+    let err = format!(
+        "{:#}",
+        DepylerPipeline::new()
+            .transpile(root_validator)
+            .unwrap_err()
+    );
+    assert!(
+        err.contains("`@root_validator` is not supported"),
+        "{}",
+        err
+    );
+
+    let field_option = r#"
+from pydantic import BaseModel, Field
+
+class Price(BaseModel):
+    cents: int = Field(multiple_of=5)
+"#;
+    let err = format!(
+        "{:#}",
+        DepylerPipeline::new().transpile(field_option).unwrap_err()
+    );
+    assert!(
+        err.contains("pydantic field `Price.cents`: `multiple_of=` is not supported"),
+        "{}",
+        err
+    );
+}
@@ -0,0 +1,91 @@
+//! `ErrorModel::ModuleEnum`: one error enum per module
+//!
+//! Every exception a module raises or catches becomes a variant of
+//! `ModuleError`, and every fallible function returns it.
+
+mod common;
+
+use common::transpile_with;
+use depyler_core::rust_gen::ErrorModel;
+use depyler_core::DepylerPipeline;
+
+const PARSER: &str = r#"
+def parse_port(text: str) -> int:
+    if not text:
+        raise ValueError("empty port")
+    port = int(text)
+    if port > 65535:
+        raise OverflowError("port out of range")
+    return port
+
+def lookup(table: Dict[str, int], key: str) -> int:
+    if key not in table:
+        raise KeyError(key)
+    return table[key]
+"#;
+
+fn transpile(python: &str) -> String {
+    transpile_with(
+        DepylerPipeline::new().with_error_model(ErrorModel::ModuleEnum),
+        python,
+    )
+}
+
+#[test]
+fn test_enum_has_a_variant_per_exception() {
+    let code = transpile(PARSER);
+
+    assert!(code.contains("pubenumModuleError{"));
+    for variant in [
+        "KeyError(KeyError)",
+        "OverflowError(OverflowError)",
+        "ValueError(ValueError)",
+    ] {
+        assert!(code.contains(variant), "missing {}", variant);
+    }
+    assert!(code.contains("Other(String)"));
+    assert!(code.contains("implFrom<KeyError>forModuleError"));
+    assert!(code.contains("implstd::fmt::DisplayforModuleError"));
+    // Structs for exceptions beyond the predefined ones
+    assert!(code.contains("pubstructKeyError{"));
+    assert!(code.contains("pubstructOverflowError{"));
+    assert!(code.contains("pubstructValueError{"));
+}
+
+#[test]
+fn test_fallible_functions_return_module_error() {
+    let code = transpile(PARSER);
+
+    assert!(code.contains("Result<i32,ModuleError>"));
+    // Raised exceptions convert into the enum
+    assert!(code.contains("returnErr(ValueError::new("));
+    assert!(code.contains("returnErr(OverflowError::new("));
+    assert!(code.contains(").into());"));
+}
+
+#[test]
+fn test_caught_exceptions_are_variants() {
+    let python = r#"
+def safe_div(a: int, b: int) -> int:
+    try:
+        return a // b
+    except ZeroDivisionError:
+        return 0
+"#;
+    let code = transpile(python);
+    assert!(code.contains("ZeroDivisionError(ZeroDivisionError)"));
+    assert!(code.contains("pubstructZeroDivisionError{"));
+}
+
+#[test]
+fn test_module_without_exceptions_has_no_enum() {
+    let code = transpile("def add(a: int, b: int) -> int:\n    return a + b\n");
+    assert!(!code.contains("ModuleError"));
+}
+
+#[test]
+fn test_per_function_model_is_the_default() {
+    let rust = DepylerPipeline::new().transpile(PARSER).unwrap();
+    assert!(!rust.contains("ModuleError"));
+    assert!(rust.contains("Box<dyn std::error::Error>"));
+}
@@ -0,0 +1,79 @@
+//! List, set and dict comprehensions with several `for` and `if` clauses
+//! lower to `flat_map` chains and give Python's results
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def pairs(n: int) -> list[int]:
+    return [x * 10 + y for x in range(n) if x > 0 for y in range(x) if y != 1]
+
+def flatten(matrix: list[list[str]]) -> list[str]:
+    return [item for row in matrix for item in row]
+
+def products(n: int) -> set[int]:
+    return {x * y for x in range(n) for y in range(n) if x < y}
+
+def index(words: list[str]) -> dict[str, int]:
+    return {c: i for i in range(2) for w in words if len(w) > i for c in w}
+"#;
+
+#[test]
+fn test_clauses_become_flat_map_chains() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("(0..n).flat_map(|x|ifx>0{(0..x).filter_map(|y|{letx=x.clone();"));
+    assert!(code.contains("matrix.iter().cloned().flat_map(|row|row.iter().cloned().map(|item|item).collect::<Vec<_>>())"));
+    assert!(code.contains(".collect::<HashSet<_>>()"));
+    assert!(code.contains(".collect::<HashMap<_,_>>()"));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(pairs(4), vec![10, 20, 30, 32]);
+    let matrix = vec![
+        vec!["a".to_string(), "b".to_string()],
+        vec![],
+        vec!["c".to_string()],
+    ];
+    assert_eq!(flatten(matrix), vec!["a", "b", "c"]);
+    let mut products: Vec<i32> = products(4).into_iter().collect();
+    products.sort();
+    assert_eq!(products, vec![0, 2, 3, 6]);
+    let index = index(vec!["ab".to_string(), "c".to_string()]);
+    assert_eq!(index.len(), 3);
+    assert_eq!(index["a"], 1);
+    assert_eq!(index["b"], 1);
+    assert_eq!(index["c"], 0);
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("comprehensions.rs");
+    let binary = dir.path().join("comprehensions");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
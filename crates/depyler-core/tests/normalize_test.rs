@@ -0,0 +1,151 @@
+//! Surface forms reach the HIR in their core form
+
+use depyler_core::hir::{AssignTarget, BinOp, HirExpr, HirStmt};
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+fn body(python: &str) -> Vec<HirStmt> {
+    let mut module = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    module.functions.remove(0).body
+}
+
+#[test]
+fn test_chained_assignment_is_one_assignment_per_target() {
+    let body = body(
+        r#"
+def f() -> int:
+    a = b = len([1, 2])
+    return a + b
+"#,
+    );
+
+    match &body[..] {
+        [HirStmt::Assign {
+            target: AssignTarget::Symbol(a),
+            value: HirExpr::Call { .. },
+            ..
+        }, HirStmt::Assign {
+            target: AssignTarget::Symbol(b),
+            value:
+                HirExpr::MethodCall {
+                    object,
+                    method,
+                    args,
+                    ..
+                },
+            ..
+        }, HirStmt::Return(_)] => {
+            assert_eq!((a.as_str(), b.as_str()), ("a", "b"));
+            assert!(matches!(&**object, HirExpr::Var(module) if module == "copy"));
+            assert_eq!(method, "copy");
+            assert!(matches!(&args[..], [HirExpr::Var(copied)] if copied == "a"));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_chained_assignment_leaves_the_first_target_usable() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def sizes() -> int:
+    a = b = [1, 2]
+    b.append(3)
+    return len(a) + len(b)
+"#,
+        )
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("chained.rs");
+    std::fs::write(&source, &rust_code).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "--crate-type", "lib"])
+        .arg("--out-dir")
+        .arg(dir.path())
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_multiple_context_managers_nest() {
+    let body = body(
+        r#"
+def copy(src: str, dst: str) -> None:
+    with open(src) as f, open(dst, "w") as g:
+        g.write(f.read())
+"#,
+    );
+
+    match &body[0] {
+        HirStmt::With {
+            target: Some(f),
+            body: inner,
+            ..
+        } => {
+            assert_eq!(f, "f");
+            assert!(matches!(&inner[0], HirStmt::With { target: Some(g), .. } if g == "g"));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_chained_comparison_splits_into_single_comparisons() {
+    let body = body(
+        r#"
+def in_range(x: int) -> bool:
+    return 0 <= x < 10
+"#,
+    );
+
+    match &body[0] {
+        HirStmt::Return(Some(HirExpr::Binary {
+            op: BinOp::And,
+            left,
+            right,
+        })) => {
+            assert!(matches!(
+                &**left,
+                HirExpr::Binary {
+                    op: BinOp::LtEq,
+                    ..
+                }
+            ));
+            assert!(matches!(&**right, HirExpr::Binary { op: BinOp::Lt, .. }));
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_augmented_assignment_in_nested_blocks() {
+    let body = body(
+        r#"
+def f(n: int) -> int:
+    total = 0
+    for i in range(n):
+        total += i
+    return total
+"#,
+    );
+
+    match &body[1] {
+        HirStmt::For { body, .. } => assert!(matches!(
+            &body[0],
+            HirStmt::Assign {
+                value: HirExpr::Binary { op: BinOp::Add, .. },
+                ..
+            }
+        )),
+        other => panic!("unexpected {:?}", other),
+    }
+}
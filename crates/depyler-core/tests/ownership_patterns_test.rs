@@ -1,12 +1,9 @@
 //! Tests for improved ownership pattern inference
 
-use depyler_annotations::TranspilationAnnotations;
 use depyler_core::borrowing_context::{BorrowingContext, BorrowingStrategy};
-use depyler_core::hir::{
-    BinOp, FunctionProperties, HirExpr, HirFunction, HirParam, HirStmt, Literal, Type as PythonType,
-};
+use depyler_core::hir::{BinOp, HirExpr, HirStmt, Literal, Type as PythonType};
+use depyler_core::hir_builder::HirFunctionBuilder;
 use depyler_core::type_mapper::TypeMapper;
-use smallvec::smallvec;
 
 #[test]
 fn test_read_only_string_borrowed() {
@@ -14,15 +11,15 @@ fn test_read_only_string_borrowed() {
     let type_mapper = TypeMapper::new();
 
     // Function that only reads a string parameter
-    let func = HirFunction {
-        name: "get_length".to_string(),
-        params: smallvec![HirParam::new("text".to_string(), PythonType::String)],
-        ret_type: PythonType::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Call { func: "len".to_string(), args: vec![HirExpr::Var("text".to_string())], kwargs: vec![] }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("get_length")
+        .param("text", PythonType::String)
+        .returns(PythonType::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Call {
+            func: "len".to_string(),
+            args: vec![HirExpr::Var("text".to_string())],
+            kwargs: vec![],
+        })))
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
     let strategy = result.param_strategies.get("text").unwrap();
@@ -40,21 +37,17 @@ fn test_list_append_takes_ownership() {
     let type_mapper = TypeMapper::new();
 
     // Function that appends to a list
-    let func = HirFunction {
-        name: "add_item".to_string(),
-        params: smallvec![HirParam::new(
-            "items".to_string(),
-            PythonType::List(Box::new(PythonType::Int))
-        )],
-        ret_type: PythonType::None,
-        body: vec![HirStmt::Expr(HirExpr::Call { func: "append".to_string(), args: vec![
+    let func = HirFunctionBuilder::new("add_item")
+        .param("items", PythonType::List(Box::new(PythonType::Int)))
+        .stmt(HirStmt::Expr(HirExpr::Call {
+            func: "append".to_string(),
+            args: vec![
                 HirExpr::Var("items".to_string()),
                 HirExpr::Literal(Literal::Int(42)),
-            ], kwargs: vec![] })],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+            ],
+            kwargs: vec![],
+        }))
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
     let strategy = result.param_strategies.get("items").unwrap();
@@ -69,15 +62,11 @@ fn test_escaping_parameter_takes_ownership() {
     let type_mapper = TypeMapper::new();
 
     // Function that returns its parameter
-    let func = HirFunction {
-        name: "identity".to_string(),
-        params: smallvec![HirParam::new("value".to_string(), PythonType::String)],
-        ret_type: PythonType::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Var("value".to_string())))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("identity")
+        .param("value", PythonType::String)
+        .returns(PythonType::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Var("value".to_string()))))
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
     let strategy = result.param_strategies.get("value").unwrap();
@@ -94,19 +83,15 @@ fn test_string_concatenation_uses_cow() {
     let type_mapper = TypeMapper::new();
 
     // Function that returns a modified string
-    let func = HirFunction {
-        name: "add_suffix".to_string(),
-        params: smallvec![HirParam::new("prefix".to_string(), PythonType::String)],
-        ret_type: PythonType::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("add_suffix")
+        .param("prefix", PythonType::String)
+        .returns(PythonType::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Add,
             left: Box::new(HirExpr::Var("prefix".to_string())),
             right: Box::new(HirExpr::Literal(Literal::String("_suffix".to_string()))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
     let strategy = result.param_strategies.get("prefix").unwrap();
@@ -125,19 +110,15 @@ fn test_copy_type_takes_value() {
     let type_mapper = TypeMapper::new();
 
     // Function with integer parameter
-    let func = HirFunction {
-        name: "double".to_string(),
-        params: smallvec![HirParam::new("n".to_string(), PythonType::Int)],
-        ret_type: PythonType::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("double")
+        .param("n", PythonType::Int)
+        .returns(PythonType::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Mul,
             left: Box::new(HirExpr::Var("n".to_string())),
             right: Box::new(HirExpr::Literal(Literal::Int(2))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
     let strategy = result.param_strategies.get("n").unwrap();
@@ -160,19 +141,14 @@ fn test_unnecessary_move_detection() {
     let type_mapper = TypeMapper::new();
 
     // Function that passes string to a function that could borrow
-    let func = HirFunction {
-        name: "print_message".to_string(),
-        params: smallvec![HirParam::new("msg".to_string(), PythonType::String)],
-        ret_type: PythonType::None,
-        body: vec![HirStmt::Expr(HirExpr::Call {
+    let func = HirFunctionBuilder::new("print_message")
+        .param("msg", PythonType::String)
+        .body(vec![HirStmt::Expr(HirExpr::Call {
             func: "unknown_function".to_string(), // Conservative: assumes ownership
             args: vec![HirExpr::Var("msg".to_string())],
             kwargs: vec![],
-        })],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        })])
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
 
@@ -191,14 +167,11 @@ fn test_loop_usage_affects_borrowing() {
     let type_mapper = TypeMapper::new();
 
     // Function that uses parameter in a loop
-    let func = HirFunction {
-        name: "count_occurrences".to_string(),
-        params: smallvec![
-            HirParam::new("haystack".to_string(), PythonType::String),
-            HirParam::new("needle".to_string(), PythonType::String)
-        ],
-        ret_type: PythonType::Int,
-        body: vec![
+    let func = HirFunctionBuilder::new("count_occurrences")
+        .param("haystack", PythonType::String)
+        .param("needle", PythonType::String)
+        .returns(PythonType::Int)
+        .body(vec![
             HirStmt::Assign {
                 target: depyler_core::hir::AssignTarget::Symbol("count".to_string()),
                 value: HirExpr::Literal(Literal::Int(0)),
@@ -207,10 +180,14 @@ fn test_loop_usage_affects_borrowing() {
             HirStmt::While {
                 condition: HirExpr::Literal(Literal::Bool(true)),
                 body: vec![HirStmt::If {
-                    condition: HirExpr::Call { func: "contains".to_string(), args: vec![
+                    condition: HirExpr::Call {
+                        func: "contains".to_string(),
+                        args: vec![
                             HirExpr::Var("haystack".to_string()),
                             HirExpr::Var("needle".to_string()),
-                        ], kwargs: vec![] },
+                        ],
+                        kwargs: vec![],
+                    },
                     then_body: vec![HirStmt::Assign {
                         target: depyler_core::hir::AssignTarget::Symbol("count".to_string()),
                         value: HirExpr::Binary {
@@ -224,11 +201,8 @@ fn test_loop_usage_affects_borrowing() {
                 }],
             },
             HirStmt::Return(Some(HirExpr::Var("count".to_string()))),
-        ],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+        ])
+        .build();
 
     let result = ctx.analyze_function(&func, &type_mapper);
 
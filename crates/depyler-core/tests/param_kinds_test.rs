@@ -0,0 +1,73 @@
+//! Positional-only and keyword-only parameters keep their kind in the HIR,
+//! and calls that pass them the wrong way are rejected
+
+mod common;
+
+use common::{transpile, transpile_error};
+use depyler_core::hir::ParamKind;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def clamp(value: int, /, low: int, *, high: int = 100) -> int:
+    return max(low, min(value, high))
+
+def use_clamp() -> int:
+    return clamp(5, low=1, high=3) + clamp(50, 0)
+"#;
+
+#[test]
+fn test_param_kinds_in_hir() {
+    let module = DepylerPipeline::new().parse_to_hir(SOURCE).unwrap();
+    let kinds: Vec<ParamKind> = module.functions[0].params.iter().map(|p| p.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            ParamKind::PositionalOnly,
+            ParamKind::PositionalOrKeyword,
+            ParamKind::KeywordOnly
+        ]
+    );
+}
+
+#[test]
+fn test_calls_bind_in_signature_order() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("fnclamp(value:i32,low:i32,high:Option<i32>)->i32"));
+    assert!(code.contains("clamp(5,1,Some(3))"));
+    assert!(code.contains("clamp(50,0,None)"));
+}
+
+#[test]
+fn test_positional_only_passed_by_keyword() {
+    let source = "def f(a: int, /, b: int) -> int:\n    return a + b\n\ndef g() -> int:\n    return f(a=1, b=2)\n";
+    let err = transpile_error(source);
+    assert!(
+        err.contains("f() got a positional-only argument passed as keyword: 'a'"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_keyword_only_passed_positionally() {
+    let source = "def f(a: int, *, b: int) -> int:\n    return a + b\n\ndef g() -> int:\n    return f(1, 2)\n";
+    let err = transpile_error(source);
+    assert!(
+        err.contains("f() takes 1 positional argument but 2 were given; 'b' is keyword-only"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn test_missing_keyword_only_argument() {
+    let source =
+        "def f(a: int, *, b: int) -> int:\n    return a + b\n\ndef g() -> int:\n    return f(1)\n";
+    let err = transpile_error(source);
+    assert!(
+        err.contains("f() missing required keyword-only argument: 'b'"),
+        "{}",
+        err
+    );
+}
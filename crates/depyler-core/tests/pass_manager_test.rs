@@ -0,0 +1,76 @@
+//! HIR passes as configured through the pipeline
+
+use depyler_core::optimize::PassOptions;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def seconds_per_day() -> int:
+    return 24 * 60 * 60
+"#;
+
+fn pass_names(pipeline: DepylerPipeline) -> Vec<String> {
+    let (_, metrics) = pipeline.transpile_with_metrics(SOURCE).unwrap();
+    metrics.passes.into_iter().map(|pass| pass.name).collect()
+}
+
+#[test]
+fn test_default_passes() {
+    assert_eq!(
+        pass_names(DepylerPipeline::new()),
+        [
+            "const-generics",
+            "type-hints",
+            "annotations",
+            "integer-semantics"
+        ]
+    );
+}
+
+#[test]
+fn test_passes_are_toggled_by_name() {
+    let options = PassOptions {
+        toggles: [
+            ("const-fold".to_string(), true),
+            ("type-hints".to_string(), false),
+        ]
+        .into(),
+        ..PassOptions::default()
+    };
+    let pipeline = DepylerPipeline::new().with_pass_options(options);
+
+    assert_eq!(
+        pass_names(pipeline),
+        [
+            "const-fold",
+            "const-generics",
+            "annotations",
+            "integer-semantics"
+        ]
+    );
+}
+
+#[test]
+fn test_const_folding_is_the_const_fold_pass() {
+    let (rust_code, metrics) = DepylerPipeline::new()
+        .with_const_folding()
+        .transpile_with_metrics(SOURCE)
+        .unwrap();
+
+    assert_eq!(metrics.passes[0].name, "const-fold");
+    assert_eq!(metrics.passes[0].nodes_simplified, 2);
+    assert!(rust_code.contains("86400"));
+}
+
+#[test]
+fn test_unknown_pass_fails_the_transpile() {
+    let options = PassOptions {
+        print_hir_after: ["inlining".to_string()].into(),
+        ..PassOptions::default()
+    };
+    let err = DepylerPipeline::new()
+        .with_pass_options(options)
+        .transpile(SOURCE)
+        .unwrap_err();
+
+    assert!(err.to_string().contains("unknown HIR pass `inlining`"));
+}
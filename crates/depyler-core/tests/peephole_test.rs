@@ -0,0 +1,132 @@
+//! Idiom rewrites of the `peephole` pass
+
+use depyler_core::hir::{AssignTarget, HirExpr, HirStmt};
+use depyler_core::optimize::peephole::{PeepholeRewriter, RewriteRule};
+use depyler_core::optimize::PassOptions;
+use depyler_core::DepylerPipeline;
+
+fn rewrite(python: &str) -> (Vec<HirStmt>, usize) {
+    let mut module = DepylerPipeline::new().parse_to_hir(python).unwrap();
+    let count = PeepholeRewriter::standard()
+        .rewrite_module(&mut module)
+        .unwrap();
+    (module.functions[0].body.clone(), count)
+}
+
+#[test]
+fn test_concatenating_one_element_becomes_an_append() {
+    let (body, count) = rewrite(
+        r#"
+def squares(n: int) -> list:
+    result = []
+    for i in range(n):
+        result = result + [i * i]
+    return result
+"#,
+    );
+
+    assert_eq!(count, 1);
+    match &body[1] {
+        HirStmt::For { body, .. } => assert!(matches!(
+            &body[0],
+            HirStmt::Expr(HirExpr::MethodCall { method, .. }) if method == "append"
+        )),
+        other => panic!("expected a for loop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_parameters_are_not_appended_to() {
+    let (_, count) = rewrite("def grow(xs: list) -> list:\n    xs = xs + [0]\n    return xs\n");
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_range_len_over_a_read_only_parameter_becomes_enumerate() {
+    let (body, count) = rewrite(
+        r#"
+def total(values: list[int]) -> int:
+    s = 0
+    for i in range(len(values)):
+        s = s + values[i] * i
+    return s
+"#,
+    );
+
+    assert_eq!(count, 1);
+    match &body[1] {
+        HirStmt::For {
+            target: AssignTarget::Tuple(targets),
+            iter: HirExpr::Call { func, .. },
+            ..
+        } => {
+            assert_eq!(func, "enumerate");
+            assert_eq!(targets[0], AssignTarget::Symbol("i".to_string()));
+        }
+        other => panic!("expected an enumerate loop, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_range_len_over_a_mutated_list_is_kept() {
+    let (_, count) = rewrite(
+        r#"
+def double(values: list[int]) -> None:
+    for i in range(len(values)):
+        values[i] = values[i] * 2
+"#,
+    );
+    assert_eq!(count, 0);
+
+    let (_, count) = rewrite(
+        r#"
+def local() -> int:
+    values = [1, 2, 3]
+    s = 0
+    for i in range(len(values)):
+        s = s + values[i]
+    return s
+"#,
+    );
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_enumerate_loop_generates_an_iterator() {
+    let rust_code = DepylerPipeline::new()
+        .transpile(
+            r#"
+def weighted(values: list[int]) -> int:
+    s = 0
+    for i in range(len(values)):
+        s = s + values[i] * i
+    return s
+"#,
+        )
+        .unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains(".enumerate()"));
+    assert!(!rust_code.contains("0..values.len()"));
+}
+
+#[test]
+fn test_pipeline_rules_run_after_the_standard_ones() {
+    let python = "def f() -> list:\n    xs = [3, 1, 2]\n    xs = sorted(xs)\n    return xs\n";
+    let rule = RewriteRule::parse("sorted-in-place", "$x = sorted($x)", "$x.sort()").unwrap();
+
+    let rust_code = DepylerPipeline::new()
+        .with_rewrite_rule(rule.clone())
+        .transpile(python)
+        .unwrap();
+    assert!(rust_code.contains("xs.sort()"));
+
+    let mut options = PassOptions::default();
+    options.toggles.insert("peephole".to_string(), false);
+    let rust_code = DepylerPipeline::new()
+        .with_rewrite_rule(rule)
+        .with_pass_options(options)
+        .transpile(python)
+        .unwrap();
+    assert!(!rust_code.contains("xs.sort()"));
+}
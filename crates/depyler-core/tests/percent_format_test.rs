@@ -0,0 +1,106 @@
+//! printf-style `%` formatting lowers to `format!`
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def describe(name: str, count: int) -> str:
+    return "%s: %d" % (name, count)
+
+def padded(n: int) -> str:
+    return "[%05d|%-4d|%4s]" % (n, n, "ab")
+
+def price(amount: float) -> str:
+    return "$%.2f (%+.1f%%)" % (amount, amount / 10.0)
+
+def quoted(word: str) -> str:
+    return "got %r" % word
+
+def flags(on: bool, ratio: float) -> str:
+    return "%s %s %d" % (on, ratio, ratio)
+
+def codes(n: int) -> str:
+    return "%x %X %#o %c {%s}" % (n, n, n, 65, n)
+
+def radix_widths(n: int) -> str:
+    return "[%6x|%-6x|%06x|%#08x|%+x]" % (n, n, n, n, n)
+
+class Point:
+    def __init__(self, x: int, y: int):
+        self.x = x
+        self.y = y
+
+    def label(self) -> str:
+        return "(%d, %d)" % (self.x, self.y)
+"#;
+
+#[test]
+fn test_percent_formatting_becomes_format() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains(r#"format!("{}:{}",name,count)"#));
+    assert!(code.contains(r#"format!("[{:05}|{:<4}|{:>4}]",n,n,"#));
+    assert!(code.contains(r#"format!("${:.2}({:+.1}%)",amount,amount/10.0)"#));
+    assert!(code.contains("word.py_repr()"));
+    assert!(code.contains("(ratioasi64)"));
+    assert!(code.contains(r#""{}{}{}{}{{{}}}""#));
+    assert!(code.contains(r#"format!("{}{:#o}",sign,__value.unsigned_abs())"#));
+    assert!(code.contains(r#"format!("({},{})",self.x,self.y)"#));
+    assert!(!code.contains("%%"));
+}
+
+#[test]
+fn test_unsupported_templates_are_errors() {
+    let err = DepylerPipeline::new()
+        .transpile("def f(x: float) -> str:\n    return \"%e\" % x\n")
+        .unwrap_err();
+    assert!(format!("{:#}", err).contains("`%e` has no `format!` equivalent"));
+    assert!(DepylerPipeline::new()
+        .transpile("def f(x: int) -> str:\n    return \"%d %d\" % (x,)\n")
+        .is_err());
+}
+
+#[test]
+fn test_formatted_strings_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(describe("apples", 3), "apples: 3");
+    assert_eq!(padded(-42), "[-0042|-42 |  ab]");
+    assert_eq!(price(19.0), "$19.00 (+1.9%)");
+    assert_eq!(quoted("it"), "got 'it'");
+    assert_eq!(flags(true, 2.75), "True 2.75 2");
+    assert_eq!(codes(255), "ff FF 0o377 A {255}");
+    assert_eq!(codes(-255), "-ff -FF -0o377 A {-255}");
+    assert_eq!(radix_widths(255), "[    ff|ff    |0000ff|0x0000ff|+ff]");
+    assert_eq!(radix_widths(-255), "[   -ff|-ff   |-000ff|-0x000ff|-ff]");
+    assert_eq!(Point::new(1, -2).label(), "(1, -2)");
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("percent.rs");
+    let binary = dir.path().join("percent");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,73 @@
+//! `time.perf_counter()` / timeit idioms lowered to `std::time::Instant`
+//!
+//! `start = time.perf_counter(); ...; elapsed = time.perf_counter() - start`
+//! must produce `start.elapsed().as_secs_f64()` rather than Instant arithmetic.
+
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_perf_counter_delta_becomes_elapsed() {
+    let python = r#"
+import time
+
+def measure(n: int) -> float:
+    start = time.perf_counter()
+    total = 0
+    for i in range(n):
+        total = total + i
+    elapsed = time.perf_counter() - start
+    return elapsed
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("let start = std::time::Instant::now()"));
+    assert!(rust_code.contains("start.elapsed().as_secs_f64()"));
+    assert!(!rust_code.contains("std::time::Instant::now() - start"));
+}
+
+#[test]
+fn test_end_minus_start_uses_duration_since() {
+    let python = r#"
+import time
+
+def measure() -> float:
+    start = time.monotonic()
+    end = time.monotonic()
+    return end - start
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(rust_code.contains("end.checked_duration_since(start)"));
+    assert!(rust_code.contains("-start.duration_since(end).as_secs_f64()"));
+}
+
+#[test]
+fn test_start_minus_now_is_negative_elapsed() {
+    let python = r#"
+import time
+
+def measure() -> float:
+    start = time.perf_counter()
+    return start - time.perf_counter()
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(rust_code.contains("-start.elapsed().as_secs_f64()"));
+    assert!(!rust_code.contains("duration_since(std::time::Instant::now())"));
+}
+
+#[test]
+fn test_timeit_default_timer_maps_to_instant() {
+    let python = r#"
+import timeit
+
+def measure() -> float:
+    start = timeit.default_timer()
+    return timeit.default_timer() - start
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(rust_code.contains("std::time::Instant::now()"));
+    assert!(rust_code.contains("start.elapsed().as_secs_f64()"));
+}
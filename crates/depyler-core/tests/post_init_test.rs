@@ -0,0 +1,80 @@
+//! Dataclass `__post_init__` folded into the generated `new()`
+
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_raising_post_init_makes_new_fallible() {
+    let python = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Range:
+    low: int
+    high: int
+
+    def __post_init__(self) -> None:
+        if self.low > self.high:
+            raise ValueError("low must not exceed high")
+
+def make_range(a: int, b: int) -> Range:
+    return Range(a, b)
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("-> Result<Self, ValueError>"));
+    assert!(rust_code.contains("let this = Self {"));
+    assert!(rust_code.contains("this.low") && rust_code.contains("this.high"));
+    assert!(rust_code.contains("return Err(ValueError::new("));
+    assert!(rust_code.contains("Ok(this)"));
+    assert!(rust_code.contains("pub struct ValueError"));
+    assert!(!rust_code.contains("__post_init__"));
+
+    // The caller becomes fallible and propagates the constructor error
+    assert!(rust_code.contains("Range::new(a, b)?"));
+}
+
+#[test]
+fn test_non_raising_post_init_keeps_plain_new() {
+    let python = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Counter:
+    start: int
+    current: int
+
+    def __post_init__(self) -> None:
+        self.current = self.start
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("let mut this = Self {"));
+    assert!(rust_code.contains("this.current = "));
+    assert!(!rust_code.contains("Result<Self"));
+}
+
+#[test]
+fn test_several_exception_types_use_boxed_error() {
+    let python = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Slot:
+    index: int
+    size: int
+
+    def __post_init__(self) -> None:
+        if self.size == 0:
+            raise ValueError("empty slot")
+        if self.index >= self.size:
+            raise IndexError("index out of range")
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("-> Result<Self, Box<dyn std::error::Error>>"));
+    assert!(rust_code.contains("return Err(Box::new(ValueError::new("));
+    assert!(rust_code.contains("return Err(Box::new(IndexError::new("));
+}
@@ -0,0 +1,59 @@
+//! `print()` statements are kept, stripped or logged, for a whole module or
+//! per function with a `print` annotation
+
+mod common;
+
+use common::{compact, transpile, transpile_with};
+use depyler_annotations::PrintHandling;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def total(items: list[int]) -> int:
+    result = 0
+    for item in items:
+        print("adding", item)
+        result = result + item
+    print()
+    return result
+
+# @depyler: print = "keep"
+def report(result: int) -> None:
+    print("total", result)
+"#;
+
+fn transpile_handling(handling: PrintHandling) -> String {
+    transpile_with(DepylerPipeline::new().with_print_handling(handling), SOURCE)
+}
+
+#[test]
+fn test_prints_are_kept_by_default() {
+    let code = transpile(SOURCE);
+    assert!(code.contains(r#"println!("{}{}","adding",item);"#));
+    assert!(code.contains("println!();"));
+    assert!(!code.contains("log::"));
+}
+
+#[test]
+fn test_strip_drops_prints() {
+    let code = transpile_handling(PrintHandling::Strip);
+    assert!(!code.contains("adding"));
+    assert!(!code.contains("println!();"));
+    // The annotation keeps the prints of `report`
+    assert!(code.contains(r#"println!("{}{}","total",result);"#));
+}
+
+#[test]
+fn test_log_routes_prints_to_debug() {
+    let rust_code = DepylerPipeline::new()
+        .with_print_handling(PrintHandling::Log)
+        .transpile(SOURCE)
+        .unwrap();
+    let code = compact(&rust_code);
+    assert!(code.contains(r#"log::debug!("{}{}","adding",item);"#));
+    assert!(code.contains(r#"log::debug!("");"#));
+    assert!(code.contains(r#"println!("{}{}","total",result);"#));
+
+    let dependencies = detect_dependencies(&rust_code).unwrap();
+    assert!(dependencies.iter().any(|dep| dep.name == "log"));
+}
@@ -0,0 +1,314 @@
+//! Transpiling the modules of a multi-file project together
+
+use depyler_core::DepylerPipeline;
+use std::fs;
+use std::path::Path;
+
+fn write(root: &Path, relative: &str, source: &str) {
+    let path = root.join(relative);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, source).unwrap();
+}
+
+fn shop_project(root: &Path) {
+    write(
+        root,
+        "shop/__init__.py",
+        "def version() -> str:\n    return \"1.0\"\n",
+    );
+    write(
+        root,
+        "shop/prices.py",
+        "def price(cents: int) -> int:\n    return cents * 2\n",
+    );
+    write(
+        root,
+        "shop/tax.py",
+        "def tax(cents: int) -> int:\n    return cents // 10\n",
+    );
+    write(
+        root,
+        "shop/cart.py",
+        r#"
+from .prices import price
+from .tax import tax
+
+def total(items: list[int]) -> int:
+    result = 0
+    for item in items:
+        result = result + price(item) + tax(item)
+    return result
+"#,
+    );
+    write(
+        root,
+        "main.py",
+        r#"
+from shop.cart import total
+
+def run() -> int:
+    return total([1, 2, 3])
+"#,
+    );
+    write(
+        root,
+        "__pycache__/stale.py",
+        "def stale() -> int:\n    return 0\n",
+    );
+}
+
+#[test]
+fn test_modules_are_layered_by_their_imports() {
+    let dir = tempfile::tempdir().unwrap();
+    shop_project(dir.path());
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+
+    assert_eq!(
+        report.layers,
+        vec![
+            vec!["shop", "shop.prices", "shop.tax"],
+            vec!["shop.cart"],
+            vec!["main"],
+        ]
+    );
+    assert_eq!(
+        report.module("shop.cart").unwrap().dependencies,
+        vec!["shop.prices", "shop.tax"]
+    );
+    assert_eq!(
+        report.module("main").unwrap().dependencies,
+        vec!["shop.cart"]
+    );
+    assert!(report.module("stale").is_none());
+
+    let cart = &report.module("shop.cart").unwrap().rust_code;
+    assert!(cart.contains("pub fn total"));
+}
+
+#[test]
+fn test_imports_of_project_modules_use_crate_paths() {
+    let dir = tempfile::tempdir().unwrap();
+    shop_project(dir.path());
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+
+    let cart = &report.module("shop.cart").unwrap().rust_code;
+    assert!(cart.contains("use crate::shop::prices::price;"));
+    assert!(cart.contains("use crate::shop::tax::tax;"));
+    assert!(!cart.contains("TODO: Map Python module"));
+    let main = &report.module("main").unwrap().rust_code;
+    assert!(main.contains("use crate::shop::cart::total;"));
+}
+
+/// The call to `callee` in the body of `caller`
+fn call_in(rust_code: &str, caller: &str, callee: &str) -> String {
+    let body = &rust_code[rust_code.find(&format!("fn {}", caller)).unwrap()..];
+    let call = &body[body.find(&format!("{}(", callee)).unwrap()..];
+    call[..call.find(')').unwrap() + 1].to_string()
+}
+
+#[test]
+fn test_calls_into_other_modules_follow_the_callee_signature() {
+    let utils = r#"
+def keep_even(values: list[int]) -> list[int]:
+    values.append(0)
+    return [v for v in values if v % 2 == 0]
+
+def count(values: list[int]) -> int:
+    return len(values)
+"#;
+    let app = r#"
+def run(values: list[int]) -> int:
+    evens = keep_even(values)
+    return count(evens)
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "utils.py", utils);
+    write(
+        dir.path(),
+        "app.py",
+        &format!("from utils import keep_even, count\n{}", app),
+    );
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+    let single_file = DepylerPipeline::new()
+        .transpile(&format!("{}\n{}", utils, app))
+        .unwrap();
+
+    let project_app = &report.module("app").unwrap().rust_code;
+    for callee in ["keep_even", "count"] {
+        assert_eq!(
+            call_in(project_app, "run", callee),
+            call_in(&single_file, "run", callee)
+        );
+    }
+}
+
+#[test]
+fn test_whole_module_imports_call_through_the_crate_path() {
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        dir.path(),
+        "geometry.py",
+        "def area(w: int, h: int) -> int:\n    return w * h\n",
+    );
+    write(
+        dir.path(),
+        "report.py",
+        "import geometry\n\ndef show() -> int:\n    return geometry.area(2, 3)\n",
+    );
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+
+    let code = &report.module("report").unwrap().rust_code;
+    assert!(code.contains("crate::geometry::area(2"));
+    assert!(!code.contains("use crate::geometry"));
+}
+
+#[test]
+fn test_crate_files_mirror_the_package_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    shop_project(dir.path());
+    write(
+        dir.path(),
+        "tools/clock.py",
+        "def now() -> int:\n    return 0\n",
+    );
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+    let files = report.crate_files("shop").unwrap();
+
+    let paths: Vec<String> = files
+        .keys()
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "Cargo.toml",
+            "src/lib.rs",
+            "src/main_module.rs",
+            "src/shop/cart.rs",
+            "src/shop/mod.rs",
+            "src/shop/prices.rs",
+            "src/shop/tax.rs",
+            "src/tools/clock.rs",
+            "src/tools/mod.rs",
+        ]
+    );
+    assert_eq!(
+        files[Path::new("src/lib.rs")],
+        "#[path = \"main_module.rs\"]\npub mod main;\npub mod shop;\npub mod tools;\n"
+    );
+    let shop = &files[Path::new("src/shop/mod.rs")];
+    assert!(shop.contains("pub fn version"));
+    assert!(shop.ends_with("pub mod cart;\npub mod prices;\npub mod tax;\n"));
+    assert_eq!(files[Path::new("src/tools/mod.rs")], "pub mod clock;\n");
+    assert!(files[Path::new("Cargo.toml")].starts_with("[package]\nname = \"shop\"\n"));
+
+    let out = tempfile::tempdir().unwrap();
+    report.write_crate(out.path(), "shop").unwrap();
+    let cart = out.path().join("src/shop/cart.rs");
+    assert!(cart.is_file());
+
+    // Rewriting the crate keeps what was added by hand
+    let edited = format!(
+        "{}// depyler:keep-start\nfn audit() {{}}\n// depyler:keep-end\n",
+        fs::read_to_string(&cart).unwrap()
+    );
+    fs::write(&cart, edited).unwrap();
+    let conflicts = report.write_crate(out.path(), "shop").unwrap();
+    assert!(conflicts.is_empty());
+    assert!(fs::read_to_string(&cart).unwrap().contains("fn audit() {}"));
+}
+
+#[test]
+fn test_module_metrics_add_up_to_the_totals() {
+    let dir = tempfile::tempdir().unwrap();
+    shop_project(dir.path());
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+
+    assert_eq!(report.modules.len(), 5);
+    assert_eq!(report.totals.functions_transpiled, 5);
+    assert_eq!(
+        report.totals.output_size_bytes,
+        report
+            .modules
+            .iter()
+            .map(|m| m.rust_code.len())
+            .sum::<usize>()
+    );
+    assert_eq!(
+        report.totals.source_size_bytes,
+        report
+            .modules
+            .iter()
+            .map(|m| m.metrics.source_size_bytes)
+            .sum::<usize>()
+    );
+}
+
+#[test]
+fn test_import_cycles_share_a_layer() {
+    let dir = tempfile::tempdir().unwrap();
+    write(
+        dir.path(),
+        "even.py",
+        "from odd import is_odd\n\ndef is_even(n: int) -> bool:\n    return n == 0 or is_odd(n - 1)\n",
+    );
+    write(
+        dir.path(),
+        "odd.py",
+        "from even import is_even\n\ndef is_odd(n: int) -> bool:\n    return n != 0 and is_even(n - 1)\n",
+    );
+
+    let report = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap();
+
+    assert_eq!(report.layers, vec![vec!["even", "odd"]]);
+}
+
+#[test]
+fn test_files_given_directly_are_named_by_stem() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "a/util.py", "def one() -> int:\n    return 1\n");
+    write(dir.path(), "b/util.py", "def two() -> int:\n    return 2\n");
+
+    let pipeline = DepylerPipeline::new();
+    let report = pipeline
+        .transpile_project(&[dir.path().join("a/util.py")])
+        .unwrap();
+    assert_eq!(report.modules[0].name, "util");
+
+    let error = pipeline
+        .transpile_project(&[dir.path().join("a/util.py"), dir.path().join("b/util.py")])
+        .unwrap_err();
+    assert!(error.to_string().contains("module `util`"));
+}
+
+#[test]
+fn test_errors_name_the_module() {
+    let dir = tempfile::tempdir().unwrap();
+    write(dir.path(), "broken.py", "def broken(:\n");
+
+    let error = DepylerPipeline::new()
+        .transpile_project(&[dir.path().to_path_buf()])
+        .unwrap_err();
+
+    assert!(error.to_string().contains("module `broken`"));
+}
@@ -0,0 +1,114 @@
+//! `str()` and `repr()` produce what Python prints
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def show_float(x: float) -> str:
+    return str(x)
+
+def show_bool(flag: bool) -> str:
+    return str(flag)
+
+def show_scores(scores: list[float]) -> str:
+    return str(scores)
+
+def quoted(name: str) -> str:
+    return repr(name)
+
+def two() -> str:
+    return str(2.0)
+
+def nothing() -> str:
+    return str(None)
+
+def quoted_hi() -> str:
+    return repr("hi")
+
+def compared() -> str:
+    return str(1 < 2)
+"#;
+
+#[test]
+fn test_conversions_follow_the_argument_type() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("x.py_repr()"));
+    assert!(code.contains("flag.py_repr()"));
+    assert!(code.contains("name.py_repr()"));
+    assert!(code.contains("\"2.0\".to_string()"));
+    assert!(code.contains("\"None\".to_string()"));
+    assert!(code.contains("\"'hi'\".to_string()"));
+    assert!(code.contains("\"True\".to_string()"));
+}
+
+#[test]
+fn test_native_strings_keep_rust_formatting() {
+    let code = compact(
+        &DepylerPipeline::new()
+            .with_native_strings()
+            .transpile(SOURCE)
+            .unwrap(),
+    );
+
+    assert!(code.contains("x.to_string()"));
+    assert!(code.contains("format!(\"{:?}\",name)"));
+    assert!(!code.contains("PyRepr"));
+}
+
+#[test]
+fn test_output_matches_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(show_float(3.0), "3.0");
+    assert_eq!(show_float(0.1), "0.1");
+    assert_eq!(show_float(-2.5), "-2.5");
+    assert_eq!(show_float(1e20), "1e+20");
+    assert_eq!(show_float(1.5e-7), "1.5e-07");
+    assert_eq!(show_float(f64::INFINITY), "inf");
+    assert_eq!(show_bool(true), "True");
+    assert_eq!(show_bool(false), "False");
+    assert_eq!(two(), "2.0");
+    assert_eq!(nothing(), "None");
+    assert_eq!(quoted_hi(), "'hi'");
+    assert_eq!(compared(), "True");
+
+    assert_eq!("it's".py_repr(), "\"it's\"");
+    assert_eq!("a'b\"c".py_repr(), "'a\\'b\"c'");
+    assert_eq!("tab\there\n".py_repr(), "'tab\\there\\n'");
+    assert_eq!(vec![1.0, 2.5].py_repr(), "[1.0, 2.5]");
+    assert_eq!(vec!["a".to_string()].py_repr(), "['a']");
+    assert_eq!(Some(1).py_repr(), "1");
+    assert_eq!(None::<i32>.py_repr(), "None");
+    assert_eq!((1, true).py_repr(), "(1, True)");
+    assert_eq!(std::collections::HashSet::<i32>::new().py_repr(), "set()");
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("strs.rs");
+    let binary = dir.path().join("strs");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -0,0 +1,106 @@
+//! Self-referential classes: recursive fields are boxed
+
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_linked_list_node_uses_option_box() {
+    let python = r#"
+from dataclasses import dataclass
+from typing import Optional
+
+@dataclass
+class Node:
+    value: int
+    next: Optional["Node"]
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub next: Option<Box<Node>>"));
+    // Callers pass an unboxed value; the constructor boxes it
+    assert!(rust_code.contains("next: Option<Node>"));
+    assert!(rust_code.contains("next: next.map(Box::new)"));
+}
+
+#[test]
+fn test_binary_tree_children_are_boxed() {
+    let python = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Tree:
+    value: int
+    left: "Tree"
+    right: "Tree"
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub left: Box<Tree>"));
+    assert!(rust_code.contains("pub right: Box<Tree>"));
+    assert!(rust_code.contains("left: Box::new(left)"));
+}
+
+#[test]
+fn test_mutual_recursion_is_boxed() {
+    let python = r#"
+from dataclasses import dataclass
+from typing import Optional
+
+@dataclass
+class Parent:
+    child: Optional["Child"]
+
+@dataclass
+class Child:
+    parent: Optional[Parent]
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub child: Option<Box<Child>>"));
+    assert!(rust_code.contains("pub parent: Option<Box<Parent>>"));
+}
+
+#[test]
+fn test_collection_of_self_is_not_boxed() {
+    let python = r#"
+from dataclasses import dataclass
+from typing import List
+
+@dataclass
+class Dir:
+    name: str
+    children: List["Dir"]
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub children: Vec<Dir>"));
+    assert!(!rust_code.contains("Box<Dir>"));
+}
+
+#[test]
+fn test_method_assignment_and_read_are_boxed() {
+    let python = r#"
+from typing import Optional
+
+class Node:
+    def __init__(self, value: int, next: Optional["Node"]) -> None:
+        self.value = value
+        self.next = next
+
+    def set_next(self, other: Optional["Node"]) -> None:
+        self.next = other
+
+    def get_next(self) -> Optional["Node"]:
+        return self.next
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub next: Option<Box<Node>>"));
+    assert!(rust_code.contains("next: next.map(Box::new)"));
+    assert!(rust_code.contains("self.next = other.map(Box::new)"));
+    assert!(rust_code.contains("as_deref().cloned()"));
+}
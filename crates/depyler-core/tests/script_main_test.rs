@@ -0,0 +1,117 @@
+//! Module-level statements kept in a generated `main()` with `with_script_main`
+
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+/// Compiles `rust_code` as a binary and returns what it prints
+fn run(rust_code: &str) -> String {
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("script.rs");
+    let binary = dir.path().join("script");
+    std::fs::write(&source, rust_code).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(run.status.success());
+    String::from_utf8(run.stdout).unwrap()
+}
+
+fn transpile(python_code: &str) -> String {
+    DepylerPipeline::new()
+        .with_script_main()
+        .transpile(python_code)
+        .unwrap()
+}
+
+#[test]
+fn test_statements_become_main() {
+    let python_code = r#"
+def square(x: int) -> int:
+    return x * x
+
+LIMIT = 3
+print("start")
+total = 0
+for i in range(LIMIT):
+    total += square(i)
+print(total)
+"#;
+    let rust_code = transpile(python_code);
+
+    assert!(rust_code.contains("pub const LIMIT"));
+    assert_eq!(run(&rust_code), "start\n5\n");
+}
+
+#[test]
+fn test_main_guard_calls_init_first() {
+    let python_code = r#"
+def load() -> list[int]:
+    print("loading")
+    return [1, 2, 3]
+
+print("importing")
+DATA = load()
+
+if __name__ == "__main__":
+    print("running")
+    print(len(DATA))
+"#;
+    let rust_code = transpile(python_code);
+
+    assert!(rust_code.contains("pub fn init()"));
+    assert!(rust_code.contains("LazyLock::force(&DATA)"));
+    // DATA is loaded where it is assigned, not when main() first reads it
+    assert_eq!(run(&rust_code), "importing\nloading\nrunning\n3\n");
+}
+
+#[test]
+fn test_module_main_stays_the_entry_point() {
+    let python_code = r#"
+def main() -> None:
+    print("main")
+
+print("setup")
+
+if __name__ == "__main__":
+    main()
+"#;
+    let rust_code = transpile(python_code);
+
+    assert_eq!(rust_code.matches("fn main()").count(), 1);
+    assert_eq!(run(&rust_code), "setup\nmain\n");
+}
+
+#[test]
+fn test_statements_after_the_guard_are_rejected() {
+    let python_code = r#"
+if __name__ == "__main__":
+    print("running")
+print("done")
+"#;
+    let error = DepylerPipeline::new()
+        .with_script_main()
+        .transpile(python_code)
+        .unwrap_err();
+
+    assert!(format!("{:#}", error).contains("move them above it"));
+}
+
+#[test]
+fn test_statements_are_skipped_by_default() {
+    let rust_code = DepylerPipeline::new()
+        .transpile("print(\"start\")\n")
+        .unwrap();
+
+    assert!(!rust_code.contains("fn main"));
+}
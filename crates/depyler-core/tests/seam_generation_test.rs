@@ -0,0 +1,51 @@
+//! Trait seams for module functions that tests replace with `mock.patch`
+
+use depyler_core::DepylerPipeline;
+
+const MODULE: &str = r#"
+def fetch_rate(currency: str) -> float:
+    return 1.1
+
+def convert(amount: float, currency: str) -> float:
+    return amount * fetch_rate(currency)
+
+def report(amount: float) -> str:
+    return str(convert(amount, "EUR"))
+"#;
+
+#[test]
+fn test_seam_trait_default_and_caller_variant() {
+    let rust_code = DepylerPipeline::new()
+        .with_seams(vec!["fetch_rate".to_string()])
+        .transpile(MODULE)
+        .unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("pub trait FetchRateSeam"));
+    assert!(rust_code.contains("pub struct DefaultFetchRate"));
+    assert!(rust_code.contains("impl FetchRateSeam for DefaultFetchRate"));
+    assert!(rust_code.contains("fn convert_with("));
+    assert!(rust_code.contains("fetch_rate_seam: &impl FetchRateSeam"));
+    assert!(rust_code.contains("fetch_rate_seam.fetch_rate("));
+    assert!(rust_code.contains("convert_with(&DefaultFetchRate"));
+    // Only direct callers take the seam
+    assert!(!rust_code.contains("report_with"));
+}
+
+#[test]
+fn test_unknown_seam_target_is_an_error() {
+    let err = DepylerPipeline::new()
+        .with_seams(vec!["missing".to_string()])
+        .transpile(MODULE)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("seam target `missing` is not a module-level function"));
+}
+
+#[test]
+fn test_no_seams_leaves_output_unchanged() {
+    let rust_code = DepylerPipeline::new().transpile(MODULE).unwrap();
+    assert!(!rust_code.contains("Seam"));
+    assert!(!rust_code.contains("convert_with"));
+}
@@ -0,0 +1,82 @@
+//! Slices and slice assignment follow Python's index rules: negative and
+//! out-of-range bounds, negative steps, and splicing into lists
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def tail(xs: list[int]) -> list[int]:
+    return xs[-3:]
+
+def evens(xs: list[int]) -> list[int]:
+    return xs[::2]
+
+def backwards(xs: list[int]) -> list[int]:
+    return xs[5:1:-1]
+
+def every_other_back(s: str) -> str:
+    return s[-1::-2]
+
+def replace_middle(xs: list[int]) -> list[int]:
+    xs[1:3] = [20, 30, 40]
+    return xs
+
+def clear_evens(xs: list[int]) -> list[int]:
+    xs[::2] = [0, 0, 0]
+    return xs
+"#;
+
+#[test]
+fn test_slice_assignment_uses_helpers() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("py_slice(&xs,None,None,Some(2))"));
+    assert!(code.contains("py_subslice(xs,Some(-3),None)"));
+    assert!(code.contains("py_slice_assign(xs.as_mut(),Some(1),Some(3),vec![20,30,40]);"));
+    assert!(code.contains("fnpy_slice_assign_step<T>("));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    let xs: Vec<i32> = (0..8).collect();
+    assert_eq!(tail(&xs), vec![5, 6, 7]);
+    assert_eq!(tail(&vec![1]), vec![1]);
+    assert_eq!(evens(xs.clone()), vec![0, 2, 4, 6]);
+    assert_eq!(backwards(xs), vec![5, 4, 3, 2]);
+    assert_eq!(backwards(vec![1, 2]), Vec::<i32>::new());
+    assert_eq!(every_other_back("abcdef".into()), "fdb");
+    assert_eq!(replace_middle(vec![1, 2, 3, 4]), vec![1, 20, 30, 40, 4]);
+    assert_eq!(clear_evens(vec![1, 2, 3, 4, 5]).unwrap(), vec![0, 2, 0, 4, 0]);
+    assert!(clear_evens(vec![1, 2]).is_err());
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("slices.rs");
+    let binary = dir.path().join("slices");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
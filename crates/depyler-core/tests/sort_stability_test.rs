@@ -0,0 +1,94 @@
+//! Sorts are stable, order floats totally and raise `TypeError` on `None`,
+//! as Python's do
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+from typing import Optional
+
+def ascending(xs: list[float]) -> list[float]:
+    return sorted(xs)
+
+def descending(xs: list[float]) -> list[float]:
+    return sorted(xs, reverse=True)
+
+def by_score(pairs: list[tuple[str, float]]) -> list[tuple[str, float]]:
+    return sorted(pairs, key=lambda p: p[1], reverse=True)
+
+def by_length(words: list[str]) -> list[str]:
+    return sorted(words, key=lambda w: len(w), reverse=True)
+
+def in_place(xs: list[float]) -> list[float]:
+    xs.sort()
+    return xs
+
+def maybe(xs: list[Optional[int]]) -> list[Optional[int]]:
+    return sorted(xs)
+"#;
+
+#[test]
+fn test_floats_and_reversed_keys_use_comparators() {
+    let code = compact(&DepylerPipeline::new().transpile(SOURCE).unwrap());
+
+    assert!(code.contains("(*b).partial_cmp(&(*a))"));
+    assert!(code.contains("xs.sort_by(|a,b|"));
+    assert!(code.contains("let__key_a={letp=__b;"));
+    assert!(!code.contains("partial_cmp(b).unwrap()"));
+}
+
+#[test]
+fn test_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(ascending(vec![2.5, -1.0, 0.0]), vec![-1.0, 0.0, 2.5]);
+    let with_nan = ascending(vec![f64::NAN, 1.0, 0.5]);
+    assert_eq!(&with_nan[..2], &[0.5, 1.0]);
+    assert!(with_nan[2].is_nan());
+    assert_eq!(descending(vec![1.0, 3.0, 2.0]), vec![3.0, 2.0, 1.0]);
+
+    let pairs = vec![
+        ("a".to_string(), 1.0),
+        ("b".to_string(), 2.0),
+        ("c".to_string(), 1.0),
+    ];
+    let names: Vec<String> = by_score(pairs).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["b", "a", "c"]);
+
+    let words = vec!["aa".to_string(), "b".to_string(), "cc".to_string()];
+    assert_eq!(by_length(words), vec!["aa", "cc", "b"]);
+
+    assert_eq!(in_place(vec![3.0, 1.0]), vec![1.0, 3.0]);
+    assert_eq!(maybe(vec![Some(2), Some(1)]), vec![Some(1), Some(2)]);
+    assert_eq!(maybe(vec![None]), vec![None]);
+    assert!(std::panic::catch_unwind(|| maybe(vec![Some(1), None])).is_err());
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("sorts.rs");
+    let binary = dir.path().join("sorts");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
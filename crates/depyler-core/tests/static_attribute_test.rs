@@ -0,0 +1,66 @@
+//! `getattr`, `setattr` and `hasattr` with literal names become field
+//! accesses; names computed at runtime are reported with their line
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+const POINT: &str = r#"
+class Point:
+    def __init__(self, x: int, y: int):
+        self.x = x
+        self.y = y
+"#;
+
+fn transpile(functions: &str) -> anyhow::Result<String> {
+    let source = format!("{}\n{}", POINT, functions);
+    Ok(compact(&DepylerPipeline::new().transpile(&source)?))
+}
+
+#[test]
+fn test_getattr_with_a_literal_name_reads_the_field() {
+    let code = transpile("def get_x(p: Point) -> int:\n    return getattr(p, \"x\")\n").unwrap();
+    assert!(code.contains("p.x"), "{}", code);
+    assert!(!code.contains("getattr"));
+}
+
+#[test]
+fn test_getattr_default_stands_in_for_a_missing_field() {
+    let code = transpile("def get_z(p: Point) -> int:\n    return getattr(p, \"z\", 0)\n").unwrap();
+    assert!(!code.contains("p.z"));
+    assert!(!code.contains("getattr"));
+
+    let code = transpile("def get_y(p: Point) -> int:\n    return getattr(p, \"y\", 0)\n").unwrap();
+    assert!(code.contains("p.y"));
+}
+
+#[test]
+fn test_hasattr_is_decided_from_the_class_fields() {
+    let code = transpile("def has_x(p: Point) -> bool:\n    return hasattr(p, \"x\")\n").unwrap();
+    assert!(code.contains("true"), "{}", code);
+    assert!(!code.contains("hasattr"));
+
+    let code = transpile("def has_z(p: Point) -> bool:\n    return hasattr(p, \"z\")\n").unwrap();
+    assert!(code.contains("false"), "{}", code);
+}
+
+#[test]
+fn test_setattr_with_a_literal_name_assigns_the_field() {
+    let code = transpile("def reset(p: Point) -> None:\n    setattr(p, \"x\", 0)\n").unwrap();
+    assert!(code.contains("p.x=0"), "{}", code);
+}
+
+#[test]
+fn test_computed_names_are_reported_with_their_line() {
+    let err = transpile("def get(p: Point, name: str) -> int:\n    return getattr(p, name, 0)\n")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains(
+            "line 8, column 5: `getattr(p, name, 0)` in `get` names the attribute at runtime"
+        ),
+        "{}",
+        err
+    );
+}
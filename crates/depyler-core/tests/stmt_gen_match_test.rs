@@ -0,0 +1,130 @@
+//! Tests for codegen_match_stmt
+//!
+//! Covers literal, tuple, class, or- and wildcard patterns, guards, and
+//! the fallthrough arm added for non-exhaustive matches.
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_match_literal_patterns() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def describe(code: int) -> str:
+    match code:
+        case 200:
+            return "ok"
+        case 404 | 410:
+            return "gone"
+        case -1:
+            return "unknown"
+        case _:
+            return "error"
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("matchcode{"));
+    assert!(rust_code.contains("200=>{"));
+    assert!(rust_code.contains("404|410=>{"));
+    assert!(rust_code.contains("-1=>{"));
+    assert!(rust_code.contains("_=>{"));
+}
+
+#[test]
+fn test_match_string_subject_is_borrowed() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def command(name: str) -> int:
+    match name:
+        case "start":
+            return 1
+        case "stop":
+            return 2
+    return 0
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("match&*name{"));
+    assert!(rust_code.contains("\"start\"=>{"));
+    // No case is irrefutable, so the match falls through
+    assert!(rust_code.contains("_=>{}"));
+}
+
+#[test]
+fn test_match_tuple_patterns_with_guard() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def quadrant(point: tuple[int, int]) -> str:
+    match point:
+        case (0, 0):
+            return "origin"
+        case (x, 0) if x > 0:
+            return "positive x axis"
+        case (_, y):
+            return "elsewhere"
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("(0,0)=>{"));
+    assert!(rust_code.contains("(x,0)ifx>0=>{"));
+    // `y` is never read, so its binding is underscored
+    assert!(rust_code.contains("(_,_y)=>{"));
+}
+
+#[test]
+fn test_match_class_pattern() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+from dataclasses import dataclass
+
+@dataclass
+class Point:
+    x: int
+    y: int
+
+def on_axis(p: Point) -> bool:
+    match p:
+        case Point(x=0):
+            return True
+        case Point(y=0):
+            return True
+        case _:
+            return False
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("Point{x:0,..}=>{"));
+    assert!(rust_code.contains("Point{y:0,..}=>{"));
+}
+
+#[test]
+fn test_match_capture_is_irrefutable() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def double(n: int) -> int:
+    match n:
+        case 0:
+            return 0
+        case other:
+            return other * 2
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("other=>{"));
+    assert!(!rust_code.contains("_=>{}"));
+}
+
+#[test]
+fn test_match_unsupported_pattern_errors() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def first(items: list[int]) -> int:
+    match items:
+        case [head, *rest]:
+            return head
+    return 0
+"#;
+    assert!(pipeline.transpile(python_code).is_err());
+}
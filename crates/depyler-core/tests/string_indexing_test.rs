@@ -0,0 +1,83 @@
+//! Indexing and slicing strings counts characters, so multi-byte UTF-8 text
+//! never splits, unless `string_indexing = "ascii"` opts into byte offsets
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+def first(word: str) -> str:
+    return word[0]
+
+def last(word: str) -> str:
+    return word[-1]
+
+def at(word: str, i: int) -> str:
+    return word[i]
+
+def middle(word: str, a: int, b: int) -> str:
+    return word[a:b]
+
+def drop_last(word: str) -> str:
+    return word[:-1]
+
+# @depyler: string_indexing = "ascii"
+def opcode(line: str) -> str:
+    return line[0:4] + line[-1]
+"#;
+
+#[test]
+fn test_string_indexing_lowering() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("word.chars().nth(0)"));
+    assert!(code.contains("word.chars().rev().nth(0)"));
+    assert!(code.contains("py_str_slice(word,Some((a)asi64),Some((b)asi64))"));
+    assert!(code.contains("py_ascii_slice(&line,Some(0),Some(4))"));
+    assert!(code.contains("line.as_bytes().iter().rev().nth(0)"));
+}
+
+#[test]
+fn test_unicode_results_match_python() {
+    let rust_code = DepylerPipeline::new().transpile(SOURCE).unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(first("héllo".into()), "h");
+    assert_eq!(first("".into()), "");
+    assert_eq!(last("naïve😀".into()), "😀");
+    assert_eq!(at("日本語".into(), 1), "本");
+    assert_eq!(at("日本語".into(), -3), "日");
+    assert_eq!(at("日本語".into(), 3), "");
+    assert_eq!(middle("日本語テキスト".into(), 2, 5), "語テキ");
+    assert_eq!(middle("日本語".into(), -2, 10), "本語");
+    assert_eq!(middle("日本語".into(), 2, 1), "");
+    assert_eq!(drop_last("café".into()), "caf");
+    assert_eq!(opcode("MOVE r1".into()), "MOVE1");
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("strings.rs");
+    let binary = dir.path().join("strings");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
@@ -36,11 +36,12 @@ def identity(s: str) -> str:
     let rust_code = pipeline.transpile(python_code).unwrap();
     println!("Generated code for identity:\n{}", rust_code);
 
-    // DEPYLER-0357: Uses String instead of Cow for escaping strings
-    // Previous Cow<'static> behavior caused lifetime mismatch compilation errors
+    // DEPYLER-0357: No Cow<'static> for escaping strings, which caused
+    // lifetime mismatches; the returned parameter is borrowed for the
+    // lifetime the return shares with it
     assert!(
-        rust_code.contains("s: String") && rust_code.contains("-> String"),
-        "Should use String for string that escapes through return"
+        rust_code.contains("fn identity<'a>(s: &'a str) -> &'a str"),
+        "Should return the borrowed parameter"
     );
 }
 
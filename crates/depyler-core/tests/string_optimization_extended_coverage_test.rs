@@ -20,6 +20,7 @@ use depyler_core::string_optimization::{
     OptimalStringType, StringContext, StringOptimizer,
 };
 use depyler_core::hir::*;
+use depyler_core::hir_builder::HirFunctionBuilder;
 
 /// Unit Test: escape_char all escape sequences
 ///
@@ -57,21 +58,15 @@ fn test_get_interned_name_empty_string() {
     let mut optimizer = StringOptimizer::new();
 
     // Add empty string 5 times to trigger interning
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: vec![
+    let func = HirFunctionBuilder::new("test")
+        .body(vec![
             HirStmt::Expr(HirExpr::Literal(Literal::String("".to_string()))),
             HirStmt::Expr(HirExpr::Literal(Literal::String("".to_string()))),
             HirStmt::Expr(HirExpr::Literal(Literal::String("".to_string()))),
             HirStmt::Expr(HirExpr::Literal(Literal::String("".to_string()))),
             HirStmt::Expr(HirExpr::Literal(Literal::String("".to_string()))),
-        ],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        ])
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -86,17 +81,17 @@ fn test_get_interned_name_empty_string() {
 fn test_get_interned_name_special_chars() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..5).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("hello-world!@#".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test")
+        .body(
+            (0..5)
+                .map(|_| {
+                    HirStmt::Expr(HirExpr::Literal(Literal::String(
+                        "hello-world!@#".to_string(),
+                    )))
+                })
+                .collect(),
+        )
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -116,17 +111,13 @@ fn test_get_interned_name_special_chars() {
 fn test_generate_interned_constants() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..5).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("repeated".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test")
+        .body(
+            (0..5)
+                .map(|_| HirStmt::Expr(HirExpr::Literal(Literal::String("repeated".to_string()))))
+                .collect(),
+        )
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -153,15 +144,14 @@ fn test_is_mutating_method_all_methods() {
     ];
 
     for method in mutating_methods {
-        let func = HirFunction {
-            name: "test".to_string(),
-            params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-            ret_type: Type::None,
-            body: vec![HirStmt::Expr(HirExpr::Call { func: method.to_string(), args: vec![HirExpr::Var("s".to_string())], kwargs: vec![] })],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        };
+        let func = HirFunctionBuilder::new("test")
+            .param("s", Type::String)
+            .stmt(HirStmt::Expr(HirExpr::Call {
+                func: method.to_string(),
+                args: vec![HirExpr::Var("s".to_string())],
+                kwargs: vec![],
+            }))
+            .build();
 
         let mut opt = StringOptimizer::new();
         opt.analyze_function(&func);
@@ -183,19 +173,15 @@ fn test_is_mutating_method_all_methods() {
 fn test_mark_as_owned_var_expr() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("test")
+        .param("s", Type::String)
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Add,
             left: Box::new(HirExpr::Var("s".to_string())),
             right: Box::new(HirExpr::Literal(Literal::String("suffix".to_string()))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -232,20 +218,15 @@ fn test_string_context_display() {
 fn test_analyze_while_stmt_with_strings() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-        ret_type: Type::None,
-        body: vec![HirStmt::While {
+    let func = HirFunctionBuilder::new("test")
+        .param("s", Type::String)
+        .stmt(HirStmt::While {
             condition: HirExpr::Var("s".to_string()),
-            body: vec![
-                HirStmt::Expr(HirExpr::Literal(Literal::String("iteration".to_string()))),
-            ],
-        }],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+            body: vec![HirStmt::Expr(HirExpr::Literal(Literal::String(
+                "iteration".to_string(),
+            )))],
+        })
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -260,22 +241,16 @@ fn test_analyze_while_stmt_with_strings() {
 fn test_analyze_for_stmt_with_strings() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: vec![HirStmt::For {
+    let func = HirFunctionBuilder::new("test")
+        .stmt(HirStmt::For {
             target: AssignTarget::Symbol("item".to_string()),
             iter: HirExpr::List(vec![
                 HirExpr::Literal(Literal::String("a".to_string())),
                 HirExpr::Literal(Literal::String("b".to_string())),
             ]),
             body: vec![HirStmt::Expr(HirExpr::Var("item".to_string()))],
-        }],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        })
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -294,11 +269,9 @@ fn test_analyze_for_stmt_with_strings() {
 fn test_analyze_dict_expr_with_strings() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::Dict(Box::new(Type::String), Box::new(Type::String)),
-        body: vec![HirStmt::Return(Some(HirExpr::Dict(vec![
+    let func = HirFunctionBuilder::new("test")
+        .returns(Type::Dict(Box::new(Type::String), Box::new(Type::String)))
+        .stmt(HirStmt::Return(Some(HirExpr::Dict(vec![
             (
                 HirExpr::Literal(Literal::String("key1".to_string())),
                 HirExpr::Literal(Literal::String("value1".to_string())),
@@ -307,11 +280,8 @@ fn test_analyze_dict_expr_with_strings() {
                 HirExpr::Literal(Literal::String("key2".to_string())),
                 HirExpr::Literal(Literal::String("value2".to_string())),
             ),
-        ])))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        ]))))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -331,18 +301,13 @@ fn test_analyze_dict_expr_with_strings() {
 fn test_analyze_collection_expr_tuple() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::Tuple(vec![Type::String, Type::String]),
-        body: vec![HirStmt::Return(Some(HirExpr::Tuple(vec![
+    let func = HirFunctionBuilder::new("test")
+        .returns(Type::Tuple(vec![Type::String, Type::String]))
+        .stmt(HirStmt::Return(Some(HirExpr::Tuple(vec![
             HirExpr::Literal(Literal::String("first".to_string())),
             HirExpr::Literal(Literal::String("second".to_string())),
-        ])))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        ]))))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -361,19 +326,22 @@ fn test_is_string_expr_call_functions() {
     let mut optimizer = StringOptimizer::new();
 
     // Test string-returning functions: str, format, to_string, join
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("test")
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Add,
-            left: Box::new(HirExpr::Call { func: "str".to_string(), args: vec![HirExpr::Literal(Literal::Int(42))], kwargs: vec![] }),
-            right: Box::new(HirExpr::Call { func: "format".to_string(), args: vec![], kwargs: vec![] }),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+            left: Box::new(HirExpr::Call {
+                func: "str".to_string(),
+                args: vec![HirExpr::Literal(Literal::Int(42))],
+                kwargs: vec![],
+            }),
+            right: Box::new(HirExpr::Call {
+                func: "format".to_string(),
+                args: vec![],
+                kwargs: vec![],
+            }),
+        })))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -388,19 +356,15 @@ fn test_is_string_expr_call_functions() {
 fn test_analyze_binary_expr_non_add() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("a".to_string(), Type::Int)].into(),
-        ret_type: Type::Bool,
-        body: vec![HirStmt::Return(Some(HirExpr::Binary {
+    let func = HirFunctionBuilder::new("test")
+        .param("a", Type::Int)
+        .returns(Type::Bool)
+        .stmt(HirStmt::Return(Some(HirExpr::Binary {
             op: BinOp::Eq,
             left: Box::new(HirExpr::Var("a".to_string())),
             right: Box::new(HirExpr::Literal(Literal::Int(42))),
-        }))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        })))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -414,15 +378,11 @@ fn test_analyze_binary_expr_non_add() {
 fn test_analyze_var_usage_returned() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-        ret_type: Type::String,
-        body: vec![HirStmt::Return(Some(HirExpr::Var("s".to_string())))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test")
+        .param("s", Type::String)
+        .returns(Type::String)
+        .stmt(HirStmt::Return(Some(HirExpr::Var("s".to_string()))))
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -443,17 +403,13 @@ fn test_string_interning_threshold_boundary() {
     let mut optimizer = StringOptimizer::new();
 
     // Test with exactly 4 occurrences (> 3 threshold)
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..4).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("boundary".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test")
+        .body(
+            (0..4)
+                .map(|_| HirStmt::Expr(HirExpr::Literal(Literal::String("boundary".to_string()))))
+                .collect(),
+        )
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -469,17 +425,15 @@ fn test_string_interning_threshold_not_met() {
     let mut optimizer = StringOptimizer::new();
 
     // Test with exactly 3 occurrences (NOT > 3)
-    let func = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..3).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("notinterned".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func = HirFunctionBuilder::new("test")
+        .body(
+            (0..3)
+                .map(|_| {
+                    HirStmt::Expr(HirExpr::Literal(Literal::String("notinterned".to_string())))
+                })
+                .collect(),
+        )
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -511,14 +465,11 @@ fn test_optimal_string_type_cow_str() {
 fn test_combined_statement_analysis() {
     let mut optimizer = StringOptimizer::new();
 
-    let func = HirFunction {
-        name: "complex".to_string(),
-        params: vec![
-            HirParam::new("flag".to_string(), Type::Bool),
-            HirParam::new("items".to_string(), Type::List(Box::new(Type::Int))),
-        ].into(),
-        ret_type: Type::String,
-        body: vec![
+    let func = HirFunctionBuilder::new("complex")
+        .param("flag", Type::Bool)
+        .param("items", Type::List(Box::new(Type::Int)))
+        .returns(Type::String)
+        .body(vec![
             HirStmt::Assign {
                 target: AssignTarget::Symbol("result".to_string()),
                 value: HirExpr::Literal(Literal::String("init".to_string())),
@@ -526,27 +477,20 @@ fn test_combined_statement_analysis() {
             },
             HirStmt::If {
                 condition: HirExpr::Var("flag".to_string()),
-                then_body: vec![
-                    HirStmt::Assign {
-                        target: AssignTarget::Symbol("result".to_string()),
-                        value: HirExpr::Literal(Literal::String("true_branch".to_string())),
-                        type_annotation: None,
-                    },
-                ],
-                else_body: Some(vec![
-                    HirStmt::Assign {
-                        target: AssignTarget::Symbol("result".to_string()),
-                        value: HirExpr::Literal(Literal::String("false_branch".to_string())),
-                        type_annotation: None,
-                    },
-                ]),
+                then_body: vec![HirStmt::Assign {
+                    target: AssignTarget::Symbol("result".to_string()),
+                    value: HirExpr::Literal(Literal::String("true_branch".to_string())),
+                    type_annotation: None,
+                }],
+                else_body: Some(vec![HirStmt::Assign {
+                    target: AssignTarget::Symbol("result".to_string()),
+                    value: HirExpr::Literal(Literal::String("false_branch".to_string())),
+                    type_annotation: None,
+                }]),
             },
             HirStmt::Return(Some(HirExpr::Var("result".to_string()))),
-        ],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        ])
+        .build();
 
     optimizer.analyze_function(&func);
 
@@ -576,15 +520,7 @@ fn test_property_all_statement_types() {
     ];
 
     for (name, stmt) in statement_types {
-        let func = HirFunction {
-            name: "test".to_string(),
-            params: vec![].into(),
-            ret_type: Type::None,
-            body: vec![stmt],
-            properties: FunctionProperties::default(),
-            annotations: Default::default(),
-            docstring: None,
-        };
+        let func = HirFunctionBuilder::new("test").stmt(stmt).build();
 
         let mut opt = StringOptimizer::new();
         opt.analyze_function(&func);
@@ -625,30 +561,22 @@ fn test_mutation_interning_threshold() {
     let mut opt_4 = StringOptimizer::new();
 
     // Exactly 3 occurrences
-    let func_3 = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..3).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("s".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func_3 = HirFunctionBuilder::new("test")
+        .body(
+            (0..3)
+                .map(|_| HirStmt::Expr(HirExpr::Literal(Literal::String("s".to_string()))))
+                .collect(),
+        )
+        .build();
 
     // Exactly 4 occurrences
-    let func_4 = HirFunction {
-        name: "test".to_string(),
-        params: vec![].into(),
-        ret_type: Type::None,
-        body: (0..4).map(|_| {
-            HirStmt::Expr(HirExpr::Literal(Literal::String("s".to_string())))
-        }).collect(),
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func_4 = HirFunctionBuilder::new("test")
+        .body(
+            (0..4)
+                .map(|_| HirStmt::Expr(HirExpr::Literal(Literal::String("s".to_string()))))
+                .collect(),
+        )
+        .build();
 
     opt_3.analyze_function(&func_3);
     opt_4.analyze_function(&func_4);
@@ -667,33 +595,29 @@ fn test_mutation_mixed_usage_detection() {
     let mut opt_mutated = StringOptimizer::new();
 
     // Immutable parameter: not mutated, can be borrowed
-    let func_immutable = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Call { func: "len".to_string(), args: vec![HirExpr::Var("s".to_string())], kwargs: vec![] }))],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+    let func_immutable = HirFunctionBuilder::new("test")
+        .param("s", Type::String)
+        .returns(Type::Int)
+        .stmt(HirStmt::Return(Some(HirExpr::Call {
+            func: "len".to_string(),
+            args: vec![HirExpr::Var("s".to_string())],
+            kwargs: vec![],
+        })))
+        .build();
 
     // Mutated parameter: reassigned, needs ownership
-    let func_mutated = HirFunction {
-        name: "test".to_string(),
-        params: vec![HirParam::new("s".to_string(), Type::String)].into(),
-        ret_type: Type::String,
-        body: vec![
+    let func_mutated = HirFunctionBuilder::new("test")
+        .param("s", Type::String)
+        .returns(Type::String)
+        .body(vec![
             HirStmt::Assign {
                 target: AssignTarget::Symbol("s".to_string()),
                 value: HirExpr::Literal(Literal::String("new".to_string())),
                 type_annotation: None,
             },
             HirStmt::Return(Some(HirExpr::Var("s".to_string()))),
-        ],
-        properties: FunctionProperties::default(),
-        annotations: Default::default(),
-        docstring: None,
-    };
+        ])
+        .build();
 
     opt_immutable.analyze_function(&func_immutable);
     opt_mutated.analyze_function(&func_mutated);
@@ -95,6 +95,42 @@ def use_repeated_string():
     );
 }
 
+#[test]
+fn test_intern_threshold_counts_literals_across_functions() {
+    let python_code = r#"
+def first() -> str:
+    return "unknown"
+
+def second(n: int) -> str:
+    if n > 0:
+        return "unknown"
+    return "unknown"
+"#;
+
+    let rust_code = DepylerPipeline::new()
+        .with_intern_threshold(3)
+        .transpile(python_code)
+        .unwrap();
+    assert_eq!(
+        rust_code.matches("const STR_UNKNOWN").count(),
+        1,
+        "{}",
+        rust_code
+    );
+    // The functions return `String`, so the shared constant is copied out
+    assert!(
+        rust_code.contains("STR_UNKNOWN.to_string()"),
+        "{}",
+        rust_code
+    );
+
+    let rust_code = DepylerPipeline::new()
+        .with_intern_threshold(0)
+        .transpile(python_code)
+        .unwrap();
+    assert!(!rust_code.contains("const STR_"), "{}", rust_code);
+}
+
 #[test]
 fn test_function_taking_str_reference() {
     let pipeline = DepylerPipeline::new();
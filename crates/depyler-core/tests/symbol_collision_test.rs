@@ -0,0 +1,53 @@
+//! Module items that would share one Rust name are reported up front
+
+mod common;
+
+use common::transpile_error;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_duplicate_function_is_reported() {
+    let source = r#"
+def get(x: int) -> int:
+    return x
+
+def get(x: int) -> int:
+    return x + 1
+"#;
+    assert!(transpile_error(source).contains("`get` is defined more than once"));
+}
+
+#[test]
+fn test_generator_state_struct_clashing_with_class_is_reported() {
+    let source = r#"
+class CounterState:
+    def __init__(self, value: int):
+        self.value = value
+
+def counter(n: int):
+    i = 0
+    while i < n:
+        yield i
+        i += 1
+"#;
+    assert!(transpile_error(source).contains(
+        "`CounterState` would be generated for both `CounterState` and `counter.<state>`"
+    ));
+}
+
+#[test]
+fn test_distinct_names_transpile() {
+    let source = r#"
+class Cache:
+    def __init__(self):
+        self.size = 0
+
+    def get(self) -> int:
+        return self.size
+
+def get(cache: Cache) -> int:
+    return cache.get()
+"#;
+    let rust = DepylerPipeline::new().transpile(source).unwrap();
+    assert!(rust.contains("fn get("));
+}
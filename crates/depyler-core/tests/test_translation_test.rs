@@ -0,0 +1,330 @@
+//! pytest / unittest tests translated into Rust integration tests
+
+use depyler_core::DepylerPipeline;
+
+const MODULE: &str = r#"
+def add(a: int, b: int) -> int:
+    return a + b
+
+def parse_positive(text: str) -> int:
+    value = int(text)
+    if value < 0:
+        raise ValueError("negative")
+    return value
+
+def mean(values: list[float]) -> float:
+    return sum(values) / len(values)
+"#;
+
+#[test]
+fn test_pytest_functions_and_asserts() {
+    let tests = r#"
+import pytest
+from calc import add, mean
+
+def test_add():
+    assert add(2, 3) == 5
+    assert add(2, 2) != 5
+
+def test_mean():
+    assert mean([1.0, 2.0]) == pytest.approx(1.5)
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(translated.tests, ["test_add", "test_mean"]);
+    assert!(translated.ignored.is_empty());
+    assert!(translated.code.contains("use calc::*;"));
+    assert!(translated.code.contains("#[test]"));
+    assert!(translated.code.contains("assert_eq!(add(2, 3), 5)"));
+    assert!(translated.code.contains("assert_ne!(add(2, 2), 5)"));
+    assert!(translated.code.contains("<="));
+    // Module functions come from the crate, not the test file
+    assert!(!translated.code.contains("pub fn add"));
+}
+
+#[test]
+fn test_pytest_raises_on_fallible_function() {
+    let tests = r#"
+import pytest
+import calc
+
+def test_rejects_negative():
+    with pytest.raises(ValueError):
+        calc.parse_positive("-1")
+
+def test_accepts_positive():
+    assert calc.parse_positive("4") == 4
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert!(translated.code.contains(".is_err()"));
+    assert!(translated.code.contains("expected ValueError to be raised"));
+    assert!(translated.code.contains(".unwrap(), 4)"));
+    assert!(!translated.code.contains("__depyler_expect_raises__"));
+}
+
+#[test]
+fn test_unittest_test_case_with_set_up() {
+    let tests = r#"
+import unittest
+from calc import add
+
+class TestAdd(unittest.TestCase):
+    def setUp(self):
+        self.base = 10
+
+    def test_positive(self):
+        self.assertEqual(add(self.base, 1), 11)
+
+    def test_bounds(self):
+        self.assertTrue(add(self.base, 0) > 0)
+        self.assertIn(add(1, 1), [2, 3])
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(
+        translated.tests,
+        ["test_add_test_positive", "test_add_test_bounds"]
+    );
+    assert!(translated.code.contains("base = 10"));
+    assert!(translated.code.contains("assert_eq!(add(base, 1), 11)"));
+    assert!(!translated.code.contains("self"));
+}
+
+#[test]
+fn test_fixtures_are_called_for_parameters() {
+    let tests = r#"
+import pytest
+from calc import add
+
+@pytest.fixture
+def numbers() -> list[int]:
+    return [1, 2, 3]
+
+def test_sum(numbers: list[int]):
+    assert add(numbers[0], numbers[1]) == 3
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(translated.tests, ["test_sum"]);
+    assert!(translated.code.contains("fn numbers()"));
+    assert!(translated.code.contains("numbers()"));
+}
+
+#[test]
+fn test_unsupported_tests_become_ignored_stubs() {
+    let tests = r#"
+import pytest
+from calc import add
+
+@pytest.mark.skip(reason="needs network")
+def test_remote():
+    assert add(1, 1) == 2
+
+def test_tmp(tmp_path):
+    assert add(1, 1) == 2
+
+@pytest.fixture
+def resource():
+    yield 1
+
+def test_resource(resource):
+    assert resource == 1
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert!(translated.tests.is_empty());
+    let reasons: Vec<_> = translated
+        .ignored
+        .iter()
+        .map(|t| (t.name.as_str(), t.reason.as_str()))
+        .collect();
+    assert_eq!(
+        reasons,
+        [
+            ("test_remote", "needs network"),
+            ("test_tmp", "fixture `tmp_path` is not supported"),
+            ("test_resource", "yield fixture `resource` is not supported"),
+        ]
+    );
+    assert!(translated.code.contains("#[ignore = \"needs network\"]"));
+    assert!(translated.code.contains("fn test_tmp() {}"));
+}
+
+#[test]
+fn test_parametrize_becomes_rstest_cases() {
+    let tests = r#"
+import pytest
+from calc import add
+
+@pytest.mark.parametrize("a, b, expected", [(1, 2, 3), (-1, 1, 0)], ids=["small", "zero sum"])
+def test_add(a, b, expected):
+    assert add(a, b) == expected
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(translated.tests, ["test_add"]);
+    assert_eq!(
+        translated.dev_dependencies,
+        [("rstest".to_string(), "0.18".to_string())]
+    );
+    assert!(translated.code.contains("use rstest::rstest;"));
+    assert!(translated.code.contains("#[rstest]"));
+    assert!(translated.code.contains("#[case::small(1, 2, 3)]"));
+    assert!(translated.code.contains("#[case::zero_sum(-1, 1, 0)]"));
+    assert!(translated.code.contains("#[case] a: i32"));
+    assert!(translated.code.contains("assert_eq!(add(a, b), expected)"));
+    assert!(!translated.code.contains("__depyler_rstest_case__"));
+}
+
+#[test]
+fn test_parametrize_without_literal_types_expands_cases() {
+    let tests = r#"
+import pytest
+from calc import parse_positive
+
+@pytest.mark.parametrize("text, expected", [(str(4), 4), (str(10), 10)])
+def test_parse(text, expected):
+    assert parse_positive(text) == expected
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(
+        translated.tests,
+        ["test_parse_text0_4", "test_parse_text1_10"]
+    );
+    assert!(translated.dev_dependencies.is_empty());
+    assert!(!translated.code.contains("rstest"));
+    assert!(translated.code.contains("fn test_parse_text0_4()"));
+}
+
+const RATES: &str = r#"
+def fetch_rate(currency: str) -> float:
+    return 1.1
+
+def convert(amount: float, currency: str) -> float:
+    return amount * fetch_rate(currency)
+"#;
+
+const PATCHED_TESTS: &str = r#"
+from unittest.mock import patch
+from rates import convert
+
+@patch("rates.fetch_rate")
+def test_convert(mock_rate):
+    mock_rate.return_value = 2
+    assert convert(10.0, "EUR") == 20.0
+
+def test_convert_with_block():
+    with patch("rates.fetch_rate", return_value=3):
+        assert convert(1.0, "USD") == 3.0
+
+@patch("os.getcwd")
+def test_cwd(mock_cwd):
+    assert convert(1.0, "USD") > 0.0
+"#;
+
+#[test]
+fn test_patched_tests_are_reported_and_ignored_without_seams() {
+    let translated = DepylerPipeline::new()
+        .translate_tests(RATES, "rates", PATCHED_TESTS, "rates")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    let patches: Vec<_> = translated
+        .patches
+        .iter()
+        .map(|p| (p.test.as_str(), p.target.as_str(), p.function.as_deref()))
+        .collect();
+    assert_eq!(
+        patches,
+        [
+            ("test_convert", "rates.fetch_rate", Some("fetch_rate")),
+            (
+                "test_convert_with_block",
+                "rates.fetch_rate",
+                Some("fetch_rate")
+            ),
+            ("test_cwd", "os.getcwd", None),
+        ]
+    );
+    assert!(translated.tests.is_empty());
+    assert_eq!(
+        translated.ignored[0].reason,
+        "patches `rates.fetch_rate`; transpile with `--seam fetch_rate` to inject a fake"
+    );
+    assert_eq!(
+        translated.ignored[2].reason,
+        "patches `os.getcwd`, which is not a function of the module under test"
+    );
+}
+
+#[test]
+fn test_seamed_patches_become_fakes() {
+    let translated = DepylerPipeline::new()
+        .with_seams(vec!["fetch_rate".to_string()])
+        .translate_tests(RATES, "rates", PATCHED_TESTS, "rates")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(
+        translated.tests,
+        ["test_convert", "test_convert_with_block"]
+    );
+    assert!(translated.code.contains("struct FakeFetchRate;"));
+    assert!(translated
+        .code
+        .contains("impl FetchRateSeam for FakeFetchRate"));
+    assert!(translated.code.contains("2.0"));
+    assert!(translated
+        .code
+        .contains("convert_with(&FakeFetchRate, 10.0"));
+    assert!(!translated.code.contains("mock_rate"));
+    assert!(!translated.code.contains("__depyler_"));
+    // The crate provides the seam; the test file only implements it
+    assert!(!translated.code.contains("pub trait"));
+}
+
+#[test]
+fn test_helper_named_like_module_function_is_renamed() {
+    let tests = r#"
+import calc
+
+def mean(a: float, b: float) -> float:
+    return (a + b) / 2
+
+def test_mean():
+    assert calc.mean([1.0, 3.0]) == mean(1.0, 3.0)
+"#;
+    let translated = DepylerPipeline::new()
+        .translate_tests(MODULE, "calc", tests, "calc")
+        .unwrap();
+    println!("Generated tests:\n{}", translated.code);
+
+    assert_eq!(translated.tests, ["test_mean"]);
+    assert!(translated.code.contains("fn tests_mean("));
+    assert!(translated.code.contains("tests_mean(1.0, 3.0)"));
+    // `calc.mean` still reaches the crate's function
+    assert!(!translated.code.contains("pub fn mean"));
+}
@@ -0,0 +1,86 @@
+//! Module-level type aliases become `type` items resolved in every
+//! signature, and `NewType`s become tuple structs
+
+mod common;
+
+use common::transpile;
+use depyler_core::DepylerPipeline;
+use std::process::Command;
+
+const SOURCE: &str = r#"
+from typing import NewType, TypeAlias
+
+UserId = NewType("UserId", int)
+Vector = list[float]
+Grid: TypeAlias = list[Vector]
+
+def norm_sq(v: Vector) -> float:
+    total: float = 0.0
+    for x in v:
+        total += x * x
+    return total
+
+def cells(g: Grid) -> int:
+    n: int = 0
+    for row in g:
+        n += len(row)
+    return n
+
+def admin() -> UserId:
+    return UserId(1)
+"#;
+
+#[test]
+fn test_aliases_and_newtypes_are_declared() {
+    let code = transpile(SOURCE);
+
+    assert!(code.contains("pubtypeVector=Vec<f64>;"));
+    assert!(code.contains("pubtypeGrid=Vec<Vec<f64>>;"));
+    assert!(code.contains("pubstructUserId(pubi32);"));
+    // Signatures see through the aliases, so lists are still borrowed
+    assert!(code.contains("fnnorm_sq(v:&Vec<f64>)->f64"));
+    assert!(code.contains("fncells(g:&Vec<Vec<f64>>)->i32"));
+    assert!(code.contains("fnadmin()->UserId"));
+    assert!(code.contains("UserId::new(1)"));
+}
+
+#[test]
+fn test_newtype_conversions_compile() {
+    let rust_code = DepylerPipeline::new()
+        .with_newtype_conversions()
+        .transpile(SOURCE)
+        .unwrap();
+    let checks = r#"
+fn main() {
+    assert_eq!(norm_sq(&vec![3.0, 4.0]), 25.0);
+    assert_eq!(cells(&vec![vec![1.0], vec![2.0, 3.0]]), 3);
+    assert_eq!(admin(), UserId(1));
+    assert_eq!(*admin() + 1, 2);
+    let raw: i32 = admin().into();
+    assert_eq!(UserId::from(raw), admin());
+}
+"#;
+    let dir = tempfile::tempdir().unwrap();
+    let source = dir.path().join("aliases.rs");
+    let binary = dir.path().join("aliases");
+    std::fs::write(&source, format!("{}\n{}", rust_code, checks)).unwrap();
+
+    let output = Command::new("rustc")
+        .args(["--edition", "2021", "-o"])
+        .arg(&binary)
+        .arg(&source)
+        .output()
+        .expect("Failed to execute rustc");
+    assert!(
+        output.status.success(),
+        "Generated code should compile:\n{}\n{}",
+        rust_code,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let run = Command::new(&binary).output().unwrap();
+    assert!(
+        run.status.success(),
+        "{}",
+        String::from_utf8_lossy(&run.stderr)
+    );
+}
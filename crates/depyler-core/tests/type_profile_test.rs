@@ -0,0 +1,61 @@
+//! Type mapping profiles for server, embedded and wasm targets
+
+mod common;
+
+use common::compact;
+use depyler_core::cargo_toml_gen::detect_dependencies;
+use depyler_core::type_mapper::TypeProfile;
+use depyler_core::DepylerPipeline;
+
+const SOURCE: &str = r#"
+def tally(words: List[str]) -> Dict[str, int]:
+    counts = {}
+    for word in words:
+        counts[word] = counts.get(word, 0) + 1
+    return counts
+
+def unique(xs: List[int]) -> Set[int]:
+    return set(xs)
+
+def add(a: int, b: int) -> int:
+    return a + b
+"#;
+
+fn transpile(profile: TypeProfile) -> String {
+    DepylerPipeline::new()
+        .with_type_profile(profile)
+        .transpile(SOURCE)
+        .unwrap()
+}
+
+#[test]
+fn test_server_profile_keeps_hash_maps() {
+    let code = compact(&transpile(TypeProfile::Server));
+    assert!(code.contains("usestd::collections::HashMap;"));
+    assert!(!code.contains("wasm_bindgen"));
+}
+
+#[test]
+fn test_embedded_profile_uses_btrees() {
+    let rust = transpile(TypeProfile::Embedded);
+    let code = compact(&rust);
+    assert!(code.contains("usestd::collections::BTreeMapasHashMap;"));
+    assert!(code.contains("usestd::collections::BTreeSetasHashSet;"));
+    assert!(code.contains("fnadd(a:i32,b:i32)->i32"));
+    assert!(detect_dependencies(&rust).unwrap().is_empty());
+}
+
+#[test]
+fn test_wasm_profile_exports_passable_functions() {
+    let rust = transpile(TypeProfile::Wasm);
+    let code = compact(&rust);
+    assert!(code.contains("usewasm_bindgen::prelude::*;"));
+    assert!(code.contains("#[wasm_bindgen]pubfnadd(a:i32,b:i32)->i32"));
+    assert!(!code.contains("#[wasm_bindgen]pubfntally"));
+    let deps: Vec<_> = detect_dependencies(&rust)
+        .unwrap()
+        .iter()
+        .map(|d| d.name)
+        .collect();
+    assert_eq!(deps, ["wasm_bindgen"]);
+}
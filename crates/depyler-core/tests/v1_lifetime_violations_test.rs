@@ -61,9 +61,10 @@ def get_string(use_default: bool, custom: str) -> str:
     let rust_code = pipeline.transpile(python_code).unwrap();
     println!("Generated code for get_string:\n{}", rust_code);
 
-    // Should use Cow or String to handle mixed lifetimes
+    // The literal outlives any borrow, so both returns fit the parameter's
+    // lifetime
     assert!(
-        rust_code.contains("Cow") || rust_code.contains("String"),
+        rust_code.contains("custom: &'a str") && rust_code.contains("-> &'a str"),
         "Should handle mixed lifetime returns"
     );
 }
@@ -0,0 +1,59 @@
+//! Tests for visibility inference on generated functions
+
+mod common;
+
+use common::compact;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_underscore_helper_is_private() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+def _square(x: int) -> int:
+    return x * x
+
+def sum_of_squares(a: int, b: int) -> int:
+    return _square(a) + _square(b)
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubfnsum_of_squares("));
+    assert!(rust_code.contains("fn_square("));
+    assert!(!rust_code.contains("pubfn_square("));
+}
+
+#[test]
+fn test_all_decides_public_api() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+__all__ = ["area"]
+
+def scale(x: int) -> int:
+    return x * 2
+
+def area(w: int, h: int) -> int:
+    return scale(w) * h
+
+def perimeter(w: int, h: int) -> int:
+    return 2 * (w + h)
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubfnarea("));
+    assert!(!rust_code.contains("pubfnscale("));
+    assert!(rust_code.contains("pub(crate)fnperimeter("));
+    assert!(!rust_code.contains("__all__"));
+}
+
+#[test]
+fn test_visibility_annotation_overrides_convention() {
+    let pipeline = DepylerPipeline::new();
+    let python_code = r#"
+# @depyler: visibility = "pub"
+def _exported(x: int) -> int:
+    return x + 1
+"#;
+    let rust_code = compact(&pipeline.transpile(python_code).unwrap());
+
+    assert!(rust_code.contains("pubfn_exported("));
+}
@@ -0,0 +1,109 @@
+//! `warnings` and `traceback` module translation
+//!
+//! Both modules are lowered at the call site, so `import warnings` /
+//! `import traceback` must not leave an unmapped-module placeholder behind.
+
+use depyler_core::rust_gen::CodeGenOptions;
+use depyler_core::DepylerPipeline;
+
+#[test]
+fn test_warn_defaults_to_user_warning() {
+    let python = r#"
+import warnings
+
+def check(n: int) -> int:
+    if n < 0:
+        warnings.warn("negative input")
+    return n
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("eprintln!(\"{}: {}\", \"UserWarning\""));
+    assert!(!rust_code.contains("TODO: Map Python module 'warnings'"));
+    assert!(!rust_code.contains("Python import: warnings"));
+}
+
+#[test]
+fn test_warn_keeps_explicit_category() {
+    let python = r#"
+import warnings
+
+def old_api() -> None:
+    warnings.warn("use new_api instead", DeprecationWarning)
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(rust_code.contains("\"DeprecationWarning\""));
+}
+
+#[test]
+fn test_warn_via_log_option() {
+    let python = r#"
+import warnings
+
+def old_api() -> None:
+    warnings.warn("use new_api instead")
+"#;
+    let pipeline = DepylerPipeline::new().with_codegen_options(CodeGenOptions {
+        warnings_via_log: true,
+        ..CodeGenOptions::default()
+    });
+    let rust_code = pipeline.transpile(python).unwrap();
+
+    assert!(rust_code.contains("log::warn!"));
+    assert!(!rust_code.contains("eprintln!"));
+}
+
+#[test]
+fn test_print_exc_names_the_bound_error_and_its_causes() {
+    let python = r#"
+import traceback
+
+def parse(s: str) -> int:
+    try:
+        return int(s)
+    except ValueError as e:
+        traceback.print_exc()
+        return -1
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+    println!("Generated code:\n{}", rust_code);
+
+    assert!(rust_code.contains("\"ValueError: {}\\n\""));
+    assert!(rust_code.contains("std::error::Error::source(&e)"));
+    assert!(rust_code.contains("The above exception was the direct cause"));
+    assert!(!rust_code.contains("TODO: Map Python module 'traceback'"));
+}
+
+#[test]
+fn test_format_exc_without_bound_error_is_rejected() {
+    let python = r#"
+import traceback
+
+def parse(s: str) -> str:
+    try:
+        n = int(s)
+        return str(n)
+    except ValueError as e:
+        return traceback.format_exc()
+"#;
+    // `e` is not bound to a Rust value here, so there is no message to print
+    let err = DepylerPipeline::new().transpile(python).unwrap_err();
+
+    assert!(format!("{:#}", err)
+        .contains("traceback.format_exc() in `except ValueError as e:` is not supported"));
+}
+
+#[test]
+fn test_format_exc_outside_handler() {
+    let python = r#"
+import traceback
+
+def report() -> str:
+    return traceback.format_exc()
+"#;
+    let rust_code = DepylerPipeline::new().transpile(python).unwrap();
+
+    assert!(rust_code.contains("NoneType: None"));
+}
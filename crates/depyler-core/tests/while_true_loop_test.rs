@@ -0,0 +1,114 @@
+//! `while True:` loops generated as `loop`, or as `while` on their leading
+//! break condition
+
+mod common;
+
+use common::transpile;
+
+#[test]
+fn test_while_true_becomes_loop() {
+    let code = transpile(
+        r#"
+def first_square_over(limit: int) -> int:
+    n = 0
+    while True:
+        n = n + 1
+        if n * n > limit:
+            return n
+"#,
+    );
+
+    assert!(code.contains("loop{"));
+    assert!(!code.contains("whiletrue"));
+}
+
+#[test]
+fn test_while_one_becomes_loop() {
+    let code = transpile(
+        r#"
+def spin(steps: int) -> int:
+    count = 0
+    while 1:
+        count = count + 1
+        if count == steps:
+            break
+    return count
+"#,
+    );
+
+    assert!(code.contains("loop{"));
+}
+
+#[test]
+fn test_leading_break_condition_becomes_while() {
+    let code = transpile(
+        r#"
+def halve(n: int) -> int:
+    steps = 0
+    while True:
+        if n <= 1:
+            break
+        n = n // 2
+        steps = steps + 1
+    return steps
+"#,
+    );
+
+    assert!(code.contains("while!(n<=1){"));
+    assert!(!code.contains("loop{"));
+    assert!(!code.contains("break;"));
+}
+
+#[test]
+fn test_negated_break_condition_is_not_doubled() {
+    let code = transpile(
+        r#"
+def drain(ready: bool, count: int) -> int:
+    while True:
+        if not ready:
+            break
+        count = count - 1
+        ready = count > 0
+    return count
+"#,
+    );
+
+    assert!(code.contains("whileready{"));
+}
+
+#[test]
+fn test_break_later_in_the_body_stays_a_loop() {
+    let code = transpile(
+        r#"
+def countdown(n: int) -> int:
+    while True:
+        n = n - 1
+        if n == 0:
+            break
+    return n
+"#,
+    );
+
+    assert!(code.contains("loop{"));
+    assert!(code.contains("break;"));
+}
+
+#[test]
+fn test_while_true_in_a_method_becomes_loop() {
+    let code = transpile(
+        r#"
+class Counter:
+    def __init__(self):
+        self.count = 0
+
+    def run(self, limit: int) -> int:
+        while True:
+            self.count = self.count + 1
+            if self.count >= limit:
+                return self.count
+"#,
+    );
+
+    assert!(code.contains("loop{"));
+    assert!(!code.contains("whiletrue"));
+}
@@ -0,0 +1,597 @@
+//! Size of the generated Rust and budgets on it
+//!
+//! A large Python module becomes one large Rust file, and its compile time
+//! grows faster than its line count: generic items, `impl Trait` and
+//! closures are compiled again for every type they are used with, and every
+//! derived trait is an impl of its own. [`CodeSizeReport::measure`] parses
+//! the generated file and counts, leaving out `#[cfg(test)]` code,
+//!
+//! - lines of code, without blank lines and comments,
+//! - items: functions and methods, types, impls, traits and constants,
+//! - generic constructs: generic items, `impl Trait`, closures and derives,
+//!
+//! and the lines of each class (its type and the impls for it) and module
+//! function. When a [`CodeSizeBudget`] is exceeded,
+//! [`CodeSizeReport::suggestions`] names the parts worth splitting off.
+//! rustc partitions code generation by module, so classes in modules of their
+//! own (`transpile --class-modules`) also compile in parallel.
+
+use crate::{QualityError, QualityGateResult, QualityRequirement, Severity};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+pub const GATE_NAME: &str = "Code Size Budget";
+
+/// Limits on the generated code of one module
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeSizeBudget {
+    /// Most lines of code
+    pub max_lines: Option<usize>,
+    /// Most items
+    pub max_items: Option<usize>,
+    /// Most generic items, `impl Trait`s, closures and derives
+    pub max_generic_constructs: Option<usize>,
+}
+
+impl CodeSizeBudget {
+    pub fn requirements(&self) -> Vec<QualityRequirement> {
+        let mut requirements = Vec::new();
+        if let Some(max) = self.max_lines {
+            requirements.push(QualityRequirement::MaxGeneratedLines(max));
+        }
+        if let Some(max) = self.max_items {
+            requirements.push(QualityRequirement::MaxGeneratedItems(max));
+        }
+        if let Some(max) = self.max_generic_constructs {
+            requirements.push(QualityRequirement::MaxGenericConstructs(max));
+        }
+        requirements
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ItemCounts {
+    /// Module functions, methods and nested functions
+    pub functions: usize,
+    /// Structs, enums, unions and type aliases
+    pub types: usize,
+    pub impls: usize,
+    pub traits: usize,
+    /// Constants and statics
+    pub constants: usize,
+}
+
+impl ItemCounts {
+    pub fn total(&self) -> usize {
+        self.functions + self.types + self.impls + self.traits + self.constants
+    }
+}
+
+/// Constructs that cost rustc more than their lines suggest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenericConstructs {
+    /// Items with type parameters, monomorphized per instantiation
+    pub generic_items: usize,
+    /// `impl Trait` in parameters and return types
+    pub impl_traits: usize,
+    /// Closures, each a type of its own
+    pub closures: usize,
+    /// Derived traits, each expanding into an impl
+    pub derives: usize,
+}
+
+impl GenericConstructs {
+    pub fn total(&self) -> usize {
+        self.generic_items + self.impl_traits + self.closures + self.derives
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitKind {
+    Class,
+    Function,
+}
+
+/// A class with its impls, or a module function
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeUnit {
+    pub name: String,
+    pub kind: UnitKind,
+    pub lines: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeSizeReport {
+    /// Lines of code
+    pub lines: usize,
+    pub items: ItemCounts,
+    pub generic_constructs: GenericConstructs,
+    /// Classes and module functions, largest first
+    pub units: Vec<SizeUnit>,
+    /// Whether the classes already have modules of their own
+    pub class_modules: bool,
+}
+
+impl CodeSizeReport {
+    pub fn measure(rust_code: &str) -> Result<Self, QualityError> {
+        let file =
+            syn::parse_file(rust_code).map_err(|e| QualityError::MetricCalculationFailed {
+                metric: format!("code size: {}", e),
+            })?;
+        let mut counter = SizeCounter::default();
+        counter.visit_file(&file);
+
+        let code_lines: Vec<bool> = rust_code.lines().map(is_code_line).collect();
+        let count_lines = |range: &RangeInclusive<usize>| {
+            range
+                .clone()
+                .filter(|line| code_lines.get(line - 1).copied().unwrap_or(false))
+                .count()
+        };
+        let test_lines: usize = counter.test_ranges.iter().map(count_lines).sum();
+
+        let mut units = UnitCollector::default();
+        units.collect(&file.items);
+        let mut units: Vec<SizeUnit> = units
+            .units
+            .into_iter()
+            .map(|(name, kind, ranges)| SizeUnit {
+                name,
+                kind,
+                lines: ranges.iter().map(count_lines).sum(),
+            })
+            .collect();
+        units.sort_by(|a, b| b.lines.cmp(&a.lines));
+
+        Ok(Self {
+            lines: code_lines.iter().filter(|&&code| code).count() - test_lines,
+            items: counter.items,
+            generic_constructs: counter.generic,
+            units,
+            class_modules: counter.class_modules,
+        })
+    }
+
+    /// Whether the report satisfies a code size requirement, with the value
+    /// shown for it; `None` for requirements on other metrics
+    pub fn evaluate(&self, requirement: &QualityRequirement) -> Option<(bool, String)> {
+        let (actual, max) = match requirement {
+            QualityRequirement::MaxGeneratedLines(max) => (self.lines, max),
+            QualityRequirement::MaxGeneratedItems(max) => (self.items.total(), max),
+            QualityRequirement::MaxGenericConstructs(max) => (self.generic_constructs.total(), max),
+            _ => return None,
+        };
+        Some((actual <= *max, actual.to_string()))
+    }
+
+    pub fn gate_results(&self, budget: &CodeSizeBudget) -> Vec<QualityGateResult> {
+        budget
+            .requirements()
+            .into_iter()
+            .filter_map(|requirement| {
+                let (passed, actual_value) = self.evaluate(&requirement)?;
+                Some(QualityGateResult {
+                    gate_name: GATE_NAME.to_string(),
+                    requirement,
+                    actual_value,
+                    passed,
+                    severity: Severity::Error,
+                })
+            })
+            .collect()
+    }
+
+    /// Ways of bringing the module back within the limits of `budget` it
+    /// exceeds
+    pub fn suggestions(&self, budget: &CodeSizeBudget) -> Vec<String> {
+        let exceeds = |requirement: QualityRequirement| {
+            self.evaluate(&requirement)
+                .is_some_and(|(passed, _)| !passed)
+        };
+        let too_large = budget
+            .max_lines
+            .is_some_and(|max| exceeds(QualityRequirement::MaxGeneratedLines(max)))
+            || budget
+                .max_items
+                .is_some_and(|max| exceeds(QualityRequirement::MaxGeneratedItems(max)));
+        let too_generic = budget
+            .max_generic_constructs
+            .is_some_and(|max| exceeds(QualityRequirement::MaxGenericConstructs(max)));
+
+        let mut suggestions = Vec::new();
+        if too_large {
+            let largest_class = self.units.iter().find(|u| u.kind == UnitKind::Class);
+            if let (Some(class), false) = (largest_class, self.class_modules) {
+                suggestions.push(format!(
+                    "transpile with --class-modules to give each class a module of its own; \
+                     the largest, `{}`, has {} lines",
+                    class.name, class.lines
+                ));
+            }
+            if let Some(largest) = self.units.first() {
+                if largest.kind == UnitKind::Function && largest.lines * 4 > self.lines {
+                    suggestions.push(format!(
+                        "`{}` alone has {} of the {} lines; break it into smaller functions",
+                        largest.name, largest.lines, self.lines
+                    ));
+                }
+            }
+            // The largest parts that together make up half of the module
+            let mut moved = Vec::new();
+            let mut moved_lines = 0;
+            for unit in &self.units {
+                if moved_lines * 2 >= self.lines {
+                    break;
+                }
+                moved.push(format!("`{}`", unit.name));
+                moved_lines += unit.lines;
+            }
+            if moved.len() > 1 && moved.len() < self.units.len() {
+                suggestions.push(format!(
+                    "move {} ({} lines) into a Python module of their own, \
+                     which becomes a Rust file of its own",
+                    moved.join(", "),
+                    moved_lines
+                ));
+            }
+        }
+        if too_generic {
+            let generic = &self.generic_constructs;
+            suggestions.push(format!(
+                "{} generic items, {} `impl Trait`s and {} closures are compiled once per type \
+                 they are used with; concrete annotations in place of TypeVars and Callables \
+                 avoid the copies",
+                generic.generic_items, generic.impl_traits, generic.closures
+            ));
+        }
+        suggestions
+    }
+}
+
+/// Whether `line` holds code rather than a blank or a comment
+fn is_code_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.is_empty() && !line.starts_with("//")
+}
+
+fn lines_of(node: &impl Spanned) -> RangeInclusive<usize> {
+    let span = node.span();
+    span.start().line..=span.end().line
+}
+
+pub(crate) fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|arg| arg == "test")
+    })
+}
+
+fn derive_count(attrs: &[syn::Attribute]) -> usize {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<syn::Path, syn::Token![,]>::parse_terminated)
+                .ok()
+        })
+        .map(|derives| derives.len())
+        .sum()
+}
+
+fn is_generic(generics: &syn::Generics) -> bool {
+    generics.type_params().next().is_some()
+}
+
+#[derive(Default)]
+struct SizeCounter {
+    items: ItemCounts,
+    generic: GenericConstructs,
+    test_ranges: Vec<RangeInclusive<usize>>,
+    class_modules: bool,
+}
+
+impl SizeCounter {
+    fn generics(&mut self, generics: &syn::Generics) {
+        if is_generic(generics) {
+            self.generic.generic_items += 1;
+        }
+    }
+
+    fn skip_test(&mut self, attrs: &[syn::Attribute], node: &impl Spanned) -> bool {
+        let test = is_cfg_test(attrs);
+        if test {
+            self.test_ranges.push(lines_of(node));
+        }
+        test
+    }
+}
+
+impl<'ast> Visit<'ast> for SizeCounter {
+    fn visit_item_mod(&mut self, module: &'ast syn::ItemMod) {
+        if !self.skip_test(&module.attrs, module) {
+            self.class_modules |= module.content.is_some();
+            visit::visit_item_mod(self, module);
+        }
+    }
+
+    fn visit_item_fn(&mut self, func: &'ast syn::ItemFn) {
+        if !self.skip_test(&func.attrs, func) {
+            self.items.functions += 1;
+            self.generics(&func.sig.generics);
+            visit::visit_item_fn(self, func);
+        }
+    }
+
+    fn visit_impl_item_fn(&mut self, func: &'ast syn::ImplItemFn) {
+        self.items.functions += 1;
+        self.generics(&func.sig.generics);
+        visit::visit_impl_item_fn(self, func);
+    }
+
+    fn visit_trait_item_fn(&mut self, func: &'ast syn::TraitItemFn) {
+        self.items.functions += 1;
+        self.generics(&func.sig.generics);
+        visit::visit_trait_item_fn(self, func);
+    }
+
+    fn visit_item_struct(&mut self, item: &'ast syn::ItemStruct) {
+        self.items.types += 1;
+        self.generics(&item.generics);
+        self.generic.derives += derive_count(&item.attrs);
+        visit::visit_item_struct(self, item);
+    }
+
+    fn visit_item_enum(&mut self, item: &'ast syn::ItemEnum) {
+        self.items.types += 1;
+        self.generics(&item.generics);
+        self.generic.derives += derive_count(&item.attrs);
+        visit::visit_item_enum(self, item);
+    }
+
+    fn visit_item_union(&mut self, item: &'ast syn::ItemUnion) {
+        self.items.types += 1;
+        self.generics(&item.generics);
+        visit::visit_item_union(self, item);
+    }
+
+    fn visit_item_type(&mut self, item: &'ast syn::ItemType) {
+        self.items.types += 1;
+        visit::visit_item_type(self, item);
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        if !self.skip_test(&item.attrs, item) {
+            self.items.impls += 1;
+            self.generics(&item.generics);
+            visit::visit_item_impl(self, item);
+        }
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        self.items.traits += 1;
+        self.generics(&item.generics);
+        visit::visit_item_trait(self, item);
+    }
+
+    fn visit_item_const(&mut self, item: &'ast syn::ItemConst) {
+        self.items.constants += 1;
+        visit::visit_item_const(self, item);
+    }
+
+    fn visit_item_static(&mut self, item: &'ast syn::ItemStatic) {
+        self.items.constants += 1;
+        visit::visit_item_static(self, item);
+    }
+
+    fn visit_type_impl_trait(&mut self, ty: &'ast syn::TypeImplTrait) {
+        self.generic.impl_traits += 1;
+        visit::visit_type_impl_trait(self, ty);
+    }
+
+    fn visit_expr_closure(&mut self, closure: &'ast syn::ExprClosure) {
+        self.generic.closures += 1;
+        visit::visit_expr_closure(self, closure);
+    }
+}
+
+/// Groups the top-level items, and those of class modules, into units
+#[derive(Default)]
+struct UnitCollector {
+    units: Vec<(String, UnitKind, Vec<RangeInclusive<usize>>)>,
+    /// Type name -> index into `units`
+    classes: HashMap<String, usize>,
+}
+
+impl UnitCollector {
+    fn collect(&mut self, items: &[syn::Item]) {
+        for item in items {
+            match item {
+                syn::Item::Struct(item) => self.class(&item.ident, item),
+                syn::Item::Enum(item) => self.class(&item.ident, item),
+                syn::Item::Fn(func) if !is_cfg_test(&func.attrs) => {
+                    self.units.push((
+                        func.sig.ident.to_string(),
+                        UnitKind::Function,
+                        vec![lines_of(func)],
+                    ));
+                }
+                syn::Item::Impl(item) if !is_cfg_test(&item.attrs) => {
+                    let owner = match &*item.self_ty {
+                        syn::Type::Path(path) => path.path.segments.last(),
+                        _ => None,
+                    };
+                    let index = owner.and_then(|owner| self.classes.get(&owner.ident.to_string()));
+                    if let Some(&index) = index {
+                        self.units[index].2.push(lines_of(item));
+                    }
+                }
+                syn::Item::Mod(module) if !is_cfg_test(&module.attrs) => {
+                    if let Some((_, items)) = &module.content {
+                        self.collect(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn class(&mut self, name: &syn::Ident, item: &impl Spanned) {
+        let name = name.to_string();
+        self.classes.insert(name.clone(), self.units.len());
+        self.units
+            .push((name, UnitKind::Class, vec![lines_of(item)]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODE: &str = r#"use std::collections::HashMap;
+
+const LIMIT: i32 = 10;
+
+// Generated from shapes.py
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stack {
+    items: Vec<i32>,
+}
+
+impl Stack {
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    pub fn push(&mut self, x: i32) {
+        self.items.push(x);
+    }
+
+    pub fn total(&self) -> i32 {
+        self.items.iter().map(|x| x * 2).sum()
+    }
+}
+
+pub fn largest<T: PartialOrd + Copy>(xs: &[T]) -> T {
+    let mut best = xs[0];
+    for &x in xs {
+        if x > best {
+            best = x;
+        }
+    }
+    best
+}
+
+pub fn evens(xs: &[i32]) -> impl Iterator<Item = &i32> {
+    xs.iter().filter(|x| *x % 2 == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stack() {
+        let mut stack = Stack::new();
+        stack.push(1);
+        assert_eq!(stack.total(), 2);
+    }
+}
+"#;
+
+    fn report() -> CodeSizeReport {
+        CodeSizeReport::measure(CODE).unwrap()
+    }
+
+    #[test]
+    fn test_lines_and_items_leave_out_tests() {
+        let report = report();
+        assert_eq!(report.lines, 29);
+        assert_eq!(
+            report.items,
+            ItemCounts {
+                functions: 5,
+                types: 1,
+                impls: 1,
+                traits: 0,
+                constants: 1,
+            }
+        );
+        assert_eq!(
+            report.generic_constructs,
+            GenericConstructs {
+                generic_items: 1,
+                impl_traits: 1,
+                closures: 2,
+                derives: 3,
+            }
+        );
+        assert!(!report.class_modules);
+    }
+
+    #[test]
+    fn test_units_are_largest_first() {
+        let units: Vec<_> = report()
+            .units
+            .into_iter()
+            .map(|u| (u.name, u.kind, u.lines))
+            .collect();
+        assert_eq!(
+            units,
+            [
+                ("Stack".to_string(), UnitKind::Class, 15),
+                ("largest".to_string(), UnitKind::Function, 9),
+                ("evens".to_string(), UnitKind::Function, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_budget_gates_and_suggestions() {
+        let budget = CodeSizeBudget {
+            max_lines: Some(20),
+            max_items: Some(100),
+            max_generic_constructs: Some(3),
+        };
+        let report = report();
+        let results = report.gate_results(&budget);
+        assert_eq!(results.len(), 3);
+        assert!(!results[0].passed);
+        assert_eq!(results[0].actual_value, "29");
+        assert!(results[1].passed);
+        assert!(!results[2].passed);
+        assert_eq!(results[2].actual_value, "7");
+
+        let suggestions = report.suggestions(&budget);
+        assert_eq!(suggestions.len(), 2);
+        assert!(suggestions[0].contains("--class-modules"));
+        assert!(suggestions[0].contains("`Stack`, has 15 lines"));
+        assert!(suggestions[1].starts_with("1 generic items, 1 `impl Trait`s and 2 closures"));
+    }
+
+    #[test]
+    fn test_class_modules_are_measured_per_class() {
+        let code = "pub mod stack {\n    use super::*;\n    pub struct Stack {\n        items: Vec<i32>,\n    }\n    impl Stack {\n        pub fn len(&self) -> usize {\n            self.items.len()\n        }\n    }\n}\npub use stack::Stack;\n";
+        let report = CodeSizeReport::measure(code).unwrap();
+        assert!(report.class_modules);
+        assert_eq!(report.units[0].name, "Stack");
+        assert_eq!(report.units[0].lines, 8);
+        let budget = CodeSizeBudget {
+            max_lines: Some(1),
+            ..Default::default()
+        };
+        assert!(report.suggestions(&budget).is_empty());
+    }
+
+    #[test]
+    fn test_empty_budget_has_no_requirements() {
+        assert!(CodeSizeBudget::default().requirements().is_empty());
+        assert!(report().gate_results(&CodeSizeBudget::default()).is_empty());
+    }
+}
@@ -0,0 +1,294 @@
+//! Project quality gates from `depyler.toml`
+//!
+//! The `[quality]` table of a project's `depyler.toml` replaces the built-in
+//! thresholds of [`QualityAnalyzer`](crate::QualityAnalyzer). Every key is
+//! optional; the defaults are the built-in gates:
+//!
+//! ```toml
+//! [quality]
+//! max_cyclomatic = 20
+//! max_cognitive = 15
+//! min_test_coverage = 0.80
+//! min_function_coverage = 0.85
+//! min_tdg = 1.0
+//! max_tdg = 2.0
+//! # Gates that only apply when set
+//! min_type_coverage = 0.9
+//! allowed_panics = 0
+//!
+//! [quality.pmat_weights]
+//! productivity = 0.25
+//! maintainability = 0.25
+//! accessibility = 0.25
+//! testability = 0.25
+//! ```
+//!
+//! Other tables of `depyler.toml` are left to the tools that read them.
+
+use crate::{QualityError, QualityGate, QualityRequirement, Severity};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The file [`QualityConfig::load`] expects in a project's root
+pub const CONFIG_FILE: &str = "depyler.toml";
+
+pub const TYPE_COVERAGE_GATE: &str = "Type Coverage";
+pub const PANIC_GATE: &str = "Panic Budget";
+
+/// The `[quality]` table of `depyler.toml`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QualityConfig {
+    /// Highest cyclomatic complexity of any function
+    pub max_cyclomatic: u32,
+    /// Highest cognitive complexity of any function
+    pub max_cognitive: u32,
+    /// Lowest line coverage, as a fraction
+    pub min_test_coverage: f64,
+    /// Lowest function coverage, as a fraction
+    pub min_function_coverage: f64,
+    pub min_tdg: f64,
+    pub max_tdg: f64,
+    /// Lowest share of parameters and return types with annotations, as a
+    /// fraction
+    pub min_type_coverage: Option<f64>,
+    /// Most panic sites (`panic!`, `.unwrap()`, `.expect(..)`, ...) the
+    /// generated code may have
+    pub allowed_panics: Option<usize>,
+    pub pmat_weights: PmatWeights,
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            max_cyclomatic: 20,
+            max_cognitive: 15,
+            min_test_coverage: 0.80,
+            min_function_coverage: 0.85,
+            min_tdg: 1.0,
+            max_tdg: 2.0,
+            min_type_coverage: None,
+            allowed_panics: None,
+            pmat_weights: PmatWeights::default(),
+        }
+    }
+}
+
+/// How much each PMAT score counts towards the TDG score
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PmatWeights {
+    pub productivity: f64,
+    pub maintainability: f64,
+    pub accessibility: f64,
+    pub testability: f64,
+}
+
+impl Default for PmatWeights {
+    fn default() -> Self {
+        Self {
+            productivity: 0.25,
+            maintainability: 0.25,
+            accessibility: 0.25,
+            testability: 0.25,
+        }
+    }
+}
+
+impl PmatWeights {
+    fn total(&self) -> f64 {
+        self.productivity + self.maintainability + self.accessibility + self.testability
+    }
+}
+
+/// `depyler.toml` as far as quality gates go
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    quality: QualityConfig,
+}
+
+impl QualityConfig {
+    /// Reads and validates the `[quality]` table of the `depyler.toml` at
+    /// `path`
+    pub fn load(path: &Path) -> Result<Self, QualityError> {
+        let text = fs::read_to_string(path).map_err(|e| QualityError::ConfigUnreadable {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        Self::from_toml(&text)
+    }
+
+    /// Parses and validates the `[quality]` table of a `depyler.toml`
+    pub fn from_toml(text: &str) -> Result<Self, QualityError> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| QualityError::InvalidConfig {
+            reason: e.to_string(),
+        })?;
+        file.quality
+            .validate()
+            .map_err(|reason| QualityError::InvalidConfig { reason })?;
+        Ok(file.quality)
+    }
+
+    /// Checks that every threshold can be met and the weights add up to 1
+    pub fn validate(&self) -> Result<(), String> {
+        let fractions = [
+            ("min_test_coverage", Some(self.min_test_coverage)),
+            ("min_function_coverage", Some(self.min_function_coverage)),
+            ("min_type_coverage", self.min_type_coverage),
+        ];
+        for (key, value) in fractions {
+            if let Some(value) = value {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(format!("{} must be between 0 and 1, not {}", key, value));
+                }
+            }
+        }
+        // TDG scores range from 0 to 2
+        for (key, value) in [("min_tdg", self.min_tdg), ("max_tdg", self.max_tdg)] {
+            if !(0.0..=2.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 2, not {}", key, value));
+            }
+        }
+        if self.min_tdg > self.max_tdg {
+            return Err(format!(
+                "min_tdg ({}) is above max_tdg ({})",
+                self.min_tdg, self.max_tdg
+            ));
+        }
+        if self.max_cyclomatic == 0 {
+            return Err("max_cyclomatic must be at least 1".to_string());
+        }
+
+        let weights = &self.pmat_weights;
+        let named = [
+            ("productivity", weights.productivity),
+            ("maintainability", weights.maintainability),
+            ("accessibility", weights.accessibility),
+            ("testability", weights.testability),
+        ];
+        for (key, weight) in named {
+            if weight.is_nan() || weight < 0.0 {
+                return Err(format!("pmat_weights.{} must not be negative", key));
+            }
+        }
+        if (weights.total() - 1.0).abs() > 1e-6 {
+            return Err(format!(
+                "pmat_weights must add up to 1, not {}",
+                weights.total()
+            ));
+        }
+        Ok(())
+    }
+
+    /// The gates these thresholds make up
+    pub fn gates(&self) -> Vec<QualityGate> {
+        let mut gates = vec![
+            QualityGate {
+                name: "PMAT TDG Range".to_string(),
+                requirements: vec![
+                    QualityRequirement::MinPmatTdg(self.min_tdg),
+                    QualityRequirement::MaxPmatTdg(self.max_tdg),
+                ],
+                severity: Severity::Error,
+            },
+            QualityGate {
+                name: "Complexity Limits".to_string(),
+                requirements: vec![
+                    QualityRequirement::MaxComplexity(self.max_cyclomatic),
+                    QualityRequirement::MaxCognitiveComplexity(self.max_cognitive),
+                ],
+                severity: Severity::Error,
+            },
+            QualityGate {
+                name: "Test Coverage".to_string(),
+                requirements: vec![
+                    QualityRequirement::MinTestCoverage(self.min_test_coverage),
+                    QualityRequirement::MinFunctionCoverage(self.min_function_coverage),
+                ],
+                severity: Severity::Error,
+            },
+            QualityGate {
+                name: "Code Quality".to_string(),
+                requirements: vec![
+                    QualityRequirement::CompilationSuccess,
+                    QualityRequirement::ClippyClean,
+                    QualityRequirement::AnnotationConsistency,
+                ],
+                severity: Severity::Error,
+            },
+            QualityGate {
+                name: "Energy Efficiency".to_string(),
+                requirements: vec![QualityRequirement::EnergyEfficient(0.75)],
+                severity: Severity::Warning,
+            },
+        ];
+        if let Some(min) = self.min_type_coverage {
+            gates.push(QualityGate {
+                name: TYPE_COVERAGE_GATE.to_string(),
+                requirements: vec![QualityRequirement::MinTypeCoverage(min)],
+                severity: Severity::Error,
+            });
+        }
+        if let Some(max) = self.allowed_panics {
+            gates.push(QualityGate {
+                name: PANIC_GATE.to_string(),
+                requirements: vec![QualityRequirement::MaxPanics(max)],
+                severity: Severity::Error,
+            });
+        }
+        gates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_keys_keep_the_defaults() {
+        let config = QualityConfig::from_toml("[quality]\nmax_cyclomatic = 12\n").unwrap();
+        assert_eq!(config.max_cyclomatic, 12);
+        assert_eq!(config.max_cognitive, 15);
+        assert_eq!(config.pmat_weights, PmatWeights::default());
+
+        let config = QualityConfig::from_toml("[package]\nname = \"app\"\n").unwrap();
+        assert_eq!(config, QualityConfig::default());
+    }
+
+    #[test]
+    fn test_optional_gates_are_added_when_set() {
+        assert_eq!(QualityConfig::default().gates().len(), 5);
+
+        let config =
+            QualityConfig::from_toml("[quality]\nmin_type_coverage = 0.9\nallowed_panics = 2\n")
+                .unwrap();
+        let gates = config.gates();
+        assert_eq!(gates.len(), 7);
+        assert_eq!(
+            gates[5].requirements,
+            vec![QualityRequirement::MinTypeCoverage(0.9)]
+        );
+        assert_eq!(
+            gates[6].requirements,
+            vec![QualityRequirement::MaxPanics(2)]
+        );
+    }
+
+    #[test]
+    fn test_invalid_thresholds_are_rejected() {
+        let error = |text: &str| QualityConfig::from_toml(text).unwrap_err().to_string();
+
+        assert!(error("[quality]\nmin_test_coverage = 80\n").contains("between 0 and 1"));
+        assert!(error("[quality]\nmin_tdg = 1.5\nmax_tdg = 1.2\n").contains("above max_tdg"));
+        assert!(error("[quality]\nmax_cyclomatic = 0\n").contains("at least 1"));
+        assert!(error("[quality.pmat_weights]\nproductivity = 0.5\n").contains("add up to 1"));
+        assert!(
+            error("[quality.pmat_weights]\nproductivity = -0.25\nmaintainability = 0.75\n")
+                .contains("negative")
+        );
+        assert!(error("[quality]\nmax_complexity = 10\n").contains("max_complexity"));
+        assert!(error("[quality]\nallowed_panics = -1\n").contains("allowed_panics"));
+    }
+}
@@ -0,0 +1,238 @@
+//! Measured coverage of a transpiled crate
+//!
+//! Runs the crate's tests (typically the ones `depyler translate-tests`
+//! wrote) under `cargo llvm-cov`, reads the LCOV output and attributes the
+//! covered lines and branches to each generated function. The module-wide
+//! numbers replace the estimated [`CoverageMetrics`] in the quality gates.
+//!
+//! llvm-cov only emits branch records for branch-instrumented (nightly)
+//! builds; without them branch coverage follows line coverage.
+
+use crate::code_size::is_cfg_test;
+use crate::{CoverageMetrics, QualityError};
+use depyler_analyzer::coverage::{
+    function_coverage, CoverageReport, FunctionCoverage, FunctionSpan,
+};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use syn::spanned::Spanned;
+
+/// Per-function and module-wide coverage of a crate's generated code
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateCoverage {
+    pub functions: Vec<FunctionCoverage>,
+    pub metrics: CoverageMetrics,
+}
+
+/// Runs the tests of the crate in `crate_dir` with coverage instrumentation
+/// and measures `source` (relative to the crate, usually `src/lib.rs`)
+pub fn measure_crate_coverage(
+    crate_dir: &Path,
+    source: &Path,
+) -> Result<CrateCoverage, QualityError> {
+    let crate_dir = crate_dir
+        .canonicalize()
+        .unwrap_or_else(|_| crate_dir.to_path_buf());
+    let source_path = crate_dir.join(source);
+    let code = fs::read_to_string(&source_path).map_err(|e| measurement_failed(e.to_string()))?;
+
+    let lcov = run_llvm_cov(&crate_dir)?;
+    let report = CoverageReport::from_lcov(&lcov).map_err(|e| measurement_failed(e.to_string()))?;
+    let file = report.file(&source_path).ok_or_else(|| {
+        measurement_failed(format!(
+            "no coverage recorded for {}",
+            source_path.display()
+        ))
+    })?;
+
+    let functions = function_coverage(&rust_function_spans(&code)?, file);
+    let metrics = file.summary(&functions).into();
+    Ok(CrateCoverage { functions, metrics })
+}
+
+/// `cargo llvm-cov --lcov` in `crate_dir`, returning the tracefile
+fn run_llvm_cov(crate_dir: &Path) -> Result<String, QualityError> {
+    let out_dir = tempfile::tempdir().map_err(|e| measurement_failed(e.to_string()))?;
+    let lcov_path = out_dir.path().join("lcov.info");
+
+    let output = Command::new("cargo")
+        .args(["llvm-cov", "--lcov", "--output-path"])
+        .arg(&lcov_path)
+        .current_dir(crate_dir)
+        .output()
+        .map_err(|_| QualityError::CoverageToolMissing {
+            tool: "cargo".to_string(),
+        })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("no such command") {
+            return Err(QualityError::CoverageToolMissing {
+                tool: "cargo-llvm-cov (cargo install cargo-llvm-cov)".to_string(),
+            });
+        }
+        let reason = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .unwrap_or("cargo llvm-cov failed")
+            .to_string();
+        return Err(measurement_failed(reason));
+    }
+
+    fs::read_to_string(&lcov_path).map_err(|e| measurement_failed(e.to_string()))
+}
+
+fn measurement_failed(reason: String) -> QualityError {
+    QualityError::CoverageMeasurementFailed { reason }
+}
+
+/// Spans of the free functions and inherent/trait methods in `code`, also
+/// in class modules; methods are named `Type.method` like their Python
+/// counterparts
+pub fn rust_function_spans(code: &str) -> Result<Vec<FunctionSpan>, QualityError> {
+    let file = syn::parse_file(code).map_err(|e| measurement_failed(e.to_string()))?;
+    let mut spans = Vec::new();
+    collect_function_spans(&file.items, &mut spans);
+    Ok(spans)
+}
+
+/// # Complexity
+/// 6 (item match with impl methods and module recursion)
+fn collect_function_spans(items: &[syn::Item], spans: &mut Vec<FunctionSpan>) {
+    for item in items {
+        match item {
+            syn::Item::Fn(func) if !is_cfg_test(&func.attrs) => {
+                spans.push(function_span(
+                    func.sig.ident.to_string(),
+                    &func.sig,
+                    &func.block,
+                ));
+            }
+            syn::Item::Impl(item) if !is_cfg_test(&item.attrs) => {
+                let Some(ty) = impl_target(&item.self_ty) else {
+                    continue;
+                };
+                for impl_item in &item.items {
+                    if let syn::ImplItem::Fn(method) = impl_item {
+                        let name = format!("{}.{}", ty, method.sig.ident);
+                        spans.push(function_span(name, &method.sig, &method.block));
+                    }
+                }
+            }
+            syn::Item::Mod(module) if !is_cfg_test(&module.attrs) => {
+                if let Some((_, items)) = &module.content {
+                    collect_function_spans(items, spans);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn function_span(name: String, sig: &syn::Signature, block: &syn::Block) -> FunctionSpan {
+    let braces = block.brace_token.span;
+    let end_line = braces.close().end().line as u32;
+    FunctionSpan {
+        name,
+        def_line: sig.span().start().line as u32,
+        body_start: (braces.open().start().line as u32 + 1).min(end_line),
+        end_line,
+    }
+}
+
+/// `impl Trait for Type<T>` -> `Type`
+fn impl_target(self_ty: &syn::Type) -> Option<String> {
+    match self_ty {
+        syn::Type::Path(path) => path.path.segments.last().map(|s| s.ident.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GENERATED: &str = "\
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub balance: f64,
+}
+
+impl Account {
+    pub fn new(balance: f64) -> Self {
+        Self { balance }
+    }
+
+    pub fn deposit(&mut self, amount: f64) {}
+}
+
+pub fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+#[cfg(test)]
+mod tests {
+    fn helper() {}
+}
+";
+
+    #[test]
+    fn test_rust_function_spans() {
+        let spans: Vec<_> = rust_function_spans(GENERATED)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.def_line, s.end_line))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("Account.new".to_string(), 7, 9),
+                ("Account.deposit".to_string(), 11, 11),
+                ("add".to_string(), 14, 16),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans_follow_items_not_indentation() {
+        let code = "\
+mod account {
+    pub struct Account;
+    impl std::fmt::Display for Account {
+        fn fmt(
+            &self,
+            f: &mut std::fmt::Formatter<'_>,
+        ) -> std::fmt::Result {
+            write!(f, \"account\")
+        }
+    }
+}
+pub fn add(a: i32, b: i32) -> i32 { a + b }
+";
+        let spans: Vec<_> = rust_function_spans(code)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.name, s.def_line, s.body_start, s.end_line))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                ("Account.fmt".to_string(), 4, 8, 9),
+                ("add".to_string(), 12, 12, 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_impl_target() {
+        let target = |ty: &str| impl_target(&syn::parse_str(ty).unwrap());
+        assert_eq!(target("Account").as_deref(), Some("Account"));
+        assert_eq!(target("Stack<T>").as_deref(), Some("Stack"));
+        assert_eq!(
+            target("crate::DefaultFetchRate").as_deref(),
+            Some("DefaultFetchRate")
+        );
+        assert_eq!(target("(i32, i32)"), None);
+    }
+}
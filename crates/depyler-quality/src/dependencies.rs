@@ -0,0 +1,155 @@
+//! Dependency budget and license summary of a generated crate
+//!
+//! The crates are the ones `cargo_toml_gen` writes into the generated
+//! Cargo.toml, so the policy applies to exactly what a build would pull in
+//! directly. Licenses come from the same table and need no registry access.
+
+use crate::{QualityGateResult, QualityRequirement, Severity};
+use depyler_core::cargo_toml_gen::Dependency;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const GATE_NAME: &str = "Dependency Policy";
+
+/// Limits an organisation puts on generated crates
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyPolicy {
+    /// Most crates the generated Cargo.toml may list
+    pub max_dependencies: Option<usize>,
+    /// Crates that must not be used at all
+    pub banned_crates: Vec<String>,
+}
+
+impl DependencyPolicy {
+    pub fn requirements(&self) -> Vec<QualityRequirement> {
+        let mut requirements = Vec::new();
+        if let Some(max) = self.max_dependencies {
+            requirements.push(QualityRequirement::MaxDependencies(max));
+        }
+        if !self.banned_crates.is_empty() {
+            requirements.push(QualityRequirement::NoBannedCrates(
+                self.banned_crates.clone(),
+            ));
+        }
+        requirements
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEntry {
+    pub name: String,
+    pub version: String,
+    pub license: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyReport {
+    pub dependencies: Vec<DependencyEntry>,
+    /// License expression -> crates released under it
+    pub licenses: BTreeMap<String, Vec<String>>,
+}
+
+impl DependencyReport {
+    pub fn new(dependencies: &[Dependency]) -> Self {
+        let mut licenses: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for dep in dependencies {
+            licenses
+                .entry(dep.license.to_string())
+                .or_default()
+                .push(dep.name.to_string());
+        }
+        Self {
+            dependencies: dependencies
+                .iter()
+                .map(|dep| DependencyEntry {
+                    name: dep.name.to_string(),
+                    version: dep.version.to_string(),
+                    license: dep.license.to_string(),
+                })
+                .collect(),
+            licenses,
+        }
+    }
+
+    /// Whether the report satisfies a dependency requirement, with the
+    /// value shown for it; `None` for requirements on other metrics
+    pub fn evaluate(&self, requirement: &QualityRequirement) -> Option<(bool, String)> {
+        match requirement {
+            QualityRequirement::MaxDependencies(max) => Some((
+                self.dependencies.len() <= *max,
+                self.dependencies.len().to_string(),
+            )),
+            QualityRequirement::NoBannedCrates(banned) => {
+                let used: Vec<&str> = self
+                    .dependencies
+                    .iter()
+                    .filter(|dep| banned.contains(&dep.name))
+                    .map(|dep| dep.name.as_str())
+                    .collect();
+                let actual = if used.is_empty() {
+                    "none".to_string()
+                } else {
+                    used.join(", ")
+                };
+                Some((used.is_empty(), actual))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn gate_results(&self, policy: &DependencyPolicy) -> Vec<QualityGateResult> {
+        policy
+            .requirements()
+            .into_iter()
+            .filter_map(|requirement| {
+                let (passed, actual_value) = self.evaluate(&requirement)?;
+                Some(QualityGateResult {
+                    gate_name: GATE_NAME.to_string(),
+                    requirement,
+                    actual_value,
+                    passed,
+                    severity: Severity::Error,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use depyler_core::cargo_toml_gen::detect_dependencies;
+
+    fn report() -> DependencyReport {
+        let code = "#[tokio::main] async fn main() { let _ = serde_json::json!(1); }";
+        DependencyReport::new(&detect_dependencies(code).unwrap())
+    }
+
+    #[test]
+    fn test_licenses_are_grouped() {
+        let report = report();
+        assert_eq!(report.licenses["MIT"], ["tokio"]);
+        assert_eq!(report.licenses["MIT OR Apache-2.0"], ["serde_json"]);
+    }
+
+    #[test]
+    fn test_budget_and_banned_crates() {
+        let policy = DependencyPolicy {
+            max_dependencies: Some(1),
+            banned_crates: vec!["tokio".to_string(), "regex".to_string()],
+        };
+        let results = report().gate_results(&policy);
+        assert_eq!(results.len(), 2);
+        assert!(!results[0].passed);
+        assert_eq!(results[0].actual_value, "2");
+        assert!(!results[1].passed);
+        assert_eq!(results[1].actual_value, "tokio");
+    }
+
+    #[test]
+    fn test_empty_policy_has_no_requirements() {
+        assert!(report()
+            .gate_results(&DependencyPolicy::default())
+            .is_empty());
+    }
+}
@@ -1,8 +1,22 @@
+pub mod code_size;
+pub mod config;
+pub mod coverage;
+pub mod dependencies;
+pub mod mutation;
+pub mod report;
+
+use code_size::{CodeSizeBudget, CodeSizeReport, UnitKind};
+use config::{PmatWeights, QualityConfig};
+use dependencies::{DependencyPolicy, DependencyReport};
+use depyler_analyzer::coverage::CoverageSummary;
 use depyler_analyzer::{calculate_cognitive, calculate_cyclomatic, count_statements};
 use depyler_annotations::AnnotationValidator;
-use depyler_core::hir::HirFunction;
+use depyler_core::cargo_toml_gen::Dependency;
+use depyler_core::hir::{HirFunction, Type};
+use mutation::MutationMetrics;
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 use thiserror::Error;
 
@@ -14,6 +28,16 @@ pub enum QualityError {
     MetricCalculationFailed { metric: String },
     #[error("Coverage data unavailable")]
     CoverageUnavailable,
+    #[error("Coverage tool not found: {tool}")]
+    CoverageToolMissing { tool: String },
+    #[error("Coverage measurement failed: {reason}")]
+    CoverageMeasurementFailed { reason: String },
+    #[error("Mutation testing failed: {reason}")]
+    MutationTestingFailed { reason: String },
+    #[error("Cannot read {path}: {reason}")]
+    ConfigUnreadable { path: String, reason: String },
+    #[error("Invalid depyler.toml: {reason}")]
+    InvalidConfig { reason: String },
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -36,6 +60,13 @@ pub enum QualityRequirement {
     AnnotationConsistency,       // Annotations must be valid and consistent
     MaxCognitiveComplexity(u32), // <= 15 per function
     MinFunctionCoverage(f64),    // >= 85% function coverage
+    MaxDependencies(usize),      // crates in the generated Cargo.toml
+    NoBannedCrates(Vec<String>), // crates the generated code must not use
+    MinTypeCoverage(f64),        // annotated parameters and return types
+    MaxPanics(usize),            // panic sites in the generated code
+    MaxGeneratedLines(usize),    // lines of generated Rust
+    MaxGeneratedItems(usize),    // functions, types, impls, ... in the generated Rust
+    MaxGenericConstructs(usize), // generic items, impl Trait, closures and derives
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -62,6 +93,21 @@ pub struct QualityReport {
     pub gates_passed: Vec<String>,
     pub gates_failed: Vec<QualityGateResult>,
     pub overall_status: QualityStatus,
+    /// Set by the caller after running the generated tests against mutants
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mutation_metrics: Option<MutationMetrics>,
+    /// Set by [`QualityAnalyzer::check_dependencies`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dependency_report: Option<DependencyReport>,
+    /// Share of parameters and return types with annotations
+    #[serde(default)]
+    pub type_coverage: f64,
+    /// Set by [`QualityAnalyzer::check_panics`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub panic_sites: Option<usize>,
+    /// Set by [`QualityAnalyzer::check_code_size`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_size: Option<CodeSizeReport>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -79,6 +125,16 @@ pub struct CoverageMetrics {
     pub function_coverage: f64,
 }
 
+impl From<CoverageSummary> for CoverageMetrics {
+    fn from(summary: CoverageSummary) -> Self {
+        Self {
+            line_coverage: summary.line_coverage,
+            branch_coverage: summary.branch_coverage,
+            function_coverage: summary.function_coverage,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct QualityGateResult {
     pub gate_name: String,
@@ -97,7 +153,10 @@ pub enum QualityStatus {
 
 pub struct QualityAnalyzer {
     gates: Vec<QualityGate>,
+    pmat_weights: PmatWeights,
     annotation_validator: AnnotationValidator,
+    /// Measured coverage, e.g. from a coverage.py report
+    coverage: Option<CoverageMetrics>,
 }
 
 impl Default for QualityAnalyzer {
@@ -108,53 +167,31 @@ impl Default for QualityAnalyzer {
 
 impl QualityAnalyzer {
     pub fn new() -> Self {
-        let gates = vec![
-            QualityGate {
-                name: "PMAT TDG Range".to_string(),
-                requirements: vec![
-                    QualityRequirement::MinPmatTdg(1.0),
-                    QualityRequirement::MaxPmatTdg(2.0),
-                ],
-                severity: Severity::Error,
-            },
-            QualityGate {
-                name: "Complexity Limits".to_string(),
-                requirements: vec![
-                    QualityRequirement::MaxComplexity(20),
-                    QualityRequirement::MaxCognitiveComplexity(15),
-                ],
-                severity: Severity::Error,
-            },
-            QualityGate {
-                name: "Test Coverage".to_string(),
-                requirements: vec![
-                    QualityRequirement::MinTestCoverage(0.80),
-                    QualityRequirement::MinFunctionCoverage(0.85),
-                ],
-                severity: Severity::Error,
-            },
-            QualityGate {
-                name: "Code Quality".to_string(),
-                requirements: vec![
-                    QualityRequirement::CompilationSuccess,
-                    QualityRequirement::ClippyClean,
-                    QualityRequirement::AnnotationConsistency,
-                ],
-                severity: Severity::Error,
-            },
-            QualityGate {
-                name: "Energy Efficiency".to_string(),
-                requirements: vec![QualityRequirement::EnergyEfficient(0.75)],
-                severity: Severity::Warning,
-            },
-        ];
+        Self::with_config(&QualityConfig::default())
+    }
 
+    /// Uses the gates and PMAT weights of `config` instead of the built-in
+    /// ones
+    pub fn with_config(config: &QualityConfig) -> Self {
         Self {
-            gates,
+            gates: config.gates(),
+            pmat_weights: config.pmat_weights.clone(),
             annotation_validator: AnnotationValidator::new(),
+            coverage: None,
         }
     }
 
+    /// Uses the project's gates from the `depyler.toml` at `path`
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self, QualityError> {
+        Ok(Self::with_config(&QualityConfig::load(path.as_ref())?))
+    }
+
+    /// Evaluates the coverage gates against measured coverage
+    pub fn with_coverage(mut self, coverage: CoverageMetrics) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
     pub fn analyze_quality(
         &self,
         functions: &[HirFunction],
@@ -162,13 +199,19 @@ impl QualityAnalyzer {
         let pmat_metrics = self.calculate_pmat_metrics(functions)?;
         let complexity_metrics = self.calculate_complexity_metrics(functions);
         let coverage_metrics = self.calculate_coverage_metrics()?;
+        let type_coverage = calculate_type_coverage(functions);
 
         let mut gates_passed = Vec::new();
         let mut gates_failed = Vec::new();
 
         for gate in &self.gates {
-            let results =
-                self.evaluate_gate(gate, &pmat_metrics, &complexity_metrics, &coverage_metrics);
+            let results = self.evaluate_gate(
+                gate,
+                &pmat_metrics,
+                &complexity_metrics,
+                &coverage_metrics,
+                type_coverage,
+            );
 
             let mut gate_passed = true;
             for result in results {
@@ -183,16 +226,7 @@ impl QualityAnalyzer {
             }
         }
 
-        let overall_status = if gates_failed.is_empty() {
-            QualityStatus::Passed
-        } else if gates_failed
-            .iter()
-            .any(|r| matches!(r.severity, Severity::Error))
-        {
-            QualityStatus::Failed
-        } else {
-            QualityStatus::Warning
-        };
+        let overall_status = overall_status(&gates_failed);
 
         Ok(QualityReport {
             pmat_metrics,
@@ -201,9 +235,72 @@ impl QualityAnalyzer {
             gates_passed,
             gates_failed,
             overall_status,
+            mutation_metrics: None,
+            dependency_report: None,
+            type_coverage,
+            panic_sites: None,
+            code_size: None,
         })
     }
 
+    /// Adds the licenses of the generated crate's dependencies to `report`
+    /// and gates them on `policy`
+    pub fn check_dependencies(
+        &self,
+        report: &mut QualityReport,
+        dependencies: &[Dependency],
+        policy: &DependencyPolicy,
+    ) {
+        let dependency_report = DependencyReport::new(dependencies);
+        let results = dependency_report.gate_results(policy);
+        record_gate(report, dependencies::GATE_NAME, results);
+        report.dependency_report = Some(dependency_report);
+    }
+
+    /// Measures the generated `rust_code` into `report` and gates it on
+    /// `budget`
+    pub fn check_code_size(
+        &self,
+        report: &mut QualityReport,
+        rust_code: &str,
+        budget: &CodeSizeBudget,
+    ) -> Result<(), QualityError> {
+        let code_size = CodeSizeReport::measure(rust_code)?;
+        let results = code_size.gate_results(budget);
+        record_gate(report, code_size::GATE_NAME, results);
+        report.code_size = Some(code_size);
+        Ok(())
+    }
+
+    /// Counts the panic sites of the generated `rust_code` into `report`
+    /// and gates them on the configured panic budget
+    pub fn check_panics(&self, report: &mut QualityReport, rust_code: &str) {
+        let panic_sites = count_panic_sites(rust_code);
+        report.panic_sites = Some(panic_sites);
+        for gate in &self.gates {
+            let mut gate_passed = true;
+            for requirement in &gate.requirements {
+                if let QualityRequirement::MaxPanics(max) = requirement {
+                    if panic_sites > *max {
+                        gate_passed = false;
+                        report.gates_failed.push(QualityGateResult {
+                            gate_name: gate.name.clone(),
+                            requirement: requirement.clone(),
+                            actual_value: panic_sites.to_string(),
+                            passed: false,
+                            severity: gate.severity.clone(),
+                        });
+                    }
+                }
+            }
+            // The gate was marked passed before the panics were counted
+            if !gate_passed {
+                report.gates_passed.retain(|name| name != &gate.name);
+            }
+        }
+        report.overall_status = overall_status(&report.gates_failed);
+    }
+
     fn calculate_pmat_metrics(
         &self,
         functions: &[HirFunction],
@@ -240,11 +337,14 @@ impl QualityAnalyzer {
         // Testability: based on function complexity and testable patterns
         let testability_score = if avg_complexity <= 10.0 { 90.0 } else { 70.0 };
 
-        // Calculate TDG (Time, Defects, Gaps) score
-        let tdg =
-            (productivity_score + maintainability_score + accessibility_score + testability_score)
-                / 400.0
-                * 2.0;
+        // Calculate TDG (Time, Defects, Gaps) score from the weighted scores
+        let weights = &self.pmat_weights;
+        let tdg = (productivity_score * weights.productivity
+            + maintainability_score * weights.maintainability
+            + accessibility_score * weights.accessibility
+            + testability_score * weights.testability)
+            / 100.0
+            * 2.0;
 
         Ok(PmatMetrics {
             productivity_score,
@@ -285,6 +385,9 @@ impl QualityAnalyzer {
     }
 
     fn calculate_coverage_metrics(&self) -> Result<CoverageMetrics, QualityError> {
+        if let Some(coverage) = &self.coverage {
+            return Ok(coverage.clone());
+        }
         // Updated coverage metrics based on improved test suite
         // We now have comprehensive playground tests added
         // This represents significant coverage improvement with new wasm-bindgen tests
@@ -301,6 +404,7 @@ impl QualityAnalyzer {
         pmat: &PmatMetrics,
         complexity: &ComplexityMetrics,
         coverage: &CoverageMetrics,
+        type_coverage: f64,
     ) -> Vec<QualityGateResult> {
         let mut results = Vec::new();
 
@@ -348,6 +452,20 @@ impl QualityAnalyzer {
                     coverage.function_coverage >= *min,
                     format!("{:.1}%", coverage.function_coverage * 100.0),
                 ),
+                QualityRequirement::MinTypeCoverage(min) => (
+                    type_coverage >= *min,
+                    format!("{:.1}%", type_coverage * 100.0),
+                ),
+                QualityRequirement::MaxDependencies(_)
+                | QualityRequirement::NoBannedCrates(_)
+                | QualityRequirement::MaxPanics(_)
+                | QualityRequirement::MaxGeneratedLines(_)
+                | QualityRequirement::MaxGeneratedItems(_)
+                | QualityRequirement::MaxGenericConstructs(_) => {
+                    // Needs the generated code; see check_dependencies,
+                    // check_panics and check_code_size
+                    (true, "NOT CHECKED".to_string())
+                }
             };
 
             results.push(QualityGateResult {
@@ -362,6 +480,67 @@ impl QualityAnalyzer {
         results
     }
 
+    fn print_mutation_metrics(&self, mutation: &MutationMetrics) {
+        let percent = |score: Option<f64>| match score {
+            Some(score) => format!("{:.1}%", score * 100.0),
+            None => "n/a".to_string(),
+        };
+        println!("Mutation Testing:");
+        println!(
+            "  Score: {} ({} killed, {} survived, {} unviable)",
+            percent(mutation.mutation_score),
+            mutation.killed,
+            mutation.survived,
+            mutation.unviable
+        );
+        for function in &mutation.functions {
+            println!(
+                "  {}: {} ({}/{} killed)",
+                function.function,
+                percent(function.mutation_score),
+                function.killed,
+                function.killed + function.survived
+            );
+        }
+        for survivor in &mutation.survivors {
+            println!(
+                "  ⚠️  Survived: {} in {}",
+                survivor.description, survivor.function
+            );
+        }
+        println!();
+    }
+
+    fn print_code_size(&self, code_size: &CodeSizeReport) {
+        let items = &code_size.items;
+        let generic = &code_size.generic_constructs;
+        println!(
+            "Generated Code: {} lines, {} items",
+            code_size.lines,
+            items.total()
+        );
+        println!(
+            "  Functions: {}, Types: {}, Impls: {}, Traits: {}, Constants: {}",
+            items.functions, items.types, items.impls, items.traits, items.constants
+        );
+        println!(
+            "  Generic Constructs: {} ({} generic items, {} impl Trait, {} closures, {} derives)",
+            generic.total(),
+            generic.generic_items,
+            generic.impl_traits,
+            generic.closures,
+            generic.derives
+        );
+        for unit in code_size.units.iter().take(5) {
+            let kind = match unit.kind {
+                UnitKind::Class => "class",
+                UnitKind::Function => "function",
+            };
+            println!("  {:>6} lines  {kind} {}", unit.lines, unit.name);
+        }
+        println!();
+    }
+
     pub fn print_quality_report(&self, report: &QualityReport) {
         println!("Quality Report");
         println!("==============");
@@ -416,8 +595,30 @@ impl QualityAnalyzer {
             "  Function: {:.1}%",
             report.coverage_metrics.function_coverage * 100.0
         );
+        println!("  Type Annotations: {:.1}%", report.type_coverage * 100.0);
         println!();
 
+        if let Some(panic_sites) = report.panic_sites {
+            println!("Panic Sites: {panic_sites}");
+            println!();
+        }
+
+        if let Some(mutation) = &report.mutation_metrics {
+            self.print_mutation_metrics(mutation);
+        }
+
+        if let Some(dependencies) = &report.dependency_report {
+            println!("Dependencies: {}", dependencies.dependencies.len());
+            for (license, crates) in &dependencies.licenses {
+                println!("  {license}: {}", crates.join(", "));
+            }
+            println!();
+        }
+
+        if let Some(code_size) = &report.code_size {
+            self.print_code_size(code_size);
+        }
+
         println!("Quality Gates:");
         for gate in &report.gates_passed {
             println!("  ✅ {gate}");
@@ -546,6 +747,69 @@ edition = "2021"
     }
 }
 
+/// Share of the parameters and return types of `functions` that are
+/// annotated; 1 when there are none
+fn calculate_type_coverage(functions: &[HirFunction]) -> f64 {
+    let annotated = |ty: &Type| !matches!(ty, Type::Unknown);
+    let total: usize = functions.iter().map(|f| f.params.len() + 1).sum();
+    if total == 0 {
+        return 1.0;
+    }
+    let covered: usize = functions
+        .iter()
+        .map(|f| {
+            f.params.iter().filter(|p| annotated(&p.ty)).count()
+                + usize::from(annotated(&f.ret_type))
+        })
+        .sum();
+    covered as f64 / total as f64
+}
+
+/// Calls that panic in `rust_code`, leaving out its `#[cfg(test)]` module
+fn count_panic_sites(rust_code: &str) -> usize {
+    const PANICS: [&str; 6] = [
+        "panic!(",
+        "unreachable!(",
+        "todo!(",
+        "unimplemented!(",
+        ".unwrap()",
+        ".expect(",
+    ];
+    let code = match rust_code.find("#[cfg(test)]") {
+        Some(tests) => &rust_code[..tests],
+        None => rust_code,
+    };
+    PANICS.iter().map(|panic| code.matches(panic).count()).sum()
+}
+
+/// Adds the `results` of a gate checked after [`QualityAnalyzer::analyze_quality`]
+/// to `report`
+fn record_gate(report: &mut QualityReport, gate_name: &str, results: Vec<QualityGateResult>) {
+    if results.is_empty() {
+        return;
+    }
+    if results.iter().all(|r| r.passed) {
+        report.gates_passed.push(gate_name.to_string());
+    }
+    report
+        .gates_failed
+        .extend(results.into_iter().filter(|r| !r.passed));
+    report.overall_status = overall_status(&report.gates_failed);
+}
+
+fn overall_status(gates_failed: &[QualityGateResult]) -> QualityStatus {
+    if gates_failed.is_empty() {
+        QualityStatus::Passed
+    } else if gates_failed
+        .iter()
+        .any(|r| matches!(r.severity, Severity::Error))
+    {
+        QualityStatus::Failed
+    } else {
+        QualityStatus::Warning
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -636,6 +900,17 @@ mod tests {
         assert!(coverage.function_coverage > 0.0);
     }
 
+    #[test]
+    fn test_measured_coverage_replaces_estimate() {
+        let measured = CoverageMetrics {
+            line_coverage: 0.5,
+            branch_coverage: 0.25,
+            function_coverage: 1.0,
+        };
+        let analyzer = QualityAnalyzer::new().with_coverage(measured.clone());
+        assert_eq!(analyzer.calculate_coverage_metrics().unwrap(), measured);
+    }
+
     #[test]
     fn test_annotation_validation() {
         let analyzer = QualityAnalyzer::new();
@@ -670,6 +945,134 @@ mod tests {
         assert!(cognitive_gate_results.is_empty() || cognitive_gate_results[0].passed);
     }
 
+    #[test]
+    fn test_from_config_uses_the_project_gates() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(config::CONFIG_FILE);
+        fs::write(
+            &path,
+            "[quality]\nmax_cyclomatic = 2\nmin_type_coverage = 1.0\n",
+        )
+        .unwrap();
+
+        let analyzer = QualityAnalyzer::from_config(&path).unwrap();
+        let report = analyzer
+            .analyze_quality(&[create_test_function(3)])
+            .unwrap();
+
+        assert_eq!(report.overall_status, QualityStatus::Failed);
+        assert!(report
+            .gates_failed
+            .iter()
+            .any(|r| r.requirement == QualityRequirement::MaxComplexity(2)));
+        // The test function has no parameters and an annotated return type
+        assert_eq!(report.type_coverage, 1.0);
+        assert!(report
+            .gates_passed
+            .contains(&config::TYPE_COVERAGE_GATE.to_string()));
+
+        let missing = QualityAnalyzer::from_config(dir.path().join("missing.toml"));
+        assert!(matches!(
+            missing,
+            Err(QualityError::ConfigUnreadable { .. })
+        ));
+    }
+
+    #[test]
+    fn test_pmat_weights_shift_the_tdg() {
+        let functions = vec![create_test_function(5)];
+        let even = QualityAnalyzer::new()
+            .calculate_pmat_metrics(&functions)
+            .unwrap();
+        let config = QualityConfig {
+            pmat_weights: PmatWeights {
+                productivity: 0.0,
+                maintainability: 0.0,
+                accessibility: 1.0,
+                testability: 0.0,
+            },
+            ..QualityConfig::default()
+        };
+        let weighted = QualityAnalyzer::with_config(&config)
+            .calculate_pmat_metrics(&functions)
+            .unwrap();
+
+        assert!((weighted.tdg - even.accessibility_score / 50.0).abs() < 1e-9);
+        assert!(weighted.tdg != even.tdg);
+    }
+
+    #[test]
+    fn test_panic_budget() {
+        let config = QualityConfig {
+            allowed_panics: Some(1),
+            ..QualityConfig::default()
+        };
+        let analyzer = QualityAnalyzer::with_config(&config);
+        let rust_code = r#"
+pub fn first(values: &[i32]) -> i32 {
+    let value = values.first().unwrap();
+    if *value < 0 {
+        panic!("negative");
+    }
+    values.get(1).copied().unwrap_or(*value)
+}
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_first() {
+        assert_eq!(super::first(&[1]), 1);
+        None::<i32>.unwrap();
+    }
+}
+"#;
+
+        let mut report = analyzer.analyze_quality(&[]).unwrap();
+        assert!(report
+            .gates_passed
+            .contains(&config::PANIC_GATE.to_string()));
+        analyzer.check_panics(&mut report, rust_code);
+
+        assert_eq!(report.panic_sites, Some(2));
+        assert!(!report
+            .gates_passed
+            .contains(&config::PANIC_GATE.to_string()));
+        let failed = report.gates_failed.last().unwrap();
+        assert_eq!(failed.requirement, QualityRequirement::MaxPanics(1));
+        assert_eq!(failed.actual_value, "2");
+    }
+
+    #[test]
+    fn test_code_size_budget() {
+        let analyzer = QualityAnalyzer::new();
+        let rust_code = "pub fn double(x: i32) -> i32 {\n    x * 2\n}\n";
+        let within = CodeSizeBudget {
+            max_lines: Some(3),
+            max_items: Some(1),
+            ..CodeSizeBudget::default()
+        };
+        let mut report = analyzer.analyze_quality(&[]).unwrap();
+        analyzer
+            .check_code_size(&mut report, rust_code, &within)
+            .unwrap();
+        assert_eq!(report.code_size.as_ref().unwrap().lines, 3);
+        assert!(report
+            .gates_passed
+            .contains(&code_size::GATE_NAME.to_string()));
+
+        let mut report = analyzer.analyze_quality(&[]).unwrap();
+        let budget = CodeSizeBudget {
+            max_lines: Some(2),
+            ..within
+        };
+        analyzer
+            .check_code_size(&mut report, rust_code, &budget)
+            .unwrap();
+        let failed = report.gates_failed.last().unwrap();
+        assert_eq!(failed.gate_name, code_size::GATE_NAME);
+        assert_eq!(failed.requirement, QualityRequirement::MaxGeneratedLines(2));
+        assert_eq!(report.overall_status, QualityStatus::Failed);
+    }
+
     #[test]
     fn test_quality_gates_with_all_requirements() {
         let analyzer = QualityAnalyzer::new();
@@ -0,0 +1,217 @@
+//! Mutation testing of a transpiled crate
+//!
+//! Each mutant's generated code replaces the crate's source in turn and the
+//! crate's tests run against it. A test failure kills the mutant; a mutant
+//! that passes every test points at behaviour the generated tests never
+//! check. Mutants that do not compile are unviable and left out of the score.
+
+use crate::QualityError;
+use depyler_core::mutation::{Mutant, Mutation};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MutantOutcome {
+    /// A test failed
+    Killed,
+    /// Every test passed
+    Survived,
+    /// The mutant did not generate or compile
+    Unviable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionMutationScore {
+    pub function: String,
+    pub killed: usize,
+    pub survived: usize,
+    pub unviable: usize,
+    /// Killed share of the viable mutants; `None` when none compiled
+    pub mutation_score: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutationMetrics {
+    pub killed: usize,
+    pub survived: usize,
+    pub unviable: usize,
+    pub mutation_score: Option<f64>,
+    pub functions: Vec<FunctionMutationScore>,
+    /// Mutants no test detected
+    pub survivors: Vec<Mutation>,
+}
+
+/// Runs the tests of the crate in `crate_dir` against each mutant
+///
+/// `source` is the generated file inside the crate (usually `src/lib.rs`);
+/// `baseline` is the unmutated generated code, whose tests must pass. The
+/// file's original content is restored afterwards.
+pub fn run_mutants(
+    crate_dir: &Path,
+    source: &Path,
+    baseline: &str,
+    mutants: &[Mutant],
+) -> Result<MutationMetrics, QualityError> {
+    let path = crate_dir.join(source);
+    let original = fs::read_to_string(&path).map_err(|e| mutation_failed(e.to_string()))?;
+    let _restore = RestoreSource {
+        path: path.clone(),
+        original,
+    };
+
+    write_source(&path, baseline)?;
+    if run_tests(crate_dir)? != MutantOutcome::Survived {
+        return Err(mutation_failed(
+            "the tests do not pass on the unmutated code".to_string(),
+        ));
+    }
+
+    let mut results = Vec::with_capacity(mutants.len());
+    for mutant in mutants {
+        let outcome = match &mutant.code {
+            Some(code) => {
+                write_source(&path, code)?;
+                run_tests(crate_dir)?
+            }
+            None => MutantOutcome::Unviable,
+        };
+        results.push((&mutant.mutation, outcome));
+    }
+    Ok(MutationMetrics::from_results(&results))
+}
+
+impl MutationMetrics {
+    pub fn from_results(results: &[(&Mutation, MutantOutcome)]) -> Self {
+        let mut functions: Vec<FunctionMutationScore> = Vec::new();
+        for (mutation, outcome) in results {
+            let index = match functions
+                .iter()
+                .position(|f| f.function == mutation.function)
+            {
+                Some(index) => index,
+                None => {
+                    functions.push(FunctionMutationScore {
+                        function: mutation.function.clone(),
+                        killed: 0,
+                        survived: 0,
+                        unviable: 0,
+                        mutation_score: None,
+                    });
+                    functions.len() - 1
+                }
+            };
+            let function = &mut functions[index];
+            match outcome {
+                MutantOutcome::Killed => function.killed += 1,
+                MutantOutcome::Survived => function.survived += 1,
+                MutantOutcome::Unviable => function.unviable += 1,
+            }
+        }
+        for function in &mut functions {
+            function.mutation_score = score(function.killed, function.survived);
+        }
+
+        let killed = functions.iter().map(|f| f.killed).sum();
+        let survived = functions.iter().map(|f| f.survived).sum();
+        Self {
+            killed,
+            survived,
+            unviable: functions.iter().map(|f| f.unviable).sum(),
+            mutation_score: score(killed, survived),
+            functions,
+            survivors: results
+                .iter()
+                .filter(|(_, outcome)| *outcome == MutantOutcome::Survived)
+                .map(|(mutation, _)| (*mutation).clone())
+                .collect(),
+        }
+    }
+}
+
+fn score(killed: usize, survived: usize) -> Option<f64> {
+    let viable = killed + survived;
+    (viable > 0).then(|| killed as f64 / viable as f64)
+}
+
+/// Builds the tests, then runs them
+fn run_tests(crate_dir: &Path) -> Result<MutantOutcome, QualityError> {
+    if !cargo(crate_dir, &["test", "--no-run", "--quiet"])? {
+        return Ok(MutantOutcome::Unviable);
+    }
+    Ok(if cargo(crate_dir, &["test", "--quiet"])? {
+        MutantOutcome::Survived
+    } else {
+        MutantOutcome::Killed
+    })
+}
+
+fn cargo(crate_dir: &Path, args: &[&str]) -> Result<bool, QualityError> {
+    let output = Command::new("cargo")
+        .args(args)
+        .current_dir(crate_dir)
+        .output()
+        .map_err(|e| mutation_failed(e.to_string()))?;
+    Ok(output.status.success())
+}
+
+fn write_source(path: &Path, code: &str) -> Result<(), QualityError> {
+    fs::write(path, code).map_err(|e| mutation_failed(e.to_string()))
+}
+
+fn mutation_failed(reason: String) -> QualityError {
+    QualityError::MutationTestingFailed { reason }
+}
+
+/// Puts the crate's source back however mutation testing ends
+struct RestoreSource {
+    path: PathBuf,
+    original: String,
+}
+
+impl Drop for RestoreSource {
+    fn drop(&mut self) {
+        let _ = fs::write(&self.path, &self.original);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use depyler_core::mutation::MutationOperator;
+
+    fn mutation(function: &str, site: usize) -> Mutation {
+        Mutation {
+            function: function.to_string(),
+            site,
+            operator: MutationOperator::OperatorSwap,
+            description: "replace `+` with `-`".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_scores_per_function() {
+        let (a0, a1, a2, b0) = (
+            mutation("add", 0),
+            mutation("add", 1),
+            mutation("add", 2),
+            mutation("concat", 0),
+        );
+        let metrics = MutationMetrics::from_results(&[
+            (&a0, MutantOutcome::Killed),
+            (&a1, MutantOutcome::Survived),
+            (&a2, MutantOutcome::Unviable),
+            (&b0, MutantOutcome::Unviable),
+        ]);
+
+        assert_eq!(
+            (metrics.killed, metrics.survived, metrics.unviable),
+            (1, 1, 2)
+        );
+        assert_eq!(metrics.mutation_score, Some(0.5));
+        assert_eq!(metrics.functions[0].mutation_score, Some(0.5));
+        assert_eq!(metrics.functions[1].mutation_score, None);
+        assert_eq!(metrics.survivors, [a1]);
+    }
+}
@@ -0,0 +1,3 @@
+//! Reports of depyler's findings in formats other tools read
+
+pub mod sarif;
@@ -0,0 +1,463 @@
+//! SARIF 2.1.0 logs of quality, lint and verification findings
+//!
+//! GitHub code scanning and most static analysis dashboards ingest
+//! [SARIF](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html).
+//! A [`SarifBuilder`] collects findings into a single run of the `depyler`
+//! tool:
+//!
+//! | Finding                         | Rule id               | Level            |
+//! |---------------------------------|-----------------------|------------------|
+//! | failed quality gate             | `quality/<gate>`      | gate severity    |
+//! | lint diagnostic                 | `lint/<lint>`         | `warning`        |
+//! | violated verification property  | `verify/<property>`   | `error`          |
+//! | unknown verification property   | `verify/<property>`   | `note`           |
+//!
+//! Passed gates and proven properties are left out. Findings about a
+//! function point at the line defining it in the artifact they are added
+//! with; quality gates, which measure the whole file, point at the file.
+//!
+//! ```rust
+//! use depyler_quality::report::sarif::{Artifact, SarifBuilder};
+//! use depyler_verify::{PropertyStatus, VerificationMethod, VerificationResult};
+//!
+//! let python = "def divide(a, b):\n    return a / b\n";
+//! let results = [VerificationResult {
+//!     property: "panic_free".to_string(),
+//!     status: PropertyStatus::Violated("division by zero".to_string()),
+//!     confidence: 1.0,
+//!     method: VerificationMethod::StaticAnalysis,
+//!     counterexamples: vec![],
+//! }];
+//!
+//! let mut sarif = SarifBuilder::new();
+//! sarif.add_verification(&Artifact::new("src/calc.py", python), "divide", &results);
+//! let log = sarif.build();
+//! assert_eq!(log.runs[0].results[0].rule_id, "verify/panic_free");
+//! ```
+
+use crate::{QualityGateResult, QualityReport, Severity};
+use depyler_analyzer::lints::LintDiagnostic;
+use depyler_verify::{PropertyStatus, VerificationResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// A file findings are reported against
+#[derive(Debug, Clone, Copy)]
+pub struct Artifact<'a> {
+    /// Path as the consumer should show it, usually relative to the
+    /// repository root
+    pub uri: &'a str,
+    /// Contents, used to find the lines functions are defined on
+    pub text: &'a str,
+}
+
+impl<'a> Artifact<'a> {
+    pub fn new(uri: &'a str, text: &'a str) -> Self {
+        Self { uri, text }
+    }
+
+    /// 1-based line of the first line starting with `def name(` or
+    /// containing `fn name(`/`fn name<`
+    fn function_line(&self, name: &str) -> Option<usize> {
+        // Lint diagnostics name methods `Type::method`
+        let name = name.rsplit("::").next().unwrap_or(name);
+        let def = format!("def {}(", name);
+        let async_def = format!("async def {}(", name);
+        let fn_paren = format!("fn {}(", name);
+        let fn_generic = format!("fn {}<", name);
+        self.text
+            .lines()
+            .position(|line| {
+                let line = line.trim_start();
+                line.starts_with(&def)
+                    || line.starts_with(&async_def)
+                    || line.contains(&fn_paren)
+                    || line.contains(&fn_generic)
+            })
+            .map(|index| index + 1)
+    }
+}
+
+/// Collects findings into one SARIF run
+#[derive(Debug, Clone, Default)]
+pub struct SarifBuilder {
+    rules: BTreeMap<String, ReportingDescriptor>,
+    results: Vec<SarifResult>,
+}
+
+impl SarifBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A result for every failed gate of `report`, located at `artifact`
+    pub fn add_quality_report(&mut self, artifact: &Artifact<'_>, report: &QualityReport) {
+        for gate in &report.gates_failed {
+            let rule_id = format!("quality/{}", slug(&gate.gate_name));
+            self.add_rule(&rule_id, &format!("Quality gate '{}'", gate.gate_name));
+            self.results.push(SarifResult {
+                rule_id,
+                level: level(&gate.severity).to_string(),
+                message: Message::new(gate_message(gate)),
+                locations: vec![Location::file(artifact.uri)],
+            });
+        }
+    }
+
+    /// A result for every diagnostic, located at the function it was found
+    /// in within the generated code `artifact`
+    pub fn add_lints(&mut self, artifact: &Artifact<'_>, diagnostics: &[LintDiagnostic]) {
+        for diagnostic in diagnostics {
+            let rule_id = format!("lint/{}", diagnostic.lint);
+            self.add_rule(&rule_id, &format!("Lint '{}'", diagnostic.lint));
+            let message = format!(
+                "{} (replace `{}` with `{}`)",
+                diagnostic.message,
+                diagnostic.suggestion.original,
+                diagnostic.suggestion.replacement
+            );
+            self.results.push(SarifResult {
+                rule_id,
+                level: "warning".to_string(),
+                message: Message::new(message),
+                locations: vec![Location::function(artifact, &diagnostic.function)],
+            });
+        }
+    }
+
+    /// A result for every property of `function` that was violated or could
+    /// not be decided, located at the function in `artifact`
+    pub fn add_verification(
+        &mut self,
+        artifact: &Artifact<'_>,
+        function: &str,
+        results: &[VerificationResult],
+    ) {
+        for result in results {
+            let (level, message) = match &result.status {
+                PropertyStatus::Violated(reason) => (
+                    "error",
+                    format!(
+                        "Property '{}' is violated in '{}': {}",
+                        result.property, function, reason
+                    ),
+                ),
+                PropertyStatus::Unknown => (
+                    "note",
+                    format!(
+                        "Property '{}' could not be verified for '{}'",
+                        result.property, function
+                    ),
+                ),
+                PropertyStatus::Proven
+                | PropertyStatus::HighConfidence
+                | PropertyStatus::Likely => continue,
+            };
+            let rule_id = format!("verify/{}", result.property);
+            self.add_rule(&rule_id, &format!("Property '{}'", result.property));
+            self.results.push(SarifResult {
+                rule_id,
+                level: level.to_string(),
+                message: Message::new(message),
+                locations: vec![Location::function(artifact, function)],
+            });
+        }
+    }
+
+    /// The SARIF log of everything added so far
+    pub fn build(&self) -> SarifLog {
+        SarifLog {
+            schema: SARIF_SCHEMA.to_string(),
+            version: SARIF_VERSION.to_string(),
+            runs: vec![Run {
+                tool: Tool {
+                    driver: ToolComponent {
+                        name: "depyler".to_string(),
+                        version: env!("CARGO_PKG_VERSION").to_string(),
+                        information_uri: env!("CARGO_PKG_REPOSITORY").to_string(),
+                        rules: self.rules.values().cloned().collect(),
+                    },
+                },
+                results: self.results.clone(),
+            }],
+        }
+    }
+
+    /// [`build`](Self::build), as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.build())
+    }
+
+    fn add_rule(&mut self, id: &str, description: &str) {
+        self.rules
+            .entry(id.to_string())
+            .or_insert_with(|| ReportingDescriptor {
+                id: id.to_string(),
+                short_description: Message::new(description.to_string()),
+            });
+    }
+}
+
+fn level(severity: &Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+fn gate_message(gate: &QualityGateResult) -> String {
+    format!(
+        "Quality gate '{}' failed: {:?} not met (actual: {})",
+        gate.gate_name, gate.requirement, gate.actual_value
+    )
+}
+
+/// `PMAT TDG Range` as `pmat-tdg-range`
+fn slug(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+/// The top-level `sarifLog` object
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SarifLog {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    pub version: String,
+    pub runs: Vec<Run>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Run {
+    pub tool: Tool,
+    pub results: Vec<SarifResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tool {
+    pub driver: ToolComponent,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolComponent {
+    pub name: String,
+    pub version: String,
+    pub information_uri: String,
+    pub rules: Vec<ReportingDescriptor>,
+}
+
+/// A rule results refer to by id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportingDescriptor {
+    pub id: String,
+    pub short_description: Message,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SarifResult {
+    pub rule_id: String,
+    /// `error`, `warning` or `note`
+    pub level: String,
+    pub message: Message,
+    pub locations: Vec<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub text: String,
+}
+
+impl Message {
+    fn new(text: String) -> Self {
+        Self { text }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub physical_location: PhysicalLocation,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logical_locations: Vec<LogicalLocation>,
+}
+
+impl Location {
+    fn file(uri: &str) -> Self {
+        Self {
+            physical_location: PhysicalLocation {
+                artifact_location: ArtifactLocation {
+                    uri: uri.to_string(),
+                },
+                region: None,
+            },
+            logical_locations: Vec::new(),
+        }
+    }
+
+    /// The line defining `function`, or the whole file when it isn't found
+    fn function(artifact: &Artifact<'_>, function: &str) -> Self {
+        let mut location = Self::file(artifact.uri);
+        location.physical_location.region = artifact
+            .function_line(function)
+            .map(|start_line| Region { start_line });
+        location.logical_locations.push(LogicalLocation {
+            fully_qualified_name: function.to_string(),
+            kind: "function".to_string(),
+        });
+        location
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhysicalLocation {
+    pub artifact_location: ArtifactLocation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<Region>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArtifactLocation {
+    pub uri: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Region {
+    /// 1-based
+    pub start_line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogicalLocation {
+    pub fully_qualified_name: String,
+    pub kind: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::QualityAnalyzer;
+    use depyler_analyzer::lints::{Applicability, Suggestion};
+    use depyler_verify::VerificationMethod;
+
+    const PYTHON: &str = "import math\n\ndef area(r):\n    return math.pi * r * r\n";
+    const RUST: &str = "use std::f64;\n\npub fn area(r: f64) -> f64 {\n    r.clone() * r\n}\n";
+
+    fn verification(property: &str, status: PropertyStatus) -> VerificationResult {
+        VerificationResult {
+            property: property.to_string(),
+            status,
+            confidence: 1.0,
+            method: VerificationMethod::StaticAnalysis,
+            counterexamples: vec![],
+        }
+    }
+
+    #[test]
+    fn test_failed_gates_become_file_results() {
+        let mut report = QualityAnalyzer::new().analyze_quality(&[]).unwrap();
+        report.gates_failed.push(QualityGateResult {
+            gate_name: "Complexity Limits".to_string(),
+            requirement: crate::QualityRequirement::MaxComplexity(20),
+            actual_value: "31".to_string(),
+            passed: false,
+            severity: Severity::Warning,
+        });
+        let mut sarif = SarifBuilder::new();
+        sarif.add_quality_report(&Artifact::new("src/geometry.py", PYTHON), &report);
+        let log = sarif.build();
+
+        let result = log.runs[0]
+            .results
+            .iter()
+            .find(|r| r.rule_id == "quality/complexity-limits")
+            .unwrap();
+        assert_eq!(result.level, "warning");
+        assert!(result.message.text.contains("actual: 31"));
+        assert_eq!(result.locations[0].physical_location.region, None);
+    }
+
+    #[test]
+    fn test_lints_and_properties_point_at_their_function() {
+        let lint = LintDiagnostic {
+            lint: "unnecessary_clone".to_string(),
+            function: "area".to_string(),
+            message: "`r` is cloned but only read".to_string(),
+            suggestion: Suggestion {
+                original: "r.clone()".to_string(),
+                replacement: "r".to_string(),
+                applicability: Applicability::MachineApplicable,
+            },
+        };
+        let mut sarif = SarifBuilder::new();
+        sarif.add_lints(&Artifact::new("src/geometry.rs", RUST), &[lint]);
+        sarif.add_verification(
+            &Artifact::new("src/geometry.py", PYTHON),
+            "area",
+            &[
+                verification("type_preservation", PropertyStatus::Proven),
+                verification("null_safety", PropertyStatus::Violated("r".to_string())),
+                verification("termination", PropertyStatus::Unknown),
+            ],
+        );
+        let log = sarif.build();
+        let results = &log.runs[0].results;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].rule_id, "lint/unnecessary_clone");
+        assert_eq!(
+            results[0].locations[0].physical_location.region,
+            Some(Region { start_line: 3 })
+        );
+        assert_eq!(results[1].level, "error");
+        assert_eq!(
+            results[1].locations[0].physical_location.region,
+            Some(Region { start_line: 3 })
+        );
+        assert_eq!(results[2].level, "note");
+        assert_eq!(log.runs[0].tool.driver.rules.len(), 3);
+    }
+
+    #[test]
+    fn test_json_uses_sarif_names() {
+        let mut sarif = SarifBuilder::new();
+        sarif.add_verification(
+            &Artifact::new("src/geometry.py", PYTHON),
+            "missing",
+            &[verification(
+                "panic_free",
+                PropertyStatus::Violated("x".to_string()),
+            )],
+        );
+        let json: serde_json::Value = serde_json::from_str(&sarif.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["version"], "2.1.0");
+        assert_eq!(json["$schema"], SARIF_SCHEMA);
+        let result = &json["runs"][0]["results"][0];
+        assert_eq!(result["ruleId"], "verify/panic_free");
+        assert_eq!(
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"],
+            "src/geometry.py"
+        );
+        // An undefined function leaves the region out
+        assert!(result["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+        assert_eq!(
+            json["runs"][0]["tool"]["driver"]["rules"][0]["shortDescription"]["text"],
+            "Property 'panic_free'"
+        );
+    }
+}
@@ -752,6 +752,11 @@ mod tests {
             error_types: vec![],
             is_async: false,
             is_generator: false,
+            is_context_manager: false,
+            is_async_entry: false,
+            memoize: None,
+            mutated_params: None,
+            complexity: None,
         };
 
         let func = create_test_function("safe_function", vec![], Type::Int, vec![], properties);
@@ -776,6 +781,11 @@ mod tests {
             error_types: vec![],
             is_async: false,
             is_generator: false,
+            is_context_manager: false,
+            is_async_entry: false,
+            memoize: None,
+            mutated_params: None,
+            complexity: None,
         };
 
         let func = create_test_function(
@@ -806,6 +816,11 @@ mod tests {
             error_types: vec![],
             is_async: false,
             is_generator: false,
+            is_context_manager: false,
+            is_async_entry: false,
+            memoize: None,
+            mutated_params: None,
+            complexity: None,
         };
 
         let func = create_test_function(
@@ -1113,6 +1128,11 @@ mod tests {
                 error_types: vec![],
                 is_async: false,
                 is_generator: false,
+                is_context_manager: false,
+                is_async_entry: false,
+                memoize: None,
+                mutated_params: None,
+                complexity: None,
             },
         );
 
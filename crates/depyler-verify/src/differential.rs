@@ -0,0 +1,903 @@
+//! Differential testing against CPython
+//!
+//! The Python function and its transpiled Rust are both run on the same
+//! generated inputs and their results compared. The Python side runs in a
+//! `python3` subprocess importing the original source; the Rust side is the
+//! transpiled module compiled by rustc into a binary whose `main` calls the
+//! function on every input. Both print one outcome per input, as JSON:
+//!
+//! ```text
+//! {"value": [1, 2.5, "a"]}
+//! {"error": "ZeroDivisionError"}
+//! ```
+//!
+//! Tuples are written as arrays, sets as `{"set": [...]}` and dicts as
+//! `{"dict": [[key, value], ...]}`, both compared without regard to order;
+//! NaN and infinities as the strings `"nan"`, `"inf"` and `"-inf"`. Numbers
+//! compare by value, so an `int` result matches an `f64` one, and floats
+//! within a relative `1e-9`.
+//!
+//! A Python exception and a Rust `Err` or panic count as agreeing whatever
+//! their types, since Rust errors carry no Python type name. Every other
+//! difference is a divergence, reported as a counterexample of a violated
+//! `python_equivalence` property.
+//!
+//! Only functions whose parameters and return type are built from `int`,
+//! `float`, `bool`, `str`, `None`, lists, sets, dicts, tuples and
+//! `Optional` are tested; others are skipped.
+
+use crate::rustc::scratch_dir;
+use crate::{proptest_gen, PropertyStatus, TestCase, VerificationMethod, VerificationResult};
+use anyhow::{bail, Context, Result};
+use depyler_core::hir::{HirFunction, Type};
+use depyler_core::DepylerPipeline;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Marks the lines holding outcomes, so output the function prints itself
+/// is skipped
+const OUTCOME_PREFIX: &str = "@@depyler-outcome@@ ";
+
+/// Python module the subject source is written to
+const SUBJECT_MODULE: &str = "depyler_subject";
+
+/// Longest generated string, list, set or dict
+const MAX_LEN: u64 = 5;
+
+/// Runs Python functions and their transpiled Rust side by side
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DifferentialTester {
+    /// The CPython interpreter
+    pub python: PathBuf,
+    pub rustc: PathBuf,
+    pub edition: String,
+    /// Inputs per function, the first of them the "empty" value of every
+    /// parameter type
+    pub cases: usize,
+    /// Seed of the input generator; the same seed gives the same inputs
+    pub seed: u64,
+    /// Longest either program may run before the test fails
+    pub timeout_secs: u64,
+}
+
+/// What one call did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+    /// The function returned, encoded as described in the module docs
+    Value(Value),
+    /// The function raised, returned `Err` or panicked
+    Error(String),
+}
+
+impl Default for DifferentialTester {
+    fn default() -> Self {
+        Self {
+            python: PathBuf::from("python3"),
+            rustc: PathBuf::from("rustc"),
+            edition: "2021".to_string(),
+            cases: 100,
+            seed: 0x5eed,
+            timeout_secs: 30,
+        }
+    }
+}
+
+impl DifferentialTester {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_python(mut self, python: impl Into<PathBuf>) -> Self {
+        self.python = python.into();
+        self
+    }
+
+    pub fn with_rustc(mut self, rustc: impl Into<PathBuf>) -> Self {
+        self.rustc = rustc.into();
+        self
+    }
+
+    pub fn with_cases(mut self, cases: usize) -> Self {
+        self.cases = cases;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Transpiles `python_source` and tests every function it can, by name
+    pub fn verify_python(
+        &self,
+        pipeline: &DepylerPipeline,
+        python_source: &str,
+    ) -> Result<Vec<(String, VerificationResult)>> {
+        let module = pipeline.parse_to_hir(python_source)?;
+        let rust_code = pipeline.transpile(python_source)?;
+        let mut results = Vec::new();
+        for func in &module.functions {
+            if let Some(result) = self.verify_function(python_source, func, &rust_code)? {
+                results.push((func.name.clone(), result));
+            }
+        }
+        Ok(results)
+    }
+
+    /// Compares `func` from `python_source` with its transpilation in
+    /// `rust_code`, or `None` when its types are not supported
+    pub fn verify_function(
+        &self,
+        python_source: &str,
+        func: &HirFunction,
+        rust_code: &str,
+    ) -> Result<Option<VerificationResult>> {
+        if !is_testable(func) {
+            return Ok(None);
+        }
+        let inputs = generate_inputs(func, self.cases, self.seed);
+        let python = self.run_python(python_source, func, &inputs)?;
+        let rust = self.run_rust(rust_code, func, &inputs)?;
+        Ok(Some(compare_outcomes(&inputs, &python, &rust)))
+    }
+
+    /// The outcome of calling the Python `func` on each of `inputs`
+    pub fn run_python(
+        &self,
+        python_source: &str,
+        func: &HirFunction,
+        inputs: &[Vec<Value>],
+    ) -> Result<Vec<Outcome>> {
+        let dir = scratch_dir("differential")?;
+        let result = (|| -> Result<Vec<Outcome>> {
+            std::fs::write(dir.join(format!("{}.py", SUBJECT_MODULE)), python_source)?;
+            let driver = dir.join("driver.py");
+            std::fs::write(&driver, python_driver(func, inputs)?)?;
+            let mut command = Command::new(&self.python);
+            command.arg(&driver).current_dir(&dir);
+            let stdout = self.run(command, &dir, "python")?;
+            Ok(parse_outcomes(&stdout, inputs.len()))
+        })();
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// The outcome of calling the transpiled `func` on each of `inputs`
+    pub fn run_rust(
+        &self,
+        rust_code: &str,
+        func: &HirFunction,
+        inputs: &[Vec<Value>],
+    ) -> Result<Vec<Outcome>> {
+        let dir = scratch_dir("differential")?;
+        let result = (|| -> Result<Vec<Outcome>> {
+            let source = dir.join("driver.rs");
+            let binary = dir.join("driver");
+            std::fs::write(&source, rust_driver(rust_code, func, inputs)?)?;
+            let output = Command::new(&self.rustc)
+                .args(["--edition", &self.edition, "--cap-lints", "allow", "-o"])
+                .arg(&binary)
+                .arg(&source)
+                .output()
+                .with_context(|| format!("failed to run {}", self.rustc.display()))?;
+            if !output.status.success() {
+                bail!(
+                    "transpiled `{}` does not compile:\n{}",
+                    func.name,
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            let stdout = self.run(Command::new(&binary), &dir, "rust")?;
+            Ok(parse_outcomes(&stdout, inputs.len()))
+        })();
+        let _ = std::fs::remove_dir_all(&dir);
+        result
+    }
+
+    /// The standard output of `command`, killed after the timeout
+    ///
+    /// Output goes to a file rather than a pipe so a chatty program cannot
+    /// block on a full pipe while it is being timed.
+    fn run(&self, mut command: Command, dir: &Path, name: &str) -> Result<String> {
+        let stdout_path = dir.join(format!("{}.out", name));
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(std::fs::File::create(&stdout_path)?)
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| format!("failed to run the {} side", name))?;
+        let deadline = Instant::now() + Duration::from_secs(self.timeout_secs);
+        while child.try_wait()?.is_none() {
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                bail!("the {} side ran over {}s", name, self.timeout_secs);
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        Ok(std::fs::read_to_string(&stdout_path)?)
+    }
+}
+
+/// Whether `func` takes and returns only values the harness can generate
+/// and compare
+pub fn is_testable(func: &HirFunction) -> bool {
+    !func.properties.is_async
+        && !func.properties.is_generator
+        && func.params.iter().all(|param| can_generate(&param.ty))
+        && can_compare(&func.ret_type)
+}
+
+fn can_generate(ty: &Type) -> bool {
+    match ty {
+        Type::Int | Type::Float | Type::Bool | Type::String => true,
+        Type::List(inner) | Type::Set(inner) | Type::Optional(inner) => can_generate(inner),
+        Type::Dict(key, value) => {
+            matches!(key.as_ref(), Type::Int | Type::String) && can_generate(value)
+        }
+        Type::Tuple(items) => !items.is_empty() && items.iter().all(can_generate),
+        _ => false,
+    }
+}
+
+fn can_compare(ty: &Type) -> bool {
+    match ty {
+        Type::None => true,
+        Type::List(inner) | Type::Set(inner) | Type::Optional(inner) => can_compare(inner),
+        Type::Dict(key, value) => can_compare(key) && can_compare(value),
+        Type::Tuple(items) => items.iter().all(can_compare),
+        ty => can_generate(ty),
+    }
+}
+
+/// `cases` argument lists for `func`, the first of them the "empty" value
+/// of each parameter type
+pub fn generate_inputs(func: &HirFunction, cases: usize, seed: u64) -> Vec<Vec<Value>> {
+    let mut rng = SplitMix64(seed);
+    (0..cases)
+        .map(|case| {
+            func.params
+                .iter()
+                .map(|param| {
+                    if case == 0 {
+                        empty_value(&param.ty)
+                    } else {
+                        random_value(&param.ty, &mut rng)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The smallest value of `ty`: zero, `""`, an empty collection
+fn empty_value(ty: &Type) -> Value {
+    match ty {
+        Type::Int => Value::from(0),
+        Type::Float => Value::from(0.0),
+        Type::Bool => Value::Bool(false),
+        Type::String => Value::from(""),
+        Type::Dict(_, _) => Value::Object(Default::default()),
+        Type::Tuple(items) => Value::Array(items.iter().map(empty_value).collect()),
+        Type::Optional(_) => Value::Null,
+        _ => Value::Array(vec![]),
+    }
+}
+
+fn random_value(ty: &Type, rng: &mut SplitMix64) -> Value {
+    const ALPHABET: [char; 12] = ['a', 'b', 'c', 'X', 'Y', ' ', '0', '1', '9', '_', '-', 'é'];
+    match ty {
+        Type::Int => Value::from(rng.below(201) as i64 - 100),
+        // Eighths print exactly in both languages
+        Type::Float => Value::from((rng.below(1601) as i64 - 800) as f64 / 8.0),
+        Type::Bool => Value::Bool(rng.below(2) == 1),
+        Type::String => {
+            let len = rng.below(MAX_LEN + 4);
+            let text: String = (0..len)
+                .map(|_| ALPHABET[rng.below(ALPHABET.len() as u64) as usize])
+                .collect();
+            Value::from(text)
+        }
+        Type::List(inner) | Type::Set(inner) => {
+            let len = rng.below(MAX_LEN + 1);
+            Value::Array((0..len).map(|_| random_value(inner, rng)).collect())
+        }
+        Type::Dict(key, value) => {
+            let len = rng.below(MAX_LEN);
+            let entries = (0..len)
+                .map(|_| {
+                    // JSON object keys are always strings
+                    let key = match random_value(key, rng) {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    (key, random_value(value, rng))
+                })
+                .collect();
+            Value::Object(entries)
+        }
+        Type::Tuple(items) => {
+            Value::Array(items.iter().map(|item| random_value(item, rng)).collect())
+        }
+        Type::Optional(inner) => {
+            if rng.below(4) == 0 {
+                Value::Null
+            } else {
+                random_value(inner, rng)
+            }
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Deterministic generator, so a divergence reproduces from its seed
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+/// A Python expression of type `ty` for the JSON of a value
+pub fn python_literal(ty: &Type, value: &Value) -> Option<String> {
+    let literal = match (ty, value) {
+        (Type::Optional(_), Value::Null) => "None".to_string(),
+        (Type::Optional(inner), value) => python_literal(inner, value)?,
+        (Type::Int, Value::Number(n)) => n.as_i64()?.to_string(),
+        (Type::Float, Value::Number(n)) => format!("{:?}", n.as_f64()?),
+        (Type::Bool, Value::Bool(b)) => (if *b { "True" } else { "False" }).to_string(),
+        // A JSON string is also a valid Python string literal
+        (Type::String, Value::String(_)) => value.to_string(),
+        (Type::List(inner), Value::Array(items)) => {
+            format!("[{}]", python_items(inner, items)?.join(", "))
+        }
+        (Type::Set(_), Value::Array(items)) if items.is_empty() => "set()".to_string(),
+        (Type::Set(inner), Value::Array(items)) => {
+            format!("{{{}}}", python_items(inner, items)?.join(", "))
+        }
+        (Type::Tuple(types), Value::Array(items)) if types.len() == items.len() => {
+            let items = types
+                .iter()
+                .zip(items)
+                .map(|(ty, item)| python_literal(ty, item))
+                .collect::<Option<Vec<_>>>()?;
+            format!("({},)", items.join(", "))
+        }
+        (Type::Dict(key_type, value_type), Value::Object(entries)) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match key_type.as_ref() {
+                        Type::String => Value::String(key.clone()),
+                        Type::Int => Value::from(key.parse::<i64>().ok()?),
+                        _ => return None,
+                    };
+                    Some(format!(
+                        "{}: {}",
+                        python_literal(key_type, &key)?,
+                        python_literal(value_type, value)?
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format!("{{{}}}", entries.join(", "))
+        }
+        _ => return None,
+    };
+    Some(literal)
+}
+
+fn python_items(ty: &Type, items: &[Value]) -> Option<Vec<String>> {
+    items.iter().map(|item| python_literal(ty, item)).collect()
+}
+
+/// The Python script printing the outcome of `func` on each input
+fn python_driver(func: &HirFunction, inputs: &[Vec<Value>]) -> Result<String> {
+    let mut script = format!(
+        "import json\nimport math\n\nimport {} as subject\n\n{}\nCASES = [\n",
+        SUBJECT_MODULE, PYTHON_ENCODE
+    );
+    for input in inputs {
+        let args = func
+            .params
+            .iter()
+            .zip(input)
+            .map(|(param, value)| python_literal(&param.ty, value))
+            .collect::<Option<Vec<_>>>()
+            .with_context(|| format!("no Python literal for an input of `{}`", func.name))?;
+        script.push_str(&format!(
+            "    lambda: subject.{}({}),\n",
+            func.name,
+            args.join(", ")
+        ));
+    }
+    script.push_str(&format!(
+        concat!(
+            "]\n\n",
+            "for case in CASES:\n",
+            "    try:\n",
+            "        outcome = {{\"value\": encode(case())}}\n",
+            "    except Exception as error:\n",
+            "        outcome = {{\"error\": type(error).__name__}}\n",
+            "    print({:?} + json.dumps(outcome), flush=True)\n",
+        ),
+        OUTCOME_PREFIX
+    ));
+    Ok(script)
+}
+
+const PYTHON_ENCODE: &str = r#"def encode(value):
+    if isinstance(value, float):
+        if math.isnan(value):
+            return "nan"
+        if math.isinf(value):
+            return "inf" if value > 0 else "-inf"
+        return value
+    if isinstance(value, (list, tuple)):
+        return [encode(item) for item in value]
+    if isinstance(value, (set, frozenset)):
+        return {"set": [encode(item) for item in value]}
+    if isinstance(value, dict):
+        return {"dict": [[encode(k), encode(v)] for k, v in value.items()]}
+    if value is None or isinstance(value, (bool, int, str)):
+        return value
+    return repr(value)
+"#;
+
+/// The Rust program printing the outcome of the transpiled `func` on each
+/// input; `rust_code` is compiled as a module so its own `main`, if any,
+/// does not clash
+fn rust_driver(rust_code: &str, func: &HirFunction, inputs: &[Vec<Value>]) -> Result<String> {
+    let report = if func.properties.can_fail {
+        "__depyler_report_result"
+    } else {
+        "__depyler_report"
+    };
+    let mut calls = String::new();
+    for input in inputs {
+        let args = func
+            .params
+            .iter()
+            .zip(input)
+            .map(|(param, value)| proptest_gen::literal_argument(&param.ty, value))
+            .collect::<Option<Vec<_>>>()
+            .with_context(|| format!("no Rust literal for an input of `{}`", func.name))?;
+        calls.push_str(&format!(
+            "    {}(std::panic::catch_unwind(|| generated::{}({})));\n",
+            report,
+            func.name,
+            args.join(", ")
+        ));
+    }
+    Ok(format!(
+        "#[allow(dead_code, unused)]\nmod generated {{\n{}\n}}\n\n{}\nfn main() {{\n    std::panic::set_hook(Box::new(|_| {{}}));\n{}}}\n",
+        rust_code,
+        RUST_ENCODE.replace("@PREFIX@", OUTCOME_PREFIX),
+        calls
+    ))
+}
+
+const RUST_ENCODE: &str = r#"trait __DepylerJson {
+    fn json(&self) -> String;
+}
+
+macro_rules! __depyler_json_number {
+    ($($ty:ty),*) => {$(
+        impl __DepylerJson for $ty {
+            fn json(&self) -> String {
+                self.to_string()
+            }
+        }
+    )*};
+}
+__depyler_json_number!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+impl __DepylerJson for f64 {
+    fn json(&self) -> String {
+        if self.is_nan() {
+            "\"nan\"".to_string()
+        } else if self.is_infinite() {
+            (if *self > 0.0 { "\"inf\"" } else { "\"-inf\"" }).to_string()
+        } else {
+            format!("{:?}", self)
+        }
+    }
+}
+
+impl __DepylerJson for f32 {
+    fn json(&self) -> String {
+        f64::from(*self).json()
+    }
+}
+
+impl __DepylerJson for bool {
+    fn json(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl __DepylerJson for () {
+    fn json(&self) -> String {
+        "null".to_string()
+    }
+}
+
+impl __DepylerJson for str {
+    fn json(&self) -> String {
+        __depyler_json_string(self)
+    }
+}
+
+impl __DepylerJson for String {
+    fn json(&self) -> String {
+        __depyler_json_string(self)
+    }
+}
+
+impl __DepylerJson for std::borrow::Cow<'_, str> {
+    fn json(&self) -> String {
+        __depyler_json_string(self)
+    }
+}
+
+impl<T: __DepylerJson + ?Sized> __DepylerJson for &T {
+    fn json(&self) -> String {
+        (**self).json()
+    }
+}
+
+impl<T: __DepylerJson + ?Sized> __DepylerJson for Box<T> {
+    fn json(&self) -> String {
+        (**self).json()
+    }
+}
+
+impl<T: __DepylerJson> __DepylerJson for Option<T> {
+    fn json(&self) -> String {
+        match self {
+            Some(value) => value.json(),
+            None => "null".to_string(),
+        }
+    }
+}
+
+impl<T: __DepylerJson> __DepylerJson for [T] {
+    fn json(&self) -> String {
+        let items: Vec<String> = self.iter().map(|item| item.json()).collect();
+        format!("[{}]", items.join(","))
+    }
+}
+
+impl<T: __DepylerJson> __DepylerJson for Vec<T> {
+    fn json(&self) -> String {
+        self.as_slice().json()
+    }
+}
+
+impl<T: __DepylerJson, S> __DepylerJson for std::collections::HashSet<T, S> {
+    fn json(&self) -> String {
+        let items: Vec<String> = self.iter().map(|item| item.json()).collect();
+        format!("{{\"set\":[{}]}}", items.join(","))
+    }
+}
+
+impl<K: __DepylerJson, V: __DepylerJson, S> __DepylerJson for std::collections::HashMap<K, V, S> {
+    fn json(&self) -> String {
+        let entries: Vec<String> = self
+            .iter()
+            .map(|(key, value)| format!("[{},{}]", key.json(), value.json()))
+            .collect();
+        format!("{{\"dict\":[{}]}}", entries.join(","))
+    }
+}
+
+macro_rules! __depyler_json_tuple {
+    ($(($($name:ident),+)),*) => {$(
+        impl<$($name: __DepylerJson),+> __DepylerJson for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn json(&self) -> String {
+                let ($($name,)+) = self;
+                let items: Vec<String> = vec![$($name.json()),+];
+                format!("[{}]", items.join(","))
+            }
+        }
+    )*};
+}
+__depyler_json_tuple!((A), (A, B), (A, B, C), (A, B, C, D), (A, B, C, D, E));
+
+fn __depyler_json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn __depyler_panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    let message = match panic.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => panic.downcast_ref::<String>().cloned().unwrap_or_default(),
+    };
+    format!("panic: {}", message)
+}
+
+fn __depyler_print(outcome: &str, json: String) {
+    println!("@PREFIX@{{\"{}\":{}}}", outcome, json);
+}
+
+fn __depyler_report<T: __DepylerJson>(outcome: std::thread::Result<T>) {
+    match outcome {
+        Ok(value) => __depyler_print("value", value.json()),
+        Err(panic) => __depyler_print("error", __depyler_json_string(&__depyler_panic_message(panic))),
+    }
+}
+
+fn __depyler_report_result<T: __DepylerJson, E: std::fmt::Display>(
+    outcome: std::thread::Result<Result<T, E>>,
+) {
+    match outcome {
+        Ok(Ok(value)) => __depyler_print("value", value.json()),
+        Ok(Err(error)) => __depyler_print("error", __depyler_json_string(&error.to_string())),
+        Err(panic) => __depyler_print("error", __depyler_json_string(&__depyler_panic_message(panic))),
+    }
+}
+"#;
+
+/// The outcomes in a program's output, padded with errors when it stopped
+/// before reporting them all
+fn parse_outcomes(stdout: &str, expected: usize) -> Vec<Outcome> {
+    let mut outcomes: Vec<Outcome> = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix(OUTCOME_PREFIX))
+        .map(|json| {
+            serde_json::from_str(json)
+                .unwrap_or_else(|e| Outcome::Error(format!("unreadable outcome: {}", e)))
+        })
+        .collect();
+    outcomes.resize(
+        expected.max(outcomes.len()),
+        Outcome::Error("the program exited before this input".to_string()),
+    );
+    outcomes
+}
+
+/// The `python_equivalence` property from the outcomes of both sides
+pub fn compare_outcomes(
+    inputs: &[Vec<Value>],
+    python: &[Outcome],
+    rust: &[Outcome],
+) -> VerificationResult {
+    let counterexamples: Vec<TestCase> = inputs
+        .iter()
+        .zip(python.iter().zip(rust))
+        .filter(|(_, (python, rust))| !outcomes_agree(python, rust))
+        .map(|(input, (python, rust))| divergence(input, python, rust))
+        .collect();
+    let status = if counterexamples.is_empty() {
+        PropertyStatus::HighConfidence
+    } else {
+        PropertyStatus::Violated(format!(
+            "{} of {} inputs give a different result than Python",
+            counterexamples.len(),
+            inputs.len()
+        ))
+    };
+    VerificationResult {
+        property: "python_equivalence".into(),
+        status,
+        confidence: if inputs.is_empty() { 0.0 } else { 1.0 },
+        method: VerificationMethod::Differential,
+        counterexamples,
+    }
+}
+
+fn divergence(input: &[Value], python: &Outcome, rust: &Outcome) -> TestCase {
+    let value = |outcome: &Outcome| match outcome {
+        Outcome::Value(value) => Some(value.clone()),
+        Outcome::Error(_) => None,
+    };
+    let error = match (python, rust) {
+        (Outcome::Error(raised), _) => Some(format!("Python raised {}, Rust did not fail", raised)),
+        (_, Outcome::Error(error)) => Some(format!("Rust failed ({}), Python did not", error)),
+        _ => None,
+    };
+    TestCase {
+        inputs: input.to_vec(),
+        expected_output: value(python),
+        actual_output: value(rust),
+        error,
+    }
+}
+
+/// Whether both sides failed, or returned equal values
+pub fn outcomes_agree(python: &Outcome, rust: &Outcome) -> bool {
+    match (python, rust) {
+        (Outcome::Error(_), Outcome::Error(_)) => true,
+        (Outcome::Value(python), Outcome::Value(rust)) => values_agree(python, rust),
+        _ => false,
+    }
+}
+
+fn values_agree(python: &Value, rust: &Value) -> bool {
+    match (python, rust) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_i64(), b.as_i64()) {
+            (Some(a), Some(b)) => a == b,
+            _ => match (a.as_f64(), b.as_f64()) {
+                (Some(a), Some(b)) => (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0),
+                _ => false,
+            },
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| values_agree(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => match (unordered_items(a), unordered_items(b)) {
+            (Some(a), Some(b)) => same_items(a, b),
+            _ => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(key, a)| b.get(key).is_some_and(|b| values_agree(a, b)))
+            }
+        },
+        (a, b) => a == b,
+    }
+}
+
+/// The items of an encoded set or dict
+fn unordered_items(object: &serde_json::Map<String, Value>) -> Option<&Vec<Value>> {
+    if object.len() != 1 {
+        return None;
+    }
+    match object.get("set").or_else(|| object.get("dict")) {
+        Some(Value::Array(items)) => Some(items),
+        _ => None,
+    }
+}
+
+/// Whether `a` and `b` hold agreeing items in any order
+fn same_items(a: &[Value], b: &[Value]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut unmatched: Vec<&Value> = b.iter().collect();
+    for item in a {
+        match unmatched.iter().position(|other| values_agree(item, other)) {
+            Some(at) => {
+                unmatched.swap_remove(at);
+            }
+            None => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use depyler_core::hir_builder::HirFunctionBuilder;
+    use serde_json::json;
+
+    #[test]
+    fn test_inputs_are_reproducible_and_start_empty() {
+        let func = HirFunctionBuilder::new("f")
+            .param("xs", Type::List(Box::new(Type::Int)))
+            .param("s", Type::String)
+            .returns(Type::Int)
+            .build();
+        let inputs = generate_inputs(&func, 10, 7);
+
+        assert_eq!(inputs, generate_inputs(&func, 10, 7));
+        assert_eq!(inputs[0], vec![json!([]), json!("")]);
+        assert!(inputs.iter().all(|input| input.len() == 2));
+    }
+
+    #[test]
+    fn test_python_literals() {
+        let pair = Type::Tuple(vec![Type::Int, Type::Bool]);
+        assert_eq!(
+            python_literal(&pair, &json!([1, true])).unwrap(),
+            "(1, True,)"
+        );
+        let set = Type::Set(Box::new(Type::Int));
+        assert_eq!(python_literal(&set, &json!([])).unwrap(), "set()");
+        let dict = Type::Dict(Box::new(Type::Int), Box::new(Type::String));
+        assert_eq!(
+            python_literal(&dict, &json!({"2": "a\"b"})).unwrap(),
+            "{2: \"a\\\"b\"}"
+        );
+        let maybe = Type::Optional(Box::new(Type::Float));
+        assert_eq!(python_literal(&maybe, &json!(null)).unwrap(), "None");
+        assert_eq!(python_literal(&maybe, &json!(1.5)).unwrap(), "1.5");
+    }
+
+    #[test]
+    fn test_values_compare_like_python() {
+        let agree = |a: Value, b: Value| outcomes_agree(&Outcome::Value(a), &Outcome::Value(b));
+
+        assert!(agree(json!(2), json!(2.0)));
+        assert!(agree(json!(0.1), json!(0.1 + 1e-12)));
+        assert!(!agree(json!(1), json!(2)));
+        assert!(agree(json!({"set": [1, 2]}), json!({"set": [2, 1]})));
+        assert!(agree(
+            json!({"dict": [["a", 1], ["b", 2]]}),
+            json!({"dict": [["b", 2], ["a", 1]]})
+        ));
+        assert!(!agree(json!([1, 2]), json!([2, 1])));
+        assert!(outcomes_agree(
+            &Outcome::Error("ValueError".into()),
+            &Outcome::Error("value error: bad".into())
+        ));
+        assert!(!outcomes_agree(
+            &Outcome::Error("KeyError".into()),
+            &Outcome::Value(json!(0))
+        ));
+    }
+
+    #[test]
+    fn test_divergences_become_counterexamples() {
+        let inputs = vec![vec![json!(1)], vec![json!(2)]];
+        let python = vec![
+            Outcome::Value(json!(1)),
+            Outcome::Error("ZeroDivisionError".into()),
+        ];
+        let rust = vec![Outcome::Value(json!(1)), Outcome::Value(json!(0))];
+
+        let result = compare_outcomes(&inputs, &python, &rust);
+        assert!(matches!(result.status, PropertyStatus::Violated(_)));
+        assert_eq!(result.counterexamples.len(), 1);
+        let case = &result.counterexamples[0];
+        assert_eq!(case.inputs, vec![json!(2)]);
+        assert_eq!(case.actual_output, Some(json!(0)));
+        assert!(case.error.as_deref().unwrap().contains("ZeroDivisionError"));
+    }
+
+    #[test]
+    fn test_transpiled_function_matches_python() {
+        if Command::new("python3").arg("--version").output().is_err() {
+            return;
+        }
+        let source = "def total(xs: list[int], scale: int) -> int:\n    return sum(xs) * scale\n";
+        let results = DifferentialTester::new()
+            .with_cases(20)
+            .verify_python(&DepylerPipeline::new(), source)
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        let (name, result) = &results[0];
+        assert_eq!(name, "total");
+        assert!(
+            matches!(result.status, PropertyStatus::HighConfidence),
+            "{:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_missing_outcomes_are_errors() {
+        let stdout = format!(
+            "printed by the function\n{}{{\"value\":3}}\n",
+            OUTCOME_PREFIX
+        );
+        let outcomes = parse_outcomes(&stdout, 2);
+
+        assert_eq!(outcomes[0], Outcome::Value(json!(3)));
+        assert!(matches!(outcomes[1], Outcome::Error(_)));
+    }
+}
@@ -1,13 +1,19 @@
 pub mod contract_verification;
 pub mod contracts;
+pub mod differential;
 pub mod lifetime_analysis;
 pub mod memory_safety;
+pub mod panic_freedom;
 pub mod properties;
+pub mod proptest_gen;
 pub mod quickcheck;
+pub mod rustc;
+pub mod safety_audit;
 
 use anyhow::Result;
 use depyler_core::hir::HirFunction;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PropertyVerifier {
@@ -41,6 +47,8 @@ pub enum VerificationMethod {
     StaticAnalysis,
     StructuralInduction,
     Heuristic,
+    /// Compared with the Python original on the same inputs
+    Differential,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -131,15 +139,27 @@ impl PropertyVerifier {
         }
 
         // Property 4: Panic freedom
-        if func.properties.panic_free {
-            results.push(VerificationResult {
-                property: "panic_free".into(),
-                status: PropertyStatus::HighConfidence,
-                confidence: 0.95,
-                method: VerificationMethod::StaticAnalysis,
-                counterexamples: vec![],
-            });
-        }
+        let panic_sites = panic_freedom::panic_sites(func);
+        let (status, confidence) = if panic_sites.is_empty() {
+            (PropertyStatus::Proven, 1.0)
+        } else {
+            (PropertyStatus::Unknown, 0.0)
+        };
+        results.push(VerificationResult {
+            property: "panic_free".into(),
+            status,
+            confidence,
+            method: VerificationMethod::StaticAnalysis,
+            counterexamples: panic_sites
+                .iter()
+                .map(|site| TestCase {
+                    inputs: vec![],
+                    expected_output: None,
+                    actual_output: None,
+                    error: Some(site.to_string()),
+                })
+                .collect(),
+        });
 
         // Property 5: Termination
         if func.properties.always_terminates {
@@ -233,6 +253,33 @@ impl PropertyVerifier {
         let test_code = properties::generate_quickcheck_tests(func, self.test_iterations)?;
         Ok(test_code)
     }
+
+    /// proptest tests of `func`: generated arguments must not make it panic,
+    /// and the `recorded` Python calls must return the same values
+    pub fn generate_proptests(&self, func: &HirFunction, recorded: &[TestCase]) -> Option<String> {
+        proptest_gen::generate_proptests(func, self.test_iterations, recorded)
+    }
+
+    /// `rust_code` with the proptest tests of `functions` in its tests
+    /// module; `recorded` holds Python calls by function name
+    pub fn write_proptests(
+        &self,
+        rust_code: &str,
+        functions: &[HirFunction],
+        recorded: &HashMap<String, Vec<TestCase>>,
+    ) -> String {
+        let items: String = functions
+            .iter()
+            .filter_map(|func| {
+                let cases = recorded.get(&func.name).map(Vec::as_slice).unwrap_or(&[]);
+                self.generate_proptests(func, cases)
+            })
+            .collect();
+        if items.is_empty() {
+            return rust_code.to_string();
+        }
+        proptest_gen::insert_into_tests_module(rust_code, &items)
+    }
 }
 
 #[cfg(test)]
@@ -261,6 +308,11 @@ mod tests {
                 error_types: vec![],
                 is_async: false,
                 is_generator: false,
+                is_context_manager: false,
+                is_async_entry: false,
+                memoize: None,
+                mutated_params: None,
+                complexity: None,
             },
             annotations: TranspilationAnnotations {
                 thread_safety: if thread_safe {
@@ -344,6 +396,26 @@ mod tests {
         assert!(matches!(type_result.status, PropertyStatus::Unknown));
     }
 
+    #[test]
+    fn test_panic_freedom_verification() {
+        let verifier = PropertyVerifier::new();
+        let mut func = create_test_function("index_func", false, false);
+
+        let results = verifier.verify_function(&func);
+        let panic_result = results.iter().find(|r| r.property == "panic_free").unwrap();
+        assert!(matches!(panic_result.status, PropertyStatus::Proven));
+
+        // `return x[0]` may index an empty list
+        func.body = vec![HirStmt::Return(Some(HirExpr::Index {
+            base: Box::new(HirExpr::Var("x".to_string())),
+            index: Box::new(HirExpr::Literal(depyler_core::hir::Literal::Int(0))),
+        }))];
+        let results = verifier.verify_function(&func);
+        let panic_result = results.iter().find(|r| r.property == "panic_free").unwrap();
+        assert!(matches!(panic_result.status, PropertyStatus::Unknown));
+        assert_eq!(panic_result.counterexamples.len(), 1);
+    }
+
     #[test]
     fn test_memory_safety_verification() {
         let verifier = PropertyVerifier::new();
@@ -363,6 +435,31 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_write_proptests_adds_a_tests_module() {
+        let verifier = PropertyVerifier::new().with_iterations(50);
+        let func = create_test_function("double", true, false);
+        let recorded = HashMap::from([(
+            "double".to_string(),
+            vec![TestCase {
+                inputs: vec![serde_json::json!(2)],
+                expected_output: Some(serde_json::json!(2)),
+                actual_output: None,
+                error: None,
+            }],
+        )]);
+
+        let code =
+            verifier.write_proptests("pub fn double(x: i32) -> i32 { x }\n", &[func], &recorded);
+        assert!(code.contains("#[cfg(test)]\nmod tests {"));
+        assert!(code.contains("fn proptest_double_does_not_panic(x in -10_000i32..=10_000)"));
+        assert!(code.contains("assert_eq!(double(2), 2);"));
+        assert_eq!(
+            verifier.write_proptests("fn f() {}\n", &[], &HashMap::new()),
+            "fn f() {}\n"
+        );
+    }
+
     #[test]
     fn test_property_status_serialization() {
         use serde_json;
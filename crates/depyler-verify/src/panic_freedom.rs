@@ -0,0 +1,1401 @@
+//! Panic-freedom proofs
+//!
+//! Generated Rust panics where Python would raise: indexing out of range
+//! or with a missing key, dividing by zero, slicing with a zero step,
+//! unwrapping what `pop()`, `max()` or `next()` finds in an empty
+//! collection, and a failing `assert`. [`panic_sites`] walks a function's
+//! HIR for those sites and discharges the ones the facts gathered on the
+//! way rule out:
+//!
+//! | Fact                                   | Proves                                   |
+//! |----------------------------------------|------------------------------------------|
+//! | `for i in range(len(xs))`              | `xs[i]`                                  |
+//! | `for i in range(1, len(xs) - 1)`       | `xs[i - 1]`, `xs[i + 1]`                 |
+//! | `for i, x in enumerate(xs)`            | `xs[i]`                                  |
+//! | `if xs:`, `if len(xs) > 0:`            | `xs[0]`, `xs[-1]`, `xs.pop()`, `max(xs)` |
+//! | `xs = [a, b]`, a `tuple[int, int]` param | `xs[1]`                                |
+//! | `if b != 0:`, `if b == 0: return`      | `a // b`, `a % b`                        |
+//! | `if key in d:`, `d[key] = v`           | `d[key]`, `d.pop(key)`                   |
+//! | a non-zero literal                     | `a / 2`, `xs[::2]`                       |
+//!
+//! A fact lasts until a variable it is about is assigned, or the sequence
+//! shrinks through `pop()`, `remove()`, `clear()` or by being handed to a
+//! function that might shrink it. Inside a loop it only holds if nothing in
+//! the loop body undoes it. The sites left over are reported with the path
+//! of their statement, which [`HirSpans`] turns into a Python line, and
+//! [`prove_module`] marks the functions without any as panic-free.
+
+use depyler_core::hir::{
+    AssignTarget, BinOp, HirExpr, HirFunction, HirMethod, HirModule, HirParam, HirStmt, Literal,
+    Type, UnaryOp,
+};
+use depyler_core::hir_spans::HirSpans;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Methods that make the collection they are called on smaller
+const SHRINKING_METHODS: &[&str] = &["pop", "popitem", "remove", "discard", "clear"];
+
+/// Builtins known not to mutate the collections passed to them
+const READ_ONLY_BUILTINS: &[&str] = &[
+    "abs",
+    "all",
+    "any",
+    "bool",
+    "dict",
+    "enumerate",
+    "float",
+    "frozenset",
+    "int",
+    "isinstance",
+    "len",
+    "list",
+    "max",
+    "min",
+    "print",
+    "range",
+    "repr",
+    "reversed",
+    "set",
+    "sorted",
+    "str",
+    "sum",
+    "tuple",
+    "zip",
+];
+
+/// What a potential panic site does wrong
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PanicKind {
+    /// `xs[i]` out of range, or `d[k]` with a missing key
+    Index,
+    /// `xs[a:b:step]` with a zero step
+    Slice,
+    /// `/`, `//` or `%` by zero
+    Division,
+    /// `pop()`, `max()`, `next()` and the like finding nothing
+    Unwrap,
+    /// `assert` with a false condition
+    Assert,
+}
+
+/// A place that may panic and that no fact rules out
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PanicSite {
+    pub kind: PanicKind,
+    /// Function or `Class.method` the site is in
+    pub function: String,
+    /// Path of the statement in `function`, as [`FunctionSpans::statement`]
+    /// takes it
+    ///
+    /// [`FunctionSpans::statement`]: depyler_core::hir_spans::FunctionSpans::statement
+    pub path: Vec<usize>,
+    /// The expression that may panic, written as Python
+    pub expr: String,
+}
+
+impl PanicSite {
+    /// Python line of the statement the site is in
+    pub fn line(&self, spans: &HirSpans) -> Option<usize> {
+        let stmt = spans.function(&self.function)?.statement(&self.path)?;
+        Some(spans.position(stmt.span.start).line)
+    }
+}
+
+impl fmt::Display for PanicSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let risk = match self.kind {
+            PanicKind::Index => "may index out of range or miss its key",
+            PanicKind::Slice => "may slice with a zero step",
+            PanicKind::Division => "may divide by zero",
+            PanicKind::Unwrap => "may find nothing to return",
+            PanicKind::Assert => "may fail",
+        };
+        write!(f, "`{}` in `{}` {}", self.expr, self.function, risk)
+    }
+}
+
+/// Potential panic sites of `func` that could not be proven absent, in
+/// source order
+pub fn panic_sites(func: &HirFunction) -> Vec<PanicSite> {
+    Prover::run(&func.name, &func.params, &func.body)
+}
+
+/// [`panic_sites`] of a method of `class`
+pub fn method_panic_sites(class: &str, method: &HirMethod) -> Vec<PanicSite> {
+    let name = format!("{}.{}", class, method.name);
+    Prover::run(&name, &method.params, &method.body)
+}
+
+/// Proves what it can for every function and method of `module`, setting
+/// `FunctionProperties::panic_free` of each function to whether no site is
+/// left, and returns the sites that are
+///
+/// Methods carry no properties, so they only contribute sites.
+pub fn prove_module(module: &mut HirModule) -> Vec<PanicSite> {
+    let mut sites = Vec::new();
+    for func in &mut module.functions {
+        let mut func_sites = panic_sites(func);
+        func.properties.panic_free = func_sites.is_empty();
+        sites.append(&mut func_sites);
+    }
+    for class in &module.classes {
+        for method in &class.methods {
+            sites.extend(method_panic_sites(&class.name, method));
+        }
+    }
+    sites
+}
+
+/// `i` runs over `low..len(seq) - slack`
+#[derive(Debug, Clone, PartialEq)]
+struct Bound {
+    var: String,
+    seq: String,
+    low: i64,
+    slack: i64,
+}
+
+/// What is known at a point of a function body
+#[derive(Debug, Clone, Default)]
+struct Facts {
+    bounds: Vec<Bound>,
+    /// Lower bounds on the lengths of sequences
+    min_len: HashMap<String, i64>,
+    non_zero: HashSet<String>,
+    /// Keys known to be in a dict, as `(dict, key)`
+    keys: Vec<(String, HirExpr)>,
+}
+
+impl Facts {
+    /// Drops what is known about `name`, which was assigned
+    fn forget(&mut self, name: &str) {
+        self.bounds.retain(|b| b.var != name && b.seq != name);
+        self.min_len.remove(name);
+        self.non_zero.remove(name);
+        self.keys
+            .retain(|(dict, key)| dict != name && !mentions(key, name));
+    }
+
+    fn without(&self, names: &HashSet<String>) -> Facts {
+        let mut facts = self.clone();
+        for name in names {
+            facts.forget(name);
+        }
+        facts
+    }
+
+    /// What holds on both of two paths that join
+    fn intersect(&mut self, other: &Facts) {
+        self.bounds.retain(|b| other.bounds.contains(b));
+        self.min_len = self
+            .min_len
+            .iter()
+            .filter_map(|(seq, n)| Some((seq.clone(), (*n).min(*other.min_len.get(seq)?))))
+            .collect();
+        self.non_zero.retain(|name| other.non_zero.contains(name));
+        self.keys.retain(|key| other.keys.contains(key));
+    }
+
+    fn at_least(&mut self, seq: &str, len: i64) {
+        let entry = self.min_len.entry(seq.to_string()).or_insert(0);
+        *entry = (*entry).max(len);
+    }
+
+    fn min_len(&self, seq: &str) -> i64 {
+        self.min_len.get(seq).copied().unwrap_or(0)
+    }
+
+    /// Learns what `cond` being `truth` implies
+    fn assume(&mut self, cond: &HirExpr, truth: bool) {
+        match cond {
+            HirExpr::Unary {
+                op: UnaryOp::Not,
+                operand,
+            } => self.assume(operand, !truth),
+            HirExpr::Binary {
+                op: BinOp::And,
+                left,
+                right,
+            } if truth => {
+                self.assume(left, true);
+                self.assume(right, true);
+            }
+            HirExpr::Binary {
+                op: BinOp::Or,
+                left,
+                right,
+            } if !truth => {
+                self.assume(left, false);
+                self.assume(right, false);
+            }
+            // A truthy number is non-zero, a truthy collection non-empty
+            HirExpr::Var(name) if truth => {
+                self.non_zero.insert(name.clone());
+                self.at_least(name, 1);
+            }
+            HirExpr::Call { func, args, .. } if truth && func == "len" && args.len() == 1 => {
+                if let HirExpr::Var(seq) = &args[0] {
+                    self.at_least(seq, 1);
+                }
+            }
+            HirExpr::Binary {
+                op: BinOp::In,
+                left,
+                right,
+            } if truth => self.learn_key(right, left),
+            HirExpr::Binary {
+                op: BinOp::NotIn,
+                left,
+                right,
+            } if !truth => self.learn_key(right, left),
+            HirExpr::Binary { op, left, right } => {
+                if let Some((subject, op, n)) = comparison(*op, left, right, truth) {
+                    self.learn_comparison(subject, op, n);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn learn_key(&mut self, dict: &HirExpr, key: &HirExpr) {
+        if let HirExpr::Var(dict) = dict {
+            self.keys.push((dict.clone(), key.clone()));
+        }
+    }
+
+    /// Learns from `subject op n`
+    fn learn_comparison(&mut self, subject: &HirExpr, op: BinOp, n: i64) {
+        match subject {
+            HirExpr::Var(name) => {
+                let non_zero = match op {
+                    BinOp::NotEq => n == 0,
+                    BinOp::Eq => n != 0,
+                    BinOp::Gt => n >= 0,
+                    BinOp::GtEq => n >= 1,
+                    BinOp::Lt => n <= 0,
+                    BinOp::LtEq => n <= -1,
+                    _ => false,
+                };
+                if non_zero {
+                    self.non_zero.insert(name.clone());
+                }
+            }
+            HirExpr::Call { func, args, .. } if func == "len" && args.len() == 1 => {
+                let HirExpr::Var(seq) = &args[0] else {
+                    return;
+                };
+                let len = match op {
+                    BinOp::Gt => n + 1,
+                    BinOp::GtEq | BinOp::Eq => n,
+                    BinOp::NotEq if n == 0 => 1,
+                    _ => return,
+                };
+                self.at_least(seq, len);
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether `base[index]` is in range, or a key `base` has
+    fn proves_index(&self, base: &HirExpr, index: &HirExpr) -> bool {
+        let HirExpr::Var(seq) = base else {
+            return false;
+        };
+        if self.keys.iter().any(|(d, k)| d == seq && k == index) {
+            return true;
+        }
+        let min_len = self.min_len(seq);
+        if let Some(n) = int_literal(index) {
+            return if n >= 0 { n < min_len } else { -n <= min_len };
+        }
+        // `xs[len(xs) - d]`
+        if let HirExpr::Binary {
+            op: BinOp::Sub,
+            left,
+            right,
+        } = index
+        {
+            if is_len_of(left, seq) {
+                return int_literal(right).is_some_and(|d| (1..=min_len).contains(&d));
+            }
+        }
+        let (var, offset) = match index {
+            HirExpr::Var(var) => (var, 0),
+            HirExpr::Binary {
+                op: op @ (BinOp::Add | BinOp::Sub),
+                left,
+                right,
+            } => match (left.as_ref(), int_literal(right)) {
+                (HirExpr::Var(var), Some(d)) if *op == BinOp::Add => (var, d),
+                (HirExpr::Var(var), Some(d)) => (var, -d),
+                _ => return false,
+            },
+            _ => return false,
+        };
+        self.bounds
+            .iter()
+            .any(|b| &b.var == var && &b.seq == seq && b.low + offset >= 0 && offset <= b.slack)
+    }
+
+    fn proves_non_zero(&self, expr: &HirExpr) -> bool {
+        match expr {
+            HirExpr::Literal(Literal::Int(n)) => *n != 0,
+            HirExpr::Literal(Literal::Float(f)) => *f != 0.0,
+            HirExpr::Unary {
+                op: UnaryOp::Neg,
+                operand,
+            } => self.proves_non_zero(operand),
+            HirExpr::Var(name) => self.non_zero.contains(name),
+            HirExpr::Call { func, args, .. } if func == "len" && args.len() == 1 => {
+                matches!(&args[0], HirExpr::Var(seq) if self.min_len(seq) >= 1)
+            }
+            _ => false,
+        }
+    }
+
+    fn proves_non_empty(&self, expr: &HirExpr) -> bool {
+        match expr {
+            HirExpr::Var(seq) => self.min_len(seq) >= 1,
+            HirExpr::List(items) | HirExpr::Tuple(items) | HirExpr::Set(items) => !items.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// `left op right` as `subject op n` for an integer `n`, given it is `truth`
+fn comparison<'e>(
+    op: BinOp,
+    left: &'e HirExpr,
+    right: &'e HirExpr,
+    truth: bool,
+) -> Option<(&'e HirExpr, BinOp, i64)> {
+    let (subject, op, n) = match (int_literal(left), int_literal(right)) {
+        (None, Some(n)) => (left, op, n),
+        (Some(n), None) => {
+            let flipped = match op {
+                BinOp::Lt => BinOp::Gt,
+                BinOp::LtEq => BinOp::GtEq,
+                BinOp::Gt => BinOp::Lt,
+                BinOp::GtEq => BinOp::LtEq,
+                other => other,
+            };
+            (right, flipped, n)
+        }
+        _ => return None,
+    };
+    let op = if truth {
+        op
+    } else {
+        match op {
+            BinOp::Eq => BinOp::NotEq,
+            BinOp::NotEq => BinOp::Eq,
+            BinOp::Lt => BinOp::GtEq,
+            BinOp::LtEq => BinOp::Gt,
+            BinOp::Gt => BinOp::LtEq,
+            BinOp::GtEq => BinOp::Lt,
+            _ => return None,
+        }
+    };
+    Some((subject, op, n))
+}
+
+fn int_literal(expr: &HirExpr) -> Option<i64> {
+    match expr {
+        HirExpr::Literal(Literal::Int(n)) => Some(*n),
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => int_literal(operand).map(|n| -n),
+        _ => None,
+    }
+}
+
+fn is_len_of(expr: &HirExpr, seq: &str) -> bool {
+    matches!(
+        expr,
+        HirExpr::Call { func, args, .. }
+            if func == "len" && matches!(args.as_slice(), [HirExpr::Var(s)] if s == seq)
+    )
+}
+
+fn mentions(expr: &HirExpr, name: &str) -> bool {
+    matches!(expr, HirExpr::Var(var) if var == name)
+        || expr
+            .children()
+            .into_iter()
+            .any(|child| mentions(child, name))
+}
+
+/// `var` over `iter` when `iter` is `range(low, len(seq) - slack)`
+fn range_bound(var: &str, iter: &HirExpr) -> Option<Bound> {
+    let HirExpr::Call { func, args, kwargs } = iter else {
+        return None;
+    };
+    if func != "range" || !kwargs.is_empty() {
+        return None;
+    }
+    let (low, stop) = match args.as_slice() {
+        [stop] => (0, stop),
+        [start, stop] => (int_literal(start)?, stop),
+        [start, stop, step] if int_literal(step)? > 0 => (int_literal(start)?, stop),
+        _ => return None,
+    };
+    let (len, slack) = match stop {
+        HirExpr::Binary {
+            op: BinOp::Sub,
+            left,
+            right,
+        } => (left.as_ref(), int_literal(right)?),
+        stop => (stop, 0),
+    };
+    let HirExpr::Call { func, args, .. } = len else {
+        return None;
+    };
+    match args.as_slice() {
+        [HirExpr::Var(seq)] if func == "len" && low >= 0 && slack >= 0 => Some(Bound {
+            var: var.to_string(),
+            seq: seq.clone(),
+            low,
+            slack,
+        }),
+        _ => None,
+    }
+}
+
+/// Names a target binds
+fn target_names(target: &AssignTarget, names: &mut HashSet<String>) {
+    match target {
+        AssignTarget::Symbol(name) => {
+            names.insert(name.clone());
+        }
+        AssignTarget::Tuple(targets) => targets.iter().for_each(|t| target_names(t, names)),
+        AssignTarget::Index { .. } | AssignTarget::Attribute { .. } => {}
+    }
+}
+
+/// Variables `stmts` assign or may shrink
+fn changed_in(stmts: &[HirStmt], names: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                target_names(target, names);
+                if let AssignTarget::Index { base, index } = target {
+                    changed_by(base, names);
+                    changed_by(index, names);
+                }
+                changed_by(value, names);
+            }
+            HirStmt::Expr(expr) | HirStmt::Return(Some(expr)) => changed_by(expr, names),
+            HirStmt::Raise { exception, cause } => exception
+                .iter()
+                .chain(cause)
+                .for_each(|e| changed_by(e, names)),
+            HirStmt::Assert { test, msg } => std::iter::once(test)
+                .chain(msg)
+                .for_each(|e| changed_by(e, names)),
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                changed_by(condition, names);
+                changed_in(then_body, names);
+                if let Some(else_body) = else_body {
+                    changed_in(else_body, names);
+                }
+            }
+            HirStmt::While { condition, body } => {
+                changed_by(condition, names);
+                changed_in(body, names);
+            }
+            HirStmt::For { target, iter, body } => {
+                target_names(target, names);
+                changed_by(iter, names);
+                changed_in(body, names);
+            }
+            HirStmt::With {
+                context,
+                target,
+                body,
+            } => {
+                changed_by(context, names);
+                names.extend(target.iter().cloned());
+                changed_in(body, names);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                changed_in(body, names);
+                for handler in handlers {
+                    names.extend(handler.name.iter().cloned());
+                    changed_in(&handler.body, names);
+                }
+                for block in orelse.iter().chain(finalbody) {
+                    changed_in(block, names);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                changed_by(subject, names);
+                for case in cases {
+                    names.extend(case.pattern.bindings());
+                    changed_in(&case.body, names);
+                }
+            }
+            HirStmt::Return(None)
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+            | HirStmt::Pass => {}
+        }
+    }
+}
+
+/// Variables evaluating `expr` may shrink: the receivers of shrinking
+/// methods, and collections handed to anything but a read-only builtin
+fn changed_by(expr: &HirExpr, names: &mut HashSet<String>) {
+    match expr {
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } => {
+            if let HirExpr::Var(name) = object.as_ref() {
+                if SHRINKING_METHODS.contains(&method.as_str()) {
+                    names.insert(name.clone());
+                }
+            }
+            names.extend(args.iter().filter_map(var_name));
+        }
+        HirExpr::Call { func, args, .. } if !READ_ONLY_BUILTINS.contains(&func.as_str()) => {
+            names.extend(args.iter().filter_map(var_name));
+        }
+        _ => {}
+    }
+    for child in expr.children() {
+        changed_by(child, names);
+    }
+}
+
+fn var_name(expr: &HirExpr) -> Option<String> {
+    match expr {
+        HirExpr::Var(name) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+/// Whether control never falls off the end of `stmts`
+fn exits(stmts: &[HirStmt]) -> bool {
+    matches!(
+        stmts.last(),
+        Some(
+            HirStmt::Return(_)
+                | HirStmt::Raise { .. }
+                | HirStmt::Break { .. }
+                | HirStmt::Continue { .. }
+        )
+    )
+}
+
+/// Walks one function body, following the statement paths of `HirSpans`
+struct Prover<'a> {
+    function: &'a str,
+    /// Variables holding dicts, whose integer keys are no indices
+    dicts: HashSet<String>,
+    sites: Vec<PanicSite>,
+}
+
+impl<'a> Prover<'a> {
+    fn run(function: &'a str, params: &[HirParam], body: &[HirStmt]) -> Vec<PanicSite> {
+        let mut facts = Facts::default();
+        let mut dicts = HashSet::new();
+        for param in params {
+            match &param.ty {
+                Type::Tuple(items) => facts.at_least(&param.name, items.len() as i64),
+                Type::Dict(..) => {
+                    dicts.insert(param.name.clone());
+                }
+                _ => {}
+            }
+        }
+        collect_dicts(body, &mut dicts);
+        let mut prover = Self {
+            function,
+            dicts,
+            sites: Vec::new(),
+        };
+        prover.stmts(body, &mut facts, &mut Vec::new());
+        prover.sites
+    }
+
+    fn stmts(&mut self, stmts: &[HirStmt], facts: &mut Facts, path: &mut Vec<usize>) {
+        for (index, stmt) in stmts.iter().enumerate() {
+            path.push(index);
+            self.stmt(stmt, facts, path);
+            path.pop();
+        }
+    }
+
+    fn block(&mut self, block: usize, stmts: &[HirStmt], facts: &mut Facts, path: &mut Vec<usize>) {
+        path.push(block);
+        self.stmts(stmts, facts, path);
+        path.pop();
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt, facts: &mut Facts, path: &mut Vec<usize>) {
+        match stmt {
+            HirStmt::Assign { target, value, .. } => {
+                self.check(value, facts, path);
+                if let AssignTarget::Index { base, index } = target {
+                    self.check(base, facts, path);
+                    self.check(index, facts, path);
+                    // Assigning to a dict key inserts it
+                    match base.as_ref() {
+                        HirExpr::Var(dict) if self.dicts.contains(dict) => {
+                            facts.keys.push((dict.clone(), index.as_ref().clone()));
+                        }
+                        _ if !facts.proves_index(base, index) => {
+                            self.report(PanicKind::Index, &render_index(base, index), path)
+                        }
+                        _ => {}
+                    }
+                }
+                let mut changed = HashSet::new();
+                changed_in(std::slice::from_ref(stmt), &mut changed);
+                *facts = facts.without(&changed);
+                if let AssignTarget::Symbol(name) = target {
+                    self.learn_assignment(name, value, facts);
+                }
+            }
+            HirStmt::Expr(expr) | HirStmt::Return(Some(expr)) => {
+                self.check(expr, facts, path);
+                let mut changed = HashSet::new();
+                changed_by(expr, &mut changed);
+                *facts = facts.without(&changed);
+            }
+            HirStmt::Raise { exception, cause } => {
+                for expr in exception.iter().chain(cause) {
+                    self.check(expr, facts, path);
+                }
+            }
+            HirStmt::Assert { test, msg } => {
+                self.check(test, facts, path);
+                if let Some(msg) = msg {
+                    self.check(msg, facts, path);
+                }
+                if !matches!(test, HirExpr::Literal(Literal::Bool(true))) {
+                    self.report(PanicKind::Assert, &format!("assert {}", render(test)), path);
+                }
+                facts.assume(test, true);
+            }
+            HirStmt::If {
+                condition,
+                then_body,
+                else_body,
+            } => {
+                self.check(condition, facts, path);
+                let mut then_facts = facts.clone();
+                then_facts.assume(condition, true);
+                self.block(0, then_body, &mut then_facts, path);
+                let mut else_facts = facts.clone();
+                else_facts.assume(condition, false);
+                let else_body = else_body.as_deref().unwrap_or(&[]);
+                self.block(1, else_body, &mut else_facts, path);
+                *facts = match (exits(then_body), exits(else_body)) {
+                    (true, _) => else_facts,
+                    (false, true) => then_facts,
+                    (false, false) => {
+                        then_facts.intersect(&else_facts);
+                        then_facts
+                    }
+                };
+            }
+            HirStmt::While { condition, body } => {
+                let mut changed = HashSet::new();
+                changed_in(body, &mut changed);
+                changed_by(condition, &mut changed);
+                *facts = facts.without(&changed);
+                self.check(condition, facts, path);
+                let mut body_facts = facts.clone();
+                body_facts.assume(condition, true);
+                self.block(0, body, &mut body_facts, path);
+            }
+            HirStmt::For { target, iter, body } => {
+                self.check(iter, facts, path);
+                let mut changed = HashSet::new();
+                changed_in(std::slice::from_ref(stmt), &mut changed);
+                *facts = facts.without(&changed);
+                let mut body_facts = facts.clone();
+                let mut loop_vars = HashSet::new();
+                changed_in(body, &mut loop_vars);
+                let bound = match target {
+                    AssignTarget::Symbol(var) => range_bound(var, iter),
+                    AssignTarget::Tuple(targets) => enumerate_bound(targets, iter),
+                    _ => None,
+                };
+                if let Some(bound) = bound {
+                    if !loop_vars.contains(&bound.var)
+                        && !loop_vars.contains(&bound.seq)
+                        && !self.dicts.contains(&bound.seq)
+                    {
+                        body_facts.bounds.push(bound);
+                    }
+                }
+                self.block(0, body, &mut body_facts, path);
+            }
+            HirStmt::With {
+                context,
+                target,
+                body,
+            } => {
+                self.check(context, facts, path);
+                if let Some(target) = target {
+                    facts.forget(target);
+                }
+                self.block(0, body, facts, path);
+            }
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                // A handler or `finally` may start after any statement of the body
+                let mut changed = HashSet::new();
+                changed_in(std::slice::from_ref(stmt), &mut changed);
+                *facts = facts.without(&changed);
+                self.block(0, body, &mut facts.clone(), path);
+                for (i, handler) in handlers.iter().enumerate() {
+                    self.block(1 + i, &handler.body, &mut facts.clone(), path);
+                }
+                if let Some(orelse) = orelse {
+                    self.block(1 + handlers.len(), orelse, &mut facts.clone(), path);
+                }
+                if let Some(finalbody) = finalbody {
+                    self.block(2 + handlers.len(), finalbody, &mut facts.clone(), path);
+                }
+            }
+            HirStmt::Match { subject, cases } => {
+                self.check(subject, facts, path);
+                let mut changed = HashSet::new();
+                changed_in(std::slice::from_ref(stmt), &mut changed);
+                *facts = facts.without(&changed);
+                for (i, case) in cases.iter().enumerate() {
+                    let mut case_facts = facts.clone();
+                    if let Some(guard) = &case.guard {
+                        self.check(guard, &case_facts, path);
+                        case_facts.assume(guard, true);
+                    }
+                    self.block(i, &case.body, &mut case_facts, path);
+                }
+            }
+            HirStmt::Return(None)
+            | HirStmt::Break { .. }
+            | HirStmt::Continue { .. }
+            | HirStmt::Pass => {}
+        }
+    }
+
+    /// What `name = value` establishes about `name`
+    fn learn_assignment(&self, name: &str, value: &HirExpr, facts: &mut Facts) {
+        match value {
+            HirExpr::List(items) | HirExpr::Tuple(items) => {
+                facts.at_least(name, items.len() as i64);
+            }
+            HirExpr::Dict(items) => {
+                for (key, _) in items {
+                    facts.keys.push((name.to_string(), key.clone()));
+                }
+            }
+            value if facts.proves_non_zero(value) => {
+                facts.non_zero.insert(name.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Reports the sites of a statement's expression
+    ///
+    /// What the expression shrinks may already be gone when the rest of it
+    /// runs, so only the shrinking calls themselves see the facts from
+    /// before it.
+    fn check(&mut self, expr: &HirExpr, before: &Facts, path: &[usize]) {
+        let mut changed = HashSet::new();
+        changed_by(expr, &mut changed);
+        let facts = before.without(&changed);
+        self.expr(expr, &facts, before, path);
+    }
+
+    fn expr(&mut self, expr: &HirExpr, facts: &Facts, before: &Facts, path: &[usize]) {
+        match expr {
+            HirExpr::Index { base, index } if !facts.proves_index(base, index) => {
+                self.report(PanicKind::Index, &render(expr), path);
+            }
+            HirExpr::Slice {
+                step: Some(step), ..
+            } if !facts.proves_non_zero(step) => {
+                self.report(PanicKind::Slice, &render(expr), path);
+            }
+            HirExpr::Binary {
+                op: BinOp::Div | BinOp::FloorDiv | BinOp::Mod,
+                right,
+                ..
+            } if !facts.proves_non_zero(right) => {
+                self.report(PanicKind::Division, &render(expr), path);
+            }
+            HirExpr::MethodCall {
+                object,
+                method,
+                args,
+                ..
+            } => {
+                let proven = match (method.as_str(), args.as_slice()) {
+                    ("pop", []) => before.proves_non_empty(object),
+                    ("pop", [key]) => before.proves_index(object, key),
+                    ("pop", [_, _]) => true,
+                    ("index" | "remove", _) => false,
+                    _ => true,
+                };
+                if !proven {
+                    self.report(PanicKind::Unwrap, &render(expr), path);
+                }
+            }
+            HirExpr::Call { func, args, kwargs } => {
+                let proven = match (func.as_str(), args.as_slice()) {
+                    ("max" | "min", [items]) => {
+                        kwargs.iter().any(|(name, _)| name == "default")
+                            || facts.proves_non_empty(items)
+                    }
+                    ("next", [_]) => false,
+                    _ => true,
+                };
+                if !proven {
+                    self.report(PanicKind::Unwrap, &render(expr), path);
+                }
+            }
+            // Short-circuiting: `xs and xs[0]`, `not xs or xs[0]`
+            HirExpr::Binary {
+                op: op @ (BinOp::And | BinOp::Or),
+                left,
+                right,
+            } => {
+                self.expr(left, facts, before, path);
+                let mut right_facts = facts.clone();
+                right_facts.assume(left, *op == BinOp::And);
+                self.expr(right, &right_facts, before, path);
+                return;
+            }
+            HirExpr::IfExpr { test, body, orelse } => {
+                self.expr(test, facts, before, path);
+                for (branch, truth) in [(body, true), (orelse, false)] {
+                    let mut branch_facts = facts.clone();
+                    branch_facts.assume(test, truth);
+                    self.expr(branch, &branch_facts, before, path);
+                }
+                return;
+            }
+            HirExpr::ListComp {
+                element,
+                target,
+                iter,
+                condition,
+            }
+            | HirExpr::SetComp {
+                element,
+                target,
+                iter,
+                condition,
+            } => {
+                let conditions = condition.as_deref().map(std::slice::from_ref);
+                let inner = self.comprehension(
+                    facts,
+                    before,
+                    target,
+                    iter,
+                    conditions.unwrap_or_default(),
+                    path,
+                );
+                self.expr(element, &inner, before, path);
+                return;
+            }
+            HirExpr::DictComp {
+                key,
+                value,
+                target,
+                iter,
+                condition,
+            } => {
+                let conditions = condition.as_deref().map(std::slice::from_ref);
+                let inner = self.comprehension(
+                    facts,
+                    before,
+                    target,
+                    iter,
+                    conditions.unwrap_or_default(),
+                    path,
+                );
+                self.expr(key, &inner, before, path);
+                self.expr(value, &inner, before, path);
+                return;
+            }
+            HirExpr::GeneratorExp {
+                element,
+                generators,
+            } => {
+                let mut inner = facts.clone();
+                for generator in generators {
+                    inner = self.comprehension(
+                        &inner,
+                        before,
+                        &generator.target,
+                        &generator.iter,
+                        &generator.conditions,
+                        path,
+                    );
+                }
+                self.expr(element, &inner, before, path);
+                return;
+            }
+            // The body runs later, when none of the facts need to hold
+            HirExpr::Lambda { body, .. } => {
+                let facts = Facts::default();
+                self.expr(body, &facts, &facts, path);
+                return;
+            }
+            _ => {}
+        }
+        for child in expr.children() {
+            self.expr(child, facts, before, path);
+        }
+    }
+
+    /// Checks a `for target in iter if conditions` clause and returns the
+    /// facts its element is evaluated under
+    fn comprehension(
+        &mut self,
+        facts: &Facts,
+        before: &Facts,
+        target: &str,
+        iter: &HirExpr,
+        conditions: &[HirExpr],
+        path: &[usize],
+    ) -> Facts {
+        self.expr(iter, facts, before, path);
+        let mut inner = facts.clone();
+        inner.forget(target);
+        if let Some(bound) = range_bound(target, iter) {
+            if !self.dicts.contains(&bound.seq) {
+                inner.bounds.push(bound);
+            }
+        }
+        for condition in conditions {
+            self.expr(condition, &inner, before, path);
+            inner.assume(condition, true);
+        }
+        inner
+    }
+
+    fn report(&mut self, kind: PanicKind, expr: &str, path: &[usize]) {
+        self.sites.push(PanicSite {
+            kind,
+            function: self.function.to_string(),
+            path: path.to_vec(),
+            expr: expr.to_string(),
+        });
+    }
+}
+
+/// `for i, x in enumerate(seq)` bounds `i` by `seq`
+fn enumerate_bound(targets: &[AssignTarget], iter: &HirExpr) -> Option<Bound> {
+    let (AssignTarget::Symbol(var), HirExpr::Call { func, args, kwargs }) =
+        (targets.first()?, iter)
+    else {
+        return None;
+    };
+    match args.as_slice() {
+        [HirExpr::Var(seq)] if func == "enumerate" && kwargs.is_empty() && targets.len() == 2 => {
+            Some(Bound {
+                var: var.clone(),
+                seq: seq.clone(),
+                low: 0,
+                slack: 0,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Variables assigned a dict anywhere in `stmts`
+fn collect_dicts(stmts: &[HirStmt], dicts: &mut HashSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            HirStmt::Assign {
+                target: AssignTarget::Symbol(name),
+                value,
+                type_annotation,
+            } => {
+                let is_dict = matches!(value, HirExpr::Dict(_) | HirExpr::DictComp { .. })
+                    || matches!(value, HirExpr::Call { func, .. } if func == "dict")
+                    || matches!(type_annotation, Some(Type::Dict(..)));
+                if is_dict {
+                    dicts.insert(name.clone());
+                }
+            }
+            HirStmt::If {
+                then_body,
+                else_body,
+                ..
+            } => {
+                collect_dicts(then_body, dicts);
+                if let Some(else_body) = else_body {
+                    collect_dicts(else_body, dicts);
+                }
+            }
+            HirStmt::While { body, .. }
+            | HirStmt::For { body, .. }
+            | HirStmt::With { body, .. } => collect_dicts(body, dicts),
+            HirStmt::Try {
+                body,
+                handlers,
+                orelse,
+                finalbody,
+            } => {
+                collect_dicts(body, dicts);
+                for handler in handlers {
+                    collect_dicts(&handler.body, dicts);
+                }
+                for block in orelse.iter().chain(finalbody) {
+                    collect_dicts(block, dicts);
+                }
+            }
+            HirStmt::Match { cases, .. } => {
+                for case in cases {
+                    collect_dicts(&case.body, dicts);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn render_index(base: &HirExpr, index: &HirExpr) -> String {
+    format!("{}[{}]", render(base), render(index))
+}
+
+/// `expr` written as Python, with `...` for what does not matter to a report
+fn render(expr: &HirExpr) -> String {
+    let list = |items: &[HirExpr]| items.iter().map(render).collect::<Vec<_>>().join(", ");
+    match expr {
+        HirExpr::Literal(Literal::Int(n)) => n.to_string(),
+        HirExpr::Literal(Literal::Float(f)) => format!("{:?}", f),
+        HirExpr::Literal(Literal::String(s)) => format!("{:?}", s),
+        HirExpr::Literal(Literal::Bool(true)) => "True".to_string(),
+        HirExpr::Literal(Literal::Bool(false)) => "False".to_string(),
+        HirExpr::Literal(Literal::None) => "None".to_string(),
+        HirExpr::Var(name) => name.clone(),
+        HirExpr::Attribute { value, attr } => format!("{}.{}", render(value), attr),
+        HirExpr::Index { base, index } => render_index(base, index),
+        HirExpr::Slice {
+            base,
+            start,
+            stop,
+            step,
+        } => {
+            let part =
+                |part: &Option<Box<HirExpr>>| part.as_deref().map(render).unwrap_or_default();
+            let step = match step {
+                Some(step) => format!(":{}", render(step)),
+                None => String::new(),
+            };
+            format!("{}[{}:{}{}]", render(base), part(start), part(stop), step)
+        }
+        HirExpr::Binary { op, left, right } => {
+            let op = match op {
+                BinOp::Add => "+",
+                BinOp::Sub => "-",
+                BinOp::Mul => "*",
+                BinOp::Div => "/",
+                BinOp::FloorDiv => "//",
+                BinOp::Mod => "%",
+                BinOp::Eq => "==",
+                BinOp::NotEq => "!=",
+                BinOp::Lt => "<",
+                BinOp::LtEq => "<=",
+                BinOp::Gt => ">",
+                BinOp::GtEq => ">=",
+                BinOp::And => "and",
+                BinOp::Or => "or",
+                BinOp::In => "in",
+                BinOp::NotIn => "not in",
+                _ => return "...".to_string(),
+            };
+            format!("{} {} {}", render(left), op, render(right))
+        }
+        HirExpr::Unary {
+            op: UnaryOp::Neg,
+            operand,
+        } => format!("-{}", render(operand)),
+        HirExpr::Unary {
+            op: UnaryOp::Not,
+            operand,
+        } => format!("not {}", render(operand)),
+        HirExpr::Call { func, args, .. } => format!("{}({})", func, list(args)),
+        HirExpr::MethodCall {
+            object,
+            method,
+            args,
+            ..
+        } => format!("{}.{}({})", render(object), method, list(args)),
+        HirExpr::List(items) => format!("[{}]", list(items)),
+        HirExpr::Tuple(items) => format!("({})", list(items)),
+        _ => "...".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use depyler_core::hir_builder::HirFunctionBuilder;
+
+    fn var(name: &str) -> HirExpr {
+        HirExpr::Var(name.to_string())
+    }
+
+    fn int(n: i64) -> HirExpr {
+        HirExpr::Literal(Literal::Int(n))
+    }
+
+    fn call(func: &str, args: Vec<HirExpr>) -> HirExpr {
+        HirExpr::Call {
+            func: func.to_string(),
+            args,
+            kwargs: vec![],
+        }
+    }
+
+    fn index(base: &str, index: HirExpr) -> HirExpr {
+        HirExpr::Index {
+            base: Box::new(var(base)),
+            index: Box::new(index),
+        }
+    }
+
+    fn binary(op: BinOp, left: HirExpr, right: HirExpr) -> HirExpr {
+        HirExpr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+
+    fn for_range(var_name: &str, iter: HirExpr, body: Vec<HirStmt>) -> HirStmt {
+        HirStmt::For {
+            target: AssignTarget::Symbol(var_name.to_string()),
+            iter,
+            body,
+        }
+    }
+
+    fn int_list() -> Type {
+        Type::List(Box::new(Type::Int))
+    }
+
+    #[test]
+    fn test_range_of_len_proves_indexing() {
+        let func = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .returns(Type::Int)
+            .body(vec![for_range(
+                "i",
+                call("range", vec![call("len", vec![var("xs")])]),
+                vec![HirStmt::Expr(index("xs", var("i")))],
+            )])
+            .build();
+        assert!(panic_sites(&func).is_empty());
+    }
+
+    #[test]
+    fn test_offsets_need_slack() {
+        let stop = binary(BinOp::Sub, call("len", vec![var("xs")]), int(1));
+        let body = |offset: i64| {
+            vec![HirStmt::Expr(index(
+                "xs",
+                binary(BinOp::Add, var("i"), int(offset)),
+            ))]
+        };
+        let within = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .returns(Type::Int)
+            .body(vec![for_range(
+                "i",
+                call("range", vec![stop.clone()]),
+                body(1),
+            )])
+            .build();
+        assert!(panic_sites(&within).is_empty());
+
+        let beyond = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .returns(Type::Int)
+            .body(vec![for_range("i", call("range", vec![stop]), body(2))])
+            .build();
+        assert_eq!(panic_sites(&beyond).len(), 1);
+    }
+
+    #[test]
+    fn test_shrinking_the_sequence_in_the_loop_voids_the_bound() {
+        let pop = HirExpr::MethodCall {
+            object: Box::new(var("xs")),
+            method: "pop".to_string(),
+            args: vec![],
+            kwargs: vec![],
+        };
+        let func = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .returns(Type::Int)
+            .body(vec![for_range(
+                "i",
+                call("range", vec![call("len", vec![var("xs")])]),
+                vec![HirStmt::Expr(index("xs", var("i"))), HirStmt::Expr(pop)],
+            )])
+            .build();
+        let sites = panic_sites(&func);
+        let kinds: Vec<PanicKind> = sites.iter().map(|s| s.kind).collect();
+        assert_eq!(kinds, [PanicKind::Index, PanicKind::Unwrap]);
+        assert_eq!(sites[0].path, [0, 0, 0]);
+        assert_eq!(sites[0].expr, "xs[i]");
+    }
+
+    #[test]
+    fn test_guards_prove_division_and_first_element() {
+        // if b == 0: return 0
+        // return xs[0] // b  -- only the index is unproven
+        let early_exit = HirStmt::If {
+            condition: binary(BinOp::Eq, var("b"), int(0)),
+            then_body: vec![HirStmt::Return(Some(int(0)))],
+            else_body: None,
+        };
+        let ret = HirStmt::Return(Some(binary(BinOp::FloorDiv, index("xs", int(0)), var("b"))));
+        let func = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .param("b", Type::Int)
+            .returns(Type::Int);
+        let sites = panic_sites(
+            &func
+                .clone()
+                .body(vec![early_exit.clone(), ret.clone()])
+                .build(),
+        );
+        assert_eq!(sites.len(), 1);
+        assert_eq!(sites[0].kind, PanicKind::Index);
+
+        // Under `if xs:` the index is proven as well
+        let guarded = func
+            .body(vec![
+                early_exit,
+                HirStmt::If {
+                    condition: var("xs"),
+                    then_body: vec![ret],
+                    else_body: None,
+                },
+            ])
+            .build();
+        assert!(panic_sites(&guarded).is_empty());
+    }
+
+    #[test]
+    fn test_membership_proves_dict_lookup() {
+        let func = HirFunctionBuilder::new("f")
+            .param("d", Type::Dict(Box::new(Type::String), Box::new(Type::Int)))
+            .returns(Type::Int)
+            .body(vec![HirStmt::If {
+                condition: binary(BinOp::In, var("k"), var("d")),
+                then_body: vec![HirStmt::Return(Some(index("d", var("k"))))],
+                else_body: None,
+            }])
+            .build();
+        assert!(panic_sites(&func).is_empty());
+    }
+
+    #[test]
+    fn test_dicts_are_not_bounded_by_ranges() {
+        let func = HirFunctionBuilder::new("f")
+            .param("d", Type::Dict(Box::new(Type::Int), Box::new(Type::Int)))
+            .returns(Type::Int)
+            .body(vec![for_range(
+                "i",
+                call("range", vec![call("len", vec![var("d")])]),
+                vec![HirStmt::Expr(index("d", var("i")))],
+            )])
+            .build();
+        assert_eq!(panic_sites(&func).len(), 1);
+    }
+
+    #[test]
+    fn test_tuple_params_and_literals_have_lengths() {
+        let func = HirFunctionBuilder::new("f")
+            .param("t", Type::Tuple(vec![Type::Int, Type::Int]))
+            .returns(Type::Int)
+            .body(vec![
+                HirStmt::Expr(index("t", int(1))),
+                HirStmt::Expr(index("t", int(2))),
+            ])
+            .build();
+        assert_eq!(panic_sites(&func).len(), 1);
+    }
+
+    #[test]
+    fn test_prove_module_sets_panic_free() {
+        let mut module = HirModule {
+            functions: vec![
+                HirFunctionBuilder::new("f")
+                    .param("xs", int_list())
+                    .returns(Type::Int)
+                    .body(vec![HirStmt::Return(Some(var("xs")))])
+                    .build(),
+                HirFunctionBuilder::new("f")
+                    .param("xs", int_list())
+                    .returns(Type::Int)
+                    .body(vec![HirStmt::Return(Some(index("xs", var("i"))))])
+                    .build(),
+            ],
+            imports: vec![],
+            type_aliases: vec![],
+            protocols: vec![],
+            classes: vec![],
+            constants: vec![],
+        };
+        let sites = prove_module(&mut module);
+        assert_eq!(sites.len(), 1);
+        assert!(module.functions[0].properties.panic_free);
+        assert!(!module.functions[1].properties.panic_free);
+        assert_eq!(
+            sites[0].to_string(),
+            "`xs[i]` in `f` may index out of range or miss its key"
+        );
+    }
+
+    #[test]
+    fn test_site_lines_come_from_spans() {
+        let source = "def f(xs: list[int], i: int) -> int:\n    y = 1\n    return xs[i]\n";
+        let spans = HirSpans::parse(source).unwrap();
+        let func = HirFunctionBuilder::new("f")
+            .param("xs", int_list())
+            .returns(Type::Int)
+            .body(vec![
+                HirStmt::Assign {
+                    target: AssignTarget::Symbol("y".to_string()),
+                    value: int(1),
+                    type_annotation: None,
+                },
+                HirStmt::Return(Some(index("xs", var("i")))),
+            ])
+            .build();
+        let sites = panic_sites(&func);
+        assert_eq!(sites[0].line(&spans), Some(3));
+    }
+}
@@ -300,6 +300,11 @@ mod tests {
             error_types: vec![],
             is_async: false,
             is_generator: false,
+            is_context_manager: false,
+            is_async_entry: false,
+            memoize: None,
+            mutated_params: None,
+            complexity: None,
         };
 
         let func = create_test_function(
@@ -473,6 +478,11 @@ mod tests {
             error_types: vec![],
             is_async: false,
             is_generator: false,
+            is_context_manager: false,
+            is_async_entry: false,
+            memoize: None,
+            mutated_params: None,
+            complexity: None,
         };
 
         let func = create_test_function(
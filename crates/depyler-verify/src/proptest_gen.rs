@@ -0,0 +1,347 @@
+//! proptest property tests for transpiled functions
+//!
+//! A function whose parameters all have a [`strategy`] gets a `proptest!`
+//! test calling it with generated arguments, which fails on any panic:
+//! an index out of bounds, an overflow, an `unwrap` of `None`. Calls
+//! recorded from the Python original, as [`TestCase`]s, become an example
+//! test asserting that the Rust function returns what Python did.
+//!
+//! Integers and floats are drawn from small ranges. Python's integers
+//! never overflow, so `i32` overflow on huge inputs says nothing about the
+//! transpilation.
+//!
+//! The tests are written into the generated `#[cfg(test)] mod tests` and
+//! need `proptest` as a dev-dependency of the generated crate.
+
+use crate::TestCase;
+use depyler_core::hir::{HirFunction, Type};
+use serde_json::Value;
+
+/// Longest generated list, set or dict
+const MAX_LEN: usize = 8;
+
+/// The proptest strategy generating values of `ty`, as a Rust expression
+pub fn strategy(ty: &Type) -> Option<String> {
+    let strategy = match ty {
+        Type::Int => "-10_000i32..=10_000".to_string(),
+        Type::Float => "-1.0e6f64..1.0e6".to_string(),
+        Type::Bool => "proptest::bool::ANY".to_string(),
+        Type::String => "\"[ -~]{0,16}\"".to_string(),
+        Type::List(inner) => format!(
+            "proptest::collection::vec({}, 0..{})",
+            strategy(inner)?,
+            MAX_LEN
+        ),
+        Type::Set(inner) => format!(
+            "proptest::collection::hash_set({}, 0..{})",
+            strategy(inner)?,
+            MAX_LEN
+        ),
+        Type::Dict(key, value) => format!(
+            "proptest::collection::hash_map({}, {}, 0..{})",
+            strategy(key)?,
+            strategy(value)?,
+            MAX_LEN
+        ),
+        Type::Optional(inner) => format!("proptest::option::of({})", strategy(inner)?),
+        Type::Tuple(items) if (1..=4).contains(&items.len()) => {
+            let items = items.iter().map(strategy).collect::<Option<Vec<_>>>()?;
+            format!("({},)", items.join(", "))
+        }
+        _ => return None,
+    };
+    Some(strategy)
+}
+
+/// The test items for `func`, indented for a module body, or `None` when
+/// neither its parameters nor `recorded` allow a test
+pub fn generate_proptests(
+    func: &HirFunction,
+    cases: usize,
+    recorded: &[TestCase],
+) -> Option<String> {
+    if func.properties.is_async || func.properties.is_generator {
+        return None;
+    }
+    let mut items = String::new();
+    if let Some(test) = panic_freedom_test(func, cases) {
+        items.push_str(&test);
+    }
+    let examples: Vec<String> = recorded
+        .iter()
+        .filter_map(|case| example(func, case))
+        .collect();
+    if !examples.is_empty() {
+        items.push_str("    #[test]\n");
+        items.push_str(&format!("    fn test_{}_matches_python() {{\n", func.name));
+        for example in examples {
+            items.push_str(&format!("        {}\n", example));
+        }
+        items.push_str("    }\n\n");
+    }
+    (!items.is_empty()).then_some(items)
+}
+
+/// `rust_code` with `items` added to its `mod tests`, which is created when
+/// there is none
+pub fn insert_into_tests_module(rust_code: &str, items: &str) -> String {
+    const TESTS_MODULE: &str = "mod tests {\n";
+    let start = match rust_code.find(TESTS_MODULE) {
+        Some(start) => start + TESTS_MODULE.len(),
+        None => {
+            return format!(
+                "{}\n#[cfg(test)]\nmod tests {{\n    use super::*;\n\n{}}}\n",
+                rust_code.trim_end(),
+                items
+            )
+        }
+    };
+    // After the module's imports
+    let mut at = start;
+    for line in rust_code[start..].split_inclusive('\n') {
+        if !line.trim_start().starts_with("use ") {
+            break;
+        }
+        at += line.len();
+    }
+    format!("{}\n{}{}", &rust_code[..at], items, &rust_code[at..])
+}
+
+fn panic_freedom_test(func: &HirFunction, cases: usize) -> Option<String> {
+    if func.params.is_empty() {
+        return None;
+    }
+    let strategies = func
+        .params
+        .iter()
+        .map(|param| strategy(&param.ty))
+        .collect::<Option<Vec<_>>>()?;
+    let bindings: Vec<String> = func
+        .params
+        .iter()
+        .zip(&strategies)
+        .map(|(param, strategy)| format!("{} in {}", param.name, strategy))
+        .collect();
+    let args: Vec<String> = func
+        .params
+        .iter()
+        .map(|param| argument(&param.ty, &param.name))
+        .collect();
+    let call = format!("{}({})", func.name, args.join(", "));
+    let call = if func.ret_type == Type::None && !func.properties.can_fail {
+        format!("{};", call)
+    } else {
+        format!("let _ = {};", call)
+    };
+
+    let mut test = String::new();
+    test.push_str("    proptest::proptest! {\n");
+    test.push_str(&format!(
+        "        #![proptest_config(proptest::test_runner::Config::with_cases({}))]\n",
+        cases
+    ));
+    test.push_str("        #[test]\n");
+    test.push_str(&format!(
+        "        fn proptest_{}_does_not_panic({}) {{\n",
+        func.name,
+        bindings.join(", ")
+    ));
+    test.push_str(&format!("            {}\n", call));
+    test.push_str("        }\n");
+    test.push_str("    }\n\n");
+    Some(test)
+}
+
+/// `name` passed the way the generated signature takes a `ty`
+fn argument(ty: &Type, name: &str) -> String {
+    match ty {
+        // `&str`, `String` and `Cow<str>` parameters all convert from `&str`
+        Type::String => format!("(&*{}).into()", name),
+        Type::List(_) | Type::Dict(_, _) | Type::Set(_) => format!("&{}", name),
+        _ => name.to_string(),
+    }
+}
+
+/// One assertion replaying a recorded call
+fn example(func: &HirFunction, case: &TestCase) -> Option<String> {
+    if case.inputs.len() != func.params.len() {
+        return None;
+    }
+    let args = func
+        .params
+        .iter()
+        .zip(&case.inputs)
+        .map(|(param, value)| literal_argument(&param.ty, value))
+        .collect::<Option<Vec<_>>>()?;
+    let call = format!("{}({})", func.name, args.join(", "));
+
+    if case.error.is_some() {
+        // Only a `Result` lets the Rust function report the exception
+        return func
+            .properties
+            .can_fail
+            .then(|| format!("assert!({}.is_err());", call));
+    }
+    let call = if func.properties.can_fail {
+        format!("{}.unwrap()", call)
+    } else {
+        call
+    };
+    match (&func.ret_type, &case.expected_output) {
+        (Type::None, _) | (_, None) => Some(format!("{};", call)),
+        (Type::Float, Some(expected)) => Some(format!(
+            "assert!(({} - {}).abs() < 1e-9);",
+            call,
+            literal(&Type::Float, expected)?
+        )),
+        (ty, Some(expected)) => Some(format!("assert_eq!({}, {});", call, literal(ty, expected)?)),
+    }
+}
+
+/// The JSON of a Python value as an argument to a parameter of type `ty`
+pub(crate) fn literal_argument(ty: &Type, value: &Value) -> Option<String> {
+    match (ty, value) {
+        (Type::String, Value::String(s)) => Some(format!("{:?}.into()", s)),
+        (Type::List(_) | Type::Dict(_, _) | Type::Set(_), _) => {
+            Some(format!("&{}", literal(ty, value)?))
+        }
+        _ => literal(ty, value),
+    }
+}
+
+/// A Rust expression of type `ty` for the JSON of a Python value
+fn literal(ty: &Type, value: &Value) -> Option<String> {
+    let literal = match (ty, value) {
+        (Type::Int, Value::Number(n)) => n.as_i64()?.to_string(),
+        (Type::Float, Value::Number(n)) => format!("{:?}", n.as_f64()?),
+        (Type::Bool, Value::Bool(b)) => b.to_string(),
+        (Type::String, Value::String(s)) => format!("{:?}.to_string()", s),
+        (Type::Optional(_), Value::Null) => "None".to_string(),
+        (Type::Optional(inner), value) => format!("Some({})", literal(inner, value)?),
+        (Type::List(inner), Value::Array(items)) => {
+            let items = items
+                .iter()
+                .map(|item| literal(inner, item))
+                .collect::<Option<Vec<_>>>()?;
+            format!("vec![{}]", items.join(", "))
+        }
+        (Type::Set(inner), Value::Array(items)) => {
+            let items = items
+                .iter()
+                .map(|item| literal(inner, item))
+                .collect::<Option<Vec<_>>>()?;
+            format!("std::collections::HashSet::from([{}])", items.join(", "))
+        }
+        (Type::Dict(key_type, value_type), Value::Object(entries)) => {
+            let entries = entries
+                .iter()
+                .map(|(key, value)| {
+                    // JSON object keys are always strings
+                    let key = match key_type.as_ref() {
+                        Type::String => Value::String(key.clone()),
+                        Type::Int => Value::from(key.parse::<i64>().ok()?),
+                        _ => return None,
+                    };
+                    Some(format!(
+                        "({}, {})",
+                        literal(key_type, &key)?,
+                        literal(value_type, value)?
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?;
+            format!("std::collections::HashMap::from([{}])", entries.join(", "))
+        }
+        (Type::Tuple(types), Value::Array(items)) if types.len() == items.len() => {
+            let items = types
+                .iter()
+                .zip(items)
+                .map(|(ty, item)| literal(ty, item))
+                .collect::<Option<Vec<_>>>()?;
+            format!("({},)", items.join(", "))
+        }
+        _ => return None,
+    };
+    Some(literal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use depyler_core::hir_builder::HirFunctionBuilder;
+    use serde_json::json;
+
+    fn recorded(inputs: Vec<Value>, output: Value) -> TestCase {
+        TestCase {
+            inputs,
+            expected_output: Some(output),
+            actual_output: None,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_strategies_follow_the_type() {
+        assert_eq!(strategy(&Type::Int).unwrap(), "-10_000i32..=10_000");
+        assert_eq!(
+            strategy(&Type::List(Box::new(Type::Optional(Box::new(
+                Type::String
+            )))))
+            .unwrap(),
+            "proptest::collection::vec(proptest::option::of(\"[ -~]{0,16}\"), 0..8)"
+        );
+        assert!(strategy(&Type::Custom("Point".to_string())).is_none());
+        assert!(strategy(&Type::List(Box::new(Type::Unknown))).is_none());
+    }
+
+    #[test]
+    fn test_panic_freedom_test_calls_with_generated_arguments() {
+        let func = HirFunctionBuilder::new("count_words")
+            .param("text", Type::String)
+            .param("limits", Type::List(Box::new(Type::Int)))
+            .returns(Type::Int)
+            .build();
+        let items = generate_proptests(&func, 256, &[]).unwrap();
+
+        assert!(items.contains("Config::with_cases(256)"));
+        assert!(items.contains(
+            "fn proptest_count_words_does_not_panic(text in \"[ -~]{0,16}\", limits in proptest::collection::vec(-10_000i32..=10_000, 0..8))"
+        ));
+        assert!(items.contains("let _ = count_words((&*text).into(), &limits);"));
+        assert!(!items.contains("matches_python"));
+    }
+
+    #[test]
+    fn test_recorded_calls_become_assertions() {
+        let func = HirFunctionBuilder::new("scale")
+            .param("values", Type::List(Box::new(Type::Int)))
+            .param("name", Type::String)
+            .returns(Type::Dict(Box::new(Type::String), Box::new(Type::Int)))
+            .build();
+        let cases = [
+            recorded(vec![json!([1, 2]), json!("a")], json!({"a": 3})),
+            // Not a list of ints, so left out
+            recorded(vec![json!("x"), json!("a")], json!({})),
+        ];
+        let items = generate_proptests(&func, 10, &cases).unwrap();
+
+        assert!(items.contains("fn test_scale_matches_python()"));
+        assert!(items.contains(
+            "assert_eq!(scale(&vec![1, 2], \"a\".into()), std::collections::HashMap::from([(\"a\".to_string(), 3)]));"
+        ));
+        assert_eq!(items.matches("assert_eq!").count(), 1);
+    }
+
+    #[test]
+    fn test_items_go_into_the_tests_module() {
+        let items = "    #[test]\n    fn new_test() {}\n";
+        let code = "fn f() {}\n#[cfg(test)]\nmod tests {\n    use super::*;\n    use quickcheck::quickcheck;\n    #[test]\n    fn old_test() {}\n}\n";
+
+        let updated = insert_into_tests_module(code, items);
+        assert_eq!(updated.matches("mod tests").count(), 1);
+        assert!(updated.contains("use quickcheck::quickcheck;\n\n    #[test]\n    fn new_test()"));
+
+        let created = insert_into_tests_module("fn f() {}\n", items);
+        assert!(created
+            .ends_with("mod tests {\n    use super::*;\n\n    #[test]\n    fn new_test() {}\n}\n"));
+    }
+}
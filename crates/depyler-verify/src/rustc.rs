@@ -0,0 +1,255 @@
+//! Compile generated Rust with rustc and report errors against the Python
+//!
+//! The generated file is piped to `rustc --edition 2021 --crate-type lib
+//! --error-format=json --emit=metadata`, so nothing is written but the
+//! metadata output in a scratch directory. Each JSON diagnostic is resolved
+//! through the [`SourceMapTable`] to the Python definition whose code it
+//! points into.
+
+use anyhow::{bail, Context, Result};
+use depyler_core::rust_gen::source_map::{LineRange, SourceMapTable};
+use depyler_core::DepylerPipeline;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Runs rustc over transpiled code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RustcVerifier {
+    /// The rustc binary
+    pub rustc: PathBuf,
+    pub edition: String,
+    /// `-L` directories holding the crates generated code depends on
+    pub library_paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiagnosticLevel {
+    Error,
+    Warning,
+}
+
+/// A rustc diagnostic on the generated code
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustcDiagnostic {
+    pub level: DiagnosticLevel,
+    /// Error code such as `E0308`
+    pub code: Option<String>,
+    pub message: String,
+    /// Line of the primary span in the generated Rust
+    pub rust_line: Option<usize>,
+    /// Python definition containing the code the diagnostic points at
+    pub python_symbol: Option<String>,
+    /// The Python statement, or the whole definition when the statement
+    /// is not known
+    pub python_lines: Option<LineRange>,
+    pub python_column: Option<usize>,
+    /// rustc's own rendering, with the Rust snippet
+    pub rendered: Option<String>,
+}
+
+/// Everything rustc said about one file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustcReport {
+    pub diagnostics: Vec<RustcDiagnostic>,
+}
+
+impl RustcReport {
+    pub fn errors(&self) -> impl Iterator<Item = &RustcDiagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.level == DiagnosticLevel::Error)
+    }
+
+    pub fn compiles(&self) -> bool {
+        self.errors().next().is_none()
+    }
+}
+
+impl Default for RustcVerifier {
+    fn default() -> Self {
+        Self {
+            rustc: PathBuf::from("rustc"),
+            edition: "2021".to_string(),
+            library_paths: Vec::new(),
+        }
+    }
+}
+
+impl RustcVerifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rustc(mut self, rustc: impl Into<PathBuf>) -> Self {
+        self.rustc = rustc.into();
+        self
+    }
+
+    pub fn with_library_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.library_paths.push(path.into());
+        self
+    }
+
+    /// Transpiles `python_source` and compiles the result
+    pub fn verify_python(
+        &self,
+        pipeline: &DepylerPipeline,
+        python_source: &str,
+    ) -> Result<RustcReport> {
+        let (rust_code, source_map) = pipeline.transpile_with_source_map(python_source)?;
+        self.verify(&rust_code, &source_map)
+    }
+
+    /// Compiles `rust_code` as a library crate, mapping diagnostics through
+    /// `source_map`
+    pub fn verify(&self, rust_code: &str, source_map: &SourceMapTable) -> Result<RustcReport> {
+        let out_dir = scratch_dir("rustc")?;
+        let result = self.run_rustc(rust_code, &out_dir);
+        // Best effort: only the metadata file is left behind
+        let _ = std::fs::remove_dir_all(&out_dir);
+        let stderr = result?;
+        Ok(parse_diagnostics(&stderr, source_map))
+    }
+
+    fn run_rustc(&self, rust_code: &str, out_dir: &std::path::Path) -> Result<String> {
+        let mut command = Command::new(&self.rustc);
+        command
+            .args(["-", "--crate-type", "lib", "--crate-name", "depyler_verify"])
+            .args(["--edition", &self.edition])
+            .args(["--error-format=json", "--emit=metadata"])
+            .arg("--out-dir")
+            .arg(out_dir);
+        for path in &self.library_paths {
+            command.arg("-L").arg(path);
+        }
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to run {}", self.rustc.display()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(rust_code.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        // A failed build always explains itself in JSON; anything else is
+        // rustc itself failing
+        if !output.status.success() && !stderr.lines().any(|l| l.starts_with('{')) {
+            bail!("rustc failed: {}", stderr.trim());
+        }
+        Ok(stderr)
+    }
+}
+
+/// A fresh directory under the system temp dir, named after `purpose`
+pub(crate) fn scratch_dir(purpose: &str) -> Result<PathBuf> {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "depyler-{}-{}-{}",
+        purpose,
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// The fields of rustc's JSON diagnostics used here
+#[derive(Deserialize)]
+struct JsonDiagnostic {
+    message: String,
+    code: Option<JsonCode>,
+    level: String,
+    spans: Vec<JsonSpan>,
+    rendered: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsonCode {
+    code: String,
+}
+
+#[derive(Deserialize)]
+struct JsonSpan {
+    line_start: usize,
+    is_primary: bool,
+}
+
+/// Errors and warnings from rustc's JSON output, skipping the
+/// `aborting due to` summary and notes about other diagnostics
+fn parse_diagnostics(stderr: &str, source_map: &SourceMapTable) -> RustcReport {
+    let diagnostics = stderr
+        .lines()
+        .filter_map(|line| serde_json::from_str::<JsonDiagnostic>(line).ok())
+        .filter_map(|diagnostic| {
+            let level = match diagnostic.level.as_str() {
+                "error" => DiagnosticLevel::Error,
+                "warning" => DiagnosticLevel::Warning,
+                _ => return None,
+            };
+            if diagnostic.spans.is_empty() && diagnostic.message.starts_with("aborting due to") {
+                return None;
+            }
+            let rust_line = diagnostic
+                .spans
+                .iter()
+                .find(|span| span.is_primary)
+                .map(|span| span.line_start);
+            let entry = rust_line.and_then(|line| source_map.lookup(line));
+            Some(RustcDiagnostic {
+                level,
+                code: diagnostic.code.map(|c| c.code),
+                message: diagnostic.message,
+                rust_line,
+                python_symbol: entry.map(|e| e.python_symbol.clone()),
+                python_lines: entry.map(|e| e.python_lines),
+                python_column: entry.map(|e| e.python_column),
+                rendered: diagnostic.rendered,
+            })
+        })
+        .collect();
+    RustcReport { diagnostics }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PYTHON: &str = "def half(x: int) -> int:\n    return x / 2\n";
+    const RUST: &str = "pub fn half(x: i32) -> i32 {\n    let y: i32 = \"two\";\n    x / y\n}\n";
+
+    fn source_map() -> SourceMapTable {
+        SourceMapTable::build(PYTHON, RUST).unwrap()
+    }
+
+    #[test]
+    fn test_errors_are_mapped_to_python_lines() {
+        let stderr = concat!(
+            r#"{"$message_type":"diagnostic","message":"mismatched types","code":{"code":"E0308","explanation":null},"level":"error","spans":[{"file_name":"<anon>","line_start":2,"line_end":2,"is_primary":true}],"children":[],"rendered":"error[E0308]: mismatched types\n"}"#,
+            "\n",
+            r#"{"$message_type":"diagnostic","message":"aborting due to 1 previous error","code":null,"level":"error","spans":[],"children":[],"rendered":"error: aborting due to 1 previous error\n"}"#,
+            "\n",
+        );
+        let report = parse_diagnostics(stderr, &source_map());
+        assert_eq!(report.diagnostics.len(), 1);
+        let error = &report.diagnostics[0];
+        assert_eq!(error.code.as_deref(), Some("E0308"));
+        assert_eq!(error.rust_line, Some(2));
+        assert_eq!(error.python_symbol.as_deref(), Some("half"));
+        assert_eq!(error.python_lines, Some(LineRange { start: 1, end: 2 }));
+        assert!(!report.compiles());
+    }
+
+    #[test]
+    fn test_warnings_alone_still_compile() {
+        let stderr = r#"{"$message_type":"diagnostic","message":"unused variable: `y`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"<anon>","line_start":2,"line_end":2,"is_primary":true}],"children":[],"rendered":null}"#;
+        let report = parse_diagnostics(stderr, &source_map());
+        assert_eq!(report.diagnostics[0].level, DiagnosticLevel::Warning);
+        assert!(report.compiles());
+    }
+}
@@ -0,0 +1,460 @@
+//! Memory-safety audit of generated Rust
+//!
+//! Transpiled code is safe Rust unless a mapping says otherwise, but some
+//! safe constructs still behave unlike the Python they came from: `as`
+//! casts truncate and wrap where Python ints grow, and indexing panics
+//! where Python raises. [`MemorySafetyReport::audit`] parses the generated
+//! file and lists every such construct, and anything that leaves safe Rust
+//! altogether, with a severity and the line and column it is on:
+//!
+//! | Pattern                           | Example                      | Severity |
+//! |-----------------------------------|------------------------------|----------|
+//! | [`AuditPattern::Transmute`]       | `std::mem::transmute(x)`     | Critical |
+//! | [`AuditPattern::Unsafe`]          | `unsafe { .. }`, `unsafe fn` | High     |
+//! | [`AuditPattern::UncheckedAccess`] | `v.get_unchecked(i)`         | High     |
+//! | [`AuditPattern::RawPointer`]      | `*const T`, `*mut T`         | High     |
+//! | [`AuditPattern::NarrowingCast`]   | `n as i32`, `x as u8`        | Medium   |
+//! | [`AuditPattern::SignCast`]        | `i as usize`                 | Low      |
+//! | [`AuditPattern::RawIndex`]        | `v[i]`, `s[a..b]`            | Low      |
+//!
+//! Casts of literals, whose value is known, and code under `#[cfg(test)]`
+//! are left out.
+
+use anyhow::{Context, Result};
+use depyler_core::rust_gen::source_map::{LineRange, SourceMapTable};
+use depyler_core::DepylerPipeline;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// How far a finding is from idiomatic safe Rust
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditSeverity {
+    /// Safe, but panics where Python would raise
+    Low,
+    /// Safe, but silently computes something Python would not
+    Medium,
+    /// Outside safe Rust; sound only if the generator got it right
+    High,
+    /// Reinterprets memory; almost never what a Python program meant
+    Critical,
+}
+
+impl fmt::Display for AuditSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditSeverity::Low => "low",
+            AuditSeverity::Medium => "medium",
+            AuditSeverity::High => "high",
+            AuditSeverity::Critical => "critical",
+        })
+    }
+}
+
+/// The kinds of construct the audit reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditPattern {
+    /// `mem::transmute` and `mem::transmute_copy`
+    Transmute,
+    /// An `unsafe` block, function, impl or trait
+    Unsafe,
+    /// A `*_unchecked` function or method, like `get_unchecked`
+    UncheckedAccess,
+    /// A `*const T` or `*mut T` type
+    RawPointer,
+    /// `as` to an integer or float type narrower than 64 bits
+    NarrowingCast,
+    /// `as` to an unsigned 64-bit or pointer-sized integer, which wraps
+    /// negative values
+    SignCast,
+    /// `base[index]`, which panics when the index is out of range
+    RawIndex,
+}
+
+impl AuditPattern {
+    pub fn severity(self) -> AuditSeverity {
+        match self {
+            AuditPattern::Transmute => AuditSeverity::Critical,
+            AuditPattern::Unsafe | AuditPattern::UncheckedAccess | AuditPattern::RawPointer => {
+                AuditSeverity::High
+            }
+            AuditPattern::NarrowingCast => AuditSeverity::Medium,
+            AuditPattern::SignCast | AuditPattern::RawIndex => AuditSeverity::Low,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            AuditPattern::Transmute => "reinterprets the bits of a value as another type",
+            AuditPattern::Unsafe => "opts out of the borrow checker's guarantees",
+            AuditPattern::UncheckedAccess => "skips a bounds or validity check",
+            AuditPattern::RawPointer => "uses a raw pointer",
+            AuditPattern::NarrowingCast => "truncates values that do not fit",
+            AuditPattern::SignCast => "wraps negative values to large positive ones",
+            AuditPattern::RawIndex => "panics when the index is out of range",
+        }
+    }
+}
+
+/// One construct found by the audit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditFinding {
+    pub pattern: AuditPattern,
+    pub severity: AuditSeverity,
+    /// 1-based line in the generated Rust
+    pub line: usize,
+    /// 1-based column in the generated Rust
+    pub column: usize,
+    /// `area`, `Shape::area`; `None` outside functions
+    pub rust_symbol: Option<String>,
+    /// The line of Rust the construct is on, trimmed
+    pub snippet: String,
+    /// Python definition the code was generated from, when a source map
+    /// was given
+    pub python_symbol: Option<String>,
+    pub python_lines: Option<LineRange>,
+}
+
+impl fmt::Display for AuditFinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] `{}`", self.severity, self.snippet)?;
+        if let Some(symbol) = &self.rust_symbol {
+            write!(f, " in `{}`", symbol)?;
+        }
+        write!(f, " {}", self.pattern.describe())
+    }
+}
+
+/// Every audited construct in one generated file, in source order
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySafetyReport {
+    pub findings: Vec<AuditFinding>,
+}
+
+impl MemorySafetyReport {
+    /// Audits `rust_code`, which must parse as a Rust file
+    pub fn audit(rust_code: &str) -> Result<Self> {
+        let file = syn::parse_file(rust_code).context("cannot audit invalid Rust")?;
+        let mut auditor = Auditor {
+            lines: rust_code.lines().collect(),
+            symbol: None,
+            self_ty: None,
+            findings: Vec::new(),
+        };
+        auditor.visit_file(&file);
+        let mut findings = auditor.findings;
+        findings.sort_by_key(|f| (f.line, f.column));
+        Ok(Self { findings })
+    }
+
+    /// Audits `rust_code`, attributing each finding to the Python definition
+    /// `source_map` pairs its line with
+    pub fn audit_mapped(rust_code: &str, source_map: &SourceMapTable) -> Result<Self> {
+        let mut report = Self::audit(rust_code)?;
+        for finding in &mut report.findings {
+            if let Some(entry) = source_map.lookup(finding.line) {
+                finding.python_symbol = Some(entry.python_symbol.clone());
+                finding.python_lines = Some(entry.python_lines);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Transpiles `python_source` and audits the result
+    pub fn audit_python(pipeline: &DepylerPipeline, python_source: &str) -> Result<Self> {
+        let (rust_code, source_map) = pipeline.transpile_with_source_map(python_source)?;
+        Self::audit_mapped(&rust_code, &source_map)
+    }
+
+    /// Findings of `severity` or worse
+    pub fn at_least(&self, severity: AuditSeverity) -> impl Iterator<Item = &AuditFinding> {
+        self.findings.iter().filter(move |f| f.severity >= severity)
+    }
+
+    /// The worst severity found, if anything was
+    pub fn max_severity(&self) -> Option<AuditSeverity> {
+        self.findings.iter().map(|f| f.severity).max()
+    }
+
+    /// Whether the file stays within safe Rust
+    pub fn is_safe(&self) -> bool {
+        self.at_least(AuditSeverity::High).next().is_none()
+    }
+}
+
+/// Integer and float types an `as` cast can lose information converting to
+const NARROW_TYPES: &[&str] = &["i8", "i16", "i32", "u8", "u16", "u32", "f32"];
+
+/// Unsigned types wide enough for any `i64` magnitude
+const UNSIGNED_TYPES: &[&str] = &["u64", "u128", "usize"];
+
+struct Auditor<'a> {
+    lines: Vec<&'a str>,
+    /// Function being visited
+    symbol: Option<String>,
+    /// Type of the impl being visited
+    self_ty: Option<String>,
+    findings: Vec<AuditFinding>,
+}
+
+impl Auditor<'_> {
+    fn report(&mut self, pattern: AuditPattern, node: &impl Spanned) {
+        let start = node.span().start();
+        let snippet = self
+            .lines
+            .get(start.line.saturating_sub(1))
+            .map(|line| line.trim().to_string())
+            .unwrap_or_default();
+        self.findings.push(AuditFinding {
+            pattern,
+            severity: pattern.severity(),
+            line: start.line,
+            column: start.column + 1,
+            rust_symbol: self.symbol.clone(),
+            snippet,
+            python_symbol: None,
+            python_lines: None,
+        });
+    }
+
+    fn in_function(&mut self, name: &syn::Ident, visit: impl FnOnce(&mut Self)) {
+        let name = match &self.self_ty {
+            Some(ty) => format!("{}::{}", ty, name),
+            None => name.to_string(),
+        };
+        let outer = self.symbol.replace(name);
+        visit(self);
+        self.symbol = outer;
+    }
+}
+
+fn is_cfg_test(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path().is_ident("cfg")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|arg| arg == "test")
+    })
+}
+
+fn last_segment(path: &syn::Path) -> Option<String> {
+    path.segments.last().map(|s| s.ident.to_string())
+}
+
+impl<'ast> Visit<'ast> for Auditor<'_> {
+    fn visit_item_mod(&mut self, module: &'ast syn::ItemMod) {
+        if !is_cfg_test(&module.attrs) {
+            visit::visit_item_mod(self, module);
+        }
+    }
+
+    fn visit_item_fn(&mut self, func: &'ast syn::ItemFn) {
+        if is_cfg_test(&func.attrs) {
+            return;
+        }
+        if let Some(unsafety) = &func.sig.unsafety {
+            self.report(AuditPattern::Unsafe, unsafety);
+        }
+        self.in_function(&func.sig.ident, |this| visit::visit_item_fn(this, func));
+    }
+
+    fn visit_item_impl(&mut self, item: &'ast syn::ItemImpl) {
+        if is_cfg_test(&item.attrs) {
+            return;
+        }
+        if let Some(unsafety) = &item.unsafety {
+            self.report(AuditPattern::Unsafe, unsafety);
+        }
+        let self_ty = match &*item.self_ty {
+            syn::Type::Path(path) => last_segment(&path.path),
+            _ => None,
+        };
+        let outer = std::mem::replace(&mut self.self_ty, self_ty);
+        visit::visit_item_impl(self, item);
+        self.self_ty = outer;
+    }
+
+    fn visit_impl_item_fn(&mut self, func: &'ast syn::ImplItemFn) {
+        if let Some(unsafety) = &func.sig.unsafety {
+            self.report(AuditPattern::Unsafe, unsafety);
+        }
+        self.in_function(&func.sig.ident, |this| {
+            visit::visit_impl_item_fn(this, func)
+        });
+    }
+
+    fn visit_item_trait(&mut self, item: &'ast syn::ItemTrait) {
+        if let Some(unsafety) = &item.unsafety {
+            self.report(AuditPattern::Unsafe, unsafety);
+        }
+        visit::visit_item_trait(self, item);
+    }
+
+    fn visit_expr_unsafe(&mut self, expr: &'ast syn::ExprUnsafe) {
+        self.report(AuditPattern::Unsafe, expr);
+        visit::visit_expr_unsafe(self, expr);
+    }
+
+    fn visit_expr_call(&mut self, call: &'ast syn::ExprCall) {
+        if let syn::Expr::Path(func) = &*call.func {
+            match last_segment(&func.path).as_deref() {
+                Some("transmute" | "transmute_copy") => self.report(AuditPattern::Transmute, call),
+                Some(name) if name.ends_with("_unchecked") => {
+                    self.report(AuditPattern::UncheckedAccess, call)
+                }
+                _ => {}
+            }
+        }
+        visit::visit_expr_call(self, call);
+    }
+
+    fn visit_expr_method_call(&mut self, call: &'ast syn::ExprMethodCall) {
+        if call.method.to_string().ends_with("_unchecked") {
+            self.report(AuditPattern::UncheckedAccess, &call.method);
+        }
+        visit::visit_expr_method_call(self, call);
+    }
+
+    fn visit_type_ptr(&mut self, ptr: &'ast syn::TypePtr) {
+        self.report(AuditPattern::RawPointer, ptr);
+        visit::visit_type_ptr(self, ptr);
+    }
+
+    fn visit_expr_cast(&mut self, cast: &'ast syn::ExprCast) {
+        let is_literal = match &*cast.expr {
+            syn::Expr::Lit(_) => true,
+            syn::Expr::Unary(unary) => {
+                matches!(unary.op, syn::UnOp::Neg(_)) && matches!(&*unary.expr, syn::Expr::Lit(_))
+            }
+            _ => false,
+        };
+        let target = match &*cast.ty {
+            syn::Type::Path(path) if path.qself.is_none() => {
+                path.path.get_ident().map(|i| i.to_string())
+            }
+            _ => None,
+        };
+        if let (false, Some(target)) = (is_literal, target.as_deref()) {
+            if NARROW_TYPES.contains(&target) {
+                self.report(AuditPattern::NarrowingCast, cast);
+            } else if UNSIGNED_TYPES.contains(&target) {
+                self.report(AuditPattern::SignCast, cast);
+            }
+        }
+        visit::visit_expr_cast(self, cast);
+    }
+
+    fn visit_expr_index(&mut self, index: &'ast syn::ExprIndex) {
+        self.report(AuditPattern::RawIndex, index);
+        visit::visit_expr_index(self, index);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RUST: &str = r#"pub fn checksum(data: &Vec<i64>) -> i32 {
+    let mut total: i64 = 0;
+    for i in 0..data.len() {
+        total += data[i];
+    }
+    let first = unsafe { *data.get_unchecked(0) };
+    (total + first) as i32
+}
+
+pub struct Buffer {
+    pub bytes: Vec<u8>,
+}
+
+impl Buffer {
+    pub fn at(&self, i: i64) -> u8 {
+        self.bytes[i as usize]
+    }
+
+    pub fn width(&self) -> u32 {
+        8 as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_index() {
+        let v = vec![1];
+        assert_eq!(v[0] as u8, 1);
+    }
+}
+"#;
+
+    fn found(report: &MemorySafetyReport) -> Vec<(AuditPattern, usize, Option<&str>)> {
+        report
+            .findings
+            .iter()
+            .map(|f| (f.pattern, f.line, f.rust_symbol.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn test_patterns_are_found_with_their_locations() {
+        let report = MemorySafetyReport::audit(RUST).unwrap();
+        assert_eq!(
+            found(&report),
+            [
+                (AuditPattern::RawIndex, 4, Some("checksum")),
+                (AuditPattern::Unsafe, 6, Some("checksum")),
+                (AuditPattern::UncheckedAccess, 6, Some("checksum")),
+                (AuditPattern::NarrowingCast, 7, Some("checksum")),
+                (AuditPattern::RawIndex, 16, Some("Buffer::at")),
+                (AuditPattern::SignCast, 16, Some("Buffer::at")),
+            ]
+        );
+        assert_eq!(report.findings[0].column, 18);
+        assert_eq!(report.findings[0].snippet, "total += data[i];");
+        assert_eq!(report.max_severity(), Some(AuditSeverity::High));
+        assert!(!report.is_safe());
+        assert_eq!(report.at_least(AuditSeverity::Medium).count(), 3);
+    }
+
+    #[test]
+    fn test_transmute_is_critical() {
+        let report = MemorySafetyReport::audit(
+            "pub fn bits(x: f64) -> u64 {\n    unsafe { std::mem::transmute(x) }\n}\n",
+        )
+        .unwrap();
+        let transmute = report
+            .findings
+            .iter()
+            .find(|f| f.pattern == AuditPattern::Transmute)
+            .unwrap();
+        assert_eq!(transmute.severity, AuditSeverity::Critical);
+        assert_eq!(
+            transmute.to_string(),
+            "[critical] `unsafe { std::mem::transmute(x) }` in `bits` reinterprets the bits \
+             of a value as another type"
+        );
+    }
+
+    #[test]
+    fn test_raw_pointers_and_unsafe_impls() {
+        let report = MemorySafetyReport::audit(
+            "pub struct Handle(*mut u8);\nunsafe impl Send for Handle {}\n",
+        )
+        .unwrap();
+        let patterns: Vec<AuditPattern> = report.findings.iter().map(|f| f.pattern).collect();
+        assert_eq!(patterns, [AuditPattern::RawPointer, AuditPattern::Unsafe]);
+    }
+
+    #[test]
+    fn test_safe_code_is_clean() {
+        let report =
+            MemorySafetyReport::audit("pub fn add(a: i64, b: i64) -> i64 {\n    a + b\n}\n")
+                .unwrap();
+        assert!(report.findings.is_empty());
+        assert!(report.is_safe());
+        assert_eq!(report.max_severity(), None);
+    }
+}
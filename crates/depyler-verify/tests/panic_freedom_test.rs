@@ -0,0 +1,68 @@
+//! Panic sites found in parsed Python, and the ones loop bounds and guards
+//! rule out
+
+use depyler_core::DepylerPipeline;
+use depyler_verify::panic_freedom::{prove_module, PanicKind};
+
+const SOURCE: &str = r#"
+def total(xs: list[int]) -> int:
+    result = 0
+    for i in range(len(xs)):
+        result += xs[i]
+    return result
+
+def ratio(a: int, b: int) -> int:
+    if b != 0:
+        return a // b
+    return 0
+
+def lookup(d: dict[str, int], k: str) -> int:
+    n = len(d)
+    return d[k]
+
+def neighbours(xs: list[int]) -> int:
+    s = 0
+    for i in range(1, len(xs) - 1):
+        s += xs[i - 1] + xs[i + 1]
+    return s
+"#;
+
+#[test]
+fn test_residual_sites_have_python_lines() {
+    let (mut module, spans) = DepylerPipeline::new()
+        .parse_to_hir_with_spans(SOURCE)
+        .unwrap();
+    let sites = prove_module(&mut module);
+
+    assert_eq!(sites.len(), 1, "{sites:?}");
+    assert_eq!(sites[0].kind, PanicKind::Index);
+    assert_eq!(sites[0].function, "lookup");
+    assert_eq!(sites[0].expr, "d[k]");
+    assert_eq!(sites[0].line(&spans), Some(15));
+
+    let panic_free: Vec<(&str, bool)> = module
+        .functions
+        .iter()
+        .map(|f| (f.name.as_str(), f.properties.panic_free))
+        .collect();
+    assert_eq!(
+        panic_free,
+        [
+            ("total", true),
+            ("ratio", true),
+            ("lookup", false),
+            ("neighbours", true)
+        ]
+    );
+}
+
+#[test]
+fn test_proven_functions_are_marked_in_generated_code() {
+    let pipeline = DepylerPipeline::new();
+    let mut module = pipeline.parse_to_hir(SOURCE).unwrap();
+    prove_module(&mut module);
+    let rust_code = pipeline.transpile_hir(module).unwrap();
+
+    let marked = rust_code.matches("Depyler: verified panic-free").count();
+    assert_eq!(marked, 3);
+}
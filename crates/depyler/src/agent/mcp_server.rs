@@ -905,7 +905,7 @@ async fn analyze_python_compatibility(source: &str) -> Result<Value> {
     let lines = source.lines().collect::<Vec<_>>();
     let mut supported_features = Vec::new();
     let mut warnings = Vec::new();
-    let mut unsupported_features = Vec::new();
+    let mut unsupported_features: Vec<String> = Vec::new();
 
     // Check for basic Python constructs
     if source.contains("def ") {
@@ -921,9 +921,19 @@ async fn analyze_python_compatibility(source: &str) -> Result<Value> {
         supported_features.push("Conditionals");
     }
 
-    // Check for potentially problematic constructs
-    if source.contains("eval") || source.contains("exec") {
-        unsupported_features.push("Dynamic code execution (eval/exec)");
+    // Check for potentially problematic constructs. eval() of a constant
+    // expression is folded while parsing; every call left runs code that
+    // the transpiled program cannot, and counts against the score.
+    match DepylerPipeline::new().parse_to_hir(source) {
+        Ok(module) => unsupported_features.extend(
+            depyler_core::dynamic_code::find(&module)
+                .iter()
+                .map(|call| format!("Dynamic code execution: {}", call)),
+        ),
+        Err(_) if source.contains("eval(") || source.contains("exec(") => {
+            unsupported_features.push("Dynamic code execution (eval/exec)".to_string());
+        }
+        Err(_) => {}
     }
     if source.contains("import sys") {
         warnings.push("System imports may need manual handling");
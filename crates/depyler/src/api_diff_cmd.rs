@@ -0,0 +1,133 @@
+//! API diff command
+//!
+//! Compares the public surface of two generated outputs, e.g. a crate
+//! transpiled with the current depyler and the same sources regenerated
+//! after an upgrade, and reports the semver bump the changes require.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use depyler_core::api_diff::{ApiDiff, ChangeKind, PublicApi, SemverImpact};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct ApiDiffArgs {
+    /// Previously generated Rust file, or a crate directory (uses src/lib.rs)
+    pub old: PathBuf,
+
+    /// Newly generated Rust file, or a crate directory (uses src/lib.rs)
+    pub new: PathBuf,
+
+    /// Output format (text, json)
+    #[arg(short, long, default_value = "text")]
+    pub format: String,
+
+    /// Exit with an error when the changes are breaking
+    #[arg(long)]
+    pub fail_on_breaking: bool,
+}
+
+pub fn handle_api_diff_command(args: ApiDiffArgs) -> Result<()> {
+    let old = PublicApi::extract(&read_generated(&args.old)?)
+        .with_context(|| format!("Failed to parse {}", args.old.display()))?;
+    let new = PublicApi::extract(&read_generated(&args.new)?)
+        .with_context(|| format!("Failed to parse {}", args.new.display()))?;
+    let diff = ApiDiff::between(&old, &new);
+
+    match args.format.as_str() {
+        "json" => {
+            let report = serde_json::json!({
+                "impact": diff.impact(),
+                "changes": diff.changes,
+            });
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        }
+        _ => print_diff(&args.old, &args.new, &diff),
+    }
+
+    if args.fail_on_breaking && diff.is_breaking() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn read_generated(path: &Path) -> Result<String> {
+    let file = if path.is_dir() {
+        path.join("src").join("lib.rs")
+    } else {
+        path.to_path_buf()
+    };
+    fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))
+}
+
+fn print_diff(old: &Path, new: &Path, diff: &ApiDiff) {
+    println!("🔍 Public API: {} -> {}", old.display(), new.display());
+    if diff.changes.is_empty() {
+        println!("   No public API changes");
+    }
+    for change in &diff.changes {
+        let impact = match change.impact {
+            SemverImpact::Major => "breaking".red(),
+            SemverImpact::Minor => "additive".green(),
+            SemverImpact::Patch => "patch".normal(),
+        };
+        match change.kind {
+            ChangeKind::Added => println!(
+                "   + {} [{}] {}",
+                change.path,
+                impact,
+                change.new.as_deref().unwrap_or_default()
+            ),
+            ChangeKind::Removed => println!(
+                "   - {} [{}] {}",
+                change.path,
+                impact,
+                change.old.as_deref().unwrap_or_default()
+            ),
+            ChangeKind::Changed => {
+                println!("   ~ {} [{}]", change.path, impact);
+                println!("       was: {}", change.old.as_deref().unwrap_or_default());
+                println!("       now: {}", change.new.as_deref().unwrap_or_default());
+            }
+        }
+        if let Some(note) = &change.note {
+            println!("       {}", note);
+        }
+    }
+
+    let bump = match diff.impact() {
+        SemverImpact::Major => "major (breaking changes)".red().bold(),
+        SemverImpact::Minor => "minor (additions only)".green().bold(),
+        SemverImpact::Patch => "patch (public API unchanged)".normal(),
+    };
+    println!("📦 Required version bump: {}", bump);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_api_diff_reads_files_and_crates() {
+        let dir = tempdir().unwrap();
+        let old = dir.path().join("old.rs");
+        let new_crate = dir.path().join("new");
+        fs::create_dir_all(new_crate.join("src")).unwrap();
+        fs::write(&old, "pub fn add(a: i32, b: i32) -> i32 { a + b }\n").unwrap();
+        fs::write(
+            new_crate.join("src/lib.rs"),
+            "pub fn add(a: i32, b: i32) -> i32 { a + b }\npub fn sub(a: i32, b: i32) -> i32 { a - b }\n",
+        )
+        .unwrap();
+
+        let args = ApiDiffArgs {
+            old,
+            new: new_crate,
+            format: "text".to_string(),
+            fail_on_breaking: true,
+        };
+        assert!(handle_api_diff_command(args).is_ok());
+    }
+}
@@ -0,0 +1,8 @@
+//! `depyler-lsp`: the depyler language server on stdin and stdout
+
+use anyhow::Result;
+
+fn main() -> Result<()> {
+    let verbose = std::env::args().any(|arg| arg == "--verbose" || arg == "-v");
+    depyler::lsp_cmd::lsp_command(None, verbose)
+}
@@ -13,7 +13,7 @@
 //! Coverage: ≥85%
 
 use anyhow::{Context, Result};
-use depyler_core::DepylerPipeline;
+use depyler_core::{cargo_toml_gen, DepylerPipeline};
 use indicatif::{ProgressBar, ProgressStyle};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -95,17 +95,10 @@ fn create_cargo_project(input: &Path, rust_code: &str) -> Result<PathBuf> {
     // Create project structure
     fs::create_dir_all(project_dir.join("src")).context("Failed to create src directory")?;
 
-    // Write Cargo.toml
-    let cargo_toml = format!(
-        r#"[package]
-name = "{}"
-version = "0.1.0"
-edition = "2021"
-
-[dependencies]
-"#,
-        project_name
-    );
+    // Write Cargo.toml with the crates the generated code refers to
+    let dependencies = cargo_toml_gen::detect_dependencies(rust_code)
+        .context("Failed to parse the generated Rust")?;
+    let cargo_toml = cargo_toml_gen::generate_cargo_toml(project_name, &dependencies);
     fs::write(project_dir.join("Cargo.toml"), cargo_toml)
         .context("Failed to write Cargo.toml")?;
 
@@ -0,0 +1,261 @@
+//! Example project command
+//!
+//! Writes a small Python module and everything the pipeline makes of it,
+//! side by side, as a starting point for evaluating depyler:
+//!
+//! ```text
+//! <output>/
+//!   README.md
+//!   python/inventory.py           the module
+//!   python/test_inventory.py      its pytest tests
+//!   rust/Cargo.toml               the transpiled crate
+//!   rust/src/lib.rs
+//!   rust/tests/test_inventory.rs  the translated tests
+//!   reports/quality.json          quality metrics of the module
+//!   reports/verification.json     verified properties, per function
+//!   reports/divergences.json      known deviations from Python
+//! ```
+//!
+//! The generated crate builds and its tests pass, which the CLI's own test
+//! suite checks.
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use depyler_core::cargo_toml_gen::{detect_dependencies, generate_cargo_toml};
+use depyler_core::DepylerPipeline;
+use depyler_verify::PropertyVerifier;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MODULE_NAME: &str = "inventory";
+
+const MODULE_SOURCE: &str = r#""""Stock levels of a small shop."""
+
+
+def total_stock(levels: list[int]) -> int:
+    """Items on all shelves."""
+    total = 0
+    for level in levels:
+        total = total + level
+    return total
+
+
+def needs_reorder(level: int, threshold: int) -> bool:
+    """Whether an item ran below its threshold."""
+    return level < threshold
+
+
+def reorder_amount(level: int, capacity: int) -> int:
+    """Items to order to fill a shelf."""
+    if level >= capacity:
+        return 0
+    return capacity - level
+
+
+def shelf_label(name: str, level: int) -> str:
+    """The label printed on a shelf."""
+    return f"{name}: {level}"
+"#;
+
+const TEST_SOURCE: &str = r#"from inventory import needs_reorder, reorder_amount, shelf_label, total_stock
+
+
+def test_total_stock():
+    assert total_stock([3, 4, 5]) == 12
+    assert total_stock([]) == 0
+
+
+def test_needs_reorder():
+    assert needs_reorder(2, 5)
+    assert not needs_reorder(5, 5)
+
+
+def test_reorder_amount():
+    assert reorder_amount(3, 10) == 7
+    assert reorder_amount(12, 10) == 0
+
+
+def test_shelf_label():
+    assert shelf_label("tea", 4) == "tea: 4"
+"#;
+
+const README: &str = r#"# depyler example: inventory
+
+A Python module and what depyler makes of it.
+
+| Path | Produced by |
+|------|-------------|
+| `python/inventory.py` | hand-written |
+| `python/test_inventory.py` | hand-written |
+| `rust/src/lib.rs` | `DepylerPipeline::transpile_with_ledger` |
+| `rust/Cargo.toml` | `cargo_toml_gen::generate_cargo_toml` |
+| `rust/tests/test_inventory.rs` | `DepylerPipeline::translate_tests` |
+| `reports/quality.json` | `depyler::generate_quality_report` |
+| `reports/verification.json` | `depyler_verify::PropertyVerifier::verify_function` |
+| `reports/divergences.json` | the ledger of `transpile_with_ledger` |
+
+Run both test suites:
+
+```bash
+(cd python && python -m pytest)
+(cd rust && cargo test)
+```
+
+Edit `python/inventory.py` and regenerate with the CLI:
+
+```bash
+depyler transpile python/inventory.py -o rust/src/lib.rs --divergences
+depyler translate-tests -m python/inventory.py python/test_inventory.py -o rust
+depyler quality-check python/inventory.py
+```
+"#;
+
+#[derive(Debug, Args)]
+pub struct InitExampleArgs {
+    /// Directory to create the example project in
+    #[arg(default_value = "depyler-example")]
+    pub output: PathBuf,
+
+    /// Write into the directory even if it already exists
+    #[arg(long)]
+    pub force: bool,
+}
+
+pub fn handle_init_example_command(args: InitExampleArgs) -> Result<()> {
+    if args.output.exists() && !args.force {
+        bail!(
+            "{} already exists; pass --force to write into it",
+            args.output.display()
+        );
+    }
+    generate_example(&args.output)?;
+
+    println!("📦 Example project written to {}", args.output.display());
+    println!("   python/   the module and its pytest tests");
+    println!("   rust/     the transpiled crate and translated tests");
+    println!("   reports/  quality, verification and divergences");
+    println!(
+        "💡 Run `cargo test` in {}",
+        args.output.join("rust").display()
+    );
+    Ok(())
+}
+
+/// Writes the example project into `root`
+pub fn generate_example(root: &Path) -> Result<()> {
+    let python_dir = root.join("python");
+    let crate_dir = root.join("rust");
+    let reports_dir = root.join("reports");
+    for dir in [
+        &python_dir,
+        &crate_dir.join("src"),
+        &crate_dir.join("tests"),
+        &reports_dir,
+    ] {
+        fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let module_path = python_dir.join(format!("{}.py", MODULE_NAME));
+    fs::write(&module_path, MODULE_SOURCE)?;
+    fs::write(
+        python_dir.join(format!("test_{}.py", MODULE_NAME)),
+        TEST_SOURCE,
+    )?;
+    fs::write(root.join("README.md"), README)?;
+
+    let pipeline = DepylerPipeline::new();
+    let (rust_code, ledger) = pipeline.transpile_with_ledger(MODULE_SOURCE)?;
+    let translated =
+        pipeline.translate_tests(MODULE_SOURCE, MODULE_NAME, TEST_SOURCE, MODULE_NAME)?;
+    fs::write(crate_dir.join("src/lib.rs"), &rust_code)?;
+    fs::write(
+        crate_dir.join(format!("tests/test_{}.rs", MODULE_NAME)),
+        &translated.code,
+    )?;
+
+    let mut manifest = generate_cargo_toml(MODULE_NAME, &detect_dependencies(&rust_code)?);
+    if !translated.dev_dependencies.is_empty() {
+        manifest.push_str("\n[dev-dependencies]\n");
+        for (name, version) in &translated.dev_dependencies {
+            manifest.push_str(&format!("{} = \"{}\"\n", name, version));
+        }
+    }
+    fs::write(crate_dir.join("Cargo.toml"), manifest)?;
+
+    let quality = crate::generate_quality_report(&module_path, None, None)?;
+    fs::write(
+        reports_dir.join("quality.json"),
+        serde_json::to_string_pretty(&quality)?,
+    )?;
+
+    let module = pipeline.parse_to_hir(MODULE_SOURCE)?;
+    let verifier = PropertyVerifier::new();
+    let verification: serde_json::Map<String, serde_json::Value> = module
+        .functions
+        .iter()
+        .map(|func| {
+            let results = verifier.verify_function(func);
+            Ok((func.name.clone(), serde_json::to_value(results)?))
+        })
+        .collect::<Result<_>>()?;
+    fs::write(
+        reports_dir.join("verification.json"),
+        serde_json::to_string_pretty(&verification)?,
+    )?;
+    fs::write(reports_dir.join("divergences.json"), ledger.to_json()?)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_example_project_layout() {
+        let dir = tempdir().unwrap();
+        generate_example(dir.path()).unwrap();
+
+        for file in [
+            "README.md",
+            "python/inventory.py",
+            "python/test_inventory.py",
+            "rust/Cargo.toml",
+            "rust/src/lib.rs",
+            "rust/tests/test_inventory.rs",
+            "reports/quality.json",
+            "reports/verification.json",
+            "reports/divergences.json",
+        ] {
+            assert!(dir.path().join(file).is_file(), "missing {}", file);
+        }
+
+        let lib = fs::read_to_string(dir.path().join("rust/src/lib.rs")).unwrap();
+        assert!(lib.contains("pub fn total_stock"));
+        assert!(lib.contains("pub fn shelf_label"));
+        let tests = fs::read_to_string(dir.path().join("rust/tests/test_inventory.rs")).unwrap();
+        assert!(tests.contains("use inventory::*;"));
+        assert!(tests.contains("fn test_reorder_amount()"));
+        let verification =
+            fs::read_to_string(dir.path().join("reports/verification.json")).unwrap();
+        assert!(verification.contains("\"needs_reorder\""));
+    }
+
+    #[test]
+    fn test_existing_directory_needs_force() {
+        let dir = tempdir().unwrap();
+        let args = InitExampleArgs {
+            output: dir.path().to_path_buf(),
+            force: false,
+        };
+        assert!(handle_init_example_command(args).is_err());
+
+        let args = InitExampleArgs {
+            output: dir.path().to_path_buf(),
+            force: true,
+        };
+        handle_init_example_command(args).unwrap();
+        assert!(dir.path().join("rust/src/lib.rs").is_file());
+    }
+}
@@ -56,27 +56,48 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use depyler_analyzer::Analyzer;
+use depyler_analyzer::coverage::{
+    rank_for_migration, CoverageReport, CoverageSummary, MigrationPriority, MigrationReadiness,
+};
+use depyler_analyzer::{AnalysisResult, Analyzer};
 use depyler_core::{
+    analysis_cache::AnalysisCache,
+    hir_format::{HirDocument, HirEncoding},
+    keep_regions::{merge_keep_regions, KeepMerge},
     lambda_codegen::{LambdaCodeGenerator, LambdaProject},
     lambda_inference::{AnalysisReport, LambdaTypeInferencer},
     lambda_optimizer::LambdaOptimizer,
     lambda_testing::LambdaTestHarness,
+    optimize::PassOptions,
+    regen_merge::{merge_regenerated, RegenMerge},
+    rust_gen::{count_prints, CodeGenOptions},
     DepylerPipeline,
 };
-use depyler_quality::QualityAnalyzer;
+use depyler_quality::code_size::CodeSizeBudget;
+use depyler_quality::config::QualityConfig;
+use depyler_quality::coverage::measure_crate_coverage;
+use depyler_quality::dependencies::DependencyPolicy;
+use depyler_quality::mutation::run_mutants;
+use depyler_quality::{CoverageMetrics, QualityAnalyzer, QualityStatus};
 use indicatif::{ProgressBar, ProgressStyle};
+use print_filter::PrintFilter;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{Duration, Instant};
 
 pub mod agent;
+pub mod api_diff_cmd;
 pub mod compile_cmd;
 pub mod debug_cmd;
 pub mod docs_cmd;
+pub mod init_example_cmd;
 pub mod interactive;
+pub mod lint_cmd;
+pub mod lsp_cmd;
+pub mod print_filter;
 pub mod profile_cmd;
+pub mod test_translate_cmd;
 
 #[derive(Parser)]
 #[command(name = "depyler")]
@@ -93,7 +114,8 @@ pub struct Cli {
 pub enum Commands {
     /// Transpile Python code to Rust
     Transpile {
-        /// Input Python file
+        /// Input Python file, or a HIR document (.json/.cbor) written by
+        /// `inspect --repr hir`
         input: PathBuf,
 
         /// Output Rust file (defaults to input with .rs extension)
@@ -108,6 +130,15 @@ pub enum Commands {
         #[arg(long)]
         gen_tests: bool,
 
+        /// Write criterion benchmarks for pure functions to benches/ beside the output
+        #[arg(long)]
+        emit_benches: bool,
+
+        /// Write C ABI wrappers for functions of simple types into the output,
+        /// and a C header declaring them to <output>.h
+        #[arg(long)]
+        emit_ffi: bool,
+
         /// Enable debug mode
         #[arg(long)]
         debug: bool,
@@ -115,6 +146,62 @@ pub enum Commands {
         /// Generate source map
         #[arg(long)]
         source_map: bool,
+
+        /// Fold constant expressions and `if True`/`if False` branches
+        #[arg(long)]
+        fold_constants: bool,
+
+        /// Write the known deviations from Python of each function to
+        /// <output>.divergences.json
+        #[arg(long)]
+        divergences: bool,
+
+        /// Put each class and its impls in a module of its own
+        #[arg(long)]
+        class_modules: bool,
+
+        /// Keep module-level statements, in a generated main() (and init())
+        #[arg(long)]
+        script_main: bool,
+
+        /// Pass stdin, stdout and stderr to the functions doing I/O as
+        /// parameters, bound to the std streams by main()
+        #[arg(long)]
+        inject_io: bool,
+
+        /// Merge hand edits of the output into the regenerated code item by
+        /// item, against the code generated last time
+        #[arg(long)]
+        merge: bool,
+
+        /// Keep per-function analysis results in this directory and reuse
+        /// them for functions unchanged since an earlier run
+        #[arg(long, value_name = "DIR")]
+        analysis_cache: Option<PathBuf>,
+
+        /// Switch on a HIR pass that is off by default (repeatable)
+        #[arg(long = "enable-pass", value_name = "PASS")]
+        enabled_passes: Vec<String>,
+
+        /// Switch off a HIR pass (repeatable)
+        #[arg(long = "disable-pass", value_name = "PASS")]
+        disabled_passes: Vec<String>,
+
+        /// Print the HIR to stderr after a pass runs (repeatable)
+        #[arg(long, value_name = "PASS")]
+        print_hir_after: Vec<String>,
+
+        /// Put a module function behind a trait seam so tests can fake it (repeatable)
+        #[arg(long = "seam", value_name = "FUNCTION")]
+        seams: Vec<String>,
+
+        /// What becomes of print() statements (keep, strip, log)
+        #[arg(long = "print", value_name = "HANDLING", default_value = "keep")]
+        print_handling: String,
+
+        /// Apply --print only to inputs matching this glob (repeatable)
+        #[arg(long = "print-path", value_name = "GLOB")]
+        print_paths: Vec<String>,
     },
 
     /// Compile Python to standalone binary (DEPYLER-0380)
@@ -139,6 +226,11 @@ pub enum Commands {
         /// Output format (json, text)
         #[arg(short, long, default_value = "text")]
         format: String,
+
+        /// coverage.py report (`coverage json` or `coverage xml`) used to
+        /// rank functions for migration
+        #[arg(long, value_name = "REPORT")]
+        coverage: Option<PathBuf>,
     },
 
     /// Check if Python code can be transpiled
@@ -171,6 +263,48 @@ pub enum Commands {
         /// Minimum coverage percentage
         #[arg(long, default_value = "80")]
         min_coverage: u32,
+
+        /// coverage.py report (`coverage json` or `coverage xml`) with the
+        /// measured coverage for the coverage gates
+        #[arg(long, value_name = "REPORT")]
+        coverage: Option<PathBuf>,
+
+        /// Transpiled crate whose tests are run under cargo-llvm-cov to
+        /// measure the generated code's coverage for the coverage gates
+        #[arg(long, value_name = "DIR", conflicts_with = "coverage")]
+        crate_coverage: Option<PathBuf>,
+
+        /// Transpiled crate whose tests are run against mutants of the
+        /// generated code (operator swaps, boundary changes)
+        #[arg(long, value_name = "DIR")]
+        mutate: Option<PathBuf>,
+
+        /// Most crates the generated Cargo.toml may depend on
+        #[arg(long, value_name = "COUNT")]
+        max_dependencies: Option<usize>,
+
+        /// Crate the generated code must not depend on (repeatable)
+        #[arg(long = "ban-crate", value_name = "CRATE")]
+        banned_crates: Vec<String>,
+
+        /// Most lines of code the generated Rust may have
+        #[arg(long, value_name = "LINES")]
+        max_generated_lines: Option<usize>,
+
+        /// Most items (functions, methods, types, impls, ...) the generated
+        /// Rust may have
+        #[arg(long, value_name = "COUNT")]
+        max_generated_items: Option<usize>,
+
+        /// Most generic items, `impl Trait`s, closures and derives the
+        /// generated Rust may have
+        #[arg(long, value_name = "COUNT")]
+        max_generic_constructs: Option<usize>,
+
+        /// depyler.toml whose [quality] table replaces the thresholds above
+        /// and adds its own gates
+        #[arg(long, value_name = "FILE")]
+        config: Option<PathBuf>,
     },
 
     /// Interactive transpilation with annotation suggestions
@@ -192,7 +326,8 @@ pub enum Commands {
         #[arg(short, long, default_value = "hir")]
         repr: String,
 
-        /// Output format: json, debug, pretty
+        /// Output format: json, cbor, debug, pretty; json and cbor write a
+        /// versioned HIR document that `transpile` accepts
         #[arg(short, long, default_value = "pretty")]
         format: String,
 
@@ -207,9 +342,9 @@ pub enum Commands {
 
     /// Start Language Server Protocol (LSP) server for IDE integration
     Lsp {
-        /// Port to listen on
-        #[arg(short, long, default_value = "2087")]
-        port: u16,
+        /// Listen on this TCP port instead of serving stdin and stdout
+        #[arg(short, long)]
+        port: Option<u16>,
 
         /// Enable verbose logging
         #[arg(short, long)]
@@ -289,6 +424,74 @@ pub enum Commands {
         index: bool,
     },
 
+    /// Translate pytest / unittest tests into Rust integration tests
+    TranslateTests {
+        /// Python test files (pytest or unittest)
+        #[arg(required = true)]
+        tests: Vec<PathBuf>,
+
+        /// Python module under test
+        #[arg(short, long)]
+        module: PathBuf,
+
+        /// Root of the transpiled crate; tests are written to `<output>/tests/`
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Library crate name the tests import (defaults to the module name)
+        #[arg(long)]
+        crate_name: Option<String>,
+
+        /// Module function the crate was transpiled with a seam for; tests
+        /// patching it get a fake (repeatable)
+        #[arg(long = "seam", value_name = "FUNCTION")]
+        seams: Vec<String>,
+    },
+
+    /// Compare the public API of two generated outputs and classify the
+    /// changes by semver impact
+    ApiDiff {
+        /// Previously generated Rust file, or a crate directory (uses src/lib.rs)
+        old: PathBuf,
+
+        /// Newly generated Rust file, or a crate directory (uses src/lib.rs)
+        new: PathBuf,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Exit with an error when the changes are breaking
+        #[arg(long)]
+        fail_on_breaking: bool,
+    },
+
+    /// Create an example Python project with its transpiled crate,
+    /// translated tests and reports
+    InitExample {
+        /// Directory to create the example project in
+        #[arg(default_value = "depyler-example")]
+        output: PathBuf,
+
+        /// Write into the directory even if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Lint the generated Rust for unidiomatic code and suggest fixes
+    Lint {
+        /// Python file to transpile and lint, or a generated `.rs` file
+        input: PathBuf,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+
+        /// Exit with an error when any lint fires
+        #[arg(long)]
+        deny_warnings: bool,
+    },
+
     /// Profile Python code for performance analysis
     Profile {
         /// Input Python file
@@ -528,19 +731,39 @@ pub fn compile_command(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn transpile_command(
     input: PathBuf,
     output: Option<PathBuf>,
     verify: bool,
     gen_tests: bool,
+    emit_benches: bool,
+    emit_ffi: bool,
     debug: bool,
     source_map: bool,
+    fold_constants: bool,
+    divergences: bool,
+    class_modules: bool,
+    script_main: bool,
+    inject_io: bool,
+    merge: bool,
+    analysis_cache: Option<PathBuf>,
+    passes: PassOptions,
+    seams: Vec<String>,
+    print: PrintFilter,
 ) -> Result<()> {
     let start = Instant::now();
 
-    // Read input file
-    let python_source = fs::read_to_string(&input)?;
-    let source_size = python_source.len();
+    // Read input file: Python source, or HIR saved by `inspect`
+    let hir_document = match HirEncoding::from_path(&input) {
+        Some(_) => Some(HirDocument::read(&input)?),
+        None => None,
+    };
+    let python_source = match hir_document {
+        Some(_) => String::new(),
+        None => fs::read_to_string(&input)?,
+    };
+    let source_size = fs::metadata(&input)?.len() as usize;
 
     // Create progress bar
     let pb = ProgressBar::new(4);
@@ -557,6 +780,33 @@ pub fn transpile_command(
     if verify {
         pipeline = pipeline.with_verification();
     }
+    if fold_constants {
+        pipeline = pipeline.with_const_folding();
+    }
+    if emit_benches {
+        pipeline = pipeline.with_benches();
+    }
+    if emit_ffi {
+        pipeline = pipeline.with_ffi();
+    }
+    if class_modules {
+        pipeline = pipeline.with_class_modules();
+    }
+    if script_main {
+        pipeline = pipeline.with_script_main();
+    }
+    if inject_io {
+        pipeline = pipeline.with_injectable_io();
+    }
+    if let Some(dir) = analysis_cache {
+        pipeline = pipeline.with_analysis_cache(AnalysisCache::open(dir)?);
+    }
+    pipeline = pipeline.with_pass_options(passes);
+    if !seams.is_empty() {
+        pipeline = pipeline.with_seams(seams);
+    }
+    let print_handling = print.handling_for(&input);
+    pipeline = pipeline.with_print_handling(print_handling);
     if debug || source_map {
         let debug_config = depyler_core::debug::DebugConfig {
             debug_level: if debug {
@@ -573,29 +823,113 @@ pub fn transpile_command(
     }
     pb.inc(1);
 
+    let output_path = output.unwrap_or_else(|| {
+        let mut path = input.clone();
+        path.set_extension("rs");
+        path
+    });
+
     // Parse Python
     pb.set_message("Parsing Python source...");
     let parse_start = Instant::now();
-    let rust_code = pipeline.transpile(&python_source)?;
-    let parse_time = parse_start.elapsed();
-    pb.inc(1);
-
-    // Analyze if requested
-    if verify {
+    let (mut module, spans) = match hir_document {
+        Some(document) => (document.module, None),
+        None if verify => {
+            let (module, spans) = pipeline.parse_to_hir_with_spans(&python_source)?;
+            (module, Some(spans))
+        }
+        None => (pipeline.parse_to_hir(&python_source)?, None),
+    };
+    let crate_name = output_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("output")
+        .to_string();
+    let benches = pipeline.generate_benches(&module, &crate_name)?;
+    let ffi = pipeline.generate_ffi(&module, &crate_name)?;
+    let prints = count_prints(
+        &module,
+        &CodeGenOptions {
+            print_handling,
+            ..Default::default()
+        },
+    );
+    // Prove what can be proven before code generation reads the properties
+    let panic_sites = if verify {
         pb.set_message("Analyzing code...");
-        // Analysis would happen here
+        let sites = depyler_verify::panic_freedom::prove_module(&mut module);
         pb.inc(1);
+        sites
+    } else {
+        Vec::new()
+    };
+    let (mut rust_code, optimization, ledger) = pipeline.transpile_hir_with_ledger(module)?;
+    if let Some(ffi) = &ffi {
+        rust_code.push('\n');
+        rust_code.push_str(&ffi.rust);
     }
+    let parse_time = parse_start.elapsed();
+    pb.inc(1);
 
-    // Generate output
+    // Generate output, carrying the keep regions of an earlier run over,
+    // or with --merge the hand edits made since the code it generated
     pb.set_message("Writing output...");
-    let output_path = output.unwrap_or_else(|| {
-        let mut path = input.clone();
-        path.set_extension("rs");
-        path
-    });
-
+    let base_path = output_path.with_extension("rs.base");
+    let previous = fs::read_to_string(&output_path).ok();
+    let regen_merge = match (&previous, fs::read_to_string(&base_path)) {
+        (Some(previous), Ok(base)) if merge => Some(
+            merge_regenerated(&base, previous, &rust_code)
+                .map_err(|e| anyhow::anyhow!("cannot merge {}: {e}", output_path.display()))?,
+        ),
+        _ => None,
+    };
+    let keep_merge = match &previous {
+        Some(previous) if regen_merge.is_none() => {
+            let keep_merge = merge_keep_regions(previous, &rust_code);
+            if keep_merge.dropped_regions() {
+                fs::write(output_path.with_extension("rs.orig"), previous)?;
+            }
+            Some(keep_merge)
+        }
+        _ => None,
+    };
+    if merge {
+        fs::write(&base_path, &rust_code)?;
+    }
+    let rust_code = match (&regen_merge, &keep_merge) {
+        (Some(regen_merge), _) => regen_merge.code.clone(),
+        (None, Some(keep_merge)) => keep_merge.code.clone(),
+        (None, None) => rust_code,
+    };
     fs::write(&output_path, &rust_code)?;
+    let bench_path = match &benches {
+        Some(benches) => {
+            let dir = output_path
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join("benches");
+            fs::create_dir_all(&dir)?;
+            let path = dir.join(format!("{crate_name}.rs"));
+            fs::write(&path, benches)?;
+            Some(path)
+        }
+        None => None,
+    };
+    let header_path = match &ffi {
+        Some(ffi) => {
+            let path = output_path.with_extension("h");
+            fs::write(&path, &ffi.header)?;
+            Some(path)
+        }
+        None => None,
+    };
+    let ledger_path = if divergences {
+        let path = output_path.with_extension("divergences.json");
+        fs::write(&path, ledger.to_json()?)?;
+        Some(path)
+    } else {
+        None
+    };
     pb.inc(1);
 
     pb.finish_and_clear();
@@ -620,15 +954,107 @@ pub fn transpile_command(
     println!("⏱️  Parse time: {:.2}ms", parse_time.as_millis());
     println!("📊 Throughput: {throughput:.1} KB/s");
     println!("⏱️  Total time: {:.2}ms", total_time.as_millis());
+    if fold_constants {
+        println!("🧮 Simplified: {} nodes", optimization.nodes_simplified);
+    }
+    if let Some(cache) = pipeline.analysis_cache() {
+        let stats = cache.stats();
+        println!(
+            "🗃️  Analysis cache: {} reused, {} analyzed",
+            stats.hits, stats.misses
+        );
+    }
+    match bench_path {
+        Some(path) => println!(
+            "📈 Benchmarks: {}; add to Cargo.toml:\n{}",
+            path.display(),
+            depyler_core::cargo_toml_gen::bench_targets(&[&crate_name])
+        ),
+        None if emit_benches => println!("📈 Benchmarks: no pure functions to benchmark"),
+        None => {}
+    }
+    match header_path {
+        Some(path) => println!(
+            "🔌 FFI: {}; add to Cargo.toml:\n{}",
+            path.display(),
+            depyler_core::cargo_toml_gen::cdylib_target()
+        ),
+        None if emit_ffi => println!("🔌 FFI: no functions of simple types to wrap"),
+        None => {}
+    }
+    if let Some(ffi) = &ffi {
+        for name in &ffi.skipped {
+            println!("   not wrapped: {name} (types without a C equivalent)");
+        }
+    }
+    if let Some(merge) = &keep_merge {
+        print_keep_merge(merge, &output_path);
+    }
+    if let Some(merge) = &regen_merge {
+        print_regen_merge(merge);
+    }
+    if let Some(path) = ledger_path {
+        println!(
+            "⚖️  Divergences: {} ({} recorded)",
+            path.display(),
+            ledger.entries.len()
+        );
+    }
+    if prints.total() > 0 {
+        println!(
+            "🖨️  Prints: {} kept, {} stripped, {} logged",
+            prints.kept, prints.stripped, prints.logged
+        );
+    }
+    if debug {
+        for pass in &optimization.passes {
+            println!(
+                "⏱️  Pass {}: {:.2}ms",
+                pass.name,
+                pass.duration.as_secs_f64() * 1000.0
+            );
+        }
+    }
 
     if verify {
         println!("✓ Properties Verified");
+        if !panic_sites.is_empty() {
+            println!("⚠️  Possible panics: {}", panic_sites.len());
+        }
+        for site in &panic_sites {
+            match spans.as_ref().and_then(|spans| site.line(spans)) {
+                Some(line) => println!("   line {line}: {site}"),
+                None => println!("   {site}"),
+            }
+        }
+        let audit = depyler_verify::safety_audit::MemorySafetyReport::audit(&rust_code)?;
+        if let Some(worst) = audit.max_severity() {
+            println!(
+                "🛡️  Safety audit: {} findings, worst {worst}",
+                audit.findings.len()
+            );
+        }
+        for finding in audit.at_least(depyler_verify::safety_audit::AuditSeverity::Medium) {
+            println!("   {}:{}: {finding}", output_path.display(), finding.line);
+        }
     }
 
     Ok(())
 }
 
-pub fn analyze_command(input: PathBuf, format: String) -> Result<()> {
+/// `analyze --format json` output; the coverage fields are present when a
+/// coverage report was given
+#[derive(serde::Serialize)]
+struct AnalyzeOutput<'a> {
+    #[serde(flatten)]
+    analysis: &'a AnalysisResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    coverage: Option<&'a CoverageSummary>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    migration_priority: Option<&'a [MigrationPriority]>,
+}
+
+pub fn analyze_command(input: PathBuf, format: String, coverage: Option<PathBuf>) -> Result<()> {
     // Read and parse
     let python_source = fs::read_to_string(&input)?;
     let _pipeline = DepylerPipeline::new();
@@ -644,9 +1070,23 @@ pub fn analyze_command(input: PathBuf, format: String) -> Result<()> {
     let analyzer = Analyzer::new();
     let analysis = analyzer.analyze(&hir)?;
 
+    let measured = match &coverage {
+        Some(report) => {
+            let module = CoverageReport::load(report)?.module_coverage(&input, &python_source)?;
+            let priorities = rank_for_migration(&module.functions, &analysis.function_metrics);
+            Some((module.summary, priorities))
+        }
+        None => None,
+    };
+
     match format.as_str() {
         "json" => {
-            let json = serde_json::to_string_pretty(&analysis)?;
+            let output = AnalyzeOutput {
+                analysis: &analysis,
+                coverage: measured.as_ref().map(|(summary, _)| summary),
+                migration_priority: measured.as_ref().map(|(_, ranked)| ranked.as_slice()),
+            };
+            let json = serde_json::to_string_pretty(&output)?;
             println!("{json}");
         }
         _ => {
@@ -669,12 +1109,77 @@ pub fn analyze_command(input: PathBuf, format: String) -> Result<()> {
                 "Type Coverage: {:.0}%",
                 analysis.type_coverage.coverage_percentage
             );
+            if let Some((summary, priorities)) = &measured {
+                print_migration_priority(summary, priorities);
+            }
         }
     }
 
     Ok(())
 }
 
+fn print_keep_merge(merge: &KeepMerge, output_path: &Path) {
+    if merge.kept == 0 && merge.conflicts.is_empty() {
+        return;
+    }
+    println!(
+        "✂️  Keep regions: {} kept, {} conflicts",
+        merge.kept,
+        merge.conflicts.len()
+    );
+    for line in merge.report().lines() {
+        println!("   {}", line.yellow());
+    }
+    if merge.dropped_regions() {
+        println!(
+            "   previous output saved to {}",
+            output_path.with_extension("rs.orig").display()
+        );
+    }
+}
+
+fn print_regen_merge(merge: &RegenMerge) {
+    println!(
+        "🔀 Hand edits: {} merged, {} conflicts",
+        merge.integrated,
+        merge.conflicts.len()
+    );
+    for conflict in &merge.conflicts {
+        println!("   {}", conflict.to_string().yellow());
+    }
+}
+
+fn print_migration_priority(summary: &CoverageSummary, priorities: &[MigrationPriority]) {
+    println!(
+        "Test Coverage: {:.0}% lines, {:.0}% branches, {:.0}% functions",
+        summary.line_coverage * 100.0,
+        summary.branch_coverage * 100.0,
+        summary.function_coverage * 100.0
+    );
+    println!();
+    println!("Migration priority (hot, well-tested functions first):");
+    for priority in priorities {
+        let complexity = match priority.cyclomatic_complexity {
+            Some(complexity) => format!(", complexity {complexity}"),
+            None => String::new(),
+        };
+        let advice = match priority.readiness {
+            MigrationReadiness::Ready => "✓ transpile now".green(),
+            MigrationReadiness::PartiallyTested => "◐ add tests for the missed lines".yellow(),
+            MigrationReadiness::Untested => "⚠ untested, migrate last".red(),
+        };
+        println!(
+            "  {:>2}. {} — {:.0}% covered, {} hits{} {}",
+            priority.rank,
+            priority.name,
+            priority.line_coverage * 100.0,
+            priority.hits,
+            complexity,
+            advice
+        );
+    }
+}
+
 pub fn check_command(input: PathBuf) -> Result<()> {
     let python_source = fs::read_to_string(&input)?;
     let pipeline = DepylerPipeline::new();
@@ -702,6 +1207,7 @@ pub fn complexity_rating(complexity: f64) -> colored::ColoredString {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn quality_check_command(
     input: PathBuf,
     enforce: bool,
@@ -709,10 +1215,75 @@ pub fn quality_check_command(
     max_tdg: f64,
     max_complexity: u32,
     min_coverage: u32,
+    coverage: Option<PathBuf>,
+    crate_coverage: Option<PathBuf>,
+    mutate: Option<PathBuf>,
+    dependency_policy: DependencyPolicy,
+    code_size_budget: CodeSizeBudget,
+    config: Option<PathBuf>,
 ) -> Result<()> {
-    let report = generate_quality_report(&input)?;
-    let quality_analyzer = QualityAnalyzer::new();
+    let quality_config = config.as_deref().map(QualityConfig::load).transpose()?;
+    let (min_tdg, max_tdg, max_complexity, min_coverage) = match &quality_config {
+        Some(config) => (
+            config.min_tdg,
+            config.max_tdg,
+            config.max_cyclomatic,
+            (config.min_test_coverage * 100.0).round() as u32,
+        ),
+        None => (min_tdg, max_tdg, max_complexity, min_coverage),
+    };
+    let measured = match (coverage, crate_coverage) {
+        (_, Some(crate_dir)) => {
+            let measured = measure_crate_coverage(&crate_dir, Path::new("src/lib.rs"))?;
+            print_function_coverage(&crate_dir, &measured.functions);
+            Some(measured.metrics)
+        }
+        (Some(report), None) => {
+            let python_source = fs::read_to_string(&input)?;
+            let module = CoverageReport::load(&report)?.module_coverage(&input, &python_source)?;
+            Some(module.summary.into())
+        }
+        (None, None) => None,
+    };
+    let mut report = generate_quality_report(&input, measured, quality_config.as_ref())?;
+    if let Some(crate_dir) = mutate {
+        let python_source = fs::read_to_string(&input)?;
+        let pipeline = DepylerPipeline::new();
+        let baseline = pipeline.transpile(&python_source)?;
+        let mutants = pipeline.transpile_mutants(&python_source)?;
+        println!(
+            "🧬 Running {} mutants against the tests in {}",
+            mutants.len(),
+            crate_dir.display()
+        );
+        report.mutation_metrics = Some(run_mutants(
+            &crate_dir,
+            Path::new("src/lib.rs"),
+            &baseline,
+            &mutants,
+        )?);
+    }
+    let quality_analyzer = quality_analyzer(quality_config.as_ref());
+    let python_source = fs::read_to_string(&input)?;
+    // Untranspilable input already fails the compilation check below
+    if let Ok(rust_code) = DepylerPipeline::new().transpile(&python_source) {
+        let dependencies = depyler_core::cargo_toml_gen::detect_dependencies(&rust_code)?;
+        quality_analyzer.check_dependencies(&mut report, &dependencies, &dependency_policy);
+        quality_analyzer.check_panics(&mut report, &rust_code);
+        quality_analyzer.check_code_size(&mut report, &rust_code, &code_size_budget)?;
+    }
     quality_analyzer.print_quality_report(&report);
+    let code_size_ok = report
+        .gates_failed
+        .iter()
+        .all(|r| r.gate_name != depyler_quality::code_size::GATE_NAME);
+    if let (Some(code_size), false) = (&report.code_size, code_size_ok) {
+        println!("💡 To bring the generated code within its budget:");
+        for suggestion in code_size.suggestions(&code_size_budget) {
+            println!("  - {suggestion}");
+        }
+        println!();
+    }
 
     let validations =
         validate_quality_targets(&report, min_tdg, max_tdg, max_complexity, min_coverage);
@@ -721,7 +1292,18 @@ pub fn quality_check_command(
     let compilation_results = check_compilation_quality(&input)?;
     print_compilation_results(&compilation_results);
 
-    let all_passed = validations.all_passed && compilation_results.all_passed;
+    let dependencies_ok = report
+        .gates_failed
+        .iter()
+        .all(|r| r.gate_name != depyler_quality::dependencies::GATE_NAME);
+    // The project's own gates are enforced as a whole
+    let config_gates_ok =
+        quality_config.is_none() || report.overall_status != QualityStatus::Failed;
+    let all_passed = validations.all_passed
+        && compilation_results.all_passed
+        && dependencies_ok
+        && code_size_ok
+        && config_gates_ok;
 
     if enforce && !all_passed {
         std::process::exit(1);
@@ -748,14 +1330,31 @@ pub struct CompilationResults {
     pub all_passed: bool,
 }
 
-pub fn generate_quality_report(input: &std::path::Path) -> Result<depyler_quality::QualityReport> {
+/// The analyzer for `config`'s gates, or the built-in ones
+fn quality_analyzer(config: Option<&QualityConfig>) -> QualityAnalyzer {
+    match config {
+        Some(config) => QualityAnalyzer::with_config(config),
+        None => QualityAnalyzer::new(),
+    }
+}
+
+/// Quality report for `input`; measured `coverage` replaces the estimated
+/// coverage metrics and `config` the built-in gates
+pub fn generate_quality_report(
+    input: &std::path::Path,
+    coverage: Option<CoverageMetrics>,
+    config: Option<&QualityConfig>,
+) -> Result<depyler_quality::QualityReport> {
     let python_source = fs::read_to_string(input)?;
     let ast = {
         use rustpython_parser::{parse, Mode};
         parse(&python_source, Mode::Module, "<input>")?
     };
     let hir = depyler_core::ast_bridge::python_to_hir(ast)?;
-    let quality_analyzer = QualityAnalyzer::new();
+    let mut quality_analyzer = quality_analyzer(config);
+    if let Some(coverage) = coverage {
+        quality_analyzer = quality_analyzer.with_coverage(coverage);
+    }
     Ok(quality_analyzer.analyze_quality(&hir.functions)?)
 }
 
@@ -827,6 +1426,36 @@ pub fn check_compilation_quality(input: &std::path::Path) -> Result<CompilationR
     })
 }
 
+fn print_function_coverage(
+    crate_dir: &Path,
+    functions: &[depyler_analyzer::coverage::FunctionCoverage],
+) {
+    println!("Generated Code Coverage ({}):", crate_dir.display());
+    for function in functions {
+        let branches = if function.branches == 0 {
+            String::new()
+        } else {
+            format!(
+                ", {}/{} branches",
+                function.covered_branches, function.branches
+            )
+        };
+        println!(
+            "  {} {}: {:.0}% lines ({}/{}){}",
+            if function.covered_lines > 0 {
+                "✅"
+            } else {
+                "❌"
+            },
+            function.name,
+            function.line_coverage() * 100.0,
+            function.covered_lines,
+            function.lines,
+            branches
+        );
+    }
+}
+
 pub fn print_compilation_results(results: &CompilationResults) {
     println!("Compilation Check:");
     println!(
@@ -862,6 +1491,26 @@ pub fn inspect_command(
     let python_source = fs::read_to_string(&input)?;
     let pipeline = DepylerPipeline::new();
 
+    // CBOR is binary, so it only goes to a file
+    if format == "cbor" {
+        let output_path = match output {
+            Some(path) => path,
+            None => return Err(anyhow::anyhow!("--format cbor needs --output")),
+        };
+        let hir = match repr.as_str() {
+            "hir" => pipeline.parse_to_hir(&python_source)?,
+            "typed-hir" => pipeline.analyze_to_typed_hir(&python_source)?,
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "Only HIR can be written as CBOR, not {repr}"
+                ))
+            }
+        };
+        HirDocument::new(hir).write(&output_path, HirEncoding::Cbor)?;
+        println!("✅ Output written to: {}", output_path.display());
+        return Ok(());
+    }
+
     let output_content = match repr.as_str() {
         "python-ast" => inspect_python_ast(&python_source, &format)?,
         "hir" => {
@@ -909,7 +1558,10 @@ pub fn inspect_python_ast(python_source: &str, format: &str) -> Result<String> {
 
 pub fn inspect_hir(hir: &depyler_core::hir::HirModule, format: &str) -> Result<String> {
     match format {
-        "json" => Ok(serde_json::to_string_pretty(hir)?),
+        "json" => {
+            let document = HirDocument::new(hir.clone()).encode(HirEncoding::Json)?;
+            Ok(String::from_utf8(document)?)
+        }
         "debug" => Ok(format!("{hir:#?}")),
         "pretty" => Ok(format_hir_pretty(hir)),
         _ => Err(anyhow::anyhow!("Unknown format: {}", format)),
@@ -1069,49 +1721,6 @@ pub fn debug_command(
     Ok(())
 }
 
-// LSP command implementation
-
-pub fn lsp_command(port: u16, verbose: bool) -> Result<()> {
-    use depyler_core::lsp::LspServer;
-    use std::io::{self, BufRead, Write};
-
-    println!("🚀 Starting Depyler Language Server on port {}...", port);
-
-    // For now, implement a simple stdio-based LSP server
-    // In a full implementation, this would handle TCP connections
-
-    let _server = LspServer::new();
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-
-    println!("📡 Language Server ready. Waiting for client connections...");
-    println!("   Use with your IDE's LSP client configuration:");
-    println!("   - Command: depyler lsp");
-    println!("   - Port: {}", port);
-    println!("   - Language: Python");
-
-    // Simple message loop (in practice, would use full JSON-RPC)
-    for line in stdin.lock().lines() {
-        let line = line?;
-
-        if verbose {
-            eprintln!("Received: {}", line);
-        }
-
-        // Handle shutdown
-        if line.contains("shutdown") {
-            println!("👋 Language Server shutting down...");
-            break;
-        }
-
-        // Echo back for now (real implementation would parse JSON-RPC)
-        writeln!(stdout, "{{\"jsonrpc\":\"2.0\",\"result\":null,\"id\":1}}")?;
-        stdout.flush()?;
-    }
-
-    Ok(())
-}
-
 // Lambda-specific command implementations
 
 pub fn lambda_analyze_command(input: PathBuf, format: String, confidence: f64) -> Result<()> {
@@ -1722,8 +2331,230 @@ mod tests {
     fn test_transpile_command_basic() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
 
-        let result = transpile_command(input_path, None, false, false, false, false);
+        let result = transpile_command(
+            input_path,
+            None,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            PassOptions::default(),
+            vec![],
+            PrintFilter::default(),
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_transpile_command_emits_benches() {
+        let (temp_dir, input_path) =
+            create_test_python_file("def double(x: int) -> int:\n    return x * 2\n");
+
+        let result = transpile_command(
+            input_path,
+            None,
+            false,
+            false,
+            true,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            PassOptions::default(),
+            vec![],
+            PrintFilter::default(),
+        );
+        assert!(result.is_ok());
+        let bench = fs::read_to_string(temp_dir.path().join("benches/test.rs")).unwrap();
+        assert!(bench.contains("use test::*;"));
+        assert!(bench.contains("fn bench_double(c: &mut Criterion)"));
+    }
+
+    #[test]
+    fn test_transpile_command_emits_ffi() {
+        let (_temp_dir, input_path) =
+            create_test_python_file("def double(x: int) -> int:\n    return x * 2\n");
+
+        let result = transpile_command(
+            input_path.clone(),
+            None,
+            false,
+            false,
+            false,
+            true,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            PassOptions::default(),
+            vec![],
+            PrintFilter::default(),
+        );
+        assert!(result.is_ok());
+        let rust = fs::read_to_string(input_path.with_extension("rs")).unwrap();
+        assert!(rust.contains("pub mod ffi"));
+        assert!(rust.contains("extern \"C\" fn test_double("));
+        let header = fs::read_to_string(input_path.with_extension("h")).unwrap();
+        assert!(header.contains("int32_t test_double(int64_t x, int64_t *out);"));
+    }
+
+    #[test]
+    fn test_transpile_command_writes_divergences() {
+        let (_temp_dir, input_path) =
+            create_test_python_file("def add(a: int, b: int) -> int:\n    return a + b\n");
+
+        let result = transpile_command(
+            input_path.clone(),
+            None,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            true,
+            false,
+            false,
+            false,
+            false,
+            None,
+            PassOptions::default(),
+            vec![],
+            PrintFilter::default(),
+        );
         assert!(result.is_ok());
+        let ledger = fs::read_to_string(input_path.with_extension("divergences.json")).unwrap();
+        assert!(ledger.contains("\"function\": \"add\""));
+        assert!(ledger.contains("\"kind\": \"int_width\""));
+    }
+
+    #[test]
+    fn test_transpile_command_keeps_edited_regions() {
+        let (_temp_dir, input_path) = create_test_python_file(
+            "def area(w: int, h: int) -> int:\n    return w * h\n\n\
+             def perimeter(w: int, h: int) -> int:\n    return 2 * (w + h)\n",
+        );
+        let output_path = input_path.with_extension("rs");
+        let transpile = || {
+            transpile_command(
+                input_path.clone(),
+                None,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                None,
+                PassOptions::default(),
+                vec![],
+                PrintFilter::default(),
+            )
+        };
+
+        transpile().unwrap();
+        let generated = fs::read_to_string(&output_path).unwrap();
+        let edited = generated.replacen(
+            "pub fn perimeter",
+            "// depyler:keep-start\nfn helper() {}\n// depyler:keep-end\npub fn perimeter",
+            1,
+        );
+        assert_ne!(edited, generated);
+        fs::write(&output_path, &edited).unwrap();
+
+        transpile().unwrap();
+        let regenerated = fs::read_to_string(&output_path).unwrap();
+        assert!(regenerated.contains("// depyler:keep-start\nfn helper() {}\n// depyler:keep-end "));
+        assert_eq!(regenerated.matches("fn helper").count(), 1);
+        assert!(!input_path.with_extension("rs.orig").exists());
+
+        // Re-running over the merged file changes nothing
+        transpile().unwrap();
+        assert_eq!(fs::read_to_string(&output_path).unwrap(), regenerated);
+    }
+
+    #[test]
+    fn test_transpile_command_merges_hand_edits() {
+        let (_temp_dir, input_path) = create_test_python_file(
+            "def area(w: int, h: int) -> int:\n    return w * h\n\n\
+             def perimeter(w: int, h: int) -> int:\n    return 2 * (w + h)\n",
+        );
+        let output_path = input_path.with_extension("rs");
+        let transpile = || {
+            transpile_command(
+                input_path.clone(),
+                None,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                true,
+                None,
+                PassOptions::default(),
+                vec![],
+                PrintFilter::default(),
+            )
+        };
+
+        transpile().unwrap();
+        let generated = fs::read_to_string(&output_path).unwrap();
+        assert_eq!(
+            fs::read_to_string(input_path.with_extension("rs.base")).unwrap(),
+            generated
+        );
+        let edited =
+            generated.replacen("pub fn perimeter", "fn helper() {}\n\npub fn perimeter", 1);
+        fs::write(&output_path, &edited).unwrap();
+
+        // The generator changes perimeter; the helper stays
+        fs::write(
+            &input_path,
+            "def area(w: int, h: int) -> int:\n    return w * h\n\n\
+             def perimeter(w: int, h: int) -> int:\n    return w + w + h + h\n",
+        )
+        .unwrap();
+        transpile().unwrap();
+        let merged = fs::read_to_string(&output_path).unwrap();
+        let regenerated = fs::read_to_string(input_path.with_extension("rs.base")).unwrap();
+        assert_ne!(regenerated, generated);
+        assert_eq!(
+            merged,
+            regenerated.replacen("pub fn perimeter", "fn helper() {}\n\npub fn perimeter", 1)
+        );
     }
 
     #[test]
@@ -1738,6 +2569,18 @@ mod tests {
             false,
             false,
             false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            false,
+            None,
+            PassOptions::default(),
+            vec![],
+            PrintFilter::default(),
         );
         assert!(result.is_ok());
         assert!(output_path.exists());
@@ -1747,7 +2590,7 @@ mod tests {
     fn test_analyze_command_text_format() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
 
-        let result = analyze_command(input_path, "text".to_string());
+        let result = analyze_command(input_path, "text".to_string(), None);
         assert!(result.is_ok());
     }
 
@@ -1755,10 +2598,34 @@ mod tests {
     fn test_analyze_command_json_format() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
 
-        let result = analyze_command(input_path, "json".to_string());
+        let result = analyze_command(input_path, "json".to_string(), None);
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_analyze_command_with_coverage_report() {
+        let (temp_dir, input_path) = create_test_python_file(
+            "def hot(x: int) -> int:\n    return x + 1\n\ndef cold(x: int) -> int:\n    return x * 2\n",
+        );
+        let report_path = temp_dir.path().join("coverage.json");
+        let file_name = input_path.file_name().unwrap().to_string_lossy();
+        fs::write(
+            &report_path,
+            format!(
+                r#"{{"files": {{"{file_name}": {{"executed_lines": [1, 2, 4], "missing_lines": [5]}}}}}}"#
+            ),
+        )
+        .unwrap();
+
+        assert!(analyze_command(
+            input_path.clone(),
+            "text".to_string(),
+            Some(report_path.clone())
+        )
+        .is_ok());
+        assert!(analyze_command(input_path, "json".to_string(), Some(report_path)).is_ok());
+    }
+
     #[test]
     fn test_check_command_valid() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
@@ -1775,6 +2642,48 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_hir_document_transpiles_like_its_source() {
+        let (temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
+        let expected = DepylerPipeline::new()
+            .transpile(&fs::read_to_string(&input_path).unwrap())
+            .unwrap();
+
+        for (format, name) in [("json", "test.hir.json"), ("cbor", "test.hir.cbor")] {
+            let document = temp_dir.path().join(name);
+            inspect_command(
+                input_path.clone(),
+                "hir".to_string(),
+                format.to_string(),
+                Some(document.clone()),
+            )
+            .unwrap();
+            let output = temp_dir.path().join(format!("{format}.rs"));
+            transpile_command(
+                document,
+                Some(output.clone()),
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                None,
+                PassOptions::default(),
+                vec![],
+                PrintFilter::default(),
+            )
+            .unwrap();
+            assert_eq!(fs::read_to_string(output).unwrap(), expected);
+        }
+    }
+
     #[test]
     fn test_inspect_command_python_ast() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
@@ -1792,7 +2701,20 @@ mod tests {
     fn test_quality_check_command() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
 
-        let result = quality_check_command(input_path, false, 1.0, 2.0, 20, 80);
+        let result = quality_check_command(
+            input_path,
+            false,
+            1.0,
+            2.0,
+            20,
+            80,
+            None,
+            None,
+            None,
+            DependencyPolicy::default(),
+            CodeSizeBudget::default(),
+            None,
+        );
         assert!(result.is_ok());
     }
 
@@ -1800,7 +2722,7 @@ mod tests {
     fn test_generate_quality_report() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
 
-        let result = generate_quality_report(&input_path);
+        let result = generate_quality_report(&input_path, None, None);
         assert!(result.is_ok());
 
         let report = result.unwrap();
@@ -1810,7 +2732,7 @@ mod tests {
     #[test]
     fn test_validate_quality_targets() {
         let (_temp_dir, input_path) = create_test_python_file("def hello() -> int: return 42");
-        let report = generate_quality_report(&input_path).unwrap();
+        let report = generate_quality_report(&input_path, None, None).unwrap();
 
         let validations = validate_quality_targets(&report, 1.0, 2.0, 20, 80);
         assert!(validations.tdg_ok);
@@ -0,0 +1,113 @@
+//! Lint command
+//!
+//! Runs the generated-code lints over a Python file's transpilation, or
+//! over Rust that was generated earlier, and reports each diagnostic with
+//! its suggested fix.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use depyler_analyzer::lints::{Applicability, LintDiagnostic, Linter};
+use depyler_core::DepylerPipeline;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct LintArgs {
+    /// Python file to transpile and lint, or a generated `.rs` file
+    pub input: PathBuf,
+
+    /// Output format (text, json)
+    #[arg(short, long, default_value = "text")]
+    pub format: String,
+
+    /// Exit with an error when any lint fires
+    #[arg(long)]
+    pub deny_warnings: bool,
+}
+
+pub fn handle_lint_command(args: LintArgs) -> Result<()> {
+    let code = generated_code(&args.input)?;
+    let diagnostics = Linter::new()
+        .lint(&code)
+        .context("Failed to parse the generated Rust")?;
+
+    match args.format.as_str() {
+        "json" => println!("{}", serde_json::to_string_pretty(&diagnostics)?),
+        _ => print_diagnostics(&args.input, &diagnostics),
+    }
+
+    if args.deny_warnings && !diagnostics.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn generated_code(input: &Path) -> Result<String> {
+    let source =
+        fs::read_to_string(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    if input.extension().is_some_and(|ext| ext == "rs") {
+        return Ok(source);
+    }
+    DepylerPipeline::new()
+        .transpile(&source)
+        .with_context(|| format!("Failed to transpile {}", input.display()))
+}
+
+fn print_diagnostics(input: &Path, diagnostics: &[LintDiagnostic]) {
+    println!("🔎 Lints: {}", input.display());
+    if diagnostics.is_empty() {
+        println!("   {} No lints fired", "✓".green());
+        return;
+    }
+    for diagnostic in diagnostics {
+        println!(
+            "   {} [{}] {}: {}",
+            "warning".yellow(),
+            diagnostic.lint,
+            diagnostic.function,
+            diagnostic.message
+        );
+        let suggestion = &diagnostic.suggestion;
+        let note = match suggestion.applicability {
+            Applicability::MachineApplicable => "",
+            Applicability::MaybeIncorrect => " (may need adjusting)",
+        };
+        println!(
+            "       {} -> {}{}",
+            suggestion.original.red(),
+            suggestion.replacement.green(),
+            note
+        );
+    }
+    println!("   {} warning(s)", diagnostics.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_lint_generated_rust_file() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("lib.rs");
+        fs::write(
+            &input,
+            "pub fn size(items: &Vec<i32>) -> usize {\n    let mut n = items.clone().len();\n    n\n}\n",
+        )
+        .unwrap();
+
+        let code = generated_code(&input).unwrap();
+        let diagnostics = Linter::new().lint(&code).unwrap();
+        let lints: Vec<_> = diagnostics.iter().map(|d| d.lint.as_str()).collect();
+        assert_eq!(lints, ["unnecessary_clone", "needless_mut"]);
+
+        let args = LintArgs {
+            input,
+            format: "json".to_string(),
+            deny_warnings: false,
+        };
+        assert!(handle_lint_command(args).is_ok());
+    }
+}
@@ -0,0 +1,483 @@
+//! Language server
+//!
+//! `depyler lsp` and the `depyler-lsp` binary speak the Language Server
+//! Protocol, over stdin and stdout or a TCP connection. Every Python file
+//! the editor opens or changes is checked, and what is found is published
+//! as diagnostics:
+//!
+//! - syntax errors, where the parser stopped;
+//! - definitions that do not transpile, on their first line, with the
+//!   reason; the module is transpiled as a whole first, and only when that
+//!   fails is each definition tried on its own to find the culprits;
+//! - functions and methods over the complexity limits of the quality
+//!   gates, which come from the `depyler.toml` at the workspace root when
+//!   there is one.
+
+use anyhow::{Context, Result};
+use depyler_analyzer::{calculate_cognitive, calculate_cyclomatic};
+use depyler_core::hir::HirStmt;
+use depyler_core::hir_spans::Span;
+use depyler_core::lsp::{DiagnosticLsp, Position, Range};
+use depyler_core::DepylerPipeline;
+use depyler_quality::config::QualityConfig;
+use depyler_quality::{QualityRequirement, Severity};
+use rustpython_ast::{self as ast, Ranged};
+use rustpython_parser::Parse;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::Path;
+
+/// `DiagnosticSeverity` of the protocol
+const ERROR: i32 = 1;
+const WARNING: i32 = 2;
+const INFORMATION: i32 = 3;
+
+/// JSON-RPC error code for requests the server does not handle
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Serves one client on stdin and stdout, or on the first connection to
+/// `port` when one is given
+pub fn lsp_command(port: Option<u16>, verbose: bool) -> Result<()> {
+    match port {
+        Some(port) => {
+            let listener = TcpListener::bind(("127.0.0.1", port))
+                .with_context(|| format!("Failed to listen on port {}", port))?;
+            eprintln!("Depyler language server listening on 127.0.0.1:{}", port);
+            let (stream, _) = listener.accept()?;
+            serve(BufReader::new(stream.try_clone()?), stream, verbose)
+        }
+        None => serve(std::io::stdin().lock(), std::io::stdout().lock(), verbose),
+    }
+}
+
+/// Answers the messages from `reader` on `writer` until the client exits
+/// or hangs up
+pub fn serve(mut reader: impl BufRead, mut writer: impl Write, verbose: bool) -> Result<()> {
+    let mut server = LanguageServer::new();
+    while let Some(message) = read_message(&mut reader)? {
+        if verbose {
+            eprintln!("<- {}", message);
+        }
+        for reply in server.handle(&message) {
+            if verbose {
+                eprintln!("-> {}", reply);
+            }
+            write_message(&mut writer, &reply)?;
+        }
+        if server.exited {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The open documents and what they are checked against
+#[derive(Default)]
+pub struct LanguageServer {
+    documents: HashMap<String, String>,
+    config: QualityConfig,
+    exited: bool,
+}
+
+impl LanguageServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles one message from the client and returns the messages to send
+    /// back: the response to a request, and diagnostics for the documents
+    /// that changed
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let Some(method) = message["method"].as_str() else {
+            // A response to a request of ours; the server sends none
+            return Vec::new();
+        };
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "initialize" => {
+                self.load_config(params);
+                vec![response(
+                    id,
+                    json!({
+                        "capabilities": {
+                            "textDocumentSync": { "openClose": true, "change": 1 }
+                        },
+                        "serverInfo": {
+                            "name": "depyler-lsp",
+                            "version": env!("CARGO_PKG_VERSION")
+                        }
+                    }),
+                )]
+            }
+            "shutdown" => vec![response(id, Value::Null)],
+            "exit" => {
+                self.exited = true;
+                Vec::new()
+            }
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.documents.insert(uri.to_string(), text.to_string());
+                vec![self.publish(uri)]
+            }
+            "textDocument/didChange" => {
+                // Full synchronization: the last change holds the whole text
+                let changes = params["contentChanges"].as_array();
+                match changes.and_then(|changes| changes.last()?["text"].as_str()) {
+                    Some(text) => {
+                        self.documents.insert(uri.to_string(), text.to_string());
+                        vec![self.publish(uri)]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+                vec![publish_diagnostics(uri, Vec::new())]
+            }
+            _ if id.is_some() => vec![json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {
+                    "code": METHOD_NOT_FOUND,
+                    "message": format!("{} is not supported", method)
+                }
+            })],
+            _ => Vec::new(),
+        }
+    }
+
+    fn publish(&self, uri: &str) -> Value {
+        let text = self.documents.get(uri).map_or("", String::as_str);
+        publish_diagnostics(uri, analyze(text, &self.config))
+    }
+
+    /// Picks up the `depyler.toml` of the workspace the client opened
+    fn load_config(&mut self, params: &Value) {
+        let root = params["rootUri"]
+            .as_str()
+            .and_then(|uri| uri.strip_prefix("file://"))
+            .or_else(|| params["rootPath"].as_str());
+        let Some(path) = root.map(|root| Path::new(root).join("depyler.toml")) else {
+            return;
+        };
+        if !path.is_file() {
+            return;
+        }
+        match QualityConfig::load(&path) {
+            Ok(config) => self.config = config,
+            Err(error) => eprintln!("Ignoring {}: {}", path.display(), error),
+        }
+    }
+}
+
+/// The diagnostics of a Python source
+pub fn analyze(source: &str, config: &QualityConfig) -> Vec<DiagnosticLsp> {
+    let suite = match ast::Suite::parse(source, "<input>") {
+        Ok(suite) => suite,
+        Err(error) => {
+            let at = position(source, u32::from(error.offset) as usize);
+            return vec![diagnostic(
+                Range {
+                    start: at.clone(),
+                    end: at,
+                },
+                ERROR,
+                "syntax",
+                error.error.to_string(),
+            )];
+        }
+    };
+    let mut diagnostics = transpile_errors(source, &suite);
+    diagnostics.extend(complexity_findings(source, config));
+    diagnostics
+}
+
+fn transpile_errors(source: &str, suite: &[ast::Stmt]) -> Vec<DiagnosticLsp> {
+    let pipeline = DepylerPipeline::new();
+    let Err(error) = pipeline.transpile(source) else {
+        return Vec::new();
+    };
+
+    let text = |stmt: &ast::Stmt| {
+        let range = stmt.range();
+        &source[usize::from(range.start())..usize::from(range.end())]
+    };
+    let imports: String = suite
+        .iter()
+        .filter(|stmt| matches!(stmt, ast::Stmt::Import(_) | ast::Stmt::ImportFrom(_)))
+        .map(|stmt| format!("{}\n", text(stmt)))
+        .collect();
+    let failing: Vec<DiagnosticLsp> = suite
+        .iter()
+        .filter_map(|stmt| {
+            let name = match stmt {
+                ast::Stmt::FunctionDef(f) => f.name.as_str(),
+                ast::Stmt::AsyncFunctionDef(f) => f.name.as_str(),
+                ast::Stmt::ClassDef(class) => class.name.as_str(),
+                _ => return None,
+            };
+            let error = pipeline
+                .transpile(&format!("{}{}\n", imports, text(stmt)))
+                .err()?;
+            Some(diagnostic(
+                first_line(source, Span::of(stmt)),
+                ERROR,
+                "transpile",
+                format!("`{}` won't transpile: {:#}", name, error),
+            ))
+        })
+        .collect();
+    if !failing.is_empty() {
+        return failing;
+    }
+    // Only the module as a whole fails, e.g. on names claimed twice
+    let start = Position {
+        line: 0,
+        character: 0,
+    };
+    vec![diagnostic(
+        Range {
+            start: start.clone(),
+            end: start,
+        },
+        ERROR,
+        "transpile",
+        format!("The module won't transpile: {:#}", error),
+    )]
+}
+
+fn complexity_findings(source: &str, config: &QualityConfig) -> Vec<DiagnosticLsp> {
+    let Ok((module, spans)) = DepylerPipeline::new().parse_to_hir_with_spans(source) else {
+        return Vec::new();
+    };
+    let functions = module.functions.iter().map(|f| (f.name.clone(), &f.body));
+    let methods = module.classes.iter().flat_map(|class| {
+        class
+            .methods
+            .iter()
+            .map(move |method| (format!("{}.{}", class.name, method.name), &method.body))
+    });
+    let bodies: Vec<(String, &Vec<HirStmt>)> = functions.chain(methods).collect();
+
+    let mut diagnostics = Vec::new();
+    for gate in config.gates() {
+        let severity = match gate.severity {
+            Severity::Error => ERROR,
+            Severity::Warning => WARNING,
+            Severity::Info => INFORMATION,
+        };
+        for requirement in &gate.requirements {
+            let (metric, max, measure): (&str, u32, fn(&[HirStmt]) -> u32) = match requirement {
+                QualityRequirement::MaxComplexity(max) => {
+                    ("cyclomatic complexity", *max, calculate_cyclomatic)
+                }
+                QualityRequirement::MaxCognitiveComplexity(max) => {
+                    ("cognitive complexity", *max, calculate_cognitive)
+                }
+                _ => continue,
+            };
+            for (name, body) in &bodies {
+                let value = measure(body);
+                let Some(function) = spans.function(name) else {
+                    continue;
+                };
+                if value > max {
+                    diagnostics.push(diagnostic(
+                        first_line(source, function.def),
+                        severity,
+                        "complexity",
+                        format!(
+                            "`{}` has a {} of {}, over the {} of the `{}` gate",
+                            name, metric, value, max, gate.name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+    diagnostics
+}
+
+fn diagnostic(range: Range, severity: i32, code: &str, message: String) -> DiagnosticLsp {
+    DiagnosticLsp {
+        range,
+        severity: Some(severity),
+        code: Some(code.to_string()),
+        source: Some("depyler".to_string()),
+        message,
+    }
+}
+
+/// The first line of `span`, where a definition's name is
+fn first_line(source: &str, span: Span) -> Range {
+    let start = span.start as usize;
+    let end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |newline| start + newline);
+    Range {
+        start: position(source, start),
+        end: position(source, end),
+    }
+}
+
+/// The protocol's position of a byte offset: a 0-based line, and a column
+/// counted in UTF-16 code units
+fn position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset.min(source.len())];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    Position {
+        line: before.matches('\n').count(),
+        character: before[line_start..].encode_utf16().count(),
+    }
+}
+
+fn response(id: Option<Value>, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn publish_diagnostics(uri: &str, diagnostics: Vec<DiagnosticLsp>) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics }
+    })
+}
+
+/// The next message, without its `Content-Length` header, or `None` once
+/// the client has hung up
+fn read_message(reader: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(
+                    value
+                        .trim()
+                        .parse::<usize>()
+                        .context("Bad Content-Length")?,
+                );
+            }
+        }
+    }
+    let mut body = vec![0; length.context("Message without a Content-Length")?];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(message: Value) -> String {
+        let body = message.to_string();
+        format!("Content-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn test_syntax_errors_point_at_the_parser_position() {
+        let diagnostics = analyze("def f(:\n    pass\n", &QualityConfig::default());
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code.as_deref(), Some("syntax"));
+        assert_eq!(diagnostics[0].range.start.line, 0);
+    }
+
+    #[test]
+    fn test_definitions_that_do_not_transpile_are_flagged() {
+        let source = "def ok(x: int) -> int:\n    return x\n\n\
+                      def bump() -> None:\n    global counter\n    counter = 1\n";
+        let diagnostics = analyze(source, &QualityConfig::default());
+
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, Some(ERROR));
+        assert_eq!(diagnostic.range.start.line, 3);
+        assert_eq!(diagnostic.range.end.character, "def bump() -> None:".len());
+        assert!(diagnostic.message.starts_with("`bump` won't transpile"));
+    }
+
+    #[test]
+    fn test_complexity_over_the_gate_is_flagged() {
+        let config = QualityConfig {
+            max_cyclomatic: 2,
+            ..QualityConfig::default()
+        };
+        let source = "class Grader:\n    def grade(self, n: int) -> str:\n        \
+                      if n > 90:\n            return \"A\"\n        \
+                      if n > 80:\n            return \"B\"\n        return \"C\"\n";
+        let diagnostics = analyze(source, &config);
+
+        let complexity: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.code.as_deref() == Some("complexity"))
+            .collect();
+        assert_eq!(complexity.len(), 1, "{:?}", diagnostics);
+        assert_eq!(complexity[0].range.start.line, 1);
+        assert!(complexity[0]
+            .message
+            .contains("`Grader.grade` has a cyclomatic complexity of 3, over the 2"));
+    }
+
+    #[test]
+    fn test_positions_count_utf16_units() {
+        let source = "s = \"é😀\"\nx";
+        assert_eq!(position(source, source.len() - 1).line, 1);
+        let end_of_first_line = source.find('\n').unwrap();
+        assert_eq!(position(source, end_of_first_line).character, 8);
+    }
+
+    #[test]
+    fn test_session_publishes_diagnostics() {
+        let open = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": {
+                "uri": "file:///tmp/a.py", "languageId": "python", "version": 1,
+                "text": "def f(:\n"
+            }}
+        });
+        let input = [
+            frame(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })),
+            frame(open),
+            frame(json!({ "jsonrpc": "2.0", "id": 2, "method": "shutdown" })),
+            frame(json!({ "jsonrpc": "2.0", "method": "exit" })),
+        ]
+        .concat();
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, false).unwrap();
+
+        let mut reader = output.as_slice();
+        let mut replies = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            replies.push(message);
+        }
+        assert_eq!(replies.len(), 3);
+        assert_eq!(
+            replies[0]["result"]["capabilities"]["textDocumentSync"]["change"],
+            1
+        );
+        assert_eq!(replies[1]["method"], "textDocument/publishDiagnostics");
+        assert_eq!(replies[1]["params"]["diagnostics"][0]["code"], "syntax");
+        assert_eq!(replies[2]["id"], 2);
+    }
+}
@@ -2,12 +2,18 @@ use anyhow::Result;
 use clap::Parser;
 use depyler::{
     agent_logs_command, agent_restart_command, agent_start_command, agent_status_command,
-    agent_stop_command, analyze_command, check_command, compile_command, debug_command,
-    docs_cmd::handle_docs_command, inspect_command, interactive_command, lambda_analyze_command,
-    lambda_build_command, lambda_convert_command, lambda_deploy_command, lambda_test_command,
-    lsp_command, profile_cmd::handle_profile_command, quality_check_command, transpile_command,
-    AgentCommands, Cli, Commands, LambdaCommands,
+    agent_stop_command, analyze_command, api_diff_cmd::handle_api_diff_command, check_command,
+    compile_command, debug_command, docs_cmd::handle_docs_command,
+    init_example_cmd::handle_init_example_command, inspect_command, interactive_command,
+    lambda_analyze_command, lambda_build_command, lambda_convert_command, lambda_deploy_command,
+    lambda_test_command, lint_cmd::handle_lint_command, lsp_cmd::lsp_command,
+    print_filter::PrintFilter, profile_cmd::handle_profile_command, quality_check_command,
+    test_translate_cmd::handle_translate_tests_command, transpile_command, AgentCommands, Cli,
+    Commands, LambdaCommands,
 };
+use depyler_core::optimize::PassOptions;
+use depyler_quality::code_size::CodeSizeBudget;
+use depyler_quality::dependencies::DependencyPolicy;
 use std::path::PathBuf;
 
 /// Handle agent add-project command
@@ -124,9 +130,50 @@ async fn handle_command(command: Commands) -> Result<()> {
             output,
             verify,
             gen_tests,
+            emit_benches,
+            emit_ffi,
             debug,
             source_map,
-        } => transpile_command(input, output, verify, gen_tests, debug, source_map),
+            fold_constants,
+            divergences,
+            class_modules,
+            script_main,
+            inject_io,
+            merge,
+            analysis_cache,
+            enabled_passes,
+            disabled_passes,
+            print_hir_after,
+            seams,
+            print_handling,
+            print_paths,
+        } => transpile_command(
+            input,
+            output,
+            verify,
+            gen_tests,
+            emit_benches,
+            emit_ffi,
+            debug,
+            source_map,
+            fold_constants,
+            divergences,
+            class_modules,
+            script_main,
+            inject_io,
+            merge,
+            analysis_cache,
+            PassOptions {
+                toggles: enabled_passes
+                    .into_iter()
+                    .map(|pass| (pass, true))
+                    .chain(disabled_passes.into_iter().map(|pass| (pass, false)))
+                    .collect(),
+                print_hir_after: print_hir_after.into_iter().collect(),
+            },
+            seams,
+            PrintFilter::new(&print_handling, print_paths)?,
+        ),
         Commands::Compile {
             input,
             output,
@@ -135,7 +182,11 @@ async fn handle_command(command: Commands) -> Result<()> {
             let cli = Cli::parse();
             compile_command(input, output, profile, cli.verbose)
         }
-        Commands::Analyze { input, format } => analyze_command(input, format),
+        Commands::Analyze {
+            input,
+            format,
+            coverage,
+        } => analyze_command(input, format, coverage),
         Commands::Check { input } => check_command(input),
         Commands::QualityCheck {
             input,
@@ -144,6 +195,15 @@ async fn handle_command(command: Commands) -> Result<()> {
             max_tdg,
             max_complexity,
             min_coverage,
+            coverage,
+            crate_coverage,
+            mutate,
+            max_dependencies,
+            banned_crates,
+            max_generated_lines,
+            max_generated_items,
+            max_generic_constructs,
+            config,
         } => quality_check_command(
             input,
             enforce,
@@ -151,6 +211,19 @@ async fn handle_command(command: Commands) -> Result<()> {
             max_tdg,
             max_complexity,
             min_coverage,
+            coverage,
+            crate_coverage,
+            mutate,
+            DependencyPolicy {
+                max_dependencies,
+                banned_crates,
+            },
+            CodeSizeBudget {
+                max_lines: max_generated_lines,
+                max_items: max_generated_items,
+                max_generic_constructs,
+            },
+            config,
         ),
         Commands::Interactive { input, annotate } => interactive_command(input, annotate),
         Commands::Inspect {
@@ -198,6 +271,52 @@ async fn handle_command(command: Commands) -> Result<()> {
             };
             handle_docs_command(args)
         }
+        Commands::TranslateTests {
+            tests,
+            module,
+            output,
+            crate_name,
+            seams,
+        } => {
+            let args = depyler::test_translate_cmd::TranslateTestsArgs {
+                tests,
+                module,
+                output,
+                crate_name,
+                seams,
+            };
+            handle_translate_tests_command(args)
+        }
+        Commands::ApiDiff {
+            old,
+            new,
+            format,
+            fail_on_breaking,
+        } => {
+            let args = depyler::api_diff_cmd::ApiDiffArgs {
+                old,
+                new,
+                format,
+                fail_on_breaking,
+            };
+            handle_api_diff_command(args)
+        }
+        Commands::InitExample { output, force } => {
+            let args = depyler::init_example_cmd::InitExampleArgs { output, force };
+            handle_init_example_command(args)
+        }
+        Commands::Lint {
+            input,
+            format,
+            deny_warnings,
+        } => {
+            let args = depyler::lint_cmd::LintArgs {
+                input,
+                format,
+                deny_warnings,
+            };
+            handle_lint_command(args)
+        }
         Commands::Profile {
             file,
             count_instructions,
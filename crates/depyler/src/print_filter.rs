@@ -0,0 +1,130 @@
+//! `transpile --print`
+//!
+//! Chooses what becomes of a module's `print()` statements. The handling
+//! applies to every input, or with `--print-path` only to inputs matching
+//! one of the globs, so `--print strip --print-path 'src/lib/**'` strips
+//! the prints of library modules and keeps those of scripts:
+//!
+//! | Pattern | Matches                          |
+//! |---------|----------------------------------|
+//! | `*`     | any characters but `/`           |
+//! | `**`    | any characters, `/` included     |
+//! | `?`     | one character other than `/`     |
+//!
+//! `print` annotations on functions take precedence either way.
+
+use anyhow::{bail, Result};
+use depyler_annotations::PrintHandling;
+use std::path::Path;
+
+/// The `print()` handling asked for on the command line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PrintFilter {
+    pub handling: PrintHandling,
+    /// Globs the input has to match for `handling` to apply; empty for all
+    pub paths: Vec<String>,
+}
+
+impl PrintFilter {
+    /// A filter from the values of `--print` and `--print-path`
+    pub fn new(handling: &str, paths: Vec<String>) -> Result<Self> {
+        let handling = match handling {
+            "keep" => PrintHandling::Keep,
+            "strip" => PrintHandling::Strip,
+            "log" => PrintHandling::Log,
+            other => bail!(
+                "Unknown print handling '{}'; expected keep, strip or log",
+                other
+            ),
+        };
+        Ok(Self { handling, paths })
+    }
+
+    /// The handling of the prints in `input`
+    pub fn handling_for(&self, input: &Path) -> PrintHandling {
+        let path: Vec<char> = input.to_string_lossy().replace('\\', "/").chars().collect();
+        let selected = self.paths.is_empty()
+            || self.paths.iter().any(|pattern| {
+                let pattern: Vec<char> = pattern.chars().collect();
+                glob_matches(&pattern, &path)
+            });
+        if selected {
+            self.handling
+        } else {
+            PrintHandling::Keep
+        }
+    }
+}
+
+fn glob_matches(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            glob_matches(rest, path)
+                || (0..path.len()).any(|i| path[i] == '/' && glob_matches(rest, &path[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|i| glob_matches(rest, &path[i..])),
+        ['*', rest @ ..] => (0..=path.len())
+            .take_while(|&i| i == 0 || path[i - 1] != '/')
+            .any(|i| glob_matches(rest, &path[i..])),
+        ['?', rest @ ..] => {
+            matches!(path.first(), Some(c) if *c != '/') && glob_matches(rest, &path[1..])
+        }
+        [c, rest @ ..] => path.first() == Some(c) && glob_matches(rest, &path[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(paths: &[&str]) -> PrintFilter {
+        PrintFilter::new("strip", paths.iter().map(|p| p.to_string()).collect()).unwrap()
+    }
+
+    #[test]
+    fn test_without_paths_applies_everywhere() {
+        assert_eq!(
+            filter(&[]).handling_for(Path::new("app/main.py")),
+            PrintHandling::Strip
+        );
+    }
+
+    #[test]
+    fn test_path_globs() {
+        let lib = filter(&["src/lib/**"]);
+        assert_eq!(
+            lib.handling_for(Path::new("src/lib/a/b.py")),
+            PrintHandling::Strip
+        );
+        assert_eq!(
+            lib.handling_for(Path::new("src/main.py")),
+            PrintHandling::Keep
+        );
+
+        let top = filter(&["src/*.py"]);
+        assert_eq!(
+            top.handling_for(Path::new("src/util.py")),
+            PrintHandling::Strip
+        );
+        assert_eq!(
+            top.handling_for(Path::new("src/lib/util.py")),
+            PrintHandling::Keep
+        );
+
+        let any = filter(&["**/model?.py"]);
+        assert_eq!(
+            any.handling_for(Path::new("model1.py")),
+            PrintHandling::Strip
+        );
+        assert_eq!(
+            any.handling_for(Path::new("a/b/models.py")),
+            PrintHandling::Strip
+        );
+    }
+
+    #[test]
+    fn test_unknown_handling() {
+        assert!(PrintFilter::new("discard", vec![]).is_err());
+    }
+}
@@ -0,0 +1,274 @@
+//! Test translation command
+//!
+//! Converts pytest / unittest files into Rust integration tests for the
+//! crate produced by transpiling the module they exercise.
+
+use anyhow::{Context, Result};
+use clap::Args;
+use depyler_core::symbol_names::{QualifiedName, SymbolTable};
+use depyler_core::DepylerPipeline;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct TranslateTestsArgs {
+    /// Python test files (pytest or unittest)
+    #[arg(required = true)]
+    pub tests: Vec<PathBuf>,
+
+    /// Python module under test
+    #[arg(short, long)]
+    pub module: PathBuf,
+
+    /// Root of the transpiled crate; tests are written to `<output>/tests/`
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Library crate name the tests import (defaults to the module name)
+    #[arg(long)]
+    pub crate_name: Option<String>,
+
+    /// Module function the crate was transpiled with a seam for; tests
+    /// patching it get a fake (repeatable)
+    #[arg(long = "seam", value_name = "FUNCTION")]
+    pub seams: Vec<String>,
+}
+
+pub fn handle_translate_tests_command(args: TranslateTestsArgs) -> Result<()> {
+    let module_name = file_stem(&args.module)?;
+    let crate_name = args
+        .crate_name
+        .clone()
+        .unwrap_or_else(|| module_name.clone());
+    let module_source = fs::read_to_string(&args.module)
+        .with_context(|| format!("Failed to read {}", args.module.display()))?;
+
+    let crate_root = match &args.output {
+        Some(output) => output.clone(),
+        None => args
+            .module
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default(),
+    };
+    let tests_dir = crate_root.join("tests");
+    fs::create_dir_all(&tests_dir)?;
+
+    let pipeline = DepylerPipeline::new().with_seams(args.seams.clone());
+    let mut dev_dependencies = Vec::new();
+    let mut missing_seams: Vec<String> = Vec::new();
+    // `unit/test_api.py` and `integration/test_api.py` both want `test_api.rs`
+    let mut output_names = SymbolTable::default();
+    for test_file in &args.tests {
+        let test_source = fs::read_to_string(test_file)
+            .with_context(|| format!("Failed to read {}", test_file.display()))?;
+        let translated = pipeline
+            .translate_tests(&module_source, &module_name, &test_source, &crate_name)
+            .with_context(|| format!("Failed to translate {}", test_file.display()))?;
+
+        let output_name = output_names.assign(&file_stem(test_file)?, test_file_origin(test_file));
+        let output_path = tests_dir.join(format!("{}.rs", output_name));
+        fs::write(&output_path, &translated.code)?;
+
+        println!(
+            "🧪 {} -> {} ({} translated, {} ignored)",
+            test_file.display(),
+            output_path.display(),
+            translated.tests.len(),
+            translated.ignored.len()
+        );
+        for ignored in &translated.ignored {
+            println!("   ⚠️  {}: {}", ignored.name, ignored.reason);
+        }
+        for patch in &translated.patches {
+            println!("   🩹 {} patches {}", patch.test, patch.target);
+            if let Some(function) = &patch.function {
+                if !args.seams.contains(function) && !missing_seams.contains(function) {
+                    missing_seams.push(function.clone());
+                }
+            }
+        }
+        for dependency in translated.dev_dependencies {
+            if !dev_dependencies.contains(&dependency) {
+                dev_dependencies.push(dependency);
+            }
+        }
+    }
+
+    if !missing_seams.is_empty() {
+        let flags: Vec<String> = missing_seams
+            .iter()
+            .map(|f| format!("--seam {}", f))
+            .collect();
+        println!(
+            "💡 Transpile {} and translate again with `{}` to fake the patched functions",
+            args.module.display(),
+            flags.join(" ")
+        );
+    }
+
+    if !dev_dependencies.is_empty() {
+        let manifest = crate_root.join("Cargo.toml");
+        if manifest.is_file() {
+            add_dev_dependencies(&manifest, &dev_dependencies)?;
+            println!("📦 Updated [dev-dependencies] in {}", manifest.display());
+        } else {
+            println!("📦 Add to [dev-dependencies]:");
+            for (name, version) in &dev_dependencies {
+                println!("   {} = \"{}\"", name, version);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds the missing `name = "version"` lines to the manifest's `[dev-dependencies]`
+fn add_dev_dependencies(manifest: &Path, dependencies: &[(String, String)]) -> Result<()> {
+    let mut content = fs::read_to_string(manifest)?;
+    let section = "[dev-dependencies]";
+    let declared = |name: &str| {
+        content.lines().any(|line| {
+            line.trim_start()
+                .strip_prefix(name)
+                .is_some_and(|rest| rest.trim_start().starts_with('=') || rest.starts_with('.'))
+        })
+    };
+    let missing: Vec<String> = dependencies
+        .iter()
+        .filter(|(name, _)| !declared(name))
+        .map(|(name, version)| format!("{} = \"{}\"", name, version))
+        .collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    match content.find(section) {
+        Some(start) => {
+            content.insert_str(start + section.len(), &format!("\n{}", missing.join("\n")))
+        }
+        None => {
+            if !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&format!("\n{}\n{}\n", section, missing.join("\n")));
+        }
+    }
+    fs::write(manifest, content)?;
+    Ok(())
+}
+
+fn file_stem(path: &Path) -> Result<String> {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or_else(|| anyhow::anyhow!("Invalid file name: {}", path.display()))
+}
+
+/// The test file's directories are the scopes that tell same-named files apart
+fn test_file_origin(path: &Path) -> QualifiedName {
+    QualifiedName::new(
+        path.with_extension("")
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+                _ => None,
+            }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_translate_tests_writes_tests_dir() {
+        let dir = tempdir().unwrap();
+        let module_path = dir.path().join("calc.py");
+        let test_path = dir.path().join("test_calc.py");
+
+        fs::write(
+            &module_path,
+            "def add(a: int, b: int) -> int:\n    return a + b\n",
+        )
+        .unwrap();
+        fs::write(
+            &test_path,
+            "from calc import add\n\ndef test_add():\n    assert add(1, 2) == 3\n",
+        )
+        .unwrap();
+
+        let args = TranslateTestsArgs {
+            tests: vec![test_path],
+            module: module_path,
+            output: Some(dir.path().join("calc_rs")),
+            crate_name: None,
+            seams: vec![],
+        };
+        assert!(handle_translate_tests_command(args).is_ok());
+
+        let code = fs::read_to_string(dir.path().join("calc_rs/tests/test_calc.rs")).unwrap();
+        assert!(code.contains("use calc::*;"));
+        assert!(code.contains("#[test]"));
+        assert!(code.contains("fn test_add()"));
+    }
+
+    #[test]
+    fn test_same_named_test_files_get_distinct_outputs() {
+        let dir = tempdir().unwrap();
+        let module_path = dir.path().join("calc.py");
+        fs::write(
+            &module_path,
+            "def add(a: int, b: int) -> int:\n    return a + b\n",
+        )
+        .unwrap();
+        let mut tests = Vec::new();
+        for suite in ["unit", "integration"] {
+            fs::create_dir_all(dir.path().join(suite)).unwrap();
+            let test_path = dir.path().join(suite).join("test_calc.py");
+            let test = format!(
+                "from calc import add\n\ndef test_{}():\n    assert add(1, 2) == 3\n",
+                suite
+            );
+            fs::write(&test_path, test).unwrap();
+            tests.push(test_path);
+        }
+
+        let args = TranslateTestsArgs {
+            tests,
+            module: module_path,
+            output: Some(dir.path().join("calc_rs")),
+            crate_name: None,
+            seams: vec![],
+        };
+        handle_translate_tests_command(args).unwrap();
+
+        let tests_dir = dir.path().join("calc_rs/tests");
+        let unit = fs::read_to_string(tests_dir.join("test_calc.rs")).unwrap();
+        let integration = fs::read_to_string(tests_dir.join("integration_test_calc.rs")).unwrap();
+        assert!(unit.contains("fn test_unit()"));
+        assert!(integration.contains("fn test_integration()"));
+    }
+
+    #[test]
+    fn test_add_dev_dependencies() {
+        let dir = tempdir().unwrap();
+        let manifest = dir.path().join("Cargo.toml");
+        let rstest = vec![("rstest".to_string(), "0.18".to_string())];
+
+        fs::write(&manifest, "[package]\nname = \"calc\"\n").unwrap();
+        add_dev_dependencies(&manifest, &rstest).unwrap();
+        add_dev_dependencies(&manifest, &rstest).unwrap();
+        let content = fs::read_to_string(&manifest).unwrap();
+        assert_eq!(content.matches("rstest = \"0.18\"").count(), 1);
+        assert!(content.contains("[dev-dependencies]\nrstest"));
+
+        fs::write(&manifest, "[dev-dependencies]\nproptest = \"1\"\n").unwrap();
+        add_dev_dependencies(&manifest, &rstest).unwrap();
+        let content = fs::read_to_string(&manifest).unwrap();
+        assert_eq!(
+            content,
+            "[dev-dependencies]\nrstest = \"0.18\"\nproptest = \"1\"\n"
+        );
+    }
+}
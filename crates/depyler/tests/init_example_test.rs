@@ -0,0 +1,33 @@
+//! The project written by `depyler init-example` builds and passes its
+//! translated tests
+
+use assert_cmd::cargo::CommandCargoExt;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_init_example_crate_passes_its_tests() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let project = temp_dir.path().join("example");
+
+    let status = Command::cargo_bin("depyler")
+        .expect("Failed to find depyler binary")
+        .arg("init-example")
+        .arg(&project)
+        .status()
+        .expect("Failed to run depyler");
+    assert!(status.success());
+
+    let output = Command::new("cargo")
+        .args(["test", "--offline", "--quiet"])
+        .current_dir(project.join("rust"))
+        .env("CARGO_TARGET_DIR", temp_dir.path().join("target"))
+        .output()
+        .expect("Failed to run cargo");
+    assert!(
+        output.status.success(),
+        "Generated crate should pass its tests:\n{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
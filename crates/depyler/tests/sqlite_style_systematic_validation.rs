@@ -2054,7 +2054,7 @@ def test_float(value: int) -> float:
 
     let rust = transpile_and_verify(python, "builtin_float").unwrap();
     assert!(rust.contains("fn test_float"));
-    assert!(rust.contains("as f64"));
+    assert!(rust.contains("f64::from(value)"));
 }
 
 #[test]
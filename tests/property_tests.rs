@@ -1,5 +1,5 @@
 use depyler_annotations::TranspilationAnnotations;
-use depyler_core::hir::{HirExpr, HirParam, Literal, Symbol, Type};
+use depyler_core::hir::{HirExpr, HirParam, Literal, ParamKind, Symbol, Type};
 use depyler_core::hir::{HirFunction, HirStmt};
 
 use depyler_core::direct_rules::apply_rules;
@@ -156,6 +156,7 @@ impl Arbitrary for ArbitraryFunction {
                 name: Symbol::from(format!("param_{i}")),
                 ty: arbitrary_simple_type(g),
                 default: None,
+                kind: ParamKind::PositionalOrKeyword,
             })
             .collect();
 
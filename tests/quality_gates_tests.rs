@@ -1,72 +1,36 @@
-use depyler_annotations::TranspilationAnnotations;
 use depyler_core::hir::*;
+use depyler_core::hir_builder::{expr, stmt, HirFunctionBuilder};
 use depyler_quality::*;
-use smallvec::smallvec;
 
 fn create_simple_function() -> HirFunction {
-    HirFunction {
-        name: "simple".to_string(),
-        params: smallvec![HirParam {
-            name: Symbol::from("x"),
-            ty: Type::Int,
-            default: None
-        }],
-        ret_type: Type::Int,
-        body: vec![HirStmt::Return(Some(HirExpr::Var("x".to_string())))],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    }
+    HirFunctionBuilder::new("simple")
+        .param("x", Type::Int)
+        .returns(Type::Int)
+        .stmt(stmt::ret(expr::var("x")))
+        .build()
 }
 
 fn create_complex_function() -> HirFunction {
-    HirFunction {
-        name: "complex".to_string(),
-        params: smallvec![
-            HirParam {
-                name: Symbol::from("a"),
-                ty: Type::Int,
-                default: None
-            },
-            HirParam {
-                name: Symbol::from("b"),
-                ty: Type::Int,
-                default: None
-            },
-            HirParam {
-                name: Symbol::from("c"),
-                ty: Type::Int,
-                default: None
-            }
-        ],
-        ret_type: Type::Int,
-        body: vec![HirStmt::If {
-            condition: HirExpr::Binary {
-                op: BinOp::Gt,
-                left: Box::new(HirExpr::Var("a".to_string())),
-                right: Box::new(HirExpr::Literal(Literal::Int(0))),
-            },
-            then_body: vec![HirStmt::If {
-                condition: HirExpr::Binary {
-                    op: BinOp::Gt,
-                    left: Box::new(HirExpr::Var("b".to_string())),
-                    right: Box::new(HirExpr::Literal(Literal::Int(0))),
-                },
-                then_body: vec![HirStmt::Return(Some(HirExpr::Binary {
-                    op: BinOp::Add,
-                    left: Box::new(HirExpr::Var("a".to_string())),
-                    right: Box::new(HirExpr::Var("b".to_string())),
-                }))],
-                else_body: Some(vec![HirStmt::Return(Some(HirExpr::Var("a".to_string())))]),
-            }],
-            else_body: Some(vec![HirStmt::Return(Some(HirExpr::Literal(Literal::Int(
-                0,
-            ))))]),
-        }],
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    }
+    let positive = |name: &str| expr::binary(BinOp::Gt, expr::var(name), expr::int(0));
+    HirFunctionBuilder::new("complex")
+        .param("a", Type::Int)
+        .param("b", Type::Int)
+        .param("c", Type::Int)
+        .returns(Type::Int)
+        .stmt(stmt::if_else(
+            positive("a"),
+            vec![stmt::if_else(
+                positive("b"),
+                vec![stmt::ret(expr::binary(
+                    BinOp::Add,
+                    expr::var("a"),
+                    expr::var("b"),
+                ))],
+                Some(vec![stmt::ret(expr::var("a"))]),
+            )],
+            Some(vec![stmt::ret(expr::int(0))]),
+        ))
+        .build()
 }
 
 #[test]
@@ -209,15 +173,7 @@ fn test_pmat_edge_cases() {
     let analyzer = QualityAnalyzer::new();
 
     // Test with function that has zero complexity
-    let zero_complexity_func = HirFunction {
-        name: "empty".to_string(),
-        params: smallvec![],
-        ret_type: Type::None,
-        body: vec![], // Empty body
-        properties: FunctionProperties::default(),
-        annotations: TranspilationAnnotations::default(),
-        docstring: None,
-    };
+    let zero_complexity_func = HirFunctionBuilder::new("empty").build();
 
     let functions = vec![zero_complexity_func];
     let report = analyzer.analyze_quality(&functions).unwrap();